@@ -1,27 +1,58 @@
 use crate::{
+    constants::{EXTENSION_PREFIX, MAX_PING_REPLY_LEN},
     data::{
-        Capabilities, ChangeKind, DirEntry, Environment, Error, Metadata, ProcessId, PtySize,
-        SearchId, SearchQuery, SystemInfo,
+        Capabilities, ChangeKind, DirEntry, DirReadOptions, Environment, Error, ErrorKind,
+        HashAlgorithm, JournalEntry, JournalSeq, Metadata, Metrics, ProcInfo, ProcLimits,
+        ProcStats, ProcessId, PtySize, SearchId, SearchQuery, SessionId, Signal, SnapshotId,
+        SnapshotInfo, SystemInfo, SystemStats, SystemStatsId, TunnelId, WatchId, WorkspaceRoot,
     },
     DistantMsg, DistantRequestData, DistantResponseData,
 };
 use async_trait::async_trait;
 use distant_net::common::ConnectionId;
-use distant_net::server::{ConnectionCtx, Reply, ServerCtx, ServerHandler};
+use distant_net::server::{ConnectionCtx, Reply, ServerClients, ServerCtx, ServerHandler};
 use log::*;
-use std::{io, path::PathBuf, sync::Arc};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+mod batch;
+mod dedup;
+pub(crate) use dedup::content_hash;
+mod diff;
+mod idempotency;
 
 mod local;
-pub use local::LocalDistantApi;
+pub use local::{
+    DefaultModes, LocalDistantApi, OutputLimits, ProcessPolicy, ProcessScope, ScopeLimits,
+    ServerHooks,
+};
+
+mod path_map;
+use path_map::{PathMap, PathMaps};
+
+mod plan;
 
 mod reply;
-use reply::DistantSingleReply;
+use reply::{DistantPathMapReply, DistantSingleReply};
+
+mod temp;
+pub use temp::{make_temp_name, MAX_TEMP_ATTEMPTS};
+
+mod transaction;
 
 /// Represents the context provided to the [`DistantApi`] for incoming requests
 pub struct DistantCtx<T> {
     pub connection_id: ConnectionId,
     pub reply: Box<dyn Reply<Data = DistantResponseData>>,
     pub local_data: Arc<T>,
+
+    /// Point in time by which the request must complete, after which it is aborted with
+    /// [`ErrorKind::DeadlineExceeded`](crate::data::ErrorKind::DeadlineExceeded)
+    pub deadline: Option<Instant>,
 }
 
 /// Represents a [`ServerHandler`] that leverages an API compliant with `distant`
@@ -30,6 +61,9 @@ where
     T: DistantApi<LocalData = D>,
 {
     api: T,
+    content: dedup::ContentIndex,
+    results: idempotency::ResultCache<DistantMsg<DistantResponseData>>,
+    path_maps: PathMaps,
 }
 
 impl<T, D> DistantApiServerHandler<T, D>
@@ -37,16 +71,27 @@ where
     T: DistantApi<LocalData = D>,
 {
     pub fn new(api: T) -> Self {
-        Self { api }
+        Self {
+            api,
+            content: Default::default(),
+            results: Default::default(),
+            path_maps: Default::default(),
+        }
     }
 }
 
 impl DistantApiServerHandler<LocalDistantApi, <LocalDistantApi as DistantApi>::LocalData> {
     /// Creates a new server using the [`LocalDistantApi`] implementation
     pub fn local() -> io::Result<Self> {
-        Ok(Self {
-            api: LocalDistantApi::initialize()?,
-        })
+        Ok(Self::new(LocalDistantApi::initialize()?))
+    }
+
+    /// Creates a new server using the [`LocalDistantApi`] implementation, buffering the output of
+    /// spawned processes within `output_limits`
+    pub fn local_with_limits(output_limits: OutputLimits) -> io::Result<Self> {
+        Ok(Self::new(LocalDistantApi::initialize_with_limits(
+            output_limits,
+        )?))
     }
 }
 
@@ -82,6 +127,8 @@ pub trait DistantApi {
     /// Reads bytes from a file.
     ///
     /// * `path` - the path to the file
+    /// * `offset` - if provided, reads starting at this byte offset within the file
+    /// * `len` - if provided, reads at most this many bytes
     ///
     /// *Override this, otherwise it will return "unsupported" as an error.*
     #[allow(unused_variables)]
@@ -89,6 +136,8 @@ pub trait DistantApi {
         &self,
         ctx: DistantCtx<Self::LocalData>,
         path: PathBuf,
+        offset: Option<u64>,
+        len: Option<u64>,
     ) -> io::Result<Vec<u8>> {
         unsupported("read_file")
     }
@@ -111,6 +160,9 @@ pub trait DistantApi {
     ///
     /// * `path` - the path to the file
     /// * `data` - the data to write
+    /// * `offset` - if provided, writes the data at this byte offset within the file instead of
+    ///   overwriting the whole file, creating the file if it does not exist
+    /// * `mode` - if provided, the permissions given to the file if it is created
     ///
     /// *Override this, otherwise it will return "unsupported" as an error.*
     #[allow(unused_variables)]
@@ -119,6 +171,8 @@ pub trait DistantApi {
         ctx: DistantCtx<Self::LocalData>,
         path: PathBuf,
         data: Vec<u8>,
+        offset: Option<u64>,
+        mode: Option<u32>,
     ) -> io::Result<()> {
         unsupported("write_file")
     }
@@ -171,6 +225,17 @@ pub trait DistantApi {
         unsupported("append_file_text")
     }
 
+    /// Checks that `data` may be written to the file at `path`, where `data` is none if its
+    /// contents do not come from the client, such as when copying. This is used by operations
+    /// that write a file by way of several others, such as transactions that stage a write next
+    /// to a file before renaming it into place, so that the path finally written is checked.
+    ///
+    /// *Override this to reject writes, otherwise every write is permitted.*
+    #[allow(unused_variables)]
+    async fn check_write(&self, path: &Path, data: Option<&[u8]>) -> io::Result<()> {
+        Ok(())
+    }
+
     /// Reads entries from a directory.
     ///
     /// * `path` - the path to the directory
@@ -178,9 +243,10 @@ pub trait DistantApi {
     /// * `absolute` - if true, will return absolute paths instead of relative paths
     /// * `canonicalize` - if true, will canonicalize entry paths before returned
     /// * `include_root` - if true, will include the directory specified in the entries
+    /// * `options` - sorting and filtering to apply to the entries within each directory
     ///
     /// *Override this, otherwise it will return "unsupported" as an error.*
-    #[allow(unused_variables)]
+    #[allow(unused_variables, clippy::too_many_arguments)]
     async fn read_dir(
         &self,
         ctx: DistantCtx<Self::LocalData>,
@@ -189,14 +255,69 @@ pub trait DistantApi {
         absolute: bool,
         canonicalize: bool,
         include_root: bool,
+        options: DirReadOptions,
     ) -> io::Result<(Vec<DirEntry>, Vec<io::Error>)> {
         unsupported("read_dir")
     }
 
+    /// Reads entries from a directory like [`read_dir`](Self::read_dir), returning the first
+    /// chunk of at most `chunk_size` entries and sending the rest through the reply of the context
+    /// as `dir_entries_chunk` responses after returning. Any errors are included with the last
+    /// chunk, which is also the only chunk marked as done, where the returned flag is true if the
+    /// first chunk is also the last.
+    ///
+    /// *Override this to send entries as they are read, otherwise every entry is read using
+    /// `read_dir` before any are sent.*
+    #[allow(clippy::too_many_arguments)]
+    async fn read_dir_chunked(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        depth: usize,
+        absolute: bool,
+        canonicalize: bool,
+        include_root: bool,
+        options: DirReadOptions,
+        chunk_size: usize,
+    ) -> io::Result<(Vec<DirEntry>, Vec<io::Error>, bool)> {
+        let reply = ctx.reply.clone_reply();
+        let (entries, errors) = self
+            .read_dir(
+                ctx,
+                path,
+                depth,
+                absolute,
+                canonicalize,
+                include_root,
+                options,
+            )
+            .await?;
+        Ok(send_dir_entries_chunks(reply.as_ref(), entries, errors, chunk_size).await)
+    }
+
+    /// Lists every file under a directory along with its size, last modified time, and hash of
+    /// its content, sending the files through the reply of the context as `dir_manifest_chunk`
+    /// responses after returning, the last of which is marked as done.
+    ///
+    /// * `path` - the path to the directory
+    /// * `algorithm` - the algorithm used to hash the content of each file
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn dir_manifest(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        algorithm: HashAlgorithm,
+    ) -> io::Result<()> {
+        unsupported("dir_manifest")
+    }
+
     /// Creates a directory.
     ///
     /// * `path` - the path to the directory
     /// * `all` - if true, will create all missing parent components
+    /// * `mode` - if provided, the permissions given to each directory that is created
     ///
     /// *Override this, otherwise it will return "unsupported" as an error.*
     #[allow(unused_variables)]
@@ -205,10 +326,57 @@ pub trait DistantApi {
         ctx: DistantCtx<Self::LocalData>,
         path: PathBuf,
         all: bool,
+        mode: Option<u32>,
     ) -> io::Result<()> {
         unsupported("create_dir")
     }
 
+    /// Creates a named pipe (FIFO).
+    ///
+    /// * `path` - the path to the named pipe
+    /// * `mode` - if provided, the permissions given to the named pipe
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn create_fifo(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        mode: Option<u32>,
+    ) -> io::Result<()> {
+        unsupported("create_fifo")
+    }
+
+    /// Creates a uniquely-named, empty file, returning its path.
+    ///
+    /// * `parent` - the directory in which to create the file, or the temporary directory if
+    ///   not provided
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn make_temp_file(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        parent: Option<PathBuf>,
+    ) -> io::Result<PathBuf> {
+        unsupported("make_temp_file")
+    }
+
+    /// Creates a uniquely-named, empty directory, returning its path.
+    ///
+    /// * `parent` - the directory in which to create the directory, or the temporary directory
+    ///   if not provided
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn make_temp_dir(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        parent: Option<PathBuf>,
+    ) -> io::Result<PathBuf> {
+        unsupported("make_temp_dir")
+    }
+
     /// Copies some file or directory.
     ///
     /// * `src` - the path to the file or directory to copy
@@ -257,12 +425,82 @@ pub trait DistantApi {
         unsupported("rename")
     }
 
+    /// Creates a symlink, replacing any file or symlink that is already at its path.
+    ///
+    /// * `path` - the path of the symlink
+    /// * `target` - the path that the symlink points to
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn create_symlink(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        target: PathBuf,
+    ) -> io::Result<()> {
+        unsupported("create_symlink")
+    }
+
+    /// Takes a snapshot of a file or directory, returning the id of the snapshot.
+    ///
+    /// * `path` - the path to the file or directory
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn create_snapshot(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+    ) -> io::Result<SnapshotId> {
+        unsupported("create_snapshot")
+    }
+
+    /// Lists the snapshots that have been taken.
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn list_snapshots(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+    ) -> io::Result<Vec<SnapshotInfo>> {
+        unsupported("list_snapshots")
+    }
+
+    /// Replaces the file or directory that a snapshot was taken of with the snapshot.
+    ///
+    /// * `id` - the id of the snapshot
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn restore_snapshot(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: SnapshotId,
+    ) -> io::Result<()> {
+        unsupported("restore_snapshot")
+    }
+
+    /// Removes a snapshot.
+    ///
+    /// * `id` - the id of the snapshot
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn remove_snapshot(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: SnapshotId,
+    ) -> io::Result<()> {
+        unsupported("remove_snapshot")
+    }
+
     /// Watches a file or directory for changes.
     ///
     /// * `path` - the path to the file or directory
     /// * `recursive` - if true, will watch for changes within subdirectories and beyond
     /// * `only` - if non-empty, will limit reported changes to those included in this list
     /// * `except` - if non-empty, will limit reported changes to those not included in this list
+    /// * `id` - if provided, tells this watch apart from others of the path over the connection
     ///
     /// *Override this, otherwise it will return "unsupported" as an error.*
     #[allow(unused_variables)]
@@ -273,6 +511,7 @@ pub trait DistantApi {
         recursive: bool,
         only: Vec<ChangeKind>,
         except: Vec<ChangeKind>,
+        id: Option<WatchId>,
     ) -> io::Result<()> {
         unsupported("watch")
     }
@@ -280,13 +519,67 @@ pub trait DistantApi {
     /// Removes a file or directory from being watched.
     ///
     /// * `path` - the path to the file or directory
+    /// * `id` - if provided, only removes the watch with this id, leaving other watches in place
     ///
     /// *Override this, otherwise it will return "unsupported" as an error.*
     #[allow(unused_variables)]
-    async fn unwatch(&self, ctx: DistantCtx<Self::LocalData>, path: PathBuf) -> io::Result<()> {
+    async fn unwatch(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        id: Option<WatchId>,
+    ) -> io::Result<()> {
         unsupported("unwatch")
     }
 
+    /// Starts recording changes to a file or directory in a journal that outlives the connection.
+    ///
+    /// * `path` - the path to the file or directory
+    /// * `recursive` - if true, will record changes within subdirectories and beyond
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn journal_start(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        recursive: bool,
+    ) -> io::Result<()> {
+        unsupported("journal_start")
+    }
+
+    /// Retrieves the changes recorded in the journal of a file or directory, returning the
+    /// sequence number of the latest change alongside them.
+    ///
+    /// * `path` - the path to the file or directory
+    /// * `since` - sequence number of the last change already seen, where only later changes
+    ///   are returned
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn journal_changes(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        since: JournalSeq,
+    ) -> io::Result<(JournalSeq, Vec<JournalEntry>)> {
+        unsupported("journal_changes")
+    }
+
+    /// Stops recording changes to a file or directory, discarding its journal.
+    ///
+    /// * `path` - the path to the file or directory
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn journal_stop(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+    ) -> io::Result<()> {
+        unsupported("journal_stop")
+    }
+
     /// Checks if the specified path exists.
     ///
     /// * `path` - the path to the file or directory
@@ -343,15 +636,36 @@ pub trait DistantApi {
         unsupported("cancel_search")
     }
 
+    /// Requests the next page of results of an actively-ongoing search performed on demand,
+    /// which are sent through the reply of the context that started the search.
+    ///
+    /// * `id` - the id of the search
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn search_next(&self, ctx: DistantCtx<Self::LocalData>, id: SearchId) -> io::Result<()> {
+        unsupported("search_next")
+    }
+
     /// Spawns a new process, returning its id.
     ///
     /// * `cmd` - the full command to run as a new process (including arguments)
     /// * `environment` - the environment variables to associate with the process
     /// * `current_dir` - the alternative current directory to use with the process
     /// * `pty` - if provided, will run the process within a PTY of the given size
+    /// * `output_window` - if provided, the maximum bytes of stdout and stderr to send before
+    ///   waiting for them to be acknowledged
+    /// * `timestamped` - if true, stdout and stderr are sent with a sequence number and timestamp
+    ///   shared by both
+    /// * `session` - if provided, keeps the process running under this session while no client
+    ///   is attached to it
+    /// * `limits` - the priority and limits on resources to apply to the process before it runs
+    /// * `kill_tree` - if true, killing the process also kills the processes it started
+    /// * `user` - if provided, the user to run the process as
     ///
     /// *Override this, otherwise it will return "unsupported" as an error.*
     #[allow(unused_variables)]
+    #[allow(clippy::too_many_arguments)]
     async fn proc_spawn(
         &self,
         ctx: DistantCtx<Self::LocalData>,
@@ -359,10 +673,68 @@ pub trait DistantApi {
         environment: Environment,
         current_dir: Option<PathBuf>,
         pty: Option<PtySize>,
+        output_window: Option<usize>,
+        timestamped: bool,
+        session: Option<SessionId>,
+        limits: ProcLimits,
+        kill_tree: bool,
+        user: Option<String>,
     ) -> io::Result<ProcessId> {
         unsupported("proc_spawn")
     }
 
+    /// Returns the output window given to a process spawned asking for `output_window`, which is
+    /// reported back to the client so that it knows when to acknowledge output.
+    ///
+    /// *Override this to limit output windows, otherwise processes are given the window asked
+    /// for.*
+    fn output_window(&self, output_window: Option<usize>) -> Option<usize> {
+        output_window
+    }
+
+    /// Spawns a new process detached from the server, which keeps running after the server exits,
+    /// returning the id given to it by the operating system.
+    ///
+    /// * `cmd` - the full command to run as a new process (including arguments)
+    /// * `environment` - the environment variables to associate with the process
+    /// * `current_dir` - the alternative current directory to use with the process
+    /// * `limits` - the priority and limits on resources to apply to the process before it runs
+    /// * `user` - if provided, the user to run the process as
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn proc_spawn_detached(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        cmd: String,
+        environment: Environment,
+        current_dir: Option<PathBuf>,
+        limits: ProcLimits,
+        user: Option<String>,
+    ) -> io::Result<u32> {
+        unsupported("proc_spawn_detached")
+    }
+
+    /// Sends the output of a running process to the reply of `ctx` going forward, in place of the
+    /// reply of the request that spawned it, returning the id of the process.
+    ///
+    /// * `id` - the unique id of the process
+    /// * `session` - if provided, the session of the persistent process to attach to instead
+    /// * `replay` - if true, output of a persistent process that could not be sent while no
+    ///   client was attached is sent first
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn proc_attach(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: ProcessId,
+        session: Option<SessionId>,
+        replay: bool,
+    ) -> io::Result<ProcessId> {
+        unsupported("proc_attach")
+    }
+
     /// Kills a running process by its id.
     ///
     /// * `id` - the unique id of the process
@@ -373,6 +745,36 @@ pub trait DistantApi {
         unsupported("proc_kill")
     }
 
+    /// Lists the running processes spawned by the server.
+    ///
+    /// * `all` - if true, will also include every other process of the system
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn proc_list(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        all: bool,
+    ) -> io::Result<Vec<ProcInfo>> {
+        unsupported("proc_list")
+    }
+
+    /// Sends a signal to a running process by its id.
+    ///
+    /// * `id` - the unique id of the process
+    /// * `signal` - the signal to send to the process
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn proc_signal(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: ProcessId,
+        signal: Signal,
+    ) -> io::Result<()> {
+        unsupported("proc_signal")
+    }
+
     /// Sends data to the stdin of the process with the specified id.
     ///
     /// * `id` - the unique id of the process
@@ -389,6 +791,38 @@ pub trait DistantApi {
         unsupported("proc_stdin")
     }
 
+    /// Closes the stdin of the process with the specified id, which then reads EOF while it keeps
+    /// running.
+    ///
+    /// * `id` - the unique id of the process
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn proc_stdin_close(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: ProcessId,
+    ) -> io::Result<()> {
+        unsupported("proc_stdin_close")
+    }
+
+    /// Acknowledges output sent by the process with the specified id, allowing it to send more
+    /// when it was spawned with an output window.
+    ///
+    /// * `id` - the unique id of the process
+    /// * `len` - the total bytes of stdout and stderr being acknowledged
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn proc_output_ack(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: ProcessId,
+        len: usize,
+    ) -> io::Result<()> {
+        unsupported("proc_output_ack")
+    }
+
     /// Resizes the PTY of the process with the specified id.
     ///
     /// * `id` - the unique id of the process
@@ -405,112 +839,469 @@ pub trait DistantApi {
         unsupported("proc_resize_pty")
     }
 
-    /// Retrieves information about the system.
+    /// Opens a TCP connection from the server to `host` and `port`, returning the id of the
+    /// tunnel, where data read from the connection is sent back through the reply of the request.
+    ///
+    /// * `host` - the host to connect to
+    /// * `port` - the port to connect to
     ///
     /// *Override this, otherwise it will return "unsupported" as an error.*
     #[allow(unused_variables)]
-    async fn system_info(&self, ctx: DistantCtx<Self::LocalData>) -> io::Result<SystemInfo> {
-        unsupported("system_info")
+    async fn tunnel_open(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        host: String,
+        port: u16,
+    ) -> io::Result<TunnelId> {
+        unsupported("tunnel_open")
     }
-}
 
-#[async_trait]
-impl<T, D> ServerHandler for DistantApiServerHandler<T, D>
-where
-    T: DistantApi<LocalData = D> + Send + Sync,
-    D: Send + Sync,
-{
-    type Request = DistantMsg<DistantRequestData>;
-    type Response = DistantMsg<DistantResponseData>;
-    type LocalData = D;
+    /// Sends data through the tunnel with the specified id.
+    ///
+    /// * `id` - the unique id of the tunnel
+    /// * `data` - the bytes to send
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn tunnel_write(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: TunnelId,
+        data: Vec<u8>,
+    ) -> io::Result<()> {
+        unsupported("tunnel_write")
+    }
 
-    /// Overridden to leverage [`DistantApi`] implementation of `on_accept`
-    async fn on_accept(&self, ctx: ConnectionCtx<'_, Self::LocalData>) -> io::Result<()> {
-        T::on_accept(&self.api, ctx).await
+    /// Stops sending data through the tunnel with the specified id, which keeps sending back
+    /// what it reads until the other end closes it.
+    ///
+    /// * `id` - the unique id of the tunnel
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn tunnel_close(&self, ctx: DistantCtx<Self::LocalData>, id: TunnelId) -> io::Result<()> {
+        unsupported("tunnel_close")
     }
 
-    async fn on_request(&self, ctx: ServerCtx<Self::Request, Self::Response, Self::LocalData>) {
-        let ServerCtx {
+    /// Listens for TCP connections on the server at `host` and `port`, returning the id of the
+    /// listener and the port it is bound to, where each connection accepted is announced through
+    /// the reply of the request before data read from it is sent back the same way.
+    ///
+    /// * `host` - the address to listen on
+    /// * `port` - the port to listen on, where 0 picks any free port
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn tunnel_listen(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        host: String,
+        port: u16,
+    ) -> io::Result<(TunnelId, u16)> {
+        unsupported("tunnel_listen")
+    }
+
+    /// Listens for connections on a new unix socket in a directory of the server that only its
+    /// user can access, returning the id of the listener and the path of the socket, where each
+    /// connection accepted is announced through the reply of the request before data read from
+    /// it is sent back the same way.
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn tunnel_listen_unix(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+    ) -> io::Result<(TunnelId, PathBuf)> {
+        unsupported("tunnel_listen_unix")
+    }
+
+    /// Stops the listener with the specified id from accepting connections.
+    ///
+    /// * `id` - the unique id of the listener
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn tunnel_unlisten(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: TunnelId,
+    ) -> io::Result<()> {
+        unsupported("tunnel_unlisten")
+    }
+
+    /// Retrieves information about the system.
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn system_info(&self, ctx: DistantCtx<Self::LocalData>) -> io::Result<SystemInfo> {
+        unsupported("system_info")
+    }
+
+    /// Retrieves the environment variables of the server that are safe to share with clients.
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn system_env(&self, ctx: DistantCtx<Self::LocalData>) -> io::Result<Environment> {
+        unsupported("system_env")
+    }
+
+    /// Retrieves metrics about the data written on behalf of the connection.
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn metrics(&self, ctx: DistantCtx<Self::LocalData>) -> io::Result<Metrics> {
+        unsupported("metrics")
+    }
+
+    /// Retrieves the resource usage of the system, returning it along with an id that can be
+    /// used to cancel additional reports.
+    ///
+    /// * `interval` - if provided, will continue to report usage through the reply of the
+    ///   context each time the interval elapses until canceled
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn system_stats(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        interval: Option<Duration>,
+    ) -> io::Result<(SystemStatsId, SystemStats)> {
+        unsupported("system_stats")
+    }
+
+    /// Retrieves the resource usage of a spawned process, returning it along with an id that can
+    /// be used to cancel additional reports.
+    ///
+    /// * `id` - the id of the process
+    /// * `interval` - if provided, will continue to report usage through the reply of the
+    ///   context each time the interval elapses until canceled or the process exits
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn proc_stats(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: ProcessId,
+        interval: Option<Duration>,
+    ) -> io::Result<(SystemStatsId, ProcStats)> {
+        unsupported("proc_stats")
+    }
+
+    /// Stops reporting the resource usage of the system or a process.
+    ///
+    /// * `id` - the id of the reporting to cancel
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn cancel_system_stats(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: SystemStatsId,
+    ) -> io::Result<()> {
+        unsupported("cancel_system_stats")
+    }
+
+    /// Sends a signal to any process of the system, not just those spawned by the server.
+    ///
+    /// * `pid` - the pid of the process given by the operating system
+    /// * `signal` - the signal to send to the process
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn system_kill(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        pid: u32,
+        signal: Signal,
+    ) -> io::Result<()> {
+        unsupported("system_kill")
+    }
+
+    /// Handles a custom request using whichever extension is registered for it.
+    ///
+    /// * `name` - the name of the custom request, which starts with `x-`
+    /// * `payload` - the arbitrary data provided with the request
+    ///
+    /// *Override this, otherwise it will return "unsupported" as an error.*
+    #[allow(unused_variables)]
+    async fn extension(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        name: String,
+        payload: serde_json::Value,
+    ) -> io::Result<serde_json::Value> {
+        unsupported("extension")
+    }
+}
+
+#[async_trait]
+impl<T, D> ServerHandler for DistantApiServerHandler<T, D>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    type Request = DistantMsg<DistantRequestData>;
+    type Response = DistantMsg<DistantResponseData>;
+    type LocalData = D;
+
+    /// Overridden to leverage [`DistantApi`] implementation of `on_accept`
+    async fn on_accept(&self, ctx: ConnectionCtx<'_, Self::LocalData>) -> io::Result<()> {
+        T::on_accept(&self.api, ctx).await
+    }
+
+    async fn on_request(&self, ctx: ServerCtx<Self::Request, Self::Response, Self::LocalData>) {
+        let ServerCtx {
             connection_id,
             request,
             reply,
             local_data,
+            clients,
         } = ctx;
 
         // Convert our reply to a queued reply so we can ensure that the result
         // of an API function is sent back before anything else
         let reply = reply.queue();
 
-        // Process single vs batch requests
-        let response = match request.payload {
-            DistantMsg::Single(data) => {
-                let ctx = DistantCtx {
-                    connection_id,
-                    reply: Box::new(DistantSingleReply::from(reply.clone_reply())),
-                    local_data,
-                };
-
-                let data = handle_request(self, ctx, data).await;
-
-                // Report outgoing errors in our debug logs
-                if let DistantResponseData::Error(x) = &data {
-                    debug!("[Conn {}] {}", connection_id, x);
-                }
+        // Logs about the request name its correlation id (when it has one) so that it can be
+        // traced back through the manager and client
+        let context = match request.correlation_id.as_deref() {
+            Some(correlation_id) => {
+                format!(
+                    "[Conn {connection_id} :: Req {} :: Corr {correlation_id}]",
+                    request.id
+                )
+            }
+            None => format!("[Conn {connection_id} :: Req {}]", request.id),
+        };
+
+        // The deadline is relative to when the request arrived, which is (roughly) now
+        let deadline = request
+            .deadline()
+            .and_then(|deadline| Instant::now().checked_add(deadline));
 
-                DistantMsg::Single(data)
+        // Paths are translated for connections that have set mappings, both within the request
+        // and within anything sent back for it, including replies sent later such as watch events
+        let path_map = self.path_maps.get(connection_id);
+        let make_reply = || -> Box<dyn Reply<Data = DistantResponseData>> {
+            let reply = Box::new(DistantSingleReply::from(reply.clone_reply()));
+            match path_map.as_ref() {
+                Some(path_map) => Box::new(DistantPathMapReply::new(reply, Arc::clone(path_map))),
+                None => reply,
             }
-            DistantMsg::Batch(list) => {
-                let mut out = Vec::new();
+        };
 
-                for data in list {
+        // Process single vs batch requests
+        let process = async {
+            match request.payload {
+                DistantMsg::Single(data) => {
                     let ctx = DistantCtx {
                         connection_id,
-                        reply: Box::new(DistantSingleReply::from(reply.clone_reply())),
-                        local_data: Arc::clone(&local_data),
+                        reply: make_reply(),
+                        local_data,
+                        deadline,
                     };
 
-                    // TODO: This does not run in parallel, meaning that the next item in the
-                    //       batch will not be queued until the previous item completes! This
-                    //       would be useful if we wanted to chain requests where the previous
-                    //       request feeds into the current request, but not if we just want
-                    //       to run everything together. So we should instead rewrite this
-                    //       to spawn a task per request and then await completion of all tasks
-                    let data = handle_request(self, ctx, data).await;
+                    let mut data =
+                        handle_mapped_request(self, ctx, data, path_map.as_deref(), &clients).await;
 
                     // Report outgoing errors in our debug logs
-                    if let DistantResponseData::Error(x) = &data {
-                        debug!("[Conn {}] {}", connection_id, x);
+                    if let DistantResponseData::Error(x) = &mut data {
+                        debug!("{context} {x}");
+                        tag_error(x, request.correlation_id.as_deref());
                     }
 
-                    out.push(data);
+                    DistantMsg::Single(data)
                 }
+                DistantMsg::Batch(list) => {
+                    let mut out = Vec::new();
+
+                    for data in list {
+                        let ctx = DistantCtx {
+                            connection_id,
+                            reply: make_reply(),
+                            local_data: Arc::clone(&local_data),
+                            deadline,
+                        };
+
+                        // TODO: This does not run in parallel, meaning that the next item in the
+                        //       batch will not be queued until the previous item completes! This
+                        //       would be useful if we wanted to chain requests where the previous
+                        //       request feeds into the current request, but not if we just want
+                        //       to run everything together. So we should instead rewrite this
+                        //       to spawn a task per request and then await completion of all tasks
+                        let mut data =
+                            handle_mapped_request(self, ctx, data, path_map.as_deref(), &clients)
+                                .await;
 
-                DistantMsg::Batch(out)
+                        // Report outgoing errors in our debug logs
+                        if let DistantResponseData::Error(x) = &mut data {
+                            debug!("{context} {x}");
+                            tag_error(x, request.correlation_id.as_deref());
+                        }
+
+                        out.push(data);
+                    }
+
+                    DistantMsg::Batch(out)
+                }
             }
         };
 
+        // Retries of a request with an idempotency key get the result of the first attempt
+        // rather than performing it again
+        let response = match request.idempotency_key {
+            Some(key) => self.results.get_or_init(connection_id, key, process).await,
+            None => process.await,
+        };
+
         // Queue up our result to go before ANY of the other messages that might be sent.
         // This is important to avoid situations such as when a process is started, but before
         // the confirmation can be sent some stdout or stderr is captured and sent first.
         if let Err(x) = reply.send_before(response).await {
-            error!("[Conn {}] Failed to send response: {}", connection_id, x);
+            error!("{context} Failed to send response: {x}");
         }
 
         // Flush out all of our replies thus far and toggle to no longer hold submissions
         if let Err(x) = reply.flush(false).await {
-            error!(
-                "[Conn {}] Failed to flush response queue: {}",
-                connection_id, x
-            );
+            error!("{context} Failed to flush response queue: {x}");
+        }
+    }
+}
+
+/// Notes `correlation_id` (if any) in the description of an error sent back for a request, so that
+/// whoever sees the error can find the request in the logs of each process that handled it
+fn tag_error(error: &mut Error, correlation_id: Option<&str>) {
+    if let Some(correlation_id) = correlation_id {
+        error.description = format!("{} (correlation id {correlation_id})", error.description);
+    }
+}
+
+/// Processes an incoming request, translating the paths within it (and its response) for the
+/// connection when it has a path map
+async fn handle_mapped_request<T, D>(
+    server: &DistantApiServerHandler<T, D>,
+    ctx: DistantCtx<D>,
+    mut request: DistantRequestData,
+    path_map: Option<&PathMap>,
+    clients: &ServerClients,
+) -> DistantResponseData
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let Some(path_map) = path_map else {
+        return handle_request_before_deadline(server, ctx, request, clients).await;
+    };
+
+    if let Err(x) = path_map.to_server(&mut request) {
+        return DistantResponseData::Error(x);
+    }
+
+    // Paths as written can still lead outside of the roots through symlinks, which only the
+    // server can resolve
+    if path_map.has_roots() {
+        for path in PathMap::paths(&mut request) {
+            match canonicalize(server, &ctx, &path).await {
+                Ok(resolved) if path_map.is_within_roots(&resolved) => (),
+                Ok(_) => {
+                    return DistantResponseData::Error(Error {
+                        kind: ErrorKind::PermissionDenied,
+                        description: format!(
+                            "{} leads outside of the declared roots",
+                            path.display()
+                        ),
+                    })
+                }
+                Err(x) => return DistantResponseData::from(x),
+            }
+        }
+    }
+
+    let mut response = handle_request_before_deadline(server, ctx, request, clients).await;
+    path_map.to_client(&mut response);
+    response
+}
+
+/// Resolves `path` into its canonical form using the api, where a path that does not exist yet is
+/// resolved through the closest of its ancestors that does
+async fn canonicalize<T, D>(
+    server: &DistantApiServerHandler<T, D>,
+    ctx: &DistantCtx<D>,
+    path: &Path,
+) -> io::Result<PathBuf>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let mut missing = Vec::new();
+    let mut current = path;
+    loop {
+        let ctx = DistantCtx {
+            connection_id: ctx.connection_id,
+            reply: ctx.reply.clone_reply(),
+            local_data: Arc::clone(&ctx.local_data),
+            deadline: ctx.deadline,
+        };
+
+        match server
+            .api
+            .metadata(ctx, current.to_path_buf(), true, false)
+            .await
+        {
+            Ok(metadata) => {
+                let mut resolved = metadata
+                    .canonicalized_path
+                    .unwrap_or_else(|| current.to_path_buf());
+                resolved.extend(missing.into_iter().rev());
+                return Ok(resolved);
+            }
+            Err(x) if x.kind() == io::ErrorKind::NotFound => {
+                match (current.parent(), current.file_name()) {
+                    (Some(parent), Some(name)) => {
+                        missing.push(name.to_os_string());
+                        current = parent;
+                    }
+                    _ => return Err(x),
+                }
+            }
+            Err(x) => return Err(x),
         }
     }
 }
 
-/// Processes an incoming request
+/// Processes an incoming request, aborting it if it does not complete before the deadline of its
+/// context
+async fn handle_request_before_deadline<T, D>(
+    server: &DistantApiServerHandler<T, D>,
+    ctx: DistantCtx<D>,
+    request: DistantRequestData,
+    clients: &ServerClients,
+) -> DistantResponseData
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let Some(deadline) = ctx.deadline else {
+        return handle_request(server, ctx, request, clients).await;
+    };
+
+    let response = handle_request(server, ctx, request, clients);
+    match tokio::time::timeout_at(deadline.into(), response).await {
+        Ok(response) => response,
+        Err(_) => DistantResponseData::Error(Error {
+            kind: ErrorKind::DeadlineExceeded,
+            description: String::from("Request did not complete before its deadline"),
+        }),
+    }
+}
+
+/// Processes an incoming request, using `clients` for requests that manage the clients connected
+/// to the server
 async fn handle_request<T, D>(
     server: &DistantApiServerHandler<T, D>,
     ctx: DistantCtx<D>,
     request: DistantRequestData,
+    clients: &ServerClients,
 ) -> DistantResponseData
 where
     T: DistantApi<LocalData = D> + Send + Sync,
@@ -523,9 +1314,9 @@ where
             .await
             .map(|supported| DistantResponseData::Capabilities { supported })
             .unwrap_or_else(DistantResponseData::from),
-        DistantRequestData::FileRead { path } => server
+        DistantRequestData::FileRead { path, offset, len } => server
             .api
-            .read_file(ctx, path)
+            .read_file(ctx, path, offset, len)
             .await
             .map(|data| DistantResponseData::Blob { data })
             .unwrap_or_else(DistantResponseData::from),
@@ -535,12 +1326,20 @@ where
             .await
             .map(|data| DistantResponseData::Text { data })
             .unwrap_or_else(DistantResponseData::from),
-        DistantRequestData::FileWrite { path, data } => server
+        DistantRequestData::FileWrite {
+            path,
+            data,
+            offset,
+            mode,
+        } => server
             .api
-            .write_file(ctx, path, data)
+            .write_file(ctx, path, data, offset, mode)
             .await
             .map(|_| DistantResponseData::Ok)
             .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::FileWriteDedup { path, hash, data } => {
+            dedup::run(&server.api, ctx, &server.content, path, hash, data).await
+        }
         DistantRequestData::FileWriteText { path, text } => server
             .api
             .write_file_text(ctx, path, text)
@@ -565,21 +1364,116 @@ where
             absolute,
             canonicalize,
             include_root,
-        } => server
+            options,
+            chunk_size,
+        } => match chunk_size {
+            Some(size) if size > 0 => server
+                .api
+                .read_dir_chunked(
+                    ctx,
+                    path,
+                    depth,
+                    absolute,
+                    canonicalize,
+                    include_root,
+                    options,
+                    size,
+                )
+                .await
+                .map(
+                    |(entries, errors, done)| DistantResponseData::DirEntriesChunk {
+                        entries,
+                        errors: errors.into_iter().map(Error::from).collect(),
+                        done,
+                    },
+                )
+                .unwrap_or_else(DistantResponseData::from),
+            _ => server
+                .api
+                .read_dir(
+                    ctx,
+                    path,
+                    depth,
+                    absolute,
+                    canonicalize,
+                    include_root,
+                    options,
+                )
+                .await
+                .map(|(entries, errors)| DistantResponseData::DirEntries {
+                    entries,
+                    errors: errors.into_iter().map(Error::from).collect(),
+                })
+                .unwrap_or_else(DistantResponseData::from),
+        },
+        DistantRequestData::DirManifest { path, algorithm } => server
             .api
-            .read_dir(ctx, path, depth, absolute, canonicalize, include_root)
+            .dir_manifest(ctx, path, algorithm)
             .await
-            .map(|(entries, errors)| DistantResponseData::DirEntries {
-                entries,
-                errors: errors.into_iter().map(Error::from).collect(),
-            })
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::DirCreate { path, all, mode } => server
+            .api
+            .create_dir(ctx, path, all, mode)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::FifoCreate { path, mode } => server
+            .api
+            .create_fifo(ctx, path, mode)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::MakeTempFile { parent } => server
+            .api
+            .make_temp_file(ctx, parent)
+            .await
+            .map(|path| DistantResponseData::TempCreated { path })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::MakeTempDir { parent } => server
+            .api
+            .make_temp_dir(ctx, parent)
+            .await
+            .map(|path| DistantResponseData::TempCreated { path })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::Batch { ops, atomic } => {
+            batch::run(&server.api, ctx, ops, atomic).await
+        }
+        DistantRequestData::Transaction { ops } => transaction::run(&server.api, ctx, ops).await,
+        DistantRequestData::Plan { op } => plan::run(&server.api, ctx, op)
+            .await
+            .map(|paths| DistantResponseData::Plan { paths })
             .unwrap_or_else(DistantResponseData::from),
-        DistantRequestData::DirCreate { path, all } => server
+        DistantRequestData::SnapshotCreate { path } => server
             .api
-            .create_dir(ctx, path, all)
+            .create_snapshot(ctx, path)
+            .await
+            .map(|id| DistantResponseData::SnapshotCreated { id })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::SnapshotList {} => server
+            .api
+            .list_snapshots(ctx)
+            .await
+            .map(|entries| DistantResponseData::Snapshots { entries })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::SnapshotRestore { id } => server
+            .api
+            .restore_snapshot(ctx, id)
             .await
             .map(|_| DistantResponseData::Ok)
             .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::SnapshotRemove { id } => server
+            .api
+            .remove_snapshot(ctx, id)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::Diff { src, dst, context } => {
+            diff::run(&server.api, ctx, src, dst, context)
+                .await
+                .map(|patch| DistantResponseData::Diff { patch })
+                .unwrap_or_else(DistantResponseData::from)
+        }
         DistantRequestData::Remove { path, force } => server
             .api
             .remove(ctx, path, force)
@@ -603,15 +1497,34 @@ where
             recursive,
             only,
             except,
+            id,
         } => server
             .api
-            .watch(ctx, path, recursive, only, except)
+            .watch(ctx, path, recursive, only, except, id)
             .await
             .map(|_| DistantResponseData::Ok)
             .unwrap_or_else(DistantResponseData::from),
-        DistantRequestData::Unwatch { path } => server
+        DistantRequestData::Unwatch { path, id } => server
+            .api
+            .unwatch(ctx, path, id)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::JournalStart { path, recursive } => server
+            .api
+            .journal_start(ctx, path, recursive)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::JournalChanges { path, since } => server
+            .api
+            .journal_changes(ctx, path, since)
+            .await
+            .map(|(seq, entries)| DistantResponseData::JournalChanges { seq, entries })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::JournalStop { path } => server
             .api
-            .unwatch(ctx, path)
+            .journal_stop(ctx, path)
             .await
             .map(|_| DistantResponseData::Ok)
             .unwrap_or_else(DistantResponseData::from),
@@ -643,16 +1556,89 @@ where
             .await
             .map(|_| DistantResponseData::Ok)
             .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::SearchNext { id } => server
+            .api
+            .search_next(ctx, id)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
         DistantRequestData::ProcSpawn {
             cmd,
             environment,
             current_dir,
             pty,
+            output_window,
+            timestamped: _,
+            persist,
+            limits,
+            kill_tree: _,
+            user,
+            detached: true,
+        } => {
+            if pty.is_some() || output_window.is_some() || persist {
+                return DistantResponseData::from(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Detached processes cannot have a pty, output window, or session",
+                ));
+            }
+
+            server
+                .api
+                .proc_spawn_detached(ctx, cmd.into(), environment, current_dir, limits, user)
+                .await
+                .map(|pid| DistantResponseData::ProcDetached { pid })
+                .unwrap_or_else(DistantResponseData::from)
+        }
+        DistantRequestData::ProcSpawn {
+            cmd,
+            environment,
+            current_dir,
+            pty,
+            output_window,
+            timestamped,
+            persist,
+            limits,
+            kill_tree,
+            user,
+            detached: false,
+        } => {
+            let session = persist.then(rand::random);
+            server
+                .api
+                .proc_spawn(
+                    ctx,
+                    cmd.into(),
+                    environment,
+                    current_dir,
+                    pty,
+                    output_window,
+                    timestamped,
+                    session,
+                    limits,
+                    kill_tree,
+                    user,
+                )
+                .await
+                .map(|id| DistantResponseData::ProcSpawned {
+                    id,
+                    session,
+                    output_window: server.api.output_window(output_window),
+                })
+                .unwrap_or_else(DistantResponseData::from)
+        }
+        DistantRequestData::ProcAttach {
+            id,
+            session,
+            replay,
         } => server
             .api
-            .proc_spawn(ctx, cmd.into(), environment, current_dir, pty)
+            .proc_attach(ctx, id, session, replay)
             .await
-            .map(|id| DistantResponseData::ProcSpawned { id })
+            .map(|id| DistantResponseData::ProcSpawned {
+                id,
+                session,
+                output_window: None,
+            })
             .unwrap_or_else(DistantResponseData::from),
         DistantRequestData::ProcKill { id } => server
             .api
@@ -660,23 +1646,388 @@ where
             .await
             .map(|_| DistantResponseData::Ok)
             .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::ProcList { all } => server
+            .api
+            .proc_list(ctx, all)
+            .await
+            .map(|entries| DistantResponseData::ProcEntries { entries })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::ProcSignal { id, signal } => server
+            .api
+            .proc_signal(ctx, id, signal)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
         DistantRequestData::ProcStdin { id, data } => server
             .api
             .proc_stdin(ctx, id, data)
             .await
             .map(|_| DistantResponseData::Ok)
             .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::ProcStdinClose { id } => server
+            .api
+            .proc_stdin_close(ctx, id)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::ProcOutputAck { id, len } => server
+            .api
+            .proc_output_ack(ctx, id, len)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
         DistantRequestData::ProcResizePty { id, size } => server
             .api
             .proc_resize_pty(ctx, id, size)
             .await
             .map(|_| DistantResponseData::Ok)
             .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::TunnelOpen { host, port } => server
+            .api
+            .tunnel_open(ctx, host, port)
+            .await
+            .map(|id| DistantResponseData::TunnelOpened { id })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::TunnelWrite { id, data } => server
+            .api
+            .tunnel_write(ctx, id, data)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::TunnelClose { id } => server
+            .api
+            .tunnel_close(ctx, id)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::TunnelListen { host, port } => server
+            .api
+            .tunnel_listen(ctx, host, port)
+            .await
+            .map(|(id, port)| DistantResponseData::TunnelListening { id, port })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::TunnelListenUnix {} => server
+            .api
+            .tunnel_listen_unix(ctx)
+            .await
+            .map(|(id, path)| DistantResponseData::TunnelListeningUnix { id, path })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::TunnelUnlisten { id } => server
+            .api
+            .tunnel_unlisten(ctx, id)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        // Answered without involving the api so that it reflects only the time spent in transit
+        DistantRequestData::Ping { reply_len, .. } => DistantResponseData::Pong {
+            data: vec![0; reply_len.min(MAX_PING_REPLY_LEN)],
+        },
         DistantRequestData::SystemInfo {} => server
             .api
             .system_info(ctx)
             .await
             .map(DistantResponseData::SystemInfo)
             .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::SystemEnv {} => server
+            .api
+            .system_env(ctx)
+            .await
+            .map(|env| DistantResponseData::SystemEnv { env })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::PathMap { mappings } => {
+            server.path_maps.set_mappings(ctx.connection_id, mappings);
+            DistantResponseData::Ok
+        }
+        DistantRequestData::Roots { roots } => {
+            let invalid = roots
+                .iter()
+                .find(|root| !WorkspaceRoot::is_valid_name(&root.name) || !root.path.has_root());
+            match invalid {
+                Some(root) => DistantResponseData::Error(Error {
+                    kind: ErrorKind::InvalidInput,
+                    description: format!("Invalid root {root}, expected an absolute path"),
+                }),
+                None => {
+                    // Roots are resolved once so that later mappings cannot move them
+                    let path_map = server.path_maps.get(ctx.connection_id);
+                    let mut resolved_roots = Vec::new();
+                    for root in roots.iter() {
+                        let path = match path_map.as_ref() {
+                            Some(path_map) => path_map.to_server_path(root.path.clone()),
+                            None => root.path.clone(),
+                        };
+                        match canonicalize(server, &ctx, &path).await {
+                            Ok(path) => resolved_roots.push(path),
+                            Err(x) => return DistantResponseData::from(x),
+                        }
+                    }
+
+                    server
+                        .path_maps
+                        .set_roots(ctx.connection_id, roots, resolved_roots)
+                        .map(|_| DistantResponseData::Ok)
+                        .unwrap_or_else(DistantResponseData::Error)
+                }
+            }
+        }
+        DistantRequestData::Metrics {} => server
+            .api
+            .metrics(ctx)
+            .await
+            .map(DistantResponseData::Metrics)
+            .unwrap_or_else(DistantResponseData::from),
+        // Clients are tracked by the server itself rather than the api
+        DistantRequestData::ClientList {} => DistantResponseData::ClientList {
+            clients: clients.list().await,
+        },
+        // The reply would never make it back to a client that disconnects itself
+        DistantRequestData::ClientDisconnect { id } if id == ctx.connection_id => {
+            DistantResponseData::Error(Error {
+                kind: ErrorKind::InvalidInput,
+                description: format!("Client {id} is the one making the request"),
+            })
+        }
+        DistantRequestData::ClientDisconnect { id } => match clients.disconnect(id).await {
+            Ok(()) => {
+                // The client can no longer reconnect, so it has no more need of its paths or content
+                server.path_maps.remove(id);
+                server.content.remove_connection(id);
+                DistantResponseData::Ok
+            }
+            Err(x) => DistantResponseData::from(x),
+        },
+        DistantRequestData::SystemStats { interval } => server
+            .api
+            .system_stats(ctx, interval.map(Duration::from_millis))
+            .await
+            .map(|(id, stats)| DistantResponseData::SystemStats { id, stats })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::ProcStats { id, interval } => server
+            .api
+            .proc_stats(ctx, id, interval.map(Duration::from_millis))
+            .await
+            .map(|(stats_id, stats)| DistantResponseData::ProcStats {
+                id,
+                stats_id,
+                stats,
+            })
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::CancelSystemStats { id } => server
+            .api
+            .cancel_system_stats(ctx, id)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::SystemKill { pid, signal } => server
+            .api
+            .system_kill(ctx, pid, signal)
+            .await
+            .map(|_| DistantResponseData::Ok)
+            .unwrap_or_else(DistantResponseData::from),
+        DistantRequestData::Extension { name, payload } => {
+            if !name.starts_with(EXTENSION_PREFIX) {
+                return DistantResponseData::from(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Extension {name:?} must be named with the {EXTENSION_PREFIX:?} prefix"
+                    ),
+                ));
+            }
+
+            server
+                .api
+                .extension(ctx, name.clone(), payload)
+                .await
+                .map(|payload| DistantResponseData::Extension { name, payload })
+                .unwrap_or_else(DistantResponseData::from)
+        }
+    }
+}
+
+/// Splits `entries` into chunks of at most `size` entries, sending all but the first chunk
+/// through `reply` and returning the first chunk along with whether it is the last.
+///
+/// The first chunk is returned rather than sent because the reply is queued until the response
+/// is sent, which means that returning it keeps the chunks in order. Any `errors` are included
+/// with the last chunk, which is also the only chunk marked as done.
+async fn send_dir_entries_chunks(
+    reply: &dyn Reply<Data = DistantResponseData>,
+    entries: Vec<DirEntry>,
+    errors: Vec<io::Error>,
+    size: usize,
+) -> (Vec<DirEntry>, Vec<io::Error>, bool) {
+    let mut chunks: Vec<Vec<DirEntry>> = Vec::new();
+    let mut entries = entries.into_iter().peekable();
+    while entries.peek().is_some() {
+        chunks.push(entries.by_ref().take(size).collect());
+    }
+
+    // Always produce at least one chunk so the done marker is sent for empty directories
+    let mut chunks = chunks.into_iter();
+    let first = chunks.next().unwrap_or_default();
+    if chunks.len() == 0 {
+        return (first, errors, true);
+    }
+
+    let mut errors: Vec<Error> = errors.into_iter().map(Error::from).collect();
+    while let Some(entries) = chunks.next() {
+        let done = chunks.len() == 0;
+        let response = DistantResponseData::DirEntriesChunk {
+            entries,
+            errors: if done {
+                std::mem::take(&mut errors)
+            } else {
+                Vec::new()
+            },
+            done,
+        };
+
+        if let Err(x) = reply.send(response).await {
+            error!("Failed to send directory entries chunk: {}", x);
+            break;
+        }
+    }
+
+    (first, Vec::new(), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+    use tokio::sync::mpsc;
+
+    /// Api whose reads of files never complete
+    struct StalledApi;
+
+    #[async_trait]
+    impl DistantApi for StalledApi {
+        type LocalData = ();
+
+        async fn read_file(
+            &self,
+            _ctx: DistantCtx<Self::LocalData>,
+            _path: PathBuf,
+            _offset: Option<u64>,
+            _len: Option<u64>,
+        ) -> io::Result<Vec<u8>> {
+            std::future::pending().await
+        }
+    }
+
+    fn make_ctx_for(connection_id: ConnectionId) -> DistantCtx<()> {
+        let (tx, _rx) = mpsc::channel(1);
+        DistantCtx {
+            connection_id,
+            reply: Box::new(tx),
+            local_data: Arc::new(()),
+            deadline: None,
+        }
+    }
+
+    fn make_ctx(deadline: Option<Instant>) -> DistantCtx<()> {
+        let (tx, _rx) = mpsc::channel(1);
+        DistantCtx {
+            connection_id: rand::random(),
+            reply: Box::new(tx),
+            local_data: Arc::new(()),
+            deadline,
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn handle_request_before_deadline_should_abort_requests_past_their_deadline() {
+        let server = DistantApiServerHandler::new(StalledApi);
+        let deadline = Instant::now() + Duration::from_millis(50);
+
+        let response = handle_request_before_deadline(
+            &server,
+            make_ctx(Some(deadline)),
+            DistantRequestData::FileRead {
+                path: PathBuf::from("file"),
+                offset: None,
+                len: None,
+            },
+            &ServerClients::default(),
+        )
+        .await;
+
+        match response {
+            DistantResponseData::Error(x) => assert_eq!(x.kind, ErrorKind::DeadlineExceeded),
+            x => panic!("Unexpected response: {x:?}"),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn handle_request_before_deadline_should_not_abort_requests_that_complete_in_time() {
+        let server = DistantApiServerHandler::new(StalledApi);
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        let response = handle_request_before_deadline(
+            &server,
+            make_ctx(Some(deadline)),
+            DistantRequestData::Ping {
+                data: Vec::new(),
+                reply_len: 3,
+            },
+            &ServerClients::default(),
+        )
+        .await;
+
+        assert_eq!(
+            response,
+            DistantResponseData::Pong {
+                data: vec![0, 0, 0]
+            }
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn file_write_dedup_should_not_reuse_content_written_by_another_connection() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let server = DistantApiServerHandler::new(LocalDistantApi::initialize().unwrap());
+        let data = b"some content".to_vec();
+        let hash = content_hash(&data);
+
+        let response = handle_request_before_deadline(
+            &server,
+            make_ctx_for(1),
+            DistantRequestData::FileWriteDedup {
+                path: temp.path().join("a"),
+                hash: hash.clone(),
+                data: Some(data),
+            },
+            &ServerClients::default(),
+        )
+        .await;
+        assert_eq!(response, DistantResponseData::Ok);
+
+        let request = DistantRequestData::FileWriteDedup {
+            path: temp.path().join("b"),
+            hash,
+            data: None,
+        };
+        let response = handle_request_before_deadline(
+            &server,
+            make_ctx_for(2),
+            request.clone(),
+            &ServerClients::default(),
+        )
+        .await;
+        assert_eq!(response, DistantResponseData::ContentMissing);
+        assert!(!temp.path().join("b").exists());
+
+        let response = handle_request_before_deadline(
+            &server,
+            make_ctx_for(1),
+            request,
+            &ServerClients::default(),
+        )
+        .await;
+        assert_eq!(response, DistantResponseData::Ok);
+        assert_eq!(
+            std::fs::read(temp.path().join("b")).unwrap(),
+            b"some content"
+        );
     }
 }
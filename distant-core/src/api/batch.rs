@@ -0,0 +1,168 @@
+use super::{DistantApi, DistantCtx};
+use crate::data::{BatchOp, Error};
+use crate::DistantResponseData;
+use log::*;
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Represents an action that reverses the effects of a completed [`BatchOp`]
+#[derive(Debug)]
+enum Undo {
+    /// Removes a file or directory that the operation created
+    Remove(PathBuf),
+
+    /// Moves a file or directory back to where it was before the operation
+    Rename { src: PathBuf, dst: PathBuf },
+}
+
+/// Creates a new context for a single operation of the batch
+pub(super) fn fork<D>(ctx: &DistantCtx<D>) -> DistantCtx<D> {
+    DistantCtx {
+        connection_id: ctx.connection_id,
+        reply: ctx.reply.clone_reply(),
+        local_data: Arc::clone(&ctx.local_data),
+        deadline: ctx.deadline,
+    }
+}
+
+/// Performs each of `ops` in order using `api`, stopping at the first failure and (if `atomic`)
+/// undoing the operations that completed before it
+pub async fn run<T, D>(
+    api: &T,
+    ctx: DistantCtx<D>,
+    ops: Vec<BatchOp>,
+    atomic: bool,
+) -> DistantResponseData
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let mut undos = Vec::new();
+
+    for (step, op) in ops.into_iter().enumerate() {
+        let result = if atomic {
+            match plan_undo(api, &ctx, &op).await {
+                Ok(undo) => perform(api, fork(&ctx), op).await.map(|_| undo),
+                Err(x) => Err(x),
+            }
+        } else {
+            perform(api, fork(&ctx), op).await.map(|_| None)
+        };
+
+        match result {
+            Ok(undo) => undos.extend(undo),
+            Err(x) => {
+                debug!(
+                    "[Conn {}] Batch failed at step {}: {}",
+                    ctx.connection_id, step, x
+                );
+                let rolled_back = atomic && rollback(api, &ctx, undos).await;
+                return DistantResponseData::BatchFailed {
+                    step,
+                    error: Error::from(x),
+                    rolled_back,
+                };
+            }
+        }
+    }
+
+    DistantResponseData::Ok
+}
+
+async fn perform<T, D>(api: &T, ctx: DistantCtx<D>, op: BatchOp) -> io::Result<()>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    match op {
+        BatchOp::DirCreate { path, all } => api.create_dir(ctx, path, all, None).await,
+        BatchOp::FileWrite { path, data } => api.write_file(ctx, path, data, None, None).await,
+        BatchOp::FileWriteText { path, text } => api.write_file_text(ctx, path, text).await,
+        BatchOp::FileAppend { path, data } => api.append_file(ctx, path, data).await,
+        BatchOp::FileAppendText { path, text } => api.append_file_text(ctx, path, text).await,
+        BatchOp::Copy { src, dst } => api.copy(ctx, src, dst).await,
+        BatchOp::Rename { src, dst } => api.rename(ctx, src, dst).await,
+        BatchOp::Remove { path, force } => api.remove(ctx, path, force).await,
+        BatchOp::Symlink { path, target } => api.create_symlink(ctx, path, target).await,
+    }
+}
+
+/// Determines how to undo `op` prior to it being performed, returning none if it creates nothing
+async fn plan_undo<T, D>(api: &T, ctx: &DistantCtx<D>, op: &BatchOp) -> io::Result<Option<Undo>>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    match op {
+        // Remove the topmost directory that is missing, which covers any parents created
+        BatchOp::DirCreate { path, .. } => {
+            Ok(topmost_missing(api, ctx, path).await?.map(Undo::Remove))
+        }
+        BatchOp::FileWrite { path, .. }
+        | BatchOp::FileWriteText { path, .. }
+        | BatchOp::FileAppend { path, .. }
+        | BatchOp::FileAppendText { path, .. }
+        | BatchOp::Copy { dst: path, .. }
+        | BatchOp::Symlink { path, .. } => {
+            if api.exists(fork(ctx), path.clone()).await? {
+                Ok(None)
+            } else {
+                Ok(Some(Undo::Remove(path.clone())))
+            }
+        }
+        BatchOp::Rename { src, dst } => Ok(Some(Undo::Rename {
+            src: dst.clone(),
+            dst: src.clone(),
+        })),
+        BatchOp::Remove { .. } => Ok(None),
+    }
+}
+
+/// Returns the topmost of `path` and its ancestors that is missing, whose removal covers every
+/// directory created for `path`
+pub(super) async fn topmost_missing<T, D>(
+    api: &T,
+    ctx: &DistantCtx<D>,
+    path: &Path,
+) -> io::Result<Option<PathBuf>>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let mut topmost = None;
+    for ancestor in path.ancestors() {
+        if ancestor.as_os_str().is_empty() || api.exists(fork(ctx), ancestor.to_path_buf()).await? {
+            break;
+        }
+        topmost = Some(ancestor.to_path_buf());
+    }
+    Ok(topmost)
+}
+
+/// Undoes completed operations in reverse order, returning true if all were undone
+async fn rollback<T, D>(api: &T, ctx: &DistantCtx<D>, undos: Vec<Undo>) -> bool
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let mut success = true;
+    for undo in undos.into_iter().rev() {
+        trace!("[Conn {}] Rolling back: {:?}", ctx.connection_id, undo);
+        let result = match undo {
+            Undo::Remove(path) => api.remove(fork(ctx), path, true).await,
+            Undo::Rename { src, dst } => api.rename(fork(ctx), src, dst).await,
+        };
+
+        if let Err(x) = result {
+            error!(
+                "[Conn {}] Failed to roll back batch: {}",
+                ctx.connection_id, x
+            );
+            success = false;
+        }
+    }
+    success
+}
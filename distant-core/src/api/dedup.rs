@@ -0,0 +1,272 @@
+use super::batch::fork;
+use super::{DistantApi, DistantCtx};
+use crate::constants::MAX_INDEXED_CONTENT;
+use crate::DistantResponseData;
+use distant_net::common::ConnectionId;
+use log::*;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Returns the hex-encoded SHA-256 hash of `data`, which identifies content for deduplicated writes
+pub(crate) fn content_hash(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Index of content written through deduplicated writes, mapping the hash of the content to the
+/// most recent path that each connection wrote it to
+///
+/// Connections only ever see content they wrote themselves, and the least recently used content
+/// is forgotten once more than `capacity` pieces are indexed.
+pub(crate) struct ContentIndex {
+    capacity: usize,
+    inner: Mutex<IndexInner>,
+}
+
+#[derive(Default)]
+struct IndexInner {
+    entries: HashMap<(ConnectionId, String), IndexEntry>,
+    tick: u64,
+}
+
+struct IndexEntry {
+    path: PathBuf,
+    last_used: u64,
+}
+
+impl Default for ContentIndex {
+    fn default() -> Self {
+        Self::with_capacity(MAX_INDEXED_CONTENT)
+    }
+}
+
+impl ContentIndex {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(IndexInner::default()),
+        }
+    }
+
+    fn get(&self, connection_id: ConnectionId, hash: &str) -> Option<PathBuf> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let entry = inner.entries.get_mut(&(connection_id, hash.to_string()))?;
+        entry.last_used = tick;
+        Some(entry.path.clone())
+    }
+
+    fn insert(&self, connection_id: ConnectionId, hash: String, path: PathBuf) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let last_used = inner.tick;
+        inner
+            .entries
+            .insert((connection_id, hash), IndexEntry { path, last_used });
+
+        if inner.entries.len() > self.capacity {
+            let oldest = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = oldest {
+                inner.entries.remove(&key);
+            }
+        }
+    }
+
+    fn remove(&self, connection_id: ConnectionId, hash: &str) {
+        self.inner
+            .lock()
+            .unwrap()
+            .entries
+            .remove(&(connection_id, hash.to_string()));
+    }
+
+    /// Forgets all content written by the connection
+    pub fn remove_connection(&self, connection_id: ConnectionId) {
+        self.inner
+            .lock()
+            .unwrap()
+            .entries
+            .retain(|(id, _), _| *id != connection_id);
+    }
+}
+
+/// Writes `data` to `path` if provided, otherwise looks for existing content matching `hash` at
+/// `path` or wherever it was last written, responding that the content is missing if not found
+pub async fn run<T, D>(
+    api: &T,
+    ctx: DistantCtx<D>,
+    index: &ContentIndex,
+    path: PathBuf,
+    hash: String,
+    data: Option<Vec<u8>>,
+) -> DistantResponseData
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let result = match data {
+        Some(data) => write(api, ctx, index, path, hash, data).await,
+        None => write_from_existing(api, ctx, index, path, hash).await,
+    };
+
+    match result {
+        Ok(true) => DistantResponseData::Ok,
+        Ok(false) => DistantResponseData::ContentMissing,
+        Err(x) => DistantResponseData::from(x),
+    }
+}
+
+async fn write<T, D>(
+    api: &T,
+    ctx: DistantCtx<D>,
+    index: &ContentIndex,
+    path: PathBuf,
+    hash: String,
+    data: Vec<u8>,
+) -> io::Result<bool>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    // Refuse to index content under the wrong hash, which would corrupt later writes
+    if !content_hash(&data).eq_ignore_ascii_case(&hash) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Content does not match hash",
+        ));
+    }
+
+    let connection_id = ctx.connection_id;
+    api.write_file(ctx, path.clone(), data, None, None).await?;
+    index.insert(connection_id, hash.to_ascii_lowercase(), path);
+    Ok(true)
+}
+
+async fn write_from_existing<T, D>(
+    api: &T,
+    ctx: DistantCtx<D>,
+    index: &ContentIndex,
+    path: PathBuf,
+    hash: String,
+) -> io::Result<bool>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let hash = hash.to_ascii_lowercase();
+
+    // Repeated writes of the same content to the same path need no work at all
+    if read_if_matches(api, &ctx, &path, &hash).await.is_some() {
+        trace!(
+            "[Conn {}] {:?} already has content {}",
+            ctx.connection_id,
+            path,
+            hash
+        );
+        index.insert(ctx.connection_id, hash, path);
+        return Ok(true);
+    }
+
+    let src = match index.get(ctx.connection_id, &hash) {
+        Some(src) => src,
+        None => return Ok(false),
+    };
+
+    // The file we last wrote may have since changed, so we only reuse content that still matches
+    match read_if_matches(api, &ctx, &src, &hash).await {
+        Some(data) => {
+            debug!(
+                "[Conn {}] Writing {:?} using content {} from {:?}",
+                ctx.connection_id, path, hash, src
+            );
+            let connection_id = ctx.connection_id;
+            api.write_file(ctx, path.clone(), data, None, None).await?;
+            index.insert(connection_id, hash, path);
+            Ok(true)
+        }
+        None => {
+            index.remove(ctx.connection_id, &hash);
+            Ok(false)
+        }
+    }
+}
+
+/// Reads the file at `path`, returning its content if it matches `hash`
+async fn read_if_matches<T, D>(
+    api: &T,
+    ctx: &DistantCtx<D>,
+    path: &Path,
+    hash: &str,
+) -> Option<Vec<u8>>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let data = api
+        .read_file(fork(ctx), path.to_path_buf(), None, None)
+        .await
+        .ok()?;
+    if content_hash(&data) == hash {
+        Some(data)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_hash_should_return_hex_encoded_sha256() {
+        assert_eq!(
+            content_hash(b"hello"),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn content_index_should_only_return_content_written_by_the_same_connection() {
+        let index = ContentIndex::default();
+        index.insert(1, String::from("abc"), PathBuf::from("/a"));
+
+        assert_eq!(index.get(1, "abc"), Some(PathBuf::from("/a")));
+        assert_eq!(index.get(2, "abc"), None);
+    }
+
+    #[test]
+    fn content_index_should_forget_least_recently_used_content_once_full() {
+        let index = ContentIndex::with_capacity(2);
+        index.insert(1, String::from("a"), PathBuf::from("/a"));
+        index.insert(1, String::from("b"), PathBuf::from("/b"));
+
+        // Using "a" makes "b" the least recently used
+        assert!(index.get(1, "a").is_some());
+        index.insert(1, String::from("c"), PathBuf::from("/c"));
+
+        assert_eq!(index.get(1, "a"), Some(PathBuf::from("/a")));
+        assert_eq!(index.get(1, "b"), None);
+        assert_eq!(index.get(1, "c"), Some(PathBuf::from("/c")));
+    }
+
+    #[test]
+    fn content_index_should_forget_content_of_removed_connection() {
+        let index = ContentIndex::default();
+        index.insert(1, String::from("a"), PathBuf::from("/a"));
+        index.insert(2, String::from("a"), PathBuf::from("/b"));
+
+        index.remove_connection(1);
+
+        assert_eq!(index.get(1, "a"), None);
+        assert_eq!(index.get(2, "a"), Some(PathBuf::from("/b")));
+    }
+}
@@ -0,0 +1,90 @@
+use super::batch::fork;
+use super::{DistantApi, DistantCtx};
+use crate::data::DiffTarget;
+use std::{io, path::PathBuf};
+
+/// Lines of context included around each change when not specified
+const DEFAULT_CONTEXT: usize = 3;
+
+/// Reads `src` and `dst` using `api` and returns a unified diff of the changes from `src` to
+/// `dst`, with `context` lines of unchanged text around each change
+pub async fn run<T, D>(
+    api: &T,
+    ctx: DistantCtx<D>,
+    src: PathBuf,
+    dst: DiffTarget,
+    context: Option<usize>,
+) -> io::Result<String>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let old = String::from_utf8_lossy(&api.read_file(fork(&ctx), src.clone(), None, None).await?)
+        .into_owned();
+    let (label, new) = match dst {
+        DiffTarget::Path(path) => {
+            let new = String::from_utf8_lossy(&api.read_file(ctx, path.clone(), None, None).await?)
+                .into_owned();
+            (path, new)
+        }
+        DiffTarget::Text(text) => (src.clone(), text),
+    };
+
+    Ok(unified_diff(
+        &src.to_string_lossy(),
+        &label.to_string_lossy(),
+        &old,
+        &new,
+        context.unwrap_or(DEFAULT_CONTEXT),
+    ))
+}
+
+/// Produces a unified diff from `old` to `new`, or an empty string if they are the same
+fn unified_diff(old_label: &str, new_label: &str, old: &str, new: &str, context: usize) -> String {
+    let old_lines = old.split_inclusive('\n').collect::<Vec<_>>();
+    let new_lines = new.split_inclusive('\n').collect::<Vec<_>>();
+    let lines = difflib::unified_diff(&old_lines, &new_lines, "", "", "", "", context);
+
+    let mut patch = String::new();
+    if lines.is_empty() {
+        return patch;
+    }
+
+    // Write our own header as the one produced includes empty timestamps
+    patch.push_str(&format!("--- {old_label}\n+++ {new_label}\n"));
+    for line in lines.into_iter().skip(2) {
+        patch.push_str(&line);
+        if !line.ends_with('\n') {
+            patch.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+    patch
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unified_diff_should_be_empty_if_no_changes() {
+        assert_eq!(unified_diff("a", "b", "one\ntwo\n", "one\ntwo\n", 3), "");
+    }
+
+    #[test]
+    fn unified_diff_should_include_context_around_changes() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n";
+        let new = "1\n2\n3\nfour\n5\n6\n7\n";
+        assert_eq!(
+            unified_diff("old", "new", old, new, 1),
+            "--- old\n+++ new\n@@ -3,3 +3,3 @@\n 3\n-4\n+four\n 5\n"
+        );
+    }
+
+    #[test]
+    fn unified_diff_should_mark_missing_trailing_newline() {
+        assert_eq!(
+            unified_diff("old", "new", "a\nb\n", "a\nb", 3),
+            "--- old\n+++ new\n@@ -1,2 +1,2 @@\n a\n-b\n+b\n\\ No newline at end of file\n"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use crate::constants::IDEMPOTENCY_KEY_TTL;
+use distant_net::common::ConnectionId;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+use tokio::sync::OnceCell;
+
+/// Results of requests with idempotency keys, kept for a short time so that retries of a request
+/// receive the result of the first attempt rather than performing the request again
+pub struct ResultCache<T>(Mutex<HashMap<(ConnectionId, String), Entry<T>>>);
+
+struct Entry<T> {
+    created: Instant,
+    result: Arc<OnceCell<T>>,
+}
+
+impl<T> Default for ResultCache<T> {
+    fn default() -> Self {
+        Self(Mutex::new(HashMap::new()))
+    }
+}
+
+impl<T: Clone> ResultCache<T> {
+    /// Returns the result of the request with `key` from `connection_id`, running `f` to get it
+    /// only if no request with the key was seen recently
+    ///
+    /// Retries that arrive while the first attempt is still running wait for its result.
+    pub async fn get_or_init<F>(&self, connection_id: ConnectionId, key: String, f: F) -> T
+    where
+        F: Future<Output = T>,
+    {
+        let cell = {
+            let mut entries = self.0.lock().unwrap();
+            let now = Instant::now();
+            entries.retain(|_, entry| now.duration_since(entry.created) < IDEMPOTENCY_KEY_TTL);
+
+            let entry = entries
+                .entry((connection_id, key))
+                .or_insert_with(|| Entry {
+                    created: now,
+                    result: Arc::new(OnceCell::new()),
+                });
+            Arc::clone(&entry.result)
+        };
+
+        cell.get_or_init(|| f).await.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test(tokio::test)]
+    async fn get_or_init_should_only_run_once_per_connection_and_key() {
+        let cache = ResultCache::default();
+
+        assert_eq!(
+            cache.get_or_init(1, String::from("a"), async { 1 }).await,
+            1
+        );
+        assert_eq!(
+            cache.get_or_init(1, String::from("a"), async { 2 }).await,
+            1
+        );
+        assert_eq!(
+            cache.get_or_init(1, String::from("b"), async { 3 }).await,
+            3
+        );
+        assert_eq!(
+            cache.get_or_init(2, String::from("a"), async { 4 }).await,
+            4
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn get_or_init_should_wait_for_an_attempt_in_progress() {
+        let cache = ResultCache::default();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        let (first, retry) = tokio::join!(
+            cache.get_or_init(1, String::from("a"), async { rx.await.unwrap() }),
+            async {
+                // The first attempt cannot see this until polled again, by which point the retry
+                // is already waiting on it
+                tx.send(1).unwrap();
+                cache.get_or_init(1, String::from("a"), async { 2 }).await
+            }
+        );
+
+        assert_eq!((first, retry), (1, 1));
+    }
+}
@@ -1,20 +1,41 @@
 use crate::{
+    constants::SERVER_ENV_ALLOWLIST,
     data::{
-        Capabilities, ChangeKind, ChangeKindSet, DirEntry, Environment, FileType, Metadata,
-        ProcessId, PtySize, SearchId, SearchQuery, SystemInfo,
+        Capabilities, CapabilityKind, ChangeKind, ChangeKindSet, DirEntry, DirReadOptions,
+        Environment, Error, ErrorKind, FileType, HashAlgorithm, JournalEntry, JournalSeq, Metadata,
+        Metrics, ProcInfo, ProcLimits, ProcStats, ProcessId, PtySize, SearchId, SearchQuery,
+        SessionId, Signal, SnapshotId, SnapshotInfo, SystemInfo, SystemStats, SystemStatsId,
+        TunnelId, WatchId,
     },
-    DistantApi, DistantCtx,
+    make_temp_name, DistantApi, DistantCtx, DistantResponseData, MAX_TEMP_ATTEMPTS,
 };
 use async_trait::async_trait;
+use distant_net::common::ConnectionId;
 use log::*;
 use std::{
+    collections::HashSet,
     io,
     path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
 };
 use tokio::io::AsyncWriteExt;
 use walkdir::WalkDir;
 
+mod dir;
+use dir::ReadDir;
+
+mod hooks;
+pub use hooks::ServerHooks;
+
+mod manifest;
+
+mod modes;
+pub use modes::DefaultModes;
+
 mod process;
+pub use process::{OutputLimits, ProcessPolicy, ProcessScope, ScopeLimits};
+use process::{RunAs, ScopePrefix};
 
 mod state;
 use state::*;
@@ -25,15 +46,97 @@ use state::*;
 /// implementations on top of SSH and other protocol
 pub struct LocalDistantApi {
     state: GlobalState,
+    hooks: Option<Arc<dyn ServerHooks>>,
+    scope: Option<ScopePrefix>,
+    policy: ProcessPolicy,
+    modes: DefaultModes,
+    max_output_window: Option<usize>,
 }
 
 impl LocalDistantApi {
     /// Initialize the api instance
     pub fn initialize() -> io::Result<Self> {
+        Self::initialize_with_limits(OutputLimits::default())
+    }
+
+    /// Initialize the api instance, buffering the output of spawned processes within
+    /// `output_limits`
+    pub fn initialize_with_limits(output_limits: OutputLimits) -> io::Result<Self> {
         Ok(Self {
-            state: GlobalState::initialize()?,
+            max_output_window: output_limits.max_window,
+            state: GlobalState::initialize(output_limits)?,
+            hooks: None,
+            scope: None,
+            policy: ProcessPolicy::default(),
+            modes: DefaultModes::default(),
         })
     }
+
+    /// Invokes `hooks` as requests are handled by the api instance
+    pub fn with_hooks(mut self, hooks: Arc<dyn ServerHooks>) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// Sets a soft quota on the bytes written to files on behalf of each connection, where going
+    /// over it is logged as a warning but does not stop anything from being written
+    pub fn with_write_quota(mut self, quota: Option<u64>) -> Self {
+        self.state.usage = UsageState::new(quota);
+        self
+    }
+
+    /// Spawns processes within `scope` when it is available, otherwise spawning them without one
+    pub fn with_process_scope(mut self, scope: Option<ProcessScope>) -> Self {
+        self.scope = scope.and_then(ProcessScope::prepare);
+        self
+    }
+
+    /// Lets tunnel listeners bind to addresses other than loopback ones when `allow` is true,
+    /// otherwise only the machine of the server can reach them
+    pub fn with_remote_tunnels(mut self, allow: bool) -> Self {
+        self.state.tunnel.allow_remote(allow);
+        self
+    }
+
+    /// Only spawns processes whose commands are permitted by `policy`
+    pub fn with_process_policy(mut self, policy: ProcessPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Gives files and directories created on behalf of clients the permissions of `modes` when
+    /// the clients do not ask for permissions of their own
+    pub fn with_default_modes(mut self, modes: DefaultModes) -> Self {
+        self.modes = modes;
+        self
+    }
+
+    /// Checks that the policy permits spawning `cmd`, along with the program that runs it as
+    /// another user when given `run_as`
+    fn check_policy(
+        &self,
+        connection_id: ConnectionId,
+        cmd: &str,
+        run_as: Option<&RunAs>,
+        environment: &Environment,
+        current_dir: Option<&Path>,
+    ) -> io::Result<()> {
+        let mut result = self.policy.check(cmd, environment, current_dir);
+
+        // Programs such as sudo that run the command as another user are what actually gets
+        // spawned, so they need to be permitted as well
+        if let (Ok(()), Some(run_as)) = (result.as_ref(), run_as) {
+            let user_cmd = run_as.wrap(cmd.to_string());
+            if user_cmd != cmd {
+                result = self.policy.check(&user_cmd, environment, current_dir);
+            }
+        }
+
+        if let Err(x) = result.as_ref() {
+            warn!("[Conn {connection_id}] Rejected spawning {cmd}: {x}");
+        }
+        result
+    }
 }
 
 #[async_trait]
@@ -43,20 +146,44 @@ impl DistantApi for LocalDistantApi {
     async fn capabilities(&self, ctx: DistantCtx<Self::LocalData>) -> io::Result<Capabilities> {
         debug!("[Conn {}] Querying capabilities", ctx.connection_id);
 
-        Ok(Capabilities::all())
+        let mut capabilities = Capabilities::all();
+
+        // Signaling any process of the system is only supported when enabled by the policy
+        if !self.policy.system_kill {
+            capabilities.take(CapabilityKind::SystemKill);
+        }
+
+        Ok(capabilities)
     }
 
     async fn read_file(
         &self,
         ctx: DistantCtx<Self::LocalData>,
         path: PathBuf,
+        offset: Option<u64>,
+        len: Option<u64>,
     ) -> io::Result<Vec<u8>> {
         debug!(
-            "[Conn {}] Reading bytes from file {:?}",
-            ctx.connection_id, path
+            "[Conn {}] Reading bytes from file {:?} {{offset: {:?}, len: {:?}}}",
+            ctx.connection_id, path, offset, len
         );
 
-        tokio::fs::read(path).await
+        if offset.is_none() && len.is_none() {
+            return tokio::fs::read(path).await;
+        }
+
+        use tokio::io::{AsyncReadExt, AsyncSeekExt};
+        let mut file = tokio::fs::File::open(path).await?;
+        if let Some(offset) = offset {
+            file.seek(io::SeekFrom::Start(offset)).await?;
+        }
+
+        let mut data = Vec::new();
+        match len {
+            Some(len) => file.take(len).read_to_end(&mut data).await?,
+            None => file.read_to_end(&mut data).await?,
+        };
+        Ok(data)
     }
 
     async fn read_file_text(
@@ -77,13 +204,45 @@ impl DistantApi for LocalDistantApi {
         ctx: DistantCtx<Self::LocalData>,
         path: PathBuf,
         data: Vec<u8>,
+        offset: Option<u64>,
+        mode: Option<u32>,
     ) -> io::Result<()> {
         debug!(
-            "[Conn {}] Writing bytes to file {:?}",
-            ctx.connection_id, path
+            "[Conn {}] Writing bytes to file {:?} at offset {:?} {{mode: {:?}}}",
+            ctx.connection_id, path, offset, mode
         );
+        self.check_write(&path, Some(&data)).await?;
+
+        let len = data.len();
+        let mode = mode.or(self.modes.file);
+        match offset {
+            None => {
+                let mut file = modes::open_file(&path, false, mode).await?;
+                file.write_all(&data).await?;
+                file.flush().await?;
+            }
+            Some(offset) => {
+                use tokio::io::AsyncSeekExt;
+
+                // NOTE: Opening to append would force writes to the end, so the file is created
+                //       first (if needed) and then opened without truncating it
+                if mode.is_some() {
+                    drop(modes::open_file(&path, true, mode).await?);
+                }
+                let mut file = tokio::fs::OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(path)
+                    .await?;
+                file.seek(io::SeekFrom::Start(offset)).await?;
+                file.write_all(&data).await?;
+                file.flush().await?;
+            }
+        }
 
-        tokio::fs::write(path, data).await
+        self.state.usage.record(ctx.connection_id, len);
+        Ok(())
     }
 
     async fn write_file_text(
@@ -96,8 +255,14 @@ impl DistantApi for LocalDistantApi {
             "[Conn {}] Writing text to file {:?}",
             ctx.connection_id, path
         );
+        self.check_write(&path, Some(data.as_bytes())).await?;
 
-        tokio::fs::write(path, data).await
+        let len = data.len();
+        let mut file = modes::open_file(&path, false, self.modes.file).await?;
+        file.write_all(data.as_bytes()).await?;
+        file.flush().await?;
+        self.state.usage.record(ctx.connection_id, len);
+        Ok(())
     }
 
     async fn append_file(
@@ -110,13 +275,12 @@ impl DistantApi for LocalDistantApi {
             "[Conn {}] Appending bytes to file {:?}",
             ctx.connection_id, path
         );
+        self.check_write(&path, Some(&data)).await?;
 
-        let mut file = tokio::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(path)
-            .await?;
-        file.write_all(data.as_ref()).await
+        let mut file = modes::open_file(&path, true, self.modes.file).await?;
+        file.write_all(data.as_ref()).await?;
+        self.state.usage.record(ctx.connection_id, data.len());
+        Ok(())
     }
 
     async fn append_file_text(
@@ -129,15 +293,30 @@ impl DistantApi for LocalDistantApi {
             "[Conn {}] Appending text to file {:?}",
             ctx.connection_id, path
         );
+        self.check_write(&path, Some(data.as_bytes())).await?;
 
-        let mut file = tokio::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(path)
-            .await?;
-        file.write_all(data.as_ref()).await
+        let mut file = modes::open_file(&path, true, self.modes.file).await?;
+        file.write_all(data.as_ref()).await?;
+        self.state.usage.record(ctx.connection_id, data.len());
+        Ok(())
+    }
+
+    async fn check_write(&self, path: &Path, data: Option<&[u8]>) -> io::Result<()> {
+        let hooks = match self.hooks.as_ref() {
+            Some(hooks) => Arc::clone(hooks),
+            None => return Ok(()),
+        };
+
+        // Hooks such as scripts can take a while, so they get their own thread rather than
+        // holding up the runtime
+        let path = path.to_path_buf();
+        let data = data.map(<[u8]>::to_vec);
+        tokio::task::spawn_blocking(move || hooks.before_write(&path, data.as_deref()))
+            .await
+            .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn read_dir(
         &self,
         ctx: DistantCtx<Self::LocalData>,
@@ -146,91 +325,80 @@ impl DistantApi for LocalDistantApi {
         absolute: bool,
         canonicalize: bool,
         include_root: bool,
+        options: DirReadOptions,
     ) -> io::Result<(Vec<DirEntry>, Vec<io::Error>)> {
         debug!(
-            "[Conn {}] Reading directory {:?} {{depth: {}, absolute: {}, canonicalize: {}, include_root: {}}}",
-            ctx.connection_id, path, depth, absolute, canonicalize, include_root
+            "[Conn {}] Reading directory {:?} {{depth: {}, absolute: {}, canonicalize: {}, include_root: {}, options: {:?}}}",
+            ctx.connection_id, path, depth, absolute, canonicalize, include_root, options
         );
 
         // Canonicalize our provided path to ensure that it is exists, not a loop, and absolute
-        let root_path = tokio::fs::canonicalize(path).await?;
-
-        // Traverse, but don't include root directory in entries (hence min depth 1), unless indicated
-        // to do so (min depth 0)
-        let dir = WalkDir::new(root_path.as_path())
-            .min_depth(if include_root { 0 } else { 1 })
-            .sort_by_file_name();
-
-        // If depth > 0, will recursively traverse to specified max depth, otherwise
-        // performs infinite traversal
-        let dir = if depth > 0 { dir.max_depth(depth) } else { dir };
-
-        // Determine our entries and errors
-        let mut entries = Vec::new();
-        let mut errors = Vec::new();
-
-        #[inline]
-        fn map_file_type(ft: std::fs::FileType) -> FileType {
-            if ft.is_dir() {
-                FileType::Dir
-            } else if ft.is_file() {
-                FileType::File
-            } else {
-                FileType::Symlink
-            }
+        let root = tokio::fs::canonicalize(path).await?;
+        ReadDir {
+            root,
+            depth,
+            absolute,
+            canonicalize,
+            include_root,
+            options,
         }
+        .read()
+        .await
+    }
 
-        for entry in dir {
-            match entry.map_err(io::Error::from) {
-                // For entries within the root, we want to transform the path based on flags
-                Ok(e) if e.depth() > 0 => {
-                    // Canonicalize the path if specified, otherwise just return
-                    // the path as is
-                    let mut path = if canonicalize {
-                        match tokio::fs::canonicalize(e.path()).await {
-                            Ok(path) => path,
-                            Err(x) => {
-                                errors.push(x);
-                                continue;
-                            }
-                        }
-                    } else {
-                        e.path().to_path_buf()
-                    };
-
-                    // Strip the path of its prefix based if not flagged as absolute
-                    if !absolute {
-                        // NOTE: In the situation where we canonicalized the path earlier,
-                        //       there is no guarantee that our root path is still the
-                        //       parent of the symlink's destination; so, in that case we MUST just
-                        //       return the path if the strip_prefix fails
-                        path = path
-                            .strip_prefix(root_path.as_path())
-                            .map(Path::to_path_buf)
-                            .unwrap_or(path);
-                    };
-
-                    entries.push(DirEntry {
-                        path,
-                        file_type: map_file_type(e.file_type()),
-                        depth: e.depth(),
-                    });
-                }
+    #[allow(clippy::too_many_arguments)]
+    async fn read_dir_chunked(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        depth: usize,
+        absolute: bool,
+        canonicalize: bool,
+        include_root: bool,
+        options: DirReadOptions,
+        chunk_size: usize,
+    ) -> io::Result<(Vec<DirEntry>, Vec<io::Error>, bool)> {
+        debug!(
+            "[Conn {}] Reading directory {:?} in chunks of {} {{depth: {}, absolute: {}, canonicalize: {}, include_root: {}, options: {:?}}}",
+            ctx.connection_id, path, chunk_size, depth, absolute, canonicalize, include_root, options
+        );
 
-                // For the root, we just want to echo back the entry as is
-                Ok(e) => {
-                    entries.push(DirEntry {
-                        path: e.path().to_path_buf(),
-                        file_type: map_file_type(e.file_type()),
-                        depth: e.depth(),
-                    });
-                }
+        let root = tokio::fs::canonicalize(path).await?;
+        Ok(ReadDir {
+            root,
+            depth,
+            absolute,
+            canonicalize,
+            include_root,
+            options,
+        }
+        .read_chunked(chunk_size, ctx.reply)
+        .await)
+    }
 
-                Err(x) => errors.push(x),
-            }
+    async fn dir_manifest(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        algorithm: HashAlgorithm,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Listing manifest of {:?} {{algorithm: {}}}",
+            ctx.connection_id,
+            path,
+            algorithm.as_ref()
+        );
+
+        let root = tokio::fs::canonicalize(&path).await?;
+        if !tokio::fs::metadata(&root).await?.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{path:?} is not a directory"),
+            ));
         }
 
-        Ok((entries, errors))
+        manifest::spawn(root, algorithm, ctx.reply);
+        Ok(())
     }
 
     async fn create_dir(
@@ -238,16 +406,73 @@ impl DistantApi for LocalDistantApi {
         ctx: DistantCtx<Self::LocalData>,
         path: PathBuf,
         all: bool,
+        mode: Option<u32>,
     ) -> io::Result<()> {
         debug!(
-            "[Conn {}] Creating directory {:?} {{all: {}}}",
-            ctx.connection_id, path, all
+            "[Conn {}] Creating directory {:?} {{all: {}, mode: {:?}}}",
+            ctx.connection_id, path, all, mode
         );
-        if all {
-            tokio::fs::create_dir_all(path).await
-        } else {
-            tokio::fs::create_dir(path).await
-        }
+        self.check_write(&path, None).await?;
+        modes::create_dir(&path, all, mode.or(self.modes.dir)).await
+    }
+
+    async fn create_fifo(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        mode: Option<u32>,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Creating named pipe {:?} {{mode: {:?}}}",
+            ctx.connection_id, path, mode
+        );
+        self.check_write(&path, None).await?;
+        modes::create_fifo(&path, mode).await
+    }
+
+    async fn make_temp_file(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        parent: Option<PathBuf>,
+    ) -> io::Result<PathBuf> {
+        debug!(
+            "[Conn {}] Making temporary file {{parent: {:?}}}",
+            ctx.connection_id, parent
+        );
+        make_temp(parent, |path| async move {
+            self.check_write(&path, Some(&[])).await?;
+
+            // Using 600 as this mirrors "mktemp"
+            // 600: rw-------
+            let mut options = tokio::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            options.mode(0o600);
+            options.open(path).await.map(|_| ())
+        })
+        .await
+    }
+
+    async fn make_temp_dir(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        parent: Option<PathBuf>,
+    ) -> io::Result<PathBuf> {
+        debug!(
+            "[Conn {}] Making temporary directory {{parent: {:?}}}",
+            ctx.connection_id, parent
+        );
+        make_temp(parent, |path| async move {
+            self.check_write(&path, None).await?;
+
+            // Using 700 as this mirrors "mktemp -d"
+            // 700: rwx------
+            let mut builder = tokio::fs::DirBuilder::new();
+            #[cfg(unix)]
+            builder.mode(0o700);
+            builder.create(path).await
+        })
+        .await
     }
 
     async fn remove(
@@ -282,6 +507,7 @@ impl DistantApi for LocalDistantApi {
             "[Conn {}] Copying {:?} to {:?}",
             ctx.connection_id, src, dst
         );
+        self.check_write(&dst, None).await?;
         let src_metadata = tokio::fs::metadata(src.as_path()).await?;
         if src_metadata.is_dir() {
             // Create the destination directory first, regardless of if anything
@@ -292,12 +518,29 @@ impl DistantApi for LocalDistantApi {
                 .min_depth(1)
                 .follow_links(false)
                 .into_iter()
-                .filter_entry(|e| {
-                    e.file_type().is_file() || e.file_type().is_dir() || e.path_is_symlink()
-                })
             {
                 let entry = entry?;
 
+                // Fifos, sockets, and devices (including those behind symlinks) have no contents
+                // to copy, so they are left out rather than failing the entire copy
+                let file_type = if entry.path_is_symlink() {
+                    tokio::fs::metadata(entry.path())
+                        .await
+                        .map(|x| FileType::from(x.file_type()))
+                        .unwrap_or(FileType::Symlink)
+                } else {
+                    FileType::from(entry.file_type())
+                };
+                if file_type.is_special() {
+                    warn!(
+                        "[Conn {}] Skipping {:?} while copying as it is a {}",
+                        ctx.connection_id,
+                        entry.path(),
+                        file_type.as_ref().replace('_', " ")
+                    );
+                    continue;
+                }
+
                 // Get unique portion of path relative to src
                 // NOTE: Because we are traversing files that are all within src, this
                 //       should always succeed
@@ -320,7 +563,7 @@ impl DistantApi for LocalDistantApi {
 
                 // Perform copying from entry to destination (if a file/symlink)
                 if !entry.file_type().is_dir() {
-                    tokio::fs::copy(entry.path(), dst_path).await?;
+                    copy_file(entry.path(), dst_path.as_path()).await?;
 
                 // Otherwise, if a directory, create it
                 } else {
@@ -328,7 +571,7 @@ impl DistantApi for LocalDistantApi {
                 }
             }
         } else {
-            tokio::fs::copy(src, dst).await?;
+            copy_file(src.as_path(), dst.as_path()).await?;
         }
 
         Ok(())
@@ -344,9 +587,96 @@ impl DistantApi for LocalDistantApi {
             "[Conn {}] Renaming {:?} to {:?}",
             ctx.connection_id, src, dst
         );
+        self.check_write(&dst, None).await?;
         tokio::fs::rename(src, dst).await
     }
 
+    async fn create_symlink(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        target: PathBuf,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Creating symlink {:?} to {:?}",
+            ctx.connection_id, path, target
+        );
+        self.check_write(&path, None).await?;
+
+        // Create the symlink under a temporary name and rename it into place, which replaces
+        // any file or symlink already at the path in a single step
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let staged = path.with_file_name(format!(".{name}.{:08x}.symlink", rand::random::<u32>()));
+
+        #[cfg(unix)]
+        tokio::fs::symlink(&target, &staged).await?;
+
+        #[cfg(windows)]
+        {
+            // Relative targets are resolved from the directory containing the symlink
+            let resolved = path.parent().unwrap_or(Path::new("")).join(&target);
+            if tokio::fs::metadata(resolved)
+                .await
+                .map(|m| m.is_dir())
+                .unwrap_or(false)
+            {
+                tokio::fs::symlink_dir(&target, &staged).await?;
+            } else {
+                tokio::fs::symlink_file(&target, &staged).await?;
+            }
+        }
+
+        if let Err(x) = tokio::fs::rename(&staged, &path).await {
+            let _ = tokio::fs::remove_file(&staged).await;
+            return Err(x);
+        }
+
+        Ok(())
+    }
+
+    async fn create_snapshot(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+    ) -> io::Result<SnapshotId> {
+        debug!(
+            "[Conn {}] Creating snapshot of {:?}",
+            ctx.connection_id, path
+        );
+        self.state.snapshot.create(ctx.connection_id, path).await
+    }
+
+    async fn list_snapshots(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+    ) -> io::Result<Vec<SnapshotInfo>> {
+        debug!("[Conn {}] Listing snapshots", ctx.connection_id);
+        Ok(self.state.snapshot.list(ctx.connection_id))
+    }
+
+    async fn restore_snapshot(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: SnapshotId,
+    ) -> io::Result<()> {
+        debug!("[Conn {}] Restoring snapshot {}", ctx.connection_id, id);
+        let path = self.state.snapshot.path(ctx.connection_id, id)?;
+        self.check_write(&path, None).await?;
+        self.state.snapshot.restore(ctx.connection_id, id).await
+    }
+
+    async fn remove_snapshot(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: SnapshotId,
+    ) -> io::Result<()> {
+        debug!("[Conn {}] Removing snapshot {}", ctx.connection_id, id);
+        self.state.snapshot.remove(ctx.connection_id, id).await
+    }
+
     async fn watch(
         &self,
         ctx: DistantCtx<Self::LocalData>,
@@ -354,16 +684,18 @@ impl DistantApi for LocalDistantApi {
         recursive: bool,
         only: Vec<ChangeKind>,
         except: Vec<ChangeKind>,
+        id: Option<WatchId>,
     ) -> io::Result<()> {
         let only = only.into_iter().collect::<ChangeKindSet>();
         let except = except.into_iter().collect::<ChangeKindSet>();
         debug!(
-            "[Conn {}] Watching {:?} {{recursive: {}, only: {}, except: {}}}",
-            ctx.connection_id, path, recursive, only, except
+            "[Conn {}] Watching {:?} {{recursive: {}, only: {}, except: {}, id: {:?}}}",
+            ctx.connection_id, path, recursive, only, except, id
         );
 
         let path = RegisteredPath::register(
             ctx.connection_id,
+            id,
             path.as_path(),
             recursive,
             only,
@@ -377,16 +709,62 @@ impl DistantApi for LocalDistantApi {
         Ok(())
     }
 
-    async fn unwatch(&self, ctx: DistantCtx<Self::LocalData>, path: PathBuf) -> io::Result<()> {
-        debug!("[Conn {}] Unwatching {:?}", ctx.connection_id, path);
+    async fn unwatch(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        id: Option<WatchId>,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Unwatching {:?} {{id: {:?}}}",
+            ctx.connection_id, path, id
+        );
 
         self.state
             .watcher
-            .unwatch(ctx.connection_id, path.as_path())
+            .unwatch(ctx.connection_id, id, path.as_path())
             .await?;
         Ok(())
     }
 
+    async fn journal_start(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        recursive: bool,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Journaling {:?} {{recursive: {}}}",
+            ctx.connection_id, path, recursive
+        );
+        self.state.watcher.journal_start(path, recursive).await
+    }
+
+    async fn journal_changes(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+        since: JournalSeq,
+    ) -> io::Result<(JournalSeq, Vec<JournalEntry>)> {
+        debug!(
+            "[Conn {}] Retrieving changes to {:?} since {}",
+            ctx.connection_id, path, since
+        );
+        self.state.watcher.journal_changes(path, since).await
+    }
+
+    async fn journal_stop(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        path: PathBuf,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Stopping journal of {:?}",
+            ctx.connection_id, path
+        );
+        self.state.watcher.journal_stop(path).await
+    }
+
     async fn exists(&self, ctx: DistantCtx<Self::LocalData>, path: PathBuf) -> io::Result<bool> {
         debug!("[Conn {}] Checking if {:?} exists", ctx.connection_id, path);
 
@@ -423,7 +801,33 @@ impl DistantApi for LocalDistantApi {
             ctx.connection_id,
         );
 
-        self.state.search.start(query, ctx.reply).await
+        let deadline = ctx.deadline;
+        let reply = ctx.reply.clone_reply();
+        let id = self.state.search.start(query, ctx.reply).await?;
+
+        // Searches keep running after the request that started them completes, so they are
+        // canceled separately once past the deadline
+        if let Some(deadline) = deadline {
+            let search = self.state.search.clone_channel();
+            tokio::spawn(async move {
+                tokio::time::sleep_until(deadline.into()).await;
+
+                // Canceling only succeeds if the search is still running
+                if search.cancel(id).await.is_ok() {
+                    debug!("[Query {id}] Canceled after exceeding deadline");
+                    let _ = reply
+                        .send(DistantResponseData::Error(Error {
+                            kind: ErrorKind::DeadlineExceeded,
+                            description: format!(
+                                "Search {id} did not complete before its deadline"
+                            ),
+                        }))
+                        .await;
+                }
+            });
+        }
+
+        Ok(id)
     }
 
     async fn cancel_search(
@@ -436,6 +840,16 @@ impl DistantApi for LocalDistantApi {
         self.state.search.cancel(id).await
     }
 
+    async fn search_next(&self, ctx: DistantCtx<Self::LocalData>, id: SearchId) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Requesting next page of search {id}",
+            ctx.connection_id
+        );
+
+        self.state.search.next(id).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn proc_spawn(
         &self,
         ctx: DistantCtx<Self::LocalData>,
@@ -443,14 +857,161 @@ impl DistantApi for LocalDistantApi {
         environment: Environment,
         current_dir: Option<PathBuf>,
         pty: Option<PtySize>,
+        output_window: Option<usize>,
+        timestamped: bool,
+        session: Option<SessionId>,
+        limits: ProcLimits,
+        kill_tree: bool,
+        user: Option<String>,
     ) -> io::Result<ProcessId> {
         debug!(
-            "[Conn {}] Spawning {} {{environment: {:?}, current_dir: {:?}, pty: {:?}}}",
-            ctx.connection_id, cmd, environment, current_dir, pty
+            "[Conn {}] Spawning {} {{environment: {:?}, current_dir: {:?}, pty: {:?}, output_window: {:?}, timestamped: {}, session: {:?}, limits: {:?}, kill_tree: {}, user: {:?}}}",
+            ctx.connection_id, cmd, environment, current_dir, pty, output_window, timestamped, session, limits, kill_tree, user
+        );
+        // Switching users within the process only works when the process itself is spawned,
+        // rather than a pty or a program placing it within a scope, which instead run it through
+        // a program that switches users
+        let run_as = user
+            .map(|user| RunAs::prepare(&user, pty.is_some(), pty.is_none() && self.scope.is_none()))
+            .transpose()?;
+        self.check_policy(
+            ctx.connection_id,
+            &cmd,
+            run_as.as_ref(),
+            &environment,
+            current_dir.as_deref(),
+        )?;
+
+        let hook_args = self
+            .hooks
+            .as_ref()
+            .map(|hooks| (Arc::clone(hooks), cmd.clone(), current_dir.clone()));
+        let user_cmd = match run_as.as_ref() {
+            Some(run_as) => run_as.wrap(cmd.clone()),
+            None => cmd.clone(),
+        };
+        let scoped_cmd = match self.scope.as_ref() {
+            Some(scope) => scope.wrap(user_cmd),
+            None => user_cmd,
+        };
+        let id = self
+            .state
+            .process
+            .spawn(
+                scoped_cmd,
+                environment,
+                current_dir,
+                pty,
+                self.output_window(output_window),
+                timestamped,
+                session,
+                limits,
+                kill_tree,
+                run_as,
+                ctx.reply,
+            )
+            .await?;
+
+        if let Some((hooks, cmd, current_dir)) = hook_args {
+            let _ = tokio::task::spawn_blocking(move || {
+                hooks.after_spawn(id, &cmd, current_dir.as_deref())
+            })
+            .await;
+        }
+
+        Ok(id)
+    }
+
+    fn output_window(&self, output_window: Option<usize>) -> Option<usize> {
+        match (output_window, self.max_output_window) {
+            (Some(window), Some(max)) => Some(window.min(max)),
+            (window, _) => window,
+        }
+    }
+
+    async fn proc_spawn_detached(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        cmd: String,
+        environment: Environment,
+        current_dir: Option<PathBuf>,
+        limits: ProcLimits,
+        user: Option<String>,
+    ) -> io::Result<u32> {
+        debug!(
+            "[Conn {}] Spawning detached {} {{environment: {:?}, current_dir: {:?}, limits: {:?}, user: {:?}}}",
+            ctx.connection_id, cmd, environment, current_dir, limits, user
         );
+        // With no stdin to answer with, a password asked for when switching users fails the
+        // process rather than being reported as a prompt
+        let run_as = user
+            .map(|user| RunAs::prepare(&user, false, self.scope.is_none()))
+            .transpose()?;
+        self.check_policy(
+            ctx.connection_id,
+            &cmd,
+            run_as.as_ref(),
+            &environment,
+            current_dir.as_deref(),
+        )?;
+        let user_cmd = match run_as.as_ref() {
+            Some(run_as) => run_as.wrap(cmd),
+            None => cmd,
+        };
+        let scoped_cmd = match self.scope.as_ref() {
+            Some(scope) => scope.wrap(user_cmd),
+            None => user_cmd,
+        };
+
+        let mut cmd_and_args = if cfg!(windows) {
+            winsplit::split(&scoped_cmd)
+        } else {
+            shell_words::split(&scoped_cmd)
+                .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?
+        };
+        if cmd_and_args.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Command was empty",
+            ));
+        }
+        let args = cmd_and_args.split_off(1);
+
+        let child = process::spawn_detached(
+            &cmd_and_args[0],
+            args,
+            environment,
+            current_dir,
+            &limits,
+            #[cfg(unix)]
+            run_as.as_ref().and_then(RunAs::switch),
+        )?;
+
+        self.state.detached.insert(child).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "Detached process exited immediately")
+        })
+    }
+
+    async fn proc_attach(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: ProcessId,
+        session: Option<SessionId>,
+        replay: bool,
+    ) -> io::Result<ProcessId> {
+        match session {
+            Some(session) => debug!(
+                "[Conn {}] Attaching to process of session {} {{replay: {}}}",
+                ctx.connection_id, session, replay
+            ),
+            None => debug!(
+                "[Conn {}] Attaching to process {} {{replay: {}}}",
+                ctx.connection_id, id, replay
+            ),
+        }
         self.state
             .process
-            .spawn(cmd, environment, current_dir, pty, ctx.reply)
+            .attach(id, session, replay, ctx.reply)
             .await
     }
 
@@ -459,6 +1020,47 @@ impl DistantApi for LocalDistantApi {
         self.state.process.kill(id).await
     }
 
+    async fn proc_list(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        all: bool,
+    ) -> io::Result<Vec<ProcInfo>> {
+        debug!(
+            "[Conn {}] Listing processes {{all: {}}}",
+            ctx.connection_id, all
+        );
+        let mut entries = self.state.process.list().await?;
+
+        // Processes of the system that we spawned are already listed with their ids
+        if all {
+            let processes = tokio::task::spawn_blocking(system_processes)
+                .await
+                .map_err(|x| io::Error::new(io::ErrorKind::Other, x))??;
+            let spawned: HashSet<u32> = entries.iter().filter_map(|p| p.pid).collect();
+            entries.extend(
+                processes
+                    .into_iter()
+                    .filter(|p| !p.pid.is_some_and(|pid| spawned.contains(&pid))),
+            );
+        }
+
+        entries.sort_by_key(|p| (p.pid, p.id));
+        Ok(entries)
+    }
+
+    async fn proc_signal(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: ProcessId,
+        signal: Signal,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Sending {} to process {}",
+            ctx.connection_id, signal, id
+        );
+        self.state.process.signal(id, signal).await
+    }
+
     async fn proc_stdin(
         &self,
         ctx: DistantCtx<Self::LocalData>,
@@ -472,30 +1074,347 @@ impl DistantApi for LocalDistantApi {
         self.state.process.send_stdin(id, data).await
     }
 
-    async fn proc_resize_pty(
+    async fn proc_stdin_close(
         &self,
         ctx: DistantCtx<Self::LocalData>,
         id: ProcessId,
-        size: PtySize,
     ) -> io::Result<()> {
         debug!(
-            "[Conn {}] Resizing pty of process {} to {}",
-            ctx.connection_id, id, size
+            "[Conn {}] Closing stdin of process {}",
+            ctx.connection_id, id
         );
-        self.state.process.resize_pty(id, size).await
-    }
-
-    async fn system_info(&self, ctx: DistantCtx<Self::LocalData>) -> io::Result<SystemInfo> {
-        debug!("[Conn {}] Reading system information", ctx.connection_id);
-        Ok(SystemInfo::default())
+        self.state.process.close_stdin(id).await
     }
-}
+
+    async fn proc_output_ack(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: ProcessId,
+        len: usize,
+    ) -> io::Result<()> {
+        trace!(
+            "[Conn {}] Acknowledging {} bytes of output from process {}",
+            ctx.connection_id,
+            len,
+            id
+        );
+        self.state.process.ack_output(id, len).await
+    }
+
+    async fn proc_resize_pty(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: ProcessId,
+        size: PtySize,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Resizing pty of process {} to {}",
+            ctx.connection_id, id, size
+        );
+        self.state.process.resize_pty(id, size).await
+    }
+
+    async fn tunnel_open(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        host: String,
+        port: u16,
+    ) -> io::Result<TunnelId> {
+        debug!(
+            "[Conn {}] Opening tunnel to {}:{}",
+            ctx.connection_id, host, port
+        );
+        self.state.tunnel.open(host, port, ctx.reply).await
+    }
+
+    async fn tunnel_write(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: TunnelId,
+        data: Vec<u8>,
+    ) -> io::Result<()> {
+        trace!(
+            "[Conn {}] Writing {} bytes to tunnel {}",
+            ctx.connection_id,
+            data.len(),
+            id
+        );
+        self.state.tunnel.write(id, data).await
+    }
+
+    async fn tunnel_close(&self, ctx: DistantCtx<Self::LocalData>, id: TunnelId) -> io::Result<()> {
+        debug!("[Conn {}] Closing tunnel {}", ctx.connection_id, id);
+        self.state.tunnel.close(id)
+    }
+
+    async fn tunnel_listen(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        host: String,
+        port: u16,
+    ) -> io::Result<(TunnelId, u16)> {
+        debug!(
+            "[Conn {}] Listening for tunnels on {}:{}",
+            ctx.connection_id, host, port
+        );
+        self.state.tunnel.listen(host, port, ctx.reply).await
+    }
+
+    async fn tunnel_listen_unix(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+    ) -> io::Result<(TunnelId, PathBuf)> {
+        debug!(
+            "[Conn {}] Listening for tunnels on a unix socket",
+            ctx.connection_id
+        );
+        self.state.tunnel.listen_unix(ctx.reply).await
+    }
+
+    async fn tunnel_unlisten(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: TunnelId,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] No longer listening for tunnels with {}",
+            ctx.connection_id, id
+        );
+        self.state.tunnel.unlisten(id)
+    }
+
+    async fn system_info(&self, ctx: DistantCtx<Self::LocalData>) -> io::Result<SystemInfo> {
+        debug!("[Conn {}] Reading system information", ctx.connection_id);
+        Ok(SystemInfo::default())
+    }
+
+    async fn system_env(&self, ctx: DistantCtx<Self::LocalData>) -> io::Result<Environment> {
+        debug!("[Conn {}] Reading system environment", ctx.connection_id);
+        let mut env = Environment::new();
+        for (name, value) in std::env::vars_os() {
+            // Variables that are not valid unicode cannot be sent, and are left out
+            if let (Ok(name), Ok(value)) = (name.into_string(), value.into_string()) {
+                if is_env_allowed(&name) {
+                    env.insert(name, value);
+                }
+            }
+        }
+        Ok(env)
+    }
+
+    async fn metrics(&self, ctx: DistantCtx<Self::LocalData>) -> io::Result<Metrics> {
+        debug!("[Conn {}] Reading metrics", ctx.connection_id);
+        Ok(self.state.usage.metrics(ctx.connection_id))
+    }
+
+    async fn system_stats(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        interval: Option<Duration>,
+    ) -> io::Result<(SystemStatsId, SystemStats)> {
+        debug!(
+            "[Conn {}] Reading system stats {{interval: {:?}}}",
+            ctx.connection_id, interval
+        );
+        self.state.stats.start(interval, ctx.reply).await
+    }
+
+    async fn proc_stats(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: ProcessId,
+        interval: Option<Duration>,
+    ) -> io::Result<(SystemStatsId, ProcStats)> {
+        debug!(
+            "[Conn {}] Reading stats of process {} {{interval: {:?}}}",
+            ctx.connection_id, id, interval
+        );
+
+        let pid = self
+            .state
+            .process
+            .list()
+            .await?
+            .into_iter()
+            .find(|p| p.id == Some(id))
+            .and_then(|p| p.pid)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No running process found with id {id}"),
+                )
+            })?;
+
+        self.state
+            .stats
+            .start_process(id, pid, interval, ctx.reply)
+            .await
+    }
+
+    async fn cancel_system_stats(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        id: SystemStatsId,
+    ) -> io::Result<()> {
+        debug!("[Conn {}] Cancelling system stats {id}", ctx.connection_id);
+        self.state.stats.cancel(id)
+    }
+
+    async fn system_kill(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        pid: u32,
+        signal: Signal,
+    ) -> io::Result<()> {
+        debug!(
+            "[Conn {}] Sending {} to system process {}",
+            ctx.connection_id, signal, pid
+        );
+
+        // Processes spawned detached by the server can be signaled without the policy allowing
+        // signals to any process of the system
+        if self.state.detached.contains(pid) {
+            return process::send_signal(pid, signal);
+        }
+
+        if let Err(x) = self.policy.check_system_kill(pid) {
+            warn!(
+                "[Conn {}] Refused to send {} to system process {}: {}",
+                ctx.connection_id, signal, pid, x
+            );
+            return Err(x);
+        }
+
+        process::send_signal(pid, signal)
+    }
+
+    async fn extension(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        name: String,
+        payload: serde_json::Value,
+    ) -> io::Result<serde_json::Value> {
+        debug!("[Conn {}] Handling extension {:?}", ctx.connection_id, name);
+
+        let not_found = || {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("No extension registered for {name:?}"),
+            )
+        };
+        let hooks = self.hooks.as_ref().map(Arc::clone).ok_or_else(not_found)?;
+
+        // Extensions can take a while, such as when running a process, so they get their own
+        // thread rather than holding up the runtime
+        let handler_name = name.clone();
+        tokio::task::spawn_blocking(move || hooks.handle_request(&handler_name, payload))
+            .await
+            .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?
+            .unwrap_or_else(|| Err(not_found()))
+    }
+}
+
+/// Size of the chunks read when copying a sparse file, where any chunk that is entirely zeroes
+/// is skipped over in the destination rather than written
+const SPARSE_COPY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Copies a file from `src` to `dst`, preserving holes in the destination if `src` is sparse
+async fn copy_file(src: &Path, dst: &Path) -> io::Result<()> {
+    let metadata = tokio::fs::metadata(src).await?;
+
+    // Reading from a fifo or device could block forever or never end, so refuse outright
+    let file_type = FileType::from(metadata.file_type());
+    if file_type.is_special() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{src:?} is a {} and cannot be copied",
+                file_type.as_ref().replace('_', " ")
+            ),
+        ));
+    }
+
+    match crate::data::allocated_len(&metadata) {
+        Some(allocated) if allocated < metadata.len() => {
+            trace!("Copying sparse file {:?} to {:?}", src, dst);
+            copy_sparse_file(src, dst, &metadata).await
+        }
+        _ => tokio::fs::copy(src, dst).await.map(|_| ()),
+    }
+}
+
+async fn copy_sparse_file(src: &Path, dst: &Path, metadata: &std::fs::Metadata) -> io::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let mut reader = tokio::fs::File::open(src).await?;
+    let mut writer = tokio::fs::File::create(dst).await?;
+    let mut buf = vec![0; SPARSE_COPY_CHUNK_SIZE];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+
+        if buf[..n].iter().all(|b| *b == 0) {
+            writer.seek(io::SeekFrom::Current(n as i64)).await?;
+        } else {
+            writer.write_all(&buf[..n]).await?;
+        }
+    }
+
+    // Extend the file to cover any trailing hole that we seeked past without writing
+    writer.set_len(metadata.len()).await?;
+    writer.flush().await?;
+
+    tokio::fs::set_permissions(dst, metadata.permissions()).await
+}
+
+/// Returns true if the environment variable called `name` is in [`SERVER_ENV_ALLOWLIST`], ignoring
+/// case on Windows where variable names are case-insensitive
+fn is_env_allowed(name: &str) -> bool {
+    let name = if cfg!(windows) {
+        name.to_uppercase()
+    } else {
+        name.to_string()
+    };
+
+    SERVER_ENV_ALLOWLIST
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == *pattern,
+        })
+}
+
+/// Repeatedly invokes `create` with a randomly-named path within `parent` (or the temporary
+/// directory) until one is created that did not already exist, returning that path
+async fn make_temp<F, Fut>(parent: Option<PathBuf>, create: F) -> io::Result<PathBuf>
+where
+    F: Fn(PathBuf) -> Fut,
+    Fut: std::future::Future<Output = io::Result<()>>,
+{
+    let parent = parent.unwrap_or_else(std::env::temp_dir);
+    for _ in 0..MAX_TEMP_ATTEMPTS {
+        let path = parent.join(make_temp_name());
+
+        match create(path.clone()).await {
+            Ok(()) => return Ok(path),
+            Err(x) if x.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(x) => return Err(x),
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "Failed to find an unused temporary name",
+    ))
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::api::ConnectionCtx;
-    use crate::data::DistantResponseData;
+    use crate::data::{DirEntrySort, DistantResponseData};
     use assert_fs::prelude::*;
     use distant_net::server::Reply;
     use once_cell::sync::Lazy;
@@ -560,6 +1479,38 @@ mod tests {
         script
     });
 
+    static INTERLEAVE_STDOUT_AND_STDERR_SH: Lazy<assert_fs::fixture::ChildPath> = Lazy::new(|| {
+        let script = TEMP_SCRIPT_DIR.child("interleave_stdout_and_stderr.sh");
+        script
+            .write_str(indoc::indoc!(
+                r#"
+                #!/usr/bin/env bash
+                printf "a"
+                sleep 0.1
+                printf "b" 1>&2
+                sleep 0.1
+                printf "c"
+            "#
+            ))
+            .unwrap();
+        script
+    });
+
+    static SLEEP_IN_BACKGROUND_SH: Lazy<assert_fs::fixture::ChildPath> = Lazy::new(|| {
+        let script = TEMP_SCRIPT_DIR.child("sleep_in_background.sh");
+        script
+            .write_str(indoc::indoc!(
+                r#"
+                #!/usr/bin/env bash
+                sleep "$1" &
+                echo "$!"
+                wait
+            "#
+            ))
+            .unwrap();
+        script
+    });
+
     static DOES_NOT_EXIST_BIN: Lazy<assert_fs::fixture::ChildPath> =
         Lazy::new(|| TEMP_SCRIPT_DIR.child("does_not_exist_bin"));
 
@@ -587,6 +1538,7 @@ mod tests {
             connection_id,
             reply,
             local_data: Arc::new(()),
+            deadline: None,
         };
         (api, ctx, rx)
     }
@@ -607,7 +1559,7 @@ mod tests {
         let temp = assert_fs::TempDir::new().unwrap();
         let path = temp.child("missing-file").path().to_path_buf();
 
-        let _ = api.read_file(ctx, path).await.unwrap_err();
+        let _ = api.read_file(ctx, path, None, None).await.unwrap_err();
     }
 
     #[test(tokio::test)]
@@ -618,10 +1570,35 @@ mod tests {
         let file = temp.child("test-file");
         file.write_str("some file contents").unwrap();
 
-        let bytes = api.read_file(ctx, file.path().to_path_buf()).await.unwrap();
+        let bytes = api
+            .read_file(ctx, file.path().to_path_buf(), None, None)
+            .await
+            .unwrap();
         assert_eq!(bytes, b"some file contents");
     }
 
+    #[test(tokio::test)]
+    async fn read_file_should_read_at_most_len_bytes_starting_at_offset() {
+        let (api, ctx, _rx) = setup(1).await;
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("test-file");
+        file.write_str("some file contents").unwrap();
+
+        let bytes = api
+            .read_file(ctx, file.path().to_path_buf(), Some(5), Some(4))
+            .await
+            .unwrap();
+        assert_eq!(bytes, b"file");
+
+        let (_, ctx, _rx) = setup(1).await;
+        let bytes = api
+            .read_file(ctx, file.path().to_path_buf(), Some(10), Some(100))
+            .await
+            .unwrap();
+        assert_eq!(bytes, b"contents");
+    }
+
     #[test(tokio::test)]
     async fn read_file_text_should_send_error_if_fails_to_read_file() {
         let (api, ctx, _rx) = setup(1).await;
@@ -657,7 +1634,13 @@ mod tests {
         let file = temp.child("dir").child("test-file");
 
         let _ = api
-            .write_file(ctx, file.path().to_path_buf(), b"some text".to_vec())
+            .write_file(
+                ctx,
+                file.path().to_path_buf(),
+                b"some text".to_vec(),
+                None,
+                None,
+            )
             .await
             .unwrap_err();
 
@@ -674,15 +1657,127 @@ mod tests {
         let temp = assert_fs::TempDir::new().unwrap();
         let file = temp.child("test-file");
 
-        api.write_file(ctx, file.path().to_path_buf(), b"some text".to_vec())
-            .await
-            .unwrap();
+        api.write_file(
+            ctx,
+            file.path().to_path_buf(),
+            b"some text".to_vec(),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
 
         // Also verify that we actually did create the file
         // with the associated contents
         file.assert("some text");
     }
 
+    #[test(tokio::test)]
+    async fn write_file_should_write_at_offset_without_truncating_if_offset_provided() {
+        let (api, ctx, _rx) = setup(1).await;
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("test-file");
+        file.write_str("some file contents").unwrap();
+
+        api.write_file(
+            ctx,
+            file.path().to_path_buf(),
+            b"FILE".to_vec(),
+            Some(5),
+            None,
+        )
+        .await
+        .unwrap();
+
+        file.assert("some FILE contents");
+    }
+
+    #[test(tokio::test)]
+    async fn write_file_should_extend_file_if_offset_is_past_end() {
+        let (api, ctx, _rx) = setup(1).await;
+
+        // File does not exist, so it should be created with a zeroed prefix
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("test-file");
+
+        api.write_file(
+            ctx,
+            file.path().to_path_buf(),
+            b"text".to_vec(),
+            Some(3),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(std::fs::read(file.path()).unwrap(), b"\0\0\0text");
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn write_file_should_give_created_file_mode_if_provided() {
+        use std::os::unix::fs::PermissionsExt;
+        let (api, ctx, _rx) = setup(1).await;
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("test-file");
+
+        api.write_file(
+            ctx,
+            file.path().to_path_buf(),
+            b"some text".to_vec(),
+            None,
+            Some(0o666),
+        )
+        .await
+        .unwrap();
+
+        file.assert("some text");
+        let mode = std::fs::metadata(file.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o666);
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn write_file_should_use_default_mode_only_for_created_files() {
+        use std::os::unix::fs::PermissionsExt;
+        let (api, ctx_1, _rx) = setup(1).await;
+        let ctx_2 = DistantCtx {
+            connection_id: ctx_1.connection_id,
+            reply: ctx_1.reply.clone_reply(),
+            local_data: Arc::clone(&ctx_1.local_data),
+            deadline: None,
+        };
+        let api = api.with_default_modes(DefaultModes {
+            file: Some(0o640),
+            dir: None,
+        });
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let new_file = temp.child("new-file");
+        let existing_file = temp.child("existing-file");
+        existing_file.write_str("old text").unwrap();
+        std::fs::set_permissions(existing_file.path(), std::fs::Permissions::from_mode(0o600))
+            .unwrap();
+
+        api.write_file_text(ctx_1, new_file.path().to_path_buf(), String::from("text"))
+            .await
+            .unwrap();
+        api.write_file_text(
+            ctx_2,
+            existing_file.path().to_path_buf(),
+            String::from("text"),
+        )
+        .await
+        .unwrap();
+
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(new_file.path()), 0o640);
+        assert_eq!(mode(existing_file.path()), 0o600);
+        existing_file.assert("text");
+    }
+
     #[test(tokio::test)]
     async fn write_file_text_should_send_error_if_fails_to_write_file() {
         let (api, ctx, _rx) = setup(1).await;
@@ -857,21 +1952,192 @@ mod tests {
         file.assert("some file contentssome extra contents");
     }
 
-    #[test(tokio::test)]
-    async fn dir_read_should_send_error_if_directory_does_not_exist() {
-        let (api, ctx, _rx) = setup(1).await;
+    /// Hooks that reject writes of data containing "forbidden" or to paths ending in ".lock",
+    /// remembering the writes they were asked about and the spawned commands
+    #[derive(Default)]
+    struct TestHooks {
+        written: std::sync::Mutex<Vec<(PathBuf, Option<Vec<u8>>)>>,
+        spawned: std::sync::Mutex<Vec<(ProcessId, String)>>,
+    }
 
-        let temp = assert_fs::TempDir::new().unwrap();
-        let dir = temp.child("test-dir");
+    impl ServerHooks for TestHooks {
+        fn before_write(&self, path: &Path, data: Option<&[u8]>) -> io::Result<()> {
+            self.written
+                .lock()
+                .unwrap()
+                .push((path.to_path_buf(), data.map(<[u8]>::to_vec)));
 
-        let _ = api
-            .read_dir(
-                ctx,
-                dir.path().to_path_buf(),
+            if path.extension().is_some_and(|x| x == "lock") {
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, "locked"));
+            }
+            if data.is_some_and(|data| data.windows(9).any(|x| x == b"forbidden")) {
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, "forbidden"));
+            }
+            Ok(())
+        }
+
+        fn after_spawn(&self, id: ProcessId, cmd: &str, _current_dir: Option<&Path>) {
+            self.spawned.lock().unwrap().push((id, cmd.to_string()));
+        }
+
+        fn handle_request(
+            &self,
+            name: &str,
+            payload: serde_json::Value,
+        ) -> Option<io::Result<serde_json::Value>> {
+            (name == "x-echo").then_some(Ok(payload))
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn append_file_text_should_fail_without_writing_if_rejected_by_hooks() {
+        let (api, ctx, _rx) = setup(1).await;
+        let api = api.with_hooks(Arc::new(TestHooks::default()));
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("test-file");
+        file.write_str("some file contents").unwrap();
+
+        let err = api
+            .append_file_text(
+                ctx,
+                file.path().to_path_buf(),
+                "forbidden contents".to_string(),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+        // Verify that the file was left untouched
+        file.assert("some file contents");
+    }
+
+    #[test(tokio::test)]
+    async fn copy_should_fail_without_copying_if_rejected_by_hooks() {
+        let (api, ctx, _rx) = setup(1).await;
+        let api = api.with_hooks(Arc::new(TestHooks::default()));
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let src = temp.child("src");
+        src.write_str("some file contents").unwrap();
+        let dst = temp.child("dst.lock");
+
+        let err = api
+            .copy(ctx, src.path().to_path_buf(), dst.path().to_path_buf())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        dst.assert(predicate::path::missing());
+    }
+
+    #[test(tokio::test)]
+    async fn rename_should_fail_without_renaming_if_rejected_by_hooks() {
+        let (api, ctx, _rx) = setup(1).await;
+        let api = api.with_hooks(Arc::new(TestHooks::default()));
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let src = temp.child("src");
+        src.write_str("some file contents").unwrap();
+        let dst = temp.child("dst.lock");
+
+        let err = api
+            .rename(ctx, src.path().to_path_buf(), dst.path().to_path_buf())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        src.assert("some file contents");
+        dst.assert(predicate::path::missing());
+    }
+
+    #[test(tokio::test)]
+    async fn restore_snapshot_should_fail_without_restoring_if_rejected_by_hooks() {
+        let (api, ctx, _rx) = setup(1).await;
+        let connection_id = ctx.connection_id;
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("file.lock");
+        file.write_str("old").unwrap();
+
+        let id = api
+            .create_snapshot(ctx, file.path().to_path_buf())
+            .await
+            .unwrap();
+        file.write_str("new").unwrap();
+
+        let api = api.with_hooks(Arc::new(TestHooks::default()));
+        let (reply, _rx) = make_reply(1);
+        let ctx = DistantCtx {
+            connection_id,
+            reply,
+            local_data: Arc::new(()),
+            deadline: None,
+        };
+        let err = api.restore_snapshot(ctx, id).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        file.assert("new");
+    }
+
+    #[test(tokio::test)]
+    async fn make_temp_file_should_check_created_file_with_hooks() {
+        let (api, ctx, _rx) = setup(1).await;
+        let hooks = Arc::new(TestHooks::default());
+        let api = api.with_hooks(Arc::clone(&hooks) as Arc<dyn ServerHooks>);
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let path = api
+            .make_temp_file(ctx, Some(temp.path().to_path_buf()))
+            .await
+            .unwrap();
+        assert!(hooks
+            .written
+            .lock()
+            .unwrap()
+            .contains(&(path, Some(Vec::new()))));
+    }
+
+    #[test(tokio::test)]
+    async fn transaction_should_check_edited_file_with_hooks_before_staging_it() {
+        let (api, ctx, _rx) = setup(1).await;
+        let hooks = Arc::new(TestHooks::default());
+        let api = api.with_hooks(Arc::clone(&hooks) as Arc<dyn ServerHooks>);
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("test-file");
+        file.write_str("some file contents").unwrap();
+        let path = std::fs::canonicalize(file.path()).unwrap();
+
+        let response = crate::api::transaction::run(
+            &api,
+            ctx,
+            vec![crate::data::BatchOp::FileWrite {
+                path: file.path().to_path_buf(),
+                data: b"new contents".to_vec(),
+            }],
+        )
+        .await;
+        assert_eq!(response, DistantResponseData::Ok);
+        file.assert("new contents");
+
+        let written = hooks.written.lock().unwrap();
+        assert_eq!(written[0], (path, Some(b"new contents".to_vec())));
+    }
+
+    #[test(tokio::test)]
+    async fn dir_read_should_send_error_if_directory_does_not_exist() {
+        let (api, ctx, _rx) = setup(1).await;
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let dir = temp.child("test-dir");
+
+        let _ = api
+            .read_dir(
+                ctx,
+                dir.path().to_path_buf(),
                 /* depth */ 0,
                 /* absolute */ false,
                 /* canonicalize */ false,
                 /* include_root */ false,
+                /* options */ Default::default(),
             )
             .await
             .unwrap_err();
@@ -913,6 +2179,7 @@ mod tests {
                 /* absolute */ false,
                 /* canonicalize */ false,
                 /* include_root */ false,
+                /* options */ Default::default(),
             )
             .await
             .unwrap();
@@ -947,6 +2214,7 @@ mod tests {
                 /* absolute */ false,
                 /* canonicalize */ false,
                 /* include_root */ false,
+                /* options */ Default::default(),
             )
             .await
             .unwrap();
@@ -985,6 +2253,7 @@ mod tests {
                 /* absolute */ false,
                 /* canonicalize */ false,
                 /* include_root */ true,
+                /* options */ Default::default(),
             )
             .await
             .unwrap();
@@ -1024,6 +2293,7 @@ mod tests {
                 /* absolute */ true,
                 /* canonicalize */ false,
                 /* include_root */ false,
+                /* options */ Default::default(),
             )
             .await
             .unwrap();
@@ -1059,6 +2329,7 @@ mod tests {
                 /* absolute */ false,
                 /* canonicalize */ true,
                 /* include_root */ false,
+                /* options */ Default::default(),
             )
             .await
             .unwrap();
@@ -1080,130 +2351,540 @@ mod tests {
     }
 
     #[test(tokio::test)]
-    async fn create_dir_should_send_error_if_fails() {
+    async fn dir_read_should_support_sorting_entries() {
         let (api, ctx, _rx) = setup(1).await;
 
-        // Make a path that has multiple non-existent components
-        // so the creation will fail
-        let root_dir = setup_dir().await;
-        let path = root_dir.path().join("nested").join("new-dir");
+        let root_dir = assert_fs::TempDir::new().unwrap();
+        root_dir.child("a").write_str("abc").unwrap();
+        root_dir.child("b").write_str("a").unwrap();
+        root_dir.child("c").write_str("ab").unwrap();
 
-        let _ = api
-            .create_dir(ctx, path.to_path_buf(), /* all */ false)
+        let (entries, _) = api
+            .read_dir(
+                ctx,
+                root_dir.path().to_path_buf(),
+                /* depth */ 1,
+                /* absolute */ false,
+                /* canonicalize */ false,
+                /* include_root */ false,
+                DirReadOptions {
+                    sort: DirEntrySort::Size,
+                    reverse: true,
+                    ..Default::default()
+                },
+            )
             .await
-            .unwrap_err();
+            .unwrap();
 
-        // Also verify that the directory was not actually created
-        assert!(!path.exists(), "Path unexpectedly exists");
+        let paths: Vec<_> = entries.into_iter().map(|e| e.path).collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("a"), PathBuf::from("c"), PathBuf::from("b")]
+        );
     }
 
     #[test(tokio::test)]
-    async fn create_dir_should_send_ok_when_successful() {
+    async fn dir_read_should_support_filtering_by_file_type() {
         let (api, ctx, _rx) = setup(1).await;
+
+        // Create directory with some nested items
         let root_dir = setup_dir().await;
-        let path = root_dir.path().join("new-dir");
 
-        api.create_dir(ctx, path.to_path_buf(), /* all */ false)
+        let (entries, _) = api
+            .read_dir(
+                ctx,
+                root_dir.path().to_path_buf(),
+                /* depth */ 0,
+                /* absolute */ false,
+                /* canonicalize */ false,
+                /* include_root */ false,
+                DirReadOptions {
+                    allowed_file_types: vec![FileType::File].into_iter().collect(),
+                    ..Default::default()
+                },
+            )
             .await
             .unwrap();
 
-        // Also verify that the directory was actually created
-        assert!(path.exists(), "Directory not created");
+        assert_eq!(entries.len(), 2, "Wrong number of entries found");
+
+        assert_eq!(entries[0].file_type, FileType::File);
+        assert_eq!(entries[0].path, Path::new("file1"));
+        assert_eq!(entries[0].depth, 1);
+
+        // Filtered directories are still traversed
+        assert_eq!(entries[1].file_type, FileType::File);
+        assert_eq!(entries[1].path, Path::new("sub1").join("file2"));
+        assert_eq!(entries[1].depth, 2);
     }
 
     #[test(tokio::test)]
-    async fn create_dir_should_support_creating_multiple_dir_components() {
+    async fn dir_read_should_support_excluding_hidden_entries() {
         let (api, ctx, _rx) = setup(1).await;
+
+        // Create directory with some nested items, including hidden ones
         let root_dir = setup_dir().await;
-        let path = root_dir.path().join("nested").join("new-dir");
+        root_dir.child(".hidden-file").touch().unwrap();
+        root_dir
+            .child(".hidden-dir")
+            .child("file3")
+            .touch()
+            .unwrap();
 
-        api.create_dir(ctx, path.to_path_buf(), /* all */ true)
+        let (entries, _) = api
+            .read_dir(
+                ctx,
+                root_dir.path().to_path_buf(),
+                /* depth */ 0,
+                /* absolute */ false,
+                /* canonicalize */ false,
+                /* include_root */ false,
+                DirReadOptions {
+                    exclude_hidden: true,
+                    ..Default::default()
+                },
+            )
             .await
             .unwrap();
 
-        // Also verify that the directory was actually created
-        assert!(path.exists(), "Directory not created");
+        let paths: Vec<_> = entries.into_iter().map(|e| e.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("file1"),
+                PathBuf::from("link1"),
+                PathBuf::from("sub1"),
+                PathBuf::from("sub1").join("file2"),
+            ]
+        );
     }
 
     #[test(tokio::test)]
-    async fn remove_should_send_error_on_failure() {
+    async fn dir_manifest_should_fail_if_path_is_not_a_directory() {
         let (api, ctx, _rx) = setup(1).await;
-        let temp = assert_fs::TempDir::new().unwrap();
-        let file = temp.child("missing-file");
+        let root_dir = setup_dir().await;
 
         let _ = api
-            .remove(ctx, file.path().to_path_buf(), /* false */ false)
+            .dir_manifest(
+                ctx,
+                root_dir.child("file1").to_path_buf(),
+                HashAlgorithm::Sha256,
+            )
             .await
             .unwrap_err();
+    }
 
-        // Also, verify that path does not exist
-        file.assert(predicate::path::missing());
+    #[test(tokio::test)]
+    async fn dir_manifest_should_send_size_and_hash_of_each_file_without_following_symlinks() {
+        let (api, ctx, mut rx) = setup(1).await;
+        let root_dir = setup_dir().await;
+        root_dir.child("file1").write_str("hello").unwrap();
+
+        api.dir_manifest(ctx, root_dir.path().to_path_buf(), HashAlgorithm::Sha256)
+            .await
+            .unwrap();
+
+        match rx.recv().await.unwrap() {
+            DistantResponseData::DirManifestChunk {
+                entries,
+                errors,
+                done,
+            } => {
+                assert!(done, "Only chunk should be marked as done");
+                assert!(errors.is_empty(), "Unexpected errors: {errors:?}");
+                assert!(entries.iter().all(|entry| entry.modified.is_some()));
+                assert_eq!(
+                    entries
+                        .into_iter()
+                        .map(|entry| (entry.path, entry.size, entry.hash))
+                        .collect::<Vec<_>>(),
+                    vec![
+                        (
+                            PathBuf::from("file1"),
+                            5,
+                            String::from(
+                                "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+                            )
+                        ),
+                        (
+                            Path::new("sub1").join("file2"),
+                            0,
+                            String::from(
+                                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+                            )
+                        ),
+                    ]
+                );
+            }
+            x => panic!("Unexpected response: {:?}", x),
+        }
     }
 
     #[test(tokio::test)]
-    async fn remove_should_support_deleting_a_directory() {
-        let (api, ctx, _rx) = setup(1).await;
-        let temp = assert_fs::TempDir::new().unwrap();
-        let dir = temp.child("dir");
-        dir.create_dir_all().unwrap();
+    async fn dir_manifest_should_send_files_in_chunks_ordered_by_path() {
+        let (api, ctx, mut rx) = setup(1).await;
+        let root_dir = assert_fs::TempDir::new().unwrap();
+        for i in 0..150 {
+            root_dir.child(format!("file{i:03}")).touch().unwrap();
+        }
 
-        api.remove(ctx, dir.path().to_path_buf(), /* false */ false)
+        api.dir_manifest(ctx, root_dir.path().to_path_buf(), HashAlgorithm::Sha512)
             .await
             .unwrap();
 
-        // Also, verify that path does not exist
-        dir.assert(predicate::path::missing());
+        let mut paths = Vec::new();
+        for (len, last) in [(100, false), (50, true)] {
+            match rx.recv().await.unwrap() {
+                DistantResponseData::DirManifestChunk { entries, done, .. } => {
+                    assert_eq!(entries.len(), len);
+                    assert_eq!(done, last);
+                    paths.extend(entries.into_iter().map(|entry| entry.path));
+                }
+                x => panic!("Unexpected response: {:?}", x),
+            }
+        }
+
+        assert_eq!(
+            paths,
+            (0..150)
+                .map(|i| PathBuf::from(format!("file{i:03}")))
+                .collect::<Vec<_>>()
+        );
     }
 
     #[test(tokio::test)]
-    async fn remove_should_delete_nonempty_directory_if_force_is_true() {
-        let (api, ctx, _rx) = setup(1).await;
-        let temp = assert_fs::TempDir::new().unwrap();
-        let dir = temp.child("dir");
-        dir.create_dir_all().unwrap();
-        dir.child("file").touch().unwrap();
+    async fn read_dir_chunked_should_send_entries_after_first_chunk_as_they_are_read() {
+        let (api, ctx, mut rx) = setup(1).await;
+        let root_dir = assert_fs::TempDir::new().unwrap();
+        for i in 0..25 {
+            root_dir.child(format!("file{i:02}")).touch().unwrap();
+        }
 
-        api.remove(ctx, dir.path().to_path_buf(), /* false */ true)
+        let (entries, errors, done) = api
+            .read_dir_chunked(
+                ctx,
+                root_dir.path().to_path_buf(),
+                /* depth */ 1,
+                /* absolute */ false,
+                /* canonicalize */ false,
+                /* include_root */ false,
+                DirReadOptions::default(),
+                /* chunk_size */ 10,
+            )
             .await
             .unwrap();
+        assert_eq!(entries.len(), 10);
+        assert!(errors.is_empty(), "Unexpected errors: {:?}", errors);
+        assert!(!done, "First chunk unexpectedly marked as done");
+
+        let mut paths: Vec<PathBuf> = entries.into_iter().map(|entry| entry.path).collect();
+        for (len, last) in [(10, false), (5, true)] {
+            match rx.recv().await.unwrap() {
+                DistantResponseData::DirEntriesChunk {
+                    entries,
+                    errors,
+                    done,
+                } => {
+                    assert_eq!(entries.len(), len);
+                    assert!(errors.is_empty(), "Unexpected errors: {:?}", errors);
+                    assert_eq!(done, last);
+                    paths.extend(entries.into_iter().map(|entry| entry.path));
+                }
+                x => panic!("Unexpected response: {:?}", x),
+            }
+        }
 
-        // Also, verify that path does not exist
-        dir.assert(predicate::path::missing());
+        assert_eq!(
+            paths,
+            (0..25)
+                .map(|i| PathBuf::from(format!("file{i:02}")))
+                .collect::<Vec<_>>()
+        );
     }
 
     #[test(tokio::test)]
-    async fn remove_should_support_deleting_a_single_file() {
+    async fn read_dir_chunked_should_return_only_chunk_as_done_if_all_entries_fit() {
         let (api, ctx, _rx) = setup(1).await;
-        let temp = assert_fs::TempDir::new().unwrap();
-        let file = temp.child("some-file");
-        file.touch().unwrap();
+        let root_dir = setup_dir().await;
 
-        api.remove(ctx, file.path().to_path_buf(), /* false */ false)
+        let (entries, _, done) = api
+            .read_dir_chunked(
+                ctx,
+                root_dir.path().to_path_buf(),
+                /* depth */ 1,
+                /* absolute */ false,
+                /* canonicalize */ false,
+                /* include_root */ false,
+                DirReadOptions::default(),
+                /* chunk_size */ 100,
+            )
             .await
             .unwrap();
-
-        // Also, verify that path does not exist
-        file.assert(predicate::path::missing());
+        assert_eq!(entries.len(), 3);
+        assert!(done, "Only chunk not marked as done");
     }
 
     #[test(tokio::test)]
-    async fn copy_should_send_error_on_failure() {
+    async fn create_dir_should_send_error_if_fails() {
         let (api, ctx, _rx) = setup(1).await;
-        let temp = assert_fs::TempDir::new().unwrap();
-        let src = temp.child("src");
-        let dst = temp.child("dst");
+
+        // Make a path that has multiple non-existent components
+        // so the creation will fail
+        let root_dir = setup_dir().await;
+        let path = root_dir.path().join("nested").join("new-dir");
 
         let _ = api
-            .copy(ctx, src.path().to_path_buf(), dst.path().to_path_buf())
+            .create_dir(ctx, path.to_path_buf(), /* all */ false, None)
             .await
             .unwrap_err();
 
-        // Also, verify that destination does not exist
-        dst.assert(predicate::path::missing());
+        // Also verify that the directory was not actually created
+        assert!(!path.exists(), "Path unexpectedly exists");
     }
 
     #[test(tokio::test)]
-    async fn copy_should_support_copying_an_entire_directory() {
+    async fn create_dir_should_send_ok_when_successful() {
+        let (api, ctx, _rx) = setup(1).await;
+        let root_dir = setup_dir().await;
+        let path = root_dir.path().join("new-dir");
+
+        api.create_dir(ctx, path.to_path_buf(), /* all */ false, None)
+            .await
+            .unwrap();
+
+        // Also verify that the directory was actually created
+        assert!(path.exists(), "Directory not created");
+    }
+
+    #[test(tokio::test)]
+    async fn create_dir_should_support_creating_multiple_dir_components() {
+        let (api, ctx, _rx) = setup(1).await;
+        let root_dir = setup_dir().await;
+        let path = root_dir.path().join("nested").join("new-dir");
+
+        api.create_dir(ctx, path.to_path_buf(), /* all */ true, None)
+            .await
+            .unwrap();
+
+        // Also verify that the directory was actually created
+        assert!(path.exists(), "Directory not created");
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn create_dir_should_give_each_created_dir_mode_if_provided() {
+        use std::os::unix::fs::PermissionsExt;
+        let (api, ctx, _rx) = setup(1).await;
+        let root_dir = setup_dir().await;
+        let path = root_dir.path().join("nested").join("new-dir");
+        let root_mode = std::fs::metadata(root_dir.path())
+            .unwrap()
+            .permissions()
+            .mode();
+
+        // Mode is applied even though it grants more than a typical umask would allow
+        api.create_dir(ctx, path.to_path_buf(), /* all */ true, Some(0o777))
+            .await
+            .unwrap();
+
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), 0o777);
+        assert_eq!(mode(path.parent().unwrap()), 0o777);
+        assert_eq!(
+            std::fs::metadata(root_dir.path())
+                .unwrap()
+                .permissions()
+                .mode(),
+            root_mode,
+            "Existing directory unexpectedly changed"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn create_fifo_should_create_named_pipe_with_mode_if_provided() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let path = temp.child("fifo");
+
+        let (api, ctx, _rx) = setup(1).await;
+        api.create_fifo(ctx, path.to_path_buf(), Some(0o600))
+            .await
+            .unwrap();
+
+        let (api, ctx, _rx) = setup(1).await;
+        let metadata = api
+            .metadata(
+                ctx,
+                path.to_path_buf(),
+                /* canonicalize */ false,
+                /* resolve_file_type */ false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(metadata.file_type, FileType::Fifo);
+        assert_eq!(
+            std::fs::metadata(path.path()).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn make_temp_file_should_create_empty_file_within_parent() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let path = api
+            .make_temp_file(ctx, Some(temp.path().to_path_buf()))
+            .await
+            .unwrap();
+        assert_eq!(path.parent(), Some(temp.path()));
+        assert!(path.is_file(), "Temporary file missing");
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+    }
+
+    #[test(tokio::test)]
+    async fn make_temp_dir_should_create_unique_directories() {
+        let (api, ctx_1, _rx) = setup(1).await;
+        let ctx_2 = DistantCtx {
+            connection_id: ctx_1.connection_id,
+            reply: make_reply(1).0,
+            local_data: Arc::clone(&ctx_1.local_data),
+            deadline: None,
+        };
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let path_1 = api
+            .make_temp_dir(ctx_1, Some(temp.path().to_path_buf()))
+            .await
+            .unwrap();
+        let path_2 = api
+            .make_temp_dir(ctx_2, Some(temp.path().to_path_buf()))
+            .await
+            .unwrap();
+        assert_ne!(path_1, path_2);
+        assert!(path_1.is_dir(), "First temporary directory missing");
+        assert!(path_2.is_dir(), "Second temporary directory missing");
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn make_temp_should_only_let_owner_access_what_it_creates() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (api, ctx_1, _rx) = setup(1).await;
+        let ctx_2 = DistantCtx {
+            connection_id: ctx_1.connection_id,
+            reply: make_reply(1).0,
+            local_data: Arc::clone(&ctx_1.local_data),
+            deadline: None,
+        };
+        let temp = assert_fs::TempDir::new().unwrap();
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        let file = api
+            .make_temp_file(ctx_1, Some(temp.path().to_path_buf()))
+            .await
+            .unwrap();
+        assert_eq!(mode(&file), 0o600);
+
+        let dir = api
+            .make_temp_dir(ctx_2, Some(temp.path().to_path_buf()))
+            .await
+            .unwrap();
+        assert_eq!(mode(&dir), 0o700);
+    }
+
+    #[test(tokio::test)]
+    async fn make_temp_file_should_fail_if_parent_missing() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let _ = api
+            .make_temp_file(ctx, Some(temp.path().join("missing")))
+            .await
+            .unwrap_err();
+    }
+
+    #[test(tokio::test)]
+    async fn remove_should_send_error_on_failure() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("missing-file");
+
+        let _ = api
+            .remove(ctx, file.path().to_path_buf(), /* false */ false)
+            .await
+            .unwrap_err();
+
+        // Also, verify that path does not exist
+        file.assert(predicate::path::missing());
+    }
+
+    #[test(tokio::test)]
+    async fn remove_should_support_deleting_a_directory() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let dir = temp.child("dir");
+        dir.create_dir_all().unwrap();
+
+        api.remove(ctx, dir.path().to_path_buf(), /* false */ false)
+            .await
+            .unwrap();
+
+        // Also, verify that path does not exist
+        dir.assert(predicate::path::missing());
+    }
+
+    #[test(tokio::test)]
+    async fn remove_should_delete_nonempty_directory_if_force_is_true() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let dir = temp.child("dir");
+        dir.create_dir_all().unwrap();
+        dir.child("file").touch().unwrap();
+
+        api.remove(ctx, dir.path().to_path_buf(), /* false */ true)
+            .await
+            .unwrap();
+
+        // Also, verify that path does not exist
+        dir.assert(predicate::path::missing());
+    }
+
+    #[test(tokio::test)]
+    async fn remove_should_support_deleting_a_single_file() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("some-file");
+        file.touch().unwrap();
+
+        api.remove(ctx, file.path().to_path_buf(), /* false */ false)
+            .await
+            .unwrap();
+
+        // Also, verify that path does not exist
+        file.assert(predicate::path::missing());
+    }
+
+    #[test(tokio::test)]
+    async fn copy_should_send_error_on_failure() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let src = temp.child("src");
+        let dst = temp.child("dst");
+
+        let _ = api
+            .copy(ctx, src.path().to_path_buf(), dst.path().to_path_buf())
+            .await
+            .unwrap_err();
+
+        // Also, verify that destination does not exist
+        dst.assert(predicate::path::missing());
+    }
+
+    #[test(tokio::test)]
+    async fn copy_should_support_copying_an_entire_directory() {
         let (api, ctx, _rx) = setup(1).await;
         let temp = assert_fs::TempDir::new().unwrap();
 
@@ -1284,6 +2965,81 @@ mod tests {
         dst.assert(predicate::path::eq_file(src.path()));
     }
 
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn copy_should_fail_if_source_is_a_fifo() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let src = temp.child("fifo");
+        let dst = temp.child("dst");
+
+        let (api, ctx, _rx) = setup(1).await;
+        api.create_fifo(ctx, src.to_path_buf(), None).await.unwrap();
+
+        let (api, ctx, _rx) = setup(1).await;
+        let err = api
+            .copy(ctx, src.path().to_path_buf(), dst.path().to_path_buf())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        dst.assert(predicate::path::missing());
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn copy_should_skip_fifos_when_copying_a_directory() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let src = temp.child("src");
+        src.create_dir_all().unwrap();
+        src.child("file").write_str("some text").unwrap();
+        let dst = temp.child("dst");
+
+        let (api, ctx, _rx) = setup(1).await;
+        api.create_fifo(ctx, src.child("fifo").to_path_buf(), None)
+            .await
+            .unwrap();
+
+        let (api, ctx, _rx) = setup(1).await;
+        api.copy(ctx, src.path().to_path_buf(), dst.path().to_path_buf())
+            .await
+            .unwrap();
+
+        dst.child("file").assert("some text");
+        dst.child("fifo").assert(predicate::path::missing());
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn copy_should_preserve_holes_when_copying_a_sparse_file() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        let src = temp.child("src");
+        let dst = temp.child("dst");
+
+        // Create a 4 MiB file that only has data written in the middle
+        {
+            use std::io::{Seek, SeekFrom, Write};
+            let mut file = std::fs::File::create(src.path()).unwrap();
+            file.set_len(4 * 1024 * 1024).unwrap();
+            file.seek(SeekFrom::Start(2 * 1024 * 1024)).unwrap();
+            file.write_all(b"some text").unwrap();
+        }
+
+        // Skip if the underlying filesystem does not support sparse files
+        let src_metadata = Metadata::read(src.path(), false, false).await.unwrap();
+        if !src_metadata.is_sparse() {
+            return;
+        }
+
+        api.copy(ctx, src.path().to_path_buf(), dst.path().to_path_buf())
+            .await
+            .unwrap();
+
+        dst.assert(predicate::path::eq_file(src.path()));
+        let dst_metadata = Metadata::read(dst.path(), false, false).await.unwrap();
+        assert_eq!(dst_metadata.len, src_metadata.len);
+        assert!(dst_metadata.is_sparse(), "{dst_metadata:?}");
+    }
+
     #[test(tokio::test)]
     async fn rename_should_fail_if_path_missing() {
         let (api, ctx, _rx) = setup(1).await;
@@ -1341,6 +3097,30 @@ mod tests {
         dst.assert("some text");
     }
 
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn create_symlink_should_replace_an_existing_symlink() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+        temp.child("v1").write_str("one").unwrap();
+        temp.child("v2").write_str("two").unwrap();
+        let current = temp.child("current");
+        current.symlink_to_file(temp.child("v1").path()).unwrap();
+
+        api.create_symlink(ctx, current.path().to_path_buf(), PathBuf::from("v2"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_link(current.path()).unwrap(),
+            PathBuf::from("v2")
+        );
+        current.assert("two");
+
+        // Only the symlink and its targets should remain
+        assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 3);
+    }
+
     /// Validates a response as being a series of changes that include the provided paths
     fn validate_changed_paths(
         data: &DistantResponseData,
@@ -1386,6 +3166,7 @@ mod tests {
             /* recursive */ false,
             /* only */ Default::default(),
             /* except */ Default::default(),
+            /* id */ None,
         )
         .await
         .unwrap();
@@ -1422,6 +3203,7 @@ mod tests {
             /* recursive */ true,
             /* only */ Default::default(),
             /* except */ Default::default(),
+            /* id */ None,
         )
         .await
         .unwrap();
@@ -1482,25 +3264,63 @@ mod tests {
     }
 
     #[test(tokio::test)]
-    async fn watch_should_report_changes_using_the_ctx_replies() {
-        // NOTE: Supporting multiple replies being sent back as part of creating, modifying, etc.
-        let (api, ctx_1, mut rx_1) = setup(100).await;
-        let (ctx_2, mut rx_2) = {
-            let (reply, rx) = make_reply(100);
-            let ctx = DistantCtx {
-                connection_id: ctx_1.connection_id,
-                reply,
-                local_data: Arc::clone(&ctx_1.local_data),
-            };
-            (ctx, rx)
-        };
-
+    async fn watch_should_report_rename_as_a_single_change_from_and_to() {
+        let (api, ctx, mut rx) = setup(100).await;
         let temp = assert_fs::TempDir::new().unwrap();
 
-        let file_1 = temp.child("file_1");
-        file_1.touch().unwrap();
+        let from = temp.child("file");
+        from.touch().unwrap();
+        let to = temp.child("FILE");
 
-        let file_2 = temp.child("file_2");
+        api.watch(
+            ctx,
+            temp.path().to_path_buf(),
+            /* recursive */ false,
+            /* only */ ChangeKindSet::rename_set().into_sorted_vec(),
+            /* except */ Default::default(),
+            /* id */ None,
+        )
+        .await
+        .unwrap();
+
+        let from_path = from.path().canonicalize().unwrap();
+        std::fs::rename(from.path(), to.path()).unwrap();
+        let to_path = to.path().canonicalize().unwrap();
+
+        let data = rx
+            .recv()
+            .await
+            .expect("Channel closed before we got change");
+        assert_eq!(
+            data,
+            DistantResponseData::Changed(crate::data::Change {
+                kind: ChangeKind::Renamed,
+                paths: vec![from_path, to_path],
+            })
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn watch_should_report_changes_using_the_ctx_replies() {
+        // NOTE: Supporting multiple replies being sent back as part of creating, modifying, etc.
+        let (api, ctx_1, mut rx_1) = setup(100).await;
+        let (ctx_2, mut rx_2) = {
+            let (reply, rx) = make_reply(100);
+            let ctx = DistantCtx {
+                connection_id: ctx_1.connection_id,
+                reply,
+                local_data: Arc::clone(&ctx_1.local_data),
+                deadline: None,
+            };
+            (ctx, rx)
+        };
+
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let file_1 = temp.child("file_1");
+        file_1.touch().unwrap();
+
+        let file_2 = temp.child("file_2");
         file_2.touch().unwrap();
 
         // Sleep a bit to give time to get all changes happening
@@ -1514,6 +3334,7 @@ mod tests {
             /* recursive */ false,
             /* only */ Default::default(),
             /* except */ Default::default(),
+            /* id */ None,
         )
         .await
         .unwrap();
@@ -1525,6 +3346,7 @@ mod tests {
             /* recursive */ false,
             /* only */ Default::default(),
             /* except */ Default::default(),
+            /* id */ None,
         )
         .await
         .unwrap();
@@ -1554,6 +3376,186 @@ mod tests {
         );
     }
 
+    #[test(tokio::test)]
+    async fn unwatch_with_id_should_keep_other_watches_of_the_same_path() {
+        let (api, ctx_1, mut rx_1) = setup(100).await;
+        let (ctx_2, mut rx_2) = {
+            let (reply, rx) = make_reply(100);
+            let ctx = DistantCtx {
+                connection_id: ctx_1.connection_id,
+                reply,
+                local_data: Arc::clone(&ctx_1.local_data),
+                deadline: None,
+            };
+            (ctx, rx)
+        };
+        let ctx_3 = DistantCtx {
+            connection_id: ctx_1.connection_id,
+            reply: make_reply(1).0,
+            local_data: Arc::clone(&ctx_1.local_data),
+            deadline: None,
+        };
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("file");
+        file.touch().unwrap();
+
+        api.watch(
+            ctx_1,
+            file.path().to_path_buf(),
+            /* recursive */ false,
+            /* only */ Default::default(),
+            /* except */ Default::default(),
+            /* id */ Some(1),
+        )
+        .await
+        .unwrap();
+
+        api.watch(
+            ctx_2,
+            file.path().to_path_buf(),
+            /* recursive */ false,
+            /* only */ Default::default(),
+            /* except */ Default::default(),
+            /* id */ Some(2),
+        )
+        .await
+        .unwrap();
+
+        api.unwatch(ctx_3, file.path().to_path_buf(), Some(1))
+            .await
+            .unwrap();
+
+        // Only the remaining watch should be told about the change
+        file.write_str("some text").unwrap();
+        let data = rx_2
+            .recv()
+            .await
+            .expect("Channel closed before we got change");
+        validate_changed_paths(
+            &data,
+            &[file.path().to_path_buf().canonicalize().unwrap()],
+            /* should_panic */ true,
+        );
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(rx_1.try_recv().is_err(), "Unwatched subscriber got change");
+    }
+
+    #[test(tokio::test)]
+    async fn watch_with_same_id_should_move_changes_to_latest_reply() {
+        let (api, ctx_1, mut rx_1) = setup(100).await;
+        let (ctx_2, mut rx_2) = {
+            let (reply, rx) = make_reply(100);
+            let ctx = DistantCtx {
+                connection_id: ctx_1.connection_id,
+                reply,
+                local_data: Arc::clone(&ctx_1.local_data),
+                deadline: None,
+            };
+            (ctx, rx)
+        };
+        let ctx_3 = DistantCtx {
+            connection_id: ctx_1.connection_id,
+            reply: make_reply(1).0,
+            local_data: Arc::clone(&ctx_1.local_data),
+            deadline: None,
+        };
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("file");
+        file.touch().unwrap();
+
+        for ctx in [ctx_1, ctx_2] {
+            api.watch(
+                ctx,
+                file.path().to_path_buf(),
+                /* recursive */ false,
+                /* only */ Default::default(),
+                /* except */ Default::default(),
+                /* id */ Some(1),
+            )
+            .await
+            .unwrap();
+        }
+
+        file.write_str("some text").unwrap();
+        let data = rx_2
+            .recv()
+            .await
+            .expect("Channel closed before we got change");
+        validate_changed_paths(
+            &data,
+            &[file.path().to_path_buf().canonicalize().unwrap()],
+            /* should_panic */ true,
+        );
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(rx_1.try_recv().is_err(), "Replaced subscriber got change");
+
+        // With the single registration gone, there is nothing left to unwatch
+        let ctx_4 = DistantCtx {
+            connection_id: ctx_3.connection_id,
+            reply: make_reply(1).0,
+            local_data: Arc::clone(&ctx_3.local_data),
+            deadline: None,
+        };
+        api.unwatch(ctx_3, file.path().to_path_buf(), Some(1))
+            .await
+            .unwrap();
+        assert!(api
+            .unwatch(ctx_4, file.path().to_path_buf(), None)
+            .await
+            .is_err());
+    }
+
+    #[test(tokio::test)]
+    async fn journal_changes_should_fail_if_path_not_journaled() {
+        let (api, ctx, _rx) = setup(1).await;
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let err = api
+            .journal_changes(ctx, temp.path().to_path_buf(), 0)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test(tokio::test)]
+    async fn journal_changes_should_return_changes_recorded_since_journal_started() {
+        let (api, ctx_1, _rx) = setup(1).await;
+        let ctx_2 = DistantCtx {
+            connection_id: ctx_1.connection_id,
+            reply: make_reply(1).0,
+            local_data: Arc::clone(&ctx_1.local_data),
+            deadline: None,
+        };
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("file");
+        file.touch().unwrap();
+
+        api.journal_start(ctx_1, temp.path().to_path_buf(), /* recursive */ false)
+            .await
+            .unwrap();
+
+        // Change the file and give the watcher time to record it
+        file.write_str("some text").unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        let (seq, entries) = api
+            .journal_changes(ctx_2, temp.path().to_path_buf(), 0)
+            .await
+            .unwrap();
+        assert!(seq > 0, "Journal did not record any changes");
+        assert_eq!(entries.last().map(|e| e.seq), Some(seq));
+        assert!(
+            entries.iter().all(|e| e
+                .paths
+                .contains(&file.path().to_path_buf().canonicalize().unwrap())),
+            "Unexpected entries: {entries:?}"
+        );
+    }
+
     #[test(tokio::test)]
     async fn exists_should_send_true_if_path_exists() {
         let (api, ctx, _rx) = setup(1).await;
@@ -1834,6 +3836,12 @@ mod tests {
                 /* environment */ Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
             )
             .await
             .unwrap_err();
@@ -1858,12 +3866,80 @@ mod tests {
                 /* environment */ Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
             )
             .await
             .unwrap();
         assert!(id > 0);
     }
 
+    // NOTE: Ignoring on windows because it's using WSL which wants a Linux path
+    //       with / but thinks it's on windows and is providing \
+    #[test(tokio::test)]
+    #[cfg_attr(windows, ignore)]
+    async fn proc_spawn_should_invoke_hooks_with_spawned_process() {
+        let (api, ctx, _rx) = setup(1).await;
+        let hooks = Arc::new(TestHooks::default());
+        let api = api.with_hooks(Arc::clone(&hooks) as Arc<dyn ServerHooks>);
+
+        let cmd = format!(
+            "{} {}",
+            *SCRIPT_RUNNER,
+            ECHO_ARGS_TO_STDOUT_SH.to_str().unwrap()
+        );
+        let id = api
+            .proc_spawn(
+                ctx,
+                /* cmd */ cmd.clone(),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(*hooks.spawned.lock().unwrap(), vec![(id, cmd)]);
+    }
+
+    #[test(tokio::test)]
+    async fn proc_spawn_should_fail_if_command_is_not_permitted_by_policy() {
+        let (api, ctx, _rx) = setup(1).await;
+        let api = api.with_process_policy(ProcessPolicy {
+            allow: vec![String::from("git")],
+            deny: Vec::new(),
+            system_kill: false,
+        });
+
+        let err = api
+            .proc_spawn(
+                ctx,
+                /* cmd */ format!("{} -c true", *SCRIPT_RUNNER),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
     // NOTE: Ignoring on windows because it's using WSL which wants a Linux path
     //       with / but thinks it's on windows and is providing \
     #[test(tokio::test)]
@@ -1883,6 +3959,12 @@ mod tests {
                 /* environment */ Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
             )
             .await
             .unwrap();
@@ -1901,7 +3983,7 @@ mod tests {
         let mut got_done = false;
 
         let mut check_data = |data: &DistantResponseData| match data {
-            DistantResponseData::ProcStdout { id, data } => {
+            DistantResponseData::ProcStdout { id, data, .. } => {
                 assert_eq!(
                     *id, proc_id,
                     "Got {}, but expected {} as process id",
@@ -1928,6 +4010,98 @@ mod tests {
         assert!(got_done, "Missing done response");
     }
 
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn proc_spawn_should_run_process_as_user_when_privileged() {
+        // Only a privileged server can switch users without being asked for a password
+        if unsafe { libc::geteuid() } != 0 {
+            return;
+        }
+
+        let (api, ctx, mut rx) = setup(100).await;
+
+        let proc_id = api
+            .proc_spawn(
+                ctx,
+                /* cmd */ String::from("sh -c 'id -un; echo $HOME'"),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ Some(String::from("nobody")),
+            )
+            .await
+            .unwrap();
+
+        let mut stdout = Vec::new();
+        loop {
+            match rx.recv().await.expect("Missing response") {
+                DistantResponseData::ProcStdout { id, data, .. } if id == proc_id => {
+                    stdout.extend(data)
+                }
+                DistantResponseData::ProcDone { id, success, .. } if id == proc_id => {
+                    assert!(success, "Process should have completed successfully");
+                    break;
+                }
+                x => panic!("Unexpected response: {:?}", x),
+            }
+        }
+
+        let stdout = String::from_utf8(stdout).unwrap();
+        let mut lines = stdout.lines();
+        assert_eq!(lines.next(), Some("nobody"));
+        let user = process::SwitchUser::lookup("nobody").unwrap();
+        assert_eq!(lines.next().map(PathBuf::from).as_ref(), Some(user.home()));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test(tokio::test)]
+    async fn proc_spawn_should_apply_limits_before_running_process() {
+        let (api, ctx, mut rx) = setup(100).await;
+
+        let proc_id = api
+            .proc_spawn(
+                ctx,
+                /* cmd */ String::from("sh -c 'nice; ulimit -n'"),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */
+                ProcLimits {
+                    nice: Some(5),
+                    max_open_files: Some(64),
+                    ..Default::default()
+                },
+                /* kill_tree */ false,
+                /* user */ None,
+            )
+            .await
+            .unwrap();
+
+        let mut stdout = Vec::new();
+        loop {
+            match rx.recv().await.expect("Missing response") {
+                DistantResponseData::ProcStdout { id, data, .. } if id == proc_id => {
+                    stdout.extend(data)
+                }
+                DistantResponseData::ProcDone { id, success, .. } if id == proc_id => {
+                    assert!(success, "Process should have completed successfully");
+                    break;
+                }
+                x => panic!("Unexpected response: {:?}", x),
+            }
+        }
+
+        assert_eq!(String::from_utf8(stdout).unwrap(), "5\n64\n");
+    }
+
     // NOTE: Ignoring on windows because it's using WSL which wants a Linux path
     //       with / but thinks it's on windows and is providing \
     #[test(tokio::test)]
@@ -1947,6 +4121,12 @@ mod tests {
                 /* environment */ Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
             )
             .await
             .unwrap();
@@ -1965,7 +4145,7 @@ mod tests {
         let mut got_done = false;
 
         let mut check_data = |data: &DistantResponseData| match data {
-            DistantResponseData::ProcStderr { id, data } => {
+            DistantResponseData::ProcStderr { id, data, .. } => {
                 assert_eq!(
                     *id, proc_id,
                     "Got {}, but expected {} as process id",
@@ -2007,6 +4187,12 @@ mod tests {
                 /* environment */ Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
             )
             .await
             .unwrap();
@@ -2034,6 +4220,7 @@ mod tests {
                 connection_id: ctx_1.connection_id,
                 reply,
                 local_data: Arc::clone(&ctx_1.local_data),
+                deadline: None,
             };
             (ctx, rx)
         };
@@ -2046,6 +4233,12 @@ mod tests {
                 /* environment */ Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
             )
             .await
             .unwrap();
@@ -2064,30 +4257,11 @@ mod tests {
         }
     }
 
-    #[test(tokio::test)]
-    async fn proc_kill_should_fail_if_given_non_existent_process() {
-        let (api, ctx, _rx) = setup(1).await;
-
-        // Send kill to a non-existent process
-        let _ = api.proc_kill(ctx, 0xDEADBEEF).await.unwrap_err();
-    }
-
-    #[test(tokio::test)]
-    async fn proc_stdin_should_fail_if_given_non_existent_process() {
-        let (api, ctx, _rx) = setup(1).await;
-
-        // Send stdin to a non-existent process
-        let _ = api
-            .proc_stdin(ctx, 0xDEADBEEF, b"some input".to_vec())
-            .await
-            .unwrap_err();
-    }
-
     // NOTE: Ignoring on windows because it's using WSL which wants a Linux path
     //       with / but thinks it's on windows and is providing \
     #[test(tokio::test)]
     #[cfg_attr(windows, ignore)]
-    async fn proc_stdin_should_send_stdin_to_process() {
+    async fn proc_spawn_should_wait_on_output_acks_when_given_output_window() {
         let (api, ctx_1, mut rx) = setup(1).await;
         let (ctx_2, _rx) = {
             let (reply, rx) = make_reply(1);
@@ -2095,12 +4269,708 @@ mod tests {
                 connection_id: ctx_1.connection_id,
                 reply,
                 local_data: Arc::clone(&ctx_1.local_data),
+                deadline: None,
             };
             (ctx, rx)
         };
 
-        // First, run a program that listens for stdin
-        let id = api
+        let proc_id = api
+            .proc_spawn(
+                ctx_1,
+                /* cmd */
+                format!(
+                    "{} {} some stdout",
+                    *SCRIPT_RUNNER,
+                    ECHO_ARGS_TO_STDOUT_SH.to_str().unwrap()
+                ),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ Some(5),
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
+            )
+            .await
+            .unwrap();
+
+        // Only a window's worth of output is sent until it is acknowledged
+        match rx.recv().await.unwrap() {
+            DistantResponseData::ProcStdout { id, data, .. } => {
+                assert_eq!(id, proc_id);
+                assert_eq!(data, b"some ");
+            }
+            x => panic!("Unexpected response: {:?}", x),
+        }
+        tokio::time::timeout(Duration::from_millis(100), rx.recv())
+            .await
+            .expect_err("Unexpectedly got response before acknowledging output");
+
+        // Acknowledging more than was sent does not grow the window
+        api.proc_output_ack(ctx_2, proc_id, 10).await.unwrap();
+        match rx.recv().await.unwrap() {
+            DistantResponseData::ProcStdout { id, data, .. } => {
+                assert_eq!(id, proc_id);
+                assert_eq!(data, b"stdou");
+            }
+            x => panic!("Unexpected response: {:?}", x),
+        }
+
+        // The process exiting is not reported until all of its output is sent
+        tokio::time::timeout(Duration::from_millis(100), rx.recv())
+            .await
+            .expect_err("Unexpectedly got response before acknowledging output");
+
+        api.state.process.ack_output(proc_id, 5).await.unwrap();
+        match rx.recv().await.unwrap() {
+            DistantResponseData::ProcStdout { id, data, .. } => {
+                assert_eq!(id, proc_id);
+                assert_eq!(data, b"t");
+            }
+            x => panic!("Unexpected response: {:?}", x),
+        }
+        match rx.recv().await.unwrap() {
+            DistantResponseData::ProcDone { id, success, .. } => {
+                assert_eq!(id, proc_id);
+                assert!(success, "Process should have completed successfully");
+            }
+            x => panic!("Unexpected response: {:?}", x),
+        }
+    }
+
+    // NOTE: Ignoring on windows because it's using WSL which wants a Linux path
+    //       with / but thinks it's on windows and is providing \
+    #[test(tokio::test)]
+    #[cfg_attr(windows, ignore)]
+    async fn proc_spawn_should_stamp_stdout_and_stderr_in_order_read_if_timestamped() {
+        let (api, ctx, mut rx) = setup(100).await;
+
+        let proc_id = api
+            .proc_spawn(
+                ctx,
+                /* cmd */
+                format!(
+                    "{} {}",
+                    *SCRIPT_RUNNER,
+                    INTERLEAVE_STDOUT_AND_STDERR_SH.to_str().unwrap()
+                ),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ true,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
+            )
+            .await
+            .unwrap();
+
+        // Stdout and stderr are sent by separate tasks, so we go by their stamps for the order
+        let mut chunks = Vec::new();
+        loop {
+            match rx.recv().await.unwrap() {
+                DistantResponseData::ProcStdout { id, data, stamp }
+                | DistantResponseData::ProcStderr { id, data, stamp } => {
+                    assert_eq!(id, proc_id);
+                    chunks.push((stamp.expect("Output was not stamped"), data));
+                }
+                DistantResponseData::ProcDone { id, success, .. } => {
+                    assert_eq!(id, proc_id);
+                    assert!(success, "Process should have completed successfully");
+                    break;
+                }
+                x => panic!("Unexpected response: {:?}", x),
+            }
+        }
+        chunks.sort_by_key(|(stamp, _)| stamp.seq);
+
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|(stamp, _)| stamp.seq)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|(_, data)| data.as_slice())
+                .collect::<Vec<_>>(),
+            vec![b"a", b"b", b"c"]
+        );
+        assert!(
+            chunks
+                .windows(2)
+                .all(|pair| pair[0].0.timestamp < pair[1].0.timestamp),
+            "Timestamps out of order: {chunks:?}"
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn output_window_should_shrink_requested_window_to_max_output_window() {
+        let api = LocalDistantApi::initialize_with_limits(OutputLimits {
+            max_window: Some(16),
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(api.output_window(Some(1024)), Some(16));
+        assert_eq!(api.output_window(Some(8)), Some(8));
+        assert_eq!(api.output_window(None), None);
+
+        let api = LocalDistantApi::initialize().unwrap();
+        assert_eq!(api.output_window(Some(1024)), Some(1024));
+    }
+
+    // NOTE: Only on Linux as we check on the process started by the script using /proc
+    #[cfg(target_os = "linux")]
+    #[test(tokio::test)]
+    async fn proc_kill_should_also_kill_processes_started_by_process_if_killing_tree() {
+        let (api, ctx_1, mut rx) = setup(100).await;
+        let ctx_2 = DistantCtx {
+            connection_id: ctx_1.connection_id,
+            reply: make_reply(1).0,
+            local_data: Arc::clone(&ctx_1.local_data),
+            deadline: None,
+        };
+
+        let proc_id = api
+            .proc_spawn(
+                ctx_1,
+                /* cmd */
+                format!(
+                    "{} {} 30",
+                    *SCRIPT_RUNNER,
+                    SLEEP_IN_BACKGROUND_SH.to_str().unwrap()
+                ),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ true,
+                /* user */ None,
+            )
+            .await
+            .unwrap();
+
+        // The script reports the pid of the process it started in the background
+        let pid: u32 = match rx.recv().await.unwrap() {
+            DistantResponseData::ProcStdout { data, .. } => {
+                String::from_utf8(data).unwrap().trim().parse().unwrap()
+            }
+            x => panic!("Unexpected response: {:?}", x),
+        };
+
+        api.proc_kill(ctx_2, proc_id).await.unwrap();
+
+        // The process started by the script is gone (or left for its parent to clean up)
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !matches!(
+                crate::api::local::state::process_status(pid),
+                None | Some(crate::data::ProcStatus::Exited)
+            ) {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("Process started by the script is still running");
+    }
+
+    #[test(tokio::test)]
+    async fn proc_kill_should_fail_if_given_non_existent_process() {
+        let (api, ctx, _rx) = setup(1).await;
+
+        // Send kill to a non-existent process
+        let _ = api.proc_kill(ctx, 0xDEADBEEF).await.unwrap_err();
+    }
+
+    // NOTE: Ignoring on windows because it's using WSL which wants a Linux path
+    //       with / but thinks it's on windows and is providing \
+    #[test(tokio::test)]
+    #[cfg_attr(windows, ignore)]
+    async fn proc_list_should_include_running_spawned_processes() {
+        let (api, ctx_1, _rx) = setup(1).await;
+        let ctx_2 = DistantCtx {
+            connection_id: ctx_1.connection_id,
+            reply: make_reply(1).0,
+            local_data: Arc::clone(&ctx_1.local_data),
+            deadline: None,
+        };
+
+        let proc_id = api
+            .proc_spawn(
+                ctx_1,
+                /* cmd */
+                format!("{} {} 1", *SCRIPT_RUNNER, SLEEP_SH.to_str().unwrap()),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
+            )
+            .await
+            .unwrap();
+
+        let entries = api.proc_list(ctx_2, /* all */ false).await.unwrap();
+        assert_eq!(entries.len(), 1, "Unexpected entries: {entries:?}");
+        assert_eq!(entries[0].id, Some(proc_id));
+        assert!(
+            entries[0].cmd.contains(SLEEP_SH.to_str().unwrap()),
+            "Unexpected command: {}",
+            entries[0].cmd
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn proc_signal_should_fail_if_given_non_existent_process() {
+        let (api, ctx, _rx) = setup(1).await;
+
+        // Send signal to a non-existent process
+        let _ = api
+            .proc_signal(ctx, 0xDEADBEEF, Signal::Int)
+            .await
+            .unwrap_err();
+    }
+
+    #[test(tokio::test)]
+    async fn proc_stats_should_fail_if_given_non_existent_process() {
+        let (api, ctx, _rx) = setup(1).await;
+
+        let err = api.proc_stats(ctx, 0xDEADBEEF, None).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test(tokio::test)]
+    async fn proc_stats_should_report_stats_at_interval_until_process_exits() {
+        let (api, ctx_1, _rx) = setup(1).await;
+        let (reply, mut rx) = make_reply(10);
+        let ctx_2 = DistantCtx {
+            connection_id: ctx_1.connection_id,
+            reply,
+            local_data: Arc::clone(&ctx_1.local_data),
+            deadline: None,
+        };
+
+        let proc_id = api
+            .proc_spawn(
+                ctx_1,
+                /* cmd */
+                format!("{} {} 1", *SCRIPT_RUNNER, SLEEP_SH.to_str().unwrap()),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
+            )
+            .await
+            .unwrap();
+
+        let (stats_id, stats) = api
+            .proc_stats(ctx_2, proc_id, Some(Duration::from_millis(100)))
+            .await
+            .unwrap();
+        assert!(stats.memory > 0, "Missing memory: {stats:?}");
+
+        match rx.recv().await.unwrap() {
+            DistantResponseData::ProcStats {
+                id, stats_id: x, ..
+            } => {
+                assert_eq!(id, proc_id);
+                assert_eq!(x, stats_id);
+            }
+            x => panic!("Unexpected response: {:?}", x),
+        }
+
+        // Once the process exits, reporting stops and drops the reply, closing the channel
+        let wait_for_close = async { while rx.recv().await.is_some() {} };
+        tokio::time::timeout(Duration::from_secs(5), wait_for_close)
+            .await
+            .expect("Stats still being reported after process exited");
+    }
+
+    #[test(tokio::test)]
+    async fn system_kill_should_fail_unless_enabled_by_policy() {
+        let (api, ctx_1, _rx) = setup(1).await;
+        let ctx_2 = DistantCtx {
+            connection_id: ctx_1.connection_id,
+            reply: make_reply(1).0,
+            local_data: Arc::clone(&ctx_1.local_data),
+            deadline: None,
+        };
+
+        let capabilities = api.capabilities(ctx_1).await.unwrap();
+        assert!(!capabilities.contains(CapabilityKind::SystemKill));
+
+        let err = api
+            .system_kill(ctx_2, std::process::id(), Signal::Term)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    // NOTE: Ignoring on windows because only break signals can be sent there
+    #[test(tokio::test)]
+    #[cfg_attr(windows, ignore)]
+    async fn system_kill_should_signal_processes_not_spawned_by_server_if_enabled() {
+        let (api, ctx_1, _rx) = setup(1).await;
+        let ctx_2 = DistantCtx {
+            connection_id: ctx_1.connection_id,
+            reply: make_reply(1).0,
+            local_data: Arc::clone(&ctx_1.local_data),
+            deadline: None,
+        };
+        let api = api.with_process_policy(ProcessPolicy {
+            system_kill: true,
+            ..Default::default()
+        });
+
+        let capabilities = api.capabilities(ctx_1).await.unwrap();
+        assert!(capabilities.contains(CapabilityKind::SystemKill));
+
+        let mut child = std::process::Command::new("sleep")
+            .arg("10")
+            .spawn()
+            .unwrap();
+        api.system_kill(ctx_2, child.id(), Signal::Kill)
+            .await
+            .unwrap();
+        assert!(!child.wait().unwrap().success());
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn proc_spawn_detached_should_return_pid_that_can_be_signaled_via_system_kill() {
+        let (api, ctx_1, _rx) = setup(1).await;
+        let ctx_2 = DistantCtx {
+            connection_id: ctx_1.connection_id,
+            reply: make_reply(1).0,
+            local_data: Arc::clone(&ctx_1.local_data),
+            deadline: None,
+        };
+
+        let pid = api
+            .proc_spawn_detached(
+                ctx_1,
+                /* cmd */ String::from("sleep 10"),
+                /* environment */ Environment::new(),
+                /* current_dir */ None,
+                /* limits */ ProcLimits::default(),
+                /* user */ None,
+            )
+            .await
+            .unwrap();
+
+        // The process runs in a session of its own, apart from the server
+        let session = unsafe { libc::getsid(pid as libc::pid_t) };
+        assert_eq!(session, pid as libc::pid_t);
+        assert_ne!(session, unsafe { libc::getsid(0) });
+
+        // Signaling the process is permitted without the policy allowing system kills
+        assert!(api.state.detached.contains(pid));
+        api.system_kill(ctx_2, pid, Signal::Kill).await.unwrap();
+
+        let start = std::time::Instant::now();
+        while api.state.detached.contains(pid) {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "Detached process was not killed"
+            );
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn proc_attach_should_fail_if_given_non_existent_process() {
+        let (api, ctx, _rx) = setup(1).await;
+
+        // Attach to a non-existent process
+        let err = api
+            .proc_attach(ctx, 0xDEADBEEF, None, /* replay */ false)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    // NOTE: Ignoring on windows because it's using WSL which wants a Linux path
+    //       with / but thinks it's on windows and is providing \
+    #[test(tokio::test)]
+    #[cfg_attr(windows, ignore)]
+    async fn proc_attach_should_send_output_of_process_to_new_reply() {
+        let (api, ctx_1, mut rx_1) = setup(1).await;
+        let (ctx_2, mut rx_2) = {
+            let (reply, rx) = make_reply(1);
+            let ctx = DistantCtx {
+                connection_id: ctx_1.connection_id,
+                reply,
+                local_data: Arc::clone(&ctx_1.local_data),
+                deadline: None,
+            };
+            (ctx, rx)
+        };
+        let (ctx_3, _rx) = {
+            let (reply, rx) = make_reply(1);
+            let ctx = DistantCtx {
+                connection_id: ctx_1.connection_id,
+                reply,
+                local_data: Arc::clone(&ctx_1.local_data),
+                deadline: None,
+            };
+            (ctx, rx)
+        };
+
+        // First, run a program that listens for stdin
+        let id = api
+            .proc_spawn(
+                ctx_1,
+                /* cmd */
+                format!(
+                    "{} {}",
+                    *SCRIPT_RUNNER,
+                    ECHO_STDIN_TO_STDOUT_SH.to_str().unwrap()
+                ),
+                Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
+            )
+            .await
+            .unwrap();
+
+        // Second, attach to the process and send it stdin
+        api.proc_attach(ctx_2, id, None, /* replay */ false)
+            .await
+            .unwrap();
+        api.proc_stdin(ctx_3, id, b"hello world\n".to_vec())
+            .await
+            .unwrap();
+
+        // Third, check that stdout went to the reply of the attach rather than the spawn
+        match rx_2.recv().await.unwrap() {
+            DistantResponseData::ProcStdout { data, .. } => {
+                assert_eq!(data, b"hello world\n", "Mirrored data didn't match");
+            }
+            x => panic!("Unexpected response: {:?}", x),
+        }
+        assert!(
+            rx_1.try_recv().is_err(),
+            "Spawn reply unexpectedly got output"
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn proc_attach_should_find_persistent_process_by_session_after_client_is_gone() {
+        let (api, ctx_1, rx_1) = setup(1).await;
+        let (ctx_2, mut rx_2) = {
+            let (reply, rx) = make_reply(1);
+            let ctx = DistantCtx {
+                connection_id: ctx_1.connection_id,
+                reply,
+                local_data: Arc::clone(&ctx_1.local_data),
+                deadline: None,
+            };
+            (ctx, rx)
+        };
+        let (ctx_3, _rx) = {
+            let (reply, rx) = make_reply(1);
+            let ctx = DistantCtx {
+                connection_id: ctx_1.connection_id,
+                reply,
+                local_data: Arc::clone(&ctx_1.local_data),
+                deadline: None,
+            };
+            (ctx, rx)
+        };
+        let (ctx_4, _rx) = {
+            let (reply, rx) = make_reply(1);
+            let ctx = DistantCtx {
+                connection_id: ctx_1.connection_id,
+                reply,
+                local_data: Arc::clone(&ctx_1.local_data),
+                deadline: None,
+            };
+            (ctx, rx)
+        };
+
+        // First, run a program that listens for stdin with a session
+        let id = api
+            .proc_spawn(
+                ctx_1,
+                /* cmd */
+                format!(
+                    "{} {}",
+                    *SCRIPT_RUNNER,
+                    ECHO_STDIN_TO_STDOUT_SH.to_str().unwrap()
+                ),
+                Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ Some(1234),
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
+            )
+            .await
+            .unwrap();
+
+        // Second, have the client that spawned it go away and send output that it misses
+        drop(rx_1);
+        api.proc_stdin(ctx_3, id, b"missed\n".to_vec())
+            .await
+            .unwrap();
+
+        // Third, attach to the process by its session and send it more stdin
+        assert_eq!(
+            api.proc_attach(ctx_2, 0, Some(1234), /* replay */ false)
+                .await
+                .unwrap(),
+            id
+        );
+        api.proc_stdin(ctx_4, id, b"hello world\n".to_vec())
+            .await
+            .unwrap();
+
+        // Fourth, check that the process kept running, with output missed while detached dropped
+        let mut data = Vec::new();
+        while !data.ends_with(b"hello world\n") {
+            match rx_2.recv().await.unwrap() {
+                DistantResponseData::ProcStdout { data: x, .. } => data.extend(x),
+                x => panic!("Unexpected response: {:?}", x),
+            }
+        }
+        assert!(
+            data == b"hello world\n" || data == b"missed\nhello world\n",
+            "Unexpected output: {:?}",
+            String::from_utf8_lossy(&data)
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn proc_attach_should_replay_output_missed_while_no_client_was_attached_if_asked() {
+        let (api, ctx_1, rx_1) = setup(1).await;
+        let make_ctx = |ctx: &DistantCtx<()>| {
+            let (reply, rx) = make_reply(10);
+            let ctx = DistantCtx {
+                connection_id: ctx.connection_id,
+                reply,
+                local_data: Arc::clone(&ctx.local_data),
+                deadline: None,
+            };
+            (ctx, rx)
+        };
+        let (ctx_2, mut rx_2) = make_ctx(&ctx_1);
+        let (ctx_3, _rx) = make_ctx(&ctx_1);
+        let (ctx_4, _rx) = make_ctx(&ctx_1);
+
+        // First, run a program that listens for stdin with a session
+        let id = api
+            .proc_spawn(
+                ctx_1,
+                /* cmd */
+                format!(
+                    "{} {}",
+                    *SCRIPT_RUNNER,
+                    ECHO_STDIN_TO_STDOUT_SH.to_str().unwrap()
+                ),
+                Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ Some(1234),
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
+            )
+            .await
+            .unwrap();
+
+        // Second, have the client that spawned it go away and give it time to miss some output
+        drop(rx_1);
+        api.proc_stdin(ctx_3, id, b"missed\n".to_vec())
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(250)).await;
+
+        // Third, attach to the process asking for what was missed and send it more stdin
+        assert_eq!(
+            api.proc_attach(ctx_2, 0, Some(1234), /* replay */ true)
+                .await
+                .unwrap(),
+            id
+        );
+        api.proc_stdin(ctx_4, id, b"hello world\n".to_vec())
+            .await
+            .unwrap();
+
+        // Fourth, check that the missed output came first
+        let mut data = Vec::new();
+        while !data.ends_with(b"hello world\n") {
+            match rx_2.recv().await.unwrap() {
+                DistantResponseData::ProcStdout { data: x, .. } => data.extend(x),
+                x => panic!("Unexpected response: {:?}", x),
+            }
+        }
+        assert_eq!(
+            data,
+            b"missed\nhello world\n",
+            "Unexpected output: {:?}",
+            String::from_utf8_lossy(&data)
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn proc_stdin_should_fail_if_given_non_existent_process() {
+        let (api, ctx, _rx) = setup(1).await;
+
+        // Send stdin to a non-existent process
+        let _ = api
+            .proc_stdin(ctx, 0xDEADBEEF, b"some input".to_vec())
+            .await
+            .unwrap_err();
+    }
+
+    // NOTE: Ignoring on windows because it's using WSL which wants a Linux path
+    //       with / but thinks it's on windows and is providing \
+    #[test(tokio::test)]
+    #[cfg_attr(windows, ignore)]
+    async fn proc_stdin_should_send_stdin_to_process() {
+        let (api, ctx_1, mut rx) = setup(1).await;
+        let (ctx_2, _rx) = {
+            let (reply, rx) = make_reply(1);
+            let ctx = DistantCtx {
+                connection_id: ctx_1.connection_id,
+                reply,
+                local_data: Arc::clone(&ctx_1.local_data),
+                deadline: None,
+            };
+            (ctx, rx)
+        };
+
+        // First, run a program that listens for stdin
+        let id = api
             .proc_spawn(
                 ctx_1,
                 /* cmd */
@@ -2112,6 +4982,12 @@ mod tests {
                 Environment::new(),
                 /* current_dir */ None,
                 /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
             )
             .await
             .unwrap();
@@ -2130,6 +5006,86 @@ mod tests {
         }
     }
 
+    #[test(tokio::test)]
+    async fn proc_stdin_close_should_deliver_eof_while_output_is_still_received() {
+        let (api, ctx_1, mut rx) = setup(100).await;
+        let make_ctx = || {
+            let (reply, _) = make_reply(1);
+            DistantCtx {
+                connection_id: ctx_1.connection_id,
+                reply,
+                local_data: Arc::clone(&ctx_1.local_data),
+                deadline: None,
+            }
+        };
+        let (ctx_2, ctx_3, ctx_4, ctx_5) = (make_ctx(), make_ctx(), make_ctx(), make_ctx());
+
+        // Run a program that mirrors stdin until it reaches EOF
+        let id = api
+            .proc_spawn(
+                ctx_1,
+                /* cmd */
+                format!(
+                    "{} {}",
+                    *SCRIPT_RUNNER,
+                    ECHO_STDIN_TO_STDOUT_SH.to_str().unwrap()
+                ),
+                Environment::new(),
+                /* current_dir */ None,
+                /* pty */ None,
+                /* output_window */ None,
+                /* timestamped */ false,
+                /* session */ None,
+                /* limits */ ProcLimits::default(),
+                /* kill_tree */ false,
+                /* user */ None,
+            )
+            .await
+            .unwrap();
+
+        api.proc_stdin(ctx_2, id, b"hello world\n".to_vec())
+            .await
+            .unwrap();
+        api.proc_stdin_close(ctx_3, id).await.unwrap();
+
+        // Closing stdin a second time fails as it is already closed
+        let err = api.proc_stdin_close(ctx_4, id).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other, "{err}");
+        api.proc_stdin(ctx_5, id, b"too late\n".to_vec())
+            .await
+            .unwrap_err();
+
+        match rx.recv().await.unwrap() {
+            DistantResponseData::ProcStdout { data, .. } => {
+                assert_eq!(data, b"hello world\n", "Mirrored data didn't match");
+            }
+            x => panic!("Unexpected response: {:?}", x),
+        }
+
+        // Reaching EOF ends the program on its own
+        match rx.recv().await.unwrap() {
+            DistantResponseData::ProcDone { success, .. } => assert!(success),
+            x => panic!("Unexpected response: {:?}", x),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn system_env_should_only_include_allowed_variables() {
+        let (api, ctx, _rx) = setup(1).await;
+
+        let env = api.system_env(ctx).await.unwrap();
+        assert!(env.contains_key("PATH"), "{env:?}");
+        assert!(env.keys().all(|name| is_env_allowed(name)), "{env:?}");
+    }
+
+    #[test]
+    fn is_env_allowed_should_match_names_and_prefixes() {
+        assert!(is_env_allowed("PATH"));
+        assert!(is_env_allowed("LC_ALL"));
+        assert!(!is_env_allowed("PATHS"));
+        assert!(!is_env_allowed("AWS_SECRET_ACCESS_KEY"));
+    }
+
     #[test(tokio::test)]
     async fn system_info_should_return_system_info_based_on_binary() {
         let (api, ctx, _rx) = setup(1).await;
@@ -2152,4 +5108,309 @@ mod tests {
             }
         );
     }
+
+    #[cfg(target_os = "linux")]
+    #[test(tokio::test)]
+    async fn system_stats_should_report_stats_at_interval_until_canceled() {
+        let (api, ctx, mut rx) = setup(1).await;
+        let connection_id = ctx.connection_id;
+        let local_data = Arc::clone(&ctx.local_data);
+
+        let (id, stats) = api
+            .system_stats(ctx, Some(Duration::from_millis(100)))
+            .await
+            .unwrap();
+        assert!(stats.cpu_count > 0, "Missing CPUs: {stats:?}");
+
+        match rx.recv().await.unwrap() {
+            DistantResponseData::SystemStats { id: stats_id, .. } => assert_eq!(stats_id, id),
+            x => panic!("Unexpected response: {:?}", x),
+        }
+
+        let (reply, _rx) = make_reply(1);
+        let ctx = DistantCtx {
+            connection_id,
+            reply,
+            local_data,
+            deadline: None,
+        };
+        api.cancel_system_stats(ctx, id).await.unwrap();
+
+        // Canceling drops the reply, so the channel closes after anything sent before canceling
+        let wait_for_close = async { while rx.recv().await.is_some() {} };
+        tokio::time::timeout(Duration::from_millis(300), wait_for_close)
+            .await
+            .expect("Stats still being reported after canceling");
+    }
+
+    #[test(tokio::test)]
+    async fn tunnel_should_relay_data_until_both_sides_close() {
+        let (api, ctx, mut rx) = setup(100).await;
+        let connection_id = ctx.connection_id;
+        let local_data = Arc::clone(&ctx.local_data);
+        let make_ctx = || {
+            let (reply, _) = make_reply(1);
+            DistantCtx {
+                connection_id,
+                reply,
+                local_data: Arc::clone(&local_data),
+                deadline: None,
+            }
+        };
+
+        // Echo server that shouts back everything it reads until the tunnel closes for writing
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            use tokio::io::AsyncReadExt;
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut data = Vec::new();
+            stream.read_to_end(&mut data).await.unwrap();
+            stream.write_all(&data.to_ascii_uppercase()).await.unwrap();
+        });
+
+        let id = api
+            .tunnel_open(ctx, String::from("127.0.0.1"), port)
+            .await
+            .unwrap();
+        api.tunnel_write(make_ctx(), id, b"hello ".to_vec())
+            .await
+            .unwrap();
+        api.tunnel_write(make_ctx(), id, b"world".to_vec())
+            .await
+            .unwrap();
+        api.tunnel_close(make_ctx(), id).await.unwrap();
+
+        let mut data = Vec::new();
+        loop {
+            match rx.recv().await.unwrap() {
+                DistantResponseData::TunnelData {
+                    id: tunnel_id,
+                    data: x,
+                } => {
+                    assert_eq!(tunnel_id, id);
+                    data.extend(x);
+                }
+                DistantResponseData::TunnelClosed { id: tunnel_id } => {
+                    assert_eq!(tunnel_id, id);
+                    break;
+                }
+                x => panic!("Unexpected response: {:?}", x),
+            }
+        }
+        assert_eq!(data, b"HELLO WORLD");
+
+        // Once closed by both sides, the tunnel no longer accepts data
+        let result = api.tunnel_write(make_ctx(), id, b"more".to_vec()).await;
+        assert!(result.is_err(), "Tunnel accepted data after closing");
+    }
+
+    #[test(tokio::test)]
+    async fn tunnel_listen_should_announce_accepted_connections_as_tunnels() {
+        use tokio::io::AsyncReadExt;
+
+        let (api, ctx, mut rx) = setup(100).await;
+        let connection_id = ctx.connection_id;
+        let local_data = Arc::clone(&ctx.local_data);
+        let make_ctx = || {
+            let (reply, _) = make_reply(1);
+            DistantCtx {
+                connection_id,
+                reply,
+                local_data: Arc::clone(&local_data),
+                deadline: None,
+            }
+        };
+
+        let (listener, port) = api
+            .tunnel_listen(ctx, String::from("127.0.0.1"), 0)
+            .await
+            .unwrap();
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .unwrap();
+        stream.write_all(b"ping").await.unwrap();
+
+        let id = match rx.recv().await.unwrap() {
+            DistantResponseData::TunnelAccepted {
+                listener: listener_id,
+                id,
+                ..
+            } => {
+                assert_eq!(listener_id, listener);
+                id
+            }
+            x => panic!("Unexpected response: {:?}", x),
+        };
+        match rx.recv().await.unwrap() {
+            DistantResponseData::TunnelData {
+                id: tunnel_id,
+                data,
+            } => {
+                assert_eq!(tunnel_id, id);
+                assert_eq!(data, b"ping");
+            }
+            x => panic!("Unexpected response: {:?}", x),
+        }
+
+        api.tunnel_write(make_ctx(), id, b"pong".to_vec())
+            .await
+            .unwrap();
+        api.tunnel_close(make_ctx(), id).await.unwrap();
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).await.unwrap();
+        assert_eq!(data, b"pong");
+
+        // No longer listening does not affect tunnels already accepted, but refuses new ones
+        api.tunnel_unlisten(make_ctx(), listener).await.unwrap();
+        tokio::task::yield_now().await;
+        assert!(tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_err());
+    }
+
+    #[test(tokio::test)]
+    async fn tunnel_listen_should_stop_once_connection_is_gone() {
+        let (api, ctx, rx) = setup(100).await;
+
+        let (_, port) = api
+            .tunnel_listen(ctx, String::from("127.0.0.1"), 0)
+            .await
+            .unwrap();
+        drop(rx);
+
+        // Wait for the listener to notice that there is no longer anything to announce to
+        let mut refused = false;
+        for _ in 0..50 {
+            if tokio::net::TcpStream::connect(("127.0.0.1", port))
+                .await
+                .is_err()
+            {
+                refused = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(refused, "Listener still accepting connections");
+    }
+
+    #[test(tokio::test)]
+    async fn tunnel_open_should_close_once_connection_is_gone_even_if_other_end_is_silent() {
+        let (api, ctx, rx) = setup(100).await;
+
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+        api.tunnel_open(ctx, String::from("127.0.0.1"), port)
+            .await
+            .unwrap();
+        let (mut stream, _) = listener.accept().await.unwrap();
+        drop(rx);
+
+        // Nothing is ever sent by this end, so the tunnel only closes because the client is gone
+        let mut buf = Vec::new();
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut buf),
+        )
+        .await
+        .expect("Tunnel still open")
+        .unwrap();
+        assert!(buf.is_empty());
+    }
+
+    #[test(tokio::test)]
+    async fn tunnel_listen_should_fail_for_addresses_other_than_loopback_unless_allowed() {
+        let (api, ctx, _rx) = setup(100).await;
+        let connection_id = ctx.connection_id;
+
+        let err = api
+            .tunnel_listen(ctx, String::from("0.0.0.0"), 0)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+        let api = api.with_remote_tunnels(true);
+        let (reply, _rx) = make_reply(100);
+        let ctx = DistantCtx {
+            connection_id,
+            reply,
+            local_data: Arc::new(()),
+            deadline: None,
+        };
+        api.tunnel_listen(ctx, String::from("0.0.0.0"), 0)
+            .await
+            .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn tunnel_listen_unix_should_remove_private_socket_once_no_longer_listening() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (api, ctx, mut rx) = setup(100).await;
+        let connection_id = ctx.connection_id;
+        let local_data = Arc::clone(&ctx.local_data);
+        let make_ctx = || {
+            let (reply, _) = make_reply(1);
+            DistantCtx {
+                connection_id,
+                reply,
+                local_data: Arc::clone(&local_data),
+                deadline: None,
+            }
+        };
+
+        let (listener, path) = api.tunnel_listen_unix(ctx).await.unwrap();
+        let dir = path.parent().unwrap().to_path_buf();
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700, "Socket directory is not private");
+
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream.write_all(b"ping").await.unwrap();
+        match rx.recv().await.unwrap() {
+            DistantResponseData::TunnelAccepted {
+                listener: listener_id,
+                ..
+            } => assert_eq!(listener_id, listener),
+            x => panic!("Unexpected response: {:?}", x),
+        }
+
+        api.tunnel_unlisten(make_ctx(), listener).await.unwrap();
+        tokio::task::yield_now().await;
+        assert!(!dir.exists(), "Socket directory was not removed");
+    }
+
+    #[test(tokio::test)]
+    async fn extension_should_be_handled_by_hooks() {
+        let (api, ctx, _rx) = setup(1).await;
+        let api = api.with_hooks(Arc::new(TestHooks::default()));
+
+        let payload = serde_json::json!({ "key": "value" });
+        assert_eq!(
+            api.extension(ctx, String::from("x-echo"), payload.clone())
+                .await
+                .unwrap(),
+            payload
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn extension_should_fail_if_no_hooks_handle_it() {
+        let (api, ctx, _rx) = setup(1).await;
+        let api = api.with_hooks(Arc::new(TestHooks::default()));
+        let err = api
+            .extension(ctx, String::from("x-missing"), serde_json::Value::Null)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+        let (api, ctx, _rx) = setup(1).await;
+        let err = api
+            .extension(ctx, String::from("x-echo"), serde_json::Value::Null)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
 }
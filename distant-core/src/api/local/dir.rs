@@ -0,0 +1,214 @@
+use crate::data::{DirEntry, DirEntrySort, DirReadOptions, DistantResponseData, Error, FileType};
+use distant_net::server::Reply;
+use log::*;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+use tokio::sync::mpsc;
+use walkdir::WalkDir;
+
+/// Maximum entries read ahead of those sent when reading a directory in chunks
+const MAX_READ_AHEAD: usize = 1024;
+
+/// Reading of a directory, whose root has already been canonicalized
+pub struct ReadDir {
+    pub root: PathBuf,
+    pub depth: usize,
+    pub absolute: bool,
+    pub canonicalize: bool,
+    pub include_root: bool,
+    pub options: DirReadOptions,
+}
+
+impl ReadDir {
+    /// Walks the directory, calling `f` with each entry (or error) as it is found until there
+    /// are none left or `f` returns false
+    pub fn walk(self, mut f: impl FnMut(io::Result<DirEntry>) -> bool) {
+        let Self {
+            root,
+            depth,
+            absolute,
+            canonicalize,
+            include_root,
+            options,
+        } = self;
+
+        // Traverse, but don't include root directory in entries (hence min depth 1), unless indicated
+        // to do so (min depth 0)
+        let sort = options.sort;
+        let reverse = options.reverse;
+        let dir = WalkDir::new(root.as_path())
+            .min_depth(if include_root { 0 } else { 1 })
+            .sort_by(move |a, b| {
+                let ordering = match sort {
+                    DirEntrySort::Name => a.file_name().cmp(b.file_name()),
+                    DirEntrySort::Size => {
+                        let size = |e: &walkdir::DirEntry| e.metadata().map(|m| m.len()).ok();
+                        size(a).cmp(&size(b))
+                    }
+                    DirEntrySort::Modified => {
+                        let modified = |e: &walkdir::DirEntry| {
+                            e.metadata().ok().and_then(|m| m.modified().ok())
+                        };
+                        modified(a).cmp(&modified(b))
+                    }
+                }
+                .then_with(|| a.file_name().cmp(b.file_name()));
+
+                if reverse {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+
+        // If depth > 0, will recursively traverse to specified max depth, otherwise
+        // performs infinite traversal
+        let dir = if depth > 0 { dir.max_depth(depth) } else { dir };
+
+        // Skip hidden entries (and their children) if indicated, but never the root itself
+        let exclude_hidden = options.exclude_hidden;
+        let dir = dir.into_iter().filter_entry(move |e| {
+            !exclude_hidden || e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.')
+        });
+
+        for entry in dir {
+            let entry = match entry.map_err(io::Error::from) {
+                // For entries within the root, we want to transform the path based on flags
+                Ok(e) if e.depth() > 0 => {
+                    // Skip entries that are not of an allowed type, noting that we still
+                    // traverse into directories that are filtered out
+                    let file_type = FileType::from(e.file_type());
+                    if !options.allowed_file_types.is_empty()
+                        && !options.allowed_file_types.contains(&file_type)
+                    {
+                        continue;
+                    }
+
+                    // Canonicalize the path if specified, otherwise just return
+                    // the path as is
+                    let path = if canonicalize {
+                        std::fs::canonicalize(e.path())
+                    } else {
+                        Ok(e.path().to_path_buf())
+                    };
+
+                    // Strip the path of its prefix based if not flagged as absolute
+                    //
+                    // NOTE: In the situation where we canonicalized the path earlier, there is no
+                    //       guarantee that our root path is still the parent of the symlink's
+                    //       destination; so, in that case we MUST just return the path if the
+                    //       strip_prefix fails
+                    path.map(|path| DirEntry {
+                        path: if absolute {
+                            path
+                        } else {
+                            path.strip_prefix(root.as_path())
+                                .map(Path::to_path_buf)
+                                .unwrap_or(path)
+                        },
+                        file_type,
+                        depth: e.depth(),
+                    })
+                }
+
+                // For the root, we just want to echo back the entry as is
+                Ok(e) => Ok(DirEntry {
+                    path: e.path().to_path_buf(),
+                    file_type: FileType::from(e.file_type()),
+                    depth: e.depth(),
+                }),
+
+                Err(x) => Err(x),
+            };
+
+            if !f(entry) {
+                break;
+            }
+        }
+    }
+
+    /// Reads every entry of the directory, returning them along with any errors encountered
+    pub async fn read(self) -> io::Result<(Vec<DirEntry>, Vec<io::Error>)> {
+        tokio::task::spawn_blocking(move || {
+            let mut entries = Vec::new();
+            let mut errors = Vec::new();
+            self.walk(|entry| {
+                match entry {
+                    Ok(entry) => entries.push(entry),
+                    Err(x) => errors.push(x),
+                }
+                true
+            });
+            (entries, errors)
+        })
+        .await
+        .map_err(|x| io::Error::new(io::ErrorKind::Other, x))
+    }
+
+    /// Reads the directory in the background, returning the first chunk of at most `size`
+    /// entries once read and sending the rest through `reply` as `dir_entries_chunk` responses as
+    /// they are read, where any errors are included with the last chunk, which is also the only
+    /// chunk marked as done (as noted by the returned flag for the first chunk)
+    pub async fn read_chunked(
+        self,
+        size: usize,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) -> (Vec<DirEntry>, Vec<io::Error>, bool) {
+        // Reading stops once nothing is receiving the entries, such as when a chunk cannot be sent
+        let (tx, mut rx) = mpsc::channel(size.clamp(1, MAX_READ_AHEAD));
+        tokio::task::spawn_blocking(move || self.walk(|entry| tx.blocking_send(entry).is_ok()));
+
+        let mut errors = Vec::new();
+        let (first, done) = next_chunk(&mut rx, size, &mut errors).await;
+        if done {
+            return (first, errors, true);
+        }
+
+        tokio::spawn(async move {
+            loop {
+                let (entries, done) = next_chunk(&mut rx, size, &mut errors).await;
+                let response = DistantResponseData::DirEntriesChunk {
+                    entries,
+                    errors: if done {
+                        errors.drain(..).map(Error::from).collect()
+                    } else {
+                        Vec::new()
+                    },
+                    done,
+                };
+
+                if let Err(x) = reply.send(response).await {
+                    error!("Failed to send directory entries chunk: {}", x);
+                    break;
+                }
+
+                if done {
+                    break;
+                }
+            }
+        });
+
+        (first, Vec::new(), false)
+    }
+}
+
+/// Receives up to `size` entries from `rx`, collecting any errors into `errors`, returning true
+/// alongside the entries once there are none left to receive
+async fn next_chunk(
+    rx: &mut mpsc::Receiver<io::Result<DirEntry>>,
+    size: usize,
+    errors: &mut Vec<io::Error>,
+) -> (Vec<DirEntry>, bool) {
+    let mut entries = Vec::new();
+    while entries.len() < size {
+        match rx.recv().await {
+            Some(Ok(entry)) => entries.push(entry),
+            Some(Err(x)) => errors.push(x),
+            None => return (entries, true),
+        }
+    }
+
+    (entries, false)
+}
@@ -0,0 +1,31 @@
+use crate::data::ProcessId;
+use serde_json::Value;
+use std::{io, path::Path};
+
+/// Callbacks invoked by [`LocalDistantApi`](super::LocalDistantApi) as it handles requests,
+/// letting a server apply site-specific policy such as rejecting writes or tagging processes
+///
+/// Every callback does nothing by default, and is invoked on a thread where blocking is allowed,
+/// such as to run a process.
+pub trait ServerHooks: Send + Sync {
+    /// Invoked before `data` is written or appended to the file at `path`, or before anything
+    /// else is put at `path` (such as by copying, renaming, or making a directory) in which case
+    /// `data` is none, where returning an error rejects the write and is sent back to the client
+    /// instead
+    fn before_write(&self, path: &Path, data: Option<&[u8]>) -> io::Result<()> {
+        let _ = (path, data);
+        Ok(())
+    }
+
+    /// Invoked after the process with `id` is spawned by running `cmd` within `current_dir`
+    fn after_spawn(&self, id: ProcessId, cmd: &str, current_dir: Option<&Path>) {
+        let _ = (id, cmd, current_dir);
+    }
+
+    /// Invoked to handle a custom request called `name`, returning none if no handler is
+    /// registered for it
+    fn handle_request(&self, name: &str, payload: Value) -> Option<io::Result<Value>> {
+        let _ = (name, payload);
+        None
+    }
+}
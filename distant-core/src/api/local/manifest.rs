@@ -0,0 +1,143 @@
+use crate::data::{DistantResponseData, Error, HashAlgorithm, ManifestEntry};
+use distant_net::server::Reply;
+use futures::stream::{self, StreamExt};
+use log::*;
+use std::{
+    cmp,
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tokio::sync::mpsc;
+use walkdir::WalkDir;
+
+/// Maximum files hashed at the same time for a manifest
+const MAXIMUM_MANIFEST_THREADS: usize = 8;
+
+/// Maximum files sent within each chunk of a manifest
+const MANIFEST_CHUNK_SIZE: usize = 100;
+
+/// File found under the directory of a manifest, waiting on its content to be hashed
+struct ManifestFile {
+    path: PathBuf,
+    relative: PathBuf,
+    size: u64,
+    modified: Option<u128>,
+}
+
+impl ManifestFile {
+    fn from_entry(root: &Path, entry: walkdir::DirEntry) -> io::Result<Self> {
+        let metadata = entry.metadata()?;
+        Ok(Self {
+            relative: entry
+                .path()
+                .strip_prefix(root)
+                .unwrap_or(entry.path())
+                .to_path_buf(),
+            path: entry.into_path(),
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis()),
+        })
+    }
+
+    fn hash(self, algorithm: HashAlgorithm) -> io::Result<ManifestEntry> {
+        let hash = algorithm.hash_reader(&mut File::open(&self.path)?)?;
+        Ok(ManifestEntry {
+            path: self.relative,
+            size: self.size,
+            modified: self.modified,
+            hash,
+        })
+    }
+}
+
+/// Lists the files under the directory at `root` in the background, hashing several of them at
+/// a time and sending them through `reply` in chunks ordered by path, where any errors are
+/// included with the last chunk, which is also the only chunk marked as done
+pub fn spawn(
+    root: PathBuf,
+    algorithm: HashAlgorithm,
+    reply: Box<dyn Reply<Data = DistantResponseData>>,
+) {
+    tokio::spawn(async move {
+        let threads = cmp::min(MAXIMUM_MANIFEST_THREADS, num_cpus::get());
+        let (tx, rx) = mpsc::channel(threads);
+        tokio::task::spawn_blocking(move || walk(&root, &tx));
+
+        // Hashing happens on blocking threads, where files are still reported in the order that
+        // they were found regardless of which finishes hashing first
+        let entries = stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|file| (file, rx))
+        })
+        .map(|file: io::Result<ManifestFile>| async move {
+            let file = file?;
+            tokio::task::spawn_blocking(move || file.hash(algorithm))
+                .await
+                .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?
+        })
+        .buffered(threads);
+        tokio::pin!(entries);
+
+        let mut chunk = Vec::new();
+        let mut errors = Vec::new();
+        loop {
+            let done = match entries.next().await {
+                Some(Ok(entry)) => {
+                    chunk.push(entry);
+                    false
+                }
+                Some(Err(x)) => {
+                    errors.push(Error::from(x));
+                    false
+                }
+                None => true,
+            };
+
+            if done || chunk.len() >= MANIFEST_CHUNK_SIZE {
+                let response = DistantResponseData::DirManifestChunk {
+                    entries: std::mem::take(&mut chunk),
+                    errors: if done {
+                        std::mem::take(&mut errors)
+                    } else {
+                        Vec::new()
+                    },
+                    done,
+                };
+
+                if let Err(x) = reply.send(response).await {
+                    error!("Failed to send manifest chunk: {}", x);
+                    break;
+                }
+            }
+
+            if done {
+                break;
+            }
+        }
+    });
+}
+
+/// Walks the directory at `root` in order of file name, sending each regular file found (without
+/// following symlinks) through `tx` until there are none left or nothing is receiving them
+fn walk(root: &Path, tx: &mpsc::Sender<io::Result<ManifestFile>>) {
+    for entry in WalkDir::new(root)
+        .min_depth(1)
+        .follow_links(false)
+        .sort_by_file_name()
+    {
+        let file = match entry {
+            Ok(entry) if !entry.file_type().is_file() => continue,
+            Ok(entry) => ManifestFile::from_entry(root, entry),
+            Err(x) => Err(io::Error::from(x)),
+        };
+
+        if tx.blocking_send(file).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::{io, path::Path};
+use tokio::fs::{self, File, OpenOptions};
+
+/// Permissions given to files and directories created on behalf of clients that do not ask for
+/// permissions of their own, in place of those left by the umask of the server
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DefaultModes {
+    /// Permissions (such as `0o644`) of created files
+    pub file: Option<u32>,
+
+    /// Permissions (such as `0o755`) of created directories
+    pub dir: Option<u32>,
+}
+
+/// Opens the file at `path` for writing (appending if `append`, otherwise truncating it), creating
+/// it with the permissions of `mode` if it does not exist
+///
+/// On platforms other than Unix, `mode` is ignored.
+pub async fn open_file(path: &Path, append: bool, mode: Option<u32>) -> io::Result<File> {
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::{fs::Permissions, os::unix::fs::PermissionsExt};
+
+        // Only a file that we create ourselves is given the mode, leaving existing files as is
+        match OpenOptions::new()
+            .write(true)
+            .append(append)
+            .create_new(true)
+            .mode(mode)
+            .open(path)
+            .await
+        {
+            Ok(file) => {
+                // NOTE: The mode given when opening is masked by the umask, so set it explicitly
+                file.set_permissions(Permissions::from_mode(mode)).await?;
+                return Ok(file);
+            }
+            Err(x) if x.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(x) => return Err(x),
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    OpenOptions::new()
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .create(true)
+        .open(path)
+        .await
+}
+
+/// Creates the directory at `path` (along with its missing parents if `all`), giving each
+/// directory that is created the permissions of `mode`
+///
+/// On platforms other than Unix, `mode` is ignored.
+pub async fn create_dir(path: &Path, all: bool, mode: Option<u32>) -> io::Result<()> {
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::{fs::Permissions, os::unix::fs::PermissionsExt};
+
+        // Find the directories that do not exist yet, which are the ones given the mode
+        let mut missing = Vec::new();
+        if all {
+            for ancestor in path.ancestors() {
+                if ancestor.as_os_str().is_empty() || fs::symlink_metadata(ancestor).await.is_ok() {
+                    break;
+                }
+                missing.push(ancestor);
+            }
+        } else {
+            missing.push(path);
+        }
+
+        fs::DirBuilder::new()
+            .recursive(all)
+            .mode(mode)
+            .create(path)
+            .await?;
+
+        // NOTE: The mode given when creating is masked by the umask, so set it explicitly
+        for dir in missing.into_iter().rev() {
+            fs::set_permissions(dir, Permissions::from_mode(mode)).await?;
+        }
+
+        return Ok(());
+    }
+
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    if all {
+        fs::create_dir_all(path).await
+    } else {
+        fs::create_dir(path).await
+    }
+}
+
+/// Creates a named pipe (FIFO) at `path` with the permissions of `mode`, or those of `0o666` less
+/// the umask of the server if not provided
+///
+/// Named pipes are only supported on Unix.
+pub async fn create_fifo(path: &Path, mode: Option<u32>) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::{ffi::CString, fs::Permissions, os::unix::prelude::*};
+
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
+        let c_mode = mode.unwrap_or(0o666) as libc::mode_t;
+
+        // NOTE: Creating a fifo never blocks, unlike opening one
+
+        // SAFETY: The path is a nul-terminated string that lives until the call finishes
+        if unsafe { libc::mkfifo(c_path.as_ptr(), c_mode) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // NOTE: The mode given when creating is masked by the umask, so set it explicitly
+        if let Some(mode) = mode {
+            fs::set_permissions(path, Permissions::from_mode(mode)).await?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (path, mode);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Named pipes are only supported on Unix",
+        ))
+    }
+}
@@ -2,12 +2,41 @@ use crate::data::{ProcessId, PtySize};
 use std::{future::Future, pin::Pin};
 use tokio::{io, sync::mpsc};
 
+mod buffered;
+pub use buffered::*;
+
+#[cfg(unix)]
+mod child;
+#[cfg(unix)]
+use child::*;
+
+mod detached;
+pub use detached::*;
+
+mod limits;
+pub use limits::*;
+
+mod policy;
+pub use policy::*;
+
 mod pty;
 pub use pty::*;
 
+mod scope;
+pub use scope::*;
+
+mod signal;
+pub use signal::*;
+
 mod simple;
 pub use simple::*;
 
+mod tree;
+pub use tree::*;
+
+mod user;
+pub use user::*;
+
 mod wait;
 pub use wait::{ExitStatus, WaitRx, WaitTx};
 
@@ -19,6 +48,9 @@ pub trait Process: ProcessKiller + ProcessPty {
     /// Represents the id of the process
     fn id(&self) -> ProcessId;
 
+    /// Returns the id given to the process by the operating system, if known
+    fn pid(&self) -> Option<u32>;
+
     /// Waits for the process to exit, returning the exit status
     ///
     /// If the process has already exited, the status is returned immediately.
@@ -0,0 +1,424 @@
+use super::{FutureReturn, OutputChannel};
+use crate::constants::{MAX_PIPE_CHUNK_SIZE, MAX_SPILL_FILES, MAX_SPILL_FILE_SIZE};
+use log::*;
+use std::{
+    collections::VecDeque,
+    io::{self, SeekFrom},
+    path::PathBuf,
+    sync::Arc,
+};
+use tokio::{
+    fs::OpenOptions,
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+    sync::{Mutex, Notify},
+    task::JoinHandle,
+};
+
+/// Limits on the output of a spawned process that the server holds onto while waiting to send
+/// it, so that a chatty process cannot exhaust the memory of the server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputLimits {
+    /// Maximum bytes of output held in memory for each of stdout and stderr, beyond which older
+    /// output is spilled to disk. If none, output is not buffered at all and the process is
+    /// instead held back until its output can be sent
+    pub max_memory: Option<usize>,
+
+    /// Directory where spilled output is written
+    pub spill_dir: PathBuf,
+
+    /// Maximum size (in bytes) of a file of spilled output before another file is started
+    pub max_spill_file_size: u64,
+
+    /// Maximum files of spilled output kept for each of stdout and stderr, beyond which the
+    /// oldest file is deleted along with the output within it
+    pub max_spill_files: usize,
+
+    /// Largest output window that a process can be spawned with, where larger windows asked for
+    /// by clients are shrunk to it. If none, processes are given the windows asked for
+    pub max_window: Option<usize>,
+}
+
+impl Default for OutputLimits {
+    fn default() -> Self {
+        Self {
+            max_memory: None,
+            spill_dir: std::env::temp_dir(),
+            max_spill_file_size: MAX_SPILL_FILE_SIZE,
+            max_spill_files: MAX_SPILL_FILES,
+            max_window: None,
+        }
+    }
+}
+
+/// Output channel that eagerly reads from another channel so that the process is never held
+/// back, keeping the most recent output in memory and spilling older output to disk
+pub struct BufferedOutputChannel {
+    buffer: Arc<OutputBuffer>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for BufferedOutputChannel {
+    /// Stops reading from the underlying channel when dropped
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl BufferedOutputChannel {
+    /// Wraps `channel`, spawning a task to read everything from it into a buffer bound by `limits`
+    pub fn new(mut channel: Box<dyn OutputChannel>, limits: OutputLimits) -> Self {
+        let buffer = Arc::new(OutputBuffer::new(limits));
+        let task = tokio::spawn({
+            let buffer = Arc::clone(&buffer);
+            async move {
+                loop {
+                    match channel.recv().await {
+                        Ok(Some(data)) => {
+                            if let Err(x) = buffer.push(data).await {
+                                buffer.close(Some(x)).await;
+                                break;
+                            }
+                        }
+                        Ok(None) => {
+                            buffer.close(None).await;
+                            break;
+                        }
+                        Err(x) => {
+                            buffer.close(Some(x)).await;
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        Self { buffer, task }
+    }
+}
+
+impl OutputChannel for BufferedOutputChannel {
+    fn recv(&mut self) -> FutureReturn<'_, io::Result<Option<Vec<u8>>>> {
+        Box::pin(self.buffer.recv())
+    }
+}
+
+struct OutputBuffer {
+    inner: Mutex<Inner>,
+    notify: Notify,
+}
+
+struct Inner {
+    limits: OutputLimits,
+
+    /// Output held in memory, which is always more recent than any spilled output
+    memory: VecDeque<Vec<u8>>,
+    memory_len: usize,
+
+    /// Files of spilled output, oldest first
+    spills: VecDeque<SpillFile>,
+    next_spill_id: usize,
+
+    /// Total bytes of output discarded to stay within limits
+    dropped: u64,
+
+    /// Set once the underlying channel has no more output, along with the error that stopped it
+    closed: Option<Option<io::Error>>,
+}
+
+impl OutputBuffer {
+    fn new(limits: OutputLimits) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                limits,
+                memory: VecDeque::new(),
+                memory_len: 0,
+                spills: VecDeque::new(),
+                next_spill_id: 0,
+                dropped: 0,
+                closed: None,
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    async fn push(&self, data: Vec<u8>) -> io::Result<()> {
+        let mut inner = self.inner.lock().await;
+        inner.memory_len += data.len();
+        inner.memory.push_back(data);
+
+        // Move the oldest output to disk until we are back within our memory limit
+        let max_memory = inner.limits.max_memory.unwrap_or_default();
+        while inner.memory_len > max_memory {
+            let Some(data) = inner.memory.pop_front() else {
+                break;
+            };
+            inner.memory_len -= data.len();
+            inner.spill(data).await?;
+        }
+
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    async fn close(&self, error: Option<io::Error>) {
+        self.inner.lock().await.closed = Some(error);
+        self.notify.notify_one();
+    }
+
+    /// Waits for the next output, oldest first, returning none once the buffer is drained and
+    /// the underlying channel is closed
+    async fn recv(&self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            {
+                let mut inner = self.inner.lock().await;
+                if let Some(data) = inner.pop().await? {
+                    return Ok(Some(data));
+                }
+
+                if let Some(error) = inner.closed.as_mut() {
+                    return match error.take() {
+                        Some(x) => Err(x),
+                        None => Ok(None),
+                    };
+                }
+            }
+
+            self.notify.notified().await;
+        }
+    }
+}
+
+impl Inner {
+    /// Writes `data` to the newest spill file, rotating out the oldest file if we have too many
+    async fn spill(&mut self, data: Vec<u8>) -> io::Result<()> {
+        if self.limits.max_spill_files == 0 {
+            self.drop_output(data.len() as u64);
+            return Ok(());
+        }
+
+        let needs_file = match self.spills.back() {
+            Some(file) => file.len >= self.limits.max_spill_file_size,
+            None => true,
+        };
+        if needs_file {
+            let path = self.limits.spill_dir.join(format!(
+                "distant-output-{}-{}-{}",
+                std::process::id(),
+                rand::random::<u32>(),
+                self.next_spill_id,
+            ));
+            self.next_spill_id += 1;
+
+            // The file is created here so that it cannot already exist (such as a symlink placed
+            // by another user of a shared directory), and only we can read the output within
+            let mut options = OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            options.mode(0o600);
+            options.open(&path).await?;
+
+            self.spills.push_back(SpillFile {
+                path,
+                len: 0,
+                pos: 0,
+            });
+
+            if self.spills.len() > self.limits.max_spill_files {
+                if let Some(file) = self.spills.pop_front() {
+                    self.drop_output(file.len - file.pos);
+                }
+            }
+        }
+
+        let file = self.spills.back_mut().unwrap();
+        OpenOptions::new()
+            .append(true)
+            .open(&file.path)
+            .await?
+            .write_all(&data)
+            .await?;
+        file.len += data.len() as u64;
+
+        Ok(())
+    }
+
+    /// Removes the oldest output, reading from disk before memory
+    async fn pop(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if let Some(file) = self.spills.front_mut() {
+            let mut buf = vec![0; (file.len - file.pos).min(MAX_PIPE_CHUNK_SIZE as u64) as usize];
+            let mut f = tokio::fs::File::open(&file.path).await?;
+            f.seek(SeekFrom::Start(file.pos)).await?;
+            f.read_exact(&mut buf).await?;
+            file.pos += buf.len() as u64;
+
+            // Once fully read, the file is removed and the next spilled output starts a new one
+            if file.pos >= file.len {
+                self.spills.pop_front();
+            }
+
+            return Ok(Some(buf));
+        }
+
+        match self.memory.pop_front() {
+            Some(data) => {
+                self.memory_len -= data.len();
+                Ok(Some(data))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn drop_output(&mut self, len: u64) {
+        self.dropped += len;
+        warn!(
+            "Discarded {len} bytes of process output to stay within limits ({} bytes total)",
+            self.dropped
+        );
+    }
+}
+
+/// File holding output spilled to disk, where `pos` is how much of it has already been read
+struct SpillFile {
+    path: PathBuf,
+    len: u64,
+    pos: u64,
+}
+
+impl Drop for SpillFile {
+    /// Removes the file from disk when dropped
+    fn drop(&mut self) {
+        if let Err(x) = std::fs::remove_file(&self.path) {
+            if x.kind() != io::ErrorKind::NotFound {
+                error!("Failed to remove spilled output {:?}: {x}", self.path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+    use tokio::sync::mpsc;
+
+    fn limits(dir: &assert_fs::TempDir, max_memory: usize, max_files: usize) -> OutputLimits {
+        OutputLimits {
+            max_memory: Some(max_memory),
+            spill_dir: dir.path().to_path_buf(),
+            max_spill_file_size: 4,
+            max_spill_files: max_files,
+            max_window: None,
+        }
+    }
+
+    async fn recv_all(channel: &mut BufferedOutputChannel) -> Vec<u8> {
+        let mut out = Vec::new();
+        while let Some(data) = channel.recv().await.unwrap() {
+            out.extend(data);
+        }
+        out
+    }
+
+    /// Waits until everything has been read from the underlying channel into the buffer
+    async fn wait_until_closed(channel: &BufferedOutputChannel) {
+        while channel.buffer.inner.lock().await.closed.is_none() {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    fn spill_file_count(dir: &assert_fs::TempDir) -> usize {
+        std::fs::read_dir(dir.path()).unwrap().count()
+    }
+
+    #[test(tokio::test)]
+    async fn should_spill_older_output_to_disk_and_return_it_in_order() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let (tx, rx) = mpsc::channel(10);
+        let mut channel = BufferedOutputChannel::new(Box::new(rx), limits(&dir, 2, 10));
+
+        for data in [
+            b"ab".to_vec(),
+            b"cd".to_vec(),
+            b"ef".to_vec(),
+            b"gh".to_vec(),
+        ] {
+            tx.send(data).await.unwrap();
+        }
+        drop(tx);
+
+        wait_until_closed(&channel).await;
+        assert!(spill_file_count(&dir) > 0, "Expected output to be spilled");
+
+        assert_eq!(recv_all(&mut channel).await, b"abcdefgh");
+        assert_eq!(spill_file_count(&dir), 0, "Spill files not removed");
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn should_only_let_owner_read_spill_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = assert_fs::TempDir::new().unwrap();
+        let (tx, rx) = mpsc::channel(10);
+        let mut channel = BufferedOutputChannel::new(Box::new(rx), limits(&dir, 2, 10));
+
+        for data in [b"ab".to_vec(), b"cd".to_vec(), b"ef".to_vec()] {
+            tx.send(data).await.unwrap();
+        }
+        drop(tx);
+
+        wait_until_closed(&channel).await;
+        assert!(spill_file_count(&dir) > 0, "Expected output to be spilled");
+        for entry in std::fs::read_dir(dir.path()).unwrap() {
+            let mode = entry.unwrap().metadata().unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600, "Spill file readable by others");
+        }
+
+        assert_eq!(recv_all(&mut channel).await, b"abcdef");
+    }
+
+    #[test(tokio::test)]
+    async fn should_discard_oldest_spill_file_when_exceeding_max_files() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let (tx, rx) = mpsc::channel(10);
+        let mut channel = BufferedOutputChannel::new(Box::new(rx), limits(&dir, 2, 1));
+
+        for data in [b"abcd".to_vec(), b"ef".to_vec(), b"gh".to_vec()] {
+            tx.send(data).await.unwrap();
+        }
+        drop(tx);
+
+        // Read everything into the buffer before draining it so that the oldest output is lost
+        wait_until_closed(&channel).await;
+
+        assert_eq!(recv_all(&mut channel).await, b"efgh");
+        assert_eq!(spill_file_count(&dir), 0, "Spill files not removed");
+    }
+
+    #[test(tokio::test)]
+    async fn should_yield_error_from_underlying_channel_once_drained() {
+        struct FailingChannel(bool);
+        impl OutputChannel for FailingChannel {
+            fn recv(&mut self) -> FutureReturn<'_, io::Result<Option<Vec<u8>>>> {
+                let first = !std::mem::replace(&mut self.0, true);
+                Box::pin(async move {
+                    if first {
+                        Ok(Some(b"data".to_vec()))
+                    } else {
+                        Err(io::Error::new(io::ErrorKind::Other, "failed"))
+                    }
+                })
+            }
+        }
+
+        let dir = assert_fs::TempDir::new().unwrap();
+        let mut channel =
+            BufferedOutputChannel::new(Box::new(FailingChannel(false)), limits(&dir, 16, 1));
+
+        assert_eq!(channel.recv().await.unwrap().unwrap(), b"data");
+        assert_eq!(
+            channel.recv().await.unwrap_err().kind(),
+            io::ErrorKind::Other
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use super::{PreparedLimits, SwitchUser};
+use std::io;
+use tokio::process::Command;
+
+/// Group that a process leads once spawned, separating it from that of the server
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Leads {
+    /// Process leads a process group of its own, so that it can be killed along with the
+    /// processes it starts
+    ProcessGroup,
+
+    /// Process leads a session of its own, detaching it from the terminal and process group of
+    /// the server
+    Session,
+}
+
+/// Arranges for the process spawned by `command` to lead its own group as given by `leads`, then
+/// apply `limits`, and finally switch to `user` (as dropping privileges could keep the limits from
+/// applying), all within the child before it runs the program
+pub fn prepare_child(
+    command: &mut Command,
+    leads: Option<Leads>,
+    limits: Option<PreparedLimits>,
+    user: Option<&SwitchUser>,
+) {
+    if leads.is_none() && limits.is_none() && user.is_none() {
+        return;
+    }
+
+    // Give the process the basics of a login environment for the user, which can still be
+    // overridden by the environment provided
+    if let Some(user) = user {
+        command
+            .env("HOME", user.home())
+            .env("USER", user.name())
+            .env("LOGNAME", user.name());
+    }
+
+    let user = user.cloned();
+
+    // SAFETY: The closure runs in the child between forking and running the program, where only
+    //         async-signal-safe functions can be called. Everything it uses was looked up and
+    //         allocated beforehand, so it only makes system calls (setsid, setpgid, setpriority,
+    //         sched_setaffinity, setrlimit, setgroups, setgid, and setuid) that neither allocate
+    //         nor take locks
+    unsafe {
+        command.pre_exec(move || {
+            let led = match leads {
+                Some(Leads::ProcessGroup) => libc::setpgid(0, 0),
+                Some(Leads::Session) => libc::setsid(),
+                None => 0,
+            };
+            if led == -1 {
+                return Err(io::Error::last_os_error());
+            }
+
+            if let Some(limits) = limits.as_ref() {
+                limits.apply()?;
+            }
+
+            if let Some(user) = user.as_ref() {
+                user.apply()?;
+            }
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,50 @@
+use super::PreparedLimits;
+#[cfg(unix)]
+use super::{prepare_child, Leads, SwitchUser};
+use crate::data::{Environment, ProcLimits};
+use std::{ffi::OsStr, io, path::PathBuf, process::Stdio};
+use tokio::process::{Child, Command};
+
+/// Process creation flags that start the process without a console and in a new console process
+/// group, so that it is not tied to the console of the server
+#[cfg(windows)]
+const DETACHED_CREATION_FLAGS: u32 = 0x0000_0008 | 0x0000_0200;
+
+/// Spawns a process that is detached from the server, having no stdin, stdout, or stderr and
+/// (on unix) a session of its own, so that it keeps running after the server exits, including
+/// when the server is stopped by a signal sent to its process group or terminal
+pub fn spawn_detached<S, I, S2>(
+    program: S,
+    args: I,
+    environment: Environment,
+    current_dir: Option<PathBuf>,
+    limits: &ProcLimits,
+    #[cfg(unix)] user: Option<&SwitchUser>,
+) -> io::Result<Child>
+where
+    S: AsRef<OsStr>,
+    I: IntoIterator<Item = S2>,
+    S2: AsRef<OsStr>,
+{
+    let limits = PreparedLimits::prepare(limits)?;
+    let mut command = Command::new(program);
+
+    // Leading a session of its own detaches the process from the server
+    #[cfg(unix)]
+    prepare_child(&mut command, Some(Leads::Session), limits, user);
+
+    if let Some(path) = current_dir {
+        command.current_dir(path);
+    }
+
+    #[cfg(windows)]
+    command.creation_flags(DETACHED_CREATION_FLAGS);
+
+    command
+        .envs(environment)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+}
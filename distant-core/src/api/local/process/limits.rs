@@ -0,0 +1,174 @@
+use crate::data::ProcLimits;
+use std::io;
+
+/// Priority and limits on resources of a process, checked ahead of spawning it so that applying
+/// them within the process before it runs only involves system calls
+#[derive(Clone, Copy)]
+#[cfg_attr(windows, allow(dead_code))]
+pub struct PreparedLimits {
+    nice: Option<i32>,
+    #[cfg(target_os = "linux")]
+    cpu_set: Option<libc::cpu_set_t>,
+    max_memory: Option<u64>,
+    max_open_files: Option<u64>,
+}
+
+impl PreparedLimits {
+    /// Checks that `limits` can be applied on this platform, returning none if there are no
+    /// limits to apply
+    pub fn prepare(limits: &ProcLimits) -> io::Result<Option<Self>> {
+        if limits.is_empty() {
+            return Ok(None);
+        }
+
+        if cfg!(windows) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Process priority and limits are not supported on Windows",
+            ));
+        }
+
+        #[cfg(target_os = "linux")]
+        let cpu_set = match limits.cpu_affinity.as_deref() {
+            Some([]) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "CPU affinity must include at least one CPU",
+                ))
+            }
+            Some(cpus) => {
+                // SAFETY: A cpu set is a plain bitmask, where all zeroes is an empty set, and each
+                //         cpu is checked to be within the set before being added
+                let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+                for &cpu in cpus {
+                    if cpu >= libc::CPU_SETSIZE as usize {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("CPU {cpu} is beyond the CPUs that can be assigned"),
+                        ));
+                    }
+                    unsafe { libc::CPU_SET(cpu, &mut set) };
+                }
+                Some(set)
+            }
+            None => None,
+        };
+
+        #[cfg(not(target_os = "linux"))]
+        if limits.cpu_affinity.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "CPU affinity is only supported on Linux",
+            ));
+        }
+
+        Ok(Some(Self {
+            nice: limits.nice,
+            #[cfg(target_os = "linux")]
+            cpu_set,
+            max_memory: limits.max_memory,
+            max_open_files: limits.max_open_files,
+        }))
+    }
+
+    /// Applies the priority and limits to the calling process, which is meant to happen in the
+    /// child between forking and running the program
+    #[cfg(unix)]
+    pub fn apply(&self) -> io::Result<()> {
+        self.apply_to(0)
+    }
+
+    /// Applies the priority and limits to the process with `pid`, which may already be running
+    #[cfg(target_os = "linux")]
+    pub fn apply_to_pid(&self, pid: u32) -> io::Result<()> {
+        let pid = libc::pid_t::try_from(pid)
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
+        self.apply_to(pid)
+    }
+
+    /// Applies the priority and limits to the process with `pid`, where 0 is the calling process
+    /// and is the only process supported outside of Linux
+    #[cfg(unix)]
+    fn apply_to(&self, pid: libc::pid_t) -> io::Result<()> {
+        // SAFETY: These system calls have no memory safety requirements beyond the pointers to
+        //         values that live on our stack, failing with an error code if not permitted
+        unsafe {
+            if let Some(nice) = self.nice {
+                if libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            #[cfg(target_os = "linux")]
+            if let Some(set) = self.cpu_set.as_ref() {
+                if libc::sched_setaffinity(pid, std::mem::size_of::<libc::cpu_set_t>(), set) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            let rlimit = |max: u64| libc::rlimit {
+                rlim_cur: max as libc::rlim_t,
+                rlim_max: max as libc::rlim_t,
+            };
+
+            #[cfg(target_os = "linux")]
+            {
+                if let Some(max) = self.max_memory {
+                    let limit = rlimit(max);
+                    if libc::prlimit(pid, libc::RLIMIT_AS, &limit, std::ptr::null_mut()) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                if let Some(max) = self.max_open_files {
+                    let limit = rlimit(max);
+                    if libc::prlimit(pid, libc::RLIMIT_NOFILE, &limit, std::ptr::null_mut()) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+            }
+
+            #[cfg(not(target_os = "linux"))]
+            {
+                if let Some(max) = self.max_memory {
+                    if libc::setrlimit(libc::RLIMIT_AS, &rlimit(max)) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                if let Some(max) = self.max_open_files {
+                    if libc::setrlimit(libc::RLIMIT_NOFILE, &rlimit(max)) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prepare_should_return_none_if_no_limits() {
+        assert!(PreparedLimits::prepare(&ProcLimits::default())
+            .unwrap()
+            .is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn prepare_should_fail_if_cpu_affinity_is_empty_or_out_of_range() {
+        let limits = |cpus: Vec<usize>| ProcLimits {
+            cpu_affinity: Some(cpus),
+            ..Default::default()
+        };
+
+        for cpus in [vec![], vec![0, libc::CPU_SETSIZE as usize]] {
+            let err = PreparedLimits::prepare(&limits(cpus)).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+        assert!(PreparedLimits::prepare(&limits(vec![0])).unwrap().is_some());
+    }
+}
@@ -0,0 +1,538 @@
+use crate::data::Environment;
+use std::{
+    ffi::{OsStr, OsString},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Commands that the server permits spawning, checked against each command before it is spawned
+///
+/// Each pattern is a command line whose first word matches the program (by file name, or by path
+/// if the word contains a path separator) and whose remaining words match arguments of the
+/// command, with `*` within a word matching any run of characters. So `cargo` matches any command
+/// running cargo, while `git push` matches only those pushing with git.
+///
+/// Programs are resolved to the files that they run before being checked. An allowed program
+/// named by file name only matches the file found for that name within the `PATH` of the server,
+/// so a `git` elsewhere is not allowed by `git`, and allowed arguments must be the leading ones.
+/// Denied programs match by the file name of either the program or the file it resolves to, and
+/// denied arguments match any of the arguments.
+///
+/// As the environment of a process can make any program run arbitrary code, commands spawned
+/// with variables such as `LD_PRELOAD` or `BASH_ENV` are refused unless the policy permits every
+/// command.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProcessPolicy {
+    /// Patterns of commands that can be spawned, where every command is permitted (unless denied)
+    /// if there are none
+    pub allow: Vec<String>,
+
+    /// Patterns of commands that cannot be spawned, even if they are allowed
+    pub deny: Vec<String>,
+
+    /// If true, signals can be sent to any process of the system by its pid, not just those
+    /// spawned by the server
+    pub system_kill: bool,
+}
+
+/// Patterns of environment variables that can make an allowed program run other code, such as
+/// by loading libraries, sourcing scripts or naming commands to run in its place
+const DENIED_ENVIRONMENT: &[&str] = &[
+    "LD_*",
+    "DYLD_*",
+    "BASH_ENV",
+    "ENV",
+    "BASH_FUNC_*",
+    "SHELLOPTS",
+    "PS4",
+    "PROMPT_COMMAND",
+    "IFS",
+    "GIT_*",
+    "PYTHONSTARTUP",
+    "PYTHONPATH",
+    "PYTHONHOME",
+    "PERL5OPT",
+    "PERL5LIB",
+    "PERLLIB",
+    "RUBYOPT",
+    "RUBYLIB",
+    "NODE_OPTIONS",
+    "NODE_PATH",
+    "JAVA_TOOL_OPTIONS",
+    "_JAVA_OPTIONS",
+    "RUSTC_WRAPPER",
+    "CARGO_*",
+    "EDITOR",
+    "VISUAL",
+    "PAGER",
+];
+
+impl ProcessPolicy {
+    /// Returns true if the policy permits every command
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Checks that sending a signal to the process with `pid` is permitted, which is never the
+    /// case for the server itself or for pid 0, as it would signal the server's process group
+    pub(crate) fn check_system_kill(&self, pid: u32) -> io::Result<()> {
+        if !self.system_kill {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Sending signals to processes not spawned by the server is not enabled",
+            ));
+        }
+
+        if pid == 0 || pid == std::process::id() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Refusing to send signals to pid {pid}"),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `cmd` is permitted when spawned with `environment` within `current_dir`,
+    /// returning a permission denied error describing why if not
+    pub fn check(
+        &self,
+        cmd: &str,
+        environment: &Environment,
+        current_dir: Option<&Path>,
+    ) -> io::Result<()> {
+        self.check_in(
+            cmd,
+            environment,
+            current_dir,
+            std::env::var_os("PATH").as_deref(),
+        )
+    }
+
+    /// Checks that running `program` (without arguments) is permitted
+    pub fn check_program(&self, program: &Path) -> io::Result<()> {
+        let cmd = shell_words::quote(&program.to_string_lossy()).into_owned();
+        self.check(&cmd, &Environment::default(), None)
+    }
+
+    /// Checks `cmd` like [`ProcessPolicy::check`], with `server_path` as the `PATH` of the server
+    fn check_in(
+        &self,
+        cmd: &str,
+        environment: &Environment,
+        current_dir: Option<&Path>,
+        server_path: Option<&OsStr>,
+    ) -> io::Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        if let Some((key, pattern)) = environment.keys().find_map(|key| {
+            DENIED_ENVIRONMENT
+                .iter()
+                .find(|pattern| is_denied_variable(pattern, key))
+                .map(|pattern| (key, pattern))
+        }) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Environment variable {key} matches denied pattern `{pattern}`"),
+            ));
+        }
+
+        // NOTE: Commands are split the same way as when spawned, so that the words checked here
+        //       are the same as those given to the program
+        let words = split(cmd).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Command could not be split into arguments to check against the process policy",
+            )
+        })?;
+
+        let Some((program, args)) = words.split_first() else {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Command was empty",
+            ));
+        };
+
+        // NOTE: The program is looked up within the PATH given to the process if it has one, as
+        //       that is where it is found when spawned
+        let path = environment
+            .iter()
+            .find(|(key, _)| is_path_variable(key))
+            .map(|(_, value)| OsString::from(value));
+        let program = Program {
+            word: program,
+            resolved: resolve(program, path.as_deref().or(server_path), current_dir),
+        };
+
+        if let Some(pattern) = self.deny.iter().find(|p| denies(p, &program, args)) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Command matches denied pattern `{pattern}`"),
+            ));
+        }
+
+        if !self.allow.is_empty()
+            && !self
+                .allow
+                .iter()
+                .any(|p| allows(p, &program, args, server_path))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "Command `{}` does not match any allowed pattern",
+                    words.first().map(String::as_str).unwrap_or_default()
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn split(cmd: &str) -> Option<Vec<String>> {
+    if cfg!(windows) {
+        Some(winsplit::split(cmd))
+    } else {
+        shell_words::split(cmd).ok()
+    }
+}
+
+/// Program of a command, along with the file that it runs if found
+struct Program<'a> {
+    word: &'a str,
+    resolved: Option<PathBuf>,
+}
+
+impl Program<'_> {
+    /// Returns the file names of the program as written and of the file that it runs
+    fn names(&self) -> impl Iterator<Item = &str> {
+        names(Path::new(self.word)).chain(self.resolved.as_deref().into_iter().flat_map(names))
+    }
+}
+
+/// Returns true if `pattern` allows running `program` with `args`, which requires the program to
+/// run the file that the pattern names and the pattern's arguments to match the leading `args`
+fn allows(pattern: &str, program: &Program, args: &[String], server_path: Option<&OsStr>) -> bool {
+    let Some((program_pattern, arg_patterns)) = split_pattern(pattern) else {
+        return false;
+    };
+    let Some(resolved) = program.resolved.as_deref() else {
+        return false;
+    };
+
+    let program_matches = if is_path(&program_pattern) {
+        matches_path(&program_pattern, resolved)
+    } else {
+        // NOTE: The name must lead to the same file when looked up within the PATH of the server,
+        //       so that a program of the same name placed elsewhere is not allowed
+        program.names().any(|name| {
+            wildcard_match(&program_pattern, name)
+                && resolve(name, server_path, None).as_deref() == Some(resolved)
+        })
+    };
+
+    program_matches
+        && arg_patterns.len() <= args.len()
+        && arg_patterns
+            .iter()
+            .zip(args)
+            .all(|(pattern, arg)| wildcard_match(pattern, arg))
+}
+
+/// Returns true if `pattern` denies running `program` with `args`, which only requires the program
+/// to go by the name that the pattern has and each of the pattern's arguments to match any of `args`
+fn denies(pattern: &str, program: &Program, args: &[String]) -> bool {
+    let Some((program_pattern, arg_patterns)) = split_pattern(pattern) else {
+        return false;
+    };
+
+    let program_matches = if is_path(&program_pattern) {
+        wildcard_match(&program_pattern, program.word)
+            || program
+                .resolved
+                .as_deref()
+                .is_some_and(|resolved| matches_path(&program_pattern, resolved))
+    } else {
+        program
+            .names()
+            .any(|name| wildcard_match(&program_pattern, name))
+    };
+
+    program_matches
+        && arg_patterns
+            .iter()
+            .all(|pattern| args.iter().any(|arg| wildcard_match(pattern, arg)))
+}
+
+fn split_pattern(pattern: &str) -> Option<(String, Vec<String>)> {
+    let mut words = split(pattern)?;
+    if words.is_empty() {
+        return None;
+    }
+    let program = words.remove(0);
+    Some((program, words))
+}
+
+fn is_path(word: &str) -> bool {
+    word.contains(['/', '\\'])
+}
+
+fn is_path_variable(key: &str) -> bool {
+    if cfg!(windows) {
+        key.eq_ignore_ascii_case("PATH")
+    } else {
+        key == "PATH"
+    }
+}
+
+fn is_denied_variable(pattern: &str, key: &str) -> bool {
+    if cfg!(windows) {
+        wildcard_match(pattern, &key.to_ascii_uppercase())
+    } else {
+        wildcard_match(pattern, key)
+    }
+}
+
+/// Returns true if `pattern` (a path) matches the `resolved` file, either as written or as the
+/// file that it leads to
+fn matches_path(pattern: &str, resolved: &Path) -> bool {
+    wildcard_match(pattern, &resolved.to_string_lossy())
+        || Path::new(pattern)
+            .canonicalize()
+            .is_ok_and(|path| path == resolved)
+}
+
+/// Returns the file name of `path`, along with its stem on Windows where programs can be run with
+/// or without their extension
+fn names(path: &Path) -> impl Iterator<Item = &str> {
+    let name = path.file_name().and_then(OsStr::to_str);
+    let stem = if cfg!(windows) {
+        path.file_stem().and_then(OsStr::to_str)
+    } else {
+        None
+    };
+    name.into_iter().chain(stem)
+}
+
+/// Finds the file that `program` runs, looking within the directories of `path` unless it is
+/// itself a path (relative to `current_dir`), returning its canonical path
+fn resolve(program: &str, path: Option<&OsStr>, current_dir: Option<&Path>) -> Option<PathBuf> {
+    let cwd = match current_dir {
+        Some(dir) => dir.to_path_buf(),
+        None => std::env::current_dir().ok()?,
+    };
+    which::which_in(program, path, cwd)
+        .ok()?
+        .canonicalize()
+        .ok()
+}
+
+/// Returns true if `text` matches `pattern`, where `*` matches any run of characters
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard, so the pattern must be the entire text
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::{prelude::*, TempDir};
+
+    fn policy(allow: &[&str], deny: &[&str]) -> ProcessPolicy {
+        ProcessPolicy {
+            allow: allow.iter().map(ToString::to_string).collect(),
+            deny: deny.iter().map(ToString::to_string).collect(),
+            system_kill: false,
+        }
+    }
+
+    /// Creates a directory `bin` within `root` holding empty programs named `names`
+    fn programs(root: &TempDir, bin: &str, names: &[&str]) -> PathBuf {
+        let dir = root.child(bin);
+        dir.create_dir_all().unwrap();
+        for name in names {
+            let name = if cfg!(windows) {
+                format!("{name}.exe")
+            } else {
+                name.to_string()
+            };
+            let program = dir.child(name);
+            program.touch().unwrap();
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(program.path(), std::fs::Permissions::from_mode(0o755))
+                    .unwrap();
+            }
+        }
+        dir.path().canonicalize().unwrap()
+    }
+
+    /// Checks `cmd` against `policy` with `bin` as the PATH of the server
+    fn check(policy: &ProcessPolicy, bin: &Path, cmd: &str) -> io::Result<()> {
+        policy.check_in(cmd, &Environment::new(), None, Some(bin.as_os_str()))
+    }
+
+    #[test]
+    fn check_should_permit_only_allowed_programs() {
+        let root = TempDir::new().unwrap();
+        let bin = programs(&root, "bin", &["git", "cargo", "make", "gitk", "sh"]);
+        let policy = policy(&["git", "cargo build", "make"], &[]);
+        assert!(check(&policy, &bin, "git status").is_ok());
+        assert!(check(
+            &policy,
+            &bin,
+            &format!("{:?} log --oneline", bin.join("git"))
+        )
+        .is_ok());
+        assert!(check(&policy, &bin, "cargo build --release").is_ok());
+
+        let err = check(&policy, &bin, "cargo run").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(check(&policy, &bin, "sh -c 'git status'").is_err());
+        assert!(check(&policy, &bin, "gitk").is_err());
+        assert!(check(&policy, &bin, "missing").is_err());
+    }
+
+    #[test]
+    fn check_should_only_permit_allowed_names_that_run_the_program_found_in_server_path() {
+        let root = TempDir::new().unwrap();
+        let bin = programs(&root, "bin", &["git"]);
+        let other = programs(&root, "other", &["git"]);
+        let policy = policy(&["git"], &[]);
+
+        // A program of the same name elsewhere is not the one allowed, whether given by path or
+        // found through a PATH given to the process
+        assert!(check(&policy, &bin, &format!("{:?} status", other.join("git"))).is_err());
+        let mut environment = Environment::new();
+        environment.insert(String::from("PATH"), other.to_string_lossy().into_owned());
+        assert!(policy
+            .check_in("git status", &environment, None, Some(bin.as_os_str()))
+            .is_err());
+
+        // Relative paths are resolved against the directory that the process runs within
+        assert!(policy
+            .check_in(
+                &format!(".{}git", std::path::MAIN_SEPARATOR),
+                &Environment::new(),
+                Some(&bin),
+                Some(bin.as_os_str()),
+            )
+            .is_ok());
+        assert!(policy
+            .check_in(
+                &format!(".{}git", std::path::MAIN_SEPARATOR),
+                &Environment::new(),
+                Some(&other),
+                Some(bin.as_os_str()),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn check_should_reject_denied_commands_even_if_allowed() {
+        let root = TempDir::new().unwrap();
+        let bin = programs(&root, "bin", &["git"]);
+        let policy = policy(&["git"], &["git push", "* --upload-pack*"]);
+        assert!(check(&policy, &bin, "git pull").is_ok());
+        assert!(check(&policy, &bin, "git push origin main").is_err());
+
+        // Denied arguments are matched wherever they appear
+        assert!(check(&policy, &bin, "git -C repo push").is_err());
+        assert!(check(&policy, &bin, "git fetch --upload-pack=evil").is_err());
+        assert!(check(&policy, &bin, "git --upload-pack=evil fetch").is_err());
+    }
+
+    #[test]
+    fn check_should_reject_environment_that_could_run_other_code_unless_policy_is_empty() {
+        let root = TempDir::new().unwrap();
+        let bin = programs(&root, "bin", &["git"]);
+        let policy = policy(&["git"], &[]);
+
+        for key in ["LD_PRELOAD", "BASH_ENV", "GIT_SSH_COMMAND"] {
+            let mut environment = Environment::new();
+            environment.insert(key.to_string(), String::from("/tmp/evil"));
+
+            let err = policy
+                .check_in("git status", &environment, None, Some(bin.as_os_str()))
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied, "{key}");
+            assert!(ProcessPolicy::default()
+                .check_in("git status", &environment, None, Some(bin.as_os_str()))
+                .is_ok());
+        }
+
+        let mut environment = Environment::new();
+        environment.insert(String::from("RUST_LOG"), String::from("debug"));
+        assert!(policy
+            .check_in("git status", &environment, None, Some(bin.as_os_str()))
+            .is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_should_reject_programs_that_lead_to_denied_programs() {
+        let root = TempDir::new().unwrap();
+        let bin = programs(&root, "bin", &["rm"]);
+        let link = root.child("innocent");
+        link.symlink_to_file(bin.join("rm")).unwrap();
+
+        let policy = policy(&[], &["rm"]);
+        assert!(check(&policy, &bin, &format!("{:?} -rf /", link.path())).is_err());
+    }
+
+    // NOTE: Ignoring on windows because splitting commands there does not fail on quotes
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn check_should_reject_commands_that_cannot_be_split_unless_policy_is_empty() {
+        let root = TempDir::new().unwrap();
+        let bin = programs(&root, "bin", &["echo"]);
+        assert!(check(&policy(&[], &["rm"]), &bin, "echo 'text").is_err());
+        assert!(check(&ProcessPolicy::default(), &bin, "echo 'text").is_ok());
+    }
+
+    #[test]
+    fn check_system_kill_should_require_opting_in_and_reject_the_server_itself() {
+        let mut policy = ProcessPolicy::default();
+        let err = policy.check_system_kill(1234).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+        policy.system_kill = true;
+        assert!(policy.check_system_kill(1234).is_ok());
+        assert!(policy.check_system_kill(0).is_err());
+        assert!(policy.check_system_kill(std::process::id()).is_err());
+    }
+
+    #[test]
+    fn wildcard_match_should_match_any_run_of_characters() {
+        assert!(wildcard_match("make", "make"));
+        assert!(!wildcard_match("make", "cmake"));
+        assert!(wildcard_match("*make", "cmake"));
+        assert!(wildcard_match("python3*", "python3.11"));
+        assert!(wildcard_match("a*b*c", "a-b-b-c"));
+        assert!(!wildcard_match("a*b*c", "a-c-b"));
+        assert!(wildcard_match("*", ""));
+    }
+}
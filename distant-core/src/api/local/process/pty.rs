@@ -1,10 +1,10 @@
 use super::{
-    wait, ExitStatus, FutureReturn, InputChannel, OutputChannel, Process, ProcessId, ProcessKiller,
-    ProcessPty, PtySize, WaitRx,
+    wait, ExitStatus, FutureReturn, InputChannel, OutputChannel, PreparedLimits, Process,
+    ProcessId, ProcessKiller, ProcessPty, PtySize, WaitRx,
 };
 use crate::{
     constants::{MAX_PIPE_CHUNK_SIZE, READ_PAUSE_DURATION},
-    data::Environment,
+    data::{Environment, ProcLimits},
 };
 use log::*;
 use portable_pty::{CommandBuilder, MasterPty, PtySize as PortablePtySize};
@@ -19,6 +19,7 @@ use tokio::{sync::mpsc, task::JoinHandle};
 /// Represents a process that is associated with a pty
 pub struct PtyProcess {
     id: ProcessId,
+    pid: Option<u32>,
     pty_master: Option<Arc<Mutex<Box<dyn MasterPty + Send>>>>,
     stdin: Option<Box<dyn InputChannel>>,
     stdout: Option<Box<dyn OutputChannel>>,
@@ -36,6 +37,7 @@ impl PtyProcess {
         environment: Environment,
         current_dir: Option<PathBuf>,
         size: PtySize,
+        limits: &ProcLimits,
     ) -> io::Result<Self>
     where
         S: AsRef<OsStr>,
@@ -44,6 +46,16 @@ impl PtyProcess {
     {
         let id = rand::random();
 
+        // Processes within a pty can only have priority and limits applied once spawned, which
+        // requires doing so by pid
+        let limits = PreparedLimits::prepare(limits)?;
+        if limits.is_some() && !cfg!(target_os = "linux") {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Process priority and limits within a pty are only supported on Linux",
+            ));
+        }
+
         // Establish our new pty for the given size
         let pty_system = portable_pty::native_pty_system();
         let pty_pair = pty_system
@@ -70,6 +82,24 @@ impl PtyProcess {
             .spawn_command(cmd)
             .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?;
 
+        let pid = child.process_id();
+
+        #[cfg(target_os = "linux")]
+        if let Some(limits) = limits {
+            let result = match pid {
+                Some(pid) => limits.apply_to_pid(pid),
+                None => Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Unable to apply limits as process has no pid",
+                )),
+            };
+
+            if let Err(x) = result {
+                let _ = child.kill();
+                return Err(x);
+            }
+        }
+
         // NOTE: Need to drop slave to close out file handles and avoid deadlock when waiting on
         //       the child
         drop(pty_slave);
@@ -159,6 +189,7 @@ impl PtyProcess {
 
         Ok(Self {
             id,
+            pid,
             pty_master: Some(Arc::new(Mutex::new(pty_master))),
             stdin: Some(Box::new(stdin_tx)),
             stdout: Some(Box::new(stdout_rx)),
@@ -183,6 +214,10 @@ impl Process for PtyProcess {
         self.id
     }
 
+    fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
     fn wait(&mut self) -> FutureReturn<'_, io::Result<ExitStatus>> {
         async fn inner(this: &mut PtyProcess) -> io::Result<ExitStatus> {
             let mut status = this.wait.recv().await?;
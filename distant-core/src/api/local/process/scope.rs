@@ -0,0 +1,245 @@
+use log::*;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Limits on the resources used by spawned processes placed in a scope
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScopeLimits {
+    /// Maximum bytes of memory that can be used
+    pub memory_max: Option<u64>,
+
+    /// Maximum CPU time that can be used as a percentage of a single CPU, where 200 is two CPUs
+    pub cpu_quota: Option<u32>,
+
+    /// Maximum number of processes and threads that can exist at once
+    pub tasks_max: Option<u64>,
+}
+
+/// Where processes spawned by the server are placed (on Linux) so that they can be attributed to
+/// distant and bound by limits
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProcessScope {
+    /// Each process is run within its own transient scope in `distant.slice` using `systemd-run`,
+    /// with the limits applying to each process along with its children
+    Systemd(ScopeLimits),
+
+    /// Every process is moved into the cgroup (v2) at `path` before it runs, with the limits
+    /// applying to all of them together
+    Cgroup { path: PathBuf, limits: ScopeLimits },
+}
+
+impl ProcessScope {
+    /// Checks that processes can be placed in the scope, returning the command prefix that does
+    /// so, or none (after logging a warning) if they cannot, in which case processes are spawned
+    /// without a scope
+    pub(crate) fn prepare(self) -> Option<ScopePrefix> {
+        if !cfg!(target_os = "linux") {
+            warn!("Process scopes are only supported on Linux, so processes will not be scoped");
+            return None;
+        }
+
+        let result = match self {
+            Self::Systemd(limits) => prepare_systemd(&limits),
+            Self::Cgroup { path, limits } => prepare_cgroup(&path, &limits),
+        };
+
+        match result {
+            Ok(prefix) => {
+                debug!("Spawning processes using prefix {:?}", prefix.0);
+                Some(prefix)
+            }
+            Err(x) => {
+                warn!("Unable to scope processes, so they will not be scoped: {x}");
+                None
+            }
+        }
+    }
+}
+
+/// Arguments placed before a command to run it within a scope
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ScopePrefix(Vec<String>);
+
+impl ScopePrefix {
+    /// Returns `cmd` changed to run within the scope, or `cmd` itself if it cannot be split into
+    /// arguments, leaving spawning to report the problem
+    pub fn wrap(&self, cmd: String) -> String {
+        match shell_words::split(&cmd) {
+            Ok(args) if !args.is_empty() => shell_words::join(self.0.iter().chain(args.iter())),
+            _ => cmd,
+        }
+    }
+}
+
+fn prepare_systemd(limits: &ScopeLimits) -> io::Result<ScopePrefix> {
+    let mut args = vec![
+        String::from("systemd-run"),
+        String::from("--scope"),
+        String::from("--quiet"),
+        String::from("--collect"),
+        String::from("--no-ask-password"),
+        String::from("--slice=distant"),
+    ];
+
+    for property in systemd_properties(limits) {
+        args.push(String::from("-p"));
+        args.push(property);
+    }
+
+    // The system instance of systemd is used when permitted (such as when running as root),
+    // falling back to the instance of the user running the server
+    let mut last_error = None;
+    for user in [false, true] {
+        let mut args = args.clone();
+        if user {
+            args.insert(1, String::from("--user"));
+        }
+
+        match probe(&args) {
+            Ok(()) => {
+                args.push(String::from("--"));
+                return Ok(ScopePrefix(args));
+            }
+            Err(x) => last_error = Some(x),
+        }
+    }
+
+    Err(last_error.unwrap())
+}
+
+/// Runs `true` using `args` as the prefix, failing if it does not succeed
+fn probe(args: &[String]) -> io::Result<()> {
+    let output = Command::new(&args[0])
+        .args(&args[1..])
+        .arg("--")
+        .arg("true")
+        .stdin(Stdio::null())
+        .output()?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "{} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ))
+    }
+}
+
+fn systemd_properties(limits: &ScopeLimits) -> Vec<String> {
+    let mut properties = Vec::new();
+    if let Some(x) = limits.memory_max {
+        properties.push(format!("MemoryMax={x}"));
+    }
+    if let Some(x) = limits.cpu_quota {
+        properties.push(format!("CPUQuota={x}%"));
+    }
+    if let Some(x) = limits.tasks_max {
+        properties.push(format!("TasksMax={x}"));
+    }
+    properties
+}
+
+fn prepare_cgroup(path: &Path, limits: &ScopeLimits) -> io::Result<ScopePrefix> {
+    fs::create_dir_all(path)?;
+
+    // Only a cgroup filesystem fills in a new directory with the files that manage it
+    if !path.join("cgroup.procs").is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{path:?} is not a cgroup (v2)"),
+        ));
+    }
+
+    let path_str = path.to_str().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{path:?} is not valid unicode"),
+        )
+    })?;
+
+    for (file, value) in cgroup_limits(limits) {
+        fs::write(path.join(file), value)
+            .map_err(|x| io::Error::new(x.kind(), format!("Failed to set {file}: {x}")))?;
+    }
+
+    // The shell moves itself into the cgroup and then becomes the command, which keeps the id of
+    // the process and places everything the command spawns within the cgroup as well
+    Ok(ScopePrefix(vec![
+        String::from("/bin/sh"),
+        String::from("-c"),
+        String::from(r#"echo $$ > "$1/cgroup.procs" && shift && exec "$@""#),
+        String::from("sh"),
+        path_str.to_string(),
+    ]))
+}
+
+fn cgroup_limits(limits: &ScopeLimits) -> Vec<(&'static str, String)> {
+    let mut files = Vec::new();
+    if let Some(x) = limits.memory_max {
+        files.push(("memory.max", x.to_string()));
+    }
+    if let Some(x) = limits.cpu_quota {
+        // Quota is the microseconds of CPU time allowed in each period of 100ms
+        files.push(("cpu.max", format!("{} 100000", u64::from(x) * 1000)));
+    }
+    if let Some(x) = limits.tasks_max {
+        files.push(("pids.max", x.to_string()));
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_should_place_prefix_before_quoted_command() {
+        let prefix = ScopePrefix(vec![String::from("systemd-run"), String::from("--")]);
+        assert_eq!(
+            prefix.wrap(String::from("echo 'some text'")),
+            "systemd-run -- echo 'some text'"
+        );
+
+        // Commands that cannot be split are left for spawning to reject
+        assert_eq!(prefix.wrap(String::from("echo 'text")), "echo 'text");
+    }
+
+    #[test]
+    fn limits_should_be_converted_for_systemd_and_cgroups() {
+        let limits = ScopeLimits {
+            memory_max: Some(1024),
+            cpu_quota: Some(150),
+            tasks_max: None,
+        };
+
+        assert_eq!(
+            systemd_properties(&limits),
+            ["MemoryMax=1024", "CPUQuota=150%"]
+        );
+        assert_eq!(
+            cgroup_limits(&limits),
+            [
+                ("memory.max", String::from("1024")),
+                ("cpu.max", String::from("150000 100000"))
+            ]
+        );
+    }
+
+    #[test]
+    fn prepare_should_fall_back_if_path_is_not_a_cgroup() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let scope = ProcessScope::Cgroup {
+            path: temp.path().join("distant"),
+            limits: ScopeLimits::default(),
+        };
+        assert_eq!(scope.prepare(), None);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::data::Signal;
+use std::io;
+
+/// Sends `signal` to the process with `pid` given by the operating system
+#[cfg(unix)]
+pub fn send_signal(pid: u32, signal: Signal) -> io::Result<()> {
+    kill(to_pid(pid)?, signal)
+}
+
+/// Sends `signal` to every process within the process group `pgid`
+#[cfg(unix)]
+pub fn send_group_signal(pgid: u32, signal: Signal) -> io::Result<()> {
+    kill(-to_pid(pgid)?, signal)
+}
+
+#[cfg(unix)]
+fn to_pid(pid: u32) -> io::Result<libc::pid_t> {
+    libc::pid_t::try_from(pid).map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))
+}
+
+/// Sends `signal` to the process with `pid`, or to the process group `-pid` if negative
+#[cfg(unix)]
+fn kill(pid: libc::pid_t, signal: Signal) -> io::Result<()> {
+    let signal = match signal {
+        Signal::Hup => libc::SIGHUP,
+        Signal::Int | Signal::Break => libc::SIGINT,
+        Signal::Quit => libc::SIGQUIT,
+        Signal::Kill => libc::SIGKILL,
+        Signal::Term => libc::SIGTERM,
+        Signal::Usr1 => libc::SIGUSR1,
+        Signal::Usr2 => libc::SIGUSR2,
+    };
+
+    // SAFETY: Sending a signal has no memory safety requirements, failing with an error code
+    //         if the process is gone or cannot be signaled by us
+    if unsafe { libc::kill(pid, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Sends `signal` to the process with `pid` given by the operating system
+///
+/// NOTE: Only [`Signal::Break`] can be sent on Windows, as a `CTRL_BREAK` event to the console
+///       process group of the process, while stopping the process is left to its killer
+#[cfg(windows)]
+pub fn send_signal(pid: u32, signal: Signal) -> io::Result<()> {
+    use windows_sys::Win32::System::Console::{GenerateConsoleCtrlEvent, CTRL_BREAK_EVENT};
+
+    if signal != Signal::Break {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Signal {signal} is not supported on Windows"),
+        ));
+    }
+
+    // SAFETY: Generating a console event has no memory safety requirements, failing if the
+    //         process group does not share our console
+    if unsafe { GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid) } != 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{
+        os::unix::process::{CommandExt, ExitStatusExt},
+        process::Command,
+    };
+
+    #[test]
+    fn send_signal_should_deliver_signal_to_process() {
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+        send_signal(child.id(), Signal::Term).unwrap();
+
+        let status = child.wait().unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGTERM));
+    }
+
+    #[test]
+    fn send_group_signal_should_deliver_signal_to_every_process_in_group() {
+        let mut leader = Command::new("sleep")
+            .arg("10")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let mut member = Command::new("sleep")
+            .arg("10")
+            .process_group(leader.id() as i32)
+            .spawn()
+            .unwrap();
+        send_group_signal(leader.id(), Signal::Term).unwrap();
+
+        assert_eq!(leader.wait().unwrap().signal(), Some(libc::SIGTERM));
+        assert_eq!(member.wait().unwrap().signal(), Some(libc::SIGTERM));
+    }
+}
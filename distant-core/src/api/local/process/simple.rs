@@ -1,17 +1,24 @@
+#[cfg(unix)]
+use super::{prepare_child, Leads, SwitchUser};
 use super::{
-    wait, ExitStatus, FutureReturn, InputChannel, NoProcessPty, OutputChannel, Process, ProcessId,
-    ProcessKiller, WaitRx,
+    wait, ExitStatus, FutureReturn, InputChannel, NoProcessPty, OutputChannel, PreparedLimits,
+    Process, ProcessId, ProcessKiller, WaitRx,
 };
-use crate::data::Environment;
+use crate::data::{Environment, ProcLimits};
 use log::*;
 use std::{ffi::OsStr, path::PathBuf, process::Stdio};
 use tokio::{io, process::Command, sync::mpsc, task::JoinHandle};
 
 mod tasks;
 
+/// Process creation flag that starts the process in a new console process group
+#[cfg(windows)]
+const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+
 /// Represents a simple process that does not have a pty
 pub struct SimpleProcess {
     id: ProcessId,
+    pid: Option<u32>,
     stdin: Option<Box<dyn InputChannel>>,
     stdout: Option<Box<dyn OutputChannel>>,
     stderr: Option<Box<dyn OutputChannel>>,
@@ -23,12 +30,17 @@ pub struct SimpleProcess {
 }
 
 impl SimpleProcess {
-    /// Spawns a new simple process
+    /// Spawns a new simple process, which leads a process group of its own on Unix if
+    /// `process_group` is true
+    #[cfg_attr(windows, allow(unused_variables))]
     pub fn spawn<S, I, S2>(
         program: S,
         args: I,
         environment: Environment,
         current_dir: Option<PathBuf>,
+        limits: &ProcLimits,
+        process_group: bool,
+        #[cfg(unix)] user: Option<&SwitchUser>,
     ) -> io::Result<Self>
     where
         S: AsRef<OsStr>,
@@ -36,13 +48,29 @@ impl SimpleProcess {
         S2: AsRef<OsStr>,
     {
         let id = rand::random();
+        let limits = PreparedLimits::prepare(limits)?;
         let mut child = {
             let mut command = Command::new(program);
 
+            // Leading a process group of its own lets the process be killed along with the
+            // processes it starts, which are in its group unless they leave it
+            #[cfg(unix)]
+            prepare_child(
+                &mut command,
+                process_group.then_some(Leads::ProcessGroup),
+                limits,
+                user,
+            );
+
             if let Some(path) = current_dir {
                 command.current_dir(path);
             }
 
+            // Give the process a console process group of its own on Windows, which is what
+            // receives the CTRL_BREAK events sent as signals
+            #[cfg(windows)]
+            command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+
             command
                 .envs(environment)
                 .args(args)
@@ -51,6 +79,7 @@ impl SimpleProcess {
                 .stderr(Stdio::piped())
                 .spawn()?
         };
+        let pid = child.id();
 
         let stdout = child.stdout.take().unwrap();
         let (stdout_task, stdout_ch) = tasks::spawn_read_task(stdout, 1);
@@ -105,6 +134,7 @@ impl SimpleProcess {
 
         Ok(Self {
             id,
+            pid,
             stdin: Some(Box::new(stdin_ch)),
             stdout: Some(Box::new(stdout_ch)),
             stderr: Some(Box::new(stderr_ch)),
@@ -122,6 +152,10 @@ impl Process for SimpleProcess {
         self.id
     }
 
+    fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
     fn wait(&mut self) -> FutureReturn<'_, io::Result<ExitStatus>> {
         async fn inner(this: &mut SimpleProcess) -> io::Result<ExitStatus> {
             let mut status = this.wait.recv().await?;
@@ -0,0 +1,215 @@
+use super::{FutureReturn, ProcessKiller};
+use log::*;
+use std::{io, sync::Arc};
+
+#[cfg(unix)]
+use {super::send_group_signal, crate::data::Signal};
+
+/// Represents a process along with the processes it started, which are killed together, being the
+/// process group led by the process on Unix or a job object holding the process on Windows
+pub struct ProcessTree {
+    #[cfg(unix)]
+    pgid: u32,
+
+    #[cfg(windows)]
+    job: JobObject,
+}
+
+impl ProcessTree {
+    /// Returns the tree of the process with `pid`, which must already lead a process group of its
+    /// own on Unix, and is placed within a new job object on Windows
+    ///
+    /// NOTE: On Windows, processes started before the process is placed within the job object
+    ///       are not part of the tree.
+    pub fn attach(pid: u32) -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            Ok(Self { pgid: pid })
+        }
+
+        #[cfg(windows)]
+        {
+            Ok(Self {
+                job: JobObject::with_process(pid)?,
+            })
+        }
+    }
+
+    /// Sends `signal` to every process within the tree, failing without sending it once the
+    /// process leading the group has been reaped, as the id of the group can then be reused
+    #[cfg(unix)]
+    pub fn signal(&self, signal: Signal) -> io::Result<()> {
+        if !self.is_led() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "Process group {} is no longer led by our process",
+                    self.pgid
+                ),
+            ));
+        }
+
+        send_group_signal(self.pgid, signal)
+    }
+
+    /// Returns true if the process leading the group is still our child that has not been reaped
+    /// (even if it has exited), and so still holds onto the id of the group
+    #[cfg(unix)]
+    fn is_led(&self) -> bool {
+        let pid = self.pgid as libc::pid_t;
+
+        // SAFETY: Waiting with WNOWAIT and WNOHANG neither reaps nor blocks, and only fills in
+        //         the zeroed info given, which we do not read
+        let unreaped = unsafe {
+            let mut info: libc::siginfo_t = std::mem::zeroed();
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+            ) == 0
+        };
+
+        // SAFETY: Looking up the group of a process has no requirements
+        unreaped && unsafe { libc::getpgid(pid) } == pid
+    }
+
+    /// Kills every process within the tree
+    pub fn kill(&self) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            self.signal(Signal::Kill)
+        }
+
+        #[cfg(windows)]
+        {
+            self.job.terminate()
+        }
+    }
+}
+
+/// Killer of a process that kills the rest of its tree before the process itself
+pub struct TreeKiller {
+    tree: Arc<ProcessTree>,
+    killer: Box<dyn ProcessKiller>,
+}
+
+impl TreeKiller {
+    pub fn new(tree: Arc<ProcessTree>, killer: Box<dyn ProcessKiller>) -> Self {
+        Self { tree, killer }
+    }
+}
+
+impl ProcessKiller for TreeKiller {
+    fn kill(&mut self) -> FutureReturn<'_, io::Result<()>> {
+        Box::pin(async move {
+            // The tree can be gone when the process is not (and vice versa), so only the outcome
+            // of killing the process itself is reported
+            if let Err(x) = self.tree.kill() {
+                debug!("Failed to kill process tree: {x}");
+            }
+
+            self.killer.kill().await
+        })
+    }
+
+    fn clone_killer(&self) -> Box<dyn ProcessKiller> {
+        Box::new(Self {
+            tree: Arc::clone(&self.tree),
+            killer: self.killer.clone_killer(),
+        })
+    }
+}
+
+/// Job object holding a process, along with every process it starts from then on
+#[cfg(windows)]
+struct JobObject(windows_sys::Win32::Foundation::HANDLE);
+
+#[cfg(windows)]
+impl JobObject {
+    /// Creates a new job object and places the process with `pid` within it
+    fn with_process(pid: u32) -> io::Result<Self> {
+        use windows_sys::Win32::{
+            Foundation::CloseHandle,
+            System::{
+                JobObjects::{AssignProcessToJobObject, CreateJobObjectW},
+                Threading::{OpenProcess, PROCESS_SET_QUOTA, PROCESS_TERMINATE},
+            },
+        };
+
+        // SAFETY: Creating an unnamed job object with default security has no memory safety
+        //         requirements, returning a null handle if it fails
+        let job = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+        if job == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let job = Self(job);
+
+        // SAFETY: Opening a process has no memory safety requirements, returning a null handle if
+        //         the process is gone or cannot be opened by us
+        let process = unsafe { OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, 0, pid) };
+        if process == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: Both handles are valid, where the handle of the process is no longer needed
+        //         once it is within the job object
+        let assigned = unsafe { AssignProcessToJobObject(job.0, process) };
+        let result = if assigned != 0 {
+            Ok(job)
+        } else {
+            Err(io::Error::last_os_error())
+        };
+        unsafe { CloseHandle(process) };
+        result
+    }
+
+    /// Terminates every process within the job object
+    fn terminate(&self) -> io::Result<()> {
+        use windows_sys::Win32::System::JobObjects::TerminateJobObject;
+
+        // SAFETY: The handle is valid for as long as we have it
+        if unsafe { TerminateJobObject(self.0, 1) } != 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for JobObject {
+    fn drop(&mut self) {
+        // SAFETY: The handle is valid and is not used again
+        unsafe {
+            windows_sys::Win32::Foundation::CloseHandle(self.0);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    #[test]
+    fn signal_should_only_reach_group_while_leader_is_not_reaped() {
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let tree = ProcessTree::attach(child.id()).unwrap();
+
+        // Exited but not yet reaped, the leader still holds onto the group
+        tree.signal(Signal::Kill).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        tree.signal(Signal::Kill).unwrap();
+
+        child.wait().unwrap();
+        let err = tree.signal(Signal::Kill).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(tree.kill().unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,319 @@
+use std::io;
+#[cfg(unix)]
+use std::{
+    ffi::{CStr, CString},
+    path::PathBuf,
+};
+
+/// Prompt given to `sudo` when asking for a password without a pty, which is picked out of the
+/// stderr of the process and reported as a prompt in place of output
+pub const RUN_AS_PROMPT: &str = "[sudo via distant] password for %p: ";
+
+/// Start of [`RUN_AS_PROMPT`] that comes before the parts filled in by `sudo`
+const RUN_AS_PROMPT_PREFIX: &str = "[sudo via distant] ";
+
+/// How a process is run as a user other than the one running the server
+#[derive(Debug)]
+pub enum RunAs {
+    /// Server is privileged, so the process switches to the user itself before it runs
+    #[cfg(unix)]
+    Switch(SwitchUser),
+
+    /// Command is run through a program such as `sudo` that switches to the user, which may
+    /// first ask for a password (reported as a prompt when `prompts` is true)
+    Wrap { prefix: Vec<String>, prompts: bool },
+}
+
+impl RunAs {
+    /// Figures out how to run a process as `user`, switching to the user directly when the server
+    /// is privileged and `can_switch`, otherwise looking for `runuser` (when privileged), `sudo`,
+    /// or `doas` to run the process through
+    ///
+    /// When not given a pty, `sudo` is told to read any password from stdin using
+    /// [`RUN_AS_PROMPT`], as it would otherwise fail without a terminal to ask on.
+    pub fn prepare(user: &str, pty: bool, can_switch: bool) -> io::Result<Self> {
+        #[cfg(windows)]
+        {
+            let _ = (user, pty, can_switch);
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Running processes as another user is not supported on Windows",
+            ))
+        }
+
+        #[cfg(unix)]
+        {
+            // SAFETY: Checking our effective user has no requirements
+            let privileged = unsafe { libc::geteuid() } == 0;
+
+            if privileged && can_switch {
+                return SwitchUser::lookup(user).map(Self::Switch);
+            }
+
+            let user = user.to_string();
+            let prefix = if privileged && cfg!(target_os = "linux") && has_program("runuser") {
+                vec![String::from("runuser"), String::from("-u"), user]
+            } else if has_program("sudo") {
+                let mut prefix = vec![String::from("sudo")];
+                if !pty {
+                    prefix.push(String::from("-S"));
+                    prefix.push(String::from("-p"));
+                    prefix.push(RUN_AS_PROMPT.to_string());
+                }
+                prefix.push(String::from("-u"));
+                prefix.push(user);
+                prefix
+            } else if has_program("doas") {
+                vec![String::from("doas"), String::from("-u"), user]
+            } else {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Running processes as another user requires privileges, sudo, or doas",
+                ));
+            };
+
+            let prompts = !pty && prefix[0] == "sudo";
+            Ok(Self::Wrap { prefix, prompts })
+        }
+    }
+
+    /// Returns `cmd` changed to run through the program that switches users, or `cmd` itself if
+    /// the process switches to the user directly or `cmd` cannot be split into arguments
+    pub fn wrap(&self, cmd: String) -> String {
+        match self {
+            Self::Wrap { prefix, .. } => match shell_words::split(&cmd) {
+                Ok(args) if !args.is_empty() => shell_words::join(
+                    prefix
+                        .iter()
+                        .map(String::as_str)
+                        .chain(["--"])
+                        .chain(args.iter().map(String::as_str)),
+                ),
+                _ => cmd,
+            },
+
+            #[cfg(unix)]
+            Self::Switch(_) => cmd,
+        }
+    }
+
+    /// Returns the user to switch to within the process before it runs, if any
+    #[cfg(unix)]
+    pub fn switch(&self) -> Option<&SwitchUser> {
+        match self {
+            Self::Switch(user) => Some(user),
+            Self::Wrap { .. } => None,
+        }
+    }
+
+    /// Returns true if the process may ask for a password using [`RUN_AS_PROMPT`]
+    pub fn prompts(&self) -> bool {
+        matches!(self, Self::Wrap { prompts: true, .. })
+    }
+
+    /// Removes a prompt written using [`RUN_AS_PROMPT`] from the end of `data`, returning it with
+    /// the marker that identifies it as ours replaced by the usual one of `sudo`
+    pub fn split_prompt(data: &mut Vec<u8>) -> Option<String> {
+        let prefix = RUN_AS_PROMPT_PREFIX.as_bytes();
+        let start = data
+            .windows(prefix.len())
+            .position(|window| window == prefix)?;
+        let prompt = data.split_off(start);
+        Some(format!(
+            "[sudo] {}",
+            String::from_utf8_lossy(&prompt[prefix.len()..])
+        ))
+    }
+}
+
+/// User looked up (along with its groups) ahead of spawning a process so that switching to it
+/// within the process before it runs only involves system calls
+#[cfg(unix)]
+#[derive(Clone, Debug)]
+pub struct SwitchUser {
+    name: CString,
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+    groups: Vec<libc::gid_t>,
+    home: PathBuf,
+}
+
+#[cfg(unix)]
+impl SwitchUser {
+    /// Looks up `user` by name, failing if there is no such user
+    pub fn lookup(user: &str) -> io::Result<Self> {
+        let name =
+            CString::new(user).map_err(|x| io::Error::new(io::ErrorKind::InvalidInput, x))?;
+
+        // SAFETY: An all-zero passwd is valid as an output, which is filled in (pointing into
+        //         our buffer) when the user is found
+        let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut buf = vec![0 as libc::c_char; 16 * 1024];
+        let mut result = std::ptr::null_mut();
+        let errno = unsafe {
+            libc::getpwnam_r(
+                name.as_ptr(),
+                &mut passwd,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+
+        if errno != 0 {
+            return Err(io::Error::from_raw_os_error(errno));
+        }
+        if result.is_null() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No user named {user}"),
+            ));
+        }
+
+        // SAFETY: The home directory is a nul-terminated string within our buffer
+        let home = unsafe { CStr::from_ptr(passwd.pw_dir) };
+        let home = PathBuf::from(home.to_string_lossy().into_owned());
+        let groups = lookup_groups(&name, passwd.pw_gid)?;
+        Ok(Self {
+            name,
+            uid: passwd.pw_uid,
+            gid: passwd.pw_gid,
+            groups,
+            home,
+        })
+    }
+
+    /// Returns the name of the user
+    pub fn name(&self) -> &str {
+        self.name.to_str().unwrap_or_default()
+    }
+
+    /// Returns the home directory of the user
+    pub fn home(&self) -> &PathBuf {
+        &self.home
+    }
+
+    /// Switches the calling process to the user along with its groups, which is meant to happen
+    /// in the child between forking and running the program, and so neither allocates nor reads
+    /// the group database
+    pub fn apply(&self) -> io::Result<()> {
+        // SAFETY: The groups are read from a buffer that lives as long as we do, and the length
+        //         given is that of the buffer. Each call fails with an error code if not permitted
+        unsafe {
+            if libc::setgroups(self.groups.len() as _, self.groups.as_ptr()) != 0
+                || libc::setgid(self.gid) != 0
+                || libc::setuid(self.uid) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Looks up the groups of the user called `name` along with its primary group `gid`, which are
+/// those that `initgroups` would set
+#[cfg(unix)]
+fn lookup_groups(name: &CStr, gid: libc::gid_t) -> io::Result<Vec<libc::gid_t>> {
+    #[cfg(target_os = "macos")]
+    type Group = libc::c_int;
+    #[cfg(not(target_os = "macos"))]
+    type Group = libc::gid_t;
+
+    let mut capacity: libc::c_int = 32;
+    loop {
+        let mut groups: Vec<Group> = vec![0; capacity as usize];
+        let mut count = capacity;
+
+        // SAFETY: The name is a nul-terminated string, and the count given is the length of the
+        //         buffer, which is never written past
+        let found = unsafe {
+            libc::getgrouplist(name.as_ptr(), gid as Group, groups.as_mut_ptr(), &mut count)
+        };
+
+        if found >= 0 {
+            groups.truncate(count as usize);
+            return Ok(groups
+                .into_iter()
+                .map(|group| group as libc::gid_t)
+                .collect());
+        }
+
+        // The buffer was too small, where some platforms report how many groups there are
+        capacity = if count > capacity {
+            count
+        } else {
+            capacity.saturating_mul(2)
+        };
+        if capacity as usize > 64 * 1024 {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "User belongs to too many groups",
+            ));
+        }
+    }
+}
+
+/// Returns true if `program` can be found within the `PATH` of the server
+#[cfg(unix)]
+fn has_program(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|path| path.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_should_place_prefix_before_command() {
+        let run_as = RunAs::Wrap {
+            prefix: vec![
+                String::from("sudo"),
+                String::from("-u"),
+                String::from("bob"),
+            ],
+            prompts: false,
+        };
+        assert_eq!(
+            run_as.wrap(String::from("echo 'some text'")),
+            "sudo -u bob -- echo 'some text'"
+        );
+    }
+
+    #[test]
+    fn split_prompt_should_remove_prompt_from_end_of_data() {
+        let mut data = b"Sorry, try again.\n[sudo via distant] password for alice: ".to_vec();
+        assert_eq!(
+            RunAs::split_prompt(&mut data).as_deref(),
+            Some("[sudo] password for alice: ")
+        );
+        assert_eq!(data, b"Sorry, try again.\n");
+
+        let mut data = b"some error".to_vec();
+        assert_eq!(RunAs::split_prompt(&mut data), None);
+        assert_eq!(data, b"some error");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lookup_should_fail_if_user_does_not_exist() {
+        let err = SwitchUser::lookup("distant-missing-user").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lookup_should_find_user_by_name() {
+        let user = SwitchUser::lookup("root").unwrap();
+        assert_eq!(user.name(), "root");
+        assert_eq!(user.uid, 0);
+        assert!(
+            user.groups.contains(&user.gid),
+            "Missing primary group: {:?}",
+            user.groups
+        );
+    }
+}
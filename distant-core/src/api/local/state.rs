@@ -1,31 +1,67 @@
+use super::OutputLimits;
 use std::io;
 
+mod detached;
+pub use detached::*;
+
 mod process;
 pub use process::*;
 
 mod search;
 pub use search::*;
 
+mod snapshot;
+pub use snapshot::*;
+
+mod stats;
+pub use stats::*;
+
+mod tunnel;
+pub use tunnel::*;
+
+mod usage;
+pub use usage::*;
+
 mod watcher;
 pub use watcher::*;
 
 /// Holds global state state managed by the server
 pub struct GlobalState {
+    /// State that holds the processes spawned detached from the server
+    pub detached: DetachedState,
+
     /// State that holds information about processes running on the server
     pub process: ProcessState,
 
     /// State that holds information about searches running on the server
     pub search: SearchState,
 
+    /// State that holds snapshots taken by the server
+    pub snapshot: SnapshotState,
+
+    /// State that holds information about system stats being reported by the server
+    pub stats: StatsState,
+
+    /// State that holds information about tunnels opened through the server
+    pub tunnel: TunnelState,
+
+    /// State that holds the bytes written on behalf of each connection
+    pub usage: UsageState,
+
     /// Watcher used for filesystem events
     pub watcher: WatcherState,
 }
 
 impl GlobalState {
-    pub fn initialize() -> io::Result<Self> {
+    pub fn initialize(output_limits: OutputLimits) -> io::Result<Self> {
         Ok(Self {
-            process: ProcessState::new(),
+            detached: DetachedState::new(),
+            process: ProcessState::new(output_limits),
             search: SearchState::new(),
+            snapshot: SnapshotState::new(),
+            stats: StatsState::new(),
+            tunnel: TunnelState::new(),
+            usage: UsageState::new(None),
             watcher: WatcherState::initialize()?,
         })
     }
@@ -0,0 +1,44 @@
+use log::*;
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+use tokio::process::Child;
+
+/// Holds the pids of processes spawned detached from the server that are still running, which
+/// can be signaled by pid without the server permitting signals to any process of the system
+#[derive(Default)]
+pub struct DetachedState {
+    pids: Arc<Mutex<HashSet<u32>>>,
+}
+
+impl DetachedState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps track of `child` until it exits, returning its pid
+    ///
+    /// NOTE: The child is waited on only while the server runs, after which the process carries on
+    ///       without it, as it is never killed on behalf of the server
+    pub fn insert(&self, mut child: Child) -> Option<u32> {
+        let pid = child.id()?;
+        self.pids.lock().unwrap().insert(pid);
+
+        let pids = Arc::clone(&self.pids);
+        tokio::spawn(async move {
+            match child.wait().await {
+                Ok(status) => trace!("Detached process {pid} has exited: {status}"),
+                Err(x) => trace!("Detached process {pid} failed to wait: {x}"),
+            }
+            pids.lock().unwrap().remove(&pid);
+        });
+
+        Some(pid)
+    }
+
+    /// Returns true if the process with `pid` was spawned detached and is still running
+    pub fn contains(&self, pid: u32) -> bool {
+        self.pids.lock().unwrap().contains(&pid)
+    }
+}
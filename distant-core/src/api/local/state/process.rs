@@ -1,4 +1,7 @@
-use crate::data::{DistantResponseData, Environment, ProcessId, PtySize};
+use crate::api::local::process::{OutputLimits, RunAs};
+use crate::data::{
+    DistantResponseData, Environment, ProcInfo, ProcLimits, ProcessId, PtySize, SessionId, Signal,
+};
 use distant_net::server::Reply;
 use std::{collections::HashMap, io, ops::Deref, path::PathBuf};
 use tokio::{
@@ -23,9 +26,9 @@ impl Drop for ProcessState {
 }
 
 impl ProcessState {
-    pub fn new() -> Self {
+    pub fn new(output_limits: OutputLimits) -> Self {
         let (tx, rx) = mpsc::channel(1);
-        let task = tokio::spawn(process_task(tx.clone(), rx));
+        let task = tokio::spawn(process_task(tx.clone(), rx, output_limits));
 
         Self {
             channel: ProcessChannel { tx },
@@ -61,13 +64,22 @@ impl Default for ProcessChannel {
 }
 
 impl ProcessChannel {
-    /// Spawns a new process, returning the id associated with it.
+    /// Spawns a new process, returning the id associated with it. Given a `session`, the process
+    /// keeps running while no client is attached to it. Given `run_as`, the process is run as
+    /// another user, where `cmd` is expected to already be wrapped by it.
+    #[allow(clippy::too_many_arguments)]
     pub async fn spawn(
         &self,
         cmd: String,
         environment: Environment,
         current_dir: Option<PathBuf>,
         pty: Option<PtySize>,
+        output_window: Option<usize>,
+        timestamped: bool,
+        session: Option<SessionId>,
+        limits: ProcLimits,
+        kill_tree: bool,
+        run_as: Option<RunAs>,
         reply: Box<dyn Reply<Data = DistantResponseData>>,
     ) -> io::Result<ProcessId> {
         let (cb, rx) = oneshot::channel();
@@ -77,6 +89,12 @@ impl ProcessChannel {
                 environment,
                 current_dir,
                 pty,
+                output_window,
+                timestamped,
+                session,
+                limits,
+                kill_tree,
+                run_as,
                 reply,
                 cb,
             })
@@ -108,6 +126,54 @@ impl ProcessChannel {
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to stdin dropped"))?
     }
 
+    /// Closes stdin of a running process.
+    pub async fn close_stdin(&self, id: ProcessId) -> io::Result<()> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerProcessMsg::StdinClose { id, cb })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal process task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to stdin close dropped"))?
+    }
+
+    /// Acknowledges `len` bytes of output sent by a running process, allowing it to send more.
+    pub async fn ack_output(&self, id: ProcessId, len: usize) -> io::Result<()> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerProcessMsg::OutputAck { id, len, cb })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal process task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to output ack dropped"))?
+    }
+
+    /// Sends the output of a running process to `reply` going forward, in place of the reply of
+    /// the request that spawned it (or last attached to it). The process is found by `session`
+    /// when given, otherwise by `id`, and its id is returned. When `replay` is true, output of a
+    /// persistent process that could not be sent while no client was attached is sent first.
+    pub async fn attach(
+        &self,
+        id: ProcessId,
+        session: Option<SessionId>,
+        replay: bool,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) -> io::Result<ProcessId> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerProcessMsg::Attach {
+                id,
+                session,
+                replay,
+                reply,
+                cb,
+            })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal process task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to attach dropped"))?
+    }
+
     /// Kills a running process, including persistent processes if `force` is true. Will fail if
     /// unable to kill the process or `force` is false when the process is persistent.
     pub async fn kill(&self, id: ProcessId) -> io::Result<()> {
@@ -119,6 +185,29 @@ impl ProcessChannel {
         rx.await
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to kill dropped"))?
     }
+
+    /// Sends `signal` to a running process. Will fail if unable to send the signal, such as when
+    /// it is not supported by the platform of the server.
+    pub async fn signal(&self, id: ProcessId, signal: Signal) -> io::Result<()> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerProcessMsg::Signal { id, signal, cb })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal process task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to signal dropped"))?
+    }
+
+    /// Returns information about every running process spawned by the server
+    pub async fn list(&self) -> io::Result<Vec<ProcInfo>> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerProcessMsg::List { cb })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal process task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to list dropped"))
+    }
 }
 
 /// Internal message to pass to our task below to perform some action.
@@ -128,6 +217,12 @@ enum InnerProcessMsg {
         environment: Environment,
         current_dir: Option<PathBuf>,
         pty: Option<PtySize>,
+        output_window: Option<usize>,
+        timestamped: bool,
+        session: Option<SessionId>,
+        limits: ProcLimits,
+        kill_tree: bool,
+        run_as: Option<RunAs>,
         reply: Box<dyn Reply<Data = DistantResponseData>>,
         cb: oneshot::Sender<io::Result<ProcessId>>,
     },
@@ -141,16 +236,44 @@ enum InnerProcessMsg {
         data: Vec<u8>,
         cb: oneshot::Sender<io::Result<()>>,
     },
+    StdinClose {
+        id: ProcessId,
+        cb: oneshot::Sender<io::Result<()>>,
+    },
+    OutputAck {
+        id: ProcessId,
+        len: usize,
+        cb: oneshot::Sender<io::Result<()>>,
+    },
+    Attach {
+        id: ProcessId,
+        session: Option<SessionId>,
+        replay: bool,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+        cb: oneshot::Sender<io::Result<ProcessId>>,
+    },
     Kill {
         id: ProcessId,
         cb: oneshot::Sender<io::Result<()>>,
     },
+    Signal {
+        id: ProcessId,
+        signal: Signal,
+        cb: oneshot::Sender<io::Result<()>>,
+    },
+    List {
+        cb: oneshot::Sender<Vec<ProcInfo>>,
+    },
     InternalRemove {
         id: ProcessId,
     },
 }
 
-async fn process_task(tx: mpsc::Sender<InnerProcessMsg>, mut rx: mpsc::Receiver<InnerProcessMsg>) {
+async fn process_task(
+    tx: mpsc::Sender<InnerProcessMsg>,
+    mut rx: mpsc::Receiver<InnerProcessMsg>,
+    output_limits: OutputLimits,
+) {
     let mut processes: HashMap<ProcessId, ProcessInstance> = HashMap::new();
 
     while let Some(msg) = rx.recv().await {
@@ -160,11 +283,30 @@ async fn process_task(tx: mpsc::Sender<InnerProcessMsg>, mut rx: mpsc::Receiver<
                 environment,
                 current_dir,
                 pty,
+                output_window,
+                timestamped,
+                session,
+                limits,
+                kill_tree,
+                run_as,
                 reply,
                 cb,
             } => {
                 let _ = cb.send(
-                    match ProcessInstance::spawn(cmd, environment, current_dir, pty, reply) {
+                    match ProcessInstance::spawn(
+                        cmd,
+                        environment,
+                        current_dir,
+                        pty,
+                        output_window,
+                        timestamped,
+                        session,
+                        &limits,
+                        kill_tree,
+                        run_as.as_ref(),
+                        &output_limits,
+                        reply,
+                    ) {
                         Ok(mut process) => {
                             let id = process.id;
 
@@ -206,6 +348,59 @@ async fn process_task(tx: mpsc::Sender<InnerProcessMsg>, mut rx: mpsc::Receiver<
                     )),
                 });
             }
+            InnerProcessMsg::StdinClose { id, cb } => {
+                let _ = cb.send(match processes.get_mut(&id) {
+                    Some(process) => process.close_stdin().await,
+                    None => Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("No process found with id {id}"),
+                    )),
+                });
+            }
+            InnerProcessMsg::OutputAck { id, len, cb } => {
+                let _ = cb.send(match processes.get(&id) {
+                    Some(process) => {
+                        process.ack_output(len);
+                        Ok(())
+                    }
+                    None => Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("No process found with id {id}"),
+                    )),
+                });
+            }
+            InnerProcessMsg::Attach {
+                id,
+                session,
+                replay,
+                reply,
+                cb,
+            } => {
+                let process = match session {
+                    Some(session) => processes
+                        .values()
+                        .find(|process| process.session == Some(session))
+                        .ok_or_else(|| {
+                            io::Error::new(
+                                io::ErrorKind::NotFound,
+                                format!("No process found with session {session}"),
+                            )
+                        }),
+                    None => processes.get(&id).ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("No process found with id {id}"),
+                        )
+                    }),
+                };
+                let _ = cb.send(match process {
+                    Ok(process) => {
+                        process.attach(reply, replay).await;
+                        Ok(process.id)
+                    }
+                    Err(x) => Err(x),
+                });
+            }
             InnerProcessMsg::Kill { id, cb } => {
                 let _ = cb.send(match processes.get_mut(&id) {
                     Some(process) => process.killer.kill().await,
@@ -215,6 +410,18 @@ async fn process_task(tx: mpsc::Sender<InnerProcessMsg>, mut rx: mpsc::Receiver<
                     )),
                 });
             }
+            InnerProcessMsg::Signal { id, signal, cb } => {
+                let _ = cb.send(match processes.get_mut(&id) {
+                    Some(process) => process.signal(signal).await,
+                    None => Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("No process found with id {id}"),
+                    )),
+                });
+            }
+            InnerProcessMsg::List { cb } => {
+                let _ = cb.send(processes.values().map(ProcessInstance::info).collect());
+            }
             InnerProcessMsg::InternalRemove { id } => {
                 processes.remove(&id);
             }
@@ -1,13 +1,39 @@
 use crate::{
     api::local::process::{
-        InputChannel, OutputChannel, Process, ProcessKiller, ProcessPty, PtyProcess, SimpleProcess,
+        send_signal, BufferedOutputChannel, InputChannel, OutputChannel, OutputLimits, Process,
+        ProcessKiller, ProcessPty, ProcessTree, PtyProcess, RunAs, SimpleProcess, TreeKiller,
+    },
+    api::local::state::process_status,
+    data::{
+        DistantResponseData, Environment, OutputStamp, ProcInfo, ProcLimits, ProcStatus, ProcessId,
+        PtySize, SessionId, Signal,
     },
-    data::{DistantResponseData, Environment, ProcessId, PtySize},
 };
 use distant_net::server::Reply;
 use log::*;
-use std::{future::Future, io, path::PathBuf};
-use tokio::task::JoinHandle;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+use tokio::{
+    sync::{mpsc, watch, Mutex, Semaphore},
+    task::JoinHandle,
+};
+
+/// Maximum bytes of output of a persistent process kept while no client is attached to it, beyond
+/// which the oldest output is dropped
+const MAX_UNSENT_OUTPUT: usize = 256 * 1024;
+
+/// End of file character of a terminal (Ctrl-D), which is how stdin of a process in a pty is
+/// closed as the pty itself stays open for its output
+const PTY_EOF: u8 = 0x04;
 
 /// Holds information related to a spawned process on the server
 pub struct ProcessInstance {
@@ -15,10 +41,19 @@ pub struct ProcessInstance {
     pub args: Vec<String>,
 
     pub id: ProcessId,
+    pub pid: Option<u32>,
+    pub session: Option<SessionId>,
     pub stdin: Option<Box<dyn InputChannel>>,
     pub killer: Box<dyn ProcessKiller>,
     pub pty: Box<dyn ProcessPty>,
+    pub started: Instant,
+
+    /// Tree of the process that is killed along with it, if spawned to kill its tree
+    #[cfg_attr(windows, allow(dead_code))]
+    tree: Option<Arc<ProcessTree>>,
 
+    output_window: Option<OutputWindow>,
+    reply: AttachedReply,
     stdout_task: Option<JoinHandle<io::Result<()>>>,
     stderr_task: Option<JoinHandle<io::Result<()>>>,
     wait_task: Option<JoinHandle<io::Result<()>>>,
@@ -30,6 +65,11 @@ impl Drop for ProcessInstance {
         // Drop stdin first to close it
         self.stdin = None;
 
+        // Wake up any output tasks waiting on acknowledgements that will never arrive
+        if let Some(window) = self.output_window.as_ref() {
+            window.close();
+        }
+
         // Clear out our tasks if we still have them
         let stdout_task = self.stdout_task.take();
         let stderr_task = self.stderr_task.take();
@@ -58,13 +98,24 @@ impl Drop for ProcessInstance {
 }
 
 impl ProcessInstance {
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn(
         cmd: String,
         environment: Environment,
         current_dir: Option<PathBuf>,
         pty: Option<PtySize>,
+        output_window: Option<usize>,
+        timestamped: bool,
+        session: Option<SessionId>,
+        limits: &ProcLimits,
+        kill_tree: bool,
+        run_as: Option<&RunAs>,
+        output_limits: &OutputLimits,
         reply: Box<dyn Reply<Data = DistantResponseData>>,
     ) -> io::Result<Self> {
+        let output_window = output_window.map(OutputWindow::new).transpose()?;
+        let reply = AttachedReply::new(reply);
+
         // Build out the command and args from our string
         let mut cmd_and_args = if cfg!(windows) {
             winsplit::split(&cmd)
@@ -90,27 +141,88 @@ impl ProcessInstance {
                 environment,
                 current_dir,
                 size,
+                limits,
             )?),
             None => Box::new(SimpleProcess::spawn(
                 cmd.clone(),
                 args.clone(),
                 environment,
                 current_dir,
+                limits,
+                kill_tree,
+                #[cfg(unix)]
+                run_as.and_then(RunAs::switch),
             )?),
         };
 
         let id = child.id();
+        let pid = child.pid();
         let stdin = child.take_stdin();
-        let stdout = child.take_stdout();
-        let stderr = child.take_stderr();
-        let killer = child.clone_killer();
+        let mut stdout = child.take_stdout();
+        let mut stderr = child.take_stderr();
         let pty = child.clone_pty();
 
+        // NOTE: A process within a pty already leads a process group of its own on Unix, being
+        //       the leader of the session of the pty
+        let tree = if kill_tree {
+            let tree = pid
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::Other,
+                        "Unable to find tree of process as it has no pid",
+                    )
+                })
+                .and_then(ProcessTree::attach);
+            match tree {
+                Ok(tree) => Some(Arc::new(tree)),
+                Err(x) => {
+                    // Nothing else is left to kill the process once we fail
+                    let mut killer = child.clone_killer();
+                    tokio::spawn(async move { killer.kill().await });
+                    return Err(x);
+                }
+            }
+        } else {
+            None
+        };
+        let killer: Box<dyn ProcessKiller> = match tree.as_ref() {
+            Some(tree) => Box::new(TreeKiller::new(Arc::clone(tree), child.clone_killer())),
+            None => child.clone_killer(),
+        };
+
+        // When limited, output is read eagerly into a buffer so that the process keeps running
+        // even when its output cannot be sent as fast as it is produced
+        if output_limits.max_memory.is_some() {
+            let buffer = |channel: Box<dyn OutputChannel>| -> Box<dyn OutputChannel> {
+                Box::new(BufferedOutputChannel::new(channel, output_limits.clone()))
+            };
+            stdout = stdout.map(buffer);
+            stderr = stderr.map(buffer);
+        }
+
+        // Output of a persistent process is kept aside (to be replayed) while it cannot be sent,
+        // rather than holding up the process until another client attaches
+        let persist = session.is_some();
+        let prompts = run_as.is_some_and(RunAs::prompts);
+
+        // Both output tasks stamp their output from the same sequence
+        let stamper = timestamped.then(|| Arc::new(OutputStamper::new()));
+
+        // Each output task holds onto a sender so we can tell when all of them have finished,
+        // which only matters when output can be held back waiting on acknowledgements or buffered
+        let (output_done_tx, output_done_rx) = mpsc::channel::<()>(1);
+
         // Spawn a task that sends stdout as a response
         let stdout_task = match stdout {
             Some(stdout) => {
-                let reply = reply.clone_reply();
-                let task = tokio::spawn(stdout_task(id, stdout, reply));
+                let reply = reply.clone();
+                let window = output_window.clone();
+                let stamper = stamper.clone();
+                let done_tx = output_done_tx.clone();
+                let task = tokio::spawn(async move {
+                    let _done_tx = done_tx;
+                    stdout_task(id, stdout, window, stamper, persist, reply).await
+                });
                 Some(task)
             }
             None => None,
@@ -119,30 +231,132 @@ impl ProcessInstance {
         // Spawn a task that sends stderr as a response
         let stderr_task = match stderr {
             Some(stderr) => {
-                let reply = reply.clone_reply();
-                let task = tokio::spawn(stderr_task(id, stderr, reply));
+                let reply = reply.clone();
+                let window = output_window.clone();
+                let stamper = stamper.clone();
+                let done_tx = output_done_tx.clone();
+                let task = tokio::spawn(async move {
+                    let _done_tx = done_tx;
+                    stderr_task(id, stderr, window, stamper, persist, prompts, reply).await
+                });
                 Some(task)
             }
             None => None,
         };
+        drop(output_done_tx);
+
+        // When flow controlled or buffered, output may still be waiting to be sent after the
+        // process exits, so we hold off on reporting the exit until the output is drained
+        let output_done_rx = (output_window.is_some() || output_limits.max_memory.is_some())
+            .then_some(output_done_rx);
 
         // Spawn a task that waits on the process to exit but can also
         // kill the process when triggered
-        let wait_task = Some(tokio::spawn(wait_task(id, child, reply)));
+        let wait_task = Some(tokio::spawn(wait_task(
+            id,
+            child,
+            output_done_rx,
+            reply.clone(),
+        )));
 
         Ok(ProcessInstance {
             cmd,
             args,
             id,
+            pid,
+            session,
             stdin,
             killer,
             pty,
+            started: Instant::now(),
+            tree,
+            output_window,
+            reply,
             stdout_task,
             stderr_task,
             wait_task,
         })
     }
 
+    /// Sends the output of the process (and its exit) to `reply` going forward, in place of the
+    /// client that was receiving it before, such as when a client reattaches after its connection
+    /// dropped, first sending output that could not be sent while no client was attached if
+    /// `replay` is true
+    pub async fn attach(&self, reply: Box<dyn Reply<Data = DistantResponseData>>, replay: bool) {
+        // Output the previous client never acknowledged is not held against the new one
+        if let Some(window) = self.output_window.as_ref() {
+            window.reset();
+        }
+
+        self.reply.attach(reply, replay).await;
+    }
+
+    /// Sends `signal` to the process (and the rest of its tree if spawned to kill its tree),
+    /// where killing it goes through its killer as with any other kill (as does asking it to
+    /// terminate on Windows, which has no signals)
+    pub async fn signal(&mut self, signal: Signal) -> io::Result<()> {
+        if signal == Signal::Kill || (cfg!(windows) && signal == Signal::Term) {
+            return self.killer.kill().await;
+        }
+
+        #[cfg(unix)]
+        if let Some(tree) = self.tree.as_ref() {
+            return tree.signal(signal);
+        }
+
+        match self.pid {
+            Some(pid) => send_signal(pid, signal),
+            None => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("Process {} has no known pid to signal", self.id),
+            )),
+        }
+    }
+
+    /// Closes stdin of the process so that it reads EOF, failing if it was already closed
+    pub async fn close_stdin(&mut self) -> io::Result<()> {
+        let mut stdin = self.stdin.take().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("Process {} stdin is closed", self.id),
+            )
+        })?;
+
+        if self.pty.pty_size().is_some() {
+            stdin.send(&[PTY_EOF]).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns information about the process to report when listing processes
+    pub fn info(&self) -> ProcInfo {
+        let cmd = std::iter::once(self.cmd.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        ProcInfo {
+            id: Some(self.id),
+            session: self.session,
+            pid: self.pid,
+            cmd,
+            uptime: self.started.elapsed().as_secs(),
+            status: match self.pid {
+                Some(pid) => process_status(pid).unwrap_or(ProcStatus::Exited),
+                None => ProcStatus::Unknown,
+            },
+        }
+    }
+
+    /// Acknowledges that `len` bytes of output were consumed by the client, allowing that much
+    /// more output to be sent. Does nothing if the process was not spawned with an output window
+    pub fn ack_output(&self, len: usize) {
+        if let Some(window) = self.output_window.as_ref() {
+            window.release(len);
+        }
+    }
+
     /// Invokes the function once the process has completed
     ///
     /// NOTE: Can only be used with one function. All future calls
@@ -163,17 +377,266 @@ impl ProcessInstance {
     }
 }
 
+/// Limits how many bytes of output can be sent for a process before the client acknowledges them
+#[derive(Clone)]
+struct OutputWindow {
+    size: usize,
+    permits: Arc<Semaphore>,
+}
+
+impl OutputWindow {
+    fn new(size: usize) -> io::Result<Self> {
+        if size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Output window cannot be zero",
+            ));
+        }
+
+        // Permits are acquired in batches of at most u32::MAX
+        let size = size.min(u32::MAX as usize);
+        Ok(Self {
+            size,
+            permits: Arc::new(Semaphore::new(size)),
+        })
+    }
+
+    /// Waits until `len` bytes (no more than the window size) can be sent and reserves them
+    async fn reserve(&self, len: usize) -> io::Result<()> {
+        self.permits
+            .acquire_many(len as u32)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Output window closed"))?
+            .forget();
+        Ok(())
+    }
+
+    /// Frees up `len` bytes to be sent, never growing beyond the window size
+    fn release(&self, len: usize) {
+        let outstanding = self.size - self.permits.available_permits();
+        self.permits.add_permits(len.min(outstanding));
+    }
+
+    /// Frees up everything waiting on acknowledgement
+    fn reset(&self) {
+        self.release(self.size);
+    }
+
+    fn close(&self) {
+        self.permits.close();
+    }
+}
+
+/// What to do with data that fails to send to the attached reply
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Unsent {
+    /// Fail, losing the data
+    Fail,
+
+    /// Wait for another reply to be attached, sending the data to it instead
+    WaitForAttach,
+
+    /// Keep the data to replay to the next reply that is attached asking for it
+    Keep,
+}
+
+/// Reply shared by the tasks of a process, which can be swapped for the reply of another request
+/// that attaches to the process
+#[derive(Clone)]
+struct AttachedReply {
+    reply: Arc<watch::Sender<Arc<dyn Reply<Data = DistantResponseData>>>>,
+
+    /// Output that could not be sent, which is locked while swapping replies so that nothing is
+    /// sent to a newly-attached reply ahead of the output replayed to it
+    unsent: Arc<Mutex<UnsentOutput>>,
+}
+
+impl AttachedReply {
+    fn new(reply: Box<dyn Reply<Data = DistantResponseData>>) -> Self {
+        Self {
+            reply: Arc::new(watch::channel(Arc::from(reply)).0),
+            unsent: Arc::new(Mutex::new(UnsentOutput::default())),
+        }
+    }
+
+    /// Swaps in `reply`, first sending it any output that was kept when `replay` is true, and
+    /// otherwise discarding that output
+    async fn attach(&self, reply: Box<dyn Reply<Data = DistantResponseData>>, replay: bool) {
+        let reply: Arc<dyn Reply<Data = DistantResponseData>> = Arc::from(reply);
+        let mut unsent = self.unsent.lock().await;
+        self.reply.send_replace(Arc::clone(&reply));
+
+        let msgs = std::mem::take(&mut *unsent).msgs;
+        if replay {
+            for msg in msgs {
+                if let Err(x) = reply.send(msg).await {
+                    debug!("Failed to replay output to newly-attached client: {x}");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Sends `data` to the attached reply, sending it to a newly-attached reply instead if one
+    /// is attached while waiting to send (such as when the connection of the previous reply is
+    /// no longer read), and otherwise handling a failure to send based on `unsent`
+    async fn send(&self, data: DistantResponseData, unsent: Unsent) -> io::Result<()> {
+        let mut rx = self.reply.subscribe();
+        loop {
+            // Waits on any output being replayed to a newly-attached reply
+            let reply = {
+                let _unsent = self.unsent.lock().await;
+                Arc::clone(&rx.borrow_and_update())
+            };
+
+            tokio::select! {
+                result = reply.send(data.clone()) => match result {
+                    Ok(()) => return Ok(()),
+                    Err(x) => match unsent {
+                        Unsent::Fail => return Err(x),
+                        Unsent::WaitForAttach => {
+                            debug!("Waiting on another client to attach after failing to send: {x}");
+                            if rx.changed().await.is_err() {
+                                return Err(x);
+                            }
+                        }
+                        Unsent::Keep => {
+                            // Another reply may have been attached since, which gets the data
+                            let mut unsent = self.unsent.lock().await;
+                            if rx.has_changed().unwrap_or(false) {
+                                continue;
+                            }
+
+                            trace!("Keeping output of persistent process to replay later: {x}");
+                            unsent.push(data);
+                            return Ok(());
+                        }
+                    },
+                },
+                _ = rx.changed() => continue,
+            }
+        }
+    }
+}
+
+/// Output of a persistent process that could not be sent, holding onto only the most recent
+/// [`MAX_UNSENT_OUTPUT`] bytes
+#[derive(Default)]
+struct UnsentOutput {
+    msgs: VecDeque<DistantResponseData>,
+    len: usize,
+}
+
+impl UnsentOutput {
+    fn push(&mut self, msg: DistantResponseData) {
+        self.len += Self::len_of(&msg);
+        self.msgs.push_back(msg);
+
+        while self.len > MAX_UNSENT_OUTPUT && self.msgs.len() > 1 {
+            if let Some(msg) = self.msgs.pop_front() {
+                self.len -= Self::len_of(&msg);
+            }
+        }
+    }
+
+    fn len_of(msg: &DistantResponseData) -> usize {
+        match msg {
+            DistantResponseData::ProcStdout { data, .. }
+            | DistantResponseData::ProcStderr { data, .. } => data.len(),
+            DistantResponseData::ProcPrompt { prompt, .. } => prompt.len(),
+            _ => 0,
+        }
+    }
+}
+
+/// Stamps output of a process with its position among all of the stdout and stderr of the process
+struct OutputStamper {
+    next_seq: AtomicU64,
+    started: Instant,
+}
+
+impl OutputStamper {
+    fn new() -> Self {
+        Self {
+            next_seq: AtomicU64::new(0),
+            started: Instant::now(),
+        }
+    }
+
+    /// Stamps `count` chunks read just now, returning the stamp of the first chunk, where the
+    /// rest of the chunks follow it in sequence
+    fn stamp(&self, count: usize) -> OutputStamp {
+        OutputStamp {
+            seq: self.next_seq.fetch_add(count as u64, Ordering::Relaxed),
+            timestamp: self.started.elapsed().as_micros() as u64,
+        }
+    }
+}
+
+/// Sends `data` using `make_msg`, splitting it up to fit within the output window if provided,
+/// and stamping each chunk using `stamper` if provided
+///
+/// When `persist` is true, output that cannot be sent is kept to be replayed later rather than
+/// waiting on another client to attach.
+async fn send_output(
+    data: Vec<u8>,
+    window: Option<&OutputWindow>,
+    stamper: Option<&OutputStamper>,
+    persist: bool,
+    reply: &AttachedReply,
+    make_msg: impl Fn(Vec<u8>, Option<OutputStamp>) -> DistantResponseData,
+) -> io::Result<()> {
+    let unsent = if persist {
+        Unsent::Keep
+    } else {
+        Unsent::WaitForAttach
+    };
+    let send = |msg| reply.send(msg, unsent);
+
+    // Output is stamped as soon as it is read, rather than once the window has room for it, so
+    // that stdout and stderr are ordered by when the process wrote them
+    let chunk_size = window.map_or(data.len(), |window| window.size).max(1);
+    let mut stamp = stamper.map(|stamper| stamper.stamp(data.len().div_ceil(chunk_size).max(1)));
+    let mut next_stamp = move || {
+        let current = stamp;
+        if let Some(stamp) = stamp.as_mut() {
+            stamp.seq += 1;
+        }
+        current
+    };
+
+    match window {
+        Some(window) => {
+            for chunk in data.chunks(window.size) {
+                window.reserve(chunk.len()).await?;
+                send(make_msg(chunk.to_vec(), next_stamp())).await?;
+            }
+            Ok(())
+        }
+        None => send(make_msg(data, next_stamp())).await,
+    }
+}
+
 async fn stdout_task(
     id: ProcessId,
     mut stdout: Box<dyn OutputChannel>,
-    reply: Box<dyn Reply<Data = DistantResponseData>>,
+    window: Option<OutputWindow>,
+    stamper: Option<Arc<OutputStamper>>,
+    persist: bool,
+    reply: AttachedReply,
 ) -> io::Result<()> {
     loop {
         match stdout.recv().await {
             Ok(Some(data)) => {
-                reply
-                    .send(DistantResponseData::ProcStdout { id, data })
-                    .await?;
+                send_output(
+                    data,
+                    window.as_ref(),
+                    stamper.as_deref(),
+                    persist,
+                    &reply,
+                    |data, stamp| DistantResponseData::ProcStdout { id, data, stamp },
+                )
+                .await?;
             }
             Ok(None) => return Ok(()),
             Err(x) => return Err(x),
@@ -181,17 +644,48 @@ async fn stdout_task(
     }
 }
 
+/// Sends stderr of the process as responses, picking out prompts for a password (when `prompts`)
+/// that were written by `sudo` ahead of running the process as another user
 async fn stderr_task(
     id: ProcessId,
     mut stderr: Box<dyn OutputChannel>,
-    reply: Box<dyn Reply<Data = DistantResponseData>>,
+    window: Option<OutputWindow>,
+    stamper: Option<Arc<OutputStamper>>,
+    persist: bool,
+    prompts: bool,
+    reply: AttachedReply,
 ) -> io::Result<()> {
     loop {
         match stderr.recv().await {
-            Ok(Some(data)) => {
-                reply
-                    .send(DistantResponseData::ProcStderr { id, data })
+            Ok(Some(mut data)) => {
+                let prompt = if prompts {
+                    RunAs::split_prompt(&mut data)
+                } else {
+                    None
+                };
+
+                if !data.is_empty() {
+                    send_output(
+                        data,
+                        window.as_ref(),
+                        stamper.as_deref(),
+                        persist,
+                        &reply,
+                        |data, stamp| DistantResponseData::ProcStderr { id, data, stamp },
+                    )
                     .await?;
+                }
+
+                // NOTE: Prompts are not part of the output window, as they are not acknowledged
+                if let Some(prompt) = prompt {
+                    let msg = DistantResponseData::ProcPrompt { id, prompt };
+                    let unsent = if persist {
+                        Unsent::Keep
+                    } else {
+                        Unsent::WaitForAttach
+                    };
+                    reply.send(msg, unsent).await?;
+                }
             }
             Ok(None) => return Ok(()),
             Err(x) => return Err(x),
@@ -202,20 +696,25 @@ async fn stderr_task(
 async fn wait_task(
     id: ProcessId,
     mut child: Box<dyn Process>,
-    reply: Box<dyn Reply<Data = DistantResponseData>>,
+    output_done_rx: Option<mpsc::Receiver<()>>,
+    reply: AttachedReply,
 ) -> io::Result<()> {
     let status = child.wait().await;
 
-    match status {
-        Ok(status) => {
-            reply
-                .send(DistantResponseData::ProcDone {
-                    id,
-                    success: status.success,
-                    code: status.code,
-                })
-                .await
-        }
-        Err(x) => reply.send(DistantResponseData::from(x)).await,
+    // Nothing is ever sent, so this completes once every output task has dropped its sender
+    if let Some(mut rx) = output_done_rx {
+        let _ = rx.recv().await;
     }
+
+    // NOTE: The exit is not held waiting on another client to attach, as the process is
+    //       forgotten once it has exited
+    let msg = match status {
+        Ok(status) => DistantResponseData::ProcDone {
+            id,
+            success: status.success,
+            code: status.code,
+        },
+        Err(x) => DistantResponseData::from(x),
+    };
+    reply.send(msg, Unsent::Fail).await
 }
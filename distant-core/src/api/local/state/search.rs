@@ -1,20 +1,35 @@
+use crate::constants::SERVER_SEARCH_IDLE_TIMEOUT;
 use crate::data::{
-    DistantResponseData, SearchId, SearchQuery, SearchQueryContentsMatch, SearchQueryMatch,
-    SearchQueryMatchData, SearchQueryOptions, SearchQueryPathMatch, SearchQuerySubmatch,
-    SearchQueryTarget,
+    DistantResponseData, SearchId, SearchQuery, SearchQueryContentsMatch, SearchQueryContextLine,
+    SearchQueryMatch, SearchQueryMatchData, SearchQueryOptions, SearchQueryPathMatch,
+    SearchQueryRootError, SearchQuerySkipped, SearchQuerySubmatch, SearchQueryTarget,
 };
 use distant_net::server::Reply;
 use grep::{
     matcher::Matcher,
     regex::{RegexMatcher, RegexMatcherBuilder},
-    searcher::{BinaryDetection, Searcher, SearcherBuilder, Sink, SinkMatch},
+    searcher::{
+        BinaryDetection, Searcher, SearcherBuilder, Sink, SinkContext, SinkContextKind, SinkFinish,
+        SinkMatch,
+    },
 };
 use ignore::{
     types::TypesBuilder, DirEntry, ParallelVisitor, ParallelVisitorBuilder, WalkBuilder,
     WalkParallel,
 };
 use log::*;
-use std::{cmp, collections::HashMap, io, ops::Deref, path::Path};
+use std::{
+    cmp,
+    collections::HashMap,
+    io,
+    ops::Deref,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, UNIX_EPOCH},
+};
 use tokio::{
     sync::{broadcast, mpsc, oneshot},
     task::JoinHandle,
@@ -37,8 +52,14 @@ impl Drop for SearchState {
 
 impl SearchState {
     pub fn new() -> Self {
+        Self::with_idle_timeout(SERVER_SEARCH_IDLE_TIMEOUT)
+    }
+
+    /// Creates state where on-demand searches are canceled once `idle_timeout` passes without
+    /// their next page being requested
+    pub fn with_idle_timeout(idle_timeout: Duration) -> Self {
         let (tx, rx) = mpsc::channel(1);
-        let task = tokio::spawn(search_task(tx.clone(), rx));
+        let task = tokio::spawn(search_task(tx.clone(), rx, idle_timeout));
 
         Self {
             channel: SearchChannel { tx },
@@ -46,7 +67,6 @@ impl SearchState {
         }
     }
 
-    #[allow(dead_code)]
     pub fn clone_channel(&self) -> SearchChannel {
         self.channel.clone()
     }
@@ -108,6 +128,17 @@ impl SearchChannel {
         rx.await
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to cancel dropped"))?
     }
+
+    /// Requests the next page of results of an active search performed on demand
+    pub async fn next(&self, id: SearchId) -> io::Result<()> {
+        let (cb, rx) = oneshot::channel();
+        self.tx
+            .send(InnerSearchMsg::Next { id, cb })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal search task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to next dropped"))?
+    }
 }
 
 /// Internal message to pass to our task below to perform some action
@@ -121,13 +152,29 @@ enum InnerSearchMsg {
         id: SearchId,
         cb: oneshot::Sender<io::Result<()>>,
     },
+    Next {
+        id: SearchId,
+        cb: oneshot::Sender<io::Result<()>>,
+    },
     InternalRemove {
         id: SearchId,
     },
 }
 
-async fn search_task(tx: mpsc::Sender<InnerSearchMsg>, mut rx: mpsc::Receiver<InnerSearchMsg>) {
-    let mut searches: HashMap<SearchId, broadcast::Sender<()>> = HashMap::new();
+/// Handle to an active search, used to cancel it or request its next page
+struct SearchHandle {
+    cancel_tx: broadcast::Sender<()>,
+
+    /// Present only for searches performed on demand
+    next_tx: Option<mpsc::UnboundedSender<()>>,
+}
+
+async fn search_task(
+    tx: mpsc::Sender<InnerSearchMsg>,
+    mut rx: mpsc::Receiver<InnerSearchMsg>,
+    idle_timeout: Duration,
+) {
+    let mut searches: HashMap<SearchId, SearchHandle> = HashMap::new();
 
     while let Some(msg) = rx.recv().await {
         match msg {
@@ -139,15 +186,29 @@ async fn search_task(tx: mpsc::Sender<InnerSearchMsg>, mut rx: mpsc::Receiver<In
                     Ok(executor) => executor,
                     Err(x) => {
                         let _ = cb.send(Err(x));
-                        return;
+                        continue;
                     }
                 };
 
                 // Get the unique search id
                 let id = executor.id();
 
-                // Queue up our search internally with a cancel sender
-                searches.insert(id, executor.take_cancel_tx().unwrap());
+                // Queue up our search internally with a cancel sender, and a sender to request
+                // more results if performed on demand
+                let cancel_tx = executor.take_cancel_tx().unwrap();
+                let (next_tx, next_rx) = if options.on_demand {
+                    let (next_tx, next_rx) = mpsc::unbounded_channel();
+                    (Some(next_tx), Some(next_rx))
+                } else {
+                    (None, None)
+                };
+                searches.insert(
+                    id,
+                    SearchHandle {
+                        cancel_tx: cancel_tx.clone(),
+                        next_tx,
+                    },
+                );
 
                 // Report back the search id
                 let _ = cb.send(Ok(id));
@@ -157,17 +218,23 @@ async fn search_task(tx: mpsc::Sender<InnerSearchMsg>, mut rx: mpsc::Receiver<In
                     id,
                     options,
                     rx: executor.take_match_rx().unwrap(),
+                    skipped: executor.skipped(),
+                    errors: executor.root_errors().to_vec(),
                     reply,
+                    cancel_tx,
+                    next_rx,
+                    idle_timeout,
+                    tx: tx.clone(),
                 }
                 .spawn();
 
                 // Spawn our executor to run
-                executor.spawn(tx.clone());
+                executor.spawn();
             }
             InnerSearchMsg::Cancel { id, cb } => {
                 let _ = cb.send(match searches.remove(&id) {
-                    Some(tx) => {
-                        let _ = tx.send(());
+                    Some(handle) => {
+                        let _ = handle.cancel_tx.send(());
                         Ok(())
                     }
                     None => Err(io::Error::new(
@@ -176,6 +243,22 @@ async fn search_task(tx: mpsc::Sender<InnerSearchMsg>, mut rx: mpsc::Receiver<In
                     )),
                 });
             }
+            InnerSearchMsg::Next { id, cb } => {
+                let _ = cb.send(match searches.get(&id).map(|handle| &handle.next_tx) {
+                    Some(Some(next_tx)) => {
+                        let _ = next_tx.send(());
+                        Ok(())
+                    }
+                    Some(None) => Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("[Query {id}] Search is not being performed on demand"),
+                    )),
+                    None => Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("[Query {id}] Requesting next page failed because no search found"),
+                    )),
+                });
+            }
             InnerSearchMsg::InternalRemove { id } => {
                 trace!("[Query {id}] Removing internal tracking");
                 searches.remove(&id);
@@ -187,8 +270,14 @@ async fn search_task(tx: mpsc::Sender<InnerSearchMsg>, mut rx: mpsc::Receiver<In
 struct SearchQueryReporter {
     id: SearchId,
     options: SearchQueryOptions,
-    rx: mpsc::UnboundedReceiver<SearchQueryMatch>,
+    rx: SearchQueryMatchRx,
+    skipped: Arc<SearchQuerySkippedCounter>,
+    errors: Vec<SearchQueryRootError>,
     reply: Box<dyn Reply<Data = DistantResponseData>>,
+    cancel_tx: broadcast::Sender<()>,
+    next_rx: Option<mpsc::UnboundedReceiver<()>>,
+    idle_timeout: Duration,
+    tx: mpsc::Sender<InnerSearchMsg>,
 }
 
 impl SearchQueryReporter {
@@ -202,12 +291,20 @@ impl SearchQueryReporter {
             id,
             options,
             mut rx,
+            skipped,
+            errors,
             reply,
+            cancel_tx,
+            mut next_rx,
+            idle_timeout,
+            tx,
         } = self;
 
         // Queue of matches that we hold until reaching pagination
         let mut matches = Vec::new();
         let mut total_matches_cnt = 0;
+        let mut cancel_rx = cancel_tx.subscribe();
+        let mut stopped = false;
 
         trace!("[Query {id}] Starting reporter with {options:?}");
         while let Some(m) = rx.recv().await {
@@ -235,12 +332,37 @@ impl SearchQueryReporter {
                     {
                         error!("[Query {id}] Failed to send paginated matches: {x}");
                     }
+
+                    // When performed on demand, the search stays paused until the next page is
+                    // requested, as nothing is read from the executor in the meantime
+                    if let Some(next_rx) = next_rx.as_mut() {
+                        tokio::select! {
+                            Some(_) = next_rx.recv() => {
+                                trace!("[Query {id}] Next page requested");
+                            }
+                            _ = cancel_rx.recv() => {
+                                trace!("[Query {id}] Cancelled while paused");
+                                stopped = true;
+                                break;
+                            }
+                            _ = tokio::time::sleep(idle_timeout) => {
+                                debug!("[Query {id}] Cancelling as next page not requested in time");
+                                stopped = true;
+                                break;
+                            }
+                        }
+                    }
                 }
             }
         }
 
-        // Send any remaining matches
-        if !matches.is_empty() {
+        // Stop the executor in case it is still searching, as nothing more will be reported, and
+        // close our end so that it is not left waiting to hand over matches
+        let _ = cancel_tx.send(());
+        drop(rx);
+
+        // Send any remaining matches unless the search was stopped while paused
+        if !stopped && !matches.is_empty() {
             trace!("[Query {id}] Sending {} remaining matches", matches.len());
             if let Err(x) = reply
                 .send(DistantResponseData::SearchResults { id, matches })
@@ -252,31 +374,42 @@ impl SearchQueryReporter {
 
         // Report that we are done
         trace!("[Query {id}] Reporting as done");
-        if let Err(x) = reply.send(DistantResponseData::SearchDone { id }).await {
+        let skipped = skipped.summary();
+        if let Err(x) = reply
+            .send(DistantResponseData::SearchDone {
+                id,
+                skipped,
+                errors,
+            })
+            .await
+        {
             error!("[Query {id}] Failed to send done status: {x}");
         }
+
+        // Once done, we need to send a request to remove the search from our list
+        let _ = tx.send(InnerSearchMsg::InternalRemove { id }).await;
     }
 }
 
 struct SearchQueryExecutor {
     id: SearchId,
     query: SearchQuery,
-    walker: WalkParallel,
+    walker: Option<WalkParallel>,
+    root_errors: Vec<SearchQueryRootError>,
     matcher: RegexMatcher,
 
     cancel_tx: Option<broadcast::Sender<()>>,
     cancel_rx: broadcast::Receiver<()>,
 
-    match_tx: mpsc::UnboundedSender<SearchQueryMatch>,
-    match_rx: Option<mpsc::UnboundedReceiver<SearchQueryMatch>>,
+    match_tx: SearchQueryMatchTx,
+    match_rx: Option<SearchQueryMatchRx>,
+
+    skipped: Arc<SearchQuerySkippedCounter>,
 }
 
 impl SearchQueryExecutor {
     /// Creates a new executor
     pub fn new(query: SearchQuery) -> io::Result<Self> {
-        let (cancel_tx, cancel_rx) = broadcast::channel(1);
-        let (match_tx, match_rx) = mpsc::unbounded_channel();
-
         let regex = query.condition.to_regex_string();
         let mut matcher_builder = RegexMatcherBuilder::new();
         matcher_builder
@@ -297,6 +430,13 @@ impl SearchQueryExecutor {
             return Err(io::Error::new(io::ErrorKind::InvalidInput, "missing paths"));
         }
 
+        if query.options.on_demand && query.options.pagination.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "on demand search missing pagination",
+            ));
+        }
+
         // Build our list of paths so we can ensure we weed out duplicates
         let mut target_paths = Vec::new();
         for mut path in query.paths.iter().map(Deref::deref) {
@@ -333,9 +473,26 @@ impl SearchQueryExecutor {
         target_paths.sort_unstable();
         target_paths.dedup();
 
-        // Construct the walker with our paths
-        let mut walker_builder = WalkBuilder::new(target_paths[0]);
-        for path in &target_paths[1..] {
+        // Paths that cannot be searched are reported when done instead of failing the search, so
+        // that the remaining paths are still searched
+        let mut root_errors = Vec::new();
+        target_paths.retain(|path| match std::fs::metadata(path) {
+            Ok(_) => true,
+            Err(x) => {
+                root_errors.push(SearchQueryRootError {
+                    path: path.to_path_buf(),
+                    error: x.into(),
+                });
+                false
+            }
+        });
+
+        // Construct the walker with our paths, if any are left
+        let Some((first_path, other_paths)) = target_paths.split_first() else {
+            return Ok(Self::new_without_walker(query, matcher, root_errors));
+        };
+        let mut walker_builder = WalkBuilder::new(first_path);
+        for path in other_paths {
             walker_builder.add(path);
         }
 
@@ -349,6 +506,7 @@ impl SearchQueryExecutor {
                     .build()
                     .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?,
             )
+            .hidden(!query.options.follow_hidden)
             .skip_stdout(true);
 
         if query.options.upward {
@@ -369,17 +527,52 @@ impl SearchQueryExecutor {
             );
         }
 
-        Ok(Self {
+        let mut executor = Self::new_without_walker(query, matcher, root_errors);
+        executor.walker = Some(walker_builder.build_parallel());
+        Ok(executor)
+    }
+
+    /// Creates an executor that has nothing to walk, which finishes without any matches
+    fn new_without_walker(
+        query: SearchQuery,
+        matcher: RegexMatcher,
+        root_errors: Vec<SearchQueryRootError>,
+    ) -> Self {
+        let (cancel_tx, cancel_rx) = broadcast::channel(1);
+
+        // Searches performed on demand hold no more than a page of matches that have yet to be
+        // reported, blocking the executor until the page is taken
+        let (match_tx, match_rx) = match query.options.pagination {
+            Some(len) if query.options.on_demand => {
+                let (tx, rx) = mpsc::channel(cmp::max(len as usize, 1));
+                (
+                    SearchQueryMatchTx::Bounded(tx),
+                    SearchQueryMatchRx::Bounded(rx),
+                )
+            }
+            _ => {
+                let (tx, rx) = mpsc::unbounded_channel();
+                (
+                    SearchQueryMatchTx::Unbounded(tx),
+                    SearchQueryMatchRx::Unbounded(rx),
+                )
+            }
+        };
+
+        Self {
             id: rand::random(),
             query,
             matcher,
-            walker: walker_builder.build_parallel(),
+            walker: None,
+            root_errors,
             cancel_tx: Some(cancel_tx),
             cancel_rx,
 
             match_tx,
             match_rx: Some(match_rx),
-        })
+
+            skipped: Arc::new(SearchQuerySkippedCounter::default()),
+        }
     }
 
     pub fn id(&self) -> SearchId {
@@ -390,27 +583,35 @@ impl SearchQueryExecutor {
         self.cancel_tx.take()
     }
 
-    pub fn take_match_rx(&mut self) -> Option<mpsc::UnboundedReceiver<SearchQueryMatch>> {
+    pub fn take_match_rx(&mut self) -> Option<SearchQueryMatchRx> {
         self.match_rx.take()
     }
 
-    /// Runs the executor to completion in another thread
-    pub fn spawn(self, tx: mpsc::Sender<InnerSearchMsg>) {
-        tokio::task::spawn_blocking(move || {
-            let id = self.id;
-            self.run();
+    /// Returns the paths of the query that cannot be searched
+    pub fn root_errors(&self) -> &[SearchQueryRootError] {
+        &self.root_errors
+    }
 
-            // Once complete, we need to send a request to remove the search from our list
-            let _ = tx.blocking_send(InnerSearchMsg::InternalRemove { id });
-        });
+    /// Returns the counts of files skipped, which are complete once the executor has finished
+    pub fn skipped(&self) -> Arc<SearchQuerySkippedCounter> {
+        Arc::clone(&self.skipped)
+    }
+
+    /// Runs the executor to completion in another thread
+    pub fn spawn(self) {
+        tokio::task::spawn_blocking(move || self.run());
     }
 
     fn run(self) {
         let id = self.id;
-        let walker = self.walker;
+        let Some(walker) = self.walker else {
+            trace!("[Query {id}] Nothing to search");
+            return;
+        };
         let tx = self.match_tx;
         let cancel = self.cancel_rx;
         let matcher = self.matcher;
+        let skipped = self.skipped;
 
         // Create our path filter we will use to filter out entries that do not match filter
         let include_path_filter = match self.query.options.include.as_ref() {
@@ -462,6 +663,7 @@ impl SearchQueryExecutor {
             include_path_filter: &include_path_filter,
             exclude_path_filter: &exclude_path_filter,
             options_filter: &options_filter,
+            skipped: &skipped,
         };
 
         // Search all entries for matches and report them
@@ -475,11 +677,27 @@ struct SearchQueryExecutorParallelVistorBuilder<'a> {
     search_id: SearchId,
     target: SearchQueryTarget,
     cancel: broadcast::Receiver<()>,
-    tx: mpsc::UnboundedSender<SearchQueryMatch>,
+    tx: SearchQueryMatchTx,
     matcher: &'a RegexMatcher,
     include_path_filter: &'a SearchQueryPathFilter,
     exclude_path_filter: &'a SearchQueryPathFilter,
     options_filter: &'a SearchQueryOptionsFilter,
+    skipped: &'a SearchQuerySkippedCounter,
+}
+
+impl<'a> SearchQueryExecutorParallelVistorBuilder<'a> {
+    /// Returns a builder for searchers that include the lines of context requested around
+    /// matches, which only applies when searching contents
+    fn searcher_builder(&self) -> SearcherBuilder {
+        let mut builder = SearcherBuilder::new();
+        if self.target == SearchQueryTarget::Contents {
+            let options = &self.options_filter.options;
+            builder
+                .before_context(options.before_context as usize)
+                .after_context(options.after_context as usize);
+        }
+        builder
+    }
 }
 
 impl<'a> ParallelVisitorBuilder<'a> for SearchQueryExecutorParallelVistorBuilder<'a> {
@@ -487,11 +705,17 @@ impl<'a> ParallelVisitorBuilder<'a> for SearchQueryExecutorParallelVistorBuilder
         // For files that are searched as part of a recursive search
         //
         // Details:
-        //     * Will quit early if detecting binary file due to null byte
+        //     * Will quit early if detecting binary file due to null byte, unless binary files
+        //       are not being skipped, in which case they are treated like explicit files
         //
         // NOTE: Searchers are not Send/Sync so we must create them here
-        let implicit_searcher = SearcherBuilder::new()
-            .binary_detection(BinaryDetection::quit(0))
+        let implicit_searcher = self
+            .searcher_builder()
+            .binary_detection(if self.options_filter.options.skip_binary {
+                BinaryDetection::quit(0)
+            } else {
+                BinaryDetection::convert(0)
+            })
             .build();
 
         // For files that are searched because they are provided as one of our initial paths
@@ -501,7 +725,8 @@ impl<'a> ParallelVisitorBuilder<'a> for SearchQueryExecutorParallelVistorBuilder
         //     * Will convert binary data with null bytes into newlines
         //
         // NOTE: Searchers are not Send/Sync so we must create them here
-        let explicit_searcher = SearcherBuilder::new()
+        let explicit_searcher = self
+            .searcher_builder()
             .binary_detection(BinaryDetection::convert(0))
             .build();
 
@@ -516,6 +741,7 @@ impl<'a> ParallelVisitorBuilder<'a> for SearchQueryExecutorParallelVistorBuilder
             include_path_filter: self.include_path_filter,
             exclude_path_filter: self.exclude_path_filter,
             options_filter: self.options_filter,
+            skipped: self.skipped,
         })
     }
 }
@@ -524,13 +750,14 @@ struct SearchQueryExecutorParallelVistor<'a> {
     search_id: SearchId,
     target: SearchQueryTarget,
     cancel: broadcast::Receiver<()>,
-    tx: mpsc::UnboundedSender<SearchQueryMatch>,
+    tx: SearchQueryMatchTx,
     matcher: &'a RegexMatcher,
     implicit_searcher: Searcher,
     explicit_searcher: Searcher,
     include_path_filter: &'a SearchQueryPathFilter,
     exclude_path_filter: &'a SearchQueryPathFilter,
     options_filter: &'a SearchQueryOptionsFilter,
+    skipped: &'a SearchQuerySkippedCounter,
 }
 
 impl<'a> ParallelVisitor for SearchQueryExecutorParallelVistor<'a> {
@@ -565,6 +792,19 @@ impl<'a> ParallelVisitor for SearchQueryExecutorParallelVistor<'a> {
             }
         }
 
+        // Skip reading the contents of files that are too large, which are still counted
+        if self.target == SearchQueryTarget::Contents {
+            if let Some(max_filesize) = self.options_filter.options.max_filesize {
+                let is_too_large = entry.file_type().is_some_and(|ft| ft.is_file())
+                    && entry.metadata().is_ok_and(|m| m.len() > max_filesize);
+                if is_too_large {
+                    trace!("[Query {id}] Skipping {:?} as too large", entry.path());
+                    self.skipped.too_large.fetch_add(1, Ordering::Relaxed);
+                    return WalkState::Continue;
+                }
+            }
+        }
+
         // Pick searcher based on whether this was an explicit or recursive path
         let searcher = if entry.depth() == 0 {
             &mut self.explicit_searcher
@@ -583,7 +823,7 @@ impl<'a> ParallelVisitor for SearchQueryExecutorParallelVistor<'a> {
                         search_id: id,
                         path: entry.path(),
                         matcher: self.matcher,
-                        callback: |m| Ok(self.tx.send(m).is_ok()),
+                        callback: |m| Ok(self.tx.send(m)),
                     },
                 )
             }
@@ -596,7 +836,10 @@ impl<'a> ParallelVisitor for SearchQueryExecutorParallelVistor<'a> {
                     search_id: id,
                     path: entry.path(),
                     matcher: self.matcher,
-                    callback: |m| Ok(self.tx.send(m).is_ok()),
+                    callback: |m| Ok(self.tx.send(m)),
+                    pending: None,
+                    before: Vec::new(),
+                    skipped: self.skipped,
                 },
             ),
         };
@@ -609,6 +852,39 @@ impl<'a> ParallelVisitor for SearchQueryExecutorParallelVistor<'a> {
     }
 }
 
+/// Sender of matches found by an executor to its reporter
+#[derive(Clone)]
+enum SearchQueryMatchTx {
+    Unbounded(mpsc::UnboundedSender<SearchQueryMatch>),
+    Bounded(mpsc::Sender<SearchQueryMatch>),
+}
+
+impl SearchQueryMatchTx {
+    /// Sends a match, blocking while a bounded channel is full, and returns false if the reporter
+    /// is gone
+    fn send(&self, m: SearchQueryMatch) -> bool {
+        match self {
+            Self::Unbounded(tx) => tx.send(m).is_ok(),
+            Self::Bounded(tx) => tx.blocking_send(m).is_ok(),
+        }
+    }
+}
+
+/// Receiver of matches found by an executor
+enum SearchQueryMatchRx {
+    Unbounded(mpsc::UnboundedReceiver<SearchQueryMatch>),
+    Bounded(mpsc::Receiver<SearchQueryMatch>),
+}
+
+impl SearchQueryMatchRx {
+    async fn recv(&mut self) -> Option<SearchQueryMatch> {
+        match self {
+            Self::Unbounded(rx) => rx.recv().await,
+            Self::Bounded(rx) => rx.recv().await,
+        }
+    }
+}
+
 struct SearchQueryPathFilter {
     matcher: Option<RegexMatcher>,
     default_value: bool,
@@ -670,7 +946,63 @@ impl SearchQueryOptionsFilter {
             _ => true,
         };
 
-        file_type_allowed && targeted
+        file_type_allowed && targeted && self.metadata_allowed(entry)
+    }
+
+    /// Returns true if the size and modification time of the entry are within the bounds of the
+    /// options, only reading its metadata if there are bounds to check
+    fn metadata_allowed(&self, entry: &DirEntry) -> bool {
+        let options = &self.options;
+        if options.min_size.is_none()
+            && options.max_size.is_none()
+            && options.modified_after.is_none()
+            && options.modified_before.is_none()
+        {
+            return true;
+        }
+
+        let Ok(metadata) = entry.metadata() else {
+            return false;
+        };
+
+        let size = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        let after = |bound: Option<u64>, value: Option<u64>| match (bound, value) {
+            (Some(bound), Some(value)) => value >= bound,
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        let before = |bound: Option<u64>, value: Option<u64>| match (bound, value) {
+            (Some(bound), Some(value)) => value <= bound,
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+
+        after(options.min_size, Some(size))
+            && before(options.max_size, Some(size))
+            && after(options.modified_after, modified)
+            && before(options.modified_before, modified)
+    }
+}
+
+/// Counts of files skipped by a search, shared between the threads searching
+#[derive(Debug, Default)]
+struct SearchQuerySkippedCounter {
+    too_large: AtomicU64,
+    binary: AtomicU64,
+}
+
+impl SearchQuerySkippedCounter {
+    /// Returns a summary of the files skipped so far
+    fn summary(&self) -> SearchQuerySkipped {
+        SearchQuerySkipped {
+            too_large: self.too_large.load(Ordering::Relaxed),
+            binary: self.binary.load(Ordering::Relaxed),
+        }
     }
 }
 
@@ -744,6 +1076,29 @@ where
     path: &'a Path,
     matcher: &'a M,
     callback: F,
+
+    /// Latest match, held back until its lines of context after it have been collected
+    pending: Option<SearchQueryContentsMatch>,
+
+    /// Lines of context collected for the next match
+    before: Vec<SearchQueryContextLine>,
+
+    /// Counts of files skipped, which includes this file if it is found to be binary
+    skipped: &'a SearchQuerySkippedCounter,
+}
+
+impl<'a, M, F> SearchQueryContentsSink<'a, M, F>
+where
+    M: Matcher,
+    F: FnMut(SearchQueryMatch) -> Result<bool, io::Error>,
+{
+    /// Reports the pending match, if there is one
+    fn flush(&mut self) -> Result<bool, io::Error> {
+        match self.pending.take() {
+            Some(m) => (self.callback)(SearchQueryMatch::Contents(m)),
+            None => Ok(true),
+        }
+    }
 }
 
 impl<'a, M, F> Sink for SearchQueryContentsSink<'a, M, F>
@@ -753,7 +1108,56 @@ where
 {
     type Error = io::Error;
 
-    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, io::Error> {
+    fn context(&mut self, _searcher: &Searcher, ctx: &SinkContext<'_>) -> Result<bool, io::Error> {
+        let line = SearchQueryContextLine {
+            // NOTE: Since we are defining the searcher, we control always including the line
+            //       number, so we can safely unwrap here
+            line_number: ctx.line_number().unwrap(),
+            line: match std::str::from_utf8(ctx.bytes()) {
+                Ok(s) => SearchQueryMatchData::Text(s.to_string()),
+                Err(_) => SearchQueryMatchData::Bytes(ctx.bytes().to_vec()),
+            },
+        };
+
+        match ctx.kind() {
+            SinkContextKind::After => {
+                if let Some(m) = self.pending.as_mut() {
+                    m.context.push(line);
+                }
+                Ok(true)
+            }
+
+            // Context before a match means that the previous match has all of its context
+            SinkContextKind::Before => {
+                self.before.push(line);
+                self.flush()
+            }
+
+            SinkContextKind::Other => Ok(true),
+        }
+    }
+
+    fn binary_data(&mut self, searcher: &Searcher, _: u64) -> Result<bool, io::Error> {
+        // Searches quitting on binary data stop here, skipping the rest of the file
+        if searcher.binary_detection().quit_byte().is_some() {
+            trace!(
+                "[Query {}] Skipping {:?} as binary",
+                self.search_id,
+                self.path
+            );
+            self.skipped.binary.fetch_add(1, Ordering::Relaxed);
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    fn finish(&mut self, _searcher: &Searcher, _: &SinkFinish) -> Result<(), io::Error> {
+        self.flush()?;
+        Ok(())
+    }
+
+    fn matched(&mut self, searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, io::Error> {
         let mut submatches = Vec::new();
 
         // Find all matches within the line
@@ -778,9 +1182,13 @@ where
             );
         }
 
+        // Whatever match came before this one cannot have more context after it
+        let mut should_continue = self.flush()?;
+        let context = std::mem::take(&mut self.before);
+
         // If we have at least one submatch, then we have a match
-        let should_continue = if !submatches.is_empty() {
-            let r#match = SearchQueryMatch::Contents(SearchQueryContentsMatch {
+        if !submatches.is_empty() {
+            self.pending = Some(SearchQueryContentsMatch {
                 path: self.path.to_path_buf(),
                 lines: match std::str::from_utf8(mat.bytes()) {
                     Ok(s) => SearchQueryMatchData::Text(s.to_string()),
@@ -795,12 +1203,14 @@ where
                 //       match, but not inclusive of where within the match
                 absolute_offset: mat.absolute_byte_offset(),
                 submatches,
+                context,
             });
 
-            (self.callback)(r#match)?
-        } else {
-            true
-        };
+            // Without context after matches, there is nothing to wait for
+            if searcher.after_context() == 0 {
+                should_continue = self.flush()?;
+            }
+        }
 
         Ok(should_continue)
     }
@@ -859,7 +1269,11 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -936,7 +1350,11 @@ mod tests {
 
         assert_eq!(
             rx.recv().await,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -1014,7 +1432,11 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -1060,7 +1482,8 @@ mod tests {
                         r#match: SearchQueryMatchData::Text("text".to_string()),
                         start: 21,
                         end: 25,
-                    }]
+                    }],
+                    context: Vec::new(),
                 },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file1.txt")).to_path_buf(),
@@ -1071,7 +1494,8 @@ mod tests {
                         r#match: SearchQueryMatchData::Text("text".to_string()),
                         start: 9,
                         end: 13,
-                    }]
+                    }],
+                    context: Vec::new(),
                 },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file2.txt")).to_path_buf(),
@@ -1082,7 +1506,8 @@ mod tests {
                         r#match: SearchQueryMatchData::Text("text".to_string()),
                         start: 5,
                         end: 9,
-                    }]
+                    }],
+                    context: Vec::new(),
                 }
             ]
         );
@@ -1090,12 +1515,223 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
     }
 
+    #[test(tokio::test)]
+    async fn should_include_context_around_matches_within_contents() {
+        let root = setup_dir(vec![(
+            "file.txt",
+            "one\ntwo\nthree\nfour\nfive\nsix\nseven\n",
+        )]);
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::regex("two|five"),
+            options: SearchQueryOptions {
+                before_context: 1,
+                after_context: 1,
+                ..Default::default()
+            },
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+        let matches = get_matches(rx.recv().await.unwrap())
+            .into_iter()
+            .filter_map(|m| m.into_contents_match())
+            .map(|m| {
+                let context = m
+                    .context
+                    .into_iter()
+                    .map(|c| (c.line_number, c.line.to_string_lossy().into_owned()))
+                    .collect::<Vec<_>>();
+                (m.line_number, context)
+            })
+            .collect::<Vec<_>>();
+
+        // Lines between matches are context after the first and before the second
+        assert_eq!(
+            matches,
+            vec![
+                (
+                    2,
+                    vec![(1, String::from("one\n")), (3, String::from("three\n"))]
+                ),
+                (
+                    5,
+                    vec![(4, String::from("four\n")), (6, String::from("six\n"))]
+                ),
+            ]
+        );
+
+        let data = rx.recv().await;
+        assert_eq!(
+            data,
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
+        );
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn should_skip_contents_of_files_larger_than_max_filesize() {
+        let root = setup_dir(vec![
+            ("small.txt", "some text"),
+            ("large.txt", "some text that goes on for longer"),
+        ]);
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::regex("text"),
+            options: SearchQueryOptions {
+                max_filesize: Some(10),
+                ..Default::default()
+            },
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+        let paths = get_matches(rx.recv().await.unwrap())
+            .into_iter()
+            .filter_map(|m| m.into_contents_match())
+            .map(|m| m.path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec![root.child("small.txt").to_path_buf()]);
+
+        let data = rx.recv().await;
+        assert_eq!(
+            data,
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped {
+                    too_large: 1,
+                    binary: 0
+                },
+                errors: Vec::new(),
+            })
+        );
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn should_skip_binary_files_found_while_recursing_unless_disabled() {
+        let root = setup_dir(vec![("text.txt", "some text"), ("bin", "some\0text")]);
+
+        let search = |skip_binary| {
+            let root = root.path().to_path_buf();
+            async move {
+                let state = SearchState::new();
+                let (reply, mut rx) = mpsc::channel(100);
+
+                let query = SearchQuery {
+                    paths: vec![root],
+                    target: SearchQueryTarget::Contents,
+                    condition: SearchQueryCondition::regex("text"),
+                    options: SearchQueryOptions {
+                        skip_binary,
+                        ..Default::default()
+                    },
+                };
+
+                state.start(query, Box::new(reply)).await.unwrap();
+
+                let mut paths = get_matches(rx.recv().await.unwrap())
+                    .into_iter()
+                    .filter_map(|m| m.into_contents_match())
+                    .map(|m| m.path)
+                    .collect::<Vec<_>>();
+                paths.sort_unstable();
+
+                let skipped = match rx.recv().await {
+                    Some(DistantResponseData::SearchDone { skipped, .. }) => skipped,
+                    x => panic!("Unexpected response: {x:?}"),
+                };
+
+                (paths, skipped)
+            }
+        };
+
+        let (paths, skipped) = search(true).await;
+        assert_eq!(paths, vec![root.child("text.txt").to_path_buf()]);
+        assert_eq!(skipped.binary, 1);
+
+        let (paths, skipped) = search(false).await;
+        assert_eq!(
+            paths,
+            vec![
+                root.child("bin").to_path_buf(),
+                root.child("text.txt").to_path_buf()
+            ]
+        );
+        assert!(skipped.is_empty());
+    }
+
+    #[test(tokio::test)]
+    async fn should_only_search_hidden_files_if_following_hidden() {
+        let root = setup_dir(vec![("visible.txt", ""), (".hidden.txt", "")]);
+
+        let search = |follow_hidden| {
+            let root = root.path().to_path_buf();
+            async move {
+                let state = SearchState::new();
+                let (reply, mut rx) = mpsc::channel(100);
+
+                let query = SearchQuery {
+                    paths: vec![root],
+                    target: SearchQueryTarget::Path,
+                    condition: SearchQueryCondition::regex(r"\.txt$"),
+                    options: SearchQueryOptions {
+                        follow_hidden,
+                        ..Default::default()
+                    },
+                };
+
+                state.start(query, Box::new(reply)).await.unwrap();
+
+                let mut paths = get_matches(rx.recv().await.unwrap())
+                    .into_iter()
+                    .filter_map(|m| m.into_path_match())
+                    .map(|m| m.path)
+                    .collect::<Vec<_>>();
+                paths.sort_unstable();
+                paths
+            }
+        };
+
+        assert_eq!(
+            search(false).await,
+            vec![root.child("visible.txt").to_path_buf()]
+        );
+        assert_eq!(
+            search(true).await,
+            vec![
+                root.child(".hidden.txt").to_path_buf(),
+                root.child("visible.txt").to_path_buf()
+            ]
+        );
+    }
+
     #[test(tokio::test)]
     async fn should_support_multiple_submatches() {
         let root = setup_dir(vec![("path/to/file.txt", "aa ab ac\nba bb bc\nca cb cc")]);
@@ -1138,7 +1774,8 @@ mod tests {
                             start: 3,
                             end: 5,
                         }
-                    ]
+                    ],
+                    context: Vec::new(),
                 },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file.txt")).to_path_buf(),
@@ -1156,7 +1793,8 @@ mod tests {
                             start: 3,
                             end: 5,
                         }
-                    ]
+                    ],
+                    context: Vec::new(),
                 },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file.txt")).to_path_buf(),
@@ -1174,7 +1812,8 @@ mod tests {
                             start: 3,
                             end: 5,
                         }
-                    ]
+                    ],
+                    context: Vec::new(),
                 },
             ]
         );
@@ -1182,7 +1821,11 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -1248,7 +1891,8 @@ mod tests {
                         r#match: SearchQueryMatchData::Text("text".to_string()),
                         start: 21,
                         end: 25,
-                    }]
+                    }],
+                    context: Vec::new(),
                 },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file1.txt")).to_path_buf(),
@@ -1259,7 +1903,8 @@ mod tests {
                         r#match: SearchQueryMatchData::Text("text".to_string()),
                         start: 9,
                         end: 13,
-                    }]
+                    }],
+                    context: Vec::new(),
                 },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file2.txt")).to_path_buf(),
@@ -1270,7 +1915,8 @@ mod tests {
                         r#match: SearchQueryMatchData::Text("text".to_string()),
                         start: 5,
                         end: 9,
-                    }]
+                    }],
+                    context: Vec::new(),
                 }
             ]
         );
@@ -1278,12 +1924,145 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
     }
 
+    #[test(tokio::test)]
+    async fn should_only_send_pages_after_the_first_once_requested_if_on_demand() {
+        let root = setup_dir(vec![
+            ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
+            ("path/to/file2.txt", "more text"),
+            ("other/file.txt", "some other file with text"),
+        ]);
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::regex("text"),
+            options: SearchQueryOptions {
+                pagination: Some(2),
+                on_demand: true,
+                ..Default::default()
+            },
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+        assert_eq!(get_matches(rx.recv().await.unwrap()).len(), 2);
+
+        // Nothing more is sent until the next page is requested
+        tokio::time::timeout(Duration::from_millis(100), rx.recv())
+            .await
+            .expect_err("Search continued without next page being requested");
+
+        state.next(search_id).await.unwrap();
+        assert_eq!(get_matches(rx.recv().await.unwrap()).len(), 1);
+        assert_eq!(
+            rx.recv().await,
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
+        );
+        assert_eq!(rx.recv().await, None);
+
+        // Once done, there are no more pages to request
+        assert_eq!(
+            state.next(search_id).await.unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn should_cancel_on_demand_search_if_next_page_not_requested_in_time() {
+        let root = setup_dir(vec![
+            ("path/to/file1.txt", "some\nlines of text in\na\nfile"),
+            ("path/to/file2.txt", "more text"),
+            ("other/file.txt", "some other file with text"),
+        ]);
+
+        let state = SearchState::with_idle_timeout(Duration::from_millis(50));
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::regex("text"),
+            options: SearchQueryOptions {
+                pagination: Some(1),
+                on_demand: true,
+                ..Default::default()
+            },
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+        assert_eq!(get_matches(rx.recv().await.unwrap()).len(), 1);
+
+        // Remaining matches are discarded once the search is canceled
+        assert_eq!(
+            rx.recv().await,
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
+        );
+        assert_eq!(rx.recv().await, None);
+        assert_eq!(
+            state.next(search_id).await.unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn should_fail_to_request_next_page_of_search_not_performed_on_demand() {
+        let root = setup_dir(vec![("file.txt", "some text")]);
+
+        let state = SearchState::new();
+
+        // Results are left unread so that the search remains active
+        let (reply, _rx) = mpsc::channel(1);
+
+        let mut query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::regex("text"),
+            options: SearchQueryOptions {
+                on_demand: true,
+                ..Default::default()
+            },
+        };
+
+        // Performing a search on demand requires pages to request
+        let (tmp_reply, _tmp_rx) = mpsc::channel(100);
+        assert_eq!(
+            state
+                .start(query.clone(), Box::new(tmp_reply))
+                .await
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        query.options.on_demand = false;
+        query.options.pagination = Some(1);
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+        assert_eq!(
+            state.next(search_id).await.unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
     #[test(tokio::test)]
     async fn should_send_maximum_of_limit_results_if_specified() {
         let root = setup_dir(vec![
@@ -1315,7 +2094,11 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -1356,7 +2139,11 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -1403,7 +2190,11 @@ mod tests {
             let data = rx.recv().await;
             assert_eq!(
                 data,
-                Some(DistantResponseData::SearchDone { id: search_id })
+                Some(DistantResponseData::SearchDone {
+                    id: search_id,
+                    skipped: SearchQuerySkipped::default(),
+                    errors: Vec::new(),
+                })
             );
 
             assert_eq!(rx.recv().await, None);
@@ -1500,14 +2291,98 @@ mod tests {
                     r#match: SearchQueryMatchData::Text("text".to_string()),
                     start: 21,
                     end: 25,
-                }]
+                }],
+                context: Vec::new(),
             }]
         );
 
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
+        );
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn should_filter_searched_paths_to_only_those_within_size_bounds() {
+        let root = setup_dir(vec![
+            ("small.txt", "a"),
+            ("medium.txt", "abcd"),
+            ("large.txt", "abcdefghijklmnop"),
+        ]);
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Path,
+            condition: SearchQueryCondition::regex(".*"),
+            options: SearchQueryOptions {
+                allowed_file_types: vec![FileType::File].into_iter().collect(),
+                min_size: Some(2),
+                max_size: Some(8),
+                ..Default::default()
+            },
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+        let paths = get_matches(rx.recv().await.unwrap())
+            .into_iter()
+            .filter_map(|m| m.into_path_match())
+            .map(|m| m.path)
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, vec![root.child("medium.txt").to_path_buf()]);
+
+        let data = rx.recv().await;
+        assert_eq!(
+            data,
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
+        );
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn should_filter_searched_paths_to_only_those_within_modification_bounds() {
+        let root = setup_dir(vec![("file.txt", "some text")]);
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        // Nothing created by the test could have been modified within the first second of the epoch
+        let query = SearchQuery {
+            paths: vec![root.path().to_path_buf()],
+            target: SearchQueryTarget::Path,
+            condition: SearchQueryCondition::regex(".*"),
+            options: SearchQueryOptions {
+                modified_before: Some(1),
+                ..Default::default()
+            },
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+        let data = rx.recv().await;
+        assert_eq!(
+            data,
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -1556,7 +2431,8 @@ mod tests {
                         r#match: SearchQueryMatchData::Text("text".to_string()),
                         start: 9,
                         end: 13,
-                    }]
+                    }],
+                    context: Vec::new(),
                 },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file2.txt")).to_path_buf(),
@@ -1567,7 +2443,8 @@ mod tests {
                         r#match: SearchQueryMatchData::Text("text".to_string()),
                         start: 5,
                         end: 9,
-                    }]
+                    }],
+                    context: Vec::new(),
                 }
             ]
         );
@@ -1575,7 +2452,11 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -1623,14 +2504,19 @@ mod tests {
                     r#match: SearchQueryMatchData::bytes([159]),
                     start: 0,
                     end: 1,
-                }]
+                }],
+                context: Vec::new(),
             },]
         );
 
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -1660,11 +2546,18 @@ mod tests {
 
         let search_id = state.start(query, Box::new(reply)).await.unwrap();
 
-        // Get done indicator next as there were no matches
+        // Get done indicator next as there were no matches, noting the binary file skipped
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped {
+                    too_large: 0,
+                    binary: 1
+                },
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -1716,7 +2609,11 @@ mod tests {
             let data = rx.recv().await;
             assert_eq!(
                 data,
-                Some(DistantResponseData::SearchDone { id: search_id })
+                Some(DistantResponseData::SearchDone {
+                    id: search_id,
+                    skipped: SearchQuerySkipped::default(),
+                    errors: Vec::new(),
+                })
             );
 
             assert_eq!(rx.recv().await, None);
@@ -1809,7 +2706,11 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -1868,7 +2769,11 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
         );
 
         assert_eq!(rx.recv().await, None);
@@ -1919,7 +2824,8 @@ mod tests {
                         r#match: SearchQueryMatchData::Text("text".to_string()),
                         start: 9,
                         end: 13,
-                    }]
+                    }],
+                    context: Vec::new(),
                 },
                 SearchQueryContentsMatch {
                     path: root.child(make_path("path/to/file2.txt")).to_path_buf(),
@@ -1930,7 +2836,8 @@ mod tests {
                         r#match: SearchQueryMatchData::Text("text".to_string()),
                         start: 5,
                         end: 9,
-                    }]
+                    }],
+                    context: Vec::new(),
                 }
             ]
         );
@@ -1938,9 +2845,83 @@ mod tests {
         let data = rx.recv().await;
         assert_eq!(
             data,
-            Some(DistantResponseData::SearchDone { id: search_id })
+            Some(DistantResponseData::SearchDone {
+                id: search_id,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            })
+        );
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn should_search_remaining_paths_and_report_those_that_cannot_be_searched() {
+        let root = setup_dir(vec![("path/to/file.txt", "some text")]);
+        let missing = root.child("missing").to_path_buf();
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![missing.clone(), root.child("path").to_path_buf()],
+            target: SearchQueryTarget::Contents,
+            condition: SearchQueryCondition::regex("text"),
+            options: Default::default(),
+        };
+
+        let search_id = state.start(query, Box::new(reply)).await.unwrap();
+
+        let paths = get_matches(rx.recv().await.unwrap())
+            .into_iter()
+            .filter_map(|m| m.into_contents_match())
+            .map(|m| m.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![root.child(make_path("path/to/file.txt")).to_path_buf()]
         );
 
+        match rx.recv().await {
+            Some(DistantResponseData::SearchDone { id, errors, .. }) => {
+                assert_eq!(id, search_id);
+                assert_eq!(errors.len(), 1, "Unexpected errors: {errors:?}");
+                assert_eq!(errors[0].path, missing);
+                assert_eq!(errors[0].error.kind, crate::data::ErrorKind::NotFound);
+            }
+            x => panic!("Unexpected response: {x:?}"),
+        }
+
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[test(tokio::test)]
+    async fn should_finish_with_errors_if_no_paths_can_be_searched() {
+        let root = assert_fs::TempDir::new().unwrap();
+        let missing = root.child("missing").to_path_buf();
+
+        let state = SearchState::new();
+        let (reply, mut rx) = mpsc::channel(100);
+
+        let query = SearchQuery {
+            paths: vec![missing.clone()],
+            target: SearchQueryTarget::Path,
+            condition: SearchQueryCondition::regex("missing"),
+            options: Default::default(),
+        };
+
+        state.start(query, Box::new(reply)).await.unwrap();
+
+        match rx.recv().await {
+            Some(DistantResponseData::SearchDone { errors, .. }) => {
+                assert_eq!(
+                    errors.into_iter().map(|e| e.path).collect::<Vec<_>>(),
+                    vec![missing]
+                );
+            }
+            x => panic!("Unexpected response: {x:?}"),
+        }
+
         assert_eq!(rx.recv().await, None);
     }
 
@@ -1999,7 +2980,11 @@ mod tests {
             let data = rx.recv().await;
             assert_eq!(
                 data,
-                Some(DistantResponseData::SearchDone { id: search_id })
+                Some(DistantResponseData::SearchDone {
+                    id: search_id,
+                    skipped: SearchQuerySkipped::default(),
+                    errors: Vec::new(),
+                })
             );
 
             assert_eq!(rx.recv().await, None);
@@ -0,0 +1,360 @@
+use crate::data::{SnapshotId, SnapshotInfo};
+use distant_net::common::ConnectionId;
+use log::*;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use walkdir::WalkDir;
+
+/// Holds the snapshots taken by the server, each of which is kept next to the file or directory
+/// that it was taken of so that copying its files stays on the same filesystem
+///
+/// Snapshots are only tracked in memory, so they are removed along with the state and do not
+/// survive the server restarting. Each snapshot belongs to the connection that took it, and is
+/// hidden from all other connections.
+#[derive(Default)]
+pub struct SnapshotState {
+    snapshots: Mutex<HashMap<SnapshotId, Snapshot>>,
+}
+
+struct Snapshot {
+    info: SnapshotInfo,
+
+    /// Connection that took the snapshot
+    owner: ConnectionId,
+
+    /// Where the contents of the snapshot are kept
+    location: PathBuf,
+}
+
+impl Drop for SnapshotState {
+    /// Removes all snapshots, which can no longer be restored once the server is gone
+    fn drop(&mut self) {
+        for (id, snapshot) in self.snapshots.get_mut().unwrap().drain() {
+            if let Err(x) = remove_path(&snapshot.location) {
+                warn!(
+                    "[Snapshot {id}] Failed to remove {:?}: {x}",
+                    snapshot.location
+                );
+            }
+        }
+    }
+}
+
+impl SnapshotState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes a snapshot of the file or directory at `path`. Files are copied rather than hard
+    /// linked, as a hard link would share any change later made to the file in place.
+    pub async fn create(&self, owner: ConnectionId, path: PathBuf) -> io::Result<SnapshotId> {
+        let path = tokio::fs::canonicalize(path).await?;
+        let id = rand::random();
+        let location = sibling(&path, &format!("snapshot-{id:08x}"));
+
+        let (src, dst) = (path.clone(), location.clone());
+        spawn_blocking(move || clone_tree(&src, &dst)).await?;
+
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.snapshots.lock().unwrap().insert(
+            id,
+            Snapshot {
+                info: SnapshotInfo { id, path, created },
+                owner,
+                location,
+            },
+        );
+
+        Ok(id)
+    }
+
+    /// Returns information about each snapshot taken by `owner`, oldest first
+    pub fn list(&self, owner: ConnectionId) -> Vec<SnapshotInfo> {
+        let mut entries: Vec<SnapshotInfo> = self
+            .snapshots
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|snapshot| snapshot.owner == owner)
+            .map(|snapshot| snapshot.info.clone())
+            .collect();
+        entries.sort_unstable_by_key(|info| (info.created, info.id));
+        entries
+    }
+
+    /// Returns the path of the file or directory that the snapshot with the given id, taken by
+    /// `owner`, was taken of
+    pub fn path(&self, owner: ConnectionId, id: SnapshotId) -> io::Result<PathBuf> {
+        match self.snapshots.lock().unwrap().get(&id) {
+            Some(snapshot) if snapshot.owner == owner => Ok(snapshot.info.path.clone()),
+            _ => Err(not_found(id)),
+        }
+    }
+
+    /// Replaces the file or directory that the snapshot with the given id, taken by `owner`, was
+    /// taken of with a copy of the snapshot, leaving the snapshot itself untouched
+    pub async fn restore(&self, owner: ConnectionId, id: SnapshotId) -> io::Result<()> {
+        let (path, location) = match self.snapshots.lock().unwrap().get(&id) {
+            Some(snapshot) if snapshot.owner == owner => {
+                (snapshot.info.path.clone(), snapshot.location.clone())
+            }
+            _ => return Err(not_found(id)),
+        };
+
+        spawn_blocking(move || {
+            let staged = sibling(&path, &format!("restore-{:08x}", rand::random::<u32>()));
+            clone_tree(&location, &staged)?;
+
+            // Whatever is at the path is moved aside until the restored copy takes its place
+            let old = match fs::symlink_metadata(&path) {
+                Ok(_) => {
+                    let old = sibling(&path, &format!("old-{:08x}", rand::random::<u32>()));
+                    if let Err(x) = fs::rename(&path, &old) {
+                        let _ = remove_path(&staged);
+                        return Err(x);
+                    }
+                    Some(old)
+                }
+                Err(x) if x.kind() == io::ErrorKind::NotFound => None,
+                Err(x) => return Err(x),
+            };
+
+            if let Err(x) = fs::rename(&staged, &path) {
+                if let Some(old) = old {
+                    let _ = fs::rename(old, &path);
+                }
+                let _ = remove_path(&staged);
+                return Err(x);
+            }
+
+            match old {
+                Some(old) => remove_path(&old),
+                None => Ok(()),
+            }
+        })
+        .await
+    }
+
+    /// Removes the snapshot with the given id, taken by `owner`
+    pub async fn remove(&self, owner: ConnectionId, id: SnapshotId) -> io::Result<()> {
+        let snapshot = {
+            let mut snapshots = self.snapshots.lock().unwrap();
+            match snapshots.get(&id) {
+                Some(snapshot) if snapshot.owner == owner => snapshots.remove(&id).unwrap(),
+                _ => return Err(not_found(id)),
+            }
+        };
+
+        spawn_blocking(move || remove_path(&snapshot.location)).await
+    }
+}
+
+fn not_found(id: SnapshotId) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("No snapshot with id {id}"))
+}
+
+async fn spawn_blocking(f: impl FnOnce() -> io::Result<()> + Send + 'static) -> io::Result<()> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?
+}
+
+/// Returns a hidden path in the same directory as `path`, which keeps it on the same filesystem
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.distant-{suffix}"))
+}
+
+/// Recreates the file or directory at `src` at `dst` by copying its files, removing anything
+/// created at `dst` if it fails
+///
+/// Copies go through [`fs::copy`], which on Linux uses `copy_file_range` and so shares the data
+/// of files rather than duplicating it on filesystems that support doing so (such as btrfs).
+fn clone_tree(src: &Path, dst: &Path) -> io::Result<()> {
+    let result = try_clone_tree(src, dst);
+    if result.is_err() && fs::symlink_metadata(dst).is_ok() {
+        let _ = remove_path(dst);
+    }
+    result
+}
+
+fn try_clone_tree(src: &Path, dst: &Path) -> io::Result<()> {
+    // Permissions of directories are applied once they are filled, as a read-only directory
+    // could not be filled otherwise
+    let mut dirs = Vec::new();
+
+    for entry in WalkDir::new(src).follow_links(false) {
+        let entry = entry?;
+
+        // NOTE: Because we are traversing paths that are all within src, this always succeeds
+        let relative = entry.path().strip_prefix(src).unwrap();
+        let target = if relative.as_os_str().is_empty() {
+            dst.to_path_buf()
+        } else {
+            dst.join(relative)
+        };
+
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            fs::create_dir(&target)?;
+            dirs.push((target, entry.metadata()?.permissions()));
+        } else if file_type.is_symlink() {
+            symlink(entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+
+    for (dir, permissions) in dirs.into_iter().rev() {
+        fs::set_permissions(dir, permissions)?;
+    }
+
+    Ok(())
+}
+
+/// Creates a symlink at `dst` that points where the symlink at `src` does
+fn symlink(src: &Path, dst: &Path) -> io::Result<()> {
+    let target = fs::read_link(src)?;
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, dst)
+    }
+
+    #[cfg(windows)]
+    {
+        if fs::metadata(src).map(|m| m.is_dir()).unwrap_or(false) {
+            std::os::windows::fs::symlink_dir(target, dst)
+        } else {
+            std::os::windows::fs::symlink_file(target, dst)
+        }
+    }
+}
+
+/// Removes the file, symlink, or directory at `path`
+fn remove_path(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use predicates::prelude::*;
+    use test_log::test;
+
+    #[test(tokio::test)]
+    async fn restore_should_bring_back_replaced_and_removed_files() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let dir = temp.child("dir");
+        dir.child("replaced").write_str("old").unwrap();
+        dir.child("removed").write_str("removed").unwrap();
+
+        let state = SnapshotState::new();
+        let id = state.create(1, dir.to_path_buf()).await.unwrap();
+
+        let replacement = temp.child("replacement");
+        replacement.write_str("new").unwrap();
+        std::fs::rename(replacement.path(), dir.child("replaced").path()).unwrap();
+        std::fs::remove_file(dir.child("removed").path()).unwrap();
+        dir.child("added").write_str("added").unwrap();
+
+        state.restore(1, id).await.unwrap();
+
+        dir.child("replaced").assert("old");
+        dir.child("removed").assert("removed");
+        dir.child("added").assert(predicate::path::missing());
+
+        // The snapshot is kept after being restored, but removed along with the state
+        assert_eq!(state.list(1).len(), 1);
+        assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 2);
+        drop(state);
+        assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
+
+    #[test(tokio::test)]
+    async fn restore_should_bring_back_files_changed_in_place() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("file");
+        file.write_str("old").unwrap();
+
+        let state = SnapshotState::new();
+        let id = state.create(1, file.to_path_buf()).await.unwrap();
+
+        // Writing to the same inode would also change a snapshot that shared it
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(file.path())
+            .and_then(|mut f| std::io::Write::write_all(&mut f, b"new"))
+            .unwrap();
+        file.assert("new");
+
+        state.restore(1, id).await.unwrap();
+        file.assert("old");
+    }
+
+    #[test(tokio::test)]
+    async fn remove_should_fail_if_snapshot_missing() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("file");
+        file.write_str("text").unwrap();
+
+        let state = SnapshotState::new();
+        let id = state.create(1, file.to_path_buf()).await.unwrap();
+        assert_eq!(state.list(1)[0].path, file.path().canonicalize().unwrap());
+
+        state.remove(1, id).await.unwrap();
+        assert_eq!(
+            state.remove(1, id).await.unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert!(state.list(1).is_empty());
+        assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
+
+    #[test(tokio::test)]
+    async fn snapshots_should_be_hidden_from_other_connections() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("file");
+        file.write_str("old").unwrap();
+
+        let state = SnapshotState::new();
+        let id = state.create(1, file.to_path_buf()).await.unwrap();
+        file.write_str("new").unwrap();
+
+        assert!(state.list(2).is_empty());
+        assert_eq!(
+            state.path(2, id).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            state.restore(2, id).await.unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            state.remove(2, id).await.unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        file.assert("new");
+
+        // The snapshot remains usable by the connection that took it
+        assert_eq!(state.list(1).len(), 1);
+        state.restore(1, id).await.unwrap();
+        file.assert("old");
+    }
+}
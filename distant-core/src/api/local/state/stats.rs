@@ -0,0 +1,644 @@
+use crate::{
+    constants::{MIN_SYSTEM_STATS_INTERVAL, SYSTEM_STATS_SAMPLE_DURATION},
+    data::{
+        DistantResponseData, ProcInfo, ProcStats, ProcStatus, ProcessId, SystemStats, SystemStatsId,
+    },
+};
+use distant_net::server::Reply;
+use log::*;
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::task::JoinHandle;
+
+/// Holds information related to system stats being reported at an interval by the server
+#[derive(Default)]
+pub struct StatsState {
+    reporters: Arc<Mutex<HashMap<SystemStatsId, JoinHandle<()>>>>,
+}
+
+impl Drop for StatsState {
+    /// Aborts all tasks reporting stats
+    fn drop(&mut self) {
+        for (_, task) in self.reporters.lock().unwrap().drain() {
+            task.abort();
+        }
+    }
+}
+
+impl StatsState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects stats about the system, continuing to collect and send them through `reply` each
+    /// time `interval` elapses if provided
+    pub async fn start(
+        &self,
+        interval: Option<Duration>,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) -> io::Result<(SystemStatsId, SystemStats)> {
+        self.report(Sampler::default(), interval, reply, |id, stats| {
+            DistantResponseData::SystemStats { id, stats }
+        })
+        .await
+    }
+
+    /// Collects stats about the process `proc_id` whose system id is `pid`, continuing to collect
+    /// and send them through `reply` each time `interval` elapses if provided until the process
+    /// exits
+    pub async fn start_process(
+        &self,
+        proc_id: ProcessId,
+        pid: u32,
+        interval: Option<Duration>,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) -> io::Result<(SystemStatsId, ProcStats)> {
+        self.report(ProcSampler::new(pid), interval, reply, move |id, stats| {
+            DistantResponseData::ProcStats {
+                id: proc_id,
+                stats_id: id,
+                stats,
+            }
+        })
+        .await
+    }
+
+    async fn report<C, F>(
+        &self,
+        collector: C,
+        interval: Option<Duration>,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+        make_response: F,
+    ) -> io::Result<(SystemStatsId, C::Stats)>
+    where
+        C: Collect,
+        F: Fn(SystemStatsId, C::Stats) -> DistantResponseData + Send + 'static,
+    {
+        let id = rand::random();
+
+        // CPU usage is measured between samples, so the first stats need a sample to start from
+        let (collector, _) = sample(collector).await?;
+        tokio::time::sleep(SYSTEM_STATS_SAMPLE_DURATION).await;
+        let (mut collector, stats) = sample(collector).await?;
+
+        if let Some(interval) = interval {
+            let interval = interval.max(MIN_SYSTEM_STATS_INTERVAL);
+            let reporters = Arc::clone(&self.reporters);
+            let task = tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(interval).await;
+                    let stats = match sample(collector).await {
+                        Ok((next, stats)) => {
+                            collector = next;
+                            stats
+                        }
+                        Err(x) if x.kind() == io::ErrorKind::NotFound => {
+                            debug!("[Stats {id}] Stopping as {x}");
+                            break;
+                        }
+                        Err(x) => {
+                            error!("[Stats {id}] Failed to collect stats: {x}");
+                            break;
+                        }
+                    };
+
+                    if let Err(x) = reply.send(make_response(id, stats)).await {
+                        debug!("[Stats {id}] Stopping as stats could not be sent: {x}");
+                        break;
+                    }
+                }
+
+                trace!("[Stats {id}] Removing internal tracking");
+                reporters.lock().unwrap().remove(&id);
+            });
+            self.reporters.lock().unwrap().insert(id, task);
+        }
+
+        Ok((id, stats))
+    }
+
+    /// Stops reporting the stats with the given id
+    pub fn cancel(&self, id: SystemStatsId) -> io::Result<()> {
+        match self.reporters.lock().unwrap().remove(&id) {
+            Some(task) => {
+                task.abort();
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("[Stats {id}] Cancellation failed because no reporting found"),
+            )),
+        }
+    }
+}
+
+/// Returns every process running on the system, none of which are tied to spawned processes
+#[cfg(target_os = "linux")]
+pub fn system_processes() -> io::Result<Vec<ProcInfo>> {
+    use std::fs;
+
+    let uptime = procfs::uptime(&fs::read_to_string("/proc/uptime")?)
+        .ok_or_else(|| invalid_data("/proc/uptime"))?;
+
+    // SAFETY: Retrieving a configuration value has no memory safety requirements
+    let ticks_per_second = match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        x if x > 0 => x as u64,
+        _ => 100,
+    };
+
+    let mut processes = Vec::new();
+    for entry in fs::read_dir("/proc")?.filter_map(Result::ok) {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<u32>().ok())
+        else {
+            continue;
+        };
+
+        // Processes can exit while we read them, in which case they are skipped
+        let Some((name, status, started)) = fs::read_to_string(entry.path().join("stat"))
+            .ok()
+            .and_then(|stat| procfs::process_status(&stat))
+        else {
+            continue;
+        };
+
+        // Kernel threads have no command line, so they go by their name like in ps
+        let cmd = fs::read(entry.path().join("cmdline"))
+            .map(|cmdline| procfs::cmdline(&cmdline))
+            .unwrap_or_default();
+        let cmd = if cmd.is_empty() {
+            format!("[{name}]")
+        } else {
+            cmd
+        };
+
+        processes.push(ProcInfo {
+            id: None,
+            session: None,
+            pid: Some(pid),
+            cmd,
+            uptime: uptime.saturating_sub(started / ticks_per_second),
+            status,
+        });
+    }
+
+    Ok(processes)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn system_processes() -> io::Result<Vec<ProcInfo>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Listing system processes is only supported on Linux",
+    ))
+}
+
+/// Returns the status of the process with `pid` given by the operating system, or none if the
+/// process no longer exists
+#[cfg(target_os = "linux")]
+pub fn process_status(pid: u32) -> Option<ProcStatus> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    Some(procfs::process_status(&stat).map_or(ProcStatus::Unknown, |(_, status, _)| status))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn process_status(_pid: u32) -> Option<ProcStatus> {
+    Some(ProcStatus::Running)
+}
+
+/// Source of stats that are collected through blocking file i/o, measuring usage since the
+/// previous collection
+trait Collect: Send + 'static {
+    type Stats: Send + 'static;
+
+    fn collect(&mut self) -> io::Result<Self::Stats>;
+}
+
+/// Collects stats from `collector` on its own thread as reading them can block for a while,
+/// handing back the collector alongside them
+async fn sample<C: Collect>(mut collector: C) -> io::Result<(C, C::Stats)> {
+    tokio::task::spawn_blocking(move || {
+        let stats = collector.collect()?;
+        Ok((collector, stats))
+    })
+    .await
+    .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?
+}
+
+/// Collects stats about the system, remembering the CPU time used so far so that CPU usage can be
+/// measured between each sample
+#[derive(Default)]
+struct Sampler {
+    /// Total CPU time of the system and the portion of it spent idle, in clock ticks
+    cpu: (u64, u64),
+
+    /// CPU time used by each process, in clock ticks
+    processes: HashMap<u32, u64>,
+}
+
+impl Collect for Sampler {
+    type Stats = SystemStats;
+
+    #[cfg(target_os = "linux")]
+    fn collect(&mut self) -> io::Result<SystemStats> {
+        use crate::data::ProcessStats;
+        use std::fs;
+
+        let (total, idle) = procfs::cpu_times(&fs::read_to_string("/proc/stat")?)
+            .ok_or_else(|| invalid_data("/proc/stat"))?;
+        let elapsed = total.saturating_sub(self.cpu.0);
+        let busy = elapsed.saturating_sub(idle.saturating_sub(self.cpu.1));
+        self.cpu = (total, idle);
+
+        let cpu_count = num_cpus::get();
+        let percent = |ticks: u64| {
+            if elapsed == 0 {
+                0.0
+            } else {
+                (ticks as f64 * 100.0 / elapsed as f64) as f32
+            }
+        };
+
+        let meminfo = fs::read_to_string("/proc/meminfo")?;
+        let memory = procfs::kibibytes(&meminfo);
+        let bytes = |key: &str| memory.get(key).copied().unwrap_or_default() * 1024;
+
+        let load_average = procfs::load_average(&fs::read_to_string("/proc/loadavg")?)
+            .ok_or_else(|| invalid_data("/proc/loadavg"))?;
+        let uptime = procfs::uptime(&fs::read_to_string("/proc/uptime")?)
+            .ok_or_else(|| invalid_data("/proc/uptime"))?;
+
+        let mut processes = Vec::new();
+        let mut times = HashMap::new();
+        for entry in fs::read_dir("/proc")?.filter_map(Result::ok) {
+            let Some(pid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<u32>().ok())
+            else {
+                continue;
+            };
+
+            // Processes can exit while we read them, in which case they are skipped
+            let Some((name, time)) = fs::read_to_string(entry.path().join("stat"))
+                .ok()
+                .and_then(|stat| procfs::process_times(&stat))
+            else {
+                continue;
+            };
+            let memory = fs::read_to_string(entry.path().join("status"))
+                .ok()
+                .and_then(|status| procfs::kibibytes(&status).get("VmRSS").copied())
+                .unwrap_or_default();
+
+            let used = time.saturating_sub(self.processes.get(&pid).copied().unwrap_or_default());
+            times.insert(pid, time);
+            processes.push(ProcessStats {
+                pid,
+                name,
+                cpu_usage: percent(used) * cpu_count as f32,
+                memory: memory * 1024,
+            });
+        }
+        self.processes = times;
+
+        Ok(SystemStats {
+            cpu_usage: percent(busy),
+            cpu_count,
+            memory_total: bytes("MemTotal"),
+            memory_used: bytes("MemTotal").saturating_sub(bytes("MemAvailable")),
+            swap_total: bytes("SwapTotal"),
+            swap_used: bytes("SwapTotal").saturating_sub(bytes("SwapFree")),
+            load_average,
+            uptime,
+            processes,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn collect(&mut self) -> io::Result<SystemStats> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "System stats are only supported on Linux",
+        ))
+    }
+}
+
+/// Collects stats about a single process, remembering the CPU time it used so far and when so
+/// that CPU usage can be measured between each sample
+struct ProcSampler {
+    /// Id of the process assigned by the system
+    pid: u32,
+
+    /// When the last sample was collected and the CPU time used by then, in clock ticks
+    last: Option<(Instant, u64)>,
+}
+
+impl ProcSampler {
+    fn new(pid: u32) -> Self {
+        Self { pid, last: None }
+    }
+}
+
+impl Collect for ProcSampler {
+    type Stats = ProcStats;
+
+    #[cfg(target_os = "linux")]
+    fn collect(&mut self) -> io::Result<ProcStats> {
+        use std::fs;
+
+        let dir = std::path::PathBuf::from(format!("/proc/{}", self.pid));
+        let (_, time) = fs::read_to_string(dir.join("stat"))
+            .ok()
+            .and_then(|stat| procfs::process_times(&stat))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("process {} no longer exists", self.pid),
+                )
+            })?;
+        let memory = fs::read_to_string(dir.join("status"))
+            .ok()
+            .and_then(|status| procfs::kibibytes(&status).get("VmRSS").copied())
+            .unwrap_or_default();
+
+        // Storage i/o can only be read for processes of our own user, which spawned ones are
+        let (read_bytes, write_bytes) = fs::read_to_string(dir.join("io"))
+            .ok()
+            .and_then(|io| procfs::io_bytes(&io))
+            .unwrap_or_default();
+
+        // SAFETY: Retrieving a configuration value has no memory safety requirements
+        let ticks_per_second = match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+            x if x > 0 => x as f64,
+            _ => 100.0,
+        };
+
+        let now = Instant::now();
+        let cpu_usage = match self.last {
+            Some((then, last_time)) => {
+                let elapsed = now.duration_since(then).as_secs_f64();
+                let used = time.saturating_sub(last_time) as f64 / ticks_per_second;
+                if elapsed > 0.0 {
+                    (used * 100.0 / elapsed) as f32
+                } else {
+                    0.0
+                }
+            }
+            None => 0.0,
+        };
+        self.last = Some((now, time));
+
+        Ok(ProcStats {
+            cpu_usage,
+            memory: memory * 1024,
+            read_bytes,
+            write_bytes,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn collect(&mut self) -> io::Result<ProcStats> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Process stats are only supported on Linux",
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn invalid_data(path: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Failed to parse {path}"),
+    )
+}
+
+/// Parsers of the files in `/proc` that stats are collected from
+#[cfg(target_os = "linux")]
+mod procfs {
+    use crate::data::ProcStatus;
+    use std::collections::HashMap;
+
+    /// Returns the total CPU time of the system and the portion of it spent idle from the
+    /// contents of `/proc/stat`
+    pub fn cpu_times(stat: &str) -> Option<(u64, u64)> {
+        let times = stat
+            .lines()
+            .find_map(|line| line.strip_prefix("cpu "))?
+            .split_whitespace()
+            .map(|x| x.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+
+        // Guest time is already included in user time, so it would be counted twice
+        let total = times.iter().take(8).sum();
+        let idle = times.get(3)? + times.get(4).copied().unwrap_or_default();
+        Some((total, idle))
+    }
+
+    /// Returns the values of files like `/proc/meminfo` that list sizes in kibibytes, keyed by
+    /// their names
+    pub fn kibibytes(contents: &str) -> HashMap<&str, u64> {
+        contents
+            .lines()
+            .filter_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                let value = value.trim().trim_end_matches("kB").trim().parse().ok()?;
+                Some((key, value))
+            })
+            .collect()
+    }
+
+    /// Returns the 1, 5, and 15 minute load averages from the contents of `/proc/loadavg`
+    pub fn load_average(loadavg: &str) -> Option<[f32; 3]> {
+        let mut values = loadavg.split_whitespace().map(|x| x.parse::<f32>().ok());
+        Some([values.next()??, values.next()??, values.next()??])
+    }
+
+    /// Returns the seconds since boot from the contents of `/proc/uptime`
+    pub fn uptime(uptime: &str) -> Option<u64> {
+        let seconds = uptime.split_whitespace().next()?.parse::<f64>().ok()?;
+        Some(seconds as u64)
+    }
+
+    /// Returns the name of a process and the CPU time that it has used from the contents of
+    /// `/proc/[pid]/stat`
+    pub fn process_times(stat: &str) -> Option<(String, u64)> {
+        // The name is wrapped in parentheses and can itself contain spaces and parentheses
+        let start = stat.find('(')?;
+        let end = stat.rfind(')')?;
+        let name = stat.get(start + 1..end)?.to_string();
+
+        // Fields following the name start with the state, and user and system time are the
+        // 14th and 15th fields overall
+        let mut fields = stat.get(end + 1..)?.split_whitespace().skip(11);
+        let utime = fields.next()?.parse::<u64>().ok()?;
+        let stime = fields.next()?.parse::<u64>().ok()?;
+        Some((name, utime + stime))
+    }
+
+    /// Returns the bytes that a process has caused to be read from and written to storage from
+    /// the contents of `/proc/[pid]/io`
+    pub fn io_bytes(io: &str) -> Option<(u64, u64)> {
+        let mut read_bytes = None;
+        let mut write_bytes = None;
+        for line in io.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            match key {
+                "read_bytes" => read_bytes = value.trim().parse().ok(),
+                "write_bytes" => write_bytes = value.trim().parse().ok(),
+                _ => {}
+            }
+        }
+        Some((read_bytes?, write_bytes?))
+    }
+
+    /// Returns the name of a process, its status, and when it started (in clock ticks since
+    /// boot) from the contents of `/proc/[pid]/stat`
+    pub fn process_status(stat: &str) -> Option<(String, ProcStatus, u64)> {
+        let start = stat.find('(')?;
+        let end = stat.rfind(')')?;
+        let name = stat.get(start + 1..end)?.to_string();
+
+        // The state is the 3rd field overall and the start time is the 22nd
+        let mut fields = stat.get(end + 1..)?.split_whitespace();
+        let status = match fields.next()? {
+            "R" => ProcStatus::Running,
+            "S" | "D" | "I" => ProcStatus::Sleeping,
+            "T" | "t" => ProcStatus::Stopped,
+            "Z" | "X" => ProcStatus::Exited,
+            _ => ProcStatus::Unknown,
+        };
+        let started = fields.nth(18)?.parse::<u64>().ok()?;
+        Some((name, status, started))
+    }
+
+    /// Returns the command line of a process from the contents of `/proc/[pid]/cmdline`, whose
+    /// arguments are each terminated by a nul byte
+    pub fn cmdline(cmdline: &[u8]) -> String {
+        cmdline
+            .split(|b| *b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(String::from_utf8_lossy)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn procfs_should_parse_cpu_times() {
+        let stat = "cpu  10 1 5 100 4 2 3 0 7 0\ncpu0 10 1 5 100 4 2 3 0 7 0\n";
+        assert_eq!(procfs::cpu_times(stat), Some((125, 104)));
+        assert_eq!(procfs::cpu_times("intr 1 2 3"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn procfs_should_parse_process_name_and_times() {
+        let stat = "42 (my (weird) name) S 1 42 42 0 -1 4194560 100 0 0 0 7 3 0 0 20 0 1 0";
+        assert_eq!(
+            procfs::process_times(stat),
+            Some((String::from("my (weird) name"), 10))
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn procfs_should_parse_process_status_and_start_time() {
+        let stat = "42 (my (weird) name) T 1 42 42 0 -1 4194560 100 0 0 0 7 3 0 0 20 0 1 0 1234 0";
+        assert_eq!(
+            procfs::process_status(stat),
+            Some((String::from("my (weird) name"), ProcStatus::Stopped, 1234))
+        );
+        assert_eq!(procfs::cmdline(b"sleep\x0010\x00"), "sleep 10");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn procfs_should_parse_sizes_in_kibibytes() {
+        let meminfo = "MemTotal:       16 kB\nMemAvailable:    4 kB\nHugePages_Total:     0\n";
+        let sizes = procfs::kibibytes(meminfo);
+        assert_eq!(sizes.get("MemTotal"), Some(&16));
+        assert_eq!(sizes.get("MemAvailable"), Some(&4));
+        assert_eq!(sizes.get("HugePages_Total"), Some(&0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn procfs_should_parse_io_bytes() {
+        let io = "rchar: 100\nwchar: 50\nread_bytes: 4096\nwrite_bytes: 8192\n";
+        assert_eq!(procfs::io_bytes(io), Some((4096, 8192)));
+        assert_eq!(procfs::io_bytes("rchar: 100\n"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn procfs_should_parse_load_average_and_uptime() {
+        assert_eq!(
+            procfs::load_average("0.50 1.25 2.00 1/100 1234\n"),
+            Some([0.5, 1.25, 2.0])
+        );
+        assert_eq!(procfs::uptime("1234.56 5678.90\n"), Some(1234));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test(tokio::test)]
+    async fn should_include_current_process_in_stats() {
+        let (sampler, _) = sample(Sampler::default()).await.unwrap();
+        let (_, stats) = sample(sampler).await.unwrap();
+
+        assert!(stats.memory_total > 0, "Missing total memory: {stats:?}");
+        assert!(stats.memory_used <= stats.memory_total);
+        assert!(stats
+            .processes
+            .iter()
+            .any(|p| p.pid == std::process::id() && p.memory > 0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test(tokio::test)]
+    async fn should_collect_stats_of_current_process_until_it_no_longer_exists() {
+        let (sampler, _) = sample(ProcSampler::new(std::process::id())).await.unwrap();
+        let (_, stats) = sample(sampler).await.unwrap();
+        assert!(stats.memory > 0, "Missing memory: {stats:?}");
+
+        let result = sample(ProcSampler::new(u32::MAX)).await;
+        assert!(
+            matches!(&result, Err(x) if x.kind() == io::ErrorKind::NotFound),
+            "Unexpected result: {:?}",
+            result.map(|(_, stats)| stats)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn system_processes_should_include_current_process() {
+        let processes = system_processes().unwrap();
+        assert!(processes
+            .iter()
+            .any(|p| p.pid == Some(std::process::id()) && !p.cmd.is_empty()));
+    }
+
+    #[test(tokio::test)]
+    async fn cancel_should_fail_if_no_reporting_found() {
+        let state = StatsState::new();
+        assert_eq!(
+            state.cancel(12345).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+}
@@ -0,0 +1,407 @@
+use crate::{
+    constants::{MAX_PIPE_CHUNK_SIZE, TUNNEL_CAPACITY},
+    data::{DistantResponseData, TunnelId},
+};
+use distant_net::server::Reply;
+use log::*;
+use std::{
+    collections::HashMap,
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+type Tunnels = Arc<Mutex<HashMap<TunnelId, Tunnel>>>;
+
+/// Holds information related to tunnels opened through the server, either to connect to another
+/// host or as connections accepted by a listener of the server
+#[derive(Default)]
+pub struct TunnelState {
+    tunnels: Tunnels,
+    listeners: Arc<Mutex<HashMap<TunnelId, JoinHandle<()>>>>,
+
+    /// Whether listeners can bind to addresses other than loopback ones, which lets other
+    /// machines reach the connections of clients through the server
+    allow_remote: bool,
+}
+
+struct Tunnel {
+    /// Queues data to write to the connection, which is missing once the tunnel is closed for
+    /// writing
+    writer: Option<mpsc::Sender<Vec<u8>>>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for TunnelState {
+    /// Aborts all tasks relaying data through tunnels and accepting connections
+    fn drop(&mut self) {
+        for (_, task) in self.listeners.lock().unwrap().drain() {
+            task.abort();
+        }
+
+        for (_, tunnel) in self.tunnels.lock().unwrap().drain() {
+            tunnel.task.abort();
+        }
+    }
+}
+
+impl TunnelState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets listeners bind to addresses other than loopback ones when `allow` is true
+    pub fn allow_remote(&mut self, allow: bool) {
+        self.allow_remote = allow;
+    }
+
+    /// Connects to `host` and `port`, sending data read from the connection through `reply`
+    /// until the other end closes it or the connection of `reply` is gone
+    pub async fn open(
+        &self,
+        host: String,
+        port: u16,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) -> io::Result<TunnelId> {
+        let stream = TcpStream::connect((host.as_str(), port)).await?;
+        let id = rand::random();
+        add_tunnel(&self.tunnels, id, Connection::Tcp(stream), reply);
+        Ok(id)
+    }
+
+    /// Listens on `host` and `port`, returning the id of the listener and the port it is bound
+    /// to, where each connection accepted is announced through `reply` and then relayed as a
+    /// tunnel of its own, sending data read from it through `reply` as well
+    ///
+    /// Unless remote listeners are allowed, `host` must only resolve to loopback addresses.
+    pub async fn listen(
+        &self,
+        host: String,
+        port: u16,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) -> io::Result<(TunnelId, u16)> {
+        // Bind to the addresses that were checked rather than resolving the host a second time
+        let addrs: Vec<_> = tokio::net::lookup_host((host.as_str(), port))
+            .await?
+            .collect();
+        if !self.allow_remote && addrs.iter().any(|addr| !addr.ip().is_loopback()) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Listening on {host} requires the server to allow remote tunnels"),
+            ));
+        }
+
+        let listener = TcpListener::bind(addrs.as_slice()).await?;
+        let port = listener.local_addr()?.port();
+        let id = rand::random();
+        self.start_listener(id, Listener::Tcp(listener), reply);
+        Ok((id, port))
+    }
+
+    /// Listens on a new unix socket within a directory that only the user of the server can
+    /// access, returning the id of the listener and the path of the socket, where each connection
+    /// accepted is announced and relayed the same way as by [`TunnelState::listen`]
+    #[cfg(unix)]
+    pub async fn listen_unix(
+        &self,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) -> io::Result<(TunnelId, PathBuf)> {
+        use std::os::unix::fs::DirBuilderExt;
+
+        let id = rand::random();
+        let dir = std::env::temp_dir().join(format!("distant-{:016x}", rand::random::<u64>()));
+        std::fs::DirBuilder::new().mode(0o700).create(&dir)?;
+
+        // Removes the directory should binding fail, or otherwise once the listener stops
+        let dir = SocketDir(dir);
+        let path = dir.0.join("tunnel.sock");
+        let listener = UnixListener::bind(&path)?;
+        self.start_listener(id, Listener::Unix(listener, dir), reply);
+        Ok((id, path))
+    }
+
+    #[cfg(not(unix))]
+    pub async fn listen_unix(
+        &self,
+        _reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) -> io::Result<(TunnelId, PathBuf)> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Unix sockets are not supported on this platform",
+        ))
+    }
+
+    /// Accepts connections from `listener` in the background, announcing each through `reply`
+    /// before relaying it as a tunnel of its own, until the connection of `reply` is gone
+    fn start_listener(
+        &self,
+        id: TunnelId,
+        listener: Listener,
+        reply: Box<dyn Reply<Data = DistantResponseData>>,
+    ) {
+        let tunnels = Arc::clone(&self.tunnels);
+        let listeners = Arc::clone(&self.listeners);
+        let mut guard = self.listeners.lock().unwrap();
+        let task = tokio::spawn(async move {
+            loop {
+                let accepted = tokio::select! {
+                    accepted = listener.accept() => accepted,
+                    _ = reply.closed() => {
+                        debug!("[Tunnel {id}] Stopping as connection is gone");
+                        break;
+                    }
+                };
+
+                let (connection, peer) = match accepted {
+                    Ok(x) => x,
+                    Err(x) => {
+                        error!("[Tunnel {id}] Failed to accept connection: {x}");
+                        break;
+                    }
+                };
+
+                // The client learns of the tunnel before anything is read from it
+                let tunnel = rand::random();
+                let accepted = DistantResponseData::TunnelAccepted {
+                    listener: id,
+                    id: tunnel,
+                    peer,
+                };
+                if let Err(x) = reply.send(accepted).await {
+                    debug!("[Tunnel {id}] Stopping as connection could not be announced: {x}");
+                    break;
+                }
+
+                add_tunnel(&tunnels, tunnel, connection, reply.clone_reply());
+            }
+
+            trace!("[Tunnel {id}] Removing internal tracking of listener");
+            listeners.lock().unwrap().remove(&id);
+        });
+        guard.insert(id, task);
+    }
+
+    /// Stops the listener with the given id from accepting connections, leaving the tunnels of
+    /// connections already accepted open
+    pub fn unlisten(&self, id: TunnelId) -> io::Result<()> {
+        match self.listeners.lock().unwrap().remove(&id) {
+            Some(task) => {
+                task.abort();
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("[Tunnel {id}] No listener found"),
+            )),
+        }
+    }
+
+    /// Queues `data` to be written through the tunnel with the given id
+    pub async fn write(&self, id: TunnelId, data: Vec<u8>) -> io::Result<()> {
+        let writer = match self.tunnels.lock().unwrap().get(&id) {
+            Some(Tunnel {
+                writer: Some(writer),
+                ..
+            }) => writer.clone(),
+            Some(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    format!("[Tunnel {id}] Closed for writing"),
+                ))
+            }
+            None => return Err(not_found(id)),
+        };
+
+        writer.send(data).await.map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                format!("[Tunnel {id}] Connection is no longer writable"),
+            )
+        })
+    }
+
+    /// Closes the tunnel with the given id for writing once everything queued has been written,
+    /// leaving it to send back what it reads until the other end closes it
+    pub fn close(&self, id: TunnelId) -> io::Result<()> {
+        match self.tunnels.lock().unwrap().get_mut(&id) {
+            Some(tunnel) => {
+                tunnel.writer = None;
+                Ok(())
+            }
+            None => Err(not_found(id)),
+        }
+    }
+}
+
+/// Listener whose connections become tunnels
+enum Listener {
+    Tcp(TcpListener),
+
+    /// Unix socket along with the directory containing it, which is removed with the listener
+    #[cfg(unix)]
+    Unix(UnixListener, SocketDir),
+}
+
+impl Listener {
+    /// Accepts the next connection, returning it along with a description of its peer
+    async fn accept(&self) -> io::Result<(Connection, String)> {
+        match self {
+            Self::Tcp(listener) => {
+                let (stream, addr) = listener.accept().await?;
+                Ok((Connection::Tcp(stream), addr.to_string()))
+            }
+            #[cfg(unix)]
+            Self::Unix(listener, dir) => {
+                let (stream, _) = listener.accept().await?;
+                let peer = dir.0.join("tunnel.sock").to_string_lossy().to_string();
+                Ok((Connection::Unix(stream), peer))
+            }
+        }
+    }
+}
+
+/// Connection relayed through a tunnel
+enum Connection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+/// Directory created to hold a unix socket, which is removed when dropped
+#[cfg(unix)]
+struct SocketDir(PathBuf);
+
+#[cfg(unix)]
+impl Drop for SocketDir {
+    fn drop(&mut self) {
+        if let Err(x) = std::fs::remove_dir_all(&self.0) {
+            warn!("Failed to remove socket directory {:?}: {x}", self.0);
+        }
+    }
+}
+
+/// Starts relaying data between `connection` and the client as the tunnel with the given id
+fn add_tunnel(
+    tunnels: &Tunnels,
+    id: TunnelId,
+    connection: Connection,
+    reply: Box<dyn Reply<Data = DistantResponseData>>,
+) {
+    let (tx, rx) = mpsc::channel(TUNNEL_CAPACITY);
+
+    // Hold the lock while spawning so that the task cannot finish and remove the tunnel before
+    // it has been added
+    let mut guard = tunnels.lock().unwrap();
+    let tunnels = Arc::clone(tunnels);
+    let task = match connection {
+        Connection::Tcp(stream) => tokio::spawn(relay(id, stream, rx, reply, tunnels)),
+        #[cfg(unix)]
+        Connection::Unix(stream) => tokio::spawn(relay(id, stream, rx, reply, tunnels)),
+    };
+    guard.insert(
+        id,
+        Tunnel {
+            writer: Some(tx),
+            task,
+        },
+    );
+}
+
+fn not_found(id: TunnelId) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("[Tunnel {id}] No tunnel found"),
+    )
+}
+
+/// Relays data between the connection of a tunnel and its client, writing what is queued by
+/// `rx` and sending back what is read until both sides close it or the client is gone
+async fn relay<S>(
+    id: TunnelId,
+    stream: S,
+    mut rx: mpsc::Receiver<Vec<u8>>,
+    reply: Box<dyn Reply<Data = DistantResponseData>>,
+    tunnels: Tunnels,
+) where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (mut reader, mut writer) = tokio::io::split(stream);
+
+    let mut write_task = tokio::spawn(async move {
+        while let Some(data) = rx.recv().await {
+            if let Err(x) = writer.write_all(&data).await {
+                debug!("[Tunnel {id}] Failed to write: {x}");
+                return;
+            }
+        }
+
+        // Let the other end know that nothing more is coming
+        let _ = writer.shutdown().await;
+    });
+
+    let mut buf = vec![0; MAX_PIPE_CHUNK_SIZE];
+    let mut client_gone = false;
+    loop {
+        let read = tokio::select! {
+            read = reader.read(&mut buf) => read,
+            _ = reply.closed() => {
+                debug!("[Tunnel {id}] Stopping as connection is gone");
+                client_gone = true;
+                break;
+            }
+        };
+
+        match read {
+            Ok(0) => break,
+            Ok(n) => {
+                let data = buf[..n].to_vec();
+                if let Err(x) = reply
+                    .send(DistantResponseData::TunnelData { id, data })
+                    .await
+                {
+                    debug!("[Tunnel {id}] Stopping as data could not be sent: {x}");
+                    client_gone = true;
+                    break;
+                }
+            }
+            Err(x) => {
+                debug!("[Tunnel {id}] Failed to read: {x}");
+                break;
+            }
+        }
+    }
+
+    if !client_gone
+        && reply
+            .send(DistantResponseData::TunnelClosed { id })
+            .await
+            .is_err()
+    {
+        client_gone = true;
+    }
+
+    // The other end may only have closed its side for writing, so the client can keep writing
+    // until it closes the tunnel as well, unless there is no client left to do so
+    if client_gone {
+        write_task.abort();
+    } else {
+        tokio::select! {
+            _ = &mut write_task => (),
+            _ = reply.closed() => {
+                debug!("[Tunnel {id}] Stopping as connection is gone");
+                write_task.abort();
+            }
+        }
+    }
+
+    trace!("[Tunnel {id}] Removing internal tracking");
+    tunnels.lock().unwrap().remove(&id);
+}
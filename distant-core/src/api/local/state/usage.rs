@@ -0,0 +1,78 @@
+use crate::data::Metrics;
+use distant_net::common::ConnectionId;
+use log::*;
+use std::{collections::HashMap, sync::Mutex};
+
+/// Holds the bytes written to files on behalf of each connection, warning when a connection goes
+/// beyond the soft quota
+#[derive(Default)]
+pub struct UsageState {
+    /// Bytes that each connection can write before the server warns about it
+    write_quota: Option<u64>,
+
+    written: Mutex<HashMap<ConnectionId, u64>>,
+}
+
+impl UsageState {
+    pub fn new(write_quota: Option<u64>) -> Self {
+        Self {
+            write_quota,
+            written: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts `len` bytes as written on behalf of the connection, logging a warning when the
+    /// total first goes over the quota
+    pub fn record(&self, connection_id: ConnectionId, len: usize) {
+        let mut written = self.written.lock().unwrap();
+        let total = written.entry(connection_id).or_default();
+        let before = *total;
+        *total = total.saturating_add(len as u64);
+
+        if let Some(quota) = self.write_quota {
+            if before <= quota && *total > quota {
+                warn!(
+                    "[Conn {}] Wrote {} bytes, going over the soft quota of {} bytes",
+                    connection_id, total, quota
+                );
+            }
+        }
+    }
+
+    /// Returns the metrics of the connection
+    pub fn metrics(&self, connection_id: ConnectionId) -> Metrics {
+        Metrics {
+            bytes_written: self
+                .written
+                .lock()
+                .unwrap()
+                .get(&connection_id)
+                .copied()
+                .unwrap_or_default(),
+            write_quota: self.write_quota,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_should_track_bytes_written_by_each_connection() {
+        let state = UsageState::new(Some(10));
+        state.record(1, 6);
+        state.record(2, 3);
+        state.record(1, 6);
+
+        let metrics = state.metrics(1);
+        assert_eq!(metrics.bytes_written, 12);
+        assert!(metrics.is_over_quota());
+
+        let metrics = state.metrics(2);
+        assert_eq!(metrics.bytes_written, 3);
+        assert!(!metrics.is_over_quota());
+
+        assert_eq!(state.metrics(3).bytes_written, 0);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{constants::SERVER_WATCHER_CAPACITY, data::ChangeKind};
+use crate::{
+    constants::SERVER_WATCHER_CAPACITY,
+    data::{Change, JournalEntry, JournalSeq, WatchId},
+};
 use distant_net::common::ConnectionId;
 use log::*;
 use notify::{
@@ -19,9 +22,15 @@ use tokio::{
     task::JoinHandle,
 };
 
+mod journal;
+pub use journal::*;
+
 mod path;
 pub use path::*;
 
+mod rename;
+pub use rename::*;
+
 /// Holds information related to watched paths on the server
 pub struct WatcherState {
     channel: WatcherChannel,
@@ -137,19 +146,82 @@ impl WatcherChannel {
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to watch dropped"))?
     }
 
-    /// Unwatch a path for a specific connection denoted by the id
-    pub async fn unwatch(&self, id: ConnectionId, path: impl AsRef<Path>) -> io::Result<()> {
+    /// Unwatch a path for a specific connection denoted by the id, limited to the registration
+    /// with `watch_id` if provided
+    pub async fn unwatch(
+        &self,
+        id: ConnectionId,
+        watch_id: Option<WatchId>,
+        path: impl AsRef<Path>,
+    ) -> io::Result<()> {
         let (cb, rx) = oneshot::channel();
         let path = tokio::fs::canonicalize(path.as_ref())
             .await
             .unwrap_or_else(|_| path.as_ref().to_path_buf());
         self.tx
-            .send(InnerWatcherMsg::Unwatch { id, path, cb })
+            .send(InnerWatcherMsg::Unwatch {
+                id,
+                watch_id,
+                path,
+                cb,
+            })
             .await
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal watcher task closed"))?;
         rx.await
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to unwatch dropped"))?
     }
+
+    /// Start recording changes to a path in a journal that is kept regardless of connections,
+    /// doing nothing if the path already has a journal
+    pub async fn journal_start(&self, path: impl AsRef<Path>, recursive: bool) -> io::Result<()> {
+        let (cb, rx) = oneshot::channel();
+        let path = tokio::fs::canonicalize(path.as_ref()).await?;
+        self.tx
+            .send(InnerWatcherMsg::JournalStart {
+                path,
+                recursive,
+                cb,
+            })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal watcher task closed"))?;
+        rx.await.map_err(|_| {
+            io::Error::new(io::ErrorKind::Other, "Response to journal start dropped")
+        })?
+    }
+
+    /// Retrieve the changes recorded in the journal of a path after the sequence number `since`,
+    /// alongside the sequence number of the latest change
+    pub async fn journal_changes(
+        &self,
+        path: impl AsRef<Path>,
+        since: JournalSeq,
+    ) -> io::Result<(JournalSeq, Vec<JournalEntry>)> {
+        let (cb, rx) = oneshot::channel();
+        let path = tokio::fs::canonicalize(path.as_ref())
+            .await
+            .unwrap_or_else(|_| path.as_ref().to_path_buf());
+        self.tx
+            .send(InnerWatcherMsg::JournalChanges { path, since, cb })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal watcher task closed"))?;
+        rx.await.map_err(|_| {
+            io::Error::new(io::ErrorKind::Other, "Response to journal changes dropped")
+        })?
+    }
+
+    /// Stop recording changes to a path, discarding its journal
+    pub async fn journal_stop(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let (cb, rx) = oneshot::channel();
+        let path = tokio::fs::canonicalize(path.as_ref())
+            .await
+            .unwrap_or_else(|_| path.as_ref().to_path_buf());
+        self.tx
+            .send(InnerWatcherMsg::JournalStop { path, cb })
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Internal watcher task closed"))?;
+        rx.await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Response to journal stop dropped"))?
+    }
 }
 
 /// Internal message to pass to our task below to perform some action
@@ -160,6 +232,21 @@ enum InnerWatcherMsg {
     },
     Unwatch {
         id: ConnectionId,
+        watch_id: Option<WatchId>,
+        path: PathBuf,
+        cb: oneshot::Sender<io::Result<()>>,
+    },
+    JournalStart {
+        path: PathBuf,
+        recursive: bool,
+        cb: oneshot::Sender<io::Result<()>>,
+    },
+    JournalChanges {
+        path: PathBuf,
+        since: JournalSeq,
+        cb: oneshot::Sender<io::Result<(JournalSeq, Vec<JournalEntry>)>>,
+    },
+    JournalStop {
         path: PathBuf,
         cb: oneshot::Sender<io::Result<()>>,
     },
@@ -171,110 +258,226 @@ enum InnerWatcherMsg {
     },
 }
 
+/// Path being watched by the underlying watcher on behalf of registered paths and journals
+struct WatchedPath {
+    /// Registered paths and journals sharing the watch
+    cnt: usize,
+
+    /// Whether the path is being watched recursively, which is the case if any of those sharing
+    /// the watch asked for it
+    recursive: bool,
+}
+
+/// Adds to the times `path` is watched, only having `watcher` watch it the first time or again
+/// when `recursive` and the path was not yet watched recursively
+fn watch_path(
+    watcher: &mut impl Watcher,
+    watched: &mut HashMap<PathBuf, WatchedPath>,
+    path: &Path,
+    recursive: bool,
+) -> io::Result<()> {
+    if let Some(watched_path) = watched.get_mut(path) {
+        if recursive && !watched_path.recursive {
+            watcher
+                .watch(path, RecursiveMode::Recursive)
+                .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?;
+            watched_path.recursive = true;
+        }
+        watched_path.cnt += 1;
+        return Ok(());
+    }
+
+    watcher
+        .watch(
+            path,
+            if recursive {
+                RecursiveMode::Recursive
+            } else {
+                RecursiveMode::NonRecursive
+            },
+        )
+        .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?;
+    watched.insert(path.to_path_buf(), WatchedPath { cnt: 1, recursive });
+    Ok(())
+}
+
+/// Subtracts `cnt` from the times `path` is watched, having `watcher` unwatch it once nothing is
+/// left watching it
+fn unwatch_path(
+    watcher: &mut impl Watcher,
+    watched: &mut HashMap<PathBuf, WatchedPath>,
+    path: &Path,
+    cnt: usize,
+) -> io::Result<()> {
+    match watched.get_mut(path) {
+        Some(watched_path) if watched_path.cnt > cnt => {
+            watched_path.cnt -= cnt;
+            Ok(())
+        }
+        Some(_) => {
+            watched.remove(path);
+            watcher
+                .unwatch(path)
+                .map_err(|x| io::Error::new(io::ErrorKind::Other, x))
+        }
+        None => Ok(()),
+    }
+}
+
 async fn watcher_task(mut watcher: impl Watcher, mut rx: mpsc::Receiver<InnerWatcherMsg>) {
     // TODO: Optimize this in some way to be more performant than
     //       checking every path whenever an event comes in
     let mut registered_paths: Vec<RegisteredPath> = Vec::new();
-    let mut path_cnt: HashMap<PathBuf, usize> = HashMap::new();
 
-    while let Some(msg) = rx.recv().await {
+    // Each path is only watched once by our watcher no matter how many registered paths (across
+    // connections and the channels within them) share it, with changes fanned out to each of them
+    let mut watched: HashMap<PathBuf, WatchedPath> = HashMap::new();
+
+    // Journals count towards the times a path is watched so that they keep recording after all
+    // connections watching the same path have unwatched it
+    let mut journals: HashMap<PathBuf, Journal> = HashMap::new();
+
+    // Changes that may be one side of a rename are held back briefly to pair them with the other
+    let mut renames = RenamePairer::default();
+
+    loop {
+        let msg = match renames.deadline() {
+            Some(deadline) => tokio::select! {
+                msg = rx.recv() => msg,
+                _ = tokio::time::sleep_until(deadline) => {
+                    if let Some(change) = renames.flush() {
+                        report_change(&registered_paths, &mut journals, change).await;
+                    }
+                    continue;
+                }
+            },
+            None => rx.recv().await,
+        };
+
+        let msg = match msg {
+            Some(msg) => msg,
+            None => break,
+        };
+
         match msg {
             InnerWatcherMsg::Watch {
                 registered_path,
                 cb,
             } => {
-                // Check if we are tracking the path across any connection
-                if let Some(cnt) = path_cnt.get_mut(registered_path.path()) {
-                    // Increment the count of times we are watching that path
-                    *cnt += 1;
+                let res = watch_path(
+                    &mut watcher,
+                    &mut watched,
+                    registered_path.path(),
+                    registered_path.is_recursive(),
+                );
+
+                if res.is_ok() {
+                    // Watching again with the same id replaces the earlier registration, moving
+                    // its changes over to the latest request
+                    if let Some(watch_id) = registered_path.watch_id() {
+                        let old_len = registered_paths.len();
+                        registered_paths.retain(|p| {
+                            !p.is_registered_by(
+                                registered_path.id(),
+                                Some(watch_id),
+                                registered_path.path(),
+                            )
+                        });
+                        let replaced_cnt = old_len - registered_paths.len();
+                        if replaced_cnt > 0 {
+                            let _ = unwatch_path(
+                                &mut watcher,
+                                &mut watched,
+                                registered_path.path(),
+                                replaced_cnt,
+                            );
+                        }
+                    }
 
-                    // Store the registered path in our collection without worry
-                    // since we are already watching a path that impacts this one
                     registered_paths.push(registered_path);
+                }
 
-                    // Send an okay because we always succeed in this case
+                // Send the result of the watch, but don't worry if the channel was closed
+                let _ = cb.send(res);
+            }
+            InnerWatcherMsg::Unwatch {
+                id,
+                watch_id,
+                path,
+                cb,
+            } => {
+                // Remove the registrations of the path by the connection (limited to the one
+                // with the watch id if provided), capturing how many we removed
+                let removed_cnt = {
+                    let old_len = registered_paths.len();
+                    registered_paths.retain(|p| !p.is_registered_by(id, watch_id, &path));
+                    old_len - registered_paths.len()
+                };
+
+                // Only once nothing else shares the path do we actually unwatch it
+                let _ = cb.send(if removed_cnt == 0 {
+                    Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("{path:?} is not being watched"),
+                    ))
+                } else {
+                    unwatch_path(&mut watcher, &mut watched, &path, removed_cnt)
+                });
+            }
+            InnerWatcherMsg::JournalStart {
+                path,
+                recursive,
+                cb,
+            } => {
+                if journals.contains_key(path.as_path()) {
                     let _ = cb.send(Ok(()));
                 } else {
-                    let res = watcher
-                        .watch(
-                            registered_path.path(),
-                            if registered_path.is_recursive() {
-                                RecursiveMode::Recursive
-                            } else {
-                                RecursiveMode::NonRecursive
-                            },
-                        )
-                        .map_err(|x| io::Error::new(io::ErrorKind::Other, x));
-
-                    // If we succeeded, store our registered path and set the tracking cnt to 1
+                    let res = watch_path(&mut watcher, &mut watched, &path, recursive);
                     if res.is_ok() {
-                        path_cnt.insert(registered_path.path().to_path_buf(), 1);
-                        registered_paths.push(registered_path);
+                        journals.insert(path.clone(), Journal::new(path, recursive));
                     }
 
-                    // Send the result of the watch, but don't worry if the channel was closed
                     let _ = cb.send(res);
                 }
             }
-            InnerWatcherMsg::Unwatch { id, path, cb } => {
-                // Check if we are tracking the path across any connection
-                if let Some(cnt) = path_cnt.get(path.as_path()) {
-                    // Cycle through and remove all paths that match the given id and path,
-                    // capturing how many paths we removed
-                    let removed_cnt = {
-                        let old_len = registered_paths.len();
-                        registered_paths
-                            .retain(|p| p.id() != id || (p.path() != path && p.raw_path() != path));
-                        let new_len = registered_paths.len();
-                        old_len - new_len
-                    };
-
-                    // 1. If we are now at zero cnt for our path, we want to actually unwatch the
-                    //    path with our watcher
-                    // 2. If we removed nothing from our path list, we want to return an error
-                    // 3. Otherwise, we return okay because we succeeded
-                    if *cnt <= removed_cnt {
-                        let _ = cb.send(
-                            watcher
-                                .unwatch(&path)
-                                .map_err(|x| io::Error::new(io::ErrorKind::Other, x)),
-                        );
-                    } else if removed_cnt == 0 {
-                        // Send a failure as there was nothing to unwatch for this connection
-                        let _ = cb.send(Err(io::Error::new(
-                            io::ErrorKind::Other,
-                            format!("{path:?} is not being watched"),
-                        )));
-                    } else {
-                        // Send a success as we removed some paths
-                        let _ = cb.send(Ok(()));
-                    }
-                } else {
-                    // Send a failure as there was nothing to unwatch
+            InnerWatcherMsg::JournalChanges { path, since, cb } => {
+                let _ = cb.send(match journals.get(path.as_path()) {
+                    Some(journal) => journal.changes_since(since),
+                    None => Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("{path:?} is not being journaled"),
+                    )),
+                });
+            }
+            InnerWatcherMsg::JournalStop { path, cb } => {
+                if journals.remove(path.as_path()).is_none() {
                     let _ = cb.send(Err(io::Error::new(
-                        io::ErrorKind::Other,
-                        format!("{path:?} is not being watched"),
+                        io::ErrorKind::NotFound,
+                        format!("{path:?} is not being journaled"),
                     )));
+                } else {
+                    let _ = cb.send(unwatch_path(&mut watcher, &mut watched, &path, 1));
                 }
             }
             InnerWatcherMsg::Event { ev } => {
-                let kind = ChangeKind::from(ev.kind);
-
-                for registered_path in registered_paths.iter() {
-                    match registered_path.filter_and_send(kind, &ev.paths).await {
-                        Ok(_) => (),
-                        Err(x) => error!(
-                            "[Conn {}] Failed to forward changes to paths: {}",
-                            registered_path.id(),
-                            x
-                        ),
-                    }
+                for change in renames.pair(ev) {
+                    report_change(&registered_paths, &mut journals, change).await;
                 }
             }
             InnerWatcherMsg::Error { err } => {
                 let msg = err.to_string();
                 error!("Watcher encountered an error {} for {:?}", msg, err.paths);
 
+                // Without knowing which changes were missed, journals can no longer be trusted to
+                // have all changes up to now
+                for journal in journals.values_mut() {
+                    if err.paths.is_empty()
+                        || err.paths.iter().any(|p| p.starts_with(journal.path()))
+                    {
+                        journal.invalidate();
+                    }
+                }
+
                 for registered_path in registered_paths.iter() {
                     match registered_path
                         .filter_and_send_error(&msg, &err.paths, !err.paths.is_empty())
@@ -292,3 +495,28 @@ async fn watcher_task(mut watcher: impl Watcher, mut rx: mpsc::Receiver<InnerWat
         }
     }
 }
+
+/// Records `change` in the journals and sends it to the registered paths it applies to
+async fn report_change(
+    registered_paths: &[RegisteredPath],
+    journals: &mut HashMap<PathBuf, Journal>,
+    change: Change,
+) {
+    for journal in journals.values_mut() {
+        journal.record(change.kind, &change.paths);
+    }
+
+    for registered_path in registered_paths.iter() {
+        match registered_path
+            .filter_and_send(change.kind, &change.paths)
+            .await
+        {
+            Ok(_) => (),
+            Err(x) => error!(
+                "[Conn {}] Failed to forward changes to paths: {}",
+                registered_path.id(),
+                x
+            ),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use crate::constants::SERVER_JOURNAL_CAPACITY;
+use crate::data::{ChangeKind, JournalEntry, JournalSeq};
+use std::{
+    collections::VecDeque,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Records changes to a path with sequence numbers so that they can be retrieved later, keeping
+/// at most [`SERVER_JOURNAL_CAPACITY`] of the latest changes
+#[derive(Debug)]
+pub struct Journal {
+    /// The canonicalized path whose changes are recorded
+    path: PathBuf,
+
+    /// Whether or not changes within subdirectories are recorded
+    recursive: bool,
+
+    /// Sequence number of the latest change recorded, or 0 if none have been
+    seq: JournalSeq,
+
+    /// Sequence number of the oldest change that can still be retrieved, where changes before
+    /// it were either discarded or missed
+    first: JournalSeq,
+
+    /// Changes recorded, oldest first
+    entries: VecDeque<JournalEntry>,
+}
+
+impl Journal {
+    /// Creates an empty journal for the canonicalized `path`
+    pub fn new(path: impl Into<PathBuf>, recursive: bool) -> Self {
+        Self {
+            path: path.into(),
+            recursive,
+            seq: 0,
+            first: 1,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Represents the canonicalized path whose changes are recorded
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Records a change of `kind` to those of `paths` that fall within this journal, skipping
+    /// accesses as they change nothing
+    pub fn record<T>(&mut self, kind: ChangeKind, paths: T)
+    where
+        T: IntoIterator,
+        T::Item: AsRef<Path>,
+    {
+        if kind.is_access_kind() {
+            return;
+        }
+
+        let mut paths: Vec<PathBuf> = paths
+            .into_iter()
+            .map(|p| p.as_ref().to_path_buf())
+            .collect();
+
+        // Both sides of a rename are kept together when either falls within the journal
+        if kind == ChangeKind::Renamed {
+            if !paths.iter().any(|p| self.applies_to_path(p)) {
+                return;
+            }
+        } else {
+            paths.retain(|p| self.applies_to_path(p));
+            if paths.is_empty() {
+                return;
+            }
+        }
+
+        self.seq += 1;
+        self.entries.push_back(JournalEntry {
+            seq: self.seq,
+            kind,
+            paths,
+        });
+
+        while self.entries.len() > SERVER_JOURNAL_CAPACITY {
+            self.entries.pop_front();
+            self.first += 1;
+        }
+    }
+
+    /// Marks every change up to now as missed, such as when the watcher reports an error, so that
+    /// retrieving changes from before this point fails rather than leaving out changes
+    pub fn invalidate(&mut self) {
+        self.seq += 1;
+        self.first = self.seq + 1;
+        self.entries.clear();
+    }
+
+    /// Returns the sequence number of the latest change alongside the changes recorded after
+    /// `since`, failing if any of those changes are no longer in the journal
+    pub fn changes_since(&self, since: JournalSeq) -> io::Result<(JournalSeq, Vec<JournalEntry>)> {
+        if since + 1 < self.first {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "Journal of {:?} no longer has changes since {since}, only those since {}",
+                    self.path,
+                    self.first - 1
+                ),
+            ));
+        }
+
+        let entries = self
+            .entries
+            .iter()
+            .filter(|entry| entry.seq > since)
+            .cloned()
+            .collect();
+        Ok((self.seq, entries))
+    }
+
+    /// Returns true if the journal records changes to the given path, respecting recursion
+    fn applies_to_path(&self, path: &Path) -> bool {
+        match path.strip_prefix(self.path()) {
+            // 0 means exact match, 1 means within immediate directory, and 2+ means recursive
+            Ok(p) => p.components().count() < 2 || self.recursive,
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_should_skip_accesses_and_paths_outside_of_journal() {
+        let mut journal = Journal::new("/root", false);
+        journal.record(ChangeKind::AccessRead, ["/root/file"]);
+        journal.record(ChangeKind::Content, ["/other/file", "/root/dir/file"]);
+        journal.record(ChangeKind::Content, ["/root/file", "/other/file"]);
+
+        let (seq, entries) = journal.changes_since(0).unwrap();
+        assert_eq!(seq, 1);
+        assert_eq!(
+            entries,
+            vec![JournalEntry {
+                seq: 1,
+                kind: ChangeKind::Content,
+                paths: vec![PathBuf::from("/root/file")],
+            }]
+        );
+    }
+
+    #[test]
+    fn changes_since_should_only_return_changes_after_sequence_number() {
+        let mut journal = Journal::new("/root", true);
+        journal.record(ChangeKind::Create, ["/root/a"]);
+        journal.record(ChangeKind::Content, ["/root/a"]);
+        journal.record(ChangeKind::Remove, ["/root/dir/b"]);
+
+        let (seq, entries) = journal.changes_since(1).unwrap();
+        assert_eq!(seq, 3);
+        assert_eq!(
+            entries.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+
+        let (seq, entries) = journal.changes_since(3).unwrap();
+        assert_eq!(seq, 3);
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn changes_since_should_fail_if_changes_were_discarded() {
+        let mut journal = Journal::new("/root", false);
+        for _ in 0..=SERVER_JOURNAL_CAPACITY {
+            journal.record(ChangeKind::Content, ["/root/file"]);
+        }
+
+        // The first change was discarded, so we can only pick up after it
+        assert!(journal.changes_since(0).is_err());
+        let (seq, entries) = journal.changes_since(1).unwrap();
+        assert_eq!(seq, SERVER_JOURNAL_CAPACITY as JournalSeq + 1);
+        assert_eq!(entries.len(), SERVER_JOURNAL_CAPACITY);
+    }
+
+    #[test]
+    fn changes_since_should_fail_if_changes_were_missed() {
+        let mut journal = Journal::new("/root", false);
+        journal.record(ChangeKind::Content, ["/root/file"]);
+        journal.invalidate();
+
+        assert!(journal.changes_since(1).is_err());
+        let (seq, entries) = journal.changes_since(2).unwrap();
+        assert_eq!(seq, 2);
+        assert!(entries.is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::data::{Change, ChangeKind, ChangeKindSet, DistantResponseData, Error};
+use crate::data::{Change, ChangeKind, ChangeKindSet, DistantResponseData, Error, WatchId};
 use distant_net::common::ConnectionId;
 use distant_net::server::Reply;
 use std::{
@@ -14,6 +14,9 @@ pub struct RegisteredPath {
     /// Unique id tied to the path to distinguish it
     id: ConnectionId,
 
+    /// Id chosen by the connection to distinguish this from its other registrations of the path
+    watch_id: Option<WatchId>,
+
     /// The raw path provided to the watcher, which is not canonicalized
     raw_path: PathBuf,
 
@@ -36,6 +39,7 @@ pub struct RegisteredPath {
 impl fmt::Debug for RegisteredPath {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RegisteredPath")
+            .field("watch_id", &self.watch_id)
             .field("raw_path", &self.raw_path)
             .field("path", &self.path)
             .field("recursive", &self.recursive)
@@ -45,18 +49,22 @@ impl fmt::Debug for RegisteredPath {
 }
 
 impl PartialEq for RegisteredPath {
-    /// Checks for equality using the id, canonicalized path, and allowed change kinds
+    /// Checks for equality using the ids, canonicalized path, and allowed change kinds
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id && self.path == other.path && self.allowed == other.allowed
+        self.id == other.id
+            && self.watch_id == other.watch_id
+            && self.path == other.path
+            && self.allowed == other.allowed
     }
 }
 
 impl Eq for RegisteredPath {}
 
 impl Hash for RegisteredPath {
-    /// Hashes using the id, canonicalized path, and allowed change kinds
+    /// Hashes using the ids, canonicalized path, and allowed change kinds
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
+        self.watch_id.hash(state);
         self.path.hash(state);
         self.allowed.hash(state);
     }
@@ -66,6 +74,7 @@ impl RegisteredPath {
     /// Registers a new path to be watched (does not actually do any watching)
     pub async fn register(
         id: ConnectionId,
+        watch_id: Option<WatchId>,
         path: impl Into<PathBuf>,
         recursive: bool,
         only: impl Into<ChangeKindSet>,
@@ -86,6 +95,7 @@ impl RegisteredPath {
 
         Ok(Self {
             id,
+            watch_id,
             raw_path,
             path,
             recursive,
@@ -100,6 +110,24 @@ impl RegisteredPath {
         self.id
     }
 
+    /// Represents the id chosen by the connection for this registration, if any
+    pub fn watch_id(&self) -> Option<WatchId> {
+        self.watch_id
+    }
+
+    /// Returns true if this registration is the one of the path for connection `id` identified
+    /// by `watch_id`, or any of them if no `watch_id` is given
+    pub fn is_registered_by(
+        &self,
+        id: ConnectionId,
+        watch_id: Option<WatchId>,
+        path: &Path,
+    ) -> bool {
+        self.id == id
+            && (self.path() == path || self.raw_path() == path)
+            && (watch_id.is_none() || self.watch_id == watch_id)
+    }
+
     /// Represents the path provided during registration before canonicalization
     pub fn raw_path(&self) -> &Path {
         self.raw_path.as_path()
@@ -135,10 +163,24 @@ impl RegisteredPath {
 
         let paths: Vec<PathBuf> = paths
             .into_iter()
-            .filter(|p| self.applies_to_path(p.as_ref()))
             .map(|p| p.as_ref().to_path_buf())
             .collect();
 
+        // Both sides of a rename are kept together when either applies, as one alone would not
+        // say where the path went or came from
+        let paths: Vec<PathBuf> = if kind == ChangeKind::Renamed {
+            if paths.iter().any(|p| self.applies_to_path(p)) {
+                paths
+            } else {
+                Vec::new()
+            }
+        } else {
+            paths
+                .into_iter()
+                .filter(|p| self.applies_to_path(p))
+                .collect()
+        };
+
         if !paths.is_empty() {
             self.reply
                 .send(DistantResponseData::Changed(Change { kind, paths }))
@@ -0,0 +1,324 @@
+use crate::constants::SERVER_WATCHER_RENAME_TIMEOUT;
+use crate::data::{Change, ChangeKind};
+use notify::Event as WatcherEvent;
+use std::path::{Path, PathBuf};
+use tokio::time::Instant;
+
+/// Pairs up the separate changes reported for each side of a rename into a single
+/// [`ChangeKind::Renamed`] change, whose paths are where something was renamed from and to
+///
+/// Sides are matched using the cookie given to both by inotify when available. Elsewhere, a
+/// heuristic pairs a change that may be one side of a rename with the change that immediately
+/// follows it, using which of the paths still exist (respecting case) to tell them apart.
+#[derive(Debug, Default)]
+pub struct RenamePairer {
+    /// Change that may be the first side of a rename, held back until the change after it arrives
+    /// or its deadline passes
+    pending: Option<PendingRename>,
+
+    /// Tracker of the latest rename paired using trackers, whose combined change reported after
+    /// both sides is dropped as we already reported it
+    paired_tracker: Option<usize>,
+}
+
+#[derive(Debug)]
+struct PendingRename {
+    kind: ChangeKind,
+    path: PathBuf,
+    tracker: Option<usize>,
+    deadline: Instant,
+}
+
+impl RenamePairer {
+    /// Returns the time by which the held back change should be flushed, if there is one
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref().map(|pending| pending.deadline)
+    }
+
+    /// Takes the held back change as it was reported, used once no other side arrived in time
+    pub fn flush(&mut self) -> Option<Change> {
+        self.pending.take().map(PendingRename::into_change)
+    }
+
+    /// Takes in the next event reported by the watcher, returning the changes that are ready to
+    /// be reported in order, which may hold back the change of the event itself
+    pub fn pair(&mut self, ev: WatcherEvent) -> Vec<Change> {
+        let tracker = ev.tracker();
+        let kind = ChangeKind::from(ev.kind);
+        let mut changes = Vec::new();
+
+        if let Some(pending) = self.pending.take() {
+            match pending.pair_with(kind, tracker, &ev.paths) {
+                Some(paths) => {
+                    if tracker.is_some() {
+                        self.paired_tracker = tracker;
+                    }
+                    changes.push(Change {
+                        kind: ChangeKind::Renamed,
+                        paths,
+                    });
+                    return changes;
+                }
+                None => changes.push(pending.into_change()),
+            }
+        }
+
+        if kind == ChangeKind::RenameBoth && ev.paths.len() == 2 {
+            if tracker.is_none() || tracker != self.paired_tracker {
+                changes.push(Change {
+                    kind: ChangeKind::Renamed,
+                    paths: ev.paths,
+                });
+            }
+        } else if may_start_rename(kind) && ev.paths.len() == 1 {
+            self.pending = Some(PendingRename {
+                kind,
+                path: ev.paths.into_iter().next().unwrap(),
+                tracker,
+                deadline: Instant::now() + SERVER_WATCHER_RENAME_TIMEOUT,
+            });
+        } else {
+            changes.push(Change {
+                kind,
+                paths: ev.paths,
+            });
+        }
+
+        changes
+    }
+}
+
+impl PendingRename {
+    fn into_change(self) -> Change {
+        Change {
+            kind: self.kind,
+            paths: vec![self.path],
+        }
+    }
+
+    /// Returns the paths renamed from and to if the change of `kind` to `paths` is the other side
+    /// of the rename that this change may be part of
+    fn pair_with(
+        &self,
+        kind: ChangeKind,
+        tracker: Option<usize>,
+        paths: &[PathBuf],
+    ) -> Option<Vec<PathBuf>> {
+        let path = match paths {
+            [path] if *path != self.path => path,
+            _ => return None,
+        };
+
+        match (self.kind, kind) {
+            // Sides are explicit, where those with trackers must share them
+            (ChangeKind::RenameFrom, ChangeKind::RenameTo) if self.tracker == tracker => {
+                Some(vec![self.path.clone(), path.clone()])
+            }
+
+            // Sides are unknown, so the one that was renamed from is the one no longer there
+            (ChangeKind::Rename, ChangeKind::Rename) if self.tracker.is_none() => {
+                match (exists_exactly(&self.path), exists_exactly(path)) {
+                    (false, true) => Some(vec![self.path.clone(), path.clone()]),
+                    (true, false) => Some(vec![path.clone(), self.path.clone()]),
+                    _ => None,
+                }
+            }
+
+            // Watchers without rename support report a removal and creation, which we only treat
+            // as a rename when the name is kept (ignoring case) to avoid guessing about unrelated
+            // files, covering moves between directories and renames that only change case
+            (ChangeKind::Remove, ChangeKind::Create)
+                if same_name_ignoring_case(&self.path, path)
+                    && !exists_exactly(&self.path)
+                    && exists_exactly(path) =>
+            {
+                Some(vec![self.path.clone(), path.clone()])
+            }
+
+            _ => None,
+        }
+    }
+}
+
+/// Returns true if a change of `kind` may be the first side of a rename
+fn may_start_rename(kind: ChangeKind) -> bool {
+    matches!(
+        kind,
+        ChangeKind::RenameFrom | ChangeKind::Rename | ChangeKind::Remove
+    )
+}
+
+/// Returns true if both paths have the same file name when ignoring case
+fn same_name_ignoring_case(a: &Path, b: &Path) -> bool {
+    match (a.file_name(), b.file_name()) {
+        (Some(a), Some(b)) => {
+            a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase()
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if an entry exists at `path` with exactly its file name, which tells apart both
+/// sides of a rename that only changes case on filesystems that ignore case
+fn exists_exactly(path: &Path) -> bool {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => std::fs::read_dir(parent)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .any(|entry| entry.file_name() == name)
+            })
+            .unwrap_or(false),
+        _ => path.exists(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use notify::{
+        event::{CreateKind, ModifyKind, RemoveKind, RenameMode},
+        EventKind,
+    };
+    use test_log::test;
+
+    fn rename_event(mode: RenameMode, paths: &[&Path], tracker: Option<usize>) -> WatcherEvent {
+        let mut ev = WatcherEvent::new(EventKind::Modify(ModifyKind::Name(mode)));
+        for path in paths {
+            ev = ev.add_path(path.to_path_buf());
+        }
+        match tracker {
+            Some(tracker) => ev.set_tracker(tracker),
+            None => ev,
+        }
+    }
+
+    fn renamed(from: &Path, to: &Path) -> Change {
+        Change {
+            kind: ChangeKind::Renamed,
+            paths: vec![from.to_path_buf(), to.to_path_buf()],
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn pair_should_combine_sides_sharing_a_tracker_and_drop_their_combined_event() {
+        let (from, to) = (Path::new("/a/from"), Path::new("/a/to"));
+        let mut pairer = RenamePairer::default();
+
+        assert_eq!(
+            pairer.pair(rename_event(RenameMode::From, &[from], Some(3))),
+            vec![]
+        );
+        assert!(pairer.deadline().is_some());
+        assert_eq!(
+            pairer.pair(rename_event(RenameMode::To, &[to], Some(3))),
+            vec![renamed(from, to)]
+        );
+        assert_eq!(
+            pairer.pair(rename_event(RenameMode::Both, &[from, to], Some(3))),
+            vec![]
+        );
+        assert_eq!(pairer.deadline(), None);
+    }
+
+    #[test(tokio::test)]
+    async fn pair_should_report_held_back_side_as_is_if_next_change_is_not_its_other_side() {
+        let (from, to) = (Path::new("/a/from"), Path::new("/a/to"));
+        let mut pairer = RenamePairer::default();
+
+        assert_eq!(
+            pairer.pair(rename_event(RenameMode::From, &[from], Some(3))),
+            vec![]
+        );
+        assert_eq!(
+            pairer.pair(rename_event(RenameMode::To, &[to], Some(4))),
+            vec![
+                Change {
+                    kind: ChangeKind::RenameFrom,
+                    paths: vec![from.to_path_buf()],
+                },
+                Change {
+                    kind: ChangeKind::RenameTo,
+                    paths: vec![to.to_path_buf()],
+                },
+            ]
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn flush_should_return_held_back_side_as_is() {
+        let from = Path::new("/a/from");
+        let mut pairer = RenamePairer::default();
+
+        assert_eq!(
+            pairer.pair(rename_event(RenameMode::From, &[from], Some(3))),
+            vec![]
+        );
+        assert_eq!(
+            pairer.flush(),
+            Some(Change {
+                kind: ChangeKind::RenameFrom,
+                paths: vec![from.to_path_buf()],
+            })
+        );
+        assert_eq!(pairer.flush(), None);
+    }
+
+    #[test(tokio::test)]
+    async fn pair_should_order_sides_without_trackers_by_which_exists_respecting_case() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let to = temp.child("FILE");
+        to.touch().unwrap();
+        let from = temp.child("file");
+        let mut pairer = RenamePairer::default();
+
+        // Sides can be reported in either order, which we figure out from what is left
+        assert_eq!(
+            pairer.pair(rename_event(RenameMode::Any, &[to.path()], None)),
+            vec![]
+        );
+        assert_eq!(
+            pairer.pair(rename_event(RenameMode::Any, &[from.path()], None)),
+            vec![renamed(from.path(), to.path())]
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn pair_should_combine_removal_and_creation_only_if_name_is_kept() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let to = temp.child("dir").child("file");
+        to.touch().unwrap();
+        let other = temp.child("other");
+        other.touch().unwrap();
+        let from = temp.child("file");
+        let mut pairer = RenamePairer::default();
+
+        let remove =
+            || WatcherEvent::new(EventKind::Remove(RemoveKind::File)).add_path(from.to_path_buf());
+        let create = |path: &Path| {
+            WatcherEvent::new(EventKind::Create(CreateKind::File)).add_path(path.to_path_buf())
+        };
+
+        assert_eq!(pairer.pair(remove()), vec![]);
+        assert_eq!(
+            pairer.pair(create(to.path())),
+            vec![renamed(from.path(), to.path())]
+        );
+
+        assert_eq!(pairer.pair(remove()), vec![]);
+        assert_eq!(
+            pairer.pair(create(other.path())),
+            vec![
+                Change {
+                    kind: ChangeKind::Remove,
+                    paths: vec![from.to_path_buf()],
+                },
+                Change {
+                    kind: ChangeKind::Create,
+                    paths: vec![other.to_path_buf()],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,699 @@
+use crate::data::{
+    BatchOp, DiffTarget, DirEntry, DistantRequestData, DistantResponseData, Error, ErrorKind,
+    PathMapping, SearchQueryMatch, WorkspaceRoot,
+};
+use distant_net::common::ConnectionId;
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// Path maps set by each connection, which last until the connection is disconnected from the
+/// server because a connection keeps its id when it reconnects
+#[derive(Default)]
+pub struct PathMaps(Mutex<HashMap<ConnectionId, Arc<PathMap>>>);
+
+impl PathMaps {
+    /// Replaces the mappings of the connection, keeping its roots
+    pub fn set_mappings(&self, connection_id: ConnectionId, mappings: Vec<PathMapping>) {
+        self.update(connection_id, |map| map.mappings = mappings);
+    }
+
+    /// Declares the roots of the connection along with where they resolve to on the server,
+    /// keeping its mappings, and failing if the connection has already declared roots as they
+    /// would otherwise be lifted by whoever is confined to them
+    pub fn set_roots(
+        &self,
+        connection_id: ConnectionId,
+        roots: Vec<WorkspaceRoot>,
+        resolved_roots: Vec<PathBuf>,
+    ) -> Result<(), Error> {
+        if self.get(connection_id).is_some_and(|map| map.has_roots()) {
+            return Err(Error {
+                kind: ErrorKind::PermissionDenied,
+                description: String::from("Roots have already been declared for the connection"),
+            });
+        }
+
+        self.update(connection_id, |map| {
+            map.roots = roots;
+            map.resolved_roots = resolved_roots;
+        });
+        Ok(())
+    }
+
+    /// Forgets the mappings and roots of the connection
+    pub fn remove(&self, connection_id: ConnectionId) {
+        self.0.lock().unwrap().remove(&connection_id);
+    }
+
+    /// Returns the path map of the connection, if it has one
+    pub fn get(&self, connection_id: ConnectionId) -> Option<Arc<PathMap>> {
+        self.0.lock().unwrap().get(&connection_id).cloned()
+    }
+
+    fn update(&self, connection_id: ConnectionId, f: impl FnOnce(&mut PathMap)) {
+        let mut maps = self.0.lock().unwrap();
+        let mut map = maps
+            .get(&connection_id)
+            .map(|map| PathMap::clone(map))
+            .unwrap_or_default();
+        f(&mut map);
+
+        if map.mappings.is_empty() && map.roots.is_empty() {
+            maps.remove(&connection_id);
+        } else {
+            maps.insert(connection_id, Arc::new(map));
+        }
+    }
+}
+
+/// Translates the paths within requests into those known by the server, and the paths within
+/// responses back into those known by the client
+///
+/// Only absolute paths beneath a mapped directory are translated, leaving relative paths (such as
+/// those of directory entries that are not absolute) untouched.
+///
+/// When roots are declared, paths of requests can also refer to a root by name and must stay
+/// within the roots, which is checked against the paths as written here and once more by the
+/// server against the paths with symlinks resolved (see [`PathMap::paths`]). Requests that could
+/// reach outside of the roots without naming a path (such as spawning a process, which can then
+/// read or write anywhere, or restoring a snapshot to wherever it was taken) cannot be used at all.
+#[derive(Clone, Default)]
+pub struct PathMap {
+    mappings: Vec<PathMapping>,
+    roots: Vec<WorkspaceRoot>,
+
+    /// Canonical paths on the server of the declared roots
+    resolved_roots: Vec<PathBuf>,
+}
+
+impl PathMap {
+    /// Returns true if roots have been declared
+    pub fn has_roots(&self) -> bool {
+        !self.roots.is_empty()
+    }
+
+    /// Returns true if the canonical path `path` of the server is within the declared roots
+    pub fn is_within_roots(&self, path: &Path) -> bool {
+        self.resolved_roots
+            .iter()
+            .any(|root| path.starts_with(root))
+    }
+
+    /// Translates `path` of the client into that of the server
+    pub fn to_server_path(&self, mut path: PathBuf) -> PathBuf {
+        self.translate(&mut path, |m| (&m.client, &m.server));
+        path
+    }
+
+    /// Returns the paths of `request` (translated by [`PathMap::to_server`]) that must resolve to
+    /// somewhere within the roots, where the target of a symlink is joined with the link
+    pub fn paths(request: &mut DistantRequestData) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        let _ = visit_request(request, &mut |path, link| {
+            paths.push(match link {
+                Some(link) if path.is_relative() => {
+                    normalize(&link.parent().unwrap_or(link).join(&*path))
+                }
+                _ => path.clone(),
+            });
+            Ok::<_, ()>(())
+        });
+        paths
+    }
+
+    /// Translates the paths of `request` from those of the client into those of the server,
+    /// failing if any of them are outside of the declared roots
+    pub fn to_server(&self, request: &mut DistantRequestData) -> Result<(), Error> {
+        if self.has_roots() {
+            let denied = match request {
+                DistantRequestData::ProcSpawn { .. } => {
+                    Some("Processes cannot be spawned once roots are declared")
+                }
+                DistantRequestData::SystemKill { .. } => {
+                    Some("Processes of the system cannot be killed once roots are declared")
+                }
+                DistantRequestData::SnapshotCreate { .. }
+                | DistantRequestData::SnapshotList {}
+                | DistantRequestData::SnapshotRestore { .. }
+                | DistantRequestData::SnapshotRemove { .. } => {
+                    Some("Snapshots cannot be used once roots are declared")
+                }
+                DistantRequestData::TunnelListenUnix {} => {
+                    Some("Unix sockets cannot be listened on once roots are declared")
+                }
+                DistantRequestData::Extension { .. } => {
+                    Some("Extensions cannot be used once roots are declared")
+                }
+                _ => None,
+            };
+            if let Some(description) = denied {
+                return Err(Error {
+                    kind: ErrorKind::PermissionDenied,
+                    description: description.to_string(),
+                });
+            }
+        }
+
+        visit_request(request, &mut |path, link| {
+            self.confine(path, link)?;
+            self.translate(path, |m| (&m.client, &m.server));
+            Ok(())
+        })
+    }
+
+    /// Translates the paths of `response` from those of the server into those of the client
+    pub fn to_client(&self, response: &mut DistantResponseData) {
+        let map = |path: &mut PathBuf| {
+            self.translate(path, |m| (&m.server, &m.client));
+        };
+
+        match response {
+            DistantResponseData::DirEntries { entries, .. }
+            | DistantResponseData::DirEntriesChunk { entries, .. } => entries
+                .iter_mut()
+                .for_each(|entry: &mut DirEntry| map(&mut entry.path)),
+            DistantResponseData::TempCreated { path } => map(path),
+            DistantResponseData::Snapshots { entries } => {
+                entries.iter_mut().for_each(|entry| map(&mut entry.path))
+            }
+            DistantResponseData::Plan { paths } => paths.iter_mut().for_each(map),
+            DistantResponseData::Changed(change) => change.paths.iter_mut().for_each(map),
+            DistantResponseData::JournalChanges { entries, .. } => entries
+                .iter_mut()
+                .for_each(|entry| entry.paths.iter_mut().for_each(&map)),
+            DistantResponseData::Metadata(metadata) => {
+                metadata.canonicalized_path.iter_mut().for_each(map)
+            }
+            DistantResponseData::SearchResults { matches, .. } => {
+                for m in matches {
+                    match m {
+                        SearchQueryMatch::Path(m) => {
+                            // Submatches are offsets into the path, so those after the replaced
+                            // directory move along with the rest of the path
+                            let Some((old, new)) =
+                                self.translate(&mut m.path, |m| (&m.server, &m.client))
+                            else {
+                                continue;
+                            };
+                            for submatch in m.submatches.iter_mut() {
+                                if submatch.start >= old as u64 {
+                                    submatch.start = submatch.start - old as u64 + new as u64;
+                                    submatch.end = submatch.end - old as u64 + new as u64;
+                                }
+                            }
+                        }
+                        SearchQueryMatch::Contents(m) => map(&mut m.path),
+                    }
+                }
+            }
+            DistantResponseData::SearchDone { errors, .. } => {
+                errors.iter_mut().for_each(|error| map(&mut error.path))
+            }
+            DistantResponseData::SystemInfo(info) => map(&mut info.current_dir),
+            DistantResponseData::Ok
+            | DistantResponseData::Error(_)
+            | DistantResponseData::Blob { .. }
+            | DistantResponseData::Text { .. }
+            // Files of a manifest are relative to its directory
+            | DistantResponseData::DirManifestChunk { .. }
+            | DistantResponseData::ContentMissing
+            | DistantResponseData::SnapshotCreated { .. }
+            | DistantResponseData::BatchFailed { .. }
+            | DistantResponseData::Diff { .. }
+            | DistantResponseData::Exists { .. }
+            | DistantResponseData::SearchStarted { .. }
+            | DistantResponseData::ProcSpawned { .. }
+            | DistantResponseData::ProcDetached { .. }
+            | DistantResponseData::ProcStdout { .. }
+            | DistantResponseData::ProcStderr { .. }
+            | DistantResponseData::ProcPrompt { .. }
+            | DistantResponseData::ProcEntries { .. }
+            | DistantResponseData::ProcDone { .. }
+            | DistantResponseData::TunnelOpened { .. }
+            | DistantResponseData::TunnelData { .. }
+            | DistantResponseData::TunnelClosed { .. }
+            | DistantResponseData::TunnelListening { .. }
+            // Processes run by the server are given the socket as is
+            | DistantResponseData::TunnelListeningUnix { .. }
+            | DistantResponseData::TunnelAccepted { .. }
+            | DistantResponseData::Pong { .. }
+            | DistantResponseData::SystemEnv { .. }
+            | DistantResponseData::Metrics(_)
+            | DistantResponseData::ClientList { .. }
+            | DistantResponseData::SystemStats { .. }
+            | DistantResponseData::ProcStats { .. }
+            | DistantResponseData::Extension { .. }
+            | DistantResponseData::Capabilities { .. } => (),
+        }
+    }
+
+    /// Replaces a reference to a root by name in `path` with the path of the root, and then makes
+    /// sure that the path is within one of the roots (if any were declared)
+    ///
+    /// A `link` is given when `path` is the target of a symlink, where a relative target is
+    /// checked relative to the link and otherwise left as is.
+    fn confine(&self, path: &mut PathBuf, link: Option<&Path>) -> Result<(), Error> {
+        if self.roots.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(expanded) = self.expand(path) {
+            *path = expanded;
+        }
+
+        let full = match link {
+            Some(link) if path.is_relative() => {
+                let link = self.expand(link).unwrap_or_else(|| link.to_path_buf());
+                normalize(&link.parent().unwrap_or(&link).join(&*path))
+            }
+            _ => normalize(path),
+        };
+
+        let within = full.has_root()
+            && self
+                .roots
+                .iter()
+                .any(|root| full.starts_with(normalize(&root.path)));
+        if !within {
+            return Err(Error {
+                kind: ErrorKind::PermissionDenied,
+                description: format!("{} is outside of the declared roots", path.display()),
+            });
+        }
+
+        // Paths are sent on as they were checked, so `..` cannot take them anywhere else
+        if link.is_none() {
+            *path = full;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the path of the root named at the start of `path` (as `NAME:relative/path`) joined
+    /// with the rest of `path`, or none if it does not name a declared root
+    fn expand(&self, path: &Path) -> Option<PathBuf> {
+        let (name, rest) = path.to_str()?.split_once(':')?;
+        let root = self.roots.iter().find(|root| root.name == name)?;
+        Some(if rest.is_empty() {
+            root.path.clone()
+        } else {
+            root.path.join(rest)
+        })
+    }
+
+    /// Replaces the directory at the start of `path` using the mapping with the longest directory
+    /// that `f` returns along with its replacement, returning the lengths (in bytes) of the
+    /// directory before and after it was replaced
+    fn translate<'a>(
+        &'a self,
+        path: &mut PathBuf,
+        f: impl Fn(&'a PathMapping) -> (&'a PathBuf, &'a PathBuf),
+    ) -> Option<(usize, usize)> {
+        let (rest, to) = self
+            .mappings
+            .iter()
+            .map(f)
+            .filter_map(|(from, to)| Some((from, path.strip_prefix(from).ok()?, to)))
+            .max_by_key(|(from, _, _)| from.components().count())
+            .map(|(_, rest, to)| (rest.to_path_buf(), to))?;
+
+        let translated = if rest.as_os_str().is_empty() {
+            to.clone()
+        } else {
+            to.join(&rest)
+        };
+
+        let lens = (prefix_len(path, &rest), prefix_len(&translated, &rest));
+        *path = translated;
+        Some(lens)
+    }
+}
+
+/// Visits each path of `request`, along with the path of the symlink when visiting its target
+fn visit_request<E>(
+    request: &mut DistantRequestData,
+    f: &mut impl FnMut(&mut PathBuf, Option<&Path>) -> Result<(), E>,
+) -> Result<(), E> {
+    match request {
+        DistantRequestData::FileRead { path, .. }
+        | DistantRequestData::FileReadText { path }
+        | DistantRequestData::FileWrite { path, .. }
+        | DistantRequestData::FileWriteText { path, .. }
+        | DistantRequestData::FileWriteDedup { path, .. }
+        | DistantRequestData::FileAppend { path, .. }
+        | DistantRequestData::FileAppendText { path, .. }
+        | DistantRequestData::DirRead { path, .. }
+        | DistantRequestData::DirManifest { path, .. }
+        | DistantRequestData::DirCreate { path, .. }
+        | DistantRequestData::FifoCreate { path, .. }
+        | DistantRequestData::SnapshotCreate { path }
+        | DistantRequestData::Remove { path, .. }
+        | DistantRequestData::Watch { path, .. }
+        | DistantRequestData::Unwatch { path, .. }
+        | DistantRequestData::JournalStart { path, .. }
+        | DistantRequestData::JournalChanges { path, .. }
+        | DistantRequestData::JournalStop { path }
+        | DistantRequestData::Exists { path }
+        | DistantRequestData::Metadata { path, .. } => f(path, None),
+        DistantRequestData::MakeTempFile { parent }
+        | DistantRequestData::MakeTempDir { parent } => match parent {
+            Some(parent) => f(parent, None),
+            None => Ok(()),
+        },
+        DistantRequestData::Batch { ops, .. } | DistantRequestData::Transaction { ops } => {
+            ops.iter_mut().try_for_each(|op| visit_batch_op(op, f))
+        }
+        DistantRequestData::Plan { op } => visit_batch_op(op, f),
+        DistantRequestData::Diff { src, dst, .. } => {
+            f(src, None)?;
+            match dst {
+                DiffTarget::Path(dst) => f(dst, None),
+                _ => Ok(()),
+            }
+        }
+        DistantRequestData::Copy { src, dst } | DistantRequestData::Rename { src, dst } => {
+            f(src, None)?;
+            f(dst, None)
+        }
+        DistantRequestData::Search { query } => {
+            query.paths.iter_mut().try_for_each(|path| f(path, None))
+        }
+        DistantRequestData::ProcSpawn { current_dir, .. } => match current_dir {
+            Some(current_dir) => f(current_dir, None),
+            None => Ok(()),
+        },
+        DistantRequestData::Capabilities {}
+        | DistantRequestData::SnapshotList {}
+        | DistantRequestData::SnapshotRestore { .. }
+        | DistantRequestData::SnapshotRemove { .. }
+        | DistantRequestData::CancelSearch { .. }
+        | DistantRequestData::SearchNext { .. }
+        | DistantRequestData::ProcAttach { .. }
+        | DistantRequestData::ProcKill { .. }
+        | DistantRequestData::ProcList { .. }
+        | DistantRequestData::ProcSignal { .. }
+        | DistantRequestData::ProcStdin { .. }
+        | DistantRequestData::ProcStdinClose { .. }
+        | DistantRequestData::ProcOutputAck { .. }
+        | DistantRequestData::ProcResizePty { .. }
+        | DistantRequestData::TunnelOpen { .. }
+        | DistantRequestData::TunnelWrite { .. }
+        | DistantRequestData::TunnelClose { .. }
+        | DistantRequestData::TunnelListen { .. }
+        | DistantRequestData::TunnelListenUnix {}
+        | DistantRequestData::TunnelUnlisten { .. }
+        | DistantRequestData::Ping { .. }
+        | DistantRequestData::SystemInfo {}
+        | DistantRequestData::SystemEnv {}
+        | DistantRequestData::Metrics {}
+        | DistantRequestData::ClientList {}
+        | DistantRequestData::ClientDisconnect { .. }
+        | DistantRequestData::SystemStats { .. }
+        | DistantRequestData::ProcStats { .. }
+        | DistantRequestData::CancelSystemStats { .. }
+        | DistantRequestData::SystemKill { .. }
+        | DistantRequestData::PathMap { .. }
+        | DistantRequestData::Roots { .. }
+        | DistantRequestData::Extension { .. } => Ok(()),
+    }
+}
+
+fn visit_batch_op<E>(
+    op: &mut BatchOp,
+    f: &mut impl FnMut(&mut PathBuf, Option<&Path>) -> Result<(), E>,
+) -> Result<(), E> {
+    match op {
+        BatchOp::DirCreate { path, .. }
+        | BatchOp::FileWrite { path, .. }
+        | BatchOp::FileWriteText { path, .. }
+        | BatchOp::FileAppend { path, .. }
+        | BatchOp::FileAppendText { path, .. }
+        | BatchOp::Remove { path, .. } => f(path, None),
+        BatchOp::Copy { src, dst } | BatchOp::Rename { src, dst } => {
+            f(src, None)?;
+            f(dst, None)
+        }
+        BatchOp::Symlink { path, target } => {
+            // The target goes first so that it is checked against the link as the client knows it
+            f(target, Some(path))?;
+            f(path, None)
+        }
+    }
+}
+
+/// Resolves `.` and `..` within `path` without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() && !normalized.has_root() {
+                    normalized.push(component);
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Returns the length (in bytes) of `path` before `rest`, which ends it
+fn prefix_len(path: &Path, rest: &Path) -> usize {
+    let rest = rest.to_string_lossy().len();
+    path.to_string_lossy().len() - rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{
+        Change, ChangeKind, SearchQueryMatchData, SearchQueryPathMatch, SearchQueryRootError,
+        SearchQuerySubmatch,
+    };
+
+    fn path_map() -> PathMap {
+        PathMap {
+            mappings: vec![
+                PathMapping {
+                    client: PathBuf::from("/workspace"),
+                    server: PathBuf::from("/home/me/project"),
+                },
+                PathMapping {
+                    client: PathBuf::from("/workspace/data"),
+                    server: PathBuf::from("/srv/data"),
+                },
+            ],
+            roots: Vec::new(),
+            resolved_roots: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn to_server_should_use_most_specific_mapping() {
+        let mut request = DistantRequestData::Copy {
+            src: PathBuf::from("/workspace/src/main.rs"),
+            dst: PathBuf::from("/workspace/data/main.rs"),
+        };
+        path_map().to_server(&mut request).unwrap();
+        assert_eq!(
+            request,
+            DistantRequestData::Copy {
+                src: PathBuf::from("/home/me/project/src/main.rs"),
+                dst: PathBuf::from("/srv/data/main.rs"),
+            }
+        );
+
+        // Paths outside of every mapping, or only sharing part of a name, are left alone
+        let mut request = DistantRequestData::Rename {
+            src: PathBuf::from("/workspaces/file"),
+            dst: PathBuf::from("relative/file"),
+        };
+        path_map().to_server(&mut request).unwrap();
+        assert_eq!(
+            request,
+            DistantRequestData::Rename {
+                src: PathBuf::from("/workspaces/file"),
+                dst: PathBuf::from("relative/file"),
+            }
+        );
+    }
+
+    #[test]
+    fn to_server_should_resolve_roots_and_reject_paths_outside_of_them() {
+        let path_map = PathMap {
+            mappings: Vec::new(),
+            roots: vec![
+                WorkspaceRoot {
+                    name: String::from("src"),
+                    path: PathBuf::from("/srv/app"),
+                },
+                WorkspaceRoot {
+                    name: String::from("logs"),
+                    path: PathBuf::from("/var/log/app"),
+                },
+            ],
+            resolved_roots: vec![PathBuf::from("/srv/app"), PathBuf::from("/var/log/app")],
+        };
+
+        let mut request = DistantRequestData::Copy {
+            src: PathBuf::from("logs:today.log"),
+            dst: PathBuf::from("/srv/app/./logs/../today.log"),
+        };
+        path_map.to_server(&mut request).unwrap();
+        assert_eq!(
+            request,
+            DistantRequestData::Copy {
+                src: PathBuf::from("/var/log/app/today.log"),
+                dst: PathBuf::from("/srv/app/today.log"),
+            }
+        );
+
+        for path in [
+            "src:../../etc/passwd",
+            "/etc/passwd",
+            "relative",
+            "other:file",
+        ] {
+            let mut request = DistantRequestData::FileRead {
+                path: PathBuf::from(path),
+                offset: None,
+                len: None,
+            };
+            let err = path_map.to_server(&mut request).unwrap_err();
+            assert_eq!(err.kind, ErrorKind::PermissionDenied, "{path}");
+        }
+
+        // Relative targets of symlinks stay relative, but cannot point outside of the roots
+        let mut request = DistantRequestData::Plan {
+            op: BatchOp::Symlink {
+                path: PathBuf::from("src:bin/link"),
+                target: PathBuf::from("../lib"),
+            },
+        };
+        path_map.to_server(&mut request).unwrap();
+        assert_eq!(
+            request,
+            DistantRequestData::Plan {
+                op: BatchOp::Symlink {
+                    path: PathBuf::from("/srv/app/bin/link"),
+                    target: PathBuf::from("../lib"),
+                },
+            }
+        );
+
+        let mut request = DistantRequestData::Plan {
+            op: BatchOp::Symlink {
+                path: PathBuf::from("src:link"),
+                target: PathBuf::from("../../etc"),
+            },
+        };
+        assert!(path_map.to_server(&mut request).is_err());
+    }
+
+    #[test]
+    fn to_server_should_refuse_requests_that_could_reach_outside_of_roots() {
+        let path_map = PathMap {
+            mappings: Vec::new(),
+            roots: vec![WorkspaceRoot {
+                name: String::from("src"),
+                path: PathBuf::from("/srv/app"),
+            }],
+            resolved_roots: vec![PathBuf::from("/srv/app")],
+        };
+
+        let requests = [
+            serde_json::json!({ "type": "proc_spawn", "cmd": "cat /etc/shadow" }),
+            serde_json::json!({
+                "type": "proc_spawn",
+                "cmd": "cat /etc/shadow",
+                "current_dir": "/srv/app",
+            }),
+            serde_json::json!({ "type": "system_kill", "pid": 1, "signal": "kill" }),
+            serde_json::json!({ "type": "snapshot_create", "path": "/srv/app" }),
+            serde_json::json!({ "type": "snapshot_list" }),
+            serde_json::json!({ "type": "snapshot_restore", "id": 1 }),
+            serde_json::json!({ "type": "snapshot_remove", "id": 1 }),
+            serde_json::json!({ "type": "tunnel_listen_unix" }),
+            serde_json::json!({ "type": "extension", "name": "x-deploy" }),
+        ];
+        for request in requests {
+            let mut request: DistantRequestData = serde_json::from_value(request).unwrap();
+            let err = path_map.to_server(&mut request).unwrap_err();
+            assert_eq!(err.kind, ErrorKind::PermissionDenied, "{request:?}");
+        }
+
+        // Without roots, the same requests are passed along
+        let mut request = DistantRequestData::SnapshotRestore { id: 1 };
+        PathMap::default().to_server(&mut request).unwrap();
+    }
+
+    #[test]
+    fn to_client_should_translate_changes_and_search_submatches() {
+        let mut response = DistantResponseData::Changed(Change {
+            kind: ChangeKind::Modify,
+            paths: vec![PathBuf::from("/home/me/project")],
+        });
+        path_map().to_client(&mut response);
+        assert_eq!(
+            response,
+            DistantResponseData::Changed(Change {
+                kind: ChangeKind::Modify,
+                paths: vec![PathBuf::from("/workspace")],
+            })
+        );
+
+        let mut response = DistantResponseData::SearchResults {
+            id: 1,
+            matches: vec![SearchQueryMatch::Path(SearchQueryPathMatch {
+                path: PathBuf::from("/home/me/project/file"),
+                submatches: vec![SearchQuerySubmatch {
+                    r#match: SearchQueryMatchData::Text(String::from("file")),
+                    start: 17,
+                    end: 21,
+                }],
+            })],
+        };
+        path_map().to_client(&mut response);
+        assert_eq!(
+            response,
+            DistantResponseData::SearchResults {
+                id: 1,
+                matches: vec![SearchQueryMatch::Path(SearchQueryPathMatch {
+                    path: PathBuf::from("/workspace/file"),
+                    submatches: vec![SearchQuerySubmatch {
+                        r#match: SearchQueryMatchData::Text(String::from("file")),
+                        start: 11,
+                        end: 15,
+                    }],
+                })],
+            }
+        );
+    }
+
+    #[test]
+    fn to_client_should_translate_paths_of_search_errors() {
+        let error = |path: &str| SearchQueryRootError {
+            path: PathBuf::from(path),
+            error: Error::from("missing"),
+        };
+
+        let mut response = DistantResponseData::SearchDone {
+            id: 1,
+            skipped: Default::default(),
+            errors: vec![error("/home/me/project/missing")],
+        };
+        path_map().to_client(&mut response);
+        assert_eq!(
+            response,
+            DistantResponseData::SearchDone {
+                id: 1,
+                skipped: Default::default(),
+                errors: vec![error("/workspace/missing")],
+            }
+        );
+    }
+}
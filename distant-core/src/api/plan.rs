@@ -0,0 +1,111 @@
+use super::batch::{fork, topmost_missing};
+use super::transaction::file_type;
+use super::{DistantApi, DistantCtx};
+use crate::data::{BatchOp, DirReadOptions, FileType};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Resolves the paths that `op` would create, change, or remove using `api` without performing
+/// it, failing if what the operation acts upon is missing or (when removing a non-empty
+/// directory without force) would be refused
+pub async fn run<T, D>(api: &T, ctx: DistantCtx<D>, op: BatchOp) -> io::Result<Vec<PathBuf>>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    match op {
+        BatchOp::DirCreate { path, all } => {
+            if !all {
+                return Ok(vec![path]);
+            }
+
+            // Every missing directory from the topmost one down to the path gets created
+            Ok(match topmost_missing(api, &ctx, &path).await? {
+                Some(topmost) => {
+                    let mut paths: Vec<PathBuf> = path
+                        .ancestors()
+                        .take_while(|ancestor| ancestor.starts_with(&topmost))
+                        .map(Path::to_path_buf)
+                        .collect();
+                    paths.reverse();
+                    paths
+                }
+                None => Vec::new(),
+            })
+        }
+        BatchOp::FileWrite { path, .. }
+        | BatchOp::FileWriteText { path, .. }
+        | BatchOp::FileAppend { path, .. }
+        | BatchOp::FileAppendText { path, .. }
+        | BatchOp::Symlink { path, .. } => Ok(vec![path]),
+        BatchOp::Copy { src, dst } => {
+            // Copying follows symlinks, so a symlink to a directory copies the directory
+            let metadata = api.metadata(fork(&ctx), src.clone(), false, true).await?;
+            let mut paths = vec![dst.clone()];
+            if metadata.file_type == FileType::Dir {
+                paths.extend(
+                    contents(api, &ctx, &src)
+                        .await?
+                        .into_iter()
+                        .map(|relative| dst.join(relative)),
+                );
+            }
+            Ok(paths)
+        }
+        BatchOp::Rename { src, dst } => {
+            if file_type(api, &ctx, &src).await?.is_none() {
+                return Err(not_found(&src));
+            }
+            Ok(vec![src, dst])
+        }
+        BatchOp::Remove { path, force } => match file_type(api, &ctx, &path).await? {
+            None => Err(not_found(&path)),
+            Some(FileType::Dir) => {
+                let contents = contents(api, &ctx, &path).await?;
+                if !force && !contents.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("{path:?} is a non-empty directory"),
+                    ));
+                }
+
+                let mut paths = vec![path.clone()];
+                paths.extend(contents.into_iter().map(|relative| path.join(relative)));
+                Ok(paths)
+            }
+            Some(_) => Ok(vec![path]),
+        },
+    }
+}
+
+/// Returns the paths (relative to `path`) of everything within the directory at `path`, failing
+/// if any of it cannot be read
+async fn contents<T, D>(api: &T, ctx: &DistantCtx<D>, path: &Path) -> io::Result<Vec<PathBuf>>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let (entries, mut failures) = api
+        .read_dir(
+            fork(ctx),
+            path.to_path_buf(),
+            0,
+            false,
+            false,
+            false,
+            DirReadOptions::default(),
+        )
+        .await?;
+
+    if !failures.is_empty() {
+        return Err(failures.remove(0));
+    }
+
+    Ok(entries.into_iter().map(|entry| entry.path).collect())
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{path:?} does not exist"))
+}
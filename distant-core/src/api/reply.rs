@@ -1,6 +1,9 @@
-use crate::{api::DistantMsg, data::DistantResponseData};
+use crate::{
+    api::{path_map::PathMap, DistantMsg},
+    data::DistantResponseData,
+};
 use distant_net::server::Reply;
-use std::{future::Future, io, pin::Pin};
+use std::{future::Future, io, pin::Pin, sync::Arc};
 
 /// Wrapper around a reply that can be batch or single, converting
 /// a single data into the wrapped type
@@ -26,4 +29,49 @@ impl Reply for DistantSingleReply {
     fn clone_reply(&self) -> Box<dyn Reply<Data = Self::Data>> {
         Box::new(Self(self.0.clone_reply()))
     }
+
+    fn closed(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        self.0.closed()
+    }
+}
+
+/// Wrapper around a reply that translates the paths of data into those known by the client
+/// before it is sent
+pub struct DistantPathMapReply {
+    reply: Box<dyn Reply<Data = DistantResponseData>>,
+    path_map: Arc<PathMap>,
+}
+
+impl DistantPathMapReply {
+    pub fn new(reply: Box<dyn Reply<Data = DistantResponseData>>, path_map: Arc<PathMap>) -> Self {
+        Self { reply, path_map }
+    }
+}
+
+impl Reply for DistantPathMapReply {
+    type Data = DistantResponseData;
+
+    fn send(
+        &self,
+        mut data: Self::Data,
+    ) -> Pin<Box<dyn Future<Output = io::Result<()>> + Send + '_>> {
+        self.path_map.to_client(&mut data);
+        self.reply.send(data)
+    }
+
+    fn blocking_send(&self, mut data: Self::Data) -> io::Result<()> {
+        self.path_map.to_client(&mut data);
+        self.reply.blocking_send(data)
+    }
+
+    fn clone_reply(&self) -> Box<dyn Reply<Data = Self::Data>> {
+        Box::new(Self {
+            reply: self.reply.clone_reply(),
+            path_map: Arc::clone(&self.path_map),
+        })
+    }
+
+    fn closed(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        self.reply.closed()
+    }
 }
@@ -0,0 +1,14 @@
+use rand::{distributions::Alphanumeric, Rng};
+
+/// Maximum attempts at picking an unused name when making a temporary file or directory
+pub const MAX_TEMP_ATTEMPTS: usize = 32;
+
+/// Generates a random name for a temporary file or directory, hidden like those of `mktemp`
+pub fn make_temp_name() -> String {
+    let name: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(10)
+        .map(char::from)
+        .collect();
+    format!(".tmp{name}")
+}
@@ -0,0 +1,382 @@
+use super::batch::{fork, topmost_missing};
+use super::{DistantApi, DistantCtx};
+use crate::data::{BatchOp, DirReadOptions, Error, FileType};
+use crate::DistantResponseData;
+use log::*;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Represents an action that reverses a change made while applying a transaction
+#[derive(Debug)]
+enum Undo {
+    /// Removes a file or directory that the transaction created
+    Remove(PathBuf),
+
+    /// Moves a file or directory back to where it was before the transaction
+    Rename { src: PathBuf, dst: PathBuf },
+
+    /// Puts the backup of what was at `path` before the transaction back in its place
+    Restore { backup: PathBuf, path: PathBuf },
+}
+
+/// Represents a change to the contents of a file
+enum Edit {
+    Write(Vec<u8>),
+    WriteText(String),
+    Append(Vec<u8>),
+    AppendText(String),
+}
+
+/// Applies each of `ops` in order using `api`, restoring everything they changed if any of them
+/// fails. Files are written to a staging file next to their destination and renamed into place,
+/// while anything replaced or removed is kept as a backup until all operations succeed.
+pub async fn run<T, D>(api: &T, ctx: DistantCtx<D>, ops: Vec<BatchOp>) -> DistantResponseData
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let mut undos = Vec::new();
+
+    for (step, op) in ops.into_iter().enumerate() {
+        if let Err(x) = apply(api, &ctx, op, &mut undos).await {
+            debug!(
+                "[Conn {}] Transaction failed at step {}: {}",
+                ctx.connection_id, step, x
+            );
+            let rolled_back = rollback(api, &ctx, undos).await;
+            return DistantResponseData::BatchFailed {
+                step,
+                error: Error::from(x),
+                rolled_back,
+            };
+        }
+    }
+
+    commit(api, &ctx, undos).await;
+    DistantResponseData::Ok
+}
+
+/// Applies `op`, recording how to undo each change as soon as it is made so that a partially
+/// applied operation is also undone
+async fn apply<T, D>(
+    api: &T,
+    ctx: &DistantCtx<D>,
+    op: BatchOp,
+    undos: &mut Vec<Undo>,
+) -> io::Result<()>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    match op {
+        BatchOp::DirCreate { path, all } => {
+            let topmost = topmost_missing(api, ctx, &path).await?;
+            api.create_dir(fork(ctx), path, all, None).await?;
+            undos.extend(topmost.map(Undo::Remove));
+            Ok(())
+        }
+        BatchOp::FileWrite { path, data } => edit(api, ctx, path, Edit::Write(data), undos).await,
+        BatchOp::FileWriteText { path, text } => {
+            edit(api, ctx, path, Edit::WriteText(text), undos).await
+        }
+        BatchOp::FileAppend { path, data } => edit(api, ctx, path, Edit::Append(data), undos).await,
+        BatchOp::FileAppendText { path, text } => {
+            edit(api, ctx, path, Edit::AppendText(text), undos).await
+        }
+        BatchOp::Copy { src, dst } => match file_type(api, ctx, &dst).await? {
+            None => {
+                api.copy(fork(ctx), src, dst.clone()).await?;
+                undos.push(Undo::Remove(dst));
+                Ok(())
+            }
+
+            // Copying into an existing directory merges into it, so it is copied in place
+            Some(FileType::Dir) => {
+                back_up(api, ctx, &dst, undos).await?;
+                api.copy(fork(ctx), src, dst).await
+            }
+            Some(_) => {
+                let staged = sibling(&dst);
+                stage(api, ctx, &staged, async {
+                    api.copy(fork(ctx), src, staged.clone()).await?;
+                    back_up(api, ctx, &dst, undos).await?;
+                    api.rename(fork(ctx), staged.clone(), dst).await
+                })
+                .await
+            }
+        },
+        BatchOp::Rename { src, dst } => {
+            if file_type(api, ctx, &dst).await?.is_some() {
+                back_up(api, ctx, &dst, undos).await?;
+            }
+            api.rename(fork(ctx), src.clone(), dst.clone()).await?;
+            undos.push(Undo::Rename { src: dst, dst: src });
+            Ok(())
+        }
+        BatchOp::Remove { path, force } => {
+            if !force
+                && file_type(api, ctx, &path).await? == Some(FileType::Dir)
+                && !is_empty_dir(api, ctx, &path).await?
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("{path:?} is a non-empty directory"),
+                ));
+            }
+
+            move_aside(api, ctx, path, undos).await
+        }
+        BatchOp::Symlink { path, target } => {
+            // Without a way to read the existing symlink, it is moved aside rather than copied,
+            // so the path is briefly missing before the new symlink takes its place
+            if file_type(api, ctx, &path).await?.is_some() {
+                move_aside(api, ctx, path.clone(), undos).await?;
+            }
+            api.create_symlink(fork(ctx), path.clone(), target).await?;
+            undos.push(Undo::Remove(path));
+            Ok(())
+        }
+    }
+}
+
+/// Applies `edit` to a staged copy of the file at `path` and then renames it into place, which
+/// keeps the permissions of an existing file
+async fn edit<T, D>(
+    api: &T,
+    ctx: &DistantCtx<D>,
+    path: PathBuf,
+    edit: Edit,
+    undos: &mut Vec<Undo>,
+) -> io::Result<()>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    // Edit the file that a symlink points to rather than replacing the symlink
+    let (path, exists) = match api.metadata(fork(ctx), path.clone(), true, true).await {
+        Ok(metadata) => (metadata.canonicalized_path.unwrap_or(path), true),
+        Err(x) if x.kind() == io::ErrorKind::NotFound => (path, false),
+        Err(x) => return Err(x),
+    };
+
+    // The policy of the server sees the file being edited rather than only its staged copy
+    let data = match &edit {
+        Edit::Write(data) | Edit::Append(data) => data.as_slice(),
+        Edit::WriteText(text) | Edit::AppendText(text) => text.as_bytes(),
+    };
+    api.check_write(&path, Some(data)).await?;
+
+    let staged = sibling(&path);
+    stage(api, ctx, &staged, async {
+        if exists {
+            api.copy(fork(ctx), path.clone(), staged.clone()).await?;
+        }
+
+        match edit {
+            Edit::Write(data) => {
+                api.write_file(fork(ctx), staged.clone(), data, None, None)
+                    .await?
+            }
+            Edit::WriteText(text) => api.write_file_text(fork(ctx), staged.clone(), text).await?,
+            Edit::Append(data) => api.append_file(fork(ctx), staged.clone(), data).await?,
+            Edit::AppendText(text) => {
+                api.append_file_text(fork(ctx), staged.clone(), text)
+                    .await?
+            }
+        }
+
+        if exists {
+            back_up(api, ctx, &path, undos).await?;
+            api.rename(fork(ctx), staged.clone(), path).await
+        } else {
+            api.rename(fork(ctx), staged.clone(), path.clone()).await?;
+            undos.push(Undo::Remove(path));
+            Ok(())
+        }
+    })
+    .await
+}
+
+/// Waits on `f`, removing anything it left at `staged` if it fails
+async fn stage<T, D>(
+    api: &T,
+    ctx: &DistantCtx<D>,
+    staged: &Path,
+    f: impl std::future::Future<Output = io::Result<()>>,
+) -> io::Result<()>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let result = f.await;
+    if result.is_err()
+        && api
+            .exists(fork(ctx), staged.to_path_buf())
+            .await
+            .unwrap_or(false)
+    {
+        if let Err(x) = api.remove(fork(ctx), staged.to_path_buf(), true).await {
+            warn!(
+                "[Conn {}] Failed to remove staged {:?}: {}",
+                ctx.connection_id, staged, x
+            );
+        }
+    }
+    result
+}
+
+/// Copies what is at `path` to a backup, leaving `path` in place
+async fn back_up<T, D>(
+    api: &T,
+    ctx: &DistantCtx<D>,
+    path: &Path,
+    undos: &mut Vec<Undo>,
+) -> io::Result<()>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let backup = sibling(path);
+    stage(
+        api,
+        ctx,
+        &backup,
+        api.copy(fork(ctx), path.to_path_buf(), backup.clone()),
+    )
+    .await?;
+    undos.push(Undo::Restore {
+        backup,
+        path: path.to_path_buf(),
+    });
+    Ok(())
+}
+
+/// Moves what is at `path` to a backup, leaving `path` missing
+async fn move_aside<T, D>(
+    api: &T,
+    ctx: &DistantCtx<D>,
+    path: PathBuf,
+    undos: &mut Vec<Undo>,
+) -> io::Result<()>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let backup = sibling(&path);
+    api.rename(fork(ctx), path.clone(), backup.clone()).await?;
+    undos.push(Undo::Restore { backup, path });
+    Ok(())
+}
+
+/// Returns an unused path in the same directory as `path`, which keeps staged files and backups
+/// on the same filesystem so that renaming them into place happens in a single step
+fn sibling(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.distant-txn-{:08x}", rand::random::<u32>()))
+}
+
+/// Returns the type of what is at `path` without following symlinks, or none if it is missing
+pub(super) async fn file_type<T, D>(
+    api: &T,
+    ctx: &DistantCtx<D>,
+    path: &Path,
+) -> io::Result<Option<FileType>>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    match api
+        .metadata(fork(ctx), path.to_path_buf(), false, false)
+        .await
+    {
+        Ok(metadata) => Ok(Some(metadata.file_type)),
+        Err(x) if x.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(x) => Err(x),
+    }
+}
+
+async fn is_empty_dir<T, D>(api: &T, ctx: &DistantCtx<D>, path: &Path) -> io::Result<bool>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let (entries, _) = api
+        .read_dir(
+            fork(ctx),
+            path.to_path_buf(),
+            1,
+            false,
+            false,
+            false,
+            DirReadOptions::default(),
+        )
+        .await?;
+    Ok(entries.is_empty())
+}
+
+/// Undoes changes in reverse order, returning true if all were undone
+async fn rollback<T, D>(api: &T, ctx: &DistantCtx<D>, undos: Vec<Undo>) -> bool
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    let mut success = true;
+    for undo in undos.into_iter().rev() {
+        trace!("[Conn {}] Rolling back: {:?}", ctx.connection_id, undo);
+        let result = match undo {
+            Undo::Remove(path) => api.remove(fork(ctx), path, true).await,
+            Undo::Rename { src, dst } => api.rename(fork(ctx), src, dst).await,
+            Undo::Restore { backup, path } => restore(api, ctx, backup, path).await,
+        };
+
+        if let Err(x) = result {
+            error!(
+                "[Conn {}] Failed to roll back transaction: {}",
+                ctx.connection_id, x
+            );
+            success = false;
+        }
+    }
+    success
+}
+
+async fn restore<T, D>(
+    api: &T,
+    ctx: &DistantCtx<D>,
+    backup: PathBuf,
+    path: PathBuf,
+) -> io::Result<()>
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    // Directories cannot be renamed over, so whatever replaced the backup is removed first
+    if file_type(api, ctx, &path).await? == Some(FileType::Dir) {
+        api.remove(fork(ctx), path.clone(), true).await?;
+    }
+    api.rename(fork(ctx), backup, path).await
+}
+
+/// Removes the backups kept in case of a rollback, which are no longer needed once every
+/// operation has succeeded
+async fn commit<T, D>(api: &T, ctx: &DistantCtx<D>, undos: Vec<Undo>)
+where
+    T: DistantApi<LocalData = D> + Send + Sync,
+    D: Send + Sync,
+{
+    for undo in undos {
+        if let Undo::Restore { backup, .. } = undo {
+            if let Err(x) = api.remove(fork(ctx), backup.clone(), true).await {
+                warn!(
+                    "[Conn {}] Failed to remove backup {:?}: {}",
+                    ctx.connection_id, backup, x
+                );
+            }
+        }
+    }
+}
@@ -3,8 +3,10 @@ use distant_net::{client::Channel, Client};
 
 mod ext;
 mod lsp;
+mod monitor;
 mod process;
 mod searcher;
+mod tunnel;
 mod watcher;
 
 /// Represents a [`Client`] that communicates using the distant protocol
@@ -15,6 +17,8 @@ pub type DistantChannel = Channel<DistantMsg<DistantRequestData>, DistantMsg<Dis
 
 pub use ext::*;
 pub use lsp::*;
+pub use monitor::*;
 pub use process::*;
 pub use searcher::*;
+pub use tunnel::*;
 pub use watcher::*;
@@ -1,16 +1,20 @@
 use crate::{
+    api::content_hash,
     client::{
-        RemoteCommand, RemoteLspCommand, RemoteLspProcess, RemoteOutput, RemoteProcess, Searcher,
-        Watcher,
+        RemoteCommand, RemoteLspCommand, RemoteLspProcess, RemoteOutput, RemoteProcess,
+        RemoteTunnel, RemoteTunnelListener, Searcher, SystemMonitor, Watcher,
     },
     data::{
-        Capabilities, ChangeKindSet, DirEntry, DistantRequestData, DistantResponseData,
-        Environment, Error as Failure, Metadata, PtySize, SearchId, SearchQuery, SystemInfo,
+        BatchOp, Capabilities, ChangeKindSet, ClientId, ClientInfo, DiffTarget, DirEntry,
+        DirReadOptions, DistantRequestData, DistantResponseData, Environment, Error as Failure,
+        HashAlgorithm, JournalEntry, JournalSeq, ManifestEntry, Metadata, Metrics, PathMapping,
+        ProcInfo, ProcStats, ProcessId, PtySize, SearchId, SearchQuery, Signal, SnapshotId,
+        SnapshotInfo, SystemInfo, SystemStats, SystemStatsId, TunnelId, WatchId, WorkspaceRoot,
     },
     DistantMsg,
 };
 use distant_net::{client::Channel, common::Request};
-use std::{future::Future, io, path::PathBuf, pin::Pin};
+use std::{future::Future, io, path::PathBuf, pin::Pin, time::Duration};
 
 pub type AsyncReturn<'a, T, E = io::Error> =
     Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'a>>;
@@ -19,6 +23,18 @@ fn mismatched_response() -> io::Error {
     io::Error::new(io::ErrorKind::Other, "Mismatched response")
 }
 
+/// Converts the failure of a batch or transaction into an error that names the failed step
+fn batch_failed(step: usize, error: Failure, rolled_back: bool) -> io::Error {
+    let x = io::Error::from(error);
+    io::Error::new(
+        x.kind(),
+        format!(
+            "Step {step} failed{}: {x}",
+            if rolled_back { " (rolled back)" } else { "" }
+        ),
+    )
+}
+
 /// Provides convenience functions on top of a [`Channel`]
 pub trait DistantChannelExt {
     /// Appends to a remote file using the data from a collection of bytes
@@ -35,6 +51,17 @@ pub trait DistantChannelExt {
         data: impl Into<String>,
     ) -> AsyncReturn<'_, ()>;
 
+    /// Performs a sequence of remote filesystem operations, failing with the first operation
+    /// that fails, and undoing earlier operations upon failure if `atomic` is true
+    fn batch(&mut self, ops: Vec<BatchOp>, atomic: bool) -> AsyncReturn<'_, ()>;
+
+    /// Applies a sequence of remote filesystem operations all at once or not at all, failing
+    /// with the first operation that fails after restoring what earlier operations changed
+    fn transaction(&mut self, ops: Vec<BatchOp>) -> AsyncReturn<'_, ()>;
+
+    /// Resolves the remote paths that a filesystem operation would affect without performing it
+    fn plan(&mut self, op: BatchOp) -> AsyncReturn<'_, Vec<PathBuf>>;
+
     /// Retrieves server capabilities
     fn capabilities(&mut self) -> AsyncReturn<'_, Capabilities>;
 
@@ -44,8 +71,53 @@ pub trait DistantChannelExt {
     /// Creates a remote directory, optionally creating all parent components if specified
     fn create_dir(&mut self, path: impl Into<PathBuf>, all: bool) -> AsyncReturn<'_, ()>;
 
+    /// Creates a remote directory like [`create_dir`](Self::create_dir), giving each directory
+    /// that is created the permissions of `mode`
+    fn create_dir_with_mode(
+        &mut self,
+        path: impl Into<PathBuf>,
+        all: bool,
+        mode: u32,
+    ) -> AsyncReturn<'_, ()>;
+
+    /// Creates a remote named pipe (FIFO), giving it the permissions of `mode` if provided
+    fn create_fifo(&mut self, path: impl Into<PathBuf>, mode: Option<u32>) -> AsyncReturn<'_, ()>;
+
+    /// Computes a unified diff between the remote file `src` and either another remote file or
+    /// supplied text, with `context` lines around each change (or the server's default)
+    fn diff(
+        &mut self,
+        src: impl Into<PathBuf>,
+        dst: impl Into<DiffTarget>,
+        context: Option<usize>,
+    ) -> AsyncReturn<'_, String>;
+
+    /// Lists every file under a remote directory along with its size, last modified time, and
+    /// hash of its content, returning a tuple of files (ordered by path) and failures
+    fn dir_manifest(
+        &mut self,
+        path: impl Into<PathBuf>,
+        algorithm: HashAlgorithm,
+    ) -> AsyncReturn<'_, (Vec<ManifestEntry>, Vec<Failure>)>;
+
     fn exists(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, bool>;
 
+    /// Sends a custom request called `name` (starting with `x-`) to be handled by an extension
+    /// registered with the remote server, returning the extension's response
+    fn extension(
+        &mut self,
+        name: impl Into<String>,
+        payload: serde_json::Value,
+    ) -> AsyncReturn<'_, serde_json::Value>;
+
+    /// Creates a uniquely-named, empty remote file within `parent` (or the remote temporary
+    /// directory), returning its path
+    fn make_temp_file(&mut self, parent: Option<PathBuf>) -> AsyncReturn<'_, PathBuf>;
+
+    /// Creates a uniquely-named, empty remote directory within `parent` (or the remote temporary
+    /// directory), returning its path
+    fn make_temp_dir(&mut self, parent: Option<PathBuf>) -> AsyncReturn<'_, PathBuf>;
+
     /// Retrieves metadata about a path on a remote machine
     fn metadata(
         &mut self,
@@ -60,6 +132,9 @@ pub trait DistantChannelExt {
     /// Cancel an active search query
     fn cancel_search(&mut self, id: SearchId) -> AsyncReturn<'_, ()>;
 
+    /// Requests the next page of results of an active search query performed on demand
+    fn search_next(&mut self, id: SearchId) -> AsyncReturn<'_, ()>;
+
     /// Reads entries from a directory, returning a tuple of directory entries and failures
     fn read_dir(
         &mut self,
@@ -68,11 +143,21 @@ pub trait DistantChannelExt {
         absolute: bool,
         canonicalize: bool,
         include_root: bool,
+        options: DirReadOptions,
     ) -> AsyncReturn<'_, (Vec<DirEntry>, Vec<Failure>)>;
 
     /// Reads a remote file as a collection of bytes
     fn read_file(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, Vec<u8>>;
 
+    /// Reads at most `len` bytes of a remote file starting at `offset`, returning fewer bytes
+    /// only once the end of the file is reached
+    fn read_file_at(
+        &mut self,
+        path: impl Into<PathBuf>,
+        offset: u64,
+        len: u64,
+    ) -> AsyncReturn<'_, Vec<u8>>;
+
     /// Returns a remote file as a string
     fn read_file_text(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, String>;
 
@@ -83,6 +168,18 @@ pub trait DistantChannelExt {
     /// Renames a remote file or directory from src to dst
     fn rename(&mut self, src: impl Into<PathBuf>, dst: impl Into<PathBuf>) -> AsyncReturn<'_, ()>;
 
+    /// Takes a snapshot of a remote file or directory, returning the id of the snapshot
+    fn create_snapshot(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, SnapshotId>;
+
+    /// Lists the snapshots held by the remote machine, oldest first
+    fn list_snapshots(&mut self) -> AsyncReturn<'_, Vec<SnapshotInfo>>;
+
+    /// Replaces the remote file or directory that a snapshot was taken of with the snapshot
+    fn restore_snapshot(&mut self, id: SnapshotId) -> AsyncReturn<'_, ()>;
+
+    /// Removes a snapshot from the remote machine
+    fn remove_snapshot(&mut self, id: SnapshotId) -> AsyncReturn<'_, ()>;
+
     /// Watches a remote file or directory
     fn watch(
         &mut self,
@@ -92,8 +189,22 @@ pub trait DistantChannelExt {
         except: impl Into<ChangeKindSet>,
     ) -> AsyncReturn<'_, Watcher>;
 
-    /// Unwatches a remote file or directory
-    fn unwatch(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, ()>;
+    /// Unwatches a remote file or directory, limited to the watch with `id` if provided
+    fn unwatch(&mut self, path: impl Into<PathBuf>, id: Option<WatchId>) -> AsyncReturn<'_, ()>;
+
+    /// Starts recording changes to a remote file or directory in a journal kept by the server
+    fn journal_start(&mut self, path: impl Into<PathBuf>, recursive: bool) -> AsyncReturn<'_, ()>;
+
+    /// Retrieves the changes recorded in the journal of a remote file or directory after `since`,
+    /// alongside the sequence number of the latest change
+    fn journal_changes(
+        &mut self,
+        path: impl Into<PathBuf>,
+        since: JournalSeq,
+    ) -> AsyncReturn<'_, (JournalSeq, Vec<JournalEntry>)>;
+
+    /// Stops recording changes to a remote file or directory, discarding its journal
+    fn journal_stop(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, ()>;
 
     /// Spawns a process on the remote machine
     fn spawn(
@@ -122,9 +233,84 @@ pub trait DistantChannelExt {
         pty: Option<PtySize>,
     ) -> AsyncReturn<'_, RemoteOutput>;
 
+    /// Sends a signal to the process with `id` running on the remote machine
+    fn proc_signal(&mut self, id: ProcessId, signal: Signal) -> AsyncReturn<'_, ()>;
+
+    /// Lists the running processes spawned by the remote machine, or every process of it if
+    /// `all` is true
+    fn proc_list(&mut self, all: bool) -> AsyncReturn<'_, Vec<ProcInfo>>;
+
+    /// Checks that the remote server is alive, completing once it has responded
+    fn ping(&mut self) -> AsyncReturn<'_, ()>;
+
+    /// Checks that the remote server is alive while sending it `data` (which it discards),
+    /// returning the `reply_len` bytes of synthetic data that it sends back
+    fn ping_with_data(&mut self, data: Vec<u8>, reply_len: usize) -> AsyncReturn<'_, Vec<u8>>;
+
     /// Retrieves information about the remote system
     fn system_info(&mut self) -> AsyncReturn<'_, SystemInfo>;
 
+    /// Retrieves the environment variables of the remote server that are safe to share
+    fn system_env(&mut self) -> AsyncReturn<'_, Environment>;
+
+    /// Retrieves metrics about the data that the remote server has written for this connection
+    fn metrics(&mut self) -> AsyncReturn<'_, Metrics>;
+
+    /// Retrieves the clients that are connected to the remote server
+    fn client_list(&mut self) -> AsyncReturn<'_, Vec<ClientInfo>>;
+
+    /// Forcibly disconnects the client with the given `id` from the remote server
+    fn client_disconnect(&mut self, id: ClientId) -> AsyncReturn<'_, ()>;
+
+    /// Translates the paths of this connection's requests and responses using `mappings`,
+    /// replacing any mappings set before
+    fn path_map(&mut self, mappings: Vec<PathMapping>) -> AsyncReturn<'_, ()>;
+
+    /// Confines the paths of this connection's requests to `roots`, failing if roots were already
+    /// declared
+    fn roots(&mut self, roots: Vec<WorkspaceRoot>) -> AsyncReturn<'_, ()>;
+
+    /// Retrieves the current resource usage of the remote system
+    fn system_stats(&mut self) -> AsyncReturn<'_, SystemStats>;
+
+    /// Monitors the resource usage of the remote system, receiving stats each time `interval`
+    /// elapses
+    fn monitor_system(&mut self, interval: Duration) -> AsyncReturn<'_, SystemMonitor>;
+
+    /// Retrieves the current resource usage of the process spawned with `id`
+    fn proc_stats(&mut self, id: ProcessId) -> AsyncReturn<'_, ProcStats>;
+
+    /// Stops the remote system from reporting its resource usage or that of a process
+    fn cancel_system_stats(&mut self, id: SystemStatsId) -> AsyncReturn<'_, ()>;
+
+    /// Sends a signal to any process of the remote system with `pid`, not just those spawned by
+    /// the remote machine
+    fn system_kill(&mut self, pid: u32, signal: Signal) -> AsyncReturn<'_, ()>;
+
+    /// Opens a tunnel through the remote machine to `host` and `port`
+    fn open_tunnel(&mut self, host: impl Into<String>, port: u16) -> AsyncReturn<'_, RemoteTunnel>;
+
+    /// Sends data through an open tunnel
+    fn tunnel_write(&mut self, id: TunnelId, data: impl Into<Vec<u8>>) -> AsyncReturn<'_, ()>;
+
+    /// Stops sending data through an open tunnel, which keeps receiving data until the other end
+    /// closes it
+    fn tunnel_close(&mut self, id: TunnelId) -> AsyncReturn<'_, ()>;
+
+    /// Listens on the remote machine at `host` and `port` for connections that become tunnels
+    fn listen_tunnel(
+        &mut self,
+        host: impl Into<String>,
+        port: u16,
+    ) -> AsyncReturn<'_, RemoteTunnelListener>;
+
+    /// Listens on the remote machine at a new unix socket that only its user can access for
+    /// connections that become tunnels
+    fn listen_unix_tunnel(&mut self) -> AsyncReturn<'_, RemoteTunnelListener>;
+
+    /// Stops a listener on the remote machine from accepting connections
+    fn tunnel_unlisten(&mut self, id: TunnelId) -> AsyncReturn<'_, ()>;
+
     /// Writes a remote file with the data from a collection of bytes
     fn write_file(
         &mut self,
@@ -132,12 +318,39 @@ pub trait DistantChannelExt {
         data: impl Into<Vec<u8>>,
     ) -> AsyncReturn<'_, ()>;
 
+    /// Writes a collection of bytes into a remote file starting at `offset`, leaving the rest of
+    /// the file's contents intact
+    fn write_file_at(
+        &mut self,
+        path: impl Into<PathBuf>,
+        offset: u64,
+        data: impl Into<Vec<u8>>,
+    ) -> AsyncReturn<'_, ()>;
+
+    /// Writes a remote file with a collection of bytes, giving the file the permissions of `mode`
+    /// if it is created
+    fn write_file_with_mode(
+        &mut self,
+        path: impl Into<PathBuf>,
+        mode: u32,
+        data: impl Into<Vec<u8>>,
+    ) -> AsyncReturn<'_, ()>;
+
     /// Writes a remote file with the data from a string
     fn write_file_text(
         &mut self,
         path: impl Into<PathBuf>,
         data: impl Into<String>,
     ) -> AsyncReturn<'_, ()>;
+
+    /// Writes a remote file with the data from a collection of bytes, first asking whether the
+    /// remote machine already has identical content and only transferring the data if it does
+    /// not, returning true if the transfer was skipped
+    fn write_file_dedup(
+        &mut self,
+        path: impl Into<PathBuf>,
+        data: impl Into<Vec<u8>>,
+    ) -> AsyncReturn<'_, bool>;
 }
 
 macro_rules! make_body {
@@ -193,6 +406,48 @@ impl DistantChannelExt
         )
     }
 
+    fn batch(&mut self, ops: Vec<BatchOp>, atomic: bool) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::Batch { ops, atomic },
+            |data| match data {
+                DistantResponseData::Ok => Ok(()),
+                DistantResponseData::BatchFailed {
+                    step,
+                    error,
+                    rolled_back,
+                } => Err(batch_failed(step, error, rolled_back)),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn transaction(&mut self, ops: Vec<BatchOp>) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::Transaction { ops },
+            |data| match data {
+                DistantResponseData::Ok => Ok(()),
+                DistantResponseData::BatchFailed {
+                    step,
+                    error,
+                    rolled_back,
+                } => Err(batch_failed(step, error, rolled_back)),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn plan(&mut self, op: BatchOp) -> AsyncReturn<'_, Vec<PathBuf>> {
+        make_body!(self, DistantRequestData::Plan { op }, |data| match data {
+            DistantResponseData::Plan { paths } => Ok(paths),
+            DistantResponseData::Error(x) => Err(io::Error::from(x)),
+            _ => Err(mismatched_response()),
+        })
+    }
+
     fn capabilities(&mut self) -> AsyncReturn<'_, Capabilities> {
         make_body!(
             self,
@@ -216,11 +471,94 @@ impl DistantChannelExt
     fn create_dir(&mut self, path: impl Into<PathBuf>, all: bool) -> AsyncReturn<'_, ()> {
         make_body!(
             self,
-            DistantRequestData::DirCreate { path: path.into(), all },
+            DistantRequestData::DirCreate { path: path.into(), all, mode: None },
             @ok
         )
     }
 
+    fn create_dir_with_mode(
+        &mut self,
+        path: impl Into<PathBuf>,
+        all: bool,
+        mode: u32,
+    ) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::DirCreate { path: path.into(), all, mode: Some(mode) },
+            @ok
+        )
+    }
+
+    fn create_fifo(&mut self, path: impl Into<PathBuf>, mode: Option<u32>) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::FifoCreate { path: path.into(), mode },
+            @ok
+        )
+    }
+
+    fn diff(
+        &mut self,
+        src: impl Into<PathBuf>,
+        dst: impl Into<DiffTarget>,
+        context: Option<usize>,
+    ) -> AsyncReturn<'_, String> {
+        make_body!(
+            self,
+            DistantRequestData::Diff {
+                src: src.into(),
+                dst: dst.into(),
+                context,
+            },
+            |data| match data {
+                DistantResponseData::Diff { patch } => Ok(patch),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn dir_manifest(
+        &mut self,
+        path: impl Into<PathBuf>,
+        algorithm: HashAlgorithm,
+    ) -> AsyncReturn<'_, (Vec<ManifestEntry>, Vec<Failure>)> {
+        let req = Request::new(DistantMsg::Single(DistantRequestData::DirManifest {
+            path: path.into(),
+            algorithm,
+        }));
+        Box::pin(async move {
+            let mut mailbox = self.mail(req).await?;
+
+            // Files follow the directory being confirmed, arriving in chunks until the last one
+            let mut entries = Vec::new();
+            while let Some(res) = mailbox.next().await {
+                for data in res.payload.into_vec() {
+                    match data {
+                        DistantResponseData::Ok => {}
+                        DistantResponseData::DirManifestChunk {
+                            entries: chunk,
+                            errors,
+                            done,
+                        } => {
+                            entries.extend(chunk);
+                            if done {
+                                return Ok((entries, errors));
+                            }
+                        }
+                        DistantResponseData::Error(x) => return Err(io::Error::from(x)),
+                        _ => return Err(mismatched_response()),
+                    }
+                }
+            }
+
+            Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Manifest ended before its last chunk",
+            ))
+        })
+    }
+
     fn exists(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, bool> {
         make_body!(
             self,
@@ -233,6 +571,49 @@ impl DistantChannelExt
         )
     }
 
+    fn extension(
+        &mut self,
+        name: impl Into<String>,
+        payload: serde_json::Value,
+    ) -> AsyncReturn<'_, serde_json::Value> {
+        make_body!(
+            self,
+            DistantRequestData::Extension {
+                name: name.into(),
+                payload
+            },
+            |data| match data {
+                DistantResponseData::Extension { payload, .. } => Ok(payload),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn make_temp_file(&mut self, parent: Option<PathBuf>) -> AsyncReturn<'_, PathBuf> {
+        make_body!(
+            self,
+            DistantRequestData::MakeTempFile { parent },
+            |data| match data {
+                DistantResponseData::TempCreated { path } => Ok(path),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn make_temp_dir(&mut self, parent: Option<PathBuf>) -> AsyncReturn<'_, PathBuf> {
+        make_body!(
+            self,
+            DistantRequestData::MakeTempDir { parent },
+            |data| match data {
+                DistantResponseData::TempCreated { path } => Ok(path),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
     fn metadata(
         &mut self,
         path: impl Into<PathBuf>,
@@ -267,6 +648,14 @@ impl DistantChannelExt
         )
     }
 
+    fn search_next(&mut self, id: SearchId) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::SearchNext { id },
+            @ok
+        )
+    }
+
     fn read_dir(
         &mut self,
         path: impl Into<PathBuf>,
@@ -274,6 +663,7 @@ impl DistantChannelExt
         absolute: bool,
         canonicalize: bool,
         include_root: bool,
+        options: DirReadOptions,
     ) -> AsyncReturn<'_, (Vec<DirEntry>, Vec<Failure>)> {
         make_body!(
             self,
@@ -282,7 +672,9 @@ impl DistantChannelExt
                 depth,
                 absolute,
                 canonicalize,
-                include_root
+                include_root,
+                options,
+                chunk_size: None,
             },
             |data| match data {
                 DistantResponseData::DirEntries { entries, errors } => Ok((entries, errors)),
@@ -295,7 +687,32 @@ impl DistantChannelExt
     fn read_file(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, Vec<u8>> {
         make_body!(
             self,
-            DistantRequestData::FileRead { path: path.into() },
+            DistantRequestData::FileRead {
+                path: path.into(),
+                offset: None,
+                len: None,
+            },
+            |data| match data {
+                DistantResponseData::Blob { data } => Ok(data),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn read_file_at(
+        &mut self,
+        path: impl Into<PathBuf>,
+        offset: u64,
+        len: u64,
+    ) -> AsyncReturn<'_, Vec<u8>> {
+        make_body!(
+            self,
+            DistantRequestData::FileRead {
+                path: path.into(),
+                offset: Some(offset),
+                len: Some(len),
+            },
             |data| match data {
                 DistantResponseData::Blob { data } => Ok(data),
                 DistantResponseData::Error(x) => Err(io::Error::from(x)),
@@ -332,6 +749,38 @@ impl DistantChannelExt
         )
     }
 
+    fn create_snapshot(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, SnapshotId> {
+        make_body!(
+            self,
+            DistantRequestData::SnapshotCreate { path: path.into() },
+            |data| match data {
+                DistantResponseData::SnapshotCreated { id } => Ok(id),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn list_snapshots(&mut self) -> AsyncReturn<'_, Vec<SnapshotInfo>> {
+        make_body!(
+            self,
+            DistantRequestData::SnapshotList {},
+            |data| match data {
+                DistantResponseData::Snapshots { entries } => Ok(entries),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn restore_snapshot(&mut self, id: SnapshotId) -> AsyncReturn<'_, ()> {
+        make_body!(self, DistantRequestData::SnapshotRestore { id }, @ok)
+    }
+
+    fn remove_snapshot(&mut self, id: SnapshotId) -> AsyncReturn<'_, ()> {
+        make_body!(self, DistantRequestData::SnapshotRemove { id }, @ok)
+    }
+
     fn watch(
         &mut self,
         path: impl Into<PathBuf>,
@@ -345,21 +794,57 @@ impl DistantChannelExt
         Box::pin(async move { Watcher::watch(self.clone(), path, recursive, only, except).await })
     }
 
-    fn unwatch(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, ()> {
+    fn unwatch(&mut self, path: impl Into<PathBuf>, id: Option<WatchId>) -> AsyncReturn<'_, ()> {
         fn inner_unwatch(
             channel: &mut Channel<DistantMsg<DistantRequestData>, DistantMsg<DistantResponseData>>,
             path: impl Into<PathBuf>,
+            id: Option<WatchId>,
         ) -> AsyncReturn<'_, ()> {
             make_body!(
                 channel,
-                DistantRequestData::Unwatch { path: path.into() },
+                DistantRequestData::Unwatch { path: path.into(), id },
                 @ok
             )
         }
 
         let path = path.into();
 
-        Box::pin(async move { inner_unwatch(self, path).await })
+        Box::pin(async move { inner_unwatch(self, path, id).await })
+    }
+
+    fn journal_start(&mut self, path: impl Into<PathBuf>, recursive: bool) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::JournalStart { path: path.into(), recursive },
+            @ok
+        )
+    }
+
+    fn journal_changes(
+        &mut self,
+        path: impl Into<PathBuf>,
+        since: JournalSeq,
+    ) -> AsyncReturn<'_, (JournalSeq, Vec<JournalEntry>)> {
+        make_body!(
+            self,
+            DistantRequestData::JournalChanges {
+                path: path.into(),
+                since
+            },
+            |data| match data {
+                DistantResponseData::JournalChanges { seq, entries } => Ok((seq, entries)),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn journal_stop(&mut self, path: impl Into<PathBuf>) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::JournalStop { path: path.into() },
+            @ok
+        )
     }
 
     fn spawn(
@@ -418,6 +903,49 @@ impl DistantChannelExt
         })
     }
 
+    fn proc_signal(&mut self, id: ProcessId, signal: Signal) -> AsyncReturn<'_, ()> {
+        make_body!(self, DistantRequestData::ProcSignal { id, signal }, @ok)
+    }
+
+    fn proc_list(&mut self, all: bool) -> AsyncReturn<'_, Vec<ProcInfo>> {
+        make_body!(
+            self,
+            DistantRequestData::ProcList { all },
+            |data| match data {
+                DistantResponseData::ProcEntries { entries } => Ok(entries),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn ping(&mut self) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::Ping {
+                data: Vec::new(),
+                reply_len: 0
+            },
+            |data| match data {
+                DistantResponseData::Pong { .. } => Ok(()),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn ping_with_data(&mut self, data: Vec<u8>, reply_len: usize) -> AsyncReturn<'_, Vec<u8>> {
+        make_body!(
+            self,
+            DistantRequestData::Ping { data, reply_len },
+            |data| match data {
+                DistantResponseData::Pong { data } => Ok(data),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
     fn system_info(&mut self) -> AsyncReturn<'_, SystemInfo> {
         make_body!(self, DistantRequestData::SystemInfo {}, |data| match data {
             DistantResponseData::SystemInfo(x) => Ok(x),
@@ -426,6 +954,139 @@ impl DistantChannelExt
         })
     }
 
+    fn system_env(&mut self) -> AsyncReturn<'_, Environment> {
+        make_body!(self, DistantRequestData::SystemEnv {}, |data| match data {
+            DistantResponseData::SystemEnv { env } => Ok(env),
+            DistantResponseData::Error(x) => Err(io::Error::from(x)),
+            _ => Err(mismatched_response()),
+        })
+    }
+
+    fn metrics(&mut self) -> AsyncReturn<'_, Metrics> {
+        make_body!(self, DistantRequestData::Metrics {}, |data| match data {
+            DistantResponseData::Metrics(x) => Ok(x),
+            DistantResponseData::Error(x) => Err(io::Error::from(x)),
+            _ => Err(mismatched_response()),
+        })
+    }
+
+    fn client_list(&mut self) -> AsyncReturn<'_, Vec<ClientInfo>> {
+        make_body!(self, DistantRequestData::ClientList {}, |data| match data {
+            DistantResponseData::ClientList { clients } => Ok(clients),
+            DistantResponseData::Error(x) => Err(io::Error::from(x)),
+            _ => Err(mismatched_response()),
+        })
+    }
+
+    fn client_disconnect(&mut self, id: ClientId) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::ClientDisconnect { id },
+            @ok
+        )
+    }
+
+    fn path_map(&mut self, mappings: Vec<PathMapping>) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::PathMap { mappings },
+            @ok
+        )
+    }
+
+    fn roots(&mut self, roots: Vec<WorkspaceRoot>) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::Roots { roots },
+            @ok
+        )
+    }
+
+    fn system_stats(&mut self) -> AsyncReturn<'_, SystemStats> {
+        make_body!(
+            self,
+            DistantRequestData::SystemStats { interval: None },
+            |data| match data {
+                DistantResponseData::SystemStats { stats, .. } => Ok(stats),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn monitor_system(&mut self, interval: Duration) -> AsyncReturn<'_, SystemMonitor> {
+        Box::pin(async move { SystemMonitor::monitor(self.clone(), interval).await })
+    }
+
+    fn proc_stats(&mut self, id: ProcessId) -> AsyncReturn<'_, ProcStats> {
+        make_body!(
+            self,
+            DistantRequestData::ProcStats { id, interval: None },
+            |data| match data {
+                DistantResponseData::ProcStats { stats, .. } => Ok(stats),
+                DistantResponseData::Error(x) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        )
+    }
+
+    fn cancel_system_stats(&mut self, id: SystemStatsId) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::CancelSystemStats { id },
+            @ok
+        )
+    }
+
+    fn system_kill(&mut self, pid: u32, signal: Signal) -> AsyncReturn<'_, ()> {
+        make_body!(self, DistantRequestData::SystemKill { pid, signal }, @ok)
+    }
+
+    fn open_tunnel(&mut self, host: impl Into<String>, port: u16) -> AsyncReturn<'_, RemoteTunnel> {
+        let host = host.into();
+        Box::pin(async move { RemoteTunnel::open(self.clone(), host, port).await })
+    }
+
+    fn tunnel_write(&mut self, id: TunnelId, data: impl Into<Vec<u8>>) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::TunnelWrite {
+                id,
+                data: data.into()
+            },
+            @ok
+        )
+    }
+
+    fn tunnel_close(&mut self, id: TunnelId) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::TunnelClose { id },
+            @ok
+        )
+    }
+
+    fn listen_tunnel(
+        &mut self,
+        host: impl Into<String>,
+        port: u16,
+    ) -> AsyncReturn<'_, RemoteTunnelListener> {
+        let host = host.into();
+        Box::pin(async move { RemoteTunnelListener::listen(self.clone(), host, port).await })
+    }
+
+    fn listen_unix_tunnel(&mut self) -> AsyncReturn<'_, RemoteTunnelListener> {
+        Box::pin(async move { RemoteTunnelListener::listen_unix(self.clone()).await })
+    }
+
+    fn tunnel_unlisten(&mut self, id: TunnelId) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::TunnelUnlisten { id },
+            @ok
+        )
+    }
+
     fn write_file(
         &mut self,
         path: impl Into<PathBuf>,
@@ -433,7 +1094,48 @@ impl DistantChannelExt
     ) -> AsyncReturn<'_, ()> {
         make_body!(
             self,
-            DistantRequestData::FileWrite { path: path.into(), data: data.into() },
+            DistantRequestData::FileWrite {
+                path: path.into(),
+                data: data.into(),
+                offset: None,
+                mode: None,
+            },
+            @ok
+        )
+    }
+
+    fn write_file_at(
+        &mut self,
+        path: impl Into<PathBuf>,
+        offset: u64,
+        data: impl Into<Vec<u8>>,
+    ) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::FileWrite {
+                path: path.into(),
+                data: data.into(),
+                offset: Some(offset),
+                mode: None,
+            },
+            @ok
+        )
+    }
+
+    fn write_file_with_mode(
+        &mut self,
+        path: impl Into<PathBuf>,
+        mode: u32,
+        data: impl Into<Vec<u8>>,
+    ) -> AsyncReturn<'_, ()> {
+        make_body!(
+            self,
+            DistantRequestData::FileWrite {
+                path: path.into(),
+                data: data.into(),
+                offset: None,
+                mode: Some(mode),
+            },
             @ok
         )
     }
@@ -449,4 +1151,41 @@ impl DistantChannelExt
             @ok
         )
     }
+
+    fn write_file_dedup(
+        &mut self,
+        path: impl Into<PathBuf>,
+        data: impl Into<Vec<u8>>,
+    ) -> AsyncReturn<'_, bool> {
+        let path = path.into();
+        let data = data.into();
+        let hash = content_hash(&data);
+
+        Box::pin(async move {
+            let req = Request::new(DistantMsg::Single(DistantRequestData::FileWriteDedup {
+                path: path.clone(),
+                hash: hash.clone(),
+                data: None,
+            }));
+            match self.send(req).await?.payload {
+                DistantMsg::Single(DistantResponseData::Ok) => return Ok(true),
+                DistantMsg::Single(DistantResponseData::ContentMissing) => (),
+                DistantMsg::Single(DistantResponseData::Error(x)) => {
+                    return Err(io::Error::from(x))
+                }
+                _ => return Err(mismatched_response()),
+            }
+
+            let req = Request::new(DistantMsg::Single(DistantRequestData::FileWriteDedup {
+                path,
+                hash,
+                data: Some(data),
+            }));
+            match self.send(req).await?.payload {
+                DistantMsg::Single(DistantResponseData::Ok) => Ok(false),
+                DistantMsg::Single(DistantResponseData::Error(x)) => Err(io::Error::from(x)),
+                _ => Err(mismatched_response()),
+            }
+        })
+    }
 }
@@ -3,8 +3,9 @@ use crate::{
         DistantChannel, RemoteCommand, RemoteProcess, RemoteStatus, RemoteStderr, RemoteStdin,
         RemoteStdout,
     },
-    data::{Environment, PtySize},
+    data::{Environment, ProcLimits, PtySize},
 };
+use distant_net::common::authentication::AuthMethodHandler;
 use futures::stream::{Stream, StreamExt};
 use std::{
     io::{self, Cursor, Read},
@@ -25,6 +26,9 @@ pub struct RemoteLspCommand {
     pty: Option<PtySize>,
     environment: Environment,
     current_dir: Option<PathBuf>,
+    limits: ProcLimits,
+    user: Option<String>,
+    prompt_handler: Option<Box<dyn AuthMethodHandler>>,
 }
 
 impl Default for RemoteLspCommand {
@@ -40,6 +44,9 @@ impl RemoteLspCommand {
             pty: None,
             environment: Environment::new(),
             current_dir: None,
+            limits: ProcLimits::default(),
+            user: None,
+            prompt_handler: None,
         }
     }
 
@@ -61,6 +68,25 @@ impl RemoteLspCommand {
         self
     }
 
+    /// Configures the priority and limits on resources applied to the process before it runs
+    pub fn limits(&mut self, limits: ProcLimits) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Configures the process to run as `user` rather than the user running the server
+    pub fn user(&mut self, user: Option<String>) -> &mut Self {
+        self.user = user;
+        self
+    }
+
+    /// Configures `handler` to be challenged with any prompt for a password sent by the process,
+    /// sending the answer as stdin
+    pub fn prompt_handler(&mut self, handler: Box<dyn AuthMethodHandler>) -> &mut Self {
+        self.prompt_handler = Some(handler);
+        self
+    }
+
     /// Spawns the specified process on the remote machine using the given session, treating
     /// the process like an LSP server
     pub async fn spawn(
@@ -72,6 +98,11 @@ impl RemoteLspCommand {
         command.environment(self.environment.clone());
         command.current_dir(self.current_dir.clone());
         command.pty(self.pty);
+        command.limits(self.limits.clone());
+        command.user(self.user.clone());
+        if let Some(handler) = self.prompt_handler.take() {
+            command.prompt_handler(handler);
+        }
 
         let mut inner = command.spawn(channel, cmd).await?;
         let stdin = inner.stdin.take().map(RemoteLspStdin::new);
@@ -167,6 +198,11 @@ impl RemoteLspStdin {
         self.write(data.as_bytes()).await
     }
 
+    /// Closes the stdin of the remote process once everything written before now has been sent
+    pub async fn close(&mut self) -> io::Result<()> {
+        self.inner.close().await
+    }
+
     fn update_and_read_messages(&mut self, data: &[u8]) -> io::Result<Vec<LspMsg>> {
         // Create or insert into our buffer
         match &mut self.buf {
@@ -428,7 +464,11 @@ mod tests {
         // Send back a response through the session
         t1.write_frame_for(&Response::new(
             req.id,
-            DistantResponseData::ProcSpawned { id: rand::random() },
+            DistantResponseData::ProcSpawned {
+                id: rand::random(),
+                session: None,
+                output_window: None,
+            },
         ))
         .await
         .unwrap();
@@ -679,6 +719,7 @@ mod tests {
                         "field1": "a",
                         "field2": "b",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -712,6 +753,7 @@ mod tests {
                 DistantResponseData::ProcStdout {
                     id: proc.id(),
                     data: msg_a.to_vec(),
+                    stamp: None,
                 },
             ))
             .await
@@ -730,6 +772,7 @@ mod tests {
                 DistantResponseData::ProcStdout {
                     id: proc.id(),
                     data: msg_b.to_vec(),
+                    stamp: None,
                 },
             ))
             .await
@@ -764,6 +807,7 @@ mod tests {
                 DistantResponseData::ProcStdout {
                     id: proc.id(),
                     data: format!("{}{}", String::from_utf8(msg).unwrap(), extra).into_bytes(),
+                    stamp: None,
                 },
             ))
             .await
@@ -812,6 +856,7 @@ mod tests {
                         String::from_utf8(msg_2).unwrap()
                     )
                     .into_bytes(),
+                    stamp: None,
                 },
             ))
             .await
@@ -852,6 +897,7 @@ mod tests {
                         "field1": "distant://some/path",
                         "field2": "file://other/path",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -882,6 +928,7 @@ mod tests {
                         "field1": "a",
                         "field2": "b",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -915,6 +962,7 @@ mod tests {
                 DistantResponseData::ProcStderr {
                     id: proc.id(),
                     data: msg_a.to_vec(),
+                    stamp: None,
                 },
             ))
             .await
@@ -933,6 +981,7 @@ mod tests {
                 DistantResponseData::ProcStderr {
                     id: proc.id(),
                     data: msg_b.to_vec(),
+                    stamp: None,
                 },
             ))
             .await
@@ -967,6 +1016,7 @@ mod tests {
                 DistantResponseData::ProcStderr {
                     id: proc.id(),
                     data: format!("{}{}", String::from_utf8(msg).unwrap(), extra).into_bytes(),
+                    stamp: None,
                 },
             ))
             .await
@@ -1015,6 +1065,7 @@ mod tests {
                         String::from_utf8(msg_2).unwrap()
                     )
                     .into_bytes(),
+                    stamp: None,
                 },
             ))
             .await
@@ -1055,6 +1106,7 @@ mod tests {
                         "field1": "distant://some/path",
                         "field2": "file://other/path",
                     })),
+                    stamp: None,
                 },
             ))
             .await
@@ -0,0 +1,224 @@
+use crate::{
+    client::{DistantChannel, DistantChannelExt},
+    constants::CLIENT_SYSTEM_MONITOR_CAPACITY,
+    data::{DistantRequestData, DistantResponseData, SystemStats, SystemStatsId},
+    DistantMsg,
+};
+use distant_net::common::Request;
+use log::*;
+use std::{fmt, io, time::Duration};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// Represents a monitor of the resource usage of a remote system
+pub struct SystemMonitor {
+    channel: DistantChannel,
+    id: SystemStatsId,
+    task: JoinHandle<()>,
+    rx: mpsc::Receiver<SystemStats>,
+}
+
+impl fmt::Debug for SystemMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SystemMonitor")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl SystemMonitor {
+    /// Creates a monitor that receives stats about the system each time `interval` elapses
+    pub async fn monitor(mut channel: DistantChannel, interval: Duration) -> io::Result<Self> {
+        trace!("Monitoring system every {interval:?}");
+
+        // Submit our run request and get back a mailbox for responses
+        let mut mailbox = channel
+            .mail(Request::new(DistantMsg::Single(
+                DistantRequestData::SystemStats {
+                    interval: Some(interval.as_millis() as u64),
+                },
+            )))
+            .await?;
+
+        let (tx, rx) = mpsc::channel(CLIENT_SYSTEM_MONITOR_CAPACITY);
+
+        // The response to our request is the first stats, which tells us the id of the monitoring
+        let id = match mailbox.next().await.map(|res| res.payload) {
+            Some(DistantMsg::Single(DistantResponseData::SystemStats { id, stats })) => {
+                trace!("[Stats {id}] Monitor has started");
+                let _ = tx.send(stats).await;
+                id
+            }
+            Some(DistantMsg::Single(DistantResponseData::Error(x))) => {
+                return Err(io::Error::from(x))
+            }
+            Some(x) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Unexpected response: {x:?}"),
+                ))
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "System monitor missing initial stats",
+                ))
+            }
+        };
+
+        // Spawn a task that continues to forward stats, discarding anything else that it gets
+        let task = tokio::spawn(async move {
+            while let Some(res) = mailbox.next().await {
+                for data in res.payload.into_vec() {
+                    if let DistantResponseData::SystemStats { stats, .. } = data {
+                        // If we can't queue up stats anymore, we've been closed and therefore
+                        // want to quit
+                        if tx.send(stats).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            channel,
+            id,
+            task,
+            rx,
+        })
+    }
+
+    /// Returns the id of the monitoring
+    pub fn id(&self) -> SystemStatsId {
+        self.id
+    }
+
+    /// Returns true if the monitor is still receiving stats
+    pub fn is_active(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Returns the next stats received by the monitor, or none if the monitor has stopped
+    pub async fn next(&mut self) -> Option<SystemStats> {
+        self.rx.recv().await
+    }
+
+    /// Stops the remote system from reporting stats to the monitor
+    pub async fn stop(&mut self) -> io::Result<()> {
+        trace!("[Stats {}] Stopping monitor", self.id);
+        self.channel.cancel_system_stats(self.id).await?;
+
+        // Kill our task that processes inbound stats if we have successfully stopped reporting
+        self.task.abort();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DistantClient;
+    use distant_net::{
+        common::{FramedTransport, InmemoryTransport, Response},
+        Client,
+    };
+    use test_log::test;
+
+    fn make_session() -> (FramedTransport<InmemoryTransport>, DistantClient) {
+        let (t1, t2) = FramedTransport::pair(100);
+        (t1, Client::spawn_inmemory(t2, Default::default()))
+    }
+
+    fn make_stats(cpu_usage: f32) -> SystemStats {
+        SystemStats {
+            cpu_usage,
+            ..Default::default()
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn monitor_should_receive_stats_until_stopped() {
+        let (mut transport, session) = make_session();
+
+        // Create a task for monitor as we need to handle the request and a response
+        // in a separate async block
+        let monitor_task = tokio::spawn(async move {
+            SystemMonitor::monitor(session.clone_channel(), Duration::from_secs(1)).await
+        });
+
+        // Wait until we get the request from the session
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+        assert_eq!(
+            req.payload,
+            DistantRequestData::SystemStats {
+                interval: Some(1000)
+            }
+        );
+
+        // Send back the initial stats followed by another report
+        for cpu_usage in [1.0, 2.0] {
+            transport
+                .write_frame_for(&Response::new(
+                    req.id.clone(),
+                    DistantResponseData::SystemStats {
+                        id: 123,
+                        stats: make_stats(cpu_usage),
+                    },
+                ))
+                .await
+                .unwrap();
+        }
+
+        let mut monitor = monitor_task.await.unwrap().unwrap();
+        assert_eq!(monitor.id(), 123);
+        assert_eq!(monitor.next().await, Some(make_stats(1.0)));
+        assert_eq!(monitor.next().await, Some(make_stats(2.0)));
+
+        // Stopping the monitor should cancel the reporting of stats remotely
+        let stop_task = tokio::spawn(async move {
+            monitor.stop().await.unwrap();
+            monitor
+        });
+
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+        assert_eq!(
+            req.payload,
+            DistantRequestData::CancelSystemStats { id: 123 }
+        );
+        transport
+            .write_frame_for(&Response::new(req.id, DistantResponseData::Ok))
+            .await
+            .unwrap();
+
+        let monitor = stop_task.await.unwrap();
+        tokio::task::yield_now().await;
+        assert!(!monitor.is_active(), "Monitor still active after stopping");
+    }
+
+    #[test(tokio::test)]
+    async fn monitor_should_fail_if_stats_are_not_supported() {
+        let (mut transport, session) = make_session();
+
+        let monitor_task = tokio::spawn(async move {
+            SystemMonitor::monitor(session.clone_channel(), Duration::from_secs(1)).await
+        });
+
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                DistantResponseData::from(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "system_stats is unsupported",
+                )),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            monitor_task.await.unwrap().unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+    }
+}
@@ -1,15 +1,24 @@
 use crate::{
     client::DistantChannel,
-    constants::CLIENT_PIPE_CAPACITY,
-    data::{Cmd, DistantRequestData, DistantResponseData, Environment, ProcessId, PtySize},
+    constants::{CLIENT_OUTPUT_WINDOW, CLIENT_PIPE_CAPACITY},
+    data::{
+        Cmd, DistantRequestData, DistantResponseData, Environment, ProcLimits, ProcessId, PtySize,
+        SessionId,
+    },
     DistantMsg,
 };
 use distant_net::{
     client::Mailbox,
-    common::{Request, Response},
+    common::{
+        authentication::{
+            msg::{Challenge, Question},
+            AuthMethodHandler,
+        },
+        Request, Response,
+    },
 };
 use log::*;
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 use tokio::{
     io,
     sync::{
@@ -50,6 +59,14 @@ pub struct RemoteCommand {
     pty: Option<PtySize>,
     environment: Environment,
     current_dir: Option<PathBuf>,
+    output_window: Option<usize>,
+    timestamped: bool,
+    persist: bool,
+    replay: bool,
+    limits: ProcLimits,
+    kill_tree: bool,
+    user: Option<String>,
+    prompt_handler: Option<Box<dyn AuthMethodHandler>>,
 }
 
 impl Default for RemoteCommand {
@@ -65,6 +82,14 @@ impl RemoteCommand {
             pty: None,
             environment: Environment::new(),
             current_dir: None,
+            output_window: Some(CLIENT_OUTPUT_WINDOW),
+            timestamped: false,
+            persist: false,
+            replay: false,
+            limits: ProcLimits::default(),
+            kill_tree: false,
+            user: None,
+            prompt_handler: None,
         }
     }
 
@@ -86,48 +111,213 @@ impl RemoteCommand {
         self
     }
 
+    /// Configures the maximum bytes of stdout and stderr the remote process can send before
+    /// waiting on them to be read, or none to have output sent without waiting
+    pub fn output_window(&mut self, output_window: Option<usize>) -> &mut Self {
+        self.output_window = output_window;
+        self
+    }
+
+    /// Configures the remote process to send each chunk of stdout and stderr with a sequence
+    /// number and timestamp shared by both, which raw responses of the process carry as their
+    /// stamp so that they can be interleaved in the order they were read
+    pub fn timestamped(&mut self, timestamped: bool) -> &mut Self {
+        self.timestamped = timestamped;
+        self
+    }
+
+    /// Configures the process to be given a session that keeps it running while no client is
+    /// attached to it, so that it can be attached to by session later
+    pub fn persist(&mut self, persist: bool) -> &mut Self {
+        self.persist = persist;
+        self
+    }
+
+    /// Configures attaching to a persistent process to first receive the output that it produced
+    /// while no client was attached, which would otherwise be lost
+    pub fn replay(&mut self, replay: bool) -> &mut Self {
+        self.replay = replay;
+        self
+    }
+
+    /// Configures the priority and limits on resources applied to the process before it runs
+    pub fn limits(&mut self, limits: ProcLimits) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Configures killing the process to also kill the processes it started, such as the
+    /// children of a build that would otherwise be left running after canceling it
+    pub fn kill_tree(&mut self, kill_tree: bool) -> &mut Self {
+        self.kill_tree = kill_tree;
+        self
+    }
+
+    /// Configures the process to run as `user` rather than the user running the server
+    pub fn user(&mut self, user: Option<String>) -> &mut Self {
+        self.user = user;
+        self
+    }
+
+    /// Configures `handler` to be challenged with any prompt for a password sent by the process
+    /// (such as when running as another user), sending the answer as stdin, rather than passing
+    /// the prompt along as stderr
+    ///
+    /// The handler is used by the next process spawned or attached to.
+    pub fn prompt_handler(&mut self, handler: Box<dyn AuthMethodHandler>) -> &mut Self {
+        self.prompt_handler = Some(handler);
+        self
+    }
+
     /// Spawns the specified process on the remote machine using the given `channel` and `cmd`
     pub async fn spawn(
         &mut self,
-        mut channel: DistantChannel,
+        channel: DistantChannel,
         cmd: impl Into<String>,
     ) -> io::Result<RemoteProcess> {
         let cmd = cmd.into();
+        let request = DistantRequestData::ProcSpawn {
+            cmd: Cmd::from(cmd),
+            pty: self.pty,
+            environment: self.environment.clone(),
+            current_dir: self.current_dir.clone(),
+            output_window: self.output_window,
+            timestamped: self.timestamped,
+            persist: self.persist,
+            limits: self.limits.clone(),
+            kill_tree: self.kill_tree,
+            user: self.user.clone(),
+            detached: false,
+        };
+        self.start(channel, request).await
+    }
 
-        // Submit our run request and get back a mailbox for responses
+    /// Spawns the specified process on the remote machine using the given `channel` and `cmd`,
+    /// detached from the server so that it keeps running after the server exits, returning the
+    /// id given to the process by the operating system of the remote machine
+    ///
+    /// The process has no stdin, stdout, or stderr, so any pty, output window, or persistence
+    /// configured is rejected by the server.
+    pub async fn spawn_detached(
+        &mut self,
+        mut channel: DistantChannel,
+        cmd: impl Into<String>,
+    ) -> io::Result<u32> {
+        let request = DistantRequestData::ProcSpawn {
+            cmd: Cmd::from(cmd.into()),
+            pty: self.pty,
+            environment: self.environment.clone(),
+            current_dir: self.current_dir.clone(),
+            output_window: None,
+            timestamped: false,
+            persist: self.persist,
+            limits: self.limits.clone(),
+            kill_tree: false,
+            user: self.user.clone(),
+            detached: true,
+        };
+
+        let res = channel
+            .send(Request::new(DistantMsg::Single(request)))
+            .await?;
+        match res.payload {
+            DistantMsg::Single(DistantResponseData::ProcDetached { pid }) => Ok(pid),
+            DistantMsg::Single(DistantResponseData::Error(x)) => Err(x.into()),
+            DistantMsg::Single(x) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Got response type of {}", x.as_ref()),
+            )),
+            DistantMsg::Batch(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Got batch instead of single response",
+            )),
+        }
+    }
+
+    /// Attaches to the process with `id` already running on the remote machine using the given
+    /// `channel`, receiving its output from here on in place of whoever spawned it (or last
+    /// attached to it), such as after the channel used before was lost
+    ///
+    /// The output window, if any, should match that of the process when it was spawned.
+    pub async fn attach(
+        &mut self,
+        channel: DistantChannel,
+        id: ProcessId,
+    ) -> io::Result<RemoteProcess> {
+        self.start(
+            channel,
+            DistantRequestData::ProcAttach {
+                id,
+                session: None,
+                replay: self.replay,
+            },
+        )
+        .await
+    }
+
+    /// Attaches to the persistent process with `session` already running on the remote machine
+    /// using the given `channel`, such as one that a client detached from earlier
+    ///
+    /// The output window, if any, should match that of the process when it was spawned.
+    pub async fn attach_session(
+        &mut self,
+        channel: DistantChannel,
+        session: SessionId,
+    ) -> io::Result<RemoteProcess> {
+        let request = DistantRequestData::ProcAttach {
+            id: 0,
+            session: Some(session),
+            replay: self.replay,
+        };
+        self.start(channel, request).await
+    }
+
+    /// Submits `request` to spawn or attach to a process, and then handles its input and output
+    async fn start(
+        &mut self,
+        mut channel: DistantChannel,
+        request: DistantRequestData,
+    ) -> io::Result<RemoteProcess> {
+        // Submit our request and get back a mailbox for responses
         let mut mailbox = channel
-            .mail(Request::new(DistantMsg::Single(
-                DistantRequestData::ProcSpawn {
-                    cmd: Cmd::from(cmd),
-                    pty: self.pty,
-                    environment: self.environment.clone(),
-                    current_dir: self.current_dir.clone(),
-                },
-            )))
+            .mail(Request::new(DistantMsg::Single(request)))
             .await?;
 
-        // Wait until we get the first response, and get id from proc started
-        let (id, origin_id) = match mailbox.next().await {
-            Some(res) => {
-                let origin_id = res.origin_id;
-                match res.payload {
-                    DistantMsg::Single(DistantResponseData::ProcSpawned { id }) => (id, origin_id),
-                    DistantMsg::Single(DistantResponseData::Error(x)) => return Err(x.into()),
-                    DistantMsg::Single(x) => {
-                        return Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            format!("Got response type of {}", x.as_ref()),
-                        ))
-                    }
-                    DistantMsg::Batch(_) => {
-                        return Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            "Got batch instead of single response",
-                        ));
+        // Wait until we get the response with the id of the process, holding onto any output
+        // that arrives ahead of it (such as output replayed when attaching)
+        let mut early = Vec::new();
+        let (id, session, output_window, origin_id) = loop {
+            match mailbox.next().await {
+                Some(res) => {
+                    let origin_id = res.origin_id;
+                    match res.payload {
+                        DistantMsg::Single(DistantResponseData::ProcSpawned {
+                            id,
+                            session,
+                            output_window,
+                        }) => break (id, session, output_window, origin_id),
+                        DistantMsg::Single(DistantResponseData::Error(x)) => return Err(x.into()),
+                        DistantMsg::Single(
+                            x @ (DistantResponseData::ProcStdout { .. }
+                            | DistantResponseData::ProcStderr { .. }
+                            | DistantResponseData::ProcPrompt { .. }),
+                        ) => early.push(x),
+                        DistantMsg::Single(x) => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("Got response type of {}", x.as_ref()),
+                            ))
+                        }
+                        DistantMsg::Batch(_) => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "Got batch instead of single response",
+                            ));
+                        }
                     }
                 }
+                None => return Err(io::Error::from(io::ErrorKind::ConnectionAborted)),
             }
-            None => return Err(io::Error::from(io::ErrorKind::ConnectionAborted)),
         };
 
         // Create channels for our stdin/stdout/stderr
@@ -136,6 +326,15 @@ impl RemoteCommand {
         let (stderr_tx, stderr_rx) = mpsc::channel(CLIENT_PIPE_CAPACITY);
         let (resize_tx, resize_rx) = mpsc::channel(1);
 
+        // Used to acknowledge output once it has been read, which is only needed when the
+        // remote process is waiting on acknowledgements before sending more. The server can give
+        // the process a smaller window than we asked for, which we go by when it says so
+        let output_window = output_window.or(self.output_window);
+        let (ack_tx, ack_rx) = mpsc::unbounded_channel();
+        let acker = OutputAcker(output_window.map(|_| ack_tx));
+        let ack_threshold = output_window.map_or(1, |window| (window / 2).max(1));
+        let acker_2 = acker.clone();
+
         // Used to terminate request task, either explicitly by the process or internally
         // by the response task when it terminates
         let (kill_tx, kill_rx) = mpsc::channel(1);
@@ -144,12 +343,17 @@ impl RemoteCommand {
         // Now we spawn a task to handle future responses that are async
         // such as ProcStdout, ProcStderr, and ProcDone
         let (abort_res_task_tx, mut abort_res_task_rx) = mpsc::channel::<()>(1);
+        let prompter = Prompter(
+            self.prompt_handler
+                .take()
+                .map(|handler| (handler, stdin_tx.clone())),
+        );
         let res_task = tokio::spawn(async move {
             tokio::select! {
                 _ = abort_res_task_rx.recv() => {
                     panic!("killed");
                 }
-                res = process_incoming_responses(id, mailbox, stdout_tx, stderr_tx, kill_tx_2) => {
+                res = process_incoming_responses(id, early, mailbox, stdout_tx, stderr_tx, prompter, acker_2, kill_tx_2) => {
                     res
                 }
             }
@@ -162,7 +366,7 @@ impl RemoteCommand {
                 _ = abort_req_task_rx.recv() => {
                     panic!("killed");
                 }
-                res = process_outgoing_requests( id, channel, stdin_rx, resize_rx, kill_rx) => {
+                res = process_outgoing_requests(id, channel, stdin_rx, resize_rx, ack_rx, ack_threshold, kill_rx) => {
                     res
                 }
             }
@@ -180,12 +384,19 @@ impl RemoteCommand {
 
         Ok(RemoteProcess {
             id,
+            session,
             origin_id,
             abort_req_task_tx,
             abort_res_task_tx,
             stdin: Some(RemoteStdin(stdin_tx)),
-            stdout: Some(RemoteStdout(stdout_rx)),
-            stderr: Some(RemoteStderr(stderr_rx)),
+            stdout: Some(RemoteStdout {
+                rx: stdout_rx,
+                acker: acker.clone(),
+            }),
+            stderr: Some(RemoteStderr {
+                rx: stderr_rx,
+                acker,
+            }),
             resizer: RemoteProcessResizer(resize_tx),
             killer: RemoteProcessKiller(kill_tx),
             wait_task,
@@ -200,6 +411,9 @@ pub struct RemoteProcess {
     /// Id of the process
     id: ProcessId,
 
+    /// Session of the process, if it persists
+    session: Option<SessionId>,
+
     /// Id used to map back to mailbox
     origin_id: String,
 
@@ -237,6 +451,11 @@ impl RemoteProcess {
         self.id
     }
 
+    /// Returns the session of the process if it persists, which can be used to attach to it later
+    pub fn session(&self) -> Option<SessionId> {
+        self.session
+    }
+
     /// Returns the id of the request that spawned this process
     pub fn origin_id(&self) -> &str {
         &self.origin_id
@@ -275,21 +494,29 @@ impl RemoteProcess {
         let maybe_stdout = self.stdout.take();
         let maybe_stderr = self.stderr.take();
 
-        let status = self.wait().await?;
-
-        let mut stdout = Vec::new();
-        if let Some(mut reader) = maybe_stdout {
-            while let Ok(data) = reader.read().await {
-                stdout.extend(&data);
-            }
-        }
-
-        let mut stderr = Vec::new();
-        if let Some(mut reader) = maybe_stderr {
-            while let Ok(data) = reader.read().await {
-                stderr.extend(&data);
-            }
-        }
+        // Output is read while waiting as the process may not finish until its output is read
+        let (status, stdout, stderr) = tokio::join!(
+            self.wait(),
+            async move {
+                let mut stdout = Vec::new();
+                if let Some(mut reader) = maybe_stdout {
+                    while let Ok(data) = reader.read().await {
+                        stdout.extend(&data);
+                    }
+                }
+                stdout
+            },
+            async move {
+                let mut stderr = Vec::new();
+                if let Some(mut reader) = maybe_stderr {
+                    while let Ok(data) = reader.read().await {
+                        stderr.extend(&data);
+                    }
+                }
+                stderr
+            },
+        );
+        let status = status?;
 
         Ok(RemoteOutput {
             success: status.success,
@@ -355,9 +582,16 @@ impl RemoteProcessKiller {
     }
 }
 
+/// Input for the stdin of a remote process, forwarded in the order it was given
+#[derive(Debug)]
+enum StdinMsg {
+    Data(Vec<u8>),
+    Close,
+}
+
 /// A handle to a remote process' standard input (stdin)
 #[derive(Clone, Debug)]
-pub struct RemoteStdin(mpsc::Sender<Vec<u8>>);
+pub struct RemoteStdin(mpsc::Sender<StdinMsg>);
 
 impl RemoteStdin {
     /// Creates a disconnected remote stdin
@@ -369,7 +603,7 @@ impl RemoteStdin {
     /// successful, `WouldBlock` if would need to wait to send data, and `BrokenPipe`
     /// if stdin has been closed
     pub fn try_write(&mut self, data: impl Into<Vec<u8>>) -> io::Result<()> {
-        match self.0.try_send(data.into()) {
+        match self.0.try_send(StdinMsg::Data(data.into())) {
             Ok(data) => Ok(data),
             Err(TrySendError::Full(_)) => Err(io::Error::from(io::ErrorKind::WouldBlock)),
             Err(TrySendError::Closed(_)) => Err(io::Error::from(io::ErrorKind::BrokenPipe)),
@@ -384,9 +618,9 @@ impl RemoteStdin {
     /// Writes data to the stdin of a specific remote process
     pub async fn write(&mut self, data: impl Into<Vec<u8>>) -> io::Result<()> {
         self.0
-            .send(data.into())
+            .send(StdinMsg::Data(data.into()))
             .await
-            .map_err(|x| io::Error::new(io::ErrorKind::BrokenPipe, x))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Stdin channel closed"))
     }
 
     /// Same as `write`, but with a string
@@ -394,6 +628,16 @@ impl RemoteStdin {
         self.write(data.into().into_bytes()).await
     }
 
+    /// Closes the stdin of the remote process once everything written before now has been sent,
+    /// so that the process reads EOF while its output can still be read. Writing to stdin
+    /// afterwards fails, including through any clones of this handle
+    pub async fn close(&mut self) -> io::Result<()> {
+        self.0
+            .send(StdinMsg::Close)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Stdin channel closed"))
+    }
+
     /// Checks if stdin has been closed
     pub fn is_closed(&self) -> bool {
         self.0.is_closed()
@@ -402,14 +646,20 @@ impl RemoteStdin {
 
 /// A handle to a remote process' standard output (stdout)
 #[derive(Debug)]
-pub struct RemoteStdout(mpsc::Receiver<Vec<u8>>);
+pub struct RemoteStdout {
+    rx: mpsc::Receiver<Vec<u8>>,
+    acker: OutputAcker,
+}
 
 impl RemoteStdout {
     /// Tries to receive latest stdout for a remote process, yielding `None`
     /// if no stdout is available, and `BrokenPipe` if stdout has been closed
     pub fn try_read(&mut self) -> io::Result<Option<Vec<u8>>> {
-        match self.0.try_recv() {
-            Ok(data) => Ok(Some(data)),
+        match self.rx.try_recv() {
+            Ok(data) => {
+                self.acker.ack(data.len());
+                Ok(Some(data))
+            }
             Err(TryRecvError::Empty) => Ok(None),
             Err(TryRecvError::Disconnected) => Err(io::Error::from(io::ErrorKind::BrokenPipe)),
         }
@@ -428,10 +678,13 @@ impl RemoteStdout {
     /// Retrieves the latest stdout for a specific remote process, and `BrokenPipe` if stdout has
     /// been closed
     pub async fn read(&mut self) -> io::Result<Vec<u8>> {
-        self.0
+        let data = self
+            .rx
             .recv()
             .await
-            .ok_or_else(|| io::Error::from(io::ErrorKind::BrokenPipe))
+            .ok_or_else(|| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        self.acker.ack(data.len());
+        Ok(data)
     }
 
     /// Same as `read`, but returns a string
@@ -444,14 +697,20 @@ impl RemoteStdout {
 
 /// A handle to a remote process' stderr
 #[derive(Debug)]
-pub struct RemoteStderr(mpsc::Receiver<Vec<u8>>);
+pub struct RemoteStderr {
+    rx: mpsc::Receiver<Vec<u8>>,
+    acker: OutputAcker,
+}
 
 impl RemoteStderr {
     /// Tries to receive latest stderr for a remote process, yielding `None`
     /// if no stderr is available, and `BrokenPipe` if stderr has been closed
     pub fn try_read(&mut self) -> io::Result<Option<Vec<u8>>> {
-        match self.0.try_recv() {
-            Ok(data) => Ok(Some(data)),
+        match self.rx.try_recv() {
+            Ok(data) => {
+                self.acker.ack(data.len());
+                Ok(Some(data))
+            }
             Err(TryRecvError::Empty) => Ok(None),
             Err(TryRecvError::Disconnected) => Err(io::Error::from(io::ErrorKind::BrokenPipe)),
         }
@@ -470,10 +729,13 @@ impl RemoteStderr {
     /// Retrieves the latest stderr for a specific remote process, and `BrokenPipe` if stderr has
     /// been closed
     pub async fn read(&mut self) -> io::Result<Vec<u8>> {
-        self.0
+        let data = self
+            .rx
             .recv()
             .await
-            .ok_or_else(|| io::Error::from(io::ErrorKind::BrokenPipe))
+            .ok_or_else(|| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        self.acker.ack(data.len());
+        Ok(data)
     }
 
     /// Same as `read`, but returns a string
@@ -484,25 +746,55 @@ impl RemoteStderr {
     }
 }
 
+/// Acknowledges output of a remote process once read, if the process is waiting on acknowledgements
+#[derive(Clone, Debug)]
+struct OutputAcker(Option<mpsc::UnboundedSender<usize>>);
+
+impl OutputAcker {
+    fn ack(&self, len: usize) {
+        if let Some(tx) = self.0.as_ref() {
+            let _ = tx.send(len);
+        }
+    }
+}
+
 /// Helper function that loops, processing outgoing stdin requests to a remote process as well as
 /// supporting a kill request to terminate the remote process
+///
+/// Output that has been read is acknowledged once at least `ack_threshold` bytes have built up,
+/// avoiding a request for every chunk of output
 async fn process_outgoing_requests(
     id: ProcessId,
     mut channel: DistantChannel,
-    mut stdin_rx: mpsc::Receiver<Vec<u8>>,
+    mut stdin_rx: mpsc::Receiver<StdinMsg>,
     mut resize_rx: mpsc::Receiver<PtySize>,
+    mut ack_rx: mpsc::UnboundedReceiver<usize>,
+    ack_threshold: usize,
     mut kill_rx: mpsc::Receiver<()>,
 ) -> io::Result<()> {
+    // Output still needs to be acknowledged after stdin is closed, so we keep going without it
+    let mut stdin_open = true;
+    let mut unacked = 0;
+
     let result = loop {
         tokio::select! {
-            data = stdin_rx.recv() => {
+            data = stdin_rx.recv(), if stdin_open => {
                 match data {
-                    Some(data) => channel.fire(
+                    Some(StdinMsg::Data(data)) => channel.fire(
                         Request::new(
                             DistantMsg::Single(DistantRequestData::ProcStdin { id, data })
                         )
                     ).await?,
-                    None => break Err(errors::dead_channel()),
+                    Some(StdinMsg::Close) => {
+                        stdin_rx.close();
+                        stdin_open = false;
+                        channel.fire(
+                            Request::new(
+                                DistantMsg::Single(DistantRequestData::ProcStdinClose { id })
+                            )
+                        ).await?;
+                    }
+                    None => stdin_open = false,
                 }
             }
             size = resize_rx.recv() => {
@@ -515,6 +807,21 @@ async fn process_outgoing_requests(
                     None => break Err(errors::dead_channel()),
                 }
             }
+            Some(len) = ack_rx.recv() => {
+                unacked += len;
+                while let Ok(len) = ack_rx.try_recv() {
+                    unacked += len;
+                }
+
+                if unacked >= ack_threshold {
+                    let len = std::mem::take(&mut unacked);
+                    channel.fire(
+                        Request::new(
+                            DistantMsg::Single(DistantRequestData::ProcOutputAck { id, len })
+                        )
+                    ).await?;
+                }
+            }
             msg = kill_rx.recv() => {
                 if msg.is_some() {
                     channel.fire(Request::new(
@@ -532,16 +839,65 @@ async fn process_outgoing_requests(
     result
 }
 
+/// Answers prompts for a password sent by a remote process using a handler, sending answers
+/// as stdin
+///
+/// NOTE: Stdin is only held onto with a handler, so that dropping stdin otherwise closes it
+struct Prompter(Option<PromptAnswerer>);
+
+/// Handler challenged with prompts along with where its answers are sent
+type PromptAnswerer = (Box<dyn AuthMethodHandler>, mpsc::Sender<StdinMsg>);
+
+impl Prompter {
+    /// Challenges the handler with `prompt`, sending the answer (followed by a newline) as stdin,
+    /// or passes `prompt` along as stderr when there is no handler
+    async fn prompt(&mut self, prompt: String, stderr_tx: &mpsc::Sender<Vec<u8>>) {
+        let (handler, stdin_tx) = match self.0.as_mut() {
+            Some((handler, stdin_tx)) => (handler, stdin_tx),
+            None => {
+                let _ = stderr_tx.send(prompt.into_bytes()).await;
+                return;
+            }
+        };
+
+        let challenge = Challenge {
+            questions: vec![Question::new(prompt)],
+            options: HashMap::new(),
+        };
+        match handler.on_challenge(challenge).await {
+            Ok(response) => {
+                let mut answer = response.answers.into_iter().next().unwrap_or_default();
+                answer.push('\n');
+                let _ = stdin_tx.send(StdinMsg::Data(answer.into_bytes())).await;
+            }
+            Err(x) => error!("Failed to answer prompt: {x}"),
+        }
+    }
+}
+
 /// Helper function that loops, processing incoming stdout & stderr requests from a remote process
+/// Forwards the output of the process from `mailbox` (after that of `early`, which arrived ahead
+/// of the process being spawned or attached) until it exits
+#[allow(clippy::too_many_arguments)]
 async fn process_incoming_responses(
     proc_id: ProcessId,
+    early: Vec<DistantResponseData>,
     mut mailbox: Mailbox<Response<DistantMsg<DistantResponseData>>>,
     stdout_tx: mpsc::Sender<Vec<u8>>,
     stderr_tx: mpsc::Sender<Vec<u8>>,
+    mut prompter: Prompter,
+    acker: OutputAcker,
     kill_tx: mpsc::Sender<()>,
 ) -> io::Result<(bool, Option<i32>)> {
-    while let Some(res) = mailbox.next().await {
-        let payload = res.payload.into_vec();
+    let mut early = Some(early);
+    loop {
+        let payload = match early.take() {
+            Some(payload) => payload,
+            None => match mailbox.next().await {
+                Some(res) => res.payload.into_vec(),
+                None => break,
+            },
+        };
 
         // Check if any of the payload data is the termination
         let exit_status = payload.iter().find_map(|data| match data {
@@ -551,15 +907,23 @@ async fn process_incoming_responses(
             _ => None,
         });
 
-        // Next, check for stdout/stderr and send them along our channels
+        // Next, check for stdout/stderr and send them along our channels, acknowledging output
+        // right away if nothing is left to read it so the process is not held up
         // TODO: What should we do about unexpected data? For now, just ignore
         for data in payload {
             match data {
-                DistantResponseData::ProcStdout { id, data } if id == proc_id => {
-                    let _ = stdout_tx.send(data).await;
+                DistantResponseData::ProcStdout { id, data, .. } if id == proc_id => {
+                    if let Err(x) = stdout_tx.send(data).await {
+                        acker.ack(x.0.len());
+                    }
                 }
-                DistantResponseData::ProcStderr { id, data } if id == proc_id => {
-                    let _ = stderr_tx.send(data).await;
+                DistantResponseData::ProcStderr { id, data, .. } if id == proc_id => {
+                    if let Err(x) = stderr_tx.send(data).await {
+                        acker.ack(x.0.len());
+                    }
+                }
+                DistantResponseData::ProcPrompt { id, prompt } if id == proc_id => {
+                    prompter.prompt(prompt, &stderr_tx).await;
                 }
                 _ => {}
             }
@@ -601,7 +965,9 @@ mod tests {
         data::{Error, ErrorKind},
     };
     use distant_net::{
-        common::{FramedTransport, InmemoryTransport, Response},
+        common::{
+            authentication::PromptAuthMethodHandler, FramedTransport, InmemoryTransport, Response,
+        },
         Client,
     };
     use std::time::Duration;
@@ -632,7 +998,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id,
-                DistantMsg::Batch(vec![DistantResponseData::ProcSpawned { id: 1 }]),
+                DistantMsg::Batch(vec![DistantResponseData::ProcSpawned {
+                    id: 1,
+                    session: None,
+                    output_window: None,
+                }]),
             ))
             .await
             .unwrap();
@@ -700,7 +1070,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id,
-                DistantMsg::Single(DistantResponseData::ProcSpawned { id }),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
             ))
             .await
             .unwrap();
@@ -739,7 +1113,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id,
-                DistantMsg::Single(DistantResponseData::ProcSpawned { id }),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
             ))
             .await
             .unwrap();
@@ -792,7 +1170,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id,
-                DistantMsg::Single(DistantResponseData::ProcSpawned { id }),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
             ))
             .await
             .unwrap();
@@ -818,6 +1200,213 @@ mod tests {
         }
     }
 
+    #[test(tokio::test)]
+    async fn stdin_close_should_be_forwarded_after_earlier_stdin() {
+        let (mut transport, session) = make_session();
+
+        let spawn_task = tokio::spawn(async move {
+            RemoteCommand::new()
+                .spawn(session.clone_channel(), String::from("cmd arg"))
+                .await
+        });
+
+        let req: Request<DistantMsg<DistantRequestData>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+
+        let id = 12345;
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
+            ))
+            .await
+            .unwrap();
+
+        let mut proc = spawn_task.await.unwrap().unwrap();
+        let stdin = proc.stdin.as_mut().unwrap();
+        stdin.write("some input").await.unwrap();
+        stdin.close().await.unwrap();
+
+        let req: Request<DistantMsg<DistantRequestData>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match req.payload {
+            DistantMsg::Single(DistantRequestData::ProcStdin { id, data }) => {
+                assert_eq!(id, 12345);
+                assert_eq!(data, b"some input");
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+
+        let req: Request<DistantMsg<DistantRequestData>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match req.payload {
+            DistantMsg::Single(DistantRequestData::ProcStdinClose { id }) => {
+                assert_eq!(id, 12345);
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+
+        // Verify we can no longer write to stdin anymore
+        assert_eq!(
+            stdin.write("more input").await.unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn prompt_should_be_answered_as_stdin_using_prompt_handler() {
+        let (mut transport, session) = make_session();
+
+        let spawn_task = tokio::spawn(async move {
+            RemoteCommand::new()
+                .user(Some(String::from("bob")))
+                .prompt_handler(Box::new(PromptAuthMethodHandler::new(
+                    |_: &str| Ok(String::new()),
+                    |prompt: &str| {
+                        assert_eq!(prompt, "[sudo] password for alice: ");
+                        Ok(String::from("secret"))
+                    },
+                )))
+                .spawn(session.clone_channel(), String::from("cmd arg"))
+                .await
+        });
+
+        // Verify that the user is included when spawning
+        let req: Request<DistantMsg<DistantRequestData>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match &req.payload {
+            DistantMsg::Single(DistantRequestData::ProcSpawn { user, .. }) => {
+                assert_eq!(user.as_deref(), Some("bob"));
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+
+        let id = 12345;
+        transport
+            .write_frame_for(&Response::new(
+                req.id.clone(),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
+            ))
+            .await
+            .unwrap();
+        let _proc = spawn_task.await.unwrap().unwrap();
+
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                DistantMsg::Single(DistantResponseData::ProcPrompt {
+                    id,
+                    prompt: String::from("[sudo] password for alice: "),
+                }),
+            ))
+            .await
+            .unwrap();
+
+        // Verify that the answer is sent as stdin
+        let req: Request<DistantMsg<DistantRequestData>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match req.payload {
+            DistantMsg::Single(DistantRequestData::ProcStdin { id, data }) => {
+                assert_eq!(id, 12345);
+                assert_eq!(data, b"secret\n");
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn prompt_should_be_forwarded_as_stderr_without_prompt_handler() {
+        let (mut transport, session) = make_session();
+
+        let spawn_task = tokio::spawn(async move {
+            RemoteCommand::new()
+                .spawn(session.clone_channel(), String::from("cmd arg"))
+                .await
+        });
+
+        let req: Request<DistantMsg<DistantRequestData>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+
+        let id = 12345;
+        transport
+            .write_frame_for(&Response::new(
+                req.id.clone(),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
+            ))
+            .await
+            .unwrap();
+        let mut proc = spawn_task.await.unwrap().unwrap();
+
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                DistantMsg::Single(DistantResponseData::ProcPrompt {
+                    id,
+                    prompt: String::from("[sudo] password for alice: "),
+                }),
+            ))
+            .await
+            .unwrap();
+
+        let out = proc.stderr.as_mut().unwrap().read().await.unwrap();
+        assert_eq!(out, b"[sudo] password for alice: ");
+    }
+
+    #[test(tokio::test)]
+    async fn output_sent_before_spawned_response_should_still_be_forwarded() {
+        let (mut transport, session) = make_session();
+
+        let spawn_task = tokio::spawn(async move {
+            RemoteCommand::new()
+                .spawn(session.clone_channel(), String::from("cmd arg"))
+                .await
+        });
+
+        let req: Request<DistantMsg<DistantRequestData>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+
+        // Replayed output can reach us ahead of the response confirming the process
+        let id = 12345;
+        transport
+            .write_frame_for(&Response::new(
+                req.id.clone(),
+                DistantMsg::Single(DistantResponseData::ProcStdout {
+                    id,
+                    data: b"early".to_vec(),
+                    stamp: None,
+                }),
+            ))
+            .await
+            .unwrap();
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
+            ))
+            .await
+            .unwrap();
+        let mut proc = spawn_task.await.unwrap().unwrap();
+
+        let out = proc.stdout.as_mut().unwrap().read().await.unwrap();
+        assert_eq!(out, b"early");
+    }
+
     #[test(tokio::test)]
     async fn stdout_should_be_forwarded_to_receiver_field() {
         let (mut transport, session) = make_session();
@@ -839,7 +1428,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id.clone(),
-                DistantMsg::Single(DistantResponseData::ProcSpawned { id }),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
             ))
             .await
             .unwrap();
@@ -853,6 +1446,7 @@ mod tests {
                 DistantMsg::Single(DistantResponseData::ProcStdout {
                     id,
                     data: b"some out".to_vec(),
+                    stamp: None,
                 }),
             ))
             .await
@@ -862,6 +1456,144 @@ mod tests {
         assert_eq!(out, b"some out");
     }
 
+    #[test(tokio::test)]
+    async fn stdout_should_be_acknowledged_once_read() {
+        let (mut transport, session) = make_session();
+
+        // Create a task for process spawning as we need to handle the request and a response
+        // in a separate async block
+        let spawn_task = tokio::spawn(async move {
+            RemoteCommand::new()
+                .output_window(Some(16))
+                .spawn(session.clone_channel(), String::from("cmd arg"))
+                .await
+        });
+
+        // Wait until we get the request from the session
+        let req: Request<DistantMsg<DistantRequestData>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match &req.payload {
+            DistantMsg::Single(DistantRequestData::ProcSpawn { output_window, .. }) => {
+                assert_eq!(*output_window, Some(16));
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+
+        // Send back a response through the session
+        let id = 12345;
+        transport
+            .write_frame_for(&Response::new(
+                req.id.clone(),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
+            ))
+            .await
+            .unwrap();
+
+        // Receive the process and then send stdout for it in two pieces
+        let mut proc = spawn_task.await.unwrap().unwrap();
+
+        for data in [b"some", b" out"] {
+            transport
+                .write_frame_for(&Response::new(
+                    req.id.clone(),
+                    DistantMsg::Single(DistantResponseData::ProcStdout {
+                        id,
+                        data: data.to_vec(),
+                        stamp: None,
+                    }),
+                ))
+                .await
+                .unwrap();
+        }
+
+        // Nothing is acknowledged until at least half of the window has been read
+        let out = proc.stdout.as_mut().unwrap().read().await.unwrap();
+        assert_eq!(out, b"some");
+
+        tokio::time::timeout(
+            Duration::from_millis(50),
+            transport.read_frame_as::<Request<DistantMsg<DistantRequestData>>>(),
+        )
+        .await
+        .expect_err("Unexpectedly acknowledged output before half of window was read");
+
+        let out = proc.stdout.as_mut().unwrap().read().await.unwrap();
+        assert_eq!(out, b" out");
+
+        let req: Request<DistantMsg<DistantRequestData>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match req.payload {
+            DistantMsg::Single(DistantRequestData::ProcOutputAck { id: ack_id, len }) => {
+                assert_eq!(ack_id, id);
+                assert_eq!(len, 8);
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn stdout_should_be_acknowledged_using_window_reported_by_server() {
+        let (mut transport, session) = make_session();
+
+        // Create a task for process spawning as we need to handle the request and a response
+        // in a separate async block
+        let spawn_task = tokio::spawn(async move {
+            RemoteCommand::new()
+                .output_window(Some(1024))
+                .spawn(session.clone_channel(), String::from("cmd arg"))
+                .await
+        });
+
+        // Wait until we get the request from the session
+        let req: Request<DistantMsg<DistantRequestData>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+
+        // Send back a response through the session that shrinks the window
+        let id = 12345;
+        transport
+            .write_frame_for(&Response::new(
+                req.id.clone(),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: Some(8),
+                }),
+            ))
+            .await
+            .unwrap();
+
+        let mut proc = spawn_task.await.unwrap().unwrap();
+        transport
+            .write_frame_for(&Response::new(
+                req.id.clone(),
+                DistantMsg::Single(DistantResponseData::ProcStdout {
+                    id,
+                    data: b"some".to_vec(),
+                    stamp: None,
+                }),
+            ))
+            .await
+            .unwrap();
+
+        // Half of the shrunk window being read is enough to acknowledge it
+        let out = proc.stdout.as_mut().unwrap().read().await.unwrap();
+        assert_eq!(out, b"some");
+
+        let req: Request<DistantMsg<DistantRequestData>> =
+            transport.read_frame_as().await.unwrap().unwrap();
+        match req.payload {
+            DistantMsg::Single(DistantRequestData::ProcOutputAck { id: ack_id, len }) => {
+                assert_eq!(ack_id, id);
+                assert_eq!(len, 4);
+            }
+            x => panic!("Unexpected request: {:?}", x),
+        }
+    }
+
     #[test(tokio::test)]
     async fn stderr_should_be_forwarded_to_receiver_field() {
         let (mut transport, session) = make_session();
@@ -883,7 +1615,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id.clone(),
-                DistantMsg::Single(DistantResponseData::ProcSpawned { id }),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
             ))
             .await
             .unwrap();
@@ -897,6 +1633,7 @@ mod tests {
                 DistantMsg::Single(DistantResponseData::ProcStderr {
                     id,
                     data: b"some err".to_vec(),
+                    stamp: None,
                 }),
             ))
             .await
@@ -927,7 +1664,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id,
-                DistantMsg::Single(DistantResponseData::ProcSpawned { id }),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
             ))
             .await
             .unwrap();
@@ -960,7 +1701,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id,
-                DistantMsg::Single(DistantResponseData::ProcSpawned { id }),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
             ))
             .await
             .unwrap();
@@ -1007,7 +1752,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id.clone(),
-                DistantMsg::Single(DistantResponseData::ProcSpawned { id }),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
             ))
             .await
             .unwrap();
@@ -1062,7 +1811,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id,
-                DistantMsg::Single(DistantResponseData::ProcSpawned { id }),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
             ))
             .await
             .unwrap();
@@ -1098,7 +1851,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id,
-                DistantMsg::Single(DistantResponseData::ProcSpawned { id }),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
             ))
             .await
             .unwrap();
@@ -1141,7 +1898,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id.clone(),
-                DistantMsg::Single(DistantResponseData::ProcSpawned { id }),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
             ))
             .await
             .unwrap();
@@ -1194,7 +1955,11 @@ mod tests {
         transport
             .write_frame_for(&Response::new(
                 req.id.clone(),
-                DistantMsg::Single(DistantResponseData::ProcSpawned { id }),
+                DistantMsg::Single(DistantResponseData::ProcSpawned {
+                    id,
+                    session: None,
+                    output_window: None,
+                }),
             ))
             .await
             .unwrap();
@@ -1210,6 +1975,7 @@ mod tests {
                 DistantMsg::Single(DistantResponseData::ProcStdout {
                     id,
                     data: b"some out".to_vec(),
+                    stamp: None,
                 }),
             ))
             .await
@@ -1222,6 +1988,7 @@ mod tests {
                 DistantMsg::Single(DistantResponseData::ProcStderr {
                     id,
                     data: b"some err".to_vec(),
+                    stamp: None,
                 }),
             ))
             .await
@@ -176,6 +176,13 @@ impl Searcher {
         self.rx.recv().await
     }
 
+    /// Requests the next page of matches of a search performed on demand, which are then
+    /// returned by [`Searcher::next`]
+    pub async fn next_page(&mut self) -> io::Result<()> {
+        trace!("[Query {}] Requesting next page", self.id);
+        self.channel.search_next(self.id).await
+    }
+
     /// Cancels the search being performed by the watcher
     pub async fn cancel(&mut self) -> io::Result<()> {
         trace!("[Query {}] Cancelling search", self.id);
@@ -620,4 +627,52 @@ mod tests {
         );
         assert_eq!(searcher.lock().await.next().await, None);
     }
+
+    #[test(tokio::test)]
+    async fn searcher_should_request_next_page_of_its_search() {
+        let (mut transport, session) = make_session();
+
+        let test_query = SearchQuery {
+            paths: vec![PathBuf::from("/some/test/path")],
+            target: SearchQueryTarget::Path,
+            condition: SearchQueryCondition::Regex {
+                value: String::from("."),
+            },
+            options: SearchQueryOptions {
+                pagination: Some(1),
+                on_demand: true,
+                ..Default::default()
+            },
+        };
+
+        let search_task =
+            tokio::spawn(
+                async move { Searcher::search(session.clone_channel(), test_query).await },
+            );
+
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+
+        let id = rand::random::<SearchId>();
+        transport
+            .write_frame_for(&Response::new(
+                req.id,
+                DistantResponseData::SearchStarted { id },
+            ))
+            .await
+            .unwrap();
+
+        let mut searcher = search_task.await.unwrap().unwrap();
+        let next_task = tokio::spawn(async move { searcher.next_page().await });
+
+        // Verify the request is for the next page of our search, and respond with ok
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+        assert_eq!(req.payload, DistantRequestData::SearchNext { id });
+
+        transport
+            .write_frame_for(&Response::new(req.id, DistantResponseData::Ok))
+            .await
+            .unwrap();
+
+        next_task.await.unwrap().unwrap();
+    }
 }
@@ -0,0 +1,420 @@
+use crate::{
+    client::{DistantChannel, DistantChannelExt},
+    constants::TUNNEL_CAPACITY,
+    data::{DistantRequestData, DistantResponseData, TunnelId},
+    DistantMsg,
+};
+use distant_net::common::Request;
+use log::*;
+use std::{
+    collections::HashMap,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// Represents a TCP connection made by the remote machine on behalf of the client, through which
+/// data is sent and received
+pub struct RemoteTunnel {
+    reader: RemoteTunnelReader,
+    writer: RemoteTunnelWriter,
+}
+
+impl fmt::Debug for RemoteTunnel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteTunnel")
+            .field("id", &self.id())
+            .finish()
+    }
+}
+
+impl RemoteTunnel {
+    /// Opens a tunnel through the remote machine to `host` and `port`
+    pub async fn open(
+        mut channel: DistantChannel,
+        host: impl Into<String>,
+        port: u16,
+    ) -> io::Result<Self> {
+        let host = host.into();
+        trace!("Opening tunnel to {host}:{port}");
+
+        // Submit our open request and get back a mailbox for responses
+        let mut mailbox = channel
+            .mail(Request::new(DistantMsg::Single(
+                DistantRequestData::TunnelOpen { host, port },
+            )))
+            .await?;
+
+        let id = match mailbox.next().await.map(|res| res.payload) {
+            Some(DistantMsg::Single(DistantResponseData::TunnelOpened { id })) => {
+                trace!("[Tunnel {id}] Opened");
+                id
+            }
+            Some(DistantMsg::Single(DistantResponseData::Error(x))) => {
+                return Err(io::Error::from(x))
+            }
+            Some(x) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Unexpected response: {x:?}"),
+                ))
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Tunnel missing open confirmation",
+                ))
+            }
+        };
+
+        let (tx, rx) = mpsc::channel(TUNNEL_CAPACITY);
+
+        // Spawn a task that forwards data read from the tunnel until the other end closes it,
+        // discarding anything else that it gets
+        let task = tokio::spawn(async move {
+            while let Some(res) = mailbox.next().await {
+                for data in res.payload.into_vec() {
+                    let sent = match data {
+                        DistantResponseData::TunnelData { data, .. } => tx.send(data).await,
+                        DistantResponseData::TunnelClosed { .. } => {
+                            trace!("[Tunnel {id}] Closed by other end");
+                            return;
+                        }
+                        _ => Ok(()),
+                    };
+
+                    // If we can't queue up data anymore, the reader is gone and we want to quit
+                    if sent.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            reader: RemoteTunnelReader {
+                id,
+                rx,
+                task: Some(task),
+            },
+            writer: RemoteTunnelWriter { id, channel },
+        })
+    }
+
+    /// Returns the id of the tunnel
+    pub fn id(&self) -> TunnelId {
+        self.writer.id
+    }
+
+    /// Returns the next data read from the tunnel, or none once the other end has closed it
+    pub async fn read(&mut self) -> Option<Vec<u8>> {
+        self.reader.read().await
+    }
+
+    /// Sends `data` through the tunnel
+    pub async fn write(&mut self, data: impl Into<Vec<u8>>) -> io::Result<()> {
+        self.writer.write(data).await
+    }
+
+    /// Stops sending data through the tunnel, which keeps receiving data until the other end
+    /// closes it
+    pub async fn close(&mut self) -> io::Result<()> {
+        self.writer.close().await
+    }
+
+    /// Splits the tunnel into its reading and writing halves, which can be used independently
+    pub fn into_split(self) -> (RemoteTunnelReader, RemoteTunnelWriter) {
+        (self.reader, self.writer)
+    }
+}
+
+/// Reading half of a [`RemoteTunnel`]
+pub struct RemoteTunnelReader {
+    id: TunnelId,
+    rx: mpsc::Receiver<Vec<u8>>,
+
+    /// Task forwarding data read from the tunnel, missing when the tunnel was accepted by a
+    /// [`RemoteTunnelListener`] that forwards the data instead
+    task: Option<JoinHandle<()>>,
+}
+
+impl Drop for RemoteTunnelReader {
+    /// Stops receiving data from the tunnel
+    fn drop(&mut self) {
+        if let Some(task) = self.task.as_ref() {
+            task.abort();
+        }
+    }
+}
+
+impl RemoteTunnelReader {
+    /// Returns the id of the tunnel
+    pub fn id(&self) -> TunnelId {
+        self.id
+    }
+
+    /// Returns the next data read from the tunnel, or none once the other end has closed it
+    pub async fn read(&mut self) -> Option<Vec<u8>> {
+        self.rx.recv().await
+    }
+}
+
+/// Writing half of a [`RemoteTunnel`]
+pub struct RemoteTunnelWriter {
+    id: TunnelId,
+    channel: DistantChannel,
+}
+
+impl RemoteTunnelWriter {
+    /// Returns the id of the tunnel
+    pub fn id(&self) -> TunnelId {
+        self.id
+    }
+
+    /// Sends `data` through the tunnel, waiting for the server to accept it so that writes
+    /// arrive in the order they were made
+    pub async fn write(&mut self, data: impl Into<Vec<u8>>) -> io::Result<()> {
+        self.channel.tunnel_write(self.id, data).await
+    }
+
+    /// Stops sending data through the tunnel
+    pub async fn close(&mut self) -> io::Result<()> {
+        self.channel.tunnel_close(self.id).await
+    }
+}
+
+/// Represents a listener on the remote machine, where each connection it accepts is a tunnel
+pub struct RemoteTunnelListener {
+    channel: DistantChannel,
+    id: TunnelId,
+    port: u16,
+    path: Option<PathBuf>,
+    rx: mpsc::Receiver<(RemoteTunnel, String)>,
+    task: JoinHandle<()>,
+}
+
+impl fmt::Debug for RemoteTunnelListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteTunnelListener")
+            .field("id", &self.id)
+            .field("port", &self.port)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl Drop for RemoteTunnelListener {
+    /// Stops receiving connections as well as data for the tunnels already accepted
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl RemoteTunnelListener {
+    /// Listens on the remote machine at `host` and `port`, where a port of 0 picks any free port
+    pub async fn listen(
+        channel: DistantChannel,
+        host: impl Into<String>,
+        port: u16,
+    ) -> io::Result<Self> {
+        let host = host.into();
+        trace!("Listening for tunnels on {host}:{port}");
+        Self::start(channel, DistantRequestData::TunnelListen { host, port }).await
+    }
+
+    /// Listens on the remote machine at a new unix socket that only its user can access
+    pub async fn listen_unix(channel: DistantChannel) -> io::Result<Self> {
+        trace!("Listening for tunnels on a unix socket");
+        Self::start(channel, DistantRequestData::TunnelListenUnix {}).await
+    }
+
+    async fn start(mut channel: DistantChannel, request: DistantRequestData) -> io::Result<Self> {
+        // Submit our listen request and get back a mailbox for responses
+        let mut mailbox = channel
+            .mail(Request::new(DistantMsg::Single(request)))
+            .await?;
+
+        let (id, port, path) = match mailbox.next().await.map(|res| res.payload) {
+            Some(DistantMsg::Single(DistantResponseData::TunnelListening { id, port })) => {
+                trace!("[Tunnel {id}] Listening on port {port}");
+                (id, port, None)
+            }
+            Some(DistantMsg::Single(DistantResponseData::TunnelListeningUnix { id, path })) => {
+                trace!("[Tunnel {id}] Listening on {path:?}");
+                (id, 0, Some(path))
+            }
+            Some(DistantMsg::Single(DistantResponseData::Error(x))) => {
+                return Err(io::Error::from(x))
+            }
+            Some(x) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Unexpected response: {x:?}"),
+                ))
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Tunnel listener missing listen confirmation",
+                ))
+            }
+        };
+
+        let (tx, rx) = mpsc::channel(TUNNEL_CAPACITY);
+
+        // Spawn a task that hands out accepted tunnels and forwards the data read from each of
+        // them, as everything arrives through the response to our one request
+        let tunnel_channel = channel.clone();
+        let task = tokio::spawn(async move {
+            let mut readers: HashMap<TunnelId, mpsc::Sender<Vec<u8>>> = HashMap::new();
+            while let Some(res) = mailbox.next().await {
+                for data in res.payload.into_vec() {
+                    match data {
+                        DistantResponseData::TunnelAccepted { id, peer, .. } => {
+                            trace!("[Tunnel {id}] Accepted connection from {peer}");
+                            let (data_tx, data_rx) = mpsc::channel(TUNNEL_CAPACITY);
+                            readers.insert(id, data_tx);
+
+                            let tunnel = RemoteTunnel {
+                                reader: RemoteTunnelReader {
+                                    id,
+                                    rx: data_rx,
+                                    task: None,
+                                },
+                                writer: RemoteTunnelWriter {
+                                    id,
+                                    channel: tunnel_channel.clone(),
+                                },
+                            };
+
+                            // Tunnels already accepted still get their data when no one is
+                            // accepting more
+                            let _ = tx.send((tunnel, peer)).await;
+                        }
+                        DistantResponseData::TunnelData { id, data } => {
+                            let closed = match readers.get(&id) {
+                                Some(reader) => reader.send(data).await.is_err(),
+                                None => false,
+                            };
+                            if closed {
+                                readers.remove(&id);
+                            }
+                        }
+                        DistantResponseData::TunnelClosed { id } => {
+                            trace!("[Tunnel {id}] Closed by other end");
+                            readers.remove(&id);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            channel,
+            id,
+            port,
+            path,
+            rx,
+            task,
+        })
+    }
+
+    /// Returns the id of the listener
+    pub fn id(&self) -> TunnelId {
+        self.id
+    }
+
+    /// Returns the port being listened on, which is useful when listening on port 0, and is 0
+    /// when listening on a unix socket
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns the path of the unix socket being listened on, if listening on one
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns the tunnel of the next connection accepted along with the address of its peer,
+    /// or none if the listener has stopped
+    pub async fn accept(&mut self) -> Option<(RemoteTunnel, String)> {
+        self.rx.recv().await
+    }
+
+    /// Stops the remote machine from accepting connections, leaving the tunnels already
+    /// accepted open
+    pub async fn close(&mut self) -> io::Result<()> {
+        trace!("[Tunnel {}] Stopping listener", self.id);
+        self.channel.tunnel_unlisten(self.id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DistantClient;
+    use distant_net::{
+        common::{FramedTransport, InmemoryTransport, Response},
+        Client,
+    };
+    use test_log::test;
+
+    fn make_session() -> (FramedTransport<InmemoryTransport>, DistantClient) {
+        let (t1, t2) = FramedTransport::pair(100);
+        (t1, Client::spawn_inmemory(t2, Default::default()))
+    }
+
+    #[test(tokio::test)]
+    async fn tunnel_should_read_data_until_closed_by_other_end() {
+        let (mut transport, session) = make_session();
+
+        let open_task = tokio::spawn(async move {
+            RemoteTunnel::open(session.clone_channel(), "db.internal", 5432).await
+        });
+
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+        assert_eq!(
+            req.payload,
+            DistantRequestData::TunnelOpen {
+                host: String::from("db.internal"),
+                port: 5432,
+            }
+        );
+
+        for data in [
+            DistantResponseData::TunnelOpened { id: 7 },
+            DistantResponseData::TunnelData {
+                id: 7,
+                data: b"hello".to_vec(),
+            },
+            DistantResponseData::TunnelClosed { id: 7 },
+        ] {
+            transport
+                .write_frame_for(&Response::new(req.id.clone(), data))
+                .await
+                .unwrap();
+        }
+
+        let mut tunnel = open_task.await.unwrap().unwrap();
+        assert_eq!(tunnel.id(), 7);
+        assert_eq!(tunnel.read().await, Some(b"hello".to_vec()));
+        assert_eq!(tunnel.read().await, None);
+
+        // Writing still goes through to the server, which decides whether the tunnel is open
+        let write_task = tokio::spawn(async move { tunnel.write(b"world".to_vec()).await });
+        let req: Request<DistantRequestData> = transport.read_frame_as().await.unwrap().unwrap();
+        assert_eq!(
+            req.payload,
+            DistantRequestData::TunnelWrite {
+                id: 7,
+                data: b"world".to_vec(),
+            }
+        );
+        transport
+            .write_frame_for(&Response::new(req.id, DistantResponseData::Ok))
+            .await
+            .unwrap();
+        write_task.await.unwrap().unwrap();
+    }
+}
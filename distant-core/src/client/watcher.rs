@@ -1,7 +1,7 @@
 use crate::{
     client::{DistantChannel, DistantChannelExt},
     constants::CLIENT_WATCHER_CAPACITY,
-    data::{Change, ChangeKindSet, DistantRequestData, DistantResponseData},
+    data::{Change, ChangeKindSet, DistantRequestData, DistantResponseData, WatchId},
     DistantMsg,
 };
 use distant_net::common::Request;
@@ -15,6 +15,7 @@ use tokio::{sync::mpsc, task::JoinHandle};
 /// Represents a watcher of some path on a remote machine
 pub struct Watcher {
     channel: DistantChannel,
+    id: WatchId,
     path: PathBuf,
     task: JoinHandle<()>,
     rx: mpsc::Receiver<Change>,
@@ -23,7 +24,10 @@ pub struct Watcher {
 
 impl fmt::Debug for Watcher {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Watcher").field("path", &self.path).finish()
+        f.debug_struct("Watcher")
+            .field("id", &self.id)
+            .field("path", &self.path)
+            .finish()
     }
 }
 
@@ -37,10 +41,12 @@ impl Watcher {
         except: impl Into<ChangeKindSet>,
     ) -> io::Result<Self> {
         let path = path.into();
+        let id = rand::random();
         let only = only.into();
         let except = except.into();
         trace!(
-            "Watching {:?} (recursive = {}){}{}",
+            "[Watch {}] Watching {:?} (recursive = {}){}{}",
+            id,
             path,
             recursive,
             if only.is_empty() {
@@ -63,6 +69,7 @@ impl Watcher {
                     recursive,
                     only: only.into_sorted_vec(),
                     except: except.into_sorted_vec(),
+                    id: Some(id),
                 },
             )))
             .await?;
@@ -143,6 +150,7 @@ impl Watcher {
         });
 
         Ok(Self {
+            id,
             path,
             channel,
             task,
@@ -151,6 +159,11 @@ impl Watcher {
         })
     }
 
+    /// Returns the id of the watch, which tells it apart from others of the same path
+    pub fn id(&self) -> WatchId {
+        self.id
+    }
+
     /// Returns a reference to the path this watcher is monitoring
     pub fn path(&self) -> &Path {
         self.path.as_path()
@@ -168,8 +181,10 @@ impl Watcher {
 
     /// Unwatches the path being watched, closing out the watcher
     pub async fn unwatch(&mut self) -> io::Result<()> {
-        trace!("Unwatching {:?}", self.path);
-        self.channel.unwatch(self.path.to_path_buf()).await?;
+        trace!("[Watch {}] Unwatching {:?}", self.id, self.path);
+        self.channel
+            .unwatch(self.path.to_path_buf(), Some(self.id))
+            .await?;
 
         // Kill our task that processes inbound changes if we have successfully unwatched the path
         self.task.abort();
@@ -3,15 +3,42 @@ use std::time::Duration;
 /// Capacity associated stdin, stdout, and stderr pipes receiving data from remote server
 pub const CLIENT_PIPE_CAPACITY: usize = 10000;
 
+/// Maximum bytes of stdout and stderr a remote process will send before waiting on the client to
+/// read them
+///
+/// Current setting is 1MB size
+pub const CLIENT_OUTPUT_WINDOW: usize = 1024 * 1024;
+
+/// Maximum size (in bytes) of a file of process output spilled to disk by the server before
+/// another file is started
+///
+/// Current setting is 8MB size
+pub const MAX_SPILL_FILE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Maximum files of process output spilled to disk that the server keeps for each of stdout and
+/// stderr before discarding the oldest
+pub const MAX_SPILL_FILES: usize = 4;
+
 /// Capacity associated with a client watcher receiving changes
 pub const CLIENT_WATCHER_CAPACITY: usize = 100;
 
 /// Capacity associated with a client searcher receiving matches
 pub const CLIENT_SEARCHER_CAPACITY: usize = 10000;
 
+/// Time that the server waits on a client to request the next page of results of an on-demand
+/// search before canceling the search
+pub const SERVER_SEARCH_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
 /// Capacity associated with the server's file watcher to pass events outbound
 pub const SERVER_WATCHER_CAPACITY: usize = 10000;
 
+/// Maximum changes kept in the journal of a path on the server before the oldest are discarded
+pub const SERVER_JOURNAL_CAPACITY: usize = 10000;
+
+/// Time that the server's file watcher holds back a change that may be one side of a rename,
+/// waiting for the other side so that both can be reported as a single change
+pub const SERVER_WATCHER_RENAME_TIMEOUT: Duration = Duration::from_millis(50);
+
 /// Represents the maximum size (in bytes) that data will be read from pipes
 /// per individual `read` call
 ///
@@ -21,3 +48,57 @@ pub const MAX_PIPE_CHUNK_SIZE: usize = 16384;
 /// Duration in milliseconds to sleep between reading stdout/stderr chunks
 /// to avoid sending many small messages to clients
 pub const READ_PAUSE_DURATION: Duration = Duration::from_millis(1);
+
+/// Time over which CPU usage is measured when first reporting system stats, as usage is
+/// otherwise measured since the previous report
+pub const SYSTEM_STATS_SAMPLE_DURATION: Duration = Duration::from_millis(250);
+
+/// Shortest time the server will wait between reports of system stats
+pub const MIN_SYSTEM_STATS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Environment variables of the server that clients can retrieve, where a trailing `*` matches
+/// any suffix; others are withheld as they may hold secrets such as tokens
+pub const SERVER_ENV_ALLOWLIST: &[&str] = &[
+    "COMSPEC",
+    "HOME",
+    "LANG",
+    "LANGUAGE",
+    "LC_*",
+    "LOGNAME",
+    "PATH",
+    "PATHEXT",
+    "PWD",
+    "SHELL",
+    "SYSTEMROOT",
+    "TEMP",
+    "TERM",
+    "TMP",
+    "TMPDIR",
+    "TZ",
+    "USER",
+    "USERNAME",
+    "USERPROFILE",
+];
+
+/// Capacity associated with a client system monitor receiving stats
+pub const CLIENT_SYSTEM_MONITOR_CAPACITY: usize = 10;
+
+/// Maximum size (in bytes) of the synthetic data the server sends back in response to a ping
+///
+/// Current setting is 16MB size
+pub const MAX_PING_REPLY_LEN: usize = 16 * 1024 * 1024;
+
+/// Time for which the server keeps the result of a request with an idempotency key, responding
+/// with it to retries of the request
+pub const IDEMPOTENCY_KEY_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Prefix that the names of custom requests handled by extensions must start with
+pub const EXTENSION_PREFIX: &str = "x-";
+
+/// Capacity associated with a tunnel on the server queuing data to write to its connection, and
+/// with a client tunnel receiving data read from the other end
+pub const TUNNEL_CAPACITY: usize = 100;
+
+/// Maximum pieces of content the server remembers for deduplicated writes, after which the least
+/// recently used is forgotten
+pub const MAX_INDEXED_CONTENT: usize = 4096;
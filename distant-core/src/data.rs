@@ -3,9 +3,15 @@ use serde::{Deserialize, Serialize};
 use std::{io, path::PathBuf};
 use strum::{AsRefStr, EnumDiscriminants, EnumIter, EnumMessage, EnumString};
 
+mod batch;
+pub use batch::*;
+
 mod capabilities;
 pub use capabilities::*;
 
+mod diff;
+pub use diff::*;
+
 mod change;
 pub use change::*;
 
@@ -18,15 +24,36 @@ pub use error::*;
 mod filesystem;
 pub use filesystem::*;
 
+mod journal;
+pub use journal::*;
+
+mod manifest;
+pub use manifest::*;
+
 mod metadata;
 pub use metadata::*;
 
+mod metrics;
+pub use metrics::*;
+
+mod path_map;
+pub use path_map::*;
+
+mod process;
+pub use process::*;
+
 mod pty;
 pub use pty::*;
 
 mod search;
 pub use search::*;
 
+mod signal;
+pub use signal::*;
+
+mod snapshot;
+pub use snapshot::*;
+
 mod system;
 pub use system::*;
 
@@ -36,9 +63,21 @@ pub(crate) use utils::*;
 /// Id for a remote process
 pub type ProcessId = u32;
 
+/// Id for a persistent session of a remote process, which clients use to attach to it
+pub type SessionId = u32;
+
+/// Id for a tunnel opened through the remote machine
+pub type TunnelId = u32;
+
 /// Mapping of environment variables
 pub type Environment = distant_net::common::Map;
 
+/// Information about a client connected to the server
+pub type ClientInfo = distant_net::server::ClientInfo;
+
+/// Id of a client's connection with the server
+pub type ClientId = distant_net::common::ConnectionId;
+
 /// Represents a wrapper around a distant message, supporting single and batch requests
 #[derive(Clone, Debug, From, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -156,6 +195,17 @@ pub enum DistantRequestData {
     FileRead {
         /// The path to the file on the remote machine
         path: PathBuf,
+
+        /// If provided, reads starting at this byte offset within the file rather than at its
+        /// start
+        ///
+        /// Not supported by servers that reach files over sftp, which cannot seek within them.
+        #[serde(default)]
+        offset: Option<u64>,
+
+        /// If provided, reads at most this many bytes rather than the rest of the file
+        #[serde(default)]
+        len: Option<u64>,
     },
 
     /// Reads a file from the specified path on the remote machine
@@ -177,6 +227,18 @@ pub enum DistantRequestData {
         #[serde(with = "serde_bytes")]
         #[cfg_attr(feature = "schemars", schemars(with = "Vec<u8>"))]
         data: Vec<u8>,
+
+        /// If provided, writes the data starting at this byte offset within the file, leaving
+        /// the rest of the existing content intact, rather than overwriting the entire file
+        ///
+        /// Not supported by servers that reach files over sftp, which cannot seek within them.
+        #[serde(default)]
+        offset: Option<u64>,
+
+        /// If provided, permissions (such as `0o644`) given to the file if it is created, in
+        /// place of the server's default, regardless of the server's umask
+        #[serde(default)]
+        mode: Option<u32>,
     },
 
     /// Writes a file using text instead of bytes, creating it if it does not exist,
@@ -190,6 +252,26 @@ pub enum DistantRequestData {
         text: String,
     },
 
+    /// Writes a file identified by the hash of its content, creating it if it does not exist, and
+    /// overwriting any existing content on the remote machine, skipping the transfer of the
+    /// content when the remote machine already has identical content
+    #[strum_discriminants(strum(
+        message = "Supports writing binary file deduplicated by content hash"
+    ))]
+    FileWriteDedup {
+        /// The path to the file on the remote machine
+        path: PathBuf,
+
+        /// Hex-encoded SHA-256 hash of the content
+        hash: String,
+
+        /// Content of the file, which is omitted to first ask whether the remote machine already
+        /// has content matching the hash, in which case it is written without being transferred
+        #[serde(default, with = "serde_bytes")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Option<Vec<u8>>"))]
+        data: Option<Vec<u8>>,
+    },
+
     /// Appends to a file, creating it if it does not exist, on the remote machine
     #[strum_discriminants(strum(message = "Supports appending to binary file"))]
     FileAppend {
@@ -244,6 +326,30 @@ pub enum DistantRequestData {
         /// absolute path and will not follow any of the other flags
         #[serde(default)]
         include_root: bool,
+
+        /// Sorting and filtering to apply to the retrieved entries
+        #[serde(default)]
+        options: DirReadOptions,
+
+        /// If provided and non-zero, entries are streamed back as a series of
+        /// `dir_entries_chunk` responses containing at most this many entries
+        /// each, with the last chunk marked as done, instead of a single
+        /// `dir_entries` response
+        #[serde(default)]
+        chunk_size: Option<usize>,
+    },
+
+    /// Lists every file under a directory on the remote machine along with its size, last
+    /// modified time, and hash of its content, as a series of `dir_manifest_chunk` responses
+    /// that follow the directory being confirmed with an `ok` response
+    #[strum_discriminants(strum(message = "Supports listing hashes of files within directory"))]
+    DirManifest {
+        /// The path to the directory on the remote machine
+        path: PathBuf,
+
+        /// Algorithm used to hash the content of each file
+        #[serde(default)]
+        algorithm: HashAlgorithm,
     },
 
     /// Creates a directory on the remote machine
@@ -255,6 +361,119 @@ pub enum DistantRequestData {
         /// Whether or not to create all parent directories
         #[serde(default)]
         all: bool,
+
+        /// If provided, permissions (such as `0o755`) given to each directory that is created, in
+        /// place of the server's default, regardless of the server's umask
+        #[serde(default)]
+        mode: Option<u32>,
+    },
+
+    /// Creates a named pipe (FIFO) on the remote machine
+    #[strum_discriminants(strum(message = "Supports creating named pipes"))]
+    FifoCreate {
+        /// The path to the named pipe on the remote machine
+        path: PathBuf,
+
+        /// If provided, permissions (such as `0o600`) given to the named pipe, in place of the
+        /// server's default, regardless of the server's umask
+        #[serde(default)]
+        mode: Option<u32>,
+    },
+
+    /// Creates a uniquely-named, empty file on the remote machine
+    #[strum_discriminants(strum(message = "Supports creating temporary files"))]
+    MakeTempFile {
+        /// Directory on the remote machine in which to create the file, defaulting to the
+        /// temporary directory of the remote machine
+        #[serde(default)]
+        parent: Option<PathBuf>,
+    },
+
+    /// Creates a uniquely-named, empty directory on the remote machine
+    #[strum_discriminants(strum(message = "Supports creating temporary directories"))]
+    MakeTempDir {
+        /// Directory on the remote machine in which to create the directory, defaulting to the
+        /// temporary directory of the remote machine
+        #[serde(default)]
+        parent: Option<PathBuf>,
+    },
+
+    /// Performs a sequence of filesystem operations on the remote machine, stopping at the
+    /// first operation that fails
+    #[strum_discriminants(strum(
+        message = "Supports performing a batch of filesystem operations"
+    ))]
+    Batch {
+        /// Operations to perform in order
+        ops: Vec<BatchOp>,
+
+        /// If true, undoes files and directories created or moved by earlier operations when an
+        /// operation fails, leaving the filesystem as it was prior to the batch
+        #[serde(default)]
+        atomic: bool,
+    },
+
+    /// Applies a sequence of filesystem operations on the remote machine all at once or not at
+    /// all, backing up anything they replace or remove so that it can be restored if an
+    /// operation fails
+    #[strum_discriminants(strum(
+        message = "Supports applying filesystem operations all or nothing"
+    ))]
+    Transaction {
+        /// Operations to apply in order
+        ops: Vec<BatchOp>,
+    },
+
+    /// Resolves the paths on the remote machine that a filesystem operation would affect without
+    /// performing it, failing in the same way that the operation would if it cannot be performed
+    #[strum_discriminants(strum(
+        message = "Supports planning filesystem operations without performing them"
+    ))]
+    Plan {
+        /// Operation to plan
+        op: BatchOp,
+    },
+
+    /// Takes a snapshot of a file or directory on the remote machine so that it can be restored
+    /// later, which lasts until it is removed or the server exits
+    #[strum_discriminants(strum(message = "Supports taking snapshots of files and directories"))]
+    SnapshotCreate {
+        /// The path to the file or directory on the remote machine
+        path: PathBuf,
+    },
+
+    /// Lists the snapshots held by the remote machine
+    #[strum_discriminants(strum(message = "Supports listing snapshots"))]
+    SnapshotList {},
+
+    /// Replaces a file or directory on the remote machine with the snapshot taken of it, keeping
+    /// the snapshot so that it can be restored again
+    #[strum_discriminants(strum(message = "Supports restoring snapshots"))]
+    SnapshotRestore {
+        /// Id of the snapshot to restore
+        id: SnapshotId,
+    },
+
+    /// Removes a snapshot from the remote machine
+    #[strum_discriminants(strum(message = "Supports removing snapshots"))]
+    SnapshotRemove {
+        /// Id of the snapshot to remove
+        id: SnapshotId,
+    },
+
+    /// Computes a unified diff between a file on the remote machine and either another file or
+    /// supplied text
+    #[strum_discriminants(strum(message = "Supports computing a unified diff of files"))]
+    Diff {
+        /// Path to the original file
+        src: PathBuf,
+
+        /// What to compare the original file against
+        dst: DiffTarget,
+
+        /// Number of lines of context to include around each change, defaulting to 3
+        #[serde(default)]
+        context: Option<usize>,
     },
 
     /// Removes a file or directory on the remote machine
@@ -270,6 +489,9 @@ pub enum DistantRequestData {
     },
 
     /// Copies a file or directory on the remote machine
+    ///
+    /// Holes in sparse files are preserved by the local server; the ssh server leaves this to
+    /// the remote `cp` (or `Copy-Item` on Windows), which may write them out as zeroes
     #[strum_discriminants(strum(message = "Supports copying files, directories, and symlinks"))]
     Copy {
         /// The path to the file or directory on the remote machine
@@ -307,6 +529,12 @@ pub enum DistantRequestData {
         /// Filter to report back changes except these specified changes
         #[serde(default)]
         except: Vec<ChangeKind>,
+
+        /// If provided, tells this watch apart from other watches of the same path over the same
+        /// connection (such as from other channels), so that unwatching it leaves the others in
+        /// place, where watching again with the same id moves the watch over to this request
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<WatchId>,
     },
 
     /// Unwatches a path for changes, meaning no additional changes will be reported
@@ -314,6 +542,43 @@ pub enum DistantRequestData {
     Unwatch {
         /// The path to the file, directory, or symlink on the remote machine
         path: PathBuf,
+
+        /// If provided, only unwatches the watch of the path with this id, otherwise unwatches
+        /// every watch of the path made over the connection
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<WatchId>,
+    },
+
+    /// Starts recording changes to a path in a journal kept by the server, which goes on even
+    /// while no client is connected so that a client can catch up on what changed since it left
+    #[strum_discriminants(strum(message = "Supports journaling changes to a path"))]
+    JournalStart {
+        /// The path to the file, directory, or symlink on the remote machine
+        path: PathBuf,
+
+        /// If true, will record changes within subdirectories and beyond, otherwise will only
+        /// record changes immediately within directories
+        #[serde(default)]
+        recursive: bool,
+    },
+
+    /// Retrieves the changes recorded in the journal of a path after some sequence number,
+    /// failing if the journal no longer goes back that far
+    #[strum_discriminants(strum(message = "Supports retrieving changes from a journal"))]
+    JournalChanges {
+        /// The path to the file, directory, or symlink on the remote machine
+        path: PathBuf,
+
+        /// Sequence number of the last change already seen, or 0 for all changes
+        #[serde(default)]
+        since: JournalSeq,
+    },
+
+    /// Stops recording changes to a path, discarding its journal
+    #[strum_discriminants(strum(message = "Supports stopping the journaling of a path"))]
+    JournalStop {
+        /// The path to the file, directory, or symlink on the remote machine
+        path: PathBuf,
     },
 
     /// Checks whether the given path exists
@@ -358,6 +623,15 @@ pub enum DistantRequestData {
         id: SearchId,
     },
 
+    /// Requests the next page of results of an active search performed on demand
+    #[strum_discriminants(strum(
+        message = "Supports requesting more results of an on-demand search"
+    ))]
+    SearchNext {
+        /// Id of the search
+        id: SearchId,
+    },
+
     /// Spawns a new process on the remote machine
     #[strum_discriminants(strum(message = "Supports spawning a process"))]
     ProcSpawn {
@@ -375,6 +649,66 @@ pub enum DistantRequestData {
         /// If provided, will spawn process in a pty, otherwise spawns directly
         #[serde(default)]
         pty: Option<PtySize>,
+
+        /// If provided, the maximum number of bytes of stdout and stderr that can be sent without
+        /// being acknowledged via `proc_output_ack`, after which sending pauses until more output
+        /// is acknowledged
+        #[serde(default)]
+        output_window: Option<usize>,
+
+        /// If true, each chunk of stdout and stderr is sent with a sequence number and monotonic
+        /// timestamp shared by both, so that clients can interleave them in the order they were
+        /// read, such as to render build logs
+        #[serde(default)]
+        timestamped: bool,
+
+        /// If true, the process is given a session that keeps it running while no client is
+        /// attached to it, dropping its output in the meantime, so that clients can detach from
+        /// it and attach to it again by session later
+        #[serde(default)]
+        persist: bool,
+
+        /// Priority and limits on resources of the process, applied before it runs
+        #[serde(default, skip_serializing_if = "ProcLimits::is_empty")]
+        limits: ProcLimits,
+
+        /// If true, killing the process also kills the processes it started (its process group
+        /// on Unix, or a job object on Windows), so that none of them are left running
+        #[serde(default)]
+        kill_tree: bool,
+
+        /// If provided, the process is run as this user rather than the one running the server,
+        /// which may ask for a password that is reported with `proc_prompt`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        user: Option<String>,
+
+        /// If true, the process is detached from the server with no stdin, stdout, or stderr, so
+        /// that it keeps running after the server exits, reporting its pid with `proc_detached`
+        /// for it to be signaled later via `system_kill`
+        #[serde(default)]
+        detached: bool,
+    },
+
+    /// Sends the output (and exit) of a process running on the remote machine to this request
+    /// going forward, in place of the request that spawned it, which lets a client pick up a
+    /// process again after its connection dropped
+    #[strum_discriminants(strum(
+        message = "Supports reattaching to the output of a spawned process"
+    ))]
+    ProcAttach {
+        /// Id of the actively-running process, ignored when attaching by session
+        #[serde(default)]
+        id: ProcessId,
+
+        /// If provided, attaches to the persistent process with this session instead
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session: Option<SessionId>,
+
+        /// If true, output of a persistent process that could not be sent while no client was
+        /// attached (up to the most recent 256 KiB) is sent first, which can arrive ahead of the
+        /// response to this request
+        #[serde(default)]
+        replay: bool,
     },
 
     /// Kills a process running on the remote machine
@@ -384,6 +718,26 @@ pub enum DistantRequestData {
         id: ProcessId,
     },
 
+    /// Lists the processes spawned by the server that are still running, or every process of the
+    /// remote machine
+    #[strum_discriminants(strum(message = "Supports listing processes"))]
+    ProcList {
+        /// If true, will also include processes that were not spawned by the server
+        #[serde(default)]
+        all: bool,
+    },
+
+    /// Sends a signal to a process running on the remote machine, such as to interrupt it or ask
+    /// it to terminate, leaving it to the process to decide what to do
+    #[strum_discriminants(strum(message = "Supports sending a signal to a spawned process"))]
+    ProcSignal {
+        /// Id of the actively-running process
+        id: ProcessId,
+
+        /// Signal to send to the process
+        signal: Signal,
+    },
+
     /// Sends additional data to stdin of running process
     #[strum_discriminants(strum(message = "Supports sending stdin to a spawned process"))]
     ProcStdin {
@@ -396,6 +750,25 @@ pub enum DistantRequestData {
         data: Vec<u8>,
     },
 
+    /// Closes stdin of a running process so that it reads EOF, leaving the process running so
+    /// that its output can still be received
+    #[strum_discriminants(strum(message = "Supports closing stdin of a spawned process"))]
+    ProcStdinClose {
+        /// Id of the actively-running process whose stdin to close
+        id: ProcessId,
+    },
+
+    /// Acknowledges output received from a process spawned with an output window, allowing the
+    /// process to send that many more bytes of stdout and stderr
+    #[strum_discriminants(strum(message = "Supports acknowledging output of a spawned process"))]
+    ProcOutputAck {
+        /// Id of the actively-running process whose output to acknowledge
+        id: ProcessId,
+
+        /// Total bytes of stdout and stderr being acknowledged
+        len: usize,
+    },
+
     /// Resize pty of remote process
     #[strum_discriminants(strum(message = "Supports resizing the pty of a spawned process"))]
     ProcResizePty {
@@ -406,9 +779,181 @@ pub enum DistantRequestData {
         size: PtySize,
     },
 
+    /// Opens a TCP connection from the remote machine to `host` and `port`, sending back what is
+    /// read from it as it arrives, which lets clients forward local ports through the server
+    #[strum_discriminants(strum(message = "Supports opening tunnels to TCP ports"))]
+    TunnelOpen {
+        /// Host to connect to, as resolved by the remote machine
+        host: String,
+
+        /// Port to connect to
+        port: u16,
+    },
+
+    /// Sends data through an open tunnel
+    #[strum_discriminants(strum(message = "Supports sending data through a tunnel"))]
+    TunnelWrite {
+        /// Id of the open tunnel
+        id: TunnelId,
+
+        /// Data to send through the tunnel
+        #[serde(with = "serde_bytes")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Vec<u8>"))]
+        data: Vec<u8>,
+    },
+
+    /// Stops sending data through a tunnel, which continues to send back what is read from it
+    /// until the other end closes it as well
+    #[strum_discriminants(strum(message = "Supports closing a tunnel"))]
+    TunnelClose {
+        /// Id of the open tunnel
+        id: TunnelId,
+    },
+
+    /// Listens for TCP connections on the remote machine at `host` and `port`, announcing each
+    /// connection accepted as a tunnel of its own, which lets clients forward remote ports back
+    /// to the local machine
+    #[strum_discriminants(strum(message = "Supports listening for tunnels on TCP ports"))]
+    TunnelListen {
+        /// Address to listen on, as resolved by the remote machine
+        host: String,
+
+        /// Port to listen on, where 0 picks any free port
+        port: u16,
+    },
+
+    /// Listens for connections on a unix socket created by the server within a directory that
+    /// only its user can access, announcing each connection accepted as a tunnel of its own,
+    /// which lets clients forward a local socket (such as that of an SSH agent) to processes
+    /// run by the server
+    #[strum_discriminants(strum(message = "Supports listening for tunnels on unix sockets"))]
+    TunnelListenUnix {},
+
+    /// Stops listening for TCP connections, leaving the tunnels already accepted open
+    #[strum_discriminants(strum(message = "Supports no longer listening for tunnels"))]
+    TunnelUnlisten {
+        /// Id of the listener
+        id: TunnelId,
+    },
+
+    /// Checks that the server is alive, used to measure the round-trip time of requests as well
+    /// as throughput when given synthetic data to send each way
+    #[strum_discriminants(strum(message = "Supports checking that the server is alive"))]
+    Ping {
+        /// Synthetic data sent to the server, which is discarded
+        #[serde(default, with = "serde_bytes")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Vec<u8>"))]
+        data: Vec<u8>,
+
+        /// Size (in bytes) of the synthetic data for the server to send back, up to 16MB
+        #[serde(default)]
+        reply_len: usize,
+    },
+
     /// Retrieve information about the server and the system it is on
     #[strum_discriminants(strum(message = "Supports retrieving system information"))]
     SystemInfo {},
+
+    /// Retrieve the environment variables of the server, which are inherited by the processes it
+    /// spawns, limited to those that are safe to share such as `PATH` and locale settings
+    #[strum_discriminants(strum(message = "Supports retrieving the environment of the server"))]
+    SystemEnv {},
+
+    /// Retrieve metrics about the data that the server has written on behalf of the connection
+    #[strum_discriminants(strum(
+        message = "Supports retrieving metrics about data written by the server"
+    ))]
+    Metrics {},
+
+    /// Retrieve the clients that are connected to the server
+    #[strum_discriminants(strum(message = "Supports listing clients connected to the server"))]
+    ClientList {},
+
+    /// Forcibly disconnects a client from the server, which is then unable to reconnect
+    #[strum_discriminants(strum(message = "Supports disconnecting clients from the server"))]
+    ClientDisconnect {
+        /// Id of the client's connection with the server
+        id: ClientId,
+    },
+
+    /// Retrieve the CPU, memory, load, and per-process usage of the system, optionally continuing
+    /// to report it at an interval until canceled
+    #[strum_discriminants(strum(message = "Supports retrieving resource usage of the system"))]
+    SystemStats {
+        /// If provided, the time (in milliseconds) to wait between each additional report of
+        /// usage, otherwise usage is only reported once
+        #[serde(default)]
+        interval: Option<u64>,
+    },
+
+    /// Retrieve the CPU, memory, and storage i/o usage of a spawned process, optionally
+    /// continuing to report it at an interval until canceled or the process exits
+    #[strum_discriminants(strum(
+        message = "Supports retrieving resource usage of a spawned process"
+    ))]
+    ProcStats {
+        /// Id of the actively-running process
+        id: ProcessId,
+
+        /// If provided, the time (in milliseconds) to wait between each additional report of
+        /// usage, otherwise usage is only reported once
+        #[serde(default)]
+        interval: Option<u64>,
+    },
+
+    /// Stops reporting system or process usage at an interval
+    #[strum_discriminants(strum(message = "Supports canceling reporting of resource usage"))]
+    CancelSystemStats {
+        /// Id of the reporting to cancel
+        id: SystemStatsId,
+    },
+
+    /// Sends a signal to any process of the remote machine by its pid, such as to clean up one
+    /// that was orphaned, rather than only those spawned by the server
+    #[strum_discriminants(strum(
+        message = "Supports sending a signal to any process of the system"
+    ))]
+    SystemKill {
+        /// Pid of the process given by the operating system
+        pid: u32,
+
+        /// Signal to send to the process
+        signal: Signal,
+    },
+
+    /// Translates paths of the connection's requests and responses using `mappings`, replacing
+    /// any mappings set before, which lets a client refer to paths as they are known to it
+    #[strum_discriminants(strum(
+        message = "Supports translating paths between those of client and server"
+    ))]
+    PathMap {
+        /// Directories known by different paths to the client and server, where the longest
+        /// matching path is used when more than one applies
+        mappings: Vec<PathMapping>,
+    },
+
+    /// Confines the paths of the connection's requests to `roots` (with symlinks resolved), where
+    /// paths can refer to a root by name as `NAME:relative/path` and roots cannot change once
+    /// declared
+    #[strum_discriminants(strum(
+        message = "Supports confining paths to roots addressed as name:path"
+    ))]
+    Roots {
+        /// Directories that paths must stay within, where no roots lifts the restriction
+        roots: Vec<WorkspaceRoot>,
+    },
+
+    /// Custom request handled by the server's registered extensions, such as scripts or plugin
+    /// processes, rather than by distant itself
+    #[strum_discriminants(strum(message = "Supports custom requests handled by extensions"))]
+    Extension {
+        /// Name of the custom request, which must start with `x-`
+        name: String,
+
+        /// Arbitrary data passed to the handler of the request
+        #[serde(default)]
+        payload: serde_json::Value,
+    },
 }
 
 #[cfg(feature = "schemars")]
@@ -419,7 +964,7 @@ impl DistantRequestData {
 }
 
 /// Represents the payload of a successful response
-#[derive(Clone, Debug, PartialEq, Eq, AsRefStr, IsVariant, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, AsRefStr, IsVariant, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case", deny_unknown_fields, tag = "type")]
 #[strum(serialize_all = "snake_case")]
@@ -455,9 +1000,90 @@ pub enum DistantResponseData {
         errors: Vec<Error>,
     },
 
+    /// Response containing a portion of the entries from reading a directory when streaming
+    DirEntriesChunk {
+        /// Entries contained within this chunk of the requested directory
+        entries: Vec<DirEntry>,
+
+        /// Errors encountered while scanning for entries, only populated in the last chunk
+        errors: Vec<Error>,
+
+        /// Whether or not this is the last chunk of entries
+        done: bool,
+    },
+
+    /// Response containing a portion of the files from listing the manifest of a directory
+    DirManifestChunk {
+        /// Files contained within this chunk of the manifest, ordered by path
+        entries: Vec<ManifestEntry>,
+
+        /// Errors encountered while listing or hashing files, only populated in the last chunk
+        errors: Vec<Error>,
+
+        /// Whether or not this is the last chunk of files
+        done: bool,
+    },
+
+    /// Response to a deduplicated write without content when the remote machine does not have
+    /// content matching the hash, meaning that the content needs to be sent
+    ContentMissing,
+
+    /// Response to creating a temporary file or directory
+    TempCreated {
+        /// Path to the newly-created file or directory on the remote machine
+        path: PathBuf,
+    },
+
+    /// Response to taking a snapshot
+    SnapshotCreated {
+        /// Id of the snapshot
+        id: SnapshotId,
+    },
+
+    /// Response to listing snapshots
+    Snapshots {
+        /// Snapshots held by the remote machine, oldest first
+        entries: Vec<SnapshotInfo>,
+    },
+
+    /// Response to a batch or transaction of filesystem operations where one of the operations
+    /// failed
+    BatchFailed {
+        /// Index of the operation that failed, where all operations before it succeeded
+        step: usize,
+
+        /// Error encountered by the failed operation
+        error: Error,
+
+        /// If true, the changes made by earlier operations were undone
+        rolled_back: bool,
+    },
+
+    /// Response to planning a filesystem operation
+    Plan {
+        /// Paths that the operation would create, change, or remove
+        paths: Vec<PathBuf>,
+    },
+
+    /// Response to computing a diff
+    Diff {
+        /// Unified diff of the changes, which is empty if there are none
+        patch: String,
+    },
+
     /// Response to a filesystem change for some watched file, directory, or symlink
     Changed(Change),
 
+    /// Response to retrieving changes from a journal
+    JournalChanges {
+        /// Sequence number of the latest change recorded in the journal, which is where the next
+        /// retrieval should pick up from
+        seq: JournalSeq,
+
+        /// Changes recorded after the requested sequence number, oldest first
+        entries: Vec<JournalEntry>,
+    },
+
     /// Response to checking if a path exists
     Exists { value: bool },
 
@@ -483,12 +1109,35 @@ pub enum DistantResponseData {
     SearchDone {
         /// Arbitrary id associated with search
         id: SearchId,
+
+        /// Summary of the files skipped by the search
+        #[serde(default, skip_serializing_if = "SearchQuerySkipped::is_empty")]
+        skipped: SearchQuerySkipped,
+
+        /// Paths of the query that could not be searched
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        errors: Vec<SearchQueryRootError>,
     },
 
     /// Response to starting a new process
     ProcSpawned {
         /// Arbitrary id associated with running process
         id: ProcessId,
+
+        /// Session of the process if it persists, which can be used to attach to it later
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session: Option<SessionId>,
+
+        /// Output window that the process was given, which can be smaller than the one asked for
+        /// when the server limits output windows
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        output_window: Option<usize>,
+    },
+
+    /// Response to starting a process detached from the server
+    ProcDetached {
+        /// Id given to the process by the operating system
+        pid: u32,
     },
 
     /// Actively-transmitted stdout as part of running process
@@ -500,6 +1149,11 @@ pub enum DistantResponseData {
         #[serde(with = "serde_bytes")]
         #[cfg_attr(feature = "schemars", schemars(with = "Vec<u8>"))]
         data: Vec<u8>,
+
+        /// Position of the data among all output of the process, sent only if the process was
+        /// spawned with timestamped output
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stamp: Option<OutputStamp>,
     },
 
     /// Actively-transmitted stderr as part of running process
@@ -511,6 +1165,27 @@ pub enum DistantResponseData {
         #[serde(with = "serde_bytes")]
         #[cfg_attr(feature = "schemars", schemars(with = "Vec<u8>"))]
         data: Vec<u8>,
+
+        /// Position of the data among all output of the process, sent only if the process was
+        /// spawned with timestamped output
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stamp: Option<OutputStamp>,
+    },
+
+    /// Actively-transmitted prompt for a password needed to run a process as another user, which
+    /// is answered by sending the password followed by a newline as stdin
+    ProcPrompt {
+        /// Arbitrary id associated with running process
+        id: ProcessId,
+
+        /// Text of the prompt to show
+        prompt: String,
+    },
+
+    /// Response to listing processes
+    ProcEntries {
+        /// Processes running on the remote machine, ordered by their system id
+        entries: Vec<ProcInfo>,
     },
 
     /// Response to a process finishing
@@ -525,9 +1200,118 @@ pub enum DistantResponseData {
         code: Option<i32>,
     },
 
+    /// Response to opening a tunnel
+    TunnelOpened {
+        /// Arbitrary id associated with the tunnel
+        id: TunnelId,
+    },
+
+    /// Actively-transmitted data read from the other end of a tunnel
+    TunnelData {
+        /// Arbitrary id associated with the tunnel
+        id: TunnelId,
+
+        /// Data read from the tunnel
+        #[serde(with = "serde_bytes")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Vec<u8>"))]
+        data: Vec<u8>,
+    },
+
+    /// Response to the other end of a tunnel closing it, after which nothing more is read
+    TunnelClosed {
+        /// Arbitrary id associated with the tunnel
+        id: TunnelId,
+    },
+
+    /// Response to listening for tunnels
+    TunnelListening {
+        /// Arbitrary id associated with the listener
+        id: TunnelId,
+
+        /// Port being listened on
+        port: u16,
+    },
+
+    /// Response to listening for tunnels on a unix socket
+    TunnelListeningUnix {
+        /// Arbitrary id associated with the listener
+        id: TunnelId,
+
+        /// Path to the unix socket being listened on
+        path: PathBuf,
+    },
+
+    /// Actively-transmitted connection accepted by a listener, which is sent before any data
+    /// read from it
+    TunnelAccepted {
+        /// Id of the listener that accepted the connection
+        listener: TunnelId,
+
+        /// Arbitrary id associated with the tunnel of the connection
+        id: TunnelId,
+
+        /// Address of the peer that made the connection
+        peer: String,
+    },
+
+    /// Response to checking that the server is alive
+    Pong {
+        /// Synthetic data of the requested size
+        #[serde(default, skip_serializing_if = "Vec::is_empty", with = "serde_bytes")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Vec<u8>"))]
+        data: Vec<u8>,
+    },
+
     /// Response to retrieving information about the server and the system it is on
     SystemInfo(SystemInfo),
 
+    /// Response to retrieving the environment of the server
+    SystemEnv {
+        /// Environment variables of the server that are safe to share
+        env: Environment,
+    },
+
+    /// Response to retrieving metrics about the data written by the server
+    Metrics(Metrics),
+
+    /// Response to listing the clients connected to the server
+    ClientList {
+        /// Clients that are connected, oldest first
+        clients: Vec<ClientInfo>,
+    },
+
+    /// Response to retrieving the resource usage of the system, which is sent again at the
+    /// requested interval until canceled
+    SystemStats {
+        /// Arbitrary id associated with the reporting of usage
+        id: SystemStatsId,
+
+        /// Usage of the system
+        stats: SystemStats,
+    },
+
+    /// Response to retrieving the resource usage of a spawned process, which is sent again at
+    /// the requested interval until canceled or the process exits
+    ProcStats {
+        /// Id of the process
+        id: ProcessId,
+
+        /// Arbitrary id associated with the reporting of usage, used to cancel it
+        stats_id: SystemStatsId,
+
+        /// Usage of the process
+        stats: ProcStats,
+    },
+
+    /// Response to a custom request handled by an extension
+    Extension {
+        /// Name of the custom request
+        name: String,
+
+        /// Arbitrary data returned by the handler of the request
+        payload: serde_json::Value,
+    },
+
     /// Response to retrieving information about the server's capabilities
     Capabilities { supported: Capabilities },
 }
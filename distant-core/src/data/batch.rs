@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Represents a single filesystem operation performed as a step of a batch or transaction
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields, tag = "type")]
+pub enum BatchOp {
+    /// Creates a directory, optionally creating all missing parent directories
+    DirCreate {
+        path: PathBuf,
+        #[serde(default)]
+        all: bool,
+    },
+
+    /// Writes a file, creating it if it does not exist, and overwriting any existing content
+    FileWrite {
+        path: PathBuf,
+        #[serde(with = "serde_bytes")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Vec<u8>"))]
+        data: Vec<u8>,
+    },
+
+    /// Writes a file using text, creating it if it does not exist, and overwriting any existing
+    /// content
+    FileWriteText { path: PathBuf, text: String },
+
+    /// Appends to a file, creating it if it does not exist
+    FileAppend {
+        path: PathBuf,
+        #[serde(with = "serde_bytes")]
+        #[cfg_attr(feature = "schemars", schemars(with = "Vec<u8>"))]
+        data: Vec<u8>,
+    },
+
+    /// Appends text to a file, creating it if it does not exist
+    FileAppendText { path: PathBuf, text: String },
+
+    /// Copies a file or directory
+    Copy { src: PathBuf, dst: PathBuf },
+
+    /// Moves or renames a file or directory
+    Rename { src: PathBuf, dst: PathBuf },
+
+    /// Removes a file or directory, which cannot be undone if the batch is rolled back
+    Remove {
+        path: PathBuf,
+        #[serde(default)]
+        force: bool,
+    },
+
+    /// Creates a symlink at `path` pointing to `target`, replacing any file or symlink already
+    /// at `path` in a single step
+    Symlink { path: PathBuf, target: PathBuf },
+}
@@ -12,6 +12,9 @@ use std::{
 };
 use strum::{EnumString, EnumVariantNames, VariantNames};
 
+/// Id chosen by a client for one of its watches of a path
+pub type WatchId = u32;
+
 /// Change to one or more paths on the filesystem
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -123,6 +126,10 @@ pub enum ChangeKind {
     /// is the result of the rename
     RenameTo,
 
+    /// A file or directory was renamed, and the provided paths are where it was renamed from and
+    /// to in that order, paired up from the separate changes reported for each side
+    Renamed,
+
     /// A file's size changed
     Size,
 
@@ -204,7 +211,7 @@ impl ChangeKind {
     pub fn is_rename_kind(&self) -> bool {
         matches!(
             self,
-            Self::Rename | Self::RenameBoth | Self::RenameFrom | Self::RenameTo
+            Self::Rename | Self::RenameBoth | Self::RenameFrom | Self::RenameTo | Self::Renamed
         )
     }
 
@@ -331,6 +338,7 @@ impl ChangeKindSet {
             ChangeKind::RenameBoth,
             ChangeKind::RenameFrom,
             ChangeKind::RenameTo,
+            ChangeKind::Renamed,
             ChangeKind::Size,
             ChangeKind::Ownership,
             ChangeKind::Permissions,
@@ -380,7 +388,11 @@ impl ChangeKindSet {
 
     /// Produces a changeset containing all of the rename kinds
     pub fn rename_set() -> Self {
-        ChangeKind::Rename | ChangeKind::RenameBoth | ChangeKind::RenameFrom | ChangeKind::RenameTo
+        ChangeKind::Rename
+            | ChangeKind::RenameBoth
+            | ChangeKind::RenameFrom
+            | ChangeKind::RenameTo
+            | ChangeKind::Renamed
     }
 
     /// Consumes set and returns a sorted vec of the kinds of changes
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Represents what a file is compared against when computing a diff
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum DiffTarget {
+    /// Another file on the remote machine
+    Path(PathBuf),
+
+    /// Text supplied as part of the request, such as the unsaved contents of an editor buffer
+    Text(String),
+}
+
+impl From<PathBuf> for DiffTarget {
+    fn from(path: PathBuf) -> Self {
+        Self::Path(path)
+    }
+}
+
+impl From<String> for DiffTarget {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
@@ -206,6 +206,9 @@ pub enum ErrorKind {
     /// When a task panics
     TaskPanicked,
 
+    /// When a request is aborted because it did not complete before its deadline
+    DeadlineExceeded,
+
     /// Catchall for an error that has no specific type
     Unknown,
 }
@@ -270,6 +273,7 @@ impl From<ErrorKind> for io::ErrorKind {
             ErrorKind::OutOfMemory => Self::OutOfMemory,
             ErrorKind::UnexpectedEof => Self::UnexpectedEof,
             ErrorKind::Unsupported => Self::Unsupported,
+            ErrorKind::DeadlineExceeded => Self::TimedOut,
             _ => Self::Other,
         }
     }
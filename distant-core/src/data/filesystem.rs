@@ -1,6 +1,6 @@
 use derive_more::IsVariant;
 use serde::{Deserialize, Serialize};
-use std::{fs::FileType as StdFileType, path::PathBuf};
+use std::{collections::HashSet, fs::FileType as StdFileType, path::PathBuf};
 use strum::AsRefStr;
 
 /// Represents information about a single entry within a directory
@@ -35,10 +35,47 @@ pub enum FileType {
     Dir,
     File,
     Symlink,
+
+    /// Named pipe (FIFO)
+    Fifo,
+
+    /// Unix domain socket
+    Socket,
+
+    /// Character device such as a terminal
+    CharDevice,
+
+    /// Block device such as a disk
+    BlockDevice,
+}
+
+impl FileType {
+    /// Returns true if this is a fifo, socket, or device, which has no contents of its own that
+    /// can be read or copied like those of a file
+    pub fn is_special(self) -> bool {
+        matches!(
+            self,
+            Self::Fifo | Self::Socket | Self::CharDevice | Self::BlockDevice
+        )
+    }
 }
 
 impl From<StdFileType> for FileType {
     fn from(ft: StdFileType) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if ft.is_fifo() {
+                return Self::Fifo;
+            } else if ft.is_socket() {
+                return Self::Socket;
+            } else if ft.is_char_device() {
+                return Self::CharDevice;
+            } else if ft.is_block_device() {
+                return Self::BlockDevice;
+            }
+        }
+
         if ft.is_dir() {
             Self::Dir
         } else if ft.is_symlink() {
@@ -55,3 +92,58 @@ impl FileType {
         schemars::schema_for!(FileType)
     }
 }
+
+/// Options to sort and filter the entries retrieved when reading a directory
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct DirReadOptions {
+    /// Order of entries within each directory
+    pub sort: DirEntrySort,
+
+    /// If true, will reverse the order of entries within each directory
+    pub reverse: bool,
+
+    /// Restrict entries to only these file types (otherwise all are allowed)
+    ///
+    /// Directories are still traversed when excluded, meaning that their children can still be
+    /// returned
+    pub allowed_file_types: HashSet<FileType>,
+
+    /// If true, will exclude hidden entries (those whose name starts with `.`) along with
+    /// anything contained within them
+    pub exclude_hidden: bool,
+}
+
+#[cfg(feature = "schemars")]
+impl DirReadOptions {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(DirReadOptions)
+    }
+}
+
+/// Represents the order of entries within a directory
+#[derive(
+    Copy, Clone, Debug, Default, Hash, PartialEq, Eq, AsRefStr, IsVariant, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+#[strum(serialize_all = "snake_case")]
+pub enum DirEntrySort {
+    /// Sort by file name
+    #[default]
+    Name,
+
+    /// Sort by size in bytes, smallest first
+    Size,
+
+    /// Sort by last modified time, oldest first
+    Modified,
+}
+
+#[cfg(feature = "schemars")]
+impl DirEntrySort {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(DirEntrySort)
+    }
+}
@@ -0,0 +1,29 @@
+use super::ChangeKind;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Sequence number given to a change recorded in a journal, starting at 1 and increasing by one
+/// with each change
+pub type JournalSeq = u64;
+
+/// Represents a change recorded in the journal of a path on the server
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct JournalEntry {
+    /// Sequence number of the change within the journal
+    pub seq: JournalSeq,
+
+    /// Label describing the kind of change
+    pub kind: ChangeKind,
+
+    /// Paths that were changed
+    pub paths: Vec<PathBuf>,
+}
+
+#[cfg(feature = "schemars")]
+impl JournalEntry {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(JournalEntry)
+    }
+}
@@ -0,0 +1,93 @@
+use derive_more::IsVariant;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use std::{io, path::PathBuf};
+use strum::AsRefStr;
+
+/// Represents an algorithm used to hash the content of files
+#[derive(
+    Copy, Clone, Debug, Default, Hash, PartialEq, Eq, AsRefStr, IsVariant, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+#[strum(serialize_all = "snake_case")]
+pub enum HashAlgorithm {
+    /// SHA-256, which is also used to identify content for deduplicated writes
+    #[default]
+    Sha256,
+
+    /// SHA-512
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// Returns the hex-encoded hash of everything read from `reader`
+    pub fn hash_reader(self, reader: &mut impl io::Read) -> io::Result<String> {
+        fn hash<D: Digest + io::Write>(reader: &mut impl io::Read) -> io::Result<String> {
+            let mut hasher = D::new();
+            io::copy(reader, &mut hasher)?;
+            Ok(hex::encode(hasher.finalize()))
+        }
+
+        match self {
+            Self::Sha256 => hash::<Sha256>(reader),
+            Self::Sha512 => hash::<Sha512>(reader),
+        }
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl HashAlgorithm {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(HashAlgorithm)
+    }
+}
+
+/// Represents a file listed within the manifest of a directory
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct ManifestEntry {
+    /// Path to the file relative to the directory of the manifest
+    pub path: PathBuf,
+
+    /// Size of the file in bytes
+    pub size: u64,
+
+    /// Represents the last time (in milliseconds since the Unix epoch) when the file was modified
+    pub modified: Option<u128>,
+
+    /// Hex-encoded hash of the content of the file
+    pub hash: String,
+}
+
+#[cfg(feature = "schemars")]
+impl ManifestEntry {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(ManifestEntry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_reader_should_return_hex_encoded_hash_of_content() {
+        assert_eq!(
+            HashAlgorithm::Sha256
+                .hash_reader(&mut &b"hello"[..])
+                .unwrap(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_eq!(
+            HashAlgorithm::Sha512
+                .hash_reader(&mut &b"hello"[..])
+                .unwrap(),
+            concat!(
+                "9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca7",
+                "2323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043"
+            )
+        );
+    }
+}
@@ -21,6 +21,11 @@ pub struct Metadata {
     /// Size of the file/directory/symlink in bytes
     pub len: u64,
 
+    /// Disk space (in bytes) actually allocated for the file/directory/symlink, which is less
+    /// than `len` for sparse files; can be optional as certain systems don't support this
+    #[serde(default)]
+    pub allocated_len: Option<u64>,
+
     /// Whether or not the file/directory/symlink is marked as unwriteable
     pub readonly: bool,
 
@@ -88,14 +93,9 @@ impl Metadata {
                 .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
                 .map(|d| d.as_millis()),
             len: metadata.len(),
+            allocated_len: allocated_len(&metadata),
             readonly: metadata.permissions().readonly(),
-            file_type: if file_type.is_dir() {
-                FileType::Dir
-            } else if file_type.is_file() {
-                FileType::File
-            } else {
-                FileType::Symlink
-            },
+            file_type: FileType::from(file_type),
 
             #[cfg(unix)]
             unix: Some({
@@ -118,6 +118,31 @@ impl Metadata {
     }
 }
 
+impl Metadata {
+    /// Returns true if the entry is known to have fewer bytes allocated on disk than its
+    /// logical size, meaning that it contains holes
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.allocated_len, Some(allocated) if allocated < self.len)
+    }
+}
+
+/// Returns the disk space allocated for the entry described by `metadata`, if known
+pub(crate) fn allocated_len(metadata: &std::fs::Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::prelude::*;
+
+        // Blocks are always reported in 512-byte units regardless of the filesystem block size
+        Some(metadata.blocks() * 512)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
 #[cfg(feature = "schemars")]
 impl Metadata {
     pub fn root_schema() -> schemars::schema::RootSchema {
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// Represents metrics about the data written by the server on behalf of a connection
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct Metrics {
+    /// Total bytes written to files through the connection
+    pub bytes_written: u64,
+
+    /// Soft quota (in bytes) on what each connection writes, beyond which the server warns
+    /// rather than refusing to write
+    #[serde(default)]
+    pub write_quota: Option<u64>,
+}
+
+impl Metrics {
+    /// Returns true if more has been written than the soft quota allows
+    pub fn is_over_quota(&self) -> bool {
+        matches!(self.write_quota, Some(quota) if self.bytes_written > quota)
+    }
+}
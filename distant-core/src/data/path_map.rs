@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::PathBuf, str::FromStr};
+
+/// Represents a directory known by one path to the client and another to the server, such as a
+/// directory mounted into a container
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct PathMapping {
+    /// Path to the directory as known by the client
+    pub client: PathBuf,
+
+    /// Path to the directory as known by the server
+    pub server: PathBuf,
+}
+
+impl fmt::Display for PathMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.client.display(), self.server.display())
+    }
+}
+
+impl FromStr for PathMapping {
+    type Err = &'static str;
+
+    /// Parses `CLIENT=SERVER`, where both paths must be absolute
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (client, server) = s.split_once('=').ok_or("expected CLIENT=SERVER")?;
+        let (client, server) = (PathBuf::from(client.trim()), PathBuf::from(server.trim()));
+
+        // NOTE: Only paths starting with a root are accepted, as the server may be on a different
+        //       platform than the client where what counts as absolute differs
+        if !client.has_root() || !server.has_root() {
+            return Err("both paths must be absolute");
+        }
+
+        Ok(Self { client, server })
+    }
+}
+
+/// Represents a directory that a connection declares it works within, which paths can refer to
+/// by name as `NAME:relative/path`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceRoot {
+    /// Name of the root, made up of letters, digits, `-`, and `_`
+    pub name: String,
+
+    /// Path to the directory
+    pub path: PathBuf,
+}
+
+impl WorkspaceRoot {
+    /// Returns true if `name` can be used as the name of a root
+    pub fn is_valid_name(name: &str) -> bool {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+}
+
+impl fmt::Display for WorkspaceRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.path.display())
+    }
+}
+
+impl FromStr for WorkspaceRoot {
+    type Err = &'static str;
+
+    /// Parses `NAME=PATH`, where the path must be absolute
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, path) = s.split_once('=').ok_or("expected NAME=PATH")?;
+        let (name, path) = (name.trim().to_string(), PathBuf::from(path.trim()));
+
+        if !Self::is_valid_name(&name) {
+            return Err("name must be made up of letters, digits, '-', and '_'");
+        }
+
+        if !path.has_root() {
+            return Err("path must be absolute");
+        }
+
+        Ok(Self { name, path })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_should_parse_client_and_server_paths() {
+        assert_eq!(
+            "/workspace=/home/me/project"
+                .parse::<PathMapping>()
+                .unwrap(),
+            PathMapping {
+                client: PathBuf::from("/workspace"),
+                server: PathBuf::from("/home/me/project"),
+            }
+        );
+        assert!("/workspace".parse::<PathMapping>().is_err());
+        assert!("workspace=/home/me/project".parse::<PathMapping>().is_err());
+    }
+
+    #[test]
+    fn from_str_should_parse_root_name_and_path() {
+        assert_eq!(
+            "logs=/var/log/app".parse::<WorkspaceRoot>().unwrap(),
+            WorkspaceRoot {
+                name: String::from("logs"),
+                path: PathBuf::from("/var/log/app"),
+            }
+        );
+        assert!("my logs=/var/log/app".parse::<WorkspaceRoot>().is_err());
+        assert!("logs=var/log/app".parse::<WorkspaceRoot>().is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use super::{ProcessId, SessionId};
+use serde::{Deserialize, Serialize};
+
+/// Represents a process running on the remote machine, either spawned by the server or, when
+/// listing all processes, any other process of the system
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct ProcInfo {
+    /// Id of the process if it was spawned by the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ProcessId>,
+
+    /// Session of the process if it was spawned by the server and persists
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionId>,
+
+    /// Id of the process assigned by the system, which is unrelated to ids of spawned processes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+
+    /// Command line of the process
+    pub cmd: String,
+
+    /// Time (in seconds) since the process started
+    pub uptime: u64,
+
+    /// Current status of the process
+    pub status: ProcStatus,
+}
+
+#[cfg(feature = "schemars")]
+impl ProcInfo {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(ProcInfo)
+    }
+}
+
+/// Status of a process running on the remote machine
+#[derive(Copy, Clone, Debug, strum::Display, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+#[strum(serialize_all = "snake_case")]
+pub enum ProcStatus {
+    /// Process is running or ready to run
+    Running,
+
+    /// Process is waiting on something, such as input
+    Sleeping,
+
+    /// Process was stopped, such as by a job control signal
+    Stopped,
+
+    /// Process exited, but has not yet been cleaned up
+    Exited,
+
+    /// Status of the process could not be determined
+    Unknown,
+}
+
+/// Priority and limits on resources applied to a process when it is spawned, before it runs
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(default, deny_unknown_fields)]
+pub struct ProcLimits {
+    /// Niceness of the process (from -20 to 19, where higher values yield to other processes),
+    /// with values below that of the server typically requiring privileges
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+
+    /// CPUs (numbered from 0) that the process is allowed to run on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_affinity: Option<Vec<usize>>,
+
+    /// Maximum bytes of virtual memory that the process can use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_memory: Option<u64>,
+
+    /// Maximum number of files that the process can have open at once
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_open_files: Option<u64>,
+}
+
+impl ProcLimits {
+    /// Returns true if no priority or limits are set
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl ProcLimits {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(ProcLimits)
+    }
+}
+
+/// Position of a chunk of stdout or stderr among all of the output of a process, sent when the
+/// process was spawned asking for timestamped output so that clients can interleave stdout and
+/// stderr in the order they were read
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct OutputStamp {
+    /// Sequence number of the chunk (starting at 0), shared by stdout and stderr of the process
+    pub seq: u64,
+
+    /// Time (in microseconds) since the process was spawned when the chunk was read, which never
+    /// goes backwards as it is taken from a monotonic clock
+    pub timestamp: u64,
+}
+
+#[cfg(feature = "schemars")]
+impl OutputStamp {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(OutputStamp)
+    }
+}
@@ -1,4 +1,4 @@
-use super::FileType;
+use super::{Error, FileType};
 use serde::{Deserialize, Serialize};
 use std::{borrow::Cow, collections::HashSet, path::PathBuf, str::FromStr};
 
@@ -15,7 +15,8 @@ pub struct SearchQuery {
     /// Condition to meet to be considered a match
     pub condition: SearchQueryCondition,
 
-    /// Paths in which to perform the query
+    /// Paths in which to perform the query, where those that cannot be searched are reported
+    /// when the search is done rather than failing the entire query
     pub paths: Vec<PathBuf>,
 
     /// Options to apply to the query
@@ -168,7 +169,7 @@ impl FromStr for SearchQueryCondition {
 }
 
 /// Options associated with a search query
-#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct SearchQueryOptions {
@@ -212,6 +213,68 @@ pub struct SearchQueryOptions {
     /// Amount of results to batch before sending back excluding final submission that will always
     /// include the remaining results even if less than pagination request.
     pub pagination: Option<u64>,
+
+    /// If provided, only paths whose size (in bytes) is at least this are searched.
+    pub min_size: Option<u64>,
+
+    /// If provided, only paths whose size (in bytes) is at most this are searched.
+    pub max_size: Option<u64>,
+
+    /// If provided, only paths last modified at or after this time (in seconds since the Unix
+    /// epoch) are searched.
+    pub modified_after: Option<u64>,
+
+    /// If provided, only paths last modified at or before this time (in seconds since the Unix
+    /// epoch) are searched.
+    pub modified_before: Option<u64>,
+
+    /// Number of lines before each match within a file's contents to include alongside it.
+    pub before_context: u64,
+
+    /// Number of lines after each match within a file's contents to include alongside it.
+    pub after_context: u64,
+
+    /// If provided, files larger than this (in bytes) are skipped rather than having their
+    /// contents searched.
+    pub max_filesize: Option<u64>,
+
+    /// If true, files found while recursing that contain binary data are skipped rather than
+    /// having their contents searched with null bytes treated as line breaks. Files provided
+    /// explicitly as paths are always searched.
+    pub skip_binary: bool,
+
+    /// If true, hidden files and directories are searched rather than ignored.
+    pub follow_hidden: bool,
+
+    /// If true, only the first page of results is sent right away, with each page after it sent
+    /// once requested via `search_next`. The search is paused in between, and is canceled if no
+    /// page is requested for a while. Requires `pagination`.
+    pub on_demand: bool,
+}
+
+impl Default for SearchQueryOptions {
+    fn default() -> Self {
+        Self {
+            allowed_file_types: HashSet::new(),
+            include: None,
+            exclude: None,
+            upward: false,
+            follow_symbolic_links: false,
+            limit: None,
+            max_depth: None,
+            pagination: None,
+            min_size: None,
+            max_size: None,
+            modified_after: None,
+            modified_before: None,
+            before_context: 0,
+            after_context: 0,
+            max_filesize: None,
+            skip_binary: true,
+            follow_hidden: false,
+            on_demand: false,
+        }
+    }
 }
 
 #[cfg(feature = "schemars")]
@@ -221,6 +284,50 @@ impl SearchQueryOptions {
     }
 }
 
+/// Summary of the files that a search skipped rather than searching their contents
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct SearchQuerySkipped {
+    /// Number of files skipped for being larger than the maximum file size
+    pub too_large: u64,
+
+    /// Number of files skipped for containing binary data
+    pub binary: u64,
+}
+
+impl SearchQuerySkipped {
+    /// Returns true if no files were skipped
+    pub fn is_empty(&self) -> bool {
+        self.too_large == 0 && self.binary == 0
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl SearchQuerySkipped {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(SearchQuerySkipped)
+    }
+}
+
+/// Represents one of the paths of a search query that could not be searched
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SearchQueryRootError {
+    /// Path provided to the search query
+    pub path: PathBuf,
+
+    /// Reason that the path could not be searched
+    pub error: Error,
+}
+
+#[cfg(feature = "schemars")]
+impl SearchQueryRootError {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(SearchQueryRootError)
+    }
+}
+
 /// Represents a match for a search query
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -294,6 +401,11 @@ pub struct SearchQueryContentsMatch {
     /// Collection of matches tied to `lines` where each submatch's byte offset is relative to
     /// `lines` and not the overall content
     pub submatches: Vec<SearchQuerySubmatch>,
+
+    /// Lines surrounding `lines` that were requested as context, ordered by line number, where
+    /// those before the match come first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context: Vec<SearchQueryContextLine>,
 }
 
 #[cfg(feature = "schemars")]
@@ -303,6 +415,24 @@ impl SearchQueryContentsMatch {
     }
 }
 
+/// Represents a line near a match on a file's contents that did not match itself
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SearchQueryContextLine {
+    /// Line number of the context (base index 1)
+    pub line_number: u64,
+
+    /// Contents of the line
+    pub line: SearchQueryMatchData,
+}
+
+#[cfg(feature = "schemars")]
+impl SearchQueryContextLine {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(SearchQueryContextLine)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SearchQuerySubmatch {
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use strum::{EnumString, EnumVariantNames};
+
+/// Signal sent to a process running on the remote machine
+///
+/// Windows has no signals, so only [`Signal::Kill`] and [`Signal::Term`] (which both stop the
+/// process) and [`Signal::Break`] (sent as a `CTRL_BREAK` console event) are supported there.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    strum::Display,
+    EnumString,
+    EnumVariantNames,
+    Hash,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+#[strum(serialize_all = "snake_case", ascii_case_insensitive)]
+pub enum Signal {
+    /// Hangup, such as when the terminal of the process is closed (`SIGHUP`)
+    #[strum(to_string = "hup", serialize = "sighup")]
+    Hup,
+
+    /// Interrupt, such as when `Ctrl-C` is pressed (`SIGINT`)
+    #[strum(to_string = "int", serialize = "sigint")]
+    Int,
+
+    /// Quit, such as when `Ctrl-\` is pressed (`SIGQUIT`)
+    #[strum(to_string = "quit", serialize = "sigquit")]
+    Quit,
+
+    /// Kill, which cannot be caught by the process (`SIGKILL`)
+    #[strum(to_string = "kill", serialize = "sigkill")]
+    Kill,
+
+    /// Request for the process to terminate (`SIGTERM`)
+    #[strum(to_string = "term", serialize = "sigterm")]
+    Term,
+
+    /// First user-defined signal (`SIGUSR1`)
+    #[strum(to_string = "usr1", serialize = "sigusr1")]
+    Usr1,
+
+    /// Second user-defined signal (`SIGUSR2`)
+    #[strum(to_string = "usr2", serialize = "sigusr2")]
+    Usr2,
+
+    /// `CTRL_BREAK` console event on Windows, sent as an interrupt (`SIGINT`) elsewhere
+    #[strum(to_string = "break", serialize = "ctrl_break")]
+    Break,
+}
+
+#[cfg(feature = "schemars")]
+impl Signal {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(Signal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn should_parse_names_with_or_without_sig_prefix_ignoring_case() {
+        assert_eq!(Signal::from_str("int").unwrap(), Signal::Int);
+        assert_eq!(Signal::from_str("SIGINT").unwrap(), Signal::Int);
+        assert_eq!(Signal::from_str("Term").unwrap(), Signal::Term);
+        assert_eq!(Signal::from_str("ctrl_break").unwrap(), Signal::Break);
+        assert!(Signal::from_str("sigfoo").is_err());
+    }
+
+    #[test]
+    fn should_display_and_serialize_as_short_name() {
+        assert_eq!(Signal::Usr1.to_string(), "usr1");
+        assert_eq!(serde_json::to_string(&Signal::Hup).unwrap(), "\"hup\"");
+        assert_eq!(
+            serde_json::from_str::<Signal>("\"break\"").unwrap(),
+            Signal::Break
+        );
+    }
+}
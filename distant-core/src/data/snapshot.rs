@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Id associated with a snapshot
+pub type SnapshotId = u32;
+
+/// Represents a snapshot of a file or directory held by the server
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct SnapshotInfo {
+    /// Id used to restore or remove the snapshot
+    pub id: SnapshotId,
+
+    /// Canonicalized path to the file or directory that the snapshot was taken of
+    pub path: PathBuf,
+
+    /// Represents when (in milliseconds since the Unix epoch) the snapshot was taken
+    pub created: u64,
+}
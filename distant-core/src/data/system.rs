@@ -55,3 +55,89 @@ impl Default for SystemInfo {
         }
     }
 }
+
+/// Id for a stream of system stats
+pub type SystemStatsId = u32;
+
+/// Represents the resource usage of a system at some point in time
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SystemStats {
+    /// Percentage (0 to 100) of time that all CPUs combined spent busy since the stats were
+    /// last collected
+    pub cpu_usage: f32,
+
+    /// Number of logical CPUs
+    pub cpu_count: usize,
+
+    /// Total physical memory (in bytes)
+    pub memory_total: u64,
+
+    /// Physical memory (in bytes) in use, excluding memory that can be reclaimed like caches
+    pub memory_used: u64,
+
+    /// Total swap space (in bytes)
+    pub swap_total: u64,
+
+    /// Swap space (in bytes) in use
+    pub swap_used: u64,
+
+    /// Average number of runnable processes over the last 1, 5, and 15 minutes
+    pub load_average: [f32; 3],
+
+    /// Time (in seconds) since the system booted
+    pub uptime: u64,
+
+    /// Resource usage of each process running on the system
+    pub processes: Vec<ProcessStats>,
+}
+
+#[cfg(feature = "schemars")]
+impl SystemStats {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(SystemStats)
+    }
+}
+
+/// Represents the resource usage of a process running on a system
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ProcessStats {
+    /// Id of the process assigned by the system, which is unrelated to ids of spawned processes
+    pub pid: u32,
+
+    /// Name of the program being run by the process
+    pub name: String,
+
+    /// Percentage of time that the process spent running since the stats were last collected,
+    /// where 100 is a single CPU being fully used
+    pub cpu_usage: f32,
+
+    /// Physical memory (in bytes) used by the process
+    pub memory: u64,
+}
+
+/// Represents the resource usage of a process spawned by the server at some point in time
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ProcStats {
+    /// Percentage of time that the process spent running since the stats were last collected,
+    /// where 100 is a single CPU being fully used
+    pub cpu_usage: f32,
+
+    /// Physical memory (in bytes) used by the process
+    pub memory: u64,
+
+    /// Total bytes that the process has caused to be read from storage
+    pub read_bytes: u64,
+
+    /// Total bytes that the process has caused to be written to storage
+    pub write_bytes: u64,
+}
+
+#[cfg(feature = "schemars")]
+impl ProcStats {
+    pub fn root_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(ProcStats)
+    }
+}
@@ -1,3 +1,6 @@
+mod simulated;
+pub use simulated::*;
+
 mod tcp;
 pub use tcp::*;
 
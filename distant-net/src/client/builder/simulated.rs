@@ -0,0 +1,33 @@
+use super::Connector;
+use crate::common::{LinkSimulation, Transport};
+use async_trait::async_trait;
+use std::io;
+
+/// Implementation of [`Connector`] that shapes the traffic of the transport produced by another
+/// connector to a simulated slow link.
+pub struct SimulatedConnector<C> {
+    connector: C,
+    link: LinkSimulation,
+}
+
+impl<C> SimulatedConnector<C> {
+    /// Creates a connector that shapes the transport of `connector` to `link`, where an
+    /// unlimited link leaves the transport as it is.
+    pub fn new(connector: C, link: LinkSimulation) -> Self {
+        Self { connector, link }
+    }
+}
+
+#[async_trait]
+impl<C: Connector + Send> Connector for SimulatedConnector<C> {
+    type Transport = Box<dyn Transport>;
+
+    async fn connect(self) -> io::Result<Self::Transport> {
+        let transport = self.connector.connect().await?;
+        if self.link.is_unlimited() {
+            Ok(Box::new(transport))
+        } else {
+            Ok(Box::new(self.link.shape(transport)))
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use super::Connector;
-use crate::common::TcpTransport;
+use crate::common::{Proxy, SocketOptions, TcpTransport};
 use async_trait::async_trait;
 use std::io;
 use tokio::net::ToSocketAddrs;
@@ -7,11 +7,20 @@ use tokio::net::ToSocketAddrs;
 /// Implementation of [`Connector`] to support connecting via TCP.
 pub struct TcpConnector<T> {
     addr: T,
+    options: SocketOptions,
 }
 
 impl<T> TcpConnector<T> {
     pub fn new(addr: T) -> Self {
-        Self { addr }
+        Self {
+            addr,
+            options: SocketOptions::default(),
+        }
+    }
+
+    /// Applies `options` to the socket when connecting.
+    pub fn socket_options(self, options: SocketOptions) -> Self {
+        Self { options, ..self }
     }
 }
 
@@ -26,6 +35,42 @@ impl<T: ToSocketAddrs + Send> Connector for TcpConnector<T> {
     type Transport = TcpTransport;
 
     async fn connect(self) -> io::Result<Self::Transport> {
-        TcpTransport::connect(self.addr).await
+        TcpTransport::connect_with_options(self.addr, self.options).await
+    }
+}
+
+/// Implementation of [`Connector`] to support connecting via TCP through an HTTP CONNECT or
+/// SOCKS5 proxy.
+pub struct ProxyConnector {
+    proxy: Proxy,
+    host: String,
+    port: u16,
+    options: SocketOptions,
+}
+
+impl ProxyConnector {
+    /// Creates a connector that tunnels through `proxy` to `host` and `port`, where the host is
+    /// resolved by the proxy.
+    pub fn new(proxy: Proxy, host: impl Into<String>, port: u16) -> Self {
+        Self {
+            proxy,
+            host: host.into(),
+            port,
+            options: SocketOptions::default(),
+        }
+    }
+
+    /// Applies `options` to the socket connected to the proxy.
+    pub fn socket_options(self, options: SocketOptions) -> Self {
+        Self { options, ..self }
+    }
+}
+
+#[async_trait]
+impl Connector for ProxyConnector {
+    type Transport = TcpTransport;
+
+    async fn connect(self) -> io::Result<Self::Transport> {
+        TcpTransport::connect_via_proxy(self.proxy, self.host, self.port, self.options).await
     }
 }
@@ -109,16 +109,27 @@ where
     }
 
     /// Sends a request and waits for a response, timing out after duration has passed
+    ///
+    /// If the request has no deadline, the duration is sent as its deadline so that the server
+    /// stops working on it once we are no longer waiting for a response.
     pub async fn send_timeout(
         &mut self,
         req: impl Into<Request<T>>,
         duration: impl Into<Option<Duration>>,
     ) -> io::Result<Response<U>> {
+        let req = req.into();
         match duration.into() {
-            Some(duration) => tokio::time::timeout(duration, self.send(req))
-                .await
-                .map_err(|x| io::Error::new(io::ErrorKind::TimedOut, x))
-                .and_then(convert::identity),
+            Some(duration) => {
+                let req = match req.deadline {
+                    Some(_) => req,
+                    None => req.with_deadline(duration),
+                };
+
+                tokio::time::timeout(duration, self.send(req))
+                    .await
+                    .map_err(|x| io::Error::new(io::ErrorKind::TimedOut, x))
+                    .and_then(convert::identity)
+            }
             None => self.send(req).await,
         }
     }
@@ -292,16 +303,25 @@ impl UntypedChannel {
     }
 
     /// Sends a request and waits for a response, timing out after duration has passed
+    ///
+    /// If the request has no deadline, the duration is sent as its deadline so that the server
+    /// stops working on it once we are no longer waiting for a response.
     pub async fn send_timeout(
         &mut self,
-        req: UntypedRequest<'_>,
+        mut req: UntypedRequest<'_>,
         duration: impl Into<Option<Duration>>,
     ) -> io::Result<UntypedResponse<'static>> {
         match duration.into() {
-            Some(duration) => tokio::time::timeout(duration, self.send(req))
-                .await
-                .map_err(|x| io::Error::new(io::ErrorKind::TimedOut, x))
-                .and_then(convert::identity),
+            Some(duration) => {
+                if req.deadline.is_none() {
+                    req.deadline = Some(duration.as_millis().try_into().unwrap_or(u64::MAX));
+                }
+
+                tokio::time::timeout(duration, self.send(req))
+                    .await
+                    .map_err(|x| io::Error::new(io::ErrorKind::TimedOut, x))
+                    .and_then(convert::identity)
+            }
             None => self.send(req).await,
         }
     }
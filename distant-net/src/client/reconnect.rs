@@ -1,5 +1,6 @@
 use super::Reconnectable;
 use log::*;
+use serde::{Deserialize, Serialize};
 use std::io;
 use std::time::Duration;
 use strum::Display;
@@ -8,7 +9,7 @@ use tokio::task::JoinHandle;
 
 /// Represents a watcher over a [`ConnectionState`].
 #[derive(Clone)]
-pub struct ConnectionWatcher(pub(super) watch::Receiver<ConnectionState>);
+pub struct ConnectionWatcher(pub(crate) watch::Receiver<ConnectionState>);
 
 impl ConnectionWatcher {
     /// Returns next [`ConnectionState`] after a change is detected, or `None` if no more changes
@@ -45,8 +46,9 @@ impl ConnectionWatcher {
 }
 
 /// Represents the state of a connection.
-#[derive(Copy, Clone, Debug, Display, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, Serialize, Deserialize)]
 #[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum ConnectionState {
     /// Connection is not active, but currently going through reconnection process.
     Reconnecting,
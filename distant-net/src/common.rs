@@ -6,6 +6,9 @@ mod listener;
 mod map;
 mod packet;
 mod port;
+mod proxy;
+mod simulation;
+mod socket;
 mod transport;
 pub(crate) mod utils;
 
@@ -17,4 +20,7 @@ pub use listener::*;
 pub use map::*;
 pub use packet::*;
 pub use port::*;
+pub use proxy::*;
+pub use simulation::*;
+pub use socket::*;
 pub use transport::*;
@@ -13,6 +13,7 @@ pub use static_key::*;
 /// Supports authenticating using a variety of methods
 pub struct Verifier {
     methods: HashMap<&'static str, Box<dyn AuthenticationMethod>>,
+    banner: Option<String>,
 }
 
 impl Verifier {
@@ -26,13 +27,17 @@ impl Verifier {
             m.insert(method.id(), method);
         }
 
-        Self { methods: m }
+        Self {
+            methods: m,
+            banner: None,
+        }
     }
 
     /// Creates a verifier with no methods.
     pub fn empty() -> Self {
         Self {
             methods: HashMap::new(),
+            banner: None,
         }
     }
 
@@ -50,6 +55,13 @@ impl Verifier {
         ])
     }
 
+    /// Reports `banner` (such as a message of the day) as information once authentication has
+    /// succeeded, ahead of finishing
+    pub fn with_banner(mut self, banner: Option<String>) -> Self {
+        self.banner = banner;
+        self
+    }
+
     /// Returns an iterator over the ids of the methods supported by the verifier
     pub fn methods(&self) -> impl Iterator<Item = &'static str> + '_ {
         self.methods.keys().copied()
@@ -77,6 +89,10 @@ impl Verifier {
 
                     // Perform the actual authentication
                     if method.authenticate(authenticator).await.is_ok() {
+                        if let Some(text) = self.banner.clone() {
+                            authenticator.info(Info { text }).await?;
+                        }
+
                         authenticator.finished().await?;
                         return Ok(method.id());
                     }
@@ -373,4 +389,41 @@ mod tests {
             x => panic!("Unexpected response: {x:?}"),
         }
     }
+
+    #[test(tokio::test)]
+    async fn verifier_should_send_banner_as_info_before_finished_when_a_method_succeeds() {
+        let (mut t1, mut t2) = FramedTransport::test_pair(100);
+
+        // Queue up a response to the initialization request
+        t2.write_frame_for(&AuthenticationResponse::Initialization(
+            InitializationResponse {
+                methods: vec![SuccessAuthenticationMethod.id().to_string()]
+                    .into_iter()
+                    .collect(),
+            },
+        ))
+        .await
+        .unwrap();
+
+        let methods: Vec<Box<dyn AuthenticationMethod>> =
+            vec![Box::new(SuccessAuthenticationMethod)];
+        Verifier::from(methods)
+            .with_banner(Some(String::from("maintenance at noon")))
+            .verify(&mut t1)
+            .await
+            .unwrap();
+
+        // Clear out the initialization and start method
+        t2.read_frame_as::<Authentication>().await.unwrap().unwrap();
+        t2.read_frame_as::<Authentication>().await.unwrap().unwrap();
+
+        match t2.read_frame_as::<Authentication>().await.unwrap().unwrap() {
+            Authentication::Info(x) => assert_eq!(x.text, "maintenance at noon"),
+            x => panic!("Unexpected response: {x:?}"),
+        }
+        match t2.read_frame_as::<Authentication>().await.unwrap().unwrap() {
+            Authentication::Finished => (),
+            x => panic!("Unexpected response: {x:?}"),
+        }
+    }
 }
@@ -38,6 +38,10 @@ pub enum Connection<T> {
         /// Used to send the backup into storage when the connection is dropped
         tx: oneshot::Sender<Backup>,
 
+        /// Name of the authentication method used to verify the connection, or none if it was
+        /// restored from a previous connection
+        authentication_method: Option<&'static str>,
+
         /// Underlying transport used to communicate
         transport: FramedTransport<T>,
     },
@@ -276,7 +280,7 @@ where
 
         // Based on the connection type, we either try to find and validate an existing connection
         // or we perform normal verification
-        let id = match connection_type {
+        let (id, authentication_method) = match connection_type {
             ConnectType::Connect => {
                 // Communicate the connection id
                 debug!("[Conn {id}] Telling other side to change connection id");
@@ -284,7 +288,7 @@ where
 
                 // Perform authentication to ensure the connection is valid
                 debug!("[Conn {id}] Verifying connection");
-                verifier.verify(&mut transport).await?;
+                let method = verifier.verify(&mut transport).await?;
 
                 // Derive an OTP for reauthentication
                 debug!("[Conn {id}] Deriving future OTP for reauthentication");
@@ -294,7 +298,7 @@ where
                 info!("[Conn {id}] Connect completed successfully!");
                 keychain.insert(id.to_string(), reauth_otp, rx).await;
 
-                id
+                (id, Some(method))
             }
             ConnectType::Reconnect { id: other_id, otp } => {
                 let reauth_otp = HeapSecretKey::from(otp);
@@ -353,7 +357,7 @@ where
                         info!("[Conn {id}] Reconnect restoration completed successfully!");
                         keychain.insert(id.to_string(), new_reauth_otp, rx).await;
 
-                        id
+                        (id, None)
                     }
                     KeychainResult::InvalidPassword => {
                         return Err(io::Error::new(
@@ -371,7 +375,12 @@ where
             }
         };
 
-        Ok(Self::Server { id, tx, transport })
+        Ok(Self::Server {
+            id,
+            tx,
+            authentication_method,
+            transport,
+        })
     }
 }
 
@@ -397,6 +406,7 @@ impl Connection<InmemoryTransport> {
         let server = Connection::Server {
             id,
             tx: oneshot::channel().0,
+            authentication_method: None,
             transport: t2,
         };
 
@@ -412,6 +422,18 @@ impl<T> Connection<T> {
             Self::Server { id, .. } => *id,
         }
     }
+
+    /// Returns the name of the authentication method that verified the connection, which is only
+    /// known server-side for connections that were not restored from a previous connection.
+    pub fn authentication_method(&self) -> Option<&'static str> {
+        match self {
+            Self::Client { .. } => None,
+            Self::Server {
+                authentication_method,
+                ..
+            } => *authentication_method,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1258,6 +1280,7 @@ mod tests {
         let mut connection = Connection::Server {
             id: rand::random(),
             tx: oneshot::channel().0,
+            authentication_method: None,
             transport: FramedTransport::pair(100).0,
         };
 
@@ -1,6 +1,12 @@
 use async_trait::async_trait;
 use std::io;
 
+#[cfg(unix)]
+mod activation;
+
+#[cfg(unix)]
+pub use activation::*;
+
 mod mapped;
 pub use mapped::*;
 
@@ -0,0 +1,82 @@
+use log::*;
+use socket2::Socket;
+use std::{
+    env, io,
+    os::unix::io::{FromRawFd, IntoRawFd, OwnedFd, RawFd},
+};
+
+/// First file descriptor passed to a socket-activated process, as described by `sd_listen_fds(3)`
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Takes ownership of the sockets passed to this process through socket activation, such as by a
+/// systemd `.socket` unit, returning them in the order that they were configured
+///
+/// Sockets are only taken if `LISTEN_PID` matches this process, and the `LISTEN_PID`,
+/// `LISTEN_FDS`, and `LISTEN_FDNAMES` environment variables are removed so that they are neither
+/// inherited by children nor taken twice. Returns an empty list if the process was not activated.
+///
+/// As changing the environment is not thread-safe, this should be called before any other threads
+/// (such as those of a runtime) are started.
+pub fn take_activated_sockets() -> io::Result<Vec<OwnedFd>> {
+    let count = listen_fds_count(
+        env::var("LISTEN_PID").ok().as_deref(),
+        env::var("LISTEN_FDS").ok().as_deref(),
+        std::process::id(),
+    );
+
+    env::remove_var("LISTEN_PID");
+    env::remove_var("LISTEN_FDS");
+    env::remove_var("LISTEN_FDNAMES");
+
+    let mut sockets = Vec::with_capacity(count);
+    for fd in (LISTEN_FDS_START..).take(count) {
+        // SAFETY: the service manager hands ownership of these descriptors to us, and the
+        //         environment has been cleared so that they cannot be taken again
+        let socket = unsafe { Socket::from_raw_fd(fd) };
+
+        // Descriptors are passed without close-on-exec so that they survive the exec of this
+        // process, but they should not leak into processes that we spawn
+        socket.set_cloexec(true)?;
+
+        // SAFETY: the descriptor was just released by the socket that owned it
+        sockets.push(unsafe { OwnedFd::from_raw_fd(socket.into_raw_fd()) });
+    }
+
+    if !sockets.is_empty() {
+        debug!(
+            "Took {} socket(s) passed by socket activation",
+            sockets.len()
+        );
+    }
+
+    Ok(sockets)
+}
+
+/// Returns the number of sockets passed through socket activation given the values of the
+/// `LISTEN_PID` and `LISTEN_FDS` environment variables, or zero if they are not meant for `pid`
+fn listen_fds_count(listen_pid: Option<&str>, listen_fds: Option<&str>, pid: u32) -> usize {
+    match listen_pid.and_then(|x| x.trim().parse::<u32>().ok()) {
+        Some(listen_pid) if listen_pid == pid => listen_fds
+            .and_then(|x| x.trim().parse::<usize>().ok())
+            .unwrap_or_default(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listen_fds_count_should_return_count_if_pid_matches() {
+        assert_eq!(listen_fds_count(Some("123"), Some("2"), 123), 2);
+    }
+
+    #[test]
+    fn listen_fds_count_should_return_zero_if_pid_does_not_match_or_is_missing() {
+        assert_eq!(listen_fds_count(Some("456"), Some("2"), 123), 0);
+        assert_eq!(listen_fds_count(None, Some("2"), 123), 0);
+        assert_eq!(listen_fds_count(Some("123"), None, 123), 0);
+        assert_eq!(listen_fds_count(Some("123"), Some("abc"), 123), 0);
+    }
+}
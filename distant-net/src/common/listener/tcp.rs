@@ -1,13 +1,17 @@
 use super::Listener;
-use crate::common::{PortRange, TcpTransport};
+use crate::common::{PortRange, SocketOptions, TcpTransport};
 use async_trait::async_trait;
-use std::{fmt, io, net::IpAddr};
+use std::{
+    fmt, io,
+    net::{IpAddr, SocketAddr},
+};
 use tokio::net::TcpListener as TokioTcpListener;
 
 /// Represents a [`Listener`] for incoming connections over TCP
 pub struct TcpListener {
     addr: IpAddr,
     port: u16,
+    options: SocketOptions,
     inner: TokioTcpListener,
 }
 
@@ -15,19 +19,67 @@ impl TcpListener {
     /// Creates a new listener by binding to the specified IP address and port
     /// in the given port range
     pub async fn bind(addr: IpAddr, port: impl Into<PortRange>) -> io::Result<Self> {
-        let listener =
-            TokioTcpListener::bind(port.into().make_socket_addrs(addr).as_slice()).await?;
+        Self::bind_with_options(addr, port, SocketOptions::default()).await
+    }
+
+    /// Creates a new listener by binding to the specified IP address and port
+    /// in the given port range, applying `options` to the listening socket and
+    /// every accepted connection
+    pub async fn bind_with_options(
+        addr: IpAddr,
+        port: impl Into<PortRange>,
+        options: SocketOptions,
+    ) -> io::Result<Self> {
+        let mut err = None;
+        for socket_addr in port.into().make_socket_addrs(addr) {
+            match Self::bind_socket(socket_addr, options) {
+                Ok(listener) => {
+                    // Get the port that we bound to
+                    let port = listener.local_addr()?.port();
 
-        // Get the port that we bound to
-        let port = listener.local_addr()?.port();
+                    return Ok(Self {
+                        addr,
+                        port,
+                        options,
+                        inner: listener,
+                    });
+                }
+                Err(x) => err = Some(x),
+            }
+        }
+
+        Err(err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )
+        }))
+    }
 
+    /// Creates a new listener from one that is already bound and listening, such as a socket
+    /// passed through socket activation, applying `options` to every accepted connection
+    pub fn from_std(listener: std::net::TcpListener, options: SocketOptions) -> io::Result<Self> {
+        let addr = listener.local_addr()?;
+        listener.set_nonblocking(true)?;
         Ok(Self {
-            addr,
-            port,
-            inner: listener,
+            addr: addr.ip(),
+            port: addr.port(),
+            options,
+            inner: TokioTcpListener::from_std(listener)?,
         })
     }
 
+    fn bind_socket(addr: SocketAddr, options: SocketOptions) -> io::Result<TokioTcpListener> {
+        let socket = options.new_socket(addr)?;
+
+        // Mirror the behavior of binding a std listener, which reuses addresses on unix
+        #[cfg(unix)]
+        socket.set_reuseaddr(true)?;
+
+        socket.bind(addr)?;
+        socket.listen(options.backlog_or_default())
+    }
+
     /// Returns the IP address that the listener is bound to
     pub fn ip_addr(&self) -> IpAddr {
         self.addr
@@ -54,9 +106,12 @@ impl Listener for TcpListener {
 
     async fn accept(&mut self) -> io::Result<Self::Output> {
         let (stream, peer_addr) = TokioTcpListener::accept(&self.inner).await?;
+        self.options.apply_to_stream(&stream)?;
         Ok(TcpTransport {
             addr: peer_addr.ip(),
             port: peer_addr.port(),
+            options: self.options,
+            tunnel: None,
             inner: stream,
         })
     }
@@ -90,6 +145,44 @@ mod tests {
         ));
     }
 
+    #[test(tokio::test)]
+    async fn should_apply_socket_options_to_accepted_connections() {
+        let addr = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        let port = 0; // Ephemeral port
+        let options = SocketOptions {
+            nodelay: true,
+            keepalive: Some(std::time::Duration::from_secs(30)),
+            backlog: Some(8),
+            ..Default::default()
+        };
+
+        let mut listener = TcpListener::bind_with_options(addr, port, options)
+            .await
+            .expect("Failed to bind with options");
+        let address = SocketAddr::from((addr, listener.port()));
+
+        let client = TcpTransport::connect_with_options(&address, options)
+            .await
+            .expect("Failed to connect with options");
+        let server = listener
+            .accept()
+            .await
+            .expect("Failed to accept connection");
+
+        assert!(
+            client.inner.nodelay().unwrap(),
+            "Client missing TCP_NODELAY"
+        );
+        assert!(
+            server.inner.nodelay().unwrap(),
+            "Server missing TCP_NODELAY"
+        );
+        assert!(
+            socket2::SockRef::from(&server.inner).keepalive().unwrap(),
+            "Server missing keepalive"
+        );
+    }
+
     #[test(tokio::test)]
     async fn should_be_able_to_receive_connections_and_read_and_write_data_with_them() {
         let (tx, rx) = oneshot::channel();
@@ -1,6 +1,9 @@
 use super::Listener;
+#[cfg(target_os = "linux")]
+use crate::common::transport::abstract_name;
 use crate::common::UnixSocketTransport;
 use async_trait::async_trait;
+use log::*;
 use std::{
     fmt, io,
     os::unix::fs::PermissionsExt,
@@ -12,6 +15,9 @@ use tokio::net::{UnixListener, UnixStream};
 pub struct UnixSocketListener {
     path: PathBuf,
     inner: tokio::net::UnixListener,
+
+    /// If provided, connections are only accepted from processes running as this user
+    peer_uid: Option<u32>,
 }
 
 impl UnixSocketListener {
@@ -24,7 +30,24 @@ impl UnixSocketListener {
 
     /// Creates a new listener by binding to the specified path, failing if the path already
     /// exists. Sets the unix socket file permissions to `mode`.
+    ///
+    /// On Linux, a path of the form `@name` binds to the socket named `name` within the abstract
+    /// namespace rather than creating a file. Abstract sockets have no file permissions, meaning
+    /// that `mode` is ignored and any process in the same network namespace can connect.
     pub async fn bind_with_permissions(path: impl AsRef<Path>, mode: u32) -> io::Result<Self> {
+        #[cfg(target_os = "linux")]
+        if let Some(name) = abstract_name(path.as_ref()) {
+            use std::os::{linux::net::SocketAddrExt, unix::net};
+            let addr = net::SocketAddr::from_abstract_name(name)?;
+            let listener = net::UnixListener::bind_addr(&addr)?;
+            listener.set_nonblocking(true)?;
+            return Ok(Self {
+                path: path.as_ref().to_path_buf(),
+                inner: UnixListener::from_std(listener)?,
+                peer_uid: None,
+            });
+        }
+
         // Attempt to bind to the path, and if we fail, we see if we can connect
         // to the path -- if not, we can try to delete the path and start again
         let listener = match UnixListener::bind(path.as_ref()) {
@@ -54,9 +77,62 @@ impl UnixSocketListener {
         Ok(Self {
             path: path.as_ref().to_path_buf(),
             inner: listener,
+            peer_uid: None,
         })
     }
 
+    /// Creates a new listener from one that is already bound and listening, such as a socket
+    /// passed through socket activation
+    pub fn from_std(listener: std::os::unix::net::UnixListener) -> io::Result<Self> {
+        let addr = listener.local_addr()?;
+
+        #[cfg(target_os = "linux")]
+        let name = {
+            use std::os::linux::net::SocketAddrExt;
+            addr.as_abstract_name().map(|name| {
+                let mut path = std::ffi::OsString::from("@");
+                path.push(<std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(name));
+                PathBuf::from(path)
+            })
+        };
+
+        #[cfg(not(target_os = "linux"))]
+        let name = None;
+
+        let path = addr
+            .as_pathname()
+            .map(Path::to_path_buf)
+            .or(name)
+            .unwrap_or_default();
+
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            path,
+            inner: UnixListener::from_std(listener)?,
+            peer_uid: None,
+        })
+    }
+
+    /// Only accepts connections from processes running as the same user as this process, closing
+    /// any others as they arrive. This stands in for the file permissions that abstract sockets
+    /// lack.
+    pub fn only_from_current_user(self) -> Self {
+        // SAFETY: geteuid is always successful and has no side effects
+        self.only_from_uid(unsafe { libc::geteuid() })
+    }
+
+    /// Only accepts connections from processes running as the user with `uid`, closing any others
+    /// as they arrive
+    pub fn only_from_uid(mut self, uid: u32) -> Self {
+        self.peer_uid = Some(uid);
+        self
+    }
+
+    /// Returns true if this is a socket within the abstract namespace
+    pub fn is_abstract(&self) -> bool {
+        cfg!(target_os = "linux") && self.path.to_string_lossy().starts_with('@')
+    }
+
     /// Returns the path to the socket
     pub fn path(&self) -> &Path {
         &self.path
@@ -84,11 +160,35 @@ impl Listener for UnixSocketListener {
         // NOTE: Address provided is unnamed, or at least the `as_pathname()` method is
         //       returning none, so we use our listener's path, which is the same as
         //       what is being connected, anyway
-        let (stream, _) = tokio::net::UnixListener::accept(&self.inner).await?;
-        Ok(UnixSocketTransport {
-            path: self.path.to_path_buf(),
-            inner: stream,
-        })
+        loop {
+            let (stream, _) = tokio::net::UnixListener::accept(&self.inner).await?;
+
+            if let Some(uid) = self.peer_uid {
+                match stream.peer_cred() {
+                    Ok(cred) if cred.uid() == uid => (),
+                    Ok(cred) => {
+                        warn!(
+                            "Refusing connection to {:?} from uid {}",
+                            self.path,
+                            cred.uid()
+                        );
+                        continue;
+                    }
+                    Err(x) => {
+                        warn!(
+                            "Refusing connection to {:?} as its peer is unknown: {x}",
+                            self.path
+                        );
+                        continue;
+                    }
+                }
+            }
+
+            return Ok(UnixSocketTransport {
+                path: self.path.to_path_buf(),
+                inner: stream,
+            });
+        }
     }
 }
 
@@ -208,4 +308,97 @@ mod tests {
         // Verify that the task has completed by waiting on it
         let _ = task.await.expect("Listener task failed unexpectedly");
     }
+
+    #[test(tokio::test)]
+    async fn should_support_creating_from_std_listener() {
+        let path = NamedTempFile::new()
+            .expect("Failed to create socket file")
+            .path()
+            .to_path_buf();
+
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let mut listener = UnixSocketListener::from_std(listener).unwrap();
+        assert_eq!(listener.path(), path);
+
+        let conn = UnixSocketTransport::connect(&path)
+            .await
+            .expect("Conn failed to connect");
+        conn.write_all(b"hello").await.unwrap();
+
+        let server_conn = listener.accept().await.unwrap();
+        let mut buf = [0u8; 5];
+        server_conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+    }
+
+    #[test(tokio::test)]
+    async fn should_only_accept_connections_from_the_allowed_user() {
+        let path = NamedTempFile::new()
+            .expect("Failed to create socket file")
+            .path()
+            .to_path_buf();
+
+        // SAFETY: geteuid is always successful and has no side effects
+        let uid = unsafe { libc::geteuid() };
+        let mut listener = UnixSocketListener::bind(&path)
+            .await
+            .unwrap()
+            .only_from_uid(uid.wrapping_add(1));
+
+        let conn = UnixSocketTransport::connect(&path)
+            .await
+            .expect("Conn failed to connect");
+        conn.write_all(b"hello").await.unwrap();
+        tokio::time::timeout(std::time::Duration::from_millis(100), listener.accept())
+            .await
+            .expect_err("Unexpectedly accepted connection from another user");
+
+        // The refused connection is closed rather than left waiting
+        let mut buf = Vec::new();
+        let _ = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            conn.read_to_end(&mut buf),
+        )
+        .await
+        .expect("Refused connection was not closed");
+        assert!(buf.is_empty());
+
+        let mut listener = listener.only_from_current_user();
+        let conn = UnixSocketTransport::connect(&path)
+            .await
+            .expect("Conn failed to connect");
+        conn.write_all(b"hello").await.unwrap();
+
+        let server_conn = listener.accept().await.unwrap();
+        let mut buf = [0u8; 5];
+        server_conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test(tokio::test)]
+    async fn should_support_binding_and_connecting_within_abstract_namespace() {
+        let path = PathBuf::from(format!("@distant-test-{}", rand::random::<u64>()));
+        let mut listener = UnixSocketListener::bind(&path)
+            .await
+            .expect("Failed to bind abstract socket");
+
+        // No file is created for abstract sockets
+        assert!(!path.exists());
+
+        let conn = UnixSocketTransport::connect(&path)
+            .await
+            .expect("Conn failed to connect");
+        conn.write_all(b"hello").await.unwrap();
+
+        let server_conn = listener.accept().await.unwrap();
+        let mut buf = [0u8; 5];
+        server_conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+
+        // Binding the same name again fails as it is in use
+        UnixSocketListener::bind(&path)
+            .await
+            .expect_err("Unexpectedly succeeded in binding to same abstract socket");
+    }
 }
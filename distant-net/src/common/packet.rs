@@ -44,6 +44,50 @@ fn write_str_msg_pack(s: &str, buf: &mut Vec<u8>) {
     buf.extend_from_slice(s.as_bytes());
 }
 
+/// Writes the given u64 to the end of `buf` as its most compact msgpack representation.
+fn write_u64_msg_pack(n: u64, buf: &mut Vec<u8>) {
+    if n < 128 {
+        buf.push(n as u8);
+    } else if n < 2u64.pow(8) {
+        buf.push(0xcc);
+        buf.push(n as u8);
+    } else if n < 2u64.pow(16) {
+        buf.push(0xcd);
+        buf.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n < 2u64.pow(32) {
+        buf.push(0xce);
+        buf.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        buf.push(0xcf);
+        buf.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+/// Parse msgpack unsigned integer, returning remaining bytes and integer on success, or error on
+/// failure.
+fn parse_msg_pack_u64(input: &[u8]) -> Result<(&[u8], u64), MsgPackStrParseError> {
+    // * positive fixint using 0x00 - 0x7f for the integer itself
+    // * uint 8 (0xcc), uint 16 (0xcd), uint 32 (0xce), and uint 64 (0xcf) using the next 1, 2,
+    //   4, or 8 bytes for the integer
+    let len = match input.first() {
+        Some(&n) if n <= 0x7f => return Ok((&input[1..], n.into())),
+        Some(0xcc) => 1,
+        Some(0xcd) => 2,
+        Some(0xce) => 4,
+        Some(0xcf) => 8,
+        _ => return Err(MsgPackStrParseError::InvalidFormat),
+    };
+
+    if input.len() <= len {
+        return Err(MsgPackStrParseError::InvalidFormat);
+    }
+
+    let n = input[1..=len]
+        .iter()
+        .fold(0u64, |n, b| (n << 8) | u64::from(*b));
+    Ok((&input[len + 1..], n))
+}
+
 /// Parse msgpack str, returning remaining bytes and str on success, or error on failure.
 fn parse_msg_pack_str(input: &[u8]) -> Result<(&[u8], &str), MsgPackStrParseError> {
     let ilen = input.len();
@@ -427,6 +471,68 @@ mod tests {
         }
     }
 
+    mod write_u64_msg_pack {
+        use super::*;
+
+        #[test]
+        fn should_use_the_most_compact_representation() {
+            let write = |n| {
+                let mut buf = Vec::new();
+                write_u64_msg_pack(n, &mut buf);
+                buf
+            };
+
+            assert_eq!(write(0), &[0x00]);
+            assert_eq!(write(127), &[0x7f]);
+            assert_eq!(write(128), &[0xcc, 128]);
+            assert_eq!(write(256), &[0xcd, 1, 0]);
+            assert_eq!(write(65536), &[0xce, 0, 1, 0, 0]);
+            assert_eq!(
+                write(u64::MAX),
+                &[0xcf, 255, 255, 255, 255, 255, 255, 255, 255]
+            );
+
+            // Matches what serialization produces
+            for n in [0, 127, 128, 256, 65536, u64::MAX] {
+                assert_eq!(write(n), rmp_serde::encode::to_vec(&n).unwrap());
+            }
+        }
+    }
+
+    mod parse_msg_pack_u64 {
+        use super::*;
+
+        #[test]
+        fn should_be_able_to_parse_each_representation() {
+            assert_eq!(parse_msg_pack_u64(&[0x05, 0xc3]).unwrap(), (&[0xc3][..], 5));
+            assert_eq!(parse_msg_pack_u64(&[0xcc, 200]).unwrap().1, 200);
+            assert_eq!(parse_msg_pack_u64(&[0xcd, 1, 0]).unwrap().1, 256);
+            assert_eq!(parse_msg_pack_u64(&[0xce, 0, 1, 0, 0]).unwrap().1, 65536);
+            assert_eq!(
+                parse_msg_pack_u64(&[0xcf, 255, 255, 255, 255, 255, 255, 255, 255])
+                    .unwrap()
+                    .1,
+                u64::MAX
+            );
+        }
+
+        #[test]
+        fn should_fail_parsing_other_types_or_missing_bytes() {
+            assert_eq!(
+                parse_msg_pack_u64(&[]),
+                Err(MsgPackStrParseError::InvalidFormat)
+            );
+            assert_eq!(
+                parse_msg_pack_u64(&[0xc3]), // Boolean (true)
+                Err(MsgPackStrParseError::InvalidFormat)
+            );
+            assert_eq!(
+                parse_msg_pack_u64(&[0xcd, 1]),
+                Err(MsgPackStrParseError::InvalidFormat)
+            );
+        }
+    }
+
     mod parse_msg_pack_str {
         use super::*;
 
@@ -1,8 +1,8 @@
-use super::{parse_msg_pack_str, write_str_msg_pack, Id};
+use super::{parse_msg_pack_str, parse_msg_pack_u64, write_str_msg_pack, write_u64_msg_pack, Id};
 use crate::common::utils;
 use derive_more::{Display, Error};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::{borrow::Cow, io, str};
+use std::{borrow::Cow, io, str, time::Duration};
 
 /// Represents a request to send
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -11,18 +11,76 @@ pub struct Request<T> {
     /// Unique id associated with the request
     pub id: Id,
 
+    /// Time (in milliseconds) that the request has to complete once received, after which the
+    /// server is expected to abort any work for it
+    ///
+    /// This is relative rather than a point in time so that it does not depend on the clocks of
+    /// the client and server agreeing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u64>,
+
+    /// Key unique to the operation performed by the request, where a server that receives
+    /// another request with the same key from the same connection responds with the result of the
+    /// first rather than performing the operation again
+    ///
+    /// This lets a client safely retry requests that change things, such as writing a file,
+    /// after failing to learn whether they were received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+
+    /// Id picked by whoever started the work behind the request, which is carried unchanged
+    /// through the manager to the server and included in their logs and in any error sent back,
+    /// so that a single operation can be traced through every process that handled it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+
     /// Payload associated with the request
     pub payload: T,
 }
 
 impl<T> Request<T> {
-    /// Creates a new request with a random, unique id
+    /// Creates a new request with a random, unique id and no deadline, idempotency key, or
+    /// correlation id
     pub fn new(payload: T) -> Self {
         Self {
             id: rand::random::<u64>().to_string(),
+            deadline: None,
+            idempotency_key: None,
+            correlation_id: None,
             payload,
         }
     }
+
+    /// Sets the time that the request has to complete once received by the server
+    pub fn with_deadline(self, deadline: Duration) -> Self {
+        Self {
+            deadline: Some(deadline.as_millis().try_into().unwrap_or(u64::MAX)),
+            ..self
+        }
+    }
+
+    /// Returns the time that the request has to complete once received by the server, if it has
+    /// a deadline
+    pub fn deadline(&self) -> Option<Duration> {
+        self.deadline.map(Duration::from_millis)
+    }
+
+    /// Sets the key that keeps the server from performing the request more than once when it is
+    /// retried
+    pub fn with_idempotency_key(self, key: impl Into<String>) -> Self {
+        Self {
+            idempotency_key: Some(key.into()),
+            ..self
+        }
+    }
+
+    /// Sets the id used to trace the request through the logs of every process that handles it
+    pub fn with_correlation_id(self, id: impl Into<String>) -> Self {
+        Self {
+            correlation_id: Some(id.into()),
+            ..self
+        }
+    }
 }
 
 impl<T> Request<T>
@@ -43,6 +101,9 @@ where
     pub fn to_untyped_request(&self) -> io::Result<UntypedRequest> {
         Ok(UntypedRequest {
             id: Cow::Borrowed(&self.id),
+            deadline: self.deadline,
+            idempotency_key: self.idempotency_key.as_deref().map(Cow::Borrowed),
+            correlation_id: self.correlation_id.as_deref().map(Cow::Borrowed),
             payload: Cow::Owned(self.to_payload_vec()?),
         })
     }
@@ -79,6 +140,15 @@ pub enum UntypedRequestParseError {
 
     /// When the id is not a valid UTF-8 string
     InvalidId,
+
+    /// When the deadline is not an unsigned integer
+    InvalidDeadline,
+
+    /// When the idempotency key is not a valid UTF-8 string
+    InvalidIdempotencyKey,
+
+    /// When the correlation id is not a valid UTF-8 string
+    InvalidCorrelationId,
 }
 
 /// Represents a request to send whose payload is bytes instead of a specific type
@@ -87,6 +157,18 @@ pub struct UntypedRequest<'a> {
     /// Unique id associated with the request
     pub id: Cow<'a, str>,
 
+    /// Time (in milliseconds) that the request has to complete once received
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u64>,
+
+    /// Key keeping the request from being performed more than once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<Cow<'a, str>>,
+
+    /// Id tracing the request through the processes that handle it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Cow<'a, str>>,
+
     /// Payload associated with the request as bytes
     pub payload: Cow<'a, [u8]>,
 }
@@ -96,6 +178,9 @@ impl<'a> UntypedRequest<'a> {
     pub fn to_typed_request<T: DeserializeOwned>(&self) -> io::Result<Request<T>> {
         Ok(Request {
             id: self.id.to_string(),
+            deadline: self.deadline,
+            idempotency_key: self.idempotency_key.as_deref().map(ToString::to_string),
+            correlation_id: self.correlation_id.as_deref().map(ToString::to_string),
             payload: utils::deserialize_from_slice(&self.payload)?,
         })
     }
@@ -107,6 +192,9 @@ impl<'a> UntypedRequest<'a> {
                 Cow::Borrowed(x) => Cow::Borrowed(x),
                 Cow::Owned(x) => Cow::Borrowed(x.as_str()),
             },
+            deadline: self.deadline,
+            idempotency_key: self.idempotency_key.as_deref().map(Cow::Borrowed),
+            correlation_id: self.correlation_id.as_deref().map(Cow::Borrowed),
             payload: match &self.payload {
                 Cow::Borrowed(x) => Cow::Borrowed(x),
                 Cow::Owned(x) => Cow::Borrowed(x.as_slice()),
@@ -121,6 +209,9 @@ impl<'a> UntypedRequest<'a> {
                 Cow::Borrowed(x) => Cow::Owned(x.to_string()),
                 Cow::Owned(x) => Cow::Owned(x),
             },
+            deadline: self.deadline,
+            idempotency_key: self.idempotency_key.map(|x| Cow::Owned(x.into_owned())),
+            correlation_id: self.correlation_id.map(|x| Cow::Owned(x.into_owned())),
             payload: match self.payload {
                 Cow::Borrowed(x) => Cow::Owned(x.to_vec()),
                 Cow::Owned(x) => Cow::Owned(x),
@@ -135,11 +226,30 @@ impl<'a> UntypedRequest<'a> {
 
     /// Allocates a new collection of bytes representing the request.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes = vec![0x82];
+        let len = 2
+            + self.deadline.is_some() as u8
+            + self.idempotency_key.is_some() as u8
+            + self.correlation_id.is_some() as u8;
+        let mut bytes = vec![0x80 | len];
 
         write_str_msg_pack("id", &mut bytes);
         write_str_msg_pack(&self.id, &mut bytes);
 
+        if let Some(deadline) = self.deadline {
+            write_str_msg_pack("deadline", &mut bytes);
+            write_u64_msg_pack(deadline, &mut bytes);
+        }
+
+        if let Some(key) = self.idempotency_key.as_deref() {
+            write_str_msg_pack("idempotency_key", &mut bytes);
+            write_str_msg_pack(key, &mut bytes);
+        }
+
+        if let Some(id) = self.correlation_id.as_deref() {
+            write_str_msg_pack("correlation_id", &mut bytes);
+            write_str_msg_pack(id, &mut bytes);
+        }
+
         write_str_msg_pack("payload", &mut bytes);
         bytes.extend_from_slice(&self.payload);
 
@@ -159,11 +269,13 @@ impl<'a> UntypedRequest<'a> {
 
         // MsgPack marks a fixmap using 0x80 - 0x8f to indicate the size (up to 15 elements).
         //
-        // In the case of the request, there are only two elements: id and payload. So the first
-        // byte should ALWAYS be 0x82 (130).
-        if input[0] != 0x82 {
-            return Err(UntypedRequestParseError::WrongType);
-        }
+        // In the case of the request, there are two required elements: id and payload, with up
+        // to three optional elements between them: deadline, idempotency_key, and correlation_id.
+        // So the first byte should ALWAYS be 0x82 (130) through 0x85 (133).
+        let optional_fields = match input[0] {
+            0x82..=0x85 => input[0] - 0x82,
+            _ => return Err(UntypedRequestParseError::WrongType),
+        };
 
         // Skip the first byte representing the fixmap
         let input = &input[1..];
@@ -179,7 +291,42 @@ impl<'a> UntypedRequest<'a> {
         let (input, id) =
             parse_msg_pack_str(input).map_err(|_| UntypedRequestParseError::InvalidId)?;
 
-        // Validate that second field is payload
+        // Get the optional fields, which appear in the order that they are declared
+        let mut input = input;
+        let mut deadline = None;
+        let mut idempotency_key = None;
+        let mut correlation_id = None;
+        for _ in 0..optional_fields {
+            let (rest, key) =
+                parse_msg_pack_str(input).map_err(|_| UntypedRequestParseError::WrongType)?;
+            input = match key {
+                "deadline"
+                    if deadline.is_none()
+                        && idempotency_key.is_none()
+                        && correlation_id.is_none() =>
+                {
+                    let (rest, x) = parse_msg_pack_u64(rest)
+                        .map_err(|_| UntypedRequestParseError::InvalidDeadline)?;
+                    deadline = Some(x);
+                    rest
+                }
+                "idempotency_key" if idempotency_key.is_none() && correlation_id.is_none() => {
+                    let (rest, x) = parse_msg_pack_str(rest)
+                        .map_err(|_| UntypedRequestParseError::InvalidIdempotencyKey)?;
+                    idempotency_key = Some(Cow::Borrowed(x));
+                    rest
+                }
+                "correlation_id" if correlation_id.is_none() => {
+                    let (rest, x) = parse_msg_pack_str(rest)
+                        .map_err(|_| UntypedRequestParseError::InvalidCorrelationId)?;
+                    correlation_id = Some(Cow::Borrowed(x));
+                    rest
+                }
+                _ => return Err(UntypedRequestParseError::WrongType),
+            };
+        }
+
+        // Validate that last field is payload
         let (input, payload_key) =
             parse_msg_pack_str(input).map_err(|_| UntypedRequestParseError::WrongType)?;
         if payload_key != "payload" {
@@ -189,7 +336,13 @@ impl<'a> UntypedRequest<'a> {
         let id = Cow::Borrowed(id);
         let payload = Cow::Borrowed(input);
 
-        Ok(Self { id, payload })
+        Ok(Self {
+            id,
+            deadline,
+            idempotency_key,
+            correlation_id,
+            payload,
+        })
     }
 }
 
@@ -204,6 +357,9 @@ mod tests {
     // fixstr of 2 bytes with str "id"
     const ID_FIELD_BYTES: &[u8] = &[0xa2, 0x69, 0x64];
 
+    // fixstr of 8 bytes with str "deadline"
+    const DEADLINE_FIELD_BYTES: &[u8] = &[0xa8, 0x64, 0x65, 0x61, 0x64, 0x6c, 0x69, 0x6e, 0x65];
+
     // fixstr of 7 bytes with str "payload"
     const PAYLOAD_FIELD_BYTES: &[u8] = &[0xa7, 0x70, 0x61, 0x79, 0x6c, 0x6f, 0x61, 0x64];
 
@@ -214,6 +370,9 @@ mod tests {
     fn untyped_request_should_support_converting_to_bytes() {
         let bytes = Request {
             id: "some id".to_string(),
+            deadline: None,
+            idempotency_key: None,
+            correlation_id: None,
             payload: true,
         }
         .to_vec()
@@ -227,6 +386,9 @@ mod tests {
     fn untyped_request_should_support_parsing_from_request_bytes_with_valid_payload() {
         let bytes = Request {
             id: "some id".to_string(),
+            deadline: None,
+            idempotency_key: None,
+            correlation_id: None,
             payload: true,
         }
         .to_vec()
@@ -236,6 +398,9 @@ mod tests {
             UntypedRequest::from_slice(&bytes),
             Ok(UntypedRequest {
                 id: Cow::Borrowed("some id"),
+                deadline: None,
+                idempotency_key: None,
+                correlation_id: None,
                 payload: Cow::Owned(vec![TRUE_BYTE]),
             })
         );
@@ -246,6 +411,9 @@ mod tests {
         // Request with id < 32 bytes
         let mut bytes = Request {
             id: "".to_string(),
+            deadline: None,
+            idempotency_key: None,
+            correlation_id: None,
             payload: true,
         }
         .to_vec()
@@ -259,11 +427,68 @@ mod tests {
             UntypedRequest::from_slice(&bytes),
             Ok(UntypedRequest {
                 id: Cow::Owned("".to_string()),
+                deadline: None,
+                idempotency_key: None,
+                correlation_id: None,
                 payload: Cow::Owned(vec![TRUE_BYTE, NEVER_USED_BYTE]),
             })
         );
     }
 
+    #[test]
+    fn untyped_request_should_support_round_trip_of_request_with_deadline() {
+        let request = Request::new(true).with_deadline(Duration::from_secs(5));
+        let bytes = request.to_vec().unwrap();
+
+        let untyped_request = UntypedRequest::from_slice(&bytes).unwrap();
+        assert_eq!(untyped_request.deadline, Some(5000));
+        assert_eq!(untyped_request.to_bytes(), bytes);
+        assert_eq!(untyped_request.to_typed_request::<bool>().unwrap(), request);
+    }
+
+    #[test]
+    fn untyped_request_should_support_round_trip_of_request_with_idempotency_key() {
+        let request = Request::new(true).with_idempotency_key("key");
+        let bytes = request.to_vec().unwrap();
+
+        let untyped_request = UntypedRequest::from_slice(&bytes).unwrap();
+        assert_eq!(untyped_request.idempotency_key.as_deref(), Some("key"));
+        assert_eq!(untyped_request.to_bytes(), bytes);
+        assert_eq!(untyped_request.to_typed_request::<bool>().unwrap(), request);
+
+        let request = request.with_deadline(Duration::from_millis(100));
+        let bytes = request.to_vec().unwrap();
+
+        let untyped_request = UntypedRequest::from_slice(&bytes).unwrap();
+        assert_eq!(untyped_request.deadline, Some(100));
+        assert_eq!(untyped_request.idempotency_key.as_deref(), Some("key"));
+        assert_eq!(untyped_request.to_bytes(), bytes);
+        assert_eq!(untyped_request.to_typed_request::<bool>().unwrap(), request);
+    }
+
+    #[test]
+    fn untyped_request_should_support_round_trip_of_request_with_correlation_id() {
+        let request = Request::new(true)
+            .with_deadline(Duration::from_millis(100))
+            .with_idempotency_key("key")
+            .with_correlation_id("save-42");
+        let bytes = request.to_vec().unwrap();
+
+        let untyped_request = UntypedRequest::from_slice(&bytes).unwrap();
+        assert_eq!(untyped_request.deadline, Some(100));
+        assert_eq!(untyped_request.idempotency_key.as_deref(), Some("key"));
+        assert_eq!(untyped_request.correlation_id.as_deref(), Some("save-42"));
+        assert_eq!(untyped_request.to_bytes(), bytes);
+        assert_eq!(untyped_request.to_typed_request::<bool>().unwrap(), request);
+
+        let request = Request::new(true).with_correlation_id("save-43");
+        let untyped_request = request.to_untyped_request().unwrap();
+        assert_eq!(
+            UntypedRequest::from_slice(&untyped_request.to_bytes()).unwrap(),
+            untyped_request
+        );
+    }
+
     #[test]
     fn untyped_request_should_fail_to_parse_if_given_bytes_not_representing_a_request() {
         // Empty byte slice
@@ -338,6 +563,24 @@ mod tests {
             Err(UntypedRequestParseError::InvalidId)
         );
 
+        // Non-integer deadline field value
+        assert_eq!(
+            UntypedRequest::from_slice(
+                [
+                    &[0x83],
+                    ID_FIELD_BYTES,
+                    TEST_STR_BYTES,
+                    DEADLINE_FIELD_BYTES,
+                    &[TRUE_BYTE], // deadline value set to boolean
+                    PAYLOAD_FIELD_BYTES,
+                    &[TRUE_BYTE],
+                ]
+                .concat()
+                .as_slice()
+            ),
+            Err(UntypedRequestParseError::InvalidDeadline)
+        );
+
         // Missing payload field (has valid data itself)
         assert_eq!(
             UntypedRequest::from_slice(
@@ -0,0 +1,370 @@
+use super::utils::{deserialize_from_str, serialize_to_str};
+use super::{InmemoryTransport, Transport, TransportExt};
+use derive_more::{Display, Error};
+use log::*;
+use serde::{de::Deserializer, ser::Serializer, Deserialize, Serialize};
+use std::{fmt, io, str::FromStr, sync::Arc, time::Duration};
+use tokio::{sync::mpsc, time::Instant};
+
+/// Capacity of the channels between a shaped transport and the transport it wraps
+const SIMULATION_BUFFER: usize = 100;
+
+/// Size of each read from the transport being shaped
+const SIMULATION_READ_SIZE: usize = 8 * 1024;
+
+/// Units of bandwidth alongside the number of bits per second that each represents, ordered from
+/// largest to smallest so that the largest unit that fits is displayed
+const BANDWIDTH_UNITS: &[(&str, u64)] = &[
+    ("gbit", 1_000_000_000),
+    ("mbit", 1_000_000),
+    ("kbit", 1_000),
+    ("bit", 1),
+    ("gbps", 8_000_000_000),
+    ("mbps", 8_000_000),
+    ("kbps", 8_000),
+    ("bps", 8),
+];
+
+/// Represents the conditions of a slow link that traffic is shaped to, written as
+/// `latency=<duration>,bandwidth=<rate>` where either setting can be left out
+///
+/// Latency is given in `us`, `ms`, or `s`, while bandwidth is given in bits (`bit`, `kbit`,
+/// `mbit`, `gbit`) or bytes (`bps`, `kbps`, `mbps`, `gbps`) per second like `tc` does.
+///
+/// E.g. `latency=200ms,bandwidth=1mbit`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LinkSimulation {
+    /// Delay added to data travelling in each direction
+    pub latency: Duration,
+
+    /// Bits per second that can travel in each direction, or unlimited if none
+    pub bandwidth: Option<u64>,
+}
+
+impl LinkSimulation {
+    /// Returns true if the link neither delays nor limits traffic
+    pub fn is_unlimited(&self) -> bool {
+        self.latency.is_zero() && self.bandwidth.is_none()
+    }
+
+    /// Returns the time taken to put `len` bytes onto the link
+    fn transmit_time(&self, len: usize) -> Duration {
+        match self.bandwidth {
+            Some(bits) if bits > 0 => Duration::from_secs_f64(len as f64 * 8.0 / bits as f64),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Wraps `transport` such that the data written to and read from it is shaped to this link,
+    /// spawning the tasks that move data between the two
+    ///
+    /// The returned transport cannot reconnect, so a client using it is disconnected for good
+    /// when `transport` closes.
+    pub fn shape<T: Transport + 'static>(&self, transport: T) -> InmemoryTransport {
+        let transport = Arc::new(transport);
+        let (incoming_tx, mut outgoing_rx, shaped) = InmemoryTransport::make(SIMULATION_BUFFER);
+
+        // Outgoing data is scheduled as it is written and then written to the transport on time
+        let (scheduled_tx, scheduled_rx) = mpsc::unbounded_channel();
+        let mut schedule = Schedule::new(*self);
+        tokio::spawn(async move {
+            while let Some(data) = outgoing_rx.recv().await {
+                if scheduled_tx
+                    .send((schedule.next(data.len()), data))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        tokio::spawn({
+            let transport = Arc::clone(&transport);
+            deliver(scheduled_rx, move |data| {
+                let transport = Arc::clone(&transport);
+                async move { transport.write_all(&data).await }
+            })
+        });
+
+        // Incoming data is scheduled as it is read and then made available to read on time
+        let (scheduled_tx, scheduled_rx) = mpsc::unbounded_channel();
+        let mut schedule = Schedule::new(*self);
+        let closed_tx = incoming_tx.clone();
+        tokio::spawn(async move {
+            let mut buf = vec![0; SIMULATION_READ_SIZE];
+            loop {
+                tokio::select! {
+                    _ = closed_tx.closed() => break,
+                    result = transport.readable() => {
+                        if let Err(x) = result {
+                            debug!("Shaped transport failed waiting to read: {x}");
+                            break;
+                        }
+                    }
+                }
+
+                match transport.try_read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        if scheduled_tx
+                            .send((schedule.next(n), buf[..n].to_vec()))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                    // Transports can report being readable when they are not, so give the other
+                    // tasks a chance to run before trying again
+                    Err(x) if x.kind() == io::ErrorKind::WouldBlock => {
+                        tokio::task::yield_now().await;
+                    }
+                    Err(x) => {
+                        debug!("Shaped transport failed to read: {x}");
+                        break;
+                    }
+                }
+            }
+        });
+        tokio::spawn(deliver(scheduled_rx, move |data| {
+            let incoming_tx = incoming_tx.clone();
+            async move {
+                incoming_tx
+                    .send(data)
+                    .await
+                    .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+            }
+        }));
+
+        shaped
+    }
+}
+
+/// Tracks when data sent in one direction of a link arrives
+struct Schedule {
+    link: LinkSimulation,
+
+    /// When the link finishes transmitting the data already sent over it
+    free_at: Instant,
+}
+
+impl Schedule {
+    fn new(link: LinkSimulation) -> Self {
+        Self {
+            link,
+            free_at: Instant::now(),
+        }
+    }
+
+    /// Returns when `len` bytes sent now arrive, which is after the data sent before them has
+    /// been transmitted, they have been transmitted, and the latency has passed
+    fn next(&mut self, len: usize) -> Instant {
+        self.free_at = self.free_at.max(Instant::now()) + self.link.transmit_time(len);
+        self.free_at + self.link.latency
+    }
+}
+
+/// Passes each piece of data received from `rx` to `f` once the time it was scheduled for has
+/// come, stopping once `f` fails
+async fn deliver<F, Fut>(mut rx: mpsc::UnboundedReceiver<(Instant, Vec<u8>)>, f: F)
+where
+    F: Fn(Vec<u8>) -> Fut,
+    Fut: std::future::Future<Output = io::Result<()>>,
+{
+    while let Some((at, data)) = rx.recv().await {
+        tokio::time::sleep_until(at).await;
+        if let Err(x) = f(data).await {
+            debug!("Shaped transport failed to deliver data: {x}");
+            break;
+        }
+    }
+}
+
+impl fmt::Display for LinkSimulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut settings = Vec::new();
+        if !self.latency.is_zero() {
+            let millis = self.latency.as_millis();
+            settings.push(if Duration::from_millis(millis as u64) == self.latency {
+                format!("latency={millis}ms")
+            } else {
+                format!("latency={}us", self.latency.as_micros())
+            });
+        }
+
+        if let Some(bits) = self.bandwidth {
+            let (unit, size) = BANDWIDTH_UNITS
+                .iter()
+                .copied()
+                .find(|(_, size)| *size <= bits && bits / size * size == bits)
+                .unwrap_or(("bit", 1));
+            settings.push(format!("bandwidth={}{unit}", bits / size));
+        }
+
+        write!(f, "{}", settings.join(","))
+    }
+}
+
+/// Represents an error that occurred when parsing a [`LinkSimulation`]
+#[derive(Clone, Debug, Display, Error, PartialEq, Eq)]
+pub enum LinkSimulationParseError {
+    #[display(fmt = "Unknown setting (expected latency or bandwidth): {_0}")]
+    UnknownSetting(#[error(not(source))] String),
+
+    #[display(fmt = "Bad latency (expected a number followed by us, ms, or s): {_0}")]
+    BadLatency(#[error(not(source))] String),
+
+    #[display(
+        fmt = "Bad bandwidth (expected a number followed by a unit like kbit or mbps): {_0}"
+    )]
+    BadBandwidth(#[error(not(source))] String),
+}
+
+impl FromStr for LinkSimulation {
+    type Err = LinkSimulationParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut this = Self::default();
+
+        for setting in s.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            let (key, value) = setting
+                .split_once('=')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .ok_or_else(|| LinkSimulationParseError::UnknownSetting(setting.to_string()))?;
+
+            if key.eq_ignore_ascii_case("latency") {
+                this.latency = parse_latency(value)
+                    .ok_or_else(|| LinkSimulationParseError::BadLatency(value.to_string()))?;
+            } else if key.eq_ignore_ascii_case("bandwidth") {
+                this.bandwidth =
+                    Some(parse_bandwidth(value).ok_or_else(|| {
+                        LinkSimulationParseError::BadBandwidth(value.to_string())
+                    })?);
+            } else {
+                return Err(LinkSimulationParseError::UnknownSetting(key.to_string()));
+            }
+        }
+
+        Ok(this)
+    }
+}
+
+/// Splits `s` into its number and the unit that follows it
+fn split_unit(s: &str) -> Option<(f64, String)> {
+    let i = s.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = s.split_at(i);
+    let number = number.trim().parse::<f64>().ok()?;
+    if !number.is_finite() || number < 0.0 {
+        return None;
+    }
+
+    Some((number, unit.to_ascii_lowercase()))
+}
+
+fn parse_latency(s: &str) -> Option<Duration> {
+    let (number, unit) = split_unit(s)?;
+    let secs = match unit.as_str() {
+        "us" => number / 1_000_000.0,
+        "ms" => number / 1_000.0,
+        "s" => number,
+        _ => return None,
+    };
+
+    Some(Duration::from_secs_f64(secs))
+}
+
+fn parse_bandwidth(s: &str) -> Option<u64> {
+    let (number, unit) = split_unit(s)?;
+    let (_, size) = BANDWIDTH_UNITS.iter().find(|(name, _)| *name == unit)?;
+    let bits = (number * *size as f64).round();
+    if bits < 1.0 || bits > u64::MAX as f64 {
+        return None;
+    }
+
+    Some(bits as u64)
+}
+
+impl Serialize for LinkSimulation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_to_str(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LinkSimulation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_from_str(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn from_str_should_parse_latency_and_bandwidth() {
+        let link: LinkSimulation = "latency=200ms,bandwidth=1mbit".parse().unwrap();
+        assert_eq!(link.latency, Duration::from_millis(200));
+        assert_eq!(link.bandwidth, Some(1_000_000));
+        assert_eq!(link.to_string(), "latency=200ms,bandwidth=1mbit");
+
+        let link: LinkSimulation = " bandwidth = 2KBps ".parse().unwrap();
+        assert_eq!(link.latency, Duration::ZERO);
+        assert_eq!(link.bandwidth, Some(16_000));
+        assert_eq!(link.to_string(), "bandwidth=16kbit");
+
+        let link: LinkSimulation = "latency=1.5s".parse().unwrap();
+        assert_eq!(link.latency, Duration::from_millis(1500));
+        assert_eq!(link.to_string(), "latency=1500ms");
+
+        assert!("".parse::<LinkSimulation>().unwrap().is_unlimited());
+        assert_eq!(
+            "jitter=5ms".parse::<LinkSimulation>(),
+            Err(LinkSimulationParseError::UnknownSetting(String::from(
+                "jitter"
+            )))
+        );
+        assert_eq!(
+            "latency=200".parse::<LinkSimulation>(),
+            Err(LinkSimulationParseError::BadLatency(String::from("200")))
+        );
+        assert_eq!(
+            "bandwidth=1mb".parse::<LinkSimulation>(),
+            Err(LinkSimulationParseError::BadBandwidth(String::from("1mb")))
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn shape_should_delay_data_by_latency_and_bandwidth() {
+        let (t1, t2) = InmemoryTransport::pair(100);
+        let link: LinkSimulation = "latency=100ms,bandwidth=8kbit".parse().unwrap();
+        let shaped = link.shape(t1);
+
+        // 100 bytes at 1000 bytes per second take 100ms to transmit on top of the latency
+        let start = std::time::Instant::now();
+        shaped.write_all(&[1; 100]).await.unwrap();
+        let mut buf = [0; 100];
+        t2.read_exact(&mut buf).await.unwrap();
+        assert!(
+            start.elapsed() >= Duration::from_millis(200),
+            "{:?}",
+            start.elapsed()
+        );
+        assert_eq!(buf, [1; 100]);
+
+        let start = std::time::Instant::now();
+        t2.write_all(b"hello").await.unwrap();
+        let mut buf = [0; 5];
+        shaped.read_exact(&mut buf).await.unwrap();
+        assert!(
+            start.elapsed() >= Duration::from_millis(100),
+            "{:?}",
+            start.elapsed()
+        );
+        assert_eq!(&buf, b"hello");
+    }
+}
@@ -0,0 +1,66 @@
+use std::{io, net::SocketAddr, time::Duration};
+use tokio::net::{TcpSocket, TcpStream};
+
+/// Default number of pending connections queued by a listening socket, mirroring tokio
+const DEFAULT_BACKLOG: u32 = 1024;
+
+/// Represents options applied to TCP sockets when they are created
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SocketOptions {
+    /// If true, disables Nagle's algorithm (`TCP_NODELAY`) so small writes are sent immediately
+    pub nodelay: bool,
+
+    /// If provided, enables TCP keepalive with probes sent after the connection has been idle
+    /// for this long
+    pub keepalive: Option<Duration>,
+
+    /// If provided, sets the size of the socket's send buffer (`SO_SNDBUF`)
+    pub send_buffer_size: Option<u32>,
+
+    /// If provided, sets the size of the socket's receive buffer (`SO_RCVBUF`)
+    pub recv_buffer_size: Option<u32>,
+
+    /// If provided, sets the maximum number of pending connections queued by a listening socket
+    pub backlog: Option<u32>,
+}
+
+impl SocketOptions {
+    /// Returns the backlog to use for a listening socket
+    pub fn backlog_or_default(&self) -> u32 {
+        self.backlog.unwrap_or(DEFAULT_BACKLOG)
+    }
+
+    /// Creates a new, unbound socket for the given address with options applied that must be set
+    /// prior to connecting or listening
+    pub(crate) fn new_socket(&self, addr: SocketAddr) -> io::Result<TcpSocket> {
+        let socket = if addr.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+
+        Ok(socket)
+    }
+
+    /// Applies options to a connected stream
+    pub(crate) fn apply_to_stream(&self, stream: &TcpStream) -> io::Result<()> {
+        if self.nodelay {
+            stream.set_nodelay(true)?;
+        }
+
+        if let Some(time) = self.keepalive {
+            socket2::SockRef::from(stream)
+                .set_tcp_keepalive(&socket2::TcpKeepalive::new().with_time(time))?;
+        }
+
+        Ok(())
+    }
+}
@@ -63,6 +63,12 @@ pub trait Transport: Reconnectable + fmt::Debug + Send + Sync {
     /// Waits for the transport to be ready based on the given interest, returning the ready
     /// status.
     async fn ready(&self, interest: Interest) -> io::Result<Ready>;
+
+    /// Returns a description of where the other side of the transport is connecting from, if
+    /// known, such as the IP address and port of a TCP peer.
+    fn peer_address(&self) -> Option<String> {
+        None
+    }
 }
 
 #[async_trait]
@@ -78,6 +84,10 @@ impl Transport for Box<dyn Transport> {
     async fn ready(&self, interest: Interest) -> io::Result<Ready> {
         Transport::ready(AsRef::as_ref(self), interest).await
     }
+
+    fn peer_address(&self) -> Option<String> {
+        Transport::peer_address(AsRef::as_ref(self))
+    }
 }
 
 #[async_trait]
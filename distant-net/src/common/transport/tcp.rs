@@ -1,29 +1,172 @@
 use super::{Interest, Ready, Reconnectable, Transport};
+use crate::common::{Proxy, SocketOptions};
 use async_trait::async_trait;
-use std::{fmt, io, net::IpAddr};
-use tokio::net::{TcpStream, ToSocketAddrs};
+use log::*;
+use std::{
+    fmt, io,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+use tokio::{
+    net::{lookup_host, TcpStream, ToSocketAddrs},
+    task::JoinSet,
+};
+
+/// Time to wait on a connection attempt before starting one to the next address, as recommended
+/// by [RFC 8305](https://www.rfc-editor.org/rfc/rfc8305#section-5)
+pub const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
 
 /// Represents a [`Transport`] that leverages a TCP stream
 pub struct TcpTransport {
     pub(crate) addr: IpAddr,
     pub(crate) port: u16,
+    pub(crate) options: SocketOptions,
+    pub(crate) tunnel: Option<Tunnel>,
     pub(crate) inner: TcpStream,
 }
 
+/// Represents the route taken through a proxy to reach the remote machine
+#[derive(Clone, Debug)]
+pub(crate) struct Tunnel {
+    proxy: Proxy,
+    host: String,
+    port: u16,
+}
+
 impl TcpTransport {
     /// Creates a new stream by connecting to a remote machine at the specified
     /// IP address and port
     pub async fn connect(addrs: impl ToSocketAddrs) -> io::Result<Self> {
-        let stream = TcpStream::connect(addrs).await?;
-        let addr = stream.peer_addr()?;
+        Self::connect_with_options(addrs, SocketOptions::default()).await
+    }
+
+    /// Creates a new stream by connecting to a remote machine at the specified
+    /// IP address and port, applying `options` to the socket
+    ///
+    /// When the address resolves to multiple IP addresses, connection attempts are raced in the
+    /// style of [RFC 8305](https://www.rfc-editor.org/rfc/rfc8305) (Happy Eyeballs): addresses are
+    /// interleaved by family and each attempt is started [`CONNECTION_ATTEMPT_DELAY`] after the
+    /// previous (or immediately once it fails), with the first to succeed being used
+    pub async fn connect_with_options(
+        addrs: impl ToSocketAddrs,
+        options: SocketOptions,
+    ) -> io::Result<Self> {
+        let addrs = interleave_families(lookup_host(addrs).await?.collect());
+        let (addr, stream) = match addrs.as_slice() {
+            [] => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "could not resolve to any address",
+                ))
+            }
+            [addr] => (*addr, Self::connect_socket(*addr, options).await?),
+            _ => Self::race_connect(addrs, options).await?,
+        };
+
         Ok(Self {
             addr: addr.ip(),
             port: addr.port(),
+            options,
+            tunnel: None,
             inner: stream,
         })
     }
 
-    /// Returns the IP address that the stream is connected to
+    /// Creates a new stream by connecting to `proxy` and asking it to tunnel to the remote machine
+    /// at the specified `host` and `port`, applying `options` to the socket
+    ///
+    /// The host is passed to the proxy as-is, meaning that names are resolved by the proxy rather
+    /// than locally
+    pub async fn connect_via_proxy(
+        proxy: Proxy,
+        host: impl Into<String>,
+        port: u16,
+        options: SocketOptions,
+    ) -> io::Result<Self> {
+        let tunnel = Tunnel {
+            proxy,
+            host: host.into(),
+            port,
+        };
+        let (addr, stream) = Self::connect_tunnel(&tunnel, options).await?;
+
+        Ok(Self {
+            addr: addr.ip(),
+            port: addr.port(),
+            options,
+            tunnel: Some(tunnel),
+            inner: stream,
+        })
+    }
+
+    async fn connect_tunnel(
+        tunnel: &Tunnel,
+        options: SocketOptions,
+    ) -> io::Result<(SocketAddr, TcpStream)> {
+        let proxy_addr = (tunnel.proxy.host.to_string(), tunnel.proxy.port);
+        let mut proxy = Self::connect_with_options(proxy_addr, options).await?;
+        debug!(
+            "Requesting tunnel to {}:{} from proxy {}",
+            tunnel.host, tunnel.port, tunnel.proxy
+        );
+        tunnel
+            .proxy
+            .handshake(&mut proxy.inner, &tunnel.host, tunnel.port)
+            .await?;
+        Ok((SocketAddr::new(proxy.addr, proxy.port), proxy.inner))
+    }
+
+    /// Attempts to connect to each of `addrs` in order, starting the next attempt when the
+    /// previous fails or after [`CONNECTION_ATTEMPT_DELAY`], and returning the first success
+    async fn race_connect(
+        addrs: Vec<SocketAddr>,
+        options: SocketOptions,
+    ) -> io::Result<(SocketAddr, TcpStream)> {
+        let mut pending = addrs.into_iter();
+        let mut attempts = JoinSet::new();
+        let mut err = None;
+
+        loop {
+            if let Some(addr) = pending.next() {
+                trace!("Attempting connection to {addr}");
+                attempts.spawn(async move { (addr, Self::connect_socket(addr, options).await) });
+            }
+
+            // If we have more addresses to try, only wait so long before starting the next one
+            let result = if pending.len() > 0 {
+                tokio::select! {
+                    result = attempts.join_next() => result,
+                    _ = tokio::time::sleep(CONNECTION_ATTEMPT_DELAY) => continue,
+                }
+            } else {
+                attempts.join_next().await
+            };
+
+            // Any attempts still in flight are aborted when the set is dropped
+            match result {
+                Some(Ok((addr, Ok(stream)))) => return Ok((addr, stream)),
+                Some(Ok((addr, Err(x)))) => {
+                    trace!("Connection to {addr} failed: {x}");
+                    err = Some(x);
+                }
+                Some(Err(x)) => err = Some(io::Error::new(io::ErrorKind::Other, x)),
+                None => break,
+            }
+        }
+
+        Err(err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "all connection attempts failed")
+        }))
+    }
+
+    async fn connect_socket(addr: SocketAddr, options: SocketOptions) -> io::Result<TcpStream> {
+        let stream = options.new_socket(addr)?.connect(addr).await?;
+        options.apply_to_stream(&stream)?;
+        Ok(stream)
+    }
+
+    /// Returns the IP address that the stream is connected to, which is that of the proxy when
+    /// connected through one
     pub fn ip_addr(&self) -> IpAddr {
         self.addr
     }
@@ -34,6 +177,26 @@ impl TcpTransport {
     }
 }
 
+/// Reorders `addrs` to alternate between address families, starting with the family of the first
+/// address, while otherwise preserving the order provided by the resolver
+fn interleave_families(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let first_is_ipv6 = matches!(addrs.first(), Some(addr) if addr.is_ipv6());
+    let (preferred, other): (Vec<_>, Vec<_>) = addrs
+        .into_iter()
+        .partition(|addr| addr.is_ipv6() == first_is_ipv6);
+
+    let mut addrs = Vec::with_capacity(preferred.len() + other.len());
+    let mut preferred = preferred.into_iter();
+    let mut other = other.into_iter();
+    loop {
+        match (preferred.next(), other.next()) {
+            (None, None) => break,
+            (a, b) => addrs.extend(a.into_iter().chain(b)),
+        }
+    }
+    addrs
+}
+
 impl fmt::Debug for TcpTransport {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("TcpTransport")
@@ -46,7 +209,12 @@ impl fmt::Debug for TcpTransport {
 #[async_trait]
 impl Reconnectable for TcpTransport {
     async fn reconnect(&mut self) -> io::Result<()> {
-        self.inner = TcpStream::connect((self.addr, self.port)).await?;
+        self.inner = match self.tunnel.as_ref() {
+            Some(tunnel) => Self::connect_tunnel(tunnel, self.options).await?.1,
+            None => {
+                Self::connect_socket(SocketAddr::new(self.addr, self.port), self.options).await?
+            }
+        };
         Ok(())
     }
 }
@@ -64,6 +232,10 @@ impl Transport for TcpTransport {
     async fn ready(&self, interest: Interest) -> io::Result<Ready> {
         self.inner.ready(interest).await
     }
+
+    fn peer_address(&self) -> Option<String> {
+        Some(SocketAddr::new(self.addr, self.port).to_string())
+    }
 }
 
 #[cfg(test)]
@@ -134,6 +306,43 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn interleave_families_should_alternate_starting_with_first_family() {
+        let v4 = |n| SocketAddr::from(([127, 0, 0, n], 8080));
+        let v6 = |n| SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, n], 8080));
+
+        assert_eq!(
+            interleave_families(vec![v6(1), v6(2), v6(3), v4(1), v4(2)]),
+            vec![v6(1), v4(1), v6(2), v4(2), v6(3)]
+        );
+        assert_eq!(
+            interleave_families(vec![v4(1), v6(1), v6(2), v4(2)]),
+            vec![v4(1), v6(1), v4(2), v6(2)]
+        );
+        assert_eq!(interleave_families(vec![v4(1)]), vec![v4(1)]);
+        assert_eq!(interleave_families(Vec::new()), Vec::new());
+    }
+
+    #[test(tokio::test)]
+    async fn should_connect_to_later_address_if_earlier_attempt_stalls() {
+        let listener = TcpListener::bind((Ipv6Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // Addresses in the documentation range are not routable, so attempts to them hang
+        // until the operating system gives up on them (or fail immediately if no route exists)
+        let stalled = SocketAddr::from(([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1], addr.port()));
+
+        let conn = tokio::time::timeout(
+            Duration::from_secs(5),
+            TcpTransport::connect(&[stalled, addr][..]),
+        )
+        .await
+        .expect("Connection attempt was not raced")
+        .expect("Conn failed to connect");
+        assert_eq!(conn.ip_addr(), addr.ip());
+        assert_eq!(conn.port(), addr.port());
+    }
+
     #[test(tokio::test)]
     async fn should_be_able_to_read_and_write_data() {
         let (tx, rx) = oneshot::channel();
@@ -14,8 +14,11 @@ pub struct UnixSocketTransport {
 
 impl UnixSocketTransport {
     /// Creates a new stream by connecting to the specified path
+    ///
+    /// On Linux, a path of the form `@name` connects to the socket named `name` within the
+    /// abstract namespace rather than to a file
     pub async fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
-        let stream = UnixStream::connect(path.as_ref()).await?;
+        let stream = connect_stream(path.as_ref()).await?;
         Ok(Self {
             path: path.as_ref().to_path_buf(),
             inner: stream,
@@ -28,6 +31,33 @@ impl UnixSocketTransport {
     }
 }
 
+/// Returns the name within the abstract namespace that `path` refers to, which is the case when it
+/// is of the form `@name`
+#[cfg(target_os = "linux")]
+pub(crate) fn abstract_name(path: &Path) -> Option<&[u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    match path.as_os_str().as_bytes() {
+        [b'@', name @ ..] if !name.is_empty() => Some(name),
+        _ => None,
+    }
+}
+
+async fn connect_stream(path: &Path) -> io::Result<UnixStream> {
+    #[cfg(target_os = "linux")]
+    if let Some(name) = abstract_name(path) {
+        use std::os::{linux::net::SocketAddrExt, unix::net};
+
+        // Connecting to a unix socket does not block, so we can use the std implementation
+        // that supports the abstract namespace and hand the stream over to tokio
+        let addr = net::SocketAddr::from_abstract_name(name)?;
+        let stream = net::UnixStream::connect_addr(&addr)?;
+        stream.set_nonblocking(true)?;
+        return UnixStream::from_std(stream);
+    }
+
+    UnixStream::connect(path).await
+}
+
 impl fmt::Debug for UnixSocketTransport {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("UnixSocketTransport")
@@ -39,7 +69,7 @@ impl fmt::Debug for UnixSocketTransport {
 #[async_trait]
 impl Reconnectable for UnixSocketTransport {
     async fn reconnect(&mut self) -> io::Result<()> {
-        self.inner = UnixStream::connect(self.path.as_path()).await?;
+        self.inner = connect_stream(self.path.as_path()).await?;
         Ok(())
     }
 }
@@ -57,6 +87,13 @@ impl Transport for UnixSocketTransport {
     async fn ready(&self, interest: Interest) -> io::Result<Ready> {
         self.inner.ready(interest).await
     }
+
+    /// Unix sockets have no meaningful address for the other side, so the process id of the peer
+    /// is reported instead
+    fn peer_address(&self) -> Option<String> {
+        let pid = self.inner.peer_cred().ok()?.pid()?;
+        Some(format!("pid {pid}"))
+    }
 }
 
 #[cfg(test)]
@@ -10,14 +10,24 @@ use crate::{
     manager::data::{
         ConnectionInfo, ConnectionList, ManagerCapabilities, ManagerRequest, ManagerResponse,
     },
+    server::ClientInfo,
 };
 use log::*;
-use std::io;
 
 mod channel;
 pub use channel::*;
 
+mod error;
+pub use error::*;
+
+mod event;
+pub use event::*;
+
 /// Represents a client that can connect to a remote server manager.
+///
+/// Beyond the CLI, this can be used by other tools to orchestrate connections programmatically,
+/// with failures surfaced as a [`ManagerError`] and changes to connections observed through
+/// [`ManagerClient::subscribe`].
 pub type ManagerClient = Client<ManagerRequest, ManagerResponse>;
 
 impl ManagerClient {
@@ -32,7 +42,7 @@ impl ManagerClient {
         destination: impl Into<Destination>,
         options: impl Into<Map>,
         mut handler: impl AuthHandler + Send,
-    ) -> io::Result<Destination> {
+    ) -> Result<Destination, ManagerError> {
         let destination = Box::new(destination.into());
         let options = options.into();
         trace!("launch({}, {})", destination, options);
@@ -91,11 +101,12 @@ impl ManagerClient {
                     }
                     Authentication::Info(x) => {
                         info!("{}", x.text);
+                        handler.on_info(x).await?;
                     }
                     Authentication::Error(x) => {
                         error!("{}", x.text);
                         if x.is_fatal() {
-                            return Err(x.into_io_permission_denied());
+                            return Err(ManagerError::Authentication(x));
                         }
                     }
                     Authentication::Finished => {
@@ -103,22 +114,14 @@ impl ManagerClient {
                     }
                 },
                 ManagerResponse::Launched { destination } => return Ok(destination),
-                ManagerResponse::Error { description } => {
-                    return Err(io::Error::new(io::ErrorKind::Other, description))
-                }
-                x => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        format!("Got unexpected response: {x:?}"),
-                    ))
+                ManagerResponse::Error { kind, description } => {
+                    return Err(ManagerError::Manager { kind, description })
                 }
+                x => return Err(ManagerError::unexpected(x)),
             }
         }
 
-        Err(io::Error::new(
-            io::ErrorKind::UnexpectedEof,
-            "Missing connection confirmation",
-        ))
+        Err(ManagerError::MissingResponse)
     }
 
     /// Request that the manager establishes a new connection at the given `destination`
@@ -130,16 +133,30 @@ impl ManagerClient {
         &mut self,
         destination: impl Into<Destination>,
         options: impl Into<Map>,
-        mut handler: impl AuthHandler + Send,
-    ) -> io::Result<ConnectionId> {
+        handler: impl AuthHandler + Send,
+    ) -> Result<ConnectionId, ManagerError> {
+        self.connect_with_name(destination, options, None, handler)
+            .await
+    }
+
+    /// Like [`ManagerClient::connect`], but gives the new connection the alias `name` (if
+    /// provided) that can be resolved back to its id using [`ManagerClient::resolve`].
+    pub async fn connect_with_name(
+        &mut self,
+        destination: impl Into<Destination>,
+        options: impl Into<Map>,
+        name: Option<String>,
+        mut handler: impl AuthHandler,
+    ) -> Result<ConnectionId, ManagerError> {
         let destination = Box::new(destination.into());
         let options = options.into();
-        trace!("connect({}, {})", destination, options);
+        trace!("connect({}, {}, {:?})", destination, options, name);
 
         let mut mailbox = self
             .mail(ManagerRequest::Connect {
                 destination: destination.clone(),
                 options,
+                name,
             })
             .await?;
 
@@ -190,11 +207,12 @@ impl ManagerClient {
                     }
                     Authentication::Info(x) => {
                         info!("{}", x.text);
+                        handler.on_info(x).await?;
                     }
                     Authentication::Error(x) => {
                         error!("{}", x.text);
                         if x.is_fatal() {
-                            return Err(x.into_io_permission_denied());
+                            return Err(ManagerError::Authentication(x));
                         }
                     }
                     Authentication::Finished => {
@@ -202,22 +220,14 @@ impl ManagerClient {
                     }
                 },
                 ManagerResponse::Connected { id } => return Ok(id),
-                ManagerResponse::Error { description } => {
-                    return Err(io::Error::new(io::ErrorKind::Other, description))
-                }
-                x => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        format!("Got unexpected response: {x:?}"),
-                    ))
+                ManagerResponse::Error { kind, description } => {
+                    return Err(ManagerError::Manager { kind, description })
                 }
+                x => return Err(ManagerError::unexpected(x)),
             }
         }
 
-        Err(io::Error::new(
-            io::ErrorKind::UnexpectedEof,
-            "Missing connection confirmation",
-        ))
+        Err(ManagerError::MissingResponse)
     }
 
     /// Establishes a channel with the server represented by the `connection_id`,
@@ -230,72 +240,131 @@ impl ManagerClient {
     pub async fn open_raw_channel(
         &mut self,
         connection_id: ConnectionId,
-    ) -> io::Result<RawChannel> {
+    ) -> Result<RawChannel, ManagerError> {
         trace!("open_raw_channel({})", connection_id);
         RawChannel::spawn(connection_id, self).await
     }
 
+    /// Claims the channel handed off by another client with `token` (see
+    /// [`ChannelHandoff::handoff`]), returning it along with the resources that the other client
+    /// said were running over it. Whatever the server sent through the channel since it was
+    /// handed off is read from the channel first.
+    pub async fn claim_channel(
+        &mut self,
+        token: impl Into<String>,
+    ) -> Result<(RawChannel, Map), ManagerError> {
+        trace!("claim_channel(..)");
+        RawChannel::claim(token.into(), self).await
+    }
+
     /// Retrieves a list of supported capabilities
-    pub async fn capabilities(&mut self) -> io::Result<ManagerCapabilities> {
+    pub async fn capabilities(&mut self) -> Result<ManagerCapabilities, ManagerError> {
         trace!("capabilities()");
         let res = self.send(ManagerRequest::Capabilities).await?;
         match res.payload {
             ManagerResponse::Capabilities { supported } => Ok(supported),
-            ManagerResponse::Error { description } => {
-                Err(io::Error::new(io::ErrorKind::Other, description))
+            ManagerResponse::Error { kind, description } => {
+                Err(ManagerError::Manager { kind, description })
             }
-            x => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Got unexpected response: {x:?}"),
-            )),
+            x => Err(ManagerError::unexpected(x)),
         }
     }
 
     /// Retrieves information about a specific connection
-    pub async fn info(&mut self, id: ConnectionId) -> io::Result<ConnectionInfo> {
+    pub async fn info(&mut self, id: ConnectionId) -> Result<ConnectionInfo, ManagerError> {
         trace!("info({})", id);
         let res = self.send(ManagerRequest::Info { id }).await?;
         match res.payload {
             ManagerResponse::Info(info) => Ok(info),
-            ManagerResponse::Error { description } => {
-                Err(io::Error::new(io::ErrorKind::Other, description))
+            ManagerResponse::Error { kind, description } => {
+                Err(ManagerError::Manager { kind, description })
             }
-            x => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Got unexpected response: {x:?}"),
-            )),
+            x => Err(ManagerError::unexpected(x)),
+        }
+    }
+
+    /// Retrieves the id of the connection with the alias `name`
+    pub async fn resolve(&mut self, name: impl Into<String>) -> Result<ConnectionId, ManagerError> {
+        let name = name.into();
+        trace!("resolve({})", name);
+        let res = self.send(ManagerRequest::Resolve { name }).await?;
+        match res.payload {
+            ManagerResponse::Resolved { id } => Ok(id),
+            ManagerResponse::Error { kind, description } => {
+                Err(ManagerError::Manager { kind, description })
+            }
+            x => Err(ManagerError::unexpected(x)),
         }
     }
 
     /// Kills the specified connection
-    pub async fn kill(&mut self, id: ConnectionId) -> io::Result<()> {
+    pub async fn kill(&mut self, id: ConnectionId) -> Result<(), ManagerError> {
         trace!("kill({})", id);
         let res = self.send(ManagerRequest::Kill { id }).await?;
         match res.payload {
             ManagerResponse::Killed => Ok(()),
-            ManagerResponse::Error { description } => {
-                Err(io::Error::new(io::ErrorKind::Other, description))
+            ManagerResponse::Error { kind, description } => {
+                Err(ManagerError::Manager { kind, description })
             }
-            x => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Got unexpected response: {x:?}"),
-            )),
+            x => Err(ManagerError::unexpected(x)),
         }
     }
 
     /// Retrieves a list of active connections
-    pub async fn list(&mut self) -> io::Result<ConnectionList> {
+    pub async fn list(&mut self) -> Result<ConnectionList, ManagerError> {
         trace!("list()");
         let res = self.send(ManagerRequest::List).await?;
         match res.payload {
             ManagerResponse::List(list) => Ok(list),
-            ManagerResponse::Error { description } => {
-                Err(io::Error::new(io::ErrorKind::Other, description))
+            ManagerResponse::Error { kind, description } => {
+                Err(ManagerError::Manager { kind, description })
             }
-            x => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Got unexpected response: {x:?}"),
-            )),
+            x => Err(ManagerError::unexpected(x)),
+        }
+    }
+
+    /// Retrieves a list of clients connected to the manager
+    pub async fn clients(&mut self) -> Result<Vec<ClientInfo>, ManagerError> {
+        trace!("clients()");
+        let res = self.send(ManagerRequest::Clients).await?;
+        match res.payload {
+            ManagerResponse::Clients { clients } => Ok(clients),
+            ManagerResponse::Error { kind, description } => {
+                Err(ManagerError::Manager { kind, description })
+            }
+            x => Err(ManagerError::unexpected(x)),
+        }
+    }
+
+    /// Forcibly disconnects the client with the specified `id` from the manager
+    pub async fn disconnect_client(&mut self, id: ConnectionId) -> Result<(), ManagerError> {
+        trace!("disconnect_client({})", id);
+        let res = self.send(ManagerRequest::DisconnectClient { id }).await?;
+        match res.payload {
+            ManagerResponse::ClientDisconnected => Ok(()),
+            ManagerResponse::Error { kind, description } => {
+                Err(ManagerError::Manager { kind, description })
+            }
+            x => Err(ManagerError::unexpected(x)),
+        }
+    }
+
+    /// Subscribes to events about connections being established and killed by the manager,
+    /// returning a [`ManagerEventStream`] that yields each event as it happens
+    pub async fn subscribe(&mut self) -> Result<ManagerEventStream, ManagerError> {
+        trace!("subscribe()");
+        let mut mailbox = self.mail(ManagerRequest::Subscribe).await?;
+
+        // Wait for the first response, which should be subscription confirmation
+        match mailbox.next().await {
+            Some(res) => match res.payload {
+                ManagerResponse::Subscribed => Ok(ManagerEventStream::new(mailbox)),
+                ManagerResponse::Error { kind, description } => {
+                    Err(ManagerError::Manager { kind, description })
+                }
+                x => Err(ManagerError::unexpected(x)),
+            },
+            None => Err(ManagerError::MissingResponse),
         }
     }
 }
@@ -306,6 +375,8 @@ mod tests {
     use crate::client::UntypedClient;
     use crate::common::authentication::DummyAuthHandler;
     use crate::common::{Connection, InmemoryTransport, Request, Response};
+    use crate::manager::data::{ManagerErrorKind, ManagerEvent};
+    use std::io;
 
     fn setup() -> (ManagerClient, Connection<InmemoryTransport>) {
         let (client, server) = Connection::pair(100);
@@ -468,6 +539,7 @@ mod tests {
 
             let info = ConnectionInfo {
                 id: 123,
+                name: None,
                 destination: "scheme://host".parse::<Destination>().unwrap(),
                 options: "key=value".parse::<Map>().unwrap(),
             };
@@ -623,4 +695,161 @@ mod tests {
 
         client.kill(123).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn resolve_should_return_id_from_successful_response() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(
+                matches!(&request.payload, ManagerRequest::Resolve { name } if name == "myserver")
+            );
+
+            transport
+                .write_frame_for(&Response::new(
+                    request.id,
+                    ManagerResponse::Resolved { id: 123 },
+                ))
+                .await
+                .unwrap();
+        });
+
+        assert_eq!(client.resolve("myserver").await.unwrap(), 123);
+    }
+
+    #[tokio::test]
+    async fn kill_should_report_kind_of_error_from_error_response() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            transport
+                .write_frame_for(&Response::new(
+                    request.id,
+                    ManagerResponse::from(io::Error::new(
+                        io::ErrorKind::NotConnected,
+                        "No connection found",
+                    )),
+                ))
+                .await
+                .unwrap();
+        });
+
+        let err = client.kill(123).await.unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ManagerError::Manager {
+                    kind: ManagerErrorKind::NotConnected,
+                    ..
+                }
+            ),
+            "{err:?}"
+        );
+        assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+        assert_eq!(err.to_string(), "No connection found");
+    }
+
+    #[tokio::test]
+    async fn subscribe_should_report_error_if_receives_error_response() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            transport
+                .write_frame_for(&Response::new(request.id, test_error_response()))
+                .await
+                .unwrap();
+        });
+
+        let err = match client.subscribe().await {
+            Ok(_) => panic!("Subscribing unexpectedly succeeded"),
+            Err(x) => x,
+        };
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert_eq!(err.to_string(), test_error().to_string());
+    }
+
+    #[tokio::test]
+    async fn subscribe_should_report_error_if_receives_unexpected_response() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            transport
+                .write_frame_for(&Response::new(request.id, ManagerResponse::Killed))
+                .await
+                .unwrap();
+        });
+
+        let err = match client.subscribe().await {
+            Ok(_) => panic!("Subscribing unexpectedly succeeded"),
+            Err(x) => x,
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn subscribe_should_return_stream_of_events_following_confirmation() {
+        let (mut client, mut transport) = setup();
+
+        tokio::spawn(async move {
+            let request = transport
+                .read_frame_as::<Request<ManagerRequest>>()
+                .await
+                .unwrap()
+                .unwrap();
+
+            for payload in [
+                ManagerResponse::Subscribed,
+                ManagerResponse::Event {
+                    event: ManagerEvent::Connected {
+                        id: 123,
+                        destination: "scheme://host".parse::<Destination>().unwrap(),
+                    },
+                },
+                ManagerResponse::Event {
+                    event: ManagerEvent::Disconnected { id: 123 },
+                },
+            ] {
+                transport
+                    .write_frame_for(&Response::new(request.id.clone(), payload))
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let mut events = client.subscribe().await.unwrap();
+        assert_eq!(
+            events.next().await,
+            Some(ManagerEvent::Connected {
+                id: 123,
+                destination: "scheme://host".parse::<Destination>().unwrap(),
+            })
+        );
+        assert_eq!(
+            events.next().await,
+            Some(ManagerEvent::Disconnected { id: 123 })
+        );
+    }
 }
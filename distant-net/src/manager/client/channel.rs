@@ -1,20 +1,24 @@
+use super::ManagerError;
 use crate::{
-    client::{Client, ClientConfig, UntypedClient},
-    common::{ConnectionId, FramedTransport, InmemoryTransport, UntypedRequest},
-    manager::data::{ManagerRequest, ManagerResponse},
+    client::{
+        Channel, Client, ClientConfig, ConnectionState, ConnectionWatcher, Mailbox, UntypedClient,
+    },
+    common::{ConnectionId, FramedTransport, InmemoryTransport, Map, Response, UntypedRequest},
+    manager::data::{ManagerChannelId, ManagerRequest, ManagerResponse},
 };
 use log::*;
 use serde::{de::DeserializeOwned, Serialize};
-use std::{
-    io,
-    ops::{Deref, DerefMut},
-};
-use tokio::task::JoinHandle;
+use std::ops::{Deref, DerefMut};
+use tokio::{sync::watch, task::JoinHandle};
 
 /// Represents a raw channel between a manager client and server. Underneath, this routes incoming
 /// and outgoing data from a proxied server to an inmemory transport.
 pub struct RawChannel {
+    id: ManagerChannelId,
+    connection_id: ConnectionId,
     transport: FramedTransport<InmemoryTransport>,
+    watcher: ConnectionWatcher,
+    handoff: ChannelHandoff,
     task: JoinHandle<()>,
 }
 
@@ -23,6 +27,31 @@ impl RawChannel {
         self.task.abort();
     }
 
+    /// Returns the id of the channel with the manager.
+    pub fn id(&self) -> ManagerChannelId {
+        self.id
+    }
+
+    /// Returns the id of the connection with the server behind the channel.
+    pub fn connection_id(&self) -> ConnectionId {
+        self.connection_id
+    }
+
+    /// Clones a handle to hand off this channel to another client, which remains usable after the
+    /// channel is consumed into a client.
+    pub fn clone_handoff(&self) -> ChannelHandoff {
+        self.handoff.clone()
+    }
+
+    /// Clones a watcher over the state of the connection between the manager and the server
+    /// behind this channel, which changes when the manager loses (and finds again) the server.
+    ///
+    /// This is separate from the state of any client made from the channel, as the channel itself
+    /// stays open while the manager reconnects to the server.
+    pub fn clone_connection_watcher(&self) -> ConnectionWatcher {
+        self.watcher.clone()
+    }
+
     /// Consumes this channel, returning a typed client wrapping the transport.
     ///
     /// ### Note
@@ -85,11 +114,44 @@ impl DerefMut for RawChannel {
     }
 }
 
+/// Handle to hand off a [`RawChannel`] so that another client can claim it, leaving everything
+/// started over the channel running rather than killing it
+#[derive(Clone)]
+pub struct ChannelHandoff {
+    channel_id: ManagerChannelId,
+    client: Channel<ManagerRequest, ManagerResponse>,
+}
+
+impl ChannelHandoff {
+    /// Hands off the channel, passing `resources` along to the client that claims it, and
+    /// returning the token to claim it with (see [`ManagerClient::claim_channel`]).
+    ///
+    /// From here on, nothing sent by the server reaches this side of the channel.
+    ///
+    /// [`ManagerClient::claim_channel`]: crate::manager::ManagerClient::claim_channel
+    pub async fn handoff(&mut self, resources: Map) -> Result<String, ManagerError> {
+        let res = self
+            .client
+            .send(ManagerRequest::HandoffChannel {
+                id: self.channel_id,
+                resources,
+            })
+            .await?;
+        match res.payload {
+            ManagerResponse::ChannelHandedOff { token } => Ok(token),
+            ManagerResponse::Error { kind, description } => {
+                Err(ManagerError::Manager { kind, description })
+            }
+            x => Err(ManagerError::unexpected(x)),
+        }
+    }
+}
+
 impl RawChannel {
     pub(super) async fn spawn(
         connection_id: ConnectionId,
         client: &mut Client<ManagerRequest, ManagerResponse>,
-    ) -> io::Result<Self> {
+    ) -> Result<Self, ManagerError> {
         let mut mailbox = client
             .mail(ManagerRequest::OpenChannel { id: connection_id })
             .await?;
@@ -98,22 +160,56 @@ impl RawChannel {
         let channel_id = match mailbox.next().await {
             Some(response) => match response.payload {
                 ManagerResponse::ChannelOpened { id } => Ok(id),
-                ManagerResponse::Error { description } => {
-                    Err(io::Error::new(io::ErrorKind::Other, description))
+                ManagerResponse::Error { kind, description } => {
+                    Err(ManagerError::Manager { kind, description })
                 }
-                x => Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("[Conn {connection_id}] Raw channel open unexpected response: {x:?}"),
-                )),
+                x => Err(ManagerError::unexpected(x)),
             },
-            None => Err(io::Error::new(
-                io::ErrorKind::ConnectionAborted,
-                format!("[Conn {connection_id}] Raw channel mailbox aborted"),
-            )),
+            None => Err(ManagerError::MissingResponse),
         }?;
 
+        Ok(Self::forward(connection_id, channel_id, client, mailbox))
+    }
+
+    /// Claims the channel handed off with `token`, returning it along with the resources that
+    /// the client handing it off said were running over it
+    pub(super) async fn claim(
+        token: String,
+        client: &mut Client<ManagerRequest, ManagerResponse>,
+    ) -> Result<(Self, Map), ManagerError> {
+        let mut mailbox = client.mail(ManagerRequest::ClaimChannel { token }).await?;
+
+        // Wait for the first response, which should be claim confirmation
+        let (channel_id, connection_id, resources) = match mailbox.next().await {
+            Some(response) => match response.payload {
+                ManagerResponse::ChannelClaimed {
+                    id,
+                    connection,
+                    resources,
+                } => Ok((id, connection, resources)),
+                ManagerResponse::Error { kind, description } => {
+                    Err(ManagerError::Manager { kind, description })
+                }
+                x => Err(ManagerError::unexpected(x)),
+            },
+            None => Err(ManagerError::MissingResponse),
+        }?;
+
+        let channel = Self::forward(connection_id, channel_id, client, mailbox);
+        Ok((channel, resources))
+    }
+
+    /// Spawns a task relaying between the channel's responses arriving in `mailbox` and an
+    /// inmemory transport, which becomes the channel
+    fn forward(
+        connection_id: ConnectionId,
+        channel_id: ManagerChannelId,
+        client: &mut Client<ManagerRequest, ManagerResponse>,
+        mut mailbox: Mailbox<Response<ManagerResponse>>,
+    ) -> Self {
         // Spawn our channel proxy transport
         let (mut proxy, transport) = FramedTransport::pair(1);
+        let (watcher_tx, watcher_rx) = watch::channel(ConnectionState::Connected);
 
         let mut manager_channel = client.clone_channel();
         let task = tokio::spawn(async move {
@@ -136,6 +232,10 @@ impl RawChannel {
                             ManagerResponse::ChannelClosed { .. } => {
                                 break;
                             }
+                            ManagerResponse::ChannelState { state, .. } => {
+                                debug!("[Conn {connection_id} :: Chan {channel_id}] Connection is {state}");
+                                watcher_tx.send_replace(state);
+                            }
                             _ => continue,
                         }
                     }
@@ -175,6 +275,16 @@ impl RawChannel {
             }
         });
 
-        Ok(RawChannel { transport, task })
+        RawChannel {
+            id: channel_id,
+            connection_id,
+            transport,
+            watcher: ConnectionWatcher(watcher_rx),
+            handoff: ChannelHandoff {
+                channel_id,
+                client: client.clone_channel(),
+            },
+            task,
+        }
     }
 }
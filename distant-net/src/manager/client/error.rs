@@ -0,0 +1,66 @@
+use crate::common::authentication::msg::Error as AuthenticationError;
+use crate::manager::data::ManagerErrorKind;
+use derive_more::{Display, Error};
+use std::io;
+
+/// Errors that can occur when making requests through a [`ManagerClient`](super::ManagerClient)
+#[derive(Debug, Display, Error)]
+pub enum ManagerError {
+    /// Failed to communicate with the manager
+    #[display(fmt = "{_0}")]
+    Io(io::Error),
+
+    /// Manager was unable to fulfill the request
+    #[display(fmt = "{description}")]
+    Manager {
+        kind: ManagerErrorKind,
+        description: String,
+    },
+
+    /// Authentication with the remote machine failed
+    #[display(fmt = "{_0}")]
+    Authentication(AuthenticationError),
+
+    /// Manager sent back a response that does not belong to the request
+    #[display(fmt = "Got unexpected response: {description}")]
+    UnexpectedResponse { description: String },
+
+    /// Manager stopped responding before the request was completed
+    #[display(fmt = "Missing response from manager")]
+    MissingResponse,
+}
+
+impl ManagerError {
+    /// Returns the [`io::ErrorKind`] that best describes this error
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            Self::Io(x) => x.kind(),
+            Self::Manager { kind, .. } => (*kind).into(),
+            Self::Authentication(_) => io::ErrorKind::PermissionDenied,
+            Self::UnexpectedResponse { .. } => io::ErrorKind::InvalidData,
+            Self::MissingResponse => io::ErrorKind::UnexpectedEof,
+        }
+    }
+
+    /// Creates an error for an unexpected `response`
+    pub(crate) fn unexpected(response: impl std::fmt::Debug) -> Self {
+        Self::UnexpectedResponse {
+            description: format!("{response:?}"),
+        }
+    }
+}
+
+impl From<io::Error> for ManagerError {
+    fn from(x: io::Error) -> Self {
+        Self::Io(x)
+    }
+}
+
+impl From<ManagerError> for io::Error {
+    fn from(x: ManagerError) -> Self {
+        match x {
+            ManagerError::Io(x) => x,
+            x => io::Error::new(x.kind(), x),
+        }
+    }
+}
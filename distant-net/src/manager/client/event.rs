@@ -0,0 +1,30 @@
+use crate::{
+    client::Mailbox,
+    common::Response,
+    manager::data::{ManagerEvent, ManagerResponse},
+};
+use log::*;
+
+/// Stream of [`ManagerEvent`]s received after subscribing through a
+/// [`ManagerClient`](super::ManagerClient)
+pub struct ManagerEventStream {
+    mailbox: Mailbox<Response<ManagerResponse>>,
+}
+
+impl ManagerEventStream {
+    pub(super) fn new(mailbox: Mailbox<Response<ManagerResponse>>) -> Self {
+        Self { mailbox }
+    }
+
+    /// Waits for the next event, returning none once the connection to the manager is closed
+    pub async fn next(&mut self) -> Option<ManagerEvent> {
+        while let Some(res) = self.mailbox.next().await {
+            match res.payload {
+                ManagerResponse::Event { event } => return Some(event),
+                x => warn!("Ignoring unexpected response while subscribed: {x:?}"),
+            }
+        }
+
+        None
+    }
+}
@@ -4,6 +4,12 @@ pub type ManagerAuthenticationId = u32;
 mod capabilities;
 pub use capabilities::*;
 
+mod error;
+pub use error::*;
+
+mod event;
+pub use event::*;
+
 mod info;
 pub use info::*;
 
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// Represents the kind of error reported by the manager, mirroring the subset of
+/// [`io::ErrorKind`] that the manager produces
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManagerErrorKind {
+    /// Some entity was not found
+    NotFound,
+
+    /// Operation lacked the necessary privileges to complete
+    PermissionDenied,
+
+    /// Connection to a remote server was refused
+    ConnectionRefused,
+
+    /// No connection or channel exists with the requested id
+    NotConnected,
+
+    /// A parameter, such as a destination scheme or authentication id, was incorrect
+    InvalidInput,
+
+    /// Some entity, such as a connection name, already exists
+    AlreadyExists,
+
+    /// Operation took too long to complete
+    TimedOut,
+
+    /// Operation is not supported by the manager
+    Unsupported,
+
+    /// Any error not covered by the other kinds
+    #[default]
+    Other,
+}
+
+impl From<io::ErrorKind> for ManagerErrorKind {
+    fn from(kind: io::ErrorKind) -> Self {
+        match kind {
+            io::ErrorKind::NotFound => Self::NotFound,
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+            io::ErrorKind::ConnectionRefused => Self::ConnectionRefused,
+            io::ErrorKind::NotConnected => Self::NotConnected,
+            io::ErrorKind::InvalidInput => Self::InvalidInput,
+            io::ErrorKind::AlreadyExists => Self::AlreadyExists,
+            io::ErrorKind::TimedOut => Self::TimedOut,
+            io::ErrorKind::Unsupported => Self::Unsupported,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl From<ManagerErrorKind> for io::ErrorKind {
+    fn from(kind: ManagerErrorKind) -> Self {
+        match kind {
+            ManagerErrorKind::NotFound => Self::NotFound,
+            ManagerErrorKind::PermissionDenied => Self::PermissionDenied,
+            ManagerErrorKind::ConnectionRefused => Self::ConnectionRefused,
+            ManagerErrorKind::NotConnected => Self::NotConnected,
+            ManagerErrorKind::InvalidInput => Self::InvalidInput,
+            ManagerErrorKind::AlreadyExists => Self::AlreadyExists,
+            ManagerErrorKind::TimedOut => Self::TimedOut,
+            ManagerErrorKind::Unsupported => Self::Unsupported,
+            ManagerErrorKind::Other => Self::Other,
+        }
+    }
+}
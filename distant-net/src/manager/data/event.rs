@@ -0,0 +1,22 @@
+use crate::common::{ConnectionId, Destination};
+use serde::{Deserialize, Serialize};
+
+/// Represents a change to the connections held by the manager, sent to subscribed clients
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields, tag = "type")]
+pub enum ManagerEvent {
+    /// A new connection was established
+    Connected {
+        /// Id of the new connection
+        id: ConnectionId,
+
+        /// Destination of the server the connection is with
+        destination: Destination,
+    },
+
+    /// A connection was killed and removed from the manager
+    Disconnected {
+        /// Id of the removed connection
+        id: ConnectionId,
+    },
+}
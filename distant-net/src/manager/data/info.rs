@@ -7,6 +7,10 @@ pub struct ConnectionInfo {
     /// Connection's id
     pub id: ConnectionId,
 
+    /// Alias given to the connection, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
     /// Destination with which this connection is associated
     pub destination: Destination,
 
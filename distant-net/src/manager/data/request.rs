@@ -51,6 +51,10 @@ pub enum ManagerRequest {
 
         /// Additional options specific to the connection
         options: Map,
+
+        /// Alias to give the connection, which can be used in place of its id
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
     },
 
     /// Submit some authentication message for the manager to use with an active connection
@@ -89,10 +93,36 @@ pub enum ManagerRequest {
         id: ManagerChannelId,
     },
 
+    /// Hands off an open channel so that another client can claim it with the token sent back,
+    /// leaving everything started over the channel (such as processes and watches) running and
+    /// holding on to what the server sends through it in the meantime
+    #[strum_discriminants(strum(message = "Supports handing off a channel to another client"))]
+    HandoffChannel {
+        /// Id of the channel to hand off
+        id: ManagerChannelId,
+
+        /// Details about what is running over the channel, passed along to the claiming client
+        resources: Map,
+    },
+
+    /// Claims a channel handed off by another client, receiving everything sent through it from
+    /// here on (including what was held since the handoff)
+    #[strum_discriminants(strum(
+        message = "Supports claiming a channel handed off by another client"
+    ))]
+    ClaimChannel {
+        /// Token sent back to the client that handed off the channel
+        token: String,
+    },
+
     /// Retrieve information about a specific connection
     #[strum_discriminants(strum(message = "Supports retrieving connection-specific information"))]
     Info { id: ConnectionId },
 
+    /// Look up the id of the connection with the given alias
+    #[strum_discriminants(strum(message = "Supports looking up connections by name"))]
+    Resolve { name: String },
+
     /// Kill a specific connection
     #[strum_discriminants(strum(message = "Supports killing a remote connection"))]
     Kill { id: ConnectionId },
@@ -100,4 +130,19 @@ pub enum ManagerRequest {
     /// Retrieve list of connections being managed
     #[strum_discriminants(strum(message = "Supports retrieving a list of managed connections"))]
     List,
+
+    /// Subscribe to events about connections being established and killed
+    #[strum_discriminants(strum(message = "Supports subscribing to connection events"))]
+    Subscribe,
+
+    /// Retrieve list of clients connected to the manager
+    #[strum_discriminants(strum(message = "Supports retrieving a list of connected clients"))]
+    Clients,
+
+    /// Forcibly disconnect a client connected to the manager
+    #[strum_discriminants(strum(message = "Supports disconnecting a connected client"))]
+    DisconnectClient {
+        /// Id of the client's connection with the manager
+        id: ConnectionId,
+    },
 }
@@ -1,10 +1,14 @@
 use super::{
     ConnectionInfo, ConnectionList, ManagerAuthenticationId, ManagerCapabilities, ManagerChannelId,
+    ManagerErrorKind, ManagerEvent,
 };
+use crate::client::ConnectionState;
 use crate::common::{
-    authentication::msg::Authentication, ConnectionId, Destination, UntypedResponse,
+    authentication::msg::Authentication, ConnectionId, Destination, Map, UntypedResponse,
 };
+use crate::server::ClientInfo;
 use serde::{Deserialize, Serialize};
+use std::io;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case", deny_unknown_fields, tag = "type")]
@@ -13,7 +17,14 @@ pub enum ManagerResponse {
     Killed,
 
     /// Indicates that some error occurred during a request
-    Error { description: String },
+    Error {
+        /// Kind of error that occurred
+        #[serde(default)]
+        kind: ManagerErrorKind,
+
+        /// Description of the error
+        description: String,
+    },
 
     /// Response to retrieving information about the manager's capabilities
     Capabilities { supported: ManagerCapabilities },
@@ -27,6 +38,9 @@ pub enum ManagerResponse {
     /// Confirmation of a connection being established
     Connected { id: ConnectionId },
 
+    /// Id of the connection with the requested alias
+    Resolved { id: ConnectionId },
+
     /// Authentication information being sent to a client
     Authenticate {
         /// Id tied to authentication information in case a response is needed
@@ -62,11 +76,52 @@ pub enum ManagerResponse {
         /// Id of the channel
         id: ManagerChannelId,
     },
+
+    /// Token with which another client can claim a channel that was handed off
+    ChannelHandedOff {
+        /// Token to claim the channel with
+        token: String,
+    },
+
+    /// Indicates that a channel handed off by another client has been claimed
+    ChannelClaimed {
+        /// Id of the channel
+        id: ManagerChannelId,
+
+        /// Id of the connection that the channel belongs to
+        connection: ConnectionId,
+
+        /// Details about what is running over the channel, as given when it was handed off
+        resources: Map,
+    },
+
+    /// Indicates that the connection with the server behind a channel has changed state, such as
+    /// when the server stops responding and the manager begins reconnecting to it
+    ChannelState {
+        /// Id of the channel
+        id: ManagerChannelId,
+
+        /// New state of the connection
+        state: ConnectionState,
+    },
+
+    /// Confirmation of subscribing to connection events
+    Subscribed,
+
+    /// Event sent to a subscribed client
+    Event { event: ManagerEvent },
+
+    /// List of clients connected to the manager
+    Clients { clients: Vec<ClientInfo> },
+
+    /// Acknowledgement that a client was disconnected
+    ClientDisconnected,
 }
 
-impl<T: std::error::Error> From<T> for ManagerResponse {
-    fn from(x: T) -> Self {
+impl From<io::Error> for ManagerResponse {
+    fn from(x: io::Error) -> Self {
         Self::Error {
+            kind: x.kind().into(),
             description: x.to_string(),
         }
     }
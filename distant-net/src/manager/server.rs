@@ -1,10 +1,13 @@
 use crate::{
-    common::{authentication::msg::AuthenticationResponse, ConnectionId, Destination, Map},
+    client::{ConnectionState, ConnectionWatcher},
+    common::{
+        authentication::msg::AuthenticationResponse, ConnectionId, Destination, HeapSecretKey, Map,
+    },
     manager::{
         ConnectionInfo, ConnectionList, ManagerAuthenticationId, ManagerCapabilities,
-        ManagerChannelId, ManagerRequest, ManagerResponse,
+        ManagerChannelId, ManagerErrorKind, ManagerEvent, ManagerRequest, ManagerResponse,
     },
-    server::{Server, ServerCtx, ServerHandler},
+    server::{Server, ServerCtx, ServerHandler, ServerReply},
 };
 use async_trait::async_trait;
 use log::*;
@@ -29,11 +32,17 @@ pub struct ManagerServer {
     config: Config,
 
     /// Mapping of connection id -> connection
-    connections: RwLock<HashMap<ConnectionId, ManagerConnection>>,
+    connections: Arc<RwLock<HashMap<ConnectionId, ManagerConnection>>>,
 
     /// Mapping of auth id -> callback
     registry:
         Arc<RwLock<HashMap<ManagerAuthenticationId, oneshot::Sender<AuthenticationResponse>>>>,
+
+    /// Replies of clients subscribed to connection events
+    subscribers: Arc<RwLock<Vec<ServerReply<ManagerResponse>>>>,
+
+    /// Mapping of handoff token -> channel handed off along with the resources running over it
+    handoffs: Arc<RwLock<HashMap<String, (ManagerChannel, Map)>>>,
 }
 
 impl ManagerServer {
@@ -43,8 +52,10 @@ impl ManagerServer {
     pub fn new(config: Config) -> Server<Self> {
         Server::new().handler(Self {
             config,
-            connections: RwLock::new(HashMap::new()),
+            connections: Arc::new(RwLock::new(HashMap::new())),
             registry: Arc::new(RwLock::new(HashMap::new())),
+            subscribers: Arc::new(RwLock::new(Vec::new())),
+            handoffs: Arc::new(RwLock::new(HashMap::new())),
         })
     }
 
@@ -90,13 +101,18 @@ impl ManagerServer {
 
     /// Connects to a new server at the specified `destination` using the given `options` information
     /// and authentication client (if needed) to retrieve additional information needed to
-    /// establish the connection to the server
+    /// establish the connection to the server, giving the connection the alias `name` if provided
     async fn connect(
         &self,
         destination: Destination,
         options: Map,
+        name: Option<String>,
         mut authenticator: ManagerAuthenticator,
     ) -> io::Result<ConnectionId> {
+        if let Some(name) = name.as_deref() {
+            self.validate_name(name).await?;
+        }
+
         let scheme = match destination.scheme.as_deref() {
             Some(scheme) => {
                 trace!("Using scheme {}", scheme);
@@ -124,12 +140,95 @@ impl ManagerServer {
                 .await?
         };
 
-        let connection = ManagerConnection::spawn(destination, options, client).await?;
+        let watcher = client.clone_connection_watcher();
+        let mut connection = ManagerConnection::spawn(
+            destination.clone(),
+            options,
+            client,
+            self.config.cacheable.clone(),
+        )
+        .await?;
         let id = connection.id;
-        self.connections.write().await.insert(id, connection);
+        connection.name = name;
+
+        // Another connection may have taken the name while this one was being established
+        let mut connections = self.connections.write().await;
+        if let Some(name) = connection.name.as_deref() {
+            if connections
+                .values()
+                .any(|conn| conn.name.as_deref() == Some(name))
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("Connection name {name} is already in use"),
+                ));
+            }
+        }
+        connections.insert(id, connection);
+        drop(connections);
+
+        self.notify(ManagerEvent::Connected { id, destination })
+            .await;
+        self.remove_when_lost(id, watcher);
         Ok(id)
     }
 
+    /// Spawns a task that removes the connection with `id` once `watcher` reports that its
+    /// server can no longer be reached, telling subscribers as if it had been killed
+    fn remove_when_lost(&self, id: ConnectionId, mut watcher: ConnectionWatcher) {
+        let connections = Arc::clone(&self.connections);
+        let subscribers = Arc::clone(&self.subscribers);
+        let handoffs = Arc::clone(&self.handoffs);
+        tokio::spawn(async move {
+            // No more changes means that the client is gone along with its connection
+            while let Some(state) = watcher.next().await {
+                if state == ConnectionState::Disconnected {
+                    break;
+                }
+            }
+
+            if remove_connection(&connections, &handoffs, id).await {
+                debug!("[Conn {id}] Removing as server is no longer reachable");
+                notify(&subscribers, ManagerEvent::Disconnected { id }).await;
+            }
+        });
+    }
+
+    /// Fails if `name` cannot be used as an alias, either because it would be confused with a
+    /// connection id or because another connection already has it
+    async fn validate_name(&self, name: &str) -> io::Result<()> {
+        if name.is_empty() || name.parse::<ConnectionId>().is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Connection name {name:?} must not be empty or a number"),
+            ));
+        }
+
+        match self.resolve(name).await {
+            Ok(id) => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Connection name {name} is already in use by connection {id}"),
+            )),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Retrieves the id of the connection with the alias `name`
+    async fn resolve(&self, name: &str) -> io::Result<ConnectionId> {
+        self.connections
+            .read()
+            .await
+            .values()
+            .find(|conn| conn.name.as_deref() == Some(name))
+            .map(|conn| conn.id)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No connection found with name {name}"),
+                )
+            })
+    }
+
     /// Retrieves the list of supported capabilities for this manager
     async fn capabilities(&self) -> io::Result<ManagerCapabilities> {
         Ok(ManagerCapabilities::all())
@@ -140,6 +239,7 @@ impl ManagerServer {
         match self.connections.read().await.get(&id) {
             Some(connection) => Ok(ConnectionInfo {
                 id: connection.id,
+                name: connection.name.clone(),
                 destination: connection.destination.clone(),
                 options: connection.options.clone(),
             }),
@@ -164,14 +264,107 @@ impl ManagerServer {
 
     /// Kills the connection to the server with the specified `id`
     async fn kill(&self, id: ConnectionId) -> io::Result<()> {
-        match self.connections.write().await.remove(&id) {
-            Some(_) => Ok(()),
-            None => Err(io::Error::new(
+        if remove_connection(&self.connections, &self.handoffs, id).await {
+            self.notify(ManagerEvent::Disconnected { id }).await;
+            Ok(())
+        } else {
+            Err(io::Error::new(
                 io::ErrorKind::NotConnected,
                 "No connection found",
-            )),
+            ))
         }
     }
+
+    /// Hands off `channel`, holding on to what the server sends through it until another client
+    /// claims it with the returned token, closing it if it goes unclaimed for too long
+    async fn handoff(&self, channel: ManagerChannel, resources: Map) -> io::Result<String> {
+        channel.hold()?;
+        let token = HeapSecretKey::generate(32)?.to_string();
+        self.handoffs
+            .write()
+            .await
+            .insert(token.clone(), (channel, resources));
+
+        let handoffs = Arc::clone(&self.handoffs);
+        let timeout = self.config.handoff_timeout;
+        tokio::spawn({
+            let token = token.clone();
+            async move {
+                tokio::time::sleep(timeout).await;
+                if let Some((channel, _)) = handoffs.write().await.remove(&token) {
+                    debug!("Closing channel {} as it was never claimed", channel.id());
+                    if let Err(x) = channel.close() {
+                        debug!("{x}");
+                    }
+                }
+            }
+        });
+
+        Ok(token)
+    }
+
+    /// Takes the channel handed off with `token` along with the resources running over it, which
+    /// can only be done once
+    async fn claim(&self, token: &str) -> io::Result<(ManagerChannel, Map)> {
+        self.handoffs.write().await.remove(token).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "No channel handed off with that token",
+            )
+        })
+    }
+
+    /// Registers `reply` to receive future connection events, confirming the subscription
+    /// before any event is sent through it
+    async fn subscribe(&self, reply: ServerReply<ManagerResponse>) -> io::Result<()> {
+        reply.send(ManagerResponse::Subscribed).await?;
+        self.subscribers.write().await.push(reply);
+        Ok(())
+    }
+
+    /// Sends `event` to all subscribed clients, dropping any whose connection has closed
+    async fn notify(&self, event: ManagerEvent) {
+        notify(&self.subscribers, event).await
+    }
+}
+
+/// Removes the connection with `id` along with the channels handed off from it, returning true
+/// if there was such a connection
+async fn remove_connection(
+    connections: &RwLock<HashMap<ConnectionId, ManagerConnection>>,
+    handoffs: &RwLock<HashMap<String, (ManagerChannel, Map)>>,
+    id: ConnectionId,
+) -> bool {
+    if connections.write().await.remove(&id).is_none() {
+        return false;
+    }
+
+    handoffs
+        .write()
+        .await
+        .retain(|_, (channel, _)| channel.connection_id() != id);
+    true
+}
+
+/// Sends `event` to each of `subscribers`, dropping any whose connection has closed, where the
+/// subscribers are not locked while sending so that one that is slow to read holds up nothing
+/// but the event
+async fn notify(subscribers: &RwLock<Vec<ServerReply<ManagerResponse>>>, event: ManagerEvent) {
+    let replies = subscribers.read().await.clone();
+    let mut closed = false;
+    for reply in replies {
+        let response = ManagerResponse::Event {
+            event: event.clone(),
+        };
+        if let Err(x) = reply.send(response).await {
+            debug!("Dropping subscriber: {x}");
+            closed = true;
+        }
+    }
+
+    if closed {
+        subscribers.write().await.retain(|reply| !reply.is_closed());
+    }
 }
 
 #[derive(Default)]
@@ -181,6 +374,16 @@ pub struct DistantManagerServerConnection {
     channels: RwLock<HashMap<ManagerChannelId, ManagerChannel>>,
 }
 
+impl Drop for DistantManagerServerConnection {
+    /// Closes the channels still open once the client goes away, as nothing is left to read what
+    /// they send back
+    fn drop(&mut self) {
+        for channel in self.channels.get_mut().values() {
+            let _ = channel.close();
+        }
+    }
+}
+
 #[async_trait]
 impl ServerHandler for ManagerServer {
     type Request = ManagerRequest;
@@ -193,6 +396,7 @@ impl ServerHandler for ManagerServer {
             request,
             reply,
             local_data,
+            clients,
         } = ctx;
 
         let response = match request.payload {
@@ -220,10 +424,12 @@ impl ServerHandler for ManagerServer {
             ManagerRequest::Connect {
                 destination,
                 options,
+                name,
             } => match self
                 .connect(
                     *destination,
                     options,
+                    name,
                     ManagerAuthenticator {
                         reply: reply.clone(),
                         registry: Arc::clone(&self.registry),
@@ -239,6 +445,7 @@ impl ServerHandler for ManagerServer {
                     Some(cb) => match cb.send(msg) {
                         Ok(_) => return,
                         Err(_) => ManagerResponse::Error {
+                            kind: ManagerErrorKind::Other,
                             description: "Unable to forward authentication callback".to_string(),
                         },
                     },
@@ -293,10 +500,55 @@ impl ServerHandler for ManagerServer {
                     )),
                 }
             }
+            ManagerRequest::HandoffChannel { id, resources } => {
+                match local_data.channels.write().await.remove(&id) {
+                    Some(channel) => match self.handoff(channel, resources).await {
+                        Ok(token) => {
+                            debug!("Channel {id} has been handed off");
+                            ManagerResponse::ChannelHandedOff { token }
+                        }
+                        Err(x) => ManagerResponse::from(x),
+                    },
+                    None => ManagerResponse::from(io::Error::new(
+                        io::ErrorKind::NotConnected,
+                        "Channel is not open or does not exist",
+                    )),
+                }
+            }
+            ManagerRequest::ClaimChannel { token } => match self.claim(&token).await {
+                Ok((channel, resources)) => {
+                    let id = channel.id();
+                    debug!("Channel {id} has been claimed");
+                    local_data
+                        .channels
+                        .write()
+                        .await
+                        .insert(id, channel.clone());
+
+                    // Confirm the claim before anything held for the channel is sent
+                    let response = ManagerResponse::ChannelClaimed {
+                        id,
+                        connection: channel.connection_id(),
+                        resources,
+                    };
+                    if let Err(x) = reply.send(response).await {
+                        error!("[Conn {}] {}", connection_id, x);
+                    }
+                    if let Err(x) = channel.claim(reply) {
+                        error!("[Conn {}] {}", connection_id, x);
+                    }
+                    return;
+                }
+                Err(x) => ManagerResponse::from(x),
+            },
             ManagerRequest::Info { id } => match self.info(id).await {
                 Ok(info) => ManagerResponse::Info(info),
                 Err(x) => ManagerResponse::from(x),
             },
+            ManagerRequest::Resolve { name } => match self.resolve(&name).await {
+                Ok(id) => ManagerResponse::Resolved { id },
+                Err(x) => ManagerResponse::from(x),
+            },
             ManagerRequest::List => match self.list().await {
                 Ok(list) => ManagerResponse::List(list),
                 Err(x) => ManagerResponse::from(x),
@@ -305,6 +557,24 @@ impl ServerHandler for ManagerServer {
                 Ok(()) => ManagerResponse::Killed,
                 Err(x) => ManagerResponse::from(x),
             },
+            ManagerRequest::Subscribe => match self.subscribe(reply.clone()).await {
+                Ok(()) => return,
+                Err(x) => ManagerResponse::from(x),
+            },
+            ManagerRequest::Clients => ManagerResponse::Clients {
+                clients: clients.list().await,
+            },
+            // The reply would never make it back to a client that disconnects itself
+            ManagerRequest::DisconnectClient { id } if id == connection_id => {
+                ManagerResponse::Error {
+                    kind: ManagerErrorKind::InvalidInput,
+                    description: format!("Client {id} is the one making the request"),
+                }
+            }
+            ManagerRequest::DisconnectClient { id } => match clients.disconnect(id).await {
+                Ok(()) => ManagerResponse::ClientDisconnected,
+                Err(x) => ManagerResponse::from(x),
+            },
         };
 
         if let Err(x) = reply.send(response).await {
@@ -320,6 +590,7 @@ mod tests {
     use crate::common::FramedTransport;
     use crate::server::ServerReply;
     use crate::{boxed_connect_handler, boxed_launch_handler};
+    use std::time::Duration;
     use tokio::sync::mpsc;
 
     fn test_config() -> Config {
@@ -330,6 +601,8 @@ mod tests {
             user: false,
             launch_handlers: HashMap::new(),
             connect_handlers: HashMap::new(),
+            cacheable: None,
+            handoff_timeout: Duration::from_secs(60),
         }
     }
 
@@ -352,8 +625,10 @@ mod tests {
 
         let server = ManagerServer {
             config,
-            connections: RwLock::new(HashMap::new()),
+            connections: Arc::new(RwLock::new(HashMap::new())),
             registry,
+            subscribers: Arc::new(RwLock::new(Vec::new())),
+            handoffs: Arc::new(RwLock::new(HashMap::new())),
         };
 
         (server, authenticator)
@@ -424,7 +699,7 @@ mod tests {
         let destination = "scheme://host".parse::<Destination>().unwrap();
         let options = "".parse::<Map>().unwrap();
         let err = server
-            .connect(destination, options, authenticator)
+            .connect(destination, options, None, authenticator)
             .await
             .unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{:?}", err);
@@ -446,7 +721,7 @@ mod tests {
         let destination = "scheme://host".parse::<Destination>().unwrap();
         let options = "".parse::<Map>().unwrap();
         let err = server
-            .connect(destination, options, authenticator)
+            .connect(destination, options, None, authenticator)
             .await
             .unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::Other);
@@ -467,7 +742,7 @@ mod tests {
         let destination = "scheme://host".parse::<Destination>().unwrap();
         let options = "key=value".parse::<Map>().unwrap();
         let id = server
-            .connect(destination, options, authenticator)
+            .connect(destination, options, None, authenticator)
             .await
             .unwrap();
 
@@ -478,6 +753,68 @@ mod tests {
         assert_eq!(connection.options, "key=value".parse().unwrap());
     }
 
+    #[tokio::test]
+    async fn connect_should_give_new_connection_a_name_that_resolves_to_its_id() {
+        let mut config = test_config();
+
+        let handler = boxed_connect_handler!(|_a, _b, _c| { Ok(detached_untyped_client()) });
+
+        config
+            .connect_handlers
+            .insert("scheme".to_string(), handler);
+
+        let (server, authenticator) = setup(config);
+        let destination = "scheme://host".parse::<Destination>().unwrap();
+        let id = server
+            .connect(
+                destination,
+                Map::new(),
+                Some(String::from("myserver")),
+                authenticator,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(server.resolve("myserver").await.unwrap(), id);
+        assert_eq!(
+            server.info(id).await.unwrap().name.as_deref(),
+            Some("myserver")
+        );
+
+        let err = server.resolve("other").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound, "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn connect_should_fail_if_name_is_a_number_or_already_in_use() {
+        let mut config = test_config();
+
+        let handler = boxed_connect_handler!(|_a, _b, _c| { Ok(detached_untyped_client()) });
+
+        config
+            .connect_handlers
+            .insert("scheme".to_string(), handler);
+
+        let (server, authenticator) = setup(config);
+        let destination = "scheme://host".parse::<Destination>().unwrap();
+        let connect = |name: &str| {
+            server.connect(
+                destination.clone(),
+                Map::new(),
+                Some(name.to_string()),
+                authenticator.clone(),
+            )
+        };
+
+        connect("myserver").await.unwrap();
+
+        let err = connect("myserver").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists, "{:?}", err);
+
+        let err = connect("123").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{:?}", err);
+    }
+
     #[tokio::test]
     async fn info_should_fail_if_no_connection_found_for_specified_id() {
         let (server, _) = setup(test_config());
@@ -494,6 +831,7 @@ mod tests {
             "scheme://host".parse().unwrap(),
             "key=value".parse().unwrap(),
             detached_untyped_client(),
+            None,
         )
         .await
         .unwrap();
@@ -505,6 +843,7 @@ mod tests {
             info,
             ConnectionInfo {
                 id,
+                name: None,
                 destination: "scheme://host".parse().unwrap(),
                 options: "key=value".parse().unwrap(),
             }
@@ -527,6 +866,7 @@ mod tests {
             "scheme://host".parse().unwrap(),
             "key=value".parse().unwrap(),
             detached_untyped_client(),
+            None,
         )
         .await
         .unwrap();
@@ -537,6 +877,7 @@ mod tests {
             "other://host2".parse().unwrap(),
             "key=value".parse().unwrap(),
             detached_untyped_client(),
+            None,
         )
         .await
         .unwrap();
@@ -570,6 +911,7 @@ mod tests {
             "scheme://host".parse().unwrap(),
             "key=value".parse().unwrap(),
             detached_untyped_client(),
+            None,
         )
         .await
         .unwrap();
@@ -581,4 +923,174 @@ mod tests {
         let lock = server.connections.read().await;
         assert!(!lock.contains_key(&id), "Connection still exists");
     }
+
+    #[tokio::test]
+    async fn claim_should_fail_if_no_channel_handed_off_with_token() {
+        let (server, _) = setup(test_config());
+
+        let err = server.claim("token").await.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound, "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn claim_should_return_channel_handed_off_with_token_only_once() {
+        let (server, _) = setup(test_config());
+
+        let connection = ManagerConnection::spawn(
+            "scheme://host".parse().unwrap(),
+            "key=value".parse().unwrap(),
+            detached_untyped_client(),
+            None,
+        )
+        .await
+        .unwrap();
+        let channel = connection
+            .open_channel(ServerReply {
+                origin_id: "origin".to_string(),
+                tx: mpsc::channel(1).0,
+            })
+            .unwrap();
+        let channel_id = channel.id();
+
+        let token = server
+            .handoff(channel, "process=123".parse().unwrap())
+            .await
+            .unwrap();
+
+        let (channel, resources) = server.claim(&token).await.unwrap();
+        assert_eq!(channel.id(), channel_id);
+        assert_eq!(channel.connection_id(), connection.id);
+        assert_eq!(resources, "process=123".parse().unwrap());
+
+        let err = server.claim(&token).await.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound, "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn claim_should_fail_once_channel_has_gone_unclaimed_for_too_long() {
+        let mut config = test_config();
+        config.handoff_timeout = Duration::from_millis(50);
+        let (server, _) = setup(config);
+
+        let connection = ManagerConnection::spawn(
+            "scheme://host".parse().unwrap(),
+            "key=value".parse().unwrap(),
+            detached_untyped_client(),
+            None,
+        )
+        .await
+        .unwrap();
+        let channel = connection
+            .open_channel(ServerReply {
+                origin_id: "origin".to_string(),
+                tx: mpsc::channel(1).0,
+            })
+            .unwrap();
+
+        let token = server.handoff(channel, Map::new()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(250)).await;
+
+        let err = server.claim(&token).await.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound, "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn kill_should_drop_channels_handed_off_for_connection() {
+        let (server, _) = setup(test_config());
+
+        let connection = ManagerConnection::spawn(
+            "scheme://host".parse().unwrap(),
+            "key=value".parse().unwrap(),
+            detached_untyped_client(),
+            None,
+        )
+        .await
+        .unwrap();
+        let id = connection.id;
+        let channel = connection
+            .open_channel(ServerReply {
+                origin_id: "origin".to_string(),
+                tx: mpsc::channel(1).0,
+            })
+            .unwrap();
+        server.connections.write().await.insert(id, connection);
+
+        let token = server.handoff(channel, Map::new()).await.unwrap();
+        server.kill(id).await.unwrap();
+
+        let err = server.claim(&token).await.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound, "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn subscribe_should_confirm_subscription_and_forward_connection_events() {
+        let mut config = test_config();
+
+        let handler = boxed_connect_handler!(|_a, _b, _c| { Ok(detached_untyped_client()) });
+
+        config
+            .connect_handlers
+            .insert("scheme".to_string(), handler);
+
+        let (server, authenticator) = setup(config);
+        let (tx, mut rx) = mpsc::channel(10);
+        server
+            .subscribe(ServerReply {
+                origin_id: "origin".to_string(),
+                tx,
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            rx.recv().await.unwrap().payload,
+            ManagerResponse::Subscribed
+        ));
+
+        let destination = "scheme://host".parse::<Destination>().unwrap();
+        let options = "key=value".parse::<Map>().unwrap();
+        let id = server
+            .connect(destination, options, None, authenticator)
+            .await
+            .unwrap();
+        server.kill(id).await.unwrap();
+
+        let res = rx.recv().await.unwrap();
+        assert_eq!(res.origin_id, "origin");
+        match res.payload {
+            ManagerResponse::Event { event } => assert_eq!(
+                event,
+                ManagerEvent::Connected {
+                    id,
+                    destination: "scheme://host".parse().unwrap(),
+                }
+            ),
+            x => panic!("Unexpected response: {x:?}"),
+        }
+
+        match rx.recv().await.unwrap().payload {
+            ManagerResponse::Event { event } => {
+                assert_eq!(event, ManagerEvent::Disconnected { id })
+            }
+            x => panic!("Unexpected response: {x:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn notify_should_drop_subscribers_whose_connection_has_closed() {
+        let (server, _) = setup(test_config());
+
+        let (tx, rx) = mpsc::channel(10);
+        server
+            .subscribe(ServerReply {
+                origin_id: "origin".to_string(),
+                tx,
+            })
+            .await
+            .unwrap();
+        assert_eq!(server.subscribers.read().await.len(), 1);
+        drop(rx);
+
+        server.notify(ManagerEvent::Disconnected { id: 123 }).await;
+        assert!(server.subscribers.read().await.is_empty());
+    }
 }
@@ -1,5 +1,22 @@
 use super::{BoxedConnectHandler, BoxedLaunchHandler};
-use std::collections::HashMap;
+use crate::common::{UntypedRequest, UntypedResponse};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// Decides which responses of a connection the manager caches, answering repeats of their
+/// requests itself once the server has answered them once
+#[derive(Clone)]
+pub struct CachePolicy {
+    /// Picks the requests whose responses stay the same over the life of a connection
+    pub request: Arc<dyn Fn(&UntypedRequest<'_>) -> bool + Send + Sync>,
+
+    /// Picks the responses to those requests that can be cached, such as those that are not
+    /// errors that a later attempt may not run into
+    pub response: Arc<dyn Fn(&UntypedResponse<'_>) -> bool + Send + Sync>,
+
+    /// Picks the requests that change how the server answers others, such as those that change
+    /// the paths it reports, which clear the cache
+    pub invalidates: Arc<dyn Fn(&UntypedRequest<'_>) -> bool + Send + Sync>,
+}
 
 /// Configuration settings for a manager.
 pub struct Config {
@@ -20,6 +37,13 @@ pub struct Config {
 
     /// Handlers to use for connect requests
     pub connect_handlers: HashMap<String, BoxedConnectHandler>,
+
+    /// Requests whose responses are cached for each connection (until it reconnects), answering
+    /// repeats of them without a round trip to the server
+    pub cacheable: Option<CachePolicy>,
+
+    /// Time that a channel handed off is held for another client to claim before it is closed
+    pub handoff_timeout: Duration,
 }
 
 impl Default for Config {
@@ -35,6 +59,8 @@ impl Default for Config {
             user: false,
             launch_handlers: HashMap::new(),
             connect_handlers: HashMap::new(),
+            cacheable: None,
+            handoff_timeout: Duration::from_secs(5 * 60),
         }
     }
 }
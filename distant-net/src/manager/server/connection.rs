@@ -1,16 +1,38 @@
+use super::CachePolicy;
 use crate::{
-    client::{Mailbox, UntypedClient},
+    client::{ConnectionState, Mailbox, UntypedClient},
     common::{ConnectionId, Destination, Map, UntypedRequest, UntypedResponse},
     manager::data::{ManagerChannelId, ManagerResponse},
     server::ServerReply,
 };
 use log::*;
-use std::{collections::HashMap, io};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet, VecDeque},
+    io,
+    time::Duration,
+};
 use tokio::{sync::mpsc, task::JoinHandle};
 
+/// Most responses held for a channel that was handed off, beyond which the channel is closed
+/// rather than have its client miss any of them
+const MAX_HELD_RESPONSES: usize = 1024;
+
+/// Most responses queued for the client of a channel before the channel is dropped as stalled
+const MAX_QUEUED_RESPONSES: usize = 1024;
+
+/// Longest time spent waiting on the client of a channel to take a response before the channel is
+/// dropped as stalled
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Most cacheable requests waiting on a response at once, beyond which further requests are sent
+/// on without caching their responses
+const MAX_PENDING_CACHEABLE: usize = 64;
+
 /// Represents a connection a distant manager has with some distant-compatible server
 pub struct ManagerConnection {
     pub id: ConnectionId,
+    pub name: Option<String>,
     pub destination: Destination,
     pub options: Map,
     tx: mpsc::UnboundedSender<Action>,
@@ -18,10 +40,12 @@ pub struct ManagerConnection {
     action_task: JoinHandle<()>,
     request_task: JoinHandle<()>,
     response_task: JoinHandle<()>,
+    watcher_task: JoinHandle<()>,
 }
 
 #[derive(Clone)]
 pub struct ManagerChannel {
+    connection_id: ConnectionId,
     channel_id: ManagerChannelId,
     tx: mpsc::UnboundedSender<Action>,
 }
@@ -32,6 +56,11 @@ impl ManagerChannel {
         self.channel_id
     }
 
+    /// Returns the id of the connection that the channel belongs to.
+    pub fn connection_id(&self) -> ConnectionId {
+        self.connection_id
+    }
+
     /// Sends the untyped request to the server on the other side of the channel.
     pub fn send(&self, req: UntypedRequest<'static>) -> io::Result<()> {
         let id = self.channel_id;
@@ -54,19 +83,56 @@ impl ManagerChannel {
             )
         })
     }
+
+    /// Stops sending responses to the client that opened the channel, holding on to them until
+    /// another client claims the channel with [`ManagerChannel::claim`]. If too many responses
+    /// come in before then, the channel is closed and its claimant told so.
+    pub fn hold(&self) -> io::Result<()> {
+        let id = self.channel_id;
+        self.tx.send(Action::Hold { id }).map_err(|x| {
+            io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                format!("channel {id} hold failed: {x}"),
+            )
+        })
+    }
+
+    /// Sends responses to `reply` going forward, starting with those held since the channel was
+    /// handed off.
+    pub fn claim(&self, reply: ServerReply<ManagerResponse>) -> io::Result<()> {
+        let id = self.channel_id;
+        self.tx.send(Action::Register { id, reply }).map_err(|x| {
+            io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                format!("channel {id} claim failed: {x}"),
+            )
+        })
+    }
 }
 
 impl ManagerConnection {
+    /// Spawns the tasks of a connection over `client`, caching the responses to requests deemed
+    /// cacheable by `cacheable` until the client reconnects
     pub async fn spawn(
         spawn: Destination,
         options: Map,
         client: UntypedClient,
+        cacheable: Option<CachePolicy>,
     ) -> io::Result<Self> {
         let connection_id = rand::random();
         let (tx, rx) = mpsc::unbounded_channel();
 
         let (request_tx, request_rx) = mpsc::unbounded_channel();
-        let action_task = tokio::spawn(action_task(connection_id, rx, request_tx));
+        let action_task = tokio::spawn(action_task(connection_id, rx, request_tx, cacheable));
+
+        // Channels are told when the server is lost or found again, and responses cached from
+        // before reconnecting may no longer hold for the server reached
+        let watcher_task = client.on_connection_change({
+            let tx = tx.clone();
+            move |state| {
+                let _ = tx.send(Action::StateChanged { state });
+            }
+        });
         let response_task = tokio::spawn(response_task(
             connection_id,
             client.assign_default_mailbox(100).await?,
@@ -76,12 +142,14 @@ impl ManagerConnection {
 
         Ok(Self {
             id: connection_id,
+            name: None,
             destination: spawn,
             options,
             tx,
             action_task,
             request_task,
             response_task,
+            watcher_task,
         })
     }
 
@@ -99,6 +167,7 @@ impl ManagerConnection {
                 )
             })?;
         Ok(ManagerChannel {
+            connection_id: self.id,
             channel_id,
             tx: self.tx.clone(),
         })
@@ -110,6 +179,7 @@ impl Drop for ManagerConnection {
         self.action_task.abort();
         self.request_task.abort();
         self.response_task.abort();
+        self.watcher_task.abort();
     }
 }
 
@@ -123,6 +193,10 @@ enum Action {
         id: ManagerChannelId,
     },
 
+    Hold {
+        id: ManagerChannelId,
+    },
+
     Read {
         res: UntypedResponse<'static>,
     },
@@ -131,6 +205,10 @@ enum Action {
         id: ManagerChannelId,
         req: UntypedRequest<'static>,
     },
+
+    StateChanged {
+        state: ConnectionState,
+    },
 }
 
 /// Internal task to process outgoing [`UntypedRequest`]s.
@@ -159,27 +237,109 @@ async fn response_task(
     }
 }
 
+/// Spawns a task to send the responses of channel `id` to `reply` in order, returning the queue
+/// that feeds it. Each channel gets its own task so that a client that has gone away or stopped
+/// reading cannot hold up the other channels of the connection.
+fn forward(
+    connection_id: ConnectionId,
+    id: ManagerChannelId,
+    reply: ServerReply<ManagerResponse>,
+) -> mpsc::Sender<ManagerResponse> {
+    let (tx, mut rx) = mpsc::channel(MAX_QUEUED_RESPONSES);
+    tokio::spawn(async move {
+        while let Some(response) = rx.recv().await {
+            match tokio::time::timeout(REPLY_TIMEOUT, reply.send(response)).await {
+                Ok(Ok(())) => (),
+                Ok(Err(x)) => {
+                    debug!("[Conn {connection_id} :: Chan {id}] {x}");
+                    break;
+                }
+                Err(_) => {
+                    warn!("[Conn {connection_id} :: Chan {id}] Client stopped reading responses");
+                    break;
+                }
+            }
+        }
+    });
+    tx
+}
+
+/// Queues `response` for the client of channel `id`, unregistering the channel if its client has
+/// gone away or fallen too far behind.
+fn send_to_channel(
+    connection_id: ConnectionId,
+    registered: &mut HashMap<ManagerChannelId, mpsc::Sender<ManagerResponse>>,
+    id: ManagerChannelId,
+    response: ManagerResponse,
+) {
+    if let Some(tx) = registered.get(&id) {
+        if let Err(x) = tx.try_send(response) {
+            if let mpsc::error::TrySendError::Full(_) = x {
+                warn!("[Conn {connection_id} :: Chan {id}] Dropping stalled channel");
+            }
+            registered.remove(&id);
+        }
+    }
+}
+
 /// Internal task to process [`Action`] items.
 ///
 /// * `id` - the id of the connection.
 /// * `rx` - used to receive new [`Action`]s to process.
 /// * `tx` - used to send outgoing requests through the connection.
+/// * `cacheable` - used to pick the requests whose responses are cached.
 async fn action_task(
     id: ConnectionId,
     mut rx: mpsc::UnboundedReceiver<Action>,
     tx: mpsc::UnboundedSender<UntypedRequest<'static>>,
+    cacheable: Option<CachePolicy>,
 ) {
-    let mut registered = HashMap::new();
+    let connection_id = id;
+    let mut registered: HashMap<ManagerChannelId, mpsc::Sender<ManagerResponse>> = HashMap::new();
+
+    // Responses for channels that were handed off, waiting on another client to claim them,
+    // along with the channels closed while handed off for having too many responses held
+    let mut held: HashMap<ManagerChannelId, VecDeque<ManagerResponse>> = HashMap::new();
+    let mut overflowed: HashSet<ManagerChannelId> = HashSet::new();
+
+    // Payloads of cached responses keyed by the payloads of their requests, along with the
+    // payloads of cacheable requests still waiting on a response keyed by their (combined) ids
+    let mut cache: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+    let mut pending: HashMap<String, Vec<u8>> = HashMap::new();
 
     while let Some(action) = rx.recv().await {
         match action {
             Action::Register { id, reply } => {
-                registered.insert(id, reply);
+                registered.insert(id, forward(connection_id, id, reply));
+                for response in held.remove(&id).unwrap_or_default() {
+                    send_to_channel(connection_id, &mut registered, id, response);
+                }
+
+                if overflowed.remove(&id) {
+                    let response = ManagerResponse::ChannelClosed { id };
+                    send_to_channel(connection_id, &mut registered, id, response);
+                    registered.remove(&id);
+                }
             }
             Action::Unregister { id } => {
                 registered.remove(&id);
+                held.remove(&id);
+                overflowed.remove(&id);
+            }
+            Action::Hold { id } => {
+                registered.remove(&id);
+                held.insert(id, VecDeque::new());
             }
             Action::Read { mut res } => {
+                if let Some(key) = pending.remove(res.origin_id.as_ref()) {
+                    if cacheable
+                        .as_ref()
+                        .is_some_and(|cacheable| (cacheable.response)(&res))
+                    {
+                        cache.insert(key, res.payload.to_vec());
+                    }
+                }
+
                 // Split {channel id}_{request id} back into pieces and
                 // update the origin id to match the request id only
                 let channel_id = match res.origin_id.split_once('_') {
@@ -194,17 +354,64 @@ async fn action_task(
                     None => continue,
                 };
 
-                if let Some(reply) = registered.get(&channel_id) {
-                    let response = ManagerResponse::Channel {
-                        id: channel_id,
-                        response: res,
-                    };
-                    if let Err(x) = reply.send(response).await {
-                        error!("[Conn {id}] {x}");
+                let response = ManagerResponse::Channel {
+                    id: channel_id,
+                    response: res,
+                };
+                if registered.contains_key(&channel_id) {
+                    send_to_channel(connection_id, &mut registered, channel_id, response);
+                } else if let Some(responses) = held.get_mut(&channel_id) {
+                    if responses.len() == MAX_HELD_RESPONSES {
+                        warn!(
+                            "[Conn {id} :: Chan {channel_id}] Closing handed off channel as too \
+                            many responses are held for it"
+                        );
+                        held.remove(&channel_id);
+                        overflowed.insert(channel_id);
+                    } else {
+                        responses.push_back(response);
                     }
                 }
             }
             Action::Write { id, mut req } => {
+                if let Some(correlation_id) = req.correlation_id.as_deref() {
+                    debug!(
+                        "[Conn {connection_id} :: Chan {id} :: Req {} :: Corr {correlation_id}] \
+                        Forwarding request",
+                        req.id
+                    );
+                }
+
+                if cacheable
+                    .as_ref()
+                    .is_some_and(|cacheable| (cacheable.invalidates)(&req))
+                {
+                    trace!("[Conn {id}] Clearing cache for request {}", req.id);
+                    cache.clear();
+                    pending.clear();
+                } else if cacheable
+                    .as_ref()
+                    .is_some_and(|cacheable| (cacheable.request)(&req))
+                {
+                    if let Some(payload) = cache.get(req.payload.as_ref()) {
+                        trace!("[Conn {id}] Answering request {} from cache", req.id);
+                        let response = ManagerResponse::Channel {
+                            id,
+                            response: UntypedResponse {
+                                id: Cow::Owned(rand::random::<u64>().to_string()),
+                                origin_id: req.id,
+                                payload: Cow::Owned(payload.clone()),
+                            },
+                        };
+                        send_to_channel(connection_id, &mut registered, id, response);
+                        continue;
+                    }
+
+                    if pending.len() < MAX_PENDING_CACHEABLE {
+                        pending.insert(format!("{id}_{}", req.id), req.payload.to_vec());
+                    }
+                }
+
                 // Combine channel id with request id so we can properly forward
                 // the response containing this in the origin id
                 req.set_id(format!("{id}_{}", req.id));
@@ -213,6 +420,411 @@ async fn action_task(
                     error!("[Conn {id}] {x}");
                 }
             }
+            Action::StateChanged { state } => {
+                if state == ConnectionState::Reconnecting {
+                    cache.clear();
+                    pending.clear();
+                }
+
+                let channel_ids: Vec<_> = registered.keys().copied().collect();
+                for channel_id in channel_ids {
+                    let response = ManagerResponse::ChannelState {
+                        id: channel_id,
+                        state,
+                    };
+                    send_to_channel(connection_id, &mut registered, channel_id, response);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::ClientConfig,
+        common::{Connection, Request, Response},
+    };
+    use std::sync::Arc;
+    use test_log::test;
+
+    /// Treats requests with a payload of 1 as cacheable, responses with a payload of 0 as failures,
+    /// and requests with a payload of 9 as clearing the cache
+    fn cache_policy() -> CachePolicy {
+        let payload_of =
+            |req: &UntypedRequest<'_>| req.to_typed_request::<u8>().ok().map(|req| req.payload);
+        CachePolicy {
+            request: Arc::new(move |req| payload_of(req) == Some(1)),
+            response: Arc::new(|res| {
+                res.to_typed_response::<u8>()
+                    .is_ok_and(|res| res.payload != 0)
+            }),
+            invalidates: Arc::new(move |req| payload_of(req) == Some(9)),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn should_answer_cacheable_requests_from_cache_after_first_response() {
+        let (client, mut server) = Connection::pair(100);
+        let client = UntypedClient::spawn(client, ClientConfig::default());
+        let connection = ManagerConnection::spawn(
+            "scheme://host".parse().unwrap(),
+            Map::new(),
+            client,
+            Some(cache_policy()),
+        )
+        .await
+        .unwrap();
+
+        let (reply_tx, mut reply_rx) = mpsc::channel(100);
+        let channel = connection
+            .open_channel(ServerReply {
+                origin_id: "origin".to_string(),
+                tx: reply_tx,
+            })
+            .unwrap();
+
+        // Send each of the requests twice, answering whatever reaches the server with a payload
+        // counting the requests that it has received
+        let mut answered = 0u8;
+        for payload in [1u8, 1, 2, 2] {
+            let req = Request::new(payload);
+            channel
+                .send(req.to_untyped_request().unwrap().into_owned())
+                .unwrap();
+
+            if payload == 2 || answered == 0 {
+                let req = server
+                    .read_frame_as::<Request<u8>>()
+                    .await
+                    .unwrap()
+                    .unwrap();
+                answered += 1;
+                server
+                    .write_frame_for(&Response::new(req.id, answered))
+                    .await
+                    .unwrap();
+            }
+
+            let res = reply_rx.recv().await.unwrap();
+            match res.payload {
+                ManagerResponse::Channel { response, .. } => {
+                    assert_eq!(response.origin_id, req.id);
+                    assert_eq!(
+                        response.to_typed_response::<u8>().unwrap().payload,
+                        answered
+                    );
+                }
+                x => panic!("Unexpected response: {x:?}"),
+            }
+        }
+
+        assert_eq!(answered, 3, "Cacheable request should reach server once");
+    }
+
+    #[test(tokio::test)]
+    async fn should_only_cache_successful_responses_until_cache_is_invalidated() {
+        let (client, mut server) = Connection::pair(100);
+        let client = UntypedClient::spawn(client, ClientConfig::default());
+        let connection = ManagerConnection::spawn(
+            "scheme://host".parse().unwrap(),
+            Map::new(),
+            client,
+            Some(cache_policy()),
+        )
+        .await
+        .unwrap();
+
+        let (reply_tx, mut reply_rx) = mpsc::channel(100);
+        let channel = connection
+            .open_channel(ServerReply {
+                origin_id: "origin".to_string(),
+                tx: reply_tx,
+            })
+            .unwrap();
+
+        // Sends a request, answering it with `answer` if expected to reach the server, and returns
+        // the payload of the response that comes back
+        macro_rules! send {
+            ($payload:expr, $answer:expr) => {{
+                let payload: u8 = $payload;
+                let req = Request::new(payload);
+                channel
+                    .send(req.to_untyped_request().unwrap().into_owned())
+                    .unwrap();
+
+                let answer: Option<u8> = $answer;
+                if let Some(answer) = answer {
+                    let req = server
+                        .read_frame_as::<Request<u8>>()
+                        .await
+                        .unwrap()
+                        .unwrap();
+                    server
+                        .write_frame_for(&Response::new(req.id, answer))
+                        .await
+                        .unwrap();
+                }
+
+                match reply_rx.recv().await.unwrap().payload {
+                    ManagerResponse::Channel { response, .. } => {
+                        response.to_typed_response::<u8>().unwrap().payload
+                    }
+                    x => panic!("Unexpected response: {x:?}"),
+                }
+            }};
+        }
+
+        // A failed response is passed along, but the request still reaches the server next time
+        assert_eq!(send!(1, Some(0)), 0);
+        assert_eq!(send!(1, Some(5)), 5);
+        assert_eq!(send!(1, None), 5);
+
+        // Once something changes how the server answers, the request reaches the server again
+        assert_eq!(send!(9, Some(9)), 9);
+        assert_eq!(send!(1, Some(6)), 6);
+        assert_eq!(send!(1, None), 6);
+    }
+
+    #[test(tokio::test)]
+    async fn should_hold_responses_for_handed_off_channel_until_claimed() {
+        let (client, mut server) = Connection::pair(100);
+        let client = UntypedClient::spawn(client, ClientConfig::default());
+        let connection =
+            ManagerConnection::spawn("scheme://host".parse().unwrap(), Map::new(), client, None)
+                .await
+                .unwrap();
+
+        let (reply_tx, mut reply_rx) = mpsc::channel(100);
+        let channel = connection
+            .open_channel(ServerReply {
+                origin_id: "origin".to_string(),
+                tx: reply_tx,
+            })
+            .unwrap();
+
+        let req = Request::new(1u8);
+        channel
+            .send(req.to_untyped_request().unwrap().into_owned())
+            .unwrap();
+        let req = server
+            .read_frame_as::<Request<u8>>()
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Responses sent while the channel is handed off are held rather than sent to either side
+        channel.hold().unwrap();
+        for payload in [2u8, 3] {
+            server
+                .write_frame_for(&Response::new(req.id.clone(), payload))
+                .await
+                .unwrap();
+        }
+
+        let (claim_tx, mut claim_rx) = mpsc::channel(100);
+        channel
+            .claim(ServerReply {
+                origin_id: "claim".to_string(),
+                tx: claim_tx,
+            })
+            .unwrap();
+
+        for payload in [2u8, 3] {
+            let res = claim_rx.recv().await.unwrap();
+            assert_eq!(res.origin_id, "claim");
+            match res.payload {
+                ManagerResponse::Channel { id, response } => {
+                    assert_eq!(id, channel.id());
+                    assert_eq!(response.to_typed_response::<u8>().unwrap().payload, payload);
+                }
+                x => panic!("Unexpected response: {x:?}"),
+            }
+        }
+
+        assert!(
+            reply_rx.try_recv().is_err(),
+            "Handed off channel got a response"
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn should_close_handed_off_channel_once_too_many_responses_are_held_for_it() {
+        let (client, mut server) = Connection::pair(100);
+        let client = UntypedClient::spawn(client, ClientConfig::default());
+        let connection =
+            ManagerConnection::spawn("scheme://host".parse().unwrap(), Map::new(), client, None)
+                .await
+                .unwrap();
+
+        let channel = connection
+            .open_channel(ServerReply {
+                origin_id: "origin".to_string(),
+                tx: mpsc::channel(100).0,
+            })
+            .unwrap();
+        let (other_tx, mut other_rx) = mpsc::channel(100);
+        let other = connection
+            .open_channel(ServerReply {
+                origin_id: "other".to_string(),
+                tx: other_tx,
+            })
+            .unwrap();
+
+        let req = Request::new(1u8);
+        channel
+            .send(req.to_untyped_request().unwrap().into_owned())
+            .unwrap();
+        let req = server
+            .read_frame_as::<Request<u8>>()
+            .await
+            .unwrap()
+            .unwrap();
+
+        channel.hold().unwrap();
+        for _ in 0..=MAX_HELD_RESPONSES {
+            server
+                .write_frame_for(&Response::new(req.id.clone(), 0u8))
+                .await
+                .unwrap();
+        }
+
+        // Responses are handled in order, so once the other channel gets its response, all of
+        // those for the handed off channel have been handled
+        let req = Request::new(2u8);
+        other
+            .send(req.to_untyped_request().unwrap().into_owned())
+            .unwrap();
+        let req = server
+            .read_frame_as::<Request<u8>>()
+            .await
+            .unwrap()
+            .unwrap();
+        server
+            .write_frame_for(&Response::new(req.id.clone(), 3u8))
+            .await
+            .unwrap();
+        other_rx.recv().await.unwrap();
+
+        let (claim_tx, mut claim_rx) = mpsc::channel(100);
+        channel
+            .claim(ServerReply {
+                origin_id: "claim".to_string(),
+                tx: claim_tx,
+            })
+            .unwrap();
+
+        // Rather than missing some of the responses, the claimant is told that the channel closed
+        match claim_rx.recv().await.unwrap().payload {
+            ManagerResponse::ChannelClosed { id } => assert_eq!(id, channel.id()),
+            x => panic!("Unexpected response: {x:?}"),
+        }
+        assert!(claim_rx.recv().await.is_none(), "Closed channel still open");
+    }
+
+    #[test(tokio::test)]
+    async fn should_keep_sending_to_channels_when_another_stops_reading() {
+        let (client, mut server) = Connection::pair(100);
+        let client = UntypedClient::spawn(client, ClientConfig::default());
+        let connection =
+            ManagerConnection::spawn("scheme://host".parse().unwrap(), Map::new(), client, None)
+                .await
+                .unwrap();
+
+        // Like the client of a connection that went away, which is kept around in case the client
+        // reconnects, but never reads again
+        let (stalled_tx, _stalled_rx) = mpsc::channel(1);
+        let stalled = connection
+            .open_channel(ServerReply {
+                origin_id: "stalled".to_string(),
+                tx: stalled_tx,
+            })
+            .unwrap();
+
+        let (reply_tx, mut reply_rx) = mpsc::channel(100);
+        let channel = connection
+            .open_channel(ServerReply {
+                origin_id: "origin".to_string(),
+                tx: reply_tx,
+            })
+            .unwrap();
+
+        let req = Request::new(1u8);
+        stalled
+            .send(req.to_untyped_request().unwrap().into_owned())
+            .unwrap();
+        let req = server
+            .read_frame_as::<Request<u8>>()
+            .await
+            .unwrap()
+            .unwrap();
+        for payload in 0..10u8 {
+            server
+                .write_frame_for(&Response::new(req.id.clone(), payload))
+                .await
+                .unwrap();
+        }
+
+        let req = Request::new(2u8);
+        channel
+            .send(req.to_untyped_request().unwrap().into_owned())
+            .unwrap();
+        let req = server
+            .read_frame_as::<Request<u8>>()
+            .await
+            .unwrap()
+            .unwrap();
+        server
+            .write_frame_for(&Response::new(req.id.clone(), 3u8))
+            .await
+            .unwrap();
+
+        let res = tokio::time::timeout(Duration::from_secs(1), reply_rx.recv())
+            .await
+            .expect("Channel was held up by stalled channel")
+            .unwrap();
+        match res.payload {
+            ManagerResponse::Channel { id, response } => {
+                assert_eq!(id, channel.id());
+                assert_eq!(response.to_typed_response::<u8>().unwrap().payload, 3);
+            }
+            x => panic!("Unexpected response: {x:?}"),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn should_tell_channels_when_connection_with_server_is_lost() {
+        let (client, server) = Connection::pair(100);
+        let client = UntypedClient::spawn(client, ClientConfig::default());
+        let connection =
+            ManagerConnection::spawn("scheme://host".parse().unwrap(), Map::new(), client, None)
+                .await
+                .unwrap();
+
+        let (reply_tx, mut reply_rx) = mpsc::channel(100);
+        let channel = connection
+            .open_channel(ServerReply {
+                origin_id: "origin".to_string(),
+                tx: reply_tx,
+            })
+            .unwrap();
+
+        // Without a reconnect strategy, losing the server gives up on it right away
+        drop(server);
+
+        // NOTE: Changes made in quick succession may be seen as only the last of them
+        loop {
+            match reply_rx.recv().await.unwrap().payload {
+                ManagerResponse::ChannelState { id, state } => {
+                    assert_eq!(id, channel.id());
+                    if state == ConnectionState::Disconnected {
+                        break;
+                    }
+                    assert_eq!(state, ConnectionState::Reconnecting);
+                }
+                x => panic!("Unexpected response: {x:?}"),
+            }
         }
     }
 }
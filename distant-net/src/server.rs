@@ -8,6 +8,9 @@ use tokio::sync::{broadcast, RwLock};
 mod builder;
 pub use builder::*;
 
+mod clients;
+pub use clients::*;
+
 mod config;
 pub use config::*;
 
@@ -1,34 +1,38 @@
-use crate::common::{authentication::Verifier, PortRange, TcpListener};
+use crate::common::{authentication::Verifier, PortRange, SocketOptions, TcpListener};
 use crate::server::{Server, ServerConfig, ServerHandler, TcpServerRef};
 use serde::{de::DeserializeOwned, Serialize};
 use std::{io, net::IpAddr};
 
-pub struct TcpServerBuilder<T>(Server<T>);
+pub struct TcpServerBuilder<T>(Server<T>, SocketOptions);
 
 impl<T> Server<T> {
     /// Consume [`Server`] and produce a builder for a TCP variant.
     pub fn into_tcp_builder(self) -> TcpServerBuilder<T> {
-        TcpServerBuilder(self)
+        TcpServerBuilder(self, SocketOptions::default())
     }
 }
 
 impl Default for TcpServerBuilder<()> {
     fn default() -> Self {
-        Self(Server::new())
+        Self(Server::new(), SocketOptions::default())
     }
 }
 
 impl<T> TcpServerBuilder<T> {
     pub fn config(self, config: ServerConfig) -> Self {
-        Self(self.0.config(config))
+        Self(self.0.config(config), self.1)
     }
 
     pub fn handler<U>(self, handler: U) -> TcpServerBuilder<U> {
-        TcpServerBuilder(self.0.handler(handler))
+        TcpServerBuilder(self.0.handler(handler), self.1)
     }
 
     pub fn verifier(self, verifier: Verifier) -> Self {
-        Self(self.0.verifier(verifier))
+        Self(self.0.verifier(verifier), self.1)
+    }
+
+    pub fn socket_options(self, options: SocketOptions) -> Self {
+        Self(self.0, options)
     }
 }
 
@@ -43,7 +47,14 @@ where
     where
         P: Into<PortRange> + Send,
     {
-        let listener = TcpListener::bind(addr, port).await?;
+        let listener = TcpListener::bind_with_options(addr, port, self.1).await?;
+        self.start_with_listener(listener)
+    }
+
+    /// Starts the server using a `listener` that is already bound, such as one created from a
+    /// socket passed through socket activation
+    pub fn start_with_listener(self, listener: TcpListener) -> io::Result<TcpServerRef> {
+        let addr = listener.ip_addr();
         let port = listener.port();
         let inner = self.0.start(listener)?;
         Ok(TcpServerRef { addr, port, inner })
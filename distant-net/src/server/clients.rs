@@ -0,0 +1,74 @@
+use super::ServerState;
+use crate::common::ConnectionId;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::{
+    io,
+    sync::{Arc, Weak},
+};
+
+/// Information about a client that is connected to a server
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct ClientInfo {
+    /// Id of the connection with the client
+    pub id: ConnectionId,
+
+    /// Name of the authentication method that the client was verified with
+    pub identity: String,
+
+    /// Time (in seconds since the Unix epoch) at which the client connected
+    pub connected_at: u64,
+
+    /// Where the client is connecting from, such as its IP address and port
+    #[serde(default)]
+    pub address: Option<String>,
+}
+
+/// Handle to the clients connected to a server, used by a [`ServerHandler`] to list them and to
+/// forcibly disconnect them
+///
+/// [`ServerHandler`]: super::ServerHandler
+#[derive(Clone)]
+pub struct ServerClients(Weak<dyn ClientRegistry>);
+
+impl ServerClients {
+    pub(super) fn new<T: Send + 'static>(state: &Arc<ServerState<T>>) -> Self {
+        let state: Weak<ServerState<T>> = Arc::downgrade(state);
+        Self(state)
+    }
+
+    /// Returns information about each client that is currently connected, oldest first
+    pub async fn list(&self) -> Vec<ClientInfo> {
+        match Weak::upgrade(&self.0) {
+            Some(registry) => registry.list().await,
+            None => Vec::new(),
+        }
+    }
+
+    /// Disconnects the client with the given `id`, also forgetting what it would need to
+    /// reconnect, failing if no such client is connected
+    pub async fn disconnect(&self, id: ConnectionId) -> io::Result<()> {
+        match Weak::upgrade(&self.0) {
+            Some(registry) => registry.disconnect(id).await,
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "Server is no longer running",
+            )),
+        }
+    }
+}
+
+impl Default for ServerClients {
+    /// Creates a handle that is not tied to any server, having no clients
+    fn default() -> Self {
+        Self(Weak::<ServerState<()>>::new())
+    }
+}
+
+#[async_trait]
+pub(super) trait ClientRegistry: Send + Sync {
+    async fn list(&self) -> Vec<ClientInfo>;
+    async fn disconnect(&self, id: ConnectionId) -> io::Result<()>;
+}
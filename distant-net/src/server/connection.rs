@@ -1,6 +1,6 @@
 use super::{
-    ConnectionCtx, ConnectionState, ServerCtx, ServerHandler, ServerReply, ServerState,
-    ShutdownTimer,
+    ClientInfo, ConnectionCtx, ConnectionState, ServerClients, ServerCtx, ServerHandler,
+    ServerReply, ServerState, ShutdownTimer,
 };
 use crate::common::{
     authentication::{Keychain, Verifier},
@@ -14,7 +14,7 @@ use std::{
     pin::Pin,
     sync::{Arc, Weak},
     task::{Context, Poll},
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tokio::{
     sync::{broadcast, mpsc, oneshot, RwLock},
@@ -408,6 +408,7 @@ where
 
         // Properly establish the connection's transport
         debug!("Establishing full connection using {transport:?}");
+        let peer_address = transport.peer_address();
         let mut connection = match Weak::upgrade(&verifier) {
             Some(verifier) => {
                 match await_or_shutdown!(Box::pin(Connection::server(
@@ -443,7 +444,29 @@ where
         let mut last_heartbeat = Instant::now();
 
         // Restore our connection's channels if we have them, otherwise make new ones
-        let (tx, mut rx) = match state.connections.write().await.remove(&id) {
+        let existing = state.connections.write().await.remove(&id);
+
+        // Clients restored from a previous connection keep how and when they first connected
+        let info = match existing.as_ref().and_then(ConnectionState::info) {
+            Some(info) => ClientInfo {
+                address: peer_address,
+                ..info.clone()
+            },
+            None => ClientInfo {
+                id,
+                identity: connection
+                    .authentication_method()
+                    .unwrap_or("unknown")
+                    .to_string(),
+                connected_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                address: peer_address,
+            },
+        };
+
+        let (tx, mut rx) = match existing {
             Some(conn) => match conn.shutdown_and_wait().await {
                 Some(x) => {
                     debug!("[Conn {id}] Marked as existing connection");
@@ -461,7 +484,12 @@ where
         };
 
         // Store our connection details
-        state.connections.write().await.insert(id, connection_state);
+        state
+            .connections
+            .write()
+            .await
+            .insert(id, connection_state.with_info(info));
+        let clients = ServerClients::new(&state);
 
         debug!("[Conn {id}] Beginning read/write loop");
         loop {
@@ -493,6 +521,7 @@ where
                                         tx: tx.clone(),
                                     },
                                     local_data: Arc::clone(&local_data),
+                                    clients: clients.clone(),
                                 };
 
                                 // Spawn a new task to run the request handler so we don't block
@@ -932,6 +961,51 @@ mod tests {
         assert_eq!(response.payload, "hello");
     }
 
+    #[test(tokio::test)]
+    async fn should_list_client_until_it_is_disconnected() {
+        let handler = Arc::new(TestServerHandler);
+        let state = Arc::new(ServerState::default());
+        let (t1, t2) = InmemoryTransport::pair(100);
+        let shutdown_timer = Arc::new(RwLock::new(ShutdownTimer::start(Shutdown::Never)));
+        let verifier = Arc::new(Verifier::none());
+
+        let task = ConnectionTask::build()
+            .handler(Arc::downgrade(&handler))
+            .state(Arc::downgrade(&state))
+            .keychain(state.keychain.clone())
+            .transport(t1)
+            .shutdown_timer(Arc::downgrade(&shutdown_timer))
+            .verifier(Arc::downgrade(&verifier))
+            .spawn();
+
+        // Wait for a response so that we know the connection has been fully established
+        let mut client = Connection::client(t2, DummyAuthHandler)
+            .await
+            .expect("Fail to establish client-side connection");
+        client.write_frame_for(&Request::new(123u16)).await.unwrap();
+        client
+            .read_frame_as::<Response<String>>()
+            .await
+            .unwrap()
+            .unwrap();
+
+        let clients = ServerClients::new(&state);
+        let list = clients.list().await;
+        assert_eq!(list.len(), 1, "Unexpected clients: {list:?}");
+        assert_eq!(list[0].id, client.id());
+        assert_eq!(list[0].identity, "none");
+
+        clients.disconnect(client.id()).await.unwrap();
+        wait_for_termination!(task);
+
+        assert_eq!(clients.list().await, Vec::new());
+        assert!(!state.keychain.has_id(client.id().to_string()).await);
+        assert_eq!(
+            clients.disconnect(client.id()).await.unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
     #[test(tokio::test)]
     async fn should_send_heartbeat_via_empty_frame_every_minimum_duration() {
         let handler = Arc::new(TestServerHandler);
@@ -1,4 +1,4 @@
-use super::ServerReply;
+use super::{ServerClients, ServerReply};
 use crate::common::{ConnectionId, Request};
 use std::sync::Arc;
 
@@ -15,6 +15,9 @@ pub struct ServerCtx<T, U, D> {
 
     /// Reference to the connection's local data
     pub local_data: Arc<D>,
+
+    /// Handle to the clients connected to the server
+    pub clients: ServerClients,
 }
 
 /// Represents contextual information for working with an inbound connection
@@ -14,6 +14,14 @@ pub trait Reply: Send + Sync {
 
     /// Clones this reply
     fn clone_reply(&self) -> Box<dyn Reply<Data = Self::Data>>;
+
+    /// Waits until replies can no longer be sent, such as once the connection that they were
+    /// sent through is gone for good
+    ///
+    /// *By default, this waits forever.*
+    fn closed(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(std::future::pending())
+    }
 }
 
 impl<T: Send + 'static> Reply for mpsc::Sender<T> {
@@ -35,6 +43,10 @@ impl<T: Send + 'static> Reply for mpsc::Sender<T> {
     fn clone_reply(&self) -> Box<dyn Reply<Data = Self::Data>> {
         Box::new(self.clone())
     }
+
+    fn closed(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(mpsc::Sender::closed(self))
+    }
 }
 
 /// Utility to send ad-hoc replies from the server back through the connection
@@ -93,6 +105,10 @@ impl<T: Send + 'static> Reply for ServerReply<T> {
     fn clone_reply(&self) -> Box<dyn Reply<Data = Self::Data>> {
         Box::new(self.clone())
     }
+
+    fn closed(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(self.tx.closed())
+    }
 }
 
 /// Represents a reply where all sends are queued up but not sent until
@@ -195,4 +211,8 @@ impl<T: Send + 'static> Reply for QueuedServerReply<T> {
     fn clone_reply(&self) -> Box<dyn Reply<Data = Self::Data>> {
         Box::new(self.clone())
     }
+
+    fn closed(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(self.inner.tx.closed())
+    }
 }
@@ -1,5 +1,7 @@
+use super::{ClientInfo, ClientRegistry};
 use crate::common::{authentication::Keychain, Backup, ConnectionId};
-use std::collections::HashMap;
+use async_trait::async_trait;
+use std::{collections::HashMap, io};
 use tokio::sync::{mpsc, oneshot, RwLock};
 use tokio::task::JoinHandle;
 
@@ -27,9 +29,46 @@ impl<T> Default for ServerState<T> {
     }
 }
 
+#[async_trait]
+impl<T: Send + 'static> ClientRegistry for ServerState<T> {
+    async fn list(&self) -> Vec<ClientInfo> {
+        let mut clients: Vec<ClientInfo> = self
+            .connections
+            .read()
+            .await
+            .values()
+            .filter(|conn| !conn.is_finished())
+            .filter_map(|conn| conn.info.clone())
+            .collect();
+        clients.sort_by_key(|info| (info.connected_at, info.id));
+        clients
+    }
+
+    async fn disconnect(&self, id: ConnectionId) -> io::Result<()> {
+        let conn = self.connections.write().await.remove(&id);
+        match conn {
+            // Forget the OTP of the connection first so that the client cannot reconnect once the
+            // connection is shut down, which also applies to clients waiting to reconnect
+            Some(conn) => {
+                self.keychain.remove(id.to_string()).await;
+                conn.shutdown_and_wait().await;
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No client with id {id}"),
+            )),
+        }
+    }
+}
+
 pub struct ConnectionState<T> {
     shutdown_tx: oneshot::Sender<()>,
     task: JoinHandle<Option<(mpsc::Sender<T>, mpsc::Receiver<T>)>>,
+
+    /// Information about the client on the other side of the connection, available once the
+    /// connection is fully established
+    info: Option<ClientInfo>,
 }
 
 impl<T: Send + 'static> ConnectionState<T> {
@@ -55,10 +94,24 @@ impl<T: Send + 'static> ConnectionState<T> {
                         Err(_) => None,
                     }
                 }),
+                info: None,
             },
         )
     }
 
+    /// Attaches information about the client on the other side of the connection
+    pub fn with_info(self, info: ClientInfo) -> Self {
+        Self {
+            info: Some(info),
+            ..self
+        }
+    }
+
+    /// Returns information about the client on the other side of the connection, if known
+    pub fn info(&self) -> Option<&ClientInfo> {
+        self.info.as_ref()
+    }
+
     pub fn is_finished(&self) -> bool {
         self.task.is_finished()
     }
@@ -3,10 +3,11 @@ use distant_net::boxed_connect_handler;
 use distant_net::client::Client;
 use distant_net::common::authentication::{DummyAuthHandler, Verifier};
 use distant_net::common::{Destination, InmemoryTransport, Map, OneshotListener};
-use distant_net::manager::{Config, ManagerClient, ManagerServer};
-use distant_net::server::{Server, ServerCtx, ServerHandler};
+use distant_net::manager::{Config, ManagerClient, ManagerEvent, ManagerServer};
+use distant_net::server::{Server, ServerCtx, ServerHandler, ServerRef};
 use log::*;
 use std::io;
+use std::sync::Mutex;
 use test_log::test;
 
 struct TestServerHandler;
@@ -65,6 +66,13 @@ async fn should_be_able_to_establish_a_single_connection_and_communicate_with_a_
         .await
         .expect("Failed to connect to manager");
 
+    // Subscribe to events so that we can verify the connection and kill are reported
+    info!("Submitting subscription request to manager");
+    let mut events = client
+        .subscribe()
+        .await
+        .expect("Failed to subscribe to events");
+
     // Test establishing a connection to some remote server
     info!("Submitting server connection request to manager");
     let id = client
@@ -75,6 +83,13 @@ async fn should_be_able_to_establish_a_single_connection_and_communicate_with_a_
         )
         .await
         .expect("Failed to connect to a remote server");
+    assert_eq!(
+        events.next().await,
+        Some(ManagerEvent::Connected {
+            id,
+            destination: "scheme://host".parse::<Destination>().unwrap(),
+        })
+    );
 
     // Test retrieving list of connections
     info!("Submitting connection list request to manager");
@@ -113,11 +128,90 @@ async fn should_be_able_to_establish_a_single_connection_and_communicate_with_a_
     // Test killing a connection
     info!("Submitting connection kill request to manager");
     client.kill(id).await.expect("Failed to kill connection");
+    assert_eq!(events.next().await, Some(ManagerEvent::Disconnected { id }));
 
     // Test getting an error to ensure that serialization of that data works,
     // which we do by trying to access a connection that no longer exists
     info!("Verifying server connection held by manager has terminated");
     let err = client.info(id).await.unwrap_err();
-    assert_eq!(err.kind(), io::ErrorKind::Other);
+    assert_eq!(err.kind(), io::ErrorKind::NotConnected);
     assert_eq!(err.to_string(), "No connection found");
 }
+
+#[test(tokio::test)]
+async fn should_report_connections_that_are_lost_without_being_killed() {
+    // Servers that the manager connects to, kept so that they can be shut down by the test
+    static SERVERS: Mutex<Vec<Box<dyn ServerRef>>> = Mutex::new(Vec::new());
+
+    let (t1, t2) = InmemoryTransport::pair(100);
+
+    let mut config = Config::default();
+    config.connect_handlers.insert(
+        "scheme".to_string(),
+        boxed_connect_handler!(|_a, _b, _c| {
+            let (t1, t2) = InmemoryTransport::pair(100);
+
+            let server = Server::new()
+                .handler(TestServerHandler)
+                .verifier(Verifier::none())
+                .start(OneshotListener::from_value(t2))?;
+            SERVERS.lock().unwrap().push(server);
+
+            let client = Client::build()
+                .auth_handler(DummyAuthHandler)
+                .connector(t1)
+                .connect_untyped()
+                .await?;
+
+            Ok(client)
+        }),
+    );
+
+    info!("Starting manager");
+    let _manager_ref = ManagerServer::new(config)
+        .verifier(Verifier::none())
+        .start(OneshotListener::from_value(t2))
+        .expect("Failed to start manager server");
+
+    info!("Connecting to manager");
+    let mut client: ManagerClient = Client::build()
+        .auth_handler(DummyAuthHandler)
+        .connector(t1)
+        .connect()
+        .await
+        .expect("Failed to connect to manager");
+
+    let mut events = client
+        .subscribe()
+        .await
+        .expect("Failed to subscribe to events");
+
+    info!("Submitting server connection request to manager");
+    let id = client
+        .connect(
+            "scheme://host".parse::<Destination>().unwrap(),
+            "key=value".parse::<Map>().unwrap(),
+            DummyAuthHandler,
+        )
+        .await
+        .expect("Failed to connect to a remote server");
+    assert_eq!(
+        events.next().await,
+        Some(ManagerEvent::Connected {
+            id,
+            destination: "scheme://host".parse::<Destination>().unwrap(),
+        })
+    );
+
+    // Take down the server out from under the manager, which should notice on its own
+    info!("Shutting down remote server");
+    for server in SERVERS.lock().unwrap().drain(..) {
+        server.shutdown();
+    }
+    assert_eq!(events.next().await, Some(ManagerEvent::Disconnected { id }));
+
+    info!("Verifying connection was removed by manager");
+    let err = client.info(id).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+    assert!(client.list().await.unwrap().is_empty());
+}
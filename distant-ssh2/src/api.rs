@@ -7,11 +7,12 @@ use async_once_cell::OnceCell;
 use async_trait::async_trait;
 use distant_core::{
     data::{
-        Capabilities, CapabilityKind, DirEntry, Environment, FileType, Metadata, ProcessId,
-        PtySize, SystemInfo, UnixMetadata,
+        Capabilities, CapabilityKind, DirEntry, DirEntrySort, DirReadOptions, Environment,
+        FileType, Metadata, ProcLimits, ProcessId, PtySize, SessionId, SystemInfo, UnixMetadata,
     },
+    make_temp_name,
     net::server::ConnectionCtx,
-    DistantApi, DistantCtx,
+    DistantApi, DistantCtx, MAX_TEMP_ATTEMPTS,
 };
 use log::*;
 use std::{
@@ -22,7 +23,9 @@ use std::{
     time::Duration,
 };
 use tokio::sync::{mpsc, RwLock};
-use wezterm_ssh::{FilePermissions, OpenFileType, OpenOptions, Session as WezSession, WriteMode};
+use wezterm_ssh::{
+    FilePermissions, OpenFileType, OpenOptions, RenameOptions, Session as WezSession, WriteMode,
+};
 
 /// Time after copy completes to wait for stdout/stderr to close
 const COPY_COMPLETE_TIMEOUT: Duration = Duration::from_secs(1);
@@ -50,6 +53,12 @@ pub struct SshDistantApi {
 
     /// Global tracking of running processes by id
     processes: Arc<RwLock<HashMap<ProcessId, Process>>>,
+
+    /// Cached temporary directory of the remote server
+    temp_dir: OnceCell<PathBuf>,
+
+    /// Cached check of whether the remote server is a Windows machine
+    is_windows: OnceCell<bool>,
 }
 
 impl SshDistantApi {
@@ -57,16 +66,27 @@ impl SshDistantApi {
         Self {
             session,
             processes: Arc::new(RwLock::new(HashMap::new())),
+            temp_dir: OnceCell::new(),
+            is_windows: OnceCell::new(),
         }
     }
 
+    /// Looks up the temporary directory of the remote server
+    async fn temp_dir(&self) -> io::Result<PathBuf> {
+        // We cache the request as it should not change for the lifetime of the ssh connection
+        let is_windows = self.is_windows().await?;
+        Ok(self
+            .temp_dir
+            .get_or_try_init(utils::query_temp_dir(&self.session, is_windows))
+            .await?
+            .clone())
+    }
+
     /// Checks if the remote server is a Windows machine
     async fn is_windows(&self) -> io::Result<bool> {
         // We cache the request as it should not change for the lifetime of the ssh connection
-        static IS_WINDOWS: OnceCell<bool> = OnceCell::new();
-
-        // Look up whether the remote system is windows
-        Ok(*IS_WINDOWS
+        Ok(*self
+            .is_windows
             .get_or_try_init(utils::is_windows(&self.session))
             .await?)
     }
@@ -90,6 +110,46 @@ impl DistantApi for SshDistantApi {
         // TODO: Could we have external search using ripgrep's JSON lines API?
         capabilities.take(CapabilityKind::Search);
         capabilities.take(CapabilityKind::CancelSearch);
+        capabilities.take(CapabilityKind::SearchNext);
+
+        // Output is relayed without flow control, so acknowledgements are not supported
+        capabilities.take(CapabilityKind::ProcOutputAck);
+
+        // Processes can only be killed over ssh, not sent other signals
+        capabilities.take(CapabilityKind::ProcSignal);
+
+        // Processes spawned over ssh have no known system ids, so they are not listed
+        capabilities.take(CapabilityKind::ProcList);
+
+        // Changes are not journaled as nothing keeps running on the remote machine over ssh
+        capabilities.take(CapabilityKind::JournalStart);
+        capabilities.take(CapabilityKind::JournalChanges);
+        capabilities.take(CapabilityKind::JournalStop);
+
+        // Resource usage of the remote system and its processes is not collected over ssh
+        capabilities.take(CapabilityKind::SystemStats);
+        capabilities.take(CapabilityKind::CancelSystemStats);
+        capabilities.take(CapabilityKind::ProcStats);
+
+        // Processes of the remote system cannot be signaled by pid over ssh
+        capabilities.take(CapabilityKind::SystemKill);
+
+        // Stdin of a process cannot be closed apart from the rest of its ssh channel
+        capabilities.take(CapabilityKind::ProcStdinClose);
+
+        // Files are not hashed over ssh, where listing a manifest would read every file in full
+        capabilities.take(CapabilityKind::DirManifest);
+
+        // No extensions can be registered with the ssh implementation
+        capabilities.take(CapabilityKind::Extension);
+
+        // Tunnels are not opened through the ssh implementation
+        capabilities.take(CapabilityKind::TunnelOpen);
+        capabilities.take(CapabilityKind::TunnelWrite);
+        capabilities.take(CapabilityKind::TunnelClose);
+        capabilities.take(CapabilityKind::TunnelListen);
+        capabilities.take(CapabilityKind::TunnelListenUnix);
+        capabilities.take(CapabilityKind::TunnelUnlisten);
 
         Ok(capabilities)
     }
@@ -98,12 +158,23 @@ impl DistantApi for SshDistantApi {
         &self,
         ctx: DistantCtx<Self::LocalData>,
         path: PathBuf,
+        offset: Option<u64>,
+        len: Option<u64>,
     ) -> io::Result<Vec<u8>> {
         debug!(
-            "[Conn {}] Reading bytes from file {:?}",
-            ctx.connection_id, path
+            "[Conn {}] Reading bytes from file {:?} {{offset: {:?}, len: {:?}}}",
+            ctx.connection_id, path, offset, len
         );
 
+        // Files opened over sftp cannot be seeked, so reading from elsewhere than the start would
+        // mean reading everything before it
+        if offset.is_some_and(|offset| offset > 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Reading at an offset within a file is not supported",
+            ));
+        }
+
         use smol::io::AsyncReadExt;
         let mut file = self
             .session
@@ -113,9 +184,12 @@ impl DistantApi for SshDistantApi {
             .await
             .map_err(to_other_error)?;
 
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).compat().await?;
-        Ok(contents.into_bytes())
+        let mut contents = Vec::new();
+        match len {
+            Some(len) => file.take(len).read_to_end(&mut contents).compat().await?,
+            None => file.read_to_end(&mut contents).compat().await?,
+        };
+        Ok(contents)
     }
 
     async fn read_file_text(
@@ -147,21 +221,43 @@ impl DistantApi for SshDistantApi {
         ctx: DistantCtx<Self::LocalData>,
         path: PathBuf,
         data: Vec<u8>,
+        offset: Option<u64>,
+        mode: Option<u32>,
     ) -> io::Result<()> {
         debug!(
-            "[Conn {}] Writing bytes to file {:?}",
-            ctx.connection_id, path
+            "[Conn {}] Writing bytes to file {:?} at offset {:?} {{mode: {:?}}}",
+            ctx.connection_id, path, offset, mode
         );
 
+        // Files opened over sftp cannot be seeked, so we cannot write within them without
+        // rewriting everything around the data
+        if offset.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Writing at an offset within a file is not supported",
+            ));
+        }
+
         use smol::io::AsyncWriteExt;
-        let mut file = self
-            .session
-            .sftp()
-            .create(path)
-            .compat()
-            .await
-            .map_err(to_other_error)?;
+        let sftp = self.session.sftp();
 
+        // NOTE: The mode is applied by the ssh server when creating the file, subject to its umask
+        let mut file = match mode {
+            Some(mode) => sftp
+                .open_with_mode(
+                    path,
+                    OpenOptions {
+                        read: false,
+                        write: Some(WriteMode::Write),
+                        mode: mode as i32,
+                        ty: OpenFileType::File,
+                    },
+                )
+                .compat()
+                .await
+                .map_err(to_other_error)?,
+            None => sftp.create(path).compat().await.map_err(to_other_error)?,
+        };
         file.write_all(data.as_ref()).compat().await?;
 
         Ok(())
@@ -260,6 +356,7 @@ impl DistantApi for SshDistantApi {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn read_dir(
         &self,
         ctx: DistantCtx<Self::LocalData>,
@@ -268,10 +365,11 @@ impl DistantApi for SshDistantApi {
         absolute: bool,
         canonicalize: bool,
         include_root: bool,
+        options: DirReadOptions,
     ) -> io::Result<(Vec<DirEntry>, Vec<io::Error>)> {
         debug!(
-            "[Conn {}] Reading directory {:?} {{depth: {}, absolute: {}, canonicalize: {}, include_root: {}}}",
-            ctx.connection_id, path, depth, absolute, canonicalize, include_root
+            "[Conn {}] Reading directory {:?} {{depth: {}, absolute: {}, canonicalize: {}, include_root: {}, options: {:?}}}",
+            ctx.connection_id, path, depth, absolute, canonicalize, include_root, options
         );
 
         let sftp = self.session.sftp();
@@ -299,15 +397,21 @@ impl DistantApi for SshDistantApi {
                 entry.path.to_path_buf()
             };
 
-            // Always include any non-root in our traverse list, but only include the
-            // root directory if flagged to do so
-            if !is_root || include_root {
+            // Always include any non-root of an allowed type in our traverse list, but only
+            // include the root directory if flagged to do so
+            let is_allowed =
+                options.allowed_file_types.is_empty() || options.allowed_file_types.contains(&ft);
+            if (!is_root && is_allowed) || (is_root && include_root) {
                 entries.push(entry);
             }
 
             let is_dir = match ft {
                 FileType::Dir => true,
-                FileType::File => false,
+                FileType::File
+                | FileType::Fifo
+                | FileType::Socket
+                | FileType::CharDevice
+                | FileType::BlockDevice => false,
                 FileType::Symlink => match sftp.metadata(path.to_path_buf()).await {
                     Ok(metadata) => metadata.is_dir(),
                     Err(x) => {
@@ -326,7 +430,15 @@ impl DistantApi for SshDistantApi {
                     .map_err(to_other_error)
                 {
                     Ok(entries) => {
+                        let mut children = Vec::new();
                         for (path, metadata) in entries {
+                            // Skip hidden entries, which also means we will not traverse them
+                            if options.exclude_hidden
+                                && path.file_name().map_or(false, |name| name.starts_with('.'))
+                            {
+                                continue;
+                            }
+
                             // Canonicalize the path if specified, otherwise just return
                             // the path as is
                             let mut path = if canonicalize {
@@ -365,18 +477,40 @@ impl DistantApi for SshDistantApi {
                             };
 
                             let ft = metadata.ty;
-                            to_traverse.push(DirEntry {
-                                path,
-                                file_type: if ft.is_dir() {
-                                    FileType::Dir
-                                } else if ft.is_file() {
-                                    FileType::File
-                                } else {
-                                    FileType::Symlink
+                            let key = match options.sort {
+                                DirEntrySort::Name => None,
+                                DirEntrySort::Size => metadata.size,
+                                DirEntrySort::Modified => metadata.modified,
+                            };
+                            children.push((
+                                key,
+                                DirEntry {
+                                    path,
+                                    file_type: if ft.is_dir() {
+                                        FileType::Dir
+                                    } else if ft.is_file() {
+                                        FileType::File
+                                    } else {
+                                        FileType::Symlink
+                                    },
+                                    depth: next_depth,
                                 },
-                                depth: next_depth,
-                            });
+                            ));
                         }
+
+                        // Order the children of this directory, falling back to their names,
+                        // and then queue them in reverse as we pop from the end of our list
+                        children.sort_unstable_by(|(a_key, a), (b_key, b)| {
+                            let ordering = a_key
+                                .cmp(b_key)
+                                .then_with(|| a.path.file_name().cmp(&b.path.file_name()));
+                            if options.reverse {
+                                ordering.reverse()
+                            } else {
+                                ordering
+                            }
+                        });
+                        to_traverse.extend(children.into_iter().rev().map(|(_, entry)| entry));
                     }
                     Err(x) if is_root => return Err(io::Error::new(io::ErrorKind::Other, x)),
                     Err(x) => errors.push(x),
@@ -384,9 +518,6 @@ impl DistantApi for SshDistantApi {
             }
         }
 
-        // Sort entries by filename
-        entries.sort_unstable_by_key(|e| e.path.to_path_buf());
-
         Ok((entries, errors))
     }
 
@@ -395,19 +526,22 @@ impl DistantApi for SshDistantApi {
         ctx: DistantCtx<Self::LocalData>,
         path: PathBuf,
         all: bool,
+        mode: Option<u32>,
     ) -> io::Result<()> {
         debug!(
-            "[Conn {}] Creating directory {:?} {{all: {}}}",
-            ctx.connection_id, path, all
+            "[Conn {}] Creating directory {:?} {{all: {}, mode: {:?}}}",
+            ctx.connection_id, path, all, mode
         );
 
         let sftp = self.session.sftp();
 
+        // Using 755 as this mirrors "ssh <host> mkdir ..."
+        // 755: rwxr-xr-x
+        let mode = mode.unwrap_or(0o755);
+
         // Makes the immediate directory, failing if given a path with missing components
-        async fn mkdir(sftp: &wezterm_ssh::Sftp, path: PathBuf) -> io::Result<()> {
-            // Using 755 as this mirrors "ssh <host> mkdir ..."
-            // 755: rwxr-xr-x
-            sftp.create_dir(path, 0o755)
+        async fn mkdir(sftp: &wezterm_ssh::Sftp, path: PathBuf, mode: u32) -> io::Result<()> {
+            sftp.create_dir(path, mode as i32)
                 .compat()
                 .await
                 .map_err(to_other_error)
@@ -419,7 +553,7 @@ impl DistantApi for SshDistantApi {
             let mut cur_path = path.as_path();
             let mut first_err = None;
             loop {
-                match mkdir(&sftp, cur_path.to_path_buf()).await {
+                match mkdir(&sftp, cur_path.to_path_buf(), mode).await {
                     Ok(_) => break,
                     Err(x) => {
                         failed_paths.push(cur_path);
@@ -442,15 +576,140 @@ impl DistantApi for SshDistantApi {
             // Now that we've successfully created a parent component (or the directory), proceed
             // to attempt to create each failed directory
             while let Some(path) = failed_paths.pop() {
-                mkdir(&sftp, path.to_path_buf()).await?;
+                mkdir(&sftp, path.to_path_buf(), mode).await?;
             }
         } else {
-            mkdir(&sftp, path).await?;
+            mkdir(&sftp, path, mode).await?;
         }
 
         Ok(())
     }
 
+    async fn make_temp_file(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        parent: Option<PathBuf>,
+    ) -> io::Result<PathBuf> {
+        debug!(
+            "[Conn {}] Making temporary file {{parent: {:?}}}",
+            ctx.connection_id, parent
+        );
+
+        let parent = match parent {
+            Some(parent) => parent,
+            None => self.temp_dir().await?,
+        };
+
+        let sftp = self.session.sftp();
+        for _ in 0..MAX_TEMP_ATTEMPTS {
+            let path = parent.join(make_temp_name());
+
+            if sftp.metadata(path.clone()).compat().await.is_ok() {
+                continue;
+            }
+
+            // NOTE: sftp has no exclusive create, so we create the file within a fresh directory
+            //       that nobody else can have touched (mkdir is exclusive) and then move it into
+            //       place with a rename that refuses to overwrite an existing path
+            let staging = parent.join(make_temp_name());
+            if let Err(x) = sftp.create_dir(staging.clone(), 0o700).compat().await {
+                if sftp.metadata(staging).compat().await.is_ok() {
+                    continue;
+                }
+
+                return Err(to_other_error(x));
+            }
+
+            let staged = staging.join(make_temp_name());
+            let result = async {
+                sftp.open_with_mode(
+                    staged.clone(),
+                    OpenOptions {
+                        read: false,
+                        write: Some(WriteMode::Write),
+                        // Using 600 as this mirrors "ssh <host> mktemp"
+                        // 600: rw-------
+                        mode: 0o600,
+                        ty: OpenFileType::File,
+                    },
+                )
+                .compat()
+                .await?;
+
+                sftp.rename(
+                    staged.clone(),
+                    path.clone(),
+                    RenameOptions {
+                        overwrite: false,
+                        atomic: true,
+                        native: true,
+                    },
+                )
+                .compat()
+                .await
+            }
+            .await;
+
+            // Clean up whatever is left of the staging directory
+            if result.is_err() {
+                let _ = sftp.remove_file(staged).compat().await;
+            }
+            let _ = sftp.remove_dir(staging).compat().await;
+
+            match result {
+                Ok(()) => return Ok(path),
+
+                // Someone else claimed the name in the meantime, so try another
+                Err(_) if sftp.metadata(path).compat().await.is_ok() => continue,
+                Err(x) => return Err(to_other_error(x)),
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "Failed to find an unused temporary name",
+        ))
+    }
+
+    async fn make_temp_dir(
+        &self,
+        ctx: DistantCtx<Self::LocalData>,
+        parent: Option<PathBuf>,
+    ) -> io::Result<PathBuf> {
+        debug!(
+            "[Conn {}] Making temporary directory {{parent: {:?}}}",
+            ctx.connection_id, parent
+        );
+
+        let parent = match parent {
+            Some(parent) => parent,
+            None => self.temp_dir().await?,
+        };
+
+        let sftp = self.session.sftp();
+        for _ in 0..MAX_TEMP_ATTEMPTS {
+            let path = parent.join(make_temp_name());
+
+            if sftp.metadata(path.clone()).compat().await.is_ok() {
+                continue;
+            }
+
+            // Using 700 as this mirrors "ssh <host> mktemp -d"
+            // 700: rwx------
+            sftp.create_dir(path.clone(), 0o700)
+                .compat()
+                .await
+                .map_err(to_other_error)?;
+
+            return Ok(path);
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "Failed to find an unused temporary name",
+        ))
+    }
+
     async fn remove(
         &self,
         ctx: DistantCtx<Self::LocalData>,
@@ -553,7 +812,9 @@ impl DistantApi for SshDistantApi {
         );
 
         // NOTE: SFTP does not provide a remote-to-remote copy method, so we instead execute
-        //       a program based on the platform and hope that it applies
+        //       a program based on the platform and hope that it applies. This also means that
+        //       sparse files only keep their holes if the remote program does so itself (GNU cp
+        //       does by default, while Copy-Item and most other cp implementations do not)
         let is_windows = self.is_windows().await?;
         let output = if is_windows {
             utils::powershell_output(
@@ -665,6 +926,7 @@ impl DistantApi for SshDistantApi {
             canonicalized_path,
             file_type,
             len: metadata.size.unwrap_or(0),
+            allocated_len: None,
             // Check that owner, group, or other has write permission (if not, then readonly)
             readonly: metadata
                 .permissions
@@ -688,6 +950,7 @@ impl DistantApi for SshDistantApi {
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn proc_spawn(
         &self,
         ctx: DistantCtx<Self::LocalData>,
@@ -695,12 +958,59 @@ impl DistantApi for SshDistantApi {
         environment: Environment,
         current_dir: Option<PathBuf>,
         pty: Option<PtySize>,
+        _output_window: Option<usize>,
+        timestamped: bool,
+        session: Option<SessionId>,
+        limits: ProcLimits,
+        kill_tree: bool,
+        user: Option<String>,
     ) -> io::Result<ProcessId> {
         debug!(
             "[Conn {}] Spawning {} {{environment: {:?}, current_dir: {:?}, pty: {:?}}}",
             ctx.connection_id, cmd, environment, current_dir, pty
         );
 
+        // Processes spawned over ssh end with the ssh session, so they cannot outlive clients
+        if session.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Persistent processes are not supported",
+            ));
+        }
+
+        // Stdout and stderr are read separately over ssh, so we cannot tell which came first
+        if timestamped {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Timestamped process output is not supported",
+            ));
+        }
+
+        // Commands run over ssh go through the remote shell, leaving us no place to apply limits
+        if !limits.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Process priority and limits are not supported",
+            ));
+        }
+
+        // Killing a process over ssh closes its channel, which leaves the remote machine to decide
+        // what happens to the processes it started
+        if kill_tree {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Killing process trees is not supported",
+            ));
+        }
+
+        // Processes run as the user that the ssh session was authenticated as
+        if user.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Running processes as another user is not supported",
+            ));
+        }
+
         let global_processes = Arc::downgrade(&self.processes);
         let local_processes = Arc::downgrade(&ctx.local_data.processes);
         let cleanup = |id: ProcessId| async move {
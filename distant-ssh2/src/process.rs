@@ -217,6 +217,7 @@ fn spawn_blocking_stdout_task(
                     let payload = DistantResponseData::ProcStdout {
                         id,
                         data: buf[..n].to_vec(),
+                        stamp: None,
                     };
                     if reply.blocking_send(payload).is_err() {
                         error!("[Ssh | Proc {}] Stdout channel closed", id);
@@ -248,6 +249,7 @@ fn spawn_nonblocking_stdout_task(
                     let payload = DistantResponseData::ProcStdout {
                         id,
                         data: buf[..n].to_vec(),
+                        stamp: None,
                     };
                     if reply.send(payload).await.is_err() {
                         error!("[Ssh | Proc {}] Stdout channel closed", id);
@@ -282,6 +284,7 @@ fn spawn_nonblocking_stderr_task(
                     let payload = DistantResponseData::ProcStderr {
                         id,
                         data: buf[..n].to_vec(),
+                        stamp: None,
                     };
                     if reply.send(payload).await.is_err() {
                         error!("[Ssh | Proc {}] Stderr channel closed", id);
@@ -203,6 +203,30 @@ pub async fn query_shell(session: &Session, is_windows: bool) -> io::Result<Stri
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
+/// Query remote system for the temporary directory of current user
+pub async fn query_temp_dir(session: &Session, is_windows: bool) -> io::Result<PathBuf> {
+    let output = if is_windows {
+        powershell_output(session, "[System.IO.Path]::GetTempPath()", SSH_EXEC_TIMEOUT).await?
+    } else {
+        execute_output(
+            session,
+            "/bin/sh -c 'echo ${TMPDIR:-/tmp}'",
+            SSH_EXEC_TIMEOUT,
+        )
+        .await?
+    };
+
+    let output = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Unable to determine temporary directory",
+        ));
+    }
+
+    Ok(PathBuf::from(output))
+}
+
 /// Attempts to convert UTF8 str into a path compliant with Windows
 pub fn convert_to_windows_path_string(s: &str) -> Option<String> {
     let path = WindowsPath::new(s);
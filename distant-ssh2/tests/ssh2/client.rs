@@ -162,6 +162,24 @@ async fn write_file_should_send_ok_when_successful(#[future] client: Ctx<Distant
     file.assert("some text");
 }
 
+#[rstest]
+#[test(tokio::test)]
+async fn write_file_at_should_fail_without_changing_file(#[future] client: Ctx<DistantClient>) {
+    let mut client = client.await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test-file");
+    file.write_str("some file contents").unwrap();
+
+    let err = client
+        .write_file_at(file.path().to_path_buf(), 5, b"FILE".to_vec())
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported, "{err}");
+
+    file.assert("some file contents");
+}
+
 #[rstest]
 #[test(tokio::test)]
 async fn write_file_text_should_send_error_if_fails_to_write_file(
@@ -350,6 +368,7 @@ async fn dir_read_should_send_error_if_directory_does_not_exist(
             /* absolute */ false,
             /* canonicalize */ false,
             /* include_root */ false,
+            /* options */ Default::default(),
         )
         .await
         .unwrap_err();
@@ -417,6 +436,7 @@ async fn dir_read_should_support_depth_limits(#[future] client: Ctx<DistantClien
             /* absolute */ false,
             /* canonicalize */ false,
             /* include_root */ false,
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -454,6 +474,7 @@ async fn dir_read_should_support_unlimited_depth_using_zero(#[future] client: Ct
             /* absolute */ false,
             /* canonicalize */ false,
             /* include_root */ false,
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -496,6 +517,7 @@ async fn dir_read_should_support_including_directory_in_returned_entries(
             /* absolute */ false,
             /* canonicalize */ false,
             /* include_root */ true,
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -540,6 +562,7 @@ async fn dir_read_should_support_returning_absolute_paths(#[future] client: Ctx<
             /* absolute */ true,
             /* canonicalize */ false,
             /* include_root */ false,
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -579,6 +602,7 @@ async fn dir_read_should_support_returning_canonicalized_paths(
             /* absolute */ false,
             /* canonicalize */ true,
             /* include_root */ false,
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
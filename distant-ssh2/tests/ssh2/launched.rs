@@ -359,6 +359,7 @@ async fn dir_read_should_send_error_if_directory_does_not_exist(
             /* absolute */ false,
             /* canonicalize */ false,
             /* include_root */ false,
+            /* options */ Default::default(),
         )
         .await
         .unwrap_err();
@@ -400,6 +401,7 @@ async fn dir_read_should_support_depth_limits(#[future] launched_client: Ctx<Dis
             /* absolute */ false,
             /* canonicalize */ false,
             /* include_root */ false,
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -436,6 +438,7 @@ async fn dir_read_should_support_unlimited_depth_using_zero(
             /* absolute */ false,
             /* canonicalize */ false,
             /* include_root */ false,
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -476,6 +479,7 @@ async fn dir_read_should_support_including_directory_in_returned_entries(
             /* absolute */ false,
             /* canonicalize */ false,
             /* include_root */ true,
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -520,6 +524,7 @@ async fn dir_read_should_support_returning_absolute_paths(
             /* absolute */ true,
             /* canonicalize */ false,
             /* include_root */ false,
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -557,6 +562,7 @@ async fn dir_read_should_support_returning_canonicalized_paths(
             /* absolute */ false,
             /* canonicalize */ true,
             /* include_root */ false,
+            /* options */ Default::default(),
         )
         .await
         .unwrap();
@@ -37,6 +37,7 @@ impl Cli {
 
     /// Initializes a logger for the CLI, returning a handle to the logger
     pub fn init_logger(&self) -> flexi_logger::LoggerHandle {
+        use crate::options::LogFormat;
         use flexi_logger::{FileSpec, LevelFilter, LogSpecification, Logger};
         let modules = &["distant", "distant_core", "distant_net", "distant_ssh2"];
 
@@ -57,7 +58,12 @@ impl Cli {
         }
 
         // Create our logger, but don't initialize yet
-        let logger = Logger::with(builder.build()).format_for_files(flexi_logger::opt_format);
+        let logger = Logger::with(builder.build()).format_for_files(
+            match self.options.logging.log_format_or_default() {
+                LogFormat::Text => flexi_logger::opt_format,
+                LogFormat::Json => common::json_format,
+            },
+        );
 
         // Assign our log output to a file
         // NOTE: We can unwrap here as we assign the log file earlier
@@ -79,11 +85,63 @@ impl Cli {
 
     /// Runs the CLI
     pub fn run(self) -> CliResult {
+        if self.options.a11y {
+            commands::common::OutputPolicy::Accessible.set();
+        }
+
+        if self.options.paging.is_enabled() {
+            commands::common::Pager::enable();
+        }
+
         match self.options.command {
             DistantSubcommand::Client(cmd) => commands::client::run(cmd),
+            DistantSubcommand::Config(ref cmd) => {
+                commands::config::run(cmd, self.options.config_path())
+            }
+            DistantSubcommand::Doctor { local, ref network } => {
+                commands::doctor::run(local, network.clone(), self.options.config_path())
+            }
             DistantSubcommand::Generate(cmd) => commands::generate::run(cmd),
+            DistantSubcommand::History {
+                connection,
+                grep,
+                history_file,
+                format,
+            } => commands::history::run(connection, grep, history_file, format),
+            DistantSubcommand::ExportState {
+                ref archive,
+                ref include,
+                encrypt_keys,
+                ref password_file,
+                ref history_file,
+            } => commands::state::export(
+                archive.clone(),
+                include.clone(),
+                encrypt_keys,
+                password_file.clone(),
+                history_file.clone(),
+                self.options.config_path(),
+            ),
+            DistantSubcommand::ImportState {
+                ref archive,
+                ref include,
+                force,
+                ref password_file,
+                ref history_file,
+            } => commands::state::import(
+                archive.clone(),
+                include.clone(),
+                force,
+                password_file.clone(),
+                history_file.clone(),
+                self.options.config_path(),
+            ),
             DistantSubcommand::Manager(cmd) => commands::manager::run(cmd),
             DistantSubcommand::Server(cmd) => commands::server::run(cmd),
+            DistantSubcommand::Render { input, format } => commands::render::run(input, format),
+            DistantSubcommand::Plugin(ref args) => {
+                commands::plugin::run(args, self.options.config_path())
+            }
         }
     }
 }
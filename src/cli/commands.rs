@@ -1,5 +1,11 @@
 pub mod client;
-mod common;
+pub(crate) mod common;
+pub mod config;
+pub mod doctor;
 pub mod generate;
+pub mod history;
 pub mod manager;
+pub mod plugin;
+pub mod render;
 pub mod server;
+pub mod state;
@@ -1,37 +1,87 @@
 use crate::cli::common::{
-    Cache, Client, JsonAuthHandler, MsgReceiver, MsgSender, PromptAuthHandler,
+    Cache, Client, History, HistoryEntry, HistoryKind, JsonAuthHandler, MsgReceiver, MsgSender,
+    PromptAuthHandler, Trust,
 };
 use crate::constants::MAX_PIPE_CHUNK_SIZE;
-use crate::options::{ClientFileSystemSubcommand, ClientSubcommand, Format, NetworkSettings};
+use crate::options::{
+    ApiArgs, ClientFileSystemSubcommand, ClientNetworkConfig, ClientSubcommand, ClipboardMode,
+    CompletionKind, ConnectArgs, ConnectionSelector, EscapeChar, FindArgs, Format, GrepArgs,
+    LaunchArgs, NetworkSettings, ReadArgs, SearchArgs, ShellArgs, SpawnArgs, SyncArgs, WatchArgs,
+    WriteArgs,
+};
 use crate::{CliError, CliResult};
 use anyhow::Context;
-use distant_core::data::{ChangeKindSet, FileType, SearchQuery, SystemInfo};
-use distant_core::net::common::{ConnectionId, Host, Map, Request, Response};
+use distant_core::data::{
+    BatchOp, ChangeKindSet, DiffTarget, ProcLimits, SearchQuery, SearchQueryOptions,
+    SearchQueryTarget, SnapshotId, SnapshotInfo, SystemInfo,
+};
+use distant_core::net::common::{ConnectionId, Destination, Host, Map, Request, Response};
 use distant_core::net::manager::ManagerClient;
 use distant_core::{DistantChannel, DistantChannelExt, Watcher};
 use distant_core::{DistantMsg, DistantRequestData, DistantResponseData, RemoteCommand, Searcher};
 use log::*;
 use serde_json::json;
+use std::future::Future;
 use std::io::Write;
-use std::{io, path::Path, time::Duration};
-use tabled::{object::Rows, style::Style, Alignment, Disable, Modify, Table, Tabled};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{io, path::Path};
+use tabled::{object::Rows, style::Style, Alignment, Modify, Table, Tabled};
 use tokio::sync::mpsc;
 
+mod agent;
+mod bench;
+mod browse;
+mod clipboard;
+mod complete;
+mod copy;
+mod edit;
+mod escape;
+mod find;
+mod forward;
+mod grep;
+mod launch;
 mod lsp;
+mod modes;
+mod mouse;
+mod ongoing;
+mod session;
 mod shell;
-
-use super::common::{Formatter, RemoteProcessLink};
+mod socks;
+mod sync;
+mod title;
+mod top;
+
+use super::common::{print_procs, stdout, Formatter, OutputPolicy, RemoteProcessLink};
+use agent::AgentForwarder;
+use bench::{Bench, FrameOverhead};
+use browse::Browser;
+use copy::Copier;
+use edit::Editor;
+use forward::Forwarder;
+use grep::GrepPrinter;
+use launch::LaunchProfiles;
 use lsp::Lsp;
+use ongoing::Ongoing;
+use session::{Comparer, Mock, SessionEntry};
 use shell::Shell;
+use sync::Syncer;
+use top::Top;
 
 const SLEEP_DURATION: Duration = Duration::from_millis(1);
 
+/// Maximum time to wait for the response to a ping before considering it lost
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum entries of a directory sent in each response when reading it, so that they are printed
+/// as they are read rather than once all of them have been
+const DIR_READ_CHUNK_SIZE: usize = 1000;
+
 pub fn run(cmd: ClientSubcommand) -> CliResult {
     let rt = tokio::runtime::Runtime::new().context("Failed to start up runtime")?;
     rt.block_on(async_run(cmd))
 }
 
-async fn read_cache(path: &Path) -> Cache {
+pub(super) async fn read_cache(path: &Path) -> Cache {
     // If we get an error, just default anyway
     Cache::read_from_disk_or_default(path.to_path_buf())
         .await
@@ -40,12 +90,91 @@ async fn read_cache(path: &Path) -> Cache {
 
 async fn async_run(cmd: ClientSubcommand) -> CliResult {
     match cmd {
+        ClientSubcommand::Bench {
+            cache,
+            connection,
+            network,
+            count,
+            size,
+        } => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            let mut bench = Bench::new(channel.into_client().into_channel(), size, count);
+            let upload = bench
+                .upload()
+                .await
+                .with_context(|| format!("Failed to upload using connection {connection_id}"))?;
+            println!("Upload:   {}", upload.describe());
+
+            let download = bench
+                .download()
+                .await
+                .with_context(|| format!("Failed to download using connection {connection_id}"))?;
+            println!("Download: {}", download.describe());
+
+            let overhead = FrameOverhead::of(size).context("Failed to measure frame overhead")?;
+            println!(
+                "Frame overhead: {} B per request, {} B per response",
+                overhead.request, overhead.response
+            );
+        }
+        ClientSubcommand::Browse {
+            cache,
+            connection,
+            network,
+            download_dir,
+            path,
+        } => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            let download_dir = match download_dir {
+                Some(dir) => dir,
+                None => std::env::current_dir()
+                    .context("Failed to get current directory for downloads")?,
+            };
+
+            Browser::new(channel.into_client().into_channel(), download_dir)
+                .browse(path)
+                .await
+                .with_context(|| format!("Failed to browse using connection {connection_id}"))?;
+        }
         ClientSubcommand::Capabilities {
             cache,
             connection,
             format,
             network,
         } => {
+            let format = format.unwrap_or_default();
             debug!("Connecting to manager");
             let mut client = connect_to_manager(format, network).await?;
 
@@ -72,35 +201,104 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 })?;
 
             match format {
-                Format::Shell => {
-                    #[derive(Tabled)]
-                    struct EntryRow {
-                        kind: String,
-                        description: String,
-                    }
-
-                    let table = Table::new(capabilities.into_sorted_vec().into_iter().map(|cap| {
-                        EntryRow {
-                            kind: cap.kind,
-                            description: cap.description,
-                        }
-                    }))
-                    .with(Style::ascii())
-                    .with(Modify::new(Rows::new(..)).with(Alignment::left()))
-                    .to_string();
-
-                    println!("{table}");
-                }
+                Format::Shell => Formatter::shell()
+                    .paged()
+                    .print(Response::new(
+                        "".to_string(),
+                        DistantMsg::Single(DistantResponseData::Capabilities {
+                            supported: capabilities,
+                        }),
+                    ))
+                    .context("Failed to print capabilities")?,
                 Format::Json => println!("{}", serde_json::to_string(&capabilities).unwrap()),
             }
         }
-        ClientSubcommand::Connect {
+        ClientSubcommand::Complete {
             cache,
-            destination,
-            format,
+            connection,
             network,
-            options,
+            kind,
+            current,
         } => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            match kind {
+                CompletionKind::Connections => {
+                    let list = client
+                        .list()
+                        .await
+                        .context("Failed to get a list of managed connections")?;
+
+                    let mut ids = list.keys().copied().collect::<Vec<_>>();
+                    ids.sort_unstable();
+                    for id in ids {
+                        if id.to_string().starts_with(&current) {
+                            let mut destination = list[id].clone();
+                            destination.password = None;
+                            println!("{id}\t{destination}");
+                        }
+                    }
+                }
+                CompletionKind::Paths => {
+                    let mut cache = read_cache(&cache).await;
+                    let connection_id =
+                        use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+                    debug!("Opening channel to connection {}", connection_id);
+                    let mut channel: DistantChannel = client
+                        .open_raw_channel(connection_id)
+                        .await
+                        .with_context(|| {
+                            format!("Failed to open channel to connection {connection_id}")
+                        })?
+                        .into_client()
+                        .into_channel();
+
+                    // An empty directory is the current directory of the server
+                    let (dir, prefix) = complete::split_path(&current);
+                    let (entries, _) = channel
+                        .read_dir(
+                            if dir.is_empty() { "." } else { dir },
+                            1,
+                            false,
+                            false,
+                            false,
+                            Default::default(),
+                        )
+                        .await
+                        .with_context(|| format!("Failed to read directory {dir:?}"))?;
+
+                    for path in complete::path_candidates(dir, prefix, entries) {
+                        println!("{path}");
+                    }
+                }
+            }
+        }
+        ClientSubcommand::Connect(args) => {
+            let ConnectArgs {
+                cache,
+                destination,
+                format,
+                history,
+                name,
+                network,
+                options,
+                path_map,
+                roots,
+            } = *args;
+            let format = format.unwrap_or_default();
+            let destination = destination
+                .context("No destination provided, either as an argument or by the profile")?;
+            let history = History::from_settings(history);
+            let started = SystemTime::now();
+            let entry_destination = destination.clone();
+            let entry_name = name.clone();
+
             debug!("Connecting to manager");
             let mut client = connect_to_manager(format, network).await?;
 
@@ -108,15 +306,50 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
             debug!("Connecting to server at {} with {}", destination, options);
             let id = match format {
                 Format::Shell => client
-                    .connect(*destination, options, PromptAuthHandler::new())
+                    .connect_with_name(*destination, options, name, PromptAuthHandler::new())
                     .await
                     .context("Failed to connect to server")?,
                 Format::Json => client
-                    .connect(*destination, options, JsonAuthHandler::default())
+                    .connect_with_name(*destination, options, name, JsonAuthHandler::default())
                     .await
                     .context("Failed to connect to server")?,
             };
 
+            history
+                .record(
+                    HistoryEntry::new(HistoryKind::Connect, id, started)
+                        .with_name(entry_name)
+                        .with_destination(&entry_destination),
+                )
+                .await;
+
+            // Mappings and roots belong to the server's side of the connection, so they apply to
+            // every channel opened to it from here on
+            if !path_map.is_empty() || !roots.is_empty() {
+                let mut channel: DistantChannel = client
+                    .open_raw_channel(id)
+                    .await
+                    .with_context(|| format!("Failed to open channel to connection {id}"))?
+                    .into_client()
+                    .into_channel();
+
+                if !path_map.is_empty() {
+                    debug!("Setting path mappings of connection {}", id);
+                    channel
+                        .path_map(path_map)
+                        .await
+                        .context("Failed to set path mappings")?;
+                }
+
+                if !roots.is_empty() {
+                    debug!("Declaring roots of connection {}", id);
+                    channel
+                        .roots(roots)
+                        .await
+                        .context("Failed to declare roots")?;
+                }
+            }
+
             // Mark the server's id as the new default
             debug!("Updating selected connection id in cache to {}", id);
             let mut cache = read_cache(&cache).await;
@@ -135,29 +368,26 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 ),
             }
         }
-        ClientSubcommand::Launch {
-            cache,
-            mut destination,
-            distant_args,
-            distant_bin,
-            distant_bind_server,
-            format,
-            network,
-            mut options,
-        } => {
-            debug!("Connecting to manager");
-            let mut client = connect_to_manager(format, network).await?;
-
-            // Grab the host we are connecting to for later use
-            let host = destination.host.to_string();
-
-            // If we have no scheme on launch, we need to fill it in with something
-            //
-            // TODO: Can we have the server support this instead of the client? Right now, the
-            //       server is failing because it cannot parse //localhost/ as it fails with
-            //       an invalid IPv4 or registered name character error on host
-            if destination.scheme.is_none() {
-                destination.scheme = Some("ssh".to_string());
+        ClientSubcommand::Launch(args) => {
+            let LaunchArgs {
+                cache,
+                destinations,
+                distant_args,
+                distant_bin,
+                distant_bind_server,
+                format,
+                history,
+                network,
+                mut options,
+                verify,
+                all_from,
+                ..
+            } = *args;
+            let format = format.unwrap_or_default();
+            if destinations.is_empty() && all_from.is_none() {
+                return Err(CliError::Error(anyhow::anyhow!(
+                    "No destination provided, either as an argument or by the profile"
+                )));
             }
 
             // TODO: Handle this more cleanly
@@ -171,72 +401,180 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 options.insert("distant.bind_server".to_string(), x.to_string());
             }
 
-            // Start the server using our manager
-            debug!("Launching server at {} with {}", destination, options);
-            let mut new_destination = match format {
-                Format::Shell => client
-                    .launch(*destination, options, PromptAuthHandler::new())
-                    .await
-                    .context("Failed to launch server")?,
-                Format::Json => client
-                    .launch(*destination, options, JsonAuthHandler::default())
-                    .await
-                    .context("Failed to launch server")?,
-            };
+            let history = History::from_settings(history);
 
-            // Update the new destination with our previously-used host if the
-            // new host is not globally-accessible
-            if !new_destination.host.is_global() {
-                trace!(
-                    "Updating host to {:?} from non-global {:?}",
-                    host,
-                    new_destination.host.to_string()
-                );
-                new_destination.host = host
-                    .parse::<Host>()
-                    .map_err(|x| anyhow::anyhow!(x))
-                    .context("Failed to replace host")?;
-            } else {
-                trace!("Host {:?} is global", new_destination.host.to_string());
+            // Single destination retains the original output of just the connection id
+            if all_from.is_none() && destinations.len() == 1 {
+                debug!("Connecting to manager");
+                let mut client = connect_to_manager(format, network).await?;
+
+                let destination = destinations.into_iter().next().unwrap();
+                let started = SystemTime::now();
+                let id = launch_server(&mut client, format, destination.clone(), options, verify)
+                    .await?;
+                history
+                    .record(
+                        HistoryEntry::new(HistoryKind::Launch, id, started)
+                            .with_destination(&destination),
+                    )
+                    .await;
+
+                // Mark the server's id as the new default
+                debug!("Updating selected connection id in cache to {}", id);
+                let mut cache = read_cache(&cache).await;
+                *cache.data.selected = id;
+                cache.write_to_disk().await?;
+
+                match format {
+                    Format::Shell => println!("{id}"),
+                    Format::Json => println!(
+                        "{}",
+                        serde_json::to_string(&json!({
+                            "type": "launched",
+                            "id": id,
+                        }))
+                        .unwrap()
+                    ),
+                }
+
+                return Ok(());
             }
 
-            // Trigger our manager to connect to the launched server
-            debug!("Connecting to server at {}", new_destination);
-            let id = match format {
-                Format::Shell => client
-                    .connect(new_destination, Map::new(), PromptAuthHandler::new())
-                    .await
-                    .context("Failed to connect to server")?,
-                Format::Json => client
-                    .connect(new_destination, Map::new(), JsonAuthHandler::default())
-                    .await
-                    .context("Failed to connect to server")?,
-            };
+            let mut targets: Vec<(String, Destination, Map)> = destinations
+                .into_iter()
+                .map(|destination| (destination.to_string(), destination, options.clone()))
+                .collect();
+
+            if let Some(path) = all_from {
+                debug!("Loading launch profiles from {:?}", path);
+                let profiles = LaunchProfiles::load(&path).await?;
+                for (name, profile) in profiles.0 {
+                    let mut options = options.clone();
+                    options.extend(profile.options);
+                    targets.push((name, profile.destination, options));
+                }
+            }
 
-            // Mark the server's id as the new default
-            debug!("Updating selected connection id in cache to {}", id);
-            let mut cache = read_cache(&cache).await;
-            *cache.data.selected = id;
-            cache.write_to_disk().await?;
+            // Launch every destination concurrently, each using its own connection to the
+            // manager so that they do not block one another
+            debug!("Launching {} servers", targets.len());
+            let mut tasks = Vec::new();
+            for (name, destination, options) in targets {
+                let network = network.clone();
+                let history = history.clone();
+                tasks.push((
+                    name,
+                    tokio::spawn(async move {
+                        let started = SystemTime::now();
+                        let mut client = connect_to_manager(format, network).await?;
+                        let id = launch_server(
+                            &mut client,
+                            format,
+                            destination.clone(),
+                            options,
+                            verify,
+                        )
+                        .await?;
+                        history
+                            .record(
+                                HistoryEntry::new(HistoryKind::Launch, id, started)
+                                    .with_destination(&destination),
+                            )
+                            .await;
+                        Ok(id)
+                    }),
+                ));
+            }
+
+            let mut results = Vec::new();
+            for (name, task) in tasks {
+                let result = match task.await {
+                    Ok(result) => result,
+                    Err(x) => Err(anyhow::anyhow!(x).context("Launch task failed")),
+                };
+                results.push((name, result));
+            }
+
+            // Mark the first successfully-launched server's id as the new default
+            if let Some(id) = results.iter().find_map(|(_, x)| x.as_ref().ok().copied()) {
+                debug!("Updating selected connection id in cache to {}", id);
+                let mut cache = read_cache(&cache).await;
+                *cache.data.selected = id;
+                cache.write_to_disk().await?;
+            }
 
+            let failed = results.iter().filter(|(_, x)| x.is_err()).count();
             match format {
-                Format::Shell => println!("{id}"),
+                Format::Shell => {
+                    #[derive(Tabled)]
+                    struct LaunchRow {
+                        name: String,
+                        status: &'static str,
+                        result: String,
+                    }
+
+                    let rows = results.into_iter().map(|(name, result)| match result {
+                        Ok(id) => LaunchRow {
+                            name,
+                            status: "ok",
+                            result: id.to_string(),
+                        },
+                        Err(x) => LaunchRow {
+                            name,
+                            status: "failed",
+                            result: format!("{x:#}"),
+                        },
+                    });
+                    let table = OutputPolicy::current().table(rows, |table| {
+                        table
+                            .with(Style::ascii())
+                            .with(Modify::new(Rows::new(..)).with(Alignment::left()))
+                    });
+
+                    println!("{table}");
+                }
                 Format::Json => println!(
                     "{}",
                     serde_json::to_string(&json!({
-                        "type": "launched",
-                        "id": id,
+                        "type": "launched_all",
+                        "results": results
+                            .into_iter()
+                            .map(|(name, result)| match result {
+                                Ok(id) => json!({ "name": name, "id": id }),
+                                Err(x) => json!({ "name": name, "error": format!("{x:#}") }),
+                            })
+                            .collect::<Vec<_>>(),
                     }))
                     .unwrap()
                 ),
             }
+
+            if failed > 0 {
+                return Err(CliError::FAILURE);
+            }
         }
-        ClientSubcommand::Api {
-            cache,
-            connection,
-            network,
-            timeout,
-        } => {
+        ClientSubcommand::Api(args) => {
+            let ApiArgs {
+                cache,
+                connection,
+                network,
+                timeout,
+                record,
+                replay,
+                mock,
+                matcher,
+                ignore,
+            } = *args;
+            let comparer = Comparer { matcher, ignore };
+            if let Some(path) = mock {
+                debug!("Mocking session recorded in {path:?}");
+                let mock = Mock::new(session::load(&path)?, comparer);
+                session::serve_mock(mock)
+                    .await
+                    .context("Failed to mock session")?;
+                return Ok(());
+            }
+
             debug!("Connecting to manager");
             let mut client = Client::new(network)
                 .using_json_auth_handler()
@@ -269,6 +607,40 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 }
             );
 
+            if let Some(path) = replay {
+                debug!("Replaying session recorded in {path:?}");
+                let exchanges = session::load(&path)?;
+                let mut channel: DistantChannel = channel.into_client().into_channel();
+                let timeout = Duration::from_secs_f32(timeout.unwrap_or(10.0));
+                let mismatches = session::replay(&mut channel, exchanges, &comparer, timeout)
+                    .await
+                    .context("Failed to replay session")?;
+
+                for mismatch in mismatches.iter() {
+                    eprintln!("{mismatch}");
+                }
+
+                if !mismatches.is_empty() {
+                    return Err(CliError::FAILURE);
+                }
+
+                return Ok(());
+            }
+
+            let recorder = record
+                .map(|path| {
+                    session::recorder(&path).with_context(|| format!("Failed to create {path:?}"))
+                })
+                .transpose()?;
+            let record = move |entry: SessionEntry| {
+                if let Some(recorder) = recorder.as_ref() {
+                    if let Err(x) = recorder.send_blocking(&entry) {
+                        error!("Failed to record session: {x}");
+                    }
+                }
+            };
+            let record_request = record.clone();
+
             debug!("Starting api tasks");
             let (msg_tx, mut msg_rx) = mpsc::channel(1);
             let request_task = tokio::spawn(async move {
@@ -277,6 +649,26 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 loop {
                     match rx.recv().await {
                         Some(Ok(request)) => {
+                            // The server gives up on requests once we would stop waiting on them
+                            let request = match timeout {
+                                Some(secs) if request.deadline.is_none() => {
+                                    request.with_deadline(Duration::from_secs_f32(secs))
+                                }
+                                _ => request,
+                            };
+
+                            if let Some(correlation_id) = request.correlation_id.as_deref() {
+                                debug!(
+                                    "[Conn {connection_id} :: Req {} :: Corr {correlation_id}] \
+                                    Sending request",
+                                    request.id
+                                );
+                            }
+
+                            if let Ok(value) = serde_json::to_value(&request) {
+                                record_request(SessionEntry::Request(value));
+                            }
+
                             if let Err(x) = msg_tx.send(request).await {
                                 error!("Failed to forward request: {x}");
                                 break;
@@ -293,6 +685,7 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
             });
             let channel_task = tokio::task::spawn(async move {
                 let tx = MsgSender::from_stdout();
+                let mut ongoing = Ongoing::default();
 
                 loop {
                     let ready = channel.readable_or_writeable().await?;
@@ -305,7 +698,22 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                         match channel
                             .try_read_frame_as::<Response<DistantMsg<DistantResponseData>>>()
                         {
-                            Ok(Some(msg)) => tx.send_blocking(&msg)?,
+                            Ok(Some(msg)) => {
+                                if let Ok(value) = serde_json::to_value(&msg) {
+                                    record(SessionEntry::Response(value));
+                                }
+
+                                ongoing.track_response(&msg.payload);
+                                match tx.send_blocking(&msg) {
+                                    // Nothing reads responses anymore, such as `| head`, so stop
+                                    // whatever would go on sending them
+                                    Err(x) if stdout::is_closed(&x) => {
+                                        debug!("Stdout closed, so cancelling ongoing operations");
+                                        return ongoing.cancel(&mut channel).await;
+                                    }
+                                    x => x?,
+                                }
+                            }
                             Ok(None) => break,
                             Err(x) if x.kind() == io::ErrorKind::WouldBlock => {
                                 read_blocked = true;
@@ -316,6 +724,7 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
 
                     if ready.is_writable() {
                         if let Ok(msg) = msg_rx.try_recv() {
+                            ongoing.track_request(&msg.payload);
                             match channel.try_write_frame_for(&msg) {
                                 Ok(_) => (),
                                 Err(x) if x.kind() == io::ErrorKind::WouldBlock => {
@@ -346,9 +755,11 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 io::Result::Ok(())
             });
 
-            let (r1, r2) = tokio::join!(request_task, channel_task);
-            match r1 {
-                Err(x) => error!("{}", x),
+            // Once responses are no longer read, there is no point in reading more requests
+            let r2 = channel_task.await;
+            request_task.abort();
+            match request_task.await {
+                Err(x) if !x.is_cancelled() => error!("{}", x),
                 Ok(Err(x)) => error!("{}", x),
                 _ => (),
             }
@@ -360,14 +771,17 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
 
             debug!("Shutting down repl");
         }
-        ClientSubcommand::Shell {
+        ClientSubcommand::Ping {
             cache,
-            cmd,
             connection,
-            current_dir,
-            environment,
             network,
+            count,
+            interval,
         } => {
+            let interval = Duration::try_from_secs_f32(interval)
+                .ok()
+                .context("Interval must be a non-negative number of seconds")?;
+
             debug!("Connecting to manager");
             let mut client = Client::new(network)
                 .using_prompt_auth_handler()
@@ -380,39 +794,56 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
 
             debug!("Opening channel to connection {}", connection_id);
-            let channel = client
+            let mut channel = client
                 .open_raw_channel(connection_id)
                 .await
-                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?
+                .into_client()
+                .into_channel();
 
-            // Convert cmd into string
-            let cmd = cmd.map(|cmd| cmd.join(" "));
+            let mut times = Vec::new();
+            for seq in 1..=count {
+                if seq > 1 {
+                    tokio::time::sleep(interval).await;
+                }
 
-            debug!(
-                "Spawning shell (environment = {:?}): {}",
-                environment,
-                cmd.as_deref().unwrap_or(r"$SHELL")
+                let start = Instant::now();
+                match tokio::time::timeout(PING_TIMEOUT, channel.ping()).await {
+                    Ok(Ok(())) => {
+                        let time = start.elapsed();
+                        println!(
+                            "Pong from connection {connection_id}: seq={seq} time={:.3} ms",
+                            millis(time)
+                        );
+                        times.push(time);
+                    }
+                    Ok(Err(x)) => eprintln!("Ping {seq} failed: {x}"),
+                    Err(_) => eprintln!("Ping {seq} timed out"),
+                }
+            }
+
+            println!("{count} pings sent, {} received", times.len());
+            let (Some(min), Some(max)) = (times.iter().min(), times.iter().max()) else {
+                return Err(CliError::FAILURE);
+            };
+            let avg = times.iter().sum::<Duration>() / times.len() as u32;
+            println!(
+                "round-trip min/avg/max = {:.3}/{:.3}/{:.3} ms",
+                millis(*min),
+                millis(avg),
+                millis(*max)
             );
-            Shell::new(channel.into_client().into_channel())
-                .spawn(cmd, environment, current_dir, MAX_PIPE_CHUNK_SIZE)
-                .await?;
         }
-        ClientSubcommand::Spawn {
+        ClientSubcommand::Ps {
             cache,
             connection,
-            cmd,
-            current_dir,
-            environment,
-            lsp,
-            pty,
             network,
+            format,
+            all,
         } => {
+            let format = format.unwrap_or_default();
             debug!("Connecting to manager");
-            let mut client = Client::new(network)
-                .using_prompt_auth_handler()
-                .connect()
-                .await
-                .context("Failed to connect to manager")?;
+            let mut client = connect_to_manager(format, network).await?;
 
             let mut cache = read_cache(&cache).await;
             let connection_id =
@@ -424,81 +855,416 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 .await
                 .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
 
-            // Convert cmd into string
-            let cmd = cmd.join(" ");
-
-            if lsp {
-                debug!(
-                    "Spawning LSP server (pty = {}, cwd = {:?}): {}",
-                    pty, current_dir, cmd
-                );
-                Lsp::new(channel.into_client().into_channel())
-                    .spawn(cmd, current_dir, pty, MAX_PIPE_CHUNK_SIZE)
-                    .await?;
-            } else if pty {
-                debug!(
-                    "Spawning pty process (environment = {:?}, cwd = {:?}): {}",
-                    environment, current_dir, cmd
-                );
-                Shell::new(channel.into_client().into_channel())
-                    .spawn(cmd, environment, current_dir, MAX_PIPE_CHUNK_SIZE)
-                    .await?;
-            } else {
-                debug!(
-                    "Spawning regular process (environment = {:?}, cwd = {:?}): {}",
-                    environment, current_dir, cmd
-                );
-                let mut proc = RemoteCommand::new()
-                    .environment(environment)
-                    .current_dir(current_dir)
-                    .pty(None)
-                    .spawn(channel.into_client().into_channel(), &cmd)
-                    .await
-                    .with_context(|| format!("Failed to spawn {cmd}"))?;
-
-                // Now, map the remote process' stdin/stdout/stderr to our own process
-                let link = RemoteProcessLink::from_remote_pipes(
-                    proc.stdin.take(),
-                    proc.stdout.take().unwrap(),
-                    proc.stderr.take().unwrap(),
-                    MAX_PIPE_CHUNK_SIZE,
-                );
-
-                let status = proc.wait().await.context("Failed to wait for process")?;
-
-                // Shut down our link
-                link.shutdown().await;
+            debug!("Listing processes");
+            let procs = channel
+                .into_client()
+                .into_channel()
+                .proc_list(all)
+                .await
+                .with_context(|| {
+                    format!("Failed to list processes using connection {connection_id}")
+                })?;
 
-                if !status.success {
-                    if let Some(code) = status.code {
-                        return Err(CliError::Exit(code as u8));
-                    } else {
-                        return Err(CliError::FAILURE);
-                    }
-                }
-            }
+            print_procs(format, procs);
         }
-        ClientSubcommand::SystemInfo {
-            cache,
-            connection,
-            network,
-        } => {
+        ClientSubcommand::Shell(args) => {
+            let ShellArgs {
+                cache,
+                cmd,
+                connection,
+                current_dir,
+                environment,
+                history,
+                trust,
+                network,
+                poll_input,
+                clipboard,
+                escape_char,
+                detach_on_exit,
+                attach,
+                claim,
+                forward_agent,
+                init_cmd,
+            } = *args;
+            let history = History::from_settings(history);
+            let trust = Trust::from_settings(trust);
+            let started = SystemTime::now();
+
             debug!("Connecting to manager");
-            let mut client = Client::new(network)
+            let mut client = Client::new(network.clone())
                 .using_prompt_auth_handler()
                 .connect()
                 .await
                 .context("Failed to connect to manager")?;
 
-            let mut cache = read_cache(&cache).await;
-            let connection_id =
-                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+            // Claiming takes over the channel of another shell, which belongs to its connection
+            let (channel, resources) = match claim {
+                Some(token) => {
+                    debug!("Claiming handed off channel");
+                    let (channel, resources) = client
+                        .claim_channel(token)
+                        .await
+                        .context("Failed to claim handed off shell")?;
+                    (channel, Some(resources))
+                }
+                None => {
+                    let mut cache = read_cache(&cache).await;
+                    let connection_id =
+                        use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+                    // Attaching is to a shell that was already trusted when it was spawned
+                    if attach.is_none() {
+                        ensure_trusted(&trust, &mut client, connection_id, current_dir.as_deref())
+                            .await?;
+                    }
 
-            debug!("Opening channel to connection {}", connection_id);
-            let channel = client
-                .open_raw_channel(connection_id)
-                .await
-                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+                    debug!("Opening channel to connection {}", connection_id);
+                    let channel =
+                        client
+                            .open_raw_channel(connection_id)
+                            .await
+                            .with_context(|| {
+                                format!("Failed to open channel to connection {connection_id}")
+                            })?;
+                    (channel, None)
+                }
+            };
+
+            let connection_id = channel.connection_id();
+            let watcher = channel.clone_connection_watcher();
+            let handoff = channel.clone_handoff();
+
+            // Title our terminal after where the shell runs, which is not worth failing over
+            let title = match client.info(connection_id).await {
+                Ok(info) => Some(title::default_title(&info.destination)),
+                Err(x) => {
+                    debug!("Failed to look up connection {connection_id} for title: {x}");
+                    None
+                }
+            };
+            let channel = channel.into_client().into_channel();
+            let mut environment = environment;
+            let agent = if forward_agent {
+                Some(AgentForwarder::start(channel.clone(), &mut environment).await?)
+            } else {
+                None
+            };
+
+            // Convert cmd into string
+            let cmd = cmd.map(|cmd| cmd.join(" "));
+
+            // Should the channel be lost, open a new one through a fresh connection to the manager
+            // as the manager itself may be what we lost our connection to
+            let reconnect: shell::Reconnect = Box::new(move || {
+                let network = network.clone();
+                Box::pin(async move {
+                    let mut client = Client::new(network)
+                        .using_prompt_auth_handler()
+                        .connect()
+                        .await
+                        .map_err(|x| io::Error::new(io::ErrorKind::NotConnected, x))?;
+                    Ok(client.open_raw_channel(connection_id).await?)
+                })
+            });
+
+            let shell = Shell::new(channel)
+                .with_connection_watcher(watcher)
+                .with_reconnect(reconnect)
+                .with_detach_on_exit(detach_on_exit)
+                .with_init_cmd(init_cmd)
+                .with_handoff(handoff)
+                .with_title(title);
+            let result = match (attach, resources) {
+                (_, Some(resources)) => {
+                    debug!("Claiming shell with resources {}", resources);
+                    shell
+                        .claim(resources, poll_input, clipboard, escape_char)
+                        .await
+                }
+                (Some(session), None) => {
+                    debug!("Attaching to shell of session {}", session);
+                    shell
+                        .attach(session, poll_input, clipboard, escape_char)
+                        .await
+                }
+                (None, None) => {
+                    debug!(
+                        "Spawning shell (environment = {:?}): {}",
+                        environment,
+                        cmd.as_deref().unwrap_or(r"$SHELL")
+                    );
+                    shell
+                        .spawn(
+                            cmd.clone(),
+                            environment,
+                            current_dir,
+                            poll_input,
+                            clipboard,
+                            escape_char,
+                        )
+                        .await
+                }
+            };
+
+            if let Some(agent) = agent {
+                agent.stop().await;
+            }
+
+            let entry = HistoryEntry::new(HistoryKind::Shell, connection_id, started);
+            let entry = match cmd {
+                Some(cmd) => entry.with_command(cmd),
+                None => entry,
+            };
+            record_command(&history, &mut client, entry, &result).await;
+            result?;
+        }
+        ClientSubcommand::Signal {
+            cache,
+            connection,
+            network,
+            id,
+            signal,
+        } => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            debug!("Sending signal {} to process {}", signal, id);
+            channel
+                .into_client()
+                .into_channel()
+                .proc_signal(id, signal)
+                .await
+                .with_context(|| format!("Failed to send signal {signal} to process {id}"))?;
+        }
+        ClientSubcommand::Kill {
+            cache,
+            connection,
+            network,
+            pid,
+            signal,
+        } => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            debug!("Sending signal {} to system process {}", signal, pid);
+            channel
+                .into_client()
+                .into_channel()
+                .system_kill(pid, signal)
+                .await
+                .with_context(|| format!("Failed to send signal {signal} to pid {pid}"))?;
+        }
+        ClientSubcommand::Spawn(args) => {
+            let SpawnArgs {
+                cache,
+                connection,
+                cmd,
+                current_dir,
+                environment,
+                history,
+                trust,
+                lsp,
+                pty,
+                forward_agent,
+                poll_input,
+                detached,
+                kill_tree,
+                limits,
+                user,
+                network,
+            } = *args;
+            let history = History::from_settings(history);
+            let trust = Trust::from_settings(trust);
+            let started = SystemTime::now();
+
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+            ensure_trusted(&trust, &mut client, connection_id, current_dir.as_deref()).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            let channel = channel.into_client().into_channel();
+            let mut environment = environment;
+            let agent = if forward_agent {
+                Some(AgentForwarder::start(channel.clone(), &mut environment).await?)
+            } else {
+                None
+            };
+
+            // Convert cmd into string
+            let cmd = cmd.join(" ");
+            let limits = ProcLimits::from(limits);
+
+            let result: CliResult = async {
+                if detached {
+                    debug!(
+                        "Spawning detached process (environment = {:?}, cwd = {:?}): {}",
+                        environment, current_dir, cmd
+                    );
+                    let pid = RemoteCommand::new()
+                        .environment(environment)
+                        .current_dir(current_dir)
+                        .limits(limits)
+                        .user(user)
+                        .spawn_detached(channel, &cmd)
+                        .await
+                        .with_context(|| format!("Failed to spawn {cmd}"))?;
+                    println!("{pid}");
+                } else if lsp {
+                    debug!(
+                        "Spawning LSP server (pty = {}, cwd = {:?}): {}",
+                        pty, current_dir, cmd
+                    );
+                    Lsp::new(channel)
+                        .spawn(
+                            cmd.clone(),
+                            current_dir,
+                            pty,
+                            limits,
+                            user,
+                            MAX_PIPE_CHUNK_SIZE,
+                        )
+                        .await?;
+                } else if pty {
+                    debug!(
+                        "Spawning pty process (environment = {:?}, cwd = {:?}): {}",
+                        environment, current_dir, cmd
+                    );
+                    Shell::new(channel)
+                        .with_limits(limits)
+                        .with_kill_tree(kill_tree)
+                        .with_user(user)
+                        .spawn(
+                            cmd.clone(),
+                            environment,
+                            current_dir,
+                            poll_input,
+                            ClipboardMode::Off,
+                            EscapeChar(None),
+                        )
+                        .await?;
+                } else {
+                    debug!(
+                        "Spawning regular process (environment = {:?}, cwd = {:?}): {}",
+                        environment, current_dir, cmd
+                    );
+                    let mut proc = RemoteCommand::new()
+                        .environment(environment)
+                        .current_dir(current_dir)
+                        .pty(None)
+                        .limits(limits)
+                        .kill_tree(kill_tree)
+                        .user(user)
+                        .prompt_handler(Box::new(PromptAuthHandler::new()))
+                        .spawn(channel, &cmd)
+                        .await
+                        .with_context(|| format!("Failed to spawn {cmd}"))?;
+
+                    // Now, map the remote process' stdin/stdout/stderr to our own process
+                    let link = RemoteProcessLink::from_remote_pipes(
+                        proc.stdin.take(),
+                        proc.stdout.take().unwrap(),
+                        proc.stderr.take().unwrap(),
+                        MAX_PIPE_CHUNK_SIZE,
+                    );
+
+                    let killer = proc.clone_killer();
+                    let status = link
+                        .wait_or_kill(proc.wait(), killer)
+                        .await
+                        .context("Failed to wait for process")?;
+
+                    // Shut down our link
+                    link.shutdown().await;
+
+                    // Nothing reads the output of the process anymore, such as `| head`
+                    let Some(status) = status else {
+                        return Ok(());
+                    };
+
+                    if !status.success {
+                        if let Some(code) = status.code {
+                            return Err(CliError::Exit(code as u8));
+                        } else {
+                            return Err(CliError::FAILURE);
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+            .await;
+
+            if let Some(agent) = agent {
+                agent.stop().await;
+            }
+
+            let entry =
+                HistoryEntry::new(HistoryKind::Spawn, connection_id, started).with_command(cmd);
+            record_command(&history, &mut client, entry, &result).await;
+            result?;
+        }
+        ClientSubcommand::SystemInfo {
+            cache,
+            connection,
+            network,
+        } => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
 
             debug!("Retrieving system information");
             let SystemInfo {
@@ -540,10 +1306,429 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
             .context("Failed to write system information to stdout")?;
             out.flush().context("Failed to flush stdout")?;
         }
+        ClientSubcommand::Top {
+            cache,
+            connection,
+            network,
+            interval,
+        } => {
+            let interval = Duration::try_from_secs_f32(interval)
+                .ok()
+                .filter(|interval| !interval.is_zero())
+                .context("Interval must be a positive number of seconds")?;
+
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            Top::new(channel.into_client().into_channel())
+                .run(interval)
+                .await
+                .with_context(|| format!("Failed to monitor using connection {connection_id}"))?;
+        }
+        ClientSubcommand::Copy {
+            cache,
+            connection,
+            network,
+            format,
+            src,
+            dst,
+        } => {
+            let format = format.unwrap_or_default();
+            debug!("Connecting to manager");
+            let mut client = connect_to_manager(format, network).await?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            debug!("Copying {src} to {dst}");
+            Copier::new(channel.into_client().into_channel(), format)
+                .copy(src.clone(), dst.clone())
+                .await
+                .with_context(|| {
+                    format!("Failed to copy {src} to {dst} using connection {connection_id}")
+                })?;
+        }
+        ClientSubcommand::Env {
+            cache,
+            connection,
+            network,
+            names,
+        } => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            debug!("Retrieving system environment");
+            let env = channel
+                .into_client()
+                .into_channel()
+                .system_env()
+                .await
+                .with_context(|| {
+                    format!("Failed to retrieve environment using connection {connection_id}")
+                })?
+                .into_map();
+
+            if names.is_empty() {
+                let mut env: Vec<_> = env.into_iter().collect();
+                env.sort();
+                for (name, value) in env {
+                    println!("{name}={value}");
+                }
+            } else {
+                // Like printenv, every variable found is printed before failing over those missing
+                let mut missing = Vec::new();
+                for name in names {
+                    match env.get(&name) {
+                        Some(value) => println!("{value}"),
+                        None => missing.push(name),
+                    }
+                }
+
+                if !missing.is_empty() {
+                    return Err(CliError::Error(anyhow::anyhow!(
+                        "No variables named {} are shared by the server",
+                        missing.join(", ")
+                    )));
+                }
+            }
+        }
+        ClientSubcommand::Edit {
+            cache,
+            connection,
+            network,
+            editor,
+            watch,
+            path,
+        } => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            debug!("Editing {path:?}");
+            Editor::new(channel.into_client().into_channel(), editor)?
+                .edit(path.clone(), watch)
+                .await
+                .with_context(|| {
+                    format!("Failed to edit {path:?} using connection {connection_id}")
+                })?;
+        }
+        ClientSubcommand::Find(args) => {
+            let FindArgs {
+                cache,
+                connection,
+                network,
+                name,
+                file_types,
+                size,
+                mtime,
+                max_depth,
+                paths,
+            } = *args;
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            let query = SearchQuery {
+                target: SearchQueryTarget::Path,
+                condition: find::condition(name.as_deref()),
+                paths,
+                options: SearchQueryOptions {
+                    max_depth,
+                    ..find::options(file_types, &size, &mtime, now)
+                },
+            };
+
+            let mut searcher = Searcher::search(channel.into_client().into_channel(), query)
+                .await
+                .context("Failed to start search")?;
+
+            while let Some(m) = searcher.next().await {
+                if let Some(m) = m.into_path_match() {
+                    let printed = stdout::write_line(m.path.to_string_lossy());
+                    let cancel =
+                        async { searcher.cancel().await.context("Failed to cancel search") };
+                    if !keep_printing(printed, "path", cancel).await? {
+                        break;
+                    }
+                }
+            }
+        }
+        ClientSubcommand::Forward {
+            cache,
+            connection,
+            network,
+            local,
+            remote,
+            dynamic,
+        } => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            Forwarder::new(channel.into_client().into_channel())
+                .run(local, remote, dynamic)
+                .await
+                .with_context(|| format!("Failed to forward using connection {connection_id}"))?;
+        }
+        ClientSubcommand::Grep(args) => {
+            let GrepArgs {
+                cache,
+                connection,
+                network,
+                ignore_case,
+                fixed_strings,
+                files_with_matches,
+                include,
+                exclude,
+                max_count,
+                after_context,
+                before_context,
+                context,
+                pattern,
+                paths,
+            } = *args;
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            let query = SearchQuery {
+                target: SearchQueryTarget::Contents,
+                condition: grep::condition(pattern, ignore_case, fixed_strings),
+                paths,
+                options: SearchQueryOptions {
+                    include,
+                    exclude,
+                    limit: max_count,
+                    before_context: before_context.or(context).unwrap_or_default(),
+                    after_context: after_context.or(context).unwrap_or_default(),
+                    ..Default::default()
+                },
+            };
+            let has_context = query.options.before_context > 0 || query.options.after_context > 0;
+
+            let mut searcher = Searcher::search(channel.into_client().into_channel(), query)
+                .await
+                .context("Failed to start search")?;
+
+            let mut printer = GrepPrinter::new(files_with_matches, has_context);
+            let mut found = false;
+            while let Some(m) = searcher.next().await {
+                found = true;
+                if let Some(lines) = printer.format(m) {
+                    let printed = stdout::write_line(lines);
+                    let cancel =
+                        async { searcher.cancel().await.context("Failed to cancel search") };
+                    if !keep_printing(printed, "match", cancel).await? {
+                        break;
+                    }
+                }
+            }
+
+            // Like grep, finding nothing is reported through the exit code
+            if !found {
+                return Err(CliError::FAILURE);
+            }
+        }
+        ClientSubcommand::Sync(args) => {
+            let SyncArgs {
+                cache,
+                connection,
+                network,
+                format,
+                watch,
+                bidirectional,
+                conflict,
+                dry_run,
+                src,
+                dst,
+            } = *args;
+            let format = format.unwrap_or_default();
+            debug!("Connecting to manager");
+            let mut client = connect_to_manager(format, network).await?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            debug!("Syncing {src} with {dst}");
+            Syncer::new(
+                channel.into_client().into_channel(),
+                format,
+                src.clone(),
+                dst.clone(),
+                bidirectional,
+                conflict,
+                dry_run,
+            )
+            .await
+            .with_context(|| format!("Failed to sync {src} to {dst}"))?
+            .run(watch)
+            .await
+            .with_context(|| {
+                format!("Failed to sync {src} to {dst} using connection {connection_id}")
+            })?;
+        }
+        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Batch {
+            cache,
+            connection,
+            network,
+            atomic,
+            transaction,
+            file,
+        }) => {
+            let ops: Vec<BatchOp> = match file {
+                Some(path) => {
+                    let text = tokio::fs::read_to_string(&path)
+                        .await
+                        .with_context(|| format!("Failed to read {path:?}"))?;
+                    serde_json::from_str(&text)
+                        .with_context(|| format!("Failed to parse operations from {path:?}"))?
+                }
+                None => {
+                    use std::io::Read;
+                    let mut text = String::new();
+                    std::io::stdin()
+                        .read_to_string(&mut text)
+                        .context("Failed to read stdin")?;
+                    serde_json::from_str(&text).context("Failed to parse operations from stdin")?
+                }
+            };
+
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            let mut channel = channel.into_client().into_channel();
+            if transaction {
+                debug!("Applying transaction of {} operations", ops.len());
+                channel.transaction(ops).await.with_context(|| {
+                    format!("Failed to apply transaction using connection {connection_id}")
+                })?;
+            } else {
+                debug!(
+                    "Performing batch of {} operations (atomic = {atomic})",
+                    ops.len()
+                );
+                channel.batch(ops, atomic).await.with_context(|| {
+                    format!("Failed to perform batch using connection {connection_id}")
+                })?;
+            }
+        }
         ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Copy {
             cache,
             connection,
             network,
+            dry_run,
             src,
             dst,
         }) => {
@@ -564,16 +1749,79 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 .await
                 .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
 
+            let mut channel = channel.into_client().into_channel();
+            if dry_run {
+                debug!("Planning copy of {src:?} to {dst:?}");
+                let op = BatchOp::Copy {
+                    src: src.clone(),
+                    dst: dst.clone(),
+                };
+                print_plan(&mut channel, op).await.with_context(|| {
+                    format!("Failed to plan copying {src:?} to {dst:?} using connection {connection_id}")
+                })?;
+                return Ok(());
+            }
+
             debug!("Copying {src:?} to {dst:?}");
             channel
-                .into_client()
-                .into_channel()
                 .copy(src.as_path(), dst.as_path())
                 .await
                 .with_context(|| {
                     format!("Failed to copy {src:?} to {dst:?} using connection {connection_id}")
                 })?;
         }
+        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Diff {
+            cache,
+            connection,
+            network,
+            context,
+            src,
+            dst,
+        }) => {
+            let dst = match dst {
+                Some(path) => DiffTarget::Path(path),
+                None => {
+                    use std::io::Read;
+                    let mut text = String::new();
+                    std::io::stdin()
+                        .read_to_string(&mut text)
+                        .context("Failed to read stdin")?;
+                    DiffTarget::Text(text)
+                }
+            };
+
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            debug!("Computing diff of {src:?}");
+            let patch = channel
+                .into_client()
+                .into_channel()
+                .diff(src.as_path(), dst, Some(context))
+                .await
+                .with_context(|| {
+                    format!("Failed to compute diff of {src:?} using connection {connection_id}")
+                })?;
+
+            let mut out = std::io::stdout();
+            out.write_all(patch.as_bytes())
+                .context("Failed to write diff to stdout")?;
+            out.flush().context("Failed to flush stdout")?;
+        }
         ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Exists {
             cache,
             connection,
@@ -621,6 +1869,7 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
             network,
             path,
             all,
+            mode,
         }) => {
             debug!("Connecting to manager");
             let mut client = Client::new(network)
@@ -639,15 +1888,56 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 .await
                 .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
 
-            debug!("Making directory {path:?} (all = {all})");
-            channel
-                .into_client()
-                .into_channel()
-                .create_dir(path.as_path(), all)
+            debug!("Making directory {path:?} (all = {all}, mode = {mode:?})");
+            let mut channel = channel.into_client().into_channel();
+            match mode {
+                Some(mode) => {
+                    channel
+                        .create_dir_with_mode(path.as_path(), all, mode)
+                        .await
+                }
+                None => channel.create_dir(path.as_path(), all).await,
+            }
+            .with_context(|| {
+                format!("Failed to make directory {path:?} using connection {connection_id}")
+            })?;
+        }
+        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::MakeTemp {
+            cache,
+            connection,
+            network,
+            dir,
+            parent,
+        }) => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
                 .await
-                .with_context(|| {
-                    format!("Failed to make directory {path:?} using connection {connection_id}")
-                })?;
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
+
+            debug!("Making temporary entry (dir = {dir}, parent = {parent:?})");
+            let mut channel = channel.into_client().into_channel();
+            let path = if dir {
+                channel.make_temp_dir(parent).await
+            } else {
+                channel.make_temp_file(parent).await
+            }
+            .with_context(|| {
+                format!("Failed to make temporary entry using connection {connection_id}")
+            })?;
+
+            println!("{}", path.to_string_lossy());
         }
         ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Metadata {
             cache,
@@ -778,16 +2068,18 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 }
             )
         }
-        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Read {
-            cache,
-            connection,
-            network,
-            path,
-            depth,
-            absolute,
-            canonicalize,
-            include_root,
-        }) => {
+        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Read(args)) => {
+            let ReadArgs {
+                cache,
+                connection,
+                network,
+                path,
+                depth,
+                absolute,
+                canonicalize,
+                include_root,
+                options,
+            } = *args;
             debug!("Connecting to manager");
             let mut client = Client::new(network)
                 .using_prompt_auth_handler()
@@ -807,86 +2099,65 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 .into_client()
                 .into_channel();
 
-            // NOTE: We don't know whether the path is for a file or directory, so we try both
-            //       at the same time and return the first result, or fail if both fail!
+            // NOTE: We don't know whether the path is for a file or directory, so we read it as a
+            //       file and fall back to reading it as a directory (printing its entries as they
+            //       arrive), failing with the error of the file if both fail!
             debug!(
                 "Reading {path:?} (depth = {}, absolute = {}, canonicalize = {}, include_root = {})",
                 depth, absolute, canonicalize, include_root
             );
-            let results = channel
-                .send(DistantMsg::Batch(vec![
-                    DistantRequestData::FileRead {
-                        path: path.to_path_buf(),
-                    },
-                    DistantRequestData::DirRead {
-                        path: path.to_path_buf(),
-                        depth,
-                        absolute,
-                        canonicalize,
-                        include_root,
-                    },
-                ]))
+            let file_err = match channel.read_file(path.as_path()).await {
+                Ok(data) => {
+                    let mut out = std::io::stdout();
+                    out.write_all(&data)
+                        .context("Failed to write file contents to stdout")?;
+                    out.flush().context("Failed to flush stdout")?;
+                    return Ok(());
+                }
+                Err(x) => x,
+            };
+
+            let mut mailbox = channel
+                .mail(DistantMsg::Single(DistantRequestData::DirRead {
+                    path: path.to_path_buf(),
+                    depth,
+                    absolute,
+                    canonicalize,
+                    include_root,
+                    options: options.into(),
+                    chunk_size: Some(DIR_READ_CHUNK_SIZE),
+                }))
                 .await
                 .with_context(|| {
                     format!("Failed to read {path:?} using connection {connection_id}")
                 })?;
 
-            let mut errors = Vec::new();
-            for response in results
-                .payload
-                .into_batch()
-                .context("Got single response to batch request")?
-            {
-                match response {
-                    DistantResponseData::DirEntries { entries, .. } => {
-                        #[derive(Tabled)]
-                        struct EntryRow {
-                            ty: String,
-                            path: String,
-                        }
-
-                        let data = Table::new(entries.into_iter().map(|entry| EntryRow {
-                            ty: String::from(match entry.file_type {
-                                FileType::Dir => "<DIR>",
-                                FileType::File => "",
-                                FileType::Symlink => "<SYMLINK>",
-                            }),
-                            path: entry.path.to_string_lossy().to_string(),
-                        }))
-                        .with(Style::blank())
-                        .with(Disable::row(Rows::new(..1)))
-                        .with(Modify::new(Rows::new(..)).with(Alignment::left()))
-                        .to_string()
-                        .into_bytes();
-
-                        let mut out = std::io::stdout();
-                        out.write_all(&data)
+            let mut formatter = Formatter::shell().paged();
+            while let Some(response) = mailbox.next().await {
+                match response.payload {
+                    DistantMsg::Single(
+                        chunk @ DistantResponseData::DirEntriesChunk { done, .. },
+                    ) => {
+                        formatter
+                            .print(Response::new("".to_string(), DistantMsg::Single(chunk)))
                             .context("Failed to write directory contents to stdout")?;
-                        out.flush().context("Failed to flush stdout")?;
-                        return Ok(());
-                    }
-                    DistantResponseData::Blob { data } => {
-                        let mut out = std::io::stdout();
-                        out.write_all(&data)
-                            .context("Failed to write file contents to stdout")?;
-                        out.flush().context("Failed to flush stdout")?;
-                        return Ok(());
+                        if done {
+                            return Ok(());
+                        }
                     }
-                    DistantResponseData::Error(x) => errors.push(x),
-                    _ => continue,
+                    _ => break,
                 }
             }
 
-            if let Some(x) = errors.first() {
-                return Err(CliError::from(anyhow::anyhow!(x.to_io_error())));
-            }
+            return Err(CliError::from(anyhow::anyhow!(file_err)));
         }
         ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Remove {
             cache,
             connection,
             network,
-            path,
             force,
+            dry_run,
+            path,
         }) => {
             debug!("Connecting to manager");
             let mut client = Client::new(network)
@@ -905,10 +2176,21 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 .await
                 .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
 
+            let mut channel = channel.into_client().into_channel();
+            if dry_run {
+                debug!("Planning removal of {path:?} (force = {force})");
+                let op = BatchOp::Remove {
+                    path: path.clone(),
+                    force,
+                };
+                print_plan(&mut channel, op).await.with_context(|| {
+                    format!("Failed to plan removing {path:?} using connection {connection_id}")
+                })?;
+                return Ok(());
+            }
+
             debug!("Removing {path:?} (force = {force}");
             channel
-                .into_client()
-                .into_channel()
                 .remove(path.as_path(), force)
                 .await
                 .with_context(|| {
@@ -919,6 +2201,7 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
             cache,
             connection,
             network,
+            dry_run,
             src,
             dst,
         }) => {
@@ -939,25 +2222,37 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 .await
                 .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
 
+            let mut channel = channel.into_client().into_channel();
+            if dry_run {
+                debug!("Planning rename of {src:?} to {dst:?}");
+                let op = BatchOp::Rename {
+                    src: src.clone(),
+                    dst: dst.clone(),
+                };
+                print_plan(&mut channel, op).await.with_context(|| {
+                    format!("Failed to plan renaming {src:?} to {dst:?} using connection {connection_id}")
+                })?;
+                return Ok(());
+            }
+
             debug!("Renaming {src:?} to {dst:?}");
             channel
-                .into_client()
-                .into_channel()
                 .rename(src.as_path(), dst.as_path())
                 .await
                 .with_context(|| {
                     format!("Failed to rename {src:?} to {dst:?} using connection {connection_id}")
                 })?;
         }
-        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Search {
-            cache,
-            connection,
-            network,
-            target,
-            condition,
-            options,
-            paths,
-        }) => {
+        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Search(args)) => {
+            let SearchArgs {
+                cache,
+                connection,
+                network,
+                target,
+                condition,
+                options,
+                paths,
+            } = *args;
             debug!("Connecting to manager");
             let mut client = Client::new(network)
                 .using_prompt_auth_handler()
@@ -975,7 +2270,7 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                 .await
                 .with_context(|| format!("Failed to open channel to connection {connection_id}"))?;
 
-            let mut formatter = Formatter::shell();
+            let mut formatter = Formatter::shell().paged();
             let query = SearchQuery {
                 target: target.into(),
                 condition,
@@ -998,16 +2293,17 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                     }),
                 );
 
-                formatter.print(res).context("Failed to print match")?;
+                let printed = formatter.print(res);
+                let cancel = async { searcher.cancel().await.context("Failed to cancel search") };
+                if !keep_printing(printed, "match", cancel).await? {
+                    break;
+                }
             }
         }
-        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Watch {
+        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Snapshot {
             cache,
             connection,
             network,
-            recursive,
-            only,
-            except,
             path,
         }) => {
             debug!("Connecting to manager");
@@ -1021,6 +2317,173 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
             let connection_id =
                 use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
 
+            debug!("Opening channel to connection {}", connection_id);
+            let mut channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?
+                .into_client()
+                .into_channel();
+
+            debug!("Taking snapshot of {path:?}");
+            let id = channel
+                .create_snapshot(path.as_path())
+                .await
+                .with_context(|| {
+                    format!("Failed to take snapshot of {path:?} using connection {connection_id}")
+                })?;
+
+            println!("{id}");
+        }
+        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::SnapshotList {
+            cache,
+            connection,
+            network,
+        }) => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let mut channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?
+                .into_client()
+                .into_channel();
+
+            debug!("Listing snapshots");
+            let entries = channel.list_snapshots().await.with_context(|| {
+                format!("Failed to list snapshots using connection {connection_id}")
+            })?;
+
+            use chrono::TimeZone;
+
+            #[derive(Tabled)]
+            struct EntryRow {
+                id: SnapshotId,
+                created: String,
+                path: String,
+            }
+
+            let table = Table::new(entries.into_iter().map(|entry| {
+                EntryRow {
+                    id: entry.id,
+                    created: chrono::Local
+                        .timestamp_millis_opt(entry.created as i64)
+                        .single()
+                        .map(|created| created.format("%Y-%m-%d %H:%M:%S").to_string())
+                        .unwrap_or_default(),
+                    path: entry.path.to_string_lossy().to_string(),
+                }
+            }))
+            .with(Style::blank())
+            .with(Modify::new(Rows::new(..)).with(Alignment::left()))
+            .to_string();
+
+            println!("{table}");
+        }
+        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::SnapshotPrune {
+            cache,
+            connection,
+            network,
+            keep,
+            ids,
+        }) => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let mut channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?
+                .into_client()
+                .into_channel();
+
+            let ids = match keep {
+                Some(keep) => {
+                    let entries = channel.list_snapshots().await.with_context(|| {
+                        format!("Failed to list snapshots using connection {connection_id}")
+                    })?;
+                    snapshots_to_prune(entries, keep)
+                }
+                None => ids,
+            };
+
+            for id in ids {
+                debug!("Removing snapshot {id}");
+                channel.remove_snapshot(id).await.with_context(|| {
+                    format!("Failed to remove snapshot {id} using connection {connection_id}")
+                })?;
+            }
+        }
+        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::SnapshotRestore {
+            cache,
+            connection,
+            network,
+            id,
+        }) => {
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let mut channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?
+                .into_client()
+                .into_channel();
+
+            debug!("Restoring snapshot {id}");
+            channel.restore_snapshot(id).await.with_context(|| {
+                format!("Failed to restore snapshot {id} using connection {connection_id}")
+            })?;
+        }
+        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Watch(args)) => {
+            let WatchArgs {
+                cache,
+                connection,
+                network,
+                recursive,
+                only,
+                except,
+                path,
+            } = *args;
+            debug!("Connecting to manager");
+            let mut client = Client::new(network)
+                .using_prompt_auth_handler()
+                .connect()
+                .await
+                .context("Failed to connect to manager")?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
             debug!("Opening channel to connection {}", connection_id);
             let channel = client
                 .open_raw_channel(connection_id)
@@ -1047,17 +2510,30 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                     DistantMsg::Single(DistantResponseData::Changed(change)),
                 );
 
-                formatter.print(res).context("Failed to print change")?;
+                let printed = formatter.print(res);
+                let unwatch = async {
+                    watcher
+                        .unwatch()
+                        .await
+                        .with_context(|| format!("Failed to unwatch {path:?}"))
+                };
+                if !keep_printing(printed, "change", unwatch).await? {
+                    break;
+                }
             }
         }
-        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Write {
-            cache,
-            connection,
-            network,
-            append,
-            path,
-            data,
-        }) => {
+        ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Write(args)) => {
+            let WriteArgs {
+                cache,
+                connection,
+                network,
+                append,
+                offset,
+                dedup,
+                mode,
+                path,
+                data,
+            } = *args;
             let data = match data {
                 Some(x) => match x.into_string() {
                     Ok(x) => x.into_bytes(),
@@ -1105,6 +2581,39 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
                     .with_context(|| {
                         format!("Failed to write to {path:?} using connection {connection_id}")
                     })?;
+            } else if let Some(offset) = offset {
+                debug!("Writing contents to {path:?} at offset {offset}");
+                channel
+                    .into_client()
+                    .into_channel()
+                    .write_file_at(path.as_path(), offset, data)
+                    .await
+                    .with_context(|| {
+                        format!("Failed to write to {path:?} using connection {connection_id}")
+                    })?;
+            } else if dedup {
+                debug!("Writing contents to {path:?} unless remote machine has them");
+                let skipped = channel
+                    .into_client()
+                    .into_channel()
+                    .write_file_dedup(path.as_path(), data)
+                    .await
+                    .with_context(|| {
+                        format!("Failed to write to {path:?} using connection {connection_id}")
+                    })?;
+                if skipped {
+                    debug!("Skipped transfer as remote machine already has contents");
+                }
+            } else if let Some(mode) = mode {
+                debug!("Writing contents to {path:?} with mode {mode:o}");
+                channel
+                    .into_client()
+                    .into_channel()
+                    .write_file_with_mode(path.as_path(), mode, data)
+                    .await
+                    .with_context(|| {
+                        format!("Failed to write to {path:?} using connection {connection_id}")
+                    })?;
             } else {
                 debug!("Writing contents to {path:?}");
                 channel
@@ -1122,16 +2631,23 @@ async fn async_run(cmd: ClientSubcommand) -> CliResult {
     Ok(())
 }
 
-async fn use_or_lookup_connection_id(
+pub(super) async fn use_or_lookup_connection_id(
     cache: &mut Cache,
-    connection: Option<ConnectionId>,
+    connection: Option<ConnectionSelector>,
     client: &mut ManagerClient,
 ) -> anyhow::Result<ConnectionId> {
     match connection {
-        Some(id) => {
+        Some(ConnectionSelector::Id(id)) => {
             trace!("Using specified connection id: {}", id);
             Ok(id)
         }
+        Some(ConnectionSelector::Name(name)) => {
+            trace!("Looking up connection named {}", name);
+            client
+                .resolve(name.as_str())
+                .await
+                .with_context(|| format!("Failed to find connection named {name}"))
+        }
         None => {
             trace!("Looking up connection id");
             let list = client
@@ -1164,7 +2680,173 @@ async fn use_or_lookup_connection_id(
     }
 }
 
-async fn connect_to_manager(
+/// Checks the `result` of printing a `what` to stdout, where a stdout closed by whatever was
+/// reading it (such as `head`) awaits `stop` to end whatever keeps producing output, returning
+/// false if nothing more should be printed
+async fn keep_printing(
+    result: io::Result<()>,
+    what: &str,
+    stop: impl Future<Output = anyhow::Result<()>>,
+) -> anyhow::Result<bool> {
+    match result {
+        Err(x) if stdout::is_closed(&x) => {
+            debug!("Stdout closed, so stopping what prints each {what}");
+            stop.await?;
+            Ok(false)
+        }
+        x => {
+            x.with_context(|| format!("Failed to print {what}"))?;
+            Ok(true)
+        }
+    }
+}
+
+/// Launches a server at `destination` using the manager, connecting to it and optionally
+/// verifying that it works, returning the id of the new connection
+async fn launch_server(
+    client: &mut ManagerClient,
+    format: Format,
+    mut destination: Destination,
+    options: Map,
+    verify: bool,
+) -> anyhow::Result<ConnectionId> {
+    // Grab the host we are connecting to for later use
+    let host = destination.host.to_string();
+
+    // Several destinations can be launched at once, so questions asked while authenticating
+    // name the destination that they are for (without its password)
+    let label = Destination {
+        password: None,
+        ..destination.clone()
+    }
+    .to_string();
+
+    // If we have no scheme on launch, we need to fill it in with something
+    //
+    // TODO: Can we have the server support this instead of the client? Right now, the
+    //       server is failing because it cannot parse //localhost/ as it fails with
+    //       an invalid IPv4 or registered name character error on host
+    if destination.scheme.is_none() {
+        destination.scheme = Some("ssh".to_string());
+    }
+
+    // Carry over any network options to the connection made to the launched server, along with
+    // the profile that it was launched with
+    let mut network_options = Map::from(ClientNetworkConfig::from(options.clone()));
+    if let Some(profile) = options.get("profile") {
+        network_options.insert("profile".to_string(), profile.clone());
+    }
+
+    // Start the server using our manager
+    debug!("Launching server at {} with {}", destination, options);
+    let mut new_destination = match format {
+        Format::Shell => client
+            .launch(
+                destination,
+                options,
+                PromptAuthHandler::new().with_destination(&label),
+            )
+            .await
+            .context("Failed to launch server")?,
+        Format::Json => client
+            .launch(
+                destination,
+                options,
+                JsonAuthHandler::default().with_destination(&label),
+            )
+            .await
+            .context("Failed to launch server")?,
+    };
+
+    // Update the new destination with our previously-used host if the
+    // new host is not globally-accessible
+    if !new_destination.host.is_global() {
+        trace!(
+            "Updating host to {:?} from non-global {:?}",
+            host,
+            new_destination.host.to_string()
+        );
+        new_destination.host = host
+            .parse::<Host>()
+            .map_err(|x| anyhow::anyhow!(x))
+            .context("Failed to replace host")?;
+    } else {
+        trace!("Host {:?} is global", new_destination.host.to_string());
+    }
+
+    // Trigger our manager to connect to the launched server
+    debug!("Connecting to server at {}", new_destination);
+    let id = match format {
+        Format::Shell => client
+            .connect(
+                new_destination,
+                network_options,
+                PromptAuthHandler::new().with_destination(&label),
+            )
+            .await
+            .context("Failed to connect to server")?,
+        Format::Json => client
+            .connect(
+                new_destination,
+                network_options,
+                JsonAuthHandler::default().with_destination(&label),
+            )
+            .await
+            .context("Failed to connect to server")?,
+    };
+
+    // Ensure that the server is actually usable before we report it as launched,
+    // tearing down the connection if it is not
+    if verify {
+        debug!("Verifying launched server for connection {}", id);
+        if let Err(x) = verify_connection(client, id).await {
+            debug!("Killing connection {} that failed verification", id);
+            if let Err(kill_err) = client.kill(id).await {
+                error!("Failed to kill connection {}: {}", id, kill_err);
+            }
+
+            return Err(x.context(format!("Launched server failed verification ({id})")));
+        }
+    }
+
+    Ok(id)
+}
+
+/// Performs a series of requests against the connection to verify that the server behind it is
+/// working: retrieving system info, retrieving capabilities, and checking that the server's
+/// current directory exists
+async fn verify_connection(
+    client: &mut ManagerClient,
+    connection_id: ConnectionId,
+) -> anyhow::Result<()> {
+    let mut channel: DistantChannel = client
+        .open_raw_channel(connection_id)
+        .await
+        .with_context(|| format!("Failed to open channel to connection {connection_id}"))?
+        .into_client()
+        .into_channel();
+
+    let info = channel
+        .system_info()
+        .await
+        .context("Failed to retrieve system info")?;
+    channel
+        .capabilities()
+        .await
+        .context("Failed to retrieve capabilities")?;
+
+    if !channel
+        .exists(info.current_dir.as_path())
+        .await
+        .context("Failed to check existence of current directory")?
+    {
+        anyhow::bail!("Current directory {:?} does not exist", info.current_dir);
+    }
+
+    Ok(())
+}
+
+pub(super) async fn connect_to_manager(
     format: Format,
     network: NetworkSettings,
 ) -> anyhow::Result<ManagerClient> {
@@ -1181,3 +2863,102 @@ async fn connect_to_manager(
             .context("Failed to connect to manager")?,
     })
 }
+
+/// Records `entry` for a command that finished with `result` into `history`, filling in the
+/// name and destination of its connection if the manager still knows of it
+async fn record_command(
+    history: &History,
+    client: &mut ManagerClient,
+    entry: HistoryEntry,
+    result: &CliResult,
+) {
+    if !history.is_enabled() {
+        return;
+    }
+
+    let mut entry = entry.with_exit_code(match result {
+        Ok(()) => Some(0),
+        Err(CliError::Exit(code)) => Some(*code as i32),
+        Err(CliError::Error(_)) => None,
+    });
+
+    match client.info(entry.connection).await {
+        Ok(info) => {
+            entry = entry
+                .with_name(info.name)
+                .with_destination(&info.destination)
+        }
+        Err(x) => debug!("Failed to look up connection {}: {x}", entry.connection),
+    }
+
+    history.record(entry).await;
+}
+
+/// Ensures that the destination of the connection and the root directory that a process is run
+/// in, being `current_dir` or otherwise the current directory of the server, are trusted
+async fn ensure_trusted(
+    trust: &Trust,
+    client: &mut ManagerClient,
+    connection_id: ConnectionId,
+    current_dir: Option<&Path>,
+) -> anyhow::Result<()> {
+    let info = client
+        .info(connection_id)
+        .await
+        .with_context(|| format!("Failed to look up connection {connection_id}"))?;
+
+    let root = match current_dir {
+        Some(dir) => dir.to_string_lossy().to_string(),
+        None => {
+            let mut channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?
+                .into_client()
+                .into_channel();
+            let system_info = channel
+                .system_info()
+                .await
+                .context("Failed to get current directory of server")?;
+            system_info.current_dir.to_string_lossy().to_string()
+        }
+    };
+
+    trust.ensure(&info.destination, &root).await
+}
+
+/// Returns `duration` as fractional milliseconds, used when reporting latency
+/// Prints the remote paths that `op` would affect, which is how filesystem commands perform a
+/// dry run
+async fn print_plan(channel: &mut DistantChannel, op: BatchOp) -> anyhow::Result<()> {
+    let paths = channel.plan(op).await?;
+    Formatter::shell()
+        .print(Response::new(
+            "".to_string(),
+            DistantMsg::Single(DistantResponseData::Plan { paths }),
+        ))
+        .context("Failed to print plan")
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Returns the ids of the snapshots to remove so that only the newest `keep` snapshots of each
+/// path remain, where `entries` are ordered oldest first
+fn snapshots_to_prune(entries: Vec<SnapshotInfo>, keep: usize) -> Vec<SnapshotId> {
+    let mut remaining = std::collections::HashMap::new();
+    for entry in entries.iter() {
+        *remaining.entry(entry.path.clone()).or_insert(0usize) += 1;
+    }
+
+    let mut ids = Vec::new();
+    for entry in entries {
+        let count = remaining.get_mut(&entry.path).unwrap();
+        if *count > keep {
+            ids.push(entry.id);
+        }
+        *count -= 1;
+    }
+    ids
+}
@@ -0,0 +1,92 @@
+use super::forward::relay;
+use anyhow::Context;
+use distant_core::data::{Environment, TunnelId};
+use distant_core::{DistantChannel, DistantChannelExt, RemoteTunnelListener};
+use log::*;
+use std::path::PathBuf;
+use tokio::task::JoinHandle;
+
+/// Name of the environment variable holding the socket of an SSH agent
+const SSH_AUTH_SOCK: &str = "SSH_AUTH_SOCK";
+
+/// Forwards the local SSH agent to processes on the remote machine, which reach it through a
+/// unix socket that the server listens on
+pub struct AgentForwarder {
+    channel: DistantChannel,
+    id: TunnelId,
+    task: JoinHandle<()>,
+}
+
+impl AgentForwarder {
+    /// Starts forwarding the agent found at `SSH_AUTH_SOCK` through a new socket on the remote
+    /// machine, pointing `SSH_AUTH_SOCK` of `environment` at it, and failing if there is no agent
+    /// to forward
+    pub async fn start(
+        mut channel: DistantChannel,
+        environment: &mut Environment,
+    ) -> anyhow::Result<Self> {
+        let agent = std::env::var_os(SSH_AUTH_SOCK)
+            .map(PathBuf::from)
+            .with_context(|| format!("No SSH agent to forward as {SSH_AUTH_SOCK} is not set"))?;
+
+        let listener = channel
+            .listen_unix_tunnel()
+            .await
+            .context("Failed to listen for the SSH agent on the remote machine")?;
+        let id = listener.id();
+        let path = listener
+            .path()
+            .context("Remote machine did not report its SSH agent socket")?
+            .to_path_buf();
+        debug!("Forwarding SSH agent {agent:?} to {path:?}");
+        environment.insert(
+            SSH_AUTH_SOCK.to_string(),
+            path.to_string_lossy().to_string(),
+        );
+
+        Ok(Self {
+            channel,
+            id,
+            task: tokio::spawn(accept(listener, agent)),
+        })
+    }
+
+    /// Stops forwarding the agent, which removes the socket on the remote machine
+    pub async fn stop(mut self) {
+        self.task.abort();
+        if let Err(x) = self.channel.tunnel_unlisten(self.id).await {
+            debug!("Failed to stop listening for the SSH agent: {x}");
+        }
+    }
+}
+
+/// Accepts connections made to the remote `listener`, relaying each to the local `agent`
+#[cfg(unix)]
+async fn accept(mut listener: RemoteTunnelListener, agent: PathBuf) {
+    while let Some((mut tunnel, _)) = listener.accept().await {
+        let agent = agent.clone();
+        tokio::spawn(async move {
+            let result = match tokio::net::UnixStream::connect(&agent).await {
+                Ok(stream) => relay(tunnel, stream).await,
+                Err(x) => {
+                    let _ = tunnel.close().await;
+                    Err(x)
+                }
+            };
+
+            if let Err(x) = result {
+                error!("Failed to forward SSH agent {agent:?}: {x}");
+            }
+        });
+    }
+}
+
+/// Accepts connections made to the remote `listener`, refusing each as SSH agents are only
+/// reached through unix sockets
+#[cfg(not(unix))]
+async fn accept(mut listener: RemoteTunnelListener, agent: PathBuf) {
+    while let Some((mut tunnel, _)) = listener.accept().await {
+        error!("Failed to forward SSH agent {agent:?}: only supported on unix");
+        let _ = tunnel.close().await;
+    }
+}
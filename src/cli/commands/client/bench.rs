@@ -0,0 +1,143 @@
+use super::copy::human_bytes;
+use distant_core::net::common::{Request, Response};
+use distant_core::{
+    DistantChannel, DistantChannelExt, DistantMsg, DistantRequestData, DistantResponseData,
+};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Measures the throughput of a connection by sending pings that carry synthetic payloads in
+/// each direction
+pub struct Bench {
+    channel: DistantChannel,
+
+    /// Size (in bytes) of each payload
+    size: usize,
+
+    /// Number of payloads sent in each direction
+    count: u32,
+}
+
+/// Bytes transferred in one direction and the time it took
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Throughput {
+    pub bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl Throughput {
+    /// Returns a description of the throughput such as `4.0 MiB in 0.250s (16.0 MiB/s)`
+    pub fn describe(&self) -> String {
+        let secs = self.elapsed.as_secs_f64();
+        let rate = if secs > 0.0 {
+            human_bytes((self.bytes as f64 / secs) as u64)
+        } else {
+            String::from("∞")
+        };
+
+        format!("{} in {secs:.3}s ({rate}/s)", human_bytes(self.bytes))
+    }
+}
+
+/// Bytes that framing adds to a payload, not counting what the transport itself adds such as
+/// through compression or encryption
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FrameOverhead {
+    pub request: usize,
+    pub response: usize,
+}
+
+impl FrameOverhead {
+    /// Calculates the overhead of sending a payload of `size` bytes in either direction
+    pub fn of(size: usize) -> io::Result<Self> {
+        // Ids are random, so the longest one is used to report the worst case
+        let request = Request {
+            id: u64::MAX.to_string(),
+            deadline: None,
+            idempotency_key: None,
+            correlation_id: None,
+            payload: DistantMsg::Single(DistantRequestData::Ping {
+                data: vec![0; size],
+                reply_len: 0,
+            }),
+        };
+        let response = Response {
+            id: request.id.clone(),
+            origin_id: request.id.clone(),
+            payload: DistantMsg::Single(DistantResponseData::Pong {
+                data: vec![0; size],
+            }),
+        };
+
+        Ok(Self {
+            request: request.to_vec()?.len().saturating_sub(size),
+            response: response.to_vec()?.len().saturating_sub(size),
+        })
+    }
+}
+
+impl Bench {
+    pub fn new(channel: DistantChannel, size: usize, count: u32) -> Self {
+        Self {
+            channel,
+            size,
+            count,
+        }
+    }
+
+    /// Sends each payload to the remote server, waiting for it to be acknowledged before sending
+    /// the next
+    pub async fn upload(&mut self) -> io::Result<Throughput> {
+        let mut bytes = 0;
+        let start = Instant::now();
+        for _ in 0..self.count {
+            self.channel.ping_with_data(vec![0; self.size], 0).await?;
+            bytes += self.size as u64;
+        }
+
+        Ok(Throughput {
+            bytes,
+            elapsed: start.elapsed(),
+        })
+    }
+
+    /// Has the remote server send each payload, waiting to receive it before asking for the next
+    pub async fn download(&mut self) -> io::Result<Throughput> {
+        let mut bytes = 0;
+        let start = Instant::now();
+        for _ in 0..self.count {
+            // The server caps the size of what it sends, so only what arrives is counted
+            let data = self.channel.ping_with_data(Vec::new(), self.size).await?;
+            bytes += data.len() as u64;
+        }
+
+        Ok(Throughput {
+            bytes,
+            elapsed: start.elapsed(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_overhead_should_not_grow_with_payload() {
+        let small = FrameOverhead::of(16).unwrap();
+        let large = FrameOverhead::of(1024 * 1024).unwrap();
+
+        // Only the length prefix of the payload grows, going from one to four bytes
+        assert_eq!(large.request, small.request + 3);
+        assert_eq!(large.response, small.response + 3);
+    }
+
+    #[test]
+    fn throughput_should_describe_bytes_per_second() {
+        let throughput = Throughput {
+            bytes: 4 * 1024 * 1024,
+            elapsed: Duration::from_millis(250),
+        };
+        assert_eq!(throughput.describe(), "4.0 MiB in 0.250s (16.0 MiB/s)");
+    }
+}
@@ -0,0 +1,678 @@
+use super::copy::{describe, download_file, join, walk_remote_dir, Entry};
+use anyhow::Context;
+use distant_core::data::{DirEntry, DirReadOptions, FileType};
+use distant_core::{DistantChannel, DistantChannelExt};
+use log::*;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use termwiz::{
+    caps::Capabilities,
+    cell::{AttributeChange, Intensity},
+    input::{InputEvent, KeyCode, KeyEvent, Modifiers},
+    surface::{Change, CursorVisibility, Position},
+    terminal::{buffered::BufferedTerminal, new_terminal, Terminal},
+};
+
+/// Time to wait between checks for terminal input
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Files larger than this (in bytes) are not downloaded just to be previewed
+const MAX_PREVIEW_SIZE: u64 = 64 * 1024;
+
+/// Keys listed in the status line when there is nothing else to report
+const HELP: &str =
+    "j/k: move  l: open  h: up  r: rename  x: delete  d: download  F5: refresh  q: quit";
+
+/// Interactive browser of the file system of a remote machine
+pub struct Browser {
+    channel: DistantChannel,
+
+    /// Local directory where downloads are placed
+    download_dir: PathBuf,
+
+    /// Remote directory being listed along with its entries, directories first
+    dir: PathBuf,
+    entries: Vec<DirEntry>,
+
+    /// Index of the selected entry and of the first entry that is visible
+    selected: usize,
+    offset: usize,
+
+    /// Lines describing the selected entry
+    preview: Vec<String>,
+
+    /// Message reporting the outcome of the last operation
+    status: Option<String>,
+
+    prompt: Option<Prompt>,
+}
+
+/// Input being collected in the status line before an operation is performed
+enum Prompt {
+    Rename(String),
+    ConfirmDelete,
+}
+
+/// What to do after handling some input
+enum Flow {
+    Continue,
+    Quit,
+}
+
+impl Browser {
+    pub fn new(channel: DistantChannel, download_dir: PathBuf) -> Self {
+        Self {
+            channel,
+            download_dir,
+            dir: PathBuf::new(),
+            entries: Vec::new(),
+            selected: 0,
+            offset: 0,
+            preview: Vec::new(),
+            status: None,
+            prompt: None,
+        }
+    }
+
+    /// Browses starting at `path`, or the current directory of the remote machine if none,
+    /// until the user quits
+    pub async fn browse(mut self, path: Option<PathBuf>) -> anyhow::Result<()> {
+        let dir = match path {
+            Some(path) => path,
+            None => {
+                self.channel
+                    .system_info()
+                    .await
+                    .context("Failed to get current directory of remote machine")?
+                    .current_dir
+            }
+        };
+        self.open(dir, None).await?;
+
+        let mut terminal = new_terminal(
+            Capabilities::new_from_env().context("Failed to load terminal capabilities")?,
+        )
+        .context("Failed to create terminal")?;
+        terminal.set_raw_mode().context("Failed to set raw mode")?;
+        terminal
+            .enter_alternate_screen()
+            .context("Failed to enter alternate screen")?;
+        let mut terminal = BufferedTerminal::new(terminal).context("Failed to create terminal")?;
+
+        // The terminal restores itself once dropped, even if we fail part way through
+        let mut dirty = true;
+        loop {
+            if terminal.check_for_resize()? || dirty {
+                self.render(&mut terminal)?;
+                dirty = false;
+            }
+
+            match terminal.terminal().poll_input(Some(Duration::ZERO))? {
+                Some(InputEvent::Key(key)) => {
+                    dirty = true;
+                    if let Flow::Quit = self.handle_key(key, &terminal).await {
+                        break;
+                    }
+                }
+                Some(InputEvent::Resized { .. }) => dirty = true,
+                Some(_) => {}
+                None => tokio::time::sleep(INPUT_POLL_INTERVAL).await,
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_key(
+        &mut self,
+        key: KeyEvent,
+        terminal: &BufferedTerminal<impl Terminal>,
+    ) -> Flow {
+        if let Some(prompt) = self.prompt.take() {
+            self.handle_prompt_key(prompt, key).await;
+            return Flow::Continue;
+        }
+
+        let page = terminal.dimensions().1.saturating_sub(2).max(1);
+        let before = self.selected;
+        self.status = None;
+        match (key.key, key.modifiers) {
+            (KeyCode::Char('q') | KeyCode::Escape, _) | (KeyCode::Char('c'), Modifiers::CTRL) => {
+                return Flow::Quit
+            }
+            (KeyCode::Char('j') | KeyCode::DownArrow, _) => self.select(self.selected + 1),
+            (KeyCode::Char('k') | KeyCode::UpArrow, _) => {
+                self.select(self.selected.saturating_sub(1))
+            }
+            (KeyCode::PageDown, _) => self.select(self.selected + page),
+            (KeyCode::PageUp, _) => self.select(self.selected.saturating_sub(page)),
+            (KeyCode::Char('g') | KeyCode::Home, _) => self.select(0),
+            (KeyCode::Char('G') | KeyCode::End, _) => self.select(usize::MAX),
+            (KeyCode::Char('l') | KeyCode::RightArrow | KeyCode::Enter, _) => {
+                if let Some(entry) = self.entries.get(self.selected) {
+                    if entry.file_type == FileType::Dir {
+                        let path = entry.path.clone();
+                        let result = self.open(path, None).await;
+                        self.report(result);
+                        return Flow::Continue;
+                    }
+                }
+            }
+            (KeyCode::Char('h') | KeyCode::LeftArrow | KeyCode::Backspace, _) => {
+                if let Some(parent) = self.dir.parent().map(Path::to_path_buf) {
+                    let child = self.dir.clone();
+                    let result = self.open(parent, Some(child)).await;
+                    self.report(result);
+                    return Flow::Continue;
+                }
+            }
+            (KeyCode::Function(5), _) => {
+                let dir = self.dir.clone();
+                let selected = self.selected_path();
+                let result = self.open(dir, selected).await;
+                self.report(result);
+                return Flow::Continue;
+            }
+            (KeyCode::Char('r'), _) => {
+                if let Some(name) = self.selected_name() {
+                    self.prompt = Some(Prompt::Rename(name));
+                }
+            }
+            (KeyCode::Char('x') | KeyCode::Delete, _) if !self.entries.is_empty() => {
+                self.prompt = Some(Prompt::ConfirmDelete);
+            }
+            (KeyCode::Char('d'), _) => {
+                if let Some(path) = self.selected_path() {
+                    let result = self.download(&path).await;
+                    self.report(result);
+                }
+            }
+            _ => {}
+        }
+
+        if self.selected != before {
+            self.load_preview().await;
+        }
+
+        Flow::Continue
+    }
+
+    async fn handle_prompt_key(&mut self, prompt: Prompt, key: KeyEvent) {
+        let Some(path) = self.selected_path() else {
+            return;
+        };
+
+        match prompt {
+            Prompt::Rename(mut name) => match key.key {
+                KeyCode::Enter if !name.is_empty() => {
+                    let dst = self.dir.join(&name);
+                    let result = self
+                        .channel
+                        .rename(path.as_path(), dst.as_path())
+                        .await
+                        .with_context(|| format!("Failed to rename {path:?} to {dst:?}"));
+                    self.after_change(result, Some(dst), format!("Renamed to {name}"))
+                        .await;
+                }
+                KeyCode::Escape => {}
+                KeyCode::Backspace => {
+                    name.pop();
+                    self.prompt = Some(Prompt::Rename(name));
+                }
+                KeyCode::Char(c) => {
+                    name.push(c);
+                    self.prompt = Some(Prompt::Rename(name));
+                }
+                _ => self.prompt = Some(Prompt::Rename(name)),
+            },
+            Prompt::ConfirmDelete => {
+                if let KeyCode::Char('y' | 'Y') = key.key {
+                    let result = self
+                        .channel
+                        .remove(path.as_path(), true)
+                        .await
+                        .with_context(|| format!("Failed to delete {path:?}"));
+                    let name = self.selected_name().unwrap_or_default();
+                    // Select whatever takes the place of the deleted entry
+                    let selected = self
+                        .entries
+                        .get(self.selected + 1)
+                        .or_else(|| self.entries.get(self.selected.checked_sub(1)?))
+                        .map(|x| x.path.clone());
+                    self.after_change(result, selected, format!("Deleted {name}"))
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// Reloads the current directory after an operation that changed it, selecting `selected`
+    async fn after_change(
+        &mut self,
+        result: anyhow::Result<()>,
+        selected: Option<PathBuf>,
+        message: String,
+    ) {
+        match result {
+            Ok(()) => {
+                let dir = self.dir.clone();
+                match self.open(dir, selected).await {
+                    // Failures to read the directory take precedence over the message
+                    Ok(()) if self.status.is_none() => self.status = Some(message),
+                    Ok(()) => {}
+                    Err(x) => self.report(Err(x)),
+                }
+            }
+            Err(x) => self.report(Err(x)),
+        }
+    }
+
+    /// Lists `dir`, selecting the entry at `selected` if it exists within it, and reporting any
+    /// entries that could not be read in the status line
+    async fn open(&mut self, dir: PathBuf, selected: Option<PathBuf>) -> anyhow::Result<()> {
+        debug!("Listing {dir:?}");
+        let (mut entries, failures) = self
+            .channel
+            .read_dir(
+                dir.as_path(),
+                /* depth */ 1,
+                /* absolute */ true,
+                /* canonicalize */ false,
+                /* include_root */ false,
+                DirReadOptions::default(),
+            )
+            .await
+            .with_context(|| format!("Failed to read directory {dir:?}"))?;
+
+        for failure in failures.iter() {
+            warn!("Failed to read entry of {dir:?}: {failure}");
+        }
+        self.status = failures
+            .first()
+            .map(|x| format!("Failed to read {} entries of {dir:?}: {x}", failures.len()));
+
+        entries.sort_by(|a, b| {
+            (b.file_type == FileType::Dir)
+                .cmp(&(a.file_type == FileType::Dir))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+
+        self.dir = dir;
+        self.entries = entries;
+        self.offset = 0;
+        self.selected = selected
+            .and_then(|path| self.entries.iter().position(|x| x.path == path))
+            .unwrap_or(0);
+        self.load_preview().await;
+        Ok(())
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.entries.len().saturating_sub(1));
+    }
+
+    fn selected_path(&self) -> Option<PathBuf> {
+        self.entries.get(self.selected).map(|x| x.path.clone())
+    }
+
+    fn selected_name(&self) -> Option<String> {
+        self.entries.get(self.selected).map(display_name)
+    }
+
+    /// Describes the selected entry, including the start of its contents if it is a small file
+    async fn load_preview(&mut self) {
+        self.preview.clear();
+        let Some(path) = self.selected_path() else {
+            return;
+        };
+
+        let metadata = match self.channel.metadata(path.as_path(), false, true).await {
+            Ok(x) => x,
+            Err(x) => {
+                self.preview.push(format!("Failed to read metadata: {x}"));
+                return;
+            }
+        };
+
+        self.preview.push(format!(
+            "{} ({} bytes{})",
+            metadata.file_type.as_ref(),
+            metadata.len,
+            if metadata.readonly { ", readonly" } else { "" }
+        ));
+        self.preview.push(String::new());
+
+        if metadata.file_type != FileType::File {
+            return;
+        }
+
+        if metadata.len > MAX_PREVIEW_SIZE {
+            self.preview.push(String::from("Too large to preview"));
+            return;
+        }
+
+        match self.channel.read_file(path.as_path()).await {
+            Ok(data) => match String::from_utf8(data) {
+                Ok(text) => self.preview.extend(text.lines().map(ToString::to_string)),
+                Err(_) => self.preview.push(String::from("Binary file")),
+            },
+            Err(x) => self.preview.push(format!("Failed to read file: {x}")),
+        }
+    }
+
+    /// Downloads the file or directory at `path` into the download directory, leaving out any
+    /// fifos, sockets, and devices within a directory
+    async fn download(&mut self, path: &Path) -> anyhow::Result<()> {
+        let name = path
+            .file_name()
+            .with_context(|| format!("{path:?} has no file name"))?;
+        let dst = self.download_dir.join(name);
+        let metadata = self
+            .channel
+            .metadata(path, false, true)
+            .await
+            .with_context(|| format!("Failed to read metadata of {path:?}"))?;
+
+        if metadata.file_type.is_special() {
+            anyhow::bail!(
+                "Unable to download {path:?} as it is a {}",
+                describe(metadata.file_type)
+            );
+        }
+
+        let mut skipped = 0;
+        let entries = if metadata.file_type == FileType::Dir {
+            walk_remote_dir(&mut self.channel, path, |path, file_type| {
+                debug!("Skipping {path:?} as it is a {}", describe(file_type));
+                skipped += 1;
+            })
+            .await?
+        } else {
+            vec![Entry {
+                path: PathBuf::new(),
+                len: Some(metadata.len),
+            }]
+        };
+
+        let mut files = 0;
+        for entry in entries {
+            let remote = join(path, &entry.path);
+            let local = join(&dst, &entry.path);
+            if entry.len.is_none() {
+                tokio::fs::create_dir_all(&local)
+                    .await
+                    .with_context(|| format!("Failed to create directory {local:?}"))?;
+                continue;
+            }
+
+            download_file(&mut self.channel, &remote, &local, |_| {}).await?;
+            files += 1;
+        }
+
+        self.status = Some(if skipped > 0 {
+            format!("Downloaded {files} file(s) to {dst:?}, skipping {skipped} special file(s)")
+        } else {
+            format!("Downloaded {files} file(s) to {dst:?}")
+        });
+        Ok(())
+    }
+
+    /// Reports a failed operation in the status line
+    fn report(&mut self, result: anyhow::Result<()>) {
+        if let Err(x) = result {
+            error!("{x:#}");
+            self.status = Some(format!("{x:#}"));
+        }
+    }
+
+    fn render(&mut self, terminal: &mut BufferedTerminal<impl Terminal>) -> anyhow::Result<()> {
+        let (cols, rows) = terminal.dimensions();
+        let list_rows = rows.saturating_sub(2);
+        let list_cols = (cols / 2).max(1);
+        let preview_cols = cols.saturating_sub(list_cols + 1);
+
+        // Keep the selected entry in view
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if list_rows > 0 && self.selected >= self.offset + list_rows {
+            self.offset = self.selected + 1 - list_rows;
+        }
+
+        // Every line is redrawn in full, so only what changed is written out to the terminal
+        terminal.add_change(Change::CursorVisibility(CursorVisibility::Hidden));
+        draw_line(
+            terminal,
+            0,
+            0,
+            cols,
+            &self.dir.to_string_lossy(),
+            AttributeChange::Intensity(Intensity::Bold),
+        );
+
+        for row in 0..list_rows {
+            let (name, selected) = match self.entries.get(self.offset + row) {
+                Some(entry) if entry.file_type == FileType::Dir => (
+                    format!("{}/", display_name(entry)),
+                    self.offset + row == self.selected,
+                ),
+                Some(entry) => (display_name(entry), self.offset + row == self.selected),
+                None => (String::new(), false),
+            };
+            draw_line(
+                terminal,
+                0,
+                row + 1,
+                list_cols,
+                &name,
+                AttributeChange::Reverse(selected),
+            );
+
+            if preview_cols > 0 {
+                draw_line(
+                    terminal,
+                    list_cols,
+                    row + 1,
+                    1,
+                    "│",
+                    AttributeChange::Reverse(false),
+                );
+                draw_line(
+                    terminal,
+                    list_cols + 1,
+                    row + 1,
+                    preview_cols,
+                    self.preview
+                        .get(row)
+                        .map(String::as_str)
+                        .unwrap_or_default(),
+                    AttributeChange::Reverse(false),
+                );
+            }
+        }
+
+        let status = match &self.prompt {
+            Some(Prompt::Rename(name)) => format!("Rename to: {name}"),
+            Some(Prompt::ConfirmDelete) => {
+                format!("Delete {}? (y/n)", self.selected_name().unwrap_or_default())
+            }
+            None => match &self.status {
+                Some(status) => status.clone(),
+                None if self.entries.is_empty() => format!("Empty directory  {HELP}"),
+                None => HELP.to_string(),
+            },
+        };
+        draw_line(
+            terminal,
+            0,
+            rows.saturating_sub(1),
+            cols,
+            &status,
+            AttributeChange::Reverse(true),
+        );
+
+        terminal.flush()?;
+        Ok(())
+    }
+}
+
+/// Name of an entry as shown in the listing
+fn display_name(entry: &DirEntry) -> String {
+    entry
+        .path
+        .file_name()
+        .unwrap_or(entry.path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Draws `text` at column `x` of row `y`, truncating it to `width` columns
+pub(super) fn draw_line(
+    terminal: &mut BufferedTerminal<impl Terminal>,
+    x: usize,
+    y: usize,
+    width: usize,
+    text: &str,
+    attribute: AttributeChange,
+) {
+    // Tabs and other control characters would move the cursor outside of the line, and padding
+    // the line means highlighting covers all of it
+    let text: String = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .chain(std::iter::repeat(' '))
+        .take(width)
+        .collect();
+
+    terminal.add_change(Change::CursorPosition {
+        x: Position::Absolute(x),
+        y: Position::Absolute(y),
+    });
+    terminal.add_change(Change::Attribute(attribute));
+    terminal.add_change(Change::Text(text));
+    terminal.add_change(Change::AllAttributes(Default::default()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use distant_core::net::client::Client;
+    use distant_core::net::common::authentication::{DummyAuthHandler, Verifier};
+    use distant_core::net::common::{InmemoryTransport, OneshotListener};
+    use distant_core::net::server::Server;
+    use distant_core::{DistantApiServerHandler, DistantClient, LocalDistantApi};
+    use test_log::test;
+
+    /// Creates a browser of the local machine, served over an in-memory transport, that
+    /// downloads into `download_dir`
+    async fn browser(download_dir: &Path) -> Browser {
+        let (t1, t2) = InmemoryTransport::pair(100);
+        Server::new()
+            .handler(DistantApiServerHandler::new(
+                LocalDistantApi::initialize().unwrap(),
+            ))
+            .verifier(Verifier::none())
+            .start(OneshotListener::from_value(t2))
+            .unwrap();
+
+        let client: DistantClient = Client::build()
+            .auth_handler(DummyAuthHandler)
+            .connector(t1)
+            .connect()
+            .await
+            .unwrap();
+        Browser::new(client.into_channel(), download_dir.to_path_buf())
+    }
+
+    #[test(tokio::test)]
+    async fn download_should_copy_file_into_download_directory() {
+        let remote = assert_fs::TempDir::new().unwrap();
+        let local = assert_fs::TempDir::new().unwrap();
+        remote.child("file").write_str("some text").unwrap();
+
+        let mut browser = browser(local.path()).await;
+        browser.download(&remote.child("file")).await.unwrap();
+
+        local.child("file").assert("some text");
+        assert_eq!(
+            browser.status,
+            Some(format!(
+                "Downloaded 1 file(s) to {:?}",
+                local.child("file").path()
+            ))
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn download_should_copy_directory_along_with_everything_within_it() {
+        let remote = assert_fs::TempDir::new().unwrap();
+        let local = assert_fs::TempDir::new().unwrap();
+        remote.child("dir/file1").write_str("one").unwrap();
+        remote.child("dir/sub/file2").write_str("two").unwrap();
+        remote.child("dir/empty").create_dir_all().unwrap();
+
+        let mut browser = browser(local.path()).await;
+        browser.download(&remote.child("dir")).await.unwrap();
+
+        local.child("dir/file1").assert("one");
+        local.child("dir/sub/file2").assert("two");
+        assert!(local.child("dir/empty").path().is_dir());
+        assert_eq!(
+            browser.status,
+            Some(format!(
+                "Downloaded 2 file(s) to {:?}",
+                local.child("dir").path()
+            ))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn download_should_skip_special_files_within_directory() {
+        let remote = assert_fs::TempDir::new().unwrap();
+        let local = assert_fs::TempDir::new().unwrap();
+        remote.child("dir/file").write_str("text").unwrap();
+        let _socket =
+            std::os::unix::net::UnixListener::bind(remote.child("dir/socket").path()).unwrap();
+
+        let mut browser = browser(local.path()).await;
+        browser.download(&remote.child("dir")).await.unwrap();
+
+        local.child("dir/file").assert("text");
+        local
+            .child("dir/socket")
+            .assert(predicates::path::missing());
+        assert_eq!(
+            browser.status,
+            Some(format!(
+                "Downloaded 1 file(s) to {:?}, skipping 1 special file(s)",
+                local.child("dir").path()
+            ))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test(tokio::test)]
+    async fn download_should_fail_if_path_is_special_file() {
+        let remote = assert_fs::TempDir::new().unwrap();
+        let local = assert_fs::TempDir::new().unwrap();
+        let _socket =
+            std::os::unix::net::UnixListener::bind(remote.child("socket").path()).unwrap();
+
+        let mut browser = browser(local.path()).await;
+        let err = browser.download(&remote.child("socket")).await.unwrap_err();
+
+        assert!(err.to_string().contains("socket"), "{err}");
+        local.child("socket").assert(predicates::path::missing());
+    }
+
+    #[test(tokio::test)]
+    async fn download_should_fail_if_path_is_missing() {
+        let remote = assert_fs::TempDir::new().unwrap();
+        let local = assert_fs::TempDir::new().unwrap();
+
+        let mut browser = browser(local.path()).await;
+        assert!(browser.download(&remote.child("missing")).await.is_err());
+        assert_eq!(browser.status, None);
+    }
+}
@@ -0,0 +1,223 @@
+use log::*;
+use std::{io, process::Stdio};
+use termwiz::escape::OperatingSystemCommand;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+/// Start of an OSC 52 sequence, which programs use to access the clipboard of the terminal
+const OSC52_PREFIX: &[u8] = b"\x1b]52;";
+
+/// Maximum size of an OSC 52 sequence that is held back waiting for its end, beyond which it is
+/// passed through untouched
+const MAX_OSC52_LEN: usize = 1024 * 1024;
+
+/// Local clipboard, accessed using whichever clipboard command is installed
+#[derive(Clone)]
+pub struct Clipboard {
+    copy: &'static [&'static str],
+    paste: &'static [&'static str],
+}
+
+impl Clipboard {
+    /// Finds a clipboard command suited to the local machine, returning none if there is not one
+    pub fn find() -> Option<Self> {
+        let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+        let x11 = std::env::var_os("DISPLAY").is_some();
+        let candidates: [(bool, Self); 5] = [
+            (
+                cfg!(target_os = "macos"),
+                Self {
+                    copy: &["pbcopy"],
+                    paste: &["pbpaste"],
+                },
+            ),
+            (
+                cfg!(windows),
+                Self {
+                    copy: &["clip.exe"],
+                    paste: &["powershell.exe", "-NoProfile", "-Command", "Get-Clipboard"],
+                },
+            ),
+            (
+                wayland,
+                Self {
+                    copy: &["wl-copy"],
+                    paste: &["wl-paste", "--no-newline"],
+                },
+            ),
+            (
+                x11,
+                Self {
+                    copy: &["xclip", "-selection", "clipboard"],
+                    paste: &["xclip", "-selection", "clipboard", "-o"],
+                },
+            ),
+            (
+                x11,
+                Self {
+                    copy: &["xsel", "--clipboard", "--input"],
+                    paste: &["xsel", "--clipboard", "--output"],
+                },
+            ),
+        ];
+
+        candidates
+            .into_iter()
+            .filter(|(usable, _)| *usable)
+            .map(|(_, clipboard)| clipboard)
+            .find(|clipboard| which::which(clipboard.copy[0]).is_ok())
+    }
+
+    /// Replaces the contents of the clipboard with `text`
+    pub async fn set(&self, text: &str) -> io::Result<()> {
+        let mut child = Command::new(self.copy[0])
+            .args(&self.copy[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        // NOTE: Dropping stdin once written closes it, which is when the command copies the text
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(text.as_bytes()).await?;
+        drop(stdin);
+
+        let status = child.wait().await?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{} exited with {status}", self.copy[0]),
+            ))
+        }
+    }
+
+    /// Returns the contents of the clipboard
+    pub async fn get(&self) -> io::Result<String> {
+        let output = Command::new(self.paste[0])
+            .args(&self.paste[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .await?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{} exited with {}", self.paste[0], output.status),
+            ))
+        }
+    }
+}
+
+/// Removes OSC 52 sequences from the output of a remote program, decoding each as it is removed
+/// and holding back the start of any sequence that is split across multiple pieces of output
+#[derive(Default)]
+pub struct Osc52Filter {
+    pending: Vec<u8>,
+}
+
+impl Osc52Filter {
+    /// Filters the next piece of `output`, returning the output that remains along with each
+    /// sequence that was removed
+    pub fn filter(&mut self, output: &[u8]) -> (Vec<u8>, Vec<OperatingSystemCommand>) {
+        let mut data = std::mem::take(&mut self.pending);
+        data.extend_from_slice(output);
+
+        let mut remaining = Vec::with_capacity(data.len());
+        let mut commands = Vec::new();
+        let mut i = 0;
+        while i < data.len() {
+            let rest = &data[i..];
+            if rest[0] != 0x1b {
+                remaining.push(rest[0]);
+                i += 1;
+                continue;
+            }
+
+            // Hold onto what may be the start of a sequence until the rest of it arrives
+            if rest.len() < OSC52_PREFIX.len() {
+                if OSC52_PREFIX.starts_with(rest) {
+                    self.pending = rest.to_vec();
+                    break;
+                }
+            } else if rest.starts_with(OSC52_PREFIX) {
+                match find_terminator(&rest[OSC52_PREFIX.len()..]) {
+                    Some((end, len)) => {
+                        let body = &rest[2..OSC52_PREFIX.len() + end];
+                        let params: Vec<&[u8]> = body.split(|b| *b == b';').collect();
+                        commands.push(OperatingSystemCommand::parse(&params));
+                        i += OSC52_PREFIX.len() + end + len;
+                        continue;
+                    }
+                    None if rest.len() <= MAX_OSC52_LEN => {
+                        self.pending = rest.to_vec();
+                        break;
+                    }
+                    None => {
+                        warn!("Passing through OSC 52 sequence that exceeds maximum length");
+                    }
+                }
+            }
+
+            remaining.push(rest[0]);
+            i += 1;
+        }
+
+        (remaining, commands)
+    }
+}
+
+/// Returns the position and length of the first BEL or ST that ends a sequence
+fn find_terminator(data: &[u8]) -> Option<(usize, usize)> {
+    data.iter().enumerate().find_map(|(i, b)| match b {
+        0x07 => Some((i, 1)),
+        0x1b if data.get(i + 1) == Some(&b'\\') => Some((i, 2)),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use termwiz::escape::osc::Selection;
+
+    #[test]
+    fn filter_should_remove_and_decode_sequences_split_across_output() {
+        let mut filter = Osc52Filter::default();
+
+        let (remaining, commands) = filter.filter(b"before\x1b]5");
+        assert_eq!(remaining, b"before");
+        assert!(commands.is_empty());
+
+        let (remaining, commands) = filter.filter(b"2;c;dGV4dA==");
+        assert_eq!(remaining, b"");
+        assert!(commands.is_empty());
+
+        let (remaining, commands) = filter.filter(b"\x1b\\after\x1b]52;c;?\x07");
+        assert_eq!(remaining, b"after");
+        assert_eq!(
+            commands,
+            [
+                OperatingSystemCommand::SetSelection(Selection::CLIPBOARD, String::from("text")),
+                OperatingSystemCommand::QuerySelection(Selection::CLIPBOARD),
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_should_pass_through_other_escape_sequences() {
+        let mut filter = Osc52Filter::default();
+        let output = b"\x1b[1m\x1b]0;title\x07\x1b]5x\x1b";
+
+        // A trailing escape may begin a sequence, so it is held back until more output arrives
+        let (remaining, commands) = filter.filter(output);
+        assert_eq!(remaining, &output[..output.len() - 1]);
+        assert!(commands.is_empty());
+
+        let (remaining, _) = filter.filter(b"c");
+        assert_eq!(remaining, b"\x1bc");
+    }
+}
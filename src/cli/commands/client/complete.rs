@@ -0,0 +1,74 @@
+use distant_core::data::{DirEntry, FileType};
+
+/// Splits the path being completed into the directory to read, including its trailing
+/// separator, and the beginning of the name being completed within it
+pub fn split_path(current: &str) -> (&str, &str) {
+    match current.rfind(['/', '\\']) {
+        Some(i) => current.split_at(i + 1),
+        None => ("", current),
+    }
+}
+
+/// Returns the paths that complete `prefix` within `dir` from the `entries` read from it, where
+/// directories end with a separator so that their contents can be completed next
+///
+/// Like shells, hidden entries are only included when the prefix starts with a dot.
+pub fn path_candidates(dir: &str, prefix: &str, entries: Vec<DirEntry>) -> Vec<String> {
+    let separator = if dir.ends_with('\\') { '\\' } else { '/' };
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let name = entry.path.file_name()?.to_string_lossy().into_owned();
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+
+            Some(match entry.file_type {
+                FileType::Dir => format!("{dir}{name}{separator}"),
+                _ => format!("{dir}{name}"),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn entry(path: &str, file_type: FileType) -> DirEntry {
+        DirEntry {
+            path: PathBuf::from(path),
+            file_type,
+            depth: 1,
+        }
+    }
+
+    #[test]
+    fn split_path_should_split_after_last_separator() {
+        assert_eq!(split_path(""), ("", ""));
+        assert_eq!(split_path("fi"), ("", "fi"));
+        assert_eq!(split_path("/home/us"), ("/home/", "us"));
+        assert_eq!(split_path("dir/"), ("dir/", ""));
+        assert_eq!(split_path(r"C:\Users\me"), (r"C:\Users\", "me"));
+    }
+
+    #[test]
+    fn path_candidates_should_only_include_matching_entries() {
+        let entries = || {
+            vec![
+                entry("file.txt", FileType::File),
+                entry("dir", FileType::Dir),
+                entry(".hidden", FileType::File),
+                entry("other", FileType::Symlink),
+            ]
+        };
+
+        assert_eq!(
+            path_candidates("/root/", "", entries()),
+            vec!["/root/file.txt", "/root/dir/", "/root/other"]
+        );
+        assert_eq!(path_candidates("", "d", entries()), vec!["dir/"]);
+        assert_eq!(path_candidates("", ".", entries()), vec![".hidden"]);
+    }
+}
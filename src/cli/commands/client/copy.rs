@@ -0,0 +1,613 @@
+use super::super::common::OutputPolicy;
+use crate::options::{Format, TransferPath};
+use anyhow::Context;
+use distant_core::data::{DirReadOptions, DistantMsg, DistantResponseData, FileType};
+use distant_core::{DistantChannel, DistantChannelExt, DistantRequestData};
+use log::*;
+use serde_json::json;
+use std::collections::HashSet;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Maximum size (in bytes) of each piece of a local file written to the remote machine, which
+/// determines how often progress is reported while uploading
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Maximum size (in bytes) of each piece of a remote file read from the remote machine, which
+/// determines how often progress is reported while downloading
+const DOWNLOAD_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Maximum entries of a remote directory whose metadata is looked up within a single request
+const METADATA_BATCH_SIZE: usize = 1000;
+
+/// Width (in characters) of the progress bar, excluding the surrounding brackets
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+/// Time to wait between lines reporting progress when output is accessible
+const PROGRESS_UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Copies files and directories between the local machine and a remote machine
+pub struct Copier {
+    channel: DistantChannel,
+    format: Format,
+}
+
+/// Represents a single file or directory to transfer, relative to the root being copied
+pub(super) struct Entry {
+    pub path: PathBuf,
+
+    /// Size of the file in bytes, or none if the entry is a directory
+    pub len: Option<u64>,
+}
+
+impl Copier {
+    pub fn new(channel: DistantChannel, format: Format) -> Self {
+        Self { channel, format }
+    }
+
+    /// Copies `src` to `dst`, where exactly one of them must be remote
+    pub async fn copy(self, src: TransferPath, dst: TransferPath) -> anyhow::Result<()> {
+        match (src, dst) {
+            (TransferPath::Local(src), TransferPath::Remote(dst)) => self.upload(src, dst).await,
+            (TransferPath::Remote(src), TransferPath::Local(dst)) => self.download(src, dst).await,
+            (TransferPath::Local(_), TransferPath::Local(_)) => anyhow::bail!(
+                "Neither path is remote! Prefix the remote path with a colon (e.g. :/tmp/file.txt)"
+            ),
+            (TransferPath::Remote(_), TransferPath::Remote(_)) => {
+                anyhow::bail!("Both paths are remote! Use `distant fs copy` instead")
+            }
+        }
+    }
+
+    async fn upload(mut self, src: PathBuf, dst: PathBuf) -> anyhow::Result<()> {
+        let metadata = tokio::fs::metadata(&src)
+            .await
+            .with_context(|| format!("Failed to read metadata of {src:?}"))?;
+
+        // Copy into the destination if it is an existing directory
+        let dst = match self.channel.metadata(dst.as_path(), false, false).await {
+            Ok(x) if x.file_type == FileType::Dir => dst.join(file_name(&src)?),
+            _ => dst,
+        };
+
+        let file_type = FileType::from(metadata.file_type());
+        if file_type.is_special() {
+            anyhow::bail!("Unable to copy {src:?} as it is a {}", describe(file_type));
+        }
+
+        let entries = if metadata.is_dir() {
+            walk_local_dir(&src, self.format).await?
+        } else {
+            vec![Entry {
+                path: PathBuf::new(),
+                len: Some(metadata.len()),
+            }]
+        };
+
+        debug!("Uploading {src:?} to {dst:?}");
+        let mut progress = Progress::new(self.format, &entries);
+        for entry in entries {
+            let local = join(&src, &entry.path);
+            let remote = join(&dst, &entry.path);
+            if entry.len.is_none() {
+                self.channel
+                    .create_dir(remote.as_path(), true)
+                    .await
+                    .with_context(|| format!("Failed to create directory {remote:?}"))?;
+                continue;
+            }
+
+            upload_file(&mut self.channel, &local, &remote, |n| {
+                progress.advance(&remote, n)
+            })
+            .await?;
+            progress.complete_file();
+        }
+        progress.finish();
+
+        Ok(())
+    }
+
+    async fn download(mut self, src: PathBuf, dst: PathBuf) -> anyhow::Result<()> {
+        let metadata = self
+            .channel
+            .metadata(src.as_path(), false, true)
+            .await
+            .with_context(|| format!("Failed to read metadata of {src:?}"))?;
+
+        // Copy into the destination if it is an existing directory
+        let dst = match tokio::fs::metadata(&dst).await {
+            Ok(x) if x.is_dir() => dst.join(file_name(&src)?),
+            _ => dst,
+        };
+
+        if metadata.file_type.is_special() {
+            anyhow::bail!(
+                "Unable to copy {src:?} as it is a {}",
+                describe(metadata.file_type)
+            );
+        }
+
+        let entries = if metadata.file_type == FileType::Dir {
+            let format = self.format;
+            walk_remote_dir(&mut self.channel, &src, |path, file_type| {
+                report_skip(format, path, file_type)
+            })
+            .await?
+        } else {
+            vec![Entry {
+                path: PathBuf::new(),
+                len: Some(metadata.len),
+            }]
+        };
+
+        debug!("Downloading {src:?} to {dst:?}");
+        let mut progress = Progress::new(self.format, &entries);
+        for entry in entries {
+            let remote = join(&src, &entry.path);
+            let local = join(&dst, &entry.path);
+            if entry.len.is_none() {
+                tokio::fs::create_dir_all(&local)
+                    .await
+                    .with_context(|| format!("Failed to create directory {local:?}"))?;
+                continue;
+            }
+
+            download_file(&mut self.channel, &remote, &local, |n| {
+                progress.advance(&local, n)
+            })
+            .await?;
+            progress.complete_file();
+        }
+        progress.finish();
+
+        Ok(())
+    }
+}
+
+/// Lists every file and directory within the remote directory `root`, parents first, where the
+/// sizes of files (and what symlinks point to) are looked up in batches, calling `on_skip` for
+/// each fifo, socket, and device left out
+pub(super) async fn walk_remote_dir(
+    channel: &mut DistantChannel,
+    root: &Path,
+    mut on_skip: impl FnMut(&Path, FileType),
+) -> anyhow::Result<Vec<Entry>> {
+    let (dir_entries, failures) = channel
+        .read_dir(
+            root,
+            /* depth */ 0,
+            /* absolute */ false,
+            /* canonicalize */ false,
+            /* include_root */ false,
+            DirReadOptions::default(),
+        )
+        .await
+        .with_context(|| format!("Failed to read directory {root:?}"))?;
+
+    if let Some(failure) = failures.first() {
+        anyhow::bail!("Failed to read entries within {root:?}: {failure}");
+    }
+
+    let mut entries = vec![Entry {
+        path: PathBuf::new(),
+        len: None,
+    }];
+    let mut unresolved = Vec::new();
+    for dir_entry in dir_entries {
+        match dir_entry.file_type {
+            FileType::Dir => entries.push(Entry {
+                path: dir_entry.path,
+                len: None,
+            }),
+            x if x.is_special() => on_skip(&root.join(&dir_entry.path), x),
+            _ => unresolved.push(dir_entry.path),
+        }
+    }
+
+    for paths in unresolved.chunks(METADATA_BATCH_SIZE) {
+        // Resolve symlinks to whatever they point to, copying that instead
+        let requests = paths
+            .iter()
+            .map(|path| DistantRequestData::Metadata {
+                path: root.join(path),
+                canonicalize: false,
+                resolve_file_type: true,
+            })
+            .collect();
+        let responses = channel
+            .send(DistantMsg::Batch(requests))
+            .await
+            .with_context(|| format!("Failed to read metadata of entries within {root:?}"))?
+            .payload
+            .into_batch()
+            .context("Got single response to batch request")?;
+
+        for (path, response) in paths.iter().zip(responses) {
+            let metadata = match response {
+                DistantResponseData::Metadata(metadata) => metadata,
+                DistantResponseData::Error(x) => {
+                    return Err(x.to_io_error()).with_context(|| {
+                        format!("Failed to read metadata of {:?}", root.join(path))
+                    })
+                }
+                x => anyhow::bail!("Got unexpected response to reading metadata: {x:?}"),
+            };
+
+            if metadata.file_type.is_special() {
+                on_skip(&root.join(path), metadata.file_type);
+                continue;
+            }
+
+            entries.push(Entry {
+                path: path.clone(),
+                len: (metadata.file_type != FileType::Dir).then_some(metadata.len),
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Writes the local file at `local` to `remote` in chunks, calling `on_chunk` with the size (in
+/// bytes) of each chunk once it has been written
+pub(super) async fn upload_file(
+    channel: &mut DistantChannel,
+    local: &Path,
+    remote: &Path,
+    mut on_chunk: impl FnMut(u64),
+) -> anyhow::Result<()> {
+    let mut file = tokio::fs::File::open(local)
+        .await
+        .with_context(|| format!("Failed to open {local:?}"))?;
+    let mut buf = vec![0; UPLOAD_CHUNK_SIZE];
+    let mut first = true;
+    loop {
+        let n = file
+            .read(&mut buf)
+            .await
+            .with_context(|| format!("Failed to read {local:?}"))?;
+
+        // The first write creates (or truncates) the file, even if it is empty
+        if first {
+            channel.write_file(remote, &buf[..n]).await
+        } else if n > 0 {
+            channel.append_file(remote, &buf[..n]).await
+        } else {
+            break;
+        }
+        .with_context(|| format!("Failed to write {remote:?}"))?;
+
+        first = false;
+        on_chunk(n as u64);
+    }
+
+    Ok(())
+}
+
+/// Writes the remote file at `remote` to `local` in chunks, calling `on_chunk` with the size (in
+/// bytes) of each chunk once it has been written
+pub(super) async fn download_file(
+    channel: &mut DistantChannel,
+    remote: &Path,
+    local: &Path,
+    mut on_chunk: impl FnMut(u64),
+) -> anyhow::Result<()> {
+    let mut file = tokio::fs::File::create(local)
+        .await
+        .with_context(|| format!("Failed to create {local:?}"))?;
+    let mut offset = 0;
+    loop {
+        let data = match channel
+            .read_file_at(remote, offset, DOWNLOAD_CHUNK_SIZE)
+            .await
+        {
+            Ok(data) => data,
+
+            // Servers that cannot read within files (such as over sftp) only send them whole
+            Err(x) if x.kind() == io::ErrorKind::Unsupported && offset > 0 => {
+                let data = channel
+                    .read_file(remote)
+                    .await
+                    .with_context(|| format!("Failed to read {remote:?}"))?;
+                data.get(offset as usize..).unwrap_or_default().to_vec()
+            }
+            Err(x) => return Err(x).with_context(|| format!("Failed to read {remote:?}")),
+        };
+
+        file.write_all(&data)
+            .await
+            .with_context(|| format!("Failed to write {local:?}"))?;
+        on_chunk(data.len() as u64);
+
+        // A short chunk marks the end of the file, as does a longer one from servers that do not
+        // know how to read within files and so send them whole
+        offset += data.len() as u64;
+        if data.len() as u64 != DOWNLOAD_CHUNK_SIZE {
+            break;
+        }
+    }
+
+    file.flush()
+        .await
+        .with_context(|| format!("Failed to write {local:?}"))?;
+    Ok(())
+}
+
+/// Lists every file and directory within the local directory `root`, parents first, skipping
+/// (and reporting using `format`) any fifos, sockets, and devices, along with symlinks to
+/// directories that have already been listed, which would otherwise be listed forever when a
+/// symlink points to one of its own parents
+async fn walk_local_dir(root: &Path, format: Format) -> anyhow::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut dirs = vec![PathBuf::new()];
+    let mut visited = HashSet::new();
+
+    while let Some(dir) = dirs.pop() {
+        let path = join(root, &dir);
+        let canonical = tokio::fs::canonicalize(&path)
+            .await
+            .with_context(|| format!("Failed to read directory {path:?}"))?;
+        if !visited.insert(canonical) {
+            report_repeat(format, &path);
+            continue;
+        }
+
+        entries.push(Entry {
+            path: dir.clone(),
+            len: None,
+        });
+
+        let mut read_dir = tokio::fs::read_dir(&path)
+            .await
+            .with_context(|| format!("Failed to read directory {path:?}"))?;
+        while let Some(dir_entry) = read_dir
+            .next_entry()
+            .await
+            .with_context(|| format!("Failed to read directory {path:?}"))?
+        {
+            // Follows symlinks, copying whatever they point to instead
+            let metadata = tokio::fs::metadata(dir_entry.path())
+                .await
+                .with_context(|| format!("Failed to read metadata of {:?}", dir_entry.path()))?;
+
+            let file_type = FileType::from(metadata.file_type());
+            if file_type.is_special() {
+                report_skip(format, &dir_entry.path(), file_type);
+                continue;
+            }
+
+            let relative = dir.join(dir_entry.file_name());
+            if metadata.is_dir() {
+                dirs.push(relative);
+            } else {
+                entries.push(Entry {
+                    path: relative,
+                    len: Some(metadata.len()),
+                });
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Joins `relative` onto `root`, avoiding a trailing separator when `relative` is empty
+pub(super) fn join(root: &Path, relative: &Path) -> PathBuf {
+    if relative.as_os_str().is_empty() {
+        root.to_path_buf()
+    } else {
+        root.join(relative)
+    }
+}
+
+/// Describes `file_type` in words, such as "char device"
+pub(super) fn describe(file_type: FileType) -> String {
+    file_type.as_ref().replace('_', " ")
+}
+
+/// Reports that `path` is being left out of a transfer because it is a fifo, socket, or device,
+/// which has no contents to transfer
+pub(super) fn report_skip(format: Format, path: &Path, file_type: FileType) {
+    match format {
+        Format::Shell => eprintln!(
+            "Skipping {} as it is a {}",
+            path.display(),
+            describe(file_type)
+        ),
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string(&json!({
+                "type": "skip",
+                "path": path,
+                "file_type": file_type,
+            }))
+            .unwrap()
+        ),
+    }
+}
+
+/// Reports that the directory at `path` is being left out of a transfer because it is a symlink to
+/// a directory that is already being transferred
+fn report_repeat(format: Format, path: &Path) {
+    match format {
+        Format::Shell => eprintln!(
+            "Skipping {} as it links to a directory already being copied",
+            path.display()
+        ),
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string(&json!({
+                "type": "skip",
+                "path": path,
+                "file_type": FileType::Symlink,
+            }))
+            .unwrap()
+        ),
+    }
+}
+
+fn file_name(path: &Path) -> anyhow::Result<&std::ffi::OsStr> {
+    path.file_name()
+        .with_context(|| format!("Unable to determine file name of {path:?}"))
+}
+
+/// Reports the progress of a transfer, either as a progress bar on stderr when it is a terminal
+/// (or periodic lines of text when output is accessible) or as a series of JSON events on stdout
+struct Progress {
+    format: Format,
+    bar: bool,
+
+    /// Time at which progress was last reported as a line of text, which is only done when
+    /// output is accessible
+    updated: Option<Instant>,
+
+    files: usize,
+    total_files: usize,
+    bytes: u64,
+    total_bytes: u64,
+}
+
+impl Progress {
+    fn new(format: Format, entries: &[Entry]) -> Self {
+        let accessible = !format.is_json() && OutputPolicy::current().is_accessible();
+        let this = Self {
+            format,
+            bar: !format.is_json() && !accessible && io::stderr().is_terminal(),
+            updated: accessible.then(Instant::now),
+            files: 0,
+            total_files: entries.iter().filter(|e| e.len.is_some()).count(),
+            bytes: 0,
+            total_bytes: entries.iter().filter_map(|e| e.len).sum(),
+        };
+
+        if accessible {
+            eprintln!(
+                "Transfer started: {} files, {}",
+                this.total_files,
+                human_bytes(this.total_bytes)
+            );
+        }
+
+        this
+    }
+
+    /// Records that `n` more bytes were transferred to `path`
+    fn advance(&mut self, path: &Path, n: u64) {
+        self.bytes += n;
+
+        match self.format {
+            Format::Shell if self.bar => self.draw(),
+            Format::Shell => self.update(),
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&json!({
+                    "type": "progress",
+                    "path": path,
+                    "bytes": self.bytes,
+                    "total_bytes": self.total_bytes,
+                }))
+                .unwrap()
+            ),
+        }
+    }
+
+    fn complete_file(&mut self) {
+        self.files += 1;
+        if self.bar {
+            self.draw();
+        } else {
+            self.update();
+        }
+    }
+
+    fn finish(&mut self) {
+        match self.format {
+            Format::Shell if self.bar => eprintln!(),
+            Format::Shell if self.updated.is_some() => eprintln!(
+                "Transfer finished: {} files, {}",
+                self.files,
+                human_bytes(self.bytes)
+            ),
+            Format::Shell => {}
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&json!({
+                    "type": "done",
+                    "files": self.files,
+                    "bytes": self.bytes,
+                }))
+                .unwrap()
+            ),
+        }
+    }
+
+    /// Reports progress as a line of text if enough time has passed since it was last reported,
+    /// doing nothing unless output is accessible
+    fn update(&mut self) {
+        let Some(updated) = self.updated else {
+            return;
+        };
+
+        if updated.elapsed() < PROGRESS_UPDATE_INTERVAL {
+            return;
+        }
+
+        eprintln!(
+            "Transferred {}%: {} of {}, {} of {} files",
+            (self.ratio() * 100.0) as u8,
+            human_bytes(self.bytes),
+            human_bytes(self.total_bytes),
+            self.files,
+            self.total_files,
+        );
+        self.updated = Some(Instant::now());
+    }
+
+    fn ratio(&self) -> f64 {
+        if self.total_bytes == 0 {
+            1.0
+        } else {
+            (self.bytes as f64 / self.total_bytes as f64).min(1.0)
+        }
+    }
+
+    fn draw(&self) {
+        let ratio = self.ratio();
+        let filled = (ratio * PROGRESS_BAR_WIDTH as f64) as usize;
+
+        let mut stderr = io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r[{}{}] {:>3}% {}/{} ({}/{} files)",
+            "=".repeat(filled),
+            " ".repeat(PROGRESS_BAR_WIDTH - filled),
+            (ratio * 100.0) as u8,
+            human_bytes(self.bytes),
+            human_bytes(self.total_bytes),
+            self.files,
+            self.total_files,
+        );
+        let _ = stderr.flush();
+    }
+}
+
+/// Formats `bytes` using the largest binary unit that keeps the value at or above one
+pub(super) fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::Context;
+use distant_core::{DistantChannel, DistantChannelExt};
+use log::*;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::time::Duration;
+use tokio::process::Child;
+
+/// How often the local copy of the file is checked for changes while watching
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Edits a file on a remote machine using an editor on the local machine
+pub struct Editor {
+    channel: DistantChannel,
+
+    /// Editor command split into the program and its arguments
+    cmd: Vec<String>,
+}
+
+impl Editor {
+    /// Creates an editor that runs `editor`, falling back to `$VISUAL`, `$EDITOR`, and finally
+    /// the default editor of the platform
+    pub fn new(channel: DistantChannel, editor: Option<String>) -> anyhow::Result<Self> {
+        let editor = editor
+            .or_else(|| std::env::var("VISUAL").ok())
+            .or_else(|| std::env::var("EDITOR").ok())
+            .filter(|x| !x.trim().is_empty())
+            .unwrap_or_else(|| String::from(if cfg!(windows) { "notepad" } else { "vi" }));
+
+        let cmd = if cfg!(windows) {
+            winsplit::split(&editor)
+        } else {
+            shell_words::split(&editor)
+                .with_context(|| format!("Failed to parse editor command {editor:?}"))?
+        };
+
+        if cmd.is_empty() {
+            anyhow::bail!("Editor command is empty");
+        }
+
+        Ok(Self { channel, cmd })
+    }
+
+    /// Downloads `path` into a temporary file, runs the editor on it, and writes it back once
+    /// the editor exits, or each time it is saved if `watch` is true
+    pub async fn edit(mut self, path: PathBuf, watch: bool) -> anyhow::Result<()> {
+        let data = if self
+            .channel
+            .exists(path.as_path())
+            .await
+            .with_context(|| format!("Failed to check if {path:?} exists"))?
+        {
+            self.channel
+                .read_file(path.as_path())
+                .await
+                .with_context(|| format!("Failed to read {path:?}"))?
+        } else {
+            debug!("{path:?} does not exist, so starting with an empty file");
+            Vec::new()
+        };
+
+        // Keep the file name so that editors can still tell what kind of file it is, placing it
+        // within a new directory that only we can access, failing rather than reusing a directory
+        // that someone else may have placed where ours would go
+        let dir = std::env::temp_dir().join(format!("distant-edit-{:016x}", rand::random::<u64>()));
+        let local = dir.join(path.file_name().unwrap_or_else(|| "file".as_ref()));
+        let mut builder = tokio::fs::DirBuilder::new();
+        #[cfg(unix)]
+        builder.mode(0o700);
+        builder
+            .create(&dir)
+            .await
+            .with_context(|| format!("Failed to create directory {dir:?}"))?;
+
+        let result = async {
+            tokio::fs::write(&local, &data)
+                .await
+                .with_context(|| format!("Failed to write {local:?}"))?;
+            self.run(&path, &local, data, watch).await
+        }
+        .await;
+
+        if let Err(x) = tokio::fs::remove_dir_all(&dir).await {
+            error!("Failed to remove {dir:?}: {x}");
+        }
+
+        result
+    }
+
+    async fn run(
+        &mut self,
+        path: &Path,
+        local: &Path,
+        mut saved: Vec<u8>,
+        watch: bool,
+    ) -> anyhow::Result<()> {
+        debug!("Launching {:?} to edit {path:?} as {local:?}", self.cmd);
+        let mut child = tokio::process::Command::new(&self.cmd[0])
+            .args(&self.cmd[1..])
+            .arg(local)
+            .spawn()
+            .with_context(|| format!("Failed to launch editor {:?}", self.cmd[0]))?;
+
+        let status = if watch {
+            self.wait_while_watching(&mut child, path, local, &mut saved)
+                .await?
+        } else {
+            child.wait().await?
+        };
+
+        // Like other tools that open an editor, an editor that fails discards any changes
+        if !status.success() {
+            anyhow::bail!("Editor exited with {status}, so {path:?} was not updated");
+        }
+
+        self.save_if_changed(path, local, &mut saved).await?;
+        Ok(())
+    }
+
+    async fn wait_while_watching(
+        &mut self,
+        child: &mut Child,
+        path: &Path,
+        local: &Path,
+        saved: &mut Vec<u8>,
+    ) -> anyhow::Result<ExitStatus> {
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            tokio::select! {
+                status = child.wait() => return Ok(status?),
+                _ = interval.tick() => {
+                    // Reporting failures would draw over the editor, so they are only logged
+                    // and will be retried with the next save or once the editor exits
+                    if let Err(x) = self.save_if_changed(path, local, saved).await {
+                        error!("{x:#}");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Writes the local copy of the file to `path` if it differs from what was last `saved`
+    async fn save_if_changed(
+        &mut self,
+        path: &Path,
+        local: &Path,
+        saved: &mut Vec<u8>,
+    ) -> anyhow::Result<()> {
+        let data = tokio::fs::read(local)
+            .await
+            .with_context(|| format!("Failed to read {local:?}"))?;
+
+        if data != *saved {
+            debug!("Writing {} bytes to {path:?}", data.len());
+            self.channel
+                .write_file(path, data.as_slice())
+                .await
+                .with_context(|| format!("Failed to write {path:?}"))?;
+            *saved = data;
+        }
+
+        Ok(())
+    }
+}
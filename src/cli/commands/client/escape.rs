@@ -0,0 +1,294 @@
+use std::{io, process::Command};
+use termwiz::input::{KeyCode, KeyEvent, Modifiers};
+
+/// Prompt shown when reading a command after `~C`
+const COMMAND_PROMPT: &str = "distant> ";
+
+/// Action to take for a key typed into a shell, which is either sent to the remote process or
+/// handled locally as part of an escape sequence
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EscapeAction {
+    /// Send the key to the remote process
+    Forward(KeyEvent),
+
+    /// Write text to the local terminal, such as the command being typed at the prompt
+    Echo(String),
+
+    /// Stop the shell without waiting on the remote process (`~.`)
+    Disconnect,
+
+    /// Describe the supported escape sequences (`~?`)
+    Help,
+
+    /// Run a command typed at the prompt (`~C`)
+    Command(EscapeCommand),
+}
+
+/// Command that can be run from the prompt opened by `~C`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EscapeCommand {
+    /// Describe the supported commands
+    Help,
+
+    /// Stop the shell without waiting on the remote process
+    Disconnect,
+
+    /// Stop the shell while leaving the remote process running for another client to claim
+    Handoff,
+
+    /// Run a command on the local machine, showing its output
+    Local(String),
+
+    /// Command that is not supported, reported back without doing anything
+    Unknown(String),
+}
+
+impl EscapeCommand {
+    /// Parses a line typed at the prompt, returning none if it is blank
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        Some(match line {
+            "" => return None,
+            "?" | "help" => Self::Help,
+            "disconnect" | "exit" => Self::Disconnect,
+            "handoff" => Self::Handoff,
+            _ => match line.strip_prefix('!') {
+                Some(cmd) => Self::Local(cmd.trim().to_string()),
+                None => Self::Unknown(line.to_string()),
+            },
+        })
+    }
+
+    /// Returns the description of each supported command
+    pub fn help() -> String {
+        [
+            "Commands:",
+            "  help        describe these commands",
+            "  disconnect  disconnect from the shell",
+            "  handoff     hand off the shell for another client to claim",
+            "  !COMMAND    run COMMAND on the local machine",
+        ]
+        .join("\r\n")
+    }
+}
+
+/// Reads the keys typed into a shell, picking out escape sequences in the style of ssh: the
+/// escape character (`~` by default) typed at the start of a line followed by another character
+#[derive(Clone, Debug)]
+pub struct EscapeReader {
+    escape_char: char,
+    state: State,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum State {
+    /// At the start of a line, where the escape character begins a sequence
+    LineStart,
+
+    /// Somewhere after the start of a line
+    Line,
+
+    /// Escape character was typed, waiting on the character following it
+    Escaped,
+
+    /// Reading a command at the prompt
+    Command(String),
+}
+
+impl EscapeReader {
+    pub fn new(escape_char: char) -> Self {
+        Self {
+            escape_char,
+            state: State::LineStart,
+        }
+    }
+
+    /// Returns the description of each supported escape sequence
+    pub fn help(&self) -> String {
+        let c = self.escape_char;
+        [
+            String::from("Supported escape sequences:"),
+            format!("  {c}.  disconnect"),
+            format!("  {c}C  open a command line"),
+            format!("  {c}?  this message"),
+            format!("  {c}{c}  send the escape character by typing it twice"),
+            String::from("(Note that escapes are only recognized immediately after newline.)"),
+        ]
+        .join("\r\n")
+    }
+
+    /// Reads the next key, returning the actions to take for it
+    pub fn read(&mut self, key: KeyEvent) -> Vec<EscapeAction> {
+        match std::mem::replace(&mut self.state, State::Line) {
+            State::LineStart if plain_char(&key) == Some(self.escape_char) => {
+                self.state = State::Escaped;
+                Vec::new()
+            }
+            State::LineStart | State::Line => self.forward(vec![key]),
+            State::Escaped => match plain_char(&key) {
+                Some('.') => vec![EscapeAction::Disconnect],
+                Some('C') => {
+                    self.state = State::Command(String::new());
+                    vec![EscapeAction::Echo(format!("\r\n{COMMAND_PROMPT}"))]
+                }
+                Some('?') => {
+                    self.state = State::LineStart;
+                    vec![EscapeAction::Help]
+                }
+                Some(c) if c == self.escape_char => self.forward(vec![key]),
+                _ => self.forward(vec![self.escape_key(), key]),
+            },
+            State::Command(mut line) => match (key.key, plain_char(&key)) {
+                (KeyCode::Enter, _) => {
+                    self.state = State::LineStart;
+                    let mut actions = vec![EscapeAction::Echo(String::from("\r\n"))];
+                    actions.extend(EscapeCommand::parse(&line).map(EscapeAction::Command));
+                    actions
+                }
+                (KeyCode::Backspace, _) => {
+                    let echo = match line.pop() {
+                        Some(_) => String::from("\x08 \x08"),
+                        None => String::new(),
+                    };
+                    self.state = State::Command(line);
+                    vec![EscapeAction::Echo(echo)]
+                }
+                (KeyCode::Escape, _) | (KeyCode::Char('\x03'), _) => {
+                    self.state = State::LineStart;
+                    vec![EscapeAction::Echo(String::from("\r\n"))]
+                }
+                (KeyCode::Char('c'), _) if key.modifiers.contains(Modifiers::CTRL) => {
+                    self.state = State::LineStart;
+                    vec![EscapeAction::Echo(String::from("\r\n"))]
+                }
+                (_, Some(c)) => {
+                    line.push(c);
+                    self.state = State::Command(line);
+                    vec![EscapeAction::Echo(c.to_string())]
+                }
+                _ => {
+                    self.state = State::Command(line);
+                    Vec::new()
+                }
+            },
+        }
+    }
+
+    /// Forwards `keys` to the remote process, noting whether the last of them starts a new line
+    fn forward(&mut self, keys: Vec<KeyEvent>) -> Vec<EscapeAction> {
+        self.state = match keys.last().map(|key| key.key) {
+            Some(KeyCode::Enter) => State::LineStart,
+            _ => State::Line,
+        };
+        keys.into_iter().map(EscapeAction::Forward).collect()
+    }
+
+    fn escape_key(&self) -> KeyEvent {
+        KeyEvent {
+            key: KeyCode::Char(self.escape_char),
+            modifiers: Modifiers::NONE,
+        }
+    }
+}
+
+/// Returns the character of a key typed without modifiers (other than shift)
+fn plain_char(key: &KeyEvent) -> Option<char> {
+    match key.key {
+        KeyCode::Char(c) if (key.modifiers - Modifiers::SHIFT).is_empty() && !c.is_control() => {
+            Some(c)
+        }
+        _ => None,
+    }
+}
+
+/// Runs `cmd` using the local shell, returning its combined output with newlines suited to a
+/// terminal in raw mode
+pub fn run_local_command(cmd: &str) -> io::Result<String> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").arg("/C").arg(cmd).output()?
+    } else {
+        Command::new("sh").arg("-c").arg(cmd).output()?
+    };
+
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    if !output.status.success() {
+        text.push_str(&format!("{cmd}: {}\n", output.status));
+    }
+    Ok(text.replace("\r\n", "\n").replace('\n', "\r\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent {
+            key: KeyCode::Char(c),
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    fn enter() -> KeyEvent {
+        KeyEvent {
+            key: KeyCode::Enter,
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn read_should_only_recognize_escape_character_at_start_of_line() {
+        let mut reader = EscapeReader::new('~');
+
+        // Mid-line, the escape character is sent like any other
+        assert_eq!(reader.read(key('a')), [EscapeAction::Forward(key('a'))]);
+        assert_eq!(reader.read(key('~')), [EscapeAction::Forward(key('~'))]);
+        assert_eq!(reader.read(key('.')), [EscapeAction::Forward(key('.'))]);
+        assert_eq!(reader.read(enter()), [EscapeAction::Forward(enter())]);
+
+        // At the start of a line, it is held until the next key decides what it means
+        assert_eq!(reader.read(key('~')), []);
+        assert_eq!(
+            reader.read(key('x')),
+            [
+                EscapeAction::Forward(key('~')),
+                EscapeAction::Forward(key('x'))
+            ]
+        );
+        assert_eq!(reader.read(enter()), [EscapeAction::Forward(enter())]);
+        assert_eq!(reader.read(key('~')), []);
+        assert_eq!(reader.read(key('~')), [EscapeAction::Forward(key('~'))]);
+        assert_eq!(reader.read(enter()), [EscapeAction::Forward(enter())]);
+        assert_eq!(reader.read(key('~')), []);
+        assert_eq!(reader.read(key('.')), [EscapeAction::Disconnect]);
+    }
+
+    #[test]
+    fn read_should_collect_command_typed_at_prompt() {
+        let mut reader = EscapeReader::new('~');
+        assert_eq!(reader.read(key('~')), []);
+        assert_eq!(
+            reader.read(key('C')),
+            [EscapeAction::Echo(format!("\r\n{COMMAND_PROMPT}"))]
+        );
+
+        for c in "!lsx".chars() {
+            assert_eq!(reader.read(key(c)), [EscapeAction::Echo(c.to_string())]);
+        }
+        reader.read(KeyEvent {
+            key: KeyCode::Backspace,
+            modifiers: Modifiers::NONE,
+        });
+
+        assert_eq!(
+            reader.read(enter()),
+            [
+                EscapeAction::Echo(String::from("\r\n")),
+                EscapeAction::Command(EscapeCommand::Local(String::from("ls")))
+            ]
+        );
+
+        // Having run the command, the next key is at the start of a line
+        assert_eq!(reader.read(key('~')), []);
+    }
+}
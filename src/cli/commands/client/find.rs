@@ -0,0 +1,149 @@
+use crate::options::FindBound;
+use distant_core::data::{FileType, SearchQueryCondition, SearchQueryOptions};
+
+/// Seconds within a day, the unit of modification times given to find
+const SECS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// Returns the condition that matches paths whose file name matches the glob `name`, or every
+/// path if there is no name
+pub fn condition(name: Option<&str>) -> SearchQueryCondition {
+    match name {
+        Some(name) => SearchQueryCondition::regex(format!(r"(^|[/\\]){}$", glob_to_regex(name))),
+        None => SearchQueryCondition::regex(".*"),
+    }
+}
+
+/// Converts a glob supporting `*`, `?`, and `[...]` into a regex matching a single path component
+fn glob_to_regex(glob: &str) -> String {
+    let escape = |s: &str| SearchQueryCondition::contains(s).to_regex_string();
+    let mut regex = String::new();
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(r"[^/\\]*"),
+            '?' => regex.push_str(r"[^/\\]"),
+
+            // A class without its closing bracket is treated as literal text
+            '[' => {
+                let mut class = String::from("[");
+                let mut rest = chars.clone();
+                if rest.peek() == Some(&'!') {
+                    rest.next();
+                    class.push('^');
+                }
+
+                let mut closed = false;
+                let mut first = true;
+                for c in rest.by_ref() {
+                    match c {
+                        ']' if !first => {
+                            closed = true;
+                            break;
+                        }
+                        '\\' | '[' | ']' => {
+                            class.push('\\');
+                            class.push(c);
+                        }
+                        c => class.push(c),
+                    }
+                    first = false;
+                }
+
+                if closed {
+                    class.push(']');
+                    regex.push_str(&class);
+                    chars = rest;
+                } else {
+                    regex.push_str(&escape("["));
+                }
+            }
+            c => regex.push_str(&escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+
+    regex
+}
+
+/// Returns the options that restrict a search to paths with one of `file_types`, whose sizes are
+/// within `sizes`, and that were modified within `mtimes` days of `now` (in seconds since the
+/// Unix epoch)
+pub fn options(
+    file_types: Vec<FileType>,
+    sizes: &[FindBound],
+    mtimes: &[FindBound],
+    now: u64,
+) -> SearchQueryOptions {
+    let mut options = SearchQueryOptions {
+        allowed_file_types: file_types.into_iter().collect(),
+        ..Default::default()
+    };
+
+    // Bounds are combined so that several of them narrow the search like they do with find
+    let narrow =
+        |min: &mut Option<u64>, max: &mut Option<u64>, (lo, hi): (Option<u64>, Option<u64>)| {
+            *min = (*min).max(lo);
+            *max = match (*max, hi) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        };
+
+    for size in sizes {
+        narrow(&mut options.min_size, &mut options.max_size, size.range());
+    }
+
+    // A file modified N days ago was modified within the day that began N + 1 days ago
+    let days_ago = |days: u64| now.saturating_sub(days.saturating_mul(SECS_PER_DAY));
+    for mtime in mtimes {
+        let (fewest, most) = mtime.range();
+        narrow(
+            &mut options.modified_after,
+            &mut options.modified_before,
+            (
+                most.map(|days| days_ago(days.saturating_add(1))),
+                fewest.map(days_ago),
+            ),
+        );
+    }
+
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condition_should_match_file_names_against_glob() {
+        assert_eq!(
+            condition(Some("*.rs")).to_regex_string(),
+            r"(^|[/\\])[^/\\]*\.rs$"
+        );
+        assert_eq!(
+            condition(Some("file[!0-9]?")).to_regex_string(),
+            r"(^|[/\\])file[^0-9][^/\\]$"
+        );
+        assert_eq!(condition(Some("a[b")).to_regex_string(), r"(^|[/\\])a\[b$");
+    }
+
+    #[test]
+    fn options_should_narrow_bounds_on_size_and_mtime() {
+        let now = 100 * SECS_PER_DAY;
+        let narrowed = options(
+            vec![FileType::File],
+            &[FindBound::MoreThan(10), FindBound::LessThan(100)],
+            &[FindBound::LessThan(7)],
+            now,
+        );
+
+        assert_eq!(narrowed.min_size, Some(11));
+        assert_eq!(narrowed.max_size, Some(99));
+        assert_eq!(narrowed.modified_after, Some(now - 7 * SECS_PER_DAY));
+        assert_eq!(narrowed.modified_before, None);
+
+        let exact = options(Vec::new(), &[], &[FindBound::Exactly(2)], now);
+        assert_eq!(exact.modified_after, Some(now - 3 * SECS_PER_DAY));
+        assert_eq!(exact.modified_before, Some(now - 2 * SECS_PER_DAY));
+    }
+}
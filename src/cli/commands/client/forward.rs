@@ -0,0 +1,229 @@
+use super::socks;
+use crate::constants::MAX_PIPE_CHUNK_SIZE;
+use crate::options::{DynamicForward, PortForward};
+use anyhow::Context;
+use distant_core::{DistantChannel, DistantChannelExt, RemoteTunnel, RemoteTunnelListener};
+use log::*;
+use std::io;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinSet,
+};
+
+/// Forwards ports through tunnels opened by the remote machine, in either direction
+pub struct Forwarder {
+    channel: DistantChannel,
+}
+
+impl Forwarder {
+    pub fn new(channel: DistantChannel) -> Self {
+        Self { channel }
+    }
+
+    /// Listens on the local port of each of `local` and `dynamic` and the remote port of each of
+    /// `remote`, relaying every connection made to them through its own tunnel, until
+    /// interrupted or one of the listeners fails
+    pub async fn run(
+        mut self,
+        local: Vec<PortForward>,
+        remote: Vec<PortForward>,
+        dynamic: Vec<DynamicForward>,
+    ) -> anyhow::Result<()> {
+        let mut listeners = JoinSet::new();
+        for forward in local {
+            let listener = TcpListener::bind((forward.bind_address.as_str(), forward.port))
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to listen on {}:{}",
+                        forward.bind_address, forward.port
+                    )
+                })?;
+
+            // The port is read back from the listener as port 0 picks any free port
+            let addr = listener
+                .local_addr()
+                .context("Failed to read listening address")?;
+            println!(
+                "Forwarding {addr} to {}:{} through the remote machine",
+                forward.host, forward.host_port
+            );
+
+            listeners.spawn(accept_local(self.channel.clone(), listener, forward));
+        }
+
+        for forward in remote {
+            let listener = self
+                .channel
+                .listen_tunnel(forward.bind_address.as_str(), forward.port)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to listen on {}:{} of the remote machine",
+                        forward.bind_address, forward.port
+                    )
+                })?;
+            println!(
+                "Forwarding {}:{} on the remote machine to {}:{}",
+                forward.bind_address,
+                listener.port(),
+                forward.host,
+                forward.host_port
+            );
+
+            listeners.spawn(accept_remote(listener, forward));
+        }
+
+        for forward in dynamic {
+            let listener = TcpListener::bind((forward.bind_address.as_str(), forward.port))
+                .await
+                .with_context(|| format!("Failed to listen on {forward}"))?;
+            let addr = listener
+                .local_addr()
+                .context("Failed to read listening address")?;
+            println!("Forwarding {addr} as a SOCKS5 proxy through the remote machine");
+
+            listeners.spawn(accept_dynamic(self.channel.clone(), listener));
+        }
+
+        while let Some(result) = listeners.join_next().await {
+            result.context("Listener task failed")??;
+        }
+
+        Ok(())
+    }
+}
+
+/// Accepts connections made to `listener`, relaying each through a new tunnel to the host and
+/// port of `forward`
+async fn accept_local(
+    channel: DistantChannel,
+    listener: TcpListener,
+    forward: PortForward,
+) -> anyhow::Result<()> {
+    loop {
+        let (stream, addr) = listener
+            .accept()
+            .await
+            .with_context(|| format!("Failed to accept connection for {forward}"))?;
+        debug!("Accepted connection from {addr} for {forward}");
+
+        let mut channel = channel.clone();
+        let host = forward.host.clone();
+        let port = forward.host_port;
+        tokio::spawn(async move {
+            let result = match channel.open_tunnel(host.as_str(), port).await {
+                Ok(tunnel) => relay(tunnel, stream).await,
+                Err(x) => Err(x),
+            };
+
+            if let Err(x) = result {
+                error!("Connection from {addr} to {host}:{port} failed: {x}");
+            }
+        });
+    }
+}
+
+/// Accepts connections made to `listener` as a SOCKS5 proxy, relaying each through a new tunnel
+/// to the host and port that it asks for
+async fn accept_dynamic(channel: DistantChannel, listener: TcpListener) -> anyhow::Result<()> {
+    loop {
+        let (mut stream, addr) = listener
+            .accept()
+            .await
+            .context("Failed to accept connection for SOCKS5 proxy")?;
+        debug!("Accepted SOCKS5 connection from {addr}");
+
+        let mut channel = channel.clone();
+        tokio::spawn(async move {
+            let result = async {
+                let (host, port) = socks::handshake(&mut stream).await?;
+                let tunnel = match channel.open_tunnel(host.as_str(), port).await {
+                    Ok(tunnel) => tunnel,
+                    Err(x) => {
+                        let reply = match x.kind() {
+                            io::ErrorKind::ConnectionRefused => socks::Reply::ConnectionRefused,
+                            _ => socks::Reply::GeneralFailure,
+                        };
+                        socks::reply(&mut stream, reply).await?;
+                        return Err(x);
+                    }
+                };
+
+                socks::reply(&mut stream, socks::Reply::Succeeded).await?;
+                relay(tunnel, stream).await
+            }
+            .await;
+
+            if let Err(x) = result {
+                error!("SOCKS5 connection from {addr} failed: {x}");
+            }
+        });
+    }
+}
+
+/// Accepts connections made to the remote `listener`, relaying each to the host and port of
+/// `forward` on the local machine
+async fn accept_remote(
+    mut listener: RemoteTunnelListener,
+    forward: PortForward,
+) -> anyhow::Result<()> {
+    while let Some((mut tunnel, peer)) = listener.accept().await {
+        debug!("Accepted remote connection from {peer} for {forward}");
+
+        let host = forward.host.clone();
+        let port = forward.host_port;
+        tokio::spawn(async move {
+            let result = match TcpStream::connect((host.as_str(), port)).await {
+                Ok(stream) => relay(tunnel, stream).await,
+                Err(x) => {
+                    // Let the remote peer know that nothing is coming
+                    let _ = tunnel.close().await;
+                    Err(x)
+                }
+            };
+
+            if let Err(x) = result {
+                error!("Remote connection from {peer} to {host}:{port} failed: {x}");
+            }
+        });
+    }
+
+    anyhow::bail!("Stopped listening on the remote machine for {forward}")
+}
+
+/// Relays data between `tunnel` and `stream` until both sides close
+pub(super) async fn relay<S>(tunnel: RemoteTunnel, stream: S) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    debug!("[Tunnel {}] Relaying", tunnel.id());
+
+    let (mut tunnel_reader, mut tunnel_writer) = tunnel.into_split();
+    let (mut reader, mut writer) = tokio::io::split(stream);
+
+    let outgoing = async {
+        let mut buf = vec![0; MAX_PIPE_CHUNK_SIZE];
+        loop {
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            tunnel_writer.write(&buf[..n]).await?;
+        }
+
+        // Pass along that nothing more is coming, leaving the other direction open
+        tunnel_writer.close().await
+    };
+
+    let incoming = async {
+        while let Some(data) = tunnel_reader.read().await {
+            writer.write_all(&data).await?;
+        }
+        writer.shutdown().await
+    };
+
+    tokio::try_join!(outgoing, incoming)?;
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use distant_core::data::{SearchQueryCondition, SearchQueryMatch};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Returns the condition that matches `pattern` within the contents of files
+pub fn condition(pattern: String, ignore_case: bool, fixed_strings: bool) -> SearchQueryCondition {
+    let condition = if fixed_strings {
+        SearchQueryCondition::contains(pattern)
+    } else {
+        SearchQueryCondition::regex(pattern)
+    };
+
+    if ignore_case {
+        SearchQueryCondition::regex(format!("(?i){}", condition.to_regex_string()))
+    } else {
+        condition
+    }
+}
+
+/// Formats matches like grep does, one line per match
+pub struct GrepPrinter {
+    /// If true, each file with matches is printed once rather than each matching line
+    files_with_matches: bool,
+
+    /// If true, groups of lines that are not next to each other are separated by `--`
+    has_context: bool,
+
+    /// Files that have already been printed when only printing files
+    printed: HashSet<PathBuf>,
+
+    /// Path and number of the last line printed
+    last_line: Option<(PathBuf, u64)>,
+}
+
+impl GrepPrinter {
+    pub fn new(files_with_matches: bool, has_context: bool) -> Self {
+        Self {
+            files_with_matches,
+            has_context,
+            printed: HashSet::new(),
+            last_line: None,
+        }
+    }
+
+    /// Returns the lines to print for `m`, which are none if its file was already printed
+    pub fn format(&mut self, m: SearchQueryMatch) -> Option<String> {
+        let m = match m {
+            SearchQueryMatch::Contents(m) => m,
+            SearchQueryMatch::Path(m) => return Some(m.path.to_string_lossy().into_owned()),
+        };
+
+        if self.files_with_matches {
+            let path = m.path.to_string_lossy().into_owned();
+            return self.printed.insert(m.path).then_some(path);
+        }
+
+        // Matches spanning several lines are printed with the number of each line, while lines
+        // of context use a dash in place of the colon
+        let text = m.lines.to_string_lossy();
+        let mut lines: Vec<(u64, char, String)> = text
+            .trim_end_matches(['\r', '\n'])
+            .split('\n')
+            .zip(m.line_number..)
+            .map(|(line, number)| (number, ':', line.to_string()))
+            .collect();
+        for context in m.context {
+            let text = context.line.to_string_lossy();
+            let line = text.trim_end_matches(['\r', '\n']).to_string();
+            lines.push((context.line_number, '-', line));
+        }
+        lines.sort_by_key(|(number, _, _)| *number);
+
+        let path = m.path.to_string_lossy().into_owned();
+        let mut output = Vec::new();
+        for (number, separator, line) in lines {
+            let is_adjacent = match &self.last_line {
+                Some((last_path, last_number)) => *last_path == m.path && number == last_number + 1,
+                None => true,
+            };
+            if self.has_context && !is_adjacent {
+                output.push(String::from("--"));
+            }
+
+            output.push(format!(
+                "{path}{separator}{number}{separator}{}",
+                line.trim_end_matches('\r')
+            ));
+            self.last_line = Some((m.path.clone(), number));
+        }
+
+        Some(output.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distant_core::data::{
+        SearchQueryContentsMatch, SearchQueryContextLine, SearchQueryMatchData,
+    };
+
+    fn contents_match(path: &str, line_number: u64, lines: &str) -> SearchQueryMatch {
+        SearchQueryMatch::Contents(SearchQueryContentsMatch {
+            path: PathBuf::from(path),
+            lines: SearchQueryMatchData::text(lines),
+            line_number,
+            absolute_offset: 0,
+            submatches: Vec::new(),
+            context: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn condition_should_escape_fixed_strings_and_ignore_case() {
+        assert_eq!(
+            condition(String::from("a.b"), false, false),
+            SearchQueryCondition::regex("a.b")
+        );
+        assert_eq!(
+            condition(String::from("a.b"), false, true),
+            SearchQueryCondition::contains("a.b")
+        );
+        assert_eq!(
+            condition(String::from("a.b"), true, true),
+            SearchQueryCondition::regex(r"(?i)a\.b")
+        );
+    }
+
+    #[test]
+    fn format_should_print_each_line_with_its_path_and_number() {
+        let mut printer = GrepPrinter::new(false, false);
+        assert_eq!(
+            printer.format(contents_match("src/lib.rs", 3, "one\r\ntwo\n")),
+            Some(String::from("src/lib.rs:3:one\nsrc/lib.rs:4:two"))
+        );
+    }
+
+    #[test]
+    fn format_should_print_each_file_once_if_only_printing_files() {
+        let mut printer = GrepPrinter::new(true, false);
+        assert_eq!(
+            printer.format(contents_match("src/lib.rs", 3, "one\n")),
+            Some(String::from("src/lib.rs"))
+        );
+        assert_eq!(
+            printer.format(contents_match("src/lib.rs", 7, "two\n")),
+            None
+        );
+    }
+
+    #[test]
+    fn format_should_print_context_with_dashes_and_separate_groups() {
+        let with_context = |line_number, lines, context: Vec<(u64, &str)>| {
+            let mut m = contents_match("src/lib.rs", line_number, lines);
+            if let SearchQueryMatch::Contents(m) = &mut m {
+                m.context = context
+                    .into_iter()
+                    .map(|(line_number, line)| SearchQueryContextLine {
+                        line_number,
+                        line: SearchQueryMatchData::text(line),
+                    })
+                    .collect();
+            }
+            m
+        };
+
+        let mut printer = GrepPrinter::new(false, true);
+        assert_eq!(
+            printer.format(with_context(2, "two\n", vec![(1, "one\n"), (3, "three\n")])),
+            Some(String::from(
+                "src/lib.rs-1-one\nsrc/lib.rs:2:two\nsrc/lib.rs-3-three"
+            ))
+        );
+        assert_eq!(
+            printer.format(with_context(6, "six\n", vec![(5, "five\n")])),
+            Some(String::from("--\nsrc/lib.rs-5-five\nsrc/lib.rs:6:six"))
+        );
+    }
+}
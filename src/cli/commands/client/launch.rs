@@ -0,0 +1,42 @@
+use anyhow::Context;
+use distant_core::net::common::{Destination, Map};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Collection of named profiles describing servers to launch, loaded from a TOML file where
+/// each table is a profile:
+///
+/// ```toml
+/// [dev1]
+/// destination = "ssh://user@dev1.example.com"
+///
+/// [dev2]
+/// destination = "ssh://user@dev2.example.com"
+/// options = "key=\"value\""
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct LaunchProfiles(pub BTreeMap<String, LaunchProfile>);
+
+impl LaunchProfiles {
+    /// Loads the profiles from the file at `path`
+    pub async fn load(path: &Path) -> anyhow::Result<Self> {
+        let bytes = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read launch profiles from {path:?}"))?;
+        toml_edit::de::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse launch profiles from {path:?}"))
+    }
+}
+
+/// Represents a single server to launch
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LaunchProfile {
+    /// Destination of the server to launch
+    pub destination: Destination,
+
+    /// Additional options to provide when launching, taking priority over those from the CLI
+    #[serde(default)]
+    pub options: Map,
+}
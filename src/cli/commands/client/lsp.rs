@@ -1,7 +1,11 @@
 use super::super::common::RemoteProcessLink;
 use super::{CliError, CliResult};
+use crate::cli::common::PromptAuthHandler;
 use anyhow::Context;
-use distant_core::{data::PtySize, DistantChannel, RemoteLspCommand};
+use distant_core::{
+    data::{ProcLimits, PtySize},
+    DistantChannel, RemoteLspCommand,
+};
 use std::path::PathBuf;
 use terminal_size::{terminal_size, Height, Width};
 
@@ -18,6 +22,8 @@ impl Lsp {
         cmd: impl Into<String>,
         current_dir: Option<PathBuf>,
         pty: bool,
+        limits: ProcLimits,
+        user: Option<String>,
         max_chunk_size: usize,
     ) -> CliResult {
         let cmd = cmd.into();
@@ -30,6 +36,9 @@ impl Lsp {
                 None
             })
             .current_dir(current_dir)
+            .limits(limits)
+            .user(user)
+            .prompt_handler(Box::new(PromptAuthHandler::new()))
             .spawn(self.0, &cmd)
             .await
             .with_context(|| format!("Failed to spawn {cmd}"))?;
@@ -42,11 +51,20 @@ impl Lsp {
             max_chunk_size,
         );
 
-        let status = proc.wait().await.context("Failed to wait for process")?;
+        let killer = proc.clone_killer();
+        let status = link
+            .wait_or_kill(proc.wait(), killer)
+            .await
+            .context("Failed to wait for process")?;
 
         // Shut down our link
         link.shutdown().await;
 
+        // Nothing reads the output of the LSP server anymore
+        let Some(status) = status else {
+            return Ok(());
+        };
+
         if !status.success {
             if let Some(code) = status.code {
                 return Err(CliError::Exit(code as u8));
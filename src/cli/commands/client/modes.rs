@@ -0,0 +1,220 @@
+use super::mouse::{MouseEncoding, MouseMode, MouseTracking};
+use std::sync::{Arc, Mutex};
+
+/// Start of a sequence setting or resetting DEC private modes, which programs use to turn mouse
+/// reporting and bracketed paste on and off
+const DEC_PRIVATE_MODE_PREFIX: &[u8] = b"\x1b[?";
+
+/// Maximum size of a DEC private mode sequence that is held back waiting for its end, beyond
+/// which it is passed through untouched
+const MAX_DEC_PRIVATE_MODE_LEN: usize = 64;
+
+/// Mode that has our own terminal report mouse events using SGR encoding, which is the only
+/// encoding that we are able to read
+const SGR_MODE: u16 = 1006;
+
+/// Mode that has pasted text wrapped in [`PASTE_START`] and [`PASTE_END`]
+const BRACKETED_PASTE_MODE: u16 = 2004;
+
+/// Marks the start of pasted text when bracketed paste is on
+const PASTE_START: &str = "\x1b[200~";
+
+/// Marks the end of pasted text when bracketed paste is on
+const PASTE_END: &str = "\x1b[201~";
+
+/// Modes of a terminal that a remote program has turned on, which decide how our own terminal
+/// input is sent to the program
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TerminalModes {
+    /// Mouse events that the program is told about, and how
+    pub mouse: MouseMode,
+
+    /// Whether the program expects pasted text to be wrapped in markers
+    pub bracketed_paste: bool,
+}
+
+impl TerminalModes {
+    /// Encodes `text` pasted into our own terminal for the remote program, wrapping it in markers
+    /// if the program asked for them so that the program can tell it apart from typed text
+    pub fn encode_paste(&self, text: &str) -> String {
+        if !self.bracketed_paste {
+            return text.to_string();
+        }
+
+        // NOTE: An end marker within the pasted text would let the rest of it be taken as typed,
+        //       so it is removed before the text is wrapped
+        format!("{PASTE_START}{}{PASTE_END}", text.replace(PASTE_END, ""))
+    }
+}
+
+/// Removes the sequences that turn mouse reporting and bracketed paste on and off from the output
+/// of a remote program, recording the modes that the program asked for in their place
+///
+/// Our own terminal is left to keep bracketed paste as it was when put into raw mode, and to
+/// report mouse events only while the remote program asks for them, always using SGR encoding.
+/// This leaves it to [`TerminalModes`] and [`MouseEncoder`](super::mouse::MouseEncoder) to send
+/// input the way that the remote program expects.
+pub struct ModeFilter {
+    pending: Vec<u8>,
+    modes: Arc<Mutex<TerminalModes>>,
+}
+
+impl ModeFilter {
+    /// Creates a filter that records the modes asked for by the remote program into `modes`
+    pub fn new(modes: Arc<Mutex<TerminalModes>>) -> Self {
+        Self {
+            pending: Vec::new(),
+            modes,
+        }
+    }
+
+    /// Filters the next piece of `output`, returning the output that remains
+    pub fn filter(&mut self, output: &[u8]) -> Vec<u8> {
+        let mut data = std::mem::take(&mut self.pending);
+        data.extend_from_slice(output);
+
+        let mut remaining = Vec::with_capacity(data.len());
+        let mut i = 0;
+        while i < data.len() {
+            let rest = &data[i..];
+            if rest[0] != 0x1b {
+                remaining.push(rest[0]);
+                i += 1;
+                continue;
+            }
+
+            // Hold onto what may be the start of a sequence until the rest of it arrives
+            if rest.len() < DEC_PRIVATE_MODE_PREFIX.len() {
+                if DEC_PRIVATE_MODE_PREFIX.starts_with(rest) {
+                    self.pending = rest.to_vec();
+                    break;
+                }
+            } else if rest.starts_with(DEC_PRIVATE_MODE_PREFIX) {
+                let params = &rest[DEC_PRIVATE_MODE_PREFIX.len()..];
+                match params.iter().position(|b| !matches!(b, b'0'..=b'9' | b';')) {
+                    Some(end) if matches!(params[end], b'h' | b'l') => {
+                        self.apply(&params[..end], params[end], &mut remaining);
+                        i += DEC_PRIVATE_MODE_PREFIX.len() + end + 1;
+                        continue;
+                    }
+                    None if rest.len() <= MAX_DEC_PRIVATE_MODE_LEN => {
+                        self.pending = rest.to_vec();
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+
+            remaining.push(rest[0]);
+            i += 1;
+        }
+
+        remaining
+    }
+
+    /// Sets (`h`) or resets (`l`) each mode within `params`, writing the modes that we do not
+    /// track back out along with what our own terminal needs to match the change
+    fn apply(&mut self, params: &[u8], action: u8, out: &mut Vec<u8>) {
+        let set = action == b'h';
+        let mut modes = self.modes.lock().unwrap();
+        let previous = modes.mouse.tracking;
+
+        let mut others = Vec::new();
+        for param in params.split(|b| *b == b';') {
+            let code = std::str::from_utf8(param)
+                .ok()
+                .and_then(|param| param.parse::<u16>().ok());
+
+            if let Some(tracking) = code.and_then(MouseTracking::from_mode) {
+                // NOTE: Like xterm, resetting any of the tracking modes stops tracking entirely
+                modes.mouse.tracking = if set { Some(tracking) } else { None };
+            } else if let Some(encoding) = code.and_then(MouseEncoding::from_mode) {
+                if set {
+                    modes.mouse.encoding = encoding;
+                } else if modes.mouse.encoding == encoding {
+                    modes.mouse.encoding = MouseEncoding::X10;
+                }
+            } else if code == Some(BRACKETED_PASTE_MODE) {
+                modes.bracketed_paste = set;
+            } else {
+                others.push(param);
+            }
+        }
+
+        if !others.is_empty() {
+            out.extend_from_slice(DEC_PRIVATE_MODE_PREFIX);
+            out.extend_from_slice(&others.join(&b';'));
+            out.push(action);
+        }
+
+        if modes.mouse.tracking != previous {
+            if let Some(tracking) = previous {
+                out.extend_from_slice(format!("\x1b[?{}l", tracking.mode()).as_bytes());
+            }
+            if let Some(tracking) = modes.mouse.tracking {
+                out.extend_from_slice(
+                    format!("\x1b[?{}h\x1b[?{SGR_MODE}h", tracking.mode()).as_bytes(),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_should_record_mouse_modes_and_keep_our_terminal_reporting_with_sgr() {
+        let modes = Arc::new(Mutex::new(TerminalModes::default()));
+        let mut filter = ModeFilter::new(Arc::clone(&modes));
+
+        // Other modes are kept, even when set alongside mouse modes
+        let output = filter.filter(b"before\x1b[?1049;100");
+        assert_eq!(output, b"before");
+        let output = filter.filter(b"2;1015h\x1b[?25lafter");
+        assert_eq!(
+            output,
+            b"\x1b[?1049h\x1b[?1002h\x1b[?1006h\x1b[?25lafter".as_slice()
+        );
+        assert_eq!(
+            modes.lock().unwrap().mouse,
+            MouseMode {
+                tracking: Some(MouseTracking::ButtonMotion),
+                encoding: MouseEncoding::Urxvt,
+            }
+        );
+
+        // Switching tracking modes is passed on, while encodings stay with us
+        assert_eq!(
+            filter.filter(b"\x1b[?1003h\x1b[?1015l"),
+            b"\x1b[?1002l\x1b[?1003h\x1b[?1006h"
+        );
+        assert_eq!(filter.filter(b"\x1b[?1000l"), b"\x1b[?1003l");
+        assert_eq!(*modes.lock().unwrap(), TerminalModes::default());
+    }
+
+    #[test]
+    fn filter_should_record_bracketed_paste_without_passing_it_on() {
+        let modes = Arc::new(Mutex::new(TerminalModes::default()));
+        let mut filter = ModeFilter::new(Arc::clone(&modes));
+
+        assert_eq!(filter.filter(b"$ \x1b[?2004h"), b"$ ");
+        assert!(modes.lock().unwrap().bracketed_paste);
+        assert_eq!(filter.filter(b"\x1b[?2004;25l"), b"\x1b[?25l");
+        assert!(!modes.lock().unwrap().bracketed_paste);
+    }
+
+    #[test]
+    fn encode_paste_should_wrap_text_only_if_bracketed_paste_is_on() {
+        let mut modes = TerminalModes::default();
+        assert_eq!(modes.encode_paste("a\rb"), "a\rb");
+
+        modes.bracketed_paste = true;
+        assert_eq!(modes.encode_paste("a\rb"), "\x1b[200~a\rb\x1b[201~");
+        assert_eq!(
+            modes.encode_paste("x\x1b[201~rm -rf ~\r"),
+            "\x1b[200~xrm -rf ~\r\x1b[201~"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use termwiz::input::{Modifiers, MouseButtons, MouseEvent};
+
+/// Mouse events that a remote program has asked to be told about, from fewest to most events
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MouseTracking {
+    /// Button presses only (mode 9)
+    Press,
+
+    /// Button presses and releases, including the wheel (mode 1000)
+    PressRelease,
+
+    /// Presses and releases, along with motion while a button is held (mode 1002)
+    ButtonMotion,
+
+    /// Presses and releases, along with all motion (mode 1003)
+    AnyMotion,
+}
+
+impl MouseTracking {
+    pub fn from_mode(mode: u16) -> Option<Self> {
+        match mode {
+            9 => Some(Self::Press),
+            1000 => Some(Self::PressRelease),
+            1002 => Some(Self::ButtonMotion),
+            1003 => Some(Self::AnyMotion),
+            _ => None,
+        }
+    }
+
+    pub fn mode(self) -> u16 {
+        match self {
+            Self::Press => 9,
+            Self::PressRelease => 1000,
+            Self::ButtonMotion => 1002,
+            Self::AnyMotion => 1003,
+        }
+    }
+}
+
+/// Encodings of mouse events that a remote program can ask for
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MouseEncoding {
+    /// Original encoding, with the button and coordinates each sent as a single byte
+    #[default]
+    X10,
+
+    /// Same as [`MouseEncoding::X10`], but with coordinates sent as UTF-8 characters (mode 1005)
+    Utf8,
+
+    /// Button and coordinates sent as decimal numbers, with releases told apart (mode 1006)
+    Sgr,
+
+    /// Button and coordinates sent as decimal numbers (mode 1015)
+    Urxvt,
+}
+
+impl MouseEncoding {
+    pub fn from_mode(mode: u16) -> Option<Self> {
+        match mode {
+            1005 => Some(Self::Utf8),
+            1006 => Some(Self::Sgr),
+            1015 => Some(Self::Urxvt),
+            _ => None,
+        }
+    }
+}
+
+/// Mouse reporting that a remote program has turned on
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MouseMode {
+    /// Events to report, where none are reported if not tracking
+    pub tracking: Option<MouseTracking>,
+
+    /// How reported events are encoded
+    pub encoding: MouseEncoding,
+}
+
+/// Turns mouse events read from our own terminal into the input that a remote program expects,
+/// keeping track of the held button to tell presses, releases, and drags apart
+#[derive(Default)]
+pub struct MouseEncoder {
+    held: Option<u16>,
+}
+
+impl MouseEncoder {
+    /// Encodes `event` (whose coordinates start at 1) for a remote program using `mode`, returning
+    /// none if the program has not asked to be told about the event
+    pub fn encode(&mut self, mode: MouseMode, event: &MouseEvent) -> Option<Vec<u8>> {
+        let buttons = &event.mouse_buttons;
+        let positive = buttons.contains(MouseButtons::WHEEL_POSITIVE);
+        let (button, release, motion) = if buttons.contains(MouseButtons::VERT_WHEEL) {
+            (if positive { 64 } else { 65 }, false, false)
+        } else if buttons.contains(MouseButtons::HORZ_WHEEL) {
+            (if positive { 66 } else { 67 }, false, false)
+        } else {
+            let pressed = if buttons.contains(MouseButtons::LEFT) {
+                Some(0)
+            } else if buttons.contains(MouseButtons::MIDDLE) {
+                Some(1)
+            } else if buttons.contains(MouseButtons::RIGHT) {
+                Some(2)
+            } else {
+                None
+            };
+
+            match (std::mem::replace(&mut self.held, pressed), pressed) {
+                (held, Some(button)) if held == Some(button) => (button, false, true),
+                (_, Some(button)) => (button, false, false),
+                (Some(button), None) => (button, true, false),
+                (None, None) => (3, false, true),
+            }
+        };
+
+        let reported = match mode.tracking? {
+            MouseTracking::Press => !release && !motion,
+            MouseTracking::PressRelease => !motion,
+            MouseTracking::ButtonMotion => !motion || self.held.is_some(),
+            MouseTracking::AnyMotion => true,
+        };
+        if !reported {
+            return None;
+        }
+
+        let mut code = button;
+        if motion {
+            code += 32;
+        }
+
+        // Programs only asking for presses are not told about modifiers
+        if mode.tracking != Some(MouseTracking::Press) {
+            let modifiers = event.modifiers;
+            if modifiers.contains(Modifiers::SHIFT) {
+                code += 4;
+            }
+            if modifiers.contains(Modifiers::ALT) {
+                code += 8;
+            }
+            if modifiers.contains(Modifiers::CTRL) {
+                code += 16;
+            }
+        }
+
+        // Apart from SGR, releases do not say which button was released
+        if release && mode.encoding != MouseEncoding::Sgr {
+            code = (code & !0b11) | 3;
+        }
+
+        let (x, y) = (event.x, event.y);
+        Some(match mode.encoding {
+            MouseEncoding::X10 => {
+                let byte = |n: u16| (n + 32).min(255) as u8;
+                vec![0x1b, b'[', b'M', byte(code), byte(x), byte(y)]
+            }
+            MouseEncoding::Utf8 => {
+                let char = |n: u16| char::from_u32(u32::from(n) + 32).unwrap_or(' ');
+                format!("\x1b[M{}{}{}", char(code), char(x), char(y)).into_bytes()
+            }
+            MouseEncoding::Sgr => {
+                let action = if release { 'm' } else { 'M' };
+                format!("\x1b[<{code};{x};{y}{action}").into_bytes()
+            }
+            MouseEncoding::Urxvt => format!("\x1b[{};{x};{y}M", code + 32).into_bytes(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(x: u16, y: u16, mouse_buttons: MouseButtons) -> MouseEvent {
+        MouseEvent {
+            x,
+            y,
+            mouse_buttons,
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn encoder_should_tell_presses_drags_and_releases_apart() {
+        let mut encoder = MouseEncoder::default();
+        let mode = MouseMode {
+            tracking: Some(MouseTracking::ButtonMotion),
+            encoding: MouseEncoding::Sgr,
+        };
+
+        let encoded = encoder.encode(mode, &event(3, 4, MouseButtons::RIGHT));
+        assert_eq!(encoded.as_deref(), Some(b"\x1b[<2;3;4M".as_slice()));
+        let encoded = encoder.encode(mode, &event(5, 4, MouseButtons::RIGHT));
+        assert_eq!(encoded.as_deref(), Some(b"\x1b[<34;5;4M".as_slice()));
+        let encoded = encoder.encode(mode, &event(5, 4, MouseButtons::NONE));
+        assert_eq!(encoded.as_deref(), Some(b"\x1b[<2;5;4m".as_slice()));
+
+        // Motion without a held button is only reported when tracking all motion
+        assert_eq!(encoder.encode(mode, &event(6, 4, MouseButtons::NONE)), None);
+        let mode = MouseMode {
+            tracking: Some(MouseTracking::AnyMotion),
+            ..mode
+        };
+        let encoded = encoder.encode(mode, &event(6, 4, MouseButtons::NONE));
+        assert_eq!(encoded.as_deref(), Some(b"\x1b[<35;6;4M".as_slice()));
+    }
+
+    #[test]
+    fn encoder_should_support_legacy_encodings_and_modifiers() {
+        let mut encoder = MouseEncoder::default();
+        let mode = MouseMode {
+            tracking: Some(MouseTracking::PressRelease),
+            encoding: MouseEncoding::X10,
+        };
+
+        let mut press = event(1, 2, MouseButtons::LEFT);
+        press.modifiers = Modifiers::CTRL;
+        let encoded = encoder.encode(mode, &press);
+        assert_eq!(encoded.as_deref(), Some(b"\x1b[M0!\"".as_slice()));
+        let encoded = encoder.encode(mode, &event(1, 2, MouseButtons::NONE));
+        assert_eq!(encoded.as_deref(), Some(b"\x1b[M#!\"".as_slice()));
+
+        let wheel = event(
+            10,
+            20,
+            MouseButtons::VERT_WHEEL | MouseButtons::WHEEL_POSITIVE,
+        );
+        let mode = MouseMode {
+            encoding: MouseEncoding::Urxvt,
+            ..mode
+        };
+        let encoded = encoder.encode(mode, &wheel);
+        assert_eq!(encoded.as_deref(), Some(b"\x1b[96;10;20M".as_slice()));
+
+        // Nothing is reported without the remote program asking for it
+        assert_eq!(encoder.encode(MouseMode::default(), &wheel), None);
+    }
+}
@@ -0,0 +1,222 @@
+use distant_core::{
+    data::{ProcessId, SearchId, WatchId},
+    net::{
+        common::{Request, Response},
+        manager::RawChannel,
+    },
+    DistantMsg, DistantRequestData, DistantResponseData,
+};
+use log::*;
+use std::{collections::HashSet, io, path::PathBuf, time::Duration};
+
+/// Longest time spent waiting on the server to cancel the operations still going on
+const CANCEL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Operations started through the api that go on sending responses until they are cancelled,
+/// which are tracked so that they can be cancelled once nothing reads those responses anymore
+#[derive(Default)]
+pub struct Ongoing {
+    processes: HashSet<ProcessId>,
+    searches: HashSet<SearchId>,
+    watches: HashSet<(PathBuf, Option<WatchId>)>,
+}
+
+impl Ongoing {
+    /// Tracks the operations started or stopped by `msg` on its way to the server
+    pub fn track_request(&mut self, msg: &DistantMsg<DistantRequestData>) {
+        for payload in payloads(msg) {
+            match payload {
+                DistantRequestData::Watch { path, id, .. } => {
+                    self.watches.insert((path.clone(), *id));
+                }
+                DistantRequestData::Unwatch { path, id } => {
+                    self.watches.remove(&(path.clone(), *id));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Tracks the operations started or finished according to `msg` from the server
+    pub fn track_response(&mut self, msg: &DistantMsg<DistantResponseData>) {
+        for payload in payloads(msg) {
+            match payload {
+                // Processes with a session are meant to outlive us, to be attached to later
+                DistantResponseData::ProcSpawned {
+                    id, session: None, ..
+                } => {
+                    self.processes.insert(*id);
+                }
+                DistantResponseData::ProcDone { id, .. } => {
+                    self.processes.remove(id);
+                }
+                DistantResponseData::SearchStarted { id } => {
+                    self.searches.insert(*id);
+                }
+                DistantResponseData::SearchDone { id, .. } => {
+                    self.searches.remove(id);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the requests that cancel every operation still going on
+    pub fn into_cancel_requests(self) -> Vec<DistantRequestData> {
+        let processes = self
+            .processes
+            .into_iter()
+            .map(|id| DistantRequestData::ProcKill { id });
+        let searches = self
+            .searches
+            .into_iter()
+            .map(|id| DistantRequestData::CancelSearch { id });
+        let watches = self
+            .watches
+            .into_iter()
+            .map(|(path, id)| DistantRequestData::Unwatch { path, id });
+        processes.chain(searches).chain(watches).collect()
+    }
+
+    /// Cancels every operation still going on over `channel`, waiting (up to a point) for the
+    /// server to respond so that the cancellations are not lost once the channel is dropped
+    pub async fn cancel(self, channel: &mut RawChannel) -> io::Result<()> {
+        let requests = self.into_cancel_requests();
+        if requests.is_empty() {
+            return Ok(());
+        }
+
+        debug!("Cancelling {} ongoing operations", requests.len());
+        let request = Request::new(DistantMsg::Batch(requests));
+        channel.write_frame_for(&request).await?;
+
+        let responded = tokio::time::timeout(CANCEL_TIMEOUT, async {
+            while let Some(res) = channel
+                .read_frame_as::<Response<DistantMsg<DistantResponseData>>>()
+                .await?
+            {
+                if res.origin_id == request.id {
+                    break;
+                }
+            }
+            io::Result::Ok(())
+        })
+        .await;
+
+        match responded {
+            Ok(result) => result,
+            Err(_) => {
+                warn!("Timed out waiting for ongoing operations to be cancelled");
+                Ok(())
+            }
+        }
+    }
+}
+
+fn payloads<T>(msg: &DistantMsg<T>) -> &[T] {
+    match msg {
+        DistantMsg::Single(x) => std::slice::from_ref(x),
+        DistantMsg::Batch(x) => x,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single<T>(payload: T) -> DistantMsg<T> {
+        DistantMsg::Single(payload)
+    }
+
+    fn watch(path: &str, id: Option<WatchId>) -> DistantRequestData {
+        DistantRequestData::Watch {
+            path: PathBuf::from(path),
+            recursive: false,
+            only: Vec::new(),
+            except: Vec::new(),
+            id,
+        }
+    }
+
+    #[test]
+    fn into_cancel_requests_should_be_empty_if_nothing_was_started() {
+        let mut ongoing = Ongoing::default();
+        ongoing.track_request(&single(DistantRequestData::SystemInfo {}));
+        ongoing.track_response(&single(DistantResponseData::Ok));
+        assert_eq!(ongoing.into_cancel_requests(), Vec::new());
+    }
+
+    #[test]
+    fn into_cancel_requests_should_cancel_processes_searches_and_watches_still_going_on() {
+        let mut ongoing = Ongoing::default();
+        ongoing.track_request(&DistantMsg::Batch(vec![
+            watch("a", None),
+            watch("b", Some(3)),
+        ]));
+        ongoing.track_response(&DistantMsg::Batch(vec![
+            DistantResponseData::ProcSpawned {
+                id: 1,
+                session: None,
+                output_window: None,
+            },
+            DistantResponseData::SearchStarted { id: 2 },
+        ]));
+
+        let mut requests = ongoing.into_cancel_requests();
+        let watches = requests.split_off(2);
+        assert_eq!(
+            requests,
+            vec![
+                DistantRequestData::ProcKill { id: 1 },
+                DistantRequestData::CancelSearch { id: 2 },
+            ]
+        );
+        assert_eq!(watches.len(), 2, "Unexpected requests: {watches:?}");
+        assert!(watches.contains(&DistantRequestData::Unwatch {
+            path: PathBuf::from("a"),
+            id: None,
+        }));
+        assert!(watches.contains(&DistantRequestData::Unwatch {
+            path: PathBuf::from("b"),
+            id: Some(3),
+        }));
+    }
+
+    #[test]
+    fn into_cancel_requests_should_skip_operations_that_have_finished() {
+        let mut ongoing = Ongoing::default();
+        ongoing.track_request(&single(watch("a", Some(3))));
+        ongoing.track_request(&single(DistantRequestData::Unwatch {
+            path: PathBuf::from("a"),
+            id: Some(3),
+        }));
+        ongoing.track_response(&single(DistantResponseData::ProcSpawned {
+            id: 1,
+            session: None,
+            output_window: None,
+        }));
+        ongoing.track_response(&single(DistantResponseData::ProcDone {
+            id: 1,
+            success: true,
+            code: Some(0),
+        }));
+        ongoing.track_response(&single(DistantResponseData::SearchStarted { id: 2 }));
+        ongoing.track_response(&single(DistantResponseData::SearchDone {
+            id: 2,
+            skipped: Default::default(),
+            errors: Vec::new(),
+        }));
+        assert_eq!(ongoing.into_cancel_requests(), Vec::new());
+    }
+
+    #[test]
+    fn into_cancel_requests_should_leave_processes_with_a_session_running() {
+        let mut ongoing = Ongoing::default();
+        ongoing.track_response(&single(DistantResponseData::ProcSpawned {
+            id: 1,
+            session: Some(Default::default()),
+            output_window: None,
+        }));
+        assert_eq!(ongoing.into_cancel_requests(), Vec::new());
+    }
+}
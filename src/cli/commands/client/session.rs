@@ -0,0 +1,347 @@
+use crate::cli::common::{MsgReceiver, MsgSender};
+use crate::options::SessionMatcher;
+use anyhow::Context;
+use distant_core::data::{Error, ErrorKind};
+use distant_core::net::common::{Request, Response};
+use distant_core::{DistantChannel, DistantMsg, DistantRequestData, DistantResponseData};
+use log::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Entry within the recording of an API session, which is written as a single line of JSON
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionEntry {
+    Request(Value),
+    Response(Value),
+}
+
+/// Returns a sender that writes each entry of a session to a new file at `path`
+pub fn recorder(path: &Path) -> io::Result<MsgSender> {
+    let mut file = File::create(path)?;
+    Ok(MsgSender::from(move |output: &'_ [u8]| {
+        file.write_all(output)?;
+        file.flush()
+    }))
+}
+
+/// Request of a recorded session alongside the payloads of the responses that it received
+#[derive(Clone, Debug, PartialEq)]
+pub struct Exchange {
+    pub request: Request<DistantMsg<DistantRequestData>>,
+    pub responses: Vec<Value>,
+}
+
+/// Loads the exchanges of the session recorded at `path` in the order that requests were sent
+pub fn load(path: &Path) -> anyhow::Result<Vec<Exchange>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+    let mut exchanges: Vec<Exchange> = Vec::new();
+
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("Failed to read {path:?}"))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry: SessionEntry = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid entry on line {} of {path:?}", i + 1))?;
+        match entry {
+            SessionEntry::Request(request) => exchanges.push(Exchange {
+                request: serde_json::from_value(request)
+                    .with_context(|| format!("Invalid request on line {} of {path:?}", i + 1))?,
+                responses: Vec::new(),
+            }),
+            SessionEntry::Response(response) => {
+                let response: Response<Value> = serde_json::from_value(response)
+                    .with_context(|| format!("Invalid response on line {} of {path:?}", i + 1))?;
+                match exchanges
+                    .iter_mut()
+                    .rfind(|x| x.request.id == response.origin_id)
+                {
+                    Some(exchange) => exchange.responses.push(response.payload),
+                    None => warn!(
+                        "Skipping response on line {} of {path:?} to unknown request {}",
+                        i + 1,
+                        response.origin_id
+                    ),
+                }
+            }
+        }
+    }
+
+    Ok(exchanges)
+}
+
+/// Compares payloads of requests and responses with the configured matcher
+#[derive(Clone, Debug)]
+pub struct Comparer {
+    pub matcher: SessionMatcher,
+
+    /// JSON pointers to fields removed from payloads before comparing them
+    pub ignore: Vec<String>,
+}
+
+impl Comparer {
+    /// Returns true if payloads `a` and `b` match
+    pub fn matches(&self, a: &Value, b: &Value) -> bool {
+        self.normalize(a) == self.normalize(b)
+    }
+
+    /// Returns `payload` reduced to what is compared, where batches are compared item by item
+    fn normalize(&self, payload: &Value) -> Value {
+        match payload {
+            Value::Array(items) => Value::Array(items.iter().map(|x| self.normalize(x)).collect()),
+            payload => match self.matcher {
+                SessionMatcher::Exact => {
+                    let mut payload = payload.clone();
+                    for pointer in self.ignore.iter() {
+                        remove_pointer(&mut payload, pointer);
+                    }
+                    payload
+                }
+                SessionMatcher::Type => payload.get("type").cloned().unwrap_or(Value::Null),
+            },
+        }
+    }
+}
+
+/// Removes the field at the JSON `pointer` within `value`, if it exists
+fn remove_pointer(value: &mut Value, pointer: &str) {
+    let Some((parent, key)) = pointer.rsplit_once('/') else {
+        return;
+    };
+
+    let key = key.replace("~1", "/").replace("~0", "~");
+    match value.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.remove(&key);
+        }
+        Some(Value::Array(items)) => {
+            if let Some(x) = key.parse::<usize>().ok().and_then(|i| items.get_mut(i)) {
+                *x = Value::Null;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Sends each request of `exchanges` over `channel` in order, returning a description of each
+/// way that the responses differed from those recorded
+///
+/// Each request waits up to `timeout` for as many responses as were recorded for it.
+pub async fn replay(
+    channel: &mut DistantChannel,
+    exchanges: Vec<Exchange>,
+    comparer: &Comparer,
+    timeout: Duration,
+) -> io::Result<Vec<String>> {
+    let mut mismatches = Vec::new();
+
+    for exchange in exchanges {
+        let id = exchange.request.id.clone();
+        debug!("Replaying request {id}");
+        let mut mailbox = channel.mail(exchange.request).await?;
+
+        let mut responses = Vec::new();
+        while responses.len() < exchange.responses.len() {
+            match mailbox.next_timeout(timeout).await {
+                Ok(Some(response)) => responses.push(serde_json::to_value(response.payload)?),
+                Ok(None) => break,
+                Err(x) if x.kind() == io::ErrorKind::TimedOut => break,
+                Err(x) => return Err(x),
+            }
+        }
+
+        if responses.len() != exchange.responses.len() {
+            mismatches.push(format!(
+                "Request {id} expected {} responses, but got {}",
+                exchange.responses.len(),
+                responses.len()
+            ));
+        }
+
+        for (i, (expected, actual)) in exchange.responses.iter().zip(responses.iter()).enumerate() {
+            if !comparer.matches(expected, actual) {
+                mismatches.push(format!(
+                    "Request {id} response {} differs\n  expected: {expected}\n    actual: {actual}",
+                    i + 1
+                ));
+            }
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Responds to requests using the responses of a recorded session, where each request is
+/// answered by the first exchange not yet used whose request matches it
+pub struct Mock {
+    exchanges: Vec<Option<Exchange>>,
+    comparer: Comparer,
+}
+
+impl Mock {
+    pub fn new(exchanges: Vec<Exchange>, comparer: Comparer) -> Self {
+        Self {
+            exchanges: exchanges.into_iter().map(Some).collect(),
+            comparer,
+        }
+    }
+
+    /// Returns the recorded payloads of the responses to `request`, or none if no recorded request
+    /// matches it
+    pub fn respond(
+        &mut self,
+        request: &Request<DistantMsg<DistantRequestData>>,
+    ) -> io::Result<Option<Vec<Value>>> {
+        let payload = serde_json::to_value(&request.payload)?;
+        for slot in self.exchanges.iter_mut() {
+            let matches = match slot {
+                Some(exchange) => {
+                    let recorded = serde_json::to_value(&exchange.request.payload)?;
+                    self.comparer.matches(&recorded, &payload)
+                }
+                None => false,
+            };
+
+            if matches {
+                return Ok(slot.take().map(|exchange| exchange.responses));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Responds to each request read from stdin using `mock`, writing the responses to stdout
+pub async fn serve_mock(mut mock: Mock) -> io::Result<()> {
+    let tx = MsgSender::from_stdout();
+    let mut rx = MsgReceiver::from_stdin().into_rx::<Request<DistantMsg<DistantRequestData>>>();
+
+    while let Some(request) = rx.recv().await {
+        let request = match request {
+            Ok(request) => request,
+            Err(x) => {
+                error!("{x}");
+                continue;
+            }
+        };
+
+        match mock.respond(&request)? {
+            Some(payloads) => {
+                for payload in payloads {
+                    tx.send_blocking(&Response::new(request.id.clone(), payload))?;
+                }
+            }
+            None => tx.send_blocking(&Response::new(
+                request.id,
+                DistantMsg::Single(DistantResponseData::Error(Error {
+                    kind: ErrorKind::NotFound,
+                    description: String::from("No recorded request matches this request"),
+                })),
+            ))?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn request(id: &str, path: &str) -> Request<DistantMsg<DistantRequestData>> {
+        Request {
+            id: id.to_string(),
+            deadline: None,
+            idempotency_key: None,
+            correlation_id: None,
+            payload: DistantMsg::Single(DistantRequestData::FileReadText {
+                path: PathBuf::from(path),
+            }),
+        }
+    }
+
+    fn comparer(matcher: SessionMatcher, ignore: &[&str]) -> Comparer {
+        Comparer {
+            matcher,
+            ignore: ignore.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn load_should_group_responses_with_their_requests() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("session.jsonl");
+        let recorder = recorder(file.path()).unwrap();
+
+        let text = json!({ "type": "text", "data": "contents" });
+        recorder
+            .send_blocking(&SessionEntry::Request(
+                serde_json::to_value(request("1", "a.txt")).unwrap(),
+            ))
+            .unwrap();
+        recorder
+            .send_blocking(&SessionEntry::Request(
+                serde_json::to_value(request("2", "b.txt")).unwrap(),
+            ))
+            .unwrap();
+        recorder
+            .send_blocking(&SessionEntry::Response(
+                serde_json::to_value(Response::new(String::from("2"), text.clone())).unwrap(),
+            ))
+            .unwrap();
+
+        assert_eq!(
+            load(file.path()).unwrap(),
+            vec![
+                Exchange {
+                    request: request("1", "a.txt"),
+                    responses: Vec::new(),
+                },
+                Exchange {
+                    request: request("2", "b.txt"),
+                    responses: vec![text],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn comparer_should_skip_ignored_fields_or_compare_only_types() {
+        let a = json!({ "type": "metadata", "len": 3, "accessed": 100 });
+        let b = json!({ "type": "metadata", "len": 3, "accessed": 200 });
+        let c = json!([{ "type": "metadata", "len": 4, "accessed": 100 }]);
+
+        assert!(!comparer(SessionMatcher::Exact, &[]).matches(&a, &b));
+        assert!(comparer(SessionMatcher::Exact, &["/accessed"]).matches(&a, &b));
+        assert!(!comparer(SessionMatcher::Exact, &["/accessed"]).matches(&a, &c));
+        assert!(comparer(SessionMatcher::Type, &[]).matches(&json!([a]), &c));
+    }
+
+    #[test]
+    fn mock_should_respond_with_each_matching_exchange_once() {
+        let text = json!({ "type": "text", "data": "contents" });
+        let mut mock = Mock::new(
+            vec![Exchange {
+                request: request("1", "a.txt"),
+                responses: vec![text.clone()],
+            }],
+            comparer(SessionMatcher::Exact, &[]),
+        );
+
+        assert_eq!(mock.respond(&request("5", "b.txt")).unwrap(), None);
+        assert_eq!(
+            mock.respond(&request("6", "a.txt")).unwrap(),
+            Some(vec![text])
+        );
+        assert_eq!(mock.respond(&request("7", "a.txt")).unwrap(), None);
+    }
+}
@@ -1,34 +1,253 @@
 use super::super::common::RemoteProcessLink;
+use super::clipboard::{Clipboard, Osc52Filter};
+use super::escape::{self, EscapeAction, EscapeCommand, EscapeReader};
+use super::modes::{ModeFilter, TerminalModes};
+use super::mouse::MouseEncoder;
+use super::title;
 use super::{CliError, CliResult};
+use crate::options::{ClipboardMode, EscapeChar};
 use anyhow::Context;
 use distant_core::{
-    data::{Environment, PtySize},
-    DistantChannel, DistantChannelExt, RemoteCommand,
+    data::{Environment, ProcLimits, ProcessId, PtySize, SessionId},
+    net::{
+        client::{ConnectionState, ConnectionWatcher},
+        common::Map,
+        manager::{ChannelHandoff, RawChannel},
+    },
+    DistantChannel, DistantChannelExt, RemoteCommand, RemoteProcess, RemoteProcessKiller,
+    RemoteProcessResizer, RemoteStatus, RemoteStderr, RemoteStdin, RemoteStdout,
 };
 use log::*;
-use std::path::PathBuf;
-use std::time::Duration;
+use std::{
+    future::Future,
+    io::{self, Write},
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 use terminal_size::{terminal_size, Height, Width};
 use termwiz::{
     caps::Capabilities,
-    input::{InputEvent, KeyCodeEncodeModes, KeyboardEncoding},
-    terminal::{new_terminal, Terminal},
+    escape::OperatingSystemCommand,
+    input::{InputEvent, KeyCodeEncodeModes, KeyEvent, KeyboardEncoding, MouseEvent},
+    terminal::{new_terminal, Terminal, TerminalWaker},
 };
+use tokio::sync::{mpsc, Notify};
+
+/// Time to wait between checks for terminal input when polling rather than blocking
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Time to wait on submitting a request to kill the remote process when disconnecting, which may
+/// not go through if the connection has hung
+const DISCONNECT_KILL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Time to wait before the first attempt to reattach to the remote process, doubling after each
+/// failed attempt up to [`MAX_RECONNECT_DELAY`]
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(250);
 
-#[derive(Clone)]
-pub struct Shell(DistantChannel);
+/// Most time to wait between attempts to reattach to the remote process
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Time after losing the connection at which to give up on reattaching to the remote process
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Opens a new channel to the connection of a shell, which is used to pick the shell back up once
+/// its channel is lost
+pub type Reconnect =
+    Box<dyn FnMut() -> Pin<Box<dyn Future<Output = io::Result<RawChannel>> + Send>> + Send>;
+
+pub struct Shell {
+    channel: DistantChannel,
+    watcher: Option<ConnectionWatcher>,
+    reconnect: Option<Reconnect>,
+    detach_on_exit: bool,
+    init_cmd: Option<String>,
+    handoff: Option<ChannelHandoff>,
+    title: Option<String>,
+    limits: ProcLimits,
+    kill_tree: bool,
+    user: Option<String>,
+}
+
+/// Reason that a shell stopped being attached to its remote process
+enum Detached {
+    /// Remote process finished, or waiting on it failed
+    Exited(io::Result<RemoteStatus>),
+
+    /// Sending input to the remote process failed
+    InputFailed(io::Error),
+
+    /// User disconnected using an escape sequence
+    Disconnected,
+
+    /// User handed off the shell using an escape sequence
+    HandedOff,
+
+    /// Manager gave up on reaching the server behind the channel
+    Lost,
+}
 
 impl Shell {
     pub fn new(channel: DistantChannel) -> Self {
-        Self(channel)
+        Self {
+            channel,
+            watcher: None,
+            reconnect: None,
+            detach_on_exit: false,
+            init_cmd: None,
+            handoff: None,
+            title: None,
+            limits: ProcLimits::default(),
+            kill_tree: false,
+            user: None,
+        }
     }
 
+    /// Shows a banner whenever `watcher` reports that the connection to the server was lost or
+    /// restored, as the channel itself stays quiet while the manager reconnects to the server
+    pub fn with_connection_watcher(mut self, watcher: ConnectionWatcher) -> Self {
+        self.watcher = Some(watcher);
+        self
+    }
+
+    /// Reattaches to the remote process over a channel from `reconnect` when the connection to it
+    /// is lost, rather than exiting
+    pub fn with_reconnect(mut self, reconnect: Reconnect) -> Self {
+        self.reconnect = Some(reconnect);
+        self
+    }
+
+    /// Spawns the remote process with a session when `detach_on_exit` is true, leaving it running
+    /// when disconnecting so that it can be attached to again with [`Shell::attach`]
+    pub fn with_detach_on_exit(mut self, detach_on_exit: bool) -> Self {
+        self.detach_on_exit = detach_on_exit;
+        self
+    }
+
+    /// Sends `init_cmd` as a line of input to the remote process once it is spawned, ahead of
+    /// anything typed by the user, such as to activate an environment or change directory
+    pub fn with_init_cmd(mut self, init_cmd: Option<String>) -> Self {
+        self.init_cmd = init_cmd;
+        self
+    }
+
+    /// Lets the user hand off the shell with `handoff` (using the `handoff` escape command), so
+    /// that another client can pick up the remote process with [`Shell::claim`]
+    pub fn with_handoff(mut self, handoff: ChannelHandoff) -> Self {
+        self.handoff = Some(handoff);
+        self
+    }
+
+    /// Sets the title of our terminal to `title` while the shell runs, restoring the title from
+    /// before once it exits, with the remote program free to change the title in the meantime
+    pub fn with_title(mut self, title: Option<String>) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Spawns the remote process with the priority and `limits` on resources applied before it
+    /// runs, which has no effect when attaching to a process
+    pub fn with_limits(mut self, limits: ProcLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Spawns the remote process to be killed along with the processes it started, which has no
+    /// effect when attaching to a process
+    pub fn with_kill_tree(mut self, kill_tree: bool) -> Self {
+        self.kill_tree = kill_tree;
+        self
+    }
+
+    /// Spawns the remote process as `user`, where any password needed to switch users is asked
+    /// for within the terminal of the process
+    pub fn with_user(mut self, user: Option<String>) -> Self {
+        self.user = user;
+        self
+    }
+
+    /// Returns a command for spawning or attaching to the remote process, where attaching to a
+    /// process left running with a session shows the output it produced while we were away
+    ///
+    /// NOTE: Processes left running with a session go without an output window, as one would
+    ///       fill up with output that no client is around to acknowledge and stall the process
+    fn command(persist: bool) -> RemoteCommand {
+        let mut command = RemoteCommand::new();
+        command.pty(pty_size()).persist(persist).replay(persist);
+        if persist {
+            command.output_window(None);
+        }
+        command
+    }
+
+    /// Attaches to the remote process with `session`, left running by an earlier shell
+    pub async fn attach(
+        self,
+        session: SessionId,
+        poll_input: bool,
+        clipboard: ClipboardMode,
+        escape_char: EscapeChar,
+    ) -> CliResult {
+        let proc = Self::command(true)
+            .attach_session(self.channel.clone(), session)
+            .await
+            .with_context(|| format!("Failed to attach to session {session}"))?;
+
+        // Catch the remote process up on the size of our terminal, which may differ from the one
+        // that it was last attached to
+        if let Some(size) = pty_size() {
+            if let Err(x) = proc.resize(size).await {
+                error!("Failed to resize remote process: {}", x);
+            }
+        }
+
+        self.run(proc, poll_input, clipboard, escape_char).await
+    }
+
+    /// Picks up the remote process described by `resources`, which came with a channel that
+    /// another shell handed off
+    ///
+    /// NOTE: Output sent by the remote process between the handoff and the claim is not shown
+    pub async fn claim(
+        self,
+        resources: Map,
+        poll_input: bool,
+        clipboard: ClipboardMode,
+        escape_char: EscapeChar,
+    ) -> CliResult {
+        let id = resources
+            .get("process")
+            .and_then(|id| id.parse::<ProcessId>().ok())
+            .context("Handed off channel is missing its shell")?;
+        let persist = resources.contains_key("session");
+        let proc = Self::command(persist)
+            .attach(self.channel.clone(), id)
+            .await
+            .with_context(|| format!("Failed to attach to process {id}"))?;
+
+        if let Some(size) = pty_size() {
+            if let Err(x) = proc.resize(size).await {
+                error!("Failed to resize remote process: {}", x);
+            }
+        }
+
+        self.run(proc, poll_input, clipboard, escape_char).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub async fn spawn(
         mut self,
         cmd: impl Into<Option<String>>,
         mut environment: Environment,
         current_dir: Option<PathBuf>,
-        max_chunk_size: usize,
+        poll_input: bool,
+        clipboard: ClipboardMode,
+        escape_char: EscapeChar,
     ) -> CliResult {
         // Automatically add TERM=xterm-256color if not specified
         if !environment.contains_key("TERM") {
@@ -40,7 +259,7 @@ impl Shell {
             Some(cmd) => cmd,
             None => {
                 let system_info = self
-                    .0
+                    .channel
                     .system_info()
                     .await
                     .context("Failed to detect remote operating system")?;
@@ -57,17 +276,40 @@ impl Shell {
             }
         };
 
-        let mut proc = RemoteCommand::new()
+        let mut proc = Self::command(self.detach_on_exit)
             .environment(environment)
-            .pty(
-                terminal_size()
-                    .map(|(Width(cols), Height(rows))| PtySize::from_rows_and_cols(rows, cols)),
-            )
             .current_dir(current_dir)
-            .spawn(self.0, &cmd)
+            .limits(self.limits.clone())
+            .kill_tree(self.kill_tree)
+            .user(self.user.clone())
+            .spawn(self.channel.clone(), &cmd)
             .await
             .with_context(|| format!("Failed to spawn {cmd}"))?;
 
+        // Only a freshly spawned shell gets the initial command, as an attached one already ran it
+        if let Some(init_cmd) = self.init_cmd.take() {
+            proc.stdin
+                .as_mut()
+                .context("Spawned shell has no stdin")?
+                .write_str(format!("{init_cmd}\n"))
+                .await
+                .with_context(|| format!("Failed to send {init_cmd:?} to {cmd}"))?;
+        }
+
+        self.run(proc, poll_input, clipboard, escape_char).await
+    }
+
+    /// Forwards our own terminal to `proc` until it exits or the user disconnects
+    async fn run(
+        mut self,
+        mut proc: RemoteProcess,
+        poll_input: bool,
+        clipboard: ClipboardMode,
+        escape_char: EscapeChar,
+    ) -> CliResult {
+        // Processes with a session are left running for later rather than killed
+        let session = proc.session();
+
         // Create a new terminal in raw mode
         let mut terminal = new_terminal(
             Capabilities::new_from_env().context("Failed to load terminal capabilities")?,
@@ -75,57 +317,174 @@ impl Shell {
         .context("Failed to create terminal")?;
         terminal.set_raw_mode().context("Failed to set raw mode")?;
 
-        let mut stdin = proc.stdin.take().unwrap();
-        let resizer = proc.clone_resizer();
-        tokio::spawn(async move {
-            while let Ok(input) = terminal.poll_input(Some(Duration::new(0, 0))) {
-                match input {
-                    Some(InputEvent::Key(ev)) => {
-                        if let Ok(input) = ev.key.encode(
-                            ev.modifiers,
-                            KeyCodeEncodeModes {
-                                encoding: KeyboardEncoding::Xterm,
-                                application_cursor_keys: false,
-                                newline_mode: false,
-                                modify_other_keys: None,
-                            },
-                            /* is_down */ true,
-                        ) {
-                            if let Err(x) = stdin.write_str(input).await {
-                                error!("Failed to write to stdin of remote process: {}", x);
-                                break;
-                            }
-                        }
+        // Raw mode has our terminal report all mouse events, which we leave off until the remote
+        // process asks for them so that the mouse can still be used to select text locally
+        let _ = echo("\x1b[?1003l");
+
+        if let Some(title) = self.title.as_deref() {
+            let _ = echo(&format!("{}{}", title::SAVE_TITLE, title::set_title(title)));
+        }
+        let modes = Arc::new(Mutex::new(TerminalModes::default()));
+
+        let clipboard = match clipboard {
+            ClipboardMode::Off => None,
+            mode => {
+                let found = Clipboard::find();
+                if found.is_none() {
+                    debug!("No clipboard command found, so leaving OSC 52 to the terminal");
+                }
+                found.map(|clipboard| (clipboard, mode))
+            }
+        };
+
+        // Either wait on terminal input from a dedicated thread, or (for terminals where blocking
+        // reads misbehave) periodically check for input from it, sending the input to whichever
+        // remote process we are attached to at the time
+        let (tx, mut input) = mpsc::channel(1);
+        let input_reader = InputReader::spawn(terminal, tx, poll_input);
+
+        let disconnect = Arc::new(Notify::new());
+        let handoff = Arc::new(Notify::new());
+        let mut forwarder = InputForwarder {
+            stdin: proc.stdin.take().unwrap(),
+            resizer: proc.clone_resizer(),
+            killer: session.is_none().then(|| proc.clone_killer()),
+            escape: escape_char.0.map(EscapeReader::new),
+            disconnect: Arc::clone(&disconnect),
+            handoff: Arc::clone(&handoff),
+            modes: Arc::clone(&modes),
+            encoder: MouseEncoder::default(),
+        };
+
+        let mut token = None;
+        let status = loop {
+            let id = proc.id();
+
+            // Map the remote shell's stdout/stderr to our own process
+            let mut link = RemoteProcessLink::new();
+            link.spawn(forward_stdout(
+                proc.stdout.take().unwrap(),
+                forwarder.stdin.clone(),
+                clipboard.clone(),
+                ModeFilter::new(Arc::clone(&modes)),
+            ));
+            link.spawn(forward_stderr(proc.stderr.take().unwrap()));
+
+            // Disconnecting stops waiting on the remote process, as it may never report back
+            let mut wait = Box::pin(proc.wait());
+            let detached = tokio::select! {
+                status = &mut wait => Detached::Exited(status),
+                x = forwarder.forward_all(&mut input) => Detached::InputFailed(x),
+                _ = disconnect.notified() => Detached::Disconnected,
+                _ = handoff.notified() => Detached::HandedOff,
+                _ = watch_connection(self.watcher.clone()) => Detached::Lost,
+            };
+            link.shutdown().await;
+
+            if let Detached::HandedOff = detached {
+                match self.handoff(id, session).await {
+                    Ok(x) => {
+                        token = Some(x);
+                        break None;
                     }
-                    Some(InputEvent::Resized { cols, rows }) => {
-                        if let Err(x) = resizer
-                            .resize(PtySize::from_rows_and_cols(rows as u16, cols as u16))
-                            .await
-                        {
-                            error!("Failed to resize remote process: {}", x);
-                            break;
+                    Err(x) => break Some(Err(x)),
+                }
+            }
+
+            let lost = || {
+                io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    "Lost connection to server",
+                )
+            };
+
+            let reconnect = match self.reconnect.as_mut() {
+                Some(reconnect) => reconnect,
+
+                // Without a way to reconnect, a failure to send input leaves us waiting on the
+                // remote process as before
+                None => match detached {
+                    Detached::InputFailed(_) => break Some(wait.await),
+                    Detached::Exited(status) => break Some(status),
+                    Detached::Disconnected | Detached::HandedOff => break None,
+                    Detached::Lost => break Some(Err(lost())),
+                },
+            };
+
+            match detached {
+                Detached::Exited(Ok(status)) => break Some(Ok(status)),
+                Detached::Exited(Err(x)) | Detached::InputFailed(x) => {
+                    debug!("Lost connection to process {id}: {x}");
+                }
+                Detached::Disconnected | Detached::HandedOff => break None,
+                Detached::Lost => break Some(Err(lost())),
+            }
+            drop(wait);
+
+            let _ = echo("\r\n[Connection lost, reconnecting...]\r\n");
+            tokio::select! {
+                result = reattach(reconnect, id, session.is_some()) => match result {
+                    Ok((new_proc, watcher, handoff)) => {
+                        proc = new_proc;
+                        self.watcher = Some(watcher);
+                        if self.handoff.is_some() {
+                            self.handoff = Some(handoff);
                         }
                     }
-                    Some(_) => continue,
-                    None => tokio::time::sleep(Duration::from_millis(1)).await,
+                    Err(x) => break Some(Err(x)),
+                },
+                _ = forwarder.wait_for_disconnect(&mut input) => break None,
+            }
+            let _ = echo("[Reconnected]\r\n");
+
+            forwarder.stdin = proc.stdin.take().unwrap();
+            forwarder.resizer = proc.clone_resizer();
+            forwarder.killer = session.is_none().then(|| proc.clone_killer());
+
+            // Catch the remote process up on any change to the size of our terminal made while
+            // we were away from it
+            if let Some(size) = pty_size() {
+                if let Err(x) = forwarder.resizer.resize(size).await {
+                    error!("Failed to resize remote process: {}", x);
                 }
             }
-        });
+        };
 
-        // Now, map the remote shell's stdout/stderr to our own process,
-        // while stdin is handled by the task above
-        let link = RemoteProcessLink::from_remote_pipes(
-            None,
-            proc.stdout.take().unwrap(),
-            proc.stderr.take().unwrap(),
-            max_chunk_size,
-        );
+        // Stop reading terminal input now that nothing consumes it
+        drop(input_reader);
 
-        // Continually loop to check for terminal resize changes while the process is still running
-        let status = proc.wait().await.context("Failed to wait for process")?;
+        if self.title.is_some() {
+            let _ = echo(title::RESTORE_TITLE);
+        }
 
-        // Shut down our link
-        link.shutdown().await;
+        if let Some(token) = token {
+            eprintln!("Handed off shell (claim with `{}`)", claim_cmd(&token));
+            return Ok(());
+        }
+
+        let status = match (status, session) {
+            (None, Some(session)) => {
+                eprintln!(
+                    "Detached from session {session} (attach with `{}`)",
+                    attach_cmd(session)
+                );
+                return Ok(());
+            }
+            (None, None) => {
+                eprintln!("Disconnected from shell");
+                return Err(CliError::FAILURE);
+            }
+            (Some(Err(x)), Some(session)) if x.kind() != io::ErrorKind::NotFound => {
+                eprintln!(
+                    "Lost connection to session {session} (attach with `{}`)",
+                    attach_cmd(session)
+                );
+                return Err(CliError::Error(
+                    anyhow::Error::new(x).context("Failed to wait for process"),
+                ));
+            }
+            (Some(status), _) => status.context("Failed to wait for process")?,
+        };
 
         if !status.success {
             if let Some(code) = status.code {
@@ -137,4 +496,420 @@ impl Shell {
 
         Ok(())
     }
+
+    /// Hands off the channel of the shell, passing along what another shell needs to pick up the
+    /// remote process with `id` (and `session` if it persists), and returning the token with
+    /// which to claim it
+    async fn handoff(&mut self, id: ProcessId, session: Option<SessionId>) -> io::Result<String> {
+        let Some(handoff) = self.handoff.as_mut() else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Shell cannot be handed off",
+            ));
+        };
+
+        let mut resources = Map::new();
+        resources.insert("process".to_string(), id.to_string());
+        if let Some(session) = session {
+            resources.insert("session".to_string(), session.to_string());
+        }
+
+        handoff
+            .handoff(resources)
+            .await
+            .map_err(|x| io::Error::new(io::ErrorKind::Other, x))
+    }
+}
+
+/// Returns the command that claims the shell handed off with `token`
+fn claim_cmd(token: &str) -> String {
+    format!("distant shell --claim {token}")
+}
+
+/// Returns the command that attaches to the shell with `session` again
+fn attach_cmd(session: SessionId) -> String {
+    format!("distant shell --attach {session}")
+}
+
+/// Returns the size of our terminal, if it has one
+fn pty_size() -> Option<PtySize> {
+    terminal_size().map(|(Width(cols), Height(rows))| PtySize::from_rows_and_cols(rows, cols))
+}
+
+/// Shows a banner each time the connection watched by `watcher` is lost or restored, returning
+/// once the connection is lost for good (and never returning without a watcher)
+async fn watch_connection(watcher: Option<ConnectionWatcher>) {
+    let Some(mut watcher) = watcher else {
+        return std::future::pending().await;
+    };
+
+    while let Some(state) = watcher.next().await {
+        match state {
+            ConnectionState::Reconnecting => {
+                let _ = echo("\r\n[Connection to server lost, waiting for it to come back...]\r\n");
+            }
+            ConnectionState::Connected => {
+                let _ = echo("[Connection to server restored]\r\n");
+            }
+            ConnectionState::Disconnected => {
+                let _ = echo("\r\n[Connection to server lost]\r\n");
+                return;
+            }
+        }
+    }
+
+    // Without a channel reporting changes, there is nothing more to watch
+    std::future::pending().await
+}
+
+/// Attaches to the process with `id` (spawned with a session if `persist`) over channels from
+/// `reconnect`, trying again with a growing delay until attached or until [`RECONNECT_TIMEOUT`]
+/// passes, returning the process along with a watcher over the connection behind its channel and
+/// a handle to hand off the channel
+async fn reattach(
+    reconnect: &mut Reconnect,
+    id: ProcessId,
+    persist: bool,
+) -> io::Result<(RemoteProcess, ConnectionWatcher, ChannelHandoff)> {
+    let started = Instant::now();
+    let mut delay = INITIAL_RECONNECT_DELAY;
+    loop {
+        let result = match reconnect().await {
+            Ok(channel) => {
+                let watcher = channel.clone_connection_watcher();
+                let handoff = channel.clone_handoff();
+                Shell::command(persist)
+                    .attach(channel.into_client().into_channel(), id)
+                    .await
+                    .map(|proc| (proc, watcher, handoff))
+            }
+            Err(x) => Err(x),
+        };
+
+        match result {
+            Ok(proc) => return Ok(proc),
+
+            // Process is no longer around (having exited while we were away), so there is nothing
+            // to reattach to
+            Err(x) if x.kind() == io::ErrorKind::NotFound => return Err(x),
+            Err(x) if started.elapsed() + delay >= RECONNECT_TIMEOUT => return Err(x),
+            Err(x) => {
+                debug!("Failed to reattach to process {id}: {x}");
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        }
+    }
+}
+
+/// Forwards the stdout of the remote process to our own, keeping track of the terminal modes that
+/// it turns on and (if given a clipboard) setting the local clipboard in place of the OSC 52
+/// sequences within it, answering queries for the clipboard if permitted
+async fn forward_stdout(
+    mut stdout: RemoteStdout,
+    mut stdin: RemoteStdin,
+    clipboard: Option<(Clipboard, ClipboardMode)>,
+    mut modes: ModeFilter,
+) -> io::Result<()> {
+    let handle = io::stdout();
+    let mut filter = Osc52Filter::default();
+    loop {
+        let output = modes.filter(&stdout.read().await?);
+        let Some((clipboard, mode)) = clipboard.as_ref() else {
+            let mut out = handle.lock();
+            out.write_all(&output)?;
+            out.flush()?;
+            continue;
+        };
+
+        let (output, commands) = filter.filter(&output);
+        {
+            let mut out = handle.lock();
+            out.write_all(&output)?;
+            out.flush()?;
+        }
+
+        for command in commands {
+            match command {
+                OperatingSystemCommand::SetSelection(_, text) => {
+                    if let Err(x) = clipboard.set(&text).await {
+                        error!("Failed to set clipboard: {x}");
+                    }
+                }
+                OperatingSystemCommand::ClearSelection(_) => {
+                    if let Err(x) = clipboard.set("").await {
+                        error!("Failed to clear clipboard: {x}");
+                    }
+                }
+                OperatingSystemCommand::QuerySelection(selection) => {
+                    if *mode != ClipboardMode::ReadWrite {
+                        debug!("Ignoring query for clipboard as reading it is not permitted");
+                        continue;
+                    }
+
+                    match clipboard.get().await {
+                        Ok(text) => {
+                            let reply = OperatingSystemCommand::SetSelection(selection, text);
+                            stdin.write_str(reply.to_string()).await?;
+                        }
+                        Err(x) => error!("Failed to read clipboard: {x}"),
+                    }
+                }
+                x => debug!("Ignoring unsupported OSC 52 sequence: {x:?}"),
+            }
+        }
+    }
+}
+
+/// Forwards the stderr of the remote process to our own
+async fn forward_stderr(mut stderr: RemoteStderr) -> io::Result<()> {
+    let handle = io::stderr();
+    loop {
+        let output = stderr.read().await?;
+        let mut out = handle.lock();
+        out.write_all(&output)?;
+        out.flush()?;
+    }
+}
+
+/// Forwards terminal input to the remote process, acting on any escape sequences typed locally
+struct InputForwarder {
+    stdin: RemoteStdin,
+    resizer: RemoteProcessResizer,
+    killer: Option<RemoteProcessKiller>,
+    escape: Option<EscapeReader>,
+    disconnect: Arc<Notify>,
+    handoff: Arc<Notify>,
+    modes: Arc<Mutex<TerminalModes>>,
+    encoder: MouseEncoder,
+}
+
+impl InputForwarder {
+    /// Forwards terminal input to the remote process until forwarding fails, returning the error
+    async fn forward_all(&mut self, input: &mut mpsc::Receiver<InputEvent>) -> io::Error {
+        while let Some(ev) = input.recv().await {
+            if let Err(x) = self.forward(ev).await {
+                return x;
+            }
+        }
+
+        // Without terminal input, there is nothing left to forward
+        std::future::pending().await
+    }
+
+    /// Reads terminal input while there is no remote process to send it to, acting on escape
+    /// sequences and dropping everything else, until the user disconnects
+    async fn wait_for_disconnect(&mut self, input: &mut mpsc::Receiver<InputEvent>) {
+        while let Some(ev) = input.recv().await {
+            let InputEvent::Key(ev) = ev else { continue };
+            let Some(escape) = self.escape.as_mut() else {
+                continue;
+            };
+
+            for action in escape.read(ev) {
+                let result = match action {
+                    EscapeAction::Forward(_) => Ok(()),
+                    EscapeAction::Disconnect | EscapeAction::Command(EscapeCommand::Disconnect) => {
+                        return
+                    }
+                    EscapeAction::Command(EscapeCommand::Handoff) => {
+                        echo("Nothing to hand off while reconnecting\r\n")
+                    }
+                    action => self.act(action).await,
+                };
+                if let Err(x) = result {
+                    error!("Failed to handle escape sequence: {}", x);
+                }
+            }
+        }
+
+        std::future::pending().await
+    }
+
+    /// Forwards a single terminal input event to the remote process
+    async fn forward(&mut self, input: InputEvent) -> io::Result<()> {
+        match input {
+            InputEvent::Key(ev) => {
+                let actions = match self.escape.as_mut() {
+                    Some(escape) => escape.read(ev),
+                    None => vec![EscapeAction::Forward(ev)],
+                };
+
+                for action in actions {
+                    self.act(action).await?;
+                }
+            }
+            InputEvent::Resized { cols, rows } => {
+                if let Err(x) = self
+                    .resizer
+                    .resize(PtySize::from_rows_and_cols(rows as u16, cols as u16))
+                    .await
+                {
+                    error!("Failed to resize remote process: {}", x);
+                    return Err(x);
+                }
+            }
+            InputEvent::Mouse(ev) => self.write_mouse(ev).await?,
+
+            // NOTE: Pasted text is sent as is rather than read for escape sequences, as those are
+            //       only meant to be typed
+            InputEvent::Paste(text) => {
+                let input = self.modes.lock().unwrap().encode_paste(&text);
+                if let Err(x) = self.stdin.write_str(input).await {
+                    error!("Failed to write to stdin of remote process: {}", x);
+                    return Err(x);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    async fn act(&mut self, action: EscapeAction) -> io::Result<()> {
+        match action {
+            EscapeAction::Forward(ev) => self.write_key(ev).await?,
+            EscapeAction::Echo(text) => echo(&text)?,
+            EscapeAction::Help => {
+                let help = self.escape.as_ref().map(EscapeReader::help);
+                echo(&format!("{}\r\n", help.unwrap_or_default()))?;
+            }
+            EscapeAction::Disconnect | EscapeAction::Command(EscapeCommand::Disconnect) => {
+                // Try to stop the remote process (unless it is being left running), but give up
+                // rather than hang with it
+                if let Some(killer) = self.killer.as_mut() {
+                    let _ = tokio::time::timeout(DISCONNECT_KILL_TIMEOUT, killer.kill()).await;
+                }
+                self.disconnect.notify_one();
+            }
+            EscapeAction::Command(EscapeCommand::Handoff) => self.handoff.notify_one(),
+            EscapeAction::Command(EscapeCommand::Help) => {
+                echo(&format!("{}\r\n", EscapeCommand::help()))?;
+            }
+            EscapeAction::Command(EscapeCommand::Local(cmd)) => {
+                let output = tokio::task::spawn_blocking(move || escape::run_local_command(&cmd))
+                    .await
+                    .map_err(|x| io::Error::new(io::ErrorKind::Other, x))?;
+                match output {
+                    Ok(output) => echo(&output)?,
+                    Err(x) => echo(&format!("Failed to run command: {x}\r\n"))?,
+                }
+            }
+            EscapeAction::Command(EscapeCommand::Unknown(cmd)) => {
+                echo(&format!("Unknown command: {cmd} (see help)\r\n"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn write_mouse(&mut self, mut ev: MouseEvent) -> io::Result<()> {
+        // NOTE: Mouse events read from the Windows console are numbered from 0 rather than 1
+        if cfg!(windows) {
+            ev.x += 1;
+            ev.y += 1;
+        }
+
+        let mode = self.modes.lock().unwrap().mouse;
+        if let Some(input) = self.encoder.encode(mode, &ev) {
+            if let Err(x) = self.stdin.write(input).await {
+                error!("Failed to write to stdin of remote process: {}", x);
+                return Err(x);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn write_key(&mut self, ev: KeyEvent) -> io::Result<()> {
+        if let Ok(input) = ev.key.encode(
+            ev.modifiers,
+            KeyCodeEncodeModes {
+                encoding: KeyboardEncoding::Xterm,
+                application_cursor_keys: false,
+                newline_mode: false,
+                modify_other_keys: None,
+            },
+            /* is_down */ true,
+        ) {
+            if let Err(x) = self.stdin.write_str(input).await {
+                error!("Failed to write to stdin of remote process: {}", x);
+                return Err(x);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes text for the user to our own stdout, outside of anything sent by the remote process
+fn echo(text: &str) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    out.write_all(text.as_bytes())?;
+    out.flush()
+}
+
+/// Reads terminal input on a dedicated thread that (unless polling) blocks until input is
+/// available, so no time is spent checking for input while the terminal is idle
+///
+/// Dropping the reader wakes and stops the thread, waiting for it to drop the terminal so that
+/// its original mode is restored.
+struct InputReader {
+    stop: Arc<AtomicBool>,
+    waker: TerminalWaker,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl InputReader {
+    /// Spawns a thread that takes ownership of `terminal`, sending each input event to `tx`
+    ///
+    /// With `poll`, the thread checks for input periodically rather than blocking on it.
+    fn spawn<T>(mut terminal: T, tx: mpsc::Sender<InputEvent>, poll: bool) -> Self
+    where
+        T: Terminal + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let waker = terminal.waker();
+        let handle = thread::spawn({
+            let stop = Arc::clone(&stop);
+            move || {
+                let wait = if poll { Some(Duration::ZERO) } else { None };
+                while !stop.load(Ordering::Acquire) {
+                    match terminal.poll_input(wait) {
+                        Ok(None) if poll => thread::sleep(INPUT_POLL_INTERVAL),
+                        Ok(Some(InputEvent::Wake)) | Ok(None) => continue,
+                        Ok(Some(input)) => {
+                            if tx.blocking_send(input).is_err() {
+                                break;
+                            }
+                        }
+                        Err(x) => {
+                            error!("Failed to read terminal input: {}", x);
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        Self {
+            stop,
+            waker,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for InputReader {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Err(x) = self.waker.wake() {
+            error!("Failed to wake terminal input thread: {}", x);
+            return;
+        }
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
 }
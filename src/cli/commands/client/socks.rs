@@ -0,0 +1,178 @@
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Version of the SOCKS protocol that is supported
+const VERSION: u8 = 5;
+
+/// Authentication method where no authentication is performed
+const NO_AUTH: u8 = 0x00;
+
+/// Response to a greeting when none of the offered authentication methods are acceptable
+const NO_ACCEPTABLE_METHODS: u8 = 0xff;
+
+/// Command asking to connect to a host and port, which is the only command supported
+const CMD_CONNECT: u8 = 0x01;
+
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+/// Outcome of a request made by a SOCKS5 client, as sent back to it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Reply {
+    Succeeded = 0x00,
+    GeneralFailure = 0x01,
+    ConnectionRefused = 0x05,
+    CommandNotSupported = 0x07,
+    AddressTypeNotSupported = 0x08,
+}
+
+/// Performs the server side of a SOCKS5 handshake (RFC 1928) over `stream`, returning the host
+/// and port that the client asked to connect to, which must then be answered with [`reply`].
+///
+/// Only the `CONNECT` command without authentication is supported, and any other request is
+/// answered and failed here.
+pub async fn handshake<S>(stream: &mut S) -> io::Result<(String, u16)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Greeting of version, number of methods, and the methods themselves
+    let mut header = [0; 2];
+    stream.read_exact(&mut header).await?;
+    check_version(header[0])?;
+    let mut methods = vec![0; header[1] as usize];
+    stream.read_exact(&mut methods).await?;
+
+    if !methods.contains(&NO_AUTH) {
+        stream.write_all(&[VERSION, NO_ACCEPTABLE_METHODS]).await?;
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "SOCKS client requires authentication",
+        ));
+    }
+    stream.write_all(&[VERSION, NO_AUTH]).await?;
+
+    // Request of version, command, reserved byte, and address type
+    let mut request = [0; 4];
+    stream.read_exact(&mut request).await?;
+    check_version(request[0])?;
+
+    let host = match request[3] {
+        ATYP_IPV4 => {
+            let mut addr = [0; 4];
+            stream.read_exact(&mut addr).await?;
+            Ipv4Addr::from(addr).to_string()
+        }
+        ATYP_DOMAIN => {
+            let len = stream.read_u8().await?;
+            let mut domain = vec![0; len as usize];
+            stream.read_exact(&mut domain).await?;
+            String::from_utf8(domain).map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?
+        }
+        ATYP_IPV6 => {
+            let mut addr = [0; 16];
+            stream.read_exact(&mut addr).await?;
+            Ipv6Addr::from(addr).to_string()
+        }
+        atyp => {
+            reply(stream, Reply::AddressTypeNotSupported).await?;
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Unsupported SOCKS address type {atyp}"),
+            ));
+        }
+    };
+    let port = stream.read_u16().await?;
+
+    if request[1] != CMD_CONNECT {
+        reply(stream, Reply::CommandNotSupported).await?;
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Unsupported SOCKS command {}", request[1]),
+        ));
+    }
+
+    Ok((host, port))
+}
+
+/// Answers the request read by [`handshake`] with `reply`, after which data is relayed as is when
+/// the request succeeded
+pub async fn reply<S>(stream: &mut S, reply: Reply) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    // The address bound by the server is not meaningful when tunneling, so it is left empty
+    stream
+        .write_all(&[VERSION, reply as u8, 0, ATYP_IPV4, 0, 0, 0, 0, 0, 0])
+        .await
+}
+
+fn check_version(version: u8) -> io::Result<()> {
+    if version != VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unsupported SOCKS version {version}"),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test(tokio::test)]
+    async fn handshake_should_return_domain_and_port_requested() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+
+        let mut request = vec![
+            VERSION,
+            1,
+            NO_AUTH,
+            VERSION,
+            CMD_CONNECT,
+            0,
+            ATYP_DOMAIN,
+            11,
+        ];
+        request.extend_from_slice(b"db.internal");
+        request.extend_from_slice(&5432u16.to_be_bytes());
+        client.write_all(&request).await.unwrap();
+
+        let (host, port) = handshake(&mut server).await.unwrap();
+        assert_eq!((host.as_str(), port), ("db.internal", 5432));
+        reply(&mut server, Reply::Succeeded).await.unwrap();
+
+        let mut response = [0; 12];
+        client.read_exact(&mut response).await.unwrap();
+        assert_eq!(
+            response,
+            [VERSION, NO_AUTH, VERSION, 0, 0, ATYP_IPV4, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test(tokio::test)]
+    async fn handshake_should_fail_if_command_is_not_connect() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+
+        // BIND to 127.0.0.1:80
+        client
+            .write_all(&[
+                VERSION, 1, NO_AUTH, VERSION, 0x02, 0, ATYP_IPV4, 127, 0, 0, 1, 0, 80,
+            ])
+            .await
+            .unwrap();
+
+        assert!(handshake(&mut server).await.is_err());
+
+        let mut response = [0; 4];
+        client.read_exact(&mut response).await.unwrap();
+        assert_eq!(
+            response,
+            [VERSION, NO_AUTH, VERSION, Reply::CommandNotSupported as u8]
+        );
+    }
+}
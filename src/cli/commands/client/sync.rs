@@ -0,0 +1,724 @@
+use super::copy::{download_file, report_skip, upload_file};
+use crate::options::{ConflictStrategy, Format, TransferPath};
+use anyhow::Context;
+use distant_core::data::{Change, ChangeKindSet, DirReadOptions, FileType};
+use distant_core::{DistantChannel, DistantChannelExt, Watcher};
+use log::*;
+use notify::{RecursiveMode, Watcher as _};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tokio::sync::mpsc;
+
+/// Which way changes are mirrored between the local and remote directories
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Direction {
+    Upload,
+    Download,
+    Both,
+}
+
+impl Direction {
+    /// Returns true if changes made on `side` are mirrored to the other side
+    fn mirrors_from(self, side: Side) -> bool {
+        match self {
+            Self::Upload => side == Side::Local,
+            Self::Download => side == Side::Remote,
+            Self::Both => true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Side {
+    Local,
+    Remote,
+}
+
+impl Side {
+    fn other(self) -> Self {
+        match self {
+            Self::Local => Self::Remote,
+            Self::Remote => Self::Local,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Remote => "remote",
+        }
+    }
+}
+
+/// State of a path on one side of the sync, used to tell whether it has changed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Stamp {
+    Dir,
+    File {
+        len: u64,
+
+        /// Last time (in milliseconds since the unix epoch) that the file was modified
+        modified: Option<u128>,
+    },
+
+    /// Fifo, socket, or device, which is never synced as it has no contents to transfer
+    Special(FileType),
+}
+
+impl Stamp {
+    fn modified(self) -> Option<u128> {
+        match self {
+            Self::Dir | Self::Special(_) => None,
+            Self::File { modified, .. } => modified,
+        }
+    }
+}
+
+/// State of a path on the local and remote sides, where none means the path does not exist
+type Stamps = (Option<Stamp>, Option<Stamp>);
+
+/// Mirrors a directory between the local machine and a remote machine
+pub struct Syncer {
+    channel: DistantChannel,
+    format: Format,
+    direction: Direction,
+    conflict: ConflictStrategy,
+    local_root: PathBuf,
+    remote_root: PathBuf,
+
+    /// If true, changes are reported without being made
+    dry_run: bool,
+
+    /// State of each path on both sides as of the last time that it was synced, which lets us
+    /// tell apart new changes from the ones that we made ourselves
+    known: HashMap<PathBuf, Stamps>,
+}
+
+impl Syncer {
+    /// Prepares to sync `src` to `dst`, where exactly one of them must be remote, creating `dst`
+    /// if it does not exist unless `dry_run` is true
+    pub async fn new(
+        mut channel: DistantChannel,
+        format: Format,
+        src: TransferPath,
+        dst: TransferPath,
+        bidirectional: bool,
+        conflict: ConflictStrategy,
+        dry_run: bool,
+    ) -> anyhow::Result<Self> {
+        let (local_root, remote_root, direction) = match (src, dst) {
+            (TransferPath::Local(src), TransferPath::Remote(dst)) => (src, dst, Direction::Upload),
+            (TransferPath::Remote(src), TransferPath::Local(dst)) => {
+                (dst, src, Direction::Download)
+            }
+            (TransferPath::Local(_), TransferPath::Local(_)) => anyhow::bail!(
+                "Neither path is remote! Prefix the remote path with a colon (e.g. :/tmp/project)"
+            ),
+            (TransferPath::Remote(_), TransferPath::Remote(_)) => {
+                anyhow::bail!("Both paths are remote! Only one side of a sync can be remote")
+            }
+        };
+        let direction = if bidirectional {
+            Direction::Both
+        } else {
+            direction
+        };
+
+        // The source must already be a directory, whereas the destination is created if missing
+        let src_side = if direction == Direction::Download {
+            Side::Remote
+        } else {
+            Side::Local
+        };
+        let src_is_dir = match src_side {
+            Side::Local => tokio::fs::metadata(&local_root)
+                .await
+                .map(|x| x.is_dir())
+                .with_context(|| format!("Failed to read metadata of {local_root:?}"))?,
+            Side::Remote => channel
+                .metadata(remote_root.as_path(), false, true)
+                .await
+                .map(|x| x.file_type == FileType::Dir)
+                .with_context(|| format!("Failed to read metadata of {remote_root:?}"))?,
+        };
+        if !src_is_dir {
+            anyhow::bail!("Only directories can be synced! Use `distant cp` to copy files");
+        }
+
+        if !dry_run {
+            tokio::fs::create_dir_all(&local_root)
+                .await
+                .with_context(|| format!("Failed to create directory {local_root:?}"))?;
+            channel
+                .create_dir(remote_root.as_path(), true)
+                .await
+                .with_context(|| format!("Failed to create directory {remote_root:?}"))?;
+        }
+
+        // Changes are reported using canonical paths, so we need the roots to match them, where
+        // a destination that a dry run did not create is left as is
+        let local_root = match tokio::fs::canonicalize(&local_root).await {
+            Ok(path) => path,
+            Err(_) if dry_run => local_root,
+            Err(x) => {
+                return Err(x).with_context(|| format!("Failed to canonicalize {local_root:?}"))
+            }
+        };
+        let remote_root = match channel.metadata(remote_root.as_path(), true, false).await {
+            Ok(metadata) => metadata.canonicalized_path.unwrap_or(remote_root),
+            Err(_) if dry_run => remote_root,
+            Err(x) => {
+                return Err(x).with_context(|| format!("Failed to canonicalize {remote_root:?}"))
+            }
+        };
+
+        Ok(Self {
+            channel,
+            format,
+            direction,
+            conflict,
+            local_root,
+            remote_root,
+            dry_run,
+            known: HashMap::new(),
+        })
+    }
+
+    /// Syncs the directories, continuing to mirror changes as they happen if `watch` is true
+    pub async fn run(mut self, watch: bool) -> anyhow::Result<()> {
+        debug!(
+            "Syncing {:?} with {:?} ({:?})",
+            self.local_root, self.remote_root, self.direction
+        );
+        let stamps = (
+            Some(Stamp::Dir),
+            self.stat(Side::Remote, Path::new("")).await?,
+        );
+        self.sync_tree(Path::new(""), stamps).await?;
+
+        if watch {
+            self.watch().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn watch(&mut self) -> anyhow::Result<()> {
+        // Changes to the local directory are only watched when they need to be mirrored
+        let (tx, mut local_rx) = mpsc::unbounded_channel();
+        let _local_watcher = if self.direction.mirrors_from(Side::Local) {
+            let mut watcher = notify::recommended_watcher(move |res| {
+                let _ = tx.send(res);
+            })
+            .context("Failed to create local watcher")?;
+            watcher
+                .watch(&self.local_root, RecursiveMode::Recursive)
+                .with_context(|| format!("Failed to watch {:?}", self.local_root))?;
+            Some(watcher)
+        } else {
+            None
+        };
+
+        let mut remote_watcher = if self.direction.mirrors_from(Side::Remote) {
+            Some(
+                self.channel
+                    .watch(
+                        self.remote_root.as_path(),
+                        true,
+                        ChangeKindSet::empty(),
+                        ChangeKindSet::empty(),
+                    )
+                    .await
+                    .with_context(|| format!("Failed to watch {:?}", self.remote_root))?,
+            )
+        } else {
+            None
+        };
+
+        debug!("Watching for changes to sync");
+        loop {
+            let paths = tokio::select! {
+                Some(res) = local_rx.recv() => {
+                    let event: notify::Event = res.context("Failed to watch local directory")?;
+                    relative_paths(&self.local_root, event.paths)
+                }
+                change = next_remote_change(&mut remote_watcher) => match change {
+                    Some(change) => relative_paths(&self.remote_root, change.paths),
+                    None => anyhow::bail!("Stopped receiving changes from remote directory"),
+                },
+            };
+
+            // Failing to mirror a single change should not bring down the entire sync
+            for path in paths {
+                if let Err(x) = self.sync_path(&path).await {
+                    error!("Failed to sync {path:?}: {x:#}");
+                    self.report_error(&path, &x);
+                }
+            }
+        }
+    }
+
+    /// Syncs a single path that has been reported as changed, along with everything within it
+    /// if it is a directory
+    async fn sync_path(&mut self, path: &Path) -> anyhow::Result<()> {
+        let stamps = (
+            self.stat(Side::Local, path).await?,
+            self.stat(Side::Remote, path).await?,
+        );
+
+        if is_dir(stamps.0) || is_dir(stamps.1) {
+            self.sync_tree(path, stamps).await
+        } else {
+            self.reconcile(path, stamps).await
+        }
+    }
+
+    /// Syncs the directory at `root` (relative to the roots of the sync) and everything within it
+    async fn sync_tree(&mut self, root: &Path, stamps: Stamps) -> anyhow::Result<()> {
+        if !root.as_os_str().is_empty() {
+            self.reconcile(root, stamps).await?;
+        }
+
+        // Parents sort before their children, so directories are created before what they hold
+        let mut entries: BTreeMap<PathBuf, Stamps> = BTreeMap::new();
+        if is_dir(self.stat(Side::Local, root).await?) {
+            for (path, stamp) in self.scan_local(root).await? {
+                entries.entry(path).or_default().0 = Some(stamp);
+            }
+        }
+        if is_dir(self.stat(Side::Remote, root).await?) {
+            for (path, stamp) in self.scan_remote(root).await? {
+                entries.entry(path).or_default().1 = Some(stamp);
+            }
+        }
+
+        for (path, stamps) in entries {
+            self.reconcile(&path, stamps).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Compares the current state of `path` on both sides against what it was when last synced,
+    /// mirroring whichever side changed
+    async fn reconcile(&mut self, path: &Path, stamps: Stamps) -> anyhow::Result<()> {
+        let known = self.known.get(path).copied().unwrap_or_default();
+        let local_changed = stamps.0 != known.0;
+        let remote_changed = stamps.1 != known.1;
+
+        let from = match (local_changed, remote_changed) {
+            (false, false) => return Ok(()),
+            (true, false) => Side::Local,
+            (false, true) => Side::Remote,
+            (true, true) if in_sync(stamps) => {
+                self.known.insert(path.to_path_buf(), stamps);
+                return Ok(());
+            }
+            (true, true) => match self.resolve_conflict(path, stamps) {
+                Some(side) => side,
+                None => return Ok(()),
+            },
+        };
+
+        if !self.direction.mirrors_from(from) {
+            return Ok(());
+        }
+
+        self.mirror(path, from, stamps).await
+    }
+
+    /// Picks the side of `path` to keep when both sides have changed, or none to leave it alone
+    fn resolve_conflict(&self, path: &Path, (local, remote): Stamps) -> Option<Side> {
+        // Syncing in one direction always favors the source
+        let side = match (self.direction, self.conflict) {
+            (Direction::Upload, _) => return Some(Side::Local),
+            (Direction::Download, _) => return Some(Side::Remote),
+            (Direction::Both, ConflictStrategy::Newest) => {
+                // Missing paths are treated as older than anything that exists
+                let local = local.map(|x| x.modified().unwrap_or_default());
+                let remote = remote.map(|x| x.modified().unwrap_or_default());
+                if remote > local {
+                    Some(Side::Remote)
+                } else {
+                    Some(Side::Local)
+                }
+            }
+            (Direction::Both, ConflictStrategy::Local) => Some(Side::Local),
+            (Direction::Both, ConflictStrategy::Remote) => Some(Side::Remote),
+            (Direction::Both, ConflictStrategy::Skip) => None,
+        };
+
+        self.report_conflict(path, side);
+        side
+    }
+
+    /// Makes `path` on the other side match `path` on the `from` side
+    async fn mirror(&mut self, path: &Path, from: Side, stamps: Stamps) -> anyhow::Result<()> {
+        let to = from.other();
+        let (src, dst) = match from {
+            Side::Local => stamps,
+            Side::Remote => (stamps.1, stamps.0),
+        };
+
+        // Neither side can be written through a fifo, socket, or device, so leave the path alone
+        // (aside from removing one that no longer has a source), remembering it so that it is
+        // only reported once
+        let special = match (src, dst) {
+            (Some(Stamp::Special(file_type)), _) | (Some(_), Some(Stamp::Special(file_type))) => {
+                Some(file_type)
+            }
+            _ => None,
+        };
+        if let Some(file_type) = special {
+            report_skip(self.format, path, file_type);
+            self.known.insert(path.to_path_buf(), stamps);
+            return Ok(());
+        }
+
+        match src {
+            None => {
+                if dst.is_some() {
+                    self.remove(to, path).await?;
+                    self.report("remove", Some(to), path);
+                }
+            }
+            Some(Stamp::Special(_)) => unreachable!("special paths are never mirrored"),
+            Some(Stamp::Dir) => {
+                if let Some(Stamp::File { .. }) = dst {
+                    self.remove(to, path).await?;
+                }
+                if !is_dir(dst) {
+                    self.create_dir(to, path).await?;
+                    self.report("create_dir", Some(to), path);
+                }
+            }
+            Some(Stamp::File { .. }) => {
+                if let Some(Stamp::Dir) = dst {
+                    self.remove(to, path).await?;
+                }
+                let local = self.local_root.join(path);
+                let remote = self.remote_root.join(path);
+                match from {
+                    Side::Local => {
+                        if !self.dry_run {
+                            upload_file(&mut self.channel, &local, &remote, |_| {}).await?;
+                        }
+                        self.report("upload", None, path);
+                    }
+                    Side::Remote => {
+                        if !self.dry_run {
+                            download_file(&mut self.channel, &remote, &local, |_| {}).await?;
+                        }
+                        self.report("download", None, path);
+                    }
+                }
+            }
+        }
+
+        // Remember how both sides look now so that our own changes are not mirrored back
+        if src.is_none() {
+            self.known.retain(|x, _| !x.starts_with(path));
+        } else {
+            let stamps = (
+                self.stat(Side::Local, path).await?,
+                self.stat(Side::Remote, path).await?,
+            );
+            self.known.insert(path.to_path_buf(), stamps);
+        }
+
+        Ok(())
+    }
+
+    async fn stat(&mut self, side: Side, path: &Path) -> anyhow::Result<Option<Stamp>> {
+        match side {
+            Side::Local => {
+                let path = join(&self.local_root, path);
+                match tokio::fs::metadata(&path).await {
+                    Ok(metadata) => Ok(Some(local_stamp(&metadata))),
+                    Err(x) if x.kind() == io::ErrorKind::NotFound => Ok(None),
+                    Err(x) => {
+                        Err(x).with_context(|| format!("Failed to read metadata of {path:?}"))
+                    }
+                }
+            }
+            Side::Remote => {
+                let path = join(&self.remote_root, path);
+                if !self
+                    .channel
+                    .exists(path.as_path())
+                    .await
+                    .with_context(|| format!("Failed to check if {path:?} exists"))?
+                {
+                    return Ok(None);
+                }
+
+                let metadata = self
+                    .channel
+                    .metadata(path.as_path(), false, true)
+                    .await
+                    .with_context(|| format!("Failed to read metadata of {path:?}"))?;
+                Ok(Some(if metadata.file_type == FileType::Dir {
+                    Stamp::Dir
+                } else if metadata.file_type.is_special() {
+                    Stamp::Special(metadata.file_type)
+                } else {
+                    Stamp::File {
+                        len: metadata.len,
+                        modified: metadata.modified,
+                    }
+                }))
+            }
+        }
+    }
+
+    /// Lists the state of everything within the local directory `root`
+    async fn scan_local(&self, root: &Path) -> anyhow::Result<Vec<(PathBuf, Stamp)>> {
+        let mut entries = Vec::new();
+        let mut dirs = vec![root.to_path_buf()];
+
+        while let Some(dir) = dirs.pop() {
+            let path = join(&self.local_root, &dir);
+            let mut read_dir = tokio::fs::read_dir(&path)
+                .await
+                .with_context(|| format!("Failed to read directory {path:?}"))?;
+            while let Some(dir_entry) = read_dir
+                .next_entry()
+                .await
+                .with_context(|| format!("Failed to read directory {path:?}"))?
+            {
+                // Follows symlinks, syncing whatever they point to instead
+                let metadata = tokio::fs::metadata(dir_entry.path())
+                    .await
+                    .with_context(|| {
+                        format!("Failed to read metadata of {:?}", dir_entry.path())
+                    })?;
+
+                let relative = dir.join(dir_entry.file_name());
+                if metadata.is_dir() {
+                    dirs.push(relative.clone());
+                }
+                entries.push((relative, local_stamp(&metadata)));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Lists the state of everything within the remote directory `root`
+    async fn scan_remote(&mut self, root: &Path) -> anyhow::Result<Vec<(PathBuf, Stamp)>> {
+        let path = join(&self.remote_root, root);
+        let (dir_entries, failures) = self
+            .channel
+            .read_dir(
+                path.as_path(),
+                /* depth */ 0,
+                /* absolute */ false,
+                /* canonicalize */ false,
+                /* include_root */ false,
+                DirReadOptions::default(),
+            )
+            .await
+            .with_context(|| format!("Failed to read directory {path:?}"))?;
+
+        if let Some(failure) = failures.first() {
+            anyhow::bail!("Failed to read entries within {path:?}: {failure}");
+        }
+
+        let mut entries = Vec::new();
+        for dir_entry in dir_entries {
+            let relative = root.join(&dir_entry.path);
+            if let Some(stamp) = self.stat(Side::Remote, &relative).await? {
+                entries.push((relative, stamp));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn create_dir(&mut self, side: Side, path: &Path) -> anyhow::Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        match side {
+            Side::Local => {
+                let path = self.local_root.join(path);
+                tokio::fs::create_dir_all(&path)
+                    .await
+                    .with_context(|| format!("Failed to create directory {path:?}"))
+            }
+            Side::Remote => {
+                let path = self.remote_root.join(path);
+                self.channel
+                    .create_dir(path.as_path(), true)
+                    .await
+                    .with_context(|| format!("Failed to create directory {path:?}"))
+            }
+        }
+    }
+
+    async fn remove(&mut self, side: Side, path: &Path) -> anyhow::Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        match side {
+            Side::Local => {
+                let path = self.local_root.join(path);
+                let result = if tokio::fs::metadata(&path).await?.is_dir() {
+                    tokio::fs::remove_dir_all(&path).await
+                } else {
+                    tokio::fs::remove_file(&path).await
+                };
+                result.with_context(|| format!("Failed to remove {path:?}"))
+            }
+            Side::Remote => {
+                let path = self.remote_root.join(path);
+                self.channel
+                    .remove(path.as_path(), true)
+                    .await
+                    .with_context(|| format!("Failed to remove {path:?}"))
+            }
+        }
+    }
+
+    /// Reports a change made to `path`, where `side` is where the change was made if it is not
+    /// already implied by the kind of change
+    fn report(&self, kind: &str, side: Option<Side>, path: &Path) {
+        match self.format {
+            Format::Shell if self.dry_run => match (kind, side) {
+                ("upload", _) => println!("Would upload {}", path.display()),
+                ("download", _) => println!("Would download {}", path.display()),
+                ("create_dir", Some(side)) => {
+                    println!(
+                        "Would create {} directory {}",
+                        side.as_str(),
+                        path.display()
+                    )
+                }
+                ("remove", Some(side)) => {
+                    println!("Would remove {} {}", side.as_str(), path.display())
+                }
+                _ => println!("Would {kind} {}", path.display()),
+            },
+            Format::Shell => match (kind, side) {
+                ("upload", _) => println!("Uploaded {}", path.display()),
+                ("download", _) => println!("Downloaded {}", path.display()),
+                ("create_dir", Some(side)) => {
+                    println!("Created {} directory {}", side.as_str(), path.display())
+                }
+                ("remove", Some(side)) => println!("Removed {} {}", side.as_str(), path.display()),
+                _ => println!("{kind} {}", path.display()),
+            },
+            Format::Json => {
+                let mut event = json!({"type": kind, "path": path});
+                if let Some(side) = side {
+                    event["side"] = json!(side.as_str());
+                }
+                if self.dry_run {
+                    event["dry_run"] = json!(true);
+                }
+                println!("{}", serde_json::to_string(&event).unwrap());
+            }
+        }
+    }
+
+    fn report_conflict(&self, path: &Path, keep: Option<Side>) {
+        match self.format {
+            Format::Shell => match keep {
+                Some(side) => println!(
+                    "Conflict on {}, keeping {} copy",
+                    path.display(),
+                    side.as_str()
+                ),
+                None => println!("Conflict on {}, skipping", path.display()),
+            },
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&json!({
+                    "type": "conflict",
+                    "path": path,
+                    "keep": keep.map(Side::as_str),
+                }))
+                .unwrap()
+            ),
+        }
+    }
+
+    fn report_error(&self, path: &Path, error: &anyhow::Error) {
+        match self.format {
+            Format::Shell => eprintln!("Failed to sync {}: {error:#}", path.display()),
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&json!({
+                    "type": "error",
+                    "path": path,
+                    "description": format!("{error:#}"),
+                }))
+                .unwrap()
+            ),
+        }
+    }
+}
+
+fn is_dir(stamp: Option<Stamp>) -> bool {
+    matches!(stamp, Some(Stamp::Dir))
+}
+
+/// Returns true if both sides already match and need no syncing
+fn in_sync(stamps: Stamps) -> bool {
+    match stamps {
+        (None, None) => true,
+        (Some(Stamp::Dir), Some(Stamp::Dir)) => true,
+        (Some(local), Some(remote)) => local == remote,
+        _ => false,
+    }
+}
+
+fn local_stamp(metadata: &std::fs::Metadata) -> Stamp {
+    let file_type = FileType::from(metadata.file_type());
+    if metadata.is_dir() {
+        Stamp::Dir
+    } else if file_type.is_special() {
+        Stamp::Special(file_type)
+    } else {
+        Stamp::File {
+            len: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|x| x.duration_since(UNIX_EPOCH).ok())
+                .map(|x| x.as_millis()),
+        }
+    }
+}
+
+/// Converts `paths` reported as changed into paths relative to `root`, skipping `root` itself
+/// and anything outside of it
+fn relative_paths(root: &Path, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths
+        .into_iter()
+        .filter_map(|path| path.strip_prefix(root).ok().map(Path::to_path_buf))
+        .filter(|path| !path.as_os_str().is_empty())
+        .collect()
+}
+
+/// Waits for the next change from the remote watcher, or forever if there is no watcher
+async fn next_remote_change(watcher: &mut Option<Watcher>) -> Option<Change> {
+    match watcher {
+        Some(watcher) => watcher.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Joins `relative` onto `root`, avoiding a trailing separator when `relative` is empty
+fn join(root: &Path, relative: &Path) -> PathBuf {
+    if relative.as_os_str().is_empty() {
+        root.to_path_buf()
+    } else {
+        root.join(relative)
+    }
+}
@@ -0,0 +1,46 @@
+use distant_core::net::common::Destination;
+
+/// Saves the title of our terminal onto the terminal's own stack of titles, which is how the title
+/// from before the shell is put back once it exits
+pub const SAVE_TITLE: &str = "\x1b[22;0t";
+
+/// Restores the title of our terminal saved with [`SAVE_TITLE`], undoing whatever title was set
+/// since then by us or by the remote program
+pub const RESTORE_TITLE: &str = "\x1b[23;0t";
+
+/// Returns the title shown for a shell connected to `destination`, such as `distant: user@host`
+pub fn default_title(destination: &Destination) -> String {
+    match destination.username.as_deref() {
+        Some(username) => format!("distant: {username}@{}", destination.host),
+        None => format!("distant: {}", destination.host),
+    }
+}
+
+/// Returns the OSC 2 sequence that sets the title of our terminal to `title`, leaving out any
+/// control characters that would end the sequence early
+///
+/// NOTE: Titles set by the remote program using OSC 0 or 2 are passed through to our terminal as
+///       is, replacing this one until the shell exits
+pub fn set_title(title: &str) -> String {
+    let title: String = title.chars().filter(|c| !c.is_control()).collect();
+    format!("\x1b]2;{title}\x07")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_title_should_include_username_if_destination_has_one() {
+        let destination: Destination = "ssh://user@example.com:22".parse().unwrap();
+        assert_eq!(default_title(&destination), "distant: user@example.com");
+
+        let destination: Destination = "example.com".parse().unwrap();
+        assert_eq!(default_title(&destination), "distant: example.com");
+    }
+
+    #[test]
+    fn set_title_should_remove_control_characters_from_title() {
+        assert_eq!(set_title("a\x07b\x1b]c"), "\x1b]2;ab]c\x07");
+    }
+}
@@ -0,0 +1,277 @@
+use super::browse::draw_line;
+use super::copy::human_bytes;
+use anyhow::Context;
+use distant_core::data::{ProcessStats, SystemStats};
+use distant_core::{DistantChannel, DistantChannelExt};
+use log::*;
+use std::cmp::Ordering;
+use std::time::Duration;
+use termwiz::{
+    caps::Capabilities,
+    cell::{AttributeChange, Intensity},
+    input::{InputEvent, KeyCode, KeyEvent, Modifiers},
+    surface::{Change, CursorVisibility},
+    terminal::{buffered::BufferedTerminal, new_terminal, Terminal},
+};
+
+/// Time to wait between checks for terminal input
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Keys listed in the status line
+const HELP: &str = "c: sort by cpu  m: memory  p: pid  n: name  j/k: scroll  q: quit";
+
+/// Interactive monitor of the resource usage of a remote machine
+pub struct Top {
+    channel: DistantChannel,
+
+    /// Most recent usage reported by the remote machine, with processes in sorted order
+    stats: Option<SystemStats>,
+    sort: Sort,
+
+    /// Index of the first process that is visible
+    offset: usize,
+}
+
+/// Order in which processes are listed
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Sort {
+    Cpu,
+    Memory,
+    Pid,
+    Name,
+}
+
+/// What to do after handling some input
+enum Flow {
+    Continue,
+    Quit,
+}
+
+impl Top {
+    pub fn new(channel: DistantChannel) -> Self {
+        Self {
+            channel,
+            stats: None,
+            sort: Sort::Cpu,
+            offset: 0,
+        }
+    }
+
+    /// Monitors the remote machine, refreshing its usage each time `interval` elapses until the
+    /// user quits
+    pub async fn run(mut self, interval: Duration) -> anyhow::Result<()> {
+        let mut monitor = self
+            .channel
+            .monitor_system(interval)
+            .await
+            .context("Failed to monitor resource usage of remote machine")?;
+
+        let mut terminal = new_terminal(
+            Capabilities::new_from_env().context("Failed to load terminal capabilities")?,
+        )
+        .context("Failed to create terminal")?;
+        terminal.set_raw_mode().context("Failed to set raw mode")?;
+        terminal
+            .enter_alternate_screen()
+            .context("Failed to enter alternate screen")?;
+        let mut terminal = BufferedTerminal::new(terminal).context("Failed to create terminal")?;
+
+        // The terminal restores itself once dropped, even if we fail part way through
+        let mut dirty = true;
+        loop {
+            if terminal.check_for_resize()? || dirty {
+                self.render(&mut terminal)?;
+                dirty = false;
+            }
+
+            match terminal.terminal().poll_input(Some(Duration::ZERO))? {
+                Some(InputEvent::Key(key)) => {
+                    dirty = true;
+                    if let Flow::Quit = self.handle_key(key, &terminal) {
+                        break;
+                    }
+                }
+                Some(InputEvent::Resized { .. }) => dirty = true,
+                Some(_) => {}
+                None => tokio::select! {
+                    stats = monitor.next() => match stats {
+                        Some(stats) => {
+                            self.update(stats);
+                            dirty = true;
+                        }
+                        None => anyhow::bail!("Remote machine stopped reporting resource usage"),
+                    },
+                    _ = tokio::time::sleep(INPUT_POLL_INTERVAL) => {}
+                },
+            }
+        }
+
+        // Otherwise the remote machine keeps collecting usage for as long as it is connected
+        if let Err(x) = monitor.stop().await {
+            error!("Failed to stop monitoring remote machine: {x}");
+        }
+
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, terminal: &BufferedTerminal<impl Terminal>) -> Flow {
+        let page = terminal.dimensions().1.saturating_sub(4).max(1);
+        match (key.key, key.modifiers) {
+            (KeyCode::Char('q') | KeyCode::Escape, _) | (KeyCode::Char('c'), Modifiers::CTRL) => {
+                return Flow::Quit
+            }
+            (KeyCode::Char('c'), _) => self.sort_by(Sort::Cpu),
+            (KeyCode::Char('m'), _) => self.sort_by(Sort::Memory),
+            (KeyCode::Char('p'), _) => self.sort_by(Sort::Pid),
+            (KeyCode::Char('n'), _) => self.sort_by(Sort::Name),
+            (KeyCode::Char('j') | KeyCode::DownArrow, _) => self.scroll_to(self.offset + 1),
+            (KeyCode::Char('k') | KeyCode::UpArrow, _) => {
+                self.scroll_to(self.offset.saturating_sub(1))
+            }
+            (KeyCode::PageDown, _) => self.scroll_to(self.offset + page),
+            (KeyCode::PageUp, _) => self.scroll_to(self.offset.saturating_sub(page)),
+            (KeyCode::Char('g') | KeyCode::Home, _) => self.scroll_to(0),
+            (KeyCode::Char('G') | KeyCode::End, _) => self.scroll_to(usize::MAX),
+            _ => {}
+        }
+
+        Flow::Continue
+    }
+
+    fn update(&mut self, mut stats: SystemStats) {
+        sort_processes(&mut stats.processes, self.sort);
+        self.stats = Some(stats);
+        self.scroll_to(self.offset);
+    }
+
+    fn sort_by(&mut self, sort: Sort) {
+        self.sort = sort;
+        if let Some(stats) = self.stats.as_mut() {
+            sort_processes(&mut stats.processes, sort);
+        }
+    }
+
+    /// Scrolls so that the process at `offset` is the first visible, keeping at least one visible
+    fn scroll_to(&mut self, offset: usize) {
+        let len = self.stats.as_ref().map(|s| s.processes.len()).unwrap_or(0);
+        self.offset = offset.min(len.saturating_sub(1));
+    }
+
+    fn render(&mut self, terminal: &mut BufferedTerminal<impl Terminal>) -> anyhow::Result<()> {
+        let (cols, rows) = terminal.dimensions();
+
+        // Every line is redrawn in full, so only what changed is written out to the terminal
+        terminal.add_change(Change::CursorVisibility(CursorVisibility::Hidden));
+
+        let (summary, usage) = match &self.stats {
+            Some(stats) => (
+                format!(
+                    "up {}, load average: {:.2} {:.2} {:.2}, {} processes",
+                    format_uptime(stats.uptime),
+                    stats.load_average[0],
+                    stats.load_average[1],
+                    stats.load_average[2],
+                    stats.processes.len(),
+                ),
+                format!(
+                    "CPU: {:.1}%  CPUs: {}  Mem: {} / {}  Swap: {} / {}",
+                    stats.cpu_usage,
+                    stats.cpu_count,
+                    human_bytes(stats.memory_used),
+                    human_bytes(stats.memory_total),
+                    human_bytes(stats.swap_used),
+                    human_bytes(stats.swap_total),
+                ),
+            ),
+            None => (String::from("Waiting for resource usage..."), String::new()),
+        };
+        draw_line(
+            terminal,
+            0,
+            0,
+            cols,
+            &summary,
+            AttributeChange::Intensity(Intensity::Bold),
+        );
+        draw_line(
+            terminal,
+            0,
+            1,
+            cols,
+            &usage,
+            AttributeChange::Reverse(false),
+        );
+        draw_line(
+            terminal,
+            0,
+            2,
+            cols,
+            &format!("{:>8} {:>6} {:>10}  NAME", "PID", "%CPU", "MEM"),
+            AttributeChange::Reverse(true),
+        );
+
+        let processes = self
+            .stats
+            .as_ref()
+            .map(|stats| stats.processes.as_slice())
+            .unwrap_or_default();
+        for row in 0..rows.saturating_sub(4) {
+            let line = match processes.get(self.offset + row) {
+                Some(p) => format!(
+                    "{:>8} {:>6.1} {:>10}  {}",
+                    p.pid,
+                    p.cpu_usage,
+                    human_bytes(p.memory),
+                    p.name
+                ),
+                None => String::new(),
+            };
+            draw_line(
+                terminal,
+                0,
+                row + 3,
+                cols,
+                &line,
+                AttributeChange::Reverse(false),
+            );
+        }
+
+        draw_line(
+            terminal,
+            0,
+            rows.saturating_sub(1),
+            cols,
+            HELP,
+            AttributeChange::Reverse(true),
+        );
+
+        terminal.flush()?;
+        Ok(())
+    }
+}
+
+/// Sorts `processes` so that the heaviest users of a resource come first
+fn sort_processes(processes: &mut [ProcessStats], sort: Sort) {
+    processes.sort_by(|a, b| match sort {
+        Sort::Cpu => b
+            .cpu_usage
+            .partial_cmp(&a.cpu_usage)
+            .unwrap_or(Ordering::Equal)
+            .then(a.pid.cmp(&b.pid)),
+        Sort::Memory => b.memory.cmp(&a.memory).then(a.pid.cmp(&b.pid)),
+        Sort::Pid => a.pid.cmp(&b.pid),
+        Sort::Name => a.name.cmp(&b.name).then(a.pid.cmp(&b.pid)),
+    });
+}
+
+/// Formats `seconds` of uptime like `top` does, such as "3 days, 04:05"
+fn format_uptime(seconds: u64) -> String {
+    let days = seconds / 86400;
+    let hours = seconds % 86400 / 3600;
+    let minutes = seconds % 3600 / 60;
+    match days {
+        0 => format!("{hours:02}:{minutes:02}"),
+        1 => format!("1 day, {hours:02}:{minutes:02}"),
+        _ => format!("{days} days, {hours:02}:{minutes:02}"),
+    }
+}
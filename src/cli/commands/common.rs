@@ -1,8 +1,15 @@
 mod buf;
+mod clients;
 mod format;
 mod link;
+mod pager;
+mod procs;
 pub mod stdin;
+pub mod stdout;
 
 pub use buf::*;
+pub use clients::*;
 pub use format::*;
 pub use link::*;
+pub use pager::*;
+pub use procs::*;
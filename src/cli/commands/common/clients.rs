@@ -0,0 +1,39 @@
+use super::OutputPolicy;
+use crate::options::Format;
+use chrono::TimeZone;
+use distant_core::net::{common::ConnectionId, server::ClientInfo};
+use serde_json::json;
+use tabled::Tabled;
+
+/// Renders `clients` of a manager or server as a table, one row per client
+pub fn clients_table(policy: OutputPolicy, clients: Vec<ClientInfo>) -> String {
+    #[derive(Tabled)]
+    struct ClientRow {
+        id: ConnectionId,
+        identity: String,
+        connected: String,
+        address: String,
+    }
+
+    policy.table(
+        clients.into_iter().map(|client| ClientRow {
+            id: client.id,
+            identity: client.identity,
+            connected: chrono::Local
+                .timestamp_opt(client.connected_at as i64, 0)
+                .single()
+                .map(|connected| connected.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+            address: client.address.unwrap_or_default(),
+        }),
+        |table| table,
+    )
+}
+
+/// Prints `clients` of a manager or server in the given `format`
+pub fn print_clients(format: Format, clients: Vec<ClientInfo>) {
+    match format {
+        Format::Json => println!("{}", json!({"type": "clients", "clients": clients})),
+        Format::Shell => println!("{}", clients_table(OutputPolicy::current(), clients)),
+    }
+}
@@ -1,28 +1,106 @@
+use super::{clients_table, procs_table, stdout, Pager};
 use crate::options::Format;
 use distant_core::{
     data::{
-        ChangeKind, DistantMsg, DistantResponseData, Error, FileType, Metadata,
-        SearchQueryContentsMatch, SearchQueryMatch, SearchQueryPathMatch, SystemInfo,
+        ChangeKind, DistantMsg, DistantResponseData, Error, FileType, Metadata, Metrics,
+        SearchQueryContentsMatch, SearchQueryContextLine, SearchQueryMatch, SearchQueryPathMatch,
+        SystemInfo,
     },
     net::common::Response,
 };
 use log::*;
 use std::{
-    collections::HashMap,
     io::{self, Write},
     path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
 };
 use tabled::{object::Rows, style::Style, Alignment, Disable, Modify, Table, Tabled};
 
+/// Whether output meant for people is currently rendered for screen readers
+static ACCESSIBLE: AtomicBool = AtomicBool::new(false);
+
+/// Policy for how output meant for people (rather than programs) is rendered
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputPolicy {
+    /// Tables are drawn with borders and progress is shown as bars redrawn in place
+    #[default]
+    Visual,
+
+    /// Tables are written as labeled lines, one per field, and progress is reported as periodic
+    /// lines of text alongside announcements of when things start and finish, all of which
+    /// screen readers can read out as they arrive
+    Accessible,
+}
+
+impl OutputPolicy {
+    /// Returns the policy used by the program, which is [`OutputPolicy::Visual`] unless another
+    /// has been [set](OutputPolicy::set)
+    pub fn current() -> Self {
+        if ACCESSIBLE.load(Ordering::Relaxed) {
+            Self::Accessible
+        } else {
+            Self::Visual
+        }
+    }
+
+    /// Uses this policy for the rest of the program
+    pub fn set(self) {
+        ACCESSIBLE.store(self.is_accessible(), Ordering::Relaxed);
+    }
+
+    pub fn is_accessible(self) -> bool {
+        matches!(self, Self::Accessible)
+    }
+
+    /// Renders `rows` as a table finished by `style`, or, when accessible, as a block of
+    /// `header: value` lines for each row with a blank line between rows
+    pub fn table<T: Tabled>(
+        self,
+        rows: impl IntoIterator<Item = T>,
+        style: impl FnOnce(&mut Table) -> &mut Table,
+    ) -> String {
+        match self {
+            Self::Visual => style(&mut Table::new(rows)).to_string(),
+            Self::Accessible => {
+                let headers = T::headers();
+                rows.into_iter()
+                    .map(|row| {
+                        headers
+                            .iter()
+                            .zip(row.fields())
+                            .map(|(header, field)| format!("{header}: {field}"))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n\n")
+            }
+        }
+    }
+}
+
+/// Text printed for a search match or line of context, alongside the first and last line
+/// numbers that it covers
+struct SearchLine {
+    first: u64,
+    last: u64,
+    text: String,
+}
+
 #[derive(Default)]
 struct FormatterState {
     /// Last seen path during search
     pub last_searched_path: Option<PathBuf>,
+
+    /// Last line number printed for the last seen path during search
+    pub last_searched_line: Option<u64>,
 }
 
 pub struct Formatter {
     format: Format,
+    policy: OutputPolicy,
     state: FormatterState,
+    pager: Option<Pager<io::Stdout>>,
 }
 
 impl Formatter {
@@ -30,18 +108,49 @@ impl Formatter {
     pub fn new(format: Format) -> Self {
         Self {
             format,
+            policy: OutputPolicy::current(),
             state: Default::default(),
+            pager: None,
         }
     }
 
+    /// Pages printed shell output through a [`Pager`] of stdout, which only pages when paging is
+    /// enabled and the output does not fit on the screen, finishing once the formatter is dropped
+    pub fn paged(mut self) -> Self {
+        if self.format == Format::Shell {
+            self.pager = Some(Pager::stdout());
+        }
+        self
+    }
+
     /// Creates a new [`Formatter`] using [`Format`] of `Format::Shell`
     pub fn shell() -> Self {
         Self::new(Format::Shell)
     }
 
     /// Consumes the output message, printing it based on its configuration
+    ///
+    /// Failing to write the output is only logged, except for stdout having been closed, which
+    /// is returned as an error so that whatever produces the output can stop
     pub fn print(&mut self, res: Response<DistantMsg<DistantResponseData>>) -> io::Result<()> {
-        let output = match self.format {
+        let output = self.format(res)?;
+        let result = match self.pager.as_mut() {
+            Some(pager) => output.write_to(pager, &mut io::stderr().lock()),
+            None => output.write_to(&mut io::stdout().lock(), &mut io::stderr().lock()),
+        };
+        match result {
+            Err(x) if stdout::is_closed(&x) => return Err(x),
+            Err(x) => error!("Failed to write output: {}", x),
+            Ok(()) => (),
+        }
+
+        Ok(())
+    }
+
+    /// Formats the response into the output that [`Formatter::print`] would write, without
+    /// writing it anywhere
+    pub fn format(&mut self, res: Response<DistantMsg<DistantResponseData>>) -> io::Result<Output> {
+        Ok(match self.format {
             Format::Json => Output::StdoutLine(
                 serde_json::to_vec(&res)
                     .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?,
@@ -54,73 +163,68 @@ impl Formatter {
                     "Shell does not support batch responses",
                 ))
             }
-            Format::Shell => format_shell(&mut self.state, res.payload.into_single().unwrap()),
-        };
-
-        match output {
-            Output::Stdout(x) => {
-                // NOTE: Because we are not including a newline in the output,
-                //       it is not guaranteed to be written out. In the case of
-                //       LSP protocol, the JSON content is not followed by a
-                //       newline and was not picked up when the response was
-                //       sent back to the client; so, we need to manually flush
-                if let Err(x) = io::stdout().lock().write_all(&x) {
-                    error!("Failed to write stdout: {}", x);
-                }
-
-                if let Err(x) = io::stdout().lock().flush() {
-                    error!("Failed to flush stdout: {}", x);
-                }
-            }
-            Output::StdoutLine(x) => {
-                if let Err(x) = io::stdout().lock().write_all(&x) {
-                    error!("Failed to write stdout: {}", x);
-                }
-
-                if let Err(x) = io::stdout().lock().write(b"\n") {
-                    error!("Failed to write stdout newline: {}", x);
-                }
-            }
-            Output::Stderr(x) => {
-                // NOTE: Because we are not including a newline in the output,
-                //       it is not guaranteed to be written out. In the case of
-                //       LSP protocol, the JSON content is not followed by a
-                //       newline and was not picked up when the response was
-                //       sent back to the client; so, we need to manually flush
-                if let Err(x) = io::stderr().lock().write_all(&x) {
-                    error!("Failed to write stderr: {}", x);
-                }
-
-                if let Err(x) = io::stderr().lock().flush() {
-                    error!("Failed to flush stderr: {}", x);
-                }
-            }
-            Output::StderrLine(x) => {
-                if let Err(x) = io::stderr().lock().write_all(&x) {
-                    error!("Failed to write stderr: {}", x);
-                }
-
-                if let Err(x) = io::stderr().lock().write(b"\n") {
-                    error!("Failed to write stderr newline: {}", x);
-                }
-            }
-            Output::None => {}
-        }
-
-        Ok(())
+            Format::Shell => format_shell(
+                self.policy,
+                &mut self.state,
+                res.payload.into_single().unwrap(),
+            ),
+        })
     }
 }
 
 /// Represents the output content and destination
-enum Output {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Content written to stdout as is
     Stdout(Vec<u8>),
+
+    /// Content written to stdout followed by a newline
     StdoutLine(Vec<u8>),
+
+    /// Content written to stderr as is
     Stderr(Vec<u8>),
+
+    /// Content written to stderr followed by a newline
     StderrLine(Vec<u8>),
+
+    /// Nothing is written
     None,
 }
 
-fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output {
+impl Output {
+    /// Writes the content to `stdout` or `stderr` based on its destination
+    pub fn write_to(self, stdout: &mut impl Write, stderr: &mut impl Write) -> io::Result<()> {
+        match self {
+            // NOTE: Because we are not including a newline in the output, it is not guaranteed to
+            //       be written out. In the case of LSP protocol, the JSON content is not followed
+            //       by a newline and was not picked up when the response was sent back to the
+            //       client; so, we need to manually flush
+            Self::Stdout(x) => {
+                stdout.write_all(&x)?;
+                stdout.flush()
+            }
+            Self::StdoutLine(x) => {
+                stdout.write_all(&x)?;
+                stdout.write_all(b"\n")
+            }
+            Self::Stderr(x) => {
+                stderr.write_all(&x)?;
+                stderr.flush()
+            }
+            Self::StderrLine(x) => {
+                stderr.write_all(&x)?;
+                stderr.write_all(b"\n")
+            }
+            Self::None => Ok(()),
+        }
+    }
+}
+
+fn format_shell(
+    policy: OutputPolicy,
+    state: &mut FormatterState,
+    data: DistantResponseData,
+) -> Output {
     match data {
         DistantResponseData::Ok => Output::None,
         DistantResponseData::Error(Error { description, .. }) => {
@@ -128,7 +232,8 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
         }
         DistantResponseData::Blob { data } => Output::StdoutLine(data),
         DistantResponseData::Text { data } => Output::StdoutLine(data.into_bytes()),
-        DistantResponseData::DirEntries { entries, .. } => {
+        DistantResponseData::DirEntries { entries, .. }
+        | DistantResponseData::DirEntriesChunk { entries, .. } => {
             #[derive(Tabled)]
             struct EntryRow {
                 ty: String,
@@ -140,6 +245,10 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
                     FileType::Dir => "<DIR>",
                     FileType::File => "",
                     FileType::Symlink => "<SYMLINK>",
+                    FileType::Fifo => "<FIFO>",
+                    FileType::Socket => "<SOCKET>",
+                    FileType::CharDevice => "<CHAR>",
+                    FileType::BlockDevice => "<BLOCK>",
                 }),
                 path: entry.path.to_string_lossy().to_string(),
             }))
@@ -151,6 +260,18 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
 
             Output::Stdout(table)
         }
+        DistantResponseData::Changed(change)
+            if change.kind == ChangeKind::Renamed && change.paths.len() == 2 =>
+        {
+            Output::StdoutLine(
+                format!(
+                    "Following path was renamed:\n* {} -> {}",
+                    change.paths[0].to_string_lossy(),
+                    change.paths[1].to_string_lossy()
+                )
+                .into_bytes(),
+            )
+        }
         DistantResponseData::Changed(change) => Output::StdoutLine(
             format!(
                 "{}{}",
@@ -171,6 +292,70 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
             )
             .into_bytes(),
         ),
+        DistantResponseData::JournalChanges { seq, entries } => Output::Stdout(
+            entries
+                .into_iter()
+                .map(|entry| {
+                    format!(
+                        "{} {} {}\n",
+                        entry.seq,
+                        entry.kind,
+                        entry
+                            .paths
+                            .iter()
+                            .map(|p| p.to_string_lossy())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    )
+                })
+                .chain(std::iter::once(format!("Latest change is {seq}\n")))
+                .collect::<String>()
+                .into_bytes(),
+        ),
+        DistantResponseData::TempCreated { path } => {
+            Output::StdoutLine(path.to_string_lossy().to_string().into_bytes())
+        }
+        DistantResponseData::SnapshotCreated { id } => {
+            Output::StdoutLine(id.to_string().into_bytes())
+        }
+        DistantResponseData::Snapshots { entries } => Output::Stdout(
+            entries
+                .into_iter()
+                .map(|entry| format!("{} {}\n", entry.id, entry.path.to_string_lossy()))
+                .collect::<String>()
+                .into_bytes(),
+        ),
+        DistantResponseData::DirManifestChunk { entries, .. } => Output::Stdout(
+            entries
+                .into_iter()
+                .map(|entry| format!("{}  {}\n", entry.hash, entry.path.to_string_lossy()))
+                .collect::<String>()
+                .into_bytes(),
+        ),
+        DistantResponseData::ContentMissing => Output::StdoutLine(b"Content missing".to_vec()),
+        DistantResponseData::Diff { patch } => Output::Stdout(patch.into_bytes()),
+        DistantResponseData::BatchFailed {
+            step,
+            error: Error { description, .. },
+            rolled_back,
+        } => Output::StderrLine(
+            format!(
+                "Step {step} failed{}: {description}",
+                if rolled_back { " (rolled back)" } else { "" }
+            )
+            .into_bytes(),
+        ),
+        DistantResponseData::Plan { paths } => Output::StdoutLine(
+            format!(
+                "Following paths would be affected:\n{}",
+                paths
+                    .into_iter()
+                    .map(|p| format!("* {}", p.to_string_lossy()))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            )
+            .into_bytes(),
+        ),
         DistantResponseData::Exists { value: exists } => {
             if exists {
                 Output::StdoutLine(b"true".to_vec())
@@ -182,6 +367,7 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
             canonicalized_path,
             file_type,
             len,
+            allocated_len,
             readonly,
             accessed,
             created,
@@ -194,6 +380,7 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
                     "{}",
                     "Type: {}\n",
                     "Len: {}\n",
+                    "{}",
                     "Readonly: {}\n",
                     "Created: {}\n",
                     "Last Accessed: {}\n",
@@ -207,6 +394,9 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
                     .unwrap_or_default(),
                 file_type.as_ref(),
                 len,
+                allocated_len
+                    .map(|len| format!("Allocated Len: {len}\n"))
+                    .unwrap_or_default(),
                 readonly,
                 created.unwrap_or_default(),
                 accessed.unwrap_or_default(),
@@ -281,32 +471,96 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
         DistantResponseData::SearchStarted { id } => {
             Output::StdoutLine(format!("Query {id} started").into_bytes())
         }
-        DistantResponseData::SearchDone { .. } => Output::None,
+        DistantResponseData::SearchDone {
+            skipped, errors, ..
+        } => {
+            use std::fmt::Write;
+            let mut output = String::new();
+
+            for error in errors {
+                writeln!(
+                    &mut output,
+                    "Failed to search {}: {}",
+                    error.path.to_string_lossy(),
+                    error.error.description
+                )
+                .unwrap();
+            }
+
+            let files = |n: u64| if n == 1 { "file" } else { "files" };
+            let mut reasons = Vec::new();
+            if skipped.too_large > 0 {
+                let n = skipped.too_large;
+                reasons.push(format!("{n} {} too large", files(n)));
+            }
+            if skipped.binary > 0 {
+                let n = skipped.binary;
+                reasons.push(format!("{n} binary {}", files(n)));
+            }
+            if !reasons.is_empty() {
+                writeln!(&mut output, "Skipped {}", reasons.join(" and ")).unwrap();
+            }
+
+            if !output.is_empty() {
+                Output::Stderr(output.into_bytes())
+            } else {
+                Output::None
+            }
+        }
         DistantResponseData::SearchResults { matches, .. } => {
-            let mut files: HashMap<_, Vec<String>> = HashMap::new();
+            // Paths are kept in the order that they were first matched
+            let mut files: Vec<(PathBuf, Vec<SearchLine>)> = Vec::new();
             let mut is_targeting_paths = false;
+            let mut has_context = false;
 
             for m in matches {
-                match m {
+                let (path, lines) = match m {
+                    // Create the entry with no lines called out
                     SearchQueryMatch::Path(SearchQueryPathMatch { path, .. }) => {
-                        // Create the entry with no lines called out
-                        files.entry(path).or_default();
                         is_targeting_paths = true;
+                        (path, Vec::new())
                     }
 
+                    // Lines of context are separated from their number by a dash rather than
+                    // a colon like grep does
                     SearchQueryMatch::Contents(SearchQueryContentsMatch {
                         path,
                         lines,
                         line_number,
+                        context,
                         ..
                     }) => {
-                        let file_matches = files.entry(path).or_default();
+                        has_context |= !context.is_empty();
+                        let context_line = |c: SearchQueryContextLine| {
+                            let text = c.line.to_string_lossy();
+                            SearchLine {
+                                first: c.line_number,
+                                last: c.line_number,
+                                text: format!("{}-{}", c.line_number, text.trim_end()),
+                            }
+                        };
 
-                        file_matches.push(format!(
-                            "{line_number}:{}",
-                            lines.to_string_lossy().trim_end()
-                        ));
+                        let text = lines.to_string_lossy();
+                        let text = text.trim_end();
+                        let last_line_number = line_number + text.matches('\n').count() as u64;
+
+                        let (before, after): (Vec<_>, Vec<_>) = context
+                            .into_iter()
+                            .partition(|c| c.line_number < line_number);
+                        let mut lines: Vec<_> = before.into_iter().map(context_line).collect();
+                        lines.push(SearchLine {
+                            first: line_number,
+                            last: last_line_number,
+                            text: format!("{line_number}:{text}"),
+                        });
+                        lines.extend(after.into_iter().map(context_line));
+                        (path, lines)
                     }
+                };
+
+                match files.iter_mut().find(|(p, _)| *p == path) {
+                    Some((_, l)) => l.extend(lines),
+                    None => files.push((path, lines)),
                 }
             }
 
@@ -324,10 +578,19 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
                     }
 
                     writeln!(&mut output, "{}", path.to_string_lossy()).unwrap();
+                    state.last_searched_line = None;
                 }
 
                 for line in lines {
-                    writeln!(&mut output, "{line}").unwrap();
+                    // When including context, separate lines that are not next to each other
+                    if has_context
+                        && matches!(state.last_searched_line, Some(n) if line.first > n + 1)
+                    {
+                        writeln!(&mut output, "--").unwrap();
+                    }
+
+                    writeln!(&mut output, "{}", line.text).unwrap();
+                    state.last_searched_line = Some(line.last);
                 }
 
                 // Update our last seen path
@@ -341,8 +604,15 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
             }
         }
         DistantResponseData::ProcSpawned { .. } => Output::None,
+        DistantResponseData::ProcDetached { pid } => {
+            Output::StdoutLine(pid.to_string().into_bytes())
+        }
         DistantResponseData::ProcStdout { data, .. } => Output::Stdout(data),
         DistantResponseData::ProcStderr { data, .. } => Output::Stderr(data),
+        DistantResponseData::ProcPrompt { prompt, .. } => Output::Stderr(prompt.into_bytes()),
+        DistantResponseData::ProcEntries { entries } => {
+            Output::StdoutLine(procs_table(policy, entries).into_bytes())
+        }
         DistantResponseData::ProcDone { id, success, code } => {
             if success {
                 Output::None
@@ -352,6 +622,13 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
                 Output::StderrLine(format!("Proc {id} failed").into_bytes())
             }
         }
+        DistantResponseData::TunnelOpened { .. }
+        | DistantResponseData::TunnelClosed { .. }
+        | DistantResponseData::TunnelListening { .. }
+        | DistantResponseData::TunnelListeningUnix { .. }
+        | DistantResponseData::TunnelAccepted { .. } => Output::None,
+        DistantResponseData::TunnelData { data, .. } => Output::Stdout(data),
+        DistantResponseData::Pong { .. } => Output::StdoutLine(b"pong".to_vec()),
         DistantResponseData::SystemInfo(SystemInfo {
             family,
             os,
@@ -375,6 +652,66 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
             )
             .into_bytes(),
         ),
+        DistantResponseData::SystemEnv { env } => {
+            let mut env: Vec<_> = env.into_map().into_iter().collect();
+            env.sort();
+            Output::Stdout(
+                env.into_iter()
+                    .map(|(name, value)| format!("{name}={value}\n"))
+                    .collect::<String>()
+                    .into_bytes(),
+            )
+        }
+        DistantResponseData::Metrics(Metrics {
+            bytes_written,
+            write_quota,
+        }) => Output::StdoutLine(
+            format!(
+                "Bytes Written: {bytes_written}\nWrite Quota: {}",
+                write_quota
+                    .map(|quota| quota.to_string())
+                    .unwrap_or_else(|| String::from("none"))
+            )
+            .into_bytes(),
+        ),
+        DistantResponseData::ClientList { clients } => {
+            Output::StdoutLine(clients_table(policy, clients).into_bytes())
+        }
+        DistantResponseData::SystemStats { stats, .. } => Output::StdoutLine(
+            format!(
+                concat!(
+                    "CPU: {:.1}%\n",
+                    "CPUs: {}\n",
+                    "Memory: {} / {} bytes\n",
+                    "Swap: {} / {} bytes\n",
+                    "Load Average: {:.2} {:.2} {:.2}\n",
+                    "Uptime: {}s\n",
+                    "Processes: {}"
+                ),
+                stats.cpu_usage,
+                stats.cpu_count,
+                stats.memory_used,
+                stats.memory_total,
+                stats.swap_used,
+                stats.swap_total,
+                stats.load_average[0],
+                stats.load_average[1],
+                stats.load_average[2],
+                stats.uptime,
+                stats.processes.len(),
+            )
+            .into_bytes(),
+        ),
+        DistantResponseData::ProcStats { id, stats, .. } => Output::StdoutLine(
+            format!(
+                "Process {id}: CPU {:.1}%, Memory {} bytes, Read {} bytes, Written {} bytes",
+                stats.cpu_usage, stats.memory, stats.read_bytes, stats.write_bytes,
+            )
+            .into_bytes(),
+        ),
+        DistantResponseData::Extension { payload, .. } => {
+            Output::StdoutLine(format!("{payload:#}").into_bytes())
+        }
         DistantResponseData::Capabilities { supported } => {
             #[derive(Tabled)]
             struct EntryRow {
@@ -382,16 +719,274 @@ fn format_shell(state: &mut FormatterState, data: DistantResponseData) -> Output
                 description: String,
             }
 
-            let table = Table::new(supported.into_sorted_vec().into_iter().map(|cap| EntryRow {
+            let rows = supported.into_sorted_vec().into_iter().map(|cap| EntryRow {
                 kind: cap.kind,
                 description: cap.description,
-            }))
-            .with(Style::ascii())
-            .with(Modify::new(Rows::new(..)).with(Alignment::left()))
-            .to_string()
-            .into_bytes();
+            });
+            let table = policy
+                .table(rows, |table| {
+                    table
+                        .with(Style::ascii())
+                        .with(Modify::new(Rows::new(..)).with(Alignment::left()))
+                })
+                .into_bytes();
 
             Output::StdoutLine(table)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distant_core::data::{
+        DirEntry, ManifestEntry, SearchQueryMatchData, SearchQueryRootError, SearchQuerySkipped,
+    };
+
+    /// Formats each payload as shell output, returning what is written to stdout and stderr
+    fn render(payloads: Vec<DistantResponseData>) -> (String, String) {
+        let mut formatter = Formatter::shell();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        for payload in payloads {
+            formatter
+                .format(Response::new(
+                    String::from("0"),
+                    DistantMsg::Single(payload),
+                ))
+                .unwrap()
+                .write_to(&mut stdout, &mut stderr)
+                .unwrap();
+        }
+
+        (
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    }
+
+    fn contents_match(path: &str, line_number: u64, lines: &str) -> SearchQueryMatch {
+        SearchQueryMatch::Contents(SearchQueryContentsMatch {
+            path: PathBuf::from(path),
+            lines: SearchQueryMatchData::text(lines),
+            line_number,
+            absolute_offset: 0,
+            submatches: Vec::new(),
+            context: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn accessible_policy_should_render_table_rows_as_labeled_lines() {
+        #[derive(Tabled)]
+        struct Row {
+            kind: &'static str,
+            description: &'static str,
+        }
+
+        let rows = || {
+            vec![
+                Row {
+                    kind: "exec",
+                    description: "Runs a process",
+                },
+                Row {
+                    kind: "watch",
+                    description: "Watches a path",
+                },
+            ]
+        };
+
+        assert_eq!(
+            OutputPolicy::Accessible.table(rows(), |table| table.with(Style::ascii())),
+            "kind: exec\ndescription: Runs a process\n\nkind: watch\ndescription: Watches a path"
+        );
+        assert!(OutputPolicy::Visual
+            .table(rows(), |table| table.with(Style::ascii()))
+            .starts_with("+---"));
+    }
+
+    #[test]
+    fn shell_should_render_paired_rename_as_from_and_to() {
+        let change = |kind, paths: &[&str]| {
+            DistantResponseData::Changed(distant_core::data::Change {
+                kind,
+                paths: paths.iter().map(PathBuf::from).collect(),
+            })
+        };
+
+        assert_eq!(
+            render(vec![
+                change(ChangeKind::Renamed, &["/a/file", "/a/FILE"]),
+                change(ChangeKind::RenameFrom, &["/a/other"]),
+            ]),
+            (
+                String::from(concat!(
+                    "Following path was renamed:\n* /a/file -> /a/FILE\n",
+                    "Following paths were renamed:\n* /a/other\n",
+                )),
+                String::new()
+            )
+        );
+    }
+
+    #[test]
+    fn shell_should_render_dir_entries_as_table() {
+        let entry = |path: &str, file_type| DirEntry {
+            path: PathBuf::from(path),
+            file_type,
+            depth: 1,
+        };
+
+        assert_eq!(
+            render(vec![DistantResponseData::DirEntries {
+                entries: vec![
+                    entry("dir", FileType::Dir),
+                    entry("file.txt", FileType::File)
+                ],
+                errors: Vec::new(),
+            }]),
+            (
+                String::from(" <DIR>   dir      \n         file.txt "),
+                String::new()
+            )
+        );
+    }
+
+    #[test]
+    fn shell_should_render_manifest_chunks_as_hash_and_path_lines() {
+        let entry = |path: &str, hash: &str| ManifestEntry {
+            path: PathBuf::from(path),
+            size: 0,
+            modified: None,
+            hash: String::from(hash),
+        };
+
+        assert_eq!(
+            render(vec![
+                DistantResponseData::DirManifestChunk {
+                    entries: vec![entry("a.txt", "aa"), entry("b.txt", "bb")],
+                    errors: Vec::new(),
+                    done: false,
+                },
+                DistantResponseData::DirManifestChunk {
+                    entries: vec![entry("c.txt", "cc")],
+                    errors: Vec::new(),
+                    done: true,
+                },
+            ]),
+            (
+                String::from("aa  a.txt\nbb  b.txt\ncc  c.txt\n"),
+                String::new()
+            )
+        );
+    }
+
+    #[test]
+    fn shell_should_render_errors_to_stderr() {
+        assert_eq!(
+            render(vec![DistantResponseData::Error(Error {
+                kind: distant_core::data::ErrorKind::NotFound,
+                description: String::from("missing"),
+            })]),
+            (String::new(), String::from("missing\n"))
+        );
+    }
+
+    #[test]
+    fn shell_should_group_search_results_by_path_in_order_of_matches() {
+        let (stdout, stderr) = render(vec![
+            DistantResponseData::SearchResults {
+                id: 1,
+                matches: vec![
+                    contents_match("b.txt", 2, "two\n"),
+                    contents_match("a.txt", 1, "one\n"),
+                    contents_match("b.txt", 5, "five\n"),
+                ],
+            },
+            DistantResponseData::SearchResults {
+                id: 1,
+                matches: vec![contents_match("a.txt", 3, "three\n")],
+            },
+        ]);
+
+        assert_eq!(stdout, "b.txt\n2:two\n5:five\n\na.txt\n1:one\n3:three\n");
+        assert_eq!(stderr, "");
+    }
+
+    #[test]
+    fn shell_should_render_search_context_grep_style() {
+        let context_line = |line_number, line| SearchQueryContextLine {
+            line_number,
+            line: SearchQueryMatchData::text(line),
+        };
+
+        let mut first = contents_match("a.txt", 2, "two\n");
+        let mut second = contents_match("a.txt", 4, "four\n");
+        let mut third = contents_match("a.txt", 9, "nine\n");
+        for (m, context) in [
+            (
+                &mut first,
+                vec![context_line(1, "one\n"), context_line(3, "three\n")],
+            ),
+            (&mut second, vec![context_line(5, "five\n")]),
+            (&mut third, vec![context_line(8, "eight\n")]),
+        ] {
+            if let SearchQueryMatch::Contents(m) = m {
+                m.context = context;
+            }
+        }
+
+        let (stdout, stderr) = render(vec![
+            DistantResponseData::SearchResults {
+                id: 1,
+                matches: vec![first, second],
+            },
+            DistantResponseData::SearchResults {
+                id: 1,
+                matches: vec![third],
+            },
+        ]);
+
+        assert_eq!(
+            stdout,
+            "a.txt\n1-one\n2:two\n3-three\n4:four\n5-five\n--\n8-eight\n9:nine\n"
+        );
+        assert_eq!(stderr, "");
+    }
+
+    #[test]
+    fn shell_should_summarize_errors_and_skipped_files_when_search_is_done() {
+        assert_eq!(
+            render(vec![DistantResponseData::SearchDone {
+                id: 1,
+                skipped: SearchQuerySkipped::default(),
+                errors: Vec::new(),
+            }]),
+            (String::new(), String::new())
+        );
+        assert_eq!(
+            render(vec![DistantResponseData::SearchDone {
+                id: 1,
+                skipped: SearchQuerySkipped {
+                    too_large: 2,
+                    binary: 1,
+                },
+                errors: vec![SearchQueryRootError {
+                    path: PathBuf::from("missing"),
+                    error: Error {
+                        kind: distant_core::data::ErrorKind::NotFound,
+                        description: String::from("No such file or directory"),
+                    },
+                }],
+            }]),
+            (
+                String::new(),
+                String::from(concat!(
+                    "Failed to search missing: No such file or directory\n",
+                    "Skipped 2 files too large and 1 binary file\n",
+                ))
+            )
+        );
+    }
+}
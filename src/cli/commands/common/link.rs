@@ -1,81 +1,91 @@
-use super::stdin;
+use super::{stdin, stdout};
 use distant_core::{
-    RemoteLspStderr, RemoteLspStdin, RemoteLspStdout, RemoteStderr, RemoteStdin, RemoteStdout,
+    RemoteLspStderr, RemoteLspStdin, RemoteLspStdout, RemoteProcessKiller, RemoteStatus,
+    RemoteStderr, RemoteStdin, RemoteStdout,
 };
 use log::*;
 use std::{
+    future::Future,
     io::{self, Write},
+    sync::Arc,
     thread,
 };
-use tokio::task::{JoinError, JoinHandle};
+use tokio::{sync::Notify, task::JoinSet};
 
 /// Represents a link between a remote process' stdin/stdout/stderr and this process'
 /// stdin/stdout/stderr
+///
+/// Every task spawned on behalf of the link is supervised by it, meaning that all of them are
+/// cancelled once the link is shut down or dropped.
+#[derive(Default)]
 pub struct RemoteProcessLink {
+    /// Thread performing blocking reads of stdin, which cannot be cancelled and instead exits the
+    /// next time that it reads input after the link is gone
     _stdin_thread: Option<thread::JoinHandle<()>>,
-    stdin_task: Option<JoinHandle<io::Result<()>>>,
-    stdout_task: JoinHandle<io::Result<()>>,
-    stderr_task: JoinHandle<io::Result<()>>,
+    tasks: JoinSet<io::Result<()>>,
+
+    /// Notified once output of the remote process can no longer be written as our stdout or
+    /// stderr has been closed
+    output_closed: Arc<Notify>,
 }
 
 macro_rules! from_pipes {
     ($stdin:expr, $stdout:expr, $stderr:expr, $buffer:expr) => {{
-        let mut stdin_thread = None;
-        let mut stdin_task = None;
+        let mut link = RemoteProcessLink::new();
         if let Some(mut stdin_handle) = $stdin {
             let (thread, mut rx) = stdin::spawn_channel($buffer);
-            let task = tokio::spawn(async move {
-                loop {
-                    if let Some(input) = rx.recv().await {
-                        trace!("Forwarding stdin: {:?}", String::from_utf8_lossy(&input));
-                        if let Err(x) = stdin_handle.write(&*input).await {
-                            break Err(x);
-                        }
-                    } else {
-                        break Ok(());
-                    }
+            link.spawn(async move {
+                while let Some(input) = rx.recv().await {
+                    trace!("Forwarding stdin: {:?}", String::from_utf8_lossy(&input));
+                    stdin_handle.write(&*input).await?;
                 }
+
+                // Reaching the end of our stdin closes that of the remote process, so that
+                // piping input into it works like it would for a local process
+                trace!("Closing stdin");
+                stdin_handle.close().await
             });
-            stdin_thread = Some(thread);
-            stdin_task = Some(task);
+            link._stdin_thread = Some(thread);
         }
-        let stdout_task = tokio::spawn(async move {
+        let closed = Arc::clone(&link.output_closed);
+        link.spawn(async move {
             let handle = io::stdout();
             loop {
-                match $stdout.read().await {
-                    Ok(output) => {
-                        let mut out = handle.lock();
-                        out.write_all(&output)?;
-                        out.flush()?;
+                let output = $stdout.read().await?;
+                match write_output(&mut handle.lock(), &output) {
+                    Err(x) if stdout::is_closed(&x) => {
+                        closed.notify_one();
+                        return Ok(());
                     }
-                    Err(x) => break Err(x),
+                    x => x?,
                 }
             }
         });
-        let stderr_task = tokio::spawn(async move {
+        let closed = Arc::clone(&link.output_closed);
+        link.spawn(async move {
             let handle = io::stderr();
             loop {
-                match $stderr.read().await {
-                    Ok(output) => {
-                        let mut out = handle.lock();
-                        out.write_all(&output)?;
-                        out.flush()?;
+                let output = $stderr.read().await?;
+                match write_output(&mut handle.lock(), &output) {
+                    Err(x) if stdout::is_closed(&x) => {
+                        closed.notify_one();
+                        return Ok(());
                     }
-                    Err(x) => break Err(x),
+                    x => x?,
                 }
             }
         });
-
-        RemoteProcessLink {
-            _stdin_thread: stdin_thread,
-            stdin_task,
-            stdout_task,
-            stderr_task,
-        }
+        link
     }};
 }
 
 impl RemoteProcessLink {
+    /// Creates a new process link without any tasks, leaving it to the caller to spawn tasks that
+    /// forward the pipes of a remote process
+    pub fn new() -> Self {
+        Self::default()
+    }
+
     /// Creates a new process link from the pipes of a remote process.
     ///
     /// `max_pipe_chunk_size` represents the maximum size (in bytes) of data that will be read from
@@ -102,27 +112,116 @@ impl RemoteProcessLink {
         from_pipes!(stdin, stdout, stderr, max_pipe_chunk_size)
     }
 
-    /// Shuts down the link, aborting any running tasks, and swallowing join errors
-    pub async fn shutdown(self) {
-        self.abort();
-        let _ = self.wait().await;
+    /// Spawns an additional task whose lifetime is bound to the link, such as one forwarding
+    /// terminal input to the remote process
+    pub fn spawn<F>(&mut self, task: F)
+    where
+        F: Future<Output = io::Result<()>> + Send + 'static,
+    {
+        self.tasks.spawn(task);
+    }
+
+    /// Waits until output of the remote process can no longer be written, which happens once
+    /// whatever reads our stdout or stderr closes it (e.g. `| head`), and never happens for tasks
+    /// spawned by the caller
+    pub async fn output_closed(&self) {
+        self.output_closed.notified().await
     }
 
-    /// Waits for the stdin, stdout, and stderr tasks to complete
-    pub async fn wait(self) -> Result<(), JoinError> {
-        if let Some(stdin_task) = self.stdin_task {
-            tokio::try_join!(stdin_task, self.stdout_task, self.stderr_task).map(|_| ())
-        } else {
-            tokio::try_join!(self.stdout_task, self.stderr_task).map(|_| ())
+    /// Waits on `wait` for the remote process to exit, unless its output can no longer be written
+    /// beforehand, in which case the process is killed through `killer` and `None` is returned
+    /// once it has exited, much like a local process stopped by `SIGPIPE`
+    pub async fn wait_or_kill(
+        &self,
+        wait: impl Future<Output = io::Result<RemoteStatus>>,
+        mut killer: RemoteProcessKiller,
+    ) -> io::Result<Option<RemoteStatus>> {
+        tokio::pin!(wait);
+        tokio::select! {
+            status = &mut wait => return status.map(Some),
+            _ = self.output_closed() => {}
+        }
+
+        debug!("Output closed, so killing remote process");
+        if let Err(x) = killer.kill().await {
+            // The process may have exited in the meantime
+            debug!("Failed to kill remote process: {x}");
         }
+        wait.await?;
+        Ok(None)
     }
 
-    /// Aborts the link by aborting tasks processing stdin, stdout, and stderr
-    pub fn abort(&self) {
-        if let Some(stdin_task) = self.stdin_task.as_ref() {
-            stdin_task.abort();
+    /// Shuts down the link, cancelling all of its tasks and waiting for them to stop
+    pub async fn shutdown(mut self) {
+        self.tasks.abort_all();
+        while let Some(result) = self.tasks.join_next().await {
+            if let Ok(Err(x)) = result {
+                error!("Link task failed: {x}");
+            }
         }
-        self.stdout_task.abort();
-        self.stderr_task.abort();
+    }
+}
+
+fn write_output(out: &mut impl Write, output: &[u8]) -> io::Result<()> {
+    out.write_all(output)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use test_log::test;
+    use tokio::sync::oneshot;
+
+    /// Spawns a task on `link` that runs forever, returning a receiver that closes once the task
+    /// has been cancelled
+    fn spawn_pending(link: &mut RemoteProcessLink) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel::<()>();
+        link.spawn(async move {
+            let _tx = tx;
+            std::future::pending::<()>().await;
+            Ok(())
+        });
+        rx
+    }
+
+    #[test(tokio::test)]
+    async fn shutdown_should_cancel_all_tasks() {
+        let mut link = RemoteProcessLink::new();
+        let rx_1 = spawn_pending(&mut link);
+        let rx_2 = spawn_pending(&mut link);
+
+        link.shutdown().await;
+
+        // Senders are dropped, and therefore receivers closed, once the tasks are cancelled
+        rx_1.await.unwrap_err();
+        rx_2.await.unwrap_err();
+    }
+
+    #[test(tokio::test)]
+    async fn drop_should_cancel_all_tasks() {
+        let mut link = RemoteProcessLink::new();
+        let rx = spawn_pending(&mut link);
+
+        drop(link);
+
+        tokio::time::timeout(Duration::from_secs(1), rx)
+            .await
+            .expect("Task was not cancelled")
+            .unwrap_err();
+    }
+
+    #[test(tokio::test)]
+    async fn shutdown_should_cancel_remaining_tasks_if_a_task_failed() {
+        let mut link = RemoteProcessLink::new();
+        let rx = spawn_pending(&mut link);
+        link.spawn(async { Err(io::Error::new(io::ErrorKind::BrokenPipe, "failed")) });
+
+        tokio::time::timeout(Duration::from_secs(1), link.shutdown())
+            .await
+            .expect("Link did not shut down");
+
+        rx.await.unwrap_err();
     }
 }
@@ -0,0 +1,340 @@
+use std::{
+    env,
+    io::{self, BufRead, IsTerminal, Write},
+    process::{Child, Command, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+};
+use terminal_size::{terminal_size, Height, Width};
+
+/// Whether shell output that does not fit on the screen is paged
+static PAGING: AtomicBool = AtomicBool::new(false);
+
+/// Prompt written to stderr by the built-in pager after each page
+const MORE_PROMPT: &str = "-- More -- (Enter for the next page, q to quit) ";
+
+/// Size of the screen that output is paged on
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Screen {
+    rows: usize,
+    cols: usize,
+}
+
+impl Screen {
+    /// Returns the number of lines of a page, leaving room for the prompt of the built-in pager
+    fn page_len(self) -> usize {
+        self.rows.saturating_sub(1).max(1)
+    }
+}
+
+enum State {
+    /// Output is held back while it still fits on the screen
+    Holding(Vec<u8>),
+
+    /// Output is written as is
+    Direct,
+
+    /// Output is written to the stdin of the pager program
+    Program(Child),
+
+    /// Output is shown a page at a time, where `shown` is the number of lines on the current page
+    /// and `partial` is the end of the output that is not yet a complete line
+    BuiltIn { shown: usize, partial: Vec<u8> },
+
+    /// Output is refused as the pager was quit, just like a closed stdout would
+    Quit,
+}
+
+/// Writer of shell output that holds it back until it no longer fits on the screen, at which
+/// point it is paged through `$PAGER` (or a built-in pager if not set), otherwise writing it as
+/// is once finished
+///
+/// Output is finished when the pager is dropped, which waits on the pager program to exit.
+pub struct Pager<W: Write> {
+    out: W,
+    screen: Option<Screen>,
+    program: Option<Vec<String>>,
+    state: State,
+}
+
+impl Pager<io::Stdout> {
+    /// Pages shell output for the rest of the program
+    pub fn enable() {
+        PAGING.store(true, Ordering::Relaxed);
+    }
+
+    /// Creates a pager of stdout, which writes output as is unless paging is
+    /// [enabled](Pager::enable) and both stdin and stdout are terminals
+    pub fn stdout() -> Self {
+        let screen = if PAGING.load(Ordering::Relaxed)
+            && io::stdout().is_terminal()
+            && io::stdin().is_terminal()
+        {
+            terminal_size().map(|(Width(cols), Height(rows))| Screen {
+                rows: rows as usize,
+                cols: cols as usize,
+            })
+        } else {
+            None
+        };
+
+        let program = env::var("PAGER")
+            .ok()
+            .and_then(|pager| shell_words::split(&pager).ok())
+            .filter(|args| !args.is_empty());
+
+        Self::new(io::stdout(), screen, program)
+    }
+}
+
+impl<W: Write> Pager<W> {
+    fn new(out: W, screen: Option<Screen>, program: Option<Vec<String>>) -> Self {
+        Self {
+            out,
+            screen,
+            program,
+            state: match screen {
+                Some(_) => State::Holding(Vec::new()),
+                None => State::Direct,
+            },
+        }
+    }
+
+    /// Starts paging `data`, which is all of the output so far, through the pager program or,
+    /// if it cannot be run, the built-in pager
+    fn start(&mut self, data: Vec<u8>) -> io::Result<()> {
+        if let Some(args) = self.program.as_ref() {
+            match Command::new(&args[0])
+                .args(&args[1..])
+                .stdin(Stdio::piped())
+                .spawn()
+            {
+                Ok(child) => {
+                    self.state = State::Program(child);
+                    return self.write_to_program(&data);
+                }
+                Err(x) => log::warn!("Failed to run pager {:?}: {}", args[0], x),
+            }
+        }
+
+        self.state = State::BuiltIn {
+            shown: 0,
+            partial: Vec::new(),
+        };
+        self.write_to_built_in(&data)
+    }
+
+    fn write_to_program(&mut self, data: &[u8]) -> io::Result<()> {
+        let State::Program(child) = &mut self.state else {
+            return Ok(());
+        };
+
+        // Quitting the pager closes its stdin, after which the rest of the output is refused
+        match child.stdin.as_mut().map(|stdin| stdin.write_all(data)) {
+            Some(Err(x)) if x.kind() == io::ErrorKind::BrokenPipe => self.finish_program(),
+            Some(x) => x,
+            None => Ok(()),
+        }
+    }
+
+    fn finish_program(&mut self) -> io::Result<()> {
+        if let State::Program(mut child) = std::mem::replace(&mut self.state, State::Quit) {
+            drop(child.stdin.take());
+            child.wait()?;
+        }
+
+        Ok(())
+    }
+
+    fn write_to_built_in(&mut self, data: &[u8]) -> io::Result<()> {
+        let (Some(screen), State::BuiltIn { shown, partial }) = (self.screen, &mut self.state)
+        else {
+            return Ok(());
+        };
+
+        partial.extend_from_slice(data);
+        let mut start = 0;
+        while let Some(pos) = partial[start..].iter().position(|b| *b == b'\n') {
+            let line = &partial[start..=start + pos];
+            start += pos + 1;
+
+            self.out.write_all(line)?;
+            *shown += screen_lines(line, screen.cols);
+            if *shown < screen.page_len() {
+                continue;
+            }
+
+            *shown = 0;
+            self.out.flush()?;
+            if !prompt_for_more()? {
+                self.state = State::Quit;
+                return Ok(());
+            }
+        }
+
+        partial.drain(..start);
+        Ok(())
+    }
+
+    /// Writes out whatever output remains, waiting on the pager program to exit if one is running
+    pub fn finish(&mut self) -> io::Result<()> {
+        match std::mem::replace(&mut self.state, State::Direct) {
+            State::Holding(data) => self.out.write_all(&data)?,
+            State::BuiltIn { partial, .. } => self.out.write_all(&partial)?,
+            state @ State::Program(_) => {
+                self.state = state;
+                self.finish_program()?;
+            }
+            State::Direct => {}
+            State::Quit => self.state = State::Quit,
+        }
+
+        self.out.flush()
+    }
+}
+
+impl<W: Write> Write for Pager<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        match &mut self.state {
+            State::Holding(held) => {
+                held.extend_from_slice(data);
+                let screen = self.screen.expect("Output is only held with a screen");
+                if screen_lines(held, screen.cols) > screen.page_len() {
+                    let held = std::mem::take(held);
+                    self.start(held)?;
+                }
+            }
+            State::Direct => self.out.write_all(data)?,
+            State::Program(_) => self.write_to_program(data)?,
+            State::BuiltIn { .. } => self.write_to_built_in(data)?,
+            State::Quit => {
+                return Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "Pager has been quit",
+                ))
+            }
+        }
+
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.state {
+            State::Direct | State::BuiltIn { .. } => self.out.flush(),
+            State::Program(child) => match child.stdin.as_mut() {
+                Some(stdin) => stdin.flush(),
+                None => Ok(()),
+            },
+            State::Holding(_) | State::Quit => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Drop for Pager<W> {
+    fn drop(&mut self) {
+        if let Err(x) = self.finish() {
+            log::error!("Failed to finish paging output: {}", x);
+        }
+    }
+}
+
+/// Returns the number of lines that `data` takes up on a screen `cols` wide, where long lines
+/// wrap onto the next and a partial line at the end counts as a line
+fn screen_lines(data: &[u8], cols: usize) -> usize {
+    let cols = cols.max(1);
+    String::from_utf8_lossy(data)
+        .split_inclusive('\n')
+        .map(|line| {
+            let len = line.trim_end_matches(['\r', '\n']).chars().count();
+            len.max(1).div_ceil(cols)
+        })
+        .sum()
+}
+
+/// Asks to continue onto the next page, returning false if asked to quit
+fn prompt_for_more() -> io::Result<bool> {
+    let mut stderr = io::stderr();
+    stderr.write_all(MORE_PROMPT.as_bytes())?;
+    stderr.flush()?;
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        return Ok(false);
+    }
+
+    Ok(!answer.trim().eq_ignore_ascii_case("q"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Screen = Screen { rows: 4, cols: 10 };
+
+    #[test]
+    fn screen_lines_should_count_wrapped_and_partial_lines() {
+        assert_eq!(screen_lines(b"", 10), 0);
+        assert_eq!(screen_lines(b"a\nb\n", 10), 2);
+        assert_eq!(screen_lines(b"a\n\nb", 10), 3);
+        assert_eq!(screen_lines(b"0123456789abc\n", 10), 2);
+        assert_eq!(screen_lines(b"0123456789\r\n", 10), 1);
+    }
+
+    #[test]
+    fn pager_should_write_output_as_is_without_screen() {
+        let mut out = Vec::new();
+        {
+            let mut pager = Pager::new(&mut out, None, None);
+            pager.write_all(b"a\nb\nc\nd\ne\n").unwrap();
+        }
+        assert_eq!(out, b"a\nb\nc\nd\ne\n");
+    }
+
+    #[test]
+    fn pager_should_hold_output_that_fits_on_screen_until_finished() {
+        let mut out = Vec::new();
+        let mut pager = Pager::new(&mut out, Some(SCREEN), None);
+        pager.write_all(b"a\nb\n").unwrap();
+        pager.write_all(b"c\n").unwrap();
+        pager.flush().unwrap();
+        assert!(matches!(&pager.state, State::Holding(held) if held == b"a\nb\nc\n"));
+
+        drop(pager);
+        assert_eq!(out, b"a\nb\nc\n");
+    }
+
+    #[test]
+    fn pager_should_refuse_output_like_a_closed_stdout_once_quit() {
+        let mut out = Vec::new();
+        let mut pager = Pager::new(&mut out, Some(SCREEN), None);
+        pager.state = State::Quit;
+
+        let err = pager.write_all(b"a\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+
+        drop(pager);
+        assert!(out.is_empty(), "Unexpected output: {out:?}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pager_should_send_all_output_to_program_once_it_no_longer_fits_on_screen() {
+        let file = assert_fs::NamedTempFile::new("paged.txt").unwrap();
+        let program = vec![
+            String::from("sh"),
+            String::from("-c"),
+            format!("cat > {}", file.path().to_str().unwrap()),
+        ];
+
+        let mut out = Vec::new();
+        {
+            let mut pager = Pager::new(&mut out, Some(SCREEN), Some(program));
+            pager.write_all(b"a\nb\n").unwrap();
+            pager.write_all(b"c\nd\n").unwrap();
+            assert!(matches!(pager.state, State::Program(_)));
+            pager.write_all(b"e\n").unwrap();
+        }
+
+        assert!(out.is_empty(), "Unexpected output: {out:?}");
+        assert_eq!(std::fs::read(file.path()).unwrap(), b"a\nb\nc\nd\ne\n");
+    }
+}
@@ -0,0 +1,60 @@
+use super::OutputPolicy;
+use crate::options::Format;
+use distant_core::data::ProcInfo;
+use serde_json::json;
+use tabled::Tabled;
+
+/// Renders `procs` of a remote machine as a table, one row per process
+pub fn procs_table(policy: OutputPolicy, procs: Vec<ProcInfo>) -> String {
+    #[derive(Tabled)]
+    struct ProcRow {
+        id: String,
+        pid: String,
+        uptime: String,
+        status: String,
+        cmd: String,
+    }
+
+    policy.table(
+        procs.into_iter().map(|proc| ProcRow {
+            id: proc.id.map(|id| id.to_string()).unwrap_or_default(),
+            pid: proc.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+            uptime: format_uptime(proc.uptime),
+            status: proc.status.to_string(),
+            cmd: proc.cmd,
+        }),
+        |table| table,
+    )
+}
+
+/// Prints `procs` of a remote machine in the given `format`
+pub fn print_procs(format: Format, procs: Vec<ProcInfo>) {
+    match format {
+        Format::Json => println!("{}", json!({"type": "proc_entries", "entries": procs})),
+        Format::Shell => println!("{}", procs_table(OutputPolicy::current(), procs)),
+    }
+}
+
+/// Formats `secs` as elapsed time like `ps` does, as `[[dd-]hh:]mm:ss`
+fn format_uptime(secs: u64) -> String {
+    let (days, hours, mins, secs) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{days}-{hours:02}:{mins:02}:{secs:02}")
+    } else if hours > 0 {
+        format!("{hours:02}:{mins:02}:{secs:02}")
+    } else {
+        format!("{mins:02}:{secs:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_uptime_should_only_include_hours_and_days_when_reached() {
+        assert_eq!(format_uptime(5), "00:05");
+        assert_eq!(format_uptime(3 * 3600 + 61), "03:01:01");
+        assert_eq!(format_uptime(2 * 86400 + 3600), "2-01:00:00");
+    }
+}
@@ -0,0 +1,16 @@
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+/// Returns true if `err` comes from writing to a stdout that has been closed by whatever was
+/// reading it, such as `head` once it has read all of the lines that it wants
+pub fn is_closed(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::BrokenPipe
+}
+
+/// Writes `line` followed by a newline to stdout, which unlike `println!` reports a closed stdout
+/// as an error instead of panicking
+pub fn write_line(line: impl fmt::Display) -> io::Result<()> {
+    writeln!(io::stdout().lock(), "{line}")
+}
@@ -0,0 +1,206 @@
+use crate::constants;
+use crate::options::{Config, ConfigSubcommand, ConfigTarget};
+use crate::CliResult;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use toml_edit::{Document, Item, Table, TableLike, Value};
+
+pub fn run(cmd: &ConfigSubcommand, config_path: Option<&Path>) -> CliResult {
+    match cmd {
+        ConfigSubcommand::Get { target, key } => {
+            let document = if target.user || target.global {
+                let path = target_path(target, config_path);
+                std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read config file {path:?}"))?
+                    .parse::<Document>()
+                    .with_context(|| format!("Failed to parse config file {path:?}"))?
+            } else {
+                let config = Config::load_multi(config_path.map(Path::to_path_buf))?;
+                toml_edit::ser::to_document(&config).context("Failed to serialize config")?
+            };
+
+            let item = get(document.as_table(), key)?
+                .with_context(|| format!("No setting found at {key}"))?;
+            println!("{}", display(item));
+        }
+        ConfigSubcommand::Set { target, key, value } => {
+            Config::edit(target_path(target, config_path), |document| {
+                set(document.as_table_mut(), key, parse_value(value))
+            })?;
+        }
+        ConfigSubcommand::Unset { target, key } => {
+            Config::edit(target_path(target, config_path), |document| {
+                match unset(document.as_table_mut(), key)? {
+                    Some(_) => Ok(()),
+                    None => anyhow::bail!("No setting found at {key}"),
+                }
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the path of the config file targeted by `target`
+fn target_path(target: &ConfigTarget, config_path: Option<&Path>) -> PathBuf {
+    if target.global {
+        constants::global::CONFIG_FILE_PATH.to_path_buf()
+    } else if target.user {
+        constants::user::CONFIG_FILE_PATH.to_path_buf()
+    } else {
+        config_path
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| constants::user::CONFIG_FILE_PATH.to_path_buf())
+    }
+}
+
+/// Splits a dotted `key` into the keys of its parent tables and its last key
+fn split_key(key: &str) -> anyhow::Result<(Vec<&str>, &str)> {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|part| part.is_empty()) {
+        anyhow::bail!("Invalid key {key:?}");
+    }
+
+    let last = parts.pop().unwrap();
+    Ok((parts, last))
+}
+
+/// Returns the item at the dotted `key` within `table`, if it exists
+fn get<'a>(table: &'a Table, key: &str) -> anyhow::Result<Option<&'a Item>> {
+    let (parents, last) = split_key(key)?;
+    let mut table: &dyn TableLike = table;
+    for parent in parents {
+        match table.get(parent).and_then(Item::as_table_like) {
+            Some(x) => table = x,
+            None => return Ok(None),
+        }
+    }
+
+    Ok(table.get(last))
+}
+
+/// Sets the dotted `key` within `table` to `value`, creating any missing tables along the way
+fn set(table: &mut Table, key: &str, value: Value) -> anyhow::Result<()> {
+    let (parents, last) = split_key(key)?;
+    let mut table: &mut dyn TableLike = table;
+    for parent in parents {
+        table = table
+            .entry(parent)
+            .or_insert_with(|| {
+                let mut table = Table::new();
+                table.set_implicit(true);
+                Item::Table(table)
+            })
+            .as_table_like_mut()
+            .with_context(|| format!("{parent} within {key} is not a table"))?;
+    }
+
+    // Existing values are replaced in place so that the comments around them are kept
+    match table.get_mut(last) {
+        Some(Item::Value(existing)) => {
+            let decor = existing.decor().clone();
+            *existing = value;
+            *existing.decor_mut() = decor;
+        }
+        Some(item) if item.is_table_like() => {
+            anyhow::bail!("{key} is a table and cannot be set to a value");
+        }
+        _ => {
+            table.insert(last, Item::Value(value));
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes the dotted `key` from `table`, returning the removed item if it existed
+fn unset(table: &mut Table, key: &str) -> anyhow::Result<Option<Item>> {
+    let (parents, last) = split_key(key)?;
+    let mut table: &mut dyn TableLike = table;
+    for parent in parents {
+        match table.get_mut(parent).and_then(Item::as_table_like_mut) {
+            Some(x) => table = x,
+            None => return Ok(None),
+        }
+    }
+
+    Ok(table.remove(last))
+}
+
+/// Parses `value` as a TOML value, treating it as a string if it is not one
+fn parse_value(value: &str) -> Value {
+    match value.parse::<Value>() {
+        Ok(mut value) => {
+            value.decor_mut().clear();
+            value
+        }
+        Err(_) => Value::from(value),
+    }
+}
+
+/// Returns the text printed for `item`, where strings are printed without quotes
+fn display(item: &Item) -> String {
+    match item {
+        Item::Value(Value::String(s)) => s.value().to_string(),
+        Item::Table(table) => Document::from(table.clone()).to_string(),
+        item => item.to_string(),
+    }
+    .trim()
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = r#"
+[client]
+# Binary to run
+bin = "distant"
+
+[server.listen]
+port = 8080
+"#;
+
+    #[test]
+    fn get_should_find_items_at_dotted_keys() {
+        let document: Document = TEXT.parse().unwrap();
+        let get = |key| get(document.as_table(), key).unwrap().map(display);
+
+        assert_eq!(get("client.bin").as_deref(), Some("distant"));
+        assert_eq!(get("server.listen.port").as_deref(), Some("8080"));
+        assert_eq!(get("server.listen").as_deref(), Some("port = 8080"));
+        assert_eq!(get("client.bin.other"), None);
+        assert_eq!(get("missing"), None);
+        assert!(super::get(document.as_table(), "client..bin").is_err());
+    }
+
+    #[test]
+    fn set_and_unset_should_keep_the_rest_of_the_document() {
+        let mut document: Document = TEXT.parse().unwrap();
+        let table = document.as_table_mut();
+
+        set(table, "client.bin", parse_value("/usr/local/bin/distant")).unwrap();
+        set(table, "server.listen.port", parse_value("9000")).unwrap();
+        set(table, "manager.access", parse_value("anyone")).unwrap();
+        assert!(set(table, "server", parse_value("1")).is_err());
+        assert!(set(table, "client.bin.other", parse_value("1")).is_err());
+
+        assert!(unset(table, "server.listen.port").unwrap().is_some());
+        assert!(unset(table, "server.listen.port").unwrap().is_none());
+
+        assert_eq!(
+            document.to_string(),
+            r#"
+[client]
+# Binary to run
+bin = "/usr/local/bin/distant"
+
+[server.listen]
+
+[manager]
+access = "anyone"
+"#
+        );
+    }
+}
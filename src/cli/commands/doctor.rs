@@ -0,0 +1,330 @@
+use crate::cli::Client;
+use crate::constants;
+use crate::options::{ClientLaunchConfig, Config, ManagerAutostartConnection, NetworkSettings};
+use crate::{CliError, CliResult};
+use anyhow::Context;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Result of a single check, printed as `[status] summary` followed by an indented hint
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Check {
+    status: Status,
+    summary: String,
+
+    /// What to do to fix a failed check
+    hint: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Skipped,
+    Failed,
+}
+
+impl Check {
+    fn ok(summary: impl Into<String>) -> Self {
+        Self {
+            status: Status::Ok,
+            summary: summary.into(),
+            hint: None,
+        }
+    }
+
+    fn skipped(summary: impl Into<String>) -> Self {
+        Self {
+            status: Status::Skipped,
+            summary: summary.into(),
+            hint: None,
+        }
+    }
+
+    fn failed(summary: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            status: Status::Failed,
+            summary: summary.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self.status {
+            Status::Ok => "[ok]  ",
+            Status::Skipped => "[skip]",
+            Status::Failed => "[fail]",
+        };
+
+        // Multi-line summaries (such as parse errors) and hints are indented beneath the status
+        let mut lines = self.summary.trim_end().lines();
+        write!(f, "{status} {}", lines.next().unwrap_or_default())?;
+        for line in lines.chain(self.hint.iter().flat_map(|hint| hint.lines())) {
+            write!(f, "\n       {line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn run(local: bool, network: NetworkSettings, config_path: Option<&Path>) -> CliResult {
+    let rt = tokio::runtime::Runtime::new().context("Failed to start up runtime")?;
+    rt.block_on(async_run(local, network, config_path))
+}
+
+async fn async_run(local: bool, network: NetworkSettings, config_path: Option<&Path>) -> CliResult {
+    let mut failed = false;
+    let mut report = |check: Check| {
+        failed |= check.status == Status::Failed;
+        println!("{check}");
+    };
+
+    for check in check_config_files(config_path) {
+        report(check);
+    }
+
+    report(check_manager(network).await);
+
+    if local {
+        report(Check::skipped("Launch targets (--local)"));
+    } else {
+        let config = Config::load_multi(config_path.map(Path::to_path_buf)).unwrap_or_default();
+        for connection in config.manager.autostart.connections {
+            if connection.launch {
+                report(check_launch_target(connection).await);
+            }
+        }
+    }
+
+    if failed {
+        Err(CliError::FAILURE)
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks that each config file that gets loaded is valid TOML and that together they load as a
+/// config
+fn check_config_files(config_path: Option<&Path>) -> Vec<Check> {
+    let paths = match config_path {
+        Some(path) => vec![path.to_path_buf()],
+        None => vec![
+            constants::global::CONFIG_FILE_PATH.to_path_buf(),
+            constants::user::CONFIG_FILE_PATH.to_path_buf(),
+        ],
+    };
+
+    let mut checks = Vec::new();
+    for path in paths.iter() {
+        checks.push(match std::fs::read_to_string(path) {
+            // Only the standard config files are optional
+            Err(x) if x.kind() == io::ErrorKind::NotFound && config_path.is_none() => {
+                Check::skipped(format!("Config file {path:?} does not exist"))
+            }
+            Err(x) => Check::failed(
+                format!("Failed to read config file {path:?}: {x}"),
+                format!(
+                    "Create it with `distant generate config {}`",
+                    path.display()
+                ),
+            ),
+            Ok(text) => match text.parse::<toml_edit::Document>() {
+                Ok(_) => Check::ok(format!("Config file {path:?} is valid TOML")),
+                Err(x) => Check::failed(
+                    format!("Config file {path:?} is not valid TOML\n{x}"),
+                    format!(
+                        "Fix the file or replace it with `distant generate config {}`",
+                        path.display()
+                    ),
+                ),
+            },
+        });
+    }
+
+    // Settings are only worth checking once every file parses
+    if checks.iter().all(|check| check.status != Status::Failed) {
+        checks.push(
+            match Config::load_multi(config_path.map(Path::to_path_buf)) {
+                Ok(_) => Check::ok("Config settings are valid"),
+                Err(x) => Check::failed(
+                    format!("Config settings are invalid: {x:#}"),
+                    "Inspect settings with `distant config get <key>` and fix them with \
+                    `distant config set <key> <value>`",
+                ),
+            },
+        );
+    }
+
+    checks
+}
+
+/// Checks that a manager is listening where the client looks for one
+async fn check_manager(network: NetworkSettings) -> Check {
+    let candidates = if cfg!(windows) {
+        network.to_windows_pipe_name_candidates().join(", ")
+    } else {
+        network
+            .to_unix_socket_path_candidates()
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match Client::new(network)
+        .using_prompt_auth_handler()
+        .connect()
+        .await
+    {
+        Ok(mut client) => match client.list().await {
+            Ok(list) => Check::ok(format!(
+                "Manager is reachable and has {} active connection(s)",
+                list.len()
+            )),
+            Err(x) => Check::failed(
+                format!("Manager is reachable but failed to list connections: {x}"),
+                "Restart it with `distant manager listen --daemon`",
+            ),
+        },
+        Err(x) => Check::failed(
+            format!("No manager is reachable at {candidates}: {x:#}"),
+            "Start one with `distant manager listen --daemon`, or point to where one is \
+            listening with --unix-socket or --windows-pipe",
+        ),
+    }
+}
+
+/// Checks that the distant binary launched for an autostart connection exists where it is run
+async fn check_launch_target(connection: ManagerAutostartConnection) -> Check {
+    let mut destination = connection.destination;
+    destination.password = None;
+
+    let bin = ClientLaunchConfig::from(connection.options.clone())
+        .distant
+        .bin;
+    let hint = "Install distant there or set `distant.bin` within the options of the \
+        autostart connection";
+
+    // Launching uses ssh when no scheme is given
+    let scheme = destination
+        .scheme
+        .clone()
+        .unwrap_or_else(|| String::from("ssh"));
+    match scheme.as_str() {
+        // The manager runs the server itself, defaulting to the same binary as the manager
+        scheme if scheme.eq_ignore_ascii_case("manager") => {
+            let bin = bin.map(PathBuf::from).unwrap_or_else(|| {
+                std::env::current_exe().unwrap_or_else(|_| PathBuf::from("distant"))
+            });
+            match which::which(&bin) {
+                Ok(path) => Check::ok(format!("Launch target {destination} runs {path:?}")),
+                Err(x) => Check::failed(
+                    format!("Launch target {destination} cannot find {bin:?}: {x}"),
+                    hint,
+                ),
+            }
+        }
+        scheme if scheme.eq_ignore_ascii_case("ssh") => {
+            check_ssh_launch_target(destination, connection.options, bin, hint).await
+        }
+        scheme => Check::skipped(format!(
+            "Launch target {destination} uses {scheme}, which cannot be checked"
+        )),
+    }
+}
+
+#[cfg(any(feature = "libssh", feature = "ssh2"))]
+async fn check_ssh_launch_target(
+    destination: distant_core::net::common::Destination,
+    options: distant_core::net::common::Map,
+    bin: Option<String>,
+    hint: &str,
+) -> Check {
+    use distant_core::data::Environment;
+    use distant_core::DistantChannelExt;
+
+    let bin = bin.unwrap_or_else(|| distant_ssh2::DistantLaunchOpts::default().binary);
+    let result = async {
+        let mut ssh = super::manager::handlers::load_ssh(&destination, &options).await?;
+        ssh.authenticate(distant_ssh2::LocalSshAuthHandler).await?;
+        let client = ssh.into_distant_client().await?;
+        client
+            .clone_channel()
+            .output(format!("{bin} --version"), Environment::new(), None, None)
+            .await
+    };
+
+    match result.await {
+        Ok(output) if output.success => Check::ok(format!(
+            "Launch target {destination} runs {bin:?} ({})",
+            String::from_utf8_lossy(&output.stdout).trim()
+        )),
+        Ok(output) => Check::failed(
+            format!(
+                "Launch target {destination} failed to run {bin:?}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            hint,
+        ),
+        Err(x) => Check::failed(
+            format!("Failed to reach launch target {destination} over ssh: {x}"),
+            "Make sure that `ssh` to it works without distant, such as by adding your key to \
+            its authorized keys",
+        ),
+    }
+}
+
+#[cfg(not(any(feature = "libssh", feature = "ssh2")))]
+async fn check_ssh_launch_target(
+    destination: distant_core::net::common::Destination,
+    _options: distant_core::net::common::Map,
+    _bin: Option<String>,
+    _hint: &str,
+) -> Check {
+    Check::skipped(format!(
+        "Launch target {destination} uses ssh, which this build of distant does not support"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn check_should_print_status_with_indented_details() {
+        assert_eq!(Check::ok("Good").to_string(), "[ok]   Good");
+        assert_eq!(
+            Check::failed("Bad\n  at line 1", "Fix it").to_string(),
+            "[fail] Bad\n         at line 1\n       Fix it"
+        );
+    }
+
+    #[test]
+    fn check_config_files_should_report_invalid_files_and_settings() {
+        let temp = assert_fs::TempDir::new().unwrap();
+
+        let file = temp.child("config.toml");
+        file.write_str(Config::default_raw_str()).unwrap();
+        let statuses = |path| {
+            check_config_files(Some(path))
+                .into_iter()
+                .map(|check| check.status)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(statuses(file.path()), vec![Status::Ok, Status::Ok]);
+
+        file.write_str("[client").unwrap();
+        assert_eq!(statuses(file.path()), vec![Status::Failed]);
+
+        file.write_str("[client]\nlog_level = 5").unwrap();
+        assert_eq!(statuses(file.path()), vec![Status::Ok, Status::Failed]);
+
+        assert_eq!(
+            statuses(temp.child("missing.toml").path()),
+            vec![Status::Failed]
+        );
+    }
+}
@@ -1,11 +1,14 @@
-use crate::options::{Config, GenerateSubcommand};
+use crate::options::{Config, EditorKind, GenerateSubcommand, NetworkSettings};
 use crate::{CliResult, Options};
 use anyhow::Context;
 use clap::CommandFactory;
 use clap_complete::generate as clap_generate;
+use clap_complete::Shell;
 use distant_core::net::common::{Request, Response};
 use distant_core::{DistantMsg, DistantRequestData, DistantResponseData};
-use std::{fs, io};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 pub fn run(cmd: GenerateSubcommand) -> CliResult {
     let rt = tokio::runtime::Runtime::new().context("Failed to start up runtime")?;
@@ -51,22 +54,261 @@ async fn async_run(cmd: GenerateSubcommand) -> CliResult {
             let name = "distant";
             let mut cmd = Options::command();
 
-            if let Some(path) = file {
-                clap_generate(
-                    shell,
-                    &mut cmd,
-                    name,
-                    &mut fs::OpenOptions::new()
+            let mut out: Box<dyn Write> = if let Some(path) = file {
+                Box::new(
+                    fs::OpenOptions::new()
                         .create(true)
                         .write(true)
                         .open(&path)
                         .with_context(|| format!("Failed to open {path:?}"))?,
                 )
             } else {
-                clap_generate(shell, &mut cmd, name, &mut io::stdout())
+                Box::new(io::stdout())
+            };
+
+            clap_generate(shell, &mut cmd, name, &mut out);
+
+            if let Some(script) = dynamic_completion_script(shell) {
+                out.write_all(script.as_bytes())
+                    .context("Failed to write dynamic completion")?;
+            }
+        }
+
+        GenerateSubcommand::EditorConfig {
+            kind,
+            file,
+            profile,
+            network,
+        } => {
+            let descriptor = EditorDescriptor {
+                bin: std::env::current_exe().context("Failed to find path to distant binary")?,
+                manager: ManagerAddress::from_network(&network),
+                profile,
+            };
+            let out = descriptor.render(kind);
+
+            if let Some(path) = file {
+                tokio::fs::write(&path, out)
+                    .await
+                    .with_context(|| format!("Failed to write editor config to {path:?}"))?;
+            } else {
+                print!("{out}");
             }
         }
     }
 
     Ok(())
 }
+
+/// Returns the script appended to the completion of `shell` that completes connection ids for
+/// `distant manager select` and remote paths for `distant fs` using `distant __complete`, or none
+/// if the shell only supports the static completion generated by clap
+fn dynamic_completion_script(shell: Shell) -> Option<&'static str> {
+    match shell {
+        Shell::Bash => Some(indoc::indoc! {r#"
+
+            _distant_dynamic() {
+                local cur="${COMP_WORDS[COMP_CWORD]}"
+                if [[ "${cur}" != -* ]]; then
+                    if [[ ${COMP_CWORD} -eq 3 && "${COMP_WORDS[1]}" == "manager" && "${COMP_WORDS[2]}" == "select" ]]; then
+                        COMPREPLY=( $("${COMP_WORDS[0]}" __complete connections -- "${cur}" 2>/dev/null | cut -f1) )
+                        return 0
+                    elif [[ ${COMP_CWORD} -ge 3 && "${COMP_WORDS[1]}" == "fs" ]]; then
+                        local IFS=$'\n'
+                        COMPREPLY=( $("${COMP_WORDS[0]}" __complete paths -- "${cur}" 2>/dev/null) )
+                        if [[ ${#COMPREPLY[@]} -eq 1 && "${COMPREPLY[0]}" == */ ]]; then
+                            compopt -o nospace
+                        fi
+                        return 0
+                    fi
+                fi
+                _distant "$@"
+            }
+
+            if [[ "${BASH_VERSINFO[0]}" -eq 4 && "${BASH_VERSINFO[1]}" -ge 4 || "${BASH_VERSINFO[0]}" -gt 4 ]]; then
+                complete -F _distant_dynamic -o nosort -o bashdefault -o default distant
+            else
+                complete -F _distant_dynamic -o bashdefault -o default distant
+            fi
+        "#}),
+        Shell::Fish => Some(indoc::indoc! {r#"
+
+            function __fish_distant_complete
+                set -l cmd (commandline -opc)
+                $cmd[1] __complete $argv -- (commandline -ct) 2>/dev/null
+            end
+
+            complete -c distant -n "__fish_seen_subcommand_from manager; and __fish_seen_subcommand_from select" -f -a "(__fish_distant_complete connections)"
+            complete -c distant -n "__fish_seen_subcommand_from fs; and test (count (commandline -opc)) -ge 3" -f -a "(__fish_distant_complete paths)"
+        "#}),
+        _ => None,
+    }
+}
+
+/// Where an editor plugin finds the manager
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ManagerAddress {
+    UnixSocket(PathBuf),
+    WindowsPipe(String),
+}
+
+impl ManagerAddress {
+    /// Returns where the client looks for the manager, picking the unix socket of a manager that
+    /// is listening right now when there is more than one place to look, and otherwise where
+    /// `distant manager listen` puts it by default
+    fn from_network(network: &NetworkSettings) -> Self {
+        if cfg!(windows) {
+            let candidates = network.to_windows_pipe_name_candidates();
+            Self::WindowsPipe(candidates.last().copied().unwrap_or_default().to_string())
+        } else {
+            let candidates = network.to_unix_socket_path_candidates();
+            let path = candidates
+                .iter()
+                .find(|path| path.exists())
+                .or(candidates.last())
+                .copied()
+                .unwrap_or(Path::new(""));
+            Self::UnixSocket(path.to_path_buf())
+        }
+    }
+}
+
+/// Settings an editor plugin uses to reach distant
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct EditorDescriptor {
+    bin: PathBuf,
+    manager: ManagerAddress,
+    profile: Option<String>,
+}
+
+impl EditorDescriptor {
+    /// Renders the settings in the form expected by the plugin of the `kind` of editor
+    fn render(&self, kind: EditorKind) -> String {
+        match kind {
+            EditorKind::Vscode => self.render_vscode(),
+            EditorKind::Nvim => self.render_nvim(),
+        }
+    }
+
+    /// Renders a snippet of `settings.json`
+    fn render_vscode(&self) -> String {
+        let mut settings = serde_json::Map::new();
+        settings.insert("distant.bin".to_string(), self.bin.to_string_lossy().into());
+        match &self.manager {
+            ManagerAddress::UnixSocket(path) => {
+                settings.insert(
+                    "distant.unixSocket".to_string(),
+                    path.to_string_lossy().into(),
+                );
+            }
+            ManagerAddress::WindowsPipe(name) => {
+                settings.insert("distant.windowsPipe".to_string(), name.as_str().into());
+            }
+        }
+        if let Some(profile) = self.profile.as_deref() {
+            settings.insert("distant.profile".to_string(), profile.into());
+        }
+
+        let mut out = serde_json::to_string_pretty(&serde_json::Value::Object(settings))
+            .expect("Settings are always valid JSON");
+        out.push('\n');
+        out
+    }
+
+    /// Renders a snippet of `init.lua` that sets up `distant.nvim`
+    fn render_nvim(&self) -> String {
+        let manager = match &self.manager {
+            ManagerAddress::UnixSocket(path) => {
+                format!("unix_socket = {}", lua_string(&path.to_string_lossy()))
+            }
+            ManagerAddress::WindowsPipe(name) => format!("windows_pipe = {}", lua_string(name)),
+        };
+
+        let mut out = String::from("require('distant'):setup({\n");
+        out.push_str(&format!(
+            "    client = {{ bin = {} }},\n",
+            lua_string(&self.bin.to_string_lossy())
+        ));
+        out.push_str(&format!("    manager = {{ {manager} }},\n"));
+        if let Some(profile) = self.profile.as_deref() {
+            out.push_str(&format!("    profile = {},\n", lua_string(profile)));
+        }
+        out.push_str("})\n");
+        out
+    }
+}
+
+/// Quotes `s` as a Lua string literal
+fn lua_string(s: &str) -> String {
+    let mut out = String::from("'");
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\'' => out.push_str("\\'"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('\'');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor(profile: Option<&str>) -> EditorDescriptor {
+        EditorDescriptor {
+            bin: PathBuf::from("/usr/bin/distant"),
+            manager: ManagerAddress::UnixSocket(PathBuf::from("/run/user/1000/user.distant.sock")),
+            profile: profile.map(ToString::to_string),
+        }
+    }
+
+    #[test]
+    fn render_should_produce_vscode_settings() {
+        assert_eq!(
+            descriptor(Some("prod")).render(EditorKind::Vscode),
+            indoc::indoc! {r#"
+                {
+                  "distant.bin": "/usr/bin/distant",
+                  "distant.profile": "prod",
+                  "distant.unixSocket": "/run/user/1000/user.distant.sock"
+                }
+            "#}
+        );
+    }
+
+    #[test]
+    fn render_should_produce_nvim_setup() {
+        assert_eq!(
+            descriptor(Some("prod")).render(EditorKind::Nvim),
+            indoc::indoc! {r#"
+                require('distant'):setup({
+                    client = { bin = '/usr/bin/distant' },
+                    manager = { unix_socket = '/run/user/1000/user.distant.sock' },
+                    profile = 'prod',
+                })
+            "#}
+        );
+    }
+
+    #[test]
+    fn render_should_leave_out_profile_if_none_selected() {
+        let descriptor = EditorDescriptor {
+            manager: ManagerAddress::WindowsPipe(String::from("distant")),
+            ..descriptor(None)
+        };
+
+        assert!(!descriptor.render(EditorKind::Vscode).contains("profile"));
+        assert!(!descriptor.render(EditorKind::Nvim).contains("profile"));
+        assert!(descriptor
+            .render(EditorKind::Nvim)
+            .contains("manager = { windows_pipe = 'distant' },"));
+    }
+
+    #[test]
+    fn lua_string_should_escape_quotes_and_backslashes() {
+        assert_eq!(lua_string(r"C:\it's"), r"'C:\\it\'s'");
+    }
+}
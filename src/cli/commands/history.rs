@@ -0,0 +1,67 @@
+use crate::cli::common::History;
+use crate::options::Format;
+use crate::CliResult;
+use anyhow::Context;
+use std::path::PathBuf;
+
+/// Prints the entries of the local history that use `connection` and whose command contains
+/// `grep`, oldest first
+pub fn run(
+    connection: Option<String>,
+    grep: Option<String>,
+    history_file: Option<PathBuf>,
+    format: Format,
+) -> CliResult {
+    let rt = tokio::runtime::Runtime::new().context("Failed to start up runtime")?;
+    let entries = rt
+        .block_on(History::new(history_file).read())
+        .context("Failed to read history")?
+        .into_iter()
+        .filter(|entry| match connection.as_deref() {
+            Some(connection) => entry.is_connection(connection),
+            None => true,
+        })
+        .filter(|entry| match grep.as_deref() {
+            Some(text) => entry.is_match(text),
+            None => true,
+        });
+
+    match format {
+        Format::Shell => {
+            for entry in entries {
+                let mut line = format!(
+                    "{} {:<7} [{}] {}",
+                    entry.started().format("%Y-%m-%d %H:%M:%S"),
+                    entry.kind.as_str(),
+                    entry.connection,
+                    entry
+                        .name
+                        .as_deref()
+                        .or(entry.destination.as_deref())
+                        .unwrap_or("-"),
+                );
+
+                if let Some(command) = entry.command.as_deref() {
+                    line.push_str(&format!(": {command}"));
+                }
+
+                line.push_str(&format!(" ({}", entry.describe_duration()));
+                if let Some(code) = entry.exit_code {
+                    line.push_str(&format!(", exit {code}"));
+                }
+                line.push(')');
+
+                println!("{line}");
+            }
+        }
+        Format::Json => {
+            let entries: Vec<_> = entries.collect();
+            println!(
+                "{}",
+                serde_json::to_string(&entries).context("Failed to serialize history")?
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -1,13 +1,18 @@
-use crate::cli::common::{MsgReceiver, MsgSender};
+use super::common::{print_clients, OutputPolicy};
+use crate::cli::common::{take_activated_socket, ActivatedSocket, MsgReceiver, MsgSender};
 use crate::cli::{Cache, Client, Manager};
-use crate::options::{Format, ManagerServiceSubcommand, ManagerSubcommand, NetworkSettings};
+use crate::options::{
+    ConnectionSelector, Format, ManagerServiceSubcommand, ManagerSubcommand, NetworkSettings,
+};
 use crate::{CliError, CliResult};
 use anyhow::Context;
 use dialoguer::{console::Term, theme::ColorfulTheme, Select};
-use distant_core::net::common::ConnectionId;
+use distant_core::data::{DistantMsg, DistantRequestData, DistantResponseData};
+use distant_core::net::common::{ConnectionId, UntypedRequest, UntypedResponse};
 use distant_core::net::manager::{
-    Config as NetManagerConfig, ConnectHandler, LaunchHandler, ManagerClient,
+    CachePolicy, Config as NetManagerConfig, ConnectHandler, LaunchHandler, ManagerClient,
 };
+use distant_core::DistantChannelExt;
 use log::*;
 use once_cell::sync::Lazy;
 use serde_json::{json, Value};
@@ -15,8 +20,8 @@ use service_manager::{
     ServiceInstallCtx, ServiceLabel, ServiceLevel, ServiceManager, ServiceStartCtx, ServiceStopCtx,
     ServiceUninstallCtx,
 };
-use std::{collections::HashMap, ffi::OsString, path::PathBuf};
-use tabled::{Table, Tabled};
+use std::{collections::HashMap, ffi::OsString, path::PathBuf, sync::Arc};
+use tabled::Tabled;
 
 /// [`ServiceLabel`] for our manager in the form `rocks.distant.manager`
 static SERVICE_LABEL: Lazy<ServiceLabel> = Lazy::new(|| ServiceLabel {
@@ -25,14 +30,19 @@ static SERVICE_LABEL: Lazy<ServiceLabel> = Lazy::new(|| ServiceLabel {
     application: String::from("manager"),
 });
 
-mod handlers;
+mod autostart;
+pub(crate) mod handlers;
 
 pub fn run(cmd: ManagerSubcommand) -> CliResult {
     match &cmd {
         ManagerSubcommand::Listen { daemon, .. } if *daemon => run_daemon(cmd),
         _ => {
+            let activated = match &cmd {
+                ManagerSubcommand::Listen { .. } => take_activated_socket()?,
+                _ => None,
+            };
             let rt = tokio::runtime::Runtime::new().context("Failed to start up runtime")?;
-            rt.block_on(async_run(cmd))
+            rt.block_on(async_run(cmd, activated))
         }
     }
 }
@@ -53,8 +63,9 @@ fn run_daemon(cmd: ManagerSubcommand) -> CliResult {
     debug!("Forking process");
     match daemon(true, true) {
         Ok(Fork::Child) => {
+            let activated = take_activated_socket()?;
             let rt = tokio::runtime::Runtime::new().context("Failed to start up runtime")?;
-            rt.block_on(async { async_run(cmd).await })?;
+            rt.block_on(async { async_run(cmd, activated).await })?;
             Ok(())
         }
         Ok(Fork::Parent(pid)) => {
@@ -69,7 +80,8 @@ fn run_daemon(cmd: ManagerSubcommand) -> CliResult {
     }
 }
 
-async fn async_run(cmd: ManagerSubcommand) -> CliResult {
+/// Runs `cmd`, listening with the `activated` socket (taken before the runtime started) if any
+async fn async_run(cmd: ManagerSubcommand, activated: Option<ActivatedSocket>) -> CliResult {
     match cmd {
         ManagerSubcommand::Service(ManagerServiceSubcommand::Start { kind, user }) => {
             debug!("Starting manager service via {:?}", kind);
@@ -156,6 +168,7 @@ async fn async_run(cmd: ManagerSubcommand) -> CliResult {
             daemon: _daemon,
             network,
             user,
+            autostart,
         } => {
             let access = access.unwrap_or_default();
 
@@ -200,14 +213,25 @@ async fn async_run(cmd: ManagerSubcommand) -> CliResult {
 
                         handlers
                     },
+                    cacheable: Some(CachePolicy {
+                        request: Arc::new(is_cacheable),
+                        response: Arc::new(is_success),
+                        invalidates: Arc::new(changes_paths),
+                    }),
                     ..Default::default()
                 },
-                network,
+                network: network.clone(),
+                activated,
             }
             .listen()
             .await
             .context("Failed to start manager")?;
 
+            if !autostart.is_empty() {
+                debug!("Autostarting {} connection(s)", autostart.len());
+                autostart::spawn(network, autostart);
+            }
+
             // Let our server run to completion
             manager_ref
                 .as_ref()
@@ -246,12 +270,15 @@ async fn async_run(cmd: ManagerSubcommand) -> CliResult {
 
                     println!(
                         "{}",
-                        Table::new(caps.into_sorted_vec().into_iter().map(|cap| {
-                            CapabilityRow {
-                                kind: cap.kind,
-                                description: cap.description,
-                            }
-                        }))
+                        OutputPolicy::current().table(
+                            caps.into_sorted_vec().into_iter().map(|cap| {
+                                CapabilityRow {
+                                    kind: cap.kind,
+                                    description: cap.description,
+                                }
+                            }),
+                            |table| table
+                        )
                     );
                 }
             }
@@ -285,6 +312,7 @@ async fn async_run(cmd: ManagerSubcommand) -> CliResult {
                     #[derive(Tabled)]
                     struct InfoRow {
                         id: ConnectionId,
+                        name: String,
                         scheme: String,
                         host: String,
                         port: String,
@@ -292,17 +320,21 @@ async fn async_run(cmd: ManagerSubcommand) -> CliResult {
                     }
                     println!(
                         "{}",
-                        Table::new(vec![InfoRow {
-                            id: info.id,
-                            scheme: info.destination.scheme.unwrap_or_default(),
-                            host: info.destination.host.to_string(),
-                            port: info
-                                .destination
-                                .port
-                                .map(|x| x.to_string())
-                                .unwrap_or_default(),
-                            options: info.options.to_string()
-                        }])
+                        OutputPolicy::current().table(
+                            vec![InfoRow {
+                                id: info.id,
+                                name: info.name.unwrap_or_default(),
+                                scheme: info.destination.scheme.unwrap_or_default(),
+                                host: info.destination.host.to_string(),
+                                port: info
+                                    .destination
+                                    .port
+                                    .map(|x| x.to_string())
+                                    .unwrap_or_default(),
+                                options: info.options.to_string()
+                            }],
+                            |table| table
+                        )
                     );
                 }
             }
@@ -352,15 +384,128 @@ async fn async_run(cmd: ManagerSubcommand) -> CliResult {
 
                     println!(
                         "{}",
-                        Table::new(list.into_iter().map(|(id, destination)| {
-                            ListRow {
-                                selected: *selected == id,
-                                id,
-                                scheme: destination.scheme.unwrap_or_default(),
-                                host: destination.host.to_string(),
-                                port: destination.port.map(|x| x.to_string()).unwrap_or_default(),
-                            }
-                        }))
+                        OutputPolicy::current().table(
+                            list.into_iter().map(|(id, destination)| {
+                                ListRow {
+                                    selected: *selected == id,
+                                    id,
+                                    scheme: destination.scheme.unwrap_or_default(),
+                                    host: destination.host.to_string(),
+                                    port: destination
+                                        .port
+                                        .map(|x| x.to_string())
+                                        .unwrap_or_default(),
+                                }
+                            }),
+                            |table| table
+                        )
+                    );
+                }
+            }
+
+            Ok(())
+        }
+        ManagerSubcommand::Metrics { format, network } => {
+            debug!("Connecting to manager");
+            let mut client = connect_to_manager(format, network).await?;
+
+            debug!("Getting list of connections");
+            let list = client
+                .list()
+                .await
+                .context("Failed to get list of connections")?;
+            let mut ids: Vec<ConnectionId> = list.keys().copied().collect();
+            ids.sort_unstable();
+
+            let mut entries = Vec::new();
+            for id in ids {
+                let mut info = client
+                    .info(id)
+                    .await
+                    .with_context(|| format!("Failed to get info about connection {id}"))?;
+                info.destination.password = None;
+
+                debug!("Opening channel to connection {}", id);
+                let channel = client
+                    .open_raw_channel(id)
+                    .await
+                    .with_context(|| format!("Failed to open channel to connection {id}"))?;
+
+                // Servers that do not track what they write are still listed, just without
+                // metrics
+                let metrics = match channel.into_client().into_channel().metrics().await {
+                    Ok(metrics) => Some(metrics),
+                    Err(x) => {
+                        debug!("Failed to get metrics of connection {id}: {x}");
+                        None
+                    }
+                };
+                entries.push((info, metrics));
+            }
+
+            match format {
+                Format::Json => {
+                    let entries: Vec<Value> = entries
+                        .into_iter()
+                        .map(|(info, metrics)| {
+                            json!({
+                                "id": info.id,
+                                "name": info.name,
+                                "profile": info.options.get("profile"),
+                                "destination": info.destination.to_string(),
+                                "metrics": metrics,
+                            })
+                        })
+                        .collect();
+                    println!("{}", json!({"type": "metrics", "connections": entries}));
+                }
+                Format::Shell => {
+                    #[derive(Tabled)]
+                    struct MetricsRow {
+                        id: ConnectionId,
+                        name: String,
+                        profile: String,
+                        destination: String,
+                        written: String,
+                        quota: String,
+                    }
+
+                    for (info, metrics) in entries.iter() {
+                        if let Some(metrics) = metrics.as_ref().filter(|x| x.is_over_quota()) {
+                            eprintln!(
+                                "Connection {} has written {} bytes, over the soft quota of {} bytes",
+                                info.id,
+                                metrics.bytes_written,
+                                metrics.write_quota.unwrap_or_default()
+                            );
+                        }
+                    }
+
+                    println!(
+                        "{}",
+                        OutputPolicy::current().table(
+                            entries.into_iter().map(|(info, metrics)| {
+                                MetricsRow {
+                                    id: info.id,
+                                    name: info.name.unwrap_or_default(),
+                                    profile: info
+                                        .options
+                                        .get("profile")
+                                        .cloned()
+                                        .unwrap_or_default(),
+                                    destination: info.destination.to_string(),
+                                    written: metrics
+                                        .as_ref()
+                                        .map(|x| x.bytes_written.to_string())
+                                        .unwrap_or_else(|| String::from("unsupported")),
+                                    quota: metrics
+                                        .and_then(|x| x.write_quota)
+                                        .map(|x| x.to_string())
+                                        .unwrap_or_default(),
+                                }
+                            }),
+                            |table| table
+                        )
                     );
                 }
             }
@@ -389,6 +534,39 @@ async fn async_run(cmd: ManagerSubcommand) -> CliResult {
 
             Ok(())
         }
+        ManagerSubcommand::Clients {
+            format,
+            network,
+            disconnect,
+        } => {
+            debug!("Connecting to manager");
+            let mut client = connect_to_manager(format, network).await?;
+
+            match disconnect {
+                Some(id) => {
+                    debug!("Disconnecting client {}", id);
+                    client
+                        .disconnect_client(id)
+                        .await
+                        .with_context(|| format!("Failed to disconnect client {id}"))?;
+
+                    match format {
+                        Format::Json => println!("{}", json!({"type": "ok"})),
+                        Format::Shell => (),
+                    }
+                }
+                None => {
+                    debug!("Getting list of clients");
+                    let clients = client
+                        .clients()
+                        .await
+                        .context("Failed to get list of clients")?;
+                    print_clients(format, clients);
+                }
+            }
+
+            Ok(())
+        }
         ManagerSubcommand::Select {
             cache,
             connection,
@@ -400,11 +578,21 @@ async fn async_run(cmd: ManagerSubcommand) -> CliResult {
                 .context("Failed to look up cache")?;
 
             match connection {
-                Some(id) => {
+                Some(ConnectionSelector::Id(id)) => {
                     *cache.data.selected = id;
                     cache.write_to_disk().await?;
                     Ok(())
                 }
+                Some(ConnectionSelector::Name(name)) => {
+                    debug!("Connecting to manager");
+                    let mut client = connect_to_manager(format, network).await?;
+                    *cache.data.selected = client
+                        .resolve(name.as_str())
+                        .await
+                        .with_context(|| format!("Failed to find connection named {name}"))?;
+                    cache.write_to_disk().await?;
+                    Ok(())
+                }
                 None => {
                     debug!("Connecting to manager");
                     let mut client = connect_to_manager(format, network).await?;
@@ -537,3 +725,54 @@ async fn connect_to_manager(
             .context("Failed to connect to manager")?,
     })
 }
+
+/// Returns true if `req` asks for information about a server that stays the same while connected
+/// to it, which the manager can answer itself for every client sharing the connection
+fn is_cacheable(req: &UntypedRequest<'_>) -> bool {
+    // NOTE: Requests like these are tiny, so skip decoding the payloads of everything else
+    if req.payload.len() > 64 {
+        return false;
+    }
+
+    matches!(
+        req.to_typed_request::<DistantMsg<DistantRequestData>>()
+            .map(|req| req.payload),
+        Ok(DistantMsg::Single(
+            DistantRequestData::Capabilities {} | DistantRequestData::SystemInfo {}
+        ))
+    )
+}
+
+/// Returns true if `res` is not an error, which may not happen again when the request is repeated
+fn is_success(res: &UntypedResponse<'_>) -> bool {
+    !matches!(
+        res.to_typed_response::<DistantMsg<DistantResponseData>>()
+            .map(|res| res.payload),
+        Ok(DistantMsg::Single(DistantResponseData::Error(_))) | Err(_)
+    )
+}
+
+/// Returns true if `req` changes the paths that the server reports, such as the current directory
+/// within its system information
+fn changes_paths(req: &UntypedRequest<'_>) -> bool {
+    // NOTE: Mappings and roots are small, so skip decoding the payloads of large requests such as
+    //       those writing files
+    if req.payload.len() > 64 * 1024 {
+        return false;
+    }
+
+    let changes = |data: &DistantRequestData| {
+        matches!(
+            data,
+            DistantRequestData::PathMap { .. } | DistantRequestData::Roots { .. }
+        )
+    };
+    match req
+        .to_typed_request::<DistantMsg<DistantRequestData>>()
+        .map(|req| req.payload)
+    {
+        Ok(DistantMsg::Single(data)) => changes(&data),
+        Ok(DistantMsg::Batch(list)) => list.iter().any(changes),
+        Err(_) => false,
+    }
+}
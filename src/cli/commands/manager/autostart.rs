@@ -0,0 +1,123 @@
+use crate::cli::Client;
+use crate::options::{ManagerAutostartConnection, NetworkSettings};
+use anyhow::Context;
+use chrono::Local;
+use distant_core::net::common::authentication::DummyAuthHandler;
+use distant_core::net::common::{ConnectionId, Host, Map};
+use log::*;
+
+/// Spawns a background task for each autostart connection that establishes the connection
+/// through the manager listening on `network`, either once at startup or repeatedly on the
+/// connection's schedule
+pub fn spawn(network: NetworkSettings, connections: Vec<ManagerAutostartConnection>) {
+    for connection in connections {
+        let network = network.clone();
+        tokio::spawn(async move {
+            let destination = connection.destination.to_string();
+            match connection.schedule {
+                None => {
+                    if let Err(x) = establish(network, connection).await {
+                        error!("Autostart of {destination} failed: {x:#}");
+                    }
+                }
+                Some(schedule) => {
+                    let mut last_id = None;
+                    loop {
+                        let now = Local::now();
+                        let next = match schedule.next_after(&now) {
+                            Some(next) => next,
+                            None => {
+                                error!("Autostart of {destination} has no upcoming time for {schedule}");
+                                break;
+                            }
+                        };
+
+                        debug!("Autostart of {destination} scheduled for {next}");
+                        let delay = (next - now).to_std().unwrap_or_default();
+                        tokio::time::sleep(delay).await;
+
+                        // Skip warming up the connection if it is still alive from last time
+                        if let Some(id) = last_id {
+                            if is_alive(network.clone(), id).await {
+                                debug!("Autostart of {destination} skipped as {id} is active");
+                                continue;
+                            }
+                        }
+
+                        match establish(network.clone(), connection.clone()).await {
+                            Ok(id) => last_id = Some(id),
+                            Err(x) => error!("Autostart of {destination} failed: {x:#}"),
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Returns true if the manager still has a connection with the given `id`
+async fn is_alive(network: NetworkSettings, id: ConnectionId) -> bool {
+    match Client::new(network)
+        .using_prompt_auth_handler()
+        .connect()
+        .await
+    {
+        Ok(mut client) => client.info(id).await.is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Launches (if configured) and connects to the destination of `connection`, returning the id
+/// of the new connection held by the manager
+async fn establish(
+    network: NetworkSettings,
+    connection: ManagerAutostartConnection,
+) -> anyhow::Result<ConnectionId> {
+    let ManagerAutostartConnection {
+        mut destination,
+        launch,
+        options,
+        ..
+    } = connection;
+
+    let mut client = Client::new(network)
+        .using_prompt_auth_handler()
+        .connect()
+        .await
+        .context("Failed to connect to manager")?;
+
+    // Autostart runs without a user present, so any authentication challenge fails
+    let id = if launch {
+        let host = destination.host.to_string();
+        if destination.scheme.is_none() {
+            destination.scheme = Some("ssh".to_string());
+        }
+
+        debug!("Autostart launching {destination}");
+        let mut new_destination = client
+            .launch(destination, options, DummyAuthHandler)
+            .await
+            .context("Failed to launch server")?;
+
+        if !new_destination.host.is_global() {
+            new_destination.host = host
+                .parse::<Host>()
+                .map_err(|x| anyhow::anyhow!(x))
+                .context("Failed to replace host")?;
+        }
+
+        client
+            .connect(new_destination, Map::new(), DummyAuthHandler)
+            .await
+            .context("Failed to connect to launched server")?
+    } else {
+        debug!("Autostart connecting to {destination}");
+        client
+            .connect(destination, options, DummyAuthHandler)
+            .await
+            .context("Failed to connect to server")?
+    };
+
+    info!("Autostart established connection {id}");
+    Ok(id)
+}
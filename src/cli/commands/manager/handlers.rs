@@ -1,15 +1,19 @@
-use crate::options::{BindAddress, ClientLaunchConfig};
+use crate::options::{AddressFamily, BindAddress, ClientLaunchConfig, ClientNetworkConfig};
 use async_trait::async_trait;
-use distant_core::net::client::{Client, ClientConfig, ReconnectStrategy, UntypedClient};
+use distant_core::net::client::{
+    Client, ClientConfig, Connector, ProxyConnector, ReconnectStrategy, TcpConnector, UntypedClient,
+};
 use distant_core::net::common::authentication::msg::*;
 use distant_core::net::common::authentication::{
-    AuthHandler, Authenticator, DynAuthHandler, ProxyAuthHandler, SingleAuthHandler,
+    AuthHandler, AuthMethodHandler, Authenticator, DynAuthHandler, ProxyAuthHandler,
     StaticKeyAuthMethodHandler,
 };
-use distant_core::net::common::{Destination, Map, SecretKey32};
+use distant_core::net::common::{Destination, Map, SecretKey32, SocketOptions};
 use distant_core::net::manager::{ConnectHandler, LaunchHandler};
 use log::*;
 use std::{
+    collections::HashSet,
+    future::Future,
     io,
     net::{IpAddr, SocketAddr},
     path::PathBuf,
@@ -199,7 +203,7 @@ impl LaunchHandler for SshLaunchHandler {
         let config = ClientLaunchConfig::from(options.clone());
 
         use distant_ssh2::DistantLaunchOpts;
-        let mut ssh = load_ssh(destination, options)?;
+        let mut ssh = load_ssh(destination, options).await?;
         let handler = AuthClientSshAuthHandler::new(authenticator);
         let _ = ssh.authenticate(handler).await?;
         let opts = {
@@ -228,40 +232,158 @@ impl DistantConnectHandler {
     async fn try_connect(
         ips: Vec<IpAddr>,
         port: u16,
-        mut auth_handler: impl AuthHandler,
+        socket: SocketOptions,
+        config: ClientConfig,
+        auth_handler: &mut dyn AuthHandler,
     ) -> io::Result<UntypedClient> {
-        // Try each IP address with the same port to see if one works
-        let mut err = None;
-        for ip in ips {
-            let addr = SocketAddr::new(ip, port);
-            debug!("Attempting to connect to distant server @ {}", addr);
-
-            match Client::tcp(addr)
-                .auth_handler(DynAuthHandler::from(&mut auth_handler))
-                .config(ClientConfig {
-                    reconnect_strategy: ReconnectStrategy::ExponentialBackoff {
-                        base: Duration::from_secs(1),
-                        factor: 2.0,
-                        max_duration: Some(Duration::from_secs(10)),
-                        max_retries: None,
-                        timeout: None,
-                    },
-                    ..Default::default()
-                })
-                .connect_timeout(Duration::from_secs(180))
-                .connect_untyped()
-                .await
-            {
-                Ok(client) => return Ok(client),
-                Err(x) => err = Some(x),
-            }
+        // Every address is handed to the connector, which races them against one another rather
+        // than waiting on each in turn
+        let addrs: Vec<SocketAddr> = ips
+            .into_iter()
+            .map(|ip| SocketAddr::new(ip, port))
+            .collect();
+        debug!("Attempting to connect to distant server @ {addrs:?}");
+
+        let connector = TcpConnector::new(addrs.as_slice()).socket_options(socket);
+        Self::connect_with(connector, config, auth_handler).await
+    }
+
+    async fn connect_with(
+        connector: impl Connector,
+        config: ClientConfig,
+        auth_handler: &mut dyn AuthHandler,
+    ) -> io::Result<UntypedClient> {
+        Client::build()
+            .connector(connector)
+            .auth_handler(DynAuthHandler::new(auth_handler))
+            .config(config)
+            .connect_timeout(Duration::from_secs(180))
+            .connect_untyped()
+            .await
+    }
+
+    /// Configures clients to reconnect with a growing delay once the server has gone quiet for
+    /// longer than the heartbeat timeout of `network`
+    fn client_config(network: &ClientNetworkConfig) -> ClientConfig {
+        let config = ClientConfig::default();
+        ClientConfig {
+            reconnect_strategy: ReconnectStrategy::ExponentialBackoff {
+                base: Duration::from_secs(1),
+                factor: 2.0,
+                max_duration: Some(Duration::from_secs(10)),
+                max_retries: None,
+                timeout: None,
+            },
+            silence_duration: network
+                .heartbeat_timeout
+                .map(Duration::from_secs)
+                .unwrap_or(config.silence_duration),
+            ..config
         }
+    }
+}
+
+/// Resolves `host` into the IP addresses to attempt connections to, consulting the static mapping
+/// of `network` before falling back to DNS
+async fn resolve_host(
+    host: &str,
+    port: u16,
+    network: &ClientNetworkConfig,
+) -> io::Result<Vec<IpAddr>> {
+    if let Some((_, ip)) = network
+        .resolve
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(host))
+    {
+        debug!("Resolved host {host} to {ip} using static mapping");
+        return Ok(vec![*ip]);
+    }
+
+    debug!("Looking up host {host} @ port {port}");
+    let lookup = tokio::net::lookup_host(format!("{host}:{port}"));
+    let timeout = network
+        .dns_timeout
+        .and_then(|secs| Duration::try_from_secs_f32(secs).ok());
+    let mut candidate_ips = with_dns_timeout(host, timeout, lookup)
+        .await
+        .map_err(|x| {
+            io::Error::new(
+                x.kind(),
+                format!("{host} needs to be resolvable outside of ssh: {x}"),
+            )
+        })?
+        .map(|addr| addr.ip())
+        .collect::<Vec<IpAddr>>();
+
+    // Keep the order given by the resolver, which already sorts addresses by preference
+    let mut seen = HashSet::new();
+    candidate_ips.retain(|ip| seen.insert(*ip));
+    if candidate_ips.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrNotAvailable,
+            format!("Unable to resolve {host}:{port}"),
+        ));
+    }
 
-        // If all failed, return the last error we got
-        Err(err.expect("Err set above"))
+    if let Some(family) = network.address_family {
+        prefer_family(&mut candidate_ips, family);
+    }
+
+    Ok(candidate_ips)
+}
+
+/// Waits on the `lookup` of `host` for up to `timeout` (if provided)
+async fn with_dns_timeout<T>(
+    host: &str,
+    timeout: Option<Duration>,
+    lookup: impl Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, lookup).await.map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("Timed out resolving {host} after {timeout:?}"),
+            )
+        })?,
+        None => lookup.await,
     }
 }
 
+/// Moves addresses of the preferred `family` to the front, otherwise keeping their order
+fn prefer_family(ips: &mut [IpAddr], family: AddressFamily) {
+    ips.sort_by_key(|ip| !family.contains(ip));
+}
+
+/// Resolves the host of an ssh `destination` using the network settings within `options` when
+/// any of them (a static mapping of the host, a DNS timeout, or an address family) apply,
+/// returning the address for ssh to connect to rather than leaving ssh to resolve the host
+///
+/// Nothing is resolved when a proxy command is used, as it is what reaches the host.
+#[cfg(any(feature = "libssh", feature = "ssh2"))]
+async fn resolve_ssh_host(
+    destination: &Destination,
+    options: &Map,
+    has_proxy_command: bool,
+) -> io::Result<Option<IpAddr>> {
+    let network = ClientNetworkConfig::from(options.clone());
+    let host = destination.host.to_string();
+    let is_mapped = network
+        .resolve
+        .keys()
+        .any(|name| name.eq_ignore_ascii_case(&host));
+    if has_proxy_command
+        || (!is_mapped && network.dns_timeout.is_none() && network.address_family.is_none())
+    {
+        return Ok(None);
+    }
+
+    let port = destination.port.unwrap_or(22);
+    Ok(resolve_host(&host, port, &network)
+        .await?
+        .into_iter()
+        .next())
+}
+
 #[async_trait]
 impl ConnectHandler for DistantConnectHandler {
     async fn connect(
@@ -274,44 +396,83 @@ impl ConnectHandler for DistantConnectHandler {
         let host = destination.host.to_string();
         let port = destination.port.ok_or_else(|| missing("port"))?;
 
-        debug!("Looking up host {host} @ port {port}");
-        let mut candidate_ips = tokio::net::lookup_host(format!("{host}:{port}"))
-            .await
-            .map_err(|x| {
-                io::Error::new(
-                    x.kind(),
-                    format!("{host} needs to be resolvable outside of ssh: {x}"),
-                )
-            })?
-            .map(|addr| addr.ip())
-            .collect::<Vec<IpAddr>>();
-        candidate_ips.sort_unstable();
-        candidate_ips.dedup();
-        if candidate_ips.is_empty() {
-            return Err(io::Error::new(
-                io::ErrorKind::AddrNotAvailable,
-                format!("Unable to resolve {host}:{port}"),
-            ));
-        }
+        // Network options are provided by the client alongside any other options
+        let network = ClientNetworkConfig::from(options.clone());
+        let socket = SocketOptions::from(network.socket.clone());
+        let config = Self::client_config(&network);
 
         // For legacy reasons, we need to support a static key being provided
         // via part of the destination OR an option, and attempt to use it
         // during authentication if it is provided
-        if let Some(key) = destination
+        let key = destination
             .password
             .as_deref()
             .or_else(|| options.get("key").map(|s| s.as_str()))
-        {
-            let key = key.parse::<SecretKey32>().map_err(|_| invalid("key"))?;
-            Self::try_connect(
-                candidate_ips,
-                port,
-                SingleAuthHandler::new(StaticKeyAuthMethodHandler::simple(key)),
-            )
-            .await
-        } else {
-            Self::try_connect(candidate_ips, port, ProxyAuthHandler::new(authenticator)).await
+            .map(|key| key.parse::<SecretKey32>().map_err(|_| invalid("key")))
+            .transpose()?;
+        let mut static_key_handler;
+        let mut proxy_handler;
+        let auth_handler: &mut dyn AuthHandler = match key {
+            Some(key) => {
+                static_key_handler = StaticKeyAuthHandler::new(key, authenticator);
+                &mut static_key_handler
+            }
+            None => {
+                proxy_handler = ProxyAuthHandler::new(authenticator);
+                &mut proxy_handler
+            }
+        };
+
+        // When going through a proxy, the proxy is responsible for resolving the host
+        if let Some(proxy) = network.proxy.clone() {
+            debug!("Attempting to connect to distant server @ {host}:{port} via {proxy}");
+            let connector = ProxyConnector::new(proxy, host, port).socket_options(socket);
+            return Self::connect_with(connector, config, auth_handler).await;
         }
+
+        let candidate_ips = resolve_host(&host, port, &network).await?;
+        Self::try_connect(candidate_ips, port, socket, config, auth_handler).await
+    }
+}
+
+/// Answers challenges using a static key, while still passing along information and errors (such
+/// as the message of the day of the server) to the client being authenticated
+struct StaticKeyAuthHandler<'a> {
+    key: StaticKeyAuthMethodHandler,
+    authenticator: &'a mut dyn Authenticator,
+}
+
+impl<'a> StaticKeyAuthHandler<'a> {
+    pub fn new(key: SecretKey32, authenticator: &'a mut dyn Authenticator) -> Self {
+        Self {
+            key: StaticKeyAuthMethodHandler::simple(key),
+            authenticator,
+        }
+    }
+}
+
+#[async_trait]
+impl AuthHandler for StaticKeyAuthHandler<'_> {}
+
+#[async_trait]
+impl AuthMethodHandler for StaticKeyAuthHandler<'_> {
+    async fn on_challenge(&mut self, challenge: Challenge) -> io::Result<ChallengeResponse> {
+        self.key.on_challenge(challenge).await
+    }
+
+    async fn on_verification(
+        &mut self,
+        verification: Verification,
+    ) -> io::Result<VerificationResponse> {
+        self.key.on_verification(verification).await
+    }
+
+    async fn on_info(&mut self, info: Info) -> io::Result<()> {
+        self.authenticator.info(info).await
+    }
+
+    async fn on_error(&mut self, error: Error) -> io::Result<()> {
+        self.authenticator.error(error).await
     }
 }
 
@@ -329,7 +490,7 @@ impl ConnectHandler for SshConnectHandler {
         authenticator: &mut dyn Authenticator,
     ) -> io::Result<UntypedClient> {
         debug!("Handling connect of {destination} with options '{options}'");
-        let mut ssh = load_ssh(destination, options)?;
+        let mut ssh = load_ssh(destination, options).await?;
         let handler = AuthClientSshAuthHandler::new(authenticator);
         let _ = ssh.authenticate(handler).await?;
         Ok(ssh.into_distant_client().await?.into_untyped_client())
@@ -420,13 +581,16 @@ impl<'a> distant_ssh2::SshAuthHandler for AuthClientSshAuthHandler<'a> {
 }
 
 #[cfg(any(feature = "libssh", feature = "ssh2"))]
-fn load_ssh(destination: &Destination, options: &Map) -> io::Result<distant_ssh2::Ssh> {
+pub(crate) async fn load_ssh(
+    destination: &Destination,
+    options: &Map,
+) -> io::Result<distant_ssh2::Ssh> {
     trace!("load_ssh({destination}, {options})");
     use distant_ssh2::{Ssh, SshOpts};
 
     let host = destination.host.to_string();
 
-    let opts = SshOpts {
+    let mut opts = SshOpts {
         backend: match options
             .get("backend")
             .or_else(|| options.get("ssh.backend"))
@@ -475,6 +639,103 @@ fn load_ssh(destination: &Destination, options: &Map) -> io::Result<distant_ssh2
         ..Default::default()
     };
 
+    if let Some(ip) = resolve_ssh_host(destination, options, opts.proxy_command.is_some()).await? {
+        debug!("Resolved host {host} to {ip} for ssh");
+        opts.other.insert("hostname".to_string(), ip.to_string());
+    }
+
     debug!("Connecting to {host} via ssh with {opts:?}");
     Ssh::connect(host, opts)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    fn network(f: impl FnOnce(&mut ClientNetworkConfig)) -> ClientNetworkConfig {
+        let mut network = ClientNetworkConfig::default();
+        f(&mut network);
+        network
+    }
+
+    #[test(tokio::test)]
+    async fn resolve_host_should_use_static_mapping_before_dns() {
+        let network = network(|x| {
+            x.resolve
+                .insert(String::from("Example.Invalid"), "10.1.2.3".parse().unwrap());
+        });
+
+        let ips = resolve_host("example.invalid", 22, &network).await.unwrap();
+        assert_eq!(ips, ["10.1.2.3".parse::<IpAddr>().unwrap()]);
+    }
+
+    #[test(tokio::test)]
+    async fn with_dns_timeout_should_fail_once_timeout_elapses() {
+        let timeout = Some(Duration::from_millis(10));
+
+        let err = with_dns_timeout("host", timeout, std::future::pending::<io::Result<()>>())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        let result = with_dns_timeout("host", timeout, async { Ok(123) }).await;
+        assert_eq!(result.unwrap(), 123);
+    }
+
+    #[test]
+    fn prefer_family_should_move_addresses_of_family_to_front_keeping_their_order() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let mut ips = [ip("10.0.0.1"), ip("::1"), ip("10.0.0.2"), ip("::2")];
+
+        prefer_family(&mut ips, AddressFamily::Ipv6);
+        assert_eq!(ips, [ip("::1"), ip("::2"), ip("10.0.0.1"), ip("10.0.0.2")]);
+
+        prefer_family(&mut ips, AddressFamily::Ipv4);
+        assert_eq!(ips, [ip("10.0.0.1"), ip("10.0.0.2"), ip("::1"), ip("::2")]);
+    }
+
+    #[cfg(any(feature = "libssh", feature = "ssh2"))]
+    #[test(tokio::test)]
+    async fn resolve_ssh_host_should_only_resolve_when_network_settings_apply() {
+        let destination: Destination = "ssh://example.invalid".parse().unwrap();
+
+        // Without settings, ssh resolves the host itself
+        let options = Map::from(ClientNetworkConfig::default());
+        assert_eq!(
+            resolve_ssh_host(&destination, &options, false)
+                .await
+                .unwrap(),
+            None
+        );
+
+        let options = Map::from(network(|x| {
+            x.resolve
+                .insert(String::from("example.invalid"), "10.1.2.3".parse().unwrap());
+        }));
+        assert_eq!(
+            resolve_ssh_host(&destination, &options, false)
+                .await
+                .unwrap(),
+            Some("10.1.2.3".parse().unwrap())
+        );
+
+        // A proxy command reaches the host itself
+        assert_eq!(
+            resolve_ssh_host(&destination, &options, true)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Other settings lead to the host being resolved by us as well
+        let destination: Destination = "ssh://127.0.0.1".parse().unwrap();
+        let options = Map::from(network(|x| x.address_family = Some(AddressFamily::Ipv4)));
+        assert_eq!(
+            resolve_ssh_host(&destination, &options, false)
+                .await
+                .unwrap(),
+            Some("127.0.0.1".parse().unwrap())
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use super::client::{read_cache, use_or_lookup_connection_id};
+use crate::cli::Client;
+use crate::constants::user::CACHE_FILE_PATH;
+use crate::options::{Config, NetworkSettings};
+use crate::{CliError, CliResult};
+use anyhow::Context;
+use distant_core::net::common::ConnectionId;
+use log::*;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Prefix of the executables in PATH that are run as subcommands
+const PLUGIN_PREFIX: &str = "distant-";
+
+/// Runs the plugin named by the first of `args` with the rest of them, passing along details
+/// about the manager and connection through environment variables
+pub fn run(args: &[OsString], config_path: Option<&Path>) -> CliResult {
+    let (name, args) = args.split_first().context("Missing subcommand")?;
+    let (cache, args) = split_cache(args)?;
+    let cache = cache.unwrap_or_else(|| CACHE_FILE_PATH.to_path_buf());
+    let program = format!("{PLUGIN_PREFIX}{}", name.to_string_lossy());
+    let path = which::which(&program).map_err(|_| {
+        anyhow::anyhow!("Unrecognized subcommand {name:?}, and no {program} was found in PATH")
+    })?;
+
+    let network = Config::load_multi(config_path.map(Path::to_path_buf))?
+        .client
+        .network;
+
+    let mut cmd = Command::new(&path);
+    cmd.args(args);
+
+    if let Ok(bin) = std::env::current_exe() {
+        cmd.env("DISTANT_BIN", bin);
+    }
+    cmd.env("DISTANT_CACHE", &cache);
+
+    #[cfg(unix)]
+    {
+        let candidates = network.to_unix_socket_path_candidates();
+        if let Some(socket) = candidates
+            .iter()
+            .find(|path| path.exists())
+            .or(candidates.first())
+        {
+            cmd.env("DISTANT_UNIX_SOCKET", socket);
+        }
+    }
+
+    #[cfg(windows)]
+    if let Some(pipe) = network.to_windows_pipe_name_candidates().first() {
+        cmd.env("DISTANT_WINDOWS_PIPE", pipe);
+    }
+
+    match lookup_connection_id(network, &cache) {
+        Ok(id) => {
+            cmd.env("DISTANT_CONNECTION", id.to_string());
+        }
+        Err(x) => debug!("Not passing a connection to {program}: {x:#}"),
+    }
+
+    // Plugins print in the same format as the rest of the CLI unless told otherwise
+    if std::env::var_os("DISTANT_FORMAT").is_none() {
+        cmd.env("DISTANT_FORMAT", "shell");
+    }
+
+    debug!("Running plugin {path:?} with {args:?}");
+    let status = cmd
+        .status()
+        .with_context(|| format!("Failed to run {path:?}"))?;
+
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(CliError::Exit(code as u8)),
+        None => Err(CliError::FAILURE),
+    }
+}
+
+/// Splits off the `--cache <PATH>` (or `--cache=<PATH>`) given ahead of the arguments of a plugin,
+/// which is taken by us rather than passed along to the plugin
+fn split_cache(args: &[OsString]) -> anyhow::Result<(Option<PathBuf>, &[OsString])> {
+    let Some((arg, rest)) = args.split_first() else {
+        return Ok((None, args));
+    };
+
+    if arg == "--cache" {
+        let (path, rest) = rest.split_first().context("Missing path for --cache")?;
+        return Ok((Some(PathBuf::from(path)), rest));
+    }
+
+    match arg.to_str().and_then(|arg| arg.strip_prefix("--cache=")) {
+        Some(path) => Ok((Some(PathBuf::from(path)), rest)),
+        None => Ok((None, args)),
+    }
+}
+
+/// Finds the connection the plugin would be expected to use, the same way client commands do
+fn lookup_connection_id(network: NetworkSettings, cache: &Path) -> anyhow::Result<ConnectionId> {
+    let rt = tokio::runtime::Runtime::new().context("Failed to start up runtime")?;
+    rt.block_on(async {
+        let mut client = Client::new(network)
+            .using_prompt_auth_handler()
+            .connect()
+            .await
+            .context("Failed to connect to manager")?;
+        let mut cache = read_cache(cache).await;
+        use_or_lookup_connection_id(&mut cache, None, &mut client).await
+    })
+}
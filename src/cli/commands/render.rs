@@ -0,0 +1,97 @@
+use super::common::Formatter;
+use crate::options::Format;
+use crate::CliResult;
+use anyhow::Context;
+use distant_core::net::common::Response;
+use distant_core::{DistantMsg, DistantResponseData};
+use serde_json::Value;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+/// Prints each response within `input` (or stdin) using `format`
+pub fn run(input: Option<PathBuf>, format: Format) -> CliResult {
+    let text = match input.as_ref() {
+        Some(path) => {
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {path:?}"))?
+        }
+        None => {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .context("Failed to read stdin")?;
+            text
+        }
+    };
+
+    let mut formatter = Formatter::new(format);
+    let mut stdout = io::stdout().lock();
+    let mut stderr = io::stderr().lock();
+    for res in parse_responses(&text)? {
+        formatter
+            .format(res)
+            .and_then(|output| output.write_to(&mut stdout, &mut stderr))
+            .context("Failed to render response")?;
+    }
+
+    Ok(())
+}
+
+/// Parses the responses within `text`, which is either a JSON array of response payloads or
+/// one payload after another, giving each an id of its position
+fn parse_responses(text: &str) -> anyhow::Result<Vec<Response<DistantMsg<DistantResponseData>>>> {
+    let mut payloads = Vec::new();
+    for value in serde_json::Deserializer::from_str(text).into_iter::<Value>() {
+        match value.context("Invalid JSON")? {
+            Value::Array(values) => payloads.extend(values),
+            value => payloads.push(value),
+        }
+    }
+
+    payloads
+        .into_iter()
+        .enumerate()
+        .map(|(i, payload)| {
+            let payload: DistantResponseData = serde_json::from_value(payload)
+                .with_context(|| format!("Invalid response at position {i}"))?;
+            Ok(Response {
+                id: i.to_string(),
+                origin_id: i.to_string(),
+                payload: DistantMsg::Single(payload),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_responses_should_support_arrays_and_streams_of_payloads() {
+        let array = r#"[{"type": "ok"}, {"type": "text", "data": "hello"}]"#;
+        let stream = "{\"type\": \"ok\"}\n{\"type\": \"text\", \"data\": \"hello\"}\n";
+
+        let expected = vec![
+            Response {
+                id: String::from("0"),
+                origin_id: String::from("0"),
+                payload: DistantMsg::Single(DistantResponseData::Ok),
+            },
+            Response {
+                id: String::from("1"),
+                origin_id: String::from("1"),
+                payload: DistantMsg::Single(DistantResponseData::Text {
+                    data: String::from("hello"),
+                }),
+            },
+        ];
+        assert_eq!(parse_responses(array).unwrap(), expected);
+        assert_eq!(parse_responses(stream).unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_responses_should_fail_if_payload_is_not_a_response() {
+        let err = parse_responses(r#"{"type": "ok"} {"type": "unknown"}"#).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid response at position 1");
+    }
+}
@@ -1,19 +1,38 @@
-use crate::options::ServerSubcommand;
+use super::client::{connect_to_manager, read_cache, use_or_lookup_connection_id};
+use super::common::print_clients;
+use crate::cli::common::{take_activated_socket, ActivatedSocket};
+use crate::options::{Format, ServerSubcommand};
 use crate::{CliError, CliResult};
 use anyhow::Context;
 use distant_core::net::common::authentication::Verifier;
-use distant_core::net::common::{Host, SecretKey32};
+use distant_core::net::common::{Host, SecretKey32, SocketOptions, TcpListener};
 use distant_core::net::server::{Server, ServerConfig as NetServerConfig, ServerRef};
-use distant_core::{DistantApiServerHandler, DistantSingleKeyCredentials};
+use distant_core::{
+    DistantApiServerHandler, DistantChannelExt, DistantSingleKeyCredentials, LocalDistantApi,
+    ProcessPolicy,
+};
 use log::*;
+use serde_json::json;
 use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod extensions;
+mod scripts;
+
+use extensions::{ServerExtensions, DEFAULT_EXTENSION_TIMEOUT};
 
 pub fn run(cmd: ServerSubcommand) -> CliResult {
     match &cmd {
         ServerSubcommand::Listen { daemon, .. } if *daemon => run_daemon(cmd),
         ServerSubcommand::Listen { .. } => {
+            let activated = take_activated_socket()?;
+            let rt = tokio::runtime::Runtime::new().context("Failed to start up runtime")?;
+            rt.block_on(async_run(cmd, false, activated))
+        }
+        ServerSubcommand::Clients { .. } => {
             let rt = tokio::runtime::Runtime::new().context("Failed to start up runtime")?;
-            rt.block_on(async_run(cmd, false))
+            rt.block_on(async_run(cmd, false, None))
         }
     }
 }
@@ -75,8 +94,9 @@ fn run_daemon(cmd: ServerSubcommand) -> CliResult {
     debug!("Forking process");
     match daemon(true, true) {
         Ok(Fork::Child) => {
+            let activated = take_activated_socket()?;
             let rt = tokio::runtime::Runtime::new().context("Failed to start up runtime")?;
-            rt.block_on(async { async_run(cmd, true).await })?;
+            rt.block_on(async { async_run(cmd, true, activated).await })?;
             Ok(())
         }
         Ok(Fork::Parent(pid)) => {
@@ -91,8 +111,56 @@ fn run_daemon(cmd: ServerSubcommand) -> CliResult {
     }
 }
 
-async fn async_run(cmd: ServerSubcommand, _is_forked: bool) -> CliResult {
+/// Runs `cmd`, listening with the `activated` socket (taken before the runtime started) if any
+async fn async_run(
+    cmd: ServerSubcommand,
+    _is_forked: bool,
+    activated: Option<ActivatedSocket>,
+) -> CliResult {
     match cmd {
+        ServerSubcommand::Clients {
+            cache,
+            connection,
+            format,
+            network,
+            disconnect,
+        } => {
+            debug!("Connecting to manager");
+            let mut client = connect_to_manager(format, network).await?;
+
+            let mut cache = read_cache(&cache).await;
+            let connection_id =
+                use_or_lookup_connection_id(&mut cache, connection, &mut client).await?;
+
+            debug!("Opening channel to connection {}", connection_id);
+            let mut channel = client
+                .open_raw_channel(connection_id)
+                .await
+                .with_context(|| format!("Failed to open channel to connection {connection_id}"))?
+                .into_client()
+                .into_channel();
+
+            match disconnect {
+                Some(id) => {
+                    debug!("Disconnecting client {}", id);
+                    channel.client_disconnect(id).await.with_context(|| {
+                        format!("Failed to disconnect client {id} using connection {connection_id}")
+                    })?;
+
+                    match format {
+                        Format::Json => println!("{}", json!({"type": "ok"})),
+                        Format::Shell => (),
+                    }
+                }
+                None => {
+                    debug!("Listing clients");
+                    let clients = channel.client_list().await.with_context(|| {
+                        format!("Failed to list clients using connection {connection_id}")
+                    })?;
+                    print_clients(format, clients);
+                }
+            }
+        }
         #[allow(unused_variables)]
         ServerSubcommand::Listen {
             host,
@@ -100,6 +168,16 @@ async fn async_run(cmd: ServerSubcommand, _is_forked: bool) -> CliResult {
             use_ipv6,
             shutdown,
             current_dir,
+            write_quota,
+            heartbeat_interval,
+            motd,
+            allow_remote_tunnels,
+            socket,
+            limits,
+            scope,
+            proc,
+            fs,
+            scripts,
             daemon: _,
             key_from_stdin,
             output_to_local_pipe,
@@ -108,6 +186,9 @@ async fn async_run(cmd: ServerSubcommand, _is_forked: bool) -> CliResult {
             trace!("Starting server using unresolved host '{host}'");
             let addr = host.resolve(use_ipv6).await?;
 
+            // Load scripts before changing directory so that relative paths are found
+            let hooks = scripts::load(&scripts.paths).context("Failed to load scripts")?;
+
             // If specified, change the current working directory of this program
             if let Some(path) = current_dir {
                 debug!("Setting current directory to {:?}", path);
@@ -126,31 +207,69 @@ async fn async_run(cmd: ServerSubcommand, _is_forked: bool) -> CliResult {
                 SecretKey32::default()
             };
 
-            let port = port.into_inner();
-            debug!(
-                "Starting local API server, binding to {} {}",
-                addr,
-                if port.is_ephemeral() {
-                    format!("with port in range {port}")
-                } else {
-                    "using an ephemeral port".to_string()
-                }
-            );
-            let handler =
-                DistantApiServerHandler::local().context("Failed to create local distant api")?;
-            let server = Server::tcp()
-                .config(NetServerConfig {
-                    shutdown: shutdown.into_inner(),
-                    ..Default::default()
+            let policy = ProcessPolicy::from(proc);
+            let api = LocalDistantApi::initialize_with_limits(limits.into())
+                .context("Failed to create local distant api")?
+                .with_hooks(Arc::new(ServerExtensions::new(
+                    hooks,
+                    scripts.extensions,
+                    scripts
+                        .extension_timeout
+                        .map(Duration::from_secs)
+                        .unwrap_or(DEFAULT_EXTENSION_TIMEOUT),
+                    policy.clone(),
+                )))
+                .with_write_quota(write_quota)
+                .with_remote_tunnels(allow_remote_tunnels)
+                .with_process_scope(scope.into())
+                .with_process_policy(policy)
+                .with_default_modes(fs.into());
+            let handler = DistantApiServerHandler::new(api);
+            let socket = SocketOptions::from(socket);
+            let builder = Server::tcp()
+                .config({
+                    let config = NetServerConfig::default();
+                    NetServerConfig {
+                        shutdown: shutdown.into_inner(),
+                        connection_heartbeat: heartbeat_interval
+                            .map(Duration::from_secs)
+                            .unwrap_or(config.connection_heartbeat),
+                        ..config
+                    }
                 })
                 .handler(handler)
-                .verifier(Verifier::static_key(key.clone()))
-                .start(addr, port)
-                .await
-                .with_context(|| format!("Failed to start server @ {addr} with {port}"))?;
+                .verifier(Verifier::static_key(key.clone()).with_banner(motd))
+                .socket_options(socket);
+
+            let server = match activated {
+                Some(listener) => {
+                    debug!("Starting local API server using socket passed by socket activation");
+                    let listener = TcpListener::from_std(listener.into(), socket)
+                        .context("Socket passed by socket activation is not a TCP listener")?;
+                    builder
+                        .start_with_listener(listener)
+                        .context("Failed to start server using activated socket")?
+                }
+                None => {
+                    let port = port.into_inner();
+                    debug!(
+                        "Starting local API server, binding to {} {}",
+                        addr,
+                        if port.is_ephemeral() {
+                            format!("with port in range {port}")
+                        } else {
+                            "using an ephemeral port".to_string()
+                        }
+                    );
+                    builder
+                        .start(addr, port)
+                        .await
+                        .with_context(|| format!("Failed to start server @ {addr} with {port}"))?
+                }
+            };
 
             let credentials = DistantSingleKeyCredentials {
-                host: Host::from(addr),
+                host: Host::from(server.ip_addr()),
                 port: server.port(),
                 key,
                 username: None,
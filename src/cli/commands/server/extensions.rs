@@ -0,0 +1,300 @@
+use distant_core::data::ProcessId;
+use distant_core::{ProcessPolicy, ServerHooks};
+use log::*;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Prefix of the executables in PATH that handle custom requests, followed by the name of the
+/// request without its `x-` prefix
+const EXTENSION_PROGRAM_PREFIX: &str = "distant-ext-";
+
+/// Time that plugin processes are given to respond when the server is not configured otherwise
+pub const DEFAULT_EXTENSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Time to wait between checks of whether a plugin process has exited
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Hooks of the server, which runs the hooks registered by scripts and handles custom requests
+/// using scripts or, failing that, plugin processes found in PATH
+pub struct ServerExtensions {
+    scripts: Option<Arc<dyn ServerHooks>>,
+
+    /// PATH searched for plugin processes
+    path: Option<OsString>,
+
+    /// Names of the custom requests that plugin processes are run for, where none are run for
+    /// requests that are not listed
+    allowed: HashSet<String>,
+
+    /// Time that plugin processes are given to respond before they are killed
+    timeout: Duration,
+
+    /// Policy that plugin processes must be permitted by, like any other process of the server
+    policy: ProcessPolicy,
+}
+
+impl ServerExtensions {
+    pub fn new(
+        scripts: Option<Arc<dyn ServerHooks>>,
+        allowed: impl IntoIterator<Item = String>,
+        timeout: Duration,
+        policy: ProcessPolicy,
+    ) -> Self {
+        Self {
+            scripts,
+            path: std::env::var_os("PATH"),
+            allowed: allowed.into_iter().collect(),
+            timeout,
+            policy,
+        }
+    }
+
+    /// Returns the executable that handles custom requests called `name`, if the server permits
+    /// running one for them and it exists
+    fn find_program(&self, name: &str) -> Option<PathBuf> {
+        if !self.allowed.contains(name) {
+            return None;
+        }
+
+        // Names are restricted so that they cannot be used to run something outside of PATH
+        let name = name.strip_prefix("x-")?;
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return None;
+        }
+
+        let cwd = std::env::current_dir().ok()?;
+        which::which_in(
+            format!("{EXTENSION_PROGRAM_PREFIX}{name}"),
+            self.path.as_ref(),
+            cwd,
+        )
+        .ok()
+    }
+}
+
+impl ServerHooks for ServerExtensions {
+    fn before_write(&self, path: &Path, data: Option<&[u8]>) -> io::Result<()> {
+        match self.scripts.as_ref() {
+            Some(scripts) => scripts.before_write(path, data),
+            None => Ok(()),
+        }
+    }
+
+    fn after_spawn(&self, id: ProcessId, cmd: &str, current_dir: Option<&Path>) {
+        if let Some(scripts) = self.scripts.as_ref() {
+            scripts.after_spawn(id, cmd, current_dir);
+        }
+    }
+
+    fn handle_request(&self, name: &str, payload: Value) -> Option<io::Result<Value>> {
+        let payload = match self.scripts.as_ref() {
+            Some(scripts) => match scripts.handle_request(name, payload.clone()) {
+                Some(result) => return Some(result),
+                None => payload,
+            },
+            None => payload,
+        };
+
+        let program = self.find_program(name)?;
+        if let Err(x) = self.policy.check_program(&program) {
+            warn!("Rejected running {program:?}: {x}");
+            return Some(Err(x));
+        }
+
+        debug!("Handling {name:?} using {program:?}");
+        Some(run_program(&program, &payload, self.timeout))
+    }
+}
+
+/// Runs `program` with `payload` written as JSON to its stdin, returning the JSON that it writes
+/// to stdout, or null if it writes nothing, killing it if it has not exited within `timeout`
+fn run_program(program: &Path, payload: &Value, timeout: Duration) -> io::Result<Value> {
+    let mut child = Command::new(program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Written and read from other threads so that a program writing before it reads (or filling
+    // up one pipe while we read the other) cannot deadlock
+    let input = serde_json::to_vec(payload)?;
+    let mut stdin = child.stdin.take().expect("Missing stdin of extension");
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let stdout = read_in_background(child.stdout.take().expect("Missing stdout of extension"));
+    let stderr = read_in_background(child.stderr.take().expect("Missing stderr of extension"));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("{program:?} did not respond within {timeout:?}"),
+            ));
+        }
+
+        std::thread::sleep(EXIT_POLL_INTERVAL);
+    };
+
+    if let Ok(Err(x)) = writer.join() {
+        debug!("Failed to write payload to {program:?}: {x}");
+    }
+    let stdout = stdout.join().unwrap_or_else(|_| Ok(Vec::new()))?;
+    let stderr = stderr.join().unwrap_or_else(|_| Ok(Vec::new()))?;
+
+    if !status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "{program:?} failed with {}: {}",
+                status,
+                String::from_utf8_lossy(&stderr).trim()
+            ),
+        ));
+    }
+
+    if stdout.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Value::Null);
+    }
+
+    serde_json::from_slice(&stdout).map_err(|x| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{program:?} responded with invalid JSON: {x}"),
+        )
+    })
+}
+
+/// Reads everything from `reader` on another thread, returning the thread to join once done
+fn read_in_background(
+    mut reader: impl Read + Send + 'static,
+) -> std::thread::JoinHandle<io::Result<Vec<u8>>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        Ok(buf)
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use serde_json::json;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn extensions_with_program(name: &str, script: &str) -> (assert_fs::TempDir, ServerExtensions) {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let program = dir.child(name);
+        program.write_str(script).unwrap();
+        std::fs::set_permissions(program.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let allowed = name.strip_prefix(EXTENSION_PROGRAM_PREFIX).unwrap();
+        let extensions = ServerExtensions {
+            scripts: None,
+            path: Some(dir.path().as_os_str().to_os_string()),
+            allowed: [format!("x-{allowed}")].into_iter().collect(),
+            timeout: Duration::from_secs(5),
+            policy: ProcessPolicy::default(),
+        };
+        (dir, extensions)
+    }
+
+    #[test]
+    fn handle_request_should_run_program_with_payload() {
+        let (_dir, extensions) = extensions_with_program("distant-ext-echo", "#!/bin/sh\ncat\n");
+
+        let payload = json!({ "key": ["value", 1] });
+        assert_eq!(
+            extensions
+                .handle_request("x-echo", payload.clone())
+                .unwrap()
+                .unwrap(),
+            payload
+        );
+    }
+
+    #[test]
+    fn handle_request_should_fail_if_program_fails() {
+        let (_dir, extensions) = extensions_with_program(
+            "distant-ext-fail",
+            "#!/bin/sh\necho 'went wrong' 1>&2\nexit 3\n",
+        );
+
+        let err = extensions
+            .handle_request("x-fail", Value::Null)
+            .unwrap()
+            .unwrap_err();
+        assert!(err.to_string().contains("went wrong"), "{err}");
+    }
+
+    #[test]
+    fn handle_request_should_fail_if_program_is_not_permitted_by_policy() {
+        let (_dir, mut extensions) =
+            extensions_with_program("distant-ext-echo", "#!/bin/sh\ncat\n");
+        extensions.policy = ProcessPolicy {
+            allow: Vec::new(),
+            deny: vec![String::from("distant-ext-*")],
+            system_kill: false,
+        };
+
+        let err = extensions
+            .handle_request("x-echo", Value::Null)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn handle_request_should_ignore_requests_that_are_not_allowed() {
+        let (_dir, mut extensions) =
+            extensions_with_program("distant-ext-echo", "#!/bin/sh\ncat\n");
+        extensions.allowed.clear();
+
+        assert!(extensions.handle_request("x-echo", Value::Null).is_none());
+    }
+
+    #[test]
+    fn handle_request_should_kill_program_that_does_not_respond_in_time() {
+        let (_dir, mut extensions) =
+            extensions_with_program("distant-ext-sleep", "#!/bin/sh\nexec sleep 30\n");
+        extensions.timeout = Duration::from_millis(100);
+
+        let started = Instant::now();
+        let err = extensions
+            .handle_request("x-sleep", Value::Null)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn handle_request_should_ignore_names_that_are_not_plain() {
+        let (_dir, extensions) = extensions_with_program("distant-ext-echo", "#!/bin/sh\ncat\n");
+
+        assert!(extensions.handle_request("echo", Value::Null).is_none());
+        assert!(extensions.handle_request("x-", Value::Null).is_none());
+        assert!(extensions
+            .handle_request("x-../distant-ext-echo", Value::Null)
+            .is_none());
+        assert!(extensions
+            .handle_request("x-missing", Value::Null)
+            .is_none());
+    }
+}
@@ -0,0 +1,45 @@
+use distant_core::ServerHooks;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[cfg(feature = "scripts")]
+mod lua;
+
+/// Loads the scripts at `paths`, returning hooks that run them as requests are handled or none
+/// if there are no scripts to load
+pub fn load(paths: &[PathBuf]) -> anyhow::Result<Option<Arc<dyn ServerHooks>>> {
+    if paths.is_empty() {
+        return Ok(None);
+    }
+
+    load_scripts(paths).map(Some)
+}
+
+#[cfg(feature = "scripts")]
+fn load_scripts(paths: &[PathBuf]) -> anyhow::Result<Arc<dyn ServerHooks>> {
+    Ok(Arc::new(lua::LuaHooks::load(paths)?))
+}
+
+#[cfg(not(feature = "scripts"))]
+fn load_scripts(_paths: &[PathBuf]) -> anyhow::Result<Arc<dyn ServerHooks>> {
+    anyhow::bail!("Scripts were provided, but distant was built without the `scripts` feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_should_return_none_if_no_scripts() {
+        assert!(load(&[]).unwrap().is_none());
+    }
+
+    #[cfg(not(feature = "scripts"))]
+    #[test]
+    fn load_should_fail_if_scripts_provided_without_feature() {
+        let err = load(&[PathBuf::from("policy.lua")])
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.to_string().contains("`scripts` feature"), "{err}");
+    }
+}
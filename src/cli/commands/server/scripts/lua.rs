@@ -0,0 +1,177 @@
+use anyhow::Context;
+use distant_core::data::ProcessId;
+use distant_core::ServerHooks;
+use log::*;
+use mlua::{Function, Lua, LuaSerdeExt, Table};
+use serde_json::Value;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Lua defining the `distant` table that scripts register their hooks with
+const PRELUDE: &str = include_str!("prelude.lua");
+
+/// Hooks registered by Lua scripts, which share a single Lua state
+pub struct LuaHooks {
+    lua: Mutex<Lua>,
+}
+
+impl LuaHooks {
+    /// Runs each script at `paths` in order so that they can register their hooks
+    pub fn load(paths: &[PathBuf]) -> anyhow::Result<Self> {
+        let lua = Lua::new();
+        lua.load(PRELUDE)
+            .exec()
+            .context("Failed to load script prelude")?;
+
+        for path in paths {
+            debug!("Loading script {:?}", path);
+            let script = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read script {path:?}"))?;
+            lua.load(&script)
+                .exec()
+                .with_context(|| format!("Failed to run script {path:?}"))?;
+        }
+
+        Ok(Self {
+            lua: Mutex::new(lua),
+        })
+    }
+}
+
+/// Returns the function called `name` from the `distant` table defined by the prelude
+fn prelude_fn<'lua>(lua: &'lua Lua, name: &str) -> mlua::Result<Function<'lua>> {
+    lua.globals().get::<_, Table>("distant")?.get(name)
+}
+
+impl ServerHooks for LuaHooks {
+    fn before_write(&self, path: &Path, data: Option<&[u8]>) -> io::Result<()> {
+        let lua = self.lua.lock().unwrap();
+        let reason = prelude_fn(&lua, "_before_write").and_then(|f| {
+            let data = data.map(|data| lua.create_string(data)).transpose()?;
+            f.call::<_, Option<String>>((path.to_string_lossy().into_owned(), data))
+        });
+
+        // Failing to run the hooks rejects the write rather than skipping the policy
+        match reason {
+            Ok(None) => Ok(()),
+            Ok(Some(reason)) => Err(io::Error::new(io::ErrorKind::PermissionDenied, reason)),
+            Err(x) => {
+                error!("Failed to run write hooks for {:?}: {}", path, x);
+                Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "Write rejected as scripts failed",
+                ))
+            }
+        }
+    }
+
+    fn after_spawn(&self, id: ProcessId, cmd: &str, current_dir: Option<&Path>) {
+        let lua = self.lua.lock().unwrap();
+        let tags = lua.create_table().and_then(|process| {
+            process.set("id", id)?;
+            process.set("cmd", cmd)?;
+            process.set(
+                "current_dir",
+                current_dir.map(|path| path.to_string_lossy().into_owned()),
+            )?;
+            prelude_fn(&lua, "_after_spawn")?.call::<_, Vec<String>>(process)
+        });
+
+        match tags {
+            Ok(tags) if tags.is_empty() => {}
+            Ok(tags) => info!("[Proc {}] Tagged with {}", id, tags.join(", ")),
+            Err(x) => error!("[Proc {}] Failed to run spawn hooks: {}", id, x),
+        }
+    }
+
+    fn handle_request(&self, name: &str, payload: Value) -> Option<io::Result<Value>> {
+        let lua = self.lua.lock().unwrap();
+        let result = lua.to_value(&payload).and_then(|payload| {
+            prelude_fn(&lua, "_handle")?
+                .call::<_, (bool, Option<String>, mlua::Value)>((name, payload))
+        });
+
+        match result {
+            Ok((false, _, _)) => None,
+            Ok((true, Some(x), _)) => Some(Err(io::Error::new(io::ErrorKind::Other, x))),
+            Ok((true, None, value)) => Some(
+                lua.from_value(value)
+                    .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x.to_string())),
+            ),
+            Err(x) => Some(Err(io::Error::new(io::ErrorKind::Other, x.to_string()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use serde_json::json;
+
+    fn load(script: &str) -> LuaHooks {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("script.lua");
+        file.write_str(script).unwrap();
+        LuaHooks::load(&[file.path().to_path_buf()]).unwrap()
+    }
+
+    #[test]
+    fn before_write_should_reject_writes_refused_by_scripts() {
+        let hooks = load(indoc::indoc! {r#"
+            distant.on_write(function(path, data)
+              if path:match("%.lock$") then error("locked", 0) end
+              return data == nil or not data:find("secret")
+            end)
+        "#});
+
+        hooks
+            .before_write(Path::new("notes.txt"), Some(b"hello"))
+            .unwrap();
+        hooks.before_write(Path::new("notes.txt"), None).unwrap();
+
+        let err = hooks
+            .before_write(Path::new("file.lock"), Some(b"hello"))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(err.to_string(), "locked");
+
+        let err = hooks
+            .before_write(Path::new("file.lock"), None)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+        let err = hooks
+            .before_write(Path::new("notes.txt"), Some(b"my secret"))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn handle_request_should_only_handle_registered_requests() {
+        let hooks = load(indoc::indoc! {r#"
+            distant.handle("x-add", function(payload)
+              return { sum = payload.a + payload.b }
+            end)
+        "#});
+
+        assert!(hooks.handle_request("x-missing", json!({})).is_none());
+        assert_eq!(
+            hooks
+                .handle_request("x-add", json!({ "a": 1, "b": 2 }))
+                .unwrap()
+                .unwrap(),
+            json!({ "sum": 3 })
+        );
+    }
+
+    #[test]
+    fn load_should_fail_if_handler_name_lacks_namespace() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        let file = temp.child("script.lua");
+        file.write_str(r#"distant.handle("add", function() end)"#)
+            .unwrap();
+        assert!(LuaHooks::load(&[file.path().to_path_buf()]).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+-- Loaded before any script, defining the `distant` table that scripts use to register hooks
+distant = { _write = {}, _spawn = {}, _handlers = {} }
+
+-- Registers `f(path, data)` to run before data is written or appended to a file, or before
+-- anything else is put at `path` (such as by copying, renaming, or making a directory) in which
+-- case `data` is nil, rejecting the write if it returns false or raises an error
+function distant.on_write(f)
+  table.insert(distant._write, f)
+end
+
+-- Registers `f(process)` to run after a process is spawned, where `process` has the `id`, `cmd`,
+-- and `current_dir` of the process, returning an optional list of tags to log with the process
+function distant.on_spawn(f)
+  table.insert(distant._spawn, f)
+end
+
+-- Registers `f(payload)` to handle custom requests called `name`, returning the response
+function distant.handle(name, f)
+  if type(name) ~= "string" or name:sub(1, 2) ~= "x-" then
+    error("custom request name must start with \"x-\", got " .. tostring(name), 2)
+  end
+  distant._handlers[name] = f
+end
+
+-- Returns the reason that writing `data` to `path` was rejected, or nil if it was not rejected
+function distant._before_write(path, data)
+  for _, f in ipairs(distant._write) do
+    local ok, result = pcall(f, path, data)
+    if not ok then
+      return tostring(result)
+    elseif result == false then
+      return "Write to " .. path .. " rejected by script"
+    end
+  end
+end
+
+-- Returns the tags of the spawned `process`
+function distant._after_spawn(process)
+  local tags = {}
+  for _, f in ipairs(distant._spawn) do
+    for _, tag in ipairs(f(process) or {}) do
+      table.insert(tags, tostring(tag))
+    end
+  end
+  return tags
+end
+
+-- Returns whether a handler exists for `name`, followed by either the error that it raised or
+-- nil and its response to `payload`
+function distant._handle(name, payload)
+  local f = distant._handlers[name]
+  if f == nil then
+    return false
+  end
+
+  local ok, result = pcall(f, payload)
+  if ok then
+    return true, nil, result
+  else
+    return true, tostring(result)
+  end
+end
@@ -0,0 +1,492 @@
+use crate::constants::user::{CONFIG_FILE_PATH, HISTORY_FILE_PATH};
+use crate::options::StateItem;
+use crate::{CliError, CliResult};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+mod archive;
+mod crypt;
+
+use archive::Archive;
+use crypt::PasswordKey;
+
+/// Version of the layout of archives written by `export-state`
+const MANIFEST_VERSION: u32 = 1;
+
+const MANIFEST_PATH: &str = "manifest.json";
+const CONFIG_PATH: &str = "config.toml";
+const HISTORY_PATH: &str = "history.jsonl";
+const KNOWN_HOSTS_PATH: &str = "ssh/known_hosts";
+const KEYS_DIR: &str = "ssh/";
+
+/// Suffix of keys within an archive that are encrypted with a password
+const ENCRYPTED_SUFFIX: &str = ".enc";
+
+/// Describes what an archive written by `export-state` contains
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+
+    /// Names of the items within the archive
+    items: Vec<String>,
+
+    /// Whether or not private keys are encrypted with a password
+    #[serde(default)]
+    encrypted_keys: bool,
+}
+
+/// Locations of the client state on this machine
+struct StatePaths {
+    config: PathBuf,
+    history: PathBuf,
+    ssh_dir: PathBuf,
+}
+
+impl StatePaths {
+    fn new(config_path: Option<&Path>, history_file: Option<PathBuf>) -> anyhow::Result<Self> {
+        let home = directories::BaseDirs::new()
+            .context("Could not determine valid $HOME path")?
+            .home_dir()
+            .to_path_buf();
+
+        Ok(Self {
+            config: config_path
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| CONFIG_FILE_PATH.to_path_buf()),
+            history: history_file.unwrap_or_else(|| HISTORY_FILE_PATH.to_path_buf()),
+            ssh_dir: home.join(".ssh"),
+        })
+    }
+}
+
+/// Writes the parts of the client state in `include` (everything but keys if empty) to a tar
+/// archive at `path`, skipping parts that do not exist on this machine
+pub fn export(
+    path: PathBuf,
+    include: Vec<StateItem>,
+    encrypt_keys: bool,
+    password_file: Option<PathBuf>,
+    history_file: Option<PathBuf>,
+    config_path: Option<&Path>,
+) -> CliResult {
+    let paths = StatePaths::new(config_path, history_file)?;
+    let include: HashSet<StateItem> = if include.is_empty() {
+        [StateItem::Config, StateItem::History, StateItem::KnownHosts]
+            .into_iter()
+            .collect()
+    } else {
+        include.into_iter().collect()
+    };
+
+    let mut archive = Archive::default();
+    let mut items = Vec::new();
+
+    let files = [
+        (StateItem::Config, &paths.config, CONFIG_PATH),
+        (StateItem::History, &paths.history, HISTORY_PATH),
+        (
+            StateItem::KnownHosts,
+            &paths.ssh_dir.join("known_hosts"),
+            KNOWN_HOSTS_PATH,
+        ),
+    ];
+    for (item, src, dst) in files {
+        if !include.contains(&item) {
+            continue;
+        }
+
+        match read_optional(src)? {
+            Some(data) => {
+                archive.push(dst, 0o600, data);
+                items.push(item.as_str().to_string());
+                println!("Exported {} from {src:?}", item.as_str());
+            }
+            None => println!("Skipped {} as {src:?} does not exist", item.as_str()),
+        }
+    }
+
+    if include.contains(&StateItem::Keys) {
+        let key = if encrypt_keys {
+            Some(PasswordKey::new(&read_password(
+                password_file.as_deref(),
+                /* confirm */ true,
+            )?))
+        } else {
+            None
+        };
+
+        let keys = find_keys(&paths.ssh_dir)?;
+        for (name, src) in keys.iter() {
+            let data = fs::read(src).with_context(|| format!("Failed to read {src:?}"))?;
+            match key.as_ref() {
+                Some(key) if is_private_key(name) => archive.push(
+                    format!("{KEYS_DIR}{name}{ENCRYPTED_SUFFIX}"),
+                    0o600,
+                    key.encrypt(&data)
+                        .with_context(|| format!("Failed to encrypt {src:?}"))?,
+                ),
+                _ => archive.push(format!("{KEYS_DIR}{name}"), key_mode(name), data),
+            }
+        }
+
+        if keys.is_empty() {
+            println!("Skipped keys as none were found in {:?}", paths.ssh_dir);
+        } else {
+            items.push(StateItem::Keys.as_str().to_string());
+            println!("Exported {} keys from {:?}", keys.len(), paths.ssh_dir);
+        }
+    }
+
+    let manifest = Manifest {
+        version: MANIFEST_VERSION,
+        items,
+        encrypted_keys: encrypt_keys,
+    };
+    archive.entries.insert(
+        0,
+        archive::ArchiveEntry {
+            path: MANIFEST_PATH.to_string(),
+            mode: 0o644,
+            data: serde_json::to_vec_pretty(&manifest).context("Failed to serialize manifest")?,
+        },
+    );
+
+    let bytes = archive.to_bytes().context("Failed to build archive")?;
+    write_private(&path, &bytes).with_context(|| format!("Failed to write {path:?}"))?;
+    Ok(())
+}
+
+/// Restores the parts of the client state in `include` (everything within the archive if empty)
+/// from the tar archive at `path`, merging the history and known hosts with those already here
+/// and replacing the config and keys only if `force` is true
+pub fn import(
+    path: PathBuf,
+    include: Vec<StateItem>,
+    force: bool,
+    password_file: Option<PathBuf>,
+    history_file: Option<PathBuf>,
+    config_path: Option<&Path>,
+) -> CliResult {
+    let paths = StatePaths::new(config_path, history_file)?;
+    let bytes = fs::read(&path).with_context(|| format!("Failed to read {path:?}"))?;
+    let archive =
+        Archive::from_bytes(&bytes).with_context(|| format!("Invalid archive {path:?}"))?;
+
+    let manifest: Manifest = serde_json::from_slice(
+        &archive
+            .get(MANIFEST_PATH)
+            .with_context(|| format!("Archive {path:?} was not created by export-state"))?
+            .data,
+    )
+    .context("Invalid manifest within archive")?;
+    if manifest.version > MANIFEST_VERSION {
+        return Err(CliError::Error(anyhow::anyhow!(
+            "Archive {path:?} has version {}, but only up to {MANIFEST_VERSION} is supported",
+            manifest.version
+        )));
+    }
+
+    let wanted = |item: StateItem| {
+        manifest.items.iter().any(|x| x == item.as_str())
+            && (include.is_empty() || include.contains(&item))
+    };
+
+    if wanted(StateItem::Config) {
+        let data = &archive
+            .get(CONFIG_PATH)
+            .context("Archive missing config")?
+            .data;
+        if paths.config.exists() && !force {
+            println!(
+                "Skipped config as {:?} already exists (use --force to replace it)",
+                paths.config
+            );
+        } else {
+            write_private(&paths.config, data)
+                .with_context(|| format!("Failed to write {:?}", paths.config))?;
+            println!("Imported config to {:?}", paths.config);
+        }
+    }
+
+    let merges = [
+        (StateItem::History, &paths.history, HISTORY_PATH),
+        (
+            StateItem::KnownHosts,
+            &paths.ssh_dir.join("known_hosts"),
+            KNOWN_HOSTS_PATH,
+        ),
+    ];
+    for (item, dst, src) in merges {
+        if !wanted(item) {
+            continue;
+        }
+
+        let data = &archive
+            .get(src)
+            .with_context(|| format!("Archive missing {}", item.as_str()))?
+            .data;
+        let added = merge_lines(dst, data).with_context(|| format!("Failed to merge {dst:?}"))?;
+        println!("Merged {added} new lines of {} into {dst:?}", item.as_str());
+    }
+
+    if wanted(StateItem::Keys) {
+        let password = if manifest.encrypted_keys {
+            Some(read_password(
+                password_file.as_deref(),
+                /* confirm */ false,
+            )?)
+        } else {
+            None
+        };
+
+        // Derivation is slow on purpose, so reuse keys for entries encrypted with the same salt
+        let mut password_keys = HashMap::new();
+        let (mut imported, mut skipped) = (0, 0);
+        for entry in archive.entries.iter() {
+            let Some(name) = entry.path.strip_prefix(KEYS_DIR) else {
+                continue;
+            };
+            if entry.path == KNOWN_HOSTS_PATH {
+                continue;
+            }
+
+            let (name, data) = match (name.strip_suffix(ENCRYPTED_SUFFIX), password.as_deref()) {
+                (Some(name), Some(password)) => {
+                    let salt = crypt::salt_of(&entry.data)
+                        .with_context(|| format!("Invalid key {name}"))?;
+                    let key = password_keys
+                        .entry(salt)
+                        .or_insert_with(|| PasswordKey::with_salt(password, salt));
+                    let data = key
+                        .decrypt(&entry.data)
+                        .with_context(|| format!("Failed to decrypt key {name}"))?;
+                    (name, data)
+                }
+                _ => (name, entry.data.clone()),
+            };
+
+            // Only accept the names of keys so that an archive cannot write outside of ~/.ssh or
+            // replace other files within it, such as authorized_keys or config
+            if !is_key_name(name) {
+                return Err(CliError::Error(anyhow::anyhow!(
+                    "Archive contains invalid key name {name:?}"
+                )));
+            }
+
+            let dst = paths.ssh_dir.join(name);
+            if dst.exists() && !force {
+                skipped += 1;
+                continue;
+            }
+
+            write_with_mode(&dst, &data, key_mode(name))
+                .with_context(|| format!("Failed to write {dst:?}"))?;
+            imported += 1;
+        }
+
+        print!("Imported {imported} keys to {:?}", paths.ssh_dir);
+        if skipped > 0 {
+            print!(", skipping {skipped} that already exist (use --force to replace them)");
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Returns the name and path of every ssh key (public and private) found in `ssh_dir`, sorted
+/// by name
+fn find_keys(ssh_dir: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let entries = match fs::read_dir(ssh_dir) {
+        Ok(entries) => entries,
+        Err(x) if x.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(x) => return Err(x).with_context(|| format!("Failed to read {ssh_dir:?}")),
+    };
+
+    let mut keys = Vec::new();
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to read {ssh_dir:?}"))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_key_name(&name) && entry.file_type().is_ok_and(|ty| ty.is_file()) {
+            keys.push((name, entry.path()));
+        }
+    }
+
+    keys.sort();
+    Ok(keys)
+}
+
+/// Returns true if `name` is that of an ssh key, meaning `id_*` or `id_*.pub` made up of
+/// nothing but letters, digits, `-`, `_`, and `.`
+fn is_key_name(name: &str) -> bool {
+    let base = name.strip_suffix(".pub").unwrap_or(name);
+    base.strip_prefix("id_").is_some_and(|rest| {
+        !rest.is_empty()
+            && rest
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    })
+}
+
+fn is_private_key(name: &str) -> bool {
+    !name.ends_with(".pub")
+}
+
+/// Returns the permissions that ssh expects of the key with `name`
+fn key_mode(name: &str) -> u32 {
+    if is_private_key(name) {
+        0o600
+    } else {
+        0o644
+    }
+}
+
+/// Reads the password used for keys from `password_file`, otherwise prompting for it (twice if
+/// `confirm` is true)
+fn read_password(password_file: Option<&Path>, confirm: bool) -> anyhow::Result<String> {
+    if let Some(path) = password_file {
+        let password =
+            fs::read_to_string(path).with_context(|| format!("Failed to read {path:?}"))?;
+        return Ok(password.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    let password =
+        rpassword::prompt_password("Password for keys: ").context("Failed to read password")?;
+    if confirm {
+        let again =
+            rpassword::prompt_password("Confirm password: ").context("Failed to read password")?;
+        if password != again {
+            anyhow::bail!("Passwords do not match");
+        }
+    }
+
+    Ok(password)
+}
+
+fn read_optional(path: &Path) -> anyhow::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(x) if x.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(x) => Err(x).with_context(|| format!("Failed to read {path:?}")),
+    }
+}
+
+/// Appends the lines of `data` missing from the file at `path`, creating it if needed, and
+/// returns how many lines were added
+fn merge_lines(path: &Path, data: &[u8]) -> anyhow::Result<usize> {
+    let existing = read_optional(path)?.unwrap_or_default();
+    let existing = String::from_utf8_lossy(&existing);
+    let seen: HashSet<&str> = existing.lines().collect();
+
+    let incoming = String::from_utf8_lossy(data);
+    let mut merged = existing.to_string();
+    let mut added = 0;
+    for line in incoming.lines() {
+        if line.trim().is_empty() || seen.contains(line) {
+            continue;
+        }
+
+        if !merged.is_empty() && !merged.ends_with('\n') {
+            merged.push('\n');
+        }
+        merged.push_str(line);
+        merged.push('\n');
+        added += 1;
+    }
+
+    if added > 0 {
+        write_private(path, merged.as_bytes())?;
+    }
+
+    Ok(added)
+}
+
+/// Writes `data` to a file at `path` that only the user can read
+fn write_private(path: &Path, data: &[u8]) -> io::Result<()> {
+    write_with_mode(path, data, 0o600)
+}
+
+/// Writes `data` to a file at `path` with permissions of `mode` (on unix), creating its parent
+/// directory if needed so that only the user can access it
+///
+/// Permissions are in place before any data is written, so that the data is never readable by
+/// anyone that `mode` excludes.
+fn write_with_mode(path: &Path, data: &[u8], mode: u32) -> io::Result<()> {
+    let mut dir_builder = fs::DirBuilder::new();
+    let mut options = fs::OpenOptions::new();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+        dir_builder.mode(0o700);
+        options.mode(mode);
+    }
+
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        dir_builder.recursive(true).create(parent)?;
+    }
+
+    let mut file = options.write(true).create(true).truncate(true).open(path)?;
+
+    // NOTE: The mode only applies to files being created, so one that already exists has its
+    //       permissions changed before we write to it
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(mode))?;
+    }
+
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    io::Write::write_all(&mut file, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_key_name_should_only_accept_names_of_keys() {
+        assert!(is_key_name("id_ed25519"));
+        assert!(is_key_name("id_ed25519.pub"));
+        assert!(is_key_name("id_rsa-work"));
+
+        assert!(!is_key_name("id_"));
+        assert!(!is_key_name("id_.pub"));
+        assert!(!is_key_name("authorized_keys"));
+        assert!(!is_key_name("config"));
+        assert!(!is_key_name("known_hosts"));
+        assert!(!is_key_name("id_rsa/../config"));
+        assert!(!is_key_name("id_rsa\\config"));
+    }
+    #[cfg(unix)]
+    #[test]
+    fn write_with_mode_should_only_let_the_user_access_created_directory_and_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = assert_fs::TempDir::new().unwrap();
+        let dir = temp.path().join(".ssh");
+        let path = dir.join("id_ed25519");
+
+        write_with_mode(&path, b"key", 0o600).unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir), 0o700);
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(fs::read(&path).unwrap(), b"key");
+
+        // Files that already exist are restricted as well
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        write_with_mode(&path, b"new key", 0o600).unwrap();
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(fs::read(&path).unwrap(), b"new key");
+    }
+}
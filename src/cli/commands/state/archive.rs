@@ -0,0 +1,99 @@
+use std::io::{self, Read};
+
+/// File stored within an [`Archive`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path of the file within the archive, using `/` as the separator
+    pub path: String,
+
+    /// Permissions of the file
+    pub mode: u32,
+
+    /// Contents of the file
+    pub data: Vec<u8>,
+}
+
+/// Collection of files read from or written as a tar archive, supporting only the regular files
+/// that client state is made up of
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Archive {
+    pub entries: Vec<ArchiveEntry>,
+}
+
+impl Archive {
+    /// Adds a file at `path` with `data`
+    pub fn push(&mut self, path: impl Into<String>, mode: u32, data: Vec<u8>) {
+        self.entries.push(ArchiveEntry {
+            path: path.into(),
+            mode,
+            data,
+        });
+    }
+
+    /// Returns the entry at `path`, if there is one
+    pub fn get(&self, path: &str) -> Option<&ArchiveEntry> {
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+
+    /// Writes the archive out in the tar format
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for entry in self.entries.iter() {
+            let mut header = tar::Header::new_ustar();
+            header.set_size(entry.data.len() as u64);
+            header.set_mode(entry.mode & 0o7777);
+            header.set_entry_type(tar::EntryType::Regular);
+            builder.append_data(&mut header, &entry.path, entry.data.as_slice())?;
+        }
+
+        builder.into_inner()
+    }
+
+    /// Reads an archive in the tar format, skipping anything other than regular files
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let mut archive = Self::default();
+        for entry in tar::Archive::new(bytes).entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let path = entry.path()?.to_string_lossy().replace('\\', "/");
+            let mode = entry.header().mode().unwrap_or(0o644);
+            // NOTE: The size in the header is not trusted to size the buffer, which instead grows
+            //       with the data actually read
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            archive.push(path, mode, data);
+        }
+
+        Ok(archive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_should_survive_being_written_and_read() {
+        let mut archive = Archive::default();
+        archive.push("config.toml", 0o644, b"[client]\n".to_vec());
+        archive.push("ssh/id_ed25519", 0o600, vec![7; 513]);
+        archive.push("empty", 0o644, Vec::new());
+
+        let bytes = archive.to_bytes().unwrap();
+        assert_eq!(bytes.len() % 512, 0);
+        assert_eq!(Archive::from_bytes(&bytes).unwrap(), archive);
+    }
+
+    #[test]
+    fn from_bytes_should_fail_if_header_is_corrupted() {
+        let mut archive = Archive::default();
+        archive.push("config.toml", 0o644, b"[client]\n".to_vec());
+
+        let mut bytes = archive.to_bytes().unwrap();
+        bytes[0] = b'x';
+        assert!(Archive::from_bytes(&bytes).is_err());
+    }
+}
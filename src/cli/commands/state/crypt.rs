@@ -0,0 +1,98 @@
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use sha2::Sha256;
+use std::io;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+/// Rounds of PBKDF2 used to derive a key from a password, making each guess at the password
+/// costly for anyone holding the archive
+const ROUNDS: u32 = 100_000;
+
+/// Key derived from a password along with the salt used to derive it
+pub struct PasswordKey {
+    salt: [u8; SALT_LEN],
+    key: [u8; KEY_LEN],
+}
+
+impl PasswordKey {
+    /// Derives a key from `password` using a new random salt
+    pub fn new(password: &str) -> Self {
+        Self::with_salt(password, rand::random())
+    }
+
+    /// Derives a key from `password` using `salt`
+    pub fn with_salt(password: &str, salt: [u8; SALT_LEN]) -> Self {
+        let mut key = [0; KEY_LEN];
+        pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), &salt, ROUNDS, &mut key);
+        Self { salt, key }
+    }
+
+    /// Encrypts `data`, producing the salt and nonce followed by the ciphertext
+    pub fn encrypt(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let ciphertext = XChaCha20Poly1305::new(Key::from_slice(&self.key))
+            .encrypt(XNonce::from_slice(&nonce), data)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Failed to encrypt data"))?;
+
+        let mut bytes = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+        bytes.extend_from_slice(&self.salt);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+        Ok(bytes)
+    }
+
+    /// Decrypts `bytes` produced by [`PasswordKey::encrypt`], failing if the key was derived
+    /// from the wrong password or the bytes were altered
+    pub fn decrypt(&self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        if salt_of(bytes)? != self.salt {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Data was encrypted with a different salt",
+            ));
+        }
+
+        let nonce = &bytes[SALT_LEN..SALT_LEN + NONCE_LEN];
+        XChaCha20Poly1305::new(Key::from_slice(&self.key))
+            .decrypt(XNonce::from_slice(nonce), &bytes[SALT_LEN + NONCE_LEN..])
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "Failed to decrypt data, is the password correct?",
+                )
+            })
+    }
+}
+
+/// Returns the salt that `bytes` produced by [`PasswordKey::encrypt`] were encrypted with
+pub fn salt_of(bytes: &[u8]) -> io::Result<[u8; SALT_LEN]> {
+    if bytes.len() < SALT_LEN + NONCE_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Encrypted data is too short",
+        ));
+    }
+
+    let mut salt = [0; SALT_LEN];
+    salt.copy_from_slice(&bytes[..SALT_LEN]);
+    Ok(salt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decrypt_should_reverse_encrypt_with_same_password() {
+        let key = PasswordKey::new("hunter2");
+        let bytes = key.encrypt(b"private key").unwrap();
+
+        let key = PasswordKey::with_salt("hunter2", salt_of(&bytes).unwrap());
+        assert_eq!(key.decrypt(&bytes).unwrap(), b"private key");
+
+        let key = PasswordKey::with_salt("hunter3", salt_of(&bytes).unwrap());
+        assert!(key.decrypt(&bytes).is_err());
+    }
+}
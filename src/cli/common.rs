@@ -1,11 +1,19 @@
+mod activation;
 mod cache;
 mod client;
+mod history;
+mod logging;
 mod manager;
 mod msg;
 mod spawner;
+mod trust;
 
+pub use activation::*;
 pub use cache::*;
 pub use client::*;
+pub use history::*;
+pub use logging::*;
 pub use manager::*;
 pub use msg::*;
 pub use spawner::*;
+pub use trust::*;
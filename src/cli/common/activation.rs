@@ -0,0 +1,29 @@
+#[cfg(unix)]
+use anyhow::Context;
+
+/// Socket passed to the process through socket activation
+#[cfg(unix)]
+pub type ActivatedSocket = std::os::fd::OwnedFd;
+
+/// Socket passed to the process through socket activation, which never happens on Windows
+#[cfg(not(unix))]
+pub type ActivatedSocket = std::net::TcpListener;
+
+/// Takes the first socket passed through socket activation, if any
+///
+/// Taking the sockets removes the environment variables that describe them, which is only sound
+/// while the process has a single thread, so this must be called before the runtime starts.
+pub fn take_activated_socket() -> anyhow::Result<Option<ActivatedSocket>> {
+    #[cfg(unix)]
+    {
+        Ok(distant_core::net::common::take_activated_sockets()
+            .context("Failed to take sockets passed by socket activation")?
+            .into_iter()
+            .next())
+    }
+
+    #[cfg(not(unix))]
+    {
+        Ok(None)
+    }
+}
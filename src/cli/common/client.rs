@@ -1,7 +1,13 @@
 use crate::cli::common::{MsgReceiver, MsgSender};
 use crate::options::NetworkSettings;
 use async_trait::async_trait;
-use distant_core::net::client::{Client as NetClient, ClientConfig, ReconnectStrategy};
+#[cfg(unix)]
+use distant_core::net::client::UnixSocketConnector;
+#[cfg(windows)]
+use distant_core::net::client::WindowsPipeConnector;
+use distant_core::net::client::{
+    Client as NetClient, ClientConfig, ReconnectStrategy, SimulatedConnector,
+};
 use distant_core::net::common::authentication::msg::*;
 use distant_core::net::common::authentication::{
     AuthHandler, AuthMethodHandler, PromptAuthMethodHandler, SingleAuthHandler,
@@ -57,7 +63,11 @@ impl<T: AuthHandler + Clone> Client<T> {
             let mut maybe_client = None;
             let mut error: Option<anyhow::Error> = None;
             for path in self.network.to_unix_socket_path_candidates() {
-                match NetClient::unix_socket(path)
+                match NetClient::build()
+                    .connector(SimulatedConnector::new(
+                        UnixSocketConnector::new(path),
+                        self.network.simulate.unwrap_or_default(),
+                    ))
                     .auth_handler(self.auth_handler.clone())
                     .config(ClientConfig {
                         reconnect_strategy: ReconnectStrategy::ExponentialBackoff {
@@ -99,7 +109,11 @@ impl<T: AuthHandler + Clone> Client<T> {
             let mut maybe_client = None;
             let mut error: Option<anyhow::Error> = None;
             for name in self.network.to_windows_pipe_name_candidates() {
-                match NetClient::local_windows_pipe(name)
+                match NetClient::build()
+                    .connector(SimulatedConnector::new(
+                        WindowsPipeConnector::local(name),
+                        self.network.simulate.unwrap_or_default(),
+                    ))
                     .auth_handler(self.auth_handler.clone())
                     .config(ClientConfig {
                         reconnect_strategy: ReconnectStrategy::ExponentialBackoff {
@@ -138,16 +152,57 @@ impl<T: AuthHandler + Clone> Client<T> {
     }
 }
 
+/// Held while a handler is asking questions (a [`PromptAuthHandler`] prompting, or a
+/// [`JsonAuthHandler`] waiting on the response to what it sent), so that handlers authenticating
+/// at the same time (such as when launching several servers at once) take turns rather than
+/// interleaving questions and reading the answers meant for one another
+static AUTH_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
 /// Implementation of [`AuthHandler`] that communicates over JSON.
 #[derive(Clone)]
 pub struct JsonAuthHandler {
     tx: MsgSender,
     rx: MsgReceiver,
+
+    /// Destination being authenticated with, included in every message sent when provided
+    destination: Option<String>,
 }
 
 impl JsonAuthHandler {
     pub fn new(tx: MsgSender, rx: MsgReceiver) -> Self {
-        Self { tx, rx }
+        Self {
+            tx,
+            rx,
+            destination: None,
+        }
+    }
+
+    /// Includes `destination` in every message sent as its `destination` field, so that whoever
+    /// answers can tell which of several destinations is asking
+    pub fn with_destination(mut self, destination: impl Into<String>) -> Self {
+        self.destination = Some(destination.into());
+        self
+    }
+
+    fn send(&self, msg: Authentication) -> io::Result<()> {
+        match self.destination.as_deref() {
+            Some(destination) => {
+                let mut value = serde_json::to_value(msg)?;
+                if let Some(map) = value.as_object_mut() {
+                    map.insert("destination".to_string(), destination.into());
+                }
+                self.tx.send_blocking(&value)
+            }
+            None => self.tx.send_blocking(&msg),
+        }
+    }
+
+    /// Sends `msg` and waits for its response, holding [`AUTH_LOCK`] throughout so that no other
+    /// handler can send or read anything in between
+    async fn ask(&self, msg: Authentication) -> io::Result<AuthenticationResponse> {
+        let _lock = AUTH_LOCK.lock().await;
+        self.send(msg)?;
+        self.rx.recv_blocking::<AuthenticationResponse>()
     }
 }
 
@@ -163,9 +218,9 @@ impl AuthHandler for JsonAuthHandler {
         &mut self,
         initialization: Initialization,
     ) -> io::Result<InitializationResponse> {
-        self.tx
-            .send_blocking(&Authentication::Initialization(initialization))?;
-        let response = self.rx.recv_blocking::<AuthenticationResponse>()?;
+        let response = self
+            .ask(Authentication::Initialization(initialization))
+            .await?;
 
         match response {
             AuthenticationResponse::Initialization(x) => Ok(x),
@@ -177,23 +232,18 @@ impl AuthHandler for JsonAuthHandler {
     }
 
     async fn on_start_method(&mut self, start_method: StartMethod) -> io::Result<()> {
-        self.tx
-            .send_blocking(&Authentication::StartMethod(start_method))?;
-        Ok(())
+        self.send(Authentication::StartMethod(start_method))
     }
 
     async fn on_finished(&mut self) -> io::Result<()> {
-        self.tx.send_blocking(&Authentication::Finished)?;
-        Ok(())
+        self.send(Authentication::Finished)
     }
 }
 
 #[async_trait]
 impl AuthMethodHandler for JsonAuthHandler {
     async fn on_challenge(&mut self, challenge: Challenge) -> io::Result<ChallengeResponse> {
-        self.tx
-            .send_blocking(&Authentication::Challenge(challenge))?;
-        let response = self.rx.recv_blocking::<AuthenticationResponse>()?;
+        let response = self.ask(Authentication::Challenge(challenge)).await?;
 
         match response {
             AuthenticationResponse::Challenge(x) => Ok(x),
@@ -208,9 +258,7 @@ impl AuthMethodHandler for JsonAuthHandler {
         &mut self,
         verification: Verification,
     ) -> io::Result<VerificationResponse> {
-        self.tx
-            .send_blocking(&Authentication::Verification(verification))?;
-        let response = self.rx.recv_blocking::<AuthenticationResponse>()?;
+        let response = self.ask(Authentication::Verification(verification)).await?;
 
         match response {
             AuthenticationResponse::Verification(x) => Ok(x),
@@ -222,33 +270,63 @@ impl AuthMethodHandler for JsonAuthHandler {
     }
 
     async fn on_info(&mut self, info: Info) -> io::Result<()> {
-        self.tx.send_blocking(&Authentication::Info(info))?;
-        Ok(())
+        self.send(Authentication::Info(info))
     }
 
     async fn on_error(&mut self, error: Error) -> io::Result<()> {
-        self.tx.send_blocking(&Authentication::Error(error))?;
-        Ok(())
+        self.send(Authentication::Error(error))
     }
 }
 
 /// Implementation of [`AuthHandler`] that uses prompts to perform authentication requests and
 /// notification of different information.
-pub struct PromptAuthHandler(Box<dyn AuthHandler>);
+pub struct PromptAuthHandler {
+    inner: Box<dyn AuthHandler>,
+
+    /// Destination being authenticated with, named by every prompt when provided
+    destination: Option<String>,
+}
 
 impl PromptAuthHandler {
     pub fn new() -> Self {
-        Self(Box::new(SingleAuthHandler::new(
-            PromptAuthMethodHandler::new(
-                |prompt: &str| {
-                    eprintln!("{prompt}");
-                    let mut line = String::new();
-                    std::io::stdin().read_line(&mut line)?;
-                    Ok(line)
-                },
-                |prompt: &str| rpassword::prompt_password(prompt),
-            ),
-        )))
+        Self::with_prompts(
+            |prompt: &str| {
+                eprintln!("{prompt}");
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line)?;
+                Ok(line)
+            },
+            |prompt: &str| rpassword::prompt_password(prompt),
+        )
+    }
+
+    /// Creates a handler that asks for text using `text_prompt` and for secrets (such as
+    /// passwords) using `password_prompt`
+    fn with_prompts(
+        text_prompt: impl Fn(&str) -> io::Result<String> + Send + Sync + 'static,
+        password_prompt: impl Fn(&str) -> io::Result<String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            inner: Box::new(SingleAuthHandler::new(PromptAuthMethodHandler::new(
+                text_prompt,
+                password_prompt,
+            ))),
+            destination: None,
+        }
+    }
+
+    /// Names `destination` at the start of every prompt, so that the user can tell which of
+    /// several destinations is asking
+    pub fn with_destination(mut self, destination: impl Into<String>) -> Self {
+        self.destination = Some(destination.into());
+        self
+    }
+
+    fn tag(&self, text: String) -> String {
+        match self.destination.as_deref() {
+            Some(destination) => format!("[{destination}] {text}"),
+            None => text,
+        }
     }
 }
 
@@ -261,7 +339,10 @@ impl Clone for PromptAuthHandler {
     /// method that creates a new instance, we treat it like a clone and just create an entirely
     /// new prompt auth handler since there is no actual state to clone.
     fn clone(&self) -> Self {
-        Self::new()
+        Self {
+            destination: self.destination.clone(),
+            ..Self::new()
+        }
     }
 }
 
@@ -271,36 +352,194 @@ impl AuthHandler for PromptAuthHandler {
         &mut self,
         initialization: Initialization,
     ) -> io::Result<InitializationResponse> {
-        self.0.on_initialization(initialization).await
+        self.inner.on_initialization(initialization).await
     }
 
     async fn on_start_method(&mut self, start_method: StartMethod) -> io::Result<()> {
-        self.0.on_start_method(start_method).await
+        self.inner.on_start_method(start_method).await
     }
 
     async fn on_finished(&mut self) -> io::Result<()> {
-        self.0.on_finished().await
+        self.inner.on_finished().await
     }
 }
 
 #[async_trait]
 impl AuthMethodHandler for PromptAuthHandler {
-    async fn on_challenge(&mut self, challenge: Challenge) -> io::Result<ChallengeResponse> {
-        self.0.on_challenge(challenge).await
+    async fn on_challenge(&mut self, mut challenge: Challenge) -> io::Result<ChallengeResponse> {
+        for question in challenge.questions.iter_mut() {
+            question.text = self.tag(std::mem::take(&mut question.text));
+        }
+
+        let _lock = AUTH_LOCK.lock().await;
+        self.inner.on_challenge(challenge).await
     }
 
     async fn on_verification(
         &mut self,
-        verification: Verification,
+        mut verification: Verification,
     ) -> io::Result<VerificationResponse> {
-        self.0.on_verification(verification).await
+        verification.text = self.tag(verification.text);
+
+        let _lock = AUTH_LOCK.lock().await;
+        self.inner.on_verification(verification).await
     }
 
     async fn on_info(&mut self, info: Info) -> io::Result<()> {
-        self.0.on_info(info).await
+        // Shown on stderr (such as the message of the day of a server) so that it stays out of
+        // what commands like `distant connect` print for scripts to capture
+        eprintln!("{}", info.text);
+        Ok(())
     }
 
     async fn on_error(&mut self, error: Error) -> io::Result<()> {
-        self.0.on_error(error).await
+        self.inner.on_error(error).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use test_log::test;
+
+    #[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+    async fn prompt_auth_handlers_should_take_turns_prompting_when_authenticating_concurrently() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        // Each handler stands in for that of a separate launch, with prompts that take a while to
+        // answer so that any overlap between them would be seen
+        let tasks = ["first", "second", "third"].map(|name| {
+            let events = Arc::clone(&events);
+            let mut handler = PromptAuthHandler::with_prompts(
+                |_: &str| Ok(String::new()),
+                move |prompt: &str| {
+                    events.lock().unwrap().push(format!("start {prompt}"));
+                    std::thread::sleep(Duration::from_millis(50));
+                    events.lock().unwrap().push(format!("end {prompt}"));
+                    Ok(name.to_string())
+                },
+            );
+
+            tokio::spawn(async move {
+                handler
+                    .on_challenge(Challenge {
+                        questions: vec![
+                            Question::new(format!("{name}-1")),
+                            Question::new(format!("{name}-2")),
+                        ],
+                        options: Default::default(),
+                    })
+                    .await
+            })
+        });
+
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap().answers.len(), 2);
+        }
+
+        // Every question of a challenge is asked before those of another challenge
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 12, "{events:?}");
+        for pair in events.chunks(4) {
+            let name = pair[0]
+                .strip_prefix("start ")
+                .unwrap()
+                .strip_suffix("-1")
+                .unwrap();
+            assert_eq!(
+                pair,
+                [
+                    format!("start {name}-1"),
+                    format!("end {name}-1"),
+                    format!("start {name}-2"),
+                    format!("end {name}-2"),
+                ],
+                "{events:?}"
+            );
+        }
+    }
+
+    #[test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+    async fn json_auth_handlers_should_each_read_their_own_response_when_authenticating_concurrently(
+    ) {
+        let sent = Arc::new(Mutex::new(Vec::<serde_json::Value>::new()));
+
+        // Answers whatever was sent last with the destination that sent it, taking a while to do
+        // so, such that a handler reading the answer meant for another would get the wrong one
+        let tx = MsgSender::from({
+            let sent = Arc::clone(&sent);
+            move |data: &[u8]| {
+                sent.lock()
+                    .unwrap()
+                    .push(serde_json::from_slice(data).unwrap());
+                Ok(())
+            }
+        });
+        let rx = MsgReceiver::from({
+            let sent = Arc::clone(&sent);
+            move |input: &mut String| {
+                std::thread::sleep(Duration::from_millis(50));
+                let last = sent.lock().unwrap().last().cloned().unwrap();
+                let destination = last["destination"].as_str().unwrap();
+                input.push_str(
+                    &serde_json::to_string(&AuthenticationResponse::Challenge(ChallengeResponse {
+                        answers: vec![destination.to_string()],
+                    }))
+                    .unwrap(),
+                );
+                Ok(())
+            }
+        });
+
+        let tasks = ["ssh://first", "ssh://second", "ssh://third"].map(|destination| {
+            let mut handler =
+                JsonAuthHandler::new(tx.clone(), rx.clone()).with_destination(destination);
+            tokio::spawn(async move {
+                let response = handler
+                    .on_challenge(Challenge {
+                        questions: vec![Question::new("password")],
+                        options: Default::default(),
+                    })
+                    .await
+                    .unwrap();
+                (destination, response.answers)
+            })
+        });
+
+        for task in tasks {
+            let (destination, answers) = task.await.unwrap();
+            assert_eq!(answers, [destination]);
+        }
+
+        // Every challenge names its destination
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 3);
+        for msg in sent.iter() {
+            assert_eq!(msg["type"], "auth_challenge");
+            assert!(msg["destination"].is_string(), "{msg}");
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn prompt_auth_handler_should_name_destination_in_prompts() {
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let mut handler = PromptAuthHandler::with_prompts(|_: &str| Ok(String::new()), {
+            let prompts = Arc::clone(&prompts);
+            move |prompt: &str| {
+                prompts.lock().unwrap().push(prompt.to_string());
+                Ok(String::new())
+            }
+        })
+        .with_destination("ssh://example.com");
+
+        handler
+            .on_challenge(Challenge {
+                questions: vec![Question::new("Password: ")],
+                options: Default::default(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(*prompts.lock().unwrap(), ["[ssh://example.com] Password: "]);
     }
 }
@@ -0,0 +1,118 @@
+use flexi_logger::DeferredNow;
+use log::Record;
+use serde_json::{Map, Value};
+use std::io;
+
+/// Formats a log record as a single line of JSON, pulling the connection, channel, request, and
+/// correlation ids out of the `[Conn {id} :: Req {id}]` prefix used by log messages into their own
+/// fields
+pub fn json_format(
+    w: &mut dyn io::Write,
+    now: &mut DeferredNow,
+    record: &Record,
+) -> io::Result<()> {
+    let timestamp = now
+        .now()
+        .to_rfc3339_opts(chrono::SecondsFormat::Micros, false);
+    let line = to_json(&timestamp, record, &record.args().to_string());
+    serde_json::to_writer(&mut *w, &line)?;
+    Ok(())
+}
+
+fn to_json(timestamp: &str, record: &Record, message: &str) -> Value {
+    let mut fields = Map::new();
+    fields.insert("timestamp".to_string(), Value::from(timestamp));
+    fields.insert(
+        "level".to_string(),
+        Value::from(record.level().as_str().to_lowercase()),
+    );
+    fields.insert("target".to_string(), Value::from(record.target()));
+
+    let (ids, message) = split_ids(message);
+    for (key, id) in ids {
+        let name = match key {
+            "Conn" => "connection_id",
+            "Chan" => "channel_id",
+            "Req" => "request_id",
+            "Corr" => "correlation_id",
+            _ => continue,
+        };
+        fields.insert(name.to_string(), Value::from(id));
+    }
+
+    fields.insert("message".to_string(), Value::from(message));
+
+    if let Some(file) = record.file() {
+        fields.insert("file".to_string(), Value::from(file));
+    }
+    if let Some(line) = record.line() {
+        fields.insert("line".to_string(), Value::from(line));
+    }
+
+    Value::Object(fields)
+}
+
+/// Splits a message like `[Conn 123 :: Req abc] Failed` into its ids, `[("Conn", "123"), ("Req",
+/// "abc")]`, and the rest of the message, `Failed`, leaving messages without a prefix untouched
+fn split_ids(message: &str) -> (Vec<(&str, &str)>, &str) {
+    let prefix = message
+        .strip_prefix('[')
+        .and_then(|s| s.split_once(']'))
+        .filter(|(prefix, _)| prefix.starts_with("Conn "));
+
+    match prefix {
+        Some((prefix, rest)) => {
+            let ids = prefix
+                .split(" :: ")
+                .filter_map(|part| part.split_once(' '))
+                .collect();
+            (ids, rest.trim_start())
+        }
+        None => (Vec::new(), message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    fn record_to_json(message: &str) -> Value {
+        to_json(
+            "2023-04-01T12:00:00.000000+00:00",
+            &Record::builder()
+                .args(format_args!("{message}"))
+                .level(Level::Warn)
+                .target("distant_net::server")
+                .file(Some("connection.rs"))
+                .line(Some(42))
+                .build(),
+            message,
+        )
+    }
+
+    #[test]
+    fn to_json_should_pull_ids_out_of_message_prefix() {
+        assert_eq!(
+            record_to_json("[Conn 123 :: Req abc :: Corr save-42] Failed to send response"),
+            serde_json::json!({
+                "timestamp": "2023-04-01T12:00:00.000000+00:00",
+                "level": "warn",
+                "target": "distant_net::server",
+                "connection_id": "123",
+                "request_id": "abc",
+                "correlation_id": "save-42",
+                "message": "Failed to send response",
+                "file": "connection.rs",
+                "line": 42,
+            })
+        );
+    }
+
+    #[test]
+    fn to_json_should_leave_message_without_prefix_untouched() {
+        let line = record_to_json("[Global] Listening on 127.0.0.1");
+        assert_eq!(line["message"], "[Global] Listening on 127.0.0.1");
+        assert!(line.get("connection_id").is_none());
+    }
+}
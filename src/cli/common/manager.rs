@@ -1,3 +1,4 @@
+use super::ActivatedSocket;
 use crate::{
     constants::{global as global_paths, user as user_paths},
     options::{AccessControl, NetworkSettings},
@@ -12,6 +13,9 @@ pub struct Manager {
     pub access: AccessControl,
     pub config: ManagerConfig,
     pub network: NetworkSettings,
+
+    /// Socket passed by socket activation, used in place of binding one
+    pub activated: Option<ActivatedSocket>,
 }
 
 impl Manager {
@@ -22,6 +26,24 @@ impl Manager {
         #[cfg(unix)]
         {
             use distant_core::net::common::UnixSocketListener;
+
+            // When started through socket activation, listen using the passed socket instead
+            if let Some(fd) = self.activated {
+                let mut listener = UnixSocketListener::from_std(fd.into())
+                    .context("Socket passed by socket activation is not a unix socket")?;
+                if listener.is_abstract() {
+                    listener = listener.only_from_current_user();
+                }
+                let socket_path = listener.path().to_path_buf();
+                let boxed_ref = ManagerServer::new(self.config)
+                    .verifier(Verifier::none())
+                    .start(listener)
+                    .context("Failed to start manager using activated socket")?;
+
+                info!("Manager listening using activated unix socket @ {socket_path:?}");
+                return Ok(boxed_ref);
+            }
+
             let socket_path = self.network.unix_socket.as_deref().unwrap_or({
                 if user {
                     user_paths::UNIX_SOCKET_PATH.as_path()
@@ -30,19 +52,34 @@ impl Manager {
                 }
             });
 
-            // Ensure that the path to the socket exists
-            if let Some(parent) = socket_path.parent() {
+            // Ensure that the path to the socket exists, unless it lives in the abstract
+            // namespace (e.g. `@distant`), where there is no file
+            let is_abstract =
+                cfg!(target_os = "linux") && socket_path.to_string_lossy().starts_with('@');
+            if let Some(parent) = socket_path.parent().filter(|_| !is_abstract) {
                 tokio::fs::create_dir_all(parent)
                     .await
                     .with_context(|| format!("Failed to create socket directory {parent:?}"))?;
             }
 
+            let mut listener =
+                UnixSocketListener::bind_with_permissions(socket_path, self.access.into_mode())
+                    .await?;
+
+            // Abstract sockets have no permissions, so only our own user can connect to them
+            if listener.is_abstract() {
+                if !matches!(self.access, AccessControl::Owner) {
+                    warn!(
+                        "Abstract socket {socket_path:?} has no permissions, so only the current \
+                        user can connect to the manager"
+                    );
+                }
+                listener = listener.only_from_current_user();
+            }
+
             let boxed_ref = ManagerServer::new(self.config)
                 .verifier(Verifier::none())
-                .start(
-                    UnixSocketListener::bind_with_permissions(socket_path, self.access.into_mode())
-                        .await?,
-                )
+                .start(listener)
                 .with_context(|| format!("Failed to start manager at socket {socket_path:?}"))?;
 
             info!("Manager listening using unix socket @ {:?}", socket_path);
@@ -67,7 +67,11 @@ impl MsgReceiver {
     pub fn from_stdin() -> Self {
         let reader = std::io::stdin();
         Self::from(move |input: &'_ mut String| {
-            let _ = reader.read_line(input)?;
+            // Reading nothing means that stdin was closed, so stop rather than waiting forever
+            if reader.read_line(input)? == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+            }
+
             Ok(())
         })
     }
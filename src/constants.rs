@@ -31,6 +31,14 @@ pub mod user {
     pub static CACHE_FILE_PATH_STR: Lazy<String> =
         Lazy::new(|| CACHE_FILE_PATH.to_string_lossy().to_string());
 
+    /// Path to the local history of connections made and commands run by the client
+    pub static HISTORY_FILE_PATH: Lazy<PathBuf> =
+        Lazy::new(|| PROJECT_DIR.data_local_dir().join("history.jsonl"));
+
+    /// Path to the decisions made about which remote roots to trust running processes in
+    pub static TRUST_FILE_PATH: Lazy<PathBuf> =
+        Lazy::new(|| PROJECT_DIR.data_local_dir().join("trust.jsonl"));
+
     /// Path to log file for distant client
     pub static CLIENT_LOG_FILE_PATH: Lazy<PathBuf> =
         Lazy::new(|| PROJECT_DIR.cache_dir().join("client.log"));
@@ -1,15 +1,22 @@
 use crate::constants;
 use crate::constants::user::CACHE_FILE_PATH_STR;
+use anyhow::Context;
 use clap::builder::TypedValueParser as _;
-use clap::{Parser, Subcommand, ValueEnum, ValueHint};
+use clap::{Args, Parser, Subcommand, ValueEnum, ValueHint};
 use clap_complete::Shell as ClapCompleteShell;
 use derive_more::IsVariant;
-use distant_core::data::{ChangeKind, Environment};
+use distant_core::data::{
+    ChangeKind, Environment, FileType, PathMapping, ProcessId, SessionId, Signal, SnapshotId,
+    WorkspaceRoot,
+};
 use distant_core::net::common::{ConnectionId, Destination, Map, PortRange};
 use distant_core::net::server::Shutdown;
+use serde::{Deserialize, Serialize};
 use service_manager::ServiceManagerKind;
 use std::ffi::OsString;
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 mod common;
 mod config;
@@ -29,6 +36,18 @@ pub struct Options {
     #[clap(short = 'c', long = "config", global = true, value_parser)]
     config_path: Option<PathBuf>,
 
+    /// Profile within the config whose settings are used as defaults for client commands
+    #[clap(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Render output for screen readers, using labeled lines in place of tables and periodic
+    /// textual updates in place of progress bars
+    #[clap(long, global = true)]
+    pub a11y: bool,
+
+    #[clap(flatten)]
+    pub paging: PagingSettings,
+
     #[clap(subcommand)]
     pub command: DistantSubcommand,
 }
@@ -46,7 +65,26 @@ impl Options {
         T: Into<OsString> + Clone,
     {
         let mut this = Self::try_parse_from(args)?;
-        let config = Config::load_multi(this.config_path.take())?;
+        let config = Box::new(match Config::load_multi(this.config_path.clone()) {
+            // Config commands are how config files get fixed and doctor is how problems with them
+            // get found, so neither can require valid ones
+            Err(_) if this.command.is_config() || this.command.is_doctor() => Config::default(),
+            x => x?,
+        });
+
+        // Profile settings are applied first so that they take priority over the rest of the
+        // config when it is merged
+        if let Some(name) = this.profile.clone() {
+            // NOTE: Names are lowercased when the standard config files are merged together
+            let profile = config
+                .profile
+                .get(&name)
+                .or_else(|| config.profile.get(&name.to_lowercase()))
+                .cloned()
+                .map(Box::new)
+                .with_context(|| format!("No profile named {name} found in config"))?;
+            this.apply_profile(&name, profile);
+        }
         this.merge(config);
 
         // Assign the appropriate log file based on client/manager/server
@@ -55,7 +93,16 @@ impl Options {
             //       and that services that run manager will explicitly override the
             //       log file path
             this.logging.log_file = Some(match &this.command {
-                DistantSubcommand::Client(_) => constants::user::CLIENT_LOG_FILE_PATH.to_path_buf(),
+                DistantSubcommand::Client(_)
+                | DistantSubcommand::Config(_)
+                | DistantSubcommand::Doctor { .. }
+                | DistantSubcommand::ExportState { .. }
+                | DistantSubcommand::History { .. }
+                | DistantSubcommand::ImportState { .. }
+                | DistantSubcommand::Plugin(_)
+                | DistantSubcommand::Render { .. } => {
+                    constants::user::CLIENT_LOG_FILE_PATH.to_path_buf()
+                }
                 DistantSubcommand::Server(_) => constants::user::SERVER_LOG_FILE_PATH.to_path_buf(),
                 DistantSubcommand::Generate(_) => {
                     constants::user::GENERATE_LOG_FILE_PATH.to_path_buf()
@@ -77,8 +124,92 @@ impl Options {
         Ok(this)
     }
 
+    /// Returns the configuration file loaded instead of the default paths, if any
+    pub fn config_path(&self) -> Option<&Path> {
+        self.config_path.as_deref()
+    }
+
+    /// Updates options of client commands based on the settings of a profile, which only fill in
+    /// what was not provided on the command line. Connections are tagged with the `name` of the
+    /// profile so that `distant manager metrics` can tell which profile each belongs to.
+    fn apply_profile(&mut self, name: &str, profile: Box<ProfileConfig>) {
+        let cmd = match &mut self.command {
+            DistantSubcommand::Client(cmd) => cmd,
+
+            // Editors are only told the name of the profile to connect with
+            DistantSubcommand::Generate(GenerateSubcommand::EditorConfig { profile, .. }) => {
+                *profile = Some(name.to_string());
+                return;
+            }
+            _ => return,
+        };
+
+        match cmd {
+            ClientSubcommand::Connect(args) => {
+                let ConnectArgs {
+                    destination,
+                    format,
+                    options,
+                    ..
+                } = &mut **args;
+                if destination.is_none() {
+                    *destination = profile.destination.map(Box::new);
+                }
+                *format = format.or(profile.format);
+                options
+                    .entry("profile".to_string())
+                    .or_insert_with(|| name.to_string());
+            }
+            ClientSubcommand::Launch(args) => {
+                let LaunchArgs {
+                    all_from,
+                    destinations,
+                    distant_args,
+                    distant_bin,
+                    distant_bind_server,
+                    format,
+                    options,
+                    verify,
+                    no_verify,
+                    ..
+                } = &mut **args;
+                if destinations.is_empty() && all_from.is_none() {
+                    destinations.extend(profile.destination);
+                }
+                *format = format.or(profile.format);
+                options.merge(profile.launch.options, /* keep */ true);
+                options
+                    .entry("profile".to_string())
+                    .or_insert_with(|| name.to_string());
+                if !*verify && !*no_verify && profile.launch.verify {
+                    *verify = true;
+                }
+                *distant_args = distant_args.take().or(profile.launch.distant.args);
+                *distant_bin = distant_bin.take().or(profile.launch.distant.bin);
+                *distant_bind_server = distant_bind_server
+                    .take()
+                    .or(profile.launch.distant.bind_server);
+            }
+            ClientSubcommand::Capabilities { format, .. }
+            | ClientSubcommand::Copy { format, .. }
+            | ClientSubcommand::Ps { format, .. } => {
+                *format = format.or(profile.format);
+            }
+            ClientSubcommand::Sync(args) => {
+                args.format = args.format.or(profile.format);
+            }
+            ClientSubcommand::Shell(args) => {
+                args.environment.merge(profile.environment, /* keep */ true);
+            }
+            ClientSubcommand::Spawn(args) => {
+                args.environment.merge(profile.environment, /* keep */ true);
+            }
+            _ => {}
+        }
+    }
+
     /// Updates options based on configuration values.
-    fn merge(&mut self, config: Config) {
+    fn merge(&mut self, config: Box<Config>) {
         macro_rules! update_logging {
             ($kind:ident) => {{
                 self.logging.log_file = self
@@ -87,52 +218,118 @@ impl Options {
                     .take()
                     .or(config.$kind.logging.log_file);
                 self.logging.log_level = self.logging.log_level.or(config.$kind.logging.log_level);
+                self.logging.log_format =
+                    self.logging.log_format.or(config.$kind.logging.log_format);
             }};
         }
 
         match &mut self.command {
             DistantSubcommand::Client(cmd) => {
                 update_logging!(client);
+                self.paging.merge(config.client.paging);
                 match cmd {
-                    ClientSubcommand::Api {
-                        network, timeout, ..
-                    } => {
+                    ClientSubcommand::Api(args) => {
+                        let ApiArgs {
+                            network, timeout, ..
+                        } = &mut **args;
                         network.merge(config.client.network);
                         *timeout = timeout.take().or(config.client.api.timeout);
                     }
+                    ClientSubcommand::Bench { network, .. } => {
+                        network.merge(config.client.network);
+                    }
+                    ClientSubcommand::Browse { network, .. } => {
+                        network.merge(config.client.network);
+                    }
                     ClientSubcommand::Capabilities { network, .. } => {
                         network.merge(config.client.network);
                     }
-                    ClientSubcommand::Connect {
-                        network, options, ..
-                    } => {
+                    ClientSubcommand::Complete { network, .. } => {
+                        network.merge(config.client.network);
+                    }
+                    ClientSubcommand::Connect(args) => {
+                        let ConnectArgs {
+                            network,
+                            history,
+                            options,
+                            ..
+                        } = &mut **args;
                         network.merge(config.client.network);
+                        history.merge(config.client.history);
                         options.merge(config.client.connect.options, /* keep */ true);
+                        options.merge(config.client.net.into(), /* keep */ true);
+                    }
+                    ClientSubcommand::Copy { network, .. } => {
+                        network.merge(config.client.network);
+                    }
+                    ClientSubcommand::Edit { network, .. } => {
+                        network.merge(config.client.network);
+                    }
+                    ClientSubcommand::Env { network, .. } => {
+                        network.merge(config.client.network);
                     }
                     ClientSubcommand::FileSystem(
-                        ClientFileSystemSubcommand::Copy { network, .. }
+                        ClientFileSystemSubcommand::Batch { network, .. }
+                        | ClientFileSystemSubcommand::Copy { network, .. }
+                        | ClientFileSystemSubcommand::Diff { network, .. }
                         | ClientFileSystemSubcommand::Exists { network, .. }
                         | ClientFileSystemSubcommand::MakeDir { network, .. }
+                        | ClientFileSystemSubcommand::MakeTemp { network, .. }
                         | ClientFileSystemSubcommand::Metadata { network, .. }
-                        | ClientFileSystemSubcommand::Read { network, .. }
                         | ClientFileSystemSubcommand::Remove { network, .. }
                         | ClientFileSystemSubcommand::Rename { network, .. }
-                        | ClientFileSystemSubcommand::Search { network, .. }
-                        | ClientFileSystemSubcommand::Watch { network, .. }
-                        | ClientFileSystemSubcommand::Write { network, .. },
+                        | ClientFileSystemSubcommand::Snapshot { network, .. }
+                        | ClientFileSystemSubcommand::SnapshotList { network, .. }
+                        | ClientFileSystemSubcommand::SnapshotPrune { network, .. }
+                        | ClientFileSystemSubcommand::SnapshotRestore { network, .. },
                     ) => {
                         network.merge(config.client.network);
                     }
-                    ClientSubcommand::Launch {
-                        distant_args,
-                        distant_bin,
-                        distant_bind_server,
-                        network,
-                        options,
-                        ..
-                    } => {
+                    ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Read(args)) => {
+                        args.network.merge(config.client.network);
+                    }
+                    ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Search(args)) => {
+                        args.network.merge(config.client.network);
+                    }
+                    ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Watch(args)) => {
+                        args.network.merge(config.client.network);
+                    }
+                    ClientSubcommand::FileSystem(ClientFileSystemSubcommand::Write(args)) => {
+                        args.network.merge(config.client.network);
+                    }
+                    ClientSubcommand::Find(args) => {
+                        let FindArgs { network, .. } = &mut **args;
                         network.merge(config.client.network);
+                    }
+                    ClientSubcommand::Forward { network, .. } => {
+                        network.merge(config.client.network);
+                    }
+                    ClientSubcommand::Grep(args) => {
+                        let GrepArgs { network, .. } = &mut **args;
+                        network.merge(config.client.network);
+                    }
+                    ClientSubcommand::Kill { network, .. } => {
+                        network.merge(config.client.network);
+                    }
+                    ClientSubcommand::Launch(args) => {
+                        let LaunchArgs {
+                            distant_args,
+                            distant_bin,
+                            distant_bind_server,
+                            network,
+                            history,
+                            options,
+                            verify,
+                            no_verify,
+                            ..
+                        } = &mut **args;
+                        network.merge(config.client.network);
+                        history.merge(config.client.history);
                         options.merge(config.client.launch.options, /* keep */ true);
+                        options.merge(config.client.net.into(), /* keep */ true);
+                        if !*verify && !*no_verify && config.client.launch.verify {
+                            *verify = true;
+                        }
                         *distant_args = distant_args.take().or(config.client.launch.distant.args);
                         *distant_bin = distant_bin.take().or(config.client.launch.distant.bin);
                         *distant_bind_server =
@@ -140,19 +337,71 @@ impl Options {
                                 .take()
                                 .or(config.client.launch.distant.bind_server);
                     }
-                    ClientSubcommand::Shell { network, .. } => {
+                    ClientSubcommand::Ping { network, .. } => {
+                        network.merge(config.client.network);
+                    }
+                    ClientSubcommand::Ps { network, .. } => {
+                        network.merge(config.client.network);
+                    }
+                    ClientSubcommand::Shell(args) => {
+                        let ShellArgs {
+                            network,
+                            history,
+                            trust,
+                            init_cmd,
+                            ..
+                        } = &mut **args;
+                        network.merge(config.client.network);
+                        history.merge(config.client.history);
+                        trust.merge(config.client.trust);
+                        *init_cmd = init_cmd.take().or(config.client.shell.init_cmd);
+                    }
+                    ClientSubcommand::Signal { network, .. } => {
+                        network.merge(config.client.network);
+                    }
+                    ClientSubcommand::Spawn(args) => {
+                        let SpawnArgs {
+                            network,
+                            history,
+                            trust,
+                            ..
+                        } = &mut **args;
                         network.merge(config.client.network);
+                        history.merge(config.client.history);
+                        trust.merge(config.client.trust);
                     }
-                    ClientSubcommand::Spawn { network, .. } => {
+                    ClientSubcommand::Sync(args) => {
+                        let SyncArgs { network, .. } = &mut **args;
                         network.merge(config.client.network);
                     }
                     ClientSubcommand::SystemInfo { network, .. } => {
                         network.merge(config.client.network);
                     }
+                    ClientSubcommand::Top { network, .. } => {
+                        network.merge(config.client.network);
+                    }
                 }
             }
-            DistantSubcommand::Generate(_) => {
+            DistantSubcommand::Generate(cmd) => {
                 update_logging!(generate);
+                if let GenerateSubcommand::EditorConfig { network, .. } = cmd {
+                    network.merge(config.client.network);
+                }
+            }
+            DistantSubcommand::Config(_)
+            | DistantSubcommand::Plugin(_)
+            | DistantSubcommand::Render { .. } => {
+                update_logging!(client);
+            }
+            DistantSubcommand::Doctor { network, .. } => {
+                update_logging!(client);
+                network.merge(config.client.network);
+            }
+            DistantSubcommand::History { history_file, .. }
+            | DistantSubcommand::ExportState { history_file, .. }
+            | DistantSubcommand::ImportState { history_file, .. } => {
+                update_logging!(client);
+                *history_file = history_file.take().or(config.client.history.history_file);
             }
             DistantSubcommand::Manager(cmd) => {
                 update_logging!(manager);
@@ -169,11 +418,21 @@ impl Options {
                     ManagerSubcommand::List { network, .. } => {
                         network.merge(config.manager.network);
                     }
+                    ManagerSubcommand::Metrics { network, .. } => {
+                        network.merge(config.manager.network);
+                    }
+                    ManagerSubcommand::Clients { network, .. } => {
+                        network.merge(config.manager.network);
+                    }
                     ManagerSubcommand::Listen {
-                        access, network, ..
+                        access,
+                        network,
+                        autostart,
+                        ..
                     } => {
                         *access = access.take().or(config.manager.access);
                         network.merge(config.manager.network);
+                        autostart.extend(config.manager.autostart.connections);
                     }
                     ManagerSubcommand::Select { network, .. } => {
                         network.merge(config.manager.network);
@@ -186,13 +445,34 @@ impl Options {
                 match cmd {
                     ServerSubcommand::Listen {
                         current_dir,
+                        write_quota,
+                        heartbeat_interval,
+                        motd,
+                        allow_remote_tunnels,
                         host,
                         port,
                         shutdown,
                         use_ipv6,
+                        socket,
+                        limits,
+                        scope,
+                        proc,
+                        fs,
+                        scripts,
                         ..
                     } => {
                         *current_dir = current_dir.take().or(config.server.listen.current_dir);
+                        *write_quota = write_quota.take().or(config.server.listen.write_quota);
+                        *heartbeat_interval = heartbeat_interval
+                            .take()
+                            .or(config.server.listen.heartbeat_interval);
+                        *motd = motd.take().or(config.server.listen.motd);
+                        socket.merge(config.server.listen.socket);
+                        limits.merge(config.server.limits);
+                        scope.merge(config.server.scope);
+                        proc.merge(config.server.proc);
+                        fs.merge(config.server.fs);
+                        scripts.merge(config.server.scripts);
                         if host.is_default() && config.server.listen.host.is_some() {
                             *host = Value::Explicit(config.server.listen.host.unwrap());
                         }
@@ -205,6 +485,12 @@ impl Options {
                         if !*use_ipv6 && config.server.listen.use_ipv6 {
                             *use_ipv6 = true;
                         }
+                        if !*allow_remote_tunnels && config.server.listen.allow_remote_tunnels {
+                            *allow_remote_tunnels = true;
+                        }
+                    }
+                    ServerSubcommand::Clients { network, .. } => {
+                        network.merge(config.client.network);
                     }
                 }
             }
@@ -231,13 +517,126 @@ pub enum DistantSubcommand {
     /// Perform generation commands
     #[clap(subcommand)]
     Generate(GenerateSubcommand),
+
+    /// Read and edit settings within config files
+    #[clap(subcommand)]
+    Config(ConfigSubcommand),
+
+    /// Check for common problems that keep distant from connecting, such as invalid config
+    /// files, no reachable manager, or launch targets that are missing distant
+    Doctor {
+        /// Only run checks on this machine, skipping those that connect to launch targets
+        #[clap(long)]
+        local: bool,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+    },
+
+    /// Search the local history of connections made and commands run by the client, printing
+    /// the oldest entries first
+    History {
+        /// Only include entries using a connection with this id or name, or to a destination
+        /// whose host contains this text
+        #[clap(long)]
+        connection: Option<String>,
+
+        /// Only include entries whose command contains this text, ignoring case
+        #[clap(long, value_name = "TEXT")]
+        grep: Option<String>,
+
+        /// Location of the local history (defaults to a file in the local data directory of the
+        /// user)
+        #[clap(long, value_name = "PATH")]
+        history_file: Option<PathBuf>,
+
+        #[clap(short, long, default_value_t, value_enum)]
+        format: Format,
+    },
+
+    /// Bundle the state of the client (its config with profiles, history, ssh known hosts, and
+    /// optionally ssh keys) into a tar archive, for moving to another machine with `import-state`
+    ExportState {
+        /// Path of the archive to create
+        #[clap(value_name = "ARCHIVE")]
+        archive: PathBuf,
+
+        /// Parts of the state to include, separated by commas (defaults to everything but keys)
+        #[clap(long, value_enum, value_delimiter = ',', value_name = "ITEMS")]
+        include: Vec<StateItem>,
+
+        /// Encrypt the ssh keys within the archive using a password, which is prompted for
+        #[clap(long)]
+        encrypt_keys: bool,
+
+        /// Read the password used to encrypt keys from this file rather than prompting for it
+        #[clap(long, value_name = "PATH", requires = "encrypt_keys")]
+        password_file: Option<PathBuf>,
+
+        /// Location of the local history (defaults to a file in the local data directory of the
+        /// user)
+        #[clap(long, value_name = "PATH")]
+        history_file: Option<PathBuf>,
+    },
+
+    /// Restore the state of the client from an archive created by `export-state`, merging the
+    /// history and known hosts with those already here
+    ImportState {
+        /// Path of the archive to read
+        #[clap(value_name = "ARCHIVE")]
+        archive: PathBuf,
+
+        /// Parts of the state to restore, separated by commas (defaults to everything within the
+        /// archive)
+        #[clap(long, value_enum, value_delimiter = ',', value_name = "ITEMS")]
+        include: Vec<StateItem>,
+
+        /// Replace the config and keys that already exist rather than leaving them as they are
+        #[clap(long)]
+        force: bool,
+
+        /// Read the password used to decrypt keys from this file rather than prompting for it
+        #[clap(long, value_name = "PATH")]
+        password_file: Option<PathBuf>,
+
+        /// Location of the local history (defaults to a file in the local data directory of the
+        /// user)
+        #[clap(long, value_name = "PATH")]
+        history_file: Option<PathBuf>,
+    },
+
+    /// Prints canned responses the way that commands print them, so that their output can be
+    /// captured without a server
+    ///
+    /// Each response is given an id and origin id of its position, starting at 0, so that the
+    /// output is the same every time.
+    #[clap(name = "_render", hide = true)]
+    Render {
+        /// File of responses, either as a JSON array or as one JSON value after another, where
+        /// stdin is read if not provided
+        #[clap(long, value_name = "PATH")]
+        input: Option<PathBuf>,
+
+        #[clap(short, long, default_value_t, value_enum)]
+        format: Format,
+    },
+
+    /// Runs `distant-<name>` from PATH with the remaining arguments, cargo-style, where a
+    /// `--cache <PATH>` given first (and not passed along) picks the cache used to find the
+    /// connection
+    #[clap(external_subcommand)]
+    Plugin(Vec<OsString>),
 }
 
 /// Subcommands for `distant client`.
 #[derive(Debug, PartialEq, Subcommand, IsVariant)]
 pub enum ClientSubcommand {
     /// Listen over stdin & stdout to communicate with a distant server using the JSON lines API
-    Api {
+    Api(Box<ApiArgs>),
+
+    /// Measures the throughput of a connection by sending synthetic payloads to and from the
+    /// remote server, reporting the rate in each direction and the bytes added by framing
+    Bench {
         /// Location to store cached data
         #[clap(
             long,
@@ -247,20 +646,25 @@ pub enum ClientSubcommand {
         )]
         cache: PathBuf,
 
-        /// Represents the maximum time (in seconds) to wait for a network request before timing out.
-        #[clap(long)]
-        timeout: Option<f32>,
-
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
+
+        /// Number of payloads to send in each direction
+        #[clap(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+
+        /// Size (in bytes) of each payload, up to 16MB
+        #[clap(long, value_name = "BYTES", default_value_t = 1024 * 1024)]
+        size: usize,
     },
 
-    /// Retrieves capabilities of the remote server
-    Capabilities {
+    /// Opens an interactive browser of the file system of the remote machine, supporting
+    /// previewing, renaming, deleting, and downloading files
+    Browse {
         /// Location to store cached data
         #[clap(
             long,
@@ -270,19 +674,24 @@ pub enum ClientSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        #[clap(short, long, default_value_t, value_enum)]
-        format: Format,
+        /// Local directory where downloaded files are placed, defaulting to the current directory
+        #[clap(long, value_hint = ValueHint::DirPath)]
+        download_dir: Option<PathBuf>,
+
+        /// Remote directory to start in, defaulting to the current directory of the remote
+        /// machine
+        path: Option<PathBuf>,
     },
 
-    /// Requests that active manager connects to the server at the specified destination
-    Connect {
+    /// Retrieves capabilities of the remote server
+    Capabilities {
         /// Location to store cached data
         #[clap(
             long,
@@ -292,28 +701,22 @@ pub enum ClientSubcommand {
         )]
         cache: PathBuf,
 
-        /// Additional options to provide, typically forwarded to the handler within the manager
-        /// facilitating the connection. Options are key-value pairs separated by comma.
-        ///
-        /// E.g. `key="value",key2="value2"`
-        #[clap(long, default_value_t)]
-        options: Map,
+        /// Specify a connection being managed, either by its id or by its name
+        #[clap(long)]
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        #[clap(short, long, default_value_t, value_enum)]
-        format: Format,
-
-        destination: Box<Destination>,
+        /// Format of output, defaulting to shell unless set by `--profile`
+        #[clap(short, long, value_enum)]
+        format: Option<Format>,
     },
 
-    /// Subcommands for file system operations
-    #[clap(subcommand, name = "fs")]
-    FileSystem(ClientFileSystemSubcommand),
-
-    /// Launches the server-portion of the binary on a remote machine
-    Launch {
+    /// Prints candidates to complete the word at the cursor of a command line, one per line, used
+    /// by the scripts from `distant generate completion`
+    #[clap(name = "__complete", hide = true)]
+    Complete {
         /// Location to store cached data
         #[clap(
             long,
@@ -323,49 +726,29 @@ pub enum ClientSubcommand {
         )]
         cache: PathBuf,
 
-        /// Path to distant program on remote machine to execute via ssh;
-        /// by default, this program needs to be available within PATH as
-        /// specified when compiling ssh (not your login shell)
-        #[clap(name = "distant", long)]
-        distant_bin: Option<String>,
-
-        /// Control the IP address that the server binds to.
-        ///
-        /// The default is `ssh', in which case the server will reply from the IP address that the SSH
-        /// connection came from (as found in the SSH_CONNECTION environment variable). This is
-        /// useful for multihomed servers.
-        ///
-        /// With --bind-server=any, the server will reply on the default interface and will not bind to
-        /// a particular IP address. This can be useful if the connection is made through sslh or
-        /// another tool that makes the SSH connection appear to come from localhost.
-        ///
-        /// With --bind-server=IP, the server will attempt to bind to the specified IP address.
-        #[clap(name = "distant-bind-server", long, value_name = "ssh|any|IP")]
-        distant_bind_server: Option<BindAddress>,
-
-        /// Additional arguments to provide to the server
-        #[clap(name = "distant-args", long, allow_hyphen_values(true))]
-        distant_args: Option<String>,
-
-        /// Additional options to provide, typically forwarded to the handler within the manager
-        /// facilitating the launch of a distant server. Options are key-value pairs separated by
-        /// comma.
-        ///
-        /// E.g. `key="value",key2="value2"`
-        #[clap(long, default_value_t)]
-        options: Map,
+        /// Specify a connection being managed, either by its id or by its name
+        #[clap(long)]
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        #[clap(short, long, default_value_t, value_enum)]
-        format: Format,
+        /// Kind of value being completed
+        #[clap(value_enum)]
+        kind: CompletionKind,
 
-        destination: Box<Destination>,
+        /// Partial word being completed
+        #[clap(default_value = "")]
+        current: String,
     },
 
-    /// Specialized treatment of running a remote shell process
-    Shell {
+    /// Requests that active manager connects to the server at the specified destination
+    Connect(Box<ConnectArgs>),
+
+    /// Copies a file or directory between the local machine and the remote machine, where remote
+    /// paths are prefixed with a colon (e.g. `distant cp ./file.txt :/tmp/file.txt`)
+    #[clap(name = "cp")]
+    Copy {
         /// Location to store cached data
         #[clap(
             long,
@@ -375,28 +758,30 @@ pub enum ClientSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        /// Alternative current directory for the remote process
-        #[clap(long)]
-        current_dir: Option<PathBuf>,
+        /// Format of the progress reported while copying, either a progress bar when stderr is a
+        /// terminal (shell) or a JSON event per update printed to stdout (json), defaulting to
+        /// shell unless set by `--profile`
+        #[clap(short, long, value_enum)]
+        format: Option<Format>,
 
-        /// Environment variables to provide to the shell
-        #[clap(long, default_value_t)]
-        environment: Environment,
+        /// The path to copy from, prefixed with a colon if remote
+        src: TransferPath,
 
-        /// Optional command to run instead of $SHELL
-        #[clap(name = "CMD", last = true)]
-        cmd: Option<Vec<String>>,
+        /// The path to copy to, prefixed with a colon if remote, which places the source within
+        /// it if it is an existing directory
+        dst: TransferPath,
     },
 
-    /// Spawn a process on the remote machine
-    Spawn {
+    /// Opens a file on the remote machine in a local editor, writing it back to the remote
+    /// machine once saved
+    Edit {
         /// Location to store cached data
         #[clap(
             long,
@@ -406,36 +791,32 @@ pub enum ClientSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        /// If specified, will assume the remote process is a LSP server
-        /// and will translate paths that are local into distant:// and vice versa
-        #[clap(long)]
-        lsp: bool,
-
-        /// If specified, will spawn process using a pseudo tty
+        /// Editor command to run with the path of the local copy of the file, defaulting to
+        /// $VISUAL or $EDITOR
         #[clap(long)]
-        pty: bool,
+        editor: Option<String>,
 
-        /// Alternative current directory for the remote process
+        /// If specified, will write the file back to the remote machine every time it is saved
+        /// rather than once the editor exits
         #[clap(long)]
-        current_dir: Option<PathBuf>,
+        watch: bool,
 
-        /// Environment variables to provide to the shell
-        #[clap(long, default_value_t)]
-        environment: Environment,
-
-        /// Command to run
-        #[clap(name = "CMD", num_args = 1.., last = true)]
-        cmd: Vec<String>,
+        /// The path to the file on the remote machine, which is created if it does not exist and
+        /// anything is written to it
+        path: PathBuf,
     },
 
-    SystemInfo {
+    /// Prints the environment variables of the remote server, which are inherited by the
+    /// processes it spawns, as `NAME=value` lines. Only variables that are safe to share, such
+    /// as PATH and locale settings, are included.
+    Env {
         /// Location to store cached data
         #[clap(
             long,
@@ -445,48 +826,29 @@ pub enum ClientSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
+
+        /// Names of variables whose values are printed on their own lines, failing if any of
+        /// them are missing
+        names: Vec<String>,
     },
-}
 
-impl ClientSubcommand {
-    pub fn cache_path(&self) -> &Path {
-        match self {
-            Self::Capabilities { cache, .. } => cache.as_path(),
-            Self::Connect { cache, .. } => cache.as_path(),
-            Self::FileSystem(fs) => fs.cache_path(),
-            Self::Launch { cache, .. } => cache.as_path(),
-            Self::Api { cache, .. } => cache.as_path(),
-            Self::Shell { cache, .. } => cache.as_path(),
-            Self::Spawn { cache, .. } => cache.as_path(),
-            Self::SystemInfo { cache, .. } => cache.as_path(),
-        }
-    }
+    /// Subcommands for file system operations
+    #[clap(subcommand, name = "fs")]
+    FileSystem(ClientFileSystemSubcommand),
 
-    pub fn network_settings(&self) -> &NetworkSettings {
-        match self {
-            Self::Capabilities { network, .. } => network,
-            Self::Connect { network, .. } => network,
-            Self::FileSystem(fs) => fs.network_settings(),
-            Self::Launch { network, .. } => network,
-            Self::Api { network, .. } => network,
-            Self::Shell { network, .. } => network,
-            Self::Spawn { network, .. } => network,
-            Self::SystemInfo { network, .. } => network,
-        }
-    }
-}
+    /// Searches for paths on the remote machine by name, type, size, and modification time,
+    /// recursing through directories, and prints each match on its own line like find(1)
+    Find(Box<FindArgs>),
 
-/// Subcommands for `distant fs`.
-#[derive(Debug, PartialEq, Eq, Subcommand, IsVariant)]
-pub enum ClientFileSystemSubcommand {
-    /// Copies a file or directory on the remote machine
-    Copy {
+    /// Forwards ports through the remote machine until interrupted, either to reach a database
+    /// that only the remote machine can reach or to reach a local service from the remote machine
+    Forward {
         /// Location to store cached data
         #[clap(
             long,
@@ -496,22 +858,52 @@ pub enum ClientFileSystemSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        /// The path to the file or directory on the remote machine
-        src: PathBuf,
+        /// Listens on a local port, forwarding each connection made to it to a host and port
+        /// reachable from the remote machine (can be given more than once)
+        #[clap(
+            short = 'L',
+            long = "local",
+            value_name = "[BIND_ADDRESS:]PORT:HOST:HOSTPORT",
+            required_unless_present_any = ["remote", "dynamic"]
+        )]
+        local: Vec<PortForward>,
 
-        /// New location on the remote machine for copy of file or directory
-        dst: PathBuf,
+        /// Listens on a port of the remote machine, forwarding each connection made to it to a
+        /// host and port reachable from the local machine (can be given more than once)
+        #[clap(
+            short = 'R',
+            long = "remote",
+            value_name = "[BIND_ADDRESS:]PORT:HOST:HOSTPORT",
+            required_unless_present_any = ["local", "dynamic"]
+        )]
+        remote: Vec<PortForward>,
+
+        /// Runs a SOCKS5 proxy on a local port, forwarding each connection made through it to
+        /// whichever host and port it asks for, as reached from the remote machine (can be given
+        /// more than once)
+        #[clap(
+            short = 'D',
+            long = "dynamic",
+            value_name = "[BIND_ADDRESS:]PORT",
+            required_unless_present_any = ["local", "remote"]
+        )]
+        dynamic: Vec<DynamicForward>,
     },
 
-    /// Checks whether the specified path exists on the remote machine
-    Exists {
+    /// Searches the contents of files on the remote machine for lines matching a regex,
+    /// recursing through directories, and prints each match as `path:line:text`
+    Grep(Box<GrepArgs>),
+
+    /// Sends a signal to any process of the remote machine by its pid, such as to clean up one
+    /// that was orphaned, which the server must permit with `--proc-allow-system-kill`
+    Kill {
         /// Location to store cached data
         #[clap(
             long,
@@ -521,19 +913,28 @@ pub enum ClientFileSystemSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        /// The path to the file or directory on the remote machine
-        path: PathBuf,
+        /// Pid of the process to signal, as given by the remote operating system
+        pid: u32,
+
+        /// Signal to send (hup, int, quit, kill, term, usr1, usr2, break), with or without the
+        /// `sig` prefix
+        #[clap(default_value_t = Signal::Term)]
+        signal: Signal,
     },
 
-    /// Creates a directory on the remote machine
-    MakeDir {
+    /// Launches the server-portion of the binary on a remote machine
+    Launch(Box<LaunchArgs>),
+
+    /// Measures the round-trip latency of requests to the remote server, reporting the minimum,
+    /// average, and maximum once done
+    Ping {
         /// Location to store cached data
         #[clap(
             long,
@@ -543,23 +944,25 @@ pub enum ClientFileSystemSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        /// Whether or not to create all parent directories
-        #[clap(long)]
-        all: bool,
+        /// Number of pings to send
+        #[clap(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
 
-        /// The path to the directory on the remote machine
-        path: PathBuf,
+        /// Time (in seconds) to wait between each ping
+        #[clap(long, default_value_t = 1.0)]
+        interval: f32,
     },
 
-    /// Retrieves metadata for the specified path on the remote machine
-    Metadata {
+    /// Lists the running processes spawned by the remote server, with the id, session, system
+    /// pid, uptime, status, and command line of each
+    Ps {
         /// Location to store cached data
         #[clap(
             long,
@@ -569,30 +972,28 @@ pub enum ClientFileSystemSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        /// Whether or not to include a canonicalized version of the path, meaning
-        /// returning the canonical, absolute form of a path with all
-        /// intermediate components normalized and symbolic links resolved
-        #[clap(long)]
-        canonicalize: bool,
-
-        /// Whether or not to follow symlinks to determine absolute file type (dir/file)
-        #[clap(long)]
-        resolve_file_type: bool,
+        /// Format of output, defaulting to shell unless set by `--profile`
+        #[clap(short, long, value_enum)]
+        format: Option<Format>,
 
-        /// The path to the file, directory, or symlink on the remote machine
-        path: PathBuf,
+        /// Include every process of the remote machine, not only those spawned by the server
+        #[clap(short, long)]
+        all: bool,
     },
 
-    /// Reads the contents of a file or retrieves the entries within a directory on the remote
-    /// machine
-    Read {
+    /// Specialized treatment of running a remote shell process
+    Shell(Box<ShellArgs>),
+
+    /// Sends a signal to a process spawned on the remote machine, such as `int` to interrupt it
+    /// or `break` to send `CTRL_BREAK` on Windows
+    Signal {
         /// Location to store cached data
         #[clap(
             long,
@@ -602,53 +1003,30 @@ pub enum ClientFileSystemSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        /// Maximum depth to traverse with 0 indicating there is no maximum
-        /// depth and 1 indicating the most immediate children within the
-        /// directory.
-        ///
-        /// (directory only)
-        #[clap(long, default_value_t = 1)]
-        depth: usize,
-
-        /// Whether or not to return absolute or relative paths.
-        ///
-        /// (directory only)
-        #[clap(long)]
-        absolute: bool,
+        /// Id of the process to signal
+        id: ProcessId,
 
-        /// Whether or not to canonicalize the resulting paths, meaning
-        /// returning the canonical, absolute form of a path with all
-        /// intermediate components normalized and symbolic links resolved.
-        ///
-        /// Note that the flag absolute must be true to have absolute paths
-        /// returned, even if canonicalize is flagged as true.
-        ///
-        /// (directory only)
-        #[clap(long)]
-        canonicalize: bool,
+        /// Signal to send (hup, int, quit, kill, term, usr1, usr2, break), with or without the
+        /// `sig` prefix
+        #[clap(default_value_t = Signal::Term)]
+        signal: Signal,
+    },
 
-        /// Whether or not to include the root directory in the retrieved entries.
-        ///
-        /// If included, the root directory will also be a canonicalized,
-        /// absolute path and will not follow any of the other flags.
-        ///
-        /// (directory only)
-        #[clap(long)]
-        include_root: bool,
+    /// Spawn a process on the remote machine
+    Spawn(Box<SpawnArgs>),
 
-        /// The path to the file or directory on the remote machine.
-        path: PathBuf,
-    },
+    /// Mirrors a directory between the local machine and the remote machine, where the remote
+    /// path is prefixed with a colon (e.g. `distant sync ./project :/tmp/project`)
+    Sync(Box<SyncArgs>),
 
-    /// Removes a file or directory on the remote machine
-    Remove {
+    SystemInfo {
         /// Location to store cached data
         #[clap(
             long,
@@ -658,24 +1036,647 @@ pub enum ClientFileSystemSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
+    },
 
-        /// Whether or not to remove all contents within directory if is a directory.
-        /// Does nothing different for files
+    /// Opens an interactive monitor of the CPU, memory, load, and per-process resource usage of
+    /// the remote machine
+    Top {
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
+
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        force: bool,
+        connection: Option<ConnectionSelector>,
 
-        /// The path to the file or directory on the remote machine
-        path: PathBuf,
+        #[clap(flatten)]
+        network: NetworkSettings,
+
+        /// Time (in seconds) to wait between each refresh of the resource usage
+        #[clap(long, default_value_t = 2.0)]
+        interval: f32,
     },
+}
+/// Arguments for [`ClientSubcommand::Api`].
+#[derive(Args, Debug, PartialEq)]
+pub struct ApiArgs {
+    /// Location to store cached data
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_parser,
+        default_value = CACHE_FILE_PATH_STR.as_str()
+    )]
+    pub cache: PathBuf,
+
+    /// Represents the maximum time (in seconds) to wait for a network request before timing out.
+    ///
+    /// This is also sent as the deadline of each request that does not have one, so that the
+    /// server stops working on requests that time out.
+    #[clap(long)]
+    pub timeout: Option<f32>,
+
+    /// Specify a connection being managed, either by its id or by its name
+    #[clap(long)]
+    pub connection: Option<ConnectionSelector>,
 
-    /// Moves/renames a file or directory on the remote machine
-    Rename {
+    #[clap(flatten)]
+    pub network: NetworkSettings,
+
+    /// Record each request and response of the session to this file as JSON lines
+    #[clap(long, value_name = "PATH", conflicts_with_all = ["replay", "mock"])]
+    pub record: Option<PathBuf>,
+
+    /// Send the requests recorded in this file instead of reading them from stdin, comparing
+    /// the responses with the recorded ones and failing if any differ
+    ///
+    /// Each request waits for its responses for up to the timeout, or 10 seconds if none.
+    #[clap(long, value_name = "PATH", conflicts_with = "mock")]
+    pub replay: Option<PathBuf>,
+
+    /// Respond to requests from stdin using the responses recorded in this file rather than
+    /// a connection, such as to test a plugin without a server
+    #[clap(long, value_name = "PATH")]
+    pub mock: Option<PathBuf>,
+
+    /// How payloads are compared when replaying or mocking a recorded session
+    #[clap(long, value_enum, default_value_t)]
+    pub matcher: SessionMatcher,
+
+    /// JSON pointer to a field of payloads (such as `/path`) to ignore when comparing them,
+    /// which can be provided multiple times
+    #[clap(long, value_name = "POINTER")]
+    pub ignore: Vec<String>,
+}
+
+/// Arguments for [`ClientSubcommand::Connect`].
+#[derive(Args, Debug, PartialEq)]
+pub struct ConnectArgs {
+    /// Location to store cached data
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_parser,
+        default_value = CACHE_FILE_PATH_STR.as_str()
+    )]
+    pub cache: PathBuf,
+
+    /// Additional options to provide, typically forwarded to the handler within the manager
+    /// facilitating the connection. Options are key-value pairs separated by comma.
+    ///
+    /// E.g. `key="value",key2="value2"`
+    #[clap(long, default_value_t)]
+    pub options: Map,
+
+    /// Name to give the connection, which other commands accept in place of its id
+    #[clap(long, value_parser = parse_connection_name)]
+    pub name: Option<String>,
+
+    /// Directory known by one path locally and another on the server (such as a directory
+    /// mounted into a container), translating paths sent to and received from the server.
+    /// Can be provided multiple times.
+    #[clap(long = "path-map", value_name = "CLIENT=SERVER")]
+    pub path_map: Vec<PathMapping>,
+
+    /// Named directory that paths must stay within, which paths can refer to as
+    /// `NAME:relative/path` in commands and the JSON API. Can be provided multiple times.
+    #[clap(long = "root", value_name = "NAME=PATH")]
+    pub roots: Vec<WorkspaceRoot>,
+
+    #[clap(flatten)]
+    pub network: NetworkSettings,
+
+    #[clap(flatten)]
+    pub history: HistorySettings,
+
+    /// Format of output, defaulting to shell unless set by `--profile`
+    #[clap(short, long, value_enum)]
+    pub format: Option<Format>,
+
+    /// Destination of the server, which can be left out when it comes from `--profile`
+    pub destination: Option<Box<Destination>>,
+}
+
+/// Arguments for [`ClientSubcommand::Find`].
+#[derive(Args, Debug, PartialEq)]
+pub struct FindArgs {
+    /// Location to store cached data
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_parser,
+        default_value = CACHE_FILE_PATH_STR.as_str()
+    )]
+    pub cache: PathBuf,
+
+    /// Specify a connection being managed, either by its id or by its name
+    #[clap(long)]
+    pub connection: Option<ConnectionSelector>,
+
+    #[clap(flatten)]
+    pub network: NetworkSettings,
+
+    /// Glob (supporting `*`, `?`, and `[...]`) that the file name of each path must match
+    #[clap(long, value_name = "GLOB")]
+    pub name: Option<String>,
+
+    /// Only find paths of this type: f (file), d (directory), l (symlink), p (fifo), s
+    /// (socket), c (character device), or b (block device), which can be provided multiple
+    /// times to find any of them
+    #[clap(long = "type", value_name = "TYPE", value_parser = parse_find_file_type)]
+    pub file_types: Vec<FileType>,
+
+    /// Only find paths whose size is N bytes, or more than (+N) or less than (-N) it, where N
+    /// can be suffixed with k, M, or G
+    #[clap(
+        long,
+        value_name = "[+-]N",
+        value_parser = FindBound::parse_size,
+        allow_hyphen_values = true
+    )]
+    pub size: Vec<FindBound>,
+
+    /// Only find paths last modified N days ago, or more than (+N) or less than (-N) it
+    #[clap(
+        long,
+        value_name = "[+-]N",
+        value_parser = FindBound::parse_days,
+        allow_hyphen_values = true
+    )]
+    pub mtime: Vec<FindBound>,
+
+    /// Maximum depth of directories to descend into
+    #[clap(long)]
+    pub max_depth: Option<u64>,
+
+    /// Paths of files and directories to search
+    #[clap(default_value = ".")]
+    pub paths: Vec<PathBuf>,
+}
+
+/// Arguments for [`ClientSubcommand::Grep`].
+#[derive(Args, Debug, PartialEq)]
+pub struct GrepArgs {
+    /// Location to store cached data
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_parser,
+        default_value = CACHE_FILE_PATH_STR.as_str()
+    )]
+    pub cache: PathBuf,
+
+    /// Specify a connection being managed, either by its id or by its name
+    #[clap(long)]
+    pub connection: Option<ConnectionSelector>,
+
+    #[clap(flatten)]
+    pub network: NetworkSettings,
+
+    /// Ignore case when matching the pattern
+    #[clap(short = 'i', long)]
+    pub ignore_case: bool,
+
+    /// Treat the pattern as literal text rather than a regex
+    #[clap(short = 'F', long)]
+    pub fixed_strings: bool,
+
+    /// Only print the path of each file containing a match
+    #[clap(short = 'l', long)]
+    pub files_with_matches: bool,
+
+    /// Regex to filter the paths of files searched to only those that match
+    #[clap(long)]
+    pub include: Option<CliSearchQueryCondition>,
+
+    /// Regex to filter the paths of files searched to only those that do not match
+    #[clap(long)]
+    pub exclude: Option<CliSearchQueryCondition>,
+
+    /// Stop after finding this many matches
+    #[clap(short = 'm', long)]
+    pub max_count: Option<u64>,
+
+    /// Print this many lines of context after each match
+    #[clap(short = 'A', long)]
+    pub after_context: Option<u64>,
+
+    /// Print this many lines of context before each match
+    #[clap(short = 'B', long)]
+    pub before_context: Option<u64>,
+
+    /// Print this many lines of context before and after each match, unless overridden by
+    /// --before-context or --after-context
+    #[clap(short = 'C', long)]
+    pub context: Option<u64>,
+
+    /// Pattern to find within the contents of files
+    pub pattern: String,
+
+    /// Paths of files and directories to search
+    #[clap(default_value = ".")]
+    pub paths: Vec<PathBuf>,
+}
+
+/// Arguments for [`ClientSubcommand::Launch`].
+#[derive(Args, Debug, PartialEq)]
+pub struct LaunchArgs {
+    /// Location to store cached data
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_parser,
+        default_value = CACHE_FILE_PATH_STR.as_str()
+    )]
+    pub cache: PathBuf,
+
+    /// Path to distant program on remote machine to execute via ssh;
+    /// by default, this program needs to be available within PATH as
+    /// specified when compiling ssh (not your login shell)
+    #[clap(name = "distant", long)]
+    pub distant_bin: Option<String>,
+
+    /// Control the IP address that the server binds to.
+    ///
+    /// The default is `ssh', in which case the server will reply from the IP address that the SSH
+    /// connection came from (as found in the SSH_CONNECTION environment variable). This is
+    /// useful for multihomed servers.
+    ///
+    /// With --bind-server=any, the server will reply on the default interface and will not bind to
+    /// a particular IP address. This can be useful if the connection is made through sslh or
+    /// another tool that makes the SSH connection appear to come from localhost.
+    ///
+    /// With --bind-server=IP, the server will attempt to bind to the specified IP address.
+    #[clap(name = "distant-bind-server", long, value_name = "ssh|any|IP")]
+    pub distant_bind_server: Option<BindAddress>,
+
+    /// Additional arguments to provide to the server
+    #[clap(name = "distant-args", long, allow_hyphen_values(true))]
+    pub distant_args: Option<String>,
+
+    /// If specified, will verify that the launched server is working by issuing a series of
+    /// requests (system info, capabilities, and a trivial filesystem check), killing the
+    /// connection and failing if any of them do not succeed
+    #[clap(long)]
+    pub verify: bool,
+
+    /// If specified, will not verify the launched server, even if verification is enabled by the
+    /// config or profile
+    #[clap(long, conflicts_with = "verify")]
+    pub no_verify: bool,
+
+    /// Additional options to provide, typically forwarded to the handler within the manager
+    /// facilitating the launch of a distant server. Options are key-value pairs separated by
+    /// comma.
+    ///
+    /// E.g. `key="value",key2="value2"`
+    #[clap(long, default_value_t)]
+    pub options: Map,
+
+    #[clap(flatten)]
+    pub network: NetworkSettings,
+
+    #[clap(flatten)]
+    pub history: HistorySettings,
+
+    /// Format of output, defaulting to shell unless set by `--profile`
+    #[clap(short, long, value_enum)]
+    pub format: Option<Format>,
+
+    /// Path to a TOML file of named launch profiles, each a table with a `destination` and
+    /// optional `options`, that will be launched alongside any destinations provided
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    pub all_from: Option<PathBuf>,
+
+    /// Destinations to launch servers on, which can be left out when one comes from
+    /// `--profile`; when more than one is provided (or profiles are loaded), servers are
+    /// launched concurrently and a summary of the results is reported
+    pub destinations: Vec<Destination>,
+}
+
+/// Arguments for [`ClientSubcommand::Shell`].
+#[derive(Args, Debug, PartialEq)]
+pub struct ShellArgs {
+    /// Location to store cached data
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_parser,
+        default_value = CACHE_FILE_PATH_STR.as_str()
+    )]
+    pub cache: PathBuf,
+
+    /// Specify a connection being managed, either by its id or by its name
+    #[clap(long)]
+    pub connection: Option<ConnectionSelector>,
+
+    #[clap(flatten)]
+    pub network: NetworkSettings,
+
+    #[clap(flatten)]
+    pub history: HistorySettings,
+
+    #[clap(flatten)]
+    pub trust: TrustSettings,
+
+    /// Alternative current directory for the remote process
+    #[clap(long)]
+    pub current_dir: Option<PathBuf>,
+
+    /// Environment variables to provide to the shell
+    #[clap(long, default_value_t)]
+    pub environment: Environment,
+
+    /// If specified, will poll the terminal for input at a fixed interval rather than waiting
+    /// on it from a dedicated thread, for terminals where blocking reads misbehave
+    #[clap(long)]
+    pub poll_input: bool,
+
+    /// How to treat remote programs copying to (or reading from) the clipboard using OSC 52,
+    /// where the local clipboard is accessed using a clipboard command such as pbcopy,
+    /// wl-copy, xclip, or xsel, leaving sequences to the local terminal if none is found
+    #[clap(long, value_enum, default_value_t)]
+    pub clipboard: ClipboardMode,
+
+    /// Character that begins escape sequences when typed at the start of a line, such as
+    /// `~.` to disconnect and `~C` to open a command line (see `~?`), where `none` disables
+    /// escape sequences
+    #[clap(long, value_name = "CHAR", default_value_t)]
+    pub escape_char: EscapeChar,
+
+    /// If specified, leaves the shell running on the server when disconnecting (such as with
+    /// `~.`) or losing the connection, printing the session with which to attach to it again
+    #[clap(long)]
+    pub detach_on_exit: bool,
+
+    /// Attach to the shell with this session, left running by an earlier shell given
+    /// `--detach-on-exit`, rather than spawning a new one
+    #[clap(
+        long,
+        value_name = "SESSION",
+        conflicts_with_all = ["detach_on_exit", "CMD", "current_dir"]
+    )]
+    pub attach: Option<SessionId>,
+
+    /// Claim the shell handed off with this token (using the `handoff` command at the `~C`
+    /// prompt of another shell) rather than spawning a new one
+    #[clap(
+        long,
+        value_name = "TOKEN",
+        conflicts_with_all = ["attach", "detach_on_exit", "CMD", "current_dir", "connection"]
+    )]
+    pub claim: Option<String>,
+
+    /// If specified, forwards the local SSH agent (found at `SSH_AUTH_SOCK`) to the shell
+    /// through a socket on the remote machine, so commands such as `git push` can use local
+    /// keys
+    #[clap(long, conflicts_with_all = ["attach", "claim"])]
+    pub forward_agent: bool,
+
+    /// Command line to send to the shell once it is spawned, before any input is forwarded,
+    /// such as to activate an environment or change into a project directory
+    #[clap(long, value_name = "CMD", conflicts_with_all = ["attach", "claim"])]
+    pub init_cmd: Option<String>,
+
+    /// Optional command to run instead of $SHELL
+    #[clap(name = "CMD", last = true)]
+    pub cmd: Option<Vec<String>>,
+}
+
+/// Arguments for [`ClientSubcommand::Spawn`].
+#[derive(Args, Debug, PartialEq)]
+pub struct SpawnArgs {
+    /// Location to store cached data
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_parser,
+        default_value = CACHE_FILE_PATH_STR.as_str()
+    )]
+    pub cache: PathBuf,
+
+    /// Specify a connection being managed, either by its id or by its name
+    #[clap(long)]
+    pub connection: Option<ConnectionSelector>,
+
+    #[clap(flatten)]
+    pub network: NetworkSettings,
+
+    #[clap(flatten)]
+    pub history: HistorySettings,
+
+    #[clap(flatten)]
+    pub trust: TrustSettings,
+
+    /// If specified, will assume the remote process is a LSP server
+    /// and will translate paths that are local into distant:// and vice versa
+    #[clap(long)]
+    pub lsp: bool,
+
+    /// If specified, will spawn process using a pseudo tty
+    #[clap(long)]
+    pub pty: bool,
+
+    /// If specified, forwards the local SSH agent (found at `SSH_AUTH_SOCK`) to the process
+    /// through a socket on the remote machine, so commands such as `git push` can use local
+    /// keys
+    #[clap(long, conflicts_with = "lsp")]
+    pub forward_agent: bool,
+
+    /// If specified alongside a pseudo tty, will poll the terminal for input at a fixed
+    /// interval rather than waiting on it from a dedicated thread, for terminals where
+    /// blocking reads misbehave
+    #[clap(long, requires = "pty")]
+    pub poll_input: bool,
+
+    /// If specified, will spawn the process detached from the server so that it keeps running
+    /// after the server exits, printing its pid (for use with `distant system kill`) rather
+    /// than its output
+    #[clap(long, conflicts_with_all = ["lsp", "pty", "forward_agent"])]
+    pub detached: bool,
+
+    /// If specified, killing the process (such as by canceling it) also kills the processes it
+    /// started, rather than leaving them running on the remote machine
+    #[clap(long, conflicts_with_all = ["lsp", "detached"])]
+    pub kill_tree: bool,
+
+    /// Alternative current directory for the remote process
+    #[clap(long)]
+    pub current_dir: Option<PathBuf>,
+
+    /// Environment variables to provide to the shell
+    #[clap(long, default_value_t)]
+    pub environment: Environment,
+
+    #[clap(flatten)]
+    pub limits: ProcLimitSettings,
+
+    /// Run the process as this user on the remote machine, which uses sudo or doas (prompting
+    /// for a password if needed) unless the server is privileged
+    #[clap(long)]
+    pub user: Option<String>,
+
+    /// Command to run
+    #[clap(name = "CMD", num_args = 1.., last = true)]
+    pub cmd: Vec<String>,
+}
+
+/// Arguments for [`ClientSubcommand::Sync`].
+#[derive(Args, Debug, PartialEq)]
+pub struct SyncArgs {
+    /// Location to store cached data
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_parser,
+        default_value = CACHE_FILE_PATH_STR.as_str()
+    )]
+    pub cache: PathBuf,
+
+    /// Specify a connection being managed, either by its id or by its name
+    #[clap(long)]
+    pub connection: Option<ConnectionSelector>,
+
+    #[clap(flatten)]
+    pub network: NetworkSettings,
+
+    /// Format of the changes reported while syncing, either a line per change (shell) or a
+    /// JSON event per change (json), defaulting to shell unless set by `--profile`
+    #[clap(short, long, value_enum)]
+    pub format: Option<Format>,
+
+    /// If specified, will keep running after the initial sync, mirroring changes as they
+    /// happen
+    #[clap(long)]
+    pub watch: bool,
+
+    /// If specified, will also mirror changes from the destination back to the source
+    #[clap(long)]
+    pub bidirectional: bool,
+
+    /// How to resolve a path that changed on both sides when syncing bidirectionally
+    #[clap(long, default_value_t, value_enum)]
+    pub conflict: ConflictStrategy,
+
+    /// If specified, will report the changes that would be made without making them
+    #[clap(long, conflicts_with = "watch")]
+    pub dry_run: bool,
+
+    /// The directory to sync from, prefixed with a colon if remote
+    pub src: TransferPath,
+
+    /// The directory to sync to, prefixed with a colon if remote, which is created if missing
+    pub dst: TransferPath,
+}
+
+impl ClientSubcommand {
+    pub fn cache_path(&self) -> &Path {
+        match self {
+            Self::Bench { cache, .. } => cache.as_path(),
+            Self::Browse { cache, .. } => cache.as_path(),
+            Self::Capabilities { cache, .. } => cache.as_path(),
+            Self::Complete { cache, .. } => cache.as_path(),
+            Self::Connect(args) => args.cache.as_path(),
+            Self::Copy { cache, .. } => cache.as_path(),
+            Self::Edit { cache, .. } => cache.as_path(),
+            Self::Env { cache, .. } => cache.as_path(),
+            Self::FileSystem(fs) => fs.cache_path(),
+            Self::Find(args) => args.cache.as_path(),
+            Self::Forward { cache, .. } => cache.as_path(),
+            Self::Grep(args) => args.cache.as_path(),
+            Self::Kill { cache, .. } => cache.as_path(),
+            Self::Launch(args) => args.cache.as_path(),
+            Self::Api(args) => args.cache.as_path(),
+            Self::Ping { cache, .. } => cache.as_path(),
+            Self::Ps { cache, .. } => cache.as_path(),
+            Self::Shell(args) => args.cache.as_path(),
+            Self::Signal { cache, .. } => cache.as_path(),
+            Self::Spawn(args) => args.cache.as_path(),
+            Self::Sync(args) => args.cache.as_path(),
+            Self::SystemInfo { cache, .. } => cache.as_path(),
+            Self::Top { cache, .. } => cache.as_path(),
+        }
+    }
+
+    pub fn network_settings(&self) -> &NetworkSettings {
+        match self {
+            Self::Bench { network, .. } => network,
+            Self::Browse { network, .. } => network,
+            Self::Capabilities { network, .. } => network,
+            Self::Complete { network, .. } => network,
+            Self::Connect(args) => &args.network,
+            Self::Copy { network, .. } => network,
+            Self::Edit { network, .. } => network,
+            Self::Env { network, .. } => network,
+            Self::FileSystem(fs) => fs.network_settings(),
+            Self::Find(args) => &args.network,
+            Self::Forward { network, .. } => network,
+            Self::Grep(args) => &args.network,
+            Self::Kill { network, .. } => network,
+            Self::Launch(args) => &args.network,
+            Self::Api(args) => &args.network,
+            Self::Ping { network, .. } => network,
+            Self::Ps { network, .. } => network,
+            Self::Shell(args) => &args.network,
+            Self::Signal { network, .. } => network,
+            Self::Spawn(args) => &args.network,
+            Self::Sync(args) => &args.network,
+            Self::SystemInfo { network, .. } => network,
+            Self::Top { network, .. } => network,
+        }
+    }
+}
+
+/// Subcommands for `distant fs`.
+#[derive(Debug, PartialEq, Eq, Subcommand, IsVariant)]
+pub enum ClientFileSystemSubcommand {
+    /// Performs a batch of filesystem operations on the remote machine, read as a JSON array
+    /// (e.g. `[{"type": "dir_create", "path": "src"}]`) from a file or stdin
+    Batch {
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
+
+        /// Specify a connection being managed, either by its id or by its name
+        #[clap(long)]
+        connection: Option<ConnectionSelector>,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+
+        /// If specified, will undo the files and directories created by earlier operations if
+        /// any operation fails
+        #[clap(long)]
+        atomic: bool,
+
+        /// If specified, will apply the operations all at once or not at all, backing up
+        /// anything they replace or remove so that it can be restored if any operation fails
+        #[clap(long, conflicts_with = "atomic")]
+        transaction: bool,
+
+        /// Path to a local file containing the operations. If not provided, will read from stdin.
+        #[clap(value_hint = ValueHint::FilePath)]
+        file: Option<PathBuf>,
+    },
+
+    /// Copies a file or directory on the remote machine
+    Copy {
         /// Location to store cached data
         #[clap(
             long,
@@ -685,22 +1686,27 @@ pub enum ClientFileSystemSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
+        /// If specified, will print the paths that would be copied to instead of copying
+        #[clap(long)]
+        dry_run: bool,
+
         /// The path to the file or directory on the remote machine
         src: PathBuf,
 
-        /// New location on the remote machine for the file or directory
+        /// New location on the remote machine for copy of file or directory
         dst: PathBuf,
     },
 
-    /// Search files & directories on the remote machine
-    Search {
+    /// Computes a unified diff between a file on the remote machine and either another remote file
+    /// or text read from stdin
+    Diff {
         /// Location to store cached data
         #[clap(
             long,
@@ -710,32 +1716,27 @@ pub enum ClientFileSystemSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        /// Kind of data to examine using condition
-        #[clap(long, value_enum, default_value_t = CliSearchQueryTarget::Contents)]
-        target: CliSearchQueryTarget,
+        /// Number of lines of context to include around each change
+        #[clap(short = 'U', long, default_value_t = 3)]
+        context: usize,
 
-        /// Condition to meet to be considered a match
-        #[clap(name = "pattern")]
-        condition: CliSearchQueryCondition,
-
-        /// Options to apply to the query
-        #[clap(flatten)]
-        options: CliSearchQueryOptions,
+        /// The path to the original file on the remote machine
+        src: PathBuf,
 
-        /// Paths in which to perform the query
-        #[clap(default_value = ".")]
-        paths: Vec<PathBuf>,
+        /// The path to the changed file on the remote machine. If not provided, will compare
+        /// against text read from stdin.
+        dst: Option<PathBuf>,
     },
 
-    /// Watch a path for changes on the remote machine
-    Watch {
+    /// Checks whether the specified path exists on the remote machine
+    Exists {
         /// Location to store cached data
         #[clap(
             long,
@@ -745,44 +1746,19 @@ pub enum ClientFileSystemSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        /// If true, will recursively watch for changes within directories, othewise
-        /// will only watch for changes immediately within directories
-        #[clap(long)]
-        recursive: bool,
-
-        /// Filter to only report back specified changes
-        #[
-            clap(
-                long,
-                value_parser = clap::builder::PossibleValuesParser::new(ChangeKind::variants())
-                    .map(|s| s.parse::<ChangeKind>().unwrap()),
-            )
-        ]
-        only: Vec<ChangeKind>,
-
-        /// Filter to report back changes except these specified changes
-        #[
-            clap(
-                long,
-                value_parser = clap::builder::PossibleValuesParser::new(ChangeKind::variants())
-                    .map(|s| s.parse::<ChangeKind>().unwrap()),
-            )
-        ]
-        except: Vec<ChangeKind>,
-
-        /// The path to the file, directory, or symlink on the remote machine
+        /// The path to the file or directory on the remote machine
         path: PathBuf,
     },
 
-    /// Writes the contents to a file on the remote machine
-    Write {
+    /// Creates a directory on the remote machine
+    MakeDir {
         /// Location to store cached data
         #[clap(
             long,
@@ -792,90 +1768,56 @@ pub enum ClientFileSystemSubcommand {
         )]
         cache: PathBuf,
 
-        /// Specify a connection being managed
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        connection: Option<ConnectionId>,
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        /// If specified, will append to a file versus overwriting it
+        /// Whether or not to create all parent directories
         #[clap(long)]
-        append: bool,
+        all: bool,
 
-        /// The path to the file on the remote machine
-        path: PathBuf,
+        /// Permissions (in octal, such as 755) given to each directory that is created, in place
+        /// of the server's default
+        #[clap(long, value_name = "MODE", value_parser = parse_mode)]
+        mode: Option<u32>,
 
-        /// Data for server-side writing of content. If not provided, will read from stdin.
-        data: Option<OsString>,
+        /// The path to the directory on the remote machine
+        path: PathBuf,
     },
-}
 
-impl ClientFileSystemSubcommand {
-    pub fn cache_path(&self) -> &Path {
-        match self {
-            Self::Copy { cache, .. } => cache.as_path(),
-            Self::Exists { cache, .. } => cache.as_path(),
-            Self::MakeDir { cache, .. } => cache.as_path(),
-            Self::Metadata { cache, .. } => cache.as_path(),
-            Self::Read { cache, .. } => cache.as_path(),
-            Self::Remove { cache, .. } => cache.as_path(),
-            Self::Rename { cache, .. } => cache.as_path(),
-            Self::Search { cache, .. } => cache.as_path(),
-            Self::Watch { cache, .. } => cache.as_path(),
-            Self::Write { cache, .. } => cache.as_path(),
-        }
-    }
+    /// Creates a uniquely-named, empty file or directory on the remote machine and prints its path
+    MakeTemp {
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
 
-    pub fn network_settings(&self) -> &NetworkSettings {
-        match self {
-            Self::Copy { network, .. } => network,
-            Self::Exists { network, .. } => network,
-            Self::MakeDir { network, .. } => network,
-            Self::Metadata { network, .. } => network,
-            Self::Read { network, .. } => network,
-            Self::Remove { network, .. } => network,
-            Self::Rename { network, .. } => network,
-            Self::Search { network, .. } => network,
-            Self::Watch { network, .. } => network,
-            Self::Write { network, .. } => network,
-        }
-    }
-}
+        /// Specify a connection being managed, either by its id or by its name
+        #[clap(long)]
+        connection: Option<ConnectionSelector>,
 
-/// Subcommands for `distant generate`.
-#[derive(Debug, PartialEq, Eq, Subcommand, IsVariant)]
-pub enum GenerateSubcommand {
-    /// Generate configuration file with base settings
-    Config {
-        /// Path to where the configuration file should be created
-        file: PathBuf,
-    },
+        #[clap(flatten)]
+        network: NetworkSettings,
 
-    /// Generate JSON schema for server request/response
-    Schema {
-        /// If specified, will output to the file at the given path instead of stdout
+        /// Whether or not to create a directory instead of a file
         #[clap(long)]
-        file: Option<PathBuf>,
-    },
+        dir: bool,
 
-    // Generate completion info for CLI
-    Completion {
-        /// If specified, will output to the file at the given path instead of stdout
+        /// Directory on the remote machine in which to create the file or directory, defaulting
+        /// to the temporary directory of the remote machine
         #[clap(long)]
-        file: Option<PathBuf>,
-
-        /// Specific shell to target for the generated output
-        #[clap(value_enum, value_parser)]
-        shell: ClapCompleteShell,
+        parent: Option<PathBuf>,
     },
-}
 
-/// Subcommands for `distant manager`.
-#[derive(Debug, PartialEq, Eq, Subcommand, IsVariant)]
-pub enum ManagerSubcommand {
-    /// Select the active connection
-    Select {
+    /// Retrieves metadata for the specified path on the remote machine
+    Metadata {
         /// Location to store cached data
         #[clap(
             long,
@@ -885,66 +1827,100 @@ pub enum ManagerSubcommand {
         )]
         cache: PathBuf,
 
-        /// Connection to use, otherwise will prompt to select
-        connection: Option<ConnectionId>,
-
-        #[clap(short, long, default_value_t, value_enum)]
-        format: Format,
+        /// Specify a connection being managed, either by its id or by its name
+        #[clap(long)]
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
-    },
-
-    /// Interact with a manager being run by a service management platform
-    #[clap(subcommand)]
-    Service(ManagerServiceSubcommand),
-
-    /// Listen for incoming requests as a manager
-    Listen {
-        /// Type of access to apply to created unix socket or windows pipe
-        #[clap(long, value_enum)]
-        access: Option<AccessControl>,
 
-        /// If specified, will fork the process to run as a standalone daemon
+        /// Whether or not to include a canonicalized version of the path, meaning
+        /// returning the canonical, absolute form of a path with all
+        /// intermediate components normalized and symbolic links resolved
         #[clap(long)]
-        daemon: bool,
+        canonicalize: bool,
 
-        /// If specified, will listen on a user-local unix socket or local windows named pipe
+        /// Whether or not to follow symlinks to determine absolute file type (dir/file)
         #[clap(long)]
-        user: bool,
+        resolve_file_type: bool,
 
-        #[clap(flatten)]
-        network: NetworkSettings,
+        /// The path to the file, directory, or symlink on the remote machine
+        path: PathBuf,
     },
 
-    /// Retrieve a list of capabilities that the manager supports
-    Capabilities {
-        #[clap(short, long, default_value_t, value_enum)]
-        format: Format,
+    /// Reads the contents of a file or retrieves the entries within a directory on the remote
+    /// machine
+    Read(Box<ReadArgs>),
+
+    /// Removes a file or directory on the remote machine
+    Remove {
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
+
+        /// Specify a connection being managed, either by its id or by its name
+        #[clap(long)]
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
-    },
 
-    /// Retrieve information about a specific connection
-    Info {
-        #[clap(short, long, default_value_t, value_enum)]
-        format: Format,
+        /// Whether or not to remove all contents within directory if is a directory.
+        /// Does nothing different for files
+        #[clap(long)]
+        force: bool,
 
-        id: ConnectionId,
+        /// If specified, will print the paths that would be removed instead of removing them
+        #[clap(long)]
+        dry_run: bool,
 
-        #[clap(flatten)]
-        network: NetworkSettings,
+        /// The path to the file or directory on the remote machine
+        path: PathBuf,
     },
 
-    /// List information about all connections
-    List {
-        #[clap(short, long, default_value_t, value_enum)]
-        format: Format,
+    /// Moves/renames a file or directory on the remote machine
+    Rename {
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
+
+        /// Specify a connection being managed, either by its id or by its name
+        #[clap(long)]
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
+        /// If specified, will print the paths that would be moved instead of moving them
+        #[clap(long)]
+        dry_run: bool,
+
+        /// The path to the file or directory on the remote machine
+        src: PathBuf,
+
+        /// New location on the remote machine for the file or directory
+        dst: PathBuf,
+    },
+
+    /// Search files & directories on the remote machine
+    Search(Box<SearchArgs>),
+
+    /// Takes a snapshot of a file or directory on the remote machine, printing its id.
+    ///
+    /// Files are copied, so the snapshot keeps files that are later changed, replaced, or removed.
+    /// Snapshots are held in memory by the server, lasting until they are pruned or the server
+    /// exits, and so do not survive a restart.
+    Snapshot {
         /// Location to store cached data
         #[clap(
             long,
@@ -953,301 +1929,3195 @@ pub enum ManagerSubcommand {
             default_value = CACHE_FILE_PATH_STR.as_str()
         )]
         cache: PathBuf,
-    },
 
-    /// Kill a specific connection
-    Kill {
-        #[clap(short, long, default_value_t, value_enum)]
-        format: Format,
+        /// Specify a connection being managed, either by its id or by its name
+        #[clap(long)]
+        connection: Option<ConnectionSelector>,
 
         #[clap(flatten)]
         network: NetworkSettings,
 
-        id: ConnectionId,
+        /// The path to the file or directory on the remote machine
+        path: PathBuf,
     },
-}
 
-/// Subcommands for `distant manager service`.
-#[derive(Debug, PartialEq, Eq, Subcommand, IsVariant)]
-pub enum ManagerServiceSubcommand {
-    /// Start the manager as a service
-    Start {
-        /// Type of service manager used to run this service, defaulting to platform native
-        #[clap(long, value_enum)]
-        kind: Option<ServiceManagerKind>,
+    /// Lists the snapshots held by the remote machine
+    SnapshotList {
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
 
-        /// If specified, starts as a user-level service
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        user: bool,
+        connection: Option<ConnectionSelector>,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
     },
 
-    /// Stop the manager as a service
-    Stop {
-        #[clap(long, value_enum)]
-        kind: Option<ServiceManagerKind>,
+    /// Removes snapshots from the remote machine
+    SnapshotPrune {
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
 
-        /// If specified, stops a user-level service
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        user: bool,
-    },
+        connection: Option<ConnectionSelector>,
 
-    /// Install the manager as a service
-    Install {
-        #[clap(long, value_enum)]
-        kind: Option<ServiceManagerKind>,
+        #[clap(flatten)]
+        network: NetworkSettings,
 
-        /// If specified, installs as a user-level service
-        #[clap(long)]
-        user: bool,
+        /// If specified, will keep this many of the newest snapshots of each path and remove the
+        /// rest
+        #[clap(long, conflicts_with = "ids")]
+        keep: Option<usize>,
+
+        /// Ids of the snapshots to remove
+        #[clap(required_unless_present = "keep")]
+        ids: Vec<SnapshotId>,
     },
 
-    /// Uninstall the manager as a service
-    Uninstall {
-        #[clap(long, value_enum)]
-        kind: Option<ServiceManagerKind>,
+    /// Replaces a file or directory on the remote machine with a snapshot taken of it
+    SnapshotRestore {
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
 
-        /// If specified, uninstalls a user-level service
+        /// Specify a connection being managed, either by its id or by its name
         #[clap(long)]
-        user: bool,
+        connection: Option<ConnectionSelector>,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+
+        /// Id of the snapshot to restore
+        id: SnapshotId,
     },
+
+    /// Watch a path for changes on the remote machine
+    Watch(Box<WatchArgs>),
+
+    /// Writes the contents to a file on the remote machine
+    Write(Box<WriteArgs>),
 }
+/// Arguments for [`ClientFileSystemSubcommand::Read`].
+#[derive(Args, Debug, PartialEq, Eq)]
+pub struct ReadArgs {
+    /// Location to store cached data
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_parser,
+        default_value = CACHE_FILE_PATH_STR.as_str()
+    )]
+    pub cache: PathBuf,
+
+    /// Specify a connection being managed, either by its id or by its name
+    #[clap(long)]
+    pub connection: Option<ConnectionSelector>,
 
-/// Subcommands for `distant server`.
-#[derive(Debug, PartialEq, Subcommand, IsVariant)]
-pub enum ServerSubcommand {
-    /// Listen for incoming requests as a server
-    Listen {
-        /// Control the IP address that the distant binds to
-        ///
-        /// There are three options here:
-        ///
-        /// 1. `ssh`: the server will reply from the IP address that the SSH
-        /// connection came from (as found in the SSH_CONNECTION environment variable). This is
-        /// useful for multihomed servers.
-        ///
-        /// 2. `any`: the server will reply on the default interface and will not bind to
-        /// a particular IP address. This can be useful if the connection is made through ssh or
-        /// another tool that makes the SSH connection appear to come from localhost.
-        ///
-        /// 3. `IP`: the server will attempt to bind to the specified IP address.
-        #[clap(long, value_name = "ssh|any|IP", default_value_t = Value::Default(BindAddress::Any))]
-        host: Value<BindAddress>,
+    #[clap(flatten)]
+    pub network: NetworkSettings,
+
+    /// Maximum depth to traverse with 0 indicating there is no maximum
+    /// depth and 1 indicating the most immediate children within the
+    /// directory.
+    ///
+    /// (directory only)
+    #[clap(long, default_value_t = 1)]
+    pub depth: usize,
+
+    /// Whether or not to return absolute or relative paths.
+    ///
+    /// (directory only)
+    #[clap(long)]
+    pub absolute: bool,
+
+    /// Whether or not to canonicalize the resulting paths, meaning
+    /// returning the canonical, absolute form of a path with all
+    /// intermediate components normalized and symbolic links resolved.
+    ///
+    /// Note that the flag absolute must be true to have absolute paths
+    /// returned, even if canonicalize is flagged as true.
+    ///
+    /// (directory only)
+    #[clap(long)]
+    pub canonicalize: bool,
+
+    /// Whether or not to include the root directory in the retrieved entries.
+    ///
+    /// If included, the root directory will also be a canonicalized,
+    /// absolute path and will not follow any of the other flags.
+    ///
+    /// (directory only)
+    #[clap(long)]
+    pub include_root: bool,
+
+    /// Sorting and filtering to apply to the entries.
+    #[clap(flatten)]
+    pub options: CliDirReadOptions,
 
-        /// Set the port(s) that the server will attempt to bind to
-        ///
-        /// This can be in the form of PORT1 or PORT1:PORTN to provide a range of ports.
-        /// With `--port 0`, the server will let the operating system pick an available TCP port.
-        ///
-        /// Please note that this option does not affect the server-side port used by SSH
-        #[clap(long, value_name = "PORT[:PORT2]", default_value_t = Value::Default(PortRange::EPHEMERAL))]
-        port: Value<PortRange>,
+    /// The path to the file or directory on the remote machine.
+    pub path: PathBuf,
+}
 
-        /// If specified, will bind to the ipv6 interface if host is "any" instead of ipv4
-        #[clap(short = '6', long)]
-        use_ipv6: bool,
+/// Arguments for [`ClientFileSystemSubcommand::Search`].
+#[derive(Args, Debug, PartialEq, Eq)]
+pub struct SearchArgs {
+    /// Location to store cached data
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_parser,
+        default_value = CACHE_FILE_PATH_STR.as_str()
+    )]
+    pub cache: PathBuf,
+
+    /// Specify a connection being managed, either by its id or by its name
+    #[clap(long)]
+    pub connection: Option<ConnectionSelector>,
 
-        /// Logic to apply to server when determining when to shutdown automatically
-        ///
-        /// 1. "never" means the server will never automatically shut down
-        /// 2. "after=<N>" means the server will shut down after N seconds
-        /// 3. "lonely=<N>" means the server will shut down after N seconds with no connections
-        ///
-        /// Default is to never shut down
-        #[clap(long, default_value_t = Value::Default(Shutdown::Never))]
-        shutdown: Value<Shutdown>,
+    #[clap(flatten)]
+    pub network: NetworkSettings,
 
-        /// Changes the current working directory (cwd) to the specified directory
-        #[clap(long)]
-        current_dir: Option<PathBuf>,
+    /// Kind of data to examine using condition
+    #[clap(long, value_enum, default_value_t = CliSearchQueryTarget::Contents)]
+    pub target: CliSearchQueryTarget,
 
-        /// If specified, will fork the process to run as a standalone daemon
+    /// Condition to meet to be considered a match
+    #[clap(name = "pattern")]
+    pub condition: CliSearchQueryCondition,
+
+    /// Options to apply to the query
+    #[clap(flatten)]
+    pub options: CliSearchQueryOptions,
+
+    /// Paths in which to perform the query
+    #[clap(default_value = ".")]
+    pub paths: Vec<PathBuf>,
+}
+
+/// Arguments for [`ClientFileSystemSubcommand::Watch`].
+#[derive(Args, Debug, PartialEq, Eq)]
+pub struct WatchArgs {
+    /// Location to store cached data
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_parser,
+        default_value = CACHE_FILE_PATH_STR.as_str()
+    )]
+    pub cache: PathBuf,
+
+    /// Specify a connection being managed, either by its id or by its name
+    #[clap(long)]
+    pub connection: Option<ConnectionSelector>,
+
+    #[clap(flatten)]
+    pub network: NetworkSettings,
+
+    /// If true, will recursively watch for changes within directories, othewise
+    /// will only watch for changes immediately within directories
+    #[clap(long)]
+    pub recursive: bool,
+
+    /// Filter to only report back specified changes
+    #[
+        clap(
+            long,
+            value_parser = clap::builder::PossibleValuesParser::new(ChangeKind::variants())
+                .map(|s| s.parse::<ChangeKind>().unwrap()),
+        )
+    ]
+    pub only: Vec<ChangeKind>,
+
+    /// Filter to report back changes except these specified changes
+    #[
+        clap(
+            long,
+            value_parser = clap::builder::PossibleValuesParser::new(ChangeKind::variants())
+                .map(|s| s.parse::<ChangeKind>().unwrap()),
+        )
+    ]
+    pub except: Vec<ChangeKind>,
+
+    /// The path to the file, directory, or symlink on the remote machine
+    pub path: PathBuf,
+}
+
+/// Arguments for [`ClientFileSystemSubcommand::Write`].
+#[derive(Args, Debug, PartialEq, Eq)]
+pub struct WriteArgs {
+    /// Location to store cached data
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_parser,
+        default_value = CACHE_FILE_PATH_STR.as_str()
+    )]
+    pub cache: PathBuf,
+
+    /// Specify a connection being managed, either by its id or by its name
+    #[clap(long)]
+    pub connection: Option<ConnectionSelector>,
+
+    #[clap(flatten)]
+    pub network: NetworkSettings,
+
+    /// If specified, will append to a file versus overwriting it
+    #[clap(long)]
+    pub append: bool,
+
+    /// If specified, will write the data starting at this byte offset within the file,
+    /// leaving the rest of its contents intact, versus overwriting it
+    #[clap(long, conflicts_with = "append")]
+    pub offset: Option<u64>,
+
+    /// If specified, will first check whether the remote machine already has identical
+    /// content (by hash), skipping the transfer of the data if it does
+    #[clap(long, conflicts_with_all = ["append", "offset"])]
+    pub dedup: bool,
+
+    /// Permissions (in octal, such as 644) given to the file if it is created, in place of
+    /// the server's default
+    #[clap(
+        long,
+        value_name = "MODE",
+        value_parser = parse_mode,
+        conflicts_with_all = ["append", "offset", "dedup"]
+    )]
+    pub mode: Option<u32>,
+
+    /// The path to the file on the remote machine
+    pub path: PathBuf,
+
+    /// Data for server-side writing of content. If not provided, will read from stdin.
+    pub data: Option<OsString>,
+}
+
+impl ClientFileSystemSubcommand {
+    pub fn cache_path(&self) -> &Path {
+        match self {
+            Self::Batch { cache, .. } => cache.as_path(),
+            Self::Copy { cache, .. } => cache.as_path(),
+            Self::Diff { cache, .. } => cache.as_path(),
+            Self::Exists { cache, .. } => cache.as_path(),
+            Self::MakeDir { cache, .. } => cache.as_path(),
+            Self::MakeTemp { cache, .. } => cache.as_path(),
+            Self::Metadata { cache, .. } => cache.as_path(),
+            Self::Read(args) => args.cache.as_path(),
+            Self::Remove { cache, .. } => cache.as_path(),
+            Self::Rename { cache, .. } => cache.as_path(),
+            Self::Search(args) => args.cache.as_path(),
+            Self::Snapshot { cache, .. } => cache.as_path(),
+            Self::SnapshotList { cache, .. } => cache.as_path(),
+            Self::SnapshotPrune { cache, .. } => cache.as_path(),
+            Self::SnapshotRestore { cache, .. } => cache.as_path(),
+            Self::Watch(args) => args.cache.as_path(),
+            Self::Write(args) => args.cache.as_path(),
+        }
+    }
+
+    pub fn network_settings(&self) -> &NetworkSettings {
+        match self {
+            Self::Batch { network, .. } => network,
+            Self::Copy { network, .. } => network,
+            Self::Diff { network, .. } => network,
+            Self::Exists { network, .. } => network,
+            Self::MakeDir { network, .. } => network,
+            Self::MakeTemp { network, .. } => network,
+            Self::Metadata { network, .. } => network,
+            Self::Read(args) => &args.network,
+            Self::Remove { network, .. } => network,
+            Self::Rename { network, .. } => network,
+            Self::Search(args) => &args.network,
+            Self::Snapshot { network, .. } => network,
+            Self::SnapshotList { network, .. } => network,
+            Self::SnapshotPrune { network, .. } => network,
+            Self::SnapshotRestore { network, .. } => network,
+            Self::Watch(args) => &args.network,
+            Self::Write(args) => &args.network,
+        }
+    }
+}
+
+/// Subcommands for `distant generate`.
+#[derive(Debug, PartialEq, Eq, Subcommand, IsVariant)]
+pub enum GenerateSubcommand {
+    /// Generate configuration file with base settings
+    Config {
+        /// Path to where the configuration file should be created
+        file: PathBuf,
+    },
+
+    /// Generate JSON schema for server request/response
+    Schema {
+        /// If specified, will output to the file at the given path instead of stdout
         #[clap(long)]
-        daemon: bool,
+        file: Option<PathBuf>,
+    },
 
-        /// If specified, the server will not generate a key but instead listen on stdin for the next
-        /// 32 bytes that it will use as the key instead. Receiving less than 32 bytes before stdin
-        /// is closed is considered an error and any bytes after the first 32 are not used for the key
+    // Generate completion info for CLI
+    Completion {
+        /// If specified, will output to the file at the given path instead of stdout
         #[clap(long)]
-        key_from_stdin: bool,
+        file: Option<PathBuf>,
 
-        /// If specified, will send output to the specified named pipe (internal usage)
-        #[clap(long, help = None, long_help = None)]
-        output_to_local_pipe: Option<std::ffi::OsString>,
+        /// Specific shell to target for the generated output
+        #[clap(value_enum, value_parser)]
+        shell: ClapCompleteShell,
     },
-}
 
-/// Represents the format to use for output from a command.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
-#[clap(rename_all = "snake_case")]
-pub enum Format {
-    /// Sends and receives data in JSON format.
-    Json,
+    /// Generate the settings an editor plugin uses to reach distant (the path to the binary and
+    /// socket of the manager, along with the profile selected with `--profile`), so that setting
+    /// up the editor takes a single command that stays in sync with the paths used here
+    EditorConfig {
+        /// Editor whose plugin the settings are generated for
+        #[clap(long, value_enum)]
+        kind: EditorKind,
+
+        /// If specified, will output to the file at the given path instead of stdout
+        #[clap(long)]
+        file: Option<PathBuf>,
+
+        /// Profile that the editor connects with, taken from `--profile`
+        #[clap(skip)]
+        profile: Option<String>,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+    },
+}
+
+/// Represents an editor whose plugin is set up by `distant generate editor-config`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, ValueEnum)]
+#[clap(rename_all = "kebab_case")]
+pub enum EditorKind {
+    /// Visual Studio Code, given a snippet of `settings.json`.
+    Vscode,
+
+    /// Neovim, given a snippet of `init.lua` that sets up `distant.nvim`.
+    Nvim,
+}
+
+/// Subcommands for `distant config`.
+#[derive(Debug, PartialEq, Eq, Subcommand, IsVariant)]
+pub enum ConfigSubcommand {
+    /// Print the setting at a dotted key such as `client.launch.bin`
+    ///
+    /// Without `--user` or `--global`, the setting is read from the config that is loaded, where
+    /// the user config file takes priority over the global one.
+    Get {
+        #[clap(flatten)]
+        target: ConfigTarget,
+
+        /// Dotted key of the setting
+        key: String,
+    },
+
+    /// Change the setting at a dotted key, which is written to the user config file by default
+    ///
+    /// The value is parsed as TOML, falling back to a string if it is not valid TOML.
+    Set {
+        #[clap(flatten)]
+        target: ConfigTarget,
+
+        /// Dotted key of the setting
+        key: String,
+
+        /// New value of the setting
+        value: String,
+    },
+
+    /// Remove the setting at a dotted key, which is removed from the user config file by default
+    Unset {
+        #[clap(flatten)]
+        target: ConfigTarget,
+
+        /// Dotted key of the setting
+        key: String,
+    },
+}
+
+/// Config file edited by `distant config`, which is the file given by `--config` or the user config
+/// file if neither flag is provided
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConfigTarget {
+    /// Use the config file of the current user
+    #[clap(long, conflicts_with = "global")]
+    pub user: bool,
+
+    /// Use the config file shared by all users
+    #[clap(long)]
+    pub global: bool,
+}
+
+/// Subcommands for `distant manager`.
+#[derive(Debug, PartialEq, Eq, Subcommand, IsVariant)]
+pub enum ManagerSubcommand {
+    /// Select the active connection
+    Select {
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
+
+        /// Connection to use, either by its id or by its name, otherwise will prompt to select
+        connection: Option<ConnectionSelector>,
+
+        #[clap(short, long, default_value_t, value_enum)]
+        format: Format,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+    },
+
+    /// Interact with a manager being run by a service management platform
+    #[clap(subcommand)]
+    Service(ManagerServiceSubcommand),
+
+    /// Listen for incoming requests as a manager
+    Listen {
+        /// Type of access to apply to created unix socket or windows pipe
+        #[clap(long, value_enum)]
+        access: Option<AccessControl>,
+
+        /// If specified, will fork the process to run as a standalone daemon
+        #[clap(long)]
+        daemon: bool,
+
+        /// If specified, will listen on a user-local unix socket or local windows named pipe
+        #[clap(long)]
+        user: bool,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+
+        /// Connections to establish at startup or on a schedule, only available through config
+        #[clap(skip)]
+        autostart: Vec<ManagerAutostartConnection>,
+    },
+
+    /// Retrieve a list of capabilities that the manager supports
+    Capabilities {
+        #[clap(short, long, default_value_t, value_enum)]
+        format: Format,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+    },
+
+    /// Retrieve information about a specific connection
+    Info {
+        #[clap(short, long, default_value_t, value_enum)]
+        format: Format,
+
+        id: ConnectionId,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+    },
+
+    /// List information about all connections
+    List {
+        #[clap(short, long, default_value_t, value_enum)]
+        format: Format,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
+    },
+
+    /// Show the bytes written through each connection, warning about any that are over the soft
+    /// quota of their server
+    Metrics {
+        #[clap(short, long, default_value_t, value_enum)]
+        format: Format,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+    },
+
+    /// Kill a specific connection
+    Kill {
+        #[clap(short, long, default_value_t, value_enum)]
+        format: Format,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+
+        id: ConnectionId,
+    },
+
+    /// List the clients connected to the manager, showing how they were authenticated, when they
+    /// connected, and where from
+    Clients {
+        #[clap(short, long, default_value_t, value_enum)]
+        format: Format,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+
+        /// Forcibly disconnect the client with this id instead of listing clients
+        #[clap(long, value_name = "ID")]
+        disconnect: Option<ConnectionId>,
+    },
+}
+
+/// Subcommands for `distant manager service`.
+#[derive(Debug, PartialEq, Eq, Subcommand, IsVariant)]
+pub enum ManagerServiceSubcommand {
+    /// Start the manager as a service
+    Start {
+        /// Type of service manager used to run this service, defaulting to platform native
+        #[clap(long, value_enum)]
+        kind: Option<ServiceManagerKind>,
+
+        /// If specified, starts as a user-level service
+        #[clap(long)]
+        user: bool,
+    },
+
+    /// Stop the manager as a service
+    Stop {
+        #[clap(long, value_enum)]
+        kind: Option<ServiceManagerKind>,
+
+        /// If specified, stops a user-level service
+        #[clap(long)]
+        user: bool,
+    },
+
+    /// Install the manager as a service
+    Install {
+        #[clap(long, value_enum)]
+        kind: Option<ServiceManagerKind>,
+
+        /// If specified, installs as a user-level service
+        #[clap(long)]
+        user: bool,
+    },
+
+    /// Uninstall the manager as a service
+    Uninstall {
+        #[clap(long, value_enum)]
+        kind: Option<ServiceManagerKind>,
+
+        /// If specified, uninstalls a user-level service
+        #[clap(long)]
+        user: bool,
+    },
+}
+
+/// Subcommands for `distant server`.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, PartialEq, Subcommand, IsVariant)]
+pub enum ServerSubcommand {
+    /// Listen for incoming requests as a server
+    Listen {
+        /// Control the IP address that the distant binds to
+        ///
+        /// There are three options here:
+        ///
+        /// 1. `ssh`: the server will reply from the IP address that the SSH
+        /// connection came from (as found in the SSH_CONNECTION environment variable). This is
+        /// useful for multihomed servers.
+        ///
+        /// 2. `any`: the server will reply on the default interface and will not bind to
+        /// a particular IP address. This can be useful if the connection is made through ssh or
+        /// another tool that makes the SSH connection appear to come from localhost.
+        ///
+        /// 3. `IP`: the server will attempt to bind to the specified IP address.
+        #[clap(long, value_name = "ssh|any|IP", default_value_t = Value::Default(BindAddress::Any))]
+        host: Value<BindAddress>,
+
+        /// Set the port(s) that the server will attempt to bind to
+        ///
+        /// This can be in the form of PORT1 or PORT1:PORTN to provide a range of ports.
+        /// With `--port 0`, the server will let the operating system pick an available TCP port.
+        ///
+        /// Please note that this option does not affect the server-side port used by SSH
+        #[clap(long, value_name = "PORT[:PORT2]", default_value_t = Value::Default(PortRange::EPHEMERAL))]
+        port: Value<PortRange>,
+
+        /// If specified, will bind to the ipv6 interface if host is "any" instead of ipv4
+        #[clap(short = '6', long)]
+        use_ipv6: bool,
+
+        /// Logic to apply to server when determining when to shutdown automatically
+        ///
+        /// 1. "never" means the server will never automatically shut down
+        /// 2. "after=<N>" means the server will shut down after N seconds
+        /// 3. "lonely=<N>" means the server will shut down after N seconds with no connections
+        ///
+        /// Default is to never shut down
+        #[clap(long, default_value_t = Value::Default(Shutdown::Never))]
+        shutdown: Value<Shutdown>,
+
+        /// Changes the current working directory (cwd) to the specified directory
+        #[clap(long)]
+        current_dir: Option<PathBuf>,
+
+        /// Soft quota on the bytes written to files on behalf of each connection, beyond which
+        /// the server logs a warning rather than refusing to write
+        #[clap(long, value_name = "BYTES")]
+        write_quota: Option<u64>,
+
+        /// Seconds between heartbeats sent to each connection while it is otherwise idle, which
+        /// clients rely on to tell that the server is still there
+        ///
+        /// Default is 5 seconds
+        #[clap(long, value_name = "SECS")]
+        heartbeat_interval: Option<u64>,
+
+        /// Message of the day (such as a maintenance notice or the name of the environment)
+        /// reported to each client as it connects, which the CLI shows once per connection
+        #[clap(long, value_name = "TEXT")]
+        motd: Option<String>,
+
+        /// If specified, clients can listen for tunnels on addresses other than loopback ones
+        /// (such as `0.0.0.0`), which lets other machines reach them through the server
+        #[clap(long)]
+        allow_remote_tunnels: bool,
+
+        #[clap(flatten)]
+        socket: SocketSettings,
+
+        #[clap(flatten)]
+        limits: OutputLimitSettings,
+
+        #[clap(flatten)]
+        scope: ProcessScopeSettings,
+
+        #[clap(flatten)]
+        proc: ProcessPolicySettings,
+
+        #[clap(flatten)]
+        fs: FsSettings,
+
+        #[clap(flatten)]
+        scripts: ScriptSettings,
+
+        /// If specified, will fork the process to run as a standalone daemon
+        #[clap(long)]
+        daemon: bool,
+
+        /// If specified, the server will not generate a key but instead listen on stdin for the next
+        /// 32 bytes that it will use as the key instead. Receiving less than 32 bytes before stdin
+        /// is closed is considered an error and any bytes after the first 32 are not used for the key
+        #[clap(long)]
+        key_from_stdin: bool,
+
+        /// If specified, will send output to the specified named pipe (internal usage)
+        #[clap(long, help = None, long_help = None)]
+        output_to_local_pipe: Option<std::ffi::OsString>,
+    },
+
+    /// List the clients connected to the server of a connection, showing how they were
+    /// authenticated, when they connected, and where from
+    Clients {
+        /// Location to store cached data
+        #[clap(
+            long,
+            value_hint = ValueHint::FilePath,
+            value_parser,
+            default_value = CACHE_FILE_PATH_STR.as_str()
+        )]
+        cache: PathBuf,
+
+        /// Specify a connection being managed, either by its id or by its name
+        #[clap(long)]
+        connection: Option<ConnectionSelector>,
+
+        #[clap(short, long, default_value_t, value_enum)]
+        format: Format,
+
+        #[clap(flatten)]
+        network: NetworkSettings,
+
+        /// Forcibly disconnect the client with this id instead of listing clients, after which it
+        /// is unable to reconnect
+        #[clap(long, value_name = "ID")]
+        disconnect: Option<ConnectionId>,
+    },
+}
+
+/// Represents how payloads are compared when replaying or mocking a recorded API session.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "snake_case")]
+pub enum SessionMatcher {
+    /// Payloads must be equal other than their ignored fields.
+    #[default]
+    Exact,
+
+    /// Payloads only need to be of the same type, such as both being `file_read` requests.
+    Type,
+}
+
+/// Represents the kind of value completed by `distant __complete`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "snake_case")]
+pub enum CompletionKind {
+    /// Ids of the connections being managed, each followed by a tab and its destination.
+    Connections,
+
+    /// Paths on the remote machine of the selected connection.
+    Paths,
+}
+
+/// Represents a part of the state of the client moved between machines by `distant export-state`
+/// and `distant import-state`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, ValueEnum)]
+#[clap(rename_all = "kebab_case")]
+pub enum StateItem {
+    /// User config file, including its profiles.
+    Config,
+
+    /// Local history of connections and commands.
+    History,
+
+    /// Hosts known to ssh, found in `~/.ssh/known_hosts`.
+    KnownHosts,
+
+    /// Keys used by ssh, found as `~/.ssh/id_*`.
+    Keys,
+}
+
+impl StateItem {
+    /// Returns the name of the item as used on the command line
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Config => "config",
+            Self::History => "history",
+            Self::KnownHosts => "known-hosts",
+            Self::Keys => "keys",
+        }
+    }
+}
+
+/// Represents how a shell treats the OSC 52 sequences that remote programs (like tmux and vim)
+/// use to access the clipboard.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "kebab_case")]
+pub enum ClipboardMode {
+    /// Passes sequences through to the local terminal untouched.
+    Off,
+
+    /// Sets the local clipboard to the text that remote programs copy.
+    #[default]
+    Write,
+
+    /// Sets the local clipboard and also answers remote programs asking for its contents.
+    ReadWrite,
+}
+
+/// Represents the character that begins escape sequences (like `~.` to disconnect) typed at the
+/// start of a line within a shell, where `none` disables escape sequences.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EscapeChar(pub Option<char>);
+
+impl Default for EscapeChar {
+    fn default() -> Self {
+        Self(Some('~'))
+    }
+}
+
+impl fmt::Display for EscapeChar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            None => write!(f, "none"),
+            Some(c) if c.is_ascii_control() => write!(f, "^{}", ((c as u8) ^ 0x40) as char),
+            Some(c) => write!(f, "{c}"),
+        }
+    }
+}
+
+impl FromStr for EscapeChar {
+    type Err = &'static str;
+
+    /// Parses `none`, a single character, or a control character written as `^X`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            _ if s == "none" => Ok(Self(None)),
+            (Some(c), None, None) => Ok(Self(Some(c))),
+            (Some('^'), Some(c), None) if ('@'..='_').contains(&c.to_ascii_uppercase()) => {
+                Ok(Self(Some(((c.to_ascii_uppercase() as u8) ^ 0x40) as char)))
+            }
+            _ => Err("expected a single character, ^X for a control character, or none"),
+        }
+    }
+}
+
+/// Represents the format to use for output from a command.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[clap(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    /// Sends and receives data in JSON format.
+    Json,
+
+    /// Commands are traditional shell commands and output responses are inline with what is
+    /// expected of a program's output in a shell.
+    Shell,
+}
+
+impl Format {
+    /// Returns true if json format
+    pub fn is_json(self) -> bool {
+        matches!(self, Self::Json)
+    }
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self::Shell
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distant_core::net::common::Host;
+    use distant_core::net::map;
+    use std::time::Duration;
+
+    #[test]
+    fn distant_api_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Api(Box::new(ApiArgs {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                timeout: None,
+                record: None,
+                replay: None,
+                mock: None,
+                matcher: SessionMatcher::Exact,
+                ignore: Vec::new(),
+            }))),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                api: ClientApiConfig { timeout: Some(5.0) },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Api(Box::new(ApiArgs {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("config-unix-socket")),
+                        windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
+                    },
+                    timeout: Some(5.0),
+                    record: None,
+                    replay: None,
+                    mock: None,
+                    matcher: SessionMatcher::Exact,
+                    ignore: Vec::new(),
+                }))),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_api_should_prioritize_explicit_cli_options_when_merging() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
+                log_level: Some(LogLevel::Info),
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Api(Box::new(ApiArgs {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                    windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
+                },
+                timeout: Some(99.0),
+                record: None,
+                replay: None,
+                mock: None,
+                matcher: SessionMatcher::Exact,
+                ignore: Vec::new(),
+            }))),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                api: ClientApiConfig { timeout: Some(5.0) },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Info),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Api(Box::new(ApiArgs {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                    timeout: Some(99.0),
+                    record: None,
+                    replay: None,
+                    mock: None,
+                    matcher: SessionMatcher::Exact,
+                    ignore: Vec::new(),
+                }))),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_bench_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Bench {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                count: 10,
+                size: 1024,
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Bench {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("config-unix-socket")),
+                        windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
+                    },
+                    count: 10,
+                    size: 1024,
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_browse_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Browse {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                download_dir: Some(PathBuf::from("downloads")),
+                path: Some(PathBuf::from("dir")),
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                connect: ClientConnectConfig {
+                    options: map!("hello" -> "world"),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Browse {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("config-unix-socket")),
+                        windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
+                    },
+                    download_dir: Some(PathBuf::from("downloads")),
+                    path: Some(PathBuf::from("dir")),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_browse_should_prioritize_explicit_cli_options_when_merging() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
+                log_level: Some(LogLevel::Info),
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Browse {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                    windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
+                },
+                download_dir: Some(PathBuf::from("downloads")),
+                path: Some(PathBuf::from("dir")),
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                connect: ClientConnectConfig {
+                    options: map!("hello" -> "world", "config" -> "value"),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Info),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Browse {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                    download_dir: Some(PathBuf::from("downloads")),
+                    path: Some(PathBuf::from("dir")),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_capabilities_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Capabilities {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                format: Some(Format::Json),
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                connect: ClientConnectConfig {
+                    options: map!("hello" -> "world"),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Capabilities {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("config-unix-socket")),
+                        windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
+                    },
+                    format: Some(Format::Json),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_capabilities_should_prioritize_explicit_cli_options_when_merging() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
+                log_level: Some(LogLevel::Info),
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Capabilities {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                    windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
+                },
+                format: Some(Format::Json),
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                connect: ClientConnectConfig {
+                    options: map!("hello" -> "world", "config" -> "value"),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Info),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Capabilities {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                    format: Some(Format::Json),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_complete_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Complete {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                kind: CompletionKind::Paths,
+                current: String::from("/ho"),
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                connect: ClientConnectConfig {
+                    options: map!("hello" -> "world"),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Complete {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("config-unix-socket")),
+                        windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
+                    },
+                    kind: CompletionKind::Paths,
+                    current: String::from("/ho"),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_complete_should_prioritize_explicit_cli_options_when_merging() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
+                log_level: Some(LogLevel::Info),
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Complete {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                    windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
+                },
+                kind: CompletionKind::Paths,
+                current: String::from("/ho"),
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                connect: ClientConnectConfig {
+                    options: map!("hello" -> "world", "config" -> "value"),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Info),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Complete {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                    kind: CompletionKind::Paths,
+                    current: String::from("/ho"),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_connect_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Connect(Box::new(ConnectArgs {
+                cache: PathBuf::new(),
+                options: map!(),
+                name: None,
+                path_map: Vec::new(),
+                roots: Vec::new(),
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                history: HistorySettings {
+                    no_history: false,
+                    history_file: None,
+                },
+                format: Some(Format::Json),
+                destination: Some(Box::new("test://destination".parse().unwrap())),
+            }))),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                connect: ClientConnectConfig {
+                    options: map!("hello" -> "world"),
+                },
+                net: ClientNetworkConfig {
+                    socket: SocketSettings {
+                        tcp_nodelay: true,
+                        tcp_keepalive: Some(30),
+                        ..Default::default()
+                    },
+                    resolve: [("buildbox".to_string(), "10.1.2.3".parse().unwrap())]
+                        .into_iter()
+                        .collect(),
+                    dns_timeout: Some(1.5),
+                    heartbeat_timeout: Some(30),
+                    address_family: Some(AddressFamily::Ipv4),
+                    proxy: Some("http://proxy.example.com:3128".parse().unwrap()),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Connect(Box::new(
+                    ConnectArgs {
+                        cache: PathBuf::new(),
+                        options: map!(
+                            "hello" -> "world",
+                            "tcp_nodelay" -> "true",
+                            "tcp_keepalive" -> "30",
+                            "resolve" -> "buildbox=10.1.2.3",
+                            "dns_timeout" -> "1.5",
+                            "heartbeat_timeout" -> "30",
+                            "address_family" -> "ipv4",
+                            "proxy" -> "http://proxy.example.com:3128"
+                        ),
+                        name: None,
+                        path_map: Vec::new(),
+                        roots: Vec::new(),
+                        network: NetworkSettings {
+                            unix_socket: Some(PathBuf::from("config-unix-socket")),
+                            windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
+                        },
+                        history: HistorySettings {
+                            no_history: false,
+                            history_file: None,
+                        },
+                        format: Some(Format::Json),
+                        destination: Some(Box::new("test://destination".parse().unwrap())),
+                    }
+                ))),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_connect_should_prioritize_explicit_cli_options_when_merging() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
+                log_level: Some(LogLevel::Info),
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Connect(Box::new(ConnectArgs {
+                cache: PathBuf::new(),
+                options: map!("hello" -> "test", "cli" -> "value", "tcp_keepalive" -> "10"),
+                name: None,
+                path_map: Vec::new(),
+                roots: Vec::new(),
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                    windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
+                },
+                history: HistorySettings {
+                    no_history: false,
+                    history_file: None,
+                },
+                format: Some(Format::Json),
+                destination: Some(Box::new("test://destination".parse().unwrap())),
+            }))),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                connect: ClientConnectConfig {
+                    options: map!("hello" -> "world", "config" -> "value"),
+                },
+                net: ClientNetworkConfig {
+                    socket: SocketSettings {
+                        tcp_nodelay: true,
+                        tcp_keepalive: Some(30),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Info),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Connect(Box::new(
+                    ConnectArgs {
+                        cache: PathBuf::new(),
+                        options: map!(
+                            "hello" -> "test",
+                            "cli" -> "value",
+                            "config" -> "value",
+                            "tcp_nodelay" -> "true",
+                            "tcp_keepalive" -> "10"
+                        ),
+                        name: None,
+                        path_map: Vec::new(),
+                        roots: Vec::new(),
+                        network: NetworkSettings {
+                            unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                            windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
+                        },
+                        history: HistorySettings {
+                            no_history: false,
+                            history_file: None,
+                        },
+                        format: Some(Format::Json),
+                        destination: Some(Box::new("test://destination".parse().unwrap())),
+                    }
+                ))),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_cp_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Copy {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                format: Some(Format::Json),
+                src: TransferPath::Local(PathBuf::from("src")),
+                dst: TransferPath::Remote(PathBuf::from("dst")),
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                connect: ClientConnectConfig {
+                    options: map!("hello" -> "world"),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Copy {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("config-unix-socket")),
+                        windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
+                    },
+                    format: Some(Format::Json),
+                    src: TransferPath::Local(PathBuf::from("src")),
+                    dst: TransferPath::Remote(PathBuf::from("dst")),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_cp_should_prioritize_explicit_cli_options_when_merging() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
+                log_level: Some(LogLevel::Info),
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Copy {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                    windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
+                },
+                format: Some(Format::Json),
+                src: TransferPath::Local(PathBuf::from("src")),
+                dst: TransferPath::Remote(PathBuf::from("dst")),
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                connect: ClientConnectConfig {
+                    options: map!("hello" -> "world", "config" -> "value"),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Info),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Copy {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                    format: Some(Format::Json),
+                    src: TransferPath::Local(PathBuf::from("src")),
+                    dst: TransferPath::Remote(PathBuf::from("dst")),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_edit_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Edit {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                editor: Some(String::from("vim")),
+                watch: true,
+                path: PathBuf::from("file"),
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                connect: ClientConnectConfig {
+                    options: map!("hello" -> "world"),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Edit {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("config-unix-socket")),
+                        windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
+                    },
+                    editor: Some(String::from("vim")),
+                    watch: true,
+                    path: PathBuf::from("file"),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_edit_should_prioritize_explicit_cli_options_when_merging() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
+                log_level: Some(LogLevel::Info),
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Edit {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                    windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
+                },
+                editor: Some(String::from("vim")),
+                watch: true,
+                path: PathBuf::from("file"),
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                connect: ClientConnectConfig {
+                    options: map!("hello" -> "world", "config" -> "value"),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Info),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Edit {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                    editor: Some(String::from("vim")),
+                    watch: true,
+                    path: PathBuf::from("file"),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_find_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Find(Box::new(FindArgs {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                name: Some(String::from("*.rs")),
+                file_types: vec![FileType::File],
+                size: vec![FindBound::MoreThan(1024)],
+                mtime: vec![FindBound::LessThan(7)],
+                max_depth: Some(2),
+                paths: vec![PathBuf::from(".")],
+            }))),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Find(Box::new(FindArgs {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("config-unix-socket")),
+                        windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
+                    },
+                    name: Some(String::from("*.rs")),
+                    file_types: vec![FileType::File],
+                    size: vec![FindBound::MoreThan(1024)],
+                    mtime: vec![FindBound::LessThan(7)],
+                    max_depth: Some(2),
+                    paths: vec![PathBuf::from(".")],
+                }))),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_find_should_prioritize_explicit_cli_options_when_merging() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
+                log_level: Some(LogLevel::Info),
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Find(Box::new(FindArgs {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                    windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
+                },
+                name: Some(String::from("*.rs")),
+                file_types: vec![FileType::File],
+                size: vec![FindBound::MoreThan(1024)],
+                mtime: vec![FindBound::LessThan(7)],
+                max_depth: Some(2),
+                paths: vec![PathBuf::from(".")],
+            }))),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Info),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Find(Box::new(FindArgs {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                    name: Some(String::from("*.rs")),
+                    file_types: vec![FileType::File],
+                    size: vec![FindBound::MoreThan(1024)],
+                    mtime: vec![FindBound::LessThan(7)],
+                    max_depth: Some(2),
+                    paths: vec![PathBuf::from(".")],
+                }))),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_grep_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Grep(Box::new(GrepArgs {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                ignore_case: true,
+                fixed_strings: false,
+                files_with_matches: false,
+                include: None,
+                exclude: None,
+                max_count: Some(3),
+                after_context: None,
+                before_context: None,
+                context: None,
+                pattern: String::from("pattern"),
+                paths: vec![PathBuf::from(".")],
+            }))),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Grep(Box::new(GrepArgs {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("config-unix-socket")),
+                        windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
+                    },
+                    ignore_case: true,
+                    fixed_strings: false,
+                    files_with_matches: false,
+                    include: None,
+                    exclude: None,
+                    max_count: Some(3),
+                    after_context: None,
+                    before_context: None,
+                    context: None,
+                    pattern: String::from("pattern"),
+                    paths: vec![PathBuf::from(".")],
+                }))),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_grep_should_prioritize_explicit_cli_options_when_merging() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
+                log_level: Some(LogLevel::Info),
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Grep(Box::new(GrepArgs {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                    windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
+                },
+                ignore_case: true,
+                fixed_strings: false,
+                files_with_matches: false,
+                include: None,
+                exclude: None,
+                max_count: Some(3),
+                after_context: None,
+                before_context: None,
+                context: None,
+                pattern: String::from("pattern"),
+                paths: vec![PathBuf::from(".")],
+            }))),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Info),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Grep(Box::new(GrepArgs {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                    ignore_case: true,
+                    fixed_strings: false,
+                    files_with_matches: false,
+                    include: None,
+                    exclude: None,
+                    max_count: Some(3),
+                    after_context: None,
+                    before_context: None,
+                    context: None,
+                    pattern: String::from("pattern"),
+                    paths: vec![PathBuf::from(".")],
+                }))),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_launch_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Launch(Box::new(LaunchArgs {
+                cache: PathBuf::new(),
+                distant_bin: None,
+                distant_bind_server: None,
+                distant_args: None,
+                options: map!(),
+                verify: false,
+                no_verify: false,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                history: HistorySettings {
+                    no_history: false,
+                    history_file: None,
+                },
+                format: Some(Format::Json),
+                all_from: None,
+                destinations: vec!["test://destination".parse().unwrap()],
+            }))),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                launch: ClientLaunchConfig {
+                    distant: ClientLaunchDistantConfig {
+                        args: Some(String::from("config-args")),
+                        bin: Some(String::from("config-bin")),
+                        bind_server: Some(BindAddress::Host(Host::Name(String::from(
+                            "config-host",
+                        )))),
+                    },
+                    options: map!("hello" -> "world"),
+                    verify: true,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Launch(Box::new(
+                    LaunchArgs {
+                        cache: PathBuf::new(),
+                        distant_args: Some(String::from("config-args")),
+                        distant_bin: Some(String::from("config-bin")),
+                        distant_bind_server: Some(BindAddress::Host(Host::Name(String::from(
+                            "config-host",
+                        )))),
+                        options: map!("hello" -> "world"),
+                        verify: true,
+                        no_verify: false,
+                        network: NetworkSettings {
+                            unix_socket: Some(PathBuf::from("config-unix-socket")),
+                            windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
+                        },
+                        history: HistorySettings {
+                            no_history: false,
+                            history_file: None,
+                        },
+                        format: Some(Format::Json),
+                        all_from: None,
+                        destinations: vec!["test://destination".parse().unwrap()],
+                    }
+                ))),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_launch_should_prioritize_explicit_cli_options_when_merging() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
+                log_level: Some(LogLevel::Info),
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Launch(Box::new(LaunchArgs {
+                cache: PathBuf::new(),
+                distant_args: Some(String::from("cli-args")),
+                distant_bin: Some(String::from("cli-bin")),
+                distant_bind_server: Some(BindAddress::Host(Host::Name(String::from("cli-host")))),
+                options: map!("hello" -> "test", "cli" -> "value"),
+                verify: true,
+                no_verify: false,
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                    windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
+                },
+                history: HistorySettings {
+                    no_history: false,
+                    history_file: None,
+                },
+                format: Some(Format::Json),
+                all_from: None,
+                destinations: vec!["test://destination".parse().unwrap()],
+            }))),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                launch: ClientLaunchConfig {
+                    distant: ClientLaunchDistantConfig {
+                        args: Some(String::from("config-args")),
+                        bin: Some(String::from("config-bin")),
+                        bind_server: Some(BindAddress::Host(Host::Name(String::from(
+                            "config-host",
+                        )))),
+                    },
+                    options: map!("hello" -> "world", "config" -> "value"),
+                    verify: false,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Info),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Launch(Box::new(
+                    LaunchArgs {
+                        cache: PathBuf::new(),
+                        distant_args: Some(String::from("cli-args")),
+                        distant_bin: Some(String::from("cli-bin")),
+                        distant_bind_server: Some(BindAddress::Host(Host::Name(String::from(
+                            "cli-host",
+                        )))),
+                        options: map!("hello" -> "test", "config" -> "value", "cli" -> "value"),
+                        verify: true,
+                        no_verify: false,
+                        network: NetworkSettings {
+                            unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                            windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
+                        },
+                        history: HistorySettings {
+                            no_history: false,
+                            history_file: None,
+                        },
+                        format: Some(Format::Json),
+                        all_from: None,
+                        destinations: vec!["test://destination".parse().unwrap()],
+                    }
+                ))),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_launch_should_not_verify_if_disabled_on_cli_even_if_enabled_by_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Launch(Box::new(LaunchArgs {
+                cache: PathBuf::new(),
+                distant_bin: None,
+                distant_bind_server: None,
+                distant_args: None,
+                options: map!(),
+                verify: false,
+                no_verify: true,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                history: HistorySettings {
+                    no_history: false,
+                    history_file: None,
+                },
+                format: Some(Format::Json),
+                all_from: None,
+                destinations: vec!["test://destination".parse().unwrap()],
+            }))),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                launch: ClientLaunchConfig {
+                    verify: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        match options.command {
+            DistantSubcommand::Client(ClientSubcommand::Launch(args)) => {
+                assert!(!args.verify, "Unexpectedly enabled verification");
+            }
+            x => panic!("Unexpected command: {x:?}"),
+        }
+    }
+
+    #[test]
+    fn distant_ping_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Ping {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                count: 4,
+                interval: 1.0,
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Ping {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("config-unix-socket")),
+                        windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
+                    },
+                    count: 4,
+                    interval: 1.0,
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_shell_should_support_merging_with_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Shell(Box::new(ShellArgs {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: None,
+                    simulate: None,
+                },
+                history: HistorySettings {
+                    no_history: false,
+                    history_file: None,
+                },
+                trust: TrustSettings {
+                    trust: false,
+                    trust_file: None,
+                },
+                current_dir: None,
+                environment: map!(),
+                poll_input: false,
+                clipboard: ClipboardMode::Write,
+                escape_char: EscapeChar::default(),
+                detach_on_exit: false,
+                attach: None,
+                claim: None,
+                forward_agent: false,
+                init_cmd: None,
+                cmd: None,
+            }))),
+        };
 
-    /// Commands are traditional shell commands and output responses are inline with what is
-    /// expected of a program's output in a shell.
-    Shell,
-}
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                history: HistorySettings {
+                    no_history: true,
+                    history_file: Some(PathBuf::from("config-history-file")),
+                },
+                shell: ClientShellConfig {
+                    init_cmd: Some(String::from("config-init-cmd")),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
 
-impl Format {
-    /// Returns true if json format
-    pub fn is_json(self) -> bool {
-        matches!(self, Self::Json)
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Shell(Box::new(ShellArgs {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("config-unix-socket")),
+                        windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
+                    },
+                    history: HistorySettings {
+                        no_history: true,
+                        history_file: Some(PathBuf::from("config-history-file")),
+                    },
+                    trust: TrustSettings {
+                        trust: false,
+                        trust_file: None,
+                    },
+                    current_dir: None,
+                    environment: map!(),
+                    poll_input: false,
+                    clipboard: ClipboardMode::Write,
+                    escape_char: EscapeChar::default(),
+                    detach_on_exit: false,
+                    attach: None,
+                    claim: None,
+                    forward_agent: false,
+                    init_cmd: Some(String::from("config-init-cmd")),
+                    cmd: None,
+                }))),
+            }
+        );
     }
-}
 
-impl Default for Format {
-    fn default() -> Self {
-        Self::Shell
-    }
-}
+    #[test]
+    fn distant_shell_should_prioritize_explicit_cli_options_when_merging() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
+                log_level: Some(LogLevel::Info),
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Shell(Box::new(ShellArgs {
+                cache: PathBuf::new(),
+                connection: None,
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                    windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
+                },
+                history: HistorySettings {
+                    no_history: false,
+                    history_file: Some(PathBuf::from("cli-history-file")),
+                },
+                trust: TrustSettings {
+                    trust: false,
+                    trust_file: None,
+                },
+                current_dir: None,
+                environment: map!(),
+                poll_input: false,
+                clipboard: ClipboardMode::Write,
+                escape_char: EscapeChar::default(),
+                detach_on_exit: false,
+                attach: None,
+                claim: None,
+                forward_agent: false,
+                init_cmd: Some(String::from("cli-init-cmd")),
+                cmd: None,
+            }))),
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use distant_core::net::common::Host;
-    use distant_core::net::map;
-    use std::time::Duration;
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                history: HistorySettings {
+                    no_history: true,
+                    history_file: Some(PathBuf::from("config-history-file")),
+                },
+                shell: ClientShellConfig {
+                    init_cmd: Some(String::from("config-init-cmd")),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Info),
+                },
+                command: DistantSubcommand::Client(ClientSubcommand::Shell(Box::new(ShellArgs {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                    history: HistorySettings {
+                        no_history: true,
+                        history_file: Some(PathBuf::from("cli-history-file")),
+                    },
+                    trust: TrustSettings {
+                        trust: false,
+                        trust_file: None,
+                    },
+                    current_dir: None,
+                    environment: map!(),
+                    poll_input: false,
+                    clipboard: ClipboardMode::Write,
+                    escape_char: EscapeChar::default(),
+                    detach_on_exit: false,
+                    attach: None,
+                    claim: None,
+                    forward_agent: false,
+                    init_cmd: Some(String::from("cli-init-cmd")),
+                    cmd: None,
+                }))),
+            }
+        );
+    }
 
     #[test]
-    fn distant_api_should_support_merging_with_config() {
+    fn distant_spawn_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
-            command: DistantSubcommand::Client(ClientSubcommand::Api {
+            command: DistantSubcommand::Client(ClientSubcommand::Spawn(Box::new(SpawnArgs {
                 cache: PathBuf::new(),
                 connection: None,
                 network: NetworkSettings {
                     unix_socket: None,
                     windows_pipe: None,
+                    simulate: None,
                 },
-                timeout: None,
-            }),
+                history: HistorySettings {
+                    no_history: false,
+                    history_file: None,
+                },
+                trust: TrustSettings {
+                    trust: false,
+                    trust_file: None,
+                },
+                current_dir: None,
+                environment: map!(),
+                lsp: true,
+                detached: false,
+                kill_tree: false,
+                pty: true,
+                forward_agent: false,
+                poll_input: false,
+                limits: ProcLimitSettings {
+                    nice: None,
+                    cpu_affinity: None,
+                    max_memory: None,
+                    max_open_files: None,
+                },
+                user: None,
+                cmd: vec![String::from("cmd")],
+            }))),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
-                api: ClientApiConfig { timeout: Some(5.0) },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::Api {
+                command: DistantSubcommand::Client(ClientSubcommand::Spawn(Box::new(SpawnArgs {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("config-unix-socket")),
                         windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
                     },
-                    timeout: Some(5.0),
-                }),
+                    history: HistorySettings {
+                        no_history: false,
+                        history_file: None,
+                    },
+                    trust: TrustSettings {
+                        trust: false,
+                        trust_file: None,
+                    },
+                    current_dir: None,
+                    environment: map!(),
+                    lsp: true,
+                    detached: false,
+                    kill_tree: false,
+                    pty: true,
+                    forward_agent: false,
+                    poll_input: false,
+                    limits: ProcLimitSettings {
+                        nice: None,
+                        cpu_affinity: None,
+                        max_memory: None,
+                        max_open_files: None,
+                    },
+                    user: None,
+                    cmd: vec![String::from("cmd")],
+                }))),
             }
         );
     }
 
     #[test]
-    fn distant_api_should_prioritize_explicit_cli_options_when_merging() {
+    fn distant_spawn_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
-            command: DistantSubcommand::Client(ClientSubcommand::Api {
+            command: DistantSubcommand::Client(ClientSubcommand::Spawn(Box::new(SpawnArgs {
                 cache: PathBuf::new(),
                 connection: None,
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("cli-unix-socket")),
                     windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
                 },
-                timeout: Some(99.0),
-            }),
+                history: HistorySettings {
+                    no_history: false,
+                    history_file: None,
+                },
+                trust: TrustSettings {
+                    trust: false,
+                    trust_file: None,
+                },
+                current_dir: None,
+                environment: map!(),
+                lsp: true,
+                detached: false,
+                kill_tree: false,
+                pty: true,
+                forward_agent: false,
+                poll_input: false,
+                limits: ProcLimitSettings {
+                    nice: None,
+                    cpu_affinity: None,
+                    max_memory: None,
+                    max_open_files: None,
+                },
+                user: None,
+                cmd: vec![String::from("cmd")],
+            }))),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
-                api: ClientApiConfig { timeout: Some(5.0) },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::Api {
+                command: DistantSubcommand::Client(ClientSubcommand::Spawn(Box::new(SpawnArgs {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
-                    timeout: Some(99.0),
-                }),
+                    history: HistorySettings {
+                        no_history: false,
+                        history_file: None,
+                    },
+                    trust: TrustSettings {
+                        trust: false,
+                        trust_file: None,
+                    },
+                    current_dir: None,
+                    environment: map!(),
+                    lsp: true,
+                    detached: false,
+                    kill_tree: false,
+                    pty: true,
+                    forward_agent: false,
+                    poll_input: false,
+                    limits: ProcLimitSettings {
+                        nice: None,
+                        cpu_affinity: None,
+                        max_memory: None,
+                        max_open_files: None,
+                    },
+                    user: None,
+                    cmd: vec![String::from("cmd")],
+                }))),
             }
         );
     }
 
     #[test]
-    fn distant_capabilities_should_support_merging_with_config() {
+    fn distant_sync_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
-            command: DistantSubcommand::Client(ClientSubcommand::Capabilities {
+            command: DistantSubcommand::Client(ClientSubcommand::Sync(Box::new(SyncArgs {
                 cache: PathBuf::new(),
                 connection: None,
                 network: NetworkSettings {
                     unix_socket: None,
                     windows_pipe: None,
-                },
-                format: Format::Json,
-            }),
+                    simulate: None,
+                },
+                format: Some(Format::Json),
+                watch: true,
+                bidirectional: true,
+                conflict: ConflictStrategy::Remote,
+                dry_run: false,
+                src: TransferPath::Local(PathBuf::from("src")),
+                dst: TransferPath::Remote(PathBuf::from("dst")),
+            }))),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 connect: ClientConnectConfig {
                     options: map!("hello" -> "world"),
@@ -1255,57 +5125,81 @@ mod tests {
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::Capabilities {
+                command: DistantSubcommand::Client(ClientSubcommand::Sync(Box::new(SyncArgs {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("config-unix-socket")),
                         windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
                     },
-                    format: Format::Json,
-                }),
+                    format: Some(Format::Json),
+                    watch: true,
+                    bidirectional: true,
+                    conflict: ConflictStrategy::Remote,
+                    dry_run: false,
+                    src: TransferPath::Local(PathBuf::from("src")),
+                    dst: TransferPath::Remote(PathBuf::from("dst")),
+                }))),
             }
         );
     }
 
     #[test]
-    fn distant_capabilities_should_prioritize_explicit_cli_options_when_merging() {
+    fn distant_sync_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
-            command: DistantSubcommand::Client(ClientSubcommand::Capabilities {
+            command: DistantSubcommand::Client(ClientSubcommand::Sync(Box::new(SyncArgs {
                 cache: PathBuf::new(),
                 connection: None,
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("cli-unix-socket")),
                     windows_pipe: Some(String::from("cli-windows-pipe")),
-                },
-                format: Format::Json,
-            }),
+                    simulate: None,
+                },
+                format: Some(Format::Json),
+                watch: true,
+                bidirectional: true,
+                conflict: ConflictStrategy::Remote,
+                dry_run: false,
+                src: TransferPath::Local(PathBuf::from("src")),
+                dst: TransferPath::Remote(PathBuf::from("dst")),
+            }))),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 connect: ClientConnectConfig {
                     options: map!("hello" -> "world", "config" -> "value"),
@@ -1313,704 +5207,825 @@ mod tests {
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::Capabilities {
+                command: DistantSubcommand::Client(ClientSubcommand::Sync(Box::new(SyncArgs {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
-                    format: Format::Json,
-                }),
+                    format: Some(Format::Json),
+                    watch: true,
+                    bidirectional: true,
+                    conflict: ConflictStrategy::Remote,
+                    dry_run: false,
+                    src: TransferPath::Local(PathBuf::from("src")),
+                    dst: TransferPath::Remote(PathBuf::from("dst")),
+                }))),
             }
         );
     }
 
     #[test]
-    fn distant_connect_should_support_merging_with_config() {
+    fn distant_system_info_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
-            command: DistantSubcommand::Client(ClientSubcommand::Connect {
+            command: DistantSubcommand::Client(ClientSubcommand::SystemInfo {
                 cache: PathBuf::new(),
-                options: map!(),
+                connection: None,
                 network: NetworkSettings {
                     unix_socket: None,
                     windows_pipe: None,
+                    simulate: None,
                 },
-                format: Format::Json,
-                destination: Box::new("test://destination".parse().unwrap()),
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
-                },
-                connect: ClientConnectConfig {
-                    options: map!("hello" -> "world"),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::Connect {
+                command: DistantSubcommand::Client(ClientSubcommand::SystemInfo {
                     cache: PathBuf::new(),
-                    options: map!("hello" -> "world"),
+                    connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("config-unix-socket")),
                         windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
                     },
-                    format: Format::Json,
-                    destination: Box::new("test://destination".parse().unwrap()),
                 }),
             }
         );
     }
 
     #[test]
-    fn distant_connect_should_prioritize_explicit_cli_options_when_merging() {
+    fn distant_system_info_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
-            command: DistantSubcommand::Client(ClientSubcommand::Connect {
+            command: DistantSubcommand::Client(ClientSubcommand::SystemInfo {
                 cache: PathBuf::new(),
-                options: map!("hello" -> "test", "cli" -> "value"),
+                connection: None,
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("cli-unix-socket")),
                     windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
                 },
-                format: Format::Json,
-                destination: Box::new("test://destination".parse().unwrap()),
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
-                },
-                connect: ClientConnectConfig {
-                    options: map!("hello" -> "world", "config" -> "value"),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::Connect {
+                command: DistantSubcommand::Client(ClientSubcommand::SystemInfo {
                     cache: PathBuf::new(),
-                    options: map!("hello" -> "test", "cli" -> "value", "config" -> "value"),
+                    connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
-                    format: Format::Json,
-                    destination: Box::new("test://destination".parse().unwrap()),
                 }),
             }
         );
     }
 
     #[test]
-    fn distant_launch_should_support_merging_with_config() {
+    fn distant_top_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
-            command: DistantSubcommand::Client(ClientSubcommand::Launch {
+            command: DistantSubcommand::Client(ClientSubcommand::Top {
                 cache: PathBuf::new(),
-                distant_bin: None,
-                distant_bind_server: None,
-                distant_args: None,
-                options: map!(),
+                connection: None,
                 network: NetworkSettings {
                     unix_socket: None,
                     windows_pipe: None,
+                    simulate: None,
                 },
-                format: Format::Json,
-                destination: Box::new("test://destination".parse().unwrap()),
+                interval: 5.0,
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
-                launch: ClientLaunchConfig {
-                    distant: ClientLaunchDistantConfig {
-                        args: Some(String::from("config-args")),
-                        bin: Some(String::from("config-bin")),
-                        bind_server: Some(BindAddress::Host(Host::Name(String::from(
-                            "config-host",
-                        )))),
-                    },
+                connect: ClientConnectConfig {
                     options: map!("hello" -> "world"),
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::Launch {
+                command: DistantSubcommand::Client(ClientSubcommand::Top {
                     cache: PathBuf::new(),
-                    distant_args: Some(String::from("config-args")),
-                    distant_bin: Some(String::from("config-bin")),
-                    distant_bind_server: Some(BindAddress::Host(Host::Name(String::from(
-                        "config-host",
-                    )))),
-                    options: map!("hello" -> "world"),
+                    connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("config-unix-socket")),
                         windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
                     },
-                    format: Format::Json,
-                    destination: Box::new("test://destination".parse().unwrap()),
+                    interval: 5.0,
                 }),
             }
         );
     }
 
     #[test]
-    fn distant_launch_should_prioritize_explicit_cli_options_when_merging() {
+    fn distant_top_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
-            },
-            command: DistantSubcommand::Client(ClientSubcommand::Launch {
-                cache: PathBuf::new(),
-                distant_args: Some(String::from("cli-args")),
-                distant_bin: Some(String::from("cli-bin")),
-                distant_bind_server: Some(BindAddress::Host(Host::Name(String::from("cli-host")))),
-                options: map!("hello" -> "test", "cli" -> "value"),
+            },
+            command: DistantSubcommand::Client(ClientSubcommand::Top {
+                cache: PathBuf::new(),
+                connection: None,
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("cli-unix-socket")),
                     windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
                 },
-                format: Format::Json,
-                destination: Box::new("test://destination".parse().unwrap()),
+                interval: 5.0,
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
-                launch: ClientLaunchConfig {
-                    distant: ClientLaunchDistantConfig {
-                        args: Some(String::from("config-args")),
-                        bin: Some(String::from("config-bin")),
-                        bind_server: Some(BindAddress::Host(Host::Name(String::from(
-                            "config-host",
-                        )))),
-                    },
+                connect: ClientConnectConfig {
                     options: map!("hello" -> "world", "config" -> "value"),
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::Launch {
+                command: DistantSubcommand::Client(ClientSubcommand::Top {
                     cache: PathBuf::new(),
-                    distant_args: Some(String::from("cli-args")),
-                    distant_bin: Some(String::from("cli-bin")),
-                    distant_bind_server: Some(BindAddress::Host(Host::Name(String::from(
-                        "cli-host",
-                    )))),
-                    options: map!("hello" -> "test", "config" -> "value", "cli" -> "value"),
+                    connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
-                    format: Format::Json,
-                    destination: Box::new("test://destination".parse().unwrap()),
+                    interval: 5.0,
                 }),
             }
         );
     }
 
     #[test]
-    fn distant_shell_should_support_merging_with_config() {
+    fn distant_fs_batch_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
-            command: DistantSubcommand::Client(ClientSubcommand::Shell {
-                cache: PathBuf::new(),
-                connection: None,
-                network: NetworkSettings {
-                    unix_socket: None,
-                    windows_pipe: None,
+            command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
+                ClientFileSystemSubcommand::Batch {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: None,
+                        windows_pipe: None,
+                        simulate: None,
+                    },
+                    atomic: true,
+                    transaction: false,
+                    file: Some(PathBuf::from("file")),
                 },
-                current_dir: None,
-                environment: map!(),
-                cmd: None,
-            }),
+            )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::Shell {
-                    cache: PathBuf::new(),
-                    connection: None,
-                    network: NetworkSettings {
-                        unix_socket: Some(PathBuf::from("config-unix-socket")),
-                        windows_pipe: Some(String::from("config-windows-pipe")),
-                    },
-                    current_dir: None,
-                    environment: map!(),
-                    cmd: None,
-                }),
+                command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
+                    ClientFileSystemSubcommand::Batch {
+                        cache: PathBuf::new(),
+                        connection: None,
+                        network: NetworkSettings {
+                            unix_socket: Some(PathBuf::from("config-unix-socket")),
+                            windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
+                        },
+                        atomic: true,
+                        transaction: false,
+                        file: Some(PathBuf::from("file")),
+                    }
+                )),
             }
         );
     }
 
     #[test]
-    fn distant_shell_should_prioritize_explicit_cli_options_when_merging() {
+    fn distant_fs_batch_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
-            command: DistantSubcommand::Client(ClientSubcommand::Shell {
-                cache: PathBuf::new(),
-                connection: None,
-                network: NetworkSettings {
-                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
-                    windows_pipe: Some(String::from("cli-windows-pipe")),
+            command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
+                ClientFileSystemSubcommand::Batch {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                    atomic: true,
+                    transaction: false,
+                    file: Some(PathBuf::from("file")),
                 },
-                current_dir: None,
-                environment: map!(),
-                cmd: None,
-            }),
+            )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::Shell {
-                    cache: PathBuf::new(),
-                    connection: None,
-                    network: NetworkSettings {
-                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
-                        windows_pipe: Some(String::from("cli-windows-pipe")),
-                    },
-                    current_dir: None,
-                    environment: map!(),
-                    cmd: None,
-                }),
+                command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
+                    ClientFileSystemSubcommand::Batch {
+                        cache: PathBuf::new(),
+                        connection: None,
+                        network: NetworkSettings {
+                            unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                            windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
+                        },
+                        atomic: true,
+                        transaction: false,
+                        file: Some(PathBuf::from("file")),
+                    }
+                )),
             }
         );
     }
 
     #[test]
-    fn distant_spawn_should_support_merging_with_config() {
+    fn distant_fs_copy_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
-            command: DistantSubcommand::Client(ClientSubcommand::Spawn {
-                cache: PathBuf::new(),
-                connection: None,
-                network: NetworkSettings {
-                    unix_socket: None,
-                    windows_pipe: None,
+            command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
+                ClientFileSystemSubcommand::Copy {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: None,
+                        windows_pipe: None,
+                        simulate: None,
+                    },
+                    src: PathBuf::from("src"),
+                    dst: PathBuf::from("dst"),
+                    dry_run: false,
                 },
-                current_dir: None,
-                environment: map!(),
-                lsp: true,
-                pty: true,
-                cmd: vec![String::from("cmd")],
-            }),
+            )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::Spawn {
-                    cache: PathBuf::new(),
-                    connection: None,
-                    network: NetworkSettings {
-                        unix_socket: Some(PathBuf::from("config-unix-socket")),
-                        windows_pipe: Some(String::from("config-windows-pipe")),
-                    },
-                    current_dir: None,
-                    environment: map!(),
-                    lsp: true,
-                    pty: true,
-                    cmd: vec![String::from("cmd")],
-                }),
+                command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
+                    ClientFileSystemSubcommand::Copy {
+                        cache: PathBuf::new(),
+                        connection: None,
+                        network: NetworkSettings {
+                            unix_socket: Some(PathBuf::from("config-unix-socket")),
+                            windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
+                        },
+                        src: PathBuf::from("src"),
+                        dst: PathBuf::from("dst"),
+                        dry_run: false,
+                    }
+                )),
             }
         );
     }
 
     #[test]
-    fn distant_spawn_should_prioritize_explicit_cli_options_when_merging() {
+    fn distant_fs_copy_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
-            command: DistantSubcommand::Client(ClientSubcommand::Spawn {
-                cache: PathBuf::new(),
-                connection: None,
-                network: NetworkSettings {
-                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
-                    windows_pipe: Some(String::from("cli-windows-pipe")),
+            command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
+                ClientFileSystemSubcommand::Copy {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                    src: PathBuf::from("src"),
+                    dst: PathBuf::from("dst"),
+                    dry_run: false,
                 },
-                current_dir: None,
-                environment: map!(),
-                lsp: true,
-                pty: true,
-                cmd: vec![String::from("cmd")],
-            }),
+            )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::Spawn {
-                    cache: PathBuf::new(),
-                    connection: None,
-                    network: NetworkSettings {
-                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
-                        windows_pipe: Some(String::from("cli-windows-pipe")),
-                    },
-                    current_dir: None,
-                    environment: map!(),
-                    lsp: true,
-                    pty: true,
-                    cmd: vec![String::from("cmd")],
-                }),
+                command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
+                    ClientFileSystemSubcommand::Copy {
+                        cache: PathBuf::new(),
+                        connection: None,
+                        network: NetworkSettings {
+                            unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                            windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
+                        },
+                        src: PathBuf::from("src"),
+                        dst: PathBuf::from("dst"),
+                        dry_run: false,
+                    }
+                )),
             }
         );
     }
 
     #[test]
-    fn distant_system_info_should_support_merging_with_config() {
+    fn distant_fs_diff_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
-            command: DistantSubcommand::Client(ClientSubcommand::SystemInfo {
-                cache: PathBuf::new(),
-                connection: None,
-                network: NetworkSettings {
-                    unix_socket: None,
-                    windows_pipe: None,
+            command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
+                ClientFileSystemSubcommand::Diff {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: None,
+                        windows_pipe: None,
+                        simulate: None,
+                    },
+                    context: 3,
+                    src: PathBuf::from("src"),
+                    dst: Some(PathBuf::from("dst")),
                 },
-            }),
+            )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::SystemInfo {
-                    cache: PathBuf::new(),
-                    connection: None,
-                    network: NetworkSettings {
-                        unix_socket: Some(PathBuf::from("config-unix-socket")),
-                        windows_pipe: Some(String::from("config-windows-pipe")),
-                    },
-                }),
+                command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
+                    ClientFileSystemSubcommand::Diff {
+                        cache: PathBuf::new(),
+                        connection: None,
+                        network: NetworkSettings {
+                            unix_socket: Some(PathBuf::from("config-unix-socket")),
+                            windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
+                        },
+                        context: 3,
+                        src: PathBuf::from("src"),
+                        dst: Some(PathBuf::from("dst")),
+                    }
+                )),
             }
         );
     }
 
     #[test]
-    fn distant_system_info_should_prioritize_explicit_cli_options_when_merging() {
+    fn distant_fs_diff_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
-            command: DistantSubcommand::Client(ClientSubcommand::SystemInfo {
-                cache: PathBuf::new(),
-                connection: None,
-                network: NetworkSettings {
-                    unix_socket: Some(PathBuf::from("cli-unix-socket")),
-                    windows_pipe: Some(String::from("cli-windows-pipe")),
+            command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
+                ClientFileSystemSubcommand::Diff {
+                    cache: PathBuf::new(),
+                    connection: None,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                    context: 3,
+                    src: PathBuf::from("src"),
+                    dst: Some(PathBuf::from("dst")),
                 },
-            }),
+            )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
-                command: DistantSubcommand::Client(ClientSubcommand::SystemInfo {
-                    cache: PathBuf::new(),
-                    connection: None,
-                    network: NetworkSettings {
-                        unix_socket: Some(PathBuf::from("cli-unix-socket")),
-                        windows_pipe: Some(String::from("cli-windows-pipe")),
-                    },
-                }),
+                command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
+                    ClientFileSystemSubcommand::Diff {
+                        cache: PathBuf::new(),
+                        connection: None,
+                        network: NetworkSettings {
+                            unix_socket: Some(PathBuf::from("cli-unix-socket")),
+                            windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
+                        },
+                        context: 3,
+                        src: PathBuf::from("src"),
+                        dst: Some(PathBuf::from("dst")),
+                    }
+                )),
             }
         );
     }
 
     #[test]
-    fn distant_fs_copy_should_support_merging_with_config() {
+    fn distant_fs_exists_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::Copy {
+                ClientFileSystemSubcommand::Exists {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: None,
                         windows_pipe: None,
+                        simulate: None,
                     },
-                    src: PathBuf::from("src"),
-                    dst: PathBuf::from("dst"),
+                    path: PathBuf::from("path"),
                 },
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::Copy {
+                    ClientFileSystemSubcommand::Exists {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("config-unix-socket")),
                             windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
                         },
-                        src: PathBuf::from("src"),
-                        dst: PathBuf::from("dst"),
+                        path: PathBuf::from("path"),
                     }
                 )),
             }
@@ -2018,60 +6033,70 @@ mod tests {
     }
 
     #[test]
-    fn distant_fs_copy_should_prioritize_explicit_cli_options_when_merging() {
+    fn distant_fs_exists_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::Copy {
+                ClientFileSystemSubcommand::Exists {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
-                    src: PathBuf::from("src"),
-                    dst: PathBuf::from("dst"),
+                    path: PathBuf::from("path"),
                 },
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::Copy {
+                    ClientFileSystemSubcommand::Exists {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("cli-unix-socket")),
                             windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
                         },
-                        src: PathBuf::from("src"),
-                        dst: PathBuf::from("dst"),
+                        path: PathBuf::from("path"),
                     }
                 )),
             }
@@ -2079,58 +6104,74 @@ mod tests {
     }
 
     #[test]
-    fn distant_fs_exists_should_support_merging_with_config() {
+    fn distant_fs_makedir_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::Exists {
+                ClientFileSystemSubcommand::MakeDir {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: None,
                         windows_pipe: None,
+                        simulate: None,
                     },
                     path: PathBuf::from("path"),
+                    all: true,
+                    mode: None,
                 },
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::Exists {
+                    ClientFileSystemSubcommand::MakeDir {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("config-unix-socket")),
                             windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
                         },
                         path: PathBuf::from("path"),
+                        all: true,
+                        mode: None,
                     }
                 )),
             }
@@ -2138,58 +6179,74 @@ mod tests {
     }
 
     #[test]
-    fn distant_fs_exists_should_prioritize_explicit_cli_options_when_merging() {
+    fn distant_fs_makedir_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::Exists {
+                ClientFileSystemSubcommand::MakeDir {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                     path: PathBuf::from("path"),
+                    all: true,
+                    mode: None,
                 },
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::Exists {
+                    ClientFileSystemSubcommand::MakeDir {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("cli-unix-socket")),
                             windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
                         },
                         path: PathBuf::from("path"),
+                        all: true,
+                        mode: None,
                     }
                 )),
             }
@@ -2197,60 +6254,72 @@ mod tests {
     }
 
     #[test]
-    fn distant_fs_makedir_should_support_merging_with_config() {
+    fn distant_fs_maketemp_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::MakeDir {
+                ClientFileSystemSubcommand::MakeTemp {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: None,
                         windows_pipe: None,
+                        simulate: None,
                     },
-                    path: PathBuf::from("path"),
-                    all: true,
+                    dir: true,
+                    parent: Some(PathBuf::from("parent")),
                 },
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::MakeDir {
+                    ClientFileSystemSubcommand::MakeTemp {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("config-unix-socket")),
                             windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
                         },
-                        path: PathBuf::from("path"),
-                        all: true,
+                        dir: true,
+                        parent: Some(PathBuf::from("parent")),
                     }
                 )),
             }
@@ -2258,60 +6327,72 @@ mod tests {
     }
 
     #[test]
-    fn distant_fs_makedir_should_prioritize_explicit_cli_options_when_merging() {
+    fn distant_fs_maketemp_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::MakeDir {
+                ClientFileSystemSubcommand::MakeTemp {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
-                    path: PathBuf::from("path"),
-                    all: true,
+                    dir: true,
+                    parent: Some(PathBuf::from("parent")),
                 },
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::MakeDir {
+                    ClientFileSystemSubcommand::MakeTemp {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("cli-unix-socket")),
                             windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
                         },
-                        path: PathBuf::from("path"),
-                        all: true,
+                        dir: true,
+                        parent: Some(PathBuf::from("parent")),
                     }
                 )),
             }
@@ -2322,8 +6403,12 @@ mod tests {
     fn distant_fs_metadata_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
@@ -2333,6 +6418,7 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: None,
                         windows_pipe: None,
+                        simulate: None,
                     },
                     canonicalize: true,
                     resolve_file_type: true,
@@ -2341,27 +6427,33 @@ mod tests {
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
@@ -2371,6 +6463,7 @@ mod tests {
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("config-unix-socket")),
                             windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
                         },
                         canonicalize: true,
                         resolve_file_type: true,
@@ -2385,8 +6478,12 @@ mod tests {
     fn distant_fs_metadata_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
@@ -2396,6 +6493,7 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                     canonicalize: true,
                     resolve_file_type: true,
@@ -2404,27 +6502,33 @@ mod tests {
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
@@ -2434,6 +6538,7 @@ mod tests {
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("cli-unix-socket")),
                             windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
                         },
                         canonicalize: true,
                         resolve_file_type: true,
@@ -2448,64 +6553,78 @@ mod tests {
     fn distant_fs_read_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::Read {
+                ClientFileSystemSubcommand::Read(Box::new(ReadArgs {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: None,
                         windows_pipe: None,
+                        simulate: None,
                     },
                     path: PathBuf::from("path"),
                     depth: 1,
                     absolute: true,
                     canonicalize: true,
                     include_root: true,
-                },
+                    options: Default::default(),
+                })),
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::Read {
+                    ClientFileSystemSubcommand::Read(Box::new(ReadArgs {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("config-unix-socket")),
                             windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
                         },
                         path: PathBuf::from("path"),
                         depth: 1,
                         absolute: true,
                         canonicalize: true,
                         include_root: true,
-                    }
+                        options: Default::default(),
+                    }))
                 )),
             }
         );
@@ -2515,64 +6634,78 @@ mod tests {
     fn distant_fs_read_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::Read {
+                ClientFileSystemSubcommand::Read(Box::new(ReadArgs {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                     path: PathBuf::from("path"),
                     depth: 1,
                     absolute: true,
                     canonicalize: true,
                     include_root: true,
-                },
+                    options: Default::default(),
+                })),
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::Read {
+                    ClientFileSystemSubcommand::Read(Box::new(ReadArgs {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("cli-unix-socket")),
                             windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
                         },
                         path: PathBuf::from("path"),
                         depth: 1,
                         absolute: true,
                         canonicalize: true,
                         include_root: true,
-                    }
+                        options: Default::default(),
+                    }))
                 )),
             }
         );
@@ -2582,8 +6715,12 @@ mod tests {
     fn distant_fs_remove_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
@@ -2593,34 +6730,42 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: None,
                         windows_pipe: None,
+                        simulate: None,
                     },
                     path: PathBuf::from("path"),
                     force: true,
+                    dry_run: false,
                 },
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
@@ -2630,9 +6775,11 @@ mod tests {
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("config-unix-socket")),
                             windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
                         },
                         path: PathBuf::from("path"),
                         force: true,
+                        dry_run: false,
                     }
                 )),
             }
@@ -2643,8 +6790,12 @@ mod tests {
     fn distant_fs_remove_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
@@ -2654,34 +6805,42 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                     path: PathBuf::from("path"),
                     force: true,
+                    dry_run: false,
                 },
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
@@ -2691,9 +6850,11 @@ mod tests {
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("cli-unix-socket")),
                             windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
                         },
                         path: PathBuf::from("path"),
                         force: true,
+                        dry_run: false,
                     }
                 )),
             }
@@ -2704,8 +6865,12 @@ mod tests {
     fn distant_fs_rename_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
@@ -2715,34 +6880,42 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: None,
                         windows_pipe: None,
+                        simulate: None,
                     },
                     src: PathBuf::from("src"),
                     dst: PathBuf::from("dst"),
+                    dry_run: false,
                 },
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
@@ -2752,9 +6925,11 @@ mod tests {
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("config-unix-socket")),
                             windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
                         },
                         src: PathBuf::from("src"),
                         dst: PathBuf::from("dst"),
+                        dry_run: false,
                     }
                 )),
             }
@@ -2765,8 +6940,12 @@ mod tests {
     fn distant_fs_rename_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
@@ -2776,34 +6955,42 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                     src: PathBuf::from("src"),
                     dst: PathBuf::from("dst"),
+                    dry_run: false,
                 },
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
@@ -2813,9 +7000,11 @@ mod tests {
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("cli-unix-socket")),
                             windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
                         },
                         src: PathBuf::from("src"),
                         dst: PathBuf::from("dst"),
+                        dry_run: false,
                     }
                 )),
             }
@@ -2826,62 +7015,74 @@ mod tests {
     fn distant_fs_search_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::Search {
+                ClientFileSystemSubcommand::Search(Box::new(SearchArgs {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: None,
                         windows_pipe: None,
+                        simulate: None,
                     },
                     target: CliSearchQueryTarget::Contents,
                     condition: CliSearchQueryCondition::regex(".*"),
                     options: Default::default(),
                     paths: vec![PathBuf::from(".")],
-                },
+                })),
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::Search {
+                    ClientFileSystemSubcommand::Search(Box::new(SearchArgs {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("config-unix-socket")),
                             windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
                         },
                         target: CliSearchQueryTarget::Contents,
                         condition: CliSearchQueryCondition::regex(".*"),
                         options: Default::default(),
                         paths: vec![PathBuf::from(".")],
-                    }
+                    }))
                 )),
             }
         );
@@ -2891,62 +7092,74 @@ mod tests {
     fn distant_fs_search_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::Search {
+                ClientFileSystemSubcommand::Search(Box::new(SearchArgs {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                     target: CliSearchQueryTarget::Contents,
                     condition: CliSearchQueryCondition::regex(".*"),
                     options: Default::default(),
                     paths: vec![PathBuf::from(".")],
-                },
+                })),
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::Search {
+                    ClientFileSystemSubcommand::Search(Box::new(SearchArgs {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("cli-unix-socket")),
                             windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
                         },
                         target: CliSearchQueryTarget::Contents,
                         condition: CliSearchQueryCondition::regex(".*"),
                         options: Default::default(),
                         paths: vec![PathBuf::from(".")],
-                    }
+                    }))
                 )),
             }
         );
@@ -2956,62 +7169,74 @@ mod tests {
     fn distant_fs_watch_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::Watch {
+                ClientFileSystemSubcommand::Watch(Box::new(WatchArgs {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: None,
                         windows_pipe: None,
+                        simulate: None,
                     },
                     recursive: true,
                     only: ChangeKind::all(),
                     except: ChangeKind::all(),
                     path: PathBuf::from("path"),
-                },
+                })),
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::Watch {
+                    ClientFileSystemSubcommand::Watch(Box::new(WatchArgs {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("config-unix-socket")),
                             windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
                         },
                         recursive: true,
                         only: ChangeKind::all(),
                         except: ChangeKind::all(),
                         path: PathBuf::from("path"),
-                    }
+                    }))
                 )),
             }
         );
@@ -3021,62 +7246,74 @@ mod tests {
     fn distant_fs_watch_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::Watch {
+                ClientFileSystemSubcommand::Watch(Box::new(WatchArgs {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                     recursive: true,
                     only: ChangeKind::all(),
                     except: ChangeKind::all(),
                     path: PathBuf::from("path"),
-                },
+                })),
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::Watch {
+                    ClientFileSystemSubcommand::Watch(Box::new(WatchArgs {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("cli-unix-socket")),
                             windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
                         },
                         recursive: true,
                         only: ChangeKind::all(),
                         except: ChangeKind::all(),
                         path: PathBuf::from("path"),
-                    }
+                    }))
                 )),
             }
         );
@@ -3086,60 +7323,78 @@ mod tests {
     fn distant_fs_write_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::Write {
+                ClientFileSystemSubcommand::Write(Box::new(WriteArgs {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: None,
                         windows_pipe: None,
+                        simulate: None,
                     },
                     append: false,
+                    offset: None,
+                    dedup: false,
+                    mode: None,
                     path: PathBuf::from("path"),
                     data: None,
-                },
+                })),
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::Write {
+                    ClientFileSystemSubcommand::Write(Box::new(WriteArgs {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("config-unix-socket")),
                             windows_pipe: Some(String::from("config-windows-pipe")),
+                            simulate: None,
                         },
                         append: false,
+                        offset: None,
+                        dedup: false,
+                        mode: None,
                         path: PathBuf::from("path"),
                         data: None,
-                    }
+                    }))
                 )),
             }
         );
@@ -3149,71 +7404,256 @@ mod tests {
     fn distant_fs_write_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                ClientFileSystemSubcommand::Write {
+                ClientFileSystemSubcommand::Write(Box::new(WriteArgs {
                     cache: PathBuf::new(),
                     connection: None,
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                     append: false,
+                    offset: None,
+                    dedup: false,
+                    mode: None,
                     path: PathBuf::from("path"),
                     data: None,
-                },
+                })),
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             client: ClientConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Client(ClientSubcommand::FileSystem(
-                    ClientFileSystemSubcommand::Write {
+                    ClientFileSystemSubcommand::Write(Box::new(WriteArgs {
                         cache: PathBuf::new(),
                         connection: None,
                         network: NetworkSettings {
                             unix_socket: Some(PathBuf::from("cli-unix-socket")),
                             windows_pipe: Some(String::from("cli-windows-pipe")),
+                            simulate: None,
                         },
                         append: false,
+                        offset: None,
+                        dedup: false,
+                        mode: None,
                         path: PathBuf::from("path"),
                         data: None,
-                    }
+                    }))
                 )),
             }
         );
     }
 
+    #[test]
+    fn distant_render_should_support_merging_with_client_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Render {
+                input: None,
+                format: Format::Shell,
+            },
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Render {
+                    input: None,
+                    format: Format::Shell,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn distant_config_should_support_merging_with_client_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Config(ConfigSubcommand::Get {
+                target: ConfigTarget::default(),
+                key: String::from("client.launch.bin"),
+            }),
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Config(ConfigSubcommand::Get {
+                    target: ConfigTarget::default(),
+                    key: String::from("client.launch.bin"),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn distant_doctor_should_support_merging_with_client_config() {
+        let mut options = Options {
+            config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
+            logging: LoggingSettings {
+                log_file: None,
+                log_format: None,
+                log_level: None,
+            },
+            command: DistantSubcommand::Doctor {
+                local: true,
+                network: NetworkSettings {
+                    unix_socket: None,
+                    windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
+                },
+            },
+        };
+
+        options.merge(Box::new(Config {
+            client: ClientConfig {
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                network: NetworkSettings {
+                    unix_socket: Some(PathBuf::from("config-unix-socket")),
+                    windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            options,
+            Options {
+                config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
+                logging: LoggingSettings {
+                    log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
+                    log_level: Some(LogLevel::Trace),
+                },
+                command: DistantSubcommand::Doctor {
+                    local: true,
+                    network: NetworkSettings {
+                        unix_socket: Some(PathBuf::from("config-unix-socket")),
+                        windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
+                    },
+                },
+            }
+        );
+    }
+
     #[test]
     fn distant_generate_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Generate(GenerateSubcommand::Completion {
@@ -3222,22 +7662,27 @@ mod tests {
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             generate: GenerateConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Generate(GenerateSubcommand::Completion {
@@ -3252,8 +7697,12 @@ mod tests {
     fn distant_generate_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Generate(GenerateSubcommand::Completion {
@@ -3262,22 +7711,27 @@ mod tests {
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             generate: GenerateConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Generate(GenerateSubcommand::Completion {
@@ -3292,8 +7746,12 @@ mod tests {
     fn distant_manager_capabilities_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::Capabilities {
@@ -3301,31 +7759,38 @@ mod tests {
                 network: NetworkSettings {
                     unix_socket: None,
                     windows_pipe: None,
+                    simulate: None,
                 },
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::Capabilities {
@@ -3333,6 +7798,7 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("config-unix-socket")),
                         windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
                     },
                 }),
             }
@@ -3343,8 +7809,12 @@ mod tests {
     fn distant_manager_capabilities_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::Capabilities {
@@ -3352,31 +7822,38 @@ mod tests {
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("cli-unix-socket")),
                     windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
                 },
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::Capabilities {
@@ -3384,6 +7861,7 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                 }),
             }
@@ -3394,8 +7872,12 @@ mod tests {
     fn distant_manager_info_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::Info {
@@ -3404,31 +7886,38 @@ mod tests {
                 network: NetworkSettings {
                     unix_socket: None,
                     windows_pipe: None,
+                    simulate: None,
                 },
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::Info {
@@ -3437,6 +7926,7 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("config-unix-socket")),
                         windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
                     },
                 }),
             }
@@ -3447,8 +7937,12 @@ mod tests {
     fn distant_manager_info_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::Info {
@@ -3457,31 +7951,38 @@ mod tests {
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("cli-unix-socket")),
                     windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
                 },
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::Info {
@@ -3490,6 +7991,7 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                 }),
             }
@@ -3500,8 +8002,12 @@ mod tests {
     fn distant_manager_kill_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::Kill {
@@ -3510,31 +8016,38 @@ mod tests {
                 network: NetworkSettings {
                     unix_socket: None,
                     windows_pipe: None,
+                    simulate: None,
                 },
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::Kill {
@@ -3543,6 +8056,7 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("config-unix-socket")),
                         windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
                     },
                 }),
             }
@@ -3553,8 +8067,12 @@ mod tests {
     fn distant_manager_kill_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::Kill {
@@ -3563,31 +8081,38 @@ mod tests {
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("cli-unix-socket")),
                     windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
                 },
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::Kill {
@@ -3596,6 +8121,7 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                 }),
             }
@@ -3606,8 +8132,12 @@ mod tests {
     fn distant_manager_list_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::List {
@@ -3616,31 +8146,38 @@ mod tests {
                 network: NetworkSettings {
                     unix_socket: None,
                     windows_pipe: None,
+                    simulate: None,
                 },
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::List {
@@ -3649,6 +8186,7 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("config-unix-socket")),
                         windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
                     },
                 }),
             }
@@ -3659,8 +8197,12 @@ mod tests {
     fn distant_manager_list_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::List {
@@ -3669,31 +8211,38 @@ mod tests {
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("cli-unix-socket")),
                     windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
                 },
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::List {
@@ -3702,6 +8251,7 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                 }),
             }
@@ -3712,8 +8262,12 @@ mod tests {
     fn distant_manager_listen_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::Listen {
@@ -3723,31 +8277,47 @@ mod tests {
                 network: NetworkSettings {
                     unix_socket: None,
                     windows_pipe: None,
+                    simulate: None,
                 },
+                autostart: Vec::new(),
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 access: Some(AccessControl::Group),
+                autostart: ManagerAutostartConfig {
+                    connections: vec![ManagerAutostartConnection {
+                        destination: "ssh://example.com".parse().unwrap(),
+                        launch: true,
+                        options: map!(),
+                        schedule: None,
+                    }],
+                },
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::Listen {
@@ -3757,7 +8327,14 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("config-unix-socket")),
                         windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
                     },
+                    autostart: vec![ManagerAutostartConnection {
+                        destination: "ssh://example.com".parse().unwrap(),
+                        launch: true,
+                        options: map!(),
+                        schedule: None,
+                    }],
                 }),
             }
         );
@@ -3767,8 +8344,12 @@ mod tests {
     fn distant_manager_listen_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::Listen {
@@ -3778,31 +8359,47 @@ mod tests {
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("cli-unix-socket")),
                     windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
                 },
+                autostart: Vec::new(),
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 access: Some(AccessControl::Group),
+                autostart: ManagerAutostartConfig {
+                    connections: vec![ManagerAutostartConnection {
+                        destination: "ssh://example.com".parse().unwrap(),
+                        launch: true,
+                        options: map!(),
+                        schedule: None,
+                    }],
+                },
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::Listen {
@@ -3812,7 +8409,14 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
+                    autostart: vec![ManagerAutostartConnection {
+                        destination: "ssh://example.com".parse().unwrap(),
+                        launch: true,
+                        options: map!(),
+                        schedule: None,
+                    }],
                 }),
             }
         );
@@ -3822,8 +8426,12 @@ mod tests {
     fn distant_manager_select_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::Select {
@@ -3833,31 +8441,38 @@ mod tests {
                 network: NetworkSettings {
                     unix_socket: None,
                     windows_pipe: None,
+                    simulate: None,
                 },
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::Select {
@@ -3867,6 +8482,7 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("config-unix-socket")),
                         windows_pipe: Some(String::from("config-windows-pipe")),
+                        simulate: None,
                     },
                 }),
             }
@@ -3877,8 +8493,12 @@ mod tests {
     fn distant_manager_select_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::Select {
@@ -3888,31 +8508,38 @@ mod tests {
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("cli-unix-socket")),
                     windows_pipe: Some(String::from("cli-windows-pipe")),
+                    simulate: None,
                 },
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 network: NetworkSettings {
                     unix_socket: Some(PathBuf::from("config-unix-socket")),
                     windows_pipe: Some(String::from("config-windows-pipe")),
+                    simulate: None,
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::Select {
@@ -3922,6 +8549,7 @@ mod tests {
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("cli-unix-socket")),
                         windows_pipe: Some(String::from("cli-windows-pipe")),
+                        simulate: None,
                     },
                 }),
             }
@@ -3932,8 +8560,12 @@ mod tests {
     fn distant_manager_service_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::Service(
@@ -3944,23 +8576,28 @@ mod tests {
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::Service(
@@ -3977,8 +8614,12 @@ mod tests {
     fn distant_manager_service_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Manager(ManagerSubcommand::Service(
@@ -3989,23 +8630,28 @@ mod tests {
             )),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             manager: ManagerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 ..Default::default()
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Manager(ManagerSubcommand::Service(
@@ -4022,8 +8668,12 @@ mod tests {
     fn distant_server_listen_should_support_merging_with_config() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: None,
+                log_format: None,
                 log_level: None,
             },
             command: DistantSubcommand::Server(ServerSubcommand::Listen {
@@ -4032,16 +8682,27 @@ mod tests {
                 use_ipv6: false,
                 shutdown: Value::Default(Shutdown::After(Duration::from_secs(123))),
                 current_dir: None,
+                write_quota: None,
+                heartbeat_interval: None,
+                motd: None,
+                allow_remote_tunnels: false,
+                socket: Default::default(),
+                limits: Default::default(),
+                scope: Default::default(),
+                proc: Default::default(),
+                fs: Default::default(),
+                scripts: Default::default(),
                 daemon: false,
                 key_from_stdin: false,
                 output_to_local_pipe: None,
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             server: ServerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 listen: ServerListenConfig {
@@ -4050,17 +8711,59 @@ mod tests {
                     use_ipv6: true,
                     shutdown: Some(Shutdown::Lonely(Duration::from_secs(456))),
                     current_dir: Some(PathBuf::from("config-dir")),
+                    write_quota: Some(2048),
+                    heartbeat_interval: Some(15),
+                    motd: Some(String::from("config motd")),
+                    allow_remote_tunnels: true,
+                    socket: SocketSettings {
+                        tcp_nodelay: true,
+                        tcp_keepalive: Some(30),
+                        send_buffer_size: Some(1024),
+                        recv_buffer_size: Some(2048),
+                        backlog: Some(16),
+                    },
+                },
+                limits: OutputLimitSettings {
+                    max_output_memory: Some(1024),
+                    output_spill_dir: Some(PathBuf::from("config-spill-dir")),
+                    max_output_spill_file_size: Some(4096),
+                    max_output_spill_files: Some(2),
+                    max_output_window: Some(2048),
+                },
+                scope: ProcessScopeSettings {
+                    kind: Some(ProcessScopeKind::Systemd),
+                    cgroup: None,
+                    memory_max: Some(1024),
+                    cpu_quota: None,
+                    tasks_max: Some(64),
+                },
+                proc: ProcessPolicySettings {
+                    allow: vec![String::from("cargo")],
+                    deny: vec![String::from("rm")],
+                    allow_system_kill: false,
+                },
+                fs: FsSettings {
+                    default_file_mode: Some(0o640),
+                    default_dir_mode: None,
+                },
+                scripts: ScriptSettings {
+                    paths: vec![PathBuf::from("config-script.lua")],
+                    ..Default::default()
                 },
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 command: DistantSubcommand::Server(ServerSubcommand::Listen {
@@ -4069,6 +8772,44 @@ mod tests {
                     use_ipv6: true,
                     shutdown: Value::Explicit(Shutdown::Lonely(Duration::from_secs(456))),
                     current_dir: Some(PathBuf::from("config-dir")),
+                    write_quota: Some(2048),
+                    heartbeat_interval: Some(15),
+                    motd: Some(String::from("config motd")),
+                    allow_remote_tunnels: true,
+                    socket: SocketSettings {
+                        tcp_nodelay: true,
+                        tcp_keepalive: Some(30),
+                        send_buffer_size: Some(1024),
+                        recv_buffer_size: Some(2048),
+                        backlog: Some(16),
+                    },
+                    limits: OutputLimitSettings {
+                        max_output_memory: Some(1024),
+                        output_spill_dir: Some(PathBuf::from("config-spill-dir")),
+                        max_output_spill_file_size: Some(4096),
+                        max_output_spill_files: Some(2),
+                        max_output_window: Some(2048),
+                    },
+                    scope: ProcessScopeSettings {
+                        kind: Some(ProcessScopeKind::Systemd),
+                        cgroup: None,
+                        memory_max: Some(1024),
+                        cpu_quota: None,
+                        tasks_max: Some(64),
+                    },
+                    proc: ProcessPolicySettings {
+                        allow: vec![String::from("cargo")],
+                        deny: vec![String::from("rm")],
+                        allow_system_kill: false,
+                    },
+                    fs: FsSettings {
+                        default_file_mode: Some(0o640),
+                        default_dir_mode: None,
+                    },
+                    scripts: ScriptSettings {
+                        paths: vec![PathBuf::from("config-script.lua")],
+                        ..Default::default()
+                    },
                     daemon: false,
                     key_from_stdin: false,
                     output_to_local_pipe: None,
@@ -4081,8 +8822,12 @@ mod tests {
     fn distant_server_listen_should_prioritize_explicit_cli_options_when_merging() {
         let mut options = Options {
             config_path: None,
+            profile: None,
+            a11y: false,
+            paging: PagingSettings::default(),
             logging: LoggingSettings {
                 log_file: Some(PathBuf::from("cli-log-file")),
+                log_format: None,
                 log_level: Some(LogLevel::Info),
             },
             command: DistantSubcommand::Server(ServerSubcommand::Listen {
@@ -4091,16 +8836,55 @@ mod tests {
                 use_ipv6: true,
                 shutdown: Value::Explicit(Shutdown::After(Duration::from_secs(123))),
                 current_dir: Some(PathBuf::from("cli-dir")),
+                write_quota: Some(1024),
+                heartbeat_interval: None,
+                motd: None,
+                allow_remote_tunnels: false,
+                socket: SocketSettings {
+                    tcp_nodelay: false,
+                    tcp_keepalive: Some(10),
+                    send_buffer_size: Some(100),
+                    recv_buffer_size: None,
+                    backlog: Some(8),
+                },
+                limits: OutputLimitSettings {
+                    max_output_memory: Some(512),
+                    output_spill_dir: None,
+                    max_output_spill_file_size: None,
+                    max_output_spill_files: Some(8),
+                    max_output_window: None,
+                },
+                scope: ProcessScopeSettings {
+                    kind: Some(ProcessScopeKind::Cgroup),
+                    cgroup: None,
+                    memory_max: None,
+                    cpu_quota: Some(50),
+                    tasks_max: None,
+                },
+                proc: ProcessPolicySettings {
+                    allow: vec![String::from("git")],
+                    deny: vec![String::from("git push")],
+                    allow_system_kill: false,
+                },
+                fs: FsSettings {
+                    default_file_mode: None,
+                    default_dir_mode: Some(0o750),
+                },
+                scripts: ScriptSettings {
+                    paths: vec![PathBuf::from("cli-script.lua")],
+                    ..Default::default()
+                },
                 daemon: false,
                 key_from_stdin: false,
                 output_to_local_pipe: None,
             }),
         };
 
-        options.merge(Config {
+        options.merge(Box::new(Config {
             server: ServerConfig {
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("config-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Trace),
                 },
                 listen: ServerListenConfig {
@@ -4109,17 +8893,59 @@ mod tests {
                     use_ipv6: false,
                     shutdown: Some(Shutdown::Lonely(Duration::from_secs(456))),
                     current_dir: Some(PathBuf::from("config-dir")),
+                    write_quota: Some(2048),
+                    heartbeat_interval: None,
+                    motd: None,
+                    allow_remote_tunnels: false,
+                    socket: SocketSettings {
+                        tcp_nodelay: true,
+                        tcp_keepalive: Some(30),
+                        send_buffer_size: Some(1024),
+                        recv_buffer_size: Some(2048),
+                        backlog: Some(16),
+                    },
+                },
+                limits: OutputLimitSettings {
+                    max_output_memory: Some(1024),
+                    output_spill_dir: Some(PathBuf::from("config-spill-dir")),
+                    max_output_spill_file_size: Some(4096),
+                    max_output_spill_files: Some(2),
+                    max_output_window: Some(65536),
+                },
+                scope: ProcessScopeSettings {
+                    kind: Some(ProcessScopeKind::Systemd),
+                    cgroup: Some(PathBuf::from("config-cgroup")),
+                    memory_max: Some(1024),
+                    cpu_quota: Some(200),
+                    tasks_max: None,
+                },
+                proc: ProcessPolicySettings {
+                    allow: vec![String::from("cargo")],
+                    deny: vec![String::from("rm")],
+                    allow_system_kill: false,
+                },
+                fs: FsSettings {
+                    default_file_mode: Some(0o640),
+                    default_dir_mode: Some(0o700),
+                },
+                scripts: ScriptSettings {
+                    paths: vec![PathBuf::from("config-script.lua")],
+                    ..Default::default()
                 },
             },
             ..Default::default()
-        });
+        }));
 
         assert_eq!(
             options,
             Options {
                 config_path: None,
+                profile: None,
+                a11y: false,
+                paging: PagingSettings::default(),
                 logging: LoggingSettings {
                     log_file: Some(PathBuf::from("cli-log-file")),
+                    log_format: None,
                     log_level: Some(LogLevel::Info),
                 },
                 command: DistantSubcommand::Server(ServerSubcommand::Listen {
@@ -4128,6 +8954,44 @@ mod tests {
                     use_ipv6: true,
                     shutdown: Value::Explicit(Shutdown::After(Duration::from_secs(123))),
                     current_dir: Some(PathBuf::from("cli-dir")),
+                    write_quota: Some(1024),
+                    heartbeat_interval: None,
+                    motd: None,
+                    allow_remote_tunnels: false,
+                    socket: SocketSettings {
+                        tcp_nodelay: true,
+                        tcp_keepalive: Some(10),
+                        send_buffer_size: Some(100),
+                        recv_buffer_size: Some(2048),
+                        backlog: Some(8),
+                    },
+                    limits: OutputLimitSettings {
+                        max_output_memory: Some(512),
+                        output_spill_dir: Some(PathBuf::from("config-spill-dir")),
+                        max_output_spill_file_size: Some(4096),
+                        max_output_spill_files: Some(8),
+                        max_output_window: Some(65536),
+                    },
+                    scope: ProcessScopeSettings {
+                        kind: Some(ProcessScopeKind::Cgroup),
+                        cgroup: Some(PathBuf::from("config-cgroup")),
+                        memory_max: Some(1024),
+                        cpu_quota: Some(50),
+                        tasks_max: None,
+                    },
+                    proc: ProcessPolicySettings {
+                        allow: vec![String::from("git")],
+                        deny: vec![String::from("git push"), String::from("rm")],
+                        allow_system_kill: false,
+                    },
+                    fs: FsSettings {
+                        default_file_mode: Some(0o640),
+                        default_dir_mode: Some(0o750),
+                    },
+                    scripts: ScriptSettings {
+                        paths: vec![PathBuf::from("cli-script.lua")],
+                        ..Default::default()
+                    },
                     daemon: false,
                     key_from_stdin: false,
                     output_to_local_pipe: None,
@@ -4135,4 +8999,100 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn distant_launch_should_prioritize_profile_over_config_when_loading() {
+        use assert_fs::prelude::*;
+        let config_file = assert_fs::NamedTempFile::new("config.toml").unwrap();
+        config_file
+            .write_str(&format!(
+                r#"{}
+[profile.prod]
+destination = "ssh://prod.example.com"
+format = "json"
+
+[profile.prod.launch]
+bin = "profile-bin"
+args = "profile-args"
+"#,
+                Config::default_raw_str()
+            ))
+            .unwrap();
+
+        let options = Options::load_from([
+            "distant",
+            "--config",
+            config_file.path().to_str().unwrap(),
+            "--profile",
+            "prod",
+            "launch",
+            "--distant-args",
+            "cli-args",
+        ])
+        .unwrap();
+
+        match options.command {
+            DistantSubcommand::Client(ClientSubcommand::Launch(args)) => {
+                let LaunchArgs {
+                    destinations,
+                    distant_args,
+                    distant_bin,
+                    distant_bind_server,
+                    format,
+                    ..
+                } = *args;
+                assert_eq!(
+                    destinations,
+                    vec!["ssh://prod.example.com".parse::<Destination>().unwrap()]
+                );
+                assert_eq!(distant_args.as_deref(), Some("cli-args"));
+                assert_eq!(distant_bin.as_deref(), Some("profile-bin"));
+                assert_eq!(distant_bind_server, Some(BindAddress::Ssh));
+                assert_eq!(format, Some(Format::Json));
+            }
+            x => panic!("Unexpected command: {x:?}"),
+        }
+    }
+
+    #[test]
+    fn distant_spawn_should_prioritize_cli_environment_over_profile_when_loading() {
+        use assert_fs::prelude::*;
+        let config_file = assert_fs::NamedTempFile::new("config.toml").unwrap();
+        config_file
+            .write_str(&format!(
+                r#"{}
+[profile.dev]
+environment = "KEY=\"profile\",OTHER=\"profile\""
+"#,
+                Config::default_raw_str()
+            ))
+            .unwrap();
+        let path = config_file.path().to_str().unwrap();
+
+        let options = Options::load_from([
+            "distant",
+            "--config",
+            path,
+            "--profile",
+            "dev",
+            "spawn",
+            "--environment",
+            "KEY=\"cli\"",
+            "--",
+            "cmd",
+        ])
+        .unwrap();
+
+        match options.command {
+            DistantSubcommand::Client(ClientSubcommand::Spawn(args)) => {
+                let SpawnArgs { environment, .. } = *args;
+                assert_eq!(environment, map!("KEY" -> "cli", "OTHER" -> "profile"));
+            }
+            x => panic!("Unexpected command: {x:?}"),
+        }
+
+        let err = Options::load_from(["distant", "--config", path, "--profile", "missing", "ping"])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "No profile named missing found in config");
+    }
 }
@@ -1,13 +1,41 @@
 mod address;
 mod cmd;
+mod connection;
+mod dir;
+mod find;
+mod forward;
+mod fs;
+mod history;
+mod limits;
 mod logging;
 mod network;
+mod paging;
+mod policy;
+mod schedule;
+mod scope;
+mod scripts;
 mod search;
+mod transfer;
+mod trust;
 mod value;
 
 pub use address::*;
 pub use cmd::*;
+pub use connection::*;
+pub use dir::*;
+pub use find::*;
+pub use forward::*;
+pub use fs::*;
+pub use history::*;
+pub use limits::*;
 pub use logging::*;
 pub use network::*;
+pub use paging::*;
+pub use policy::*;
+pub use schedule::*;
+pub use scope::*;
+pub use scripts::*;
 pub use search::*;
+pub use transfer::*;
+pub use trust::*;
 pub use value::*;
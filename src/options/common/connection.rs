@@ -0,0 +1,66 @@
+use derive_more::{Display, IsVariant};
+use distant_core::net::common::ConnectionId;
+use std::convert::Infallible;
+use std::str::FromStr;
+
+/// Refers to a connection being managed, either by its id or by the name it was given when
+/// connecting
+#[derive(Clone, Debug, Display, PartialEq, Eq, IsVariant)]
+pub enum ConnectionSelector {
+    Id(ConnectionId),
+    Name(String),
+}
+
+impl From<ConnectionId> for ConnectionSelector {
+    fn from(id: ConnectionId) -> Self {
+        Self::Id(id)
+    }
+}
+
+impl FromStr for ConnectionSelector {
+    type Err = Infallible;
+
+    /// Parses `s` as an id if it is a number, and as a name otherwise
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse::<ConnectionId>() {
+            Ok(id) => Self::Id(id),
+            Err(_) => Self::Name(s.to_string()),
+        })
+    }
+}
+
+/// Parses `s` as the name of a connection, which must not be mistaken for an id
+pub fn parse_connection_name(s: &str) -> Result<String, String> {
+    match s.parse::<ConnectionSelector>() {
+        Ok(ConnectionSelector::Name(name)) if !name.is_empty() => Ok(name),
+        _ => Err(String::from("Name must not be empty or a number")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_should_parse_numbers_as_ids_and_anything_else_as_names() {
+        assert_eq!(
+            "123".parse::<ConnectionSelector>().unwrap(),
+            ConnectionSelector::Id(123)
+        );
+        assert_eq!(
+            "myserver".parse::<ConnectionSelector>().unwrap(),
+            ConnectionSelector::Name(String::from("myserver"))
+        );
+        assert_eq!(
+            "-1".parse::<ConnectionSelector>().unwrap(),
+            ConnectionSelector::Name(String::from("-1"))
+        );
+    }
+
+    #[test]
+    fn parse_connection_name_should_reject_names_that_are_empty_or_numbers() {
+        assert_eq!(parse_connection_name("myserver").unwrap(), "myserver");
+        assert!(parse_connection_name("").is_err());
+        assert!(parse_connection_name("123").is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use clap::{Args, ValueEnum};
+use distant_core::data::{DirEntrySort, DirReadOptions, FileType};
+
+/// Options to sort and filter the entries of a directory.
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CliDirReadOptions {
+    /// Order of entries within each directory.
+    ///
+    /// (directory only)
+    #[clap(long, value_enum, default_value_t = CliDirEntrySort::Name)]
+    pub sort: CliDirEntrySort,
+
+    /// Reverse the order of entries within each directory.
+    ///
+    /// (directory only)
+    #[clap(long)]
+    pub reverse: bool,
+
+    /// Restrict entries to only these file types (otherwise all are allowed).
+    ///
+    /// (directory only)
+    #[clap(long = "file-type", value_enum)]
+    pub allowed_file_types: Vec<CliFileType>,
+
+    /// Exclude hidden entries (those whose name starts with `.`) along with their contents.
+    ///
+    /// (directory only)
+    #[clap(long)]
+    pub exclude_hidden: bool,
+}
+
+impl From<CliDirReadOptions> for DirReadOptions {
+    fn from(x: CliDirReadOptions) -> Self {
+        Self {
+            sort: x.sort.into(),
+            reverse: x.reverse,
+            allowed_file_types: x
+                .allowed_file_types
+                .into_iter()
+                .map(FileType::from)
+                .collect(),
+            exclude_hidden: x.exclude_hidden,
+        }
+    }
+}
+
+/// Order of entries within a directory
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "snake_case")]
+pub enum CliDirEntrySort {
+    /// Sort by file name
+    #[default]
+    Name,
+
+    /// Sort by size in bytes, smallest first
+    Size,
+
+    /// Sort by last modified time, oldest first
+    Modified,
+}
+
+impl From<CliDirEntrySort> for DirEntrySort {
+    fn from(x: CliDirEntrySort) -> Self {
+        match x {
+            CliDirEntrySort::Name => Self::Name,
+            CliDirEntrySort::Size => Self::Size,
+            CliDirEntrySort::Modified => Self::Modified,
+        }
+    }
+}
+
+/// Type of an entry within a directory
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "snake_case")]
+pub enum CliFileType {
+    /// Directory
+    Dir,
+
+    /// Regular file
+    File,
+
+    /// Symbolic link
+    Symlink,
+
+    /// Named pipe (FIFO)
+    Fifo,
+
+    /// Unix domain socket
+    Socket,
+
+    /// Character device
+    CharDevice,
+
+    /// Block device
+    BlockDevice,
+}
+
+impl From<CliFileType> for FileType {
+    fn from(x: CliFileType) -> Self {
+        match x {
+            CliFileType::Dir => Self::Dir,
+            CliFileType::File => Self::File,
+            CliFileType::Symlink => Self::Symlink,
+            CliFileType::Fifo => Self::Fifo,
+            CliFileType::Socket => Self::Socket,
+            CliFileType::CharDevice => Self::CharDevice,
+            CliFileType::BlockDevice => Self::BlockDevice,
+        }
+    }
+}
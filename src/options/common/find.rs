@@ -0,0 +1,86 @@
+use distant_core::data::FileType;
+use std::str::FromStr;
+
+/// Bound on a number written the way find(1) writes them, where `+N` is more than `N`, `-N` is
+/// less than `N`, and `N` is exactly `N`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FindBound {
+    MoreThan(u64),
+    LessThan(u64),
+    Exactly(u64),
+}
+
+impl FindBound {
+    /// Parses `s` as a bound on a number whose unit is scaled by the suffix given to `unit`
+    fn parse(s: &str, unit: impl FnOnce(&str) -> Option<(&str, u64)>) -> Result<Self, String> {
+        let (make, rest): (fn(u64) -> Self, &str) = match s.as_bytes().first() {
+            Some(b'+') => (Self::MoreThan, &s[1..]),
+            Some(b'-') => (Self::LessThan, &s[1..]),
+            _ => (Self::Exactly, s),
+        };
+
+        let (number, scale) = unit(rest).ok_or_else(|| format!("Invalid unit in {s:?}"))?;
+        let number = u64::from_str(number).map_err(|x| format!("Invalid number in {s:?}: {x}"))?;
+        Ok(make(number.saturating_mul(scale)))
+    }
+
+    /// Returns the smallest and largest numbers (inclusive) within the bound
+    pub fn range(self) -> (Option<u64>, Option<u64>) {
+        match self {
+            Self::MoreThan(n) => (Some(n.saturating_add(1)), None),
+            Self::LessThan(n) => (None, Some(n.saturating_sub(1))),
+            Self::Exactly(n) => (Some(n), Some(n)),
+        }
+    }
+
+    /// Parses a bound on a size in bytes, optionally suffixed with `c` (bytes), `k`, `M`, or `G`
+    pub fn parse_size(s: &str) -> Result<Self, String> {
+        Self::parse(s, |s| match s.char_indices().last() {
+            Some((i, 'k')) => Some((&s[..i], 1024)),
+            Some((i, 'M')) => Some((&s[..i], 1024 * 1024)),
+            Some((i, 'G')) => Some((&s[..i], 1024 * 1024 * 1024)),
+            Some((i, 'c')) => Some((&s[..i], 1)),
+            Some((_, c)) if c.is_ascii_digit() => Some((s, 1)),
+            _ => None,
+        })
+    }
+
+    /// Parses a bound on a number of days
+    pub fn parse_days(s: &str) -> Result<Self, String> {
+        Self::parse(s, |s| Some((s, 1)))
+    }
+}
+
+/// Parses a file type written the way find(1) writes them
+pub fn parse_find_file_type(s: &str) -> Result<FileType, String> {
+    match s {
+        "f" => Ok(FileType::File),
+        "d" => Ok(FileType::Dir),
+        "l" => Ok(FileType::Symlink),
+        "p" => Ok(FileType::Fifo),
+        "s" => Ok(FileType::Socket),
+        "c" => Ok(FileType::CharDevice),
+        "b" => Ok(FileType::BlockDevice),
+        _ => Err(format!(
+            "Unknown file type {s:?}, expected f, d, l, p, s, c, or b"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_size_should_support_signs_and_units() {
+        assert_eq!(FindBound::parse_size("10"), Ok(FindBound::Exactly(10)));
+        assert_eq!(FindBound::parse_size("+2k"), Ok(FindBound::MoreThan(2048)));
+        assert_eq!(
+            FindBound::parse_size("-1M"),
+            Ok(FindBound::LessThan(1024 * 1024))
+        );
+        assert_eq!(FindBound::parse_size("5c"), Ok(FindBound::Exactly(5)));
+        assert!(FindBound::parse_size("5x").is_err());
+        assert!(FindBound::parse_size("+").is_err());
+    }
+}
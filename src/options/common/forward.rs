@@ -0,0 +1,207 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Represents a port forwarded through a connection, written the way ssh writes them as
+/// `[bind_address:]port:host:hostport`, where IPv6 addresses are wrapped in brackets.
+///
+/// E.g. `5432:db.internal:5432` or `127.0.0.1:8080:localhost:80`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortForward {
+    /// Address on which to listen for connections, defaulting to `localhost`
+    pub bind_address: String,
+
+    /// Port on which to listen for connections
+    pub port: u16,
+
+    /// Host that connections are forwarded to
+    pub host: String,
+
+    /// Port that connections are forwarded to
+    pub host_port: u16,
+}
+
+impl fmt::Display for PortForward {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bracket = |host: &str| {
+            if host.contains(':') {
+                format!("[{host}]")
+            } else {
+                host.to_string()
+            }
+        };
+
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            bracket(&self.bind_address),
+            self.port,
+            bracket(&self.host),
+            self.host_port
+        )
+    }
+}
+
+impl FromStr for PortForward {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = split_unbracketed(s)?;
+        let (bind_address, port, host, host_port) = match parts.as_slice() {
+            [port, host, host_port] => ("localhost", port, host, host_port),
+            [bind_address, port, host, host_port] => (bind_address.as_str(), port, host, host_port),
+            _ => {
+                return Err(format!(
+                    "{s:?} is not of the form [bind_address:]port:host:hostport"
+                ))
+            }
+        };
+
+        let parse_port = |port: &str| {
+            port.parse::<u16>()
+                .map_err(|_| format!("{port:?} is not a valid port"))
+        };
+
+        if host.is_empty() {
+            return Err(String::from("host cannot be empty"));
+        }
+
+        Ok(Self {
+            bind_address: if bind_address.is_empty() {
+                String::from("localhost")
+            } else {
+                bind_address.to_string()
+            },
+            port: parse_port(port)?,
+            host: host.to_string(),
+            host_port: parse_port(host_port)?,
+        })
+    }
+}
+
+/// Represents a port on which a SOCKS5 proxy listens, written the way ssh writes them as
+/// `[bind_address:]port`, where connections made through the proxy are forwarded to whichever
+/// host and port they ask for.
+///
+/// E.g. `1080` or `127.0.0.1:1080`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DynamicForward {
+    /// Address on which to listen for connections, defaulting to `localhost`
+    pub bind_address: String,
+
+    /// Port on which to listen for connections
+    pub port: u16,
+}
+
+impl fmt::Display for DynamicForward {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.bind_address.contains(':') {
+            write!(f, "[{}]:{}", self.bind_address, self.port)
+        } else {
+            write!(f, "{}:{}", self.bind_address, self.port)
+        }
+    }
+}
+
+impl FromStr for DynamicForward {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = split_unbracketed(s)?;
+        let (bind_address, port) = match parts.as_slice() {
+            [port] => ("", port),
+            [bind_address, port] => (bind_address.as_str(), port),
+            _ => return Err(format!("{s:?} is not of the form [bind_address:]port")),
+        };
+
+        Ok(Self {
+            bind_address: if bind_address.is_empty() {
+                String::from("localhost")
+            } else {
+                bind_address.to_string()
+            },
+            port: port
+                .parse()
+                .map_err(|_| format!("{port:?} is not a valid port"))?,
+        })
+    }
+}
+
+/// Splits `s` at each colon that is not within brackets, removing the brackets
+fn split_unbracketed(s: &str) -> Result<Vec<String>, String> {
+    let mut parts = vec![String::new()];
+    let mut in_brackets = false;
+    for c in s.chars() {
+        match c {
+            '[' if !in_brackets => in_brackets = true,
+            ']' if in_brackets => in_brackets = false,
+            ':' if !in_brackets => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+
+    if in_brackets {
+        return Err(format!("{s:?} is missing a closing bracket"));
+    }
+
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_should_default_bind_address_to_localhost() {
+        assert_eq!(
+            "5432:db.internal:5433".parse::<PortForward>().unwrap(),
+            PortForward {
+                bind_address: String::from("localhost"),
+                port: 5432,
+                host: String::from("db.internal"),
+                host_port: 5433,
+            }
+        );
+    }
+
+    #[test]
+    fn from_str_should_support_bracketed_ipv6_addresses() {
+        let forward = "[::1]:8080:[fe80::1]:80".parse::<PortForward>().unwrap();
+        assert_eq!(
+            forward,
+            PortForward {
+                bind_address: String::from("::1"),
+                port: 8080,
+                host: String::from("fe80::1"),
+                host_port: 80,
+            }
+        );
+        assert_eq!(forward.to_string(), "[::1]:8080:[fe80::1]:80");
+    }
+
+    #[test]
+    fn from_str_should_fail_if_malformed() {
+        assert!("8080".parse::<PortForward>().is_err());
+        assert!("8080:host".parse::<PortForward>().is_err());
+        assert!("http:host:80".parse::<PortForward>().is_err());
+        assert!("8080::80".parse::<PortForward>().is_err());
+        assert!("[::1:8080:host:80".parse::<PortForward>().is_err());
+    }
+
+    #[test]
+    fn dynamic_from_str_should_default_bind_address_to_localhost() {
+        assert_eq!(
+            "1080".parse::<DynamicForward>().unwrap(),
+            DynamicForward {
+                bind_address: String::from("localhost"),
+                port: 1080,
+            }
+        );
+
+        let forward = "[::1]:1080".parse::<DynamicForward>().unwrap();
+        assert_eq!(forward.bind_address, "::1");
+        assert_eq!(forward.to_string(), "[::1]:1080");
+
+        assert!("host".parse::<DynamicForward>().is_err());
+        assert!("a:b:1080".parse::<DynamicForward>().is_err());
+    }
+}
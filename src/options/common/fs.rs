@@ -0,0 +1,61 @@
+use clap::Args;
+use distant_core::DefaultModes;
+use serde::{Deserialize, Serialize};
+
+/// Represents the permissions given by the server to files and directories it creates
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FsSettings {
+    /// Permissions (in octal, such as 644) given to files created on behalf of clients that do
+    /// not provide their own, in place of those left by the umask of the server (Unix only)
+    #[clap(long = "default-file-mode", value_name = "MODE", value_parser = parse_mode)]
+    pub default_file_mode: Option<u32>,
+
+    /// Permissions (in octal, such as 755) given to directories created on behalf of clients
+    /// that do not provide their own, in place of those left by the umask of the server (Unix
+    /// only)
+    #[clap(long = "default-dir-mode", value_name = "MODE", value_parser = parse_mode)]
+    pub default_dir_mode: Option<u32>,
+}
+
+impl FsSettings {
+    /// Merge these settings with the `other` settings. These settings take priority
+    /// over the `other` settings.
+    pub fn merge(&mut self, other: Self) {
+        self.default_file_mode = self.default_file_mode.take().or(other.default_file_mode);
+        self.default_dir_mode = self.default_dir_mode.take().or(other.default_dir_mode);
+    }
+}
+
+impl From<FsSettings> for DefaultModes {
+    fn from(settings: FsSettings) -> Self {
+        Self {
+            file: settings.default_file_mode,
+            dir: settings.default_dir_mode,
+        }
+    }
+}
+
+/// Parses permissions written in octal, such as `644`, `0644`, or `0o644`
+pub fn parse_mode(s: &str) -> Result<u32, String> {
+    let digits = s.strip_prefix("0o").unwrap_or(s);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        Ok(_) => Err(format!("{s} is larger than 7777")),
+        Err(x) => Err(format!("{s} is not an octal mode: {x}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_mode_should_parse_octal_with_or_without_prefix() {
+        assert_eq!(parse_mode("644"), Ok(0o644));
+        assert_eq!(parse_mode("0755"), Ok(0o755));
+        assert_eq!(parse_mode("0o2775"), Ok(0o2775));
+        assert!(parse_mode("0x644").is_err());
+        assert!(parse_mode("789").is_err());
+        assert!(parse_mode("17777").is_err());
+    }
+}
@@ -0,0 +1,25 @@
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Represents settings for the local history of connections made and commands run by the client
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistorySettings {
+    /// Skip recording connections and commands in the local history
+    #[clap(long)]
+    #[serde(default)]
+    pub no_history: bool,
+
+    /// Location of the local history (defaults to a file in the local data directory of the user)
+    #[clap(long, value_name = "PATH")]
+    pub history_file: Option<PathBuf>,
+}
+
+impl HistorySettings {
+    /// Merge these settings with the `other` settings. These settings take priority
+    /// over the `other` settings.
+    pub fn merge(&mut self, other: Self) {
+        self.no_history = self.no_history || other.no_history;
+        self.history_file = self.history_file.take().or(other.history_file);
+    }
+}
@@ -0,0 +1,99 @@
+use clap::Args;
+use distant_core::{data::ProcLimits, OutputLimits};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Represents limits on the output of spawned processes that the server holds onto while waiting
+/// to send it
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputLimitSettings {
+    /// Buffer up to N bytes of each process' stdout and stderr in memory, spilling older output
+    /// to disk, rather than holding back processes whose output cannot be sent fast enough
+    #[clap(long, value_name = "BYTES")]
+    pub max_output_memory: Option<usize>,
+
+    /// Directory where process output beyond the memory limit is spilled (defaults to the
+    /// temporary directory)
+    #[clap(long, value_name = "PATH")]
+    pub output_spill_dir: Option<PathBuf>,
+
+    /// Maximum size of each file of spilled output in bytes before another file is started
+    #[clap(long, value_name = "BYTES")]
+    pub max_output_spill_file_size: Option<u64>,
+
+    /// Maximum files of spilled output kept for each of stdout and stderr, beyond which the
+    /// oldest output is discarded
+    #[clap(long, value_name = "N")]
+    pub max_output_spill_files: Option<usize>,
+
+    /// Largest window of output (in bytes) sent ahead of being acknowledged that clients can ask
+    /// for when spawning processes, where larger windows are shrunk to it
+    #[clap(long, value_name = "BYTES")]
+    pub max_output_window: Option<usize>,
+}
+
+impl OutputLimitSettings {
+    /// Merge these settings with the `other` settings. These settings take priority
+    /// over the `other` settings.
+    pub fn merge(&mut self, other: Self) {
+        self.max_output_memory = self.max_output_memory.take().or(other.max_output_memory);
+        self.output_spill_dir = self.output_spill_dir.take().or(other.output_spill_dir);
+        self.max_output_spill_file_size = self
+            .max_output_spill_file_size
+            .take()
+            .or(other.max_output_spill_file_size);
+        self.max_output_spill_files = self
+            .max_output_spill_files
+            .take()
+            .or(other.max_output_spill_files);
+        self.max_output_window = self.max_output_window.take().or(other.max_output_window);
+    }
+}
+
+impl From<OutputLimitSettings> for OutputLimits {
+    fn from(settings: OutputLimitSettings) -> Self {
+        let default = Self::default();
+        Self {
+            max_memory: settings.max_output_memory,
+            spill_dir: settings.output_spill_dir.unwrap_or(default.spill_dir),
+            max_spill_file_size: settings
+                .max_output_spill_file_size
+                .unwrap_or(default.max_spill_file_size),
+            max_spill_files: settings
+                .max_output_spill_files
+                .unwrap_or(default.max_spill_files),
+            max_window: settings.max_output_window,
+        }
+    }
+}
+
+/// Represents the priority and limits on resources applied to a spawned process before it runs
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProcLimitSettings {
+    /// Niceness of the process, where higher is a lower priority (typically -20 to 19)
+    #[clap(long, value_name = "N", allow_negative_numbers = true)]
+    pub nice: Option<i32>,
+
+    /// Comma-separated CPUs that the process is restricted to running on (Linux only)
+    #[clap(long, value_name = "CPUS", value_delimiter = ',')]
+    pub cpu_affinity: Option<Vec<usize>>,
+
+    /// Maximum bytes of memory that the process can allocate
+    #[clap(long, value_name = "BYTES")]
+    pub max_memory: Option<u64>,
+
+    /// Maximum files that the process can have open at once
+    #[clap(long, value_name = "N")]
+    pub max_open_files: Option<u64>,
+}
+
+impl From<ProcLimitSettings> for ProcLimits {
+    fn from(settings: ProcLimitSettings) -> Self {
+        Self {
+            nice: settings.nice,
+            cpu_affinity: settings.cpu_affinity,
+            max_memory: settings.max_memory,
+            max_open_files: settings.max_open_files,
+        }
+    }
+}
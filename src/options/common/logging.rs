@@ -12,12 +12,34 @@ pub struct LoggingSettings {
     /// Path to file to use for logging
     #[clap(long, global = true)]
     pub log_file: Option<PathBuf>,
+
+    /// Format of each line written to the log file
+    #[clap(long, global = true, value_enum)]
+    pub log_format: Option<LogFormat>,
 }
 
 impl LoggingSettings {
     pub fn log_level_or_default(&self) -> LogLevel {
         self.log_level.as_ref().copied().unwrap_or_default()
     }
+
+    pub fn log_format_or_default(&self) -> LogFormat {
+        self.log_format.unwrap_or_default()
+    }
+}
+
+/// Represents the format of lines written to the log file.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[clap(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human-readable lines of text
+    #[default]
+    Text,
+
+    /// One JSON object per line, with the timestamp, level, target, connection id, request id,
+    /// and message as separate fields
+    Json,
 }
 
 /// Represents the level associated with logging.
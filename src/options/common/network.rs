@@ -1,6 +1,8 @@
 use crate::constants;
 use clap::Args;
+use distant_core::net::common::{LinkSimulation, Map, SocketOptions};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// Level of access control to the unix socket or windows pipe
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
@@ -38,13 +40,19 @@ impl Default for AccessControl {
 /// Represents common networking configuration
 #[derive(Args, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NetworkSettings {
-    /// Override the path to the Unix socket used by the manager (unix-only)
+    /// Override the path to the Unix socket used by the manager (unix-only), where a path of
+    /// `@name` refers to a socket in the abstract namespace (linux-only)
     #[clap(long)]
     pub unix_socket: Option<std::path::PathBuf>,
 
     /// Override the name of the local named Windows pipe used by the manager (windows-only)
     #[clap(long)]
     pub windows_pipe: Option<String>,
+
+    /// Shape traffic to the manager as if it travelled over a slow link, such as
+    /// `latency=200ms,bandwidth=1mbit`, to see how plugins and commands behave over one
+    #[clap(long, value_name = "SPEC")]
+    pub simulate: Option<LinkSimulation>,
 }
 
 impl NetworkSettings {
@@ -53,6 +61,7 @@ impl NetworkSettings {
     pub fn merge(&mut self, other: Self) {
         self.unix_socket = self.unix_socket.take().or(other.unix_socket);
         self.windows_pipe = self.windows_pipe.take().or(other.windows_pipe);
+        self.simulate = self.simulate.take().or(other.simulate);
     }
 
     /// Returns option containing reference to unix path if configured
@@ -89,3 +98,103 @@ impl NetworkSettings {
         }
     }
 }
+
+/// Represents options applied to TCP sockets when they are created
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SocketSettings {
+    /// Disable Nagle's algorithm (TCP_NODELAY) so small writes are sent immediately
+    #[clap(long)]
+    #[serde(default)]
+    pub tcp_nodelay: bool,
+
+    /// Enable TCP keepalive, sending probes after a connection is idle for N seconds
+    #[clap(long, value_name = "SECONDS")]
+    pub tcp_keepalive: Option<u64>,
+
+    /// Size of the socket send buffer in bytes
+    #[clap(long, value_name = "BYTES")]
+    pub send_buffer_size: Option<u32>,
+
+    /// Size of the socket receive buffer in bytes
+    #[clap(long, value_name = "BYTES")]
+    pub recv_buffer_size: Option<u32>,
+
+    /// Maximum number of pending connections queued by a listening socket
+    #[clap(long)]
+    pub backlog: Option<u32>,
+}
+
+impl SocketSettings {
+    /// Merge these settings with the `other` settings. These settings take priority
+    /// over the `other` settings.
+    pub fn merge(&mut self, other: Self) {
+        if !self.tcp_nodelay && other.tcp_nodelay {
+            self.tcp_nodelay = true;
+        }
+        self.tcp_keepalive = self.tcp_keepalive.take().or(other.tcp_keepalive);
+        self.send_buffer_size = self.send_buffer_size.take().or(other.send_buffer_size);
+        self.recv_buffer_size = self.recv_buffer_size.take().or(other.recv_buffer_size);
+        self.backlog = self.backlog.take().or(other.backlog);
+    }
+}
+
+impl From<Map> for SocketSettings {
+    fn from(mut map: Map) -> Self {
+        Self {
+            tcp_nodelay: map
+                .remove("tcp_nodelay")
+                .and_then(|x| x.parse::<bool>().ok())
+                .unwrap_or_default(),
+            tcp_keepalive: map
+                .remove("tcp_keepalive")
+                .and_then(|x| x.parse::<u64>().ok()),
+            send_buffer_size: map
+                .remove("send_buffer_size")
+                .and_then(|x| x.parse::<u32>().ok()),
+            recv_buffer_size: map
+                .remove("recv_buffer_size")
+                .and_then(|x| x.parse::<u32>().ok()),
+            backlog: map.remove("backlog").and_then(|x| x.parse::<u32>().ok()),
+        }
+    }
+}
+
+impl From<SocketSettings> for Map {
+    fn from(settings: SocketSettings) -> Self {
+        let mut this = Self::new();
+
+        if settings.tcp_nodelay {
+            this.insert("tcp_nodelay".to_string(), "true".to_string());
+        }
+
+        if let Some(x) = settings.tcp_keepalive {
+            this.insert("tcp_keepalive".to_string(), x.to_string());
+        }
+
+        if let Some(x) = settings.send_buffer_size {
+            this.insert("send_buffer_size".to_string(), x.to_string());
+        }
+
+        if let Some(x) = settings.recv_buffer_size {
+            this.insert("recv_buffer_size".to_string(), x.to_string());
+        }
+
+        if let Some(x) = settings.backlog {
+            this.insert("backlog".to_string(), x.to_string());
+        }
+
+        this
+    }
+}
+
+impl From<SocketSettings> for SocketOptions {
+    fn from(settings: SocketSettings) -> Self {
+        Self {
+            nodelay: settings.tcp_nodelay,
+            keepalive: settings.tcp_keepalive.map(Duration::from_secs),
+            send_buffer_size: settings.send_buffer_size,
+            recv_buffer_size: settings.recv_buffer_size,
+            backlog: settings.backlog,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+/// Represents settings for paging shell output of the client that does not fit on the screen
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PagingSettings {
+    /// Page shell output (directory listings, search results, and capabilities) that does not
+    /// fit on the screen through `$PAGER`, or a built-in pager if not set, when stdout is a
+    /// terminal
+    #[clap(long, global = true, overrides_with = "no_pager")]
+    #[serde(default)]
+    pub pager: bool,
+
+    /// Write shell output as is, even if paging is enabled by the config
+    #[clap(long, global = true, overrides_with = "pager")]
+    #[serde(skip)]
+    pub no_pager: bool,
+}
+
+impl PagingSettings {
+    /// Merge these settings with the `other` settings. These settings take priority
+    /// over the `other` settings.
+    pub fn merge(&mut self, other: Self) {
+        self.pager = !self.no_pager && (self.pager || other.pager);
+    }
+
+    /// Returns true if shell output is paged
+    pub fn is_enabled(&self) -> bool {
+        self.pager && !self.no_pager
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_should_enable_paging_from_other_unless_disabled() {
+        let settings = |pager, no_pager| PagingSettings { pager, no_pager };
+
+        let mut this = settings(false, false);
+        this.merge(settings(true, false));
+        assert!(this.is_enabled());
+
+        let mut this = settings(false, true);
+        this.merge(settings(true, false));
+        assert!(!this.is_enabled());
+
+        let mut this = settings(true, false);
+        this.merge(settings(false, false));
+        assert!(this.is_enabled());
+    }
+}
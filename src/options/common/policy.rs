@@ -0,0 +1,50 @@
+use clap::Args;
+use distant_core::ProcessPolicy;
+use serde::{Deserialize, Serialize};
+
+/// Represents which commands the server permits spawning
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessPolicySettings {
+    /// Only spawn commands matching PATTERN, which is a program (such as `git`, which must be the
+    /// one found in the PATH of the server) optionally followed by the arguments it must start
+    /// with (such as `git status`), where `*` matches any run of characters within a word; can be
+    /// provided multiple times
+    #[clap(long = "proc-allow", value_name = "PATTERN")]
+    #[serde(default)]
+    pub allow: Vec<String>,
+
+    /// Refuse to spawn commands matching PATTERN (written like those of `--proc-allow`, though its
+    /// arguments can appear anywhere in the command), even if they are allowed; can be provided
+    /// multiple times
+    #[clap(long = "proc-deny", value_name = "PATTERN")]
+    #[serde(default)]
+    pub deny: Vec<String>,
+
+    /// Permit clients to send signals to any process of the system by its pid, such as to clean
+    /// up orphaned processes, rather than only to the processes spawned by the server
+    #[clap(long = "proc-allow-system-kill")]
+    #[serde(default)]
+    pub allow_system_kill: bool,
+}
+
+impl ProcessPolicySettings {
+    /// Merge these settings with the `other` settings. These settings take priority
+    /// over the `other` settings, except that denied patterns of both are kept.
+    pub fn merge(&mut self, other: Self) {
+        if self.allow.is_empty() {
+            self.allow = other.allow;
+        }
+        self.deny.extend(other.deny);
+        self.allow_system_kill |= other.allow_system_kill;
+    }
+}
+
+impl From<ProcessPolicySettings> for ProcessPolicy {
+    fn from(settings: ProcessPolicySettings) -> Self {
+        Self {
+            allow: settings.allow,
+            deny: settings.deny,
+            system_kill: settings.allow_system_kill,
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Weekday};
+use derive_more::{Display, Error};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// Represents a recurring time of day on some days of the week, written as `[DAYS] HH:MM` where
+/// `DAYS` is one of `daily`, `weekdays`, `weekends`, or a comma-separated list of days such as
+/// `mon,wed,fri`. If `DAYS` is omitted, the schedule is daily.
+///
+/// E.g. `weekdays 08:00`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    /// Days of the week the schedule applies, indexed starting with Monday
+    days: [bool; 7],
+
+    /// Time of day the schedule triggers
+    time: NaiveTime,
+}
+
+impl Schedule {
+    /// Returns the next time strictly after `now` that the schedule triggers, or none if no time
+    /// exists within the next week (e.g. it is skipped by a daylight saving transition)
+    pub fn next_after<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let today = now.date_naive();
+        (0..=7)
+            .filter_map(|offset| today.checked_add_signed(Duration::days(offset)))
+            .filter(|date| self.days[date.weekday().num_days_from_monday() as usize])
+            .filter_map(|date| {
+                now.timezone()
+                    .from_local_datetime(&date.and_time(self.time))
+                    .earliest()
+            })
+            .find(|time| time > now)
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.days {
+            [true, true, true, true, true, true, true] => write!(f, "daily")?,
+            [true, true, true, true, true, false, false] => write!(f, "weekdays")?,
+            [false, false, false, false, false, true, true] => write!(f, "weekends")?,
+            days => {
+                let days: Vec<String> = WEEKDAYS
+                    .iter()
+                    .zip(days)
+                    .filter(|(_, enabled)| *enabled)
+                    .map(|(day, _)| day.to_string().to_lowercase())
+                    .collect();
+                write!(f, "{}", days.join(","))?;
+            }
+        }
+
+        write!(f, " {}", self.time.format("%H:%M"))
+    }
+}
+
+/// Represents an error that occurred when parsing a [`Schedule`]
+#[derive(Clone, Debug, Display, Error, PartialEq, Eq)]
+pub enum ScheduleParseError {
+    #[display(fmt = "Bad day: {_0}")]
+    BadDay(#[error(not(source))] String),
+
+    #[display(fmt = "Bad time (expected HH:MM): {_0}")]
+    BadTime(#[error(not(source))] String),
+
+    #[display(fmt = "Missing time")]
+    MissingTime,
+}
+
+impl FromStr for Schedule {
+    type Err = ScheduleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace().rev();
+        let time = parts.next().ok_or(ScheduleParseError::MissingTime)?;
+        let time = NaiveTime::parse_from_str(time, "%H:%M")
+            .map_err(|_| ScheduleParseError::BadTime(time.to_string()))?;
+
+        let days = match parts.next() {
+            None => [true; 7],
+            Some(s) if s.eq_ignore_ascii_case("daily") => [true; 7],
+            Some(s) if s.eq_ignore_ascii_case("weekdays") => {
+                [true, true, true, true, true, false, false]
+            }
+            Some(s) if s.eq_ignore_ascii_case("weekends") => {
+                [false, false, false, false, false, true, true]
+            }
+            Some(s) => {
+                let mut days = [false; 7];
+                for day in s.split(',') {
+                    let day = day
+                        .parse::<Weekday>()
+                        .map_err(|_| ScheduleParseError::BadDay(day.to_string()))?;
+                    days[day.num_days_from_monday() as usize] = true;
+                }
+                days
+            }
+        };
+
+        // Anything remaining is an unexpected extra component
+        if let Some(s) = parts.next() {
+            return Err(ScheduleParseError::BadDay(s.to_string()));
+        }
+
+        Ok(Self { days, time })
+    }
+}
+
+impl Serialize for Schedule {
+    /// Will store the schedule as a string.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        String::serialize(&self.to_string(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Schedule {
+    /// Will parse a string into a schedule.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        FromStr::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn from_str_should_support_named_and_listed_days() {
+        let schedule: Schedule = "weekdays 08:00".parse().unwrap();
+        assert_eq!(schedule.to_string(), "weekdays 08:00");
+
+        let schedule: Schedule = "09:30".parse().unwrap();
+        assert_eq!(schedule.to_string(), "daily 09:30");
+
+        let schedule: Schedule = "mon,Wed,fri 17:45".parse().unwrap();
+        assert_eq!(schedule.to_string(), "mon,wed,fri 17:45");
+
+        assert_eq!(
+            "someday 08:00".parse::<Schedule>().unwrap_err(),
+            ScheduleParseError::BadDay("someday".to_string())
+        );
+        assert_eq!(
+            "daily 8am".parse::<Schedule>().unwrap_err(),
+            ScheduleParseError::BadTime("8am".to_string())
+        );
+        assert_eq!(
+            "".parse::<Schedule>().unwrap_err(),
+            ScheduleParseError::MissingTime
+        );
+    }
+
+    #[test]
+    fn next_after_should_return_next_matching_day_and_time() {
+        let schedule: Schedule = "weekdays 08:00".parse().unwrap();
+
+        // Friday before 8am triggers the same day
+        assert_eq!(
+            schedule.next_after(&utc("2024-03-01T07:00:00Z")),
+            Some(utc("2024-03-01T08:00:00Z"))
+        );
+
+        // Friday at exactly 8am skips the weekend to Monday
+        assert_eq!(
+            schedule.next_after(&utc("2024-03-01T08:00:00Z")),
+            Some(utc("2024-03-04T08:00:00Z"))
+        );
+
+        // Same day of the week after the time wraps around to the following week
+        let schedule: Schedule = "wed 12:00".parse().unwrap();
+        assert_eq!(
+            schedule.next_after(&utc("2024-03-06T13:00:00Z")),
+            Some(utc("2024-03-13T12:00:00Z"))
+        );
+    }
+}
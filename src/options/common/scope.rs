@@ -0,0 +1,79 @@
+use clap::{Args, ValueEnum};
+use distant_core::{ProcessScope, ScopeLimits};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Cgroup (v2) in which processes are placed when none is given
+const DEFAULT_CGROUP: &str = "/sys/fs/cgroup/distant";
+
+/// Represents how processes spawned by the server are placed in a scope
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[clap(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessScopeKind {
+    /// Run each process in its own transient scope using systemd-run
+    Systemd,
+
+    /// Move every process into a dedicated cgroup (v2)
+    Cgroup,
+}
+
+/// Represents the scope (on Linux) in which the server spawns processes and the limits placed on
+/// them
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessScopeSettings {
+    /// Spawn processes within transient systemd scopes (with limits applying to each process) or
+    /// a dedicated cgroup (with limits applying to all processes together), spawning them without
+    /// a scope if unavailable
+    #[clap(long = "process-scope", value_name = "systemd|cgroup")]
+    pub kind: Option<ProcessScopeKind>,
+
+    /// Cgroup (v2) in which processes are placed with `--process-scope cgroup` (defaults to
+    /// /sys/fs/cgroup/distant)
+    #[clap(long = "process-cgroup", value_name = "PATH")]
+    pub cgroup: Option<PathBuf>,
+
+    /// Maximum bytes of memory that scoped processes can use
+    #[clap(long = "process-memory-max", value_name = "BYTES")]
+    pub memory_max: Option<u64>,
+
+    /// Maximum CPU time that scoped processes can use as a percentage of a single CPU
+    #[clap(long = "process-cpu-quota", value_name = "PERCENT")]
+    pub cpu_quota: Option<u32>,
+
+    /// Maximum number of processes and threads that can exist within the scope
+    #[clap(long = "process-tasks-max", value_name = "N")]
+    pub tasks_max: Option<u64>,
+}
+
+impl ProcessScopeSettings {
+    /// Merge these settings with the `other` settings. These settings take priority
+    /// over the `other` settings.
+    pub fn merge(&mut self, other: Self) {
+        self.kind = self.kind.take().or(other.kind);
+        self.cgroup = self.cgroup.take().or(other.cgroup);
+        self.memory_max = self.memory_max.take().or(other.memory_max);
+        self.cpu_quota = self.cpu_quota.take().or(other.cpu_quota);
+        self.tasks_max = self.tasks_max.take().or(other.tasks_max);
+    }
+}
+
+impl From<ProcessScopeSettings> for Option<ProcessScope> {
+    fn from(settings: ProcessScopeSettings) -> Self {
+        let limits = ScopeLimits {
+            memory_max: settings.memory_max,
+            cpu_quota: settings.cpu_quota,
+            tasks_max: settings.tasks_max,
+        };
+
+        settings.kind.map(|kind| match kind {
+            ProcessScopeKind::Systemd => ProcessScope::Systemd(limits),
+            ProcessScopeKind::Cgroup => ProcessScope::Cgroup {
+                path: settings
+                    .cgroup
+                    .unwrap_or_else(|| PathBuf::from(DEFAULT_CGROUP)),
+                limits,
+            },
+        })
+    }
+}
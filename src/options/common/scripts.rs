@@ -0,0 +1,42 @@
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Represents scripts run by the server to apply site-specific policy as it handles requests
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptSettings {
+    /// Lua script loaded by the server to register hooks that run as requests are handled, which
+    /// can be provided multiple times (requires distant to be built with the `scripts` feature)
+    #[clap(long = "script", value_name = "PATH")]
+    #[serde(default)]
+    pub paths: Vec<PathBuf>,
+
+    /// Name of a custom request (such as `x-deploy`) that the server handles by running the
+    /// `distant-ext-<name>` program in its PATH when no script handles it, which can be provided
+    /// multiple times, where no such programs are run for requests that are not listed
+    #[clap(long = "extension", value_name = "NAME")]
+    #[serde(default)]
+    pub extensions: Vec<String>,
+
+    /// Seconds that `distant-ext-<name>` programs are given to respond before they are killed
+    /// (default 30)
+    #[clap(long = "extension-timeout", value_name = "SECS")]
+    #[serde(default)]
+    pub extension_timeout: Option<u64>,
+}
+
+impl ScriptSettings {
+    /// Merge these settings with the `other` settings. These settings take priority
+    /// over the `other` settings.
+    pub fn merge(&mut self, other: Self) {
+        if self.paths.is_empty() {
+            self.paths = other.paths;
+        }
+
+        if self.extensions.is_empty() {
+            self.extensions = other.extensions;
+        }
+
+        self.extension_timeout = self.extension_timeout.take().or(other.extension_timeout);
+    }
+}
@@ -56,6 +56,26 @@ pub struct CliSearchQueryOptions {
     /// include the remaining results even if less than pagination request
     #[clap(long)]
     pub pagination: Option<u64>,
+
+    /// Number of lines before each match within a file's contents to include alongside it
+    #[clap(long, default_value_t = 0)]
+    pub before_context: u64,
+
+    /// Number of lines after each match within a file's contents to include alongside it
+    #[clap(long, default_value_t = 0)]
+    pub after_context: u64,
+
+    /// Skip searching the contents of files larger than this many bytes
+    #[clap(long)]
+    pub max_filesize: Option<u64>,
+
+    /// Search the contents of binary files found while recursing rather than skipping them
+    #[clap(long)]
+    pub binary: bool,
+
+    /// Search hidden files and directories rather than ignoring them
+    #[clap(long)]
+    pub follow_hidden: bool,
 }
 
 impl From<CliSearchQueryOptions> for SearchQueryOptions {
@@ -69,6 +89,16 @@ impl From<CliSearchQueryOptions> for SearchQueryOptions {
             limit: x.limit,
             max_depth: x.max_depth,
             pagination: x.pagination,
+            min_size: None,
+            max_size: None,
+            modified_after: None,
+            modified_before: None,
+            before_context: x.before_context,
+            after_context: x.after_context,
+            max_filesize: x.max_filesize,
+            skip_binary: !x.binary,
+            follow_hidden: x.follow_hidden,
+            on_demand: false,
         }
     }
 }
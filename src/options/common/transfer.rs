@@ -0,0 +1,104 @@
+use clap::ValueEnum;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Represents one side of a transfer between the local machine and a connection, where remote
+/// paths are written with a leading colon.
+///
+/// E.g. `./file.txt` is local and `:/tmp/file.txt` is remote
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransferPath {
+    /// Path on the local machine
+    Local(PathBuf),
+
+    /// Path on the remote machine of the connection
+    Remote(PathBuf),
+}
+
+impl TransferPath {
+    /// Returns true if the path is on the remote machine
+    pub fn is_remote(&self) -> bool {
+        matches!(self, Self::Remote(_))
+    }
+
+    /// Returns the path without any indication of where it lives
+    pub fn into_path_buf(self) -> PathBuf {
+        match self {
+            Self::Local(path) | Self::Remote(path) => path,
+        }
+    }
+}
+
+impl fmt::Display for TransferPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local(path) => write!(f, "{}", path.display()),
+            Self::Remote(path) => write!(f, ":{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for TransferPath {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix(':') {
+            Some("") => Err("remote path cannot be empty"),
+            Some(path) => Ok(Self::Remote(PathBuf::from(path))),
+            None if s.is_empty() => Err("local path cannot be empty"),
+            None => Ok(Self::Local(PathBuf::from(s))),
+        }
+    }
+}
+
+/// How to resolve a path that changed on both sides of a bidirectional sync
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Keep whichever side was modified most recently
+    #[default]
+    Newest,
+
+    /// Always keep the local side
+    Local,
+
+    /// Always keep the remote side
+    Remote,
+
+    /// Leave both sides untouched and report the conflict
+    Skip,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_should_treat_leading_colon_as_remote() {
+        assert_eq!(
+            ":/tmp/file.txt".parse::<TransferPath>().unwrap(),
+            TransferPath::Remote(PathBuf::from("/tmp/file.txt"))
+        );
+        assert_eq!(
+            "./file.txt".parse::<TransferPath>().unwrap(),
+            TransferPath::Local(PathBuf::from("./file.txt"))
+        );
+
+        // Only a leading colon is meaningful, allowing paths like C:\ to be local
+        assert_eq!(
+            r"C:\file.txt".parse::<TransferPath>().unwrap(),
+            TransferPath::Local(PathBuf::from(r"C:\file.txt"))
+        );
+
+        assert!(":".parse::<TransferPath>().is_err());
+        assert!("".parse::<TransferPath>().is_err());
+    }
+
+    #[test]
+    fn display_should_round_trip() {
+        for s in [":/tmp/file.txt", "./file.txt"] {
+            assert_eq!(s.parse::<TransferPath>().unwrap().to_string(), s);
+        }
+    }
+}
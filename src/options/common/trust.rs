@@ -0,0 +1,27 @@
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Represents settings for trusting the remote roots that processes are run in
+#[derive(Args, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustSettings {
+    /// Run without asking whether to trust the destination and root directory, even if they have
+    /// never been seen before or were previously not trusted
+    #[clap(long)]
+    #[serde(default)]
+    pub trust: bool,
+
+    /// Location of the decisions made about which destinations and root directories to trust
+    /// (defaults to a file in the local data directory of the user)
+    #[clap(long, value_name = "PATH")]
+    pub trust_file: Option<PathBuf>,
+}
+
+impl TrustSettings {
+    /// Merge these settings with the `other` settings. These settings take priority
+    /// over the `other` settings.
+    pub fn merge(&mut self, other: Self) {
+        self.trust = self.trust || other.trust;
+        self.trust_file = self.trust_file.take().or(other.trust_file);
+    }
+}
@@ -3,16 +3,21 @@ use crate::constants;
 use anyhow::Context;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml_edit::Document;
 
 mod client;
 mod generate;
 mod manager;
+mod profile;
 mod server;
 
 pub use client::*;
 pub use generate::*;
 pub use manager::*;
+pub use profile::*;
 pub use server::*;
 
 const DEFAULT_RAW_STR: &str = include_str!("config.toml");
@@ -24,6 +29,10 @@ pub struct Config {
     pub generate: GenerateConfig,
     pub manager: ManagerConfig,
     pub server: ServerConfig,
+
+    /// Profiles selected with `--profile`, found in `[profile.<name>]` tables
+    #[serde(default)]
+    pub profile: BTreeMap<String, ProfileConfig>,
 }
 
 impl Config {
@@ -69,6 +78,38 @@ impl Config {
         }
     }
 
+    /// Edits the config file at `path` using `f`, keeping its comments and formatting, and then
+    /// writes it back if it still parses as a [`Config`]
+    ///
+    /// If the file does not exist, it is created from the default config file.
+    pub fn edit(
+        path: impl AsRef<Path>,
+        f: impl FnOnce(&mut Document) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(x) if x.kind() == io::ErrorKind::NotFound => Self::default_raw_str().to_string(),
+            Err(x) => {
+                return Err(x).with_context(|| format!("Failed to read config file {path:?}"))
+            }
+        };
+
+        let mut document: Document = text
+            .parse()
+            .with_context(|| format!("Failed to parse config file {path:?}"))?;
+        f(&mut document)?;
+        toml_edit::de::from_document::<Self>(document.clone())
+            .context("Edited config would be invalid")?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {parent:?}"))?;
+        }
+        std::fs::write(path, document.to_string())
+            .with_context(|| format!("Failed to write config file {path:?}"))
+    }
+
     /// Loads the specified `path` as a [`Config`]
     #[cfg(test)]
     pub async fn load(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
@@ -93,10 +134,12 @@ impl Default for Config {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::options::Format;
     use common::*;
     use distant_core::net::common::{Host, Map, PortRange};
     use distant_core::net::map;
     use distant_core::net::server::Shutdown;
+    use std::collections::BTreeMap;
     use std::net::Ipv4Addr;
     use std::time::Duration;
     use test_log::test;
@@ -118,32 +161,68 @@ mod tests {
                             bind_server: Some(BindAddress::Ssh),
                             args: Some("".to_string())
                         },
+                        verify: false,
                         options: Map::new(),
                     },
                     logging: LoggingSettings {
                         log_level: Some(LogLevel::Info),
-                        log_file: None
+                        log_file: None,
+                        log_format: None
                     },
                     network: NetworkSettings {
                         unix_socket: None,
-                        windows_pipe: None
+                        windows_pipe: None,
+                        simulate: None
+                    },
+                    history: HistorySettings {
+                        no_history: false,
+                        history_file: None,
+                    },
+                    trust: TrustSettings {
+                        trust: false,
+                        trust_file: None,
+                    },
+                    paging: PagingSettings {
+                        pager: false,
+                        no_pager: false,
                     },
+                    net: ClientNetworkConfig {
+                        socket: SocketSettings {
+                            tcp_nodelay: false,
+                            tcp_keepalive: None,
+                            send_buffer_size: None,
+                            recv_buffer_size: None,
+                            backlog: None,
+                        },
+                        resolve: BTreeMap::new(),
+                        dns_timeout: None,
+                        heartbeat_timeout: None,
+                        address_family: None,
+                        proxy: None,
+                    },
+                    shell: ClientShellConfig { init_cmd: None },
                 },
                 generate: GenerateConfig {
                     logging: LoggingSettings {
                         log_level: Some(LogLevel::Info),
-                        log_file: None
+                        log_file: None,
+                        log_format: None
                     },
                 },
                 manager: ManagerConfig {
                     access: Some(AccessControl::Owner),
+                    autostart: ManagerAutostartConfig {
+                        connections: Vec::new(),
+                    },
                     logging: LoggingSettings {
                         log_level: Some(LogLevel::Info),
-                        log_file: None
+                        log_file: None,
+                        log_format: None
                     },
                     network: NetworkSettings {
                         unix_socket: None,
-                        windows_pipe: None
+                        windows_pipe: None,
+                        simulate: None
                     },
                 },
                 server: ServerConfig {
@@ -153,12 +232,36 @@ mod tests {
                         use_ipv6: false,
                         shutdown: Some(Shutdown::Never),
                         current_dir: None,
+                        write_quota: None,
+                        heartbeat_interval: None,
+                        motd: None,
+                        allow_remote_tunnels: false,
+                        socket: SocketSettings {
+                            tcp_nodelay: false,
+                            tcp_keepalive: None,
+                            send_buffer_size: None,
+                            recv_buffer_size: None,
+                            backlog: None,
+                        },
+                    },
+                    limits: OutputLimitSettings {
+                        max_output_memory: None,
+                        output_spill_dir: None,
+                        max_output_spill_file_size: None,
+                        max_output_spill_files: None,
+                        max_output_window: None,
                     },
+                    scope: Default::default(),
+                    proc: Default::default(),
+                    fs: Default::default(),
+                    scripts: Default::default(),
                     logging: LoggingSettings {
                         log_level: Some(LogLevel::Info),
-                        log_file: None
+                        log_file: None,
+                        log_format: None
                     },
                 },
+                profile: BTreeMap::new(),
             }
         );
     }
@@ -173,8 +276,14 @@ mod tests {
 [client]
 log_file = "client-log-file"
 log_level = "trace"
+log_format = "json"
 unix_socket = "client-unix-socket"
 windows_pipe = "client-windows-pipe"
+no_history = true
+history_file = "client-history-file"
+trust = true
+trust_file = "client-trust-file"
+pager = true
 
 [client.api]
 timeout = 456
@@ -186,8 +295,23 @@ options = "key=\"value\",key2=\"value2\""
 bin = "some-bin"
 bind_server = "any"
 args = "a b c"
+verify = true
 options = "key3=\"value3\",key4=\"value4\""
 
+[client.network]
+tcp_nodelay = true
+tcp_keepalive = 30
+send_buffer_size = 1024
+recv_buffer_size = 2048
+dns_timeout = 2.5
+heartbeat_timeout = 30
+address_family = "ipv6"
+proxy = "socks5://127.0.0.1:9050"
+resolve = { "buildbox" = "10.1.2.3", "other" = "::1" }
+
+[client.shell]
+init_cmd = "cd project"
+
 [generate]
 log_file = "generate-log-file"
 log_level = "debug"
@@ -199,6 +323,15 @@ access = "anyone"
 unix_socket = "manager-unix-socket"
 windows_pipe = "manager-windows-pipe"
 
+[[manager.autostart.connections]]
+destination = "ssh://example.com"
+launch = true
+options = "key5=\"value5\""
+schedule = "weekdays 08:00"
+
+[[manager.autostart.connections]]
+destination = "distant://other.example.com"
+
 [server]
 log_file = "server-log-file"
 log_level = "error"
@@ -209,6 +342,54 @@ port = "8080:8089"
 use_ipv6 = true
 shutdown = "after=123"
 current_dir = "server-current-dir"
+write_quota = 4096
+heartbeat_interval = 10
+motd = "staging environment"
+allow_remote_tunnels = true
+tcp_nodelay = true
+tcp_keepalive = 45
+send_buffer_size = 4096
+recv_buffer_size = 8192
+backlog = 64
+
+[server.limits]
+max_output_memory = 1024
+output_spill_dir = "server-spill-dir"
+max_output_spill_file_size = 2048
+max_output_spill_files = 3
+max_output_window = 4096
+
+[server.scope]
+kind = "cgroup"
+cgroup = "server-cgroup"
+memory_max = 1048576
+cpu_quota = 150
+tasks_max = 32
+
+[server.proc]
+allow = ["git", "cargo build"]
+deny = ["git push"]
+allow_system_kill = true
+
+[server.fs]
+default_file_mode = 0o640
+default_dir_mode = 0o750
+
+[server.scripts]
+paths = ["server-script.lua"]
+extensions = ["x-deploy"]
+extension_timeout = 5
+
+[profile.prod]
+destination = "ssh://me@prod.example.com"
+environment = "RUST_LOG=debug"
+format = "json"
+
+[profile.prod.launch]
+bin = "/opt/distant/bin/distant"
+options = "key6=\"value6\""
+
+[profile.dev]
 "#,
             )
             .unwrap();
@@ -230,32 +411,88 @@ current_dir = "server-current-dir"
                             bind_server: Some(BindAddress::Any),
                             args: Some(String::from("a b c"))
                         },
+                        verify: true,
                         options: map!("key3" -> "value3", "key4" -> "value4"),
                     },
                     logging: LoggingSettings {
                         log_level: Some(LogLevel::Trace),
                         log_file: Some(PathBuf::from("client-log-file")),
+                        log_format: Some(LogFormat::Json),
                     },
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("client-unix-socket")),
-                        windows_pipe: Some(String::from("client-windows-pipe"))
+                        windows_pipe: Some(String::from("client-windows-pipe")),
+                        simulate: None
+                    },
+                    history: HistorySettings {
+                        no_history: true,
+                        history_file: Some(PathBuf::from("client-history-file")),
+                    },
+                    trust: TrustSettings {
+                        trust: true,
+                        trust_file: Some(PathBuf::from("client-trust-file")),
+                    },
+                    paging: PagingSettings {
+                        pager: true,
+                        no_pager: false,
+                    },
+                    net: ClientNetworkConfig {
+                        socket: SocketSettings {
+                            tcp_nodelay: true,
+                            tcp_keepalive: Some(30),
+                            send_buffer_size: Some(1024),
+                            recv_buffer_size: Some(2048),
+                            backlog: None,
+                        },
+                        resolve: [
+                            ("buildbox".to_string(), "10.1.2.3".parse().unwrap()),
+                            ("other".to_string(), "::1".parse().unwrap()),
+                        ]
+                        .into_iter()
+                        .collect(),
+                        dns_timeout: Some(2.5),
+                        heartbeat_timeout: Some(30),
+                        address_family: Some(AddressFamily::Ipv6),
+                        proxy: Some("socks5://127.0.0.1:9050".parse().unwrap()),
+                    },
+                    shell: ClientShellConfig {
+                        init_cmd: Some(String::from("cd project")),
                     },
                 },
                 generate: GenerateConfig {
                     logging: LoggingSettings {
                         log_level: Some(LogLevel::Debug),
-                        log_file: Some(PathBuf::from("generate-log-file"))
+                        log_file: Some(PathBuf::from("generate-log-file")),
+                        log_format: None
                     },
                 },
                 manager: ManagerConfig {
                     access: Some(AccessControl::Anyone),
+                    autostart: ManagerAutostartConfig {
+                        connections: vec![
+                            ManagerAutostartConnection {
+                                destination: "ssh://example.com".parse().unwrap(),
+                                launch: true,
+                                options: map!("key5" -> "value5"),
+                                schedule: Some("weekdays 08:00".parse().unwrap()),
+                            },
+                            ManagerAutostartConnection {
+                                destination: "distant://other.example.com".parse().unwrap(),
+                                launch: false,
+                                options: Map::new(),
+                                schedule: None,
+                            },
+                        ],
+                    },
                     logging: LoggingSettings {
                         log_level: Some(LogLevel::Warn),
-                        log_file: Some(PathBuf::from("manager-log-file"))
+                        log_file: Some(PathBuf::from("manager-log-file")),
+                        log_format: None
                     },
                     network: NetworkSettings {
                         unix_socket: Some(PathBuf::from("manager-unix-socket")),
                         windows_pipe: Some(String::from("manager-windows-pipe")),
+                        simulate: None,
                     },
                 },
                 server: ServerConfig {
@@ -268,12 +505,74 @@ current_dir = "server-current-dir"
                         use_ipv6: true,
                         shutdown: Some(Shutdown::After(Duration::from_secs(123))),
                         current_dir: Some(PathBuf::from("server-current-dir")),
+                        write_quota: Some(4096),
+                        heartbeat_interval: Some(10),
+                        motd: Some(String::from("staging environment")),
+                        allow_remote_tunnels: true,
+                        socket: SocketSettings {
+                            tcp_nodelay: true,
+                            tcp_keepalive: Some(45),
+                            send_buffer_size: Some(4096),
+                            recv_buffer_size: Some(8192),
+                            backlog: Some(64),
+                        },
+                    },
+                    limits: OutputLimitSettings {
+                        max_output_memory: Some(1024),
+                        output_spill_dir: Some(PathBuf::from("server-spill-dir")),
+                        max_output_spill_file_size: Some(2048),
+                        max_output_spill_files: Some(3),
+                        max_output_window: Some(4096),
+                    },
+                    scope: ProcessScopeSettings {
+                        kind: Some(ProcessScopeKind::Cgroup),
+                        cgroup: Some(PathBuf::from("server-cgroup")),
+                        memory_max: Some(1048576),
+                        cpu_quota: Some(150),
+                        tasks_max: Some(32),
+                    },
+                    proc: ProcessPolicySettings {
+                        allow: vec![String::from("git"), String::from("cargo build")],
+                        deny: vec![String::from("git push")],
+                        allow_system_kill: true,
+                    },
+                    fs: FsSettings {
+                        default_file_mode: Some(0o640),
+                        default_dir_mode: Some(0o750),
+                    },
+                    scripts: ScriptSettings {
+                        paths: vec![PathBuf::from("server-script.lua")],
+                        extensions: vec![String::from("x-deploy")],
+                        extension_timeout: Some(5),
                     },
                     logging: LoggingSettings {
                         log_level: Some(LogLevel::Error),
                         log_file: Some(PathBuf::from("server-log-file")),
+                        log_format: None,
                     },
                 },
+                profile: [
+                    (
+                        "prod".to_string(),
+                        ProfileConfig {
+                            destination: Some("ssh://me@prod.example.com".parse().unwrap()),
+                            launch: ClientLaunchConfig {
+                                distant: ClientLaunchDistantConfig {
+                                    bin: Some("/opt/distant/bin/distant".to_owned()),
+                                    bind_server: None,
+                                    args: None,
+                                },
+                                verify: false,
+                                options: map!("key6" -> "value6"),
+                            },
+                            environment: map!("RUST_LOG" -> "debug"),
+                            format: Some(Format::Json),
+                        },
+                    ),
+                    ("dev".to_string(), ProfileConfig::default()),
+                ]
+                .into_iter()
+                .collect(),
             }
         );
     }
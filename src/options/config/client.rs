@@ -1,13 +1,19 @@
-use super::common::{self, LoggingSettings, NetworkSettings};
+use super::common::{
+    self, HistorySettings, LoggingSettings, NetworkSettings, PagingSettings, TrustSettings,
+};
 use serde::{Deserialize, Serialize};
 
 mod api;
 mod connect;
 mod launch;
+mod network;
+mod shell;
 
 pub use api::*;
 pub use connect::*;
 pub use launch::*;
+pub use network::*;
+pub use shell::*;
 
 /// Represents configuration settings for the distant client
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
@@ -18,7 +24,25 @@ pub struct ClientConfig {
     #[serde(flatten)]
     pub network: NetworkSettings,
 
+    #[serde(flatten)]
+    pub history: HistorySettings,
+
+    #[serde(flatten)]
+    pub trust: TrustSettings,
+
+    #[serde(flatten)]
+    pub paging: PagingSettings,
+
+    /// Options applied to connections made on behalf of the client, found in the
+    /// `[client.network]` table
+    #[serde(default, rename = "network")]
+    pub net: ClientNetworkConfig,
+
     pub api: ClientApiConfig,
     pub connect: ClientConnectConfig,
     pub launch: ClientLaunchConfig,
+
+    /// Options applied to shells spawned by the client, found in the `[client.shell]` table
+    #[serde(default)]
+    pub shell: ClientShellConfig,
 }
@@ -6,6 +6,12 @@ use serde::{Deserialize, Serialize};
 pub struct ClientLaunchConfig {
     #[serde(flatten)]
     pub distant: ClientLaunchDistantConfig,
+
+    /// If true, verifies that a launched server is working before reporting success
+    #[serde(default)]
+    pub verify: bool,
+
+    #[serde(default)]
     pub options: Map,
 }
 
@@ -19,6 +25,8 @@ impl From<Map> for ClientLaunchConfig {
                     .and_then(|x| x.parse::<BindAddress>().ok()),
                 args: map.remove("distant.args"),
             },
+            // NOTE: Verification happens client-side, so it is never forwarded as an option
+            verify: false,
             options: map,
         }
     }
@@ -0,0 +1,142 @@
+use super::common::SocketSettings;
+use distant_core::net::common::{Map, Proxy};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// Represents the address family to try first when a host resolves to addresses of both
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressFamily {
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    /// Returns true if `ip` belongs to this address family
+    pub fn contains(self, ip: &IpAddr) -> bool {
+        match self {
+            Self::Ipv4 => ip.is_ipv4(),
+            Self::Ipv6 => ip.is_ipv6(),
+        }
+    }
+}
+
+impl fmt::Display for AddressFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ipv4 => write!(f, "ipv4"),
+            Self::Ipv6 => write!(f, "ipv6"),
+        }
+    }
+}
+
+impl FromStr for AddressFamily {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            x if x.eq_ignore_ascii_case("ipv4") => Ok(Self::Ipv4),
+            x if x.eq_ignore_ascii_case("ipv6") => Ok(Self::Ipv6),
+            _ => Err("expected ipv4 or ipv6"),
+        }
+    }
+}
+
+/// Represents the `[client.network]` table, which configures connections made on behalf of the
+/// client such as by the connect and launch commands
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClientNetworkConfig {
+    #[serde(flatten)]
+    pub socket: SocketSettings,
+
+    /// Static mapping of host names to IP addresses, consulted before DNS
+    #[serde(default)]
+    pub resolve: BTreeMap<String, IpAddr>,
+
+    /// Maximum time (in seconds) to wait on DNS when resolving a host
+    pub dns_timeout: Option<f32>,
+
+    /// Seconds to go without hearing from a server, including its heartbeats, before deeming it
+    /// lost and reconnecting
+    pub heartbeat_timeout: Option<u64>,
+
+    /// Address family to try first when a host resolves to both IPv4 and IPv6 addresses
+    pub address_family: Option<AddressFamily>,
+
+    /// HTTP CONNECT or SOCKS5 proxy to dial servers through
+    pub proxy: Option<Proxy>,
+}
+
+impl From<Map> for ClientNetworkConfig {
+    fn from(mut map: Map) -> Self {
+        let resolve = map
+            .remove("resolve")
+            .map(|s| {
+                s.split(',')
+                    .filter_map(|entry| {
+                        let (host, ip) = entry.split_once('=')?;
+                        Some((host.trim().to_string(), ip.trim().parse().ok()?))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let dns_timeout = map
+            .remove("dns_timeout")
+            .and_then(|x| x.parse::<f32>().ok());
+        let heartbeat_timeout = map
+            .remove("heartbeat_timeout")
+            .and_then(|x| x.parse::<u64>().ok());
+        let address_family = map
+            .remove("address_family")
+            .and_then(|x| x.parse::<AddressFamily>().ok());
+        let proxy = map.remove("proxy").and_then(|x| x.parse::<Proxy>().ok());
+
+        Self {
+            socket: SocketSettings::from(map),
+            resolve,
+            dns_timeout,
+            heartbeat_timeout,
+            address_family,
+            proxy,
+        }
+    }
+}
+
+impl From<ClientNetworkConfig> for Map {
+    fn from(config: ClientNetworkConfig) -> Self {
+        let mut this = Self::from(config.socket);
+
+        if !config.resolve.is_empty() {
+            this.insert(
+                "resolve".to_string(),
+                config
+                    .resolve
+                    .iter()
+                    .map(|(host, ip)| format!("{host}={ip}"))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+        }
+
+        if let Some(x) = config.dns_timeout {
+            this.insert("dns_timeout".to_string(), x.to_string());
+        }
+
+        if let Some(x) = config.heartbeat_timeout {
+            this.insert("heartbeat_timeout".to_string(), x.to_string());
+        }
+
+        if let Some(x) = config.address_family {
+            this.insert("address_family".to_string(), x.to_string());
+        }
+
+        if let Some(x) = config.proxy {
+            this.insert("proxy".to_string(), x.to_unredacted_string());
+        }
+
+        this
+    }
+}
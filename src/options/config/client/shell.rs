@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+/// Represents configuration settings for the distant shell, found in the `[client.shell]` table
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientShellConfig {
+    /// Command line sent to a newly spawned shell before any input is forwarded to it
+    pub init_cmd: Option<String>,
+}
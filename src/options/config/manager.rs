@@ -1,4 +1,5 @@
-use super::common::{AccessControl, LoggingSettings, NetworkSettings};
+use super::common::{AccessControl, LoggingSettings, NetworkSettings, Schedule};
+use distant_core::net::common::{Destination, Map};
 use serde::{Deserialize, Serialize};
 
 /// Represents configuration settings for the distant manager
@@ -11,4 +12,34 @@ pub struct ManagerConfig {
     pub network: NetworkSettings,
 
     pub access: Option<AccessControl>,
+
+    #[serde(default)]
+    pub autostart: ManagerAutostartConfig,
+}
+
+/// Represents configuration settings for connections the manager establishes on its own
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManagerAutostartConfig {
+    #[serde(default)]
+    pub connections: Vec<ManagerAutostartConnection>,
+}
+
+/// Represents a connection that the manager establishes at startup or on a schedule
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManagerAutostartConnection {
+    /// Destination to launch or connect to
+    pub destination: Destination,
+
+    /// If true, will launch a server at the destination and connect to it, otherwise will
+    /// connect directly to the destination
+    #[serde(default)]
+    pub launch: bool,
+
+    /// Additional options to provide when launching or connecting
+    #[serde(default)]
+    pub options: Map,
+
+    /// When to establish the connection, otherwise it is established once at startup
+    pub schedule: Option<Schedule>,
 }
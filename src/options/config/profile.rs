@@ -0,0 +1,26 @@
+use super::ClientLaunchConfig;
+use crate::options::Format;
+use distant_core::data::Environment;
+use distant_core::net::common::Destination;
+use serde::{Deserialize, Serialize};
+
+/// Represents a named set of defaults for client commands, selected with `--profile`, that take
+/// priority over the rest of the config but not over the command line
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileConfig {
+    /// Destination to launch or connect to when none is provided
+    pub destination: Option<Destination>,
+
+    /// Settings used when launching a server at the destination
+    #[serde(default)]
+    pub launch: ClientLaunchConfig,
+
+    /// Environment variables provided to remote processes, such as those started by the shell
+    /// and spawn commands
+    #[serde(default)]
+    pub environment: Environment,
+
+    /// Format of output when none is provided
+    pub format: Option<Format>,
+}
@@ -1,4 +1,7 @@
-use super::common::LoggingSettings;
+use super::common::{
+    FsSettings, LoggingSettings, OutputLimitSettings, ProcessPolicySettings, ProcessScopeSettings,
+    ScriptSettings,
+};
 use serde::{Deserialize, Serialize};
 
 mod listen;
@@ -11,4 +14,26 @@ pub struct ServerConfig {
     pub logging: LoggingSettings,
 
     pub listen: ServerListenConfig,
+
+    /// Limits on the output of spawned processes held by the server, found in the
+    /// `[server.limits]` table
+    #[serde(default)]
+    pub limits: OutputLimitSettings,
+
+    /// Scope in which the server spawns processes, found in the `[server.scope]` table
+    #[serde(default)]
+    pub scope: ProcessScopeSettings,
+
+    /// Commands that the server permits spawning, found in the `[server.proc]` table
+    #[serde(default)]
+    pub proc: ProcessPolicySettings,
+
+    /// Permissions of files and directories created by the server, found in the `[server.fs]`
+    /// table
+    #[serde(default)]
+    pub fs: FsSettings,
+
+    /// Scripts run by the server as it handles requests, found in the `[server.scripts]` table
+    #[serde(default)]
+    pub scripts: ScriptSettings,
 }
@@ -1,4 +1,4 @@
-use crate::options::BindAddress;
+use crate::options::{BindAddress, SocketSettings};
 use distant_core::net::common::{Map, PortRange};
 use distant_core::net::server::Shutdown;
 use serde::{Deserialize, Serialize};
@@ -11,6 +11,20 @@ pub struct ServerListenConfig {
     pub use_ipv6: bool,
     pub shutdown: Option<Shutdown>,
     pub current_dir: Option<PathBuf>,
+    pub write_quota: Option<u64>,
+
+    /// Seconds between heartbeats sent to each idle connection
+    pub heartbeat_interval: Option<u64>,
+
+    /// Message of the day reported to each client as it connects
+    pub motd: Option<String>,
+
+    /// Whether clients can listen for tunnels on addresses other than loopback ones
+    #[serde(default)]
+    pub allow_remote_tunnels: bool,
+
+    #[serde(flatten)]
+    pub socket: SocketSettings,
 }
 
 impl From<Map> for ServerListenConfig {
@@ -30,6 +44,18 @@ impl From<Map> for ServerListenConfig {
             current_dir: map
                 .remove("current_dir")
                 .and_then(|x| x.parse::<PathBuf>().ok()),
+            write_quota: map
+                .remove("write_quota")
+                .and_then(|x| x.parse::<u64>().ok()),
+            heartbeat_interval: map
+                .remove("heartbeat_interval")
+                .and_then(|x| x.parse::<u64>().ok()),
+            motd: map.remove("motd"),
+            allow_remote_tunnels: map
+                .remove("allow_remote_tunnels")
+                .and_then(|x| x.parse::<bool>().ok())
+                .unwrap_or_default(),
+            socket: SocketSettings::from(map),
         }
     }
 }
@@ -56,6 +82,25 @@ impl From<ServerListenConfig> for Map {
             this.insert("current_dir".to_string(), x.to_string_lossy().to_string());
         }
 
+        if let Some(x) = config.write_quota {
+            this.insert("write_quota".to_string(), x.to_string());
+        }
+
+        if let Some(x) = config.heartbeat_interval {
+            this.insert("heartbeat_interval".to_string(), x.to_string());
+        }
+
+        if let Some(x) = config.motd {
+            this.insert("motd".to_string(), x);
+        }
+
+        this.insert(
+            "allow_remote_tunnels".to_string(),
+            config.allow_remote_tunnels.to_string(),
+        );
+
+        this.extend(Map::from(config.socket));
+
         this
     }
 }
@@ -0,0 +1,107 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_performing_all_operations(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let dir = temp.child("dir");
+    let file = dir.child("file");
+    let copy = dir.child("copy");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "batch",
+            "ops": [
+                { "type": "dir_create", "path": dir.to_path_buf() },
+                { "type": "file_write_text", "path": file.to_path_buf(), "text": "some text" },
+                { "type": "copy", "src": file.to_path_buf(), "dst": copy.to_path_buf() },
+            ],
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"], json!({ "type": "ok" }), "JSON: {res}");
+
+    file.assert("some text");
+    copy.assert("some text");
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_reporting_failed_step(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let dir = temp.child("dir");
+    let missing = temp.child("missing-dir").child("missing-file");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "batch",
+            "ops": [
+                { "type": "dir_create", "path": dir.to_path_buf() },
+                { "type": "file_write_text", "path": missing.to_path_buf(), "text": "some text" },
+            ],
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "batch_failed", "JSON: {res}");
+    assert_eq!(res["payload"]["step"], 1, "JSON: {res}");
+    assert_eq!(res["payload"]["error"]["kind"], "not_found", "JSON: {res}");
+    assert_eq!(res["payload"]["rolled_back"], false, "JSON: {res}");
+
+    // Without atomic, the operations before the failure are kept
+    dir.assert(predicate::path::is_dir());
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_rolling_back_if_atomic(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let dir = temp.child("dir");
+    let nested = dir.child("nested");
+    let file = nested.child("file");
+    let missing = temp.child("missing-dir").child("missing-file");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "batch",
+            "ops": [
+                { "type": "dir_create", "path": nested.to_path_buf(), "all": true },
+                { "type": "file_write_text", "path": file.to_path_buf(), "text": "some text" },
+                { "type": "file_write_text", "path": missing.to_path_buf(), "text": "some text" },
+            ],
+            "atomic": true,
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "batch_failed", "JSON: {res}");
+    assert_eq!(res["payload"]["step"], 2, "JSON: {res}");
+    assert_eq!(res["payload"]["rolled_back"], true, "JSON: {res}");
+
+    // All directories created along the way should have been removed
+    dir.assert(predicate::path::missing());
+}
@@ -1,5 +1,5 @@
 use crate::cli::fixtures::*;
-use distant_core::data::{Capabilities, Capability};
+use distant_core::data::{Capabilities, Capability, CapabilityKind};
 use rstest::*;
 use serde_json::json;
 use test_log::test;
@@ -31,6 +31,8 @@ async fn should_support_json_capabilities(mut api_process: CtxCommand<ApiProcess
         .collect();
 
     // NOTE: Our local server api should always support all capabilities since it is the reference
-    //       implementation for our api
-    assert_eq!(supported, Capabilities::all());
+    //       implementation for our api, except for those that the server must opt into
+    let mut expected = Capabilities::all();
+    expected.take(CapabilityKind::SystemKill);
+    assert_eq!(supported, expected);
 }
@@ -0,0 +1,38 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_include_correlation_id_in_error_description(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("missing-file");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "correlation_id": "editor-save-42",
+        "payload": {
+            "type": "file_read",
+            "path": file.to_path_buf(),
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "error", "JSON: {res}");
+    assert!(
+        res["payload"]["description"]
+            .as_str()
+            .unwrap()
+            .ends_with("(correlation id editor-save-42)"),
+        "JSON: {res}"
+    );
+}
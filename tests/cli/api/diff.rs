@@ -0,0 +1,97 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_diff_of_two_files(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let src = temp.child("src");
+    src.write_str("one\ntwo\nthree\n").unwrap();
+    let dst = temp.child("dst");
+    dst.write_str("one\n2\nthree\n").unwrap();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "diff",
+            "src": src.to_path_buf(),
+            "dst": { "path": dst.to_path_buf() },
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(
+        res["payload"],
+        json!({
+            "type": "diff",
+            "patch": format!(
+                "--- {}\n+++ {}\n@@ -1,3 +1,3 @@\n one\n-two\n+2\n three\n",
+                src.to_string_lossy(),
+                dst.to_string_lossy(),
+            ),
+        }),
+        "JSON: {res}"
+    );
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_diff_against_text(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let src = temp.child("src");
+    src.write_str("one\ntwo\n").unwrap();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "diff",
+            "src": src.to_path_buf(),
+            "dst": { "text": "one\ntwo\n" },
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(
+        res["payload"],
+        json!({ "type": "diff", "patch": "" }),
+        "JSON: {res}"
+    );
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_output_for_error(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let src = temp.child("missing-file");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "diff",
+            "src": src.to_path_buf(),
+            "dst": { "text": "" },
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "error", "JSON: {res}");
+    assert_eq!(res["payload"]["kind"], "not_found", "JSON: {res}");
+}
@@ -250,6 +250,64 @@ async fn should_support_json_including_root_directory_if_specified(
     );
 }
 
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_streaming_entries_in_chunks_if_chunk_size_specified(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = make_directory();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "dir_read",
+            "path": temp.to_path_buf(),
+            "depth": 1,
+            "absolute": false,
+            "canonicalize": false,
+            "include_root": false,
+            "chunk_size": 3,
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(
+        res["payload"],
+        json!({
+            "type": "dir_entries_chunk",
+            "entries": [
+                {"path": PathBuf::from("dir1"), "file_type": "dir", "depth": 1},
+                {"path": PathBuf::from("dir2"), "file_type": "dir", "depth": 1},
+                {"path": PathBuf::from("file1"), "file_type": "file", "depth": 1},
+            ],
+            "errors": [],
+            "done": false,
+        }),
+        "JSON: {res}"
+    );
+
+    let res = api_process.read_json_from_stdout().await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(
+        res["payload"],
+        json!({
+            "type": "dir_entries_chunk",
+            "entries": [
+                {"path": PathBuf::from("file2"), "file_type": "file", "depth": 1},
+            ],
+            "errors": [],
+            "done": true,
+        }),
+        "JSON: {res}"
+    );
+}
+
 #[rstest]
 #[test(tokio::test)]
 async fn should_support_json_output_for_error(mut api_process: CtxCommand<ApiProcess>) {
@@ -0,0 +1,48 @@
+use crate::cli::fixtures::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_output_for_error_when_no_extension_is_registered(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "extension",
+            "name": "x-distant-test-missing",
+            "payload": { "key": "value" },
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "error", "JSON: {res}");
+    assert_eq!(res["payload"]["kind"], "unsupported", "JSON: {res}");
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_output_for_error_when_name_lacks_prefix(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": { "type": "extension", "name": "missing" },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "error", "JSON: {res}");
+    assert_eq!(res["payload"]["kind"], "invalid_input", "JSON: {res}");
+}
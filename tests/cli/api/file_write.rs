@@ -46,6 +46,44 @@ async fn should_support_json_output(mut api_process: CtxCommand<ApiProcess>) {
     file.assert(FILE_CONTENTS);
 }
 
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_writing_at_offset(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test-file");
+    file.write_str(FILE_CONTENTS).unwrap();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "file_write",
+            "path": file.to_path_buf(),
+            "data": b"TEXT".to_vec(),
+            "offset": 6,
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(
+        res["payload"],
+        json!({
+            "type": "ok"
+        }),
+        "JSON: {res}"
+    );
+
+    // NOTE: We wait a little bit to give the OS time to fully write to file
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    // Because we're talking to a local server, we can verify locally
+    file.assert(FILE_CONTENTS.replacen("text", "TEXT", 1));
+}
+
 #[rstest]
 #[test(tokio::test)]
 async fn should_support_json_output_for_error(mut api_process: CtxCommand<ApiProcess>) {
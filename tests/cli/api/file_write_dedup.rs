@@ -0,0 +1,142 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+const FILE_CONTENTS: &str = "some text\non multiple lines\n";
+
+/// SHA-256 of [`FILE_CONTENTS`]
+const FILE_CONTENTS_HASH: &str = "9e894e80a2db2cf27903ecc0c589410b749c7beaf48a4bfb4ff9468d77762175";
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_report_content_missing_if_remote_does_not_have_it(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test-file");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "file_write_dedup",
+            "path": file.to_path_buf(),
+            "hash": FILE_CONTENTS_HASH,
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(
+        res["payload"],
+        json!({ "type": "content_missing" }),
+        "JSON: {res}"
+    );
+
+    file.assert(predicate::path::missing());
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_reuse_content_previously_written_elsewhere(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test-file");
+    let other = temp.child("other-file");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "file_write_dedup",
+            "path": file.to_path_buf(),
+            "hash": FILE_CONTENTS_HASH,
+            "data": FILE_CONTENTS.as_bytes().to_vec(),
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"], json!({ "type": "ok" }), "JSON: {res}");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "file_write_dedup",
+            "path": other.to_path_buf(),
+            "hash": FILE_CONTENTS_HASH,
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"], json!({ "type": "ok" }), "JSON: {res}");
+
+    file.assert(FILE_CONTENTS);
+    other.assert(FILE_CONTENTS);
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_skip_writing_if_file_already_has_content(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test-file");
+    file.write_str(FILE_CONTENTS).unwrap();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "file_write_dedup",
+            "path": file.to_path_buf(),
+            "hash": FILE_CONTENTS_HASH,
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"], json!({ "type": "ok" }), "JSON: {res}");
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_output_for_error_if_content_does_not_match_hash(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test-file");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "file_write_dedup",
+            "path": file.to_path_buf(),
+            "hash": FILE_CONTENTS_HASH,
+            "data": b"other contents".to_vec(),
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "error", "JSON: {res}");
+    assert_eq!(res["payload"]["kind"], "invalid_data", "JSON: {res}");
+
+    file.assert(predicate::path::missing());
+}
@@ -0,0 +1,37 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_not_apply_retried_requests_more_than_once(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test-file");
+    file.write_str("start").unwrap();
+
+    let key = rand::random::<u64>().to_string();
+    for data in ["-retry", "-retry", "-next"] {
+        let id = rand::random::<u64>().to_string();
+        let req = json!({
+            "id": id,
+            // The last request is a different operation, so it has a different key
+            "idempotency_key": if data == "-next" { format!("{key}-next") } else { key.clone() },
+            "payload": {
+                "type": "file_append_text",
+                "path": file.to_path_buf(),
+                "text": data,
+            },
+        });
+
+        let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+        assert_eq!(res["origin_id"], id, "JSON: {res}");
+        assert_eq!(res["payload"], json!({ "type": "ok" }), "JSON: {res}");
+    }
+
+    file.assert("start-retry-next");
+}
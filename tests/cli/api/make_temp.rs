@@ -0,0 +1,85 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+use serde_json::json;
+use std::path::PathBuf;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_creating_temporary_file(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "make_temp_file",
+            "parent": temp.to_path_buf(),
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "temp_created", "JSON: {res}");
+
+    let path = PathBuf::from(res["payload"]["path"].as_str().unwrap());
+    assert_eq!(path.parent(), Some(temp.path()), "JSON: {res}");
+    assert!(path.is_file(), "Temporary file missing: {path:?}");
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_creating_temporary_directory(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "make_temp_dir",
+            "parent": temp.to_path_buf(),
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "temp_created", "JSON: {res}");
+
+    let path = PathBuf::from(res["payload"]["path"].as_str().unwrap());
+    assert_eq!(path.parent(), Some(temp.path()), "JSON: {res}");
+    assert!(path.is_dir(), "Temporary directory missing: {path:?}");
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_output_for_error(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let parent = temp.child("missing-dir");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "make_temp_file",
+            "parent": parent.to_path_buf(),
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "error", "JSON: {res}");
+    assert_eq!(res["payload"]["kind"], "not_found", "JSON: {res}");
+
+    parent.assert(predicate::path::missing());
+}
@@ -0,0 +1,43 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_counting_bytes_written(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("file");
+
+    let req = json!({
+        "id": rand::random::<u64>().to_string(),
+        "payload": {
+            "type": "file_write_text",
+            "path": file.to_path_buf(),
+            "text": "some text",
+        },
+    });
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+    assert_eq!(res["payload"], json!({ "type": "ok" }), "JSON: {res}");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": { "type": "metrics" },
+    });
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(
+        res["payload"],
+        json!({
+            "type": "metrics",
+            "bytes_written": 9,
+            "write_quota": null,
+        }),
+        "JSON: {res}"
+    );
+}
@@ -1,18 +1,34 @@
+mod batch;
 mod capabilities;
 mod copy;
+mod correlation_id;
+mod diff;
 mod dir_create;
 mod dir_read;
 mod exists;
+mod extension;
 mod file_append;
 mod file_append_text;
 mod file_read;
 mod file_read_text;
 mod file_write;
+mod file_write_dedup;
 mod file_write_text;
+mod idempotency_key;
+mod make_temp;
 mod metadata;
+mod metrics;
+mod path_map;
+mod ping;
+mod plan;
 mod proc_spawn;
 mod remove;
 mod rename;
+mod roots;
 mod search;
+mod session;
+mod snapshot;
 mod system_info;
+mod system_stats;
+mod transaction;
 mod watch;
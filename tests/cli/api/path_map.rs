@@ -0,0 +1,75 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_translate_paths_of_later_requests_and_responses(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let client_dir = temp.child("workspace");
+    let server_dir = temp.child("project");
+    server_dir.child("file").write_str("some text").unwrap();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "path_map",
+            "mappings": [{
+                "client": client_dir.to_path_buf(),
+                "server": server_dir.to_path_buf(),
+            }],
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"], json!({ "type": "ok" }), "JSON: {res}");
+
+    // Paths sent by the client are those of the client
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "file_read_text",
+            "path": client_dir.child("file").to_path_buf(),
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(
+        res["payload"],
+        json!({
+            "type": "text",
+            "data": "some text",
+        }),
+        "JSON: {res}"
+    );
+
+    // Paths sent back by the server are also those of the client
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "make_temp_file",
+            "parent": client_dir.to_path_buf(),
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "temp_created", "JSON: {res}");
+    let path = std::path::PathBuf::from(res["payload"]["path"].as_str().unwrap());
+    assert!(path.starts_with(client_dir.path()), "JSON: {res}");
+    assert_eq!(server_dir.read_dir().unwrap().count(), 2);
+}
@@ -0,0 +1,42 @@
+use crate::cli::fixtures::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_ping(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": { "type": "ping" },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"], json!({ "type": "pong" }), "JSON: {res}");
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_ping_with_synthetic_data(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": { "type": "ping", "data": [1, 2, 3], "reply_len": 2 },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(
+        res["payload"],
+        json!({ "type": "pong", "data": [0, 0] }),
+        "JSON: {res}"
+    );
+}
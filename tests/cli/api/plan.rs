@@ -0,0 +1,69 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_listing_paths_without_performing_operation(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let dir = temp.child("dir");
+    dir.child("file").touch().unwrap();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "plan",
+            "op": { "type": "remove", "path": dir.to_path_buf(), "force": true },
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(
+        res["payload"],
+        json!({
+            "type": "plan",
+            "paths": [dir.to_path_buf(), dir.child("file").to_path_buf()],
+        }),
+        "JSON: {res}"
+    );
+
+    // Nothing should have been removed
+    dir.child("file").assert(predicate::path::exists());
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_failing_like_the_operation_would(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let dir = temp.child("dir");
+    dir.child("file").touch().unwrap();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "plan",
+            "op": { "type": "remove", "path": dir.to_path_buf(), "force": false },
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "error", "JSON: {res}");
+    dir.child("file").assert(predicate::path::exists());
+}
@@ -132,6 +132,49 @@ async fn should_support_json_to_capture_and_print_stdout(mut api_process: CtxCom
     assert_eq!(res["payload"]["success"], true, "JSON: {res}");
 }
 
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_to_stamp_output_if_timestamped(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let cmd = make_cmd(vec![ECHO_ARGS_TO_STDOUT.to_str().unwrap(), "some output"]);
+
+    // Spawn the process
+    let origin_id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": origin_id,
+        "payload": {
+            "type": "proc_spawn",
+            "cmd": cmd,
+            "pty": null,
+            "timestamped": true,
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], origin_id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "proc_spawned", "JSON: {res}");
+
+    // Output comes with its position among all output of the process
+    let res = api_process.read_json_from_stdout().await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], origin_id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "proc_stdout", "JSON: {res}");
+    check_value_as_str(&res["payload"]["data"], "some output");
+    assert_eq!(res["payload"]["stamp"]["seq"], 0, "JSON: {res}");
+    assert!(res["payload"]["stamp"]["timestamp"].is_u64(), "JSON: {res}");
+
+    // Now we wait for the process to complete
+    let res = api_process.read_json_from_stdout().await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], origin_id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "proc_done", "JSON: {res}");
+    assert_eq!(res["payload"]["success"], true, "JSON: {res}");
+}
+
 #[rstest]
 #[test(tokio::test)]
 async fn should_support_json_to_capture_and_print_stderr(mut api_process: CtxCommand<ApiProcess>) {
@@ -270,3 +313,70 @@ async fn should_support_json_output_for_error(mut api_process: CtxCommand<ApiPro
     assert_eq!(res["payload"]["type"], "error", "JSON: {res}");
     assert_eq!(res["payload"]["kind"], "not_found", "JSON: {res}");
 }
+
+#[cfg(unix)]
+#[rstest]
+#[test(tokio::test)]
+async fn should_kill_remote_process_and_exit_once_stdout_is_closed(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    use std::time::{Duration, Instant};
+
+    validate_authentication(&mut api_process).await;
+
+    // Spawn a process that prints forever, starting with its pid
+    let origin_id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": origin_id,
+        "payload": {
+            "type": "proc_spawn",
+            "cmd": "sh -c 'echo $$; while true; do echo y; sleep 0.01; done'",
+            "pty": null,
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+    assert_eq!(res["payload"]["type"], "proc_spawned", "JSON: {res}");
+
+    let res = api_process.read_json_from_stdout().await.unwrap().unwrap();
+    assert_eq!(res["payload"]["type"], "proc_stdout", "JSON: {res}");
+    let pid = res["payload"]["data"]
+        .as_array()
+        .expect("data should be a byte array")
+        .iter()
+        .map(|value| value.as_u64().unwrap() as u8 as char)
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>();
+
+    // Stop reading responses, like `head` would
+    api_process.close_stdout();
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = api_process.try_wait().unwrap() {
+            break status;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "Api never exited after stdout was closed"
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    };
+    assert!(status.success(), "Api exited with {status}");
+
+    // The remote process is gone rather than left writing output that nobody reads
+    let start = Instant::now();
+    while std::process::Command::new("kill")
+        .args(["-0", &pid])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .unwrap()
+        .success()
+    {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "Remote process {pid} is still running"
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
@@ -0,0 +1,142 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_resolve_named_roots_and_reject_paths_outside_of_them(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let root = temp.child("app");
+    root.child("file").write_str("some text").unwrap();
+    temp.child("secret").write_str("hidden").unwrap();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "roots",
+            "roots": [{
+                "name": "src",
+                "path": root.to_path_buf(),
+            }],
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"], json!({ "type": "ok" }), "JSON: {res}");
+
+    // Paths can refer to a root by its name
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "file_read_text",
+            "path": "src:file",
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(
+        res["payload"],
+        json!({
+            "type": "text",
+            "data": "some text",
+        }),
+        "JSON: {res}"
+    );
+
+    // Paths outside of the roots are rejected, however they are written
+    for path in [
+        json!("src:../secret"),
+        json!(temp.child("secret").to_path_buf()),
+    ] {
+        let id = rand::random::<u64>().to_string();
+        let req = json!({
+            "id": id,
+            "payload": {
+                "type": "file_read_text",
+                "path": path,
+            },
+        });
+
+        let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+        assert_eq!(res["origin_id"], id, "JSON: {res}");
+        assert_eq!(res["payload"]["type"], "error", "JSON: {res}");
+        assert_eq!(res["payload"]["kind"], "permission_denied", "JSON: {res}");
+    }
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test(tokio::test)]
+async fn should_reject_symlinks_leading_outside_of_roots_and_requests_they_cannot_confine(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let root = temp.child("app");
+    root.create_dir_all().unwrap();
+    temp.child("secret").write_str("hidden").unwrap();
+    std::os::unix::fs::symlink(temp.child("secret").path(), root.child("link").path()).unwrap();
+
+    let declare = |path: &std::path::Path| {
+        json!({
+            "id": rand::random::<u64>().to_string(),
+            "payload": {
+                "type": "roots",
+                "roots": [{
+                    "name": "src",
+                    "path": path,
+                }],
+            },
+        })
+    };
+
+    let res = api_process
+        .write_and_read_json(declare(root.path()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(res["payload"], json!({ "type": "ok" }), "JSON: {res}");
+
+    // Roots cannot be replaced by whoever is confined to them
+    let res = api_process
+        .write_and_read_json(declare(temp.path()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(res["payload"]["kind"], "permission_denied", "JSON: {res}");
+
+    let payloads = [
+        // Symlinks within a root are followed to wherever they lead
+        json!({ "type": "file_read_text", "path": "src:link" }),
+        json!({ "type": "file_write_text", "path": "src:link", "text": "changed" }),
+        // Processes need somewhere within the roots to start
+        json!({ "type": "proc_spawn", "cmd": "true", "pty": null }),
+        json!({ "type": "extension", "name": "x-echo" }),
+    ];
+    for payload in payloads {
+        let id = rand::random::<u64>().to_string();
+        let req = json!({ "id": id, "payload": payload });
+
+        let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+        assert_eq!(res["origin_id"], id, "JSON: {res}");
+        assert_eq!(res["payload"]["type"], "error", "JSON: {res}");
+        assert_eq!(res["payload"]["kind"], "permission_denied", "JSON: {res}");
+    }
+
+    temp.child("secret").assert("hidden");
+}
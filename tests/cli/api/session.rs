@@ -0,0 +1,137 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use rstest::*;
+use serde_json::{json, Value};
+use std::time::Duration;
+use test_log::test;
+
+const AUTH_RESPONSE: &str = r#"{"type":"auth_initialization_response","methods":["none"]}"#;
+
+fn write_session(file: &assert_fs::fixture::ChildPath, path: &std::path::Path, data: &str) {
+    let request = json!({
+        "request": {
+            "id": "1",
+            "payload": { "type": "file_read_text", "path": path },
+        },
+    });
+    let response = json!({
+        "response": {
+            "id": "2",
+            "origin_id": "1",
+            "payload": { "type": "text", "data": data },
+        },
+    });
+    file.write_str(&format!("{request}\n{response}\n")).unwrap();
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_record_requests_and_responses(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let session = temp.child("session.jsonl");
+    let file = temp.child("test-file");
+    file.write_str("contents").unwrap();
+
+    let child = ctx
+        .new_std_cmd(vec!["api"])
+        .arg("--record")
+        .arg(session.path())
+        .spawn()
+        .expect("Failed to start distant api");
+    let mut api_process = ApiProcess::new(child, Duration::from_secs(3));
+    validate_authentication(&mut api_process).await;
+
+    let req = json!({
+        "id": "1",
+        "payload": { "type": "file_read_text", "path": file.to_path_buf() },
+    });
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+    assert_eq!(
+        res["payload"],
+        json!({ "type": "text", "data": "contents" })
+    );
+
+    let recorded = std::fs::read_to_string(session.path()).unwrap();
+    let entries = recorded
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        vec![
+            json!({ "request": { "id": "1", "payload": { "type": "file_read_text", "path": file.to_path_buf() } } }),
+            json!({ "response": res }),
+        ]
+    );
+}
+
+#[rstest]
+#[test_log::test]
+fn should_replay_session_and_fail_if_responses_differ(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let session = temp.child("session.jsonl");
+    let file = temp.child("test-file");
+    file.write_str("contents").unwrap();
+    write_session(&session, file.path(), "contents");
+
+    ctx.new_assert_cmd(["api"])
+        .arg("--replay")
+        .arg(session.path())
+        .write_stdin(format!("{AUTH_RESPONSE}\n"))
+        .assert()
+        .success()
+        .stderr("");
+
+    file.write_str("changed").unwrap();
+    ctx.new_assert_cmd(["api"])
+        .arg("--replay")
+        .arg(session.path())
+        .write_stdin(format!("{AUTH_RESPONSE}\n"))
+        .assert()
+        .code(1)
+        .stderr(predicates::str::contains("Request 1 response 1 differs"));
+
+    // Ignoring the data that changed lets the session pass again
+    ctx.new_assert_cmd(["api"])
+        .args([
+            "--replay",
+            &session.path().to_string_lossy(),
+            "--ignore",
+            "/data",
+        ])
+        .write_stdin(format!("{AUTH_RESPONSE}\n"))
+        .assert()
+        .success()
+        .stderr("");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_mock_responses_using_recorded_session(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let session = temp.child("session.jsonl");
+    let path = temp.child("missing-file").to_path_buf();
+    write_session(&session, &path, "contents");
+
+    let req = json!({
+        "id": "5",
+        "payload": { "type": "file_read_text", "path": path },
+    });
+    let output = ctx
+        .new_assert_cmd(["api"])
+        .arg("--mock")
+        .arg(session.path())
+        .write_stdin(format!("{req}\n"))
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let res: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(res["origin_id"], "5", "JSON: {res}");
+    assert_eq!(
+        res["payload"],
+        json!({ "type": "text", "data": "contents" })
+    );
+}
@@ -0,0 +1,55 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_restoring_a_snapshot(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("file");
+    file.write_str("old text").unwrap();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": { "type": "snapshot_create", "path": file.to_path_buf() },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "snapshot_created", "JSON: {res}");
+    let snapshot_id = res["payload"]["id"].clone();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({ "id": id, "payload": { "type": "snapshot_list" } });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "snapshots", "JSON: {res}");
+    assert_eq!(
+        res["payload"]["entries"][0]["id"], snapshot_id,
+        "JSON: {res}"
+    );
+
+    // Change the file in place, which the copy kept by the snapshot does not share
+    file.write_str("new text").unwrap();
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": { "type": "snapshot_restore", "id": snapshot_id },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"], json!({ "type": "ok" }), "JSON: {res}");
+
+    file.assert("old text");
+}
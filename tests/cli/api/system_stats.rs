@@ -0,0 +1,59 @@
+use crate::cli::fixtures::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+#[cfg_attr(not(target_os = "linux"), ignore)]
+async fn should_support_json_system_stats(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": { "type": "system_stats" },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "system_stats", "JSON: {res}");
+
+    let stats = &res["payload"]["stats"];
+    assert!(stats["cpu_count"].as_u64().unwrap() > 0, "JSON: {res}");
+    assert!(stats["memory_total"].as_u64().unwrap() > 0, "JSON: {res}");
+    assert_eq!(
+        stats["load_average"].as_array().map(Vec::len),
+        Some(3),
+        "JSON: {res}"
+    );
+    assert!(
+        stats["processes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|p| p["name"] == "distant"),
+        "JSON: {res}"
+    );
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_output_for_error_when_canceling_unknown_system_stats(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": { "type": "cancel_system_stats", "id": 12345 },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "error", "JSON: {res}");
+    assert_eq!(res["payload"]["kind"], "not_found", "JSON: {res}");
+}
@@ -0,0 +1,90 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+use serde_json::json;
+use test_log::test;
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_applying_all_operations(mut api_process: CtxCommand<ApiProcess>) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let config = temp.child("config");
+    config.write_str("old config").unwrap();
+    let release = temp.child("release");
+    let current = temp.child("current");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "transaction",
+            "ops": [
+                { "type": "dir_create", "path": release.to_path_buf() },
+                { "type": "file_write_text", "path": release.child("app").to_path_buf(), "text": "app" },
+                { "type": "file_write_text", "path": config.to_path_buf(), "text": "new config" },
+                { "type": "symlink", "path": current.to_path_buf(), "target": release.to_path_buf() },
+            ],
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"], json!({ "type": "ok" }), "JSON: {res}");
+
+    config.assert("new config");
+    current.child("app").assert("app");
+
+    // Staged files and backups should all have been cleaned up
+    assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 3);
+}
+
+#[rstest]
+#[test(tokio::test)]
+async fn should_support_json_restoring_everything_if_an_operation_fails(
+    mut api_process: CtxCommand<ApiProcess>,
+) {
+    validate_authentication(&mut api_process).await;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let config = temp.child("config");
+    config.write_str("old config").unwrap();
+    let log = temp.child("log");
+    log.write_str("line 1\n").unwrap();
+    let old = temp.child("old");
+    old.create_dir_all().unwrap();
+    old.child("file").write_str("old file").unwrap();
+    let missing = temp.child("missing-dir").child("missing-file");
+
+    let id = rand::random::<u64>().to_string();
+    let req = json!({
+        "id": id,
+        "payload": {
+            "type": "transaction",
+            "ops": [
+                { "type": "file_write_text", "path": config.to_path_buf(), "text": "new config" },
+                { "type": "file_append_text", "path": log.to_path_buf(), "text": "line 2\n" },
+                { "type": "remove", "path": old.to_path_buf(), "force": true },
+                { "type": "file_write_text", "path": missing.to_path_buf(), "text": "some text" },
+            ],
+        },
+    });
+
+    let res = api_process.write_and_read_json(req).await.unwrap().unwrap();
+
+    assert_eq!(res["origin_id"], id, "JSON: {res}");
+    assert_eq!(res["payload"]["type"], "batch_failed", "JSON: {res}");
+    assert_eq!(res["payload"]["step"], 3, "JSON: {res}");
+    assert_eq!(res["payload"]["error"]["kind"], "not_found", "JSON: {res}");
+    assert_eq!(res["payload"]["rolled_back"], true, "JSON: {res}");
+
+    // Overwritten, appended, and removed files should all be back as they were
+    config.assert("old config");
+    log.assert("line 1\n");
+    old.child("file").assert("old file");
+    missing.assert(predicate::path::missing());
+    assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 3);
+}
@@ -0,0 +1,21 @@
+use crate::cli::fixtures::*;
+use predicates::prelude::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_report_throughput_in_each_direction(ctx: DistantManagerCtx) {
+    ctx.cmd("bench")
+        .args(["--count", "2", "--size", "1024"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(concat!(
+                r"^Upload:   2\.0 KiB in \d+\.\d{3}s \(.+/s\)\n",
+                r"Download: 2\.0 KiB in \d+\.\d{3}s \(.+/s\)\n",
+                r"Frame overhead: \d+ B per request, \d+ B per response\n$",
+            ))
+            .unwrap(),
+        )
+        .stderr("");
+}
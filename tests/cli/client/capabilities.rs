@@ -1,57 +1,136 @@
 use crate::cli::fixtures::*;
 use indoc::indoc;
+use predicates::prelude::*;
 use rstest::*;
 
 const EXPECTED_TABLE: &str = indoc! {"
-+------------------+------------------------------------------------------------------+
-| kind             | description                                                      |
-+------------------+------------------------------------------------------------------+
-| cancel_search    | Supports canceling an active search against the filesystem       |
-+------------------+------------------------------------------------------------------+
-| capabilities     | Supports retrieving capabilities                                 |
-+------------------+------------------------------------------------------------------+
-| copy             | Supports copying files, directories, and symlinks                |
-+------------------+------------------------------------------------------------------+
-| dir_create       | Supports creating directory                                      |
-+------------------+------------------------------------------------------------------+
-| dir_read         | Supports reading directory                                       |
-+------------------+------------------------------------------------------------------+
-| exists           | Supports checking if a path exists                               |
-+------------------+------------------------------------------------------------------+
-| file_append      | Supports appending to binary file                                |
-+------------------+------------------------------------------------------------------+
-| file_append_text | Supports appending to text file                                  |
-+------------------+------------------------------------------------------------------+
-| file_read        | Supports reading binary file                                     |
-+------------------+------------------------------------------------------------------+
-| file_read_text   | Supports reading text file                                       |
-+------------------+------------------------------------------------------------------+
-| file_write       | Supports writing binary file                                     |
-+------------------+------------------------------------------------------------------+
-| file_write_text  | Supports writing text file                                       |
-+------------------+------------------------------------------------------------------+
-| metadata         | Supports retrieving metadata about a file, directory, or symlink |
-+------------------+------------------------------------------------------------------+
-| proc_kill        | Supports killing a spawned process                               |
-+------------------+------------------------------------------------------------------+
-| proc_resize_pty  | Supports resizing the pty of a spawned process                   |
-+------------------+------------------------------------------------------------------+
-| proc_spawn       | Supports spawning a process                                      |
-+------------------+------------------------------------------------------------------+
-| proc_stdin       | Supports sending stdin to a spawned process                      |
-+------------------+------------------------------------------------------------------+
-| remove           | Supports removing files, directories, and symlinks               |
-+------------------+------------------------------------------------------------------+
-| rename           | Supports renaming files, directories, and symlinks               |
-+------------------+------------------------------------------------------------------+
-| search           | Supports searching filesystem using queries                      |
-+------------------+------------------------------------------------------------------+
-| system_info      | Supports retrieving system information                           |
-+------------------+------------------------------------------------------------------+
-| unwatch          | Supports unwatching filesystem for changes                       |
-+------------------+------------------------------------------------------------------+
-| watch            | Supports watching filesystem for changes                         |
-+------------------+------------------------------------------------------------------+
++---------------------+------------------------------------------------------------------+
+| kind                | description                                                      |
++---------------------+------------------------------------------------------------------+
+| batch               | Supports performing a batch of filesystem operations             |
++---------------------+------------------------------------------------------------------+
+| cancel_search       | Supports canceling an active search against the filesystem       |
++---------------------+------------------------------------------------------------------+
+| cancel_system_stats | Supports canceling reporting of resource usage                   |
++---------------------+------------------------------------------------------------------+
+| capabilities        | Supports retrieving capabilities                                 |
++---------------------+------------------------------------------------------------------+
+| client_disconnect   | Supports disconnecting clients from the server                   |
++---------------------+------------------------------------------------------------------+
+| client_list         | Supports listing clients connected to the server                 |
++---------------------+------------------------------------------------------------------+
+| copy                | Supports copying files, directories, and symlinks                |
++---------------------+------------------------------------------------------------------+
+| diff                | Supports computing a unified diff of files                       |
++---------------------+------------------------------------------------------------------+
+| dir_create          | Supports creating directory                                      |
++---------------------+------------------------------------------------------------------+
+| dir_manifest        | Supports listing hashes of files within directory                |
++---------------------+------------------------------------------------------------------+
+| dir_read            | Supports reading directory                                       |
++---------------------+------------------------------------------------------------------+
+| exists              | Supports checking if a path exists                               |
++---------------------+------------------------------------------------------------------+
+| extension           | Supports custom requests handled by extensions                   |
++---------------------+------------------------------------------------------------------+
+| fifo_create         | Supports creating named pipes                                    |
++---------------------+------------------------------------------------------------------+
+| file_append         | Supports appending to binary file                                |
++---------------------+------------------------------------------------------------------+
+| file_append_text    | Supports appending to text file                                  |
++---------------------+------------------------------------------------------------------+
+| file_read           | Supports reading binary file                                     |
++---------------------+------------------------------------------------------------------+
+| file_read_text      | Supports reading text file                                       |
++---------------------+------------------------------------------------------------------+
+| file_write          | Supports writing binary file                                     |
++---------------------+------------------------------------------------------------------+
+| file_write_dedup    | Supports writing binary file deduplicated by content hash        |
++---------------------+------------------------------------------------------------------+
+| file_write_text     | Supports writing text file                                       |
++---------------------+------------------------------------------------------------------+
+| journal_changes     | Supports retrieving changes from a journal                       |
++---------------------+------------------------------------------------------------------+
+| journal_start       | Supports journaling changes to a path                            |
++---------------------+------------------------------------------------------------------+
+| journal_stop        | Supports stopping the journaling of a path                       |
++---------------------+------------------------------------------------------------------+
+| make_temp_dir       | Supports creating temporary directories                          |
++---------------------+------------------------------------------------------------------+
+| make_temp_file      | Supports creating temporary files                                |
++---------------------+------------------------------------------------------------------+
+| metadata            | Supports retrieving metadata about a file, directory, or symlink |
++---------------------+------------------------------------------------------------------+
+| metrics             | Supports retrieving metrics about data written by the server     |
++---------------------+------------------------------------------------------------------+
+| path_map            | Supports translating paths between those of client and server    |
++---------------------+------------------------------------------------------------------+
+| ping                | Supports checking that the server is alive                       |
++---------------------+------------------------------------------------------------------+
+| plan                | Supports planning filesystem operations without performing them  |
++---------------------+------------------------------------------------------------------+
+| proc_attach         | Supports reattaching to the output of a spawned process          |
++---------------------+------------------------------------------------------------------+
+| proc_kill           | Supports killing a spawned process                               |
++---------------------+------------------------------------------------------------------+
+| proc_list           | Supports listing processes                                       |
++---------------------+------------------------------------------------------------------+
+| proc_output_ack     | Supports acknowledging output of a spawned process               |
++---------------------+------------------------------------------------------------------+
+| proc_resize_pty     | Supports resizing the pty of a spawned process                   |
++---------------------+------------------------------------------------------------------+
+| proc_signal         | Supports sending a signal to a spawned process                   |
++---------------------+------------------------------------------------------------------+
+| proc_spawn          | Supports spawning a process                                      |
++---------------------+------------------------------------------------------------------+
+| proc_stats          | Supports retrieving resource usage of a spawned process          |
++---------------------+------------------------------------------------------------------+
+| proc_stdin          | Supports sending stdin to a spawned process                      |
++---------------------+------------------------------------------------------------------+
+| proc_stdin_close    | Supports closing stdin of a spawned process                      |
++---------------------+------------------------------------------------------------------+
+| remove              | Supports removing files, directories, and symlinks               |
++---------------------+------------------------------------------------------------------+
+| rename              | Supports renaming files, directories, and symlinks               |
++---------------------+------------------------------------------------------------------+
+| roots               | Supports confining paths to roots addressed as name:path         |
++---------------------+------------------------------------------------------------------+
+| search              | Supports searching filesystem using queries                      |
++---------------------+------------------------------------------------------------------+
+| search_next         | Supports requesting more results of an on-demand search          |
++---------------------+------------------------------------------------------------------+
+| snapshot_create     | Supports taking snapshots of files and directories               |
++---------------------+------------------------------------------------------------------+
+| snapshot_list       | Supports listing snapshots                                       |
++---------------------+------------------------------------------------------------------+
+| snapshot_remove     | Supports removing snapshots                                      |
++---------------------+------------------------------------------------------------------+
+| snapshot_restore    | Supports restoring snapshots                                     |
++---------------------+------------------------------------------------------------------+
+| system_env          | Supports retrieving the environment of the server                |
++---------------------+------------------------------------------------------------------+
+| system_info         | Supports retrieving system information                           |
++---------------------+------------------------------------------------------------------+
+| system_stats        | Supports retrieving resource usage of the system                 |
++---------------------+------------------------------------------------------------------+
+| transaction         | Supports applying filesystem operations all or nothing           |
++---------------------+------------------------------------------------------------------+
+| tunnel_close        | Supports closing a tunnel                                        |
++---------------------+------------------------------------------------------------------+
+| tunnel_listen       | Supports listening for tunnels on TCP ports                      |
++---------------------+------------------------------------------------------------------+
+| tunnel_listen_unix  | Supports listening for tunnels on unix sockets                   |
++---------------------+------------------------------------------------------------------+
+| tunnel_open         | Supports opening tunnels to TCP ports                            |
++---------------------+------------------------------------------------------------------+
+| tunnel_unlisten     | Supports no longer listening for tunnels                         |
++---------------------+------------------------------------------------------------------+
+| tunnel_write        | Supports sending data through a tunnel                           |
++---------------------+------------------------------------------------------------------+
+| unwatch             | Supports unwatching filesystem for changes                       |
++---------------------+------------------------------------------------------------------+
+| watch               | Supports watching filesystem for changes                         |
++---------------------+------------------------------------------------------------------+
 "};
 
 #[rstest]
@@ -63,3 +142,21 @@ fn should_output_capabilities(ctx: DistantManagerCtx) {
         .stdout(EXPECTED_TABLE)
         .stderr("");
 }
+
+#[rstest]
+#[test_log::test]
+fn should_output_capabilities_as_labeled_lines_if_accessible(ctx: DistantManagerCtx) {
+    ctx.cmd("capabilities")
+        .arg("--a11y")
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with(indoc! {"
+            kind: batch
+            description: Supports performing a batch of filesystem operations
+
+            kind: cancel_search
+            description: Supports canceling an active search against the filesystem
+        "}))
+        .stdout(predicates::str::contains("+---").not())
+        .stderr("");
+}
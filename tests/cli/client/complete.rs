@@ -0,0 +1,60 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_print_connections_with_their_destinations(ctx: DistantManagerCtx) {
+    let output = ctx
+        .new_assert_cmd(["__complete", "connections"])
+        .assert()
+        .success()
+        .stderr("")
+        .get_output()
+        .stdout
+        .clone();
+
+    let stdout = String::from_utf8(output).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1, "{stdout}");
+
+    let (id, destination) = lines[0].split_once('\t').unwrap();
+    assert!(id.parse::<u32>().is_ok(), "{id}");
+    assert!(!destination.is_empty());
+}
+
+#[rstest]
+#[test_log::test]
+fn should_print_remote_paths_completing_the_current_word(ctx: DistantManagerCtx) {
+    let root = assert_fs::TempDir::new().unwrap();
+    root.child("file1.txt").touch().unwrap();
+    root.child("file2.txt").touch().unwrap();
+    root.child("files").create_dir_all().unwrap();
+    root.child("other.txt").touch().unwrap();
+
+    let current = format!("{}/fi", root.path().to_string_lossy());
+    let output = ctx
+        .new_assert_cmd(["__complete", "paths"])
+        .arg("--")
+        .arg(&current)
+        .assert()
+        .success()
+        .stderr("")
+        .get_output()
+        .stdout
+        .clone();
+
+    let stdout = String::from_utf8(output).unwrap();
+    let mut lines = stdout.lines().collect::<Vec<_>>();
+    lines.sort_unstable();
+
+    let root = root.path().to_string_lossy();
+    assert_eq!(
+        lines,
+        vec![
+            format!("{root}/file1.txt"),
+            format!("{root}/file2.txt"),
+            format!("{root}/files/"),
+        ]
+    );
+}
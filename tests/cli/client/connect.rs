@@ -0,0 +1,80 @@
+use crate::cli::fixtures::*;
+use predicates::prelude::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_fail_if_name_is_a_number(ctx: DistantManagerCtx) {
+    ctx.cmd("connect")
+        .args(["--name", "123", "distant://localhost"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value '123' for '--name <NAME>': Name must not be empty or a number",
+        ));
+}
+
+#[rstest]
+#[test_log::test]
+fn should_fail_to_use_a_connection_name_that_was_never_given(ctx: DistantManagerCtx) {
+    ctx.cmd("ping")
+        .args(["--connection", "missing", "--count", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Failed to find connection named missing",
+        ));
+}
+
+#[rstest]
+#[test_log::test]
+fn should_show_message_of_the_day_of_server_once_connected(ctx: DistantManagerCtx) {
+    use distant_core::{net::common::Host, DistantSingleKeyCredentials};
+    use std::io::Read;
+    use std::process::{Command as StdCommand, Stdio};
+
+    // Start a second server with a message of the day
+    let mut server = StdCommand::new(assert_cmd::cargo::cargo_bin(env!("CARGO_PKG_NAME")))
+        .args([
+            "server",
+            "listen",
+            "--host",
+            "127.0.0.1",
+            "--shutdown",
+            "lonely=10",
+        ])
+        .args(["--motd", "Maintenance tonight at 10pm"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let mut stdout = server.stdout.take().unwrap();
+    let mut lines = String::new();
+    let mut buf = [0u8; 1024];
+    let mut credentials = loop {
+        let n = stdout.read(&mut buf).unwrap();
+        assert!(n > 0, "Server exited before printing credentials");
+        lines.push_str(&String::from_utf8_lossy(&buf[..n]));
+        if let Some(credentials) =
+            DistantSingleKeyCredentials::find(&lines, /* strict */ false)
+        {
+            break credentials;
+        }
+    };
+    credentials.host = Host::Name(String::from("127.0.0.1"));
+
+    // The message is shown on stderr, leaving only the connection id on stdout
+    let result = ctx
+        .cmd("connect")
+        .arg(credentials.to_string())
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^\d+\n$").unwrap())
+        .stderr(predicate::str::contains("Maintenance tonight at 10pm"));
+
+    let _ = server.kill();
+    let _ = server.wait();
+    drop(result);
+}
@@ -0,0 +1,292 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+use serde_json::{json, Value};
+
+const FILE_CONTENTS: &str = r#"
+some text
+on multiple lines
+that is a file's contents
+"#;
+
+/// Prefixes `path` with a colon to mark it as remote
+fn remote(path: &std::path::Path) -> String {
+    format!(":{}", path.to_str().unwrap())
+}
+
+#[rstest]
+#[test_log::test]
+fn should_support_uploading_file(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("file");
+    src.write_str(FILE_CONTENTS).unwrap();
+
+    let dst = temp.child("file2");
+
+    // distant cp {src} :{dst}
+    ctx.new_assert_cmd(["cp"])
+        .args([src.to_str().unwrap(), &remote(dst.path())])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    dst.assert(predicate::path::eq_file(src.path()));
+}
+
+#[rstest]
+#[test_log::test]
+fn should_support_downloading_file(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("file");
+    src.write_str(FILE_CONTENTS).unwrap();
+
+    let dst = temp.child("file2");
+
+    // distant cp :{src} {dst}
+    ctx.new_assert_cmd(["cp"])
+        .args([&remote(src.path()), dst.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    dst.assert(predicate::path::eq_file(src.path()));
+}
+
+#[rstest]
+#[test_log::test]
+fn should_support_uploading_directory_into_existing_directory(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("dir");
+    src.child("sub").create_dir_all().unwrap();
+    src.child("sub")
+        .child("file")
+        .write_str(FILE_CONTENTS)
+        .unwrap();
+    src.child("empty").create_dir_all().unwrap();
+
+    let dst = temp.child("dst");
+    dst.create_dir_all().unwrap();
+
+    // distant cp {src} :{dst}
+    ctx.new_assert_cmd(["cp"])
+        .args([src.to_str().unwrap(), &remote(dst.path())])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    dst.child("dir")
+        .child("sub")
+        .child("file")
+        .assert(FILE_CONTENTS);
+    dst.child("dir")
+        .child("empty")
+        .assert(predicate::path::is_dir());
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test_log::test]
+fn should_skip_sockets_within_directory_with_a_warning(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("dir");
+    src.create_dir_all().unwrap();
+    src.child("file").write_str(FILE_CONTENTS).unwrap();
+    let socket = src.child("socket");
+    let _listener = std::os::unix::net::UnixListener::bind(socket.path()).unwrap();
+
+    let dst = temp.child("dst");
+
+    // distant cp :{src} {dst}
+    ctx.new_assert_cmd(["cp"])
+        .args([&remote(src.path()), dst.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(format!(
+            "Skipping {} as it is a socket\n",
+            socket.path().display()
+        ));
+
+    dst.child("file").assert(FILE_CONTENTS);
+    dst.child("socket").assert(predicate::path::missing());
+}
+
+#[rstest]
+#[test_log::test]
+fn should_support_downloading_directory(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("dir");
+    src.child("sub").create_dir_all().unwrap();
+    src.child("sub")
+        .child("file")
+        .write_str(FILE_CONTENTS)
+        .unwrap();
+    src.child("file").write_str("").unwrap();
+
+    let dst = temp.child("dir2");
+
+    // distant cp :{src} {dst}
+    ctx.new_assert_cmd(["cp"])
+        .args([&remote(src.path()), dst.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    dst.child("sub").child("file").assert(FILE_CONTENTS);
+    dst.child("file").assert("");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_support_downloading_file_larger_than_a_chunk(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("file");
+    let contents: Vec<u8> = (0..200 * 1024).map(|i| (i % 251) as u8).collect();
+    src.write_binary(&contents).unwrap();
+
+    let dst = temp.child("file2");
+
+    // distant cp :{src} {dst}
+    ctx.new_assert_cmd(["cp"])
+        .args([&remote(src.path()), dst.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    dst.assert(predicate::path::eq_file(src.path()));
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test_log::test]
+fn should_skip_symlinks_to_directories_already_being_uploaded(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("dir");
+    src.child("sub").create_dir_all().unwrap();
+    src.child("sub")
+        .child("file")
+        .write_str(FILE_CONTENTS)
+        .unwrap();
+    let link = src.child("sub").child("loop");
+    link.symlink_to_dir(src.path()).unwrap();
+
+    let dst = temp.child("dst");
+
+    // distant cp {src} :{dst}
+    ctx.new_assert_cmd(["cp"])
+        .args([src.to_str().unwrap(), &remote(dst.path())])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(format!(
+            "Skipping {} as it links to a directory already being copied\n",
+            link.path().display()
+        ));
+
+    dst.child("sub").child("file").assert(FILE_CONTENTS);
+    dst.child("sub")
+        .child("loop")
+        .assert(predicate::path::missing());
+}
+
+#[rstest]
+#[test_log::test]
+fn should_report_progress_as_json_events(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("file");
+    src.write_str(FILE_CONTENTS).unwrap();
+
+    let dst = temp.child("file2");
+    let len = FILE_CONTENTS.len();
+
+    // distant cp --format json {src} :{dst}
+    let output = ctx
+        .new_assert_cmd(["cp"])
+        .args(["--format", "json"])
+        .args([src.to_str().unwrap(), &remote(dst.path())])
+        .write_stdin(
+            json!({"type": "auth_initialization_response", "methods": ["none"]}).to_string() + "\n",
+        )
+        .assert()
+        .success()
+        .stderr("")
+        .get_output()
+        .stdout
+        .clone();
+
+    // Skip past authentication with the manager to get to the progress events
+    let events: Vec<Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .skip_while(|event: &Value| event["type"] != "progress")
+        .collect();
+    assert_eq!(
+        events,
+        [
+            json!({
+                "type": "progress",
+                "path": dst.path(),
+                "bytes": len,
+                "total_bytes": len,
+            }),
+            json!({"type": "done", "files": 1, "bytes": len}),
+        ]
+    );
+
+    dst.assert(predicate::path::eq_file(src.path()));
+}
+
+#[rstest]
+#[test_log::test]
+fn yield_an_error_when_neither_path_is_remote(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("file");
+    src.write_str(FILE_CONTENTS).unwrap();
+
+    let dst = temp.child("file2");
+
+    // distant cp {src} {dst}
+    ctx.new_assert_cmd(["cp"])
+        .args([src.to_str().unwrap(), dst.to_str().unwrap()])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicates::str::contains("Neither path is remote"));
+
+    dst.assert(predicate::path::missing());
+}
+
+#[rstest]
+#[test_log::test]
+fn yield_an_error_when_fails(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("missing");
+    let dst = temp.child("file2");
+
+    // distant cp :{src} {dst}
+    ctx.new_assert_cmd(["cp"])
+        .args([&remote(src.path()), dst.to_str().unwrap()])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicates::str::is_empty().not());
+
+    dst.assert(predicate::path::missing());
+}
@@ -0,0 +1,93 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+
+/// Editor that replaces the contents of the file it is given with `text`
+fn editor_writing(text: &str) -> String {
+    format!("sh -c 'printf {text} > \"$0\"'")
+}
+
+#[rstest]
+#[test_log::test]
+fn should_write_back_changes_made_in_editor(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("file.txt");
+    file.write_str("old text").unwrap();
+
+    // distant edit --editor {editor} {path}
+    ctx.new_assert_cmd(["edit"])
+        .args(["--editor", &editor_writing("new")])
+        .arg(file.to_str().unwrap())
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    file.assert("new");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_create_file_if_missing(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("missing.txt");
+
+    // distant edit --editor {editor} {path}
+    ctx.new_assert_cmd(["edit"])
+        .args(["--editor", &editor_writing("new")])
+        .arg(file.to_str().unwrap())
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    file.assert("new");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_support_writing_back_each_save_while_watching(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("file.txt");
+    file.write_str("old text").unwrap();
+    let seen = temp.child("seen");
+
+    // Saves twice, recording what the remote file looked like in between
+    let editor = format!(
+        "sh -c 'printf first > \"$0\"; sleep 1; cat {} > {}; printf second > \"$0\"'",
+        file.to_str().unwrap(),
+        seen.to_str().unwrap(),
+    );
+
+    // distant edit --watch --editor {editor} {path}
+    ctx.new_assert_cmd(["edit"])
+        .args(["--watch", "--editor", &editor])
+        .arg(file.to_str().unwrap())
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    seen.assert("first");
+    file.assert("second");
+}
+
+#[rstest]
+#[test_log::test]
+fn yield_an_error_and_leave_file_unchanged_when_editor_fails(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("file.txt");
+    file.write_str("old text").unwrap();
+
+    // distant edit --editor {editor} {path}
+    ctx.new_assert_cmd(["edit"])
+        .args(["--editor", "sh -c 'printf new > \"$0\"; exit 1'"])
+        .arg(file.to_str().unwrap())
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("Editor exited"));
+
+    file.assert("old text");
+}
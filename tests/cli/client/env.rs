@@ -0,0 +1,42 @@
+use crate::cli::fixtures::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_print_shared_variables_of_the_server(ctx: DistantManagerCtx) {
+    let output = ctx
+        .cmd("env")
+        .assert()
+        .success()
+        .stderr("")
+        .get_output()
+        .stdout
+        .clone();
+
+    let stdout = String::from_utf8(output).unwrap();
+    assert!(
+        stdout.lines().any(|line| line.starts_with("PATH=")),
+        "{stdout}"
+    );
+}
+
+#[rstest]
+#[test_log::test]
+fn should_print_values_of_named_variables_and_fail_if_any_are_missing(ctx: DistantManagerCtx) {
+    // distant env PATH
+    ctx.new_assert_cmd(["env"])
+        .arg("PATH")
+        .assert()
+        .success()
+        .stdout(format!("{}\n", std::env::var("PATH").unwrap()))
+        .stderr("");
+
+    // distant env PATH SECRET_TOKEN
+    ctx.new_assert_cmd(["env"])
+        .args(["PATH", "SECRET_TOKEN"])
+        .assert()
+        .code(1)
+        .stderr(predicates::str::contains(
+            "No variables named SECRET_TOKEN are shared by the server",
+        ));
+}
@@ -0,0 +1,59 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_print_paths_matching_name_and_type(ctx: DistantManagerCtx) {
+    let root = assert_fs::TempDir::new().unwrap();
+    root.child("file1.rs").write_str("fn main() {}").unwrap();
+    root.child("sub/file2.rs").write_str("").unwrap();
+    root.child("sub/file3.txt").write_str("text").unwrap();
+    root.child("dir.rs").create_dir_all().unwrap();
+
+    let output = ctx
+        .new_assert_cmd(["find"])
+        .args(["--name", "*.rs", "--type", "f"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stderr("")
+        .get_output()
+        .stdout
+        .clone();
+
+    // Results arrive in no particular order
+    let stdout = String::from_utf8(output).unwrap();
+    let mut lines = stdout.lines().collect::<Vec<_>>();
+    lines.sort_unstable();
+
+    assert_eq!(
+        lines,
+        vec![
+            root.child("file1.rs").path().to_string_lossy(),
+            root.child("sub/file2.rs").path().to_string_lossy(),
+        ]
+    );
+}
+
+#[rstest]
+#[test_log::test]
+fn should_only_print_paths_within_size_bounds(ctx: DistantManagerCtx) {
+    let root = assert_fs::TempDir::new().unwrap();
+    root.child("empty.txt").touch().unwrap();
+    root.child("small.txt").write_str("some text").unwrap();
+    root.child("large.txt")
+        .write_str(&"a".repeat(2048))
+        .unwrap();
+
+    ctx.new_assert_cmd(["find"])
+        .args(["--type", "f", "--size", "+0", "--size", "-1k"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\n",
+            root.child("small.txt").path().to_string_lossy()
+        ))
+        .stderr("");
+}
@@ -0,0 +1,144 @@
+use crate::cli::{fixtures::*, utils::ThreadedReader};
+use rstest::*;
+use std::{
+    io::{Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+#[rstest]
+#[test_log::test]
+fn should_forward_local_port_to_host_reachable_from_remote_machine(ctx: DistantManagerCtx) {
+    // Server only reachable through the forwarded port, shouting back what it reads
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+        stream.write_all(&data.to_ascii_uppercase()).unwrap();
+    });
+
+    // distant forward -L 127.0.0.1:0:127.0.0.1:{port}
+    let mut child = ctx
+        .new_std_cmd(["forward"])
+        .arg("-L")
+        .arg(format!("127.0.0.1:0:127.0.0.1:{port}"))
+        .spawn()
+        .expect("Failed to execute");
+
+    // Port 0 picks any free port, which is printed back out
+    let mut stdout = ThreadedReader::new(child.stdout.take().unwrap());
+    let line = stdout.read_line_timeout(Duration::from_secs(5));
+    let addr = line
+        .strip_prefix("Forwarding ")
+        .and_then(|line| line.split_whitespace().next())
+        .unwrap_or_else(|| panic!("Unexpected output: {line:?}"));
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"hello through the tunnel").unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+
+    let mut data = String::new();
+    stream.read_to_string(&mut data).unwrap();
+    assert_eq!(data, "HELLO THROUGH THE TUNNEL");
+
+    child.kill().expect("Failed to terminate process");
+    child.wait().expect("Failed to wait for process");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_forward_remote_port_to_host_reachable_from_local_machine(ctx: DistantManagerCtx) {
+    // Server only reachable from the local machine, shouting back what it reads
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+        stream.write_all(&data.to_ascii_uppercase()).unwrap();
+    });
+
+    // distant forward -R 127.0.0.1:0:127.0.0.1:{port}
+    let mut child = ctx
+        .new_std_cmd(["forward"])
+        .arg("-R")
+        .arg(format!("127.0.0.1:0:127.0.0.1:{port}"))
+        .spawn()
+        .expect("Failed to execute");
+
+    // Port 0 picks any free port on the remote machine, which is printed back out
+    let mut stdout = ThreadedReader::new(child.stdout.take().unwrap());
+    let line = stdout.read_line_timeout(Duration::from_secs(5));
+    let addr = line
+        .strip_prefix("Forwarding ")
+        .and_then(|line| line.split_whitespace().next())
+        .unwrap_or_else(|| panic!("Unexpected output: {line:?}"));
+
+    // NOTE: The remote machine of the test server is this one, so we can connect to it directly
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"hello from the remote machine").unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+
+    let mut data = String::new();
+    stream.read_to_string(&mut data).unwrap();
+    assert_eq!(data, "HELLO FROM THE REMOTE MACHINE");
+
+    child.kill().expect("Failed to terminate process");
+    child.wait().expect("Failed to wait for process");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_forward_connections_made_through_socks_proxy(ctx: DistantManagerCtx) {
+    // Server only reachable through the proxy, shouting back what it reads
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+        stream.write_all(&data.to_ascii_uppercase()).unwrap();
+    });
+
+    // distant forward -D 127.0.0.1:0
+    let mut child = ctx
+        .new_std_cmd(["forward"])
+        .arg("-D")
+        .arg("127.0.0.1:0")
+        .spawn()
+        .expect("Failed to execute");
+
+    let mut stdout = ThreadedReader::new(child.stdout.take().unwrap());
+    let line = stdout.read_line_timeout(Duration::from_secs(5));
+    let addr = line
+        .strip_prefix("Forwarding ")
+        .and_then(|line| line.split_whitespace().next())
+        .unwrap_or_else(|| panic!("Unexpected output: {line:?}"));
+
+    // Greet without authentication and then ask to connect to 127.0.0.1:{port}
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(&[5, 1, 0]).unwrap();
+    let mut greeting = [0; 2];
+    stream.read_exact(&mut greeting).unwrap();
+    assert_eq!(greeting, [5, 0]);
+
+    let mut request = vec![5, 1, 0, 1, 127, 0, 0, 1];
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).unwrap();
+    let mut reply = [0; 10];
+    stream.read_exact(&mut reply).unwrap();
+    assert_eq!(reply[..2], [5, 0], "Unexpected reply: {reply:?}");
+
+    stream.write_all(b"hello through the proxy").unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+
+    let mut data = String::new();
+    stream.read_to_string(&mut data).unwrap();
+    assert_eq!(data, "HELLO THROUGH THE PROXY");
+
+    child.kill().expect("Failed to terminate process");
+    child.wait().expect("Failed to wait for process");
+}
@@ -0,0 +1,81 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+use serde_json::json;
+
+#[rstest]
+#[test_log::test]
+fn should_perform_operations_read_from_stdin(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let dir = temp.child("dir");
+    let file = dir.child("file");
+
+    let ops = json!([
+        { "type": "dir_create", "path": dir.to_path_buf() },
+        { "type": "file_write_text", "path": file.to_path_buf(), "text": "some text" },
+    ]);
+
+    // distant fs batch
+    ctx.new_assert_cmd(["fs", "batch"])
+        .write_stdin(ops.to_string())
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    file.assert("some text");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_undo_operations_if_atomic_and_one_fails(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let dir = temp.child("dir");
+    let missing = temp.child("missing-dir").child("missing-file");
+    let ops_file = temp.child("ops.json");
+    ops_file
+        .write_str(
+            &json!([
+                { "type": "dir_create", "path": dir.to_path_buf() },
+                { "type": "file_write_text", "path": missing.to_path_buf(), "text": "some text" },
+            ])
+            .to_string(),
+        )
+        .unwrap();
+
+    // distant fs batch --atomic {file}
+    ctx.new_assert_cmd(["fs", "batch"])
+        .args(["--atomic", ops_file.to_str().unwrap()])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicates::str::contains("Step 1 failed (rolled back)"));
+
+    dir.assert(predicate::path::missing());
+}
+
+#[rstest]
+#[test_log::test]
+fn should_restore_replaced_files_if_transaction_and_one_fails(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("file");
+    file.write_str("old text").unwrap();
+    let missing = temp.child("missing-dir").child("missing-file");
+
+    let ops = json!([
+        { "type": "file_write_text", "path": file.to_path_buf(), "text": "new text" },
+        { "type": "file_write_text", "path": missing.to_path_buf(), "text": "some text" },
+    ]);
+
+    // distant fs batch --transaction
+    ctx.new_assert_cmd(["fs", "batch"])
+        .arg("--transaction")
+        .write_stdin(ops.to_string())
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicates::str::contains("Step 1 failed (rolled back)"));
+
+    file.assert("old text");
+}
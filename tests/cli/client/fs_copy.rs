@@ -57,6 +57,32 @@ fn should_support_copying_nonempty_directory(ctx: DistantManagerCtx) {
     dst_file.assert(predicate::path::eq_file(src_file.path()));
 }
 
+#[rstest]
+#[test_log::test]
+fn should_print_paths_that_would_be_copied_to_if_dry_run(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("dir");
+    src.create_dir_all().unwrap();
+    src.child("file").write_str(FILE_CONTENTS).unwrap();
+
+    let dst = temp.child("dir2");
+
+    // distant fs copy --dry-run {src} {dst}
+    ctx.new_assert_cmd(["fs", "copy"])
+        .args(["--dry-run", src.to_str().unwrap(), dst.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(format!(
+            "Following paths would be affected:\n* {}\n* {}\n",
+            dst.display(),
+            dst.child("file").display()
+        ))
+        .stderr("");
+
+    dst.assert(predicate::path::missing());
+}
+
 #[rstest]
 #[test_log::test]
 fn yield_an_error_when_fails(ctx: DistantManagerCtx) {
@@ -0,0 +1,49 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_print_unified_diff_of_two_files(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let src = temp.child("src");
+    src.write_str("one\ntwo\nthree\n").unwrap();
+    let dst = temp.child("dst");
+    dst.write_str("one\nthree\nfour\n").unwrap();
+
+    let expected = format!(
+        "--- {}\n+++ {}\n@@ -1,3 +1,3 @@\n one\n-two\n three\n+four\n",
+        src.to_string_lossy(),
+        dst.to_string_lossy(),
+    );
+
+    // distant fs diff {src} {dst}
+    ctx.new_assert_cmd(["fs", "diff"])
+        .args([src.to_str().unwrap(), dst.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr("");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_compare_against_stdin_if_no_dst_provided(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let src = temp.child("src");
+    src.write_str("one\ntwo\n").unwrap();
+
+    let expected = format!(
+        "--- {0}\n+++ {0}\n@@ -1,2 +1,2 @@\n one\n-two\n+TWO\n",
+        src.to_string_lossy(),
+    );
+
+    // distant fs diff {src} < stdin
+    ctx.new_assert_cmd(["fs", "diff"])
+        .arg(src.to_str().unwrap())
+        .write_stdin("one\nTWO\n")
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr("");
+}
@@ -0,0 +1,59 @@
+use crate::cli::fixtures::*;
+use predicates::prelude::*;
+use rstest::*;
+use std::path::PathBuf;
+
+#[rstest]
+#[test_log::test]
+fn should_print_path_of_created_file(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    // distant fs make-temp --parent {path}
+    let output = ctx
+        .new_assert_cmd(["fs", "make-temp"])
+        .args(["--parent", temp.to_str().unwrap()])
+        .assert()
+        .success()
+        .stderr("")
+        .get_output()
+        .clone();
+
+    let path = PathBuf::from(String::from_utf8(output.stdout).unwrap().trim());
+    assert_eq!(path.parent(), Some(temp.path()));
+    assert!(path.is_file(), "Temporary file missing: {path:?}");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_support_creating_directory_if_specified(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    // distant fs make-temp --dir --parent {path}
+    let output = ctx
+        .new_assert_cmd(["fs", "make-temp"])
+        .args(["--dir", "--parent", temp.to_str().unwrap()])
+        .assert()
+        .success()
+        .stderr("")
+        .get_output()
+        .clone();
+
+    let path = PathBuf::from(String::from_utf8(output.stdout).unwrap().trim());
+    assert_eq!(path.parent(), Some(temp.path()));
+    assert!(path.is_dir(), "Temporary directory missing: {path:?}");
+}
+
+#[rstest]
+#[test_log::test]
+fn yield_an_error_when_fails(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let parent = temp.path().join("missing-dir");
+
+    // distant fs make-temp --parent {path}
+    ctx.new_assert_cmd(["fs", "make-temp"])
+        .args(["--parent", parent.to_str().unwrap()])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicates::str::is_empty().not());
+}
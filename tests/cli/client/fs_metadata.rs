@@ -25,6 +25,7 @@ fn should_output_metadata_for_file(ctx: DistantManagerCtx) {
         .stdout(regex_pred(concat!(
             "Type: file\n",
             "Len: .*\n",
+            "(Allocated Len: .*\n)?",
             "Readonly: false\n",
             "Created: .*\n",
             "Last Accessed: .*\n",
@@ -49,6 +50,7 @@ fn should_output_metadata_for_directory(ctx: DistantManagerCtx) {
         .stdout(regex_pred(concat!(
             "Type: dir\n",
             "Len: .*\n",
+            "(Allocated Len: .*\n)?",
             "Readonly: false\n",
             "Created: .*\n",
             "Last Accessed: .*\n",
@@ -80,6 +82,7 @@ fn should_support_including_a_canonicalized_path(ctx: DistantManagerCtx) {
                 "Canonicalized Path: {:?}\n",
                 "Type: symlink\n",
                 "Len: .*\n",
+                "(Allocated Len: .*\n)?",
                 "Readonly: false\n",
                 "Created: .*\n",
                 "Last Accessed: .*\n",
@@ -109,6 +112,7 @@ fn should_support_resolving_file_type_of_symlink(ctx: DistantManagerCtx) {
         .stdout(regex_pred(concat!(
             "Type: file\n",
             "Len: .*\n",
+            "(Allocated Len: .*\n)?",
             "Readonly: false\n",
             "Created: .*\n",
             "Last Accessed: .*\n",
@@ -207,6 +207,52 @@ fn should_include_root_directory_if_specified(ctx: DistantManagerCtx) {
         .stderr("");
 }
 
+#[rstest]
+#[test_log::test]
+fn should_support_sorting_and_filtering_entries(ctx: DistantManagerCtx) {
+    let temp = make_directory();
+
+    let expected = regex_pred(&regex_stdout(vec![
+        ("", Path::new("file2").to_str().unwrap()),
+        ("", Path::new("file1").to_str().unwrap()),
+        ("", Path::new("dir2").join("file2").to_str().unwrap()),
+        ("", Path::new("dir2").join("file1").to_str().unwrap()),
+        (
+            "",
+            Path::new("dir2")
+                .join("dirb")
+                .join("file1")
+                .to_str()
+                .unwrap(),
+        ),
+        ("", Path::new("dir1").join("file2").to_str().unwrap()),
+        ("", Path::new("dir1").join("file1").to_str().unwrap()),
+        (
+            "",
+            Path::new("dir1")
+                .join("dirb")
+                .join("file1")
+                .to_str()
+                .unwrap(),
+        ),
+    ]));
+
+    // distant fs read --depth 0 --reverse --file-type file {path}
+    ctx.new_assert_cmd(["fs", "read"])
+        .args([
+            "--depth",
+            "0",
+            "--reverse",
+            "--file-type",
+            "file",
+            temp.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr("");
+}
+
 #[rstest]
 #[test_log::test]
 fn yield_an_error_when_fails(ctx: DistantManagerCtx) {
@@ -62,6 +62,30 @@ fn should_support_removing_nonempty_directory_if_force_specified(ctx: DistantMan
     dir.assert(predicate::path::missing());
 }
 
+#[rstest]
+#[test_log::test]
+fn should_print_paths_that_would_be_removed_if_dry_run(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let dir = temp.child("dir");
+    dir.create_dir_all().unwrap();
+    dir.child("file").touch().unwrap();
+
+    // distant fs remove --force --dry-run {path}
+    ctx.new_assert_cmd(["fs", "remove"])
+        .args(["--force", "--dry-run", dir.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(format!(
+            "Following paths would be affected:\n* {}\n* {}\n",
+            dir.display(),
+            dir.child("file").display()
+        ))
+        .stderr("");
+
+    dir.child("file").assert(predicate::path::exists());
+}
+
 #[rstest]
 #[test_log::test]
 fn yield_an_error_when_fails(ctx: DistantManagerCtx) {
@@ -60,6 +60,32 @@ fn should_support_renaming_nonempty_directory(ctx: DistantManagerCtx) {
     dst_file.assert(FILE_CONTENTS);
 }
 
+#[rstest]
+#[test_log::test]
+fn should_print_paths_that_would_be_moved_if_dry_run(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("file");
+    src.write_str(FILE_CONTENTS).unwrap();
+
+    let dst = temp.child("file2");
+
+    // distant fs rename --dry-run {src} {dst}
+    ctx.new_assert_cmd(["fs", "rename"])
+        .args(["--dry-run", src.to_str().unwrap(), dst.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(format!(
+            "Following paths would be affected:\n* {}\n* {}\n",
+            src.display(),
+            dst.display()
+        ))
+        .stderr("");
+
+    src.assert(predicate::path::exists());
+    dst.assert(predicate::path::missing());
+}
+
 #[rstest]
 #[test_log::test]
 fn yield_an_error_when_fails(ctx: DistantManagerCtx) {
@@ -0,0 +1,70 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+
+/// Runs `distant fs {subcommand}` with `args`, returning what it printed to stdout
+fn run(ctx: &DistantManagerCtx, subcommand: &'static str, args: &[&str]) -> String {
+    let output = ctx
+        .new_assert_cmd(["fs", subcommand])
+        .args(args)
+        .assert()
+        .success()
+        .stderr("")
+        .get_output()
+        .stdout
+        .clone();
+    String::from_utf8(output).unwrap()
+}
+
+#[rstest]
+#[test_log::test]
+fn should_restore_a_directory_from_a_snapshot(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let dir = temp.child("dir");
+    let file = dir.child("file");
+    file.write_str("some text").unwrap();
+
+    // distant fs snapshot {path}
+    let id = run(&ctx, "snapshot", &[dir.to_str().unwrap()]);
+    let id = id.trim();
+    assert!(id.parse::<u32>().is_ok(), "{id}");
+
+    // distant fs snapshot-list
+    let list = run(&ctx, "snapshot-list", &[]);
+    assert!(list.contains(id), "{list}");
+
+    std::fs::remove_file(file.path()).unwrap();
+    dir.child("other").write_str("other text").unwrap();
+
+    // distant fs snapshot-restore {id}
+    run(&ctx, "snapshot-restore", &[id]);
+
+    file.assert("some text");
+    dir.child("other").assert(predicate::path::missing());
+}
+
+#[rstest]
+#[test_log::test]
+fn should_prune_all_but_the_newest_snapshots_of_each_path(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("file");
+    file.write_str("some text").unwrap();
+
+    let oldest = run(&ctx, "snapshot", &[file.to_str().unwrap()]);
+    let newest = run(&ctx, "snapshot", &[file.to_str().unwrap()]);
+
+    // distant fs snapshot-prune --keep 1
+    run(&ctx, "snapshot-prune", &["--keep", "1"]);
+
+    let list = run(&ctx, "snapshot-list", &[]);
+    assert!(!list.contains(oldest.trim()), "{list}");
+    assert!(list.contains(newest.trim()), "{list}");
+
+    // Only the file and its remaining snapshot should be left
+    assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 2);
+
+    // distant fs snapshot-prune {id}
+    run(&ctx, "snapshot-prune", &[newest.trim()]);
+    assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 1);
+}
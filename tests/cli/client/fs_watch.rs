@@ -148,3 +148,46 @@ fn yield_an_error_when_fails(ctx: DistantManagerCtx) {
     assert!(output.stdout.is_empty(), "Unexpectedly got stdout");
     assert!(!output.stderr.is_empty(), "Missing stderr output");
 }
+
+#[rstest]
+#[test_log::test]
+fn should_unwatch_and_exit_once_stdout_is_closed(ctx: DistantManagerCtx) {
+    use std::io::{BufRead, BufReader};
+    use std::time::Instant;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("file");
+    file.touch().unwrap();
+
+    // distant fs watch {path} | head -n 1
+    let mut child = ctx
+        .new_std_cmd(["fs", "watch"])
+        .arg(file.to_str().unwrap())
+        .spawn()
+        .expect("Failed to execute");
+
+    // Wait for the process to be ready
+    wait_a_bit();
+
+    // Read the first change and then close our end, like `head` would
+    file.write_str("some text").unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).expect("Failed to read change");
+    drop(stdout);
+
+    // Reporting the next change finds stdout closed
+    let start = Instant::now();
+    let status = loop {
+        file.write_str("more text").unwrap();
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if start.elapsed() >= Duration::from_secs(5) {
+            child.kill().expect("Failed to terminate process");
+            panic!("Watch never exited after stdout was closed");
+        }
+        wait_millis(100);
+    };
+    assert!(status.success(), "Watch exited with {status}");
+}
@@ -37,6 +37,39 @@ fn should_support_writing_stdin_to_file(ctx: DistantManagerCtx) {
     file.assert(FILE_CONTENTS);
 }
 
+#[rstest]
+#[test_log::test]
+fn should_support_writing_stdin_to_file_with_dedup(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test-file");
+    let other = temp.child("other-file");
+
+    // distant fs write --dedup {path}
+    ctx.new_assert_cmd(["fs", "write"])
+        .args(["--dedup", file.to_str().unwrap()])
+        .write_stdin(FILE_CONTENTS)
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    // Writing the same contents elsewhere reuses what was already written
+    ctx.new_assert_cmd(["fs", "write"])
+        .args(["--dedup", other.to_str().unwrap()])
+        .write_stdin(FILE_CONTENTS)
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    // NOTE: We wait a little bit to give the OS time to fully write to file
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    // Because we're talking to a local server, we can verify locally
+    file.assert(FILE_CONTENTS);
+    other.assert(FILE_CONTENTS);
+}
+
 #[rstest]
 #[test_log::test]
 fn should_support_appending_stdin_to_file(ctx: DistantManagerCtx) {
@@ -105,6 +138,55 @@ fn should_support_appending_argument_to_file(ctx: DistantManagerCtx) {
     file.assert(format!("{}{}", FILE_CONTENTS, APPENDED_FILE_CONTENTS));
 }
 
+#[rstest]
+#[test_log::test]
+fn should_support_writing_argument_at_offset_within_file(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test-file");
+    file.write_str(FILE_CONTENTS).unwrap();
+
+    // distant fs write --offset {offset} {path} -- {contents}
+    ctx.new_assert_cmd(["fs", "write"])
+        .args(["--offset", "5", file.to_str().unwrap(), "--"])
+        .arg("TEXT")
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    // NOTE: We wait a little bit to give the OS time to fully write to file
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    // Because we're talking to a local server, we can verify locally
+    file.assert(FILE_CONTENTS.replacen("text", "TEXT", 1));
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test_log::test]
+fn should_support_giving_created_file_a_mode(ctx: DistantManagerCtx) {
+    use std::os::unix::fs::PermissionsExt;
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test-file");
+
+    // distant fs write --mode {mode} {path} -- {contents}
+    ctx.new_assert_cmd(["fs", "write"])
+        .args(["--mode", "606", file.to_str().unwrap(), "--"])
+        .arg(FILE_CONTENTS)
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    // NOTE: We wait a little bit to give the OS time to fully write to file
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    // Because we're talking to a local server, we can verify locally
+    file.assert(FILE_CONTENTS);
+    let mode = std::fs::metadata(file.path()).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o606);
+}
+
 #[rstest]
 #[test_log::test]
 fn yield_an_error_when_fails(ctx: DistantManagerCtx) {
@@ -0,0 +1,57 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_print_matching_lines_with_path_and_line_number(ctx: DistantManagerCtx) {
+    let root = assert_fs::TempDir::new().unwrap();
+    root.child("file1.txt").write_str("some file text").unwrap();
+    root.child("sub/file2.txt")
+        .write_str("lines\nof\nTextual\ninformation")
+        .unwrap();
+    root.child("file3.txt").write_str("more content").unwrap();
+
+    let output = ctx
+        .new_assert_cmd(["grep"])
+        .args(["-i", "te[a-z]*\\b"])
+        .arg(root.path())
+        .assert()
+        .success()
+        .stderr("")
+        .get_output()
+        .stdout
+        .clone();
+
+    // Results arrive in no particular order
+    let stdout = String::from_utf8(output).unwrap();
+    let mut lines = stdout.lines().collect::<Vec<_>>();
+    lines.sort_unstable();
+
+    let file1 = root.child("file1.txt");
+    let file2 = root.child("sub/file2.txt");
+    let file3 = root.child("file3.txt");
+    assert_eq!(
+        lines,
+        vec![
+            format!("{}:1:some file text", file1.path().to_string_lossy()),
+            format!("{}:1:more content", file3.path().to_string_lossy()),
+            format!("{}:3:Textual", file2.path().to_string_lossy()),
+        ]
+    );
+}
+
+#[rstest]
+#[test_log::test]
+fn should_fail_if_nothing_matches(ctx: DistantManagerCtx) {
+    let root = assert_fs::TempDir::new().unwrap();
+    root.child("file1.txt").write_str("some file text").unwrap();
+
+    ctx.new_assert_cmd(["grep"])
+        .args(["-F", "file.*"])
+        .arg(root.path())
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr("");
+}
@@ -0,0 +1,77 @@
+use crate::cli::{fixtures::*, scripts::*};
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_record_spawned_commands_that_can_be_searched(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let history = temp.child("history.jsonl");
+
+    for code in ["0", "3"] {
+        // distant spawn --trust --history-file {path} -- {cmd} [args]
+        ctx.cmd("spawn")
+            .arg("--trust")
+            .arg("--history-file")
+            .arg(history.path())
+            .arg("--")
+            .arg(SCRIPT_RUNNER.as_str())
+            .arg(SCRIPT_RUNNER_ARG.as_str())
+            .arg(EXIT_CODE.to_str().unwrap())
+            .arg(code)
+            .assert()
+            .code(code.parse::<i32>().unwrap());
+    }
+
+    // distant spawn --trust --no-history --history-file {path} -- {cmd} [args]
+    ctx.cmd("spawn")
+        .arg("--trust")
+        .arg("--no-history")
+        .arg("--history-file")
+        .arg(history.path())
+        .arg("--")
+        .arg(SCRIPT_RUNNER.as_str())
+        .arg(SCRIPT_RUNNER_ARG.as_str())
+        .arg(EXIT_CODE.to_str().unwrap())
+        .arg("4")
+        .assert()
+        .code(4);
+
+    // distant history --history-file {path} --grep {text}
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("history")
+        .arg("--history-file")
+        .arg(history.path())
+        .arg("--grep")
+        .arg(" 3")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("spawn")
+                .and(predicate::str::contains("exit 3"))
+                .and(predicate::str::contains("exit 0").not())
+                .and(predicate::str::contains("exit 4").not()),
+        );
+
+    // distant history --history-file {path} --format json
+    let output = Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("history")
+        .arg("--history-file")
+        .arg(history.path())
+        .arg("--format")
+        .arg("json")
+        .output()
+        .unwrap();
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let codes: Vec<_> = entries
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["exit_code"].as_i64().unwrap())
+        .collect();
+    assert_eq!(codes, [0, 3]);
+}
@@ -0,0 +1,22 @@
+use crate::cli::fixtures::*;
+use predicates::prelude::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_fail_if_server_does_not_permit_signaling_system_processes(ctx: DistantManagerCtx) {
+    let mut child = std::process::Command::new("sleep")
+        .arg("10")
+        .spawn()
+        .unwrap();
+
+    ctx.cmd("kill")
+        .arg(child.id().to_string())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not enabled"));
+
+    // The process was left alone
+    assert!(child.try_wait().unwrap().is_none());
+    child.kill().unwrap();
+}
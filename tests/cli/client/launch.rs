@@ -0,0 +1,98 @@
+use crate::cli::fixtures::*;
+use rstest::*;
+use serde_json::{json, Value};
+
+#[rstest]
+#[test_log::test]
+fn should_support_launching_several_destinations_at_once_with_json_auth(ctx: DistantManagerCtx) {
+    let bin = assert_cmd::cargo::cargo_bin(env!("CARGO_PKG_NAME"));
+
+    // Each launch authenticates with the manager over the same stdin, taking one response apiece
+    let auth = json!({"type": "auth_initialization_response", "methods": ["none"]}).to_string();
+    let output = ctx
+        .cmd("launch")
+        .args(["--format", "json"])
+        .args(["--distant", bin.to_str().unwrap()])
+        .args(["--distant-args", "--shutdown lonely=10"])
+        .args(["manager://localhost", "manager://127.0.0.1"])
+        .write_stdin(format!("{auth}\n{auth}\n"))
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let events: Vec<Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let launched = events
+        .iter()
+        .find(|event| event["type"] == "launched_all")
+        .expect("Missing launch results");
+
+    let mut names: Vec<&str> = launched["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| {
+            assert!(result["id"].is_u64(), "{result}");
+            result["name"].as_str().unwrap()
+        })
+        .collect();
+    names.sort_unstable();
+    assert_eq!(names, ["manager://127.0.0.1", "manager://localhost"]);
+}
+
+#[rstest]
+#[test_log::test]
+fn should_support_verifying_launched_server(ctx: DistantManagerCtx) {
+    let bin = assert_cmd::cargo::cargo_bin(env!("CARGO_PKG_NAME"));
+
+    let auth = json!({"type": "auth_initialization_response", "methods": ["none"]}).to_string();
+    let output = ctx
+        .cmd("launch")
+        .args(["--format", "json", "--verify"])
+        .args(["--distant", bin.to_str().unwrap()])
+        .args(["--distant-args", "--shutdown lonely=10"])
+        .arg("manager://localhost")
+        .write_stdin(format!("{auth}\n"))
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    // Verification runs against the connection, so it must still be there afterwards
+    let launched: Value = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .find(|event| event["type"] == "launched")
+        .expect("Missing launch result");
+    let id = launched["id"].as_u64().expect("Missing connection id");
+
+    let stdout = ctx
+        .new_assert_cmd(["manager", "list"])
+        .args(["--format", "json"])
+        .write_stdin(format!("{auth}\n"))
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert!(
+        String::from_utf8(stdout).unwrap().contains(&id.to_string()),
+        "Verified connection {id} is missing"
+    );
+}
+
+#[rstest]
+#[test_log::test]
+fn should_not_support_both_verifying_and_not_verifying_launched_server(ctx: DistantManagerCtx) {
+    ctx.cmd("launch")
+        .args(["--verify", "--no-verify", "manager://localhost"])
+        .assert()
+        .failure();
+}
@@ -1,14 +1,43 @@
+mod bench;
 mod capabilities;
+mod complete;
+mod connect;
+mod cp;
+// Uses sh as the editor
+#[cfg(unix)]
+mod edit;
+mod env;
+mod find;
+mod forward;
+mod fs_batch;
 mod fs_copy;
+mod fs_diff;
 mod fs_exists;
 mod fs_make_dir;
+mod fs_make_temp;
 mod fs_metadata;
 mod fs_read_directory;
 mod fs_read_file;
 mod fs_remove;
 mod fs_rename;
 mod fs_search;
+mod fs_snapshot;
 mod fs_watch;
 mod fs_write;
+mod grep;
+mod history;
+// Uses sleep as the process to signal
+#[cfg(unix)]
+mod kill;
+mod launch;
+mod ping;
+// Drives sh within a pty
+#[cfg(unix)]
+mod shell;
+// Uses sh scripts as plugins
+#[cfg(unix)]
+mod plugin;
 mod spawn;
+mod sync;
 mod system_info;
+mod trust;
@@ -0,0 +1,33 @@
+use crate::cli::fixtures::*;
+use predicates::prelude::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_report_latency_of_each_ping(ctx: DistantManagerCtx) {
+    ctx.cmd("ping")
+        .args(["--count", "3", "--interval", "0"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(concat!(
+                r"^(Pong from connection \d+: seq=[1-3] time=\d+\.\d{3} ms\n){3}",
+                r"3 pings sent, 3 received\n",
+                r"round-trip min/avg/max = \d+\.\d{3}/\d+\.\d{3}/\d+\.\d{3} ms\n$",
+            ))
+            .unwrap(),
+        )
+        .stderr("");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_fail_if_count_is_zero(ctx: DistantManagerCtx) {
+    ctx.cmd("ping")
+        .args(["--count", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--count <COUNT>'",
+        ));
+}
@@ -0,0 +1,90 @@
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::os::unix::fs::PermissionsExt;
+
+/// Creates a directory with an executable `distant-{name}` that runs `script`, returning the
+/// directory along with the PATH that includes it
+fn plugin_dir(name: &str, script: &str) -> (assert_fs::TempDir, String) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child(format!("distant-{name}"));
+    file.write_str(&format!("#!/bin/sh\n{script}\n")).unwrap();
+    std::fs::set_permissions(file.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!(
+        "{}:{}",
+        temp.path().to_str().unwrap(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    (temp, path)
+}
+
+#[test_log::test]
+fn should_run_plugin_from_path_with_remaining_arguments() {
+    let (_temp, path) = plugin_dir(
+        "test-plugin",
+        r#"echo "args: $*"; echo "format: $DISTANT_FORMAT"; test -x "$DISTANT_BIN" && echo "bin: ok"; exit 3"#,
+    );
+
+    // distant test-plugin one --two
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .env("PATH", path)
+        .env_remove("DISTANT_FORMAT")
+        .args(["test-plugin", "one", "--two"])
+        .assert()
+        .code(3)
+        .stdout("args: one --two\nformat: shell\nbin: ok\n")
+        .stderr("");
+}
+
+#[test_log::test]
+fn should_use_cache_given_ahead_of_plugin_arguments() {
+    let (temp, path) = plugin_dir(
+        "test-plugin",
+        r#"echo "args: $*"; echo "cache: $DISTANT_CACHE""#,
+    );
+    let cache = temp.child("cache.toml");
+
+    // distant test-plugin --cache {cache} one --cache two
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .env("PATH", &path)
+        .args(["test-plugin", "--cache"])
+        .arg(cache.path())
+        .args(["one", "--cache", "two"])
+        .assert()
+        .success()
+        .stdout(format!(
+            "args: one --cache two\ncache: {}\n",
+            cache.path().display()
+        ));
+
+    // distant test-plugin --cache={cache} one
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .env("PATH", &path)
+        .arg("test-plugin")
+        .arg(format!("--cache={}", cache.path().display()))
+        .arg("one")
+        .assert()
+        .success()
+        .stdout(format!("args: one\ncache: {}\n", cache.path().display()));
+}
+
+#[test_log::test]
+fn yield_an_error_when_plugin_is_not_found() {
+    let (_temp, path) = plugin_dir("test-plugin", "exit 0");
+
+    // distant missing-plugin
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .env("PATH", path)
+        .arg("missing-plugin")
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains(
+            "no distant-missing-plugin was found",
+        ));
+}
@@ -0,0 +1,192 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use portable_pty::{native_pty_system, Child, CommandBuilder, PtySize};
+use rstest::*;
+use std::{
+    io::{Read, Write},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default config, which is complete unlike a config holding only the settings being tested
+const DEFAULT_CONFIG: &str = include_str!("../../../src/options/config.toml");
+
+/// Ends the title given to the terminal by the shell
+const TITLE_END: &str = "\x07";
+
+/// `distant shell` running within a pty, as it needs a terminal
+struct PtyShell {
+    child: Box<dyn Child + Send + Sync>,
+    writer: Box<dyn Write + Send>,
+    output: Arc<Mutex<String>>,
+}
+
+impl PtyShell {
+    fn spawn(ctx: &DistantManagerCtx, args: &[&str]) -> Self {
+        let cmd = ctx.new_std_cmd(["shell"]);
+        let mut builder = CommandBuilder::new(cmd.get_program());
+        builder.args(cmd.get_args());
+        builder.args(args);
+
+        let pair = native_pty_system()
+            .openpty(PtySize::default())
+            .expect("Failed to open pty");
+        let child = pair
+            .slave
+            .spawn_command(builder)
+            .expect("Failed to spawn shell");
+        drop(pair.slave);
+
+        let output = Arc::new(Mutex::new(String::new()));
+        let mut reader = pair.master.try_clone_reader().unwrap();
+        thread::spawn({
+            let output = Arc::clone(&output);
+            move || {
+                let mut buf = [0; 1024];
+                while let Ok(n) = reader.read(&mut buf) {
+                    if n == 0 {
+                        break;
+                    }
+                    output
+                        .lock()
+                        .unwrap()
+                        .push_str(&String::from_utf8_lossy(&buf[..n]));
+                }
+            }
+        });
+
+        Self {
+            child,
+            writer: pair.master.take_writer().unwrap(),
+            output,
+        }
+    }
+
+    /// Pastes `text` into the shell, which arrives in one piece unlike typed keys (each of which
+    /// is sent separately, so keys typed all at once can reach the remote process out of order)
+    fn paste(&mut self, text: &str) {
+        self.write(&format!("\x1b[200~{text}\x1b[201~"));
+    }
+
+    /// Types `keys` one at a time
+    fn type_keys(&mut self, keys: &str) {
+        for key in keys.chars() {
+            self.write(&key.to_string());
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    fn write(&mut self, input: &str) {
+        self.writer.write_all(input.as_bytes()).unwrap();
+        self.writer.flush().unwrap();
+    }
+
+    /// Waits for `text` to be output, returning all output so far
+    fn wait_for(&self, text: &str) -> String {
+        let start = Instant::now();
+        loop {
+            let output = self.output.lock().unwrap().clone();
+            if output.contains(text) {
+                return output;
+            }
+
+            assert!(
+                start.elapsed() < TIMEOUT,
+                "Timed out waiting for {text:?}, got {output:?}"
+            );
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    /// Waits for the shell to take over the terminal, as input typed before it is switched to
+    /// raw mode is discarded
+    fn wait_for_terminal(&self) {
+        // The title is only set once in raw mode
+        self.wait_for(TITLE_END);
+    }
+
+    fn wait_for_exit(&mut self) {
+        let start = Instant::now();
+        while self.child.try_wait().unwrap().is_none() {
+            assert!(start.elapsed() < TIMEOUT, "Timed out waiting for exit");
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+impl Drop for PtyShell {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+#[rstest]
+#[test_log::test]
+fn should_send_init_cmd_to_spawned_shell(ctx: DistantManagerCtx) {
+    // The command is echoed as typed, so only its result shows that it ran
+    let mut shell = PtyShell::spawn(
+        &ctx,
+        &[
+            "--trust",
+            "--init-cmd",
+            "echo INIT_$((6*7))",
+            "--",
+            "/bin/sh",
+        ],
+    );
+    shell.wait_for("INIT_42");
+
+    shell.paste("exit\r");
+    shell.wait_for_exit();
+}
+
+#[rstest]
+#[test_log::test]
+fn should_not_send_init_cmd_when_attaching_to_shell(ctx: DistantManagerCtx) {
+    let mut shell = PtyShell::spawn(&ctx, &["--trust", "--detach-on-exit", "--", "/bin/sh"]);
+    shell.wait_for_terminal();
+    shell.paste("echo READY_$((6*7))\r");
+    shell.wait_for("READY_42");
+
+    // Disconnect, leaving the shell running to attach to again
+    shell.type_keys("\r~.");
+    let output = shell.wait_for("distant shell --attach ");
+    shell.wait_for_exit();
+    let session: String = output
+        .rsplit("distant shell --attach ")
+        .next()
+        .unwrap()
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect();
+
+    // The command can only come from config when attaching, as the flag conflicts with --attach
+    let config = assert_fs::NamedTempFile::new("config.toml").unwrap();
+    config
+        .write_str(
+            &DEFAULT_CONFIG.replace("# init_cmd = \"\"", "init_cmd = \"echo INIT_$((6*7))\""),
+        )
+        .unwrap();
+
+    let mut shell = PtyShell::spawn(
+        &ctx,
+        &[
+            "--config",
+            config.path().to_str().unwrap(),
+            "--attach",
+            &session,
+        ],
+    );
+
+    // Input is handled in order, so the init command would have run before this one
+    shell.wait_for_terminal();
+    shell.paste("echo ATTACHED_$((6*7))\r");
+    let output = shell.wait_for("ATTACHED_42");
+    assert!(!output.contains("INIT_42"), "Init command ran: {output:?}");
+
+    shell.paste("exit\r");
+    shell.wait_for_exit();
+}
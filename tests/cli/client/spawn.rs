@@ -10,8 +10,9 @@ fn should_execute_program_and_return_exit_status(ctx: DistantManagerCtx) {
     #[cfg(unix)]
     let stdout = "";
 
-    // distant spawn -- {cmd} [args]
+    // distant spawn --trust -- {cmd} [args]
     ctx.cmd("spawn")
+        .arg("--trust")
         .arg("--")
         .arg(SCRIPT_RUNNER.as_str())
         .arg(SCRIPT_RUNNER_ARG.as_str())
@@ -26,8 +27,31 @@ fn should_execute_program_and_return_exit_status(ctx: DistantManagerCtx) {
 #[rstest]
 #[test_log::test]
 fn should_capture_and_print_stdout(ctx: DistantManagerCtx) {
-    // distant spawn -- {cmd} [args]
+    // distant spawn --trust -- {cmd} [args]
     ctx.cmd("spawn")
+        .arg("--trust")
+        .arg("--")
+        .arg(SCRIPT_RUNNER.as_str())
+        .arg(SCRIPT_RUNNER_ARG.as_str())
+        .arg(ECHO_ARGS_TO_STDOUT.to_str().unwrap())
+        .arg("hello world")
+        .assert()
+        .success()
+        .stdout(if cfg!(windows) {
+            "hello world\r\n"
+        } else {
+            "hello world"
+        })
+        .stderr("");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_capture_and_print_stdout_of_process_spawned_to_kill_its_tree(ctx: DistantManagerCtx) {
+    // distant spawn --trust --kill-tree -- {cmd} [args]
+    ctx.cmd("spawn")
+        .arg("--trust")
+        .arg("--kill-tree")
         .arg("--")
         .arg(SCRIPT_RUNNER.as_str())
         .arg(SCRIPT_RUNNER_ARG.as_str())
@@ -46,8 +70,9 @@ fn should_capture_and_print_stdout(ctx: DistantManagerCtx) {
 #[rstest]
 #[test_log::test]
 fn should_capture_and_print_stderr(ctx: DistantManagerCtx) {
-    // distant spawn -- {cmd} [args]
+    // distant spawn --trust -- {cmd} [args]
     ctx.cmd("spawn")
+        .arg("--trust")
         .arg("--")
         .arg(SCRIPT_RUNNER.as_str())
         .arg(SCRIPT_RUNNER_ARG.as_str())
@@ -63,10 +88,6 @@ fn should_capture_and_print_stderr(ctx: DistantManagerCtx) {
         });
 }
 
-// TODO: This used to work fine with the assert_cmd where stdin would close from our
-//       process, which would in turn lead to the remote process stdin being closed
-//       and then the process exiting. This may be a bug we've introduced with the
-//       refactor and should be revisited some day.
 #[rstest]
 #[test_log::test]
 fn should_forward_stdin_to_remote_process(ctx: DistantManagerCtx) {
@@ -75,6 +96,7 @@ fn should_forward_stdin_to_remote_process(ctx: DistantManagerCtx) {
     // distant action proc-spawn {cmd} [args]
     let mut child = ctx
         .new_std_cmd(["spawn"])
+        .arg("--trust")
         .arg("--")
         .arg(SCRIPT_RUNNER.as_str())
         .arg(SCRIPT_RUNNER_ARG.as_str())
@@ -108,6 +130,31 @@ fn should_forward_stdin_to_remote_process(ctx: DistantManagerCtx) {
     child.kill().expect("Failed to kill spawned process");
 }
 
+#[rstest]
+#[test_log::test]
+fn should_close_stdin_of_remote_process_once_stdin_is_closed(ctx: DistantManagerCtx) {
+    // distant spawn --trust -- {cmd} [args] < input
+    ctx.cmd("spawn")
+        .arg("--trust")
+        .arg("--")
+        .arg(SCRIPT_RUNNER.as_str())
+        .arg(SCRIPT_RUNNER_ARG.as_str())
+        .arg(ECHO_STDIN_TO_STDOUT.to_str().unwrap())
+        .write_stdin(if cfg!(windows) {
+            "hello\r\nworld\r\n"
+        } else {
+            "hello\nworld\n"
+        })
+        .assert()
+        .success()
+        .stdout(if cfg!(windows) {
+            "hello\r\nworld\r\n"
+        } else {
+            "hello\nworld\n"
+        })
+        .stderr("");
+}
+
 #[rstest]
 #[test_log::test]
 fn reflect_the_exit_code_of_the_process(ctx: DistantManagerCtx) {
@@ -117,8 +164,9 @@ fn reflect_the_exit_code_of_the_process(ctx: DistantManagerCtx) {
     #[cfg(unix)]
     let stdout = "";
 
-    // distant spawn -- {cmd} [args]
+    // distant spawn --trust -- {cmd} [args]
     ctx.cmd("spawn")
+        .arg("--trust")
         .arg("--")
         .arg(SCRIPT_RUNNER.as_str())
         .arg(SCRIPT_RUNNER_ARG.as_str())
@@ -133,8 +181,9 @@ fn reflect_the_exit_code_of_the_process(ctx: DistantManagerCtx) {
 #[rstest]
 #[test_log::test]
 fn yield_an_error_when_fails(ctx: DistantManagerCtx) {
-    // distant spawn -- {cmd} [args]
+    // distant spawn --trust -- {cmd} [args]
     ctx.cmd("spawn")
+        .arg("--trust")
         .arg("--")
         .arg(DOES_NOT_EXIST_BIN.to_str().unwrap())
         .assert()
@@ -142,3 +191,116 @@ fn yield_an_error_when_fails(ctx: DistantManagerCtx) {
         .stdout("")
         .stderr(regex_pred(".+"));
 }
+
+#[cfg(unix)]
+#[rstest]
+#[test_log::test]
+fn should_forward_ssh_agent_to_process_if_asked(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let agent = temp.path().join("agent.sock");
+    let _listener = std::os::unix::net::UnixListener::bind(&agent).unwrap();
+
+    // distant spawn --trust --forward-agent -- sh -c '...'
+    let output = ctx
+        .cmd("spawn")
+        .env("SSH_AUTH_SOCK", &agent)
+        .arg("--trust")
+        .arg("--forward-agent")
+        .arg("--")
+        .arg(r#"sh -c 'test -S "$SSH_AUTH_SOCK" && echo "$SSH_AUTH_SOCK"'"#)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    // The process is given a socket of its own, which is removed once it is done
+    let path = String::from_utf8(output).unwrap();
+    let path = std::path::Path::new(path.trim());
+    assert!(!path.as_os_str().is_empty());
+    assert_ne!(path, agent);
+    assert!(!path.exists(), "Forwarded agent socket was not removed");
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test_log::test]
+fn should_print_pid_of_detached_process_and_leave_it_running(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let marker = temp.path().join("marker");
+
+    // distant spawn --trust --detached -- sh -c '...'
+    ctx.cmd("spawn")
+        .arg("--trust")
+        .arg("--detached")
+        .arg("--")
+        .arg(format!("sh -c 'sleep 0.5 && touch {}'", marker.display()))
+        .assert()
+        .success()
+        .stdout(regex_pred(r"^\d+\n$"))
+        .stderr("");
+
+    // The process carries on after the command returns
+    assert!(!marker.exists(), "Detached process finished too early");
+    let start = std::time::Instant::now();
+    while !marker.exists() {
+        assert!(
+            start.elapsed() < std::time::Duration::from_secs(5),
+            "Detached process never ran"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test_log::test]
+fn should_kill_remote_process_and_exit_once_stdout_is_closed(ctx: DistantManagerCtx) {
+    use std::io::{BufRead, BufReader};
+    use std::time::{Duration, Instant};
+
+    // distant spawn --trust -- sh -c '...' | head -n 1
+    let mut child = ctx
+        .new_std_cmd(["spawn"])
+        .arg("--trust")
+        .arg("--")
+        .arg("sh -c 'echo $$; while true; do echo y; sleep 0.01; done'")
+        .spawn()
+        .expect("Failed to spawn process");
+
+    // Read the pid of the remote process and then close our end, like `head` would
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut pid = String::new();
+    stdout.read_line(&mut pid).expect("Failed to read pid");
+    let pid = pid.trim().to_string();
+    drop(stdout);
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if start.elapsed() >= Duration::from_secs(5) {
+            child.kill().expect("Failed to kill spawned process");
+            panic!("Spawn never exited after stdout was closed");
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    assert!(status.success(), "Spawn exited with {status}");
+
+    // The remote process is gone rather than left writing output that nobody reads
+    let start = Instant::now();
+    while std::process::Command::new("kill")
+        .args(["-0", &pid])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .unwrap()
+        .success()
+    {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "Remote process {pid} is still running"
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,279 @@
+use crate::cli::fixtures::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+use std::path::Path;
+use std::{thread, time::Duration};
+
+/// Prefixes `path` with a colon to mark it as remote
+fn remote(path: &Path) -> String {
+    format!(":{}", path.to_str().unwrap())
+}
+
+/// Waits up to a few seconds for `path` to contain `contents`
+fn wait_for_contents(path: &Path, contents: &str) -> bool {
+    for _ in 0..50 {
+        if std::fs::read_to_string(path).ok().as_deref() == Some(contents) {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    false
+}
+
+#[rstest]
+#[test_log::test]
+fn should_support_syncing_local_directory_to_remote(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("src");
+    src.child("sub").create_dir_all().unwrap();
+    src.child("sub")
+        .child("file")
+        .write_str("some text")
+        .unwrap();
+    src.child("empty").create_dir_all().unwrap();
+
+    let dst = temp.child("dst");
+
+    // distant sync {src} :{dst}
+    ctx.new_assert_cmd(["sync"])
+        .args([src.to_str().unwrap(), &remote(dst.path())])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Uploaded sub/file"))
+        .stderr("");
+
+    dst.child("sub").child("file").assert("some text");
+    dst.child("empty").assert(predicate::path::is_dir());
+}
+
+#[cfg(unix)]
+#[rstest]
+#[test_log::test]
+fn should_skip_sockets_with_a_warning(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("src");
+    src.create_dir_all().unwrap();
+    src.child("file").write_str("some text").unwrap();
+    let _listener = std::os::unix::net::UnixListener::bind(src.child("socket").path()).unwrap();
+
+    let dst = temp.child("dst");
+
+    // distant sync {src} :{dst}
+    ctx.new_assert_cmd(["sync"])
+        .args([src.to_str().unwrap(), &remote(dst.path())])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Uploaded file"))
+        .stderr("Skipping socket as it is a socket\n");
+
+    dst.child("file").assert("some text");
+    dst.child("socket").assert(predicate::path::missing());
+}
+
+#[rstest]
+#[test_log::test]
+fn should_support_syncing_remote_directory_to_local(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("src");
+    src.child("sub").create_dir_all().unwrap();
+    src.child("sub")
+        .child("file")
+        .write_str("some text")
+        .unwrap();
+
+    let dst = temp.child("dst");
+    dst.create_dir_all().unwrap();
+    dst.child("extra").write_str("untouched").unwrap();
+
+    // distant sync :{src} {dst}
+    ctx.new_assert_cmd(["sync"])
+        .args([&remote(src.path()), dst.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Downloaded sub/file"))
+        .stderr("");
+
+    dst.child("sub").child("file").assert("some text");
+
+    // Syncing in one direction never touches what only exists at the destination
+    dst.child("extra").assert("untouched");
+    src.child("extra").assert(predicate::path::missing());
+}
+
+#[rstest]
+#[test_log::test]
+fn should_report_changes_without_making_them_if_dry_run(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("src");
+    src.child("sub")
+        .child("file")
+        .write_str("some text")
+        .unwrap();
+
+    let dst = temp.child("dst");
+
+    // distant sync --dry-run {src} :{dst}
+    ctx.new_assert_cmd(["sync"])
+        .arg("--dry-run")
+        .args([src.to_str().unwrap(), &remote(dst.path())])
+        .assert()
+        .success()
+        .stdout("Would create remote directory sub\nWould upload sub/file\n")
+        .stderr("");
+
+    dst.assert(predicate::path::missing());
+}
+
+#[rstest]
+#[test_log::test]
+fn should_support_syncing_both_directions(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let local = temp.child("local");
+    local.child("from-local").write_str("local text").unwrap();
+
+    let remote_dir = temp.child("remote");
+    remote_dir
+        .child("from-remote")
+        .write_str("remote text")
+        .unwrap();
+
+    // distant sync --bidirectional {local} :{remote}
+    ctx.new_assert_cmd(["sync"])
+        .arg("--bidirectional")
+        .args([local.to_str().unwrap(), &remote(remote_dir.path())])
+        .assert()
+        .success()
+        .stderr("");
+
+    local.child("from-remote").assert("remote text");
+    remote_dir.child("from-local").assert("local text");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_resolve_conflicts_using_strategy(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let local = temp.child("local");
+    local.child("file").write_str("local text").unwrap();
+
+    let remote_dir = temp.child("remote");
+    remote_dir.child("file").write_str("remote text").unwrap();
+
+    // distant sync --bidirectional --conflict remote {local} :{remote}
+    ctx.new_assert_cmd(["sync"])
+        .args(["--bidirectional", "--conflict", "remote"])
+        .args([local.to_str().unwrap(), &remote(remote_dir.path())])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Conflict on file, keeping remote copy",
+        ))
+        .stderr("");
+
+    local.child("file").assert("remote text");
+    remote_dir.child("file").assert("remote text");
+
+    // distant sync --bidirectional --conflict skip {local} :{remote}
+    remote_dir
+        .child("file")
+        .write_str("newer remote text")
+        .unwrap();
+    local.child("file").write_str("newer local text").unwrap();
+    ctx.new_assert_cmd(["sync"])
+        .args(["--bidirectional", "--conflict", "skip"])
+        .args([local.to_str().unwrap(), &remote(remote_dir.path())])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Conflict on file, skipping"))
+        .stderr("");
+
+    local.child("file").assert("newer local text");
+    remote_dir.child("file").assert("newer remote text");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_support_mirroring_changes_while_watching(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let local = temp.child("local");
+    local.create_dir_all().unwrap();
+
+    let remote_dir = temp.child("remote");
+    remote_dir.create_dir_all().unwrap();
+
+    // distant sync --watch --bidirectional {local} :{remote}
+    let mut child = ctx
+        .new_std_cmd(["sync"])
+        .args(["--watch", "--bidirectional"])
+        .args([local.to_str().unwrap(), &remote(remote_dir.path())])
+        .spawn()
+        .expect("Failed to execute");
+
+    // Wait for the initial sync to finish and the watchers to be ready
+    thread::sleep(Duration::from_millis(500));
+
+    local.child("from-local").write_str("local text").unwrap();
+    let uploaded = wait_for_contents(&remote_dir.child("from-local"), "local text");
+
+    remote_dir
+        .child("from-remote")
+        .write_str("remote text")
+        .unwrap();
+    let downloaded = wait_for_contents(&local.child("from-remote"), "remote text");
+
+    child.kill().expect("Failed to terminate process");
+    child.wait().expect("Failed to wait for process");
+
+    assert!(uploaded, "Local change was not mirrored to remote");
+    assert!(downloaded, "Remote change was not mirrored to local");
+}
+
+#[rstest]
+#[test_log::test]
+fn yield_an_error_when_source_is_not_a_directory(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("file");
+    src.write_str("some text").unwrap();
+
+    let dst = temp.child("dst");
+
+    // distant sync {src} :{dst}
+    ctx.new_assert_cmd(["sync"])
+        .args([src.to_str().unwrap(), &remote(dst.path())])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("Only directories can be synced"));
+
+    dst.assert(predicate::path::missing());
+}
+
+#[rstest]
+#[test_log::test]
+fn yield_an_error_when_neither_path_is_remote(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let src = temp.child("src");
+    src.create_dir_all().unwrap();
+
+    let dst = temp.child("dst");
+
+    // distant sync {src} {dst}
+    ctx.new_assert_cmd(["sync"])
+        .args([src.to_str().unwrap(), dst.to_str().unwrap()])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("Neither path is remote"));
+
+    dst.assert(predicate::path::missing());
+}
@@ -0,0 +1,46 @@
+use crate::cli::{fixtures::*, scripts::*};
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_refuse_to_spawn_in_untrusted_root_without_terminal(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let trust = temp.child("trust.jsonl");
+    let dir = temp.child("project");
+    dir.create_dir_all().unwrap();
+
+    // distant spawn --trust-file {path} --current-dir {dir} -- {cmd} [args]
+    ctx.cmd("spawn")
+        .arg("--trust-file")
+        .arg(trust.path())
+        .arg("--current-dir")
+        .arg(dir.path())
+        .arg("--")
+        .arg(SCRIPT_RUNNER.as_str())
+        .arg(SCRIPT_RUNNER_ARG.as_str())
+        .arg(EXIT_CODE.to_str().unwrap())
+        .arg("0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("has not been trusted"));
+
+    // Nothing is remembered when there was no one to ask
+    trust.assert(predicate::path::missing());
+
+    // distant spawn --trust --trust-file {path} --current-dir {dir} -- {cmd} [args]
+    ctx.cmd("spawn")
+        .arg("--trust")
+        .arg("--trust-file")
+        .arg(trust.path())
+        .arg("--current-dir")
+        .arg(dir.path())
+        .arg("--")
+        .arg(SCRIPT_RUNNER.as_str())
+        .arg(SCRIPT_RUNNER_ARG.as_str())
+        .arg(EXIT_CODE.to_str().unwrap())
+        .arg("0")
+        .assert()
+        .success();
+}
@@ -0,0 +1,64 @@
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+fn config_cmd(config: &assert_fs::NamedTempFile, args: &[&str]) -> Command {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.arg("--config")
+        .arg(config.path())
+        .arg("config")
+        .args(args);
+    cmd
+}
+
+#[test_log::test]
+fn should_set_get_and_unset_settings_in_config_file() {
+    let config = assert_fs::NamedTempFile::new("config.toml").unwrap();
+
+    config_cmd(
+        &config,
+        &["set", "client.launch.bin", "/usr/local/bin/distant"],
+    )
+    .assert()
+    .success()
+    .stdout("");
+
+    // Missing config files start from the default one, comments and all
+    config.assert(predicate::str::contains(
+        "# Path to distant program on remote machine to execute via ssh;",
+    ));
+    config.assert(predicate::str::contains(
+        r#"bin = "/usr/local/bin/distant""#,
+    ));
+
+    config_cmd(&config, &["get", "client.launch.bin"])
+        .assert()
+        .success()
+        .stdout("/usr/local/bin/distant\n");
+
+    config_cmd(&config, &["unset", "client.launch.bin"])
+        .assert()
+        .success();
+
+    config_cmd(&config, &["get", "client.launch.bin"])
+        .assert()
+        .failure();
+}
+
+#[test_log::test]
+fn should_not_write_settings_that_make_config_invalid() {
+    let config = assert_fs::NamedTempFile::new("config.toml").unwrap();
+
+    config_cmd(&config, &["set", "client.launch.verify", "sometimes"])
+        .assert()
+        .failure();
+    config.assert(predicate::path::missing());
+
+    config_cmd(&config, &["set", "client.launch.verify", "true"])
+        .assert()
+        .success();
+    config_cmd(&config, &["get", "client.launch.verify"])
+        .assert()
+        .success()
+        .stdout("true\n");
+}
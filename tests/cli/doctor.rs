@@ -0,0 +1,49 @@
+use crate::cli::fixtures::*;
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_report_reachable_manager_and_valid_config(ctx: DistantManagerCtx) {
+    let config = assert_fs::NamedTempFile::new("config.toml").unwrap();
+    config
+        .write_str(include_str!("../../src/options/config.toml"))
+        .unwrap();
+
+    ctx.new_assert_cmd(["doctor", "--local"])
+        .arg("--config")
+        .arg(config.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[ok]   Config settings are valid"))
+        .stdout(predicate::str::contains(
+            "[ok]   Manager is reachable and has 1 active connection(s)",
+        ));
+}
+
+#[test_log::test]
+fn should_fail_with_hints_when_config_is_invalid_and_manager_is_unreachable() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let config = temp.child("config.toml");
+    config.write_str("[client").unwrap();
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("doctor")
+        .arg("--local")
+        .arg("--config")
+        .arg(config.path())
+        .arg("--unix-socket")
+        .arg(temp.child("missing.sock").path())
+        .arg("--windows-pipe")
+        .arg("distant-doctor-missing")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("[fail] Config file"))
+        .stdout(predicate::str::contains("[fail] No manager is reachable"))
+        .stdout(predicate::str::contains(
+            "Start one with `distant manager listen --daemon`",
+        ));
+}
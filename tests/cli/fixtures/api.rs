@@ -1,7 +1,7 @@
 use serde_json::Value;
 use std::{
     io::{self, BufRead, BufReader, BufWriter, Write},
-    process::Child,
+    process::{Child, ExitStatus},
     thread,
     time::Duration,
 };
@@ -200,6 +200,18 @@ impl ApiProcess {
         stderr
     }
 
+    /// Closes our end of the api's stdout, like `head` would once it has read enough, which the
+    /// api finds out about the next time that it writes a line
+    pub fn close_stdout(&mut self) {
+        let (_, rx) = mpsc::channel(1);
+        self.stdout = rx;
+    }
+
+    /// Returns the exit status of the api if it has exited
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.child.try_wait()
+    }
+
     /// Kills the api by sending a signal to the process
     pub fn kill(&mut self) -> io::Result<()> {
         self.child.kill()
@@ -3,29 +3,41 @@ use indoc::indoc;
 use rstest::*;
 
 const EXPECTED_TABLE: &str = indoc! {"
-+---------------+--------------------------------------------------------------+
-| kind          | description                                                  |
-+---------------+--------------------------------------------------------------+
-| authenticate  | Supports authenticating with a remote server                 |
-+---------------+--------------------------------------------------------------+
-| capabilities  | Supports retrieving capabilities                             |
-+---------------+--------------------------------------------------------------+
-| channel       | Supports sending data through a channel with a remote server |
-+---------------+--------------------------------------------------------------+
-| close_channel | Supports closing a channel with a remote server              |
-+---------------+--------------------------------------------------------------+
-| connect       | Supports connecting to remote servers                        |
-+---------------+--------------------------------------------------------------+
-| info          | Supports retrieving connection-specific information          |
-+---------------+--------------------------------------------------------------+
-| kill          | Supports killing a remote connection                         |
-+---------------+--------------------------------------------------------------+
-| launch        | Supports launching a server on remote machines               |
-+---------------+--------------------------------------------------------------+
-| list          | Supports retrieving a list of managed connections            |
-+---------------+--------------------------------------------------------------+
-| open_channel  | Supports opening a channel with a remote server              |
-+---------------+--------------------------------------------------------------+
++-------------------+--------------------------------------------------------------+
+| kind              | description                                                  |
++-------------------+--------------------------------------------------------------+
+| authenticate      | Supports authenticating with a remote server                 |
++-------------------+--------------------------------------------------------------+
+| capabilities      | Supports retrieving capabilities                             |
++-------------------+--------------------------------------------------------------+
+| channel           | Supports sending data through a channel with a remote server |
++-------------------+--------------------------------------------------------------+
+| claim_channel     | Supports claiming a channel handed off by another client     |
++-------------------+--------------------------------------------------------------+
+| clients           | Supports retrieving a list of connected clients              |
++-------------------+--------------------------------------------------------------+
+| close_channel     | Supports closing a channel with a remote server              |
++-------------------+--------------------------------------------------------------+
+| connect           | Supports connecting to remote servers                        |
++-------------------+--------------------------------------------------------------+
+| disconnect_client | Supports disconnecting a connected client                    |
++-------------------+--------------------------------------------------------------+
+| handoff_channel   | Supports handing off a channel to another client             |
++-------------------+--------------------------------------------------------------+
+| info              | Supports retrieving connection-specific information          |
++-------------------+--------------------------------------------------------------+
+| kill              | Supports killing a remote connection                         |
++-------------------+--------------------------------------------------------------+
+| launch            | Supports launching a server on remote machines               |
++-------------------+--------------------------------------------------------------+
+| list              | Supports retrieving a list of managed connections            |
++-------------------+--------------------------------------------------------------+
+| open_channel      | Supports opening a channel with a remote server              |
++-------------------+--------------------------------------------------------------+
+| resolve           | Supports looking up connections by name                      |
++-------------------+--------------------------------------------------------------+
+| subscribe         | Supports subscribing to connection events                    |
++-------------------+--------------------------------------------------------------+
 "};
 
 #[rstest]
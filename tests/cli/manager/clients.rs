@@ -0,0 +1,28 @@
+use crate::cli::{fixtures::*, utils::regex_pred};
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_output_clients_connected_to_the_manager(ctx: DistantManagerCtx) {
+    // distant manager clients
+    //
+    // NOTE: The command itself is one of the clients of the manager
+    ctx.new_assert_cmd(["manager", "clients"])
+        .assert()
+        .success()
+        .stdout(regex_pred(
+            r"(?m)^\| \d+ +\| none +\| \d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2} \| pid \d+ +\|$",
+        ))
+        .stderr("");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_fail_to_disconnect_unknown_client(ctx: DistantManagerCtx) {
+    // distant manager clients --disconnect 1
+    ctx.new_assert_cmd(["manager", "clients"])
+        .args(["--disconnect", "1"])
+        .assert()
+        .code(1)
+        .stderr(predicates::str::contains("No client with id 1"));
+}
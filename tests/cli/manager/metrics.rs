@@ -0,0 +1,26 @@
+use crate::cli::{fixtures::*, utils::regex_pred};
+use assert_fs::prelude::*;
+use rstest::*;
+
+#[rstest]
+#[test_log::test]
+fn should_output_bytes_written_through_each_connection(ctx: DistantManagerCtx) {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("file");
+
+    // distant fs write {path}
+    ctx.new_assert_cmd(["fs", "write"])
+        .args([file.to_str().unwrap()])
+        .write_stdin("some text")
+        .assert()
+        .success();
+
+    // distant manager metrics
+    ctx.new_assert_cmd(["manager", "metrics"])
+        .assert()
+        .success()
+        .stdout(regex_pred(
+            r"(?m)^\| \d+ +\| +\| +\| distant://[^|]+\| 9 +\| +\|$",
+        ))
+        .stderr("");
+}
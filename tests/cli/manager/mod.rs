@@ -1 +1,3 @@
 mod capabilities;
+mod clients;
+mod metrics;
@@ -1,6 +1,11 @@
 mod api;
 mod client;
+mod config;
+mod doctor;
 mod fixtures;
 mod manager;
+mod render;
 mod scripts;
+mod server;
+mod state;
 mod utils;
@@ -0,0 +1,43 @@
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+
+#[test_log::test]
+fn should_print_responses_from_input_like_commands_do() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("responses.json");
+    input
+        .write_str(
+            r#"[
+                {"type": "text", "data": "hello"},
+                {"type": "exists", "value": true},
+                {"type": "error", "kind": "not_found", "description": "missing"}
+            ]"#,
+        )
+        .unwrap();
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .arg("_render")
+        .arg("--input")
+        .arg(input.path())
+        .assert()
+        .success()
+        .stdout("hello\ntrue\n")
+        .stderr("missing\n");
+}
+
+#[test_log::test]
+fn should_give_json_responses_ids_of_their_positions() {
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .args(["_render", "--format", "json"])
+        .write_stdin(r#"{"type": "ok"} {"type": "ok"}"#)
+        .assert()
+        .success()
+        .stdout(concat!(
+            r#"{"id":"0","origin_id":"0","payload":{"type":"ok"}}"#,
+            "\n",
+            r#"{"id":"1","origin_id":"1","payload":{"type":"ok"}}"#,
+            "\n",
+        ));
+}
@@ -0,0 +1,45 @@
+use crate::cli::{fixtures::*, utils::regex_pred};
+use rstest::*;
+
+/// Matches the row of the manager, which is the only client of the server and connects over TCP
+/// using the key of the server
+const MANAGER_ROW: &str =
+    r"(?m)^\| \d+ +\| static_key \| \d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2} \| [^ ]+:\d+ +\|$";
+
+#[rstest]
+#[test_log::test]
+fn should_output_clients_connected_to_the_server(ctx: DistantManagerCtx) {
+    // distant server clients
+    ctx.new_assert_cmd(["server", "clients"])
+        .assert()
+        .success()
+        .stdout(regex_pred(MANAGER_ROW))
+        .stderr("");
+}
+
+#[rstest]
+#[test_log::test]
+fn should_refuse_to_disconnect_the_client_making_the_request(ctx: DistantManagerCtx) {
+    let output = ctx
+        .new_assert_cmd(["server", "clients"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+    let id = output
+        .lines()
+        .find(|line| line.contains("static_key"))
+        .and_then(|line| line.split('|').nth(1))
+        .unwrap()
+        .trim()
+        .to_string();
+
+    // distant server clients --disconnect {id}
+    ctx.new_assert_cmd(["server", "clients"])
+        .args(["--disconnect", id.as_str()])
+        .assert()
+        .code(1)
+        .stderr(predicates::str::contains("is the one making the request"));
+}
@@ -0,0 +1,107 @@
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::path::Path;
+
+const CONFIG: &str = include_str!("../../src/options/config.toml");
+
+fn state_cmd(home: &Path, config: &Path, history: &Path, args: &[&str]) -> Command {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.env("HOME", home)
+        .arg("--config")
+        .arg(config)
+        .args(args)
+        .arg("--history-file")
+        .arg(history);
+    cmd
+}
+
+// NOTE: The location of ~/.ssh is only taken from $HOME on unix
+#[cfg(unix)]
+#[test_log::test]
+fn should_move_state_from_one_home_to_another() {
+    let old = assert_fs::TempDir::new().unwrap();
+    old.child("config.toml")
+        .write_str(&format!("# old machine\n{CONFIG}"))
+        .unwrap();
+    old.child("history.jsonl").write_str("{\"a\":1}\n").unwrap();
+    old.child(".ssh/known_hosts")
+        .write_str("example.com ssh-ed25519 AAAA\n")
+        .unwrap();
+    old.child(".ssh/id_ed25519").write_str("secret").unwrap();
+    old.child(".ssh/id_ed25519.pub")
+        .write_str("public")
+        .unwrap();
+    old.child("password").write_str("hunter2\n").unwrap();
+
+    let archive = old.child("state.tar");
+    state_cmd(
+        old.path(),
+        old.child("config.toml").path(),
+        old.child("history.jsonl").path(),
+        &[
+            "export-state",
+            archive.to_str().unwrap(),
+            "--include",
+            "config,history,known-hosts,keys",
+            "--encrypt-keys",
+            "--password-file",
+            old.child("password").to_str().unwrap(),
+        ],
+    )
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Exported 2 keys"));
+
+    // The new machine already has some history and a config of its own
+    let new = assert_fs::TempDir::new().unwrap();
+    new.child("config.toml")
+        .write_str(&format!("# new machine\n{CONFIG}"))
+        .unwrap();
+    new.child("history.jsonl").write_str("{\"b\":2}\n").unwrap();
+    new.child("password").write_str("hunter2\n").unwrap();
+
+    state_cmd(
+        new.path(),
+        new.child("config.toml").path(),
+        new.child("history.jsonl").path(),
+        &[
+            "import-state",
+            archive.to_str().unwrap(),
+            "--password-file",
+            new.child("password").to_str().unwrap(),
+        ],
+    )
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Skipped config"))
+    .stdout(predicate::str::contains("Imported 2 keys"));
+
+    new.child("config.toml")
+        .assert(predicate::str::starts_with("# new machine\n"));
+    new.child("history.jsonl").assert("{\"b\":2}\n{\"a\":1}\n");
+    new.child(".ssh/known_hosts")
+        .assert("example.com ssh-ed25519 AAAA\n");
+    new.child(".ssh/id_ed25519").assert("secret");
+    new.child(".ssh/id_ed25519.pub").assert("public");
+
+    // Importing again with the wrong password fails before touching the keys
+    new.child("password").write_str("wrong\n").unwrap();
+    state_cmd(
+        new.path(),
+        new.child("config.toml").path(),
+        new.child("history.jsonl").path(),
+        &[
+            "import-state",
+            archive.to_str().unwrap(),
+            "--include",
+            "keys",
+            "--force",
+            "--password-file",
+            new.child("password").to_str().unwrap(),
+        ],
+    )
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("Failed to decrypt key id_ed25519"));
+}